@@ -240,7 +240,11 @@ fn bench_filter_modulation(c: &mut Criterion) {
             for i in 0..buffer_size {
                 // Simulate LFO modulation: cutoff varies from 250Hz to 2000Hz
                 let modulated_cutoff = 500.0 + 500.0 * (i as f32 / 100.0).sin();
-                black_box(filter.process_modulated(black_box(0.5), black_box(modulated_cutoff)));
+                black_box(filter.process_modulated(
+                    black_box(0.5),
+                    black_box(modulated_cutoff),
+                    1.0,
+                ));
             }
         });
     });
@@ -0,0 +1,168 @@
+// Song arrangement commands (pattern clips on the timeline)
+//
+// The arrangement lives in the shared core state, which sends it to the engine
+// while song mode is on. The web frontend has no instrument tracks: its clips
+// are placed on the synth track and play the patterns of the pattern store.
+// Pattern edits reach the clips and session slots playing the pattern (see
+// `refresh_clips`).
+
+use serde::Serialize;
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::lock_core;
+use crate::error::{DawError, DawResult};
+use mymusic_daw::sequencer::arrangement::SYNTH_TRACK;
+use mymusic_daw::sequencer::{Arrangement, ClipId, Pattern, PatternClip, PatternId};
+use std::sync::Arc;
+
+/// Clip summary (for the timeline)
+#[derive(Debug, Serialize)]
+pub struct ClipInfo {
+    pub id: ClipId,
+    pub track: u32,
+    pub pattern_id: PatternId,
+    pub pattern_name: String,
+    pub start_bar: u32,
+    pub length_bars: u32,
+}
+
+/// Song arrangement and mode
+#[derive(Debug, Serialize)]
+pub struct ArrangementInfo {
+    /// Whether the clips are played instead of the active pattern
+    pub song_mode: bool,
+    /// End of the last clip
+    pub length_bars: u32,
+    pub clips: Vec<ClipInfo>,
+}
+
+fn clip_to_info(clip: &PatternClip) -> ClipInfo {
+    ClipInfo {
+        id: clip.id,
+        track: clip.track,
+        pattern_id: clip.pattern_id(),
+        pattern_name: clip.pattern.name.clone(),
+        start_bar: clip.start_bar,
+        length_bars: clip.length_bars,
+    }
+}
+
+fn arrangement_to_info(arrangement: &Arrangement, song_mode: bool) -> ArrangementInfo {
+    ArrangementInfo {
+        song_mode,
+        length_bars: arrangement.length_bars(),
+        clips: arrangement.clips().iter().map(clip_to_info).collect(),
+    }
+}
+
+/// Play the new version of patterns in the clips and session slots using them
+pub(crate) fn refresh_clips(patterns: &[Arc<Pattern>], state: &DawState) -> DawResult<()> {
+    let mut core = lock_core(state)?;
+    let (mut used, mut in_session) = (false, false);
+    for pattern in patterns {
+        used |= core.arrangement.update_pattern(pattern);
+        in_session |= core.session.update_pattern(pattern);
+    }
+    if used && core.song_mode {
+        core.send_arrangement()?;
+    }
+    if in_session {
+        core.send_session()?;
+    }
+    Ok(())
+}
+
+/// Apply an edit to a copy of the arrangement, then send it
+fn edit_arrangement<T>(
+    state: &DawState,
+    edit: impl FnOnce(&mut Arrangement) -> Result<T, String>,
+) -> DawResult<T> {
+    let mut core = lock_core(state)?;
+    let mut arrangement = core.arrangement.clone();
+    let result = edit(&mut arrangement).map_err(DawError::InvalidArgument)?;
+    core.set_arrangement(arrangement)?;
+    Ok(result)
+}
+
+/// Get the clips of the song and whether song mode is on
+#[tauri::command]
+pub fn get_arrangement(state: State<DawState>) -> DawResult<ArrangementInfo> {
+    let core = lock_core(&state)?;
+    Ok(arrangement_to_info(&core.arrangement, core.song_mode))
+}
+
+/// Place a pattern on the timeline
+///
+/// The clip lasts `length_bars` (default: the pattern length); it cannot
+/// overlap another clip.
+#[tauri::command]
+pub fn add_pattern_clip(
+    pattern_id: PatternId,
+    start_bar: u32,
+    length_bars: Option<u32>,
+    state: State<DawState>,
+) -> DawResult<ClipInfo> {
+    if length_bars == Some(0) {
+        return Err(DawError::InvalidArgument("Clip length must be at least 1 bar".to_string()));
+    }
+    let pattern = {
+        let store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+        store
+            .patterns
+            .get(&pattern_id)
+            .cloned()
+            .ok_or_else(|| DawError::NotFound(format!("Pattern not found: {}", pattern_id)))?
+    };
+    let length_bars = length_bars.unwrap_or(pattern.length_bars);
+    let clip = PatternClip::new(SYNTH_TRACK, start_bar, length_bars, pattern);
+    let info = clip_to_info(&clip);
+    edit_arrangement(&state, |arrangement| arrangement.add_clip(clip))?;
+    Ok(info)
+}
+
+/// Move a clip to another bar, optionally changing its length
+#[tauri::command]
+pub fn move_pattern_clip(
+    clip_id: ClipId,
+    start_bar: u32,
+    length_bars: Option<u32>,
+    state: State<DawState>,
+) -> DawResult<ClipInfo> {
+    if length_bars == Some(0) {
+        return Err(DawError::InvalidArgument("Clip length must be at least 1 bar".to_string()));
+    }
+    if lock_core(&state)?.arrangement.get(clip_id).is_none() {
+        return Err(DawError::NotFound(format!("Clip not found: {}", clip_id)));
+    }
+    edit_arrangement(&state, |arrangement| {
+        let clip = arrangement.get(clip_id).ok_or("Clip not found")?;
+        let (track, length_bars) = (clip.track, length_bars.unwrap_or(clip.length_bars));
+        // Shorten first, so a clip can grow into the bars it leaves
+        arrangement.resize_clip(clip_id, 1)?;
+        arrangement.move_clip(clip_id, track, start_bar)?;
+        arrangement.resize_clip(clip_id, length_bars)?;
+        arrangement
+            .get(clip_id)
+            .map(clip_to_info)
+            .ok_or_else(|| "Clip not found".to_string())
+    })
+}
+
+/// Remove a clip from the timeline
+#[tauri::command]
+pub fn remove_pattern_clip(clip_id: ClipId, state: State<DawState>) -> DawResult<()> {
+    let mut core = lock_core(&state)?;
+    let mut arrangement = core.arrangement.clone();
+    arrangement
+        .remove_clip(clip_id)
+        .ok_or_else(|| DawError::NotFound(format!("Clip not found: {}", clip_id)))?;
+    core.set_arrangement(arrangement)?;
+    Ok(())
+}
+
+/// Play the arrangement (song mode) or loop the active pattern
+#[tauri::command]
+pub fn set_song_mode(enabled: bool, state: State<DawState>) -> DawResult<()> {
+    lock_core(&state)?.set_song_mode(enabled)?;
+    Ok(())
+}
@@ -0,0 +1,403 @@
+// Audio track commands (clips of imported audio files on the timeline)
+//
+// `DawState::audio_tracks` is the source of truth; every edit sends a snapshot
+// of it to the engine's clip player (`Command::SetAudioTracks`). Positions are
+// exchanged in seconds and stored in samples at the engine rate. Warped clips
+// follow the project tempo (see `mymusic_daw::audio::warp`).
+
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::{lock_core, send_command_to_engine};
+use crate::commands::freeze::release_freeze;
+use crate::error::{DawError, DawResult};
+use serde::Serialize;
+use mymusic_daw::audio::clip_player::{AudioClip, AudioTrack, AudioTrackList, TrackDynamics, MAX_CLIP_GAIN};
+use mymusic_daw::audio::inserts::InsertChainParams;
+use mymusic_daw::audio::outputs::TrackOutput;
+use mymusic_daw::audio::returns::TrackSends;
+use mymusic_daw::audio::surround::SurroundPan;
+use mymusic_daw::audio::warp::{estimate_loop_bpm, ClipWarp};
+use mymusic_daw::messaging::command::Command;
+use mymusic_daw::sampler::load_sample as load_sample_file;
+use std::path::PathBuf;
+use std::sync::{Arc, MutexGuard};
+
+/// Audio clip sent to the frontend (times in seconds)
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioClipInfo {
+    pub id: u32,
+    pub name: String,
+    pub source_path: String,
+    pub start_seconds: f64,
+    pub offset_seconds: f64,
+    pub length_seconds: f64,
+    /// Duration of the whole source file
+    pub source_seconds: f64,
+    pub gain: f32,
+    pub fade_in_seconds: f64,
+    pub fade_out_seconds: f64,
+    /// Pitch shift in semitones (the length is kept)
+    pub pitch: f32,
+    /// Tempo following (None: plays at the original speed)
+    pub warp: Option<ClipWarpInfo>,
+}
+
+/// Warp of a clip sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipWarpInfo {
+    pub source_bpm: f64,
+    pub markers: Vec<WarpMarkerInfo>,
+}
+
+/// Warp marker: a source position pinned to a beat of the clip
+#[derive(Debug, Clone, Serialize)]
+pub struct WarpMarkerInfo {
+    pub source_seconds: f64,
+    pub beat: f64,
+}
+
+/// Audio track sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioTrackInfo {
+    pub id: u32,
+    pub name: String,
+    pub gain: f32,
+    pub pan: f32,
+    pub muted: bool,
+    /// While any track is soloed, only the soloed ones are heard
+    pub soloed: bool,
+    /// Render of the frozen synth track (removing it unfreezes the synth)
+    pub frozen: bool,
+    /// Compressor/gate insert (see `commands::dynamics`)
+    pub dynamics: Option<TrackDynamics>,
+    /// Sends to the return buses, by bus slot (see `commands::returns`)
+    pub sends: TrackSends,
+    /// Master bus or hardware output pair (see `commands::outputs`)
+    pub output: TrackOutput,
+    /// Surround panner, None for the stereo pan (see `commands::surround`)
+    pub surround: Option<SurroundPan>,
+    /// Insert effect chain (see `commands::inserts`)
+    pub inserts: InsertChainParams,
+    pub clips: Vec<AudioClipInfo>,
+}
+
+fn clip_to_info(clip: &AudioClip, sample_rate: f32) -> AudioClipInfo {
+    let seconds = |samples: u64| samples as f64 / sample_rate as f64;
+    AudioClipInfo {
+        id: clip.id,
+        name: clip.name.clone(),
+        source_path: clip.source.to_string_lossy().into_owned(),
+        start_seconds: seconds(clip.start),
+        offset_seconds: seconds(clip.offset),
+        length_seconds: seconds(clip.length),
+        source_seconds: seconds(clip.source_length(sample_rate)),
+        gain: clip.gain,
+        fade_in_seconds: seconds(clip.fade_in),
+        fade_out_seconds: seconds(clip.fade_out),
+        pitch: clip.pitch(),
+        warp: clip.warp.as_ref().map(|warp| ClipWarpInfo {
+            source_bpm: warp.source_bpm,
+            markers: warp
+                .markers()
+                .iter()
+                .map(|marker| WarpMarkerInfo {
+                    source_seconds: seconds(marker.source),
+                    beat: marker.beat,
+                })
+                .collect(),
+        }),
+    }
+}
+
+fn track_to_info(track: &AudioTrack, sample_rate: f32) -> AudioTrackInfo {
+    AudioTrackInfo {
+        id: track.id,
+        name: track.name.clone(),
+        gain: track.gain,
+        pan: track.pan,
+        muted: track.muted,
+        soloed: track.soloed,
+        frozen: track.frozen.is_some(),
+        dynamics: track.dynamics,
+        sends: track.sends,
+        output: track.output,
+        surround: track.surround,
+        inserts: track.inserts,
+        clips: track.clips.iter().map(|clip| clip_to_info(clip, sample_rate)).collect(),
+    }
+}
+
+fn engine_sample_rate(state: &State<DawState>) -> DawResult<f32> {
+    let sample_rate = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?.sample_rate();
+    if sample_rate <= 0.0 {
+        return Err(DawError::Engine("Audio engine is not running".to_string()));
+    }
+    Ok(sample_rate)
+}
+
+fn lock_tracks<'a>(state: &'a State<DawState>) -> DawResult<MutexGuard<'a, AudioTrackList>> {
+    state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))
+}
+
+fn to_samples(seconds: f64, name: &str, sample_rate: f32) -> DawResult<u64> {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return Err(DawError::InvalidArgument(format!("Invalid {}: {} (must be >= 0 s)", name, seconds)));
+    }
+    Ok((seconds * sample_rate as f64).round() as u64)
+}
+
+fn check_gain(gain: f32) -> DawResult<()> {
+    if !(0.0..=MAX_CLIP_GAIN).contains(&gain) {
+        return Err(DawError::InvalidArgument(format!(
+            "Invalid gain: {} (must be 0.0-{})",
+            gain, MAX_CLIP_GAIN
+        )));
+    }
+    Ok(())
+}
+
+/// Send the audio tracks to the engine (the lock is held so snapshots stay ordered)
+fn send_tracks(tracks: &AudioTrackList, state: &State<DawState>) -> DawResult<()> {
+    send_command_to_engine(Command::SetAudioTracks(tracks.snapshot()), state.clone())
+}
+
+/// List audio tracks and their clips
+#[tauri::command]
+pub fn list_audio_tracks(state: State<DawState>) -> DawResult<Vec<AudioTrackInfo>> {
+    let sample_rate = engine_sample_rate(&state)?;
+    let tracks = lock_tracks(&state)?;
+    Ok(tracks.tracks().iter().map(|track| track_to_info(track, sample_rate)).collect())
+}
+
+/// Add an empty audio track
+#[tauri::command]
+pub fn add_audio_track(name: Option<String>, state: State<DawState>) -> DawResult<AudioTrackInfo> {
+    let sample_rate = engine_sample_rate(&state)?;
+    let mut tracks = lock_tracks(&state)?;
+    let name = name.unwrap_or_else(|| format!("Audio {}", tracks.tracks().len() + 1));
+    let track_id = tracks.add_track(name);
+    send_tracks(&tracks, &state)?;
+
+    let track = tracks.track(track_id).expect("track just added");
+    Ok(track_to_info(track, sample_rate))
+}
+
+/// Remove an audio track and its clips (the frozen track is unfrozen instead)
+#[tauri::command]
+pub fn remove_audio_track(track_id: u32, state: State<DawState>) -> DawResult<()> {
+    let frozen_track = {
+        let store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+        store.frozen.as_ref().map(|frozen| frozen.audio_track_id)
+    };
+    if frozen_track == Some(track_id) {
+        return release_freeze(&state);
+    }
+
+    let mut tracks = lock_tracks(&state)?;
+    tracks
+        .remove_track(track_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio track not found: {}", track_id)))?;
+    send_tracks(&tracks, &state)?;
+    Ok(lock_core(&state)?.forget_sidechain_track(track_id)?)
+}
+
+/// Update the mix settings of an audio track
+///
+/// Omitted fields keep their current value.
+#[tauri::command]
+pub fn update_audio_track(
+    track_id: u32,
+    gain: Option<f32>,
+    pan: Option<f32>,
+    muted: Option<bool>,
+    soloed: Option<bool>,
+    state: State<DawState>,
+) -> DawResult<AudioTrackInfo> {
+    if let Some(gain) = gain {
+        check_gain(gain)?;
+    }
+    if let Some(pan) = pan {
+        if !(-1.0..=1.0).contains(&pan) {
+            return Err(DawError::InvalidArgument(format!("Invalid pan: {} (must be -1.0-1.0)", pan)));
+        }
+    }
+    let sample_rate = engine_sample_rate(&state)?;
+    let mut tracks = lock_tracks(&state)?;
+    let track = tracks
+        .track_mut(track_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio track not found: {}", track_id)))?;
+    track.gain = gain.unwrap_or(track.gain);
+    track.pan = pan.unwrap_or(track.pan);
+    track.muted = muted.unwrap_or(track.muted);
+    track.soloed = soloed.unwrap_or(track.soloed);
+    let info = track_to_info(track, sample_rate);
+
+    send_tracks(&tracks, &state)?;
+    Ok(info)
+}
+
+/// Import an audio file (WAV/FLAC/MP3) as a clip of an audio track
+///
+/// The clip plays the whole file from `start_seconds` (default: song start).
+#[tauri::command]
+pub fn import_audio_clip(
+    track_id: u32,
+    path: String,
+    start_seconds: Option<f64>,
+    state: State<DawState>,
+) -> DawResult<AudioClipInfo> {
+    let sample_rate = engine_sample_rate(&state)?;
+    let start = to_samples(start_seconds.unwrap_or(0.0), "start", sample_rate)?;
+    if lock_tracks(&state)?.track(track_id).is_none() {
+        return Err(DawError::NotFound(format!("Audio track not found: {}", track_id)));
+    }
+
+    // Decode without holding the lock
+    let source = PathBuf::from(path);
+    let sample = load_sample_file(&source).map_err(DawError::Io)?;
+    let clip = AudioClip::new(source, Arc::new(sample), start, sample_rate);
+
+    let mut tracks = lock_tracks(&state)?;
+    let clip_id = tracks
+        .add_clip(track_id, clip)
+        .ok_or_else(|| DawError::NotFound(format!("Audio track not found: {}", track_id)))?;
+    send_tracks(&tracks, &state)?;
+
+    let clip = tracks.clip(clip_id).expect("clip just added");
+    Ok(clip_to_info(clip, sample_rate))
+}
+
+/// Update an audio clip
+///
+/// Omitted fields keep their current value. The offset, length and fades are
+/// shortened to fit the source file. `pitch` (semitones) repitches the clip
+/// without changing its length.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_audio_clip(
+    clip_id: u32,
+    start_seconds: Option<f64>,
+    offset_seconds: Option<f64>,
+    length_seconds: Option<f64>,
+    gain: Option<f32>,
+    fade_in_seconds: Option<f64>,
+    fade_out_seconds: Option<f64>,
+    pitch: Option<f32>,
+    state: State<DawState>,
+) -> DawResult<AudioClipInfo> {
+    if let Some(gain) = gain {
+        check_gain(gain)?;
+    }
+    let sample_rate = engine_sample_rate(&state)?;
+    let samples = |seconds: Option<f64>, name: &str| seconds.map(|s| to_samples(s, name, sample_rate)).transpose();
+    let start = samples(start_seconds, "start")?;
+    let offset = samples(offset_seconds, "offset")?;
+    let length = samples(length_seconds, "length")?;
+    let fade_in = samples(fade_in_seconds, "fade-in")?;
+    let fade_out = samples(fade_out_seconds, "fade-out")?;
+
+    let mut tracks = lock_tracks(&state)?;
+    let clip = tracks
+        .clip_mut(clip_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio clip not found: {}", clip_id)))?;
+    clip.start = start.unwrap_or(clip.start);
+    clip.offset = offset.unwrap_or(clip.offset);
+    clip.length = length.unwrap_or(clip.length);
+    clip.gain = gain.unwrap_or(clip.gain);
+    clip.fade_in = fade_in.unwrap_or(clip.fade_in);
+    clip.fade_out = fade_out.unwrap_or(clip.fade_out);
+    if let Some(pitch) = pitch {
+        clip.set_pitch(pitch).map_err(DawError::InvalidArgument)?;
+    }
+    clip.clamp(sample_rate);
+    // The warp markers follow a new region
+    if offset.is_some() || length.is_some() {
+        if let Some(source_bpm) = clip.warp.as_ref().map(|warp| warp.source_bpm) {
+            clip.warp = Some(ClipWarp::detect(clip, source_bpm, sample_rate).map_err(DawError::InvalidArgument)?);
+        }
+    }
+    let info = clip_to_info(clip, sample_rate);
+
+    send_tracks(&tracks, &state)?;
+    Ok(info)
+}
+
+/// Remove an audio clip
+#[tauri::command]
+pub fn remove_audio_clip(clip_id: u32, state: State<DawState>) -> DawResult<()> {
+    let mut tracks = lock_tracks(&state)?;
+    tracks
+        .remove_clip(clip_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio clip not found: {}", clip_id)))?;
+    send_tracks(&tracks, &state)
+}
+
+/// Make a clip follow the project tempo
+///
+/// Warp markers are placed on the transients of the clip, which is assumed to
+/// be recorded at `source_bpm` (default: guessed from the clip length).
+/// `enabled = false` plays the clip at its original speed again.
+#[tauri::command]
+pub fn set_audio_clip_warp(
+    clip_id: u32,
+    enabled: bool,
+    source_bpm: Option<f64>,
+    state: State<DawState>,
+) -> DawResult<AudioClipInfo> {
+    let sample_rate = engine_sample_rate(&state)?;
+    let mut tracks = lock_tracks(&state)?;
+    let clip = tracks
+        .clip_mut(clip_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio clip not found: {}", clip_id)))?;
+    clip.warp = if enabled {
+        let source_bpm = source_bpm.unwrap_or_else(|| estimate_loop_bpm(clip.length, sample_rate));
+        Some(ClipWarp::detect(clip, source_bpm, sample_rate).map_err(DawError::InvalidArgument)?)
+    } else {
+        None
+    };
+    let info = clip_to_info(clip, sample_rate);
+
+    send_tracks(&tracks, &state)?;
+    Ok(info)
+}
+
+/// Move a warp marker to another beat (the first marker stays at beat 0)
+///
+/// With `grid_beats`, every marker is snapped to that grid instead (e.g. 0.25
+/// for 1/16 notes) and `index`/`beat` are ignored.
+#[tauri::command]
+pub fn set_warp_markers(
+    clip_id: u32,
+    index: Option<usize>,
+    beat: Option<f64>,
+    grid_beats: Option<f64>,
+    state: State<DawState>,
+) -> DawResult<AudioClipInfo> {
+    let sample_rate = engine_sample_rate(&state)?;
+    let mut tracks = lock_tracks(&state)?;
+    let clip = tracks
+        .clip_mut(clip_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio clip not found: {}", clip_id)))?;
+    let warp = clip
+        .warp
+        .as_mut()
+        .ok_or_else(|| DawError::InvalidArgument(format!("Audio clip {} is not warped", clip_id)))?;
+    match (grid_beats, index, beat) {
+        (Some(grid), _, _) => {
+            if !grid.is_finite() || grid <= 0.0 {
+                return Err(DawError::InvalidArgument(format!("Invalid grid: {} beats", grid)));
+            }
+            warp.quantize(grid);
+        }
+        (None, Some(index), Some(beat)) => warp.set_marker_beat(index, beat).map_err(DawError::InvalidArgument)?,
+        _ => {
+            return Err(DawError::InvalidArgument(
+                "Expected a marker index and beat, or a grid".to_string(),
+            ))
+        }
+    }
+    let info = clip_to_info(clip, sample_rate);
+
+    send_tracks(&tracks, &state)?;
+    Ok(info)
+}
@@ -0,0 +1,48 @@
+// Automation commands (read/write automation lanes)
+//
+// Lanes live in the shared core state, which sends them to the engine.
+// Points are exchanged in beats, like pattern notes, so the arrangement view
+// does not depend on the tempo.
+
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::lock_core;
+use crate::error::DawResult;
+use mymusic_daw::automation::{AutomationLane, AutomationParameter, AutomationPoint};
+
+/// Get the automation lane of a parameter (empty if the parameter is not automated)
+#[tauri::command]
+pub fn get_automation_lane(parameter: AutomationParameter, state: State<DawState>) -> DawResult<AutomationLane> {
+    let core = lock_core(&state)?;
+    Ok(core
+        .automation_lanes
+        .get(&parameter)
+        .cloned()
+        .unwrap_or_else(|| AutomationLane::new(parameter)))
+}
+
+/// Replace all points of a parameter's automation lane
+///
+/// Points are sorted by time and values clamped to the parameter range;
+/// the stored lane is returned.
+#[tauri::command]
+pub fn set_automation_points(
+    parameter: AutomationParameter,
+    points: Vec<AutomationPoint>,
+    state: State<DawState>,
+) -> DawResult<AutomationLane> {
+    let mut core = lock_core(&state)?;
+    core.set_automation_points(parameter, points)?;
+    Ok(core
+        .automation_lanes
+        .get(&parameter)
+        .cloned()
+        .unwrap_or_else(|| AutomationLane::new(parameter)))
+}
+
+/// Remove all points of a parameter's automation lane
+#[tauri::command]
+pub fn clear_lane(parameter: AutomationParameter, state: State<DawState>) -> DawResult<()> {
+    lock_core(&state)?.set_automation_points(parameter, Vec::new())?;
+    Ok(())
+}
@@ -1,43 +1,118 @@
 // Basic DAW commands (volume, notes, engine status)
 
-use tauri::State;
+use serde::Serialize;
+use std::sync::MutexGuard;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
 use crate::DawState;
+use crate::error::{DawError, DawResult, BRIDGE_API_VERSION};
+use crate::events::{NotificationPayload, NOTIFICATION_EVENT};
+use mymusic_daw::command::commands::{
+    CopyPatchSlotCommand, SetAdsrCommand, SetAntiAliasingCommand, SetCrossModCommand, SetFilterCommand, SetFilterModCommand,
+    SetLfoCommand, SetModEnvelopeCommand, SetModLfoCommand, SetModRoutingCommand, SetNoiseCommand, SetOscillator2Command, SetPolyModeCommand,
+    SetPortamentoCommand, SetUnisonCommand, SetVoiceModeCommand, SetVolumeCommand, SetWaveformCommand,
+    SwitchPatchSlotCommand,
+};
+use mymusic_daw::audio::thread_priority::{self, ThreadKind};
+use mymusic_daw::command::{DawState as CoreState, HistoryEntry, PatchSlot, UndoableCommand};
+use mymusic_daw::connection::reconnect::ReconnectionStrategy;
+use mymusic_daw::messaging::notification::{Notification, NotificationCategory};
 use mymusic_daw::messaging::command::Command;
 use mymusic_daw::MidiEvent;
-use mymusic_daw::synth::oscillator::WaveformType;
+use mymusic_daw::synth::noise::{NoiseParams, NoiseType};
+use mymusic_daw::synth::oscillator::{AntiAliasing, CrossModParams, Oscillator2Params, UnisonParams, WaveformType};
 use mymusic_daw::synth::envelope::AdsrParams;
-use mymusic_daw::synth::lfo::LfoParams;
-use mymusic_daw::synth::filter::FilterParams;
+use mymusic_daw::sequencer::NoteDivision;
+use mymusic_daw::synth::lfo::{LFO_COUNT, LfoDestination, LfoMode, LfoParams};
+use mymusic_daw::synth::filter::{FilterModParams, FilterParams};
 use mymusic_daw::synth::modulation::{ModRouting, ModSource, ModDestination};
 use mymusic_daw::synth::poly_mode::PolyMode;
 use mymusic_daw::synth::portamento::PortamentoParams;
 use mymusic_daw::synth::voice_manager::VoiceMode;
 
+/// How often the engine supervisor checks for watchdog faults
+const ENGINE_SUPERVISOR_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Lock the shared core state
+pub(crate) fn lock_core(state: &DawState) -> DawResult<MutexGuard<'_, CoreState>> {
+    state.core.lock().map_err(|e| DawError::poisoned("DAW state", e))
+}
+
 /// Helper function to send commands to the audio engine
-fn send_command_to_engine(command: Command, state: State<DawState>) -> Result<(), String> {
-    if let Ok(mut tx) = state.command_tx.lock() {
-        use ringbuf::traits::Producer;
-        tx.try_push(command)
-            .map_err(|_| "Failed to send command (buffer full)".to_string())?;
+pub(crate) fn send_command_to_engine(command: Command, state: State<DawState>) -> DawResult<()> {
+    if lock_core(&state)?.send_to_audio(command) {
         Ok(())
     } else {
-        Err("Failed to acquire command producer lock".to_string())
+        Err(DawError::QueueFull)
     }
 }
 
+/// Execute an undoable change on the shared core state
+fn execute_undoable(command: Box<dyn UndoableCommand>, state: &DawState) -> DawResult<()> {
+    let mut core = lock_core(state)?;
+    let mut history = state.history.lock().map_err(|e| DawError::poisoned("history", e))?;
+    Ok(history.execute(command, &mut core)?)
+}
+
 /// Set the master volume
 #[tauri::command]
-pub fn set_volume(volume: f32, state: State<DawState>) -> Result<(), String> {
+pub fn set_volume(volume: f32, state: State<DawState>) -> DawResult<()> {
     let clamped_volume = volume.clamp(0.0, 1.0);
     state.volume_atomic.set(clamped_volume);
-    Ok(())
+    execute_undoable(Box::new(SetVolumeCommand::new(clamped_volume)), &state)
+}
+
+/// Undo the last parameter change
+///
+/// Returns the description of the undone change.
+#[tauri::command]
+pub fn undo(state: State<DawState>) -> DawResult<String> {
+    let mut core = lock_core(&state)?;
+    let mut history = state.history.lock().map_err(|e| DawError::poisoned("history", e))?;
+    let description = history.undo(&mut core)?;
+    // Volume reaches the engine through the atomic, not the command channel
+    state.volume_atomic.set(core.volume);
+    Ok(description)
+}
+
+/// Redo the last undone parameter change
+///
+/// Returns the description of the redone change.
+#[tauri::command]
+pub fn redo(state: State<DawState>) -> DawResult<String> {
+    let mut core = lock_core(&state)?;
+    let mut history = state.history.lock().map_err(|e| DawError::poisoned("history", e))?;
+    let description = history.redo(&mut core)?;
+    state.volume_atomic.set(core.volume);
+    Ok(description)
+}
+
+/// Get the steps of the undo history, oldest first
+#[tauri::command]
+pub fn get_history(state: State<DawState>) -> DawResult<Vec<HistoryEntry>> {
+    let history = state.history.lock().map_err(|e| DawError::poisoned("history", e))?;
+    Ok(history.history())
+}
+
+/// Undo or redo until `index` changes of the history are applied (0: none)
+///
+/// Returns the number of changes undone or redone.
+#[tauri::command]
+pub fn jump_to_history(index: usize, state: State<DawState>) -> DawResult<usize> {
+    let mut core = lock_core(&state)?;
+    let mut history = state.history.lock().map_err(|e| DawError::poisoned("history", e))?;
+    let result = history.jump_to(index, &mut core);
+    // Also after a failed jump, which stops partway
+    state.volume_atomic.set(core.volume);
+    Ok(result?)
 }
 
 /// Play a MIDI note
 #[tauri::command]
-pub fn play_note(note: u8, velocity: u8, state: State<DawState>) -> Result<(), String> {
+pub fn play_note(note: u8, velocity: u8, state: State<DawState>) -> DawResult<()> {
     if velocity == 0 {
-        return Err("Velocity must be greater than 0".to_string());
+        return Err(DawError::InvalidArgument("Velocity must be greater than 0".to_string()));
     }
 
     let midi_event = MidiEvent::NoteOn { note, velocity };
@@ -51,7 +126,7 @@ pub fn play_note(note: u8, velocity: u8, state: State<DawState>) -> Result<(), S
 
 /// Stop a MIDI note
 #[tauri::command]
-pub fn stop_note(note: u8, state: State<DawState>) -> Result<(), String> {
+pub fn stop_note(note: u8, state: State<DawState>) -> DawResult<()> {
     let midi_event = MidiEvent::NoteOff { note };
     let command = Command::Midi(mymusic_daw::MidiEventTimed {
         event: midi_event,
@@ -63,32 +138,187 @@ pub fn stop_note(note: u8, state: State<DawState>) -> Result<(), String> {
 
 /// Get current master volume
 #[tauri::command]
-pub fn get_volume(state: State<DawState>) -> Result<f32, String> {
+pub fn get_volume(state: State<DawState>) -> DawResult<f32> {
     Ok(state.volume_atomic.get())
 }
 
 /// Get DAW engine status/info
 #[tauri::command]
-pub fn get_engine_status() -> Result<serde_json::Value, String> {
+pub fn get_engine_status(state: State<DawState>) -> DawResult<serde_json::Value> {
+    let engine = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?;
     Ok(serde_json::json!({
         "name": "MyMusic DAW",
         "version": env!("CARGO_PKG_VERSION"),
-        "status": "running",
+        "api_version": BRIDGE_API_VERSION,
+        "status": if engine.is_running() { "running" } else { "stopped" },
         "audio_engine": "CPAL",
-        "sample_rate": 44100,
-        "buffer_size": 512
+        "sample_rate": engine.sample_rate(),
+        "buffer_size": 512,
+        "realtime_priority": thread_priority::status(ThreadKind::Audio).to_string()
+    }))
+}
+
+/// Get the state published by the audio engine at the end of its last buffer
+///
+/// The playhead is the one the engine plays: poll this instead of advancing a
+/// transport position in the frontend.
+#[tauri::command]
+pub fn get_engine_snapshot(state: State<DawState>) -> DawResult<serde_json::Value> {
+    let snapshot = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?.snapshot();
+    Ok(serde_json::json!({
+        "position_samples": snapshot.position_samples,
+        "position_seconds": snapshot.position_seconds(),
+        "playing": snapshot.playing,
+        "tempo": snapshot.tempo,
+        "sample_rate": snapshot.sample_rate,
+        "output_channels": snapshot.output_channels,
+        "active_voices": snapshot.active_voices,
+        "cpu_load": snapshot.cpu_load,
+        "peak_left": snapshot.peak_left,
+        "peak_right": snapshot.peak_right,
+        "compressor_reduction_db": snapshot.compressor_reduction_db,
+        "limiter_reduction_db": snapshot.limiter_reduction_db
     }))
 }
 
+/// Enable/disable real-time priority for the audio and MIDI threads
+///
+/// Applies from the next engine start (see `restart_engine`).
+#[tauri::command]
+pub fn set_realtime_priority(enabled: bool) {
+    thread_priority::set_enabled(enabled);
+}
+
+/// Set the maximum number of voices (1 to 16)
+///
+/// The engine may use fewer voices while the CPU load stays high.
+#[tauri::command]
+pub fn set_max_voices(max_voices: usize, state: State<DawState>) -> DawResult<()> {
+    Ok(lock_core(&state)?.set_max_voices(max_voices)?)
+}
+
+/// Get the bridge API version (bumped on breaking command/error changes)
+#[tauri::command]
+pub fn get_bridge_api_version() -> u32 {
+    BRIDGE_API_VERSION
+}
+
 /// Alias for get_engine_status (for frontend compatibility)
 #[tauri::command]
-pub fn get_engine_info() -> Result<serde_json::Value, String> {
-    get_engine_status()
+pub fn get_engine_info(state: State<DawState>) -> DawResult<serde_json::Value> {
+    get_engine_status(state)
+}
+
+/// Stop the audio engine (closes the audio stream)
+#[tauri::command]
+pub fn shutdown_engine(state: State<DawState>) -> DawResult<()> {
+    println!("🔇 Shutting down audio engine");
+    let mut engine = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?;
+    engine.shutdown();
+    Ok(())
+}
+
+/// Restart the audio engine on the current default output device
+///
+/// Loaded samples, the shared DAW state and the active pattern are sent again to the new engine.
+/// Returns the new sample rate.
+#[tauri::command]
+pub fn restart_engine(state: State<DawState>) -> DawResult<f32> {
+    println!("🔄 Restarting audio engine");
+
+    let sample_rate = {
+        let mut engine = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?;
+        let (command_tx, sample_rate) = engine.start(&state.volume_atomic, &state.synth_params).map_err(DawError::Engine)?;
+        lock_core(&state)?.set_command_sender(command_tx);
+        sample_rate
+    };
+
+    replay_engine_state(&state)?;
+    Ok(sample_rate)
+}
+
+/// Start the background loop restarting the audio engine when its watchdog reports a fault
+///
+/// Restarts are retried with exponential backoff; the frontend is notified if they all fail.
+pub fn spawn_engine_supervisor<R: Runtime>(app: AppHandle<R>) {
+    thread::Builder::new()
+        .name("engine-supervisor".to_string())
+        .spawn(move || loop {
+            thread::sleep(ENGINE_SUPERVISOR_INTERVAL);
+
+            let state = app.state::<DawState>();
+            let fault = match state.engine.lock() {
+                Ok(mut engine) => engine.take_fault(),
+                Err(_) => return,
+            };
+            let Some(fault) = fault else {
+                continue;
+            };
+
+            eprintln!("⚠️ Audio engine fault ({}), restarting", fault);
+            let mut strategy = ReconnectionStrategy::new();
+            loop {
+                match restart_engine(state.clone()) {
+                    Ok(_) => break,
+                    Err(e) => match strategy.next_delay() {
+                        Some(delay) => {
+                            eprintln!("❌ Engine restart failed: {}, retrying in {:?}", e, delay);
+                            thread::sleep(delay);
+                        }
+                        None => {
+                            let notification = Notification::error(
+                                NotificationCategory::Audio,
+                                format!("Audio engine could not be restarted: {}", e),
+                            );
+                            let _ = app.emit(NOTIFICATION_EVENT, &NotificationPayload::from(notification));
+                            break;
+                        }
+                    },
+                }
+            }
+        })
+        .expect("failed to spawn engine supervisor thread");
+}
+
+/// Send the state owned by the frontend to a freshly started engine
+fn replay_engine_state(state: &State<DawState>) -> DawResult<()> {
+    {
+        let sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+        for loaded in &sampler.samples {
+            send_command_to_engine(Command::AddSample(loaded.sample.clone()), state.clone())?;
+        }
+        for (&note, &sample_index) in &sampler.note_mappings {
+            send_command_to_engine(Command::SetNoteSampleMapping { note, sample_index }, state.clone())?;
+        }
+        for (&sample_index, &zone) in &sampler.zones {
+            send_command_to_engine(
+                Command::SetSampleZone {
+                    sample_index,
+                    zone: Some(zone),
+                },
+                state.clone(),
+            )?;
+        }
+    }
+
+    lock_core(state)?.sync_audio()?;
+
+    let active_pattern = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?.playing_pattern();
+    if let Some(pattern) = active_pattern {
+        send_command_to_engine(Command::SetPattern(pattern), state.clone())?;
+    }
+
+    let audio_tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    if !audio_tracks.is_empty() {
+        send_command_to_engine(Command::SetAudioTracks(audio_tracks.snapshot()), state.clone())?;
+    }
+
+    Ok(())
 }
 
 /// Play a test beep sound
 #[tauri::command]
-pub fn play_test_beep() -> Result<String, String> {
+pub fn play_test_beep() -> DawResult<String> {
     println!("🔊 Playing test beep (note 60, A4, 440Hz)");
     Ok("Test beep played successfully".to_string())
 }
@@ -97,53 +327,168 @@ pub fn play_test_beep() -> Result<String, String> {
 
 /// Set oscillator waveform type
 #[tauri::command]
-pub fn set_waveform(waveform: String, state: State<DawState>) -> Result<(), String> {
+pub fn set_waveform(waveform: String, state: State<DawState>) -> DawResult<()> {
     let waveform_type = match waveform.as_str() {
         "sine" => WaveformType::Sine,
         "square" => WaveformType::Square,
         "saw" => WaveformType::Saw,
         "triangle" => WaveformType::Triangle,
-        _ => return Err(format!("Invalid waveform: {}", waveform)),
+        _ => return Err(DawError::InvalidArgument(format!("Invalid waveform: {}", waveform))),
     };
 
-    let command = Command::SetWaveform(waveform_type);
-    send_command_to_engine(command, state)
+    execute_undoable(Box::new(SetWaveformCommand::new(waveform_type)), &state)
+}
+
+/// Set the second oscillator (coarse in semitones, fine in cents, mix 0 - 1)
+#[tauri::command]
+pub fn set_oscillator2(waveform: String, coarse: i32, fine: f32, mix: f32, state: State<DawState>) -> DawResult<()> {
+    let waveform_type = match waveform.as_str() {
+        "sine" => WaveformType::Sine,
+        "square" => WaveformType::Square,
+        "saw" => WaveformType::Saw,
+        "triangle" => WaveformType::Triangle,
+        _ => return Err(DawError::InvalidArgument(format!("Invalid waveform: {}", waveform))),
+    };
+
+    let params = Oscillator2Params::new(waveform_type, coarse, fine, mix);
+    execute_undoable(Box::new(SetOscillator2Command::new(params)), &state)
+}
+
+/// Set the unison stack (2 to 8 voices per note, detune in cents, spread 0 - 1)
+#[tauri::command]
+pub fn set_unison(voices: usize, detune: f32, spread: f32, state: State<DawState>) -> DawResult<()> {
+    let params = UnisonParams::new(voices, detune, spread);
+    execute_undoable(Box::new(SetUnisonCommand::new(params)), &state)
+}
+
+/// Set how the oscillators are band-limited ("polyblep" or "wavetable")
+#[tauri::command]
+pub fn set_anti_aliasing(method: String, state: State<DawState>) -> DawResult<()> {
+    let anti_aliasing = match method.as_str() {
+        "polyblep" => AntiAliasing::PolyBlep,
+        "wavetable" => AntiAliasing::Wavetable,
+        _ => return Err(DawError::InvalidArgument(format!("Invalid anti-aliasing method: {}", method))),
+    };
+
+    execute_undoable(Box::new(SetAntiAliasingCommand::new(anti_aliasing)), &state)
+}
+
+/// Set the cross-modulation of oscillator 1 by oscillator 2 (FM and ring amounts 0 - 1)
+#[tauri::command]
+pub fn set_cross_mod(fm: f32, ring: f32, state: State<DawState>) -> DawResult<()> {
+    let params = CrossModParams::new(fm, ring);
+    execute_undoable(Box::new(SetCrossModCommand::new(params)), &state)
+}
+
+/// Set the noise source ("white" or "pink", level 0 - 1)
+#[tauri::command]
+pub fn set_noise(noise_type: String, level: f32, state: State<DawState>) -> DawResult<()> {
+    let noise_type = match noise_type.as_str() {
+        "white" => NoiseType::White,
+        "pink" => NoiseType::Pink,
+        _ => return Err(DawError::InvalidArgument(format!("Invalid noise type: {}", noise_type))),
+    };
+
+    let params = NoiseParams::new(noise_type, level);
+    execute_undoable(Box::new(SetNoiseCommand::new(params)), &state)
 }
 
 /// Set ADSR envelope parameters
 #[tauri::command]
-pub fn set_adsr(attack: f32, decay: f32, sustain: f32, release: f32, state: State<DawState>) -> Result<(), String> {
+pub fn set_adsr(attack: f32, decay: f32, sustain: f32, release: f32, state: State<DawState>) -> DawResult<()> {
     let params = AdsrParams::new(attack, decay, sustain, release);
-    let command = Command::SetAdsr(params);
-    send_command_to_engine(command, state)
+    execute_undoable(Box::new(SetAdsrCommand::new(params)), &state)
 }
 
-/// Set LFO parameters
+/// Set the modulation envelope (routed by the mod matrix, source "mod_envelope")
 #[tauri::command]
-pub fn set_lfo(waveform: String, rate: f32, depth: f32, destination: String, state: State<DawState>) -> Result<(), String> {
-    let lfo_waveform = match waveform.as_str() {
-        "sine" => WaveformType::Sine,
-        "square" => WaveformType::Square,
-        "saw" => WaveformType::Saw,
-        "triangle" => WaveformType::Triangle,
-        _ => return Err(format!("Invalid LFO waveform: {}", waveform)),
+pub fn set_mod_envelope(attack: f32, decay: f32, sustain: f32, release: f32, state: State<DawState>) -> DawResult<()> {
+    let params = AdsrParams::new(attack, decay, sustain, release);
+    execute_undoable(Box::new(SetModEnvelopeCommand::new(params)), &state)
+}
+
+fn parse_lfo_waveform(waveform: &str) -> DawResult<WaveformType> {
+    match waveform {
+        "sine" => Ok(WaveformType::Sine),
+        "square" => Ok(WaveformType::Square),
+        "saw" => Ok(WaveformType::Saw),
+        "triangle" => Ok(WaveformType::Triangle),
+        _ => Err(DawError::InvalidArgument(format!("Invalid LFO waveform: {}", waveform))),
+    }
+}
+
+/// Add the tempo sync ("1/4", "1/8.", "1/16T"; None: rate in Hz), the mode
+/// ("free", "retrigger", "one_shot") and the start phase to LFO parameters
+fn with_lfo_timing(
+    params: LfoParams,
+    sync: Option<String>,
+    mode: Option<String>,
+    start_phase: Option<f32>,
+) -> DawResult<LfoParams> {
+    let sync = sync
+        .map(|division| division.parse::<NoteDivision>())
+        .transpose()
+        .map_err(DawError::InvalidArgument)?;
+    let mode = match mode.as_deref() {
+        None | Some("retrigger") => LfoMode::Retrigger,
+        Some("free") => LfoMode::Free,
+        Some("one_shot") => LfoMode::OneShot,
+        Some(other) => return Err(DawError::InvalidArgument(format!("Invalid LFO mode: {}", other))),
     };
+    Ok(params.with_timing(sync, mode, start_phase.unwrap_or(0.0)))
+}
+
+/// Set LFO parameters
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn set_lfo(
+    waveform: String,
+    rate: f32,
+    depth: f32,
+    destination: String,
+    sync: Option<String>,
+    mode: Option<String>,
+    start_phase: Option<f32>,
+    state: State<DawState>,
+) -> DawResult<()> {
+    let lfo_waveform = parse_lfo_waveform(&waveform)?;
 
     let lfo_destination = match destination.as_str() {
         "pitch" => mymusic_daw::synth::lfo::LfoDestination::Pitch,
         "volume" => mymusic_daw::synth::lfo::LfoDestination::Volume,
         "filter" => mymusic_daw::synth::lfo::LfoDestination::FilterCutoff,
-        _ => return Err(format!("Invalid LFO destination: {}", destination)),
+        _ => return Err(DawError::InvalidArgument(format!("Invalid LFO destination: {}", destination))),
     };
 
     let params = LfoParams::new(lfo_waveform, rate, depth, lfo_destination);
-    let command = Command::SetLfo(params);
-    send_command_to_engine(command, state)
+    let params = with_lfo_timing(params, sync, mode, start_phase)?;
+    execute_undoable(Box::new(SetLfoCommand::new(params)), &state)
+}
+
+/// Set a matrix LFO (`lfo` 2 or 3, routed with the sources "lfo2"/"lfo3")
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn set_mod_lfo(
+    lfo: u8,
+    waveform: String,
+    rate: f32,
+    depth: f32,
+    sync: Option<String>,
+    mode: Option<String>,
+    start_phase: Option<f32>,
+    state: State<DawState>,
+) -> DawResult<()> {
+    if !(2..=LFO_COUNT as u8).contains(&lfo) {
+        return Err(DawError::InvalidArgument(format!("Invalid matrix LFO: {}", lfo)));
+    }
+    let params = LfoParams::new(parse_lfo_waveform(&waveform)?, rate, depth, LfoDestination::None);
+    let params = with_lfo_timing(params, sync, mode, start_phase)?;
+    execute_undoable(Box::new(SetModLfoCommand::new(lfo - 1, params)), &state)
 }
 
 /// Set filter parameters
 #[tauri::command]
-pub fn set_filter(filter_type: String, cutoff: f32, resonance: f32, state: State<DawState>) -> Result<(), String> {
+pub fn set_filter(filter_type: String, cutoff: f32, resonance: f32, state: State<DawState>) -> DawResult<()> {
     use mymusic_daw::synth::filter::FilterType;
     
     let ft = match filter_type.as_str() {
@@ -151,7 +496,7 @@ pub fn set_filter(filter_type: String, cutoff: f32, resonance: f32, state: State
         "highpass" => FilterType::HighPass,
         "bandpass" => FilterType::BandPass,
         "notch" => FilterType::Notch,
-        _ => return Err(format!("Invalid filter type: {}", filter_type)),
+        _ => return Err(DawError::InvalidArgument(format!("Invalid filter type: {}", filter_type))),
     };
 
     let params = FilterParams {
@@ -160,54 +505,74 @@ pub fn set_filter(filter_type: String, cutoff: f32, resonance: f32, state: State
         filter_type: ft,
         enabled: true,
     };
-    let command = Command::SetFilter(params);
-    send_command_to_engine(command, state)
+    execute_undoable(Box::new(SetFilterCommand::new(params)), &state)
+}
+
+/// Set the filter envelope amount (octaves, driven by the mod envelope), key tracking and drive
+#[tauri::command]
+pub fn set_filter_mod(envelope_amount: f32, key_tracking: f32, drive: f32, state: State<DawState>) -> DawResult<()> {
+    let params = FilterModParams::new(envelope_amount, key_tracking, drive);
+    execute_undoable(Box::new(SetFilterModCommand::new(params)), &state)
 }
 
 /// Set polyphony mode
 #[tauri::command]
-pub fn set_poly_mode(mode: String, state: State<DawState>) -> Result<(), String> {
+pub fn set_poly_mode(mode: String, state: State<DawState>) -> DawResult<()> {
     let poly_mode = match mode.as_str() {
         "poly" => PolyMode::Poly,
         "mono" => PolyMode::Mono,
         "legato" => PolyMode::Legato,
-        _ => return Err(format!("Invalid polyphony mode: {}", mode)),
+        _ => return Err(DawError::InvalidArgument(format!("Invalid polyphony mode: {}", mode))),
     };
 
-    let command = Command::SetPolyMode(poly_mode);
-    send_command_to_engine(command, state)
+    execute_undoable(Box::new(SetPolyModeCommand::new(poly_mode)), &state)
 }
 
 /// Set portamento (glide) parameters
 #[tauri::command]
-pub fn set_portamento(time: f32, state: State<DawState>) -> Result<(), String> {
+pub fn set_portamento(time: f32, state: State<DawState>) -> DawResult<()> {
     let params = PortamentoParams::new(time);
-    let command = Command::SetPortamento(params);
-    send_command_to_engine(command, state)
+    execute_undoable(Box::new(SetPortamentoCommand::new(params)), &state)
+}
+
+/// Set the pitch bend range in semitones (0 - 24)
+#[tauri::command]
+pub fn set_pitch_bend_range(semitones: f32, state: State<DawState>) -> DawResult<()> {
+    Ok(lock_core(&state)?.set_pitch_bend_range(semitones)?)
 }
 
 /// Set voice mode (Synth vs Sampler)
 #[tauri::command]
-pub fn set_voice_mode(mode: String, state: State<DawState>) -> Result<(), String> {
+pub fn set_voice_mode(mode: String, state: State<DawState>) -> DawResult<()> {
     let voice_mode = match mode.as_str() {
         "synth" => VoiceMode::Synth,
         "sampler" => VoiceMode::Sampler,
-        _ => return Err(format!("Invalid voice mode: {}", mode)),
+        _ => return Err(DawError::InvalidArgument(format!("Invalid voice mode: {}", mode))),
     };
 
-    let command = Command::SetVoiceMode(voice_mode);
-    send_command_to_engine(command, state)
+    execute_undoable(Box::new(SetVoiceModeCommand::new(voice_mode)), &state)
 }
 
 /// Set modulation routing
 #[tauri::command]
-pub fn set_mod_routing(index: u8, source: String, destination: String, amount: f32, state: State<DawState>) -> Result<(), String> {
+pub fn set_mod_routing(index: u8, source: String, destination: String, amount: f32, state: State<DawState>) -> DawResult<()> {
     let mod_source = match source.as_str() {
         "lfo" => ModSource::Lfo(0),
+        "lfo2" => ModSource::Lfo(1),
+        "lfo3" => ModSource::Lfo(2),
         "velocity" => ModSource::Velocity,
         "aftertouch" => ModSource::Aftertouch,
         "envelope" => ModSource::Envelope,
-        _ => return Err(format!("Invalid modulation source: {}", source)),
+        "mod_wheel" => ModSource::ModWheel,
+        "mod_envelope" => ModSource::ModEnvelope,
+        "pitch_bend" => ModSource::PitchBend,
+        "key_track" => ModSource::KeyTrack,
+        "random" => ModSource::Random,
+        // "cc74": any MIDI controller
+        cc => match cc.strip_prefix("cc").and_then(|n| n.parse::<u8>().ok()) {
+            Some(controller) if controller < 128 => ModSource::MidiCc(controller),
+            _ => return Err(DawError::InvalidArgument(format!("Invalid modulation source: {}", source))),
+        },
     };
 
     let mod_destination = match destination.as_str() {
@@ -215,8 +580,22 @@ pub fn set_mod_routing(index: u8, source: String, destination: String, amount: f
         "amplitude" => ModDestination::Amplitude,
         "filter" => ModDestination::FilterCutoff,
         "pan" => ModDestination::Pan,
-        _ => return Err(format!("Invalid modulation destination: {}", destination)),
+        "resonance" => ModDestination::FilterResonance,
+        "osc_mix" => ModDestination::OscillatorMix,
+        "lfo1_rate" => ModDestination::LfoRate(0),
+        "lfo2_rate" => ModDestination::LfoRate(1),
+        "lfo3_rate" => ModDestination::LfoRate(2),
+        "lfo1_depth" => ModDestination::LfoDepth(0),
+        "lfo2_depth" => ModDestination::LfoDepth(1),
+        "lfo3_depth" => ModDestination::LfoDepth(2),
+        "env_time" => ModDestination::EnvelopeTime,
+        "mod_env_time" => ModDestination::ModEnvelopeTime,
+        "delay_send" => ModDestination::DelaySend,
+        "reverb_send" => ModDestination::ReverbSend,
+        _ => return Err(DawError::InvalidArgument(format!("Invalid modulation destination: {}", destination))),
     };
+    let range = mod_destination.amount_range();
+    let amount = amount.clamp(*range.start(), *range.end());
 
     let routing = ModRouting {
         source: mod_source,
@@ -224,20 +603,62 @@ pub fn set_mod_routing(index: u8, source: String, destination: String, amount: f
         amount,
         enabled: true,
     };
-    let command = Command::SetModRouting { index, routing };
-    send_command_to_engine(command, state)
+    execute_undoable(Box::new(SetModRoutingCommand::new(index, routing)), &state)
 }
 
 /// Clear modulation routing
 #[tauri::command]
-pub fn clear_mod_routing(index: u8, state: State<DawState>) -> Result<(), String> {
-    let command = Command::ClearModRouting { index };
-    send_command_to_engine(command, state)
+pub fn clear_mod_routing(index: u8, state: State<DawState>) -> DawResult<()> {
+    let mirrored = lock_core(&state)?.mod_routings.get(index as usize).copied();
+    match mirrored {
+        // Disabling the mirrored routing keeps the change undoable
+        Some(routing) => {
+            let cleared = ModRouting {
+                amount: 0.0,
+                enabled: false,
+                ..routing
+            };
+            execute_undoable(Box::new(SetModRoutingCommand::new(index, cleared)), &state)
+        }
+        None => send_command_to_engine(Command::ClearModRouting { index }, state),
+    }
+}
+
+/// A/B patch comparison status
+#[derive(Debug, Clone, Serialize)]
+pub struct PatchSlotsInfo {
+    pub active: PatchSlot,
+    pub a_filled: bool,
+    pub b_filled: bool,
+}
+
+/// Get the A/B patch comparison status
+#[tauri::command]
+pub fn get_patch_slots(state: State<DawState>) -> DawResult<PatchSlotsInfo> {
+    let core = lock_core(&state)?;
+    let comparison = &core.ab_comparison;
+    Ok(PatchSlotsInfo {
+        active: comparison.active(),
+        a_filled: comparison.is_filled(PatchSlot::A),
+        b_filled: comparison.is_filled(PatchSlot::B),
+    })
+}
+
+/// Switch to the other A/B patch slot (the live sound is kept in the current one)
+#[tauri::command]
+pub fn switch_patch_slot(state: State<DawState>) -> DawResult<()> {
+    execute_undoable(Box::new(SwitchPatchSlotCommand::new()), &state)
+}
+
+/// Copy the sound of an A/B patch slot into the other one
+#[tauri::command]
+pub fn copy_patch_slot(from: PatchSlot, to: PatchSlot, state: State<DawState>) -> DawResult<()> {
+    execute_undoable(Box::new(CopyPatchSlotCommand::new(from, to)), &state)
 }
 
 /// Initialize event system (call this once when app starts)
 #[tauri::command]
-pub fn initialize_events() -> Result<(), String> {
+pub fn initialize_events() -> DawResult<()> {
     // This will be called from frontend to initialize the event system
     // The actual app handle will be set up in the main Tauri setup
     Ok(())
@@ -287,7 +708,7 @@ mod tests {
         // Test zero velocity (should fail)
         let result = play_note(60, 0, state.into());
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Velocity must be greater than 0"));
+        assert!(result.unwrap_err().message().contains("Velocity must be greater than 0"));
     }
 
     #[test]
@@ -318,6 +739,6 @@ mod tests {
         // Test invalid waveform
         let result = set_waveform("invalid".to_string(), state.into());
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Invalid waveform"));
+        assert!(result.unwrap_err().message().contains("Invalid waveform"));
     }
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+// Track dynamics and sidechain routing commands
+//
+// The synth and each audio track can run a compressor/gate insert whose
+// detector is keyed by any track (see `mymusic_daw::audio::clip_player`). The
+// synth insert and the plugin sidechain live in the core state (replayed after
+// an engine restart), the audio track inserts in `DawState::audio_tracks`.
+
+use serde::Serialize;
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::{lock_core, send_command_to_engine};
+use crate::error::{DawError, DawResult};
+use mymusic_daw::audio::clip_player::{AudioTrackList, SidechainSource, TrackDynamics};
+use mymusic_daw::messaging::command::Command;
+
+/// Track that can key a sidechain
+#[derive(Debug, Clone, Serialize)]
+pub struct SidechainSourceInfo {
+    pub source: SidechainSource,
+    pub name: String,
+}
+
+/// Dynamics inserts and sidechain routing sent to the frontend (mixer strips)
+#[derive(Debug, Clone, Serialize)]
+pub struct SidechainRouting {
+    /// Insert of the synth track
+    pub synth_dynamics: Option<TrackDynamics>,
+    /// Track feeding the sidechain input of the plugins
+    pub plugin_sidechain: Option<SidechainSource>,
+    /// Choices of the sidechain pickers
+    pub sources: Vec<SidechainSourceInfo>,
+}
+
+fn check_source(source: Option<SidechainSource>, tracks: &AudioTrackList) -> DawResult<()> {
+    match source {
+        Some(SidechainSource::AudioTrack(id)) if tracks.track(id).is_none() => {
+            Err(DawError::NotFound(format!("Sidechain source track not found: {}", id)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Set the compressor/gate insert of a track (None removes it)
+///
+/// `audio_track_id` selects an audio track, the synth track when omitted.
+/// Parameters are clamped to their ranges.
+#[tauri::command]
+pub fn set_track_dynamics(
+    audio_track_id: Option<u32>,
+    dynamics: Option<TrackDynamics>,
+    state: State<DawState>,
+) -> DawResult<Option<TrackDynamics>> {
+    let dynamics = dynamics.map(|mut dynamics| {
+        dynamics.params.validate();
+        dynamics
+    });
+    let mut tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    check_source(dynamics.and_then(|dynamics| dynamics.sidechain), &tracks)?;
+
+    let Some(track_id) = audio_track_id else {
+        let mut core = lock_core(&state)?;
+        core.set_synth_dynamics(dynamics)?;
+        return Ok(core.synth_dynamics);
+    };
+    let track = tracks
+        .track_mut(track_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio track not found: {}", track_id)))?;
+    track.dynamics = dynamics;
+    send_command_to_engine(Command::SetAudioTracks(tracks.snapshot()), state.clone())?;
+    Ok(dynamics)
+}
+
+/// Route a track to the sidechain input of the plugins (None disconnects it)
+#[tauri::command]
+pub fn set_plugin_sidechain(source: Option<SidechainSource>, state: State<DawState>) -> DawResult<()> {
+    let tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    check_source(source, &tracks)?;
+    Ok(lock_core(&state)?.set_plugin_sidechain(source)?)
+}
+
+/// Current inserts and sidechain routing, with the tracks that can key them
+#[tauri::command]
+pub fn get_sidechain_routing(state: State<DawState>) -> DawResult<SidechainRouting> {
+    let tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    let sources = std::iter::once(SidechainSourceInfo {
+        source: SidechainSource::Synth,
+        name: "Synth".to_string(),
+    })
+    .chain(tracks.tracks().iter().map(|track| SidechainSourceInfo {
+        source: SidechainSource::AudioTrack(track.id),
+        name: track.name.clone(),
+    }))
+    .collect();
+
+    let core = lock_core(&state)?;
+    Ok(SidechainRouting {
+        synth_dynamics: core.synth_dynamics,
+        plugin_sidechain: core.plugin_sidechain,
+        sources,
+    })
+}
@@ -0,0 +1,48 @@
+// Offline export command
+//
+// Bounces the project built from the current state (see
+// `rescue::build_project`) to a WAV file, faster than real time: each track
+// runs its own voices and sequencer player (see `mymusic_daw::audio::export`).
+// The render runs on the command thread without holding the state locks.
+
+use tauri::State;
+use crate::DawState;
+use crate::commands::rescue::build_project;
+use crate::error::{DawError, DawResult};
+use mymusic_daw::audio::export::{RenderRange, WAV_BIT_DEPTHS};
+use std::path::Path;
+
+/// Render the project to a WAV file over a bar range, returns the written path
+///
+/// Bars are numbered from 1 and `end_bar` is inclusive (default: end of the
+/// longest pattern). `bit_depth` is 16, 24 (default) or 32 (floating point).
+#[tauri::command]
+pub fn render_to_wav(
+    path: String,
+    start_bar: Option<u32>,
+    end_bar: Option<u32>,
+    bit_depth: Option<u16>,
+    state: State<DawState>,
+) -> DawResult<String> {
+    let start_bar = start_bar.unwrap_or(1);
+    if start_bar == 0 {
+        return Err(DawError::InvalidArgument("Bars are numbered from 1".to_string()));
+    }
+    if end_bar.is_some_and(|end| end < start_bar) {
+        return Err(DawError::InvalidArgument(format!("Invalid range: bar {} is before bar {}", end_bar.unwrap_or(0), start_bar)));
+    }
+    let bit_depth = bit_depth.unwrap_or(24);
+    if !WAV_BIT_DEPTHS.contains(&bit_depth) {
+        return Err(DawError::InvalidArgument(format!("Invalid bit depth: {} (16, 24 or 32)", bit_depth)));
+    }
+
+    let project = build_project(&state)?;
+    let range = RenderRange {
+        start_bar: start_bar - 1,
+        end_bar,
+        tail_seconds: 0.0,
+    };
+    mymusic_daw::audio::export::render_to_wav(&project, Path::new(&path), &range, bit_depth)
+        .map_err(DawError::Engine)?;
+    Ok(path)
+}
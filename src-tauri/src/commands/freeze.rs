@@ -0,0 +1,141 @@
+// Track freeze commands
+//
+// Freezing renders the active pattern with the current synth sound to an audio
+// track (see `mymusic_daw::audio::freeze`) and sends an empty pattern to the
+// sequencer, so the synth voices stop using CPU. The frozen pattern cannot be
+// edited or switched; unfreezing removes the audio track, deletes the render
+// and sends the pattern again.
+
+use serde::Serialize;
+use tauri::State;
+use crate::{DawState, PatternStore};
+use crate::commands::basic::{lock_core, send_command_to_engine};
+use crate::commands::rescue::build_project;
+use crate::error::{DawError, DawResult};
+use mymusic_daw::audio::clip_player::{SidechainSource, TrackDynamics};
+use mymusic_daw::audio::freeze::{freeze_track, FrozenTrack};
+use mymusic_daw::messaging::command::Command;
+use mymusic_daw::sequencer::PatternId;
+use mymusic_daw::synth::voice_manager::VoiceMode;
+
+/// Active pattern rendered to an audio track
+#[derive(Debug)]
+pub struct FrozenPattern {
+    pub pattern_id: PatternId,
+    /// Audio track playing the render
+    pub audio_track_id: u32,
+    pub track: FrozenTrack,
+}
+
+/// Freeze state sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct FreezeStatus {
+    /// Frozen pattern (None while the synth plays live)
+    pub pattern_id: Option<PatternId>,
+    /// Audio track playing the render
+    pub audio_track_id: Option<u32>,
+}
+
+fn freeze_status(frozen: Option<&FrozenPattern>) -> FreezeStatus {
+    FreezeStatus {
+        pattern_id: frozen.map(|frozen| frozen.pattern_id),
+        audio_track_id: frozen.map(|frozen| frozen.audio_track_id),
+    }
+}
+
+/// Send the pattern the sequencer plays (empty while frozen)
+fn send_playing_pattern(store: &PatternStore, state: &State<DawState>) -> DawResult<()> {
+    match store.playing_pattern() {
+        Some(pattern) => send_command_to_engine(Command::SetPattern(pattern), state.clone()),
+        None => Ok(()),
+    }
+}
+
+/// Render the active pattern to an audio track and silence the synth voices
+#[tauri::command]
+pub fn freeze_synth_track(state: State<DawState>) -> DawResult<FreezeStatus> {
+    if lock_core(&state)?.voice_mode != VoiceMode::Synth {
+        return Err(DawError::InvalidArgument("Freeze renders the synth: switch to synth mode first".to_string()));
+    }
+    let pattern_id = {
+        let store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+        if store.frozen.is_some() {
+            return Err(DawError::InvalidArgument("The synth track is already frozen".to_string()));
+        }
+        store
+            .active_pattern_id
+            .ok_or_else(|| DawError::NotFound("No active pattern to freeze".to_string()))?
+    };
+
+    // Rendered without holding the locks (offline, faster than real time)
+    let project = build_project(&state)?;
+    let frozen = freeze_track(&project, 0, project.metadata.sample_rate as u32).map_err(DawError::Engine)?;
+
+    let mut store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+    if store.frozen.is_some() || store.active_pattern_id != Some(pattern_id) {
+        frozen.discard();
+        return Err(DawError::InvalidArgument("The active pattern changed while freezing".to_string()));
+    }
+    // The render is dry: the synth insert, sends, output and panner move to the audio track
+    let (dynamics, sends, output, surround) = {
+        let core = lock_core(&state)?;
+        let dynamics = core.synth_dynamics.map(|insert| TrackDynamics {
+            sidechain: insert.sidechain.filter(|source| *source != SidechainSource::Synth),
+            ..insert
+        });
+        (dynamics, core.synth_sends, core.synth_output, core.synth_surround)
+    };
+    let audio_track_id = {
+        let mut tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+        let id = tracks.add_track(frozen.clip.name.clone());
+        if let Some(track) = tracks.track_mut(id) {
+            track.frozen = Some(frozen.track_id);
+            track.dynamics = dynamics;
+            track.sends = sends;
+            track.output = output;
+            track.surround = surround;
+        }
+        tracks.add_clip(id, frozen.clip.clone());
+        send_command_to_engine(Command::SetAudioTracks(tracks.snapshot()), state.clone())?;
+        id
+    };
+    store.frozen = Some(FrozenPattern {
+        pattern_id,
+        audio_track_id,
+        track: frozen,
+    });
+    send_playing_pattern(&store, &state)?;
+
+    Ok(freeze_status(store.frozen.as_ref()))
+}
+
+/// Give the pattern back to the synth voices (the render is deleted)
+#[tauri::command]
+pub fn unfreeze_synth_track(state: State<DawState>) -> DawResult<FreezeStatus> {
+    release_freeze(&state)?;
+    Ok(freeze_status(None))
+}
+
+/// Current freeze state
+#[tauri::command]
+pub fn get_freeze_status(state: State<DawState>) -> DawResult<FreezeStatus> {
+    let store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+    Ok(freeze_status(store.frozen.as_ref()))
+}
+
+/// Unfreeze the active pattern, if frozen
+pub(crate) fn release_freeze(state: &State<DawState>) -> DawResult<()> {
+    let mut store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+    let Some(frozen) = store.frozen.take() else {
+        return Ok(());
+    };
+    {
+        let mut tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+        tracks.remove_track(frozen.audio_track_id);
+        send_command_to_engine(Command::SetAudioTracks(tracks.snapshot()), state.clone())?;
+    }
+    lock_core(state)?.forget_sidechain_track(frozen.audio_track_id)?;
+    send_playing_pattern(&store, state)?;
+    frozen.track.discard();
+    Ok(())
+}
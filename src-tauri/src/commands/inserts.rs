@@ -0,0 +1,118 @@
+// Track insert chain commands (filter, delay, reverb, order, bypass)
+//
+// Each track runs its built-in effects in series after the instrument or the
+// clips (see `mymusic_daw::audio::inserts`). The synth chain lives in the core
+// state (replayed after an engine restart), the audio track chains in
+// `DawState::audio_tracks`.
+
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::{lock_core, send_command_to_engine};
+use crate::error::{DawError, DawResult};
+use mymusic_daw::audio::clip_player::{AudioTrack, AudioTrackList};
+use mymusic_daw::audio::inserts::{is_valid_insert_order, InsertChainParams, InsertEffect, InsertEffectOrder};
+use mymusic_daw::audio::routing::MixerTrack;
+use mymusic_daw::messaging::command::Command;
+
+const INVALID_ORDER: &str = "Insert order must list filter, delay and reverb exactly once";
+
+fn parse_insert_order(order: Vec<InsertEffect>) -> DawResult<InsertEffectOrder> {
+    let order: InsertEffectOrder = order
+        .try_into()
+        .map_err(|_| DawError::InvalidArgument(INVALID_ORDER.to_string()))?;
+    if !is_valid_insert_order(&order) {
+        return Err(DawError::InvalidArgument(INVALID_ORDER.to_string()));
+    }
+    Ok(order)
+}
+
+/// Edit the chain of a track and send it to the engine, returning the stored chain
+///
+/// `audio_track_id` selects an audio track, the synth track when omitted.
+fn update_inserts(
+    audio_track_id: Option<u32>,
+    state: &State<DawState>,
+    edit: impl FnOnce(&mut InsertChainParams),
+) -> DawResult<InsertChainParams> {
+    let Some(track_id) = audio_track_id else {
+        let mut core = lock_core(state)?;
+        let mut inserts = core.synth_inserts;
+        edit(&mut inserts);
+        core.set_track_inserts(MixerTrack::Synth, inserts)?;
+        return Ok(core.synth_inserts);
+    };
+    let mut tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    let track = audio_track(&mut tracks, track_id)?;
+    edit(&mut track.inserts);
+    track.inserts.validate();
+    let inserts = track.inserts;
+    send_command_to_engine(Command::SetAudioTracks(tracks.snapshot()), state.clone())?;
+    Ok(inserts)
+}
+
+fn audio_track(
+    tracks: &mut AudioTrackList,
+    track_id: u32,
+) -> DawResult<&mut AudioTrack> {
+    tracks
+        .track_mut(track_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio track not found: {}", track_id)))
+}
+
+/// Get the insert chain of a track (the synth track when `audio_track_id` is omitted)
+#[tauri::command]
+pub fn get_track_inserts(audio_track_id: Option<u32>, state: State<DawState>) -> DawResult<InsertChainParams> {
+    let Some(track_id) = audio_track_id else {
+        return Ok(lock_core(&state)?.synth_inserts);
+    };
+    let mut tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    Ok(audio_track(&mut tracks, track_id)?.inserts)
+}
+
+/// Set the whole insert chain of a track (parameters are clamped to their ranges)
+#[tauri::command]
+pub fn set_track_inserts(
+    audio_track_id: Option<u32>,
+    inserts: InsertChainParams,
+    state: State<DawState>,
+) -> DawResult<InsertChainParams> {
+    update_inserts(audio_track_id, &state, |current| *current = inserts)
+}
+
+/// Set the processing order of a track's inserts
+///
+/// `order` must contain "filter", "delay" and "reverb" exactly once.
+#[tauri::command]
+pub fn set_track_insert_order(
+    audio_track_id: Option<u32>,
+    order: Vec<InsertEffect>,
+    state: State<DawState>,
+) -> DawResult<InsertChainParams> {
+    let order = parse_insert_order(order)?;
+    update_inserts(audio_track_id, &state, |inserts| inserts.order = order)
+}
+
+/// Bypass (or re-enable) an insert effect of a track
+#[tauri::command]
+pub fn set_track_insert_bypass(
+    audio_track_id: Option<u32>,
+    effect: InsertEffect,
+    bypassed: bool,
+    state: State<DawState>,
+) -> DawResult<InsertChainParams> {
+    update_inserts(audio_track_id, &state, |inserts| inserts.set_bypassed(effect, bypassed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_insert_order() {
+        let order = parse_insert_order(vec![InsertEffect::Reverb, InsertEffect::Filter, InsertEffect::Delay]).unwrap();
+        assert_eq!(order, [InsertEffect::Reverb, InsertEffect::Filter, InsertEffect::Delay]);
+
+        assert!(parse_insert_order(vec![InsertEffect::Filter, InsertEffect::Delay]).is_err());
+        assert!(parse_insert_order(vec![InsertEffect::Delay, InsertEffect::Delay, InsertEffect::Reverb]).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+// Master bus effects commands (reverb, delay, EQ, compressor, limiter, order,
+// bypass)
+//
+// The master chain configuration lives in the shared core state, so it can be
+// read back by the frontend and sent again after an engine restart. Saved
+// projects store it too (`Project::master_chain`).
+
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::lock_core;
+use crate::error::{DawError, DawResult};
+use mymusic_daw::synth::delay::DelayParams;
+use mymusic_daw::synth::dynamics::{DynamicsMode, DynamicsParams};
+use mymusic_daw::synth::equalizer::EqParams;
+use mymusic_daw::synth::limiter::LimiterParams;
+use mymusic_daw::synth::master_chain::{is_valid_order, MasterChainParams, MasterEffect, MasterEffectOrder};
+use mymusic_daw::synth::reverb::ReverbParams;
+
+const INVALID_ORDER: &str = "Effect order must list reverb, delay, eq, compressor and limiter exactly once";
+
+fn parse_master_effect(effect: &str) -> DawResult<MasterEffect> {
+    match effect {
+        "reverb" => Ok(MasterEffect::Reverb),
+        "delay" => Ok(MasterEffect::Delay),
+        "eq" => Ok(MasterEffect::Eq),
+        "compressor" => Ok(MasterEffect::Compressor),
+        "limiter" => Ok(MasterEffect::Limiter),
+        _ => Err(DawError::InvalidArgument(format!("Invalid master effect: {}", effect))),
+    }
+}
+
+fn parse_effect_order(order: &[String]) -> DawResult<MasterEffectOrder> {
+    let effects = order
+        .iter()
+        .map(|effect| parse_master_effect(effect))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let order: MasterEffectOrder = effects
+        .try_into()
+        .map_err(|_| DawError::InvalidArgument(INVALID_ORDER.to_string()))?;
+    if !is_valid_order(&order) {
+        return Err(DawError::InvalidArgument(INVALID_ORDER.to_string()));
+    }
+    Ok(order)
+}
+
+/// Get the master chain configuration
+#[tauri::command]
+pub fn get_master_effects(state: State<DawState>) -> DawResult<MasterChainParams> {
+    Ok(lock_core(&state)?.master_chain)
+}
+
+/// Set master reverb parameters (keeps the bypass state)
+#[tauri::command]
+pub fn set_master_reverb(room_size: f32, damping: f32, mix: f32, state: State<DawState>) -> DawResult<()> {
+    Ok(lock_core(&state)?
+        .set_master_reverb(ReverbParams::new(room_size, damping, mix))?)
+}
+
+/// Set master delay parameters (keeps the bypass state)
+#[tauri::command]
+pub fn set_master_delay(time_ms: f32, feedback: f32, mix: f32, state: State<DawState>) -> DawResult<()> {
+    Ok(lock_core(&state)?
+        .set_master_delay(DelayParams::new(time_ms, feedback, mix))?)
+}
+
+/// Set master EQ parameters (keeps the bypass state)
+#[tauri::command]
+pub fn set_master_eq(eq: EqParams, state: State<DawState>) -> DawResult<()> {
+    Ok(lock_core(&state)?.set_master_eq(eq)?)
+}
+
+/// Set master compressor parameters (keeps the bypass state)
+#[tauri::command]
+pub fn set_master_compressor(
+    threshold_db: f32,
+    ratio: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    makeup_db: f32,
+    state: State<DawState>,
+) -> DawResult<()> {
+    Ok(lock_core(&state)?.set_master_compressor(DynamicsParams {
+        threshold_db,
+        ratio,
+        attack_ms,
+        release_ms,
+        makeup_db,
+        ..DynamicsParams::new(DynamicsMode::Compressor)
+    })?)
+}
+
+/// Set master limiter parameters (keeps the bypass state)
+#[tauri::command]
+pub fn set_master_limiter(threshold_db: f32, release_ms: f32, state: State<DawState>) -> DawResult<()> {
+    Ok(lock_core(&state)?
+        .set_master_limiter(LimiterParams::new(threshold_db, release_ms))?)
+}
+
+/// Set the master effects processing order
+///
+/// `order` must contain "reverb", "delay", "eq", "compressor" and "limiter"
+/// exactly once.
+#[tauri::command]
+pub fn set_master_effect_order(order: Vec<String>, state: State<DawState>) -> DawResult<()> {
+    let order = parse_effect_order(&order)?;
+    Ok(lock_core(&state)?.set_master_effect_order(order)?)
+}
+
+/// Bypass (or re-enable) a master effect
+#[tauri::command]
+pub fn set_master_effect_bypass(effect: String, bypassed: bool, state: State<DawState>) -> DawResult<()> {
+    let effect = parse_master_effect(&effect)?;
+    Ok(lock_core(&state)?
+        .set_master_effect_bypass(effect, bypassed)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_effect_order() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        let order = parse_effect_order(&names(&["limiter", "compressor", "eq", "delay", "reverb"])).unwrap();
+        assert_eq!(
+            order,
+            [MasterEffect::Limiter, MasterEffect::Compressor, MasterEffect::Eq, MasterEffect::Delay, MasterEffect::Reverb]
+        );
+
+        assert!(parse_effect_order(&names(&["reverb", "delay"])).is_err());
+        assert!(parse_effect_order(&names(&["reverb", "reverb", "eq", "compressor", "limiter"])).is_err());
+        assert!(parse_effect_order(&names(&["chorus", "delay", "eq", "compressor", "limiter"])).is_err());
+    }
+}
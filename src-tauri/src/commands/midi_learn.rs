@@ -0,0 +1,82 @@
+// MIDI learn commands (controllers mapped to synth parameters)
+//
+// The map lives in the shared core state and is saved with the project. The
+// frontend forwards the control changes of its MIDI inputs through
+// `send_control_change`: mapped controllers set their parameter like a remote
+// controller would, the others only reach the engine.
+
+use serde::Serialize;
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::{lock_core, send_command_to_engine};
+use crate::commands::remote::apply_remote_action;
+use crate::error::{DawError, DawResult};
+use mymusic_daw::messaging::command::Command;
+use mymusic_daw::midi::cc_map::{CcMapping, CcParameter};
+use mymusic_daw::{MidiEvent, MidiEventTimed};
+
+/// MIDI learn state
+#[derive(Debug, Serialize)]
+pub struct CcMapInfo {
+    pub mappings: Vec<CcMapping>,
+    /// Parameter waiting for a controller
+    pub learning: Option<CcParameter>,
+}
+
+/// Get the controller mappings and the parameter armed for MIDI learn
+#[tauri::command]
+pub fn get_cc_mappings(state: State<DawState>) -> DawResult<CcMapInfo> {
+    let core = lock_core(&state)?;
+    Ok(CcMapInfo {
+        mappings: core.cc_map.mappings(),
+        learning: core.cc_map.learning(),
+    })
+}
+
+/// Bind the next received controller to a parameter
+#[tauri::command]
+pub fn arm_cc_learn(parameter: CcParameter, state: State<DawState>) -> DawResult<()> {
+    lock_core(&state)?.cc_map.arm_learn(parameter);
+    Ok(())
+}
+
+/// Stop waiting for a controller
+#[tauri::command]
+pub fn cancel_cc_learn(state: State<DawState>) -> DawResult<()> {
+    lock_core(&state)?.cc_map.cancel_learn();
+    Ok(())
+}
+
+/// Remove the controller mapped to a parameter
+#[tauri::command]
+pub fn remove_cc_mapping(parameter: CcParameter, state: State<DawState>) -> DawResult<()> {
+    lock_core(&state)?.cc_map.unmap(parameter);
+    Ok(())
+}
+
+/// Handle a control change from a MIDI input of the frontend
+///
+/// Returns the parameter it set, if the controller is mapped (or was just
+/// learned).
+#[tauri::command]
+pub fn send_control_change(cc: u8, value: u8, state: State<DawState>) -> DawResult<Option<CcParameter>> {
+    if cc > 127 || value > 127 {
+        return Err(DawError::InvalidArgument(format!("Invalid control change: {} {}", cc, value)));
+    }
+
+    let (action, parameter) = {
+        let mut core = lock_core(&state)?;
+        let action = core.cc_map.handle(cc, value);
+        (action, core.cc_map.parameter_for(cc))
+    };
+    if let Some(action) = action {
+        apply_remote_action(action, &state)?;
+    }
+
+    let command = Command::Midi(MidiEventTimed {
+        event: MidiEvent::ControlChange { controller: cc, value },
+        samples_from_now: 0,
+    });
+    send_command_to_engine(command, state)?;
+    Ok(parameter)
+}
@@ -1,3 +1,26 @@
 // Command modules for MyMusic DAW
+pub mod arrangement;
+pub mod audio_track;
+pub mod automation;
 pub mod basic;
-pub mod plugin;
\ No newline at end of file
+pub mod dynamics;
+pub mod export;
+pub mod freeze;
+pub mod inserts;
+pub mod master;
+pub mod midi_learn;
+pub mod outputs;
+pub mod pattern;
+pub mod plugin;
+pub mod plugin_window;
+pub mod preferences;
+pub mod preset;
+pub mod remote;
+pub mod rescue;
+pub mod returns;
+pub mod sampler;
+pub mod session;
+pub mod surround;
+pub mod transport;
+pub mod tuner;
+pub mod websocket;
\ No newline at end of file
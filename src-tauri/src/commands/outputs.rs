@@ -0,0 +1,93 @@
+// Hardware output routing commands
+//
+// On multi-output interfaces, the synth track, each audio track and each return
+// bus play on the master bus or on their own output pair (see
+// `mymusic_daw::audio::outputs`). The synth output lives in the core state
+// (replayed after an engine restart), the audio track outputs in
+// `DawState::audio_tracks` and the bus outputs in the bus parameters (see
+// `update_return_bus`). The number of channels the device opens with is a
+// preference (`audio.output_channels`), as is the output of the metronome bus
+// (`audio.metronome_output`), which never reaches the mix.
+
+use serde::Serialize;
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::{lock_core, send_command_to_engine};
+use crate::error::{DawError, DawResult};
+use mymusic_daw::audio::outputs::TrackOutput;
+use mymusic_daw::messaging::command::Command;
+
+/// Output a track can play on
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputChoice {
+    pub output: TrackOutput,
+    pub name: String,
+}
+
+/// Output routing sent to the frontend (audio track outputs are in
+/// `AudioTrackInfo`, bus outputs in the bus parameters)
+#[derive(Debug, Clone, Serialize)]
+pub struct OutputRouting {
+    /// Output channels of the running device
+    pub channels: usize,
+    /// Choices of the output pickers
+    pub choices: Vec<OutputChoice>,
+    pub synth_output: TrackOutput,
+    pub metronome_output: TrackOutput,
+}
+
+/// Set the output of a track
+///
+/// `audio_track_id` selects an audio track, the synth track when omitted.
+/// Pairs the running device lacks play on the master bus until a bigger
+/// interface is used.
+#[tauri::command]
+pub fn set_track_output(audio_track_id: Option<u32>, output: TrackOutput, state: State<DawState>) -> DawResult<()> {
+    if !output.is_valid() {
+        return Err(DawError::InvalidArgument(format!("Invalid output: {}", output.name())));
+    }
+    let Some(track_id) = audio_track_id else {
+        return Ok(lock_core(&state)?.set_synth_output(output)?);
+    };
+    let mut tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    let track = tracks
+        .track_mut(track_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio track not found: {}", track_id)))?;
+    track.output = output;
+    send_command_to_engine(Command::SetAudioTracks(tracks.snapshot()), state.clone())
+}
+
+/// Set the output of the metronome bus (saved in the preferences)
+///
+/// `Master` plays the click on channels 1-2 without mixing it into the master
+/// bus: it is not metered, tapped, frozen or exported.
+#[tauri::command]
+pub fn set_metronome_output(output: TrackOutput, state: State<DawState>) -> DawResult<()> {
+    if !output.is_valid() {
+        return Err(DawError::InvalidArgument(format!("Invalid output: {}", output.name())));
+    }
+    lock_core(&state)?.set_metronome_output(output)?;
+    let mut preferences = state.preferences.lock().map_err(|e| DawError::poisoned("preferences", e))?;
+    preferences.audio.metronome_output = output;
+    preferences.save().map_err(DawError::Io)
+}
+
+/// Outputs of the running device, the synth output and the metronome output
+#[tauri::command]
+pub fn get_output_routing(state: State<DawState>) -> DawResult<OutputRouting> {
+    let channels = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?.snapshot().output_channels;
+    let core = lock_core(&state)?;
+    // Pairs taken by a surround layout are not offered
+    let choices = TrackOutput::choices(channels, core.speaker_layout)
+        .map(|output| OutputChoice {
+            output,
+            name: output.name(),
+        })
+        .collect();
+    Ok(OutputRouting {
+        channels,
+        choices,
+        synth_output: core.synth_output,
+        metronome_output: core.metronome_output,
+    })
+}
@@ -0,0 +1,622 @@
+// Pattern editing commands (piano roll for the web frontend)
+//
+// Notes are exchanged in beats (quarter notes) so the React piano roll does not
+// need to know the engine sample rate. Every edit on the active pattern is pushed
+// to the audio thread with `Command::SetPattern` (the store and the audio thread
+// share the pattern, an edit copies it once), and edits reach the clips of the
+// song arrangement playing the pattern.
+
+use tauri::State;
+use crate::DawState;
+use crate::commands::arrangement::refresh_clips;
+use crate::commands::basic::{lock_core, send_command_to_engine};
+use crate::commands::freeze::release_freeze;
+use crate::error::{DawError, DawResult};
+use serde::{Deserialize, Serialize};
+use mymusic_daw::messaging::command::Command;
+use mymusic_daw::sampler::LoopSlices;
+use mymusic_daw::sequencer::groove::{MAX_SWING, MIN_SWING};
+use mymusic_daw::sequencer::note::MAX_OFFSET_TICKS;
+use mymusic_daw::sequencer::{detect_key, generate_note_id, quantize_notes, KeyEstimate, Note, NoteId, Pattern, PatternGroove, PatternId, Position, QuantizeSettings, Tempo, TimeSignature};
+use std::sync::Arc;
+
+/// Pattern summary (for pattern lists)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatternInfo {
+    pub id: PatternId,
+    pub name: String,
+    pub length_bars: u32,
+    pub note_count: usize,
+    pub is_active: bool,
+}
+
+/// A note as seen by the frontend (times in beats)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteInfo {
+    pub id: NoteId,
+    pub pitch: u8,
+    pub start_beats: f64,
+    pub duration_beats: f64,
+    pub velocity: u8,
+    /// Chance of playing on each pass (0.0 - 1.0)
+    pub probability: f32,
+    /// Micro-timing offset in ticks (480 per beat)
+    pub offset_ticks: i32,
+}
+
+/// Full pattern content
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatternData {
+    pub id: PatternId,
+    pub name: String,
+    pub length_bars: u32,
+    pub notes: Vec<NoteInfo>,
+    /// Swing and groove template played with the notes
+    pub groove: PatternGroove,
+}
+
+/// Timing context used for beats <-> samples conversion
+pub(crate) struct TimingContext {
+    sample_rate: f64,
+    tempo: Tempo,
+    time_signature: TimeSignature,
+}
+
+impl TimingContext {
+    pub(crate) fn new(sample_rate: f64, tempo: Tempo, time_signature: TimeSignature) -> Self {
+        Self {
+            sample_rate,
+            tempo,
+            time_signature,
+        }
+    }
+
+    pub(crate) fn from_state(state: &DawState) -> DawResult<Self> {
+        let (tempo, time_signature) = {
+            let core = lock_core(state)?;
+            (core.tempo, core.time_signature)
+        };
+        let sample_rate = state
+            .engine
+            .lock()
+            .map_err(|e| DawError::poisoned("engine", e))?
+            .sample_rate() as f64;
+
+        Ok(Self {
+            sample_rate,
+            tempo,
+            time_signature,
+        })
+    }
+
+    fn beats_to_samples(&self, beats: f64) -> u64 {
+        (beats.max(0.0) * self.tempo.beat_duration_samples(self.sample_rate)) as u64
+    }
+
+    fn samples_to_beats(&self, samples: u64) -> f64 {
+        samples as f64 / self.tempo.beat_duration_samples(self.sample_rate)
+    }
+
+    fn note_to_info(&self, note: &Note) -> NoteInfo {
+        NoteInfo {
+            id: note.id,
+            pitch: note.pitch,
+            start_beats: self.samples_to_beats(note.start.samples),
+            duration_beats: self.samples_to_beats(note.duration_samples),
+            velocity: note.velocity,
+            probability: note.probability,
+            offset_ticks: note.offset_ticks,
+        }
+    }
+
+    /// Build a note, validating the ranges `Note::new` would otherwise assert on
+    fn make_note(
+        &self,
+        id: NoteId,
+        pitch: u8,
+        start_beats: f64,
+        duration_beats: f64,
+        velocity: u8,
+    ) -> DawResult<Note> {
+        if pitch > 127 {
+            return Err(DawError::InvalidArgument(format!("Invalid pitch: {} (must be 0-127)", pitch)));
+        }
+        if velocity == 0 || velocity > 127 {
+            return Err(DawError::InvalidArgument(format!("Invalid velocity: {} (must be 1-127)", velocity)));
+        }
+        if !start_beats.is_finite() || start_beats < 0.0 {
+            return Err(DawError::InvalidArgument(format!("Invalid note start: {}", start_beats)));
+        }
+
+        let duration_samples = self.beats_to_samples(duration_beats);
+        if !duration_beats.is_finite() || duration_samples == 0 {
+            return Err(DawError::InvalidArgument(format!("Invalid note duration: {}", duration_beats)));
+        }
+
+        let start = Position::from_samples(
+            self.beats_to_samples(start_beats),
+            self.sample_rate,
+            &self.tempo,
+            &self.time_signature,
+        );
+
+        Ok(Note::new(id, pitch, start, duration_samples, velocity))
+    }
+}
+
+fn pattern_to_info(pattern: &Pattern, active_pattern_id: Option<PatternId>) -> PatternInfo {
+    PatternInfo {
+        id: pattern.id,
+        name: pattern.name.clone(),
+        length_bars: pattern.length_bars,
+        note_count: pattern.note_count(),
+        is_active: active_pattern_id == Some(pattern.id),
+    }
+}
+
+fn pattern_to_data(pattern: &Pattern, timing: &TimingContext) -> PatternData {
+    PatternData {
+        id: pattern.id,
+        name: pattern.name.clone(),
+        length_bars: pattern.length_bars,
+        notes: pattern.notes().iter().map(|n| timing.note_to_info(n)).collect(),
+        groove: pattern.groove.clone(),
+    }
+}
+
+/// Recompute note sample positions after a tempo or time signature change
+///
+/// Notes keep their position in beats. The active pattern is sent again to the engine.
+pub(crate) fn retime_patterns(old_timing: &TimingContext, state: &State<DawState>) -> DawResult<()> {
+    let new_timing = TimingContext::from_state(state)?;
+
+    let (active, patterns) = {
+        let mut store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+        for pattern in store.patterns.values_mut() {
+            retime_pattern(Arc::make_mut(pattern), old_timing, &new_timing);
+        }
+        (store.playing_pattern(), store.patterns.values().cloned().collect::<Vec<_>>())
+    };
+    refresh_clips(&patterns, state)?;
+
+    match active {
+        Some(pattern) => send_command_to_engine(Command::SetPattern(pattern), state.clone()),
+        None => Ok(()),
+    }
+}
+
+/// Replace every pattern (restored session), retimed from `timing` to the current timing
+///
+/// Notes keep their position in beats. The first pattern becomes the active one
+/// (a frozen pattern is unfrozen first).
+pub(crate) fn replace_patterns(patterns: Vec<Pattern>, timing: &TimingContext, state: &State<DawState>) -> DawResult<()> {
+    release_freeze(state)?;
+    let new_timing = TimingContext::from_state(state)?;
+
+    let (active, patterns) = {
+        let mut store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+        store.replace(patterns.into_iter().map(|mut pattern| {
+            retime_pattern(&mut pattern, timing, &new_timing);
+            pattern
+        }));
+        let active = store.active_pattern_id.and_then(|id| store.patterns.get(&id).cloned());
+        (active, store.patterns.values().cloned().collect::<Vec<_>>())
+    };
+    refresh_clips(&patterns, state)?;
+
+    match active {
+        Some(pattern) => send_command_to_engine(Command::SetPattern(pattern), state.clone()),
+        None => Ok(()),
+    }
+}
+
+fn retime_pattern(pattern: &mut Pattern, old_timing: &TimingContext, new_timing: &TimingContext) {
+    let notes: Vec<NoteInfo> = pattern.notes().iter().map(|n| old_timing.note_to_info(n)).collect();
+    pattern.clear();
+    for info in notes {
+        // make_note only fails for notes shorter than one sample, which are dropped
+        if let Ok(note) = new_timing.make_note(info.id, info.pitch, info.start_beats, info.duration_beats, info.velocity) {
+            pattern.add_note(Note {
+                probability: info.probability,
+                offset_ticks: info.offset_ticks,
+                ..note
+            });
+        }
+    }
+}
+
+/// Apply an edit to a pattern and push it to the audio thread if it is the
+/// active one or clips play it
+fn edit_pattern<T>(
+    pattern_id: PatternId,
+    state: &State<DawState>,
+    edit: impl FnOnce(&mut Pattern) -> DawResult<T>,
+) -> DawResult<T> {
+    let (result, updated) = {
+        let mut store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+        if store.frozen.as_ref().is_some_and(|frozen| frozen.pattern_id == pattern_id) {
+            return Err(DawError::InvalidArgument(format!("Pattern {} is frozen: unfreeze it to edit", pattern_id)));
+        }
+        let is_active = store.active_pattern_id == Some(pattern_id);
+        let pattern = store
+            .patterns
+            .get_mut(&pattern_id)
+            .ok_or_else(|| DawError::NotFound(format!("Pattern not found: {}", pattern_id)))?;
+
+        let result = edit(Arc::make_mut(pattern))?;
+        (result, (pattern.clone(), is_active))
+    };
+
+    let (pattern, is_active) = updated;
+    refresh_clips(std::slice::from_ref(&pattern), state)?;
+    if is_active {
+        send_command_to_engine(Command::SetPattern(pattern), state.clone())?;
+    }
+
+    Ok(result)
+}
+
+/// Create a new empty pattern
+///
+/// The first pattern created becomes the active (playing) pattern.
+#[tauri::command]
+pub fn create_pattern(name: String, length_bars: Option<u32>, state: State<DawState>) -> DawResult<PatternInfo> {
+    let length_bars = length_bars.unwrap_or(4);
+    if length_bars == 0 {
+        return Err(DawError::InvalidArgument("Pattern length must be at least 1 bar".to_string()));
+    }
+
+    add_pattern(|id| Pattern::new(id, name, length_bars), &state)
+}
+
+/// Add a pattern built with a new ID; the first pattern becomes the active one
+fn add_pattern(build: impl FnOnce(PatternId) -> Pattern, state: &State<DawState>) -> DawResult<PatternInfo> {
+    let (info, activated) = {
+        let mut store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+        let id = store.generate_pattern_id();
+        let pattern = Arc::new(build(id));
+
+        let activated = store.active_pattern_id.is_none();
+        if activated {
+            store.active_pattern_id = Some(id);
+        }
+
+        let info = pattern_to_info(&pattern, store.active_pattern_id);
+        let activated = activated.then(|| pattern.clone());
+        store.patterns.insert(id, pattern);
+        (info, activated)
+    };
+
+    if let Some(pattern) = activated {
+        send_command_to_engine(Command::SetPattern(pattern), state.clone())?;
+    }
+
+    Ok(info)
+}
+
+/// Add a pattern replaying a sliced loop, recorded at `source_bpm`, at the project tempo
+pub(crate) fn add_slice_pattern(
+    name: String,
+    slices: &LoopSlices,
+    first_note: u8,
+    source_bpm: f64,
+    state: &State<DawState>,
+) -> DawResult<PatternInfo> {
+    let timing = TimingContext::from_state(state)?;
+    let notes = slices
+        .notes(first_note, source_bpm, timing.sample_rate, &timing.tempo, &timing.time_signature)
+        .map_err(DawError::InvalidArgument)?;
+    let length_bars = slices.bars(source_bpm, &timing.time_signature);
+    add_pattern(
+        |id| {
+            let mut pattern = Pattern::new(id, name, length_bars);
+            pattern.set_notes(notes);
+            pattern
+        },
+        state,
+    )
+}
+
+/// List all patterns
+#[tauri::command]
+pub fn list_patterns(state: State<DawState>) -> DawResult<Vec<PatternInfo>> {
+    let store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+    let mut patterns: Vec<PatternInfo> = store
+        .patterns
+        .values()
+        .map(|p| pattern_to_info(p, store.active_pattern_id))
+        .collect();
+    patterns.sort_by_key(|p| p.id);
+    Ok(patterns)
+}
+
+/// Get the full content of a pattern
+#[tauri::command]
+pub fn get_pattern(pattern_id: PatternId, state: State<DawState>) -> DawResult<PatternData> {
+    let timing = TimingContext::from_state(&state)?;
+    let store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+    let pattern = store
+        .patterns
+        .get(&pattern_id)
+        .ok_or_else(|| DawError::NotFound(format!("Pattern not found: {}", pattern_id)))?;
+    Ok(pattern_to_data(pattern, &timing))
+}
+
+/// Make a pattern the one played by the sequencer
+#[tauri::command]
+pub fn set_active_pattern(pattern_id: PatternId, state: State<DawState>) -> DawResult<()> {
+    let pattern = {
+        let mut store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+        if store.frozen.is_some() {
+            return Err(DawError::InvalidArgument("The active pattern is frozen: unfreeze it first".to_string()));
+        }
+        let pattern = store
+            .patterns
+            .get(&pattern_id)
+            .cloned()
+            .ok_or_else(|| DawError::NotFound(format!("Pattern not found: {}", pattern_id)))?;
+        store.active_pattern_id = Some(pattern_id);
+        pattern
+    };
+
+    send_command_to_engine(Command::SetPattern(pattern), state)
+}
+
+/// Add a note to a pattern
+#[tauri::command]
+pub fn add_note(
+    pattern_id: PatternId,
+    pitch: u8,
+    start_beats: f64,
+    duration_beats: f64,
+    velocity: Option<u8>,
+    state: State<DawState>,
+) -> DawResult<NoteInfo> {
+    let timing = TimingContext::from_state(&state)?;
+    let note = timing.make_note(
+        generate_note_id(),
+        pitch,
+        start_beats,
+        duration_beats,
+        velocity.unwrap_or(100),
+    )?;
+
+    edit_pattern(pattern_id, &state, |pattern| {
+        pattern.add_note(note);
+        Ok(timing.note_to_info(&note))
+    })
+}
+
+/// Update a note (move, resize, change pitch, velocity, probability or
+/// micro-timing offset)
+///
+/// Omitted fields keep their current value.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_note(
+    pattern_id: PatternId,
+    note_id: NoteId,
+    pitch: Option<u8>,
+    start_beats: Option<f64>,
+    duration_beats: Option<f64>,
+    velocity: Option<u8>,
+    probability: Option<f32>,
+    offset_ticks: Option<i32>,
+    state: State<DawState>,
+) -> DawResult<NoteInfo> {
+    if let Some(probability) = probability.filter(|p| !(0.0..=1.0).contains(p)) {
+        return Err(DawError::InvalidArgument(format!("Invalid probability: {} (must be 0.0-1.0)", probability)));
+    }
+    if let Some(offset) = offset_ticks.filter(|ticks| ticks.abs() > MAX_OFFSET_TICKS) {
+        return Err(DawError::InvalidArgument(format!(
+            "Invalid micro-timing offset: {} ticks (must be within ±{})",
+            offset, MAX_OFFSET_TICKS
+        )));
+    }
+    let timing = TimingContext::from_state(&state)?;
+
+    edit_pattern(pattern_id, &state, |pattern| {
+        let current = pattern
+            .get_note(note_id)
+            .map(|n| timing.note_to_info(n))
+            .ok_or_else(|| DawError::NotFound(format!("Note not found: {}", note_id)))?;
+
+        let note = timing.make_note(
+            note_id,
+            pitch.unwrap_or(current.pitch),
+            start_beats.unwrap_or(current.start_beats),
+            duration_beats.unwrap_or(current.duration_beats),
+            velocity.unwrap_or(current.velocity),
+        )?;
+        let note = Note {
+            probability: probability.unwrap_or(current.probability),
+            offset_ticks: offset_ticks.unwrap_or(current.offset_ticks),
+            ..note
+        };
+
+        // Remove + add keeps the pattern sorted by start position
+        pattern.remove_note(note_id);
+        pattern.add_note(note);
+        Ok(timing.note_to_info(&note))
+    })
+}
+
+/// Delete notes from a pattern
+///
+/// Returns the number of notes actually removed.
+#[tauri::command]
+pub fn delete_notes(pattern_id: PatternId, note_ids: Vec<NoteId>, state: State<DawState>) -> DawResult<usize> {
+    edit_pattern(pattern_id, &state, |pattern| {
+        Ok(note_ids
+            .iter()
+            .filter(|id| pattern.remove_note(**id).is_some())
+            .count())
+    })
+}
+
+/// Set the swing and groove template a pattern plays with
+///
+/// The notes are not moved: the sequencer applies the groove at playback.
+#[tauri::command]
+pub fn set_pattern_groove(pattern_id: PatternId, groove: PatternGroove, state: State<DawState>) -> DawResult<()> {
+    if !(MIN_SWING..=MAX_SWING).contains(&groove.swing) {
+        return Err(DawError::InvalidArgument(format!(
+            "Invalid swing: {} (must be {}-{})",
+            groove.swing, MIN_SWING, MAX_SWING
+        )));
+    }
+    let invalid_template = groove.template.as_ref().is_some_and(|template| {
+        template.steps_per_beat == 0 || template.offsets.is_empty() || template.offsets.len() != template.velocities.len()
+    });
+    if invalid_template {
+        return Err(DawError::InvalidArgument("Invalid groove template: steps do not match".to_string()));
+    }
+
+    edit_pattern(pattern_id, &state, |pattern| {
+        pattern.groove = groove;
+        Ok(())
+    })
+}
+
+/// Snap notes of a pattern to the grid (`note_ids` empty: every note)
+///
+/// Each note moves toward its nearest grid step by `settings.strength`;
+/// every second step is delayed by the swing. Returns the number of notes moved.
+#[tauri::command]
+pub fn quantize_pattern_notes(
+    pattern_id: PatternId,
+    note_ids: Vec<NoteId>,
+    settings: QuantizeSettings,
+    state: State<DawState>,
+) -> DawResult<usize> {
+    if !(1..=32).contains(&settings.steps_per_beat) {
+        return Err(DawError::InvalidArgument(format!(
+            "Invalid grid: {} steps per beat (must be 1-32)",
+            settings.steps_per_beat
+        )));
+    }
+    if !(0.0..=1.0).contains(&settings.strength) {
+        return Err(DawError::InvalidArgument(format!(
+            "Invalid strength: {} (must be 0.0-1.0)",
+            settings.strength
+        )));
+    }
+    if !(MIN_SWING..=MAX_SWING).contains(&settings.swing) {
+        return Err(DawError::InvalidArgument(format!(
+            "Invalid swing: {} (must be {}-{})",
+            settings.swing, MIN_SWING, MAX_SWING
+        )));
+    }
+    let timing = TimingContext::from_state(&state)?;
+
+    edit_pattern(pattern_id, &state, |pattern| {
+        Ok(quantize_notes(
+            pattern,
+            &note_ids,
+            &settings,
+            timing.sample_rate,
+            &timing.tempo,
+            &timing.time_signature,
+        ))
+    })
+}
+
+/// Estimate the key of a pattern from its notes (None for an empty pattern)
+///
+/// The frontend highlights the scale of the key in the piano roll.
+#[tauri::command]
+pub fn detect_pattern_key(pattern_id: PatternId, state: State<DawState>) -> DawResult<Option<KeyEstimate>> {
+    let store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+    let pattern = store
+        .patterns
+        .get(&pattern_id)
+        .ok_or_else(|| DawError::NotFound(format!("Pattern not found: {}", pattern_id)))?;
+    Ok(detect_key(pattern.notes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing() -> TimingContext {
+        TimingContext {
+            sample_rate: 48000.0,
+            tempo: Tempo::new(120.0),
+            time_signature: TimeSignature::four_four(),
+        }
+    }
+
+    #[test]
+    fn test_beats_samples_round_trip() {
+        let timing = timing();
+
+        // At 120 BPM / 48kHz, one beat = 24000 samples
+        assert_eq!(timing.beats_to_samples(1.0), 24000);
+        assert_eq!(timing.samples_to_beats(48000), 2.0);
+    }
+
+    #[test]
+    fn test_make_note_validation() {
+        let timing = timing();
+
+        let note = timing.make_note(1, 60, 1.0, 0.5, 100).unwrap();
+        assert_eq!(note.start.samples, 24000);
+        assert_eq!(note.duration_samples, 12000);
+
+        assert!(timing.make_note(1, 128, 0.0, 1.0, 100).is_err());
+        assert!(timing.make_note(1, 60, 0.0, 1.0, 0).is_err());
+        assert!(timing.make_note(1, 60, -1.0, 1.0, 100).is_err());
+        assert!(timing.make_note(1, 60, 0.0, 0.0, 100).is_err());
+    }
+
+    #[test]
+    fn test_retime_pattern_keeps_beats() {
+        let old_timing = timing();
+        let mut pattern = Pattern::new(1, "Test".to_string(), 1);
+        let note = old_timing.make_note(1, 60, 2.0, 1.0, 100).unwrap();
+        pattern.add_note(Note {
+            probability: 0.5,
+            offset_ticks: -10,
+            ..note
+        });
+
+        let new_timing = TimingContext {
+            tempo: Tempo::new(60.0),
+            ..timing()
+        };
+        retime_pattern(&mut pattern, &old_timing, &new_timing);
+
+        // At 60 BPM / 48kHz, one beat = 48000 samples
+        let note = pattern.get_note(1).unwrap();
+        assert_eq!(note.start.samples, 96000);
+        assert_eq!(note.duration_samples, 48000);
+        assert_eq!((note.probability, note.offset_ticks), (0.5, -10));
+    }
+
+    #[test]
+    fn test_pattern_to_data() {
+        let timing = timing();
+        let mut pattern = Pattern::new(7, "Test".to_string(), 2);
+        pattern.add_note(timing.make_note(1, 64, 2.0, 1.0, 90).unwrap());
+        pattern.add_note(timing.make_note(2, 60, 0.0, 1.0, 100).unwrap());
+
+        let data = pattern_to_data(&pattern, &timing);
+        assert_eq!(data.id, 7);
+        assert_eq!(data.notes.len(), 2);
+        // Notes are sorted by start position
+        assert_eq!(data.notes[0].pitch, 60);
+        assert_eq!(data.notes[1].start_beats, 2.0);
+    }
+
+    #[test]
+    fn test_replace_patterns_in_store() {
+        let mut store = crate::PatternStore::default();
+        store.generate_pattern_id();
+        store.replace([Pattern::new(9, "B".to_string(), 1), Pattern::new(4, "A".to_string(), 1)]);
+
+        assert_eq!(store.patterns.len(), 2);
+        assert_eq!(store.active_pattern_id, Some(4));
+        // New IDs never collide with the restored ones
+        assert_eq!(store.generate_pattern_id(), 10);
+    }
+}
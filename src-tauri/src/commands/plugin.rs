@@ -1,12 +1,15 @@
 // Plugin management commands for CLAP plugins
 
-use tauri::State;
+use tauri::{AppHandle, Manager, Runtime, State};
 use crate::DawState;
 use crate::{ManagedPlugin, PluginGuiInfo};
+use crate::window_utils::{encode_window_handle, get_window_handle};
+use crate::error::{DawError, DawResult};
 use serde::{Deserialize, Serialize};
-use mymusic_daw::plugin::{Plugin, PluginHost, PluginInstanceId};
+use mymusic_daw::plugin::{ParameterType, Plugin, PluginHost, PluginInstanceId};
 use mymusic_daw::plugin::scanner::{PluginScanner, get_default_search_paths};
 use base64::Engine;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::env;
 use std::sync::mpsc;
@@ -28,6 +31,22 @@ pub struct ParameterInfo {
     pub value: f64,
     pub min: f64,
     pub max: f64,
+    pub default_value: f64,
+    /// Value as the plugin displays it (e.g. "440 Hz"), when it provides one
+    pub display: Option<String>,
+    /// Stepped or enum parameter (only whole values between min and max)
+    pub is_stepped: bool,
+    pub is_automatable: bool,
+}
+
+/// Managed plugin instance by state ID
+fn managed_plugin<'a>(
+    plugins: &'a HashMap<String, ManagedPlugin>,
+    plugin_id: &str,
+) -> DawResult<&'a ManagedPlugin> {
+    plugins
+        .get(plugin_id)
+        .ok_or_else(|| DawError::NotFound(format!("Plugin not found: {}", plugin_id)))
 }
 
 /// Load a plugin instance
@@ -36,7 +55,7 @@ pub fn load_plugin_instance(
     plugin_path: String,
     plugin_id: Option<String>,
     state: State<DawState>,
-) -> Result<String, String> {
+) -> DawResult<String> {
     println!("🔌 Loading plugin from: {}", plugin_path);
     println!("🏷️ Provided plugin_id: {:?}", plugin_id);
     
@@ -59,7 +78,7 @@ pub fn load_plugin_instance(
                     e
                 );
                 println!("❌ {}", error_msg);
-                return Err(error_msg);
+                return Err(DawError::Unavailable(error_msg));
             }
         }
     }
@@ -67,7 +86,7 @@ pub fn load_plugin_instance(
     // Check if file exists
     if !std::path::Path::new(&plugin_path).exists() {
         println!("❌ Plugin file does not exist: {}", plugin_path);
-        return Err(format!("Plugin file not found: {}", plugin_path));
+        return Err(DawError::NotFound(format!("Plugin file not found: {}", plugin_path)));
     }
     println!("✅ Plugin file exists");
     
@@ -81,7 +100,7 @@ pub fn load_plugin_instance(
     let plugin_key = host.load_plugin(&plugin_path_buf)
         .map_err(|e| {
             println!("❌ Failed to load plugin: {}", e);
-            format!("Failed to load plugin: {}", e)
+            DawError::Plugin(format!("Failed to load plugin: {}", e))
         })?;
     
     println!("✅ Plugin loaded successfully with key: {:?}", plugin_key);
@@ -91,10 +110,18 @@ pub fn load_plugin_instance(
     let instance_id = host.create_instance(&plugin_key, None)
         .map_err(|e| {
             println!("❌ Failed to create instance: {}", e);
-            format!("Failed to create instance: {}", e)
+            DawError::Plugin(format!("Failed to create instance: {}", e))
         })?;
     
     println!("✅ Plugin instance created with ID: {:?}", instance_id);
+
+    // Initialize it so the parameters (clap.params) and the GUI are available
+    let sample_rate = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?.sample_rate();
+    host.initialize_instance(instance_id, sample_rate as f64, 512)
+        .map_err(|e| {
+            println!("❌ Failed to initialize instance: {}", e);
+            DawError::Plugin(format!("Failed to initialize instance: {}", e))
+        })?;
     
     // Use provided plugin_id or generate a unique one
     let state_id = plugin_id.unwrap_or_else(|| {
@@ -106,7 +133,7 @@ pub fn load_plugin_instance(
     println!("✅ Plugin loaded with instance ID: {:?}, state ID: '{}'", instance_id, state_id);
     
     // Store the plugin in the state
-    let mut plugins = state.plugins.lock().map_err(|e| format!("Failed to lock plugins: {}", e))?;
+    let mut plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
     
     let managed_plugin = ManagedPlugin {
         host,
@@ -121,14 +148,36 @@ pub fn load_plugin_instance(
     Ok(state_id)
 }
 
-/// Get plugin parameters
+/// Get plugin parameters (from the CLAP params extension, with current values)
+///
+/// Parameter IDs are the CLAP parameter ids as decimal strings.
 #[tauri::command]
 pub fn get_plugin_parameters(
     plugin_id: String,
-    _state: State<DawState>,
-) -> Result<Vec<ParameterInfo>, String> {
+    state: State<DawState>,
+) -> DawResult<Vec<ParameterInfo>> {
     println!("🎛️ Getting parameters for plugin: {}", plugin_id);
-    Err("Plugin parameters not yet implemented".to_string())
+    let plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
+    let managed_plugin = managed_plugin(&plugins, &plugin_id)?;
+    let host = &managed_plugin.host;
+
+    let parameters = host
+        .get_parameters(managed_plugin.instance_id)
+        .map_err(|e| DawError::Plugin(e.to_string()))?;
+    Ok(parameters
+        .into_iter()
+        .map(|parameter| ParameterInfo {
+            display: host.parameter_value_text(managed_plugin.instance_id, &parameter.id, parameter.value),
+            is_stepped: matches!(parameter.parameter_type, ParameterType::Enum),
+            is_automatable: parameter.is_automatable,
+            default_value: parameter.default_value,
+            min: parameter.min_value,
+            max: parameter.max_value,
+            value: parameter.value,
+            name: parameter.name,
+            id: parameter.id,
+        })
+        .collect())
 }
 
 /// Get a specific plugin parameter value
@@ -136,43 +185,53 @@ pub fn get_plugin_parameters(
 pub fn get_plugin_parameter_value(
     plugin_id: String,
     parameter_id: String,
-    _state: State<DawState>,
-) -> Result<f64, String> {
+    state: State<DawState>,
+) -> DawResult<f64> {
     println!(
         "📊 Getting parameter {} for plugin: {}",
         parameter_id, plugin_id
     );
-    Err("Plugin parameter values not yet implemented".to_string())
+    let plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
+    let managed_plugin = managed_plugin(&plugins, &plugin_id)?;
+    managed_plugin
+        .host
+        .get_parameter(managed_plugin.instance_id, &parameter_id)
+        .map_err(|e| DawError::InvalidArgument(e.to_string()))
 }
 
-/// Set a plugin parameter value
+/// Set a plugin parameter value (clamped to its range, sent to the plugin right away)
 #[tauri::command]
 pub fn set_plugin_parameter_value(
     plugin_id: String,
     parameter_id: String,
     value: f64,
-    _state: State<DawState>,
-) -> Result<(), String> {
+    state: State<DawState>,
+) -> DawResult<()> {
     println!(
         "🎚️ Setting parameter {} = {} for plugin: {}",
         parameter_id, value, plugin_id
     );
-    Err("Plugin parameter setting not yet implemented".to_string())
+    let plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
+    let managed_plugin = managed_plugin(&plugins, &plugin_id)?;
+    managed_plugin
+        .host
+        .set_parameter_immediate(managed_plugin.instance_id, &parameter_id, value)
+        .map_err(|e| DawError::InvalidArgument(e.to_string()))
 }
 
 /// Unload a plugin instance
 #[tauri::command]
-pub fn unload_plugin_instance(plugin_id: String, _state: State<DawState>) -> Result<(), String> {
+pub fn unload_plugin_instance(plugin_id: String, _state: State<DawState>) -> DawResult<()> {
     println!("🗑️ Unloading plugin: {}", plugin_id);
-    Err("Plugin unloading not yet implemented".to_string())
+    Err(DawError::NotImplemented("Plugin unloading not yet implemented".to_string()))
 }
 
 /// Get list of loaded plugins
 #[tauri::command]
-pub fn get_loaded_plugins(state: State<DawState>) -> Result<Vec<PluginInfo>, String> {
+pub fn get_loaded_plugins(state: State<DawState>) -> DawResult<Vec<PluginInfo>> {
     println!("📋 Getting loaded plugins");
     
-    let plugins = state.plugins.lock().map_err(|e| format!("Failed to lock plugins: {}", e))?;
+    let plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
     let plugin_list: Vec<PluginInfo> = plugins
         .iter()
         .map(|(id, managed_plugin)| {
@@ -203,7 +262,7 @@ pub fn get_loaded_plugins(state: State<DawState>) -> Result<Vec<PluginInfo>, Str
 
 /// Scan for available plugins
 #[tauri::command]
-pub fn scan_for_plugins() -> Result<Vec<PluginInfo>, String> {
+pub fn scan_for_plugins() -> DawResult<Vec<PluginInfo>> {
     println!("🔍 [FRONTEND CALL] Scanning for plugins...");
     
     // Get default search paths
@@ -212,7 +271,7 @@ pub fn scan_for_plugins() -> Result<Vec<PluginInfo>, String> {
     
     // Create scanner with temporary cache
     let cache_dir = std::env::temp_dir().join("mymusic_daw_plugin_cache");
-    std::fs::create_dir_all(&cache_dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    std::fs::create_dir_all(&cache_dir).map_err(|e| DawError::Io(format!("Failed to create cache dir: {}", e)))?;
     let cache_path = cache_dir.join("plugin_cache.json");
     
     let mut scanner = PluginScanner::new(cache_path);
@@ -248,7 +307,7 @@ pub fn scan_for_plugins() -> Result<Vec<PluginInfo>, String> {
 
 /// Get plugin search paths
 #[tauri::command]
-pub fn get_plugin_search_paths() -> Result<Vec<String>, String> {
+pub fn get_plugin_search_paths() -> DawResult<Vec<String>> {
     let paths = get_default_search_paths();
     let path_strings: Vec<String> = paths
         .into_iter()
@@ -259,17 +318,17 @@ pub fn get_plugin_search_paths() -> Result<Vec<String>, String> {
 
 /// Scan a specific directory for plugins
 #[tauri::command]
-pub fn scan_plugin_directory(directory_path: String) -> Result<Vec<PluginInfo>, String> {
+pub fn scan_plugin_directory(directory_path: String) -> DawResult<Vec<PluginInfo>> {
     println!("🔍 Scanning directory: {}", directory_path);
     
     let path = PathBuf::from(directory_path);
     let cache_dir = std::env::temp_dir().join("mymusic_daw_plugin_cache");
-    std::fs::create_dir_all(&cache_dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+    std::fs::create_dir_all(&cache_dir).map_err(|e| DawError::Io(format!("Failed to create cache dir: {}", e)))?;
     let cache_path = cache_dir.join("plugin_cache.json");
     
     let mut scanner = PluginScanner::new(cache_path);
     let descriptors = scanner.scan_directory(&path)
-        .map_err(|e| format!("Failed to scan directory: {}", e))?;
+        .map_err(|e| DawError::Io(format!("Failed to scan directory: {}", e)))?;
     
     let plugin_infos: Vec<PluginInfo> = descriptors
         .into_iter()
@@ -459,14 +518,14 @@ fn is_headless_environment() -> bool {
 pub fn show_plugin_gui(
     plugin_id: String,
     state: State<DawState>,
-) -> Result<(), String> {
+) -> DawResult<()> {
     println!("🖥️ Showing GUI for plugin: '{}'", plugin_id);
     
     // Check if we're in a headless environment first
     if is_headless_environment() {
         let error_msg = "Cannot show GUI in headless environment (no display server available)";
         println!("⚠️ {}", error_msg);
-        return Err(error_msg.to_string());
+        return Err(DawError::Unavailable(error_msg.to_string()));
     }
     
     // Simple timeout approach to prevent hanging
@@ -478,8 +537,8 @@ pub fn show_plugin_gui(
     
     // Spawn GUI operation in separate thread
     std::thread::spawn(move || {
-        let result = (|| -> Result<(), String> {
-            let plugins = state_clone.plugins.lock().map_err(|e| format!("Failed to lock plugins: {}", e))?;
+        let result = (|| -> DawResult<()> {
+            let plugins = state_clone.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
             
             if let Some(managed_plugin) = plugins.get(&plugin_id_clone) {
                 println!("✅ Found plugin: '{}'", plugin_id_clone);
@@ -506,15 +565,15 @@ pub fn show_plugin_gui(
                 }));
                 
                 match gui_result {
-                    Ok(Some(result)) => result,
-                    Ok(None) => Err("Failed to get instance wrapper".to_string()),
+                    Ok(Some(result)) => result.map_err(DawError::Plugin),
+                    Ok(None) => Err(DawError::Plugin("Failed to get instance wrapper".to_string())),
                     Err(_) => {
                         println!("❌ GUI operation panicked - application is still stable");
                         Ok(()) // Don't fail the whole operation for GUI panic
                     }
                 }
             } else {
-                Err(format!("Plugin not found: {}", plugin_id_clone))
+                Err(DawError::NotFound(format!("Plugin not found: {}", plugin_id_clone)))
             }
         })();
         
@@ -527,7 +586,7 @@ pub fn show_plugin_gui(
         Err(_) => {
             let error_msg = "GUI operation timed out (plugin may be hanging). Application is still stable.";
             println!("⏰ {}", error_msg);
-            Err(error_msg.to_string())
+            Err(DawError::Window(error_msg.to_string()))
         }
     }
 }
@@ -539,10 +598,10 @@ pub fn show_plugin_gui(
 pub fn hide_plugin_gui(
     plugin_id: String,
     state: State<DawState>,
-) -> Result<(), String> {
+) -> DawResult<()> {
     println!("🙈 Hiding GUI for plugin: {}", plugin_id);
     
-    let mut plugins = state.plugins.lock().map_err(|e| format!("Failed to lock plugins: {}", e))?;
+    let mut plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
     
     if let Some(managed_plugin) = plugins.get_mut(&plugin_id) {
         // Get instance from host and try to hide GUI
@@ -569,11 +628,11 @@ pub fn hide_plugin_gui(
                 println!("✅ Plugin GUI hidden: {}", plugin_id);
                 Ok(())
             }
-            Some(Err(e)) => Err(e),
-            None => Err(format!("Plugin {} does not support GUI or instance not found", plugin_id))
+            Some(Err(e)) => Err(DawError::Plugin(e)),
+            None => Err(DawError::Plugin(format!("Plugin {} does not support GUI or instance not found", plugin_id)))
         }
     } else {
-        Err(format!("Plugin not found: {}", plugin_id))
+        Err(DawError::NotFound(format!("Plugin not found: {}", plugin_id)))
     }
 }
 
@@ -583,10 +642,10 @@ pub fn attach_plugin_gui(
     plugin_id: String,
     window_handle: String, // Base64 encoded window handle
     state: State<DawState>,
-) -> Result<(), String> {
+) -> DawResult<()> {
     println!("🔗 Attaching GUI for plugin: {} to window: {}", plugin_id, window_handle);
     
-    let mut plugins = state.plugins.lock().map_err(|e| format!("Failed to lock plugins: {}", e))?;
+    let mut plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
     
     if let Some(managed_plugin) = plugins.get_mut(&plugin_id) {
         // Get instance from host and try to attach GUI
@@ -641,17 +700,18 @@ pub fn attach_plugin_gui(
                         height,
                         can_resize,
                         api: "clap".to_string(),
+                        window_label: None,
                     });
                 }
                 
                 println!("✅ Plugin GUI attached: {} ({}x{})", plugin_id, width, height);
                 Ok(())
             }
-            Some(Err(e)) => Err(e),
-            None => Err(format!("Plugin {} does not support GUI or instance not found", plugin_id))
+            Some(Err(e)) => Err(DawError::Plugin(e)),
+            None => Err(DawError::Plugin(format!("Plugin {} does not support GUI or instance not found", plugin_id)))
         }
     } else {
-        Err(format!("Plugin not found: {}", plugin_id))
+        Err(DawError::NotFound(format!("Plugin not found: {}", plugin_id)))
     }
 }
 
@@ -660,10 +720,10 @@ pub fn attach_plugin_gui(
 pub fn get_plugin_gui_size(
     plugin_id: String,
     state: State<DawState>,
-) -> Result<(u32, u32), String> {
+) -> DawResult<(u32, u32)> {
     println!("📏 Getting GUI size for plugin: {}", plugin_id);
     
-    let plugins = state.plugins.lock().map_err(|e| format!("Failed to lock plugins: {}", e))?;
+    let plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
     
     if let Some(managed_plugin) = plugins.get(&plugin_id) {
         if let Some(ref gui_info) = managed_plugin.gui_info {
@@ -684,12 +744,12 @@ pub fn get_plugin_gui_size(
             
             match size_result {
                 Some(Ok(size)) => Ok(size),
-                Some(Err(e)) => Err(e),
-                None => Err("Failed to get instance wrapper".to_string())
+                Some(Err(e)) => Err(DawError::Plugin(e)),
+                None => Err(DawError::Plugin("Failed to get instance wrapper".to_string()))
             }
         }
     } else {
-        Err("Plugin not found".to_string())
+        Err(DawError::NotFound("Plugin not found".to_string()))
     }
 }
 
@@ -700,10 +760,10 @@ pub fn set_plugin_gui_size(
     width: u32,
     height: u32,
     state: State<DawState>,
-) -> Result<(), String> {
+) -> DawResult<()> {
     println!("📏 Setting GUI size for plugin: {} to {}x{}", plugin_id, width, height);
     
-    let mut plugins = state.plugins.lock().map_err(|e| format!("Failed to lock plugins: {}", e))?;
+    let mut plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
     
     if let Some(managed_plugin) = plugins.get_mut(&plugin_id) {
         // Try to set size on plugin instance
@@ -735,17 +795,18 @@ pub fn set_plugin_gui_size(
                         height,
                         can_resize,
                         api: "clap".to_string(),
+                        window_label: None,
                     });
                 }
                 
                 println!("✅ GUI size set: {} -> {}x{}", plugin_id, width, height);
                 Ok(())
             }
-            Some(Err(e)) => Err(e),
-            None => Err(format!("Failed to set GUI size for plugin: {}", plugin_id))
+            Some(Err(e)) => Err(DawError::Plugin(e)),
+            None => Err(DawError::Plugin(format!("Failed to set GUI size for plugin: {}", plugin_id)))
         }
     } else {
-        Err(format!("Plugin not found: {}", plugin_id))
+        Err(DawError::NotFound(format!("Plugin not found: {}", plugin_id)))
     }
 }
 
@@ -754,10 +815,10 @@ pub fn set_plugin_gui_size(
 pub fn is_plugin_gui_visible(
     plugin_id: String,
     state: State<DawState>,
-) -> Result<bool, String> {
+) -> DawResult<bool> {
     println!("👁️ Checking GUI visibility for plugin: {}", plugin_id);
     
-    let plugins = state.plugins.lock().map_err(|e| format!("Failed to lock plugins: {}", e))?;
+    let plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
     
     if let Some(managed_plugin) = plugins.get(&plugin_id) {
         if let Some(ref gui_info) = managed_plugin.gui_info {
@@ -778,57 +839,31 @@ pub fn is_plugin_gui_visible(
             
             match visibility_result {
                 Some(Ok(visible)) => Ok(visible),
-                Some(Err(e)) => Err(e),
-                None => Err("Failed to get instance wrapper".to_string())
+                Some(Err(e)) => Err(DawError::Plugin(e)),
+                None => Err(DawError::Plugin("Failed to get instance wrapper".to_string()))
             }
         }
     } else {
-        Err("Plugin not found".to_string())
+        Err(DawError::NotFound("Plugin not found".to_string()))
     }
 }
 
 /// Get window handle for plugin embedding
+///
+/// Returns the base64 encoded native handle of the window with the given label.
 #[tauri::command]
-pub fn get_window_handle_for_plugin(
+pub fn get_window_handle_for_plugin<R: Runtime>(
     window_label: String,
-) -> Result<String, String> {
+    app: AppHandle<R>,
+) -> DawResult<String> {
     println!("🪟 Getting window handle for window: {}", window_label);
-    
-    // This is a placeholder implementation
-    // In a real implementation, you would:
-    // 1. Get the native window handle using platform-specific APIs
-    // 2. Encode it as base64 for transmission to plugin
-    // 3. Handle platform differences (NSWindow on macOS, HWND on Windows, etc.)
-    
-    #[cfg(target_os = "macos")]
-    {
-        // On macOS, you would get the NSWindow pointer
-        // For now, return a dummy handle
-        let dummy_handle = vec![0u8; 8]; // 64-bit pointer
-        let encoded = base64::prelude::BASE64_STANDARD.encode(&dummy_handle);
-        Ok(encoded)
-    }
-    
-    #[cfg(target_os = "windows")]
-    {
-        // On Windows, you would get the HWND
-        let dummy_handle = vec![0u8; 8]; // 64-bit pointer
-        let encoded = base64::prelude::BASE64_STANDARD.encode(&dummy_handle);
-        Ok(encoded)
-    }
-    
-    #[cfg(target_os = "linux")]
-    {
-        // On Linux, you would get the Window or X11 Window
-        let dummy_handle = vec![0u8; 8]; // 64-bit pointer
-        let encoded = base64::prelude::BASE64_STANDARD.encode(&dummy_handle);
-        Ok(encoded)
-    }
-    
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-    {
-        Err("Unsupported platform".to_string())
-    }
+
+    let window = app
+        .get_webview_window(&window_label)
+        .ok_or_else(|| DawError::NotFound(format!("Window not found: {}", window_label)))?;
+
+    let handle = get_window_handle(&window).map_err(DawError::Window)?;
+    encode_window_handle(handle).map_err(DawError::Window)
 }
 
 // ============================================================================
@@ -870,11 +905,11 @@ pub async fn add_midi_mapping(
     min_value: f32,
     max_value: f32,
     state: State<'_, DawState>,
-) -> Result<(), String> {
+) -> DawResult<()> {
     println!("🎛️ Adding MIDI mapping: CC {} -> {} param {}", cc_number, plugin_instance_id, parameter_index);
     
     let instance_id: PluginInstanceId = plugin_instance_id.parse()
-        .map_err(|e| format!("Invalid plugin instance ID: {}", e))?;
+        .map_err(|e| DawError::InvalidArgument(format!("Invalid plugin instance ID: {}", e)))?;
     
     let mapping = MidiMapping {
         cc_number,
@@ -897,7 +932,7 @@ pub async fn add_midi_mapping(
 pub async fn remove_midi_mapping(
     cc_number: u8,
     state: State<'_, DawState>,
-) -> Result<(), String> {
+) -> DawResult<()> {
     println!("🗑️ Removing MIDI mapping: CC {}", cc_number);
     
     // TODO: Remove from MIDI bridge
@@ -910,7 +945,7 @@ pub async fn remove_midi_mapping(
 #[tauri::command]
 pub async fn get_midi_mappings(
     state: State<'_, DawState>,
-) -> Result<Vec<MidiMappingInfo>, String> {
+) -> DawResult<Vec<MidiMappingInfo>> {
     println!("📋 Getting all MIDI mappings...");
     
     // TODO: Get from MIDI bridge
@@ -925,11 +960,11 @@ pub async fn auto_map_plugin(
     plugin_instance_id: String,
     start_cc: Option<u8>,
     state: State<'_, DawState>,
-) -> Result<Vec<MidiMappingInfo>, String> {
+) -> DawResult<Vec<MidiMappingInfo>> {
     println!("🎛️ Auto-mapping plugin {} to MIDI...", plugin_instance_id);
     
     let instance_id: PluginInstanceId = plugin_instance_id.parse()
-        .map_err(|e| format!("Invalid plugin instance ID: {}", e))?;
+        .map_err(|e| DawError::InvalidArgument(format!("Invalid plugin instance ID: {}", e)))?;
     
     let start_cc = start_cc.unwrap_or(16); // Start at CC 16 by default
     
@@ -948,7 +983,7 @@ pub async fn send_midi_cc(
     cc_number: u8,
     value: u8,
     state: State<'_, DawState>,
-) -> Result<(), String> {
+) -> DawResult<()> {
     println!("🎛️ Sending MIDI CC {} value {} to plugin {}", cc_number, value, plugin_instance_id);
     
     // TODO: Send via MIDI bridge
@@ -962,7 +997,7 @@ pub async fn send_midi_cc(
 pub async fn create_virtual_midi_port(
     port_name: String,
     state: State<'_, DawState>,
-) -> Result<(), String> {
+) -> DawResult<()> {
     println!("🎹 Creating virtual MIDI port: {}", port_name);
     
     // TODO: Create via MIDI bridge
@@ -976,14 +1011,14 @@ pub async fn create_virtual_midi_port(
 pub async fn test_midi_communication(
     plugin_instance_id: String,
     state: State<'_, DawState>,
-) -> Result<String, String> {
+) -> DawResult<String> {
     println!("🧪 Testing MIDI communication with plugin {}", plugin_instance_id);
     
     // TODO: Test via MIDI bridge
     // For now, just test basic plugin loading without GUI
     
     let instance_id: PluginInstanceId = plugin_instance_id.parse()
-        .map_err(|e| format!("Invalid plugin instance ID: {}", e))?;
+        .map_err(|e| DawError::InvalidArgument(format!("Invalid plugin instance ID: {}", e)))?;
     
     // Test if plugin instance exists and can process MIDI
     println!("🧪 Testing plugin {} MIDI capabilities...", instance_id);
@@ -1005,7 +1040,7 @@ pub async fn test_midi_communication(
 
 /// Get default MIDI CC assignments
 #[tauri::command]
-pub async fn get_default_midi_assignments() -> Result<Vec<(u8, String)>, String> {
+pub async fn get_default_midi_assignments() -> DawResult<Vec<(u8, String)>> {
     println!("📋 Getting default MIDI CC assignments...");
     
     let assignments = vec![
@@ -0,0 +1,371 @@
+// Dedicated native windows for plugin GUIs
+//
+// Each plugin instance gets its own Tauri window. The plugin GUI is attached to
+// that window's real native handle, user resizes are forwarded to the plugin and
+// resize requests coming from the plugin (CLAP host GUI extension) are applied to
+// the window. Closing the window destroys the plugin GUI.
+
+use tauri::{AppHandle, Manager, PhysicalSize, Runtime, Size, State, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+use crate::{DawState, ManagedPlugin, PluginGuiInfo};
+use crate::window_utils::get_window_handle;
+use crate::error::{DawError, DawResult};
+use serde::{Deserialize, Serialize};
+use mymusic_daw::plugin::{take_gui_host_requests, ClapPluginGui, GuiHostRequest};
+use std::thread;
+use std::time::Duration;
+
+/// Interval at which plugin GUI requests are polled
+const GUI_REQUEST_POLL_INTERVAL: Duration = Duration::from_millis(30);
+
+/// Plugin window description returned to the frontend
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PluginWindowInfo {
+    pub label: String,
+    pub width: u32,
+    pub height: u32,
+    pub can_resize: bool,
+}
+
+/// Window label for a plugin instance (Tauri labels only allow `a-zA-Z0-9-/:_`)
+fn plugin_window_label(plugin_id: &str) -> String {
+    let sanitized: String = plugin_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-/:_".contains(c) { c } else { '_' })
+        .collect();
+    format!("plugin-{}", sanitized)
+}
+
+/// CLAP GUI sizes are in logical points on macOS and in physical pixels elsewhere
+fn gui_size_to_window_size(width: u32, height: u32) -> Size {
+    if cfg!(target_os = "macos") {
+        Size::Logical(tauri::LogicalSize::new(width as f64, height as f64))
+    } else {
+        Size::Physical(PhysicalSize::new(width, height))
+    }
+}
+
+fn window_size_to_gui_size(size: PhysicalSize<u32>, scale_factor: f64, logical: bool) -> (u32, u32) {
+    if logical {
+        let size = size.to_logical::<f64>(scale_factor);
+        (size.width.round() as u32, size.height.round() as u32)
+    } else {
+        (size.width, size.height)
+    }
+}
+
+/// Run an operation on the CLAP GUI of a managed plugin
+fn with_plugin_gui<T>(
+    managed_plugin: &ManagedPlugin,
+    f: impl FnOnce(&mut ClapPluginGui) -> DawResult<T>,
+) -> DawResult<T> {
+    managed_plugin
+        .host
+        .with_instance_wrapper_mut(managed_plugin.instance_id, |wrapper| {
+            let clap_instance = wrapper
+                .as_clap_plugin_mut()
+                .ok_or_else(|| DawError::Plugin("Failed to get CLAP plugin instance".to_string()))?;
+            let gui = clap_instance
+                .gui_mut()
+                .ok_or_else(|| DawError::Plugin("Plugin does not have GUI support".to_string()))?;
+            f(gui)
+        })
+        .unwrap_or_else(|| Err(DawError::Plugin("Failed to get instance wrapper".to_string())))
+}
+
+/// Open (or focus) the dedicated window of a plugin and embed its GUI
+#[tauri::command]
+pub fn open_plugin_window<R: Runtime>(
+    plugin_id: String,
+    app: AppHandle<R>,
+    state: State<DawState>,
+) -> DawResult<PluginWindowInfo> {
+    println!("🪟 Opening plugin window for: {}", plugin_id);
+
+    // Create the GUI first to know its size (lock released before touching windows,
+    // window events may fire synchronously and need the plugins lock)
+    let (title, existing_label, width, height, can_resize) = {
+        let plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
+        let managed_plugin = plugins
+            .get(&plugin_id)
+            .ok_or_else(|| DawError::NotFound(format!("Plugin not found: {}", plugin_id)))?;
+
+        let title = managed_plugin
+            .host
+            .get_instance_info(managed_plugin.instance_id)
+            .map(|info| info.plugin_name)
+            .unwrap_or_else(|| plugin_id.clone());
+        let existing_label = managed_plugin.gui_info.as_ref().and_then(|info| info.window_label.clone());
+
+        let (width, height, can_resize) = with_plugin_gui(managed_plugin, |gui| {
+            gui.create().map_err(|e| DawError::Window(format!("Failed to create GUI: {}", e)))?;
+            let (width, height) = gui.get_size();
+            Ok((width, height, gui.can_resize()))
+        })?;
+
+        (title, existing_label, width, height, can_resize)
+    };
+
+    // Already open: just bring it to front
+    if let Some(window) = existing_label.as_deref().and_then(|label| app.get_webview_window(label)) {
+        window.show().map_err(|e| DawError::Window(format!("Failed to show window: {}", e)))?;
+        window.set_focus().map_err(|e| DawError::Window(format!("Failed to focus window: {}", e)))?;
+        return Ok(PluginWindowInfo {
+            label: window.label().to_string(),
+            width,
+            height,
+            can_resize,
+        });
+    }
+
+    let label = plugin_window_label(&plugin_id);
+    let url = WebviewUrl::External("about:blank".parse().map_err(|e| DawError::Window(format!("Invalid URL: {}", e)))?);
+    let window = WebviewWindowBuilder::new(&app, &label, url)
+        .title(title)
+        .resizable(can_resize)
+        .visible(false)
+        .build()
+        .map_err(|e| DawError::Window(format!("Failed to create plugin window: {}", e)))?;
+    window
+        .set_size(gui_size_to_window_size(width.max(1), height.max(1)))
+        .map_err(|e| DawError::Window(format!("Failed to size plugin window: {}", e)))?;
+
+    let handle = get_window_handle(&window).map_err(DawError::Window)?;
+
+    let attach_result = {
+        let mut plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
+        let managed_plugin = plugins
+            .get_mut(&plugin_id)
+            .ok_or_else(|| DawError::NotFound(format!("Plugin not found: {}", plugin_id)))?;
+
+        let result = with_plugin_gui(managed_plugin, |gui| {
+            // SAFETY: handle comes from the live window created above
+            unsafe { gui.attach_to_window(handle) }.map_err(|e| DawError::Window(format!("Failed to attach GUI: {}", e)))?;
+            gui.show().map_err(|e| DawError::Window(format!("Failed to show GUI: {}", e)))
+        });
+
+        if result.is_ok() {
+            managed_plugin.gui_info = Some(PluginGuiInfo {
+                is_visible: true,
+                width,
+                height,
+                can_resize,
+                api: "clap".to_string(),
+                window_label: Some(label.clone()),
+            });
+        }
+        result
+    };
+
+    if let Err(e) = attach_result {
+        let _ = window.destroy();
+        return Err(e);
+    }
+
+    let app_handle = app.clone();
+    let event_plugin_id = plugin_id.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::Resized(size) => on_plugin_window_resized(&app_handle, &event_plugin_id, *size),
+        WindowEvent::Destroyed => on_plugin_window_destroyed(&app_handle, &event_plugin_id),
+        _ => {}
+    });
+
+    window.show().map_err(|e| DawError::Window(format!("Failed to show window: {}", e)))?;
+
+    println!("✅ Plugin window opened: {} ({}x{})", label, width, height);
+    Ok(PluginWindowInfo {
+        label,
+        width,
+        height,
+        can_resize,
+    })
+}
+
+/// Close the dedicated window of a plugin (destroys the plugin GUI)
+#[tauri::command]
+pub fn close_plugin_window<R: Runtime>(plugin_id: String, app: AppHandle<R>, state: State<DawState>) -> DawResult<()> {
+    println!("🪟 Closing plugin window for: {}", plugin_id);
+
+    let label = {
+        let plugins = state.plugins.lock().map_err(|e| DawError::poisoned("plugins", e))?;
+        let managed_plugin = plugins
+            .get(&plugin_id)
+            .ok_or_else(|| DawError::NotFound(format!("Plugin not found: {}", plugin_id)))?;
+        managed_plugin.gui_info.as_ref().and_then(|info| info.window_label.clone())
+    };
+
+    match label.and_then(|label| app.get_webview_window(&label)) {
+        // GUI cleanup happens in the `Destroyed` window event handler
+        Some(window) => window.destroy().map_err(|e| DawError::Window(format!("Failed to close plugin window: {}", e))),
+        None => Ok(()),
+    }
+}
+
+/// User resized the window: forward the new size to the plugin
+fn on_plugin_window_resized<R: Runtime>(app: &AppHandle<R>, plugin_id: &str, size: PhysicalSize<u32>) {
+    let Some(window) = plugin_window(app, plugin_id) else {
+        return;
+    };
+    let scale_factor = window.scale_factor().unwrap_or(1.0);
+    let (width, height) = window_size_to_gui_size(size, scale_factor, cfg!(target_os = "macos"));
+
+    let state = app.state::<DawState>();
+    let adjusted = {
+        let Ok(mut plugins) = state.plugins.lock() else {
+            return;
+        };
+        let Some(managed_plugin) = plugins.get_mut(plugin_id) else {
+            return;
+        };
+
+        let result = with_plugin_gui(managed_plugin, |gui| {
+            if !gui.can_resize() || gui.get_size() == (width, height) {
+                return Ok(None);
+            }
+
+            let (w, h) = gui.adjust_size(width, height);
+            gui.set_size(w, h).map_err(|e| DawError::Window(format!("Failed to set GUI size: {}", e)))?;
+            Ok(Some((w, h)))
+        });
+
+        match result {
+            Ok(Some((w, h))) => {
+                if let Some(ref mut gui_info) = managed_plugin.gui_info {
+                    gui_info.width = w;
+                    gui_info.height = h;
+                }
+                (w, h)
+            }
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("⚠️ Plugin window resize failed for {}: {}", plugin_id, e);
+                return;
+            }
+        }
+    };
+
+    // The plugin constrained the size: snap the window to it
+    if adjusted != (width, height) {
+        let _ = window.set_size(gui_size_to_window_size(adjusted.0, adjusted.1));
+    }
+}
+
+/// Window closed: destroy the plugin GUI so it can be reopened later
+fn on_plugin_window_destroyed<R: Runtime>(app: &AppHandle<R>, plugin_id: &str) {
+    let state = app.state::<DawState>();
+    let Ok(mut plugins) = state.plugins.lock() else {
+        return;
+    };
+    let Some(managed_plugin) = plugins.get_mut(plugin_id) else {
+        return;
+    };
+
+    let _ = with_plugin_gui(managed_plugin, |gui| {
+        let _ = gui.hide();
+        gui.destroy();
+        Ok(())
+    });
+
+    if let Some(ref mut gui_info) = managed_plugin.gui_info {
+        gui_info.is_visible = false;
+        gui_info.window_label = None;
+    }
+
+    println!("🧹 Plugin GUI destroyed after window close: {}", plugin_id);
+}
+
+fn plugin_window<R: Runtime>(app: &AppHandle<R>, plugin_id: &str) -> Option<WebviewWindow<R>> {
+    app.get_webview_window(&plugin_window_label(plugin_id))
+}
+
+/// Apply a request coming from a plugin GUI to its window (main thread)
+fn handle_gui_host_request<R: Runtime>(app: &AppHandle<R>, host_id: usize, request: GuiHostRequest) {
+    let state = app.state::<DawState>();
+
+    // Find the plugin owning this host, and record the size it asked for
+    let plugin_id = {
+        let Ok(mut plugins) = state.plugins.lock() else {
+            return;
+        };
+        let found = plugins.iter_mut().find(|(_, managed_plugin)| {
+            managed_plugin
+                .host
+                .with_instance_wrapper_mut(managed_plugin.instance_id, |wrapper| {
+                    wrapper.as_clap_plugin().map(|clap| clap.host_id()) == Some(host_id)
+                })
+                .unwrap_or(false)
+        });
+        let Some((plugin_id, managed_plugin)) = found else {
+            return;
+        };
+
+        if let GuiHostRequest::Resize { width, height } = request {
+            // The plugin already uses this size, no need to call set_size() back
+            let _ = with_plugin_gui(managed_plugin, |gui| {
+                gui.update_size(width, height);
+                Ok(())
+            });
+            if let Some(ref mut gui_info) = managed_plugin.gui_info {
+                gui_info.width = width;
+                gui_info.height = height;
+            }
+        }
+
+        plugin_id.clone()
+    };
+
+    let Some(window) = plugin_window(app, &plugin_id) else {
+        return;
+    };
+
+    let result = match request {
+        GuiHostRequest::Resize { width, height } => window.set_size(gui_size_to_window_size(width, height)),
+        GuiHostRequest::Show => window.show(),
+        GuiHostRequest::Hide => window.hide(),
+        GuiHostRequest::Closed { .. } => window.destroy(),
+    };
+
+    if let Err(e) = result {
+        eprintln!("⚠️ Failed to apply plugin GUI request {:?} for {}: {}", request, plugin_id, e);
+    }
+}
+
+/// Start the background loop dispatching plugin GUI requests to their windows
+///
+/// Requests are collected from the CLAP host callbacks and applied on the main thread.
+pub fn spawn_plugin_gui_request_pump<R: Runtime>(app: AppHandle<R>) {
+    thread::spawn(move || loop {
+        thread::sleep(GUI_REQUEST_POLL_INTERVAL);
+
+        for (host_id, request) in take_gui_host_requests() {
+            let app_handle = app.clone();
+            if app
+                .run_on_main_thread(move || handle_gui_host_request(&app_handle, host_id, request))
+                .is_err()
+            {
+                // Event loop is gone, the app is shutting down
+                return;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_window_label_is_sanitized() {
+        assert_eq!(plugin_window_label("plugin_0"), "plugin-plugin_0");
+        assert_eq!(plugin_window_label("my synth #2"), "plugin-my_synth__2");
+    }
+
+    #[test]
+    fn test_window_size_to_gui_size() {
+        let size = PhysicalSize::new(1600, 1200);
+
+        // Physical pixels (Windows/Linux)
+        assert_eq!(window_size_to_gui_size(size, 2.0, false), (1600, 1200));
+        // Logical points (macOS)
+        assert_eq!(window_size_to_gui_size(size, 2.0, true), (800, 600));
+    }
+}
@@ -0,0 +1,37 @@
+// Preferences commands (user settings shared with the egui frontend)
+//
+// Preferences are loaded at startup (see main.rs) and saved on every change.
+// Audio device settings apply to the next engine started (`restart_engine`).
+
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::lock_core;
+use crate::error::{DawError, DawResult};
+use mymusic_daw::config::Preferences;
+use mymusic_daw::OutputOptions;
+
+/// Get the user preferences
+#[tauri::command]
+pub fn get_preferences(state: State<DawState>) -> DawResult<Preferences> {
+    Ok(state.preferences.lock().map_err(|e| DawError::poisoned("preferences", e))?.clone())
+}
+
+/// Replace and save the user preferences
+///
+/// Invalid values (e.g. an unsupported buffer size) are reset to their default;
+/// the stored preferences are returned.
+#[tauri::command]
+pub fn set_preferences(mut preferences: Preferences, state: State<DawState>) -> DawResult<Preferences> {
+    preferences.sanitize();
+    preferences.save().map_err(DawError::Io)?;
+
+    state
+        .engine
+        .lock()
+        .map_err(|e| DawError::poisoned("engine", e))?
+        .set_output_options(OutputOptions::from(&preferences.audio));
+    // The metronome output applies right away
+    lock_core(&state)?.set_metronome_output(preferences.audio.metronome_output)?;
+    *state.preferences.lock().map_err(|e| DawError::poisoned("preferences", e))? = preferences.clone();
+    Ok(preferences)
+}
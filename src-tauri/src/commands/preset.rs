@@ -0,0 +1,178 @@
+// Preset commands (synth presets and sample banks)
+//
+// Synth presets capture the sound stored in the shared core state; sample banks
+// capture the loaded samples with their note mappings and key-range zones. Factory synth presets are
+// listed by `browse_synth_presets` and can be loaded like user presets.
+
+use tauri::State;
+use crate::{DawState, LoadedSample, SamplerStore};
+use crate::commands::basic::lock_core;
+use crate::commands::sampler::load_bank_samples;
+use crate::error::{DawError, DawResult};
+use mymusic_daw::project::{PresetEntry, PresetKind, SynthPreset};
+use mymusic_daw::sampler::bank::{SampleBank, SampleMapping};
+
+/// Build a sample bank from the samples mapped to notes or zones
+fn sampler_to_bank(name: String, sampler: &SamplerStore) -> SampleBank {
+    // Absolute paths: presets are stored away from the sample files
+    let path_of = |loaded: &LoadedSample| std::fs::canonicalize(&loaded.path).unwrap_or_else(|_| loaded.path.clone());
+    let mut bank = SampleBank::new(name);
+    for (&note, &sample_index) in &sampler.note_mappings {
+        if let Some(loaded) = sampler.samples.get(sample_index) {
+            bank.add_mapping(SampleMapping::from_sample(note, path_of(loaded), &loaded.sample));
+        }
+    }
+    for (&sample_index, &zone) in &sampler.zones {
+        if let Some(loaded) = sampler.samples.get(sample_index) {
+            bank.add_mapping(SampleMapping::from_zone(zone, path_of(loaded), &loaded.sample));
+        }
+    }
+    bank
+}
+
+/// Check a preset name, and that the preset exists when `must_exist` is set
+///
+/// Done before calling the store so the frontend gets `invalid_argument` /
+/// `not_found` instead of a generic I/O error.
+fn check_preset(kind: PresetKind, name: &str, must_exist: bool, state: &DawState) -> DawResult<()> {
+    let path = state.presets.preset_path(kind, name).map_err(DawError::InvalidArgument)?;
+    let exists = match kind {
+        PresetKind::Synth => state.presets.has_synth(name),
+        PresetKind::SampleBank => path.exists(),
+    };
+    if must_exist && !exists {
+        return Err(DawError::NotFound(format!("Preset not found: {}", name)));
+    }
+    Ok(())
+}
+
+/// List preset names of a kind ("synth" or "sample_bank")
+#[tauri::command]
+pub fn list_presets(kind: PresetKind, state: State<DawState>) -> DawResult<Vec<String>> {
+    state.presets.list(kind).map_err(DawError::Io)
+}
+
+/// List factory and user synth presets (a user preset hides the factory preset it is named after)
+#[tauri::command]
+pub fn browse_synth_presets(state: State<DawState>) -> DawResult<Vec<PresetEntry>> {
+    state.presets.browse_synth().map_err(DawError::Io)
+}
+
+/// Save the current synth sound or sample bank as a preset
+///
+/// Saving with an existing name overwrites the preset.
+#[tauri::command]
+pub fn save_preset(kind: PresetKind, name: String, state: State<DawState>) -> DawResult<()> {
+    check_preset(kind, &name, false, &state)?;
+    match kind {
+        PresetKind::Synth => {
+            let preset = SynthPreset::from_state(name, &*lock_core(&state)?);
+            state.presets.save_synth(&preset).map_err(DawError::Io)?;
+        }
+        PresetKind::SampleBank => {
+            let bank = {
+                let sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+                if sampler.note_mappings.is_empty() && sampler.zones.is_empty() {
+                    return Err(DawError::InvalidArgument("No sample is mapped to a note or zone".to_string()));
+                }
+                sampler_to_bank(name, &sampler)
+            };
+            state.presets.save_sample_bank(&bank).map_err(DawError::Io)?;
+        }
+    }
+    Ok(())
+}
+
+/// Load a preset
+///
+/// Synth presets replace the synth sound, sample banks replace all loaded samples.
+#[tauri::command]
+pub fn load_preset(kind: PresetKind, name: String, state: State<DawState>) -> DawResult<()> {
+    check_preset(kind, &name, true, &state)?;
+    match kind {
+        PresetKind::Synth => {
+            let preset = state.presets.load_synth(&name).map_err(DawError::Io)?;
+            Ok(preset.apply(&mut *lock_core(&state)?)?)
+        }
+        PresetKind::SampleBank => {
+            let bank = state.presets.load_sample_bank(&name).map_err(DawError::Io)?;
+            load_bank_samples(&bank, &state.presets.kind_dir(PresetKind::SampleBank), &state)
+        }
+    }
+}
+
+/// Delete a preset (factory presets cannot be deleted)
+#[tauri::command]
+pub fn delete_preset(kind: PresetKind, name: String, state: State<DawState>) -> DawResult<()> {
+    check_preset(kind, &name, true, &state)?;
+    if kind == PresetKind::Synth && !state.presets.preset_path(kind, &name).is_ok_and(|path| path.exists()) {
+        return Err(DawError::InvalidArgument(format!("Factory preset cannot be deleted: {}", name)));
+    }
+    state.presets.delete(kind, &name).map_err(DawError::Io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mymusic_daw::sampler::loader::{LoopMode, Sample, SampleData};
+    use mymusic_daw::sampler::SampleZone;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn loaded_sample(name: &str) -> LoadedSample {
+        LoadedSample {
+            sample: Arc::new(Sample {
+                name: name.to_string(),
+                data: SampleData::F32(vec![0.0; 16].into()),
+                sample_rate: 48000,
+                source_channels: 1,
+                loop_mode: LoopMode::Off,
+                loop_start: 0,
+                loop_end: 16,
+                reverse: false,
+                volume: 0.8,
+                pan: 0.0,
+                pitch_offset: 0,
+                fine_tune: 0.0,
+                envelope: None,
+                stretch: None,
+            }),
+            path: PathBuf::from(format!("/samples/{}.wav", name)),
+        }
+    }
+
+    #[test]
+    fn test_sampler_to_bank_only_keeps_mapped_samples() {
+        let mut sampler = SamplerStore::default();
+        sampler.samples.push(loaded_sample("kick"));
+        sampler.samples.push(loaded_sample("snare"));
+        sampler.note_mappings.insert(36, 0);
+
+        let bank = sampler_to_bank("Drums".to_string(), &sampler);
+        assert_eq!(bank.samples.len(), 1);
+
+        let mapping = bank.get_mapping(36).unwrap();
+        assert_eq!(mapping.name, "kick");
+        assert_eq!(mapping.volume, 0.8);
+        assert_eq!(mapping.sample_path, PathBuf::from("/samples/kick.wav"));
+    }
+
+    #[test]
+    fn test_sampler_to_bank_keeps_zones() {
+        let mut sampler = SamplerStore::default();
+        sampler.samples.push(loaded_sample("kick"));
+        sampler.samples.push(loaded_sample("piano"));
+        sampler.note_mappings.insert(36, 0);
+        let zone = SampleZone::new(48, 72, 60).unwrap();
+        sampler.zones.insert(1, zone);
+
+        let bank = sampler_to_bank("Keys".to_string(), &sampler);
+        assert_eq!(bank.samples.len(), 2);
+        let zones: Vec<_> = bank.zones().collect();
+        assert_eq!(zones.len(), 1);
+        assert_eq!(zones[0].name, "piano");
+        assert_eq!(zones[0].zone, Some(zone));
+        // The zone does not take over the root note
+        assert!(bank.get_mapping(60).is_none());
+    }
+}
@@ -0,0 +1,106 @@
+// Remote control commands (OSC server)
+//
+// The OSC server decodes messages on its own thread; a pump applies the
+// resulting actions to the shared core state, like the matching Tauri commands
+// would, and tells the frontend to refresh its controls.
+
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
+use crate::DawState;
+use crate::commands::basic::lock_core;
+use crate::commands::pattern::{retime_patterns, TimingContext};
+use crate::error::{DawError, DawResult};
+use mymusic_daw::remote::{OscServer, RemoteAction};
+
+/// Emitted after remote actions changed the DAW state
+pub const REMOTE_CONTROL_EVENT: &str = "remote-control";
+
+/// Interval between two polls of the OSC server
+const REMOTE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// OSC server status
+#[derive(Debug, Serialize)]
+pub struct OscStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+}
+
+fn osc_status(state: &DawState) -> DawResult<OscStatus> {
+    let server = state.osc.lock().map_err(|e| DawError::poisoned("OSC server", e))?;
+    Ok(OscStatus {
+        running: server.is_some(),
+        port: server.as_ref().map(|server| server.local_addr().port()),
+    })
+}
+
+/// Start the OSC server on a UDP port (restarts it if already running)
+#[tauri::command]
+pub fn start_osc_server(port: u16, state: State<DawState>) -> DawResult<OscStatus> {
+    {
+        let mut server = state.osc.lock().map_err(|e| DawError::poisoned("OSC server", e))?;
+        // Release the port before binding it again
+        *server = None;
+        *server = Some(OscServer::bind(("0.0.0.0", port)).map_err(DawError::Io)?);
+    }
+    osc_status(&state)
+}
+
+/// Stop the OSC server
+#[tauri::command]
+pub fn stop_osc_server(state: State<DawState>) -> DawResult<()> {
+    *state.osc.lock().map_err(|e| DawError::poisoned("OSC server", e))? = None;
+    Ok(())
+}
+
+/// Get the OSC server status
+#[tauri::command]
+pub fn get_osc_status(state: State<DawState>) -> DawResult<OscStatus> {
+    osc_status(&state)
+}
+
+/// Apply one remote action to the core state
+pub(crate) fn apply_remote_action(action: RemoteAction, state: &State<DawState>) -> DawResult<()> {
+    match action {
+        // Pattern notes keep their position in beats (see `set_tempo`)
+        RemoteAction::SetTempo(_) => {
+            let old_timing = TimingContext::from_state(state)?;
+            action.apply(&mut *lock_core(state)?)?;
+            retime_patterns(&old_timing, state)
+        }
+        RemoteAction::SetVolume(_) => {
+            let mut core = lock_core(state)?;
+            action.apply(&mut core)?;
+            state.volume_atomic.set(core.volume);
+            Ok(())
+        }
+        _ => Ok(action.apply(&mut *lock_core(state)?)?),
+    }
+}
+
+/// Start the background thread applying actions received by the OSC server
+pub fn spawn_remote_control_pump<R: Runtime>(app: AppHandle<R>) {
+    thread::Builder::new()
+        .name("remote-control".to_string())
+        .spawn(move || loop {
+            thread::sleep(REMOTE_POLL_INTERVAL);
+
+            let state = app.state::<DawState>();
+            let actions = match state.osc.lock() {
+                Ok(server) => server.as_ref().map(OscServer::poll).unwrap_or_default(),
+                Err(_) => return,
+            };
+            if actions.is_empty() {
+                continue;
+            }
+
+            for action in actions {
+                if let Err(e) = apply_remote_action(action, &state) {
+                    eprintln!("OSC {:?} failed: {}", action, e);
+                }
+            }
+            let _ = app.emit(REMOTE_CONTROL_EVENT, ());
+        })
+        .expect("failed to spawn remote control thread");
+}
@@ -0,0 +1,263 @@
+// Crash rescue commands
+//
+// The crash handlers are installed by `main` (see `mymusic_daw::crash`). A pump
+// keeps the rescue copy of the session and the crash diagnostics up to date,
+// and the frontend asks at launch for the session rescued after the previous
+// crash, then restores or discards it.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Listener, Manager, Runtime, State};
+use crate::DawState;
+use crate::commands::basic::lock_core;
+use crate::commands::pattern::{replace_patterns, TimingContext};
+use crate::error::{DawError, DawResult};
+use crate::events::{NotificationPayload, NOTIFICATION_EVENT};
+use mymusic_daw::crash::{self, Diagnostics, RescueSession};
+use mymusic_daw::messaging::command::Command;
+use mymusic_daw::project::serialization::{pattern_from_serializable, pattern_to_serializable};
+use mymusic_daw::project::{Project, ProjectLoadOptions, ProjectManager, SynthPreset};
+use mymusic_daw::sequencer::Tempo;
+use mymusic_daw::synth::modulation::{ModRouting, MAX_ROUTINGS};
+
+/// Interval between two updates of the rescue session
+const RESCUE_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Notifications kept for the crash report
+const RECENT_NOTIFICATIONS: usize = 20;
+
+/// Build a project from the current state (patterns, transport, synth sound,
+/// automation)
+pub(crate) fn build_project(state: &DawState) -> DawResult<Project> {
+    let sample_rate = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?.sample_rate() as f64;
+    if sample_rate <= 0.0 {
+        return Err(DawError::Engine("Audio engine is not running".to_string()));
+    }
+    let mut project = ProjectManager::new(sample_rate).create_new_project("Untitled Project".to_string());
+
+    {
+        let core = lock_core(state)?;
+        project.metadata.tempo = core.tempo.bpm();
+        project.metadata.time_signature = core.time_signature;
+        let synth = &mut project.synth_params;
+        synth.volume = core.volume;
+        synth.waveform = core.waveform;
+        synth.oscillator2 = core.oscillator2;
+        synth.unison = core.unison;
+        synth.anti_aliasing = core.anti_aliasing;
+        synth.cross_mod = core.cross_mod;
+        synth.noise = core.noise;
+        synth.mod_envelope = core.mod_envelope;
+        synth.filter_mod = core.filter_mod;
+        synth.mod_lfos = core.mod_lfos;
+        synth.adsr = core.adsr;
+        synth.lfo = core.lfo;
+        synth.filter = core.filter;
+        synth.portamento = core.portamento;
+        synth.poly_mode = core.poly_mode;
+        project.automation = core.automation_lanes.values().cloned().collect();
+        project.cc_mappings = core.cc_map.mappings();
+    }
+
+    let store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+    project.patterns = store
+        .patterns
+        .iter()
+        .map(|(id, pattern)| (*id, pattern_to_serializable(pattern)))
+        .collect();
+    for track in project.tracks.values_mut() {
+        track.pattern_id = store.active_pattern_id;
+    }
+    Ok(project)
+}
+
+/// What the rescue copy depends on (patterns sorted: the map order is random)
+fn project_fingerprint(project: &Project) -> String {
+    let patterns: BTreeMap<_, _> = project.patterns.iter().collect();
+    serde_json::to_string(&(
+        patterns,
+        &project.synth_params,
+        project.metadata.tempo,
+        &project.metadata.time_signature,
+        &project.automation,
+        &project.cc_mappings,
+    ))
+    .unwrap_or_default()
+}
+
+/// Load a project into the state, like the matching commands would
+fn apply_project(project: &Project, state: &State<DawState>) -> DawResult<()> {
+    let metadata = &project.metadata;
+    let timing = TimingContext::new(metadata.sample_rate, Tempo::new(metadata.tempo), metadata.time_signature);
+    let patterns = project
+        .patterns
+        .values()
+        .map(|pattern| pattern_from_serializable(pattern, metadata.sample_rate))
+        .collect();
+
+    {
+        let mut core = lock_core(state)?;
+        core.set_tempo(metadata.tempo)?;
+        core.set_time_signature(metadata.time_signature.numerator, metadata.time_signature.denominator)?;
+
+        let synth = &project.synth_params;
+        let preset = SynthPreset {
+            name: metadata.name.clone(),
+            waveform: synth.waveform,
+            oscillator2: synth.oscillator2,
+            unison: synth.unison,
+            anti_aliasing: synth.anti_aliasing,
+            cross_mod: synth.cross_mod,
+            noise: synth.noise,
+            mod_envelope: synth.mod_envelope,
+            filter_mod: synth.filter_mod,
+            mod_lfos: synth.mod_lfos,
+            adsr: synth.adsr,
+            lfo: synth.lfo,
+            filter: synth.filter,
+            portamento: synth.portamento,
+            poly_mode: synth.poly_mode,
+            mod_routings: [ModRouting::disabled(); MAX_ROUTINGS],
+        };
+        preset.apply(&mut core)?;
+
+        core.volume = synth.volume;
+        state.volume_atomic.set(synth.volume);
+        if !core.send_to_audio(Command::SetVolume(synth.volume)) {
+            return Err(DawError::QueueFull);
+        }
+        core.set_automation_lanes(project.automation.clone())?;
+        core.cc_map.set_mappings(project.cc_mappings.clone()).map_err(DawError::InvalidArgument)?;
+    }
+
+    replace_patterns(patterns, &timing, state)
+}
+
+/// Get the session rescued after the previous crash, if any
+#[tauri::command]
+pub fn get_rescue_session(state: State<DawState>) -> DawResult<Option<RescueSession>> {
+    Ok(state.rescue.lock().map_err(|e| DawError::poisoned("rescue session", e))?.clone())
+}
+
+/// Restore the rescued session (patterns, transport and synth sound)
+#[tauri::command]
+pub fn restore_rescue_session(state: State<DawState>) -> DawResult<()> {
+    let rescue = state
+        .rescue
+        .lock()
+        .map_err(|e| DawError::poisoned("rescue session", e))?
+        .clone()
+        .ok_or_else(|| DawError::NotFound("No rescued session".to_string()))?;
+
+    let project = ProjectManager::new(48000.0)
+        .load_project(&rescue.project_file, &ProjectLoadOptions::default())
+        .map_err(|e| DawError::Io(format!("Failed to load the rescued session: {}", e)))?;
+    apply_project(&project, &state)?;
+
+    discard_rescue_session(state)
+}
+
+/// Delete the rescued session
+#[tauri::command]
+pub fn discard_rescue_session(state: State<DawState>) -> DawResult<()> {
+    *state.rescue.lock().map_err(|e| DawError::poisoned("rescue session", e))? = None;
+    crash::discard_rescue();
+    Ok(())
+}
+
+fn diagnostics(state: &DawState, notifications: &Mutex<VecDeque<String>>) -> DawResult<Diagnostics> {
+    let engine_status = {
+        let engine = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?;
+        let snapshot = engine.snapshot();
+        vec![
+            format!("Running: {}", engine.is_running()),
+            format!("Sample rate: {} Hz", engine.sample_rate()),
+            format!("Playing: {}", snapshot.playing),
+            format!("Tempo: {:.1} BPM", snapshot.tempo),
+            format!("Active voices: {}", snapshot.active_voices),
+            format!("CPU load: {:.0}%", snapshot.cpu_load),
+        ]
+    };
+    let loaded_plugins = state
+        .plugins
+        .lock()
+        .map_err(|e| DawError::poisoned("plugins", e))?
+        .iter()
+        .map(|(id, managed_plugin)| {
+            managed_plugin
+                .host
+                .with_instance_wrapper(managed_plugin.instance_id, |wrapper| {
+                    format!("{} ({})", wrapper.plugin().descriptor().name, id)
+                })
+                .unwrap_or_else(|| id.clone())
+        })
+        .collect();
+    let recent_notifications = notifications
+        .lock()
+        .map_err(|e| DawError::poisoned("notifications", e))?
+        .iter()
+        .cloned()
+        .collect();
+
+    Ok(Diagnostics {
+        engine_status,
+        recent_notifications,
+        loaded_plugins,
+    })
+}
+
+/// Keep the rescue copy of the session and the crash diagnostics up to date
+///
+/// The session is copied once it differs from the state at launch, and again
+/// every time it changes.
+pub fn spawn_crash_rescue_pump<R: Runtime>(app: AppHandle<R>) {
+    let notifications = Arc::new(Mutex::new(VecDeque::new()));
+    {
+        let notifications = notifications.clone();
+        app.listen_any(NOTIFICATION_EVENT, move |event| {
+            let Ok(payload) = serde_json::from_str::<NotificationPayload>(event.payload()) else {
+                return;
+            };
+            if let Ok(mut notifications) = notifications.lock() {
+                notifications.push_back(format!("[{}/{}] {}", payload.level, payload.category, payload.message));
+                if notifications.len() > RECENT_NOTIFICATIONS {
+                    notifications.pop_front();
+                }
+            }
+        });
+    }
+
+    thread::Builder::new()
+        .name("crash-rescue".to_string())
+        .spawn(move || {
+            let state = app.state::<DawState>();
+            let mut last_fingerprint = build_project(&state).map(|project| project_fingerprint(&project)).ok();
+            loop {
+                thread::sleep(RESCUE_UPDATE_INTERVAL);
+
+                match diagnostics(&state, &notifications) {
+                    Ok(diagnostics) => crash::update_diagnostics(&diagnostics),
+                    Err(e) => eprintln!("Failed to collect crash diagnostics: {}", e),
+                }
+
+                let project = match build_project(&state) {
+                    Ok(project) => project,
+                    Err(e) => {
+                        eprintln!("Failed to build the rescue session: {}", e);
+                        continue;
+                    }
+                };
+                let fingerprint = project_fingerprint(&project);
+                if last_fingerprint.as_ref() == Some(&fingerprint) {
+                    continue;
+                }
+                match crash::update_session(&project, None) {
+                    Ok(()) => last_fingerprint = Some(fingerprint),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+        })
+        .expect("failed to spawn crash rescue thread");
+}
@@ -0,0 +1,155 @@
+// Return bus commands (shared send effects)
+//
+// Return buses run one reverb or delay fed by the sends of every track (see
+// `mymusic_daw::audio::returns`). The buses and the synth sends live in the
+// core state (replayed after an engine restart), the audio track sends in
+// `DawState::audio_tracks`. Buses and sends are addressed by bus slot.
+
+use serde::Serialize;
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::{lock_core, send_command_to_engine};
+use crate::error::{DawError, DawResult};
+use mymusic_daw::audio::returns::{ReturnBus, ReturnBusParams, ReturnEffect, TrackSend, TrackSends};
+use mymusic_daw::messaging::command::Command;
+
+/// Return bus sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct ReturnBusInfo {
+    pub slot: usize,
+    pub name: String,
+    pub params: ReturnBusParams,
+}
+
+/// Return buses and synth sends sent to the frontend (audio track sends are
+/// in `AudioTrackInfo`)
+#[derive(Debug, Clone, Serialize)]
+pub struct ReturnRouting {
+    pub buses: Vec<ReturnBusInfo>,
+    pub synth_sends: TrackSends,
+}
+
+fn bus_not_found(slot: usize) -> DawError {
+    DawError::NotFound(format!("Return bus not found: {}", slot))
+}
+
+/// Add a return bus in the first free slot, returns the bus
+#[tauri::command]
+pub fn add_return_bus(name: Option<String>, effect: ReturnEffect, state: State<DawState>) -> DawResult<ReturnBusInfo> {
+    let bus = ReturnBus {
+        name: name.unwrap_or_else(|| effect.name().to_string()),
+        params: ReturnBusParams::new(effect),
+    };
+    let mut core = lock_core(&state)?;
+    let slot = core.add_return_bus(bus)?;
+    let bus = core.return_buses[slot].clone().ok_or_else(|| bus_not_found(slot))?;
+    Ok(ReturnBusInfo {
+        slot,
+        name: bus.name,
+        params: bus.params,
+    })
+}
+
+/// Rename a return bus and/or change its effect, level and mute
+///
+/// Parameters are clamped to their ranges (the effect always runs fully wet).
+#[tauri::command]
+pub fn update_return_bus(
+    slot: usize,
+    name: Option<String>,
+    params: Option<ReturnBusParams>,
+    state: State<DawState>,
+) -> DawResult<ReturnBusInfo> {
+    let mut core = lock_core(&state)?;
+    let mut bus = core
+        .return_buses
+        .get(slot)
+        .cloned()
+        .flatten()
+        .ok_or_else(|| bus_not_found(slot))?;
+    if let Some(name) = name {
+        bus.name = name;
+    }
+    if let Some(params) = params {
+        bus.params = params;
+    }
+    core.set_return_bus(slot, Some(bus))?;
+    let bus = core.return_buses[slot].clone().ok_or_else(|| bus_not_found(slot))?;
+    Ok(ReturnBusInfo {
+        slot,
+        name: bus.name,
+        params: bus.params,
+    })
+}
+
+/// Remove a return bus (the sends of every track to it are turned off)
+#[tauri::command]
+pub fn remove_return_bus(slot: usize, state: State<DawState>) -> DawResult<()> {
+    {
+        let mut core = lock_core(&state)?;
+        if core.return_buses.get(slot).is_none_or(Option::is_none) {
+            return Err(bus_not_found(slot));
+        }
+        core.set_return_bus(slot, None)?;
+    }
+    let mut tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    tracks.clear_sends(slot);
+    send_command_to_engine(Command::SetAudioTracks(tracks.snapshot()), state.clone())
+}
+
+/// Current return buses and synth sends
+#[tauri::command]
+pub fn get_return_routing(state: State<DawState>) -> DawResult<ReturnRouting> {
+    let core = lock_core(&state)?;
+    let buses = core
+        .return_buses
+        .iter()
+        .enumerate()
+        .filter_map(|(slot, bus)| {
+            let bus = bus.as_ref()?;
+            Some(ReturnBusInfo {
+                slot,
+                name: bus.name.clone(),
+                params: bus.params,
+            })
+        })
+        .collect();
+    Ok(ReturnRouting {
+        buses,
+        synth_sends: core.synth_sends,
+    })
+}
+
+/// Set the send of a track to a return bus (level clamped to 0.0-1.0)
+///
+/// `audio_track_id` selects an audio track, the synth track when omitted (it
+/// has no fader: its pre and post-fader sends are the same).
+#[tauri::command]
+pub fn set_track_send(
+    audio_track_id: Option<u32>,
+    slot: usize,
+    send: TrackSend,
+    state: State<DawState>,
+) -> DawResult<TrackSend> {
+    let send = TrackSend {
+        level: send.level.clamp(0.0, 1.0),
+        ..send
+    };
+    let mut core = lock_core(&state)?;
+    if core.return_buses.get(slot).is_none_or(Option::is_none) {
+        return Err(bus_not_found(slot));
+    }
+    let Some(track_id) = audio_track_id else {
+        core.set_synth_send(slot, send)?;
+        return Ok(core.synth_sends[slot]);
+    };
+    drop(core);
+
+    let mut tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    let track = tracks
+        .track_mut(track_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio track not found: {}", track_id)))?;
+    track.sends[slot] = send;
+    send_command_to_engine(Command::SetAudioTracks(tracks.snapshot()), state.clone())?;
+    Ok(send)
+}
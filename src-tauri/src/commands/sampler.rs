@@ -0,0 +1,368 @@
+// Sampler commands (sample loading and waveform data)
+
+use tauri::State;
+use crate::{DawState, LoadedSample};
+use crate::commands::basic::send_command_to_engine;
+use crate::commands::pattern::{add_slice_pattern, PatternInfo};
+use crate::error::{DawError, DawResult};
+use serde::{Deserialize, Serialize};
+use mymusic_daw::audio::peaks::WaveformPeaks;
+use mymusic_daw::audio::stretch::StretchSettings;
+use mymusic_daw::audio::warp::estimate_loop_bpm;
+use mymusic_daw::messaging::command::Command;
+use mymusic_daw::sampler::slicer::DEFAULT_FIRST_NOTE;
+use mymusic_daw::sampler::{load_sample as load_sample_file, load_sfz, LoopSlices, Sample, SampleBank, SampleZone};
+use mymusic_daw::synth::voice_manager::MAX_SAMPLE_SLOTS;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Upper bound for peak resolution (keeps IPC payloads small)
+const MAX_PEAK_RESOLUTION: usize = 16384;
+
+/// Sample summary sent to the frontend
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SampleInfo {
+    pub id: usize,
+    pub name: String,
+    pub length_samples: usize,
+    pub sample_rate: u32,
+    pub duration_seconds: f64,
+}
+
+fn sample_to_info(id: usize, sample: &Sample) -> SampleInfo {
+    SampleInfo {
+        id,
+        name: sample.name.clone(),
+        length_samples: sample.len(),
+        sample_rate: sample.sample_rate,
+        duration_seconds: sample.len() as f64 / sample.sample_rate as f64,
+    }
+}
+
+/// Load an audio file (WAV/FLAC/MP3) and add it to the sampler
+#[tauri::command]
+pub fn load_sample(path: String, state: State<DawState>) -> DawResult<SampleInfo> {
+    println!("🎵 Loading sample: {}", path);
+
+    let sample = Arc::new(load_sample_file(Path::new(&path)).map_err(DawError::Io)?);
+
+    // Hold the lock while sending so indices stay in sync with the audio thread
+    let mut sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+    send_command_to_engine(Command::AddSample(sample.clone()), state.clone())?;
+
+    let id = sampler.samples.len();
+    let info = sample_to_info(id, &sample);
+    sampler.samples.push(LoadedSample {
+        sample,
+        path: PathBuf::from(path),
+    });
+
+    println!("✅ Sample loaded: {} (id {})", info.name, id);
+    Ok(info)
+}
+
+/// List loaded samples
+#[tauri::command]
+pub fn list_samples(state: State<DawState>) -> DawResult<Vec<SampleInfo>> {
+    let sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+    Ok(sampler
+        .samples
+        .iter()
+        .enumerate()
+        .map(|(id, loaded)| sample_to_info(id, &loaded.sample))
+        .collect())
+}
+
+/// Map a MIDI note to a loaded sample
+#[tauri::command]
+pub fn set_sample_note_mapping(note: u8, sample_id: usize, state: State<DawState>) -> DawResult<()> {
+    if note > 127 {
+        return Err(DawError::InvalidArgument(format!("Invalid note: {} (must be 0-127)", note)));
+    }
+
+    let mut sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+    if sample_id >= sampler.samples.len() {
+        return Err(DawError::NotFound(format!("Sample not found: {}", sample_id)));
+    }
+    send_command_to_engine(
+        Command::SetNoteSampleMapping {
+            note,
+            sample_index: sample_id,
+        },
+        state.clone(),
+    )?;
+    sampler.note_mappings.insert(note, sample_id);
+    Ok(())
+}
+
+/// Play a loaded sample across a range of notes, transposed from the zone's root note
+///
+/// `None` removes the zone. Notes mapped with `set_sample_note_mapping` win over zones.
+#[tauri::command]
+pub fn set_sample_zone(sample_id: usize, zone: Option<SampleZone>, state: State<DawState>) -> DawResult<()> {
+    if let Some(zone) = &zone {
+        zone.validate().map_err(DawError::InvalidArgument)?;
+    }
+
+    let mut sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+    if sample_id >= sampler.samples.len() {
+        return Err(DawError::NotFound(format!("Sample not found: {}", sample_id)));
+    }
+    send_command_to_engine(
+        Command::SetSampleZone {
+            sample_index: sample_id,
+            zone,
+        },
+        state.clone(),
+    )?;
+    match zone {
+        Some(zone) => sampler.zones.insert(sample_id, zone),
+        None => sampler.zones.remove(&sample_id),
+    };
+    Ok(())
+}
+
+/// Repitch a loaded sample and/or change its length (see `mymusic_daw::audio::stretch`)
+///
+/// The sample is rendered again from its original audio; loop points follow
+/// the new length. Returns the updated sample summary.
+#[tauri::command]
+pub fn set_sample_stretch(
+    sample_id: usize,
+    settings: StretchSettings,
+    state: State<DawState>,
+) -> DawResult<SampleInfo> {
+    settings.validate().map_err(DawError::InvalidArgument)?;
+
+    let mut sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+    let loaded = sampler
+        .samples
+        .get_mut(sample_id)
+        .ok_or_else(|| DawError::NotFound(format!("Sample not found: {}", sample_id)))?;
+    let mut sample = (*loaded.sample).clone();
+    sample.set_stretch(settings);
+    let sample = Arc::new(sample);
+    send_command_to_engine(Command::UpdateSample(sample_id, sample.clone()), state.clone())?;
+    loaded.sample = sample;
+    Ok(sample_to_info(sample_id, &loaded.sample))
+}
+
+/// Audition a loaded sample, bypassing the note mappings and zones
+///
+/// `note` transposes the sample from its root note; `None` plays it at its
+/// original pitch. A looped preview plays until `stop_sample_preview`.
+#[tauri::command]
+pub fn preview_sample(sample_id: usize, note: Option<u8>, looped: bool, state: State<DawState>) -> DawResult<()> {
+    if let Some(note) = note.filter(|note| *note > 127) {
+        return Err(DawError::InvalidArgument(format!("Invalid note: {} (must be 0-127)", note)));
+    }
+
+    let sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+    if sample_id >= sampler.samples.len() {
+        return Err(DawError::NotFound(format!("Sample not found: {}", sample_id)));
+    }
+    send_command_to_engine(
+        Command::PreviewSample {
+            sample_index: sample_id,
+            note,
+            looped,
+        },
+        state.clone(),
+    )
+}
+
+/// Release the sample preview
+#[tauri::command]
+pub fn stop_sample_preview(state: State<DawState>) -> DawResult<()> {
+    send_command_to_engine(Command::StopPreview, state)
+}
+
+/// Slices of a loop sent to the frontend
+#[derive(Debug, Serialize)]
+pub struct SliceInfo {
+    /// Added samples, one per slice, playing on consecutive notes from `first_note`
+    pub slices: Vec<SampleInfo>,
+    pub first_note: u8,
+    /// Pattern replaying the loop, when asked for
+    pub pattern: Option<PatternInfo>,
+}
+
+/// Cut a loaded loop at its transients into new samples (beat slicing)
+///
+/// Each slice gets a one-note zone from `first_note` (default C1), playing it at
+/// its original pitch. With `create_pattern`, a pattern replays the loop at the
+/// project tempo; `source_bpm` (default: guessed from the loop length) is the
+/// tempo the loop was played at.
+#[tauri::command]
+pub fn slice_sample(
+    sample_id: usize,
+    first_note: Option<u8>,
+    source_bpm: Option<f64>,
+    create_pattern: bool,
+    state: State<DawState>,
+) -> DawResult<SliceInfo> {
+    let first_note = first_note.unwrap_or(DEFAULT_FIRST_NOTE);
+    let (name, slices, source_bpm, infos) = {
+        let mut sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+        let loaded = sampler
+            .samples
+            .get(sample_id)
+            .ok_or_else(|| DawError::NotFound(format!("Sample not found: {}", sample_id)))?;
+        let (sample, path) = (loaded.sample.clone(), loaded.path.clone());
+        let slices = LoopSlices::detect(&sample);
+        let samples = slices.slice(&sample, first_note).map_err(DawError::InvalidArgument)?;
+        if sampler.samples.len() + samples.len() > MAX_SAMPLE_SLOTS {
+            return Err(DawError::InvalidArgument(format!(
+                "{} slices do not fit in the {} free sample slots",
+                samples.len(),
+                MAX_SAMPLE_SLOTS - sampler.samples.len()
+            )));
+        }
+        let source_bpm = source_bpm.unwrap_or_else(|| estimate_loop_bpm(sample.len() as u64, sample.sample_rate as f32));
+        if source_bpm <= 0.0 {
+            return Err(DawError::InvalidArgument(format!("Invalid source tempo: {} BPM", source_bpm)));
+        }
+
+        // Hold the lock while sending so indices stay in sync with the audio thread
+        let mut infos = Vec::new();
+        for (slice, zone) in samples {
+            let id = sampler.samples.len();
+            let slice = Arc::new(slice);
+            send_command_to_engine(Command::AddSample(slice.clone()), state.clone())?;
+            infos.push(sample_to_info(id, &slice));
+            sampler.samples.push(LoadedSample {
+                sample: slice,
+                path: path.clone(),
+            });
+            send_command_to_engine(
+                Command::SetSampleZone {
+                    sample_index: id,
+                    zone: Some(zone),
+                },
+                state.clone(),
+            )?;
+            sampler.zones.insert(id, zone);
+        }
+        (format!("{} Slices", sample.name), slices, source_bpm, infos)
+    };
+
+    let pattern = if create_pattern {
+        Some(add_slice_pattern(name, &slices, first_note, source_bpm, &state)?)
+    } else {
+        None
+    };
+    Ok(SliceInfo {
+        slices: infos,
+        first_note,
+        pattern,
+    })
+}
+
+/// Replace every loaded sample (e.g. when a sample bank is loaded)
+///
+/// Each sample comes with the MIDI note it is mapped to, or with its zone.
+pub(crate) fn replace_samples(
+    samples: Vec<(u8, Option<SampleZone>, LoadedSample)>,
+    state: &State<DawState>,
+) -> DawResult<()> {
+    let mut sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+
+    // Removing from the end keeps the remaining indices valid on the audio thread
+    for index in (0..sampler.samples.len()).rev() {
+        send_command_to_engine(Command::RemoveSample(index), state.clone())?;
+    }
+    sampler.samples.clear();
+    sampler.note_mappings.clear();
+    sampler.zones.clear();
+
+    for (note, zone, loaded) in samples {
+        let sample_index = sampler.samples.len();
+        send_command_to_engine(Command::AddSample(loaded.sample.clone()), state.clone())?;
+        sampler.samples.push(loaded);
+        if let Some(zone) = zone {
+            send_command_to_engine(
+                Command::SetSampleZone {
+                    sample_index,
+                    zone: Some(zone),
+                },
+                state.clone(),
+            )?;
+            sampler.zones.insert(sample_index, zone);
+        } else {
+            send_command_to_engine(Command::SetNoteSampleMapping { note, sample_index }, state.clone())?;
+            sampler.note_mappings.insert(note, sample_index);
+        }
+    }
+    Ok(())
+}
+
+/// Load the samples of a bank in place of the loaded samples
+///
+/// Relative sample paths are resolved from `base_dir`. Every file is loaded
+/// before touching the sampler, so a missing file keeps the current samples.
+pub(crate) fn load_bank_samples(bank: &SampleBank, base_dir: &Path, state: &State<DawState>) -> DawResult<()> {
+    let mut samples = Vec::new();
+    for mapping in bank.get_sorted_mappings() {
+        let path = mapping.resolve_path(base_dir);
+        let mut sample = load_sample_file(&path).map_err(DawError::Io)?;
+        mapping.apply_to(&mut sample);
+        samples.push((
+            mapping.note,
+            mapping.zone,
+            LoadedSample {
+                sample: Arc::new(sample),
+                path,
+            },
+        ));
+    }
+    replace_samples(samples, state)
+}
+
+/// Result of an SFZ import
+#[derive(Debug, Serialize)]
+pub struct SfzImportInfo {
+    /// Samples now loaded (one per region)
+    pub samples: Vec<SampleInfo>,
+    /// Opcodes and regions that could not be imported
+    pub warnings: Vec<String>,
+}
+
+/// Replace the loaded samples with the regions of an SFZ instrument
+///
+/// Each region becomes a sample with its key range, velocity layer and root note
+/// (see `sampler::loader::parse_sfz`).
+#[tauri::command]
+pub fn import_sfz(path: String, state: State<DawState>) -> DawResult<SfzImportInfo> {
+    let path = PathBuf::from(path);
+    let import = load_sfz(&path).map_err(DawError::Io)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    load_bank_samples(&import.bank, base_dir, &state)?;
+
+    let samples = list_samples(state)?;
+    println!("✅ SFZ imported: {} regions from {}", samples.len(), path.display());
+    Ok(SfzImportInfo {
+        samples,
+        warnings: import.warnings,
+    })
+}
+
+/// Get downsampled min/max peaks of a loaded sample for waveform drawing
+///
+/// `resolution` is the number of bins (typically the waveform width in pixels).
+#[tauri::command]
+pub fn get_sample_peaks(sample_id: usize, resolution: usize, state: State<DawState>) -> DawResult<WaveformPeaks> {
+    if resolution == 0 {
+        return Err(DawError::InvalidArgument("Resolution must be greater than 0".to_string()));
+    }
+
+    let sample = {
+        let sampler = state.sampler.lock().map_err(|e| DawError::poisoned("sampler", e))?;
+        sampler
+            .samples
+            .get(sample_id)
+            .map(|loaded| loaded.sample.clone())
+            .ok_or_else(|| DawError::NotFound(format!("Sample not found: {}", sample_id)))?
+    };
+
+    Ok(sample.peaks(resolution.min(MAX_PEAK_RESOLUTION)))
+}
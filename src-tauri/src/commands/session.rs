@@ -0,0 +1,149 @@
+// Session view commands (clip launcher)
+//
+// The clip grid lives in the shared core state, which sends it to the engine.
+// The web frontend has no instrument tracks: its slots are on the synth track
+// and hold patterns of the pattern store. Launches are timed from the engine
+// snapshot, so they land on the boundary the engine is about to play.
+
+use serde::Serialize;
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::lock_core;
+use crate::error::{DawError, DawResult};
+use mymusic_daw::audio::snapshot::EngineSnapshot;
+use mymusic_daw::sequencer::arrangement::SYNTH_TRACK;
+use mymusic_daw::sequencer::{ClipId, LaunchQuantization, PatternId, Session};
+
+/// Slot summary (for the grid)
+#[derive(Debug, Serialize)]
+pub struct SessionSlotInfo {
+    pub id: ClipId,
+    pub scene: u32,
+    pub pattern_id: PatternId,
+    pub pattern_name: String,
+}
+
+/// Clip grid and what it plays
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    pub quantization: LaunchQuantization,
+    /// Rows of the grid
+    pub scene_count: u32,
+    pub slots: Vec<SessionSlotInfo>,
+    /// Whether the synth track plays the session (instead of its pattern or the song)
+    pub active: bool,
+    /// Scene playing
+    pub playing_scene: Option<u32>,
+    /// Launch waiting for its boundary
+    pub queued: bool,
+    /// Scene of the queued launch (None: the track stops)
+    pub queued_scene: Option<u32>,
+}
+
+fn engine_snapshot(state: &DawState) -> DawResult<EngineSnapshot> {
+    Ok(state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?.snapshot())
+}
+
+/// Apply an edit to a copy of the grid, then send it
+fn edit_session(state: &DawState, edit: impl FnOnce(&mut Session)) -> DawResult<()> {
+    let mut core = lock_core(state)?;
+    let mut session = core.session.clone();
+    edit(&mut session);
+    core.set_session(session)?;
+    Ok(())
+}
+
+/// Get the slots of the grid and the launch state of the synth track
+#[tauri::command]
+pub fn get_session(state: State<DawState>) -> DawResult<SessionInfo> {
+    let snapshot = engine_snapshot(&state)?;
+    let mut core = lock_core(&state)?;
+    let launch = core
+        .session_launches(snapshot.position_samples)
+        .iter()
+        .find(|launch| launch.track == SYNTH_TRACK)
+        .copied();
+    let session = &core.session;
+    Ok(SessionInfo {
+        quantization: session.quantization,
+        scene_count: session.scene_count(),
+        slots: session
+            .track_slots(SYNTH_TRACK)
+            .iter()
+            .map(|slot| SessionSlotInfo {
+                id: slot.id,
+                scene: slot.scene,
+                pattern_id: slot.pattern.id,
+                pattern_name: slot.pattern.name.clone(),
+            })
+            .collect(),
+        active: launch.is_some(),
+        playing_scene: launch.and_then(|launch| launch.playing).map(|(scene, _)| scene),
+        queued: launch.is_some_and(|launch| launch.queued.is_some()),
+        queued_scene: launch.and_then(|launch| launch.queued).and_then(|(scene, _)| scene),
+    })
+}
+
+/// Put a pattern in a slot (`pattern_id` None empties it)
+#[tauri::command]
+pub fn set_session_slot(
+    scene: u32,
+    pattern_id: Option<PatternId>,
+    state: State<DawState>,
+) -> DawResult<()> {
+    let Some(pattern_id) = pattern_id else {
+        if lock_core(&state)?.session.get(SYNTH_TRACK, scene).is_none() {
+            return Err(DawError::NotFound(format!("No clip in scene {}", scene + 1)));
+        }
+        return edit_session(&state, |session| {
+            session.remove_slot(SYNTH_TRACK, scene);
+        });
+    };
+    let pattern = {
+        let store = state.patterns.lock().map_err(|e| DawError::poisoned("patterns", e))?;
+        store
+            .patterns
+            .get(&pattern_id)
+            .cloned()
+            .ok_or_else(|| DawError::NotFound(format!("Pattern not found: {}", pattern_id)))?
+    };
+    edit_session(&state, |session| {
+        session.set_slot(SYNTH_TRACK, scene, pattern);
+    })
+}
+
+/// Launch the slot of a scene at the next launch boundary
+#[tauri::command]
+pub fn launch_session_slot(scene: u32, state: State<DawState>) -> DawResult<()> {
+    let snapshot = engine_snapshot(&state)?;
+    let mut core = lock_core(&state)?;
+    if core.session.get(SYNTH_TRACK, scene).is_none() {
+        return Err(DawError::NotFound(format!("No clip in scene {}", scene + 1)));
+    }
+    core.launch_clip(SYNTH_TRACK, Some(scene), &snapshot)?;
+    Ok(())
+}
+
+/// Stop the clip playing at the next launch boundary
+#[tauri::command]
+pub fn stop_session(state: State<DawState>) -> DawResult<()> {
+    let snapshot = engine_snapshot(&state)?;
+    lock_core(&state)?.stop_session(&snapshot)?;
+    Ok(())
+}
+
+/// Play the active pattern (or the song clips) again
+#[tauri::command]
+pub fn release_session(state: State<DawState>) -> DawResult<()> {
+    lock_core(&state)?.release_session()?;
+    Ok(())
+}
+
+/// Set the boundary the launches wait for
+#[tauri::command]
+pub fn set_launch_quantization(
+    quantization: LaunchQuantization,
+    state: State<DawState>,
+) -> DawResult<()> {
+    edit_session(&state, |session| session.quantization = quantization)
+}
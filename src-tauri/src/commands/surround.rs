@@ -0,0 +1,65 @@
+// Surround commands (speaker layout and track panners)
+//
+// The project speaker layout (stereo, quad, 5.1) sets the channels of the mix;
+// tracks with a surround panner are placed between the speakers (see
+// `mymusic_daw::audio::surround`). The layout and the synth panner live in the
+// core state (replayed after an engine restart), the audio track panners in
+// `DawState::audio_tracks`. A layout the device cannot play folds down to
+// stereo: open more channels with the `audio.output_channels` preference.
+
+use serde::Serialize;
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::{lock_core, send_command_to_engine};
+use crate::error::{DawError, DawResult};
+use mymusic_daw::audio::surround::{SpeakerLayout, SurroundPan};
+use mymusic_daw::messaging::command::Command;
+
+/// Surround routing sent to the frontend (audio track panners are in
+/// `AudioTrackInfo`)
+#[derive(Debug, Clone, Serialize)]
+pub struct SurroundRouting {
+    pub layout: SpeakerLayout,
+    /// Layout the running device plays (stereo when it lacks channels)
+    pub playing_layout: SpeakerLayout,
+    pub synth_surround: Option<SurroundPan>,
+}
+
+/// Set the speaker layout of the mix
+#[tauri::command]
+pub fn set_speaker_layout(layout: SpeakerLayout, state: State<DawState>) -> DawResult<()> {
+    Ok(lock_core(&state)?.set_speaker_layout(layout)?)
+}
+
+/// Set the surround panner of a track (None: the stereo pan)
+///
+/// `audio_track_id` selects an audio track, the synth track when omitted (it is
+/// panned as mono).
+#[tauri::command]
+pub fn set_track_surround(
+    audio_track_id: Option<u32>,
+    surround: Option<SurroundPan>,
+    state: State<DawState>,
+) -> DawResult<()> {
+    let Some(track_id) = audio_track_id else {
+        return Ok(lock_core(&state)?.set_synth_surround(surround)?);
+    };
+    let mut tracks = state.audio_tracks.lock().map_err(|e| DawError::poisoned("audio tracks", e))?;
+    let track = tracks
+        .track_mut(track_id)
+        .ok_or_else(|| DawError::NotFound(format!("Audio track not found: {}", track_id)))?;
+    track.surround = surround;
+    send_command_to_engine(Command::SetAudioTracks(tracks.snapshot()), state.clone())
+}
+
+/// Current speaker layout and synth panner
+#[tauri::command]
+pub fn get_surround_routing(state: State<DawState>) -> DawResult<SurroundRouting> {
+    let channels = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?.snapshot().output_channels;
+    let core = lock_core(&state)?;
+    Ok(SurroundRouting {
+        layout: core.speaker_layout,
+        playing_layout: core.speaker_layout.on_device(channels),
+        synth_surround: core.synth_surround,
+    })
+}
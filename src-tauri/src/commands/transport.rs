@@ -0,0 +1,41 @@
+// Transport commands (tempo, time signature, metronome)
+//
+// Validation and mirroring happen in the shared core state (the engine asserts
+// on invalid tempo / time signature values). Changing the tempo or the time
+// signature also re-times the stored patterns, which are edited in beats.
+
+use tauri::State;
+use crate::DawState;
+use crate::commands::basic::lock_core;
+use crate::commands::pattern::{retime_patterns, TimingContext};
+use crate::error::DawResult;
+
+/// Enable/disable the metronome
+#[tauri::command]
+pub fn set_metronome_enabled(enabled: bool, state: State<DawState>) -> DawResult<()> {
+    Ok(lock_core(&state)?.set_metronome_enabled(enabled)?)
+}
+
+/// Set the metronome volume (0.0 to 1.0)
+#[tauri::command]
+pub fn set_metronome_volume(volume: f32, state: State<DawState>) -> DawResult<()> {
+    Ok(lock_core(&state)?.set_metronome_volume(volume)?)
+}
+
+/// Set the transport tempo (BPM)
+///
+/// Pattern notes keep their position in beats.
+#[tauri::command]
+pub fn set_tempo(bpm: f64, state: State<DawState>) -> DawResult<()> {
+    let old_timing = TimingContext::from_state(&state)?;
+    lock_core(&state)?.set_tempo(bpm)?;
+    retime_patterns(&old_timing, &state)
+}
+
+/// Set the transport time signature (e.g. 3/4, 6/8)
+#[tauri::command]
+pub fn set_time_signature(numerator: u8, denominator: u8, state: State<DawState>) -> DawResult<()> {
+    let old_timing = TimingContext::from_state(&state)?;
+    lock_core(&state)?.set_time_signature(numerator, denominator)?;
+    retime_patterns(&old_timing, &state)
+}
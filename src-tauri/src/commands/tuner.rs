@@ -0,0 +1,81 @@
+// Tuner commands
+//
+// The tuner listens to the master output tap of the running engine between
+// `start_tuner` and `stop_tuner` (the tap is only fed while listened to). The
+// frontend polls `get_tuner_reading`, which analyses the audio received since
+// the previous call.
+
+use serde::Serialize;
+use tauri::State;
+use crate::DawState;
+use crate::error::{DawError, DawResult};
+use mymusic_daw::audio::tuner::{TunerReading, REFERENCE_RANGE};
+
+/// Detected note sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct TunerReadingPayload {
+    pub frequency: f32,
+    pub note: u8,
+    /// Note name with its octave (e.g. "A4")
+    pub note_name: String,
+    /// Deviation from the note (-50 to +50 cents)
+    pub cents: f32,
+    pub in_tune: bool,
+}
+
+impl From<TunerReading> for TunerReadingPayload {
+    fn from(reading: TunerReading) -> Self {
+        Self {
+            frequency: reading.frequency,
+            note: reading.note,
+            note_name: reading.note_name(),
+            cents: reading.cents,
+            in_tune: reading.in_tune(),
+        }
+    }
+}
+
+/// Start the tuner, optionally with a new reference pitch for A4 (Hz)
+#[tauri::command]
+pub fn start_tuner(reference: Option<f32>, state: State<DawState>) -> DawResult<()> {
+    if let Some(reference) = reference {
+        if !REFERENCE_RANGE.contains(&reference) {
+            return Err(DawError::InvalidArgument(format!(
+                "Reference pitch must be between {} and {} Hz, got {}",
+                REFERENCE_RANGE.start(),
+                REFERENCE_RANGE.end(),
+                reference
+            )));
+        }
+    }
+    let tap = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?.master_tap();
+    let mut tuner = state.tuner.lock().map_err(|e| DawError::poisoned("tuner", e))?;
+    if let Some(reference) = reference {
+        tuner.reference = reference;
+    }
+    tuner.attach(&tap);
+    Ok(())
+}
+
+/// Stop the tuner (the master output is no longer tapped)
+#[tauri::command]
+pub fn stop_tuner(state: State<DawState>) -> DawResult<()> {
+    state.tuner.lock().map_err(|e| DawError::poisoned("tuner", e))?.detach();
+    Ok(())
+}
+
+/// Latest tuner reading (None while stopped, silent or unpitched)
+#[tauri::command]
+pub fn get_tuner_reading(state: State<DawState>) -> DawResult<Option<TunerReadingPayload>> {
+    let (tap, sample_rate) = {
+        let engine = state.engine.lock().map_err(|e| DawError::poisoned("engine", e))?;
+        (engine.master_tap(), engine.sample_rate())
+    };
+    let mut tuner = state.tuner.lock().map_err(|e| DawError::poisoned("tuner", e))?;
+    if !tuner.is_attached() {
+        return Ok(None);
+    }
+    // Follow the engine across restarts (each engine has its own tap)
+    tuner.attach(&tap);
+    Ok(tuner.update(sample_rate).map(TunerReadingPayload::from))
+}
@@ -0,0 +1,440 @@
+// WebSocket API (remote UIs and integration tests)
+//
+// Exposes the bridge command set over a WebSocket (message format in
+// `mymusic_daw::remote::websocket`). Commands take the same arguments as
+// `invoke()` from the frontend (camelCase names) and return the same values
+// and typed errors. Events emitted to the frontend are forwarded to every
+// client, and `engine-snapshot` events stream the engine telemetry.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Listener, Manager, Runtime, State};
+use crate::DawState;
+use crate::commands::arrangement::*;
+use crate::commands::audio_track::*;
+use crate::commands::automation::*;
+use crate::commands::basic::*;
+use crate::commands::dynamics::*;
+use crate::commands::export::*;
+use crate::commands::freeze::*;
+use crate::commands::inserts::*;
+use crate::commands::master::*;
+use crate::commands::midi_learn::*;
+use crate::commands::pattern::*;
+use crate::commands::plugin::{
+    get_loaded_plugins, get_plugin_parameter_value, get_plugin_parameters, load_plugin_instance,
+    set_plugin_parameter_value, unload_plugin_instance,
+};
+use crate::commands::preferences::*;
+use crate::commands::preset::*;
+use crate::commands::remote::*;
+use crate::commands::outputs::*;
+use crate::commands::rescue::*;
+use crate::commands::returns::*;
+use crate::commands::sampler::*;
+use crate::commands::session::*;
+use crate::commands::surround::*;
+use crate::commands::transport::*;
+use crate::commands::tuner::*;
+use crate::error::{DawError, DawResult, BRIDGE_API_VERSION};
+use crate::events::{ENGINE_FEEDBACK_EVENT, NOTIFICATION_EVENT};
+use mymusic_daw::audio::clip_player::{SidechainSource, TrackDynamics};
+use mymusic_daw::audio::inserts::{InsertChainParams, InsertEffect};
+use mymusic_daw::audio::outputs::TrackOutput;
+use mymusic_daw::audio::returns::{ReturnBusParams, ReturnEffect, TrackSend};
+use mymusic_daw::audio::stretch::StretchSettings;
+use mymusic_daw::audio::surround::{SpeakerLayout, SurroundPan};
+use mymusic_daw::automation::{AutomationParameter, AutomationPoint};
+use mymusic_daw::command::PatchSlot;
+use mymusic_daw::config::Preferences;
+use mymusic_daw::midi::cc_map::CcParameter;
+use mymusic_daw::project::PresetKind;
+use mymusic_daw::remote::{CommandHandler, WebSocketServer};
+use mymusic_daw::sampler::SampleZone;
+use mymusic_daw::sequencer::{ClipId, LaunchQuantization, NoteId, PatternGroove, PatternId, QuantizeSettings};
+use mymusic_daw::synth::equalizer::EqParams;
+
+/// Streamed engine telemetry (same payload as `get_engine_snapshot`)
+pub const ENGINE_SNAPSHOT_EVENT: &str = "engine-snapshot";
+
+/// Interval between two telemetry events
+const TELEMETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Frontend events forwarded to WebSocket clients
+const FORWARDED_EVENTS: [&str; 3] = [NOTIFICATION_EVENT, ENGINE_FEEDBACK_EVENT, REMOTE_CONTROL_EVENT];
+
+/// WebSocket server status
+#[derive(Debug, Serialize)]
+pub struct WebSocketStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub clients: usize,
+}
+
+fn websocket_status(state: &DawState) -> DawResult<WebSocketStatus> {
+    let server = state.websocket.lock().map_err(|e| DawError::poisoned("WebSocket server", e))?;
+    Ok(WebSocketStatus {
+        running: server.is_some(),
+        port: server.as_ref().map(|server| server.local_addr().port()),
+        clients: server.as_ref().map_or(0, WebSocketServer::client_count),
+    })
+}
+
+/// Start the WebSocket server on a TCP port (restarts it if already running)
+///
+/// Only local clients are accepted unless `allow_remote` is set: the API can
+/// load files and drive the whole DAW.
+pub(crate) fn start_websocket<R: Runtime>(app: &AppHandle<R>, port: u16, allow_remote: bool) -> DawResult<()> {
+    let host = if allow_remote { "0.0.0.0" } else { "127.0.0.1" };
+    let handler: CommandHandler = {
+        let app = app.clone();
+        Arc::new(move |command, args| dispatch(&app, command, args))
+    };
+
+    let state = app.state::<DawState>();
+    let mut server = state.websocket.lock().map_err(|e| DawError::poisoned("WebSocket server", e))?;
+    // Release the port before binding it again
+    *server = None;
+    *server = Some(WebSocketServer::bind((host, port), handler).map_err(DawError::Io)?);
+    Ok(())
+}
+
+/// Start the WebSocket API server
+#[tauri::command]
+pub fn start_websocket_server<R: Runtime>(
+    port: u16,
+    allow_remote: Option<bool>,
+    app: AppHandle<R>,
+    state: State<DawState>,
+) -> DawResult<WebSocketStatus> {
+    start_websocket(&app, port, allow_remote.unwrap_or(false))?;
+    websocket_status(&state)
+}
+
+/// Stop the WebSocket API server (closes every connection)
+#[tauri::command]
+pub fn stop_websocket_server(state: State<DawState>) -> DawResult<()> {
+    *state.websocket.lock().map_err(|e| DawError::poisoned("WebSocket server", e))? = None;
+    Ok(())
+}
+
+/// Get the WebSocket API server status
+#[tauri::command]
+pub fn get_websocket_status(state: State<DawState>) -> DawResult<WebSocketStatus> {
+    websocket_status(&state)
+}
+
+fn parse_args<T: DeserializeOwned>(args: Value) -> Result<T, Value> {
+    serde_json::from_value(args).map_err(|e| error_value(DawError::InvalidArgument(format!("Invalid arguments: {}", e))))
+}
+
+fn reply<T: Serialize>(result: DawResult<T>) -> Result<Value, Value> {
+    match result {
+        Ok(value) => serde_json::to_value(value).map_err(|e| error_value(DawError::Io(e.to_string()))),
+        Err(e) => Err(error_value(e)),
+    }
+}
+
+fn error_value(error: DawError) -> Value {
+    serde_json::to_value(error).unwrap_or(Value::Null)
+}
+
+/// Generate the command dispatcher
+///
+/// Each entry lists the command arguments (without the `State`); they are
+/// deserialized from the request `args` with the same names as `invoke()`.
+macro_rules! dispatch_commands {
+    ($app:expr, $command:expr, $args:expr; $($name:ident($($arg:ident: $ty:ty),*)),* $(,)?) => {
+        match $command {
+            $(stringify!($name) => {
+                #[derive(Deserialize)]
+                #[serde(rename_all = "camelCase")]
+                struct Args {
+                    $($arg: $ty),*
+                }
+                let Args { $($arg),* } = parse_args($args)?;
+                reply($name($($arg,)* $app.state::<DawState>()))
+            })*
+            "get_bridge_api_version" => Ok(Value::from(BRIDGE_API_VERSION)),
+            "set_realtime_priority" => {
+                #[derive(Deserialize)]
+                struct Args {
+                    enabled: bool,
+                }
+                let Args { enabled } = parse_args($args)?;
+                set_realtime_priority(enabled);
+                Ok(Value::Null)
+            }
+            _ => Err(error_value(DawError::NotFound(format!("Unknown command: {}", $command)))),
+        }
+    };
+}
+
+/// Run a bridge command from a WebSocket request
+///
+/// Plugin GUI, window and MIDI mapping commands need the desktop frontend and
+/// are not available.
+fn dispatch<R: Runtime>(app: &AppHandle<R>, command: &str, args: Value) -> Result<Value, Value> {
+    dispatch_commands!(app, command, args;
+        // Audio engine
+        set_volume(volume: f32),
+        play_note(note: u8, velocity: u8),
+        stop_note(note: u8),
+        get_volume(),
+        get_engine_status(),
+        get_engine_snapshot(),
+        get_engine_info(),
+        shutdown_engine(),
+        restart_engine(),
+        set_max_voices(max_voices: usize),
+        undo(),
+        redo(),
+        get_history(),
+        jump_to_history(index: usize),
+        // Synthesizer
+        set_waveform(waveform: String),
+        set_oscillator2(waveform: String, coarse: i32, fine: f32, mix: f32),
+        set_unison(voices: usize, detune: f32, spread: f32),
+        set_anti_aliasing(method: String),
+        set_cross_mod(fm: f32, ring: f32),
+        set_noise(noise_type: String, level: f32),
+        set_adsr(attack: f32, decay: f32, sustain: f32, release: f32),
+        set_mod_envelope(attack: f32, decay: f32, sustain: f32, release: f32),
+        set_lfo(
+            waveform: String,
+            rate: f32,
+            depth: f32,
+            destination: String,
+            sync: Option<String>,
+            mode: Option<String>,
+            start_phase: Option<f32>
+        ),
+        set_mod_lfo(
+            lfo: u8,
+            waveform: String,
+            rate: f32,
+            depth: f32,
+            sync: Option<String>,
+            mode: Option<String>,
+            start_phase: Option<f32>
+        ),
+        set_filter(filter_type: String, cutoff: f32, resonance: f32),
+        set_filter_mod(envelope_amount: f32, key_tracking: f32, drive: f32),
+        set_poly_mode(mode: String),
+        set_portamento(time: f32),
+        set_pitch_bend_range(semitones: f32),
+        set_voice_mode(mode: String),
+        set_mod_routing(index: u8, source: String, destination: String, amount: f32),
+        clear_mod_routing(index: u8),
+        get_patch_slots(),
+        switch_patch_slot(),
+        copy_patch_slot(from: PatchSlot, to: PatchSlot),
+        // Master effects
+        get_master_effects(),
+        set_master_reverb(room_size: f32, damping: f32, mix: f32),
+        set_master_delay(time_ms: f32, feedback: f32, mix: f32),
+        set_master_eq(eq: EqParams),
+        set_master_compressor(threshold_db: f32, ratio: f32, attack_ms: f32, release_ms: f32, makeup_db: f32),
+        set_master_limiter(threshold_db: f32, release_ms: f32),
+        set_master_effect_order(order: Vec<String>),
+        set_master_effect_bypass(effect: String, bypassed: bool),
+        // Transport
+        set_tempo(bpm: f64),
+        set_time_signature(numerator: u8, denominator: u8),
+        set_metronome_enabled(enabled: bool),
+        set_metronome_volume(volume: f32),
+        // Automation
+        get_automation_lane(parameter: AutomationParameter),
+        set_automation_points(parameter: AutomationParameter, points: Vec<AutomationPoint>),
+        clear_lane(parameter: AutomationParameter),
+        // MIDI learn
+        get_cc_mappings(),
+        arm_cc_learn(parameter: CcParameter),
+        cancel_cc_learn(),
+        remove_cc_mapping(parameter: CcParameter),
+        send_control_change(cc: u8, value: u8),
+        // Patterns
+        create_pattern(name: String, length_bars: Option<u32>),
+        list_patterns(),
+        get_pattern(pattern_id: PatternId),
+        set_active_pattern(pattern_id: PatternId),
+        add_note(pattern_id: PatternId, pitch: u8, start_beats: f64, duration_beats: f64, velocity: Option<u8>),
+        update_note(
+            pattern_id: PatternId,
+            note_id: NoteId,
+            pitch: Option<u8>,
+            start_beats: Option<f64>,
+            duration_beats: Option<f64>,
+            velocity: Option<u8>,
+            probability: Option<f32>,
+            offset_ticks: Option<i32>
+        ),
+        delete_notes(pattern_id: PatternId, note_ids: Vec<NoteId>),
+        set_pattern_groove(pattern_id: PatternId, groove: PatternGroove),
+        quantize_pattern_notes(pattern_id: PatternId, note_ids: Vec<NoteId>, settings: QuantizeSettings),
+        detect_pattern_key(pattern_id: PatternId),
+        // Arrangement
+        get_arrangement(),
+        add_pattern_clip(pattern_id: PatternId, start_bar: u32, length_bars: Option<u32>),
+        move_pattern_clip(clip_id: ClipId, start_bar: u32, length_bars: Option<u32>),
+        remove_pattern_clip(clip_id: ClipId),
+        set_song_mode(enabled: bool),
+        get_session(),
+        set_session_slot(scene: u32, pattern_id: Option<PatternId>),
+        launch_session_slot(scene: u32),
+        stop_session(),
+        release_session(),
+        set_launch_quantization(quantization: LaunchQuantization),
+        // Sampler
+        load_sample(path: String),
+        list_samples(),
+        get_sample_peaks(sample_id: usize, resolution: usize),
+        set_sample_note_mapping(note: u8, sample_id: usize),
+        set_sample_zone(sample_id: usize, zone: Option<SampleZone>),
+        set_sample_stretch(sample_id: usize, settings: StretchSettings),
+        preview_sample(sample_id: usize, note: Option<u8>, looped: bool),
+        stop_sample_preview(),
+        slice_sample(sample_id: usize, first_note: Option<u8>, source_bpm: Option<f64>, create_pattern: bool),
+        import_sfz(path: String),
+        // Audio tracks
+        list_audio_tracks(),
+        add_audio_track(name: Option<String>),
+        remove_audio_track(track_id: u32),
+        update_audio_track(
+            track_id: u32,
+            gain: Option<f32>,
+            pan: Option<f32>,
+            muted: Option<bool>,
+            soloed: Option<bool>
+        ),
+        import_audio_clip(track_id: u32, path: String, start_seconds: Option<f64>),
+        update_audio_clip(
+            clip_id: u32,
+            start_seconds: Option<f64>,
+            offset_seconds: Option<f64>,
+            length_seconds: Option<f64>,
+            gain: Option<f32>,
+            fade_in_seconds: Option<f64>,
+            fade_out_seconds: Option<f64>,
+            pitch: Option<f32>
+        ),
+        remove_audio_clip(clip_id: u32),
+        set_audio_clip_warp(clip_id: u32, enabled: bool, source_bpm: Option<f64>),
+        set_warp_markers(clip_id: u32, index: Option<usize>, beat: Option<f64>, grid_beats: Option<f64>),
+        // Track freeze
+        freeze_synth_track(),
+        unfreeze_synth_track(),
+        get_freeze_status(),
+        // Offline export
+        render_to_wav(path: String, start_bar: Option<u32>, end_bar: Option<u32>, bit_depth: Option<u16>),
+        // Track dynamics and sidechain routing
+        set_track_dynamics(audio_track_id: Option<u32>, dynamics: Option<TrackDynamics>),
+        set_plugin_sidechain(source: Option<SidechainSource>),
+        get_sidechain_routing(),
+        // Track insert chains
+        get_track_inserts(audio_track_id: Option<u32>),
+        set_track_inserts(audio_track_id: Option<u32>, inserts: InsertChainParams),
+        set_track_insert_order(audio_track_id: Option<u32>, order: Vec<InsertEffect>),
+        set_track_insert_bypass(audio_track_id: Option<u32>, effect: InsertEffect, bypassed: bool),
+        // Return buses
+        add_return_bus(name: Option<String>, effect: ReturnEffect),
+        update_return_bus(slot: usize, name: Option<String>, params: Option<ReturnBusParams>),
+        remove_return_bus(slot: usize),
+        get_return_routing(),
+        set_track_send(audio_track_id: Option<u32>, slot: usize, send: TrackSend),
+        // Output routing
+        set_track_output(audio_track_id: Option<u32>, output: TrackOutput),
+        set_metronome_output(output: TrackOutput),
+        get_output_routing(),
+        // Surround
+        set_speaker_layout(layout: SpeakerLayout),
+        set_track_surround(audio_track_id: Option<u32>, surround: Option<SurroundPan>),
+        get_surround_routing(),
+        // Preferences
+        get_preferences(),
+        set_preferences(preferences: Preferences),
+        // Crash rescue
+        get_rescue_session(),
+        restore_rescue_session(),
+        discard_rescue_session(),
+        // Tuner
+        start_tuner(reference: Option<f32>),
+        stop_tuner(),
+        get_tuner_reading(),
+        // Presets
+        list_presets(kind: PresetKind),
+        save_preset(kind: PresetKind, name: String),
+        load_preset(kind: PresetKind, name: String),
+        delete_preset(kind: PresetKind, name: String),
+        browse_synth_presets(),
+        // Plugins
+        load_plugin_instance(plugin_path: String, plugin_id: Option<String>),
+        get_plugin_parameters(plugin_id: String),
+        get_plugin_parameter_value(plugin_id: String, parameter_id: String),
+        set_plugin_parameter_value(plugin_id: String, parameter_id: String, value: f64),
+        unload_plugin_instance(plugin_id: String),
+        get_loaded_plugins(),
+        // Remote control
+        start_osc_server(port: u16),
+        stop_osc_server(),
+        get_osc_status(),
+    )
+}
+
+fn broadcast(state: &DawState, event: &str, payload: &Value) {
+    if let Ok(server) = state.websocket.lock() {
+        if let Some(server) = server.as_ref() {
+            server.broadcast(event, payload);
+        }
+    }
+}
+
+/// Forward frontend events to WebSocket clients and stream engine telemetry
+pub fn spawn_websocket_telemetry<R: Runtime>(app: AppHandle<R>) {
+    for event in FORWARDED_EVENTS {
+        let listener_app = app.clone();
+        app.listen_any(event, move |emitted| {
+            let payload = serde_json::from_str(emitted.payload()).unwrap_or(Value::Null);
+            broadcast(&listener_app.state::<DawState>(), event, &payload);
+        });
+    }
+
+    thread::Builder::new()
+        .name("websocket-telemetry".to_string())
+        .spawn(move || loop {
+            thread::sleep(TELEMETRY_INTERVAL);
+
+            let state = app.state::<DawState>();
+            let has_clients = match state.websocket.lock() {
+                Ok(server) => server.as_ref().is_some_and(|server| server.client_count() > 0),
+                Err(_) => return,
+            };
+            if !has_clients {
+                continue;
+            }
+            if let Ok(snapshot) = get_engine_snapshot(state.clone()) {
+                broadcast(&state, ENGINE_SNAPSHOT_EVENT, &snapshot);
+            }
+        })
+        .expect("failed to spawn WebSocket telemetry thread");
+}
+
+/// Start the WebSocket server at launch when `MYMUSIC_WEBSOCKET_PORT` is set
+///
+/// Lets integration tests drive the DAW without going through the UI.
+pub fn start_websocket_from_env<R: Runtime>(app: &AppHandle<R>) {
+    let Ok(port) = std::env::var("MYMUSIC_WEBSOCKET_PORT") else {
+        return;
+    };
+    let result = port
+        .parse()
+        .map_err(|_| DawError::InvalidArgument(format!("Invalid MYMUSIC_WEBSOCKET_PORT: {}", port)))
+        .and_then(|port| start_websocket(app, port, false));
+    if let Err(e) = result {
+        eprintln!("❌ Failed to start the WebSocket server: {}", e);
+    }
+}
@@ -0,0 +1,235 @@
+// Audio engine lifecycle for the Tauri app
+//
+// cpal streams are not `Send` on every platform, so the engine is created and
+// owned by a dedicated thread for its whole life. The Tauri side only keeps a
+// control handle: stopping it drops the stream cleanly, and restarting it builds
+// a fresh engine (e.g. after an audio device change) with new command channels.
+// The engine thread also forwards watchdog faults (stalled or overloaded
+// callback) so the engine can be restarted automatically, and the command
+// acknowledgments of the audio thread (see `events::spawn_feedback_forwarder`).
+
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter, Runtime};
+use mymusic_daw::audio::parameters::{AtomicF32, SynthParameters};
+use mymusic_daw::audio::snapshot::{AtomicEngineSnapshot, EngineSnapshot};
+use mymusic_daw::audio::tap::AudioTap;
+use mymusic_daw::audio::watchdog::WatchdogFault;
+use mymusic_daw::command::DawState as CoreState;
+use mymusic_daw::messaging::channels::{CommandProducer, NotificationProducer};
+use mymusic_daw::messaging::feedback::{create_feedback_channel, EngineFeedback};
+use mymusic_daw::plugin::PluginHost;
+use mymusic_daw::{create_command_channel, AudioEngine, OutputOptions};
+
+use crate::events::{NotificationPayload, NOTIFICATION_EVENT};
+
+/// Capacity of the UI -> audio command ringbuffer
+const COMMAND_RINGBUFFER_CAPACITY: usize = 1024;
+
+/// Capacity of the MIDI -> audio command ringbuffer
+const MIDI_RINGBUFFER_CAPACITY: usize = 1024;
+
+/// How often parameter updates coalesced while the ringbuffer was full are flushed
+const COMMAND_FLUSH_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Capacity of the audio -> UI feedback ringbuffer
+const FEEDBACK_RINGBUFFER_CAPACITY: usize = 256;
+
+/// How often the engine thread checks the watchdog and forwards engine feedback
+const ENGINE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Handle to the thread owning a running `AudioEngine`
+struct EngineThread {
+    shutdown_tx: mpsc::Sender<()>,
+    fault_rx: mpsc::Receiver<WatchdogFault>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl EngineThread {
+    /// Stop the engine thread and wait for the stream to be dropped
+    fn stop(&mut self) {
+        let _ = self.shutdown_tx.send(());
+        if let Some(handle) = self.join_handle.take() {
+            if handle.join().is_err() {
+                eprintln!("❌ Audio engine thread panicked during shutdown");
+            }
+        }
+    }
+}
+
+impl Drop for EngineThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Starts, stops and restarts the audio engine
+pub struct EngineController {
+    engine: Option<EngineThread>,
+    notification_tx: Arc<Mutex<NotificationProducer>>,
+    /// Receives the feedback of every engine started (survives restarts)
+    feedback_tx: mpsc::Sender<EngineFeedback>,
+    plugin_host: Arc<PluginHost>,
+    sample_rate: f32,
+    /// State published by the audio callback of the running engine
+    snapshot: AtomicEngineSnapshot,
+    /// Master output tap of the running engine (tuner, analyzers)
+    master_tap: AudioTap,
+    /// Output device and buffer size used by every engine started
+    output_options: OutputOptions,
+}
+
+impl EngineController {
+    pub fn new(
+        notification_tx: Arc<Mutex<NotificationProducer>>,
+        feedback_tx: mpsc::Sender<EngineFeedback>,
+        plugin_host: Arc<PluginHost>,
+    ) -> Self {
+        Self {
+            engine: None,
+            notification_tx,
+            feedback_tx,
+            plugin_host,
+            sample_rate: 0.0,
+            snapshot: AtomicEngineSnapshot::new(),
+            master_tap: AudioTap::default(),
+            output_options: OutputOptions::default(),
+        }
+    }
+
+    /// Output device and buffer size for the next engine started
+    pub fn set_output_options(&mut self, options: OutputOptions) {
+        self.output_options = options;
+    }
+
+    /// Check if an engine is currently running
+    pub fn is_running(&self) -> bool {
+        self.engine.is_some()
+    }
+
+    /// Take the fault reported by the watchdog of the running engine, if any
+    pub fn take_fault(&mut self) -> Option<WatchdogFault> {
+        self.engine.as_ref()?.fault_rx.try_recv().ok()
+    }
+
+    /// Sample rate of the running engine (or of the last one that ran)
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Last state published by the engine (playhead, voices, CPU, peaks)
+    pub fn snapshot(&self) -> EngineSnapshot {
+        self.snapshot.read()
+    }
+
+    /// Master output tap of the running engine (a new one after each restart)
+    pub fn master_tap(&self) -> AudioTap {
+        self.master_tap.clone()
+    }
+
+    /// Start a new engine on the configured output device
+    ///
+    /// Any running engine is stopped first. Returns the producer feeding the new
+    /// engine (the previous one is disconnected) and the device sample rate.
+    pub fn start(&mut self, volume: &AtomicF32, synth_params: &SynthParameters) -> Result<(CommandProducer, f32), String> {
+        self.shutdown();
+
+        let (command_tx, command_rx) = create_command_channel(COMMAND_RINGBUFFER_CAPACITY);
+        // No MIDI input in the Tauri app yet: the producer side is dropped
+        let (_midi_tx, midi_rx) = create_command_channel(MIDI_RINGBUFFER_CAPACITY);
+        let (engine_feedback_tx, mut engine_feedback_rx) = create_feedback_channel(FEEDBACK_RINGBUFFER_CAPACITY);
+
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>();
+        let (fault_tx, fault_rx) = mpsc::channel();
+        let notification_tx = self.notification_tx.clone();
+        let feedback_tx = self.feedback_tx.clone();
+        let plugin_host = self.plugin_host.clone();
+        let volume = volume.clone();
+        let synth_params = synth_params.clone();
+        let output_options = self.output_options.clone();
+
+        let join_handle = thread::Builder::new()
+            .name("audio-engine".to_string())
+            .spawn(move || {
+                let engine = match AudioEngine::with_options(command_rx, midi_rx, engine_feedback_tx, notification_tx, plugin_host, volume, synth_params, &output_options) {
+                    Ok(engine) => engine,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok((engine.sample_rate(), engine.snapshot.clone(), engine.master_tap.clone())));
+
+                // Run until shutdown is requested (or the controller is gone)
+                while let Err(RecvTimeoutError::Timeout) = shutdown_rx.recv_timeout(ENGINE_POLL_INTERVAL) {
+                    if let Some(fault) = engine.take_watchdog_fault() {
+                        let _ = fault_tx.send(fault);
+                    }
+                    while let Some(feedback) = ringbuf::traits::Consumer::try_pop(&mut engine_feedback_rx) {
+                        let _ = feedback_tx.send(feedback);
+                    }
+                }
+                drop(engine);
+                println!("🔇 Audio engine stopped");
+            })
+            .map_err(|e| format!("Failed to spawn audio engine thread: {}", e))?;
+
+        let (sample_rate, snapshot, master_tap) = match ready_rx.recv() {
+            Ok(Ok(ready)) => ready,
+            Ok(Err(e)) => {
+                let _ = join_handle.join();
+                return Err(e);
+            }
+            Err(_) => {
+                let _ = join_handle.join();
+                return Err("Audio engine thread exited during startup".to_string());
+            }
+        };
+
+        self.engine = Some(EngineThread {
+            shutdown_tx,
+            fault_rx,
+            join_handle: Some(join_handle),
+        });
+        self.sample_rate = sample_rate;
+        self.snapshot = snapshot;
+        self.master_tap = master_tap;
+
+        println!("✅ Audio engine started ({} Hz)", sample_rate);
+        Ok((command_tx, sample_rate))
+    }
+
+    /// Stop the running engine (no-op if already stopped)
+    pub fn shutdown(&mut self) {
+        if let Some(mut engine) = self.engine.take() {
+            engine.stop();
+        }
+    }
+}
+
+/// Start a background thread flushing coalesced parameter updates to the audio thread
+///
+/// Sustained command ringbuffer overflows are emitted as `notification` events.
+pub fn spawn_command_flush_pump<R: Runtime>(app: AppHandle<R>, core: Arc<Mutex<CoreState>>) {
+    thread::Builder::new()
+        .name("command-flush".to_string())
+        .spawn(move || loop {
+            thread::sleep(COMMAND_FLUSH_INTERVAL);
+
+            let notification = match core.lock() {
+                Ok(mut core) => core.flush_to_audio(),
+                Err(_) => return,
+            };
+            if let Some(notification) = notification {
+                let payload = NotificationPayload::from(notification);
+                if let Err(e) = app.emit(NOTIFICATION_EVENT, &payload) {
+                    eprintln!("Failed to emit notification: {}", e);
+                }
+            }
+        })
+        .expect("failed to spawn command flush thread");
+}
@@ -0,0 +1,187 @@
+// Typed errors returned by Tauri commands
+//
+// Every command returns `DawResult<T>`. Errors are serialized as
+// `{ category, code, message, recoverable }` so the frontend can branch on
+// `code` instead of matching message strings. `code` values are part of the
+// bridge API: renaming or removing one requires bumping `BRIDGE_API_VERSION`.
+
+use mymusic_daw::command::trait_def::CommandError;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+/// Version of the command/event surface exposed to the frontend
+///
+/// Bumped on breaking changes (renamed commands, arguments or error codes).
+pub const BRIDGE_API_VERSION: u32 = 1;
+
+/// Result type of all Tauri commands
+pub type DawResult<T> = Result<T, DawError>;
+
+/// Broad error family, for generic frontend handling (toast, dialog, retry...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The request itself is invalid
+    Validation,
+    /// The request refers to something that does not exist
+    NotFound,
+    /// Audio engine or audio thread communication
+    Engine,
+    /// Internal shared state
+    State,
+    /// File system, decoding
+    Io,
+    /// Plugin hosting and plugin GUIs
+    Plugin,
+    /// Not supported in this build or environment
+    Unsupported,
+}
+
+/// Error returned to the frontend
+#[derive(Debug, Clone, PartialEq)]
+pub enum DawError {
+    /// Invalid argument (out of range value, unknown name...)
+    InvalidArgument(String),
+    /// Pattern, note, sample, plugin or preset not found
+    NotFound(String),
+    /// Command queue to the audio thread is full
+    QueueFull,
+    /// Audio engine failure (start, stream error...)
+    Engine(String),
+    /// A shared state mutex was poisoned by a panic
+    StatePoisoned(String),
+    /// File system or decoding failure
+    Io(String),
+    /// Plugin loading or processing failure
+    Plugin(String),
+    /// Plugin GUI or native window failure
+    Window(String),
+    /// Command not implemented yet
+    NotImplemented(String),
+    /// Not available in the current environment (e.g. no display server)
+    Unavailable(String),
+}
+
+impl DawError {
+    /// Poisoned lock on the shared state named `what`
+    pub fn poisoned(what: &str, error: impl fmt::Display) -> Self {
+        DawError::StatePoisoned(format!("Failed to lock {}: {}", what, error))
+    }
+
+    /// Broad error family
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            DawError::InvalidArgument(_) => ErrorCategory::Validation,
+            DawError::NotFound(_) => ErrorCategory::NotFound,
+            DawError::QueueFull | DawError::Engine(_) => ErrorCategory::Engine,
+            DawError::StatePoisoned(_) => ErrorCategory::State,
+            DawError::Io(_) => ErrorCategory::Io,
+            DawError::Plugin(_) | DawError::Window(_) => ErrorCategory::Plugin,
+            DawError::NotImplemented(_) | DawError::Unavailable(_) => ErrorCategory::Unsupported,
+        }
+    }
+
+    /// Stable machine-readable code
+    pub fn code(&self) -> &'static str {
+        match self {
+            DawError::InvalidArgument(_) => "invalid_argument",
+            DawError::NotFound(_) => "not_found",
+            DawError::QueueFull => "queue_full",
+            DawError::Engine(_) => "engine_failure",
+            DawError::StatePoisoned(_) => "state_poisoned",
+            DawError::Io(_) => "io_error",
+            DawError::Plugin(_) => "plugin_error",
+            DawError::Window(_) => "window_error",
+            DawError::NotImplemented(_) => "not_implemented",
+            DawError::Unavailable(_) => "unavailable",
+        }
+    }
+
+    /// Human readable message
+    pub fn message(&self) -> String {
+        match self {
+            DawError::QueueFull => "Failed to send command to audio thread (buffer full)".to_string(),
+            DawError::InvalidArgument(msg)
+            | DawError::NotFound(msg)
+            | DawError::Engine(msg)
+            | DawError::StatePoisoned(msg)
+            | DawError::Io(msg)
+            | DawError::Plugin(msg)
+            | DawError::Window(msg)
+            | DawError::NotImplemented(msg)
+            | DawError::Unavailable(msg) => msg.clone(),
+        }
+    }
+
+    /// Whether the app keeps working normally, so the user can retry or fix the input
+    ///
+    /// A poisoned state or a missing feature will fail again on every call.
+    pub fn recoverable(&self) -> bool {
+        !matches!(
+            self,
+            DawError::StatePoisoned(_) | DawError::NotImplemented(_) | DawError::Unavailable(_)
+        )
+    }
+}
+
+impl fmt::Display for DawError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for DawError {}
+
+impl Serialize for DawError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("DawError", 4)?;
+        error.serialize_field("category", &self.category())?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.message())?;
+        error.serialize_field("recoverable", &self.recoverable())?;
+        error.end()
+    }
+}
+
+impl From<CommandError> for DawError {
+    fn from(error: CommandError) -> Self {
+        match error {
+            // The core state validates parameters before sending them
+            CommandError::InvalidState(msg) => DawError::InvalidArgument(msg),
+            CommandError::ExecutionFailed(msg) | CommandError::UndoFailed(msg) => DawError::Engine(msg),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_shape() {
+        let json = serde_json::to_value(DawError::NotFound("Pattern not found: 3".to_string())).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "category": "not_found",
+                "code": "not_found",
+                "message": "Pattern not found: 3",
+                "recoverable": true,
+            })
+        );
+
+        let json = serde_json::to_value(DawError::poisoned("sampler", "poisoned")).unwrap();
+        assert_eq!(json["category"], "state");
+        assert_eq!(json["code"], "state_poisoned");
+        assert_eq!(json["recoverable"], false);
+    }
+
+    #[test]
+    fn test_from_command_error() {
+        let error: DawError = CommandError::InvalidState("Invalid tempo: 5".to_string()).into();
+        assert_eq!(error, DawError::InvalidArgument("Invalid tempo: 5".to_string()));
+
+        let error: DawError = CommandError::ExecutionFailed("ringbuffer full".to_string()).into();
+        assert_eq!(error.category(), ErrorCategory::Engine);
+    }
+}
@@ -1,7 +1,13 @@
 // Event system for streaming data from audio engine to React UI
+use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 use serde::{Serialize, Deserialize};
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Runtime};
+use mymusic_daw::messaging::channels::NotificationConsumer;
+use mymusic_daw::messaging::feedback::EngineFeedback;
+use mymusic_daw::messaging::notification::{Notification, NotificationCategory, NotificationLevel};
 
 // Event types that can be streamed from audio engine to UI
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -281,4 +287,173 @@ fn get_timestamp() -> u64 {
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis() as u64
-}
\ No newline at end of file
+}
+// ============================================================================
+// Engine notifications (audio/MIDI errors, device status)
+// ============================================================================
+
+/// Name of the Tauri event carrying engine notifications
+pub const NOTIFICATION_EVENT: &str = "notification";
+
+/// Interval at which the notification channel is drained
+const NOTIFICATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Notification payload sent to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationPayload {
+    pub level: String,    // "info", "warning", "error"
+    pub category: String, // "midi", "audio", "cpu", "generic"
+    pub message: String,
+    pub timestamp: u64, // Unix timestamp in milliseconds
+}
+
+impl From<Notification> for NotificationPayload {
+    fn from(notification: Notification) -> Self {
+        let level = match notification.level {
+            NotificationLevel::Info => "info",
+            NotificationLevel::Warning => "warning",
+            NotificationLevel::Error => "error",
+        };
+        let category = match notification.category {
+            NotificationCategory::Midi => "midi",
+            NotificationCategory::Audio => "audio",
+            NotificationCategory::Cpu => "cpu",
+            NotificationCategory::Generic => "generic",
+        };
+
+        Self {
+            level: level.to_string(),
+            category: category.to_string(),
+            message: notification.message,
+            timestamp: notification.timestamp,
+        }
+    }
+}
+
+/// Start a background thread draining engine notifications and emitting them
+/// as `notification` events to the frontend
+///
+/// Notifications dropped because the channel was full are reported as a warning.
+pub fn spawn_notification_forwarder<R: Runtime>(app: AppHandle<R>, mut notification_rx: NotificationConsumer) {
+    thread::Builder::new()
+        .name("notification-forwarder".to_string())
+        .spawn(move || {
+            let mut reported_drops = 0;
+            loop {
+                let mut notifications: Vec<Notification> = std::iter::from_fn(|| notification_rx.try_pop()).collect();
+
+                let dropped = notification_rx.dropped();
+                if dropped.total() > reported_drops {
+                    notifications.push(Notification::warning(
+                        NotificationCategory::Generic,
+                        format!(
+                            "{} notifications dropped (queue full: {} info, {} warning, {} error)",
+                            dropped.total() - reported_drops,
+                            dropped.info,
+                            dropped.warning,
+                            dropped.error
+                        ),
+                    ));
+                    reported_drops = dropped.total();
+                }
+
+                for notification in notifications {
+                    let payload = NotificationPayload::from(notification);
+                    if let Err(e) = app.emit(NOTIFICATION_EVENT, &payload) {
+                        eprintln!("Failed to emit notification: {}", e);
+                    }
+                }
+                thread::sleep(NOTIFICATION_POLL_INTERVAL);
+            }
+        })
+        .expect("failed to spawn notification forwarder thread");
+}
+
+// ============================================================================
+// Engine feedback (command acknowledgments and corrections)
+// ============================================================================
+
+/// Name of the Tauri event carrying engine feedback
+pub const ENGINE_FEEDBACK_EVENT: &str = "engine-feedback";
+
+/// Engine feedback payload sent to the frontend
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineFeedbackPayload {
+    pub status: String,  // "applied", "corrected", "rejected"
+    pub command: String, // e.g. "add_sample", "set_pattern"
+    pub message: String,
+    pub timestamp: u64, // Unix timestamp in milliseconds
+}
+
+impl From<EngineFeedback> for EngineFeedbackPayload {
+    fn from(feedback: EngineFeedback) -> Self {
+        let status = match feedback {
+            EngineFeedback::Applied(_) => "applied",
+            EngineFeedback::Corrected { .. } => "corrected",
+            EngineFeedback::Rejected { .. } => "rejected",
+        };
+
+        Self {
+            status: status.to_string(),
+            command: feedback.command().name().to_string(),
+            message: feedback.to_string(),
+            timestamp: get_timestamp(),
+        }
+    }
+}
+
+/// Start a background thread emitting engine feedback as `engine-feedback` events
+///
+/// Rejections and corrections are also emitted as `notification` events.
+pub fn spawn_feedback_forwarder<R: Runtime>(app: AppHandle<R>, feedback_rx: Receiver<EngineFeedback>) {
+    thread::Builder::new()
+        .name("feedback-forwarder".to_string())
+        .spawn(move || {
+            // Ends when the engine controller is dropped
+            while let Ok(feedback) = feedback_rx.recv() {
+                if let Err(e) = app.emit(ENGINE_FEEDBACK_EVENT, &EngineFeedbackPayload::from(feedback)) {
+                    eprintln!("Failed to emit engine feedback: {}", e);
+                }
+                if let Some(notification) = feedback.to_notification() {
+                    if let Err(e) = app.emit(NOTIFICATION_EVENT, &NotificationPayload::from(notification)) {
+                        eprintln!("Failed to emit notification: {}", e);
+                    }
+                }
+            }
+        })
+        .expect("failed to spawn feedback forwarder thread");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_payload_from_notification() {
+        let notification = Notification::error(NotificationCategory::Midi, "Device lost".to_string());
+        let timestamp = notification.timestamp;
+
+        let payload = NotificationPayload::from(notification);
+        assert_eq!(payload.level, "error");
+        assert_eq!(payload.category, "midi");
+        assert_eq!(payload.message, "Device lost");
+        assert_eq!(payload.timestamp, timestamp);
+    }
+
+    #[test]
+    fn test_engine_feedback_payload() {
+        use mymusic_daw::messaging::feedback::{CommandKind, RejectReason};
+
+        let payload = EngineFeedbackPayload::from(EngineFeedback::Rejected {
+            command: CommandKind::SetNoteSampleMapping,
+            reason: RejectReason::InvalidSampleIndex(3),
+        });
+        assert_eq!(payload.status, "rejected");
+        assert_eq!(payload.command, "set_note_sample_mapping");
+        assert_eq!(payload.message, "Note mapping rejected: no sample in slot 3");
+
+        let payload = EngineFeedbackPayload::from(EngineFeedback::Applied(CommandKind::SetPattern));
+        assert_eq!(payload.status, "applied");
+        assert_eq!(payload.command, "set_pattern");
+    }
+}
@@ -1,18 +1,63 @@
 // Tauri commands for MyMusic DAW
 // Exposes audio engine controls to the React frontend
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 // Import DAW modules (from parent crate)
-use mymusic_daw::audio::parameters::AtomicF32;
+use mymusic_daw::audio::clip_player::AudioTrackList;
+use mymusic_daw::audio::parameters::{AtomicF32, SynthParameters};
+use mymusic_daw::audio::tuner::Tuner;
+use mymusic_daw::command::{CommandManager, DawState as CoreState};
+use mymusic_daw::config::Preferences;
+use mymusic_daw::crash::RescueSession;
 use mymusic_daw::messaging::channels::CommandProducer;
 use mymusic_daw::plugin::{Plugin, PluginHost, PluginInstanceId};
+use mymusic_daw::project::PresetStore;
+use mymusic_daw::remote::{OscServer, WebSocketServer};
+use mymusic_daw::sampler::{Sample, SampleZone};
+use mymusic_daw::sequencer::{Pattern, PatternId};
 
 // Import modular command modules
 mod commands;
+use commands::arrangement::*;
+use commands::audio_track::*;
+use commands::automation::*;
 use commands::basic::*;
+use commands::dynamics::*;
+use commands::export::*;
+use commands::freeze::*;
+use commands::inserts::*;
+pub use commands::basic::spawn_engine_supervisor;
+use commands::master::*;
+use commands::midi_learn::*;
+use commands::pattern::*;
 use commands::plugin::*;
+use commands::plugin_window::*;
+pub use commands::plugin_window::spawn_plugin_gui_request_pump;
+use commands::preferences::*;
+use commands::preset::*;
+use commands::remote::*;
+pub use commands::remote::spawn_remote_control_pump;
+use commands::rescue::*;
+use commands::returns::*;
+use commands::outputs::*;
+use commands::surround::*;
+pub use commands::rescue::spawn_crash_rescue_pump;
+use commands::sampler::*;
+use commands::session::*;
+use commands::transport::*;
+use commands::tuner::*;
+use commands::websocket::*;
+pub use commands::websocket::{spawn_websocket_telemetry, start_websocket_from_env};
+
+// Typed command errors and bridge API version
+pub mod error;
+
+// Audio engine lifecycle
+pub mod engine;
+use engine::EngineController;
 
 // Event system
 pub mod events;
@@ -35,32 +80,160 @@ pub struct PluginGuiInfo {
     pub height: u32,
     pub can_resize: bool,
     pub api: String,
+    /// Label of the dedicated plugin window (if open)
+    pub window_label: Option<String>,
 }
 
 /// Shared state for the DAW engine
 /// This is accessible from all Tauri commands
 #[derive(Clone)]
 pub struct DawState {
-    /// Command producer to send commands to audio thread
-    pub command_tx: Arc<Mutex<CommandProducer>>,
+    /// Shared DAW state (synth, transport and master bus parameters)
+    ///
+    /// Same core state as the egui frontend: it owns the command producer
+    /// (swapped when the engine is restarted) and mirrors everything sent to
+    /// the audio thread.
+    pub core: Arc<Mutex<CoreState>>,
+
+    /// Undo/redo history for changes made through `core`
+    pub history: Arc<Mutex<CommandManager>>,
+
+    /// Audio engine lifecycle (owns the audio thread)
+    pub engine: Arc<Mutex<EngineController>>,
 
     /// Volume control (atomic for thread-safe access)
     pub volume_atomic: Arc<AtomicF32>,
 
+    /// Continuous synth parameters, shared by `core` and every engine started
+    pub synth_params: SynthParameters,
+
     /// Loaded plugin instances (plugin_id -> instance)
     pub plugins: Arc<Mutex<HashMap<String, ManagedPlugin>>>,
 
     /// Next plugin ID counter
     pub next_plugin_id: Arc<Mutex<u32>>,
+
+    /// Patterns edited from the web frontend
+    pub patterns: Arc<Mutex<PatternStore>>,
+
+    /// Loaded samples and note mappings (mirrors the audio thread's sampler)
+    pub sampler: Arc<Mutex<SamplerStore>>,
+
+    /// Synth presets and sample banks on disk
+    pub presets: Arc<PresetStore>,
+
+    /// OSC server (remote control from tablets and controllers)
+    pub osc: Arc<Mutex<Option<OscServer>>>,
+
+    /// WebSocket API server (remote UIs, integration tests)
+    pub websocket: Arc<Mutex<Option<WebSocketServer>>>,
+
+    /// User preferences (devices, theme, keymap, paths, autosave)
+    pub preferences: Arc<Mutex<Preferences>>,
+
+    /// Session rescued after the previous crash (until restored or discarded)
+    pub rescue: Arc<Mutex<Option<RescueSession>>>,
+
+    /// Tuner on the master output (listening between `start_tuner` and `stop_tuner`)
+    pub tuner: Arc<Mutex<Tuner>>,
+
+    /// Audio tracks (clips sent to the engine's clip player after each edit)
+    pub audio_tracks: Arc<Mutex<AudioTrackList>>,
+}
+
+/// Sample loaded from the web frontend
+pub struct LoadedSample {
+    pub sample: Arc<Sample>,
+    /// Source file (needed to save sample banks)
+    pub path: PathBuf,
+}
+
+/// Samples owned by the Tauri frontend
+///
+/// `samples` is indexed like the audio thread's sample list.
+#[derive(Default)]
+pub struct SamplerStore {
+    pub samples: Vec<LoadedSample>,
+    /// MIDI note -> sample index
+    pub note_mappings: BTreeMap<u8, usize>,
+    /// Sample index -> key-range zone
+    pub zones: BTreeMap<usize, SampleZone>,
+}
+
+/// Patterns owned by the Tauri frontend
+///
+/// The audio thread only ever sees the active pattern (sent via `Command::SetPattern`),
+/// this store is the source of truth for editing. Patterns are shared with the
+/// audio thread: edit them with `Arc::make_mut`, which copies the active pattern
+/// instead of touching the version being played.
+#[derive(Debug, Default)]
+pub struct PatternStore {
+    pub patterns: HashMap<PatternId, Arc<Pattern>>,
+    pub active_pattern_id: Option<PatternId>,
+    /// Active pattern rendered to an audio track (see `commands::freeze`)
+    pub frozen: Option<FrozenPattern>,
+    next_pattern_id: PatternId,
+}
+
+impl PatternStore {
+    /// Generate a unique pattern ID
+    pub fn generate_pattern_id(&mut self) -> PatternId {
+        self.next_pattern_id += 1;
+        self.next_pattern_id
+    }
+
+    /// Pattern the sequencer plays: the active one, emptied while it is frozen
+    pub fn playing_pattern(&self) -> Option<Arc<Pattern>> {
+        let pattern = self.active_pattern_id.and_then(|id| self.patterns.get(&id))?;
+        if self.frozen.is_some() {
+            let mut silent = Pattern::clone(pattern);
+            silent.clear();
+            return Some(Arc::new(silent));
+        }
+        Some(pattern.clone())
+    }
+
+    /// Replace every pattern; the one with the lowest ID becomes active
+    pub fn replace(&mut self, patterns: impl IntoIterator<Item = Pattern>) {
+        self.patterns = patterns.into_iter().map(|pattern| (pattern.id, Arc::new(pattern))).collect();
+        self.active_pattern_id = self.patterns.keys().min().copied();
+        if let Some(&max_id) = self.patterns.keys().max() {
+            self.next_pattern_id = self.next_pattern_id.max(max_id);
+        }
+    }
 }
 
 impl DawState {
-    pub fn new(command_tx: CommandProducer, volume_atomic: Arc<AtomicF32>) -> Self {
+    pub fn new(
+        command_tx: CommandProducer,
+        volume_atomic: Arc<AtomicF32>,
+        synth_params: SynthParameters,
+        engine: EngineController,
+        preferences: Preferences,
+        rescue: Option<RescueSession>,
+    ) -> Self {
+        let mut core = CoreState::new(Arc::new(Mutex::new(command_tx)));
+        core.volume = volume_atomic.get();
+        core.synth_params = synth_params.clone();
+        let _ = core.set_metronome_output(preferences.audio.metronome_output);
+
         Self {
-            command_tx: Arc::new(Mutex::new(command_tx)),
+            core: Arc::new(Mutex::new(core)),
+            history: Arc::new(Mutex::new(CommandManager::new())),
+            engine: Arc::new(Mutex::new(engine)),
             volume_atomic,
+            synth_params,
             plugins: Arc::new(Mutex::new(HashMap::new())),
             next_plugin_id: Arc::new(Mutex::new(0)),
+            patterns: Arc::new(Mutex::new(PatternStore::default())),
+            sampler: Arc::new(Mutex::new(SamplerStore::default())),
+            presets: Arc::new(PresetStore::default()),
+            osc: Arc::new(Mutex::new(None)),
+            websocket: Arc::new(Mutex::new(None)),
+            preferences: Arc::new(Mutex::new(preferences)),
+            rescue: Arc::new(Mutex::new(rescue)),
+            tuner: Arc::new(Mutex::new(Tuner::new())),
+            audio_tracks: Arc::new(Mutex::new(AudioTrackList::new())),
         }
     }
 
@@ -82,18 +255,161 @@ pub fn register_commands<R: tauri::Runtime>(builder: tauri::Builder<R>) -> tauri
         stop_note,
         get_volume,
         get_engine_status,
+        get_engine_snapshot,
         get_engine_info,
+        get_bridge_api_version,
         play_test_beep,
+        shutdown_engine,
+        restart_engine,
+        set_max_voices,
+        set_realtime_priority,
+        undo,
+        redo,
+        get_history,
+        jump_to_history,
         // Synthesizer parameters
         set_waveform,
+        set_oscillator2,
+        set_unison,
+        set_anti_aliasing,
+        set_cross_mod,
+        set_noise,
         set_adsr,
+        set_mod_envelope,
         set_lfo,
+        set_mod_lfo,
         set_filter,
+        set_filter_mod,
         set_poly_mode,
         set_portamento,
+        set_pitch_bend_range,
         set_voice_mode,
         set_mod_routing,
         clear_mod_routing,
+        get_patch_slots,
+        switch_patch_slot,
+        copy_patch_slot,
+        // Master effects commands
+        get_master_effects,
+        set_master_reverb,
+        set_master_delay,
+        set_master_eq,
+        set_master_compressor,
+        set_master_limiter,
+        set_master_effect_order,
+        set_master_effect_bypass,
+        // Transport commands
+        set_tempo,
+        set_time_signature,
+        set_metronome_enabled,
+        set_metronome_volume,
+        // Remote control commands
+        start_osc_server,
+        stop_osc_server,
+        get_osc_status,
+        start_websocket_server,
+        stop_websocket_server,
+        get_websocket_status,
+        // Automation commands
+        get_automation_lane,
+        set_automation_points,
+        clear_lane,
+        // MIDI learn
+        get_cc_mappings,
+        arm_cc_learn,
+        cancel_cc_learn,
+        remove_cc_mapping,
+        send_control_change,
+        // Pattern editing commands
+        create_pattern,
+        list_patterns,
+        get_pattern,
+        set_active_pattern,
+        add_note,
+        update_note,
+        delete_notes,
+        set_pattern_groove,
+        quantize_pattern_notes,
+        detect_pattern_key,
+        // Arrangement commands
+        get_arrangement,
+        add_pattern_clip,
+        move_pattern_clip,
+        remove_pattern_clip,
+        set_song_mode,
+        get_session,
+        set_session_slot,
+        launch_session_slot,
+        stop_session,
+        release_session,
+        set_launch_quantization,
+        // Sampler commands
+        load_sample,
+        list_samples,
+        get_sample_peaks,
+        set_sample_note_mapping,
+        set_sample_zone,
+        set_sample_stretch,
+        preview_sample,
+        stop_sample_preview,
+        slice_sample,
+        import_sfz,
+        // Audio track commands
+        list_audio_tracks,
+        add_audio_track,
+        remove_audio_track,
+        update_audio_track,
+        import_audio_clip,
+        update_audio_clip,
+        remove_audio_clip,
+        set_audio_clip_warp,
+        set_warp_markers,
+        // Track freeze commands
+        freeze_synth_track,
+        unfreeze_synth_track,
+        get_freeze_status,
+        // Offline export commands
+        render_to_wav,
+        // Track dynamics and sidechain commands
+        set_track_dynamics,
+        set_plugin_sidechain,
+        get_sidechain_routing,
+        // Track insert chain commands
+        get_track_inserts,
+        set_track_inserts,
+        set_track_insert_order,
+        set_track_insert_bypass,
+        // Return bus commands
+        add_return_bus,
+        update_return_bus,
+        remove_return_bus,
+        get_return_routing,
+        set_track_send,
+        // Output routing commands
+        set_track_output,
+        set_metronome_output,
+        get_output_routing,
+        // Surround commands
+        set_speaker_layout,
+        set_track_surround,
+        get_surround_routing,
+        // Preferences commands
+        get_preferences,
+        set_preferences,
+        // Crash rescue commands
+        get_rescue_session,
+        restore_rescue_session,
+        discard_rescue_session,
+        // Tuner commands
+        start_tuner,
+        stop_tuner,
+        get_tuner_reading,
+        // Preset commands
+        list_presets,
+        save_preset,
+        load_preset,
+        delete_preset,
+        browse_synth_presets,
         // Event system
         initialize_events,
         // Plugin commands
@@ -114,6 +430,8 @@ pub fn register_commands<R: tauri::Runtime>(builder: tauri::Builder<R>) -> tauri
         set_plugin_gui_size,
         is_plugin_gui_visible,
         get_window_handle_for_plugin,
+        open_plugin_window,
+        close_plugin_window,
         // MIDI Bridge commands (bypass display server)
         add_midi_mapping,
         remove_midi_mapping,
@@ -3,67 +3,68 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
 use tauri::Manager;
 
 // Import DAW modules
-use mymusic_daw::audio::device::AudioDeviceManager;
-use mymusic_daw::{
-    create_command_channel, create_notification_channel, AudioEngine, MidiConnectionManager,
-};
+use mymusic_daw::audio::parameters::{AtomicF32, SynthParameters};
+use mymusic_daw::config::Preferences;
+use mymusic_daw::crash::{self, RescueStore};
+use mymusic_daw::create_notification_channel;
 use mymusic_daw::plugin::PluginHost;
+use mymusic_daw::OutputOptions;
 
 // Import library with commands and state
-use app_lib::{register_commands, DawState};
-use app_lib::events::AUDIO_EVENT_EMITTER;
+use app_lib::{
+    register_commands, spawn_crash_rescue_pump, spawn_engine_supervisor, spawn_plugin_gui_request_pump,
+    spawn_remote_control_pump, spawn_websocket_telemetry, start_websocket_from_env, DawState,
+};
+use app_lib::engine::{spawn_command_flush_pump, EngineController};
+use app_lib::events::{spawn_feedback_forwarder, spawn_notification_forwarder, AUDIO_EVENT_EMITTER};
 
 fn main() {
     // Initialize the audio engine
     println!("🎵 Initializing MyMusic DAW...");
 
-    // Create communication channels
-    // We need two separate channels: one for UI commands and one for MIDI commands
-    let (command_tx_ui, command_rx_ui) = create_command_channel(1024);
-    let (command_tx_midi, command_rx_midi) = create_command_channel(1024);
+    // User preferences (shared with the egui frontend)
+    let preferences = Preferences::load();
+
+    // Crash handler: rescues the session and reports the crash if the DAW dies
+    // (the frontend offers to restore the rescued session, see `get_rescue_session`)
+    let rescue = crash::install(RescueStore::default_dir());
 
     // Create notification channel
-    let (notification_tx, _notification_rx) = create_notification_channel(256);
+    let (notification_tx, notification_rx) = create_notification_channel(256);
     let notification_tx_arc = Arc::new(std::sync::Mutex::new(notification_tx));
 
+    // Create feedback channel (command acknowledgments from the audio thread)
+    let (feedback_tx, feedback_rx) = mpsc::channel();
+
     // Create plugin host
     let plugin_host = Arc::new(PluginHost::new());
     println!("🔌 Plugin host initialized");
 
-    // Create audio engine
-    let audio_engine = match AudioEngine::new(
-        command_rx_ui,
-        command_rx_midi,
-        notification_tx_arc.clone(),
-        plugin_host,
-    ) {
-        Ok(engine) => {
-            println!("✅ Audio engine started successfully");
-            engine
-        }
+    // Start the audio engine on its own thread (stopped cleanly on exit)
+    let mut engine = EngineController::new(notification_tx_arc.clone(), feedback_tx, plugin_host);
+    engine.set_output_options(OutputOptions::from(&preferences.audio));
+    let volume_atomic = Arc::new(AtomicF32::new(0.5));
+    let synth_params = SynthParameters::default();
+    let command_tx_ui = match engine.start(&volume_atomic, &synth_params) {
+        Ok((command_tx, _sample_rate)) => command_tx,
         Err(e) => {
             eprintln!("❌ Failed to start audio engine: {}", e);
             std::process::exit(1);
         }
     };
 
-    // Get volume from audio engine (it's created internally)
-    // Wrap it in Arc to match DawState::new() signature
-    let volume_atomic = Arc::new(audio_engine.volume.clone());
-
     // Create DAW state for Tauri
-    let daw_state = DawState::new(command_tx_ui, volume_atomic);
-
-    // Keep the audio engine alive (Tauri will manage its lifetime)
-    std::mem::forget(audio_engine);
+    let daw_state = DawState::new(command_tx_ui, volume_atomic, synth_params, engine, preferences, rescue);
+    let engine_handle = daw_state.engine.clone();
+    let core_handle = daw_state.core.clone();
 
     // Build and run Tauri application
     let builder = tauri::Builder::default()
-        .setup(|app| {
+        .setup(move |app| {
             println!("🚀 Tauri app initialized");
             println!("🎹 DAW is ready!");
 
@@ -75,6 +76,31 @@ fn main() {
                 eprintln!("❌ Failed to initialize event system");
             }
 
+            // Forward engine notifications (audio/MIDI errors) to the frontend
+            spawn_notification_forwarder(app.handle().clone(), notification_rx);
+
+            // Forward what the audio thread accepted or rejected to the frontend
+            spawn_feedback_forwarder(app.handle().clone(), feedback_rx);
+
+            // Flush parameter updates held back while the command ringbuffer was full
+            spawn_command_flush_pump(app.handle().clone(), core_handle);
+
+            // Restart the audio engine when its watchdog reports a stall or overload
+            spawn_engine_supervisor(app.handle().clone());
+
+            // Route plugin GUI requests (resize, show, hide) to their windows
+            spawn_plugin_gui_request_pump(app.handle().clone());
+
+            // Apply messages from OSC controllers (server started from the frontend)
+            spawn_remote_control_pump(app.handle().clone());
+
+            // Stream telemetry to WebSocket API clients; MYMUSIC_WEBSOCKET_PORT starts the server at launch
+            spawn_websocket_telemetry(app.handle().clone());
+            start_websocket_from_env(app.handle());
+
+            // Keep the crash rescue session and diagnostics up to date
+            spawn_crash_rescue_pump(app.handle().clone());
+
             // Log window info
             if let Some(window) = app.get_webview_window("main") {
                 println!("📱 Main window created: {:?}", window.label());
@@ -86,6 +112,16 @@ fn main() {
 
     // Register all Tauri commands
     register_commands(builder)
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // Stop the audio thread so the stream is closed properly
+                if let Ok(mut engine) = engine_handle.lock() {
+                    engine.shutdown();
+                }
+                // Clean exit: nothing to rescue
+                crash::shutdown();
+            }
+        });
 }
@@ -3,89 +3,31 @@
 // This module provides platform-specific utilities for getting window handles
 // from Tauri windows for plugin GUI embedding.
 
-use tauri::WebviewWindow;
+use tauri::{Runtime, WebviewWindow};
 use std::ffi::c_void;
 use base64::Engine;
+use raw_window_handle::{HasWindowHandle, RawWindowHandle};
 
 /// Get platform-specific window handle for plugin GUI embedding
-pub fn get_window_handle(window: &WebviewWindow) -> Result<*mut c_void, String> {
-    #[cfg(target_os = "macos")]
-    {
-        get_macos_window_handle(window)
-    }
-    
-    #[cfg(target_os = "windows")]
-    {
-        get_windows_window_handle(window)
-    }
-    
-    #[cfg(target_os = "linux")]
-    {
-        get_linux_window_handle(window)
-    }
-}
-
-/// Get macOS NSView handle from Tauri window
-#[cfg(target_os = "macos")]
-fn get_macos_window_handle(window: &WebviewWindow) -> Result<*mut c_void, String> {
-    // Get the NSView from the window
-    let ns_view = window
-        .ns_view()
-        .map_err(|e| format!("Failed to get NSView: {}", e))?;
-    
-    // Convert NSView pointer to raw pointer
-    let view_ptr: *mut c_void = ns_view as *mut c_void;
-    
-    println!("🍎 Got macOS NSView handle: {:p}", view_ptr);
-    Ok(view_ptr)
-}
+///
+/// Returns the NSView on macOS, the HWND on Windows and the X11 window ID
+/// (or Wayland surface) on Linux, as expected by the CLAP `set_parent` call.
+pub fn get_window_handle<R: Runtime>(window: &WebviewWindow<R>) -> Result<*mut c_void, String> {
+    let handle = window
+        .window_handle()
+        .map_err(|e| format!("Failed to get window handle: {}", e))?;
 
-/// Get Windows HWND from Tauri window
-#[cfg(target_os = "windows")]
-fn get_windows_window_handle(window: &WebviewWindow) -> Result<*mut c_void, String> {
-    use windows::Win32::UI::WindowsAndMessaging::GetWindowLongPtrW;
-    use windows::Win32::UI::WindowsAndMessaging::GWL_USERDATA;
-    use windows::core::PCWSTR;
-    
-    // Get the HWND from the window
-    let hwnd = window
-        .hwnd()
-        .map_err(|e| format!("Failed to get HWND: {}", e))?;
-    
-    // Convert HWND to raw pointer
-    let hwnd_ptr: *mut c_void = hwnd.0 as *mut c_void;
-    
-    println!("🪟 Got Windows HWND handle: {:p}", hwnd_ptr);
-    Ok(hwnd_ptr)
-}
+    let ptr = match handle.as_raw() {
+        RawWindowHandle::AppKit(h) => h.ns_view.as_ptr(),
+        RawWindowHandle::Win32(h) => h.hwnd.get() as *mut c_void,
+        RawWindowHandle::Xlib(h) => h.window as *mut c_void,
+        RawWindowHandle::Xcb(h) => h.window.get() as usize as *mut c_void,
+        RawWindowHandle::Wayland(h) => h.surface.as_ptr(),
+        other => return Err(format!("Unsupported window handle type: {:?}", other)),
+    };
 
-/// Get X11 Window handle from Tauri window
-#[cfg(target_os = "linux")]
-fn get_linux_window_handle(window: &WebviewWindow) -> Result<*mut c_void, String> {
-    use gtk::prelude::*;
-    use gtk::{Window as GtkWindow, WidgetExt};
-    
-    // Try to get the GTK window
-    if let Some(gtk_window) = window.gtk_window() {
-        // Get the X11 Window ID
-        let xid = gtk_window.window().and_then(|w| {
-            w.display().get_default_screen()
-                .and_then(|s| s.root_window())
-                .map(|rw| unsafe {
-                    // This is a simplified approach - in practice you'd need
-                    // more sophisticated X11 handling
-                    rw.xid() as *mut c_void
-                })
-        });
-        
-        if let Some(xid_ptr) = xid {
-            println!("🐧 Got Linux X11 Window handle: {:p}", xid_ptr);
-            return Ok(xid_ptr);
-        }
-    }
-    
-    // Fallback: try Wayland (more complex)
-    Err("Linux window handle extraction not fully implemented".to_string())
+    println!("🪟 Got native window handle: {:p}", ptr);
+    Ok(ptr)
 }
 
 /// Encode window handle as base64 string for safe transport
@@ -0,0 +1,1475 @@
+// Clip player - Audio clips on the arrangement timeline
+//
+// Audio tracks hold clips of imported audio files placed on the timeline. The
+// UI edits an `AudioTrackList` and sends immutable snapshots of it to the audio
+// thread (`Command::SetAudioTracks`); the replaced snapshot goes back through
+// the garbage chute. While the transport plays, the callback mixes the clips
+// under the playhead with `ClipPlayer::process`, alongside the synth/sampler
+// voices.
+//
+// The synth and each audio track can run a compressor/gate insert (see
+// `synth::dynamics`) whose detector listens to any track: the track itself or
+// a sidechain source. Keys are taken pre-fader, so muting or turning down the
+// source track does not change the ducking. The same keys can feed the
+// sidechain input of the plugins. After it, each track runs its chain of
+// built-in insert effects (see `audio::inserts`).
+//
+// Every track also feeds the return buses (see `audio::returns`) through its
+// sends: pre-fader sends take the signal after the insert, post-fader sends
+// after the track gain and pan. Muted tracks send nothing.
+//
+// The gain, pan, mute and solo of every track, synth included, go through the
+// mixer stage (see `audio::routing::TrackMixer`): a snapshot brings the strips
+// of its tracks, `Command::SetTrackStrip` moves a single fader without sending
+// the whole track list again. While a track is soloed, the others are silent
+// (they still key the sidechains).
+//
+// Tracks and buses play on the master bus (the `left`/`right` block) or on a
+// hardware output pair of their own (see `audio::outputs`). With a surround
+// layout, tracks with a surround panner also feed the channels past the front
+// pair (see `audio::surround`). The block is processed in chunks of scratch
+// buffers allocated up front.
+//
+// Clip positions and lengths are in samples at the engine rate. The source
+// audio is read at its own rate (linear interpolation), so a clip plays at its
+// original speed and pitch, unless it is warped to the project tempo (see
+// `audio::warp`).
+
+use crate::audio::inserts::{InsertChain, InsertChainParams, MAX_TRACK_CHAINS};
+use crate::audio::outputs::{DirectOutputs, TrackOutput};
+use crate::audio::returns::{
+    MAX_RETURN_BUSES, ReturnBusParams, ReturnBuses, ReturnEffect, TrackSends,
+};
+use crate::audio::routing::{ChannelStrip, MixerTrack, TrackMixer};
+use crate::audio::stretch::StretchSettings;
+use crate::audio::surround::{SpeakerLayout, SurroundBus, SurroundPan};
+use crate::audio::warp::ClipWarp;
+use crate::sampler::loader::{Sample, SampleData};
+use crate::synth::dynamics::{Dynamics, DynamicsParams};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Maximum clip or track gain (+6 dB)
+pub const MAX_CLIP_GAIN: f32 = 2.0;
+
+/// Audio tracks that can run a dynamics insert (the following ones are bypassed)
+pub const MAX_TRACK_INSERTS: usize = 64;
+
+/// Frames processed at once by `ClipPlayer::process` (scratch buffer size)
+const CHUNK_FRAMES: usize = 256;
+
+/// Track whose signal keys a sidechain input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SidechainSource {
+    /// The synth/sampler voices (before their own insert)
+    Synth,
+    /// An audio track, by id
+    AudioTrack(u32),
+}
+
+/// Compressor/gate inserted on a track
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TrackDynamics {
+    pub params: DynamicsParams,
+    /// Track keying the detector (None: the track itself)
+    pub sidechain: Option<SidechainSource>,
+}
+
+impl TrackDynamics {
+    pub fn new(params: DynamicsParams) -> Self {
+        Self {
+            params,
+            sidechain: None,
+        }
+    }
+}
+
+/// Audio clip placed on the timeline
+#[derive(Debug, Clone)]
+pub struct AudioClip {
+    pub id: u32,
+    pub name: String,
+    /// Imported audio file
+    pub source: PathBuf,
+    pub sample: Arc<Sample>,
+    /// Timeline position of the clip start (samples)
+    pub start: u64,
+    /// Time skipped at the beginning of the source (samples)
+    pub offset: u64,
+    /// Played duration (samples)
+    pub length: u64,
+    /// Linear gain (0.0 to `MAX_CLIP_GAIN`)
+    pub gain: f32,
+    /// Fade-in duration from the clip start (samples)
+    pub fade_in: u64,
+    /// Fade-out duration before the clip end (samples)
+    pub fade_out: u64,
+    /// Tempo following (None: plays at the original speed)
+    ///
+    /// While warped, the markers define the played region and the clip
+    /// duration follows the tempo: `offset` and `length` are kept for when
+    /// warping is turned off.
+    pub warp: Option<ClipWarp>,
+    /// Repeat the clip until the transport stops (frozen pattern tracks,
+    /// which loop like the pattern they replace)
+    pub looping: bool,
+}
+
+impl AudioClip {
+    /// Clip playing the whole source from `start`
+    pub fn new(source: PathBuf, sample: Arc<Sample>, start: u64, sample_rate: f32) -> Self {
+        let mut clip = Self {
+            id: 0,
+            name: sample.name.clone(),
+            source,
+            sample,
+            start,
+            offset: 0,
+            length: 0,
+            gain: 1.0,
+            fade_in: 0,
+            fade_out: 0,
+            warp: None,
+            looping: false,
+        };
+        clip.length = clip.source_length(sample_rate);
+        clip
+    }
+
+    /// Duration of the whole source (samples at `sample_rate`)
+    pub fn source_length(&self, sample_rate: f32) -> u64 {
+        if self.sample.sample_rate == 0 {
+            return 0;
+        }
+        (self.sample.len() as f64 * sample_rate as f64 / self.sample.sample_rate as f64) as u64
+    }
+
+    /// Timeline duration at a tempo (samples per beat)
+    pub fn timeline_length(&self, samples_per_beat: f64) -> u64 {
+        match &self.warp {
+            Some(warp) => warp.length(samples_per_beat),
+            None => self.length,
+        }
+    }
+
+    /// Timeline position where the clip stops at a tempo (samples per beat)
+    pub fn end(&self, samples_per_beat: f64) -> u64 {
+        self.start + self.timeline_length(samples_per_beat)
+    }
+
+    /// Pitch shift in semitones (the length is kept)
+    pub fn pitch(&self) -> f32 {
+        self.sample.stretch_settings().pitch
+    }
+
+    /// Shift the pitch without changing the length (see `audio::stretch`)
+    ///
+    /// Renders the source again: takes time, never call it in the audio callback.
+    pub fn set_pitch(&mut self, semitones: f32) -> Result<(), String> {
+        let settings = StretchSettings {
+            pitch: semitones,
+            ratio: 1.0,
+        };
+        settings.validate()?;
+        if settings != self.sample.stretch_settings() {
+            let mut sample = (*self.sample).clone();
+            sample.set_stretch(settings);
+            self.sample = Arc::new(sample);
+        }
+        Ok(())
+    }
+
+    /// Keep the offset, length, gain and fades within the source and each other
+    pub fn clamp(&mut self, sample_rate: f32) {
+        let source_length = self.source_length(sample_rate);
+        self.offset = self.offset.min(source_length);
+        self.length = self.length.min(source_length - self.offset);
+        self.gain = self.gain.clamp(0.0, MAX_CLIP_GAIN);
+        self.fade_in = self.fade_in.min(self.length);
+        self.fade_out = self.fade_out.min(self.length - self.fade_in);
+    }
+
+    /// Fade gain at a position relative to the clip start (linear ramps)
+    #[inline]
+    fn fade_gain(&self, local: u64, length: u64) -> f32 {
+        let mut gain = 1.0;
+        if local < self.fade_in {
+            gain *= local as f32 / self.fade_in as f32;
+        }
+        let remaining = length - local;
+        if remaining < self.fade_out {
+            gain *= remaining as f32 / self.fade_out as f32;
+        }
+        gain
+    }
+}
+
+/// Audio track (a lane of clips)
+#[derive(Debug, Clone)]
+pub struct AudioTrack {
+    pub id: u32,
+    pub name: String,
+    /// Linear gain (0.0 to `MAX_CLIP_GAIN`)
+    pub gain: f32,
+    /// Pan (-1.0 left, 0.0 center, 1.0 right)
+    pub pan: f32,
+    pub muted: bool,
+    /// While any track is soloed, only the soloed ones are heard
+    pub soloed: bool,
+    pub clips: Vec<AudioClip>,
+    /// Project track rendered into this one (see `audio::freeze`)
+    ///
+    /// Frozen tracks are not saved with the project: the source track is.
+    pub frozen: Option<u32>,
+    /// Compressor/gate insert (applied before the track gain and pan)
+    pub dynamics: Option<TrackDynamics>,
+    /// Insert effect chain (after the compressor/gate insert)
+    pub inserts: InsertChainParams,
+    /// Send levels to the return buses, by bus slot
+    pub sends: TrackSends,
+    /// Where the track plays
+    pub output: TrackOutput,
+    /// Surround panner (None: the stereo pan, on the front pair)
+    pub surround: Option<SurroundPan>,
+}
+
+impl AudioTrack {
+    pub fn new(id: u32, name: String) -> Self {
+        Self {
+            id,
+            name,
+            gain: 1.0,
+            pan: 0.0,
+            muted: false,
+            soloed: false,
+            clips: Vec::new(),
+            frozen: None,
+            dynamics: None,
+            inserts: InsertChainParams::default(),
+            sends: TrackSends::default(),
+            output: TrackOutput::Master,
+            surround: None,
+        }
+    }
+
+    /// Gain, pan, mute and solo of the track, as played by the mixer stage
+    pub fn strip(&self) -> ChannelStrip {
+        ChannelStrip {
+            gain: self.gain,
+            pan: self.pan,
+            muted: self.muted,
+            soloed: self.soloed,
+        }
+    }
+
+    /// Set the gain, pan, mute and solo of the track
+    pub fn set_strip(&mut self, strip: ChannelStrip) {
+        self.gain = strip.gain;
+        self.pan = strip.pan;
+        self.muted = strip.muted;
+        self.soloed = strip.soloed;
+    }
+}
+
+/// Audio tracks edited by the UI (never shared with the audio thread)
+///
+/// Track and clip ids are unique within the list. `snapshot` gives the copy to
+/// send with `Command::SetAudioTracks` after each edit.
+#[derive(Debug, Clone, Default)]
+pub struct AudioTrackList {
+    tracks: Vec<AudioTrack>,
+    next_track_id: u32,
+    next_clip_id: u32,
+}
+
+impl AudioTrackList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tracks(&self) -> &[AudioTrack] {
+        &self.tracks
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// Add an empty track, returns its id
+    pub fn add_track(&mut self, name: String) -> u32 {
+        let id = self.next_track_id;
+        self.next_track_id += 1;
+        self.tracks.push(AudioTrack::new(id, name));
+        id
+    }
+
+    /// Remove a track (the inserts it was keying listen to their own track again)
+    pub fn remove_track(&mut self, track_id: u32) -> Option<AudioTrack> {
+        let index = self.tracks.iter().position(|track| track.id == track_id)?;
+        for track in &mut self.tracks {
+            if let Some(dynamics) = &mut track.dynamics
+                && dynamics.sidechain == Some(SidechainSource::AudioTrack(track_id))
+            {
+                dynamics.sidechain = None;
+            }
+        }
+        Some(self.tracks.remove(index))
+    }
+
+    pub fn track(&self, track_id: u32) -> Option<&AudioTrack> {
+        self.tracks.iter().find(|track| track.id == track_id)
+    }
+
+    pub fn track_mut(&mut self, track_id: u32) -> Option<&mut AudioTrack> {
+        self.tracks.iter_mut().find(|track| track.id == track_id)
+    }
+
+    /// Add a clip to a track (its id is assigned here), returns the clip id
+    pub fn add_clip(&mut self, track_id: u32, mut clip: AudioClip) -> Option<u32> {
+        let id = self.next_clip_id;
+        let track = self.track_mut(track_id)?;
+        clip.id = id;
+        track.clips.push(clip);
+        self.next_clip_id += 1;
+        Some(id)
+    }
+
+    pub fn remove_clip(&mut self, clip_id: u32) -> Option<AudioClip> {
+        self.tracks.iter_mut().find_map(|track| {
+            let index = track.clips.iter().position(|clip| clip.id == clip_id)?;
+            Some(track.clips.remove(index))
+        })
+    }
+
+    pub fn clip(&self, clip_id: u32) -> Option<&AudioClip> {
+        self.tracks
+            .iter()
+            .flat_map(|track| &track.clips)
+            .find(|clip| clip.id == clip_id)
+    }
+
+    pub fn clip_mut(&mut self, clip_id: u32) -> Option<&mut AudioClip> {
+        self.tracks
+            .iter_mut()
+            .flat_map(|track| &mut track.clips)
+            .find(|clip| clip.id == clip_id)
+    }
+
+    /// Turn off the sends of every track to a return bus slot (removed bus)
+    pub fn clear_sends(&mut self, bus: usize) {
+        for track in &mut self.tracks {
+            if let Some(send) = track.sends.get_mut(bus) {
+                *send = Default::default();
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+    }
+
+    /// Immutable copy for the audio thread (samples are shared, not copied)
+    pub fn snapshot(&self) -> Arc<Vec<AudioTrack>> {
+        Arc::new(self.tracks.clone())
+    }
+}
+
+/// Audio thread side: mixes the clips under the playhead and runs the inserts
+pub struct ClipPlayer {
+    tracks: Arc<Vec<AudioTrack>>,
+    sample_rate: f32,
+    /// Insert state of the first tracks, by index (with the id of the track)
+    track_dynamics: Vec<(Option<u32>, Dynamics)>,
+    synth_insert: Option<TrackDynamics>,
+    synth_dynamics: Dynamics,
+    /// Insert chains of the first tracks, by index (with the id of the track)
+    track_chains: Vec<(Option<u32>, InsertChain)>,
+    synth_chain: InsertChain,
+    /// Track feeding the sidechain input of the plugins
+    plugin_sidechain: Option<SidechainSource>,
+    returns: ReturnBuses,
+    synth_sends: TrackSends,
+    /// Delay and reverb send offsets of the synth mod matrix
+    synth_send_modulation: (f32, f32),
+    synth_output: TrackOutput,
+    synth_surround: Option<SurroundPan>,
+    /// Gain, pan, mute and solo of the tracks
+    mixer: TrackMixer,
+    /// Output channels of the device
+    channels: usize,
+    /// Speaker layout of the project (played in stereo if the device lacks channels)
+    layout: SpeakerLayout,
+    /// Hardware output pairs of the tracks and buses not on the master bus
+    direct: DirectOutputs,
+    /// Channels of the surround layout past the front pair
+    surround: SurroundBus,
+    // Scratch buffers (one chunk)
+    synth_left: [f32; CHUNK_FRAMES],
+    synth_right: [f32; CHUNK_FRAMES],
+    track_buffer: [f32; CHUNK_FRAMES],
+    key_buffer: [f32; CHUNK_FRAMES],
+    bus_left: [[f32; CHUNK_FRAMES]; MAX_RETURN_BUSES],
+    bus_right: [[f32; CHUNK_FRAMES]; MAX_RETURN_BUSES],
+}
+
+impl ClipPlayer {
+    pub fn new(sample_rate: f32) -> Self {
+        let dynamics = || Dynamics::new(DynamicsParams::default(), sample_rate);
+        let chain = || InsertChain::new(InsertChainParams::default(), sample_rate);
+        Self {
+            tracks: Arc::new(Vec::new()),
+            sample_rate,
+            track_dynamics: (0..MAX_TRACK_INSERTS).map(|_| (None, dynamics())).collect(),
+            synth_insert: None,
+            synth_dynamics: dynamics(),
+            track_chains: (0..MAX_TRACK_CHAINS).map(|_| (None, chain())).collect(),
+            synth_chain: chain(),
+            plugin_sidechain: None,
+            returns: ReturnBuses::new(sample_rate),
+            synth_sends: TrackSends::default(),
+            synth_send_modulation: (0.0, 0.0),
+            synth_output: TrackOutput::Master,
+            synth_surround: None,
+            mixer: TrackMixer::new(),
+            channels: 2,
+            layout: SpeakerLayout::Stereo,
+            direct: DirectOutputs::new(2, 0),
+            surround: SurroundBus::new(0),
+            synth_left: [0.0; CHUNK_FRAMES],
+            synth_right: [0.0; CHUNK_FRAMES],
+            track_buffer: [0.0; CHUNK_FRAMES],
+            key_buffer: [0.0; CHUNK_FRAMES],
+            bus_left: [[0.0; CHUNK_FRAMES]; MAX_RETURN_BUSES],
+            bus_right: [[0.0; CHUNK_FRAMES]; MAX_RETURN_BUSES],
+        }
+    }
+
+    /// Allocate the direct outputs and surround channels of a device with
+    /// `channels` output channels, for blocks of up to `max_frames` frames
+    pub fn with_output_channels(mut self, channels: usize, max_frames: usize) -> Self {
+        self.channels = channels;
+        self.direct = DirectOutputs::new(channels, max_frames);
+        self.surround = SurroundBus::new(max_frames);
+        self.set_speaker_layout(self.layout);
+        self
+    }
+
+    /// Direct outputs rendered by the last `process` call
+    pub fn direct_outputs(&self) -> &DirectOutputs {
+        &self.direct
+    }
+
+    /// Direct outputs of the current block, for signals mixed after `process`
+    /// (the metronome bus)
+    pub fn direct_outputs_mut(&mut self) -> &mut DirectOutputs {
+        &mut self.direct
+    }
+
+    /// Surround channels rendered by the last `process` call
+    pub fn surround_outputs(&self) -> &SurroundBus {
+        &self.surround
+    }
+
+    /// Set the speaker layout of the mix (stereo if the device lacks channels)
+    pub fn set_speaker_layout(&mut self, layout: SpeakerLayout) {
+        self.layout = layout;
+        let layout = layout.on_device(self.channels);
+        self.surround.set_layout(layout);
+        self.direct.set_layout(layout);
+    }
+
+    /// Set the surround panner of the synth track (None: stereo)
+    pub fn set_synth_surround(&mut self, pan: Option<SurroundPan>) {
+        self.synth_surround = pan;
+    }
+
+    /// Replace the played tracks, returns the previous ones
+    ///
+    /// The audio thread must hand the returned snapshot to the garbage chute
+    /// instead of dropping it.
+    pub fn set_tracks(&mut self, tracks: Arc<Vec<AudioTrack>>) -> Arc<Vec<AudioTrack>> {
+        let old = std::mem::replace(&mut self.tracks, tracks);
+        self.mixer
+            .set_audio_strips(self.tracks.iter().map(|track| (track.id, track.strip())));
+        // Inserts keep their state while their track keeps its place
+        for (index, (id, dynamics)) in self.track_dynamics.iter_mut().enumerate() {
+            match self.tracks.get(index) {
+                Some(AudioTrack {
+                    id: track_id,
+                    dynamics: Some(insert),
+                    ..
+                }) => {
+                    if *id != Some(*track_id) {
+                        dynamics.reset();
+                        *id = Some(*track_id);
+                    }
+                    dynamics.set_params(insert.params);
+                }
+                _ => *id = None,
+            }
+        }
+        // Chains too, a track taking the place of another starts without tails
+        for (index, (id, chain)) in self.track_chains.iter_mut().enumerate() {
+            match self.tracks.get(index) {
+                Some(track) => {
+                    if *id != Some(track.id) {
+                        chain.clear();
+                        *id = Some(track.id);
+                    }
+                    chain.set_params(track.inserts);
+                }
+                None => *id = None,
+            }
+        }
+        old
+    }
+
+    /// Set the synth insert (None removes it)
+    pub fn set_synth_dynamics(&mut self, insert: Option<TrackDynamics>) {
+        if let Some(insert) = insert {
+            if self.synth_insert.is_none() {
+                self.synth_dynamics.reset();
+            }
+            self.synth_dynamics.set_params(insert.params);
+        }
+        self.synth_insert = insert;
+    }
+
+    /// Set the track feeding the sidechain input of the plugins
+    pub fn set_plugin_sidechain(&mut self, source: Option<SidechainSource>) {
+        self.plugin_sidechain = source;
+    }
+
+    pub fn plugin_sidechain(&self) -> Option<SidechainSource> {
+        self.plugin_sidechain
+    }
+
+    /// Configure a return bus slot (None removes the bus)
+    pub fn set_return_bus(&mut self, index: usize, params: Option<ReturnBusParams>) {
+        self.returns.set_bus(index, params);
+    }
+
+    /// Set the sends of the synth track
+    pub fn set_synth_sends(&mut self, sends: TrackSends) {
+        self.synth_sends = sends;
+    }
+
+    /// Set the offsets the synth mod matrix adds to the sends of the synth track
+    /// to delay and reverb buses (see `VoiceManager::send_modulation`)
+    pub fn set_synth_send_modulation(&mut self, (delay, reverb): (f32, f32)) {
+        self.synth_send_modulation = (delay, reverb);
+    }
+
+    /// Sends of the synth track with their modulation
+    fn modulated_synth_sends(&self) -> TrackSends {
+        let (delay, reverb) = self.synth_send_modulation;
+        let mut sends = self.synth_sends;
+        if (delay, reverb) != (0.0, 0.0) {
+            for (index, send) in sends.iter_mut().enumerate() {
+                let offset = match self.returns.effect(index) {
+                    Some(ReturnEffect::Delay(_) | ReturnEffect::StereoDelay(_)) => delay,
+                    Some(ReturnEffect::Reverb(_)) => reverb,
+                    None => continue,
+                };
+                send.level = (send.level + offset).clamp(0.0, 1.0);
+            }
+        }
+        sends
+    }
+
+    /// Set where the synth track plays
+    pub fn set_synth_output(&mut self, output: TrackOutput) {
+        self.synth_output = output;
+    }
+
+    /// Set the strip of a track in the mixer stage
+    ///
+    /// An audio track missing from the played snapshot is ignored: the next
+    /// snapshot brings its strip.
+    pub fn set_track_strip(&mut self, track: MixerTrack, strip: ChannelStrip) {
+        self.mixer.set_strip(track, strip);
+    }
+
+    /// Set the insert chain of the synth track or of an audio track
+    ///
+    /// Like strips, an audio track missing from the played snapshot is
+    /// ignored; instrument tracks run their chain in `audio::instruments`.
+    pub fn set_track_inserts(&mut self, track: MixerTrack, inserts: InsertChainParams) {
+        match track {
+            MixerTrack::Synth => self.synth_chain.set_params(inserts),
+            MixerTrack::Audio(track_id) => {
+                if let Some((_, chain)) = self
+                    .track_chains
+                    .iter_mut()
+                    .find(|(id, _)| *id == Some(track_id))
+                {
+                    chain.set_params(inserts);
+                }
+            }
+            MixerTrack::Instrument(_) => {}
+        }
+    }
+
+    /// Forget the strip of a removed instrument track
+    pub fn remove_instrument_strip(&mut self, id: u32) {
+        self.mixer.remove_instrument_strip(id);
+    }
+
+    /// Gain, pan, mute and solo of the tracks (instrument tracks are mixed
+    /// with them by `audio::instruments`)
+    pub fn mixer(&self) -> &TrackMixer {
+        &self.mixer
+    }
+
+    /// Run the inserts and add the clips playing from timeline `position` to
+    /// the synth block in `left`/`right` (RT-safe)
+    ///
+    /// `samples_per_beat` is the current tempo, followed by warped clips. While
+    /// the transport is stopped the clips are silent, the synth insert and the
+    /// return buses still run. `sidechain` receives the plugin sidechain key,
+    /// if a source is set.
+    pub fn process(
+        &mut self,
+        position: u64,
+        samples_per_beat: f64,
+        playing: bool,
+        left: &mut [f32],
+        right: &mut [f32],
+        mut sidechain: Option<(&mut [f32], &mut [f32])>,
+    ) {
+        let frames = left.len().min(right.len());
+        self.direct.begin(frames);
+        self.surround.begin(frames);
+        if !playing
+            && self.synth_insert.is_none()
+            && !self.synth_chain.is_active()
+            && self.plugin_sidechain.is_none()
+            && !self.returns.any_active()
+            && self.direct.pair(self.synth_output).is_none()
+            && self.synth_surround.is_none()
+            && self.mixer.gains(MixerTrack::Synth) == (1.0, 1.0)
+        {
+            return;
+        }
+        let mut start = 0;
+        while start < frames {
+            let end = (start + CHUNK_FRAMES).min(frames);
+            let key = sidechain.as_mut().map(|(key_left, key_right)| {
+                (&mut key_left[start..end], &mut key_right[start..end])
+            });
+            self.process_chunk(
+                start,
+                position + start as u64,
+                samples_per_beat,
+                playing,
+                &mut left[start..end],
+                &mut right[start..end],
+                key,
+            );
+            start = end;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn process_chunk(
+        &mut self,
+        offset: usize,
+        position: u64,
+        samples_per_beat: f64,
+        playing: bool,
+        left: &mut [f32],
+        right: &mut [f32],
+        sidechain: Option<(&mut [f32], &mut [f32])>,
+    ) {
+        let frames = left.len();
+        let tracks: &[AudioTrack] = if playing { &self.tracks } else { &[] };
+        let sample_rate = self.sample_rate;
+
+        // Synth signal before its insert (a key for the other tracks)
+        self.synth_left[..frames].copy_from_slice(left);
+        self.synth_right[..frames].copy_from_slice(right);
+        let synth = (&self.synth_left[..frames], &self.synth_right[..frames]);
+
+        if let Some(insert) = self.synth_insert {
+            let key = match insert.sidechain {
+                None | Some(SidechainSource::Synth) => synth,
+                Some(SidechainSource::AudioTrack(id)) => {
+                    let key = &mut self.key_buffer[..frames];
+                    render_key(tracks, id, position, samples_per_beat, sample_rate, key);
+                    (&*key, &*key)
+                }
+            };
+            for i in 0..frames {
+                (left[i], right[i]) = self
+                    .synth_dynamics
+                    .process(left[i], right[i], key.0[i], key.1[i]);
+            }
+        }
+
+        if self.synth_chain.is_active() {
+            for i in 0..frames {
+                (left[i], right[i]) = self.synth_chain.process(left[i], right[i]);
+            }
+        }
+
+        // Synth fader (the surround panner replaces the stereo pan)
+        let synth_fader = match self.synth_surround {
+            Some(_) => {
+                let level = self.mixer.level(MixerTrack::Synth);
+                (level, level)
+            }
+            None => self.mixer.gains(MixerTrack::Synth),
+        };
+        let sends = self.returns.any_active();
+        if sends {
+            for bus in self.bus_left.iter_mut().chain(self.bus_right.iter_mut()) {
+                bus[..frames].fill(0.0);
+            }
+            if self.mixer.is_audible(MixerTrack::Synth) {
+                let synth_sends = self.modulated_synth_sends();
+                let buses = (&mut self.bus_left, &mut self.bus_right);
+                add_sends(
+                    &synth_sends,
+                    &self.returns,
+                    buses,
+                    (left, right),
+                    synth_fader,
+                );
+            }
+        }
+        if synth_fader != (1.0, 1.0) {
+            for i in 0..frames {
+                left[i] *= synth_fader.0;
+                right[i] *= synth_fader.1;
+            }
+        }
+        if let Some(pair) = self.direct.pair(self.synth_output) {
+            self.direct.add(pair, offset, left, right, (1.0, 1.0));
+            left.fill(0.0);
+            right.fill(0.0);
+        } else if let Some(pan) = self.synth_surround {
+            // Panned as mono
+            let gains = pan.gains(self.surround.layout());
+            let mono = &mut self.key_buffer[..frames];
+            for i in 0..frames {
+                mono[i] = (left[i] + right[i]) * 0.5;
+                left[i] = mono[i] * gains[0];
+                right[i] = mono[i] * gains[1];
+            }
+            self.surround.add(offset, mono, &gains);
+        }
+
+        for (index, track) in tracks.iter().enumerate() {
+            let channel = MixerTrack::Audio(track.id);
+            if !self.mixer.is_audible(channel) {
+                continue;
+            }
+            let own = &mut self.track_buffer[..frames];
+            let sounding = render_track(track, position, samples_per_beat, sample_rate, own);
+
+            let insert = self
+                .track_dynamics
+                .get_mut(index)
+                .filter(|(id, _)| *id == Some(track.id))
+                .and_then(|(_, dynamics)| Some((track.dynamics?, dynamics)));
+            let chain = self
+                .track_chains
+                .get_mut(index)
+                .filter(|(id, chain)| *id == Some(track.id) && chain.is_active())
+                .map(|(_, chain)| chain);
+            // Effect tails keep playing after the clips
+            if insert.is_none() && chain.is_none() && !sounding {
+                continue;
+            }
+            if let Some((insert, dynamics)) = insert {
+                let key = &mut self.key_buffer[..frames];
+                let key: &[f32] = match insert.sidechain {
+                    Some(SidechainSource::Synth) => synth.0,
+                    Some(SidechainSource::AudioTrack(id)) if id != track.id => {
+                        render_key(tracks, id, position, samples_per_beat, sample_rate, key);
+                        key
+                    }
+                    _ => {
+                        key.copy_from_slice(own);
+                        key
+                    }
+                };
+                // The detector keeps running on silence (release)
+                for i in 0..frames {
+                    own[i] = dynamics.process(own[i], own[i], key[i], key[i]).0;
+                }
+            }
+            if let Some(chain) = chain {
+                for sample in own.iter_mut() {
+                    *sample = chain.process_mono(*sample);
+                }
+            }
+            let own = &*own;
+
+            let (gain_left, gain_right) = self.mixer.gains(channel);
+            match (self.direct.pair(track.output), track.surround) {
+                (Some(pair), _) => self
+                    .direct
+                    .add(pair, offset, own, own, (gain_left, gain_right)),
+                (None, Some(pan)) => {
+                    let gains = pan
+                        .gains(self.surround.layout())
+                        .map(|gain| gain * self.mixer.level(channel));
+                    for i in 0..frames {
+                        left[i] += own[i] * gains[0];
+                        right[i] += own[i] * gains[1];
+                    }
+                    self.surround.add(offset, own, &gains);
+                }
+                (None, None) => {
+                    for i in 0..frames {
+                        left[i] += own[i] * gain_left;
+                        right[i] += own[i] * gain_right;
+                    }
+                }
+            }
+            if sends {
+                let buses = (&mut self.bus_left, &mut self.bus_right);
+                add_sends(
+                    &track.sends,
+                    &self.returns,
+                    buses,
+                    (own, own),
+                    (gain_left, gain_right),
+                );
+            }
+        }
+
+        if sends {
+            self.returns.set_samples_per_beat(samples_per_beat);
+            for index in 0..MAX_RETURN_BUSES {
+                if !self.returns.is_active(index) {
+                    continue;
+                }
+                let pair = self.direct.pair(self.returns.output(index));
+                let (bus_left, bus_right) = (&mut self.bus_left[index], &mut self.bus_right[index]);
+                for i in 0..frames {
+                    let (wet_left, wet_right) =
+                        self.returns.process(index, bus_left[i], bus_right[i]);
+                    if pair.is_some() {
+                        // Played on its own output pair, after the loop
+                        (bus_left[i], bus_right[i]) = (wet_left, wet_right);
+                    } else {
+                        left[i] += wet_left;
+                        right[i] += wet_right;
+                    }
+                }
+                if let Some(pair) = pair {
+                    let wet = (&bus_left[..frames], &bus_right[..frames]);
+                    self.direct.add(pair, offset, wet.0, wet.1, (1.0, 1.0));
+                }
+            }
+        }
+
+        if let (Some(source), Some((key_left, key_right))) = (self.plugin_sidechain, sidechain) {
+            match source {
+                SidechainSource::Synth => {
+                    key_left.copy_from_slice(synth.0);
+                    key_right.copy_from_slice(synth.1);
+                }
+                SidechainSource::AudioTrack(id) => {
+                    render_key(
+                        tracks,
+                        id,
+                        position,
+                        samples_per_beat,
+                        sample_rate,
+                        key_left,
+                    );
+                    key_right.copy_from_slice(key_left);
+                }
+            }
+        }
+    }
+}
+
+/// Add a track signal to the inputs of the active return buses
+///
+/// `fader` is the gain applied by the track fader and pan, followed by the
+/// post-fader sends.
+fn add_sends(
+    sends: &TrackSends,
+    returns: &ReturnBuses,
+    (bus_left, bus_right): (
+        &mut [[f32; CHUNK_FRAMES]; MAX_RETURN_BUSES],
+        &mut [[f32; CHUNK_FRAMES]; MAX_RETURN_BUSES],
+    ),
+    (left, right): (&[f32], &[f32]),
+    fader: (f32, f32),
+) {
+    for (index, send) in sends.iter().enumerate() {
+        if send.level <= 0.0 || !returns.is_active(index) {
+            continue;
+        }
+        let (send_left, send_right) = if send.pre_fader {
+            (send.level, send.level)
+        } else {
+            (send.level * fader.0, send.level * fader.1)
+        };
+        for i in 0..left.len() {
+            bus_left[index][i] += left[i] * send_left;
+            bus_right[index][i] += right[i] * send_right;
+        }
+    }
+}
+
+/// Mono sum of the clips of a track from `position`, before the track gain
+///
+/// Returns false when no clip plays in the block (`out` is then silent).
+fn render_track(
+    track: &AudioTrack,
+    position: u64,
+    samples_per_beat: f64,
+    sample_rate: f32,
+    out: &mut [f32],
+) -> bool {
+    out.fill(0.0);
+    let block_end = position + out.len() as u64;
+    let mut sounding = false;
+
+    for clip in &track.clips {
+        let length = clip.timeline_length(samples_per_beat);
+        if length == 0 {
+            continue;
+        }
+        let clip_end = if clip.looping {
+            u64::MAX
+        } else {
+            clip.start + length
+        };
+        if clip_end <= position || clip.start >= block_end || clip.gain <= 0.0 {
+            continue;
+        }
+        let SampleData::F32(data) = &clip.sample.data;
+        if data.is_empty() {
+            continue;
+        }
+        // Source samples per timeline sample
+        let step = clip.sample.sample_rate as f64 / sample_rate as f64;
+        sounding = true;
+
+        let first_frame = clip.start.saturating_sub(position);
+        let end_frame = clip_end.min(block_end) - position;
+        for frame in first_frame..end_frame {
+            let local = (position + frame - clip.start) % length;
+            let value = match &clip.warp {
+                Some(warp) => warp
+                    .grains(local, samples_per_beat)
+                    .iter()
+                    .filter(|(_, weight)| *weight > 0.0)
+                    .map(|(source, weight)| read_source(data, source * step) * weight)
+                    .sum(),
+                None => read_source(data, (clip.offset + local) as f64 * step),
+            };
+            out[frame as usize] += value * clip.gain * clip.fade_gain(local, length);
+        }
+    }
+    sounding
+}
+
+/// Sidechain key of an audio track (muted tracks still key, missing ones are silent)
+fn render_key(
+    tracks: &[AudioTrack],
+    track_id: u32,
+    position: u64,
+    samples_per_beat: f64,
+    sample_rate: f32,
+    out: &mut [f32],
+) {
+    match tracks.iter().find(|track| track.id == track_id) {
+        Some(track) => {
+            render_track(track, position, samples_per_beat, sample_rate, out);
+        }
+        None => out.fill(0.0),
+    }
+}
+
+/// Source sample at a fractional index (linear interpolation, silent past the end)
+#[inline]
+fn read_source(data: &[f32], position: f64) -> f32 {
+    let index = position as usize;
+    if index + 1 >= data.len() {
+        return 0.0;
+    }
+    let fraction = (position - index as f64) as f32;
+    data[index] + (data[index + 1] - data[index]) * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::loader::LoopMode;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+    /// 120 BPM at 48 kHz
+    const SAMPLES_PER_BEAT: f64 = 24000.0;
+
+    fn sample(data: Vec<f32>, sample_rate: u32) -> Arc<Sample> {
+        let len = data.len();
+        Arc::new(Sample {
+            name: "clip".to_string(),
+            data: SampleData::F32(data.into()),
+            sample_rate,
+            source_channels: 1,
+            loop_mode: LoopMode::Off,
+            loop_start: 0,
+            loop_end: len,
+            reverse: false,
+            volume: 1.0,
+            pan: 0.0,
+            pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: None,
+        })
+    }
+
+    fn player_with(clip: AudioClip) -> ClipPlayer {
+        let mut list = AudioTrackList::new();
+        let track = list.add_track("Audio 1".to_string());
+        list.add_clip(track, clip);
+        let mut player = ClipPlayer::new(SAMPLE_RATE);
+        player.set_tracks(list.snapshot());
+        player
+    }
+
+    #[test]
+    fn test_clip_plays_at_its_timeline_position() {
+        let ramp = sample((0..100).map(|i| i as f32 / 100.0).collect(), 48000);
+        let mut clip = AudioClip::new(PathBuf::from("ramp.wav"), ramp, 10, SAMPLE_RATE);
+        clip.offset = 20;
+        clip.length = 30;
+        let mut player = player_with(clip);
+
+        let mut left = [0.0; 64];
+        let mut right = [0.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+
+        assert_eq!(left[9], 0.0);
+        // The source is read from the offset when the clip starts
+        assert!((left[10] - 0.20).abs() < 1e-6);
+        assert!((right[25] - 0.35).abs() < 1e-6);
+        assert!((left[39] - 0.49).abs() < 1e-6);
+        assert_eq!(left[40], 0.0);
+
+        // Blocks starting inside the clip continue where it is
+        let mut left = [0.0; 8];
+        let mut right = [0.0; 8];
+        player.process(30, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert!((left[0] - 0.40).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_gain_and_fades() {
+        let ones = sample(vec![1.0; 200], 48000);
+        let mut clip = AudioClip::new(PathBuf::from("ones.wav"), ones, 0, SAMPLE_RATE);
+        clip.length = 100;
+        clip.gain = 0.5;
+        clip.fade_in = 10;
+        clip.fade_out = 20;
+        let mut player = player_with(clip);
+
+        let mut left = [0.0; 100];
+        let mut right = [0.0; 100];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+
+        assert_eq!(left[0], 0.0);
+        assert!((left[5] - 0.25).abs() < 1e-6);
+        assert!((left[50] - 0.5).abs() < 1e-6);
+        assert!((right[90] - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_source_rate_is_resampled() {
+        // A 24 kHz source lasts twice as many samples at 48 kHz
+        let ramp = sample((0..50).map(|i| i as f32).collect(), 24000);
+        let clip = AudioClip::new(PathBuf::from("slow.wav"), ramp, 0, SAMPLE_RATE);
+        assert_eq!(clip.length, 100);
+        let mut player = player_with(clip);
+
+        let mut left = [0.0; 8];
+        let mut right = [0.0; 8];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert_eq!(&left[..4], &[0.0, 0.5, 1.0, 1.5]);
+    }
+
+    #[test]
+    fn test_warped_clip_follows_tempo() {
+        use crate::audio::warp::{ClipWarp, WarpMarker};
+
+        // 4 beats at 120 BPM with a marker on beat 2
+        let ramp = sample((0..96000).map(|i| i as f32 / 96000.0).collect(), 48000);
+        let mut clip = AudioClip::new(PathBuf::from("loop.wav"), ramp, 0, SAMPLE_RATE);
+        let marker = |source, beat| WarpMarker { source, beat };
+        let markers = vec![marker(0, 0.0), marker(48000, 2.0), marker(96000, 4.0)];
+        clip.warp = Some(ClipWarp::new(120.0, markers).unwrap());
+
+        // At 60 BPM the clip lasts twice as long and beat 2 falls on 96000
+        let half_tempo = 2.0 * SAMPLES_PER_BEAT;
+        assert_eq!(clip.end(half_tempo), 192000);
+        let mut player = player_with(clip);
+
+        let mut left = [0.0; 4];
+        let mut right = [0.0; 4];
+        player.process(96000, half_tempo, true, &mut left, &mut right, None);
+        assert!((left[0] - 0.5).abs() < 1e-6);
+        // Read at the original speed (the pitch is kept)
+        assert!((left[3] - (48003.0 / 96000.0)).abs() < 1e-6);
+
+        let mut left = [0.0; 4];
+        player.process(192000, half_tempo, true, &mut left, &mut right, None);
+        assert_eq!(left, [0.0; 4]);
+    }
+
+    #[test]
+    fn test_looping_clip_repeats() {
+        let ramp = sample((0..10).map(|i| i as f32 / 10.0).collect(), 48000);
+        let mut clip = AudioClip::new(PathBuf::from("loop.wav"), ramp, 0, SAMPLE_RATE);
+        clip.offset = 2;
+        clip.length = 4;
+        clip.looping = true;
+        let mut player = player_with(clip);
+
+        let mut left = [0.0; 4];
+        let mut right = [0.0; 4];
+        player.process(1001, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        // 1001 is one sample into the 251st repetition
+        for (value, expected) in left.iter().zip([0.3, 0.4, 0.5, 0.2]) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_sidechain_keys_inserts_and_plugins() {
+        use crate::synth::dynamics::DynamicsParams;
+
+        // A quiet track (-20 dB, under the threshold) keyed by a loud synth
+        let quiet = sample(vec![0.1; 48000], 48000);
+        let mut list = AudioTrackList::new();
+        let track = list.add_track("Pad".to_string());
+        list.add_clip(
+            track,
+            AudioClip::new(PathBuf::from("pad.wav"), quiet, 0, SAMPLE_RATE),
+        );
+        list.track_mut(track).unwrap().dynamics = Some(TrackDynamics {
+            params: DynamicsParams::default(),
+            sidechain: Some(SidechainSource::Synth),
+        });
+        let mut player = ClipPlayer::new(SAMPLE_RATE);
+        player.set_tracks(list.snapshot());
+
+        let mut left = vec![1.0; 24000];
+        let mut right = vec![1.0; 24000];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        // 18 dB over the threshold at 4:1: 13.5 dB of reduction on the pad only
+        let pad = left[23999] - 1.0;
+        assert!((20.0 * (pad / 0.1).log10() + 13.5).abs() < 0.1);
+
+        // A muted track still keys the plugin sidechain (pre-fader)
+        list.track_mut(track).unwrap().muted = true;
+        player.set_tracks(list.snapshot());
+        player.set_plugin_sidechain(Some(SidechainSource::AudioTrack(track)));
+        let mut left = [0.5; 8];
+        let mut right = [0.5; 8];
+        let mut key_left = [0.0; 8];
+        let mut key_right = [0.0; 8];
+        let key = Some((&mut key_left[..], &mut key_right[..]));
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, key);
+        assert_eq!(left, [0.5; 8]);
+        assert_eq!(key_right, [0.1; 8]);
+
+        // Removing the source track gives the insert its own key back
+        let keyed = list.add_track("Keyed".to_string());
+        list.track_mut(keyed).unwrap().dynamics = Some(TrackDynamics {
+            params: DynamicsParams::default(),
+            sidechain: Some(SidechainSource::AudioTrack(track)),
+        });
+        list.remove_track(track);
+        assert_eq!(list.track(keyed).unwrap().dynamics.unwrap().sidechain, None);
+    }
+
+    #[test]
+    fn test_sends_feed_return_bus() {
+        use crate::audio::returns::{ReturnEffect, TrackSend};
+        use crate::synth::delay::DelayParams;
+
+        // Fader down: only a pre-fader send reaches the 1 ms delay bus
+        let mut list = AudioTrackList::new();
+        let track = list.add_track("Vocal".to_string());
+        list.add_clip(
+            track,
+            AudioClip::new(
+                PathBuf::from("vocal.wav"),
+                sample(vec![0.1; 4800], 48000),
+                0,
+                SAMPLE_RATE,
+            ),
+        );
+        let vocal = list.track_mut(track).unwrap();
+        vocal.gain = 0.0;
+        vocal.sends[1] = TrackSend {
+            level: 1.0,
+            pre_fader: false,
+        };
+        let mut player = ClipPlayer::new(SAMPLE_RATE);
+        let delay = ReturnEffect::Delay(DelayParams::new(1.0, 0.0, 1.0));
+        player.set_return_bus(1, Some(ReturnBusParams::new(delay)));
+        player.set_tracks(list.snapshot());
+        let mut left = [0.0; 64];
+        let mut right = [0.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert_eq!(left, [0.0; 64]);
+
+        list.track_mut(track).unwrap().sends[1].pre_fader = true;
+        player.set_tracks(list.snapshot());
+        let mut left = [0.0; 64];
+        let mut right = [0.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert_eq!(left[47], 0.0);
+        assert!((left[50] - 0.1).abs() < 1e-3 && (right[50] - 0.1).abs() < 1e-3);
+
+        // The synth sends while the transport is stopped; removed buses are silent
+        list.clear_sends(1);
+        assert_eq!(list.track(track).unwrap().sends[1], TrackSend::default());
+        let mut sends = TrackSends::default();
+        sends[1].level = 0.5;
+        player.set_synth_sends(sends);
+        let mut left = [1.0; 64];
+        let mut right = [1.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, false, &mut left, &mut right, None);
+        assert!(left[63] > 1.4);
+        player.set_return_bus(1, None);
+        let mut left = [1.0; 64];
+        let mut right = [1.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, false, &mut left, &mut right, None);
+        assert_eq!(left, [1.0; 64]);
+    }
+
+    #[test]
+    fn test_synth_send_modulation() {
+        use crate::audio::returns::ReturnEffect;
+        use crate::synth::delay::DelayParams;
+        use crate::synth::reverb::ReverbParams;
+
+        let mut player = ClipPlayer::new(SAMPLE_RATE);
+        let delay = ReturnEffect::Delay(DelayParams::default());
+        let reverb = ReturnEffect::Reverb(ReverbParams::default());
+        player.set_return_bus(0, Some(ReturnBusParams::new(delay)));
+        player.set_return_bus(1, Some(ReturnBusParams::new(reverb)));
+        let mut sends = TrackSends::default();
+        sends[0].level = 0.5;
+        player.set_synth_sends(sends);
+
+        // Offsets by effect kind, within 0 - 1; slots without a bus are left alone
+        player.set_synth_send_modulation((-0.75, 0.25));
+        let modulated = player.modulated_synth_sends();
+        assert_eq!(modulated[0].level, 0.0);
+        assert_eq!(modulated[1].level, 0.25);
+        assert_eq!(modulated[2].level, 0.0);
+        player.set_synth_send_modulation((0.0, 0.0));
+        assert_eq!(player.modulated_synth_sends(), sends);
+    }
+
+    #[test]
+    fn test_mixer_strips_and_solo() {
+        let clip = AudioClip::new(
+            PathBuf::from("tone.wav"),
+            sample(vec![0.5; 4800], 48000),
+            0,
+            SAMPLE_RATE,
+        );
+        let mut player = player_with(clip);
+        let track = MixerTrack::Audio(player.tracks[0].id);
+
+        // The synth fader also works while the transport is stopped
+        let synth = ChannelStrip {
+            gain: 0.5,
+            pan: 1.0,
+            ..Default::default()
+        };
+        player.set_track_strip(MixerTrack::Synth, synth);
+        let mut left = [1.0; 64];
+        let mut right = [1.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, false, &mut left, &mut right, None);
+        assert_eq!((left[0], right[0]), (0.0, 0.5));
+
+        // Soloing the audio track silences the synth
+        let solo = ChannelStrip {
+            soloed: true,
+            ..Default::default()
+        };
+        player.set_track_strip(track, solo);
+        let mut left = [1.0; 64];
+        let mut right = [1.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert_eq!((left[10], right[10]), (0.5, 0.5));
+
+        // A muted track is silent, even when soloed
+        player.set_track_strip(
+            track,
+            ChannelStrip {
+                muted: true,
+                ..solo
+            },
+        );
+        let mut left = [0.0; 64];
+        let mut right = [0.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert_eq!(left, [0.0; 64]);
+
+        // A new snapshot brings the strips of its tracks
+        let mut list = AudioTrackList::new();
+        list.add_track("Audio 1".to_string());
+        player.set_tracks(list.snapshot());
+        let mut left = [1.0; 64];
+        let mut right = [1.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, false, &mut left, &mut right, None);
+        assert_eq!((left[0], right[0]), (0.0, 0.5));
+    }
+
+    #[test]
+    fn test_direct_outputs_bypass_master() {
+        use crate::audio::dsp_utils::soft_clip;
+
+        let mut list = AudioTrackList::new();
+        let track = list.add_track("Click".to_string());
+        list.add_clip(
+            track,
+            AudioClip::new(
+                PathBuf::from("click.wav"),
+                sample(vec![0.5; 4800], 48000),
+                0,
+                SAMPLE_RATE,
+            ),
+        );
+        list.track_mut(track).unwrap().output = TrackOutput::Direct(1);
+        let mut player = ClipPlayer::new(SAMPLE_RATE).with_output_channels(4, 64);
+        player.set_tracks(list.snapshot());
+        player.set_synth_output(TrackOutput::Direct(1));
+        let mut left = [0.25; 64];
+        let mut right = [0.25; 64];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert_eq!(left, [0.0; 64]);
+        assert!(player.direct_outputs().is_active());
+        let mut frame = [0.0f32; 4];
+        player
+            .direct_outputs()
+            .write_frame(10, (left[10], right[10]), &mut frame);
+        assert_eq!(frame[..2], [0.0, 0.0]);
+        assert!((frame[2] - soft_clip(0.75)).abs() < 1e-6);
+
+        // A stereo device plays every track on the master bus
+        let mut player = ClipPlayer::new(SAMPLE_RATE).with_output_channels(2, 64);
+        player.set_tracks(list.snapshot());
+        let mut left = [0.0; 64];
+        let mut right = [0.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert!(!player.direct_outputs().is_active());
+        assert!((left[10] - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_surround_panner_feeds_rear_channels() {
+        let mut list = AudioTrackList::new();
+        let track = list.add_track("Ambience".to_string());
+        list.add_clip(
+            track,
+            AudioClip::new(
+                PathBuf::from("ambience.wav"),
+                sample(vec![0.5; 4800], 48000),
+                0,
+                SAMPLE_RATE,
+            ),
+        );
+        list.track_mut(track).unwrap().surround = Some(SurroundPan {
+            x: 1.0,
+            y: 1.0,
+            ..SurroundPan::default()
+        });
+        let mut player = ClipPlayer::new(SAMPLE_RATE).with_output_channels(4, 64);
+        player.set_speaker_layout(SpeakerLayout::Quad);
+        player.set_tracks(list.snapshot());
+        let mut left = [0.0; 64];
+        let mut right = [0.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert!(left[10].abs() < 1e-6 && right[10].abs() < 1e-6);
+        let mut frame = [0.0f32; 4];
+        player.surround_outputs().write_frame(10, 1.0, &mut frame);
+        assert!(frame[2].abs() < 1e-6);
+        assert!((frame[3] - crate::audio::dsp_utils::soft_clip(0.5)).abs() < 1e-6);
+
+        // A stereo device folds the layout down: the panner only pans left/right
+        let mut player = ClipPlayer::new(SAMPLE_RATE).with_output_channels(2, 64);
+        player.set_speaker_layout(SpeakerLayout::Quad);
+        player.set_tracks(list.snapshot());
+        let mut left = [0.0; 64];
+        let mut right = [0.0; 64];
+        player.process(0, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert!(!player.surround_outputs().is_active());
+        assert!(left[10].abs() < 1e-6 && (right[10] - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_clamp_and_list_edits() {
+        let ones = sample(vec![1.0; 100], 48000);
+        let mut clip = AudioClip::new(PathBuf::from("ones.wav"), ones, 0, SAMPLE_RATE);
+        clip.offset = 80;
+        clip.length = 50;
+        clip.gain = 4.0;
+        clip.fade_in = 15;
+        clip.fade_out = 15;
+        clip.clamp(SAMPLE_RATE);
+        assert_eq!((clip.length, clip.fade_in, clip.fade_out), (20, 15, 5));
+        assert_eq!(clip.gain, MAX_CLIP_GAIN);
+
+        let mut list = AudioTrackList::new();
+        let first = list.add_track("Audio 1".to_string());
+        let second = list.add_track("Audio 2".to_string());
+        let a = list.add_clip(first, clip.clone()).unwrap();
+        let b = list.add_clip(second, clip.clone()).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(list.add_clip(99, clip), None);
+
+        list.clip_mut(b).unwrap().start = 1000;
+        assert_eq!(list.track(second).unwrap().clips[0].start, 1000);
+        assert_eq!(list.remove_clip(a).map(|clip| clip.id), Some(a));
+        assert!(list.clip(a).is_none());
+
+        // A muted track is silent
+        list.track_mut(second).unwrap().muted = true;
+        let mut player = ClipPlayer::new(SAMPLE_RATE);
+        player.set_tracks(list.snapshot());
+        let mut left = [0.0; 8];
+        let mut right = [0.0; 8];
+        player.process(1000, SAMPLES_PER_BEAT, true, &mut left, &mut right, None);
+        assert_eq!(left, [0.0; 8]);
+    }
+
+    #[test]
+    fn test_repitched_clip_keeps_its_length() {
+        let tone = sample(
+            (0..4800)
+                .map(|i| (std::f32::consts::TAU * 440.0 * i as f32 / SAMPLE_RATE).sin())
+                .collect(),
+            48000,
+        );
+        let mut clip = AudioClip::new(PathBuf::from("tone.wav"), tone.clone(), 0, SAMPLE_RATE);
+        clip.set_pitch(7.0).unwrap();
+        assert_eq!(clip.pitch(), 7.0);
+        assert_eq!(clip.sample.len(), 4800);
+        assert!(!Arc::ptr_eq(&clip.sample, &tone));
+        assert!(clip.set_pitch(48.0).is_err());
+        assert_eq!(clip.pitch(), 7.0);
+
+        // Back to 0: the original audio
+        clip.set_pitch(0.0).unwrap();
+        let (SampleData::F32(data), SampleData::F32(original)) = (&clip.sample.data, &tone.data);
+        assert_eq!(&data[..], &original[..]);
+    }
+}
@@ -2,10 +2,32 @@
 //
 // This module monitors the CPU load of the audio callback to prevent dropouts.
 // Uses atomics for thread-safe metric sharing between audio and UI threads.
+//
+// It also counts xruns: callbacks that exceed their deadline (processing took
+// longer than the buffer duration) and underruns (the device waited longer
+// than one buffer for the next callback, so the output was starved).
+//
+// Besides the average load since the last reset, a recent load (smoothed over
+// `RECENT_LOAD_TIME_CONSTANT`) drives the adaptive voice limit, which the audio
+// thread reports back here for display.
 
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
-use std::time::Instant;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// A callback interval longer than this many buffer durations is an underrun
+///
+/// Callbacks are not perfectly periodic: some jitter is tolerated.
+const UNDERRUN_TOLERANCE: f64 = 1.5;
+
+/// Time constant of the recent load (seconds)
+const RECENT_LOAD_TIME_CONSTANT: f64 = 0.5;
+
+/// Window used to detect xrun bursts
+pub const XRUN_BURST_WINDOW: Duration = Duration::from_secs(2);
+
+/// Number of xruns within `XRUN_BURST_WINDOW` considered a burst
+pub const XRUN_BURST_THRESHOLD: u64 = 3;
 
 /// CPU monitor for audio callback
 ///
@@ -20,6 +42,17 @@ pub struct CpuMonitor {
     total_available_time_ns: Arc<AtomicU64>,
     sample_count: Arc<AtomicU64>,
 
+    // Xrun counters (checked on every callback)
+    deadline_overruns: Arc<AtomicU64>,
+    underruns: Arc<AtomicU64>,
+
+    // Recent load in percent (f32 bits, updated on every callback)
+    recent_load: Arc<AtomicU32>,
+
+    // Voice limit reported by the audio thread
+    voice_limit: Arc<AtomicUsize>,
+    max_voices: Arc<AtomicUsize>,
+
     // Configuration
     sample_rate: f32,
     buffer_size: usize,
@@ -41,6 +74,11 @@ impl CpuMonitor {
             total_callback_time_ns: Arc::new(AtomicU64::new(0)),
             total_available_time_ns: Arc::new(AtomicU64::new(0)),
             sample_count: Arc::new(AtomicU64::new(0)),
+            deadline_overruns: Arc::new(AtomicU64::new(0)),
+            underruns: Arc::new(AtomicU64::new(0)),
+            recent_load: Arc::new(AtomicU32::new(0.0f32.to_bits())),
+            voice_limit: Arc::new(AtomicUsize::new(0)),
+            max_voices: Arc::new(AtomicUsize::new(0)),
             sample_rate,
             buffer_size,
             measure_every_n: measure_every_n.max(1),
@@ -85,6 +123,82 @@ impl CpuMonitor {
         }
     }
 
+    /// Check a callback against its deadline (call at callback end, on every callback)
+    ///
+    /// # Arguments
+    /// * `elapsed` - Time spent in the callback
+    /// * `frames` - Number of frames rendered by the callback
+    ///
+    /// Returns `true` (and counts an overrun) if the callback took longer than
+    /// the duration of the audio it rendered. Also updates the recent load.
+    #[inline]
+    pub fn check_deadline(&self, elapsed: Duration, frames: usize) -> bool {
+        let buffer_duration = self.buffer_duration(frames);
+        self.update_recent_load(elapsed, buffer_duration);
+
+        let overrun = elapsed > buffer_duration;
+        if overrun {
+            self.deadline_overruns.fetch_add(1, Ordering::Relaxed);
+        }
+        overrun
+    }
+
+    /// Smooth the load of one callback into the recent load
+    ///
+    /// Only the audio thread writes it, so a load/store pair is enough.
+    #[inline]
+    fn update_recent_load(&self, elapsed: Duration, buffer_duration: Duration) {
+        let buffer_secs = buffer_duration.as_secs_f64();
+        if buffer_secs <= 0.0 {
+            return;
+        }
+        let load = elapsed.as_secs_f64() / buffer_secs * 100.0;
+        let alpha = (buffer_secs / RECENT_LOAD_TIME_CONSTANT).min(1.0);
+
+        let previous = f32::from_bits(self.recent_load.load(Ordering::Relaxed)) as f64;
+        let recent = previous + (load - previous) * alpha;
+        self.recent_load
+            .store((recent as f32).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Check the interval between the starts of two consecutive callbacks
+    ///
+    /// # Arguments
+    /// * `interval` - Stream time elapsed since the previous callback
+    /// * `previous_frames` - Number of frames rendered by the previous callback
+    ///
+    /// Returns `true` (and counts an underrun) if the device had to wait
+    /// noticeably longer than the previous buffer lasted.
+    #[inline]
+    pub fn check_callback_interval(&self, interval: Duration, previous_frames: usize) -> bool {
+        let expected = self.buffer_duration(previous_frames);
+        let underrun = interval.as_secs_f64() > expected.as_secs_f64() * UNDERRUN_TOLERANCE;
+        if underrun {
+            self.underruns.fetch_add(1, Ordering::Relaxed);
+        }
+        underrun
+    }
+
+    /// Duration of `frames` frames of audio
+    #[inline]
+    fn buffer_duration(&self, frames: usize) -> Duration {
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Get the xrun counters
+    pub fn xrun_stats(&self) -> XrunStats {
+        XrunStats {
+            deadline_overruns: self.deadline_overruns.load(Ordering::Relaxed),
+            underruns: self.underruns.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Reset the xrun counters only
+    pub fn reset_xruns(&self) {
+        self.deadline_overruns.store(0, Ordering::Relaxed);
+        self.underruns.store(0, Ordering::Relaxed);
+    }
+
     /// Get current CPU percentage
     ///
     /// Returns an f32 between 0.0 and 100.0+ (can exceed 100% if overloaded)
@@ -110,6 +224,8 @@ impl CpuMonitor {
         self.total_available_time_ns.store(0, Ordering::Relaxed);
         self.sample_count.store(0, Ordering::Relaxed);
         self.current_count.store(0, Ordering::Relaxed);
+        self.recent_load.store(0.0f32.to_bits(), Ordering::Relaxed);
+        self.reset_xruns();
     }
 
     /// Get load level (for UI display)
@@ -119,14 +235,34 @@ impl CpuMonitor {
     /// - `CpuLoad::Medium` if 50-75%
     /// - `CpuLoad::High` if > 75%
     pub fn get_load_level(&self) -> CpuLoad {
-        let cpu = self.get_cpu_percentage();
+        CpuLoad::from_percentage(self.get_cpu_percentage())
+    }
 
-        if cpu < 50.0 {
-            CpuLoad::Low
-        } else if cpu < 75.0 {
-            CpuLoad::Medium
-        } else {
-            CpuLoad::High
+    /// Get the recent CPU percentage (smoothed over the last ~0.5 s)
+    ///
+    /// Unlike `get_cpu_percentage`, it follows load changes quickly and is
+    /// updated on every callback (by `check_deadline`).
+    pub fn get_recent_cpu_percentage(&self) -> f32 {
+        f32::from_bits(self.recent_load.load(Ordering::Relaxed))
+    }
+
+    /// Get the recent load level (same thresholds as `get_load_level`)
+    pub fn get_recent_load_level(&self) -> CpuLoad {
+        CpuLoad::from_percentage(self.get_recent_cpu_percentage())
+    }
+
+    /// Report the voice limit in use (called from the audio thread)
+    #[inline]
+    pub fn report_voice_limit(&self, limit: usize, max_voices: usize) {
+        self.voice_limit.store(limit, Ordering::Relaxed);
+        self.max_voices.store(max_voices, Ordering::Relaxed);
+    }
+
+    /// Get the voice limit last reported by the audio thread
+    pub fn voice_limit(&self) -> VoiceLimitStatus {
+        VoiceLimitStatus {
+            limit: self.voice_limit.load(Ordering::Relaxed),
+            max_voices: self.max_voices.load(Ordering::Relaxed),
         }
     }
 
@@ -158,6 +294,108 @@ pub enum CpuLoad {
     High,   // > 75% (red)
 }
 
+impl CpuLoad {
+    /// Load level of a CPU percentage
+    pub fn from_percentage(cpu: f32) -> Self {
+        if cpu < 50.0 {
+            CpuLoad::Low
+        } else if cpu < 75.0 {
+            CpuLoad::Medium
+        } else {
+            CpuLoad::High
+        }
+    }
+}
+
+/// Voice limit of the voice manager (snapshot for UI display)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VoiceLimitStatus {
+    /// Voices currently allowed
+    pub limit: usize,
+    /// User-configured maximum
+    pub max_voices: usize,
+}
+
+impl VoiceLimitStatus {
+    /// Whether the limit was lowered below the maximum because of CPU load
+    pub fn is_limited(&self) -> bool {
+        self.limit < self.max_voices
+    }
+}
+
+/// Xrun counters (snapshot for UI display)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct XrunStats {
+    /// Callbacks that took longer than the buffer duration
+    pub deadline_overruns: u64,
+    /// Output starved between two callbacks
+    pub underruns: u64,
+}
+
+impl XrunStats {
+    /// Total number of xruns
+    pub fn total(&self) -> u64 {
+        self.deadline_overruns + self.underruns
+    }
+}
+
+/// Detects xrun bursts by polling `XrunStats` (UI side)
+///
+/// Isolated xruns are only counted; a burst (`threshold` xruns within
+/// `window`) is reported once per window so the UI can notify the user.
+pub struct XrunBurstDetector {
+    window: Duration,
+    threshold: u64,
+    window_start: Option<Instant>,
+    window_start_total: u64,
+    reported: bool,
+}
+
+impl XrunBurstDetector {
+    pub fn new(window: Duration, threshold: u64) -> Self {
+        Self {
+            window,
+            threshold: threshold.max(1),
+            window_start: None,
+            window_start_total: 0,
+            reported: false,
+        }
+    }
+
+    /// Feed the current counters
+    ///
+    /// Returns the number of xruns in the current window when it first
+    /// reaches the burst threshold, `None` otherwise.
+    pub fn poll(&mut self, stats: XrunStats, now: Instant) -> Option<u64> {
+        let total = stats.total();
+        let window_expired = self
+            .window_start
+            .is_none_or(|start| now.duration_since(start) >= self.window);
+
+        // New window (also when the counters were reset)
+        if window_expired || total < self.window_start_total {
+            self.window_start = Some(now);
+            self.window_start_total = total;
+            self.reported = false;
+            return None;
+        }
+
+        let count = total - self.window_start_total;
+        if count >= self.threshold && !self.reported {
+            self.reported = true;
+            Some(count)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for XrunBurstDetector {
+    fn default() -> Self {
+        Self::new(XRUN_BURST_WINDOW, XRUN_BURST_THRESHOLD)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -234,4 +472,69 @@ mod tests {
         // Initial level (Low)
         assert_eq!(monitor.get_load_level(), CpuLoad::Low);
     }
+
+    #[test]
+    fn test_xrun_detection() {
+        // 512 frames at 48 kHz = 10.67 ms
+        let monitor = CpuMonitor::new(48000.0, 512, 10);
+
+        assert!(!monitor.check_deadline(Duration::from_millis(5), 512));
+        assert!(monitor.check_deadline(Duration::from_millis(12), 512));
+        // A smaller buffer has a shorter deadline
+        assert!(monitor.check_deadline(Duration::from_millis(5), 128));
+
+        // Jitter is tolerated, a missed period is not
+        assert!(!monitor.check_callback_interval(Duration::from_millis(13), 512));
+        assert!(monitor.check_callback_interval(Duration::from_millis(22), 512));
+
+        let stats = monitor.xrun_stats();
+        assert_eq!(stats.deadline_overruns, 2);
+        assert_eq!(stats.underruns, 1);
+        assert_eq!(stats.total(), 3);
+
+        monitor.reset_xruns();
+        assert_eq!(monitor.xrun_stats(), XrunStats::default());
+    }
+
+    #[test]
+    fn test_recent_load_follows_callback_time() {
+        // 480 frames at 48 kHz = 10 ms
+        let monitor = CpuMonitor::new(48000.0, 480, 10);
+
+        // 9 ms per 10 ms buffer for 3 s
+        for _ in 0..300 {
+            monitor.check_deadline(Duration::from_millis(9), 480);
+        }
+        assert!((monitor.get_recent_cpu_percentage() - 90.0).abs() < 1.0);
+        assert_eq!(monitor.get_recent_load_level(), CpuLoad::High);
+
+        // Back to 2 ms per buffer for 2 s
+        for _ in 0..200 {
+            monitor.check_deadline(Duration::from_millis(2), 480);
+        }
+        assert_eq!(monitor.get_recent_load_level(), CpuLoad::Low);
+    }
+
+    #[test]
+    fn test_xrun_burst_detector() {
+        let mut detector = XrunBurstDetector::new(Duration::from_secs(2), 3);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let stats = |underruns| XrunStats {
+            deadline_overruns: 0,
+            underruns,
+        };
+
+        assert_eq!(detector.poll(stats(0), at(0)), None);
+        assert_eq!(detector.poll(stats(2), at(500)), None);
+        assert_eq!(detector.poll(stats(3), at(1000)), Some(3));
+        // Reported once per window
+        assert_eq!(detector.poll(stats(5), at(1500)), None);
+
+        // Isolated xruns in the next window are not a burst
+        assert_eq!(detector.poll(stats(5), at(2000)), None);
+        assert_eq!(detector.poll(stats(6), at(3000)), None);
+        assert_eq!(detector.poll(stats(8), at(4000)), None);
+        assert_eq!(detector.poll(stats(11), at(5000)), Some(3));
+    }
 }
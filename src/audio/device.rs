@@ -49,6 +49,29 @@ impl AudioDeviceManager {
         devices
     }
 
+    /// Liste les périphériques d'entrée audio (enregistrement)
+    pub fn list_input_devices(&self) -> Vec<AudioDeviceInfo> {
+        let default_name = self
+            .host
+            .default_input_device()
+            .and_then(|d| d.name().ok())
+            .unwrap_or_default();
+
+        let mut devices = Vec::new();
+        if let Ok(input_devices) = self.host.input_devices() {
+            for (index, device) in input_devices.enumerate() {
+                if let Ok(name) = device.name() {
+                    devices.push(AudioDeviceInfo {
+                        id: format!("audio_in_{}", index),
+                        is_default: name == default_name,
+                        name,
+                    });
+                }
+            }
+        }
+        devices
+    }
+
     /// Récupère le périphérique de sortie par défaut
     pub fn get_default_output_device(&self) -> Option<Device> {
         self.host.default_output_device()
@@ -46,6 +46,7 @@ pub fn hard_clip(x: f32) -> f32 {
 ///
 /// Formule: y[n] = y[n-1] + α * (x[n] - y[n-1])
 /// où α contrôle la vitesse de convergence.
+#[derive(Debug, Clone)]
 pub struct OnePoleSmoother {
     current: f32,
     coefficient: f32,
@@ -99,6 +100,12 @@ impl OnePoleSmoother {
     pub fn get(&self) -> f32 {
         self.current
     }
+
+    /// Smoothing coefficient α (fraction of the gap covered per sample)
+    #[inline]
+    pub fn coefficient(&self) -> f32 {
+        self.coefficient
+    }
 }
 
 #[cfg(test)]
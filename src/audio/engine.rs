@@ -22,92 +22,427 @@
 // la reconnexion automatique via un thread de monitoring (comme pour MIDI).
 // L'error callback détecte les erreurs et envoie des notifications à l'UI,
 // mais la reconnexion doit être gérée manuellement.
+//
+// # Null Backend
+//
+// Sans la feature `audio-backend` (builds headless), le même callback est
+// appelé par `NullOutput` au rythme d'un device, sans sortie audio.
 
+#[cfg(feature = "audio-backend")]
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, FromSample, SampleFormat, SizedSample, Stream, StreamConfig};
-use std::sync::{Arc, Mutex};
+#[cfg(feature = "audio-backend")]
+use cpal::{Device, SampleFormat, SizedSample, Stream, StreamConfig};
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
 
 use crate::audio::cpu_monitor::CpuMonitor;
-use crate::audio::dsp_utils::{OnePoleSmoother, flush_denormals_to_zero, soft_clip};
-use crate::audio::format_conversion::write_stereo_to_interleaved_frame;
-use crate::audio::parameters::AtomicF32;
-use crate::audio::profiling::{global_profiler, profile_operation};
+use crate::audio::denormals::ScopedNoDenormals;
+use crate::audio::dsp_utils::soft_clip;
+use crate::audio::smoothing::{ParamKind, SmoothedParam};
+use crate::audio::format_conversion::{OutputSample, write_stereo_to_interleaved_frame};
+use crate::audio::clip_player::ClipPlayer;
+use crate::automation::{AutomationParameter, AutomationPlayer};
+use crate::audio::garbage::{GARBAGE_CHUTE_CAPACITY, Garbage, GarbageChute, GarbageCollector};
+use crate::audio::instruments::InstrumentRack;
+#[cfg(not(feature = "audio-backend"))]
+use crate::audio::null_backend::{
+    NULL_BUFFER_FRAMES, NULL_CHANNELS, NULL_SAMPLE_RATE, NullOutput,
+};
+use crate::audio::parameters::{AtomicF32, SynthParameters};
+use crate::audio::profiling::{global_profiler, profile_operation, section};
+use crate::audio::rt_log::start_rt_log_thread;
+use crate::audio::snapshot::{AtomicEngineSnapshot, EngineSnapshot, hold_peak};
+use crate::audio::tap::AudioTap;
+use crate::audio::thread_priority::{self, ThreadKind};
+use crate::audio::watchdog::{AudioWatchdog, Heartbeat, WatchdogFault, WatchdogMonitor};
 use crate::connection::status::{AtomicDeviceStatus, DeviceStatus};
-use crate::messaging::channels::{CommandConsumer, NotificationProducer};
+use crate::messaging::channels::{CommandConsumer, FeedbackProducer, NotificationProducer};
 use crate::messaging::command::Command;
+use crate::messaging::feedback::{
+    CommandKind, EngineFeedback, RejectReason, StateCorrection, send_feedback,
+};
 use crate::messaging::notification::{Notification, NotificationCategory};
-use crate::midi::event::{MidiEvent, MidiEventTimed};
-use crate::sequencer::metronome::{Metronome, MetronomeScheduler};
+use crate::midi::event::{MOD_WHEEL_CC, MidiEvent, MidiEventTimed, SUSTAIN_PEDAL_CC};
+use crate::sequencer::TrackSources;
+use crate::sequencer::arrangement::SYNTH_TRACK;
+use crate::sequencer::metronome::{CountIn, Metronome, MetronomeScheduler};
 use crate::sequencer::timeline::{Tempo, TimeSignature};
+use crate::synth::master_chain::{GainReduction, MasterChain, MasterChainParams};
+use crate::synth::voice::VOICE_BLOCK_SIZE;
 use crate::synth::voice_manager::VoiceManager;
 use crate::plugin::PluginHost;
+use crate::audio::buffer::AudioBuffer;
+use crate::audio::outputs::TrackOutput;
+use crate::audio::routing::MixerTrack;
+use crate::audio::recorder::Recorder;
+#[cfg(feature = "audio-backend")]
+use crate::audio::recorder::{INPUT_RING_CAPACITY, InputCapture, recording_channel};
+
+/// Largest block the plugin sidechain key and the direct outputs are preallocated
+/// for (the plugin buffer pools' size)
+const MAX_BLOCK_FRAMES: usize = 8192;
+
+/// Level of the metronome bus at full metronome volume
+const METRONOME_BUS_LEVEL: f32 = 0.3;
+
+/// How long a device switch waits for the stopped callback to hand its state back
+const CORE_HANDBACK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Output device selection (e.g. from the user preferences)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputOptions {
+    /// Output device name (None = default output device)
+    pub device_name: Option<String>,
+    /// Buffer size in frames (None = device default)
+    pub buffer_size: Option<u32>,
+    /// Output channels (None = device default)
+    pub channels: Option<u16>,
+}
 
-pub struct AudioEngine {
-    _device: Device,
+impl From<&crate::config::AudioPreferences> for OutputOptions {
+    fn from(preferences: &crate::config::AudioPreferences) -> Self {
+        Self {
+            device_name: preferences.output_device.clone(),
+            buffer_size: preferences.buffer_size,
+            channels: preferences.output_channels,
+        }
+    }
+}
+
+/// Input device selection for recording
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputOptions {
+    /// Input device name (None = default input device)
+    pub device_name: Option<String>,
+    /// Recorded channel, from 0 (None = mix of all channels)
+    pub channel: Option<u16>,
+}
+
+impl From<&crate::config::AudioPreferences> for InputOptions {
+    fn from(preferences: &crate::config::AudioPreferences) -> Self {
+        Self {
+            device_name: preferences.input_device.clone(),
+            channel: preferences.input_channel,
+        }
+    }
+}
+
+/// Audio input stream feeding a recorder (see `audio::recorder`)
+///
+/// Runs beside the output stream, on its own device clock. Dropping it closes
+/// the input.
+pub struct AudioInput {
+    #[cfg(feature = "audio-backend")]
     _stream: Stream,
+    sample_rate: u32,
+    channels: u16,
+    /// `Error` once the stream failed (device unplugged): reopen the input
+    pub status: AtomicDeviceStatus,
+}
+
+pub struct AudioEngine {
+    /// Detects a stalled or overloaded callback (declared first: stopped before the stream)
+    watchdog: Option<AudioWatchdog>,
+    /// Stream running the audio callback (None after a failed device switch)
+    output: Option<OutputStream>,
+    /// Output device, buffer size and channels of the stream
+    options: OutputOptions,
     sample_rate: f32,
     pub volume: AtomicF32,
+    /// Continuous synth parameters (filter, LFO), read once per buffer
+    pub synth_params: SynthParameters,
     pub cpu_monitor: CpuMonitor,
+    /// Engine state published by the callback (playhead, voices, CPU, peaks)
+    pub snapshot: AtomicEngineSnapshot,
+    /// Master output (mono) for the tuner and analyzers, fed while listened to
+    pub master_tap: AudioTap,
     pub status: AtomicDeviceStatus,
     pub plugin_host: Arc<PluginHost>,
+    notification_tx: Arc<Mutex<NotificationProducer>>,
+    /// Callback state handed back when the stream is dropped (device switch)
+    core_tx: mpsc::Sender<RenderCore>,
+    core_rx: mpsc::Receiver<RenderCore>,
+    /// Callback state waiting for a stream (the last device switch failed)
+    parked_core: Option<RenderCore>,
+    /// Frees what the audio callback discards (declared after the stream so it outlives it)
+    garbage_collector: GarbageCollector,
+}
+
+/// Stream running the audio callback (the callback is dropped with it)
+struct OutputStream {
+    #[cfg(feature = "audio-backend")]
+    _device: Device,
+    #[cfg(feature = "audio-backend")]
+    _stream: Stream,
+    /// Drives the callback when built without an audio backend
+    #[cfg(not(feature = "audio-backend"))]
+    _output: NullOutput,
+}
+
+/// Output device opened for a stream, with the format the callback renders
+struct OpenedOutput {
+    #[cfg(feature = "audio-backend")]
+    device: Device,
+    #[cfg(feature = "audio-backend")]
+    config: StreamConfig,
+    #[cfg(feature = "audio-backend")]
+    sample_format: SampleFormat,
+    sample_rate: f32,
+    channels: usize,
+    buffer_frames: usize,
+}
+
+impl OpenedOutput {
+    fn open(options: &OutputOptions) -> Result<Self, String> {
+        #[cfg(feature = "audio-backend")]
+        {
+            let (device, config, sample_format) = AudioEngine::open_output_device(options)?;
+            Ok(Self {
+                sample_rate: config.sample_rate.0 as f32,
+                channels: config.channels as usize,
+                // Calculate buffer size (default to 512 if not specified)
+                buffer_frames: match config.buffer_size {
+                    cpal::BufferSize::Fixed(size) => size as usize,
+                    cpal::BufferSize::Default => 512,
+                },
+                device,
+                config,
+                sample_format,
+            })
+        }
+
+        // Headless build: the null backend paces the callback like a device
+        #[cfg(not(feature = "audio-backend"))]
+        {
+            let _ = options;
+            Ok(Self {
+                sample_rate: NULL_SAMPLE_RATE,
+                channels: NULL_CHANNELS,
+                buffer_frames: NULL_BUFFER_FRAMES,
+            })
+        }
+    }
 }
 
 impl AudioEngine {
     pub fn new(
         command_rx_ui: CommandConsumer,
         command_rx_midi: CommandConsumer,
+        feedback_tx: FeedbackProducer,
         notification_tx: Arc<Mutex<NotificationProducer>>,
         plugin_host: Arc<PluginHost>,
     ) -> Result<Self, String> {
-        // Obtenir le host audio par défaut
-        let host = cpal::default_host();
+        // Default volume: 50%
+        Self::with_parameters(
+            command_rx_ui,
+            command_rx_midi,
+            feedback_tx,
+            notification_tx,
+            plugin_host,
+            AtomicF32::new(0.5),
+            SynthParameters::default(),
+        )
+    }
 
-        // Obtenir le device de sortie par défaut
-        let device = host
-            .default_output_device()
-            .ok_or("No audio device found")?;
+    /// Create an engine driven by existing volume and synth parameters
+    ///
+    /// Used when the engine is restarted (e.g. device change) so that frontends
+    /// keep controlling the same atomics.
+    pub fn with_parameters(
+        command_rx_ui: CommandConsumer,
+        command_rx_midi: CommandConsumer,
+        feedback_tx: FeedbackProducer,
+        notification_tx: Arc<Mutex<NotificationProducer>>,
+        plugin_host: Arc<PluginHost>,
+        volume: AtomicF32,
+        synth_params: SynthParameters,
+    ) -> Result<Self, String> {
+        Self::with_options(
+            command_rx_ui,
+            command_rx_midi,
+            feedback_tx,
+            notification_tx,
+            plugin_host,
+            volume,
+            synth_params,
+            &OutputOptions::default(),
+        )
+    }
 
-        println!(
-            "Device audio: {}",
-            device.name().unwrap_or("Unknown".to_string())
-        );
+    /// Create an engine on a chosen output device and buffer size
+    ///
+    /// An unknown device falls back to the default output device; the buffer
+    /// size is clamped to what the device supports. Ignored without an audio backend.
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_options(
+        command_rx_ui: CommandConsumer,
+        command_rx_midi: CommandConsumer,
+        feedback_tx: FeedbackProducer,
+        notification_tx: Arc<Mutex<NotificationProducer>>,
+        plugin_host: Arc<PluginHost>,
+        volume: AtomicF32,
+        synth_params: SynthParameters,
+        options: &OutputOptions,
+    ) -> Result<Self, String> {
+        let opened = OpenedOutput::open(options)?;
+        let sample_rate = opened.sample_rate;
 
-        // Configuration du stream
-        let supported_config = device
-            .default_output_config()
-            .map_err(|e| format!("Erreur de configuration: {}", e))?;
+        // Create CPU monitor (measure 1 out of 10 callbacks to minimize overhead)
+        let cpu_monitor = CpuMonitor::new(sample_rate, opened.buffer_frames, 10);
 
-        let sample_format = supported_config.sample_format();
-        println!("Config audio: {:?}", supported_config);
-        println!("Sample format: {:?}", sample_format);
+        // Create device status (initially connecting, atomic for UI access)
+        let status = AtomicDeviceStatus::new(DeviceStatus::Connecting);
 
-        let sample_rate = supported_config.sample_rate().0 as f32;
-        let channels = supported_config.channels() as usize;
+        // Drain log records emitted from the callback (no I/O on the audio thread)
+        start_rt_log_thread();
 
-        let config: StreamConfig = supported_config.into();
-        let buffer_size = config.buffer_size;
+        // Allocate the profiler (statistics and span ring buffer) before the first callback
+        global_profiler();
+
+        // Return path for objects discarded by the callback (freed off the audio thread)
+        let (garbage_chute, garbage_collector) = GarbageCollector::spawn(GARBAGE_CHUTE_CAPACITY);
 
-        // Calculate buffer size (default to 512 if not specified)
-        let buffer_frames = match buffer_size {
-            cpal::BufferSize::Fixed(size) => size as usize,
-            cpal::BufferSize::Default => 512,
+        // Callback state kept across streams (moved into the callback, no Mutex)
+        let core = RenderCore {
+            command_rx_ui,
+            command_rx_midi,
+            feedback_tx,
+            voice_manager: VoiceManager::new(sample_rate),
+            garbage_chute,
         };
+        let (core_tx, core_rx) = mpsc::channel();
 
-        // Create CPU monitor (measure 1 out of 10 callbacks to minimize overhead)
-        let cpu_monitor = CpuMonitor::new(sample_rate, buffer_frames, 10);
-        let cpu_monitor_clone = cpu_monitor.clone();
+        let mut engine = Self {
+            watchdog: None,
+            output: None,
+            options: options.clone(),
+            sample_rate,
+            volume,
+            synth_params,
+            cpu_monitor,
+            // Published by every callback, read by the frontends
+            snapshot: AtomicEngineSnapshot::new(),
+            master_tap: AudioTap::default(),
+            status,
+            plugin_host,
+            notification_tx,
+            core_tx,
+            core_rx,
+            parked_core: None,
+            garbage_collector,
+        };
+        engine.start_output(opened, core)?;
+        Ok(engine)
+    }
+
+    /// Move the output to another device (None = default output device)
+    ///
+    /// The stream is torn down and rebuilt on the new device. The command
+    /// channels, the synth settings and the sample bank are kept, and the
+    /// voices follow the new sample rate; transport, patterns, clips and mixer
+    /// settings start over, so the frontend sends its state again as after a
+    /// restart. Returns the new sample rate.
+    ///
+    /// If the device cannot be started, the engine stays silent (status `Error`)
+    /// until the next switch.
+    pub fn switch_device(&mut self, device_name: Option<String>) -> Result<f32, String> {
+        // The heartbeat stops with the stream: stop the watchdog first
+        self.watchdog = None;
+        self.output = None;
+
+        let core = match self.parked_core.take() {
+            Some(core) => core,
+            None => match self.core_rx.recv_timeout(CORE_HANDBACK_TIMEOUT) {
+                Ok(core) => core,
+                Err(_) => {
+                    self.status.set(DeviceStatus::Error);
+                    return Err("The audio callback did not hand its state back".to_string());
+                }
+            },
+        };
+
+        self.options.device_name = device_name;
+        self.status.set(DeviceStatus::Connecting);
+        let opened = match OpenedOutput::open(&self.options) {
+            Ok(opened) => opened,
+            Err(e) => {
+                self.parked_core = Some(core);
+                self.status.set(DeviceStatus::Error);
+                return Err(e);
+            }
+        };
+
+        let mut core = core;
+        core.voice_manager.set_sample_rate(opened.sample_rate);
+        self.start_output(opened, core)?;
+        Ok(self.sample_rate)
+    }
+
+    /// Start the audio callback on an opened device, then watch it
+    ///
+    /// On failure the callback state comes back through `core_rx` and is parked.
+    fn start_output(&mut self, opened: OpenedOutput, core: RenderCore) -> Result<(), String> {
+        let (sample_rate, channels, buffer_frames) =
+            (opened.sample_rate, opened.channels, opened.buffer_frames);
+        let heartbeat = Heartbeat::new();
+
+        let output = match self.build_output(opened, core, heartbeat.clone()) {
+            Ok(output) => output,
+            Err(e) => {
+                self.parked_core = self.core_rx.try_recv().ok();
+                self.status.set(DeviceStatus::Error);
+                return Err(e);
+            }
+        };
+        self.output = Some(output);
+        self.sample_rate = sample_rate;
+        self.cpu_monitor.update_config(sample_rate, buffer_frames);
+
+        // Set status to Connected after successful start
+        self.status.set(DeviceStatus::Connected);
+
+        // Watch the callback from now on (stream started)
+        let buffer_duration = Duration::from_secs_f32(buffer_frames as f32 / sample_rate);
+        self.watchdog = Some(AudioWatchdog::spawn(
+            heartbeat,
+            self.cpu_monitor.clone(),
+            self.status.clone(),
+            self.notification_tx.clone(),
+            WatchdogMonitor::stall_timeout_for(buffer_duration),
+        ));
+
+        println!(
+            "Audio engine started: {} Hz, {} canaux",
+            sample_rate, channels
+        );
+
+        // Send success notification
+        if let Ok(mut tx) = self.notification_tx.try_lock() {
+            let notif = Notification::info(
+                NotificationCategory::Audio,
+                format!("Audio connected: {} Hz", sample_rate),
+            );
+            tx.push(notif);
+        }
 
-        // Create atomic volume parameter (shared between UI and audio thread via atomic)
-        let volume = AtomicF32::new(0.5); // Default volume: 50%
-        let volume_clone = volume.clone();
+        Ok(())
+    }
 
-        // Create VoiceManager (will be moved into audio callback)
-        let voice_manager = VoiceManager::new(sample_rate);
+    /// Build and start the stream running the audio callback
+    fn build_output(
+        &self,
+        opened: OpenedOutput,
+        core: RenderCore,
+        heartbeat: Heartbeat,
+    ) -> Result<OutputStream, String> {
+        let (sample_rate, channels) = (opened.sample_rate, opened.channels);
 
         // Create volume smoother (10ms smoothing to avoid clicks, moved into callback)
-        let volume_smoother = OnePoleSmoother::new(
-            0.5,  // Initial value (50%)
-            10.0, // 10ms time constant
+        let volume_smoother = SmoothedParam::new(
+            ParamKind::Volume,
+            0.5, // Initial value (50%)
             sample_rate,
         );
 
@@ -115,71 +450,57 @@ impl AudioEngine {
         let metronome = Metronome::new(sample_rate);
         let metronome_scheduler = MetronomeScheduler::new();
 
-        // Create device status (initially connecting, atomic for UI access)
-        let status = AtomicDeviceStatus::new(DeviceStatus::Connecting);
-        let status_clone = status.clone();
-
-        // Clone notification_tx for the error callback
-        let notification_tx_err = notification_tx.clone();
+        // State moved into the audio callback (no Arc/Mutex except where noted)
+        let render = RenderContext {
+            channels,
+            core: Handback::new(core, self.core_tx.clone()),
+            volume: self.volume.clone(),
+            synth_params: self.synth_params.clone(),
+            volume_smoother,
+            cpu_monitor: self.cpu_monitor.clone(),
+            snapshot: self.snapshot.clone(),
+            master_tap: self.master_tap.clone(),
+            clip_player: ClipPlayer::new(sample_rate)
+                .with_output_channels(channels, MAX_BLOCK_FRAMES),
+            sidechain_left: AudioBuffer::new(MAX_BLOCK_FRAMES),
+            sidechain_right: AudioBuffer::new(MAX_BLOCK_FRAMES),
+            click_buffer: AudioBuffer::new(MAX_BLOCK_FRAMES),
+            metronome,
+            metronome_scheduler,
+            sequencer_player: crate::sequencer::SequencerPlayer::new(sample_rate as f64),
+            automation_player: AutomationPlayer::new(sample_rate as f64),
+            instrument_rack: InstrumentRack::new(sample_rate),
+            sample_rate,
+            plugin_host: self.plugin_host.clone(),
+            heartbeat,
+        };
 
         // Build stream based on the detected sample format
         // Each format gets its own stream with moved values (no Arc/Mutex in callback)
-        let stream = match sample_format {
+        #[cfg(feature = "audio-backend")]
+        let stream = match opened.sample_format {
             SampleFormat::F32 => Self::build_stream::<f32>(
-                &device,
-                &config,
-                channels,
-                command_rx_ui,               // Moved (no Arc/Mutex)
-                command_rx_midi,             // Moved (no Arc/Mutex)
-                voice_manager,               // Moved (no Arc/Mutex)
-                volume_clone,                // Clone (AtomicF32 is Arc internally)
-                volume_smoother,             // Moved (no Arc/Mutex)
-                cpu_monitor_clone,           // Clone (CpuMonitor is Arc internally for stats)
-                status_clone,                // Clone (AtomicDeviceStatus is Arc internally)
-                notification_tx_err,         // Clone (Arc<Mutex> only for error callback)
-                metronome.clone(),           // Clone (for this stream)
-                metronome_scheduler.clone(), // Clone (for this stream)
-                crate::sequencer::SequencerPlayer::new(sample_rate as f64), // New instance
-                sample_rate,                 // Pass sample rate for scheduler
-                plugin_host.clone(),          // Clone for plugin access
+                &opened.device,
+                &opened.config,
+                render,
+                self.status.clone(),          // Clone (AtomicDeviceStatus is Arc internally)
+                self.notification_tx.clone(), // Clone (Arc<Mutex> only for error callback)
             ),
             SampleFormat::I16 => Self::build_stream::<i16>(
-                &device,
-                &config,
-                channels,
-                command_rx_ui,
-                command_rx_midi,
-                voice_manager,
-                volume_clone,
-                volume_smoother,
-                cpu_monitor_clone,
-                status_clone,
-                notification_tx_err,
-                metronome.clone(),
-                metronome_scheduler.clone(),
-                crate::sequencer::SequencerPlayer::new(sample_rate as f64), // New instance
-                sample_rate,
-                plugin_host.clone(),
+                &opened.device,
+                &opened.config,
+                render,
+                self.status.clone(),
+                self.notification_tx.clone(),
             ),
             SampleFormat::U16 => Self::build_stream::<u16>(
-                &device,
-                &config,
-                channels,
-                command_rx_ui,
-                command_rx_midi,
-                voice_manager,
-                volume_clone,
-                volume_smoother,
-                cpu_monitor_clone,
-                status_clone,
-                notification_tx_err,
-                metronome.clone(),
-                metronome_scheduler.clone(),
-                crate::sequencer::SequencerPlayer::new(sample_rate as f64), // New instance
-                sample_rate,
-                plugin_host.clone(),
+                &opened.device,
+                &opened.config,
+                render,
+                self.status.clone(),
+                self.notification_tx.clone(),
             ),
-            _ => {
+            sample_format => {
                 return Err(format!(
                     "Unsupported sample format: {:?}. Supported formats: F32, I16, U16",
                     sample_format
@@ -363,383 +684,1191 @@ impl AudioEngine {
         */
 
         // Start stream
+        #[cfg(feature = "audio-backend")]
         stream
             .play()
             .map_err(|e| format!("Error in stream beginning: {}", e))?;
 
-        // Set status to Connected after successful start
-        status.set(DeviceStatus::Connected);
-
-        println!(
-            "Audio engine started: {} Hz, {} canaux",
-            sample_rate, channels
-        );
+        #[cfg(feature = "audio-backend")]
+        let output = OutputStream {
+            _device: opened.device,
+            _stream: stream,
+        };
 
-        // Send success notification
-        if let Ok(mut tx) = notification_tx.try_lock() {
-            let notif = Notification::info(
-                NotificationCategory::Audio,
-                format!("Audio connected: {} Hz", sample_rate),
-            );
-            let _ = ringbuf::traits::Producer::try_push(&mut *tx, notif);
-        }
+        // No device: the null backend calls the callback from its own thread
+        #[cfg(not(feature = "audio-backend"))]
+        let output = {
+            let mut render = Self::build_render::<f32>(render);
+            OutputStream {
+                _output: NullOutput::spawn(
+                    move |data| render(data, None),
+                    channels,
+                    opened.buffer_frames,
+                    sample_rate,
+                )?,
+            }
+        };
 
-        Ok(Self {
-            _device: device,
-            _stream: stream,
-            sample_rate,
-            volume,
-            cpu_monitor,
-            status,
-            plugin_host,
-        })
+        Ok(output)
     }
 
     pub fn sample_rate(&self) -> f32 {
         self.sample_rate
     }
 
+    /// Take the fault detected by the watchdog, if any
+    ///
+    /// The device status is already `Error`: the caller should restart the engine.
+    pub fn take_watchdog_fault(&self) -> Option<WatchdogFault> {
+        self.watchdog.as_ref()?.take_fault()
+    }
+
+    /// Number of discarded objects that had to be freed on the audio thread (chute full)
+    pub fn garbage_overflow_count(&self) -> u64 {
+        self.garbage_collector.overflow_count()
+    }
+
+    /// Open the output device and its default configuration
+    #[cfg(feature = "audio-backend")]
+    fn open_output_device(
+        options: &OutputOptions,
+    ) -> Result<(Device, StreamConfig, SampleFormat), String> {
+        // Obtenir le host audio par défaut
+        let host = cpal::default_host();
+
+        // Device demandé, sinon le device de sortie par défaut
+        let requested = options.device_name.as_ref().and_then(|name| {
+            let device = host
+                .output_devices()
+                .ok()?
+                .find(|device| device.name().is_ok_and(|n| &n == name));
+            if device.is_none() {
+                eprintln!("⚠️  Audio device '{}' not found, using the default device", name);
+            }
+            device
+        });
+        let device = match requested {
+            Some(device) => device,
+            None => host
+                .default_output_device()
+                .ok_or("No audio device found")?,
+        };
+
+        println!(
+            "Device audio: {}",
+            device.name().unwrap_or("Unknown".to_string())
+        );
+
+        // Configuration du stream
+        let supported_config = device
+            .default_output_config()
+            .map_err(|e| format!("Erreur de configuration: {}", e))?;
+
+        let sample_format = supported_config.sample_format();
+
+        // Other channel count (direct outputs), same sample format and rate
+        let supported_config = match options.channels {
+            Some(channels) if channels != supported_config.channels() => {
+                let rate = supported_config.sample_rate();
+                let range = device.supported_output_configs().ok().and_then(|mut ranges| {
+                    ranges.find(|range| {
+                        range.channels() == channels
+                            && range.sample_format() == sample_format
+                            && range.min_sample_rate() <= rate
+                            && rate <= range.max_sample_rate()
+                    })
+                });
+                match range {
+                    Some(range) => range.with_sample_rate(rate),
+                    None => {
+                        eprintln!(
+                            "⚠️  {} output channels not supported, using {}",
+                            channels,
+                            supported_config.channels()
+                        );
+                        supported_config
+                    }
+                }
+            }
+            _ => supported_config,
+        };
+        println!("Config audio: {:?}", supported_config);
+        println!("Sample format: {:?}", sample_format);
+
+        let buffer_size = options.buffer_size.map(|frames| match supported_config.buffer_size() {
+            cpal::SupportedBufferSize::Range { min, max } => frames.clamp(*min, *max),
+            cpal::SupportedBufferSize::Unknown => frames,
+        });
+        let mut config: StreamConfig = supported_config.into();
+        if let Some(frames) = buffer_size {
+            println!("Buffer size: {} frames", frames);
+            config.buffer_size = cpal::BufferSize::Fixed(frames);
+        }
+
+        Ok((device, config, sample_format))
+    }
+
     /// Build an audio stream with automatic format conversion (RT-safe)
     ///
     /// This is a generic helper that creates a stream for any sample type (f32, i16, u16)
     /// The audio callback generates f32 internally and converts to the target format.
-    ///
-    /// # RT-Safety
-    /// All mutable state is moved into the closure by value (no Arc<Mutex>), ensuring:
-    /// - Zero lock contention
-    /// - Deterministic access times
-    /// - No allocations in the audio callback
-    #[allow(clippy::too_many_arguments)]
+    #[cfg(feature = "audio-backend")]
     fn build_stream<T>(
         device: &Device,
         config: &StreamConfig,
-        channels: usize,
-        mut command_rx_ui: CommandConsumer, // Moved into closure (no Mutex)
-        mut command_rx_midi: CommandConsumer, // Moved into closure (no Mutex)
-        mut voice_manager: VoiceManager,    // Moved into closure (no Mutex)
-        volume: AtomicF32,                  // Clone (Arc internally, read-only atomic)
-        mut volume_smoother: OnePoleSmoother, // Moved into closure (no Mutex)
-        cpu_monitor: CpuMonitor,            // Clone (Arc internally for stats)
+        render: RenderContext,
         status: AtomicDeviceStatus,         // Clone (Arc internally, atomic)
         notification_tx: Arc<Mutex<NotificationProducer>>, // Keep Mutex (only error callback)
-        mut metronome: Metronome,           // Moved into closure (no Mutex)
-        mut metronome_scheduler: MetronomeScheduler, // Moved into closure (no Mutex)
-        mut sequencer_player: crate::sequencer::SequencerPlayer, // Moved into closure (no Mutex)
-        sample_rate: f32,                   // Sample rate for scheduler calculations
-        plugin_host: Arc<PluginHost>,      // Clone for plugin access
     ) -> Result<Stream, String>
     where
-        T: SizedSample + FromSample<f32> + Send + 'static,
+        T: SizedSample + OutputSample,
+    {
+        let mut render = Self::build_render::<T>(render);
+
+        // Stream time of the previous callback (underrun detection)
+        let mut last_callback: Option<cpal::StreamInstant> = None;
+
+        let stream = device
+            .build_output_stream(
+                config,
+                move |data: &mut [T], info: &cpal::OutputCallbackInfo| {
+                    // Time since the previous callback, on the device clock
+                    let callback_time = info.timestamp().callback;
+                    let interval = last_callback
+                        .and_then(|previous| callback_time.duration_since(&previous));
+                    last_callback = Some(callback_time);
+
+                    render(data, interval);
+                },
+                move |err| {
+                    // ========== ERROR CALLBACK ==========
+                    // This runs outside the audio callback, so we can do I/O here
+                    eprintln!("Audio stream error: {}", err);
+
+                    // Set status to Error (atomic operation, safe)
+                    status.set(DeviceStatus::Error);
+
+                    // Send notification to UI (non-blocking)
+                    if let Ok(mut tx) = notification_tx.try_lock() {
+                        let notif = Notification::error(
+                            NotificationCategory::Audio,
+                            format!("Audio stream error: {}", err),
+                        );
+                        tx.push(notif);
+                    }
+                },
+                None,
+            )
+            .map_err(|e| format!("Error in stream creation: {}", e))?;
+
+        Ok(stream)
+    }
+
+    /// Build the audio callback, for any backend and output sample type
+    ///
+    /// The callback renders one interleaved buffer; `interval` is the time since
+    /// the previous callback on the device clock, when the backend has one.
+    ///
+    /// # RT-Safety
+    /// All mutable state is moved into the closure by value (no Arc<Mutex>), ensuring:
+    /// - Zero lock contention
+    /// - Deterministic access times
+    /// - No allocations in the audio callback
+    fn build_render<T>(render: RenderContext) -> impl FnMut(&mut [T], Option<Duration>) + Send + 'static
+    where
+        T: OutputSample,
     {
+        let RenderContext {
+            channels,
+            mut core,
+            volume,
+            synth_params,
+            mut volume_smoother,
+            cpu_monitor,
+            snapshot,
+            master_tap,
+            mut clip_player,
+            mut sidechain_left,
+            mut sidechain_right,
+            mut click_buffer,
+            mut metronome,
+            mut metronome_scheduler,
+            mut sequencer_player,
+            mut automation_player,
+            mut instrument_rack,
+            sample_rate,
+            plugin_host,
+            heartbeat,
+        } = render;
+
         // Sequencer state (captured by closure, persists across callbacks)
         let mut current_position: u64 = 0;
         let mut current_tempo = Tempo::new(120.0);
         let mut current_time_signature = TimeSignature::four_four();
         let mut is_playing = false;
+        let mut count_in = CountIn::new();
+        let mut metronome_output = TrackOutput::Master;
+
+        // Generation of the synth parameters last applied (None: apply on first buffer)
+        let mut applied_params_generation: Option<u64> = None;
 
         // Active pattern for sequencer playback (default: empty pattern)
-        let mut active_pattern = crate::sequencer::Pattern::new_default(1, "Empty".to_string());
+        let mut active_pattern =
+            Arc::new(crate::sequencer::Pattern::new_default(1, "Empty".to_string()));
 
-        let stream = device
-            .build_output_stream(
-                config,
-                move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-                    // ========== SACRED ZONE ==========
-                    // No allocations, No I/O, No blocking locks
+        // Clips played instead of the patterns (song mode)
+        let mut arrangement: Option<Arc<crate::sequencer::Arrangement>> = None;
 
-                    // Start profiling and CPU monitoring
-                    let _callback_timer = global_profiler().start_callback();
-                    let measure_start = cpu_monitor.start_measure();
+        // Clip grid of the session view and the clips launched from it
+        let mut session = Arc::new(crate::sequencer::Session::new());
+        let mut launcher = crate::sequencer::Launcher::new();
 
-                    // helper function to process MIDI events
-                    let process_midi_event =
-                        |timed_event: MidiEventTimed, vm: &mut VoiceManager, plugin_host: &PluginHost| {
-                            // TODO Phase 4+: Implement proper sample-accurate scheduling
-                            // For now, process all events immediately at buffer start
-                            match timed_event.event {
-                                MidiEvent::NoteOn { note, velocity } => {
-                                    vm.note_on(note, velocity);
-                                }
-                                MidiEvent::NoteOff { note } => {
-                                    vm.note_off(note);
-                                }
-                                MidiEvent::ChannelAftertouch { value } => {
-                                    vm.set_aftertouch(value);
-                                }
-                                MidiEvent::PolyAftertouch {
-                                    note: _n,
-                                    value: _v,
-                                } => {
-                                    // TODO: Poly aftertouch per-note support (Phase 2+)
-                                }
-                                _ => {} // Ignore other events for now
-                            }
-                            
-                            // Route MIDI events to all loaded plugins
-                            plugin_host.process_midi_for_all_plugins(&timed_event);
-                        };
+        // Master bus effects (delay lines allocated here, outside the callback)
+        let mut master_chain = MasterChain::new(MasterChainParams::default(), sample_rate);
 
-                    // helper function to process commands
-                    let mut process_command = |cmd: Command, vm: &mut VoiceManager| {
-                        match cmd {
-                            Command::Midi(timed_event) => {
-                                process_midi_event(timed_event, vm, &plugin_host);
-                            }
-                            Command::SetVolume(_vol) => {
-                                // Volume is handled via atomic
-                            }
-                            Command::SetWaveform(waveform) => {
-                                vm.set_waveform(waveform);
-                            }
-                            Command::SetAdsr(adsr_params) => {
-                                vm.set_adsr(adsr_params);
-                            }
-                            Command::SetLfo(lfo_params) => {
-                                vm.set_lfo(lfo_params);
-                            }
-                            Command::SetPolyMode(poly_mode) => {
-                                vm.set_poly_mode(poly_mode);
-                            }
-                            Command::SetPortamento(portamento_params) => {
-                                vm.set_portamento(portamento_params);
-                            }
-                            Command::SetFilter(filter_params) => {
-                                vm.set_filter(filter_params);
-                            }
-                            Command::SetModRouting { index, routing } => {
-                                vm.set_mod_routing(index as usize, routing);
-                            }
-                            Command::ClearModRouting { index } => {
-                                vm.clear_mod_routing(index as usize);
-                            }
-                            Command::SetVoiceMode(mode) => {
-                                vm.set_voice_mode(mode);
-                            }
-                            Command::AddSample(sample) => {
-                                vm.add_sample(sample);
-                            }
-                            Command::RemoveSample(index) => {
-                                vm.remove_sample(index);
-                            }
-                            Command::SetNoteSampleMapping { note, sample_index } => {
-                                vm.set_note_to_sample(note, sample_index);
-                            }
-                            Command::UpdateSample(index, sample) => {
-                                vm.update_sample(index, sample);
-                            }
-                            Command::SetMetronomeEnabled(enabled) => {
-                                metronome.set_enabled(enabled);
-                            }
-                            Command::SetMetronomeVolume(volume) => {
-                                metronome.set_volume(volume);
-                            }
-                            Command::SetTempo(bpm) => {
-                                current_tempo = Tempo::new(bpm);
-                            }
-                            Command::SetTimeSignature(numerator, denominator) => {
-                                current_time_signature = TimeSignature::new(numerator, denominator);
-                            }
-                            Command::SetTransportPlaying(playing) => {
-                                if playing && !is_playing {
-                                    // Starting playback
-                                    is_playing = true;
-                                } else if !playing && is_playing {
-                                    // Stopping playback
-                                    is_playing = false;
-                                    current_position = 0;
-                                    metronome_scheduler.reset();
-                                }
-                            }
-                            Command::SetTransportPosition(position_samples) => {
-                                current_position = position_samples;
-                                metronome_scheduler.reset();
+        // Size of the previous callback (underrun detection)
+        let mut previous_frames: Option<usize> = None;
+
+        // Real-time priority is requested from the first callback (backend thread)
+        let mut priority_requested = false;
+
+        // Output peaks published in the snapshot (held with a release)
+        let mut peak_left = 0.0f32;
+        let mut peak_right = 0.0f32;
+        let mut gain_reduction = GainReduction::default();
+
+        move |data: &mut [T], interval: Option<Duration>| {
+            // ========== SACRED ZONE ==========
+            // No allocations, No I/O, No blocking locks
+            let RenderCore {
+                command_rx_ui,
+                command_rx_midi,
+                feedback_tx,
+                voice_manager,
+                garbage_chute,
+            } = &mut *core;
+
+            // Treat denormals as zero while rendering (restored on return)
+            let _no_denormals = ScopedNoDenormals::new();
+
+            // Tell the watchdog the callback is alive
+            heartbeat.beat();
+
+            if !priority_requested {
+                priority_requested = true;
+                thread_priority::promote_current_thread(ThreadKind::Audio);
+            }
+
+            // Start profiling and CPU monitoring
+            let _callback_timer = global_profiler().start_callback();
+            let measure_start = cpu_monitor.start_measure();
+            let callback_start = Instant::now();
+            let callback_frames = data.len() / channels;
+
+            // Underrun: the device waited too long since the previous callback
+            if let Some(previous_frames) = previous_frames
+                && let Some(interval) = interval
+                && cpu_monitor.check_callback_interval(interval, previous_frames)
+            {
+                crate::rt_warn!(
+                    "engine",
+                    "Output underrun: {} us since previous callback",
+                    interval.as_micros()
+                );
+            }
+            previous_frames = Some(callback_frames);
+
+            // helper function to process MIDI events
+            let process_midi_event =
+                |timed_event: MidiEventTimed, vm: &mut VoiceManager, plugin_host: &PluginHost| {
+                    // TODO Phase 4+: Implement proper sample-accurate scheduling
+                    // For now, process all events immediately at buffer start
+                    match timed_event.event {
+                        MidiEvent::NoteOn { note, velocity } => {
+                            vm.note_on(note, velocity);
+                        }
+                        MidiEvent::NoteOff { note } => {
+                            vm.note_off(note);
+                        }
+                        MidiEvent::ChannelAftertouch { value } => {
+                            vm.set_aftertouch(value);
+                        }
+                        MidiEvent::PitchBend { value } => {
+                            vm.set_pitch_bend(value);
+                        }
+                        MidiEvent::ControlChange {
+                            controller: MOD_WHEEL_CC,
+                            value,
+                        } => {
+                            vm.set_mod_wheel(value);
+                        }
+                        MidiEvent::ControlChange {
+                            controller: SUSTAIN_PEDAL_CC,
+                            value,
+                        } => {
+                            vm.set_sustain_pedal(value >= 64);
+                        }
+                        MidiEvent::ControlChange { controller, value } => {
+                            vm.set_controller(controller, value);
+                        }
+                        MidiEvent::PolyAftertouch {
+                            note: _n,
+                            value: _v,
+                        } => {
+                            // TODO: Poly aftertouch per-note support (Phase 2+)
+                        }
+                    }
+                
+                    // Route MIDI events to all loaded plugins
+                    plugin_host.process_midi_for_all_plugins(&timed_event);
+                };
+
+            // helper function to process commands
+            let mut process_command = |cmd: Command, vm: &mut VoiceManager| {
+                // Outcome of the command, for the acknowledged kinds
+                let kind = CommandKind::of(&cmd);
+                let mut rejection = None;
+                let mut correction = None;
+                // Sampler instrument tracks play the samples of these voices
+                let sample_edit = matches!(
+                    cmd,
+                    Command::AddSample(_)
+                        | Command::RemoveSample(_)
+                        | Command::SetNoteSampleMapping { .. }
+                        | Command::UpdateSample(..)
+                        | Command::InsertSample(..)
+                        | Command::ClearNoteSampleMapping(_)
+                        | Command::SetSampleZone { .. }
+                );
+                match cmd {
+                    Command::Midi(timed_event) => {
+                        process_midi_event(timed_event, vm, &plugin_host);
+                    }
+                    Command::SetVolume(_vol) => {
+                        // Volume is handled via atomic
+                    }
+                    Command::SetWaveform(waveform) => {
+                        vm.set_waveform(waveform);
+                    }
+                    Command::SetOscillator2(params) => {
+                        vm.set_oscillator2(params);
+                    }
+                    Command::SetUnison(params) => {
+                        vm.set_unison(params);
+                    }
+                    Command::SetModLfo { index, params } => {
+                        vm.set_mod_lfo(index as usize, params);
+                    }
+                    Command::SetFilterMod(params) => {
+                        vm.set_filter_mod(params);
+                    }
+                    Command::SetModEnvelope(params) => {
+                        vm.set_mod_envelope(params);
+                    }
+                    Command::SetNoise(params) => {
+                        vm.set_noise(params);
+                    }
+                    Command::SetCrossMod(params) => {
+                        vm.set_cross_mod(params);
+                    }
+                    Command::SetAntiAliasing(anti_aliasing) => {
+                        vm.set_anti_aliasing(anti_aliasing);
+                    }
+                    Command::SetAdsr(adsr_params) => {
+                        vm.set_adsr(adsr_params);
+                    }
+                    Command::SetLfo(lfo_params) => {
+                        vm.set_lfo(lfo_params);
+                    }
+                    Command::SetPolyMode(poly_mode) => {
+                        vm.set_poly_mode(poly_mode);
+                    }
+                    Command::SetPortamento(portamento_params) => {
+                        vm.set_portamento(portamento_params);
+                    }
+                    Command::SetFilter(filter_params) => {
+                        vm.set_filter(filter_params);
+                    }
+                    Command::SetModRouting { index, routing } => {
+                        if !vm.set_mod_routing(index as usize, routing) {
+                            rejection = Some(RejectReason::InvalidRoutingIndex(index));
+                        }
+                    }
+                    Command::ClearModRouting { index } => {
+                        if !vm.clear_mod_routing(index as usize) {
+                            rejection = Some(RejectReason::InvalidRoutingIndex(index));
+                        }
+                    }
+                    Command::SetVoiceMode(mode) => {
+                        vm.set_voice_mode(mode);
+                    }
+                    Command::AddSample(sample) => {
+                        if let Err(sample) = vm.add_sample(sample) {
+                            garbage_chute.dispose(Garbage::Sample(sample));
+                            rejection = Some(RejectReason::SampleSlotsFull);
+                        }
+                    }
+                    Command::RemoveSample(index) => {
+                        match vm.remove_sample(index) {
+                            Some(sample) => garbage_chute.dispose(Garbage::Sample(sample)),
+                            None => rejection = Some(RejectReason::InvalidSampleIndex(index)),
+                        }
+                    }
+                    Command::SetNoteSampleMapping { note, sample_index } => {
+                        if !vm.set_note_to_sample(note, sample_index) {
+                            rejection = Some(RejectReason::InvalidSampleIndex(sample_index));
+                        }
+                    }
+                    Command::InsertSample(index, sample) => {
+                        if let Err(sample) = vm.insert_sample(index, sample) {
+                            garbage_chute.dispose(Garbage::Sample(sample));
+                            rejection = Some(if index > vm.sample_count() {
+                                RejectReason::InvalidSampleIndex(index)
+                            } else {
+                                RejectReason::SampleSlotsFull
+                            });
+                        }
+                    }
+                    Command::ClearNoteSampleMapping(note) => {
+                        vm.clear_note_to_sample(note);
+                    }
+                    Command::SetSampleZone { sample_index, zone } => {
+                        if !vm.set_sample_zone(sample_index, zone) {
+                            rejection = Some(RejectReason::InvalidSampleIndex(sample_index));
+                        }
+                    }
+                    Command::PreviewSample {
+                        sample_index,
+                        note,
+                        looped,
+                    } => {
+                        vm.preview_sample(sample_index, note, looped);
+                    }
+                    Command::StopPreview => {
+                        vm.stop_preview();
+                    }
+                    Command::UpdateSample(index, sample) => {
+                        match vm.update_sample(index, sample) {
+                            Some(old) => garbage_chute.dispose(Garbage::Sample(old)),
+                            None => rejection = Some(RejectReason::InvalidSampleIndex(index)),
+                        }
+                    }
+                    Command::SetMetronomeEnabled(enabled) => {
+                        metronome.set_enabled(enabled);
+                    }
+                    Command::SetMetronomeVolume(volume) => {
+                        metronome.set_volume(volume);
+                    }
+                    Command::SetMetronomeOutput(output) => {
+                        metronome_output = output;
+                    }
+                    Command::SetTempo(bpm) => {
+                        current_tempo = Tempo::new(bpm);
+                    }
+                    Command::SetTimeSignature(numerator, denominator) => {
+                        current_time_signature = TimeSignature::new(numerator, denominator);
+                    }
+                    Command::SetTransportPlaying(playing) => {
+                        count_in.cancel();
+                        if playing && !is_playing {
+                            // Starting playback
+                            is_playing = true;
+                        } else if !playing && is_playing {
+                            // Stopping playback
+                            is_playing = false;
+                            current_position = 0;
+                            metronome_scheduler.reset();
+                        }
+                    }
+                    Command::SetTransportPosition(position_samples) => {
+                        current_position = position_samples;
+                        metronome_scheduler.reset();
+                    }
+                    Command::StartCountIn(bars) => {
+                        if !is_playing {
+                            count_in.start(
+                                bars,
+                                sample_rate as f64,
+                                &current_tempo,
+                                &current_time_signature,
+                            );
+                        }
+                    }
+                    Command::SetPattern(pattern) => {
+                        let old = std::mem::replace(&mut active_pattern, pattern);
+                        garbage_chute.dispose(Garbage::Pattern(old));
+                    }
+                    Command::SetTrackInstrument { track, instrument } => match instrument {
+                        Some(instrument) => {
+                            let bank = &*vm;
+                            if !instrument_rack.set_instrument(track, *instrument, bank, &plugin_host)
+                            {
+                                rejection = Some(RejectReason::InstrumentSlotsFull);
                             }
-                            Command::SetPattern(pattern) => {
-                                active_pattern = pattern;
+                            garbage_chute.dispose(Garbage::Instrument(instrument));
+                        }
+                        None => {
+                            if let Some(old) = instrument_rack.remove(track, &plugin_host) {
+                                garbage_chute.dispose(Garbage::Pattern(old));
                             }
-                            Command::Quit => {}
+                            clip_player.remove_instrument_strip(track);
                         }
-                    };
-
-                    // Process UI commands (direct access, no locks!)
-                    {
-                        let _cmd_timer = profile_operation("process_ui_commands");
-                        while let Some(cmd) = ringbuf::traits::Consumer::try_pop(&mut command_rx_ui) {
-                            process_command(cmd, &mut voice_manager);
+                    },
+                    Command::SetTrackPattern { track, pattern } => {
+                        // An unknown track hands the pattern back
+                        let old = instrument_rack
+                            .set_pattern(track, pattern)
+                            .unwrap_or_else(|pattern| pattern);
+                        garbage_chute.dispose(Garbage::Pattern(old));
+                    }
+                    Command::SetArrangement(clips) => {
+                        if let Some(old) = std::mem::replace(&mut arrangement, clips) {
+                            garbage_chute.dispose(Garbage::Arrangement(old));
                         }
                     }
-
-                    // Process MIDI commands (direct access, no locks!)
-                    {
-                        let _cmd_timer = profile_operation("process_midi_commands");
-                        while let Some(cmd) = ringbuf::traits::Consumer::try_pop(&mut command_rx_midi) {
-                            process_command(cmd, &mut voice_manager);
+                    Command::SetSession(slots) => {
+                        let old = std::mem::replace(&mut session, slots);
+                        garbage_chute.dispose(Garbage::Session(old));
+                    }
+                    Command::LaunchClip { track, scene, at } => {
+                        // The UI mirror refuses the same launches (same capacity)
+                        launcher.launch(track, scene, at);
+                    }
+                    Command::ReleaseSession => launcher.release(),
+                    Command::SetAudioTracks(tracks) => {
+                        let old = clip_player.set_tracks(tracks);
+                        garbage_chute.dispose(Garbage::AudioTracks(old));
+                    }
+                    Command::SetAutomation(lanes) => {
+                        let old = automation_player.set_lanes(lanes);
+                        garbage_chute.dispose(Garbage::Automation(old));
+                        // Parameters that lost their lane go back to their control
+                        applied_params_generation = None;
+                    }
+                    Command::SetSynthDynamics(insert) => {
+                        clip_player.set_synth_dynamics(insert);
+                    }
+                    Command::SetPluginSidechain(source) => {
+                        clip_player.set_plugin_sidechain(source);
+                    }
+                    Command::SetReturnBus { index, params } => {
+                        clip_player.set_return_bus(index, params);
+                    }
+                    Command::SetSynthSends(sends) => {
+                        clip_player.set_synth_sends(sends);
+                    }
+                    Command::SetSynthOutput(output) => {
+                        clip_player.set_synth_output(output);
+                    }
+                    Command::SetTrackStrip { track, strip } => {
+                        clip_player.set_track_strip(track, strip);
+                    }
+                    Command::SetTrackInserts { track, inserts } => match track {
+                        MixerTrack::Instrument(id) => instrument_rack.set_inserts(id, inserts),
+                        _ => clip_player.set_track_inserts(track, inserts),
+                    },
+                    Command::SetSpeakerLayout(layout) => {
+                        clip_player.set_speaker_layout(layout);
+                    }
+                    Command::SetSynthSurround(pan) => {
+                        clip_player.set_synth_surround(pan);
+                    }
+                    Command::SetMasterReverb(params) => {
+                        master_chain.set_reverb(params);
+                    }
+                    Command::SetMasterDelay(params) => {
+                        master_chain.set_delay(params);
+                    }
+                    Command::SetMasterEq(params) => {
+                        master_chain.set_eq(params);
+                    }
+                    Command::SetMasterCompressor(params) => {
+                        master_chain.set_compressor(params);
+                    }
+                    Command::SetMasterLimiter(params) => {
+                        master_chain.set_limiter(params);
+                    }
+                    Command::SetMasterEffectOrder(order) => {
+                        master_chain.set_order(order);
+                    }
+                    Command::SetMaxVoices(max_voices) => {
+                        vm.set_max_voices(max_voices);
+                        if vm.max_voices() != max_voices {
+                            correction = Some(StateCorrection::MaxVoices(vm.max_voices()));
                         }
                     }
+                    Command::SetPitchBendRange(semitones) => {
+                        vm.set_pitch_bend_range(semitones);
+                    }
+                    Command::Quit => {}
+                }
+                if sample_edit {
+                    instrument_rack.share_samples(vm);
+                }
+
+                if let Some(command) = kind {
+                    let feedback = match (rejection, correction) {
+                        (Some(reason), _) => EngineFeedback::Rejected { command, reason },
+                        (None, Some(correction)) => {
+                            EngineFeedback::Corrected { command, correction }
+                        }
+                        (None, None) => EngineFeedback::Applied(command),
+                    };
+                    send_feedback(feedback_tx, feedback);
+                }
+            };
+
+            // Process UI commands (direct access, no locks!)
+            {
+                let _cmd_timer = profile_operation(section::COMMANDS);
+                while let Some(cmd) = ringbuf::traits::Consumer::try_pop(command_rx_ui) {
+                    process_command(cmd, voice_manager);
+                }
+            }
+
+            // Process MIDI commands (direct access, no locks!)
+            {
+                let _cmd_timer = profile_operation(section::COMMANDS);
+                while let Some(cmd) = ringbuf::traits::Consumer::try_pop(command_rx_midi) {
+                    process_command(cmd, voice_manager);
+                }
+            }
 
-                    // Process sequencer pattern (generates MIDI events from notes)
-                    // IMPORTANT: Always call process() even when stopped, so it can send NoteOff events
-                    let buffer_size = data.len() / channels;
+            // Continuous parameters (after commands: the atomics hold the newest values)
+            let params_generation = synth_params.generation();
+            if applied_params_generation != Some(params_generation) {
+                applied_params_generation = Some(params_generation);
+                synth_params.apply_to(voice_manager);
+                automation_player.invalidate();
+            }
+            voice_manager.set_tempo(current_tempo);
+
+            // Process sequencer pattern (generates MIDI events from notes)
+            // IMPORTANT: Always call process() even when stopped, so it can send NoteOff events
+            let buffer_size = data.len() / channels;
+
+            // Count-in before recording: clicks while the timeline stands still,
+            // then the transport starts on the buffer after its last bar
+            if count_in.is_active() {
+                if let Some(click_type) = count_in.process(
+                    buffer_size,
+                    sample_rate as f64,
+                    &current_tempo,
+                    &current_time_signature,
+                ) {
+                    metronome.trigger_count_in_click(click_type);
+                }
+                if !count_in.is_active() {
+                    is_playing = true;
+                    metronome_scheduler.reset();
+                }
+            }
+
+            // Launched clips, song clips or looping patterns
+            let track_sources = TrackSources {
+                arrangement: arrangement.as_deref(),
+                session: Some((&*session, &launcher)),
+            };
+
+            // Generate MIDI events from pattern (RT-safe, no allocations)
+            let sequencer_events = {
+                let _seq_timer = profile_operation(section::SEQUENCER);
+                sequencer_player.process_track(
+                    SYNTH_TRACK,
+                    &active_pattern,
+                    track_sources,
+                    current_position,
+                    is_playing,
+                    &current_tempo,
+                    &current_time_signature,
+                    buffer_size,
+                )
+            };
+
+            // Process generated MIDI events
+            {
+                let _seq_events_timer = profile_operation(section::SEQUENCER);
+                for timed_event in sequencer_events {
+                    process_midi_event(timed_event, voice_manager, &plugin_host);
+                }
+            }
+
+            // Check for metronome clicks (if playing)
+            if is_playing {
+                let buffer_size = data.len() / channels;
+                if let Some((_offset, click_type)) = metronome_scheduler.check_for_click(
+                    current_position,
+                    buffer_size,
+                    sample_rate as f64,
+                    &current_tempo,
+                    &current_time_signature,
+                ) {
+                    // Trigger metronome click
+                    // Note: For now, we trigger at buffer start regardless of offset
+                    // TODO: Handle sample-accurate offset within buffer for perfect timing
+                    metronome.trigger_click(click_type);
+                }
+            }
 
-                    // Generate MIDI events from pattern (RT-safe, no allocations)
-                    let sequencer_events = {
-                        let _seq_timer = profile_operation("sequencer_process");
-                        sequencer_player.process(
-                            &active_pattern,
+            // Generate audio samples (direct access, no locks!)
+            let buffer_size = data.len() / channels;
+        
+            // Create temporary buffers for plugin processing
+            let mut input_buffers = std::collections::HashMap::new();
+            let mut output_buffers = std::collections::HashMap::new();
+        
+            // Create separate input and output buffers for plugins
+            let mut input_left = vec![0.0f32; buffer_size];
+            let mut input_right = vec![0.0f32; buffer_size];
+            let mut output_left = vec![0.0f32; buffer_size];
+            let mut output_right = vec![0.0f32; buffer_size];
+        
+            // Generate samples from voice manager and metronome into input buffers
+            {
+                let _audio_gen_timer = profile_operation(section::VOICES);
+
+                // Render all voices for the whole buffer (SIMD summing). Automated
+                // cutoff and LFO rate are updated before each voice block.
+                if automation_player.automates_voices() {
+                    for (block, (left, right)) in input_left
+                        .chunks_mut(VOICE_BLOCK_SIZE)
+                        .zip(input_right.chunks_mut(VOICE_BLOCK_SIZE))
+                        .enumerate()
+                    {
+                        let offset = if is_playing { (block * VOICE_BLOCK_SIZE) as u64 } else { 0 };
+                        automation_player.apply_to(
+                            voice_manager,
+                            current_position + offset,
+                            &current_tempo,
+                        );
+                        voice_manager.process_block(left, right);
+                    }
+                } else {
+                    voice_manager.process_block(&mut input_left, &mut input_right);
+                }
+                clip_player.set_synth_send_modulation(voice_manager.send_modulation());
+
+                // Track inserts and audio clips under the playhead (mixed before the master volume)
+                let sidechain = if clip_player.plugin_sidechain().is_some() {
+                    // Within the preallocated capacity: no allocation
+                    sidechain_left.resize(buffer_size);
+                    sidechain_right.resize(buffer_size);
+                    Some((sidechain_left.data_mut(), sidechain_right.data_mut()))
+                } else {
+                    None
+                };
+                clip_player.process(
+                    current_position,
+                    current_tempo.beat_duration_samples(sample_rate as f64),
+                    is_playing,
+                    &mut input_left,
+                    &mut input_right,
+                    sidechain,
+                );
+
+                // Instrument tracks play their patterns on the master bus
+                instrument_rack.process(
+                    track_sources,
+                    current_position,
+                    is_playing,
+                    &current_tempo,
+                    &current_time_signature,
+                    &plugin_host,
+                    clip_player.mixer(),
+                    &mut input_left,
+                    &mut input_right,
+                );
+                // Launches timed within this buffer now play
+                launcher.advance(current_position + buffer_size.max(1) as u64 - 1);
+
+                // Read target volume from atomic (once per buffer, smoothed per sample)
+                volume_smoother.set_target(volume.get());
+                let volume_automated = automation_player.is_automated(AutomationParameter::Volume);
+
+                // Within the preallocated capacity: no allocation
+                click_buffer.resize(buffer_size);
+                let click = click_buffer.data_mut();
+
+                for i in 0..buffer_size {
+                    // The volume lane replaces the fader, sample by sample
+                    if volume_automated
+                        && let Some(value) = automation_player.value_at(
+                            AutomationParameter::Volume,
                             current_position,
-                            is_playing,
                             &current_tempo,
-                            &current_time_signature,
-                            buffer_size,
                         )
-                    };
-
-                    // Process generated MIDI events
                     {
-                        let _seq_events_timer = profile_operation("process_sequencer_events");
-                        for timed_event in sequencer_events {
-                            process_midi_event(timed_event, &mut voice_manager, &plugin_host);
-                        }
+                        volume_smoother.set_target(value);
                     }
 
-                    // Check for metronome clicks (if playing)
+                    // Smooth volume to avoid clicks/pops
+                    let smoothed_volume = volume_smoother.next_value();
+
+                    // Stereo sample rendered by the voice manager
+                    let (mut left, mut right) = (input_left[i], input_right[i]);
+
+                    // Metronome bus (kept out of the mix)
+                    click[i] = metronome.process_sample() * METRONOME_BUS_LEVEL;
+
+                    // Apply volume
+                    left *= smoothed_volume;
+                    right *= smoothed_volume;
+
+                    // Master bus effects (post-fader)
+                    (left, right) = master_chain.process(left, right);
+
+                    // Store in input buffers for plugins
+                    input_left[i] = left;
+                    input_right[i] = right;
+                
+                    // Advance position counter if playing
                     if is_playing {
-                        let buffer_size = data.len() / channels;
-                        if let Some((_offset, click_type)) = metronome_scheduler.check_for_click(
-                            current_position,
-                            buffer_size,
-                            sample_rate as f64,
-                            &current_tempo,
-                            &current_time_signature,
-                        ) {
-                            // Trigger metronome click
-                            // Note: For now, we trigger at buffer start regardless of offset
-                            // TODO: Handle sample-accurate offset within buffer for perfect timing
-                            metronome.trigger_click(click_type);
-                        }
+                        current_position += 1;
                     }
-
-                    // Generate audio samples (direct access, no locks!)
-                    let buffer_size = data.len() / channels;
-                    
-                    // Create temporary buffers for plugin processing
-                    let mut input_buffers = std::collections::HashMap::new();
-                    let mut output_buffers = std::collections::HashMap::new();
-                    
-                    // Create separate input and output buffers for plugins
-                    let mut input_left = vec![0.0f32; buffer_size];
-                    let mut input_right = vec![0.0f32; buffer_size];
-                    let mut output_left = vec![0.0f32; buffer_size];
-                    let mut output_right = vec![0.0f32; buffer_size];
-                    
-                    // Generate samples from voice manager and metronome into input buffers
-                    {
-                        let _audio_gen_timer = profile_operation("audio_generation");
-                        for i in 0..buffer_size {
-                            // Read target volume from atomic (once per sample for smoothing)
-                            let target_volume = volume.get();
-
-                            // Smooth volume to avoid clicks/pops
-                            let smoothed_volume = volume_smoother.process(target_volume);
-
-                            // Generate stereo sample
-                            let (mut left, mut right) = voice_manager.next_sample();
-
-                            // Generate metronome click sample
-                            let metronome_sample = metronome.process_sample();
-
-                            // Anti-denormals (flush tiny values to zero)
-                            left = flush_denormals_to_zero(left);
-                            right = flush_denormals_to_zero(right);
-                            let metronome_sample = flush_denormals_to_zero(metronome_sample);
-
-                            // Apply volume
-                            left *= smoothed_volume;
-                            right *= smoothed_volume;
-
-                            // Mix in metronome (additive, doesn't affect main audio level)
-                            left += metronome_sample * 0.3; // Metronome at 30% of main volume
-                            right += metronome_sample * 0.3;
-
-                            // Store in input buffers for plugins
-                            input_left[i] = left;
-                            input_right[i] = right;
-                            
-                            // Advance position counter if playing
-                            if is_playing {
-                                current_position += 1;
-                            }
-                        }
+                }
+
+                // The metronome bus plays on its own pair, or on channels 1-2
+                // once the mix is metered (see the mixdown)
+                if let Some(pair) = clip_player.direct_outputs().pair(metronome_output) {
+                    let click = click_buffer.data();
+                    clip_player.direct_outputs_mut().add(pair, 0, click, click, (1.0, 1.0));
+                }
+            }
+        
+            // Create audio buffers for plugin processing
+            let mut left_input_buffer = crate::audio::buffer::AudioBuffer::new(buffer_size);
+            let mut right_input_buffer = crate::audio::buffer::AudioBuffer::new(buffer_size);
+            let mut left_output_buffer = crate::audio::buffer::AudioBuffer::new(buffer_size);
+            let mut right_output_buffer = crate::audio::buffer::AudioBuffer::new(buffer_size);
+        
+            // Copy input data to buffers
+            left_input_buffer.data_mut().copy_from_slice(&input_left);
+            right_input_buffer.data_mut().copy_from_slice(&input_right);
+            left_output_buffer.data_mut().copy_from_slice(&output_left);
+            right_output_buffer.data_mut().copy_from_slice(&output_right);
+        
+            // Set up input and output buffers for plugins
+            input_buffers.insert("input_left".to_string(), &left_input_buffer);
+            input_buffers.insert("input_right".to_string(), &right_input_buffer);
+            output_buffers.insert("output_left".to_string(), &mut left_output_buffer);
+            output_buffers.insert("output_right".to_string(), &mut right_output_buffer);
+            if clip_player.plugin_sidechain().is_some() {
+                input_buffers.insert("sidechain_left".to_string(), &sidechain_left);
+                input_buffers.insert("sidechain_right".to_string(), &sidechain_right);
+            }
+        
+            // Process all plugins
+            {
+                let _plugin_timer = profile_operation(section::PLUGINS);
+                if let Err(e) = plugin_host.process_all_instances(&input_buffers, &mut output_buffers, buffer_size) {
+                    // Log error but continue with audio processing
+                    crate::rt_error!("plugin", "Plugin processing error: {:?}", e);
+                }
+            }
+        
+            // Copy processed audio back to output buffer
+            let mut buffer_peak_left = 0.0f32;
+            let mut buffer_peak_right = 0.0f32;
+            let tap_master = master_tap.is_active();
+            let direct_outputs = clip_player.direct_outputs();
+            let direct = direct_outputs.is_active();
+            let click_on_master = direct_outputs.pair(metronome_output).is_none();
+            let surround_outputs = clip_player.surround_outputs();
+            let surround = surround_outputs.is_active();
+            let master_volume = volume_smoother.current();
+            {
+                let _output_timer = profile_operation(section::MIXDOWN);
+                for (i, _frame) in data.chunks_mut(channels).enumerate() {
+                    let left = left_output_buffer.data()[i];
+                    let right = right_output_buffer.data()[i];
+                
+                    // Soft saturation (protection against hard clipping)
+                    let (mix_left, mix_right) = (soft_clip(left), soft_clip(right));
+                    buffer_peak_left = buffer_peak_left.max(mix_left.abs());
+                    buffer_peak_right = buffer_peak_right.max(mix_right.abs());
+                    if tap_master {
+                        master_tap.push((mix_left + mix_right) * 0.5);
                     }
-                    
-                    // Create audio buffers for plugin processing
-                    let mut left_input_buffer = crate::audio::buffer::AudioBuffer::new(buffer_size);
-                    let mut right_input_buffer = crate::audio::buffer::AudioBuffer::new(buffer_size);
-                    let mut left_output_buffer = crate::audio::buffer::AudioBuffer::new(buffer_size);
-                    let mut right_output_buffer = crate::audio::buffer::AudioBuffer::new(buffer_size);
-                    
-                    // Copy input data to buffers
-                    left_input_buffer.data_mut().copy_from_slice(&input_left);
-                    right_input_buffer.data_mut().copy_from_slice(&input_right);
-                    left_output_buffer.data_mut().copy_from_slice(&output_left);
-                    right_output_buffer.data_mut().copy_from_slice(&output_right);
-                    
-                    // Set up input and output buffers for plugins
-                    input_buffers.insert("input_left".to_string(), &left_input_buffer);
-                    input_buffers.insert("input_right".to_string(), &right_input_buffer);
-                    output_buffers.insert("output_left".to_string(), &mut left_output_buffer);
-                    output_buffers.insert("output_right".to_string(), &mut right_output_buffer);
-                    
-                    // Process all plugins
-                    {
-                        let _plugin_timer = profile_operation("plugin_processing");
-                        if let Err(e) = plugin_host.process_all_instances(&input_buffers, &mut output_buffers, buffer_size) {
-                            // Log error but continue with audio processing
-                            eprintln!("Plugin processing error: {:?}", e);
-                        }
+
+                    // Metronome on channels 1-2: heard, but neither metered nor tapped
+                    let (left, right) = if click_on_master {
+                        let click = click_buffer.data()[i];
+                        (soft_clip(left + click), soft_clip(right + click))
+                    } else {
+                        (mix_left, mix_right)
+                    };
+
+                    // Write stereo sample to frame (and the direct output pairs)
+                    if direct {
+                        direct_outputs.write_frame(i, (left, right), _frame);
+                    } else {
+                        write_stereo_to_interleaved_frame((left, right), _frame);
                     }
-                    
-                    // Copy processed audio back to output buffer
-                    {
-                        let _output_timer = profile_operation("output_processing");
-                        for (i, _frame) in data.chunks_mut(channels).enumerate() {
-                            let left = left_output_buffer.data()[i];
-                            let right = right_output_buffer.data()[i];
-                            
-                            // Soft saturation (protection against hard clipping)
-                            let left = soft_clip(left);
-                            let right = soft_clip(right);
-
-                            // Write stereo sample to frame
-                            write_stereo_to_interleaved_frame((left, right), _frame);
-                        }
+                    // Surround channels past the front pair
+                    if surround {
+                        surround_outputs.write_frame(i, master_volume, _frame);
                     }
+                }
+            }
 
-                    // End CPU monitoring
-                    cpu_monitor.end_measure(measure_start);
-                    // ========== SACRED ZONE END ==========
+            // End CPU monitoring and check the deadline
+            cpu_monitor.end_measure(measure_start);
+            let elapsed = callback_start.elapsed();
+            if cpu_monitor.check_deadline(elapsed, callback_frames) {
+                crate::rt_warn!(
+                    "engine",
+                    "Deadline overrun: {} us for {} frames",
+                    elapsed.as_micros(),
+                    callback_frames
+                );
+            }
+
+            // Adapt the voice limit to the recent load
+            voice_manager.update_cpu_load(cpu_monitor.get_recent_load_level(), callback_frames);
+            cpu_monitor.report_voice_limit(voice_manager.voice_limit(), voice_manager.max_voices());
+
+            // Publish the engine state for the frontends
+            peak_left = hold_peak(peak_left, buffer_peak_left, callback_frames, sample_rate);
+            peak_right = hold_peak(peak_right, buffer_peak_right, callback_frames, sample_rate);
+            let buffer_reduction = master_chain.take_gain_reduction();
+            gain_reduction = GainReduction {
+                compressor_db: hold_peak(
+                    gain_reduction.compressor_db,
+                    buffer_reduction.compressor_db,
+                    callback_frames,
+                    sample_rate,
+                ),
+                limiter_db: hold_peak(
+                    gain_reduction.limiter_db,
+                    buffer_reduction.limiter_db,
+                    callback_frames,
+                    sample_rate,
+                ),
+            };
+            let buffer_duration = callback_frames as f32 / sample_rate;
+            snapshot.publish(&EngineSnapshot {
+                position_samples: current_position,
+                playing: is_playing,
+                count_in_samples: count_in.remaining_samples(),
+                tempo: current_tempo.bpm(),
+                sample_rate,
+                output_channels: channels,
+                active_voices: voice_manager.active_voice_count()
+                    + instrument_rack.active_voice_count(),
+                cpu_load: if buffer_duration > 0.0 {
+                    elapsed.as_secs_f32() / buffer_duration * 100.0
+                } else {
+                    0.0
                 },
-                move |err| {
-                    // ========== ERROR CALLBACK ==========
-                    // This runs outside the audio callback, so we can do I/O here
-                    eprintln!("Audio stream error: {}", err);
+                peak_left,
+                peak_right,
+                compressor_reduction_db: gain_reduction.compressor_db,
+                limiter_reduction_db: gain_reduction.limiter_db,
+            });
+            // ========== SACRED ZONE END ==========
+        }
+    }
+}
 
-                    // Set status to Error (atomic operation, safe)
-                    status.set(DeviceStatus::Error);
+impl AudioInput {
+    /// Open an input device, returns the stream and the recorder it feeds
+    ///
+    /// An unknown device falls back to the default input device.
+    #[cfg(feature = "audio-backend")]
+    pub fn open(options: &InputOptions) -> Result<(Self, Recorder), String> {
+        let host = cpal::default_host();
+        let requested = options.device_name.as_ref().and_then(|name| {
+            let device = host
+                .input_devices()
+                .ok()?
+                .find(|device| device.name().is_ok_and(|n| &n == name));
+            if device.is_none() {
+                eprintln!("⚠️  Input device '{}' not found, using the default device", name);
+            }
+            device
+        });
+        let device = match requested {
+            Some(device) => device,
+            None => host.default_input_device().ok_or("No audio input device found")?,
+        };
+        let supported_config = device
+            .default_input_config()
+            .map_err(|e| format!("Input configuration error: {}", e))?;
+        let sample_format = supported_config.sample_format();
+        let config: StreamConfig = supported_config.into();
+        println!(
+            "Audio input: {} ({} Hz, {} channels)",
+            device.name().unwrap_or("Unknown".to_string()),
+            config.sample_rate.0,
+            config.channels
+        );
 
-                    // Send notification to UI (non-blocking)
-                    if let Ok(mut tx) = notification_tx.try_lock() {
-                        let notif = Notification::error(
-                            NotificationCategory::Audio,
-                            format!("Audio stream error: {}", err),
-                        );
-                        let _ = ringbuf::traits::Producer::try_push(&mut *tx, notif);
+        let (capture, recorder) = recording_channel(config.sample_rate.0, INPUT_RING_CAPACITY);
+        let channel = options
+            .channel
+            .map(|channel| (channel as usize).min(config.channels as usize - 1));
+        let status = AtomicDeviceStatus::new(DeviceStatus::Connecting);
+        let stream = match sample_format {
+            SampleFormat::F32 => {
+                Self::build_stream::<f32>(&device, &config, capture, channel, status.clone())
+            }
+            SampleFormat::I16 => {
+                Self::build_stream::<i16>(&device, &config, capture, channel, status.clone())
+            }
+            SampleFormat::U16 => {
+                Self::build_stream::<u16>(&device, &config, capture, channel, status.clone())
+            }
+            _ => {
+                return Err(format!(
+                    "Unsupported input sample format: {:?}. Supported formats: F32, I16, U16",
+                    sample_format
+                ));
+            }
+        }?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start the input stream: {}", e))?;
+        status.set(DeviceStatus::Connected);
+
+        let input = Self {
+            _stream: stream,
+            sample_rate: config.sample_rate.0,
+            channels: config.channels,
+            status,
+        };
+        Ok((input, recorder))
+    }
+
+    /// Without an audio backend there is nothing to record from
+    #[cfg(not(feature = "audio-backend"))]
+    pub fn open(options: &InputOptions) -> Result<(Self, Recorder), String> {
+        let _ = options;
+        Err("Audio input is not available without an audio backend".to_string())
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Build the input stream: mono mixdown (or one channel) into the capture
+    #[cfg(feature = "audio-backend")]
+    fn build_stream<T>(
+        device: &Device,
+        config: &StreamConfig,
+        mut capture: InputCapture,
+        channel: Option<usize>,
+        status: AtomicDeviceStatus, // Clone (Arc internally, atomic)
+    ) -> Result<Stream, String>
+    where
+        T: SizedSample,
+        f32: cpal::FromSample<T>,
+    {
+        let channels = config.channels as usize;
+        let mix_gain = 1.0 / channels as f32;
+        device
+            .build_input_stream(
+                config,
+                move |data: &[T], _: &cpal::InputCallbackInfo| {
+                    // ========== SACRED ZONE ==========
+                    // No allocations, No I/O, No blocking locks
+                    capture.begin_block();
+                    for frame in data.chunks_exact(channels) {
+                        let sample = match channel {
+                            Some(channel) => frame[channel].to_sample::<f32>(),
+                            None => {
+                                frame.iter().map(|s| s.to_sample::<f32>()).sum::<f32>() * mix_gain
+                            }
+                        };
+                        capture.push(sample);
                     }
+                    capture.end_block();
+                    // ========== SACRED ZONE END ==========
+                },
+                move |err| {
+                    // Runs outside the audio callback: I/O allowed
+                    eprintln!("Audio input stream error: {}", err);
+                    status.set(DeviceStatus::Error);
                 },
                 None,
             )
-            .map_err(|e| format!("Error in stream creation: {}", e))?;
+            .map_err(|e| format!("Error in input stream creation: {}", e))
+    }
+}
 
-        Ok(stream)
+/// State moved into the audio callback (see `AudioEngine::build_render`)
+struct RenderContext {
+    channels: usize,
+    core: Handback<RenderCore>,         // Moved into closure (handed back when dropped)
+    volume: AtomicF32,                  // Clone (Arc internally, read-only atomic)
+    synth_params: SynthParameters,      // Clone (Arc internally, read-only atomics)
+    volume_smoother: SmoothedParam,     // Moved into closure (no Mutex)
+    cpu_monitor: CpuMonitor,            // Clone (Arc internally for stats)
+    snapshot: AtomicEngineSnapshot,     // Clone (Arc internally, written once per callback)
+    master_tap: AudioTap,               // Clone (Arc internally, written while listened to)
+    clip_player: ClipPlayer,            // Moved into closure (tracks replaced by command)
+    sidechain_left: AudioBuffer,        // Plugin sidechain key (preallocated, resized in capacity)
+    sidechain_right: AudioBuffer,       // Plugin sidechain key (preallocated, resized in capacity)
+    click_buffer: AudioBuffer,          // Metronome bus (preallocated, resized in capacity)
+    metronome: Metronome,               // Moved into closure (no Mutex)
+    metronome_scheduler: MetronomeScheduler, // Moved into closure (no Mutex)
+    sequencer_player: crate::sequencer::SequencerPlayer, // Moved into closure (no Mutex)
+    automation_player: AutomationPlayer, // Moved into closure (lanes replaced by command)
+    instrument_rack: InstrumentRack,    // Moved into closure (slots preallocated)
+    sample_rate: f32,                   // Sample rate for scheduler calculations
+    plugin_host: Arc<PluginHost>,       // Clone for plugin access
+    heartbeat: Heartbeat,               // Clone (Arc internally, atomic)
+}
+
+/// Callback state kept across streams (see `AudioEngine::switch_device`)
+struct RenderCore {
+    command_rx_ui: CommandConsumer,   // Frontends keep their producers
+    command_rx_midi: CommandConsumer, // MIDI input keeps its producer
+    feedback_tx: FeedbackProducer,    // Acknowledgments to the UI
+    voice_manager: VoiceManager,      // Synth settings and sample bank
+    garbage_chute: GarbageChute,      // Discarded objects go back through it
+}
+
+/// Sends its value back through a channel when dropped
+///
+/// Wraps the `RenderCore` moved into the audio callback: dropping the stream,
+/// or failing to build it, returns the core to the engine.
+struct Handback<T> {
+    value: ManuallyDrop<T>,
+    tx: mpsc::Sender<T>,
+}
+
+impl<T> Handback<T> {
+    fn new(value: T, tx: mpsc::Sender<T>) -> Self {
+        Self {
+            value: ManuallyDrop::new(value),
+            tx,
+        }
+    }
+}
+
+impl<T> Deref for Handback<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Handback<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T> Drop for Handback<T> {
+    fn drop(&mut self) {
+        // SAFETY: the value is taken once, here, and never used afterwards
+        let value = unsafe { ManuallyDrop::take(&mut self.value) };
+        // Nobody listening (engine dropped): the value is dropped here
+        let _ = self.tx.send(value);
     }
 }
@@ -4,16 +4,47 @@
 // the user to export their project to audio files. Unlike the real-time
 // audio callback, this processes audio as fast as possible without time
 // constraints.
+//
+// A whole project renders each audible track with its own voice manager and
+// sequencer player (track volume and pan applied, mute/solo honored), either
+// mixed to one file or as one file per track (stems). Used by the UI export
+// and the `mymusic_render` command-line tool. Projects in song mode render the
+// clips of their arrangement instead of looping the track patterns.
+//
+// Exports with the channel count of a speaker layout (4: quad, 6: 5.1) render
+// in surround: tracks with a surround panner are placed between the speakers
+// (see `audio::surround`), the other tracks and the metronome play on the
+// front pair. Stereo and mono exports fold the panners down to left/right.
+//
+// Mixdowns run the project master chain (see `synth::master_chain`) on the
+// front pair, after the master volume; stems are rendered without it.
+//
+// WAV files are written as 16 or 24-bit integers or 32-bit floats, FLAC files
+// as 16 or 24-bit integers. `render_to_wav` bounces the arrangement of a
+// project with its own sample rate and speaker layout.
 
-use crate::audio::dsp_utils::{OnePoleSmoother, flush_denormals_to_zero, soft_clip};
-use crate::midi::event::{MidiEvent, MidiEventTimed};
+use crate::audio::denormals::ScopedNoDenormals;
+use crate::audio::dsp_utils::soft_clip;
+use crate::audio::flac_writer::FlacWriter;
+use crate::audio::smoothing::{ParamKind, SmoothedParam};
+use crate::audio::surround::{MAX_SURROUND_CHANNELS, SpeakerLayout, SurroundGains};
+use crate::midi::event::{MOD_WHEEL_CC, MidiEvent, MidiEventTimed, SUSTAIN_PEDAL_CC};
+use crate::project::serialization::pattern_from_serializable;
+use crate::project::types::TrackType;
+use crate::project::{Project, Track};
+use crate::sampler::load_sample;
 use crate::sequencer::metronome::{Metronome, MetronomeScheduler};
-use crate::sequencer::{Pattern, SequencerPlayer, Tempo, TimeSignature};
-use crate::synth::voice_manager::VoiceManager;
+use crate::sequencer::{Pattern, PatternClip, PatternId, SequencerPlayer, Tempo, TimeSignature};
+use crate::synth::master_chain::{MasterChain, MasterChainParams};
+use crate::synth::voice_manager::{VoiceManager, VoiceMode};
 use hound::{WavSpec, WavWriter};
 use std::fs::File;
 use std::io::BufWriter;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Frames rendered per block
+const BUFFER_SIZE: usize = 512;
 
 /// Audio export format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -43,9 +74,9 @@ pub struct ExportSettings {
     pub format: ExportFormat,
     /// Sample rate (Hz)
     pub sample_rate: u32,
-    /// Bit depth (16 or 24)
+    /// Bit depth (16 or 24, 32 for floating-point WAV)
     pub bit_depth: u16,
-    /// Number of channels (1=mono, 2=stereo)
+    /// Number of channels (1=mono, 2=stereo, 4=quad, 6=5.1)
     pub channels: u16,
     /// Include metronome in export
     pub include_metronome: bool,
@@ -64,6 +95,31 @@ impl Default for ExportSettings {
     }
 }
 
+/// Bit depths of WAV exports (32: floating point)
+pub const WAV_BIT_DEPTHS: [u16; 3] = [16, 24, 32];
+
+/// Bar range of a project render
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderRange {
+    /// First bar rendered (0-based)
+    pub start_bar: u32,
+    /// Bar where rendering stops (exclusive, None = end of the longest pattern)
+    pub end_bar: Option<u32>,
+    /// Seconds rendered after the range with the sequencer stopped
+    /// (release and effect tails)
+    pub tail_seconds: f64,
+}
+
+impl Default for RenderRange {
+    fn default() -> Self {
+        Self {
+            start_bar: 0,
+            end_bar: None,
+            tail_seconds: 0.0,
+        }
+    }
+}
+
 /// Progress callback for export (reports 0.0 to 1.0).
 /// The callback should update a shared state (e.g., Arc<Mutex<f32>>) or send progress via a channel to the UI.
 pub type ProgressCallback = Box<dyn FnMut(f32) + Send>;
@@ -118,55 +174,30 @@ impl AudioExporter {
             total_duration, total_samples, self.settings.sample_rate
         );
 
-        // Export based on format
-        match self.settings.format {
-            ExportFormat::Wav => self.export_wav(
-                pattern,
-                tempo,
-                time_signature,
-                total_samples,
-                progress_callback.as_mut(),
-            ),
-            ExportFormat::Flac => self.export_flac(
-                pattern,
-                tempo,
-                time_signature,
-                total_samples,
-                progress_callback.as_mut(),
-            ),
-        }
-    }
-
-    /// Export to WAV format
-    fn export_wav(
-        &self,
-        pattern: &Pattern,
-        tempo: &Tempo,
-        time_signature: &TimeSignature,
-        total_samples: u64,
-        progress_callback: Option<&mut ProgressCallback>,
-    ) -> Result<String, String> {
-        // Create WAV spec
-        let spec = WavSpec {
-            channels: self.settings.channels,
-            sample_rate: self.settings.sample_rate,
-            bits_per_sample: self.settings.bit_depth,
-            sample_format: hound::SampleFormat::Int,
-        };
+        let mut tracks = [TrackRenderer::new(
+            TrackSequence::Pattern(pattern.clone()),
+            VoiceManager::new(self.settings.sample_rate as f32),
+            1.0,
+            0.0,
+            self.settings.sample_rate,
+        )];
 
-        // Create WAV writer
-        let path = Path::new(&self.settings.output_path);
-        let writer = WavWriter::create(path, spec)
-            .map_err(|e| format!("Failed to create WAV file: {}", e))?;
-
-        // Render audio
-        self.render_audio(
-            writer,
-            pattern,
+        // Fixed 50% volume for pattern exports
+        self.render(
+            Path::new(&self.settings.output_path),
+            &mut tracks,
+            0.5,
+            None,
             tempo,
             time_signature,
+            0,
             total_samples,
-            progress_callback,
+            0,
+            &mut |progress| {
+                if let Some(callback) = progress_callback.as_mut() {
+                    callback(progress);
+                }
+            },
         )?;
 
         Ok(format!(
@@ -175,58 +206,325 @@ impl AudioExporter {
         ))
     }
 
-    /// Export to FLAC format
-    fn export_flac(
+    /// Render the audible tracks of a project, mixed to one file
+    pub fn export_project(
         &self,
-        pattern: &Pattern,
-        tempo: &Tempo,
-        time_signature: &TimeSignature,
-        total_samples: u64,
-        progress_callback: Option<&mut ProgressCallback>,
+        project: &Project,
+        range: &RenderRange,
+        mut progress_callback: Option<ProgressCallback>,
     ) -> Result<String, String> {
-        // FLAC export using hound (which supports FLAC via feature flag)
-        // For now, we'll just export as WAV and recommend using external tools for FLAC
-        // TODO: Add proper FLAC support with claxon or similar
+        let tracks = audible_tracks(project);
+        let mut renderers = tracks
+            .iter()
+            .map(|track| self.track_renderer(project, track))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
 
-        // For now, change extension to .wav and export as WAV
-        let mut settings = self.settings.clone();
-        settings.output_path = settings.output_path.replace(".flac", ".wav");
+        self.render_project(
+            project,
+            Path::new(&self.settings.output_path),
+            &mut renderers,
+            range,
+            Some(project.master_chain),
+            &mut |progress| {
+                if let Some(callback) = progress_callback.as_mut() {
+                    callback(progress);
+                }
+            },
+        )?;
 
-        println!("Note: FLAC export not yet implemented, exporting as WAV instead");
+        Ok(format!(
+            "Successfully exported to {}",
+            self.settings.output_path
+        ))
+    }
 
-        let exporter = AudioExporter::new(settings);
-        exporter.export_wav(
-            pattern,
-            tempo,
-            time_signature,
-            total_samples,
-            progress_callback,
+    /// Render each audible track of a project to its own file
+    ///
+    /// Stems are named after the output path: `mix.wav` gives
+    /// `mix_<track id>_<track name>.wav`. Every stem covers the same range,
+    /// so the stems line up in another application.
+    ///
+    /// # Returns
+    /// The paths of the written stems
+    pub fn export_stems(
+        &self,
+        project: &Project,
+        range: &RenderRange,
+        mut progress_callback: Option<ProgressCallback>,
+    ) -> Result<Vec<String>, String> {
+        let tracks = audible_tracks(project);
+        let mut stems = Vec::new();
+        for track in &tracks {
+            if let Some(renderer) = self.track_renderer(project, track)? {
+                stems.push((self.stem_path(track), renderer));
+            }
+        }
+        if stems.is_empty() {
+            return Err("No track to render".to_string());
+        }
+
+        // Every stem ends with the longest pattern, not with its own
+        let renderers: Vec<&TrackRenderer> = stems.iter().map(|(_, renderer)| renderer).collect();
+        let range = RenderRange {
+            end_bar: Some(range.end_bar.unwrap_or_else(|| song_length_bars(renderers))),
+            ..*range
+        };
+
+        let stem_count = stems.len();
+        let mut written = Vec::with_capacity(stem_count);
+        for (index, (path, renderer)) in stems.into_iter().enumerate() {
+            self.render_project(
+                project,
+                &path,
+                &mut [renderer],
+                &range,
+                None,
+                &mut |progress| {
+                    if let Some(callback) = progress_callback.as_mut() {
+                        callback((index as f32 + progress) / stem_count as f32);
+                    }
+                },
+            )?;
+            written.push(path.to_string_lossy().into_owned());
+        }
+
+        Ok(written)
+    }
+
+    /// Path of the stem of `track`
+    fn stem_path(&self, track: &Track) -> PathBuf {
+        let output = Path::new(&self.settings.output_path);
+        let stem = output
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("export");
+        let name: String = track
+            .name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        output.with_file_name(format!(
+            "{}_{}_{}.{}",
+            stem,
+            track.id,
+            name,
+            self.settings.format.extension()
+        ))
+    }
+
+    /// Voice manager and pattern (or clips) of a project track (None if it has
+    /// nothing to play)
+    fn track_renderer(
+        &self,
+        project: &Project,
+        track: &Track,
+    ) -> Result<Option<TrackRenderer>, String> {
+        if track.track_type == TrackType::Audio {
+            return Ok(None);
+        }
+        let sequence = if project.song_mode {
+            let clips: Vec<PatternClip> = project
+                .arrangement
+                .iter()
+                .filter(|clip| clip.track == track.id)
+                .filter_map(|clip| {
+                    let pattern = self.project_pattern(project, clip.pattern_id)?;
+                    Some(PatternClip::new(
+                        clip.track,
+                        clip.start_bar,
+                        clip.length_bars,
+                        Arc::new(pattern),
+                    ))
+                })
+                .collect();
+            if clips.is_empty() {
+                return Ok(None);
+            }
+            TrackSequence::Clips(clips)
+        } else {
+            let Some(pattern) = track
+                .pattern_id
+                .and_then(|id| self.project_pattern(project, id))
+            else {
+                return Ok(None);
+            };
+            TrackSequence::Pattern(pattern)
+        };
+        let sample_rate = self.settings.sample_rate;
+
+        let params = &project.synth_params;
+        let mut voice_manager = VoiceManager::new(sample_rate as f32);
+        voice_manager.set_waveform(params.waveform);
+        voice_manager.set_oscillator2(params.oscillator2);
+        voice_manager.set_unison(params.unison);
+        voice_manager.set_anti_aliasing(params.anti_aliasing);
+        voice_manager.set_cross_mod(params.cross_mod);
+        voice_manager.set_noise(params.noise);
+        voice_manager.set_mod_envelope(params.mod_envelope);
+        voice_manager.set_filter_mod(params.filter_mod);
+        voice_manager.set_mod_lfos(params.mod_lfos);
+        voice_manager.set_adsr(params.adsr);
+        voice_manager.set_lfo(params.lfo);
+        voice_manager.set_filter(params.filter);
+        voice_manager.set_portamento(params.portamento);
+        voice_manager.set_poly_mode(params.poly_mode);
+        // Instrument tracks with their own synth sound
+        if let Some(patch) = &track.patch {
+            voice_manager.set_waveform(patch.waveform);
+            voice_manager.set_oscillator2(patch.oscillator2);
+            voice_manager.set_unison(patch.unison);
+            voice_manager.set_anti_aliasing(patch.anti_aliasing);
+            voice_manager.set_cross_mod(patch.cross_mod);
+            voice_manager.set_noise(patch.noise);
+            voice_manager.set_mod_envelope(patch.mod_envelope);
+            voice_manager.set_filter_mod(patch.filter_mod);
+            voice_manager.set_mod_lfos(patch.mod_lfos);
+            voice_manager.set_adsr(patch.adsr);
+            voice_manager.set_lfo(patch.lfo);
+            voice_manager.set_filter(patch.filter);
+            voice_manager.set_portamento(patch.portamento);
+            voice_manager.set_poly_mode(patch.poly_mode);
+        }
+
+        if track.track_type == TrackType::Sampler
+            && let Some(bank) = &project.sample_bank
+        {
+            voice_manager.set_voice_mode(VoiceMode::Sampler);
+            for mapping in &bank.samples {
+                let mut sample = load_sample(&mapping.sample_path).map_err(|e| {
+                    format!(
+                        "Track '{}': failed to load sample {}: {}",
+                        track.name,
+                        mapping.sample_path.display(),
+                        e
+                    )
+                })?;
+                mapping.apply_to(&mut sample);
+                if let Ok(index) = voice_manager.add_sample(Arc::new(sample)) {
+                    match mapping.zone {
+                        Some(zone) => voice_manager.set_sample_zone(index, Some(zone)),
+                        None => voice_manager.set_note_to_sample(mapping.note, index),
+                    };
+                }
+            }
+        }
+
+        let mut renderer = TrackRenderer::new(
+            sequence,
+            voice_manager,
+            track.volume,
+            track.pan,
+            sample_rate,
+        );
+        renderer.surround = track
+            .surround
+            .map(|pan| pan.gains(self.layout()).map(|gain| gain * track.volume));
+        Ok(Some(renderer))
+    }
+
+    /// Pattern of a project at the export rate (note positions are stored at
+    /// the project rate)
+    fn project_pattern(&self, project: &Project, id: PatternId) -> Option<Pattern> {
+        let sample_rate = self.settings.sample_rate;
+        let ratio = sample_rate as f64 / project.metadata.sample_rate;
+        let mut scaled = project.patterns.get(&id)?.clone();
+        for note in &mut scaled.notes {
+            note.start_samples = (note.start_samples as f64 * ratio).round() as u64;
+            note.duration_samples = (note.duration_samples as f64 * ratio).round() as u64;
+        }
+        Some(pattern_from_serializable(&scaled, sample_rate as f64))
+    }
+
+    /// Speaker layout of the export (stereo for mono exports)
+    fn layout(&self) -> SpeakerLayout {
+        SpeakerLayout::for_channels(self.settings.channels as usize)
+            .unwrap_or(SpeakerLayout::Stereo)
+    }
+
+    /// Render project tracks over a bar range (through `master_chain` if any)
+    fn render_project(
+        &self,
+        project: &Project,
+        path: &Path,
+        tracks: &mut [TrackRenderer],
+        range: &RenderRange,
+        master_chain: Option<MasterChainParams>,
+        progress: &mut dyn FnMut(f32),
+    ) -> Result<(), String> {
+        if tracks.is_empty() {
+            return Err("No track to render".to_string());
+        }
+
+        let tempo = Tempo::new(project.metadata.tempo);
+        let time_signature = project.metadata.time_signature;
+        let bar_samples =
+            tempo.bar_duration_samples(self.settings.sample_rate as f64, &time_signature);
+
+        let end_bar = range
+            .end_bar
+            .unwrap_or_else(|| song_length_bars(tracks.iter()));
+        if end_bar <= range.start_bar {
+            return Err(format!(
+                "Invalid range: bars {} to {} are empty",
+                range.start_bar, end_bar
+            ));
+        }
+        if range.tail_seconds < 0.0 {
+            return Err("Invalid tail: must be >= 0".to_string());
+        }
+
+        let start_sample = (range.start_bar as f64 * bar_samples) as u64;
+        let end_sample = (end_bar as f64 * bar_samples) as u64;
+        let tail_samples = (range.tail_seconds * self.settings.sample_rate as f64) as u64;
+
+        self.render(
+            path,
+            tracks,
+            project.synth_params.volume,
+            master_chain,
+            &tempo,
+            &time_signature,
+            start_sample,
+            end_sample - start_sample,
+            tail_samples,
+            progress,
         )
     }
 
-    /// Render audio to a WAV writer
-    fn render_audio(
+    /// Render tracks to `path`
+    ///
+    /// The sequencers play `play_samples` from `start_sample`, then stop for
+    /// `tail_samples` so that releases ring out.
+    #[allow(clippy::too_many_arguments)]
+    fn render(
         &self,
-        mut writer: WavWriter<BufWriter<File>>,
-        pattern: &Pattern,
+        path: &Path,
+        tracks: &mut [TrackRenderer],
+        volume: f32,
+        master_chain: Option<MasterChainParams>,
         tempo: &Tempo,
         time_signature: &TimeSignature,
-        total_samples: u64,
-        mut progress_callback: Option<&mut ProgressCallback>,
+        start_sample: u64,
+        play_samples: u64,
+        tail_samples: u64,
+        progress: &mut dyn FnMut(f32),
     ) -> Result<(), String> {
-        // Create a new voice manager for offline rendering
-        // TODO Phase 4+: Copy settings from active voice manager
-        let mut voice_manager = VoiceManager::new(self.settings.sample_rate as f32);
+        let mut writer = ExportWriter::create(path, &self.settings)?;
 
-        // Create sequencer player
-        let mut sequencer_player = SequencerPlayer::new(self.settings.sample_rate as f64);
+        // Same denormal mode as the audio thread (restored when rendering ends)
+        let _no_denormals = ScopedNoDenormals::new();
 
-        // Create volume smoother (using default 50% volume)
-        let mut volume_smoother = OnePoleSmoother::new(0.5, 10.0, self.settings.sample_rate as f32);
+        let sample_rate = self.settings.sample_rate;
+        let mut volume_smoother = SmoothedParam::new(ParamKind::Volume, volume, sample_rate as f32);
+        let mut master_chain =
+            master_chain.map(|params| MasterChain::new(params, sample_rate as f32));
 
         // Create metronome (if enabled)
         let mut metronome = if self.settings.include_metronome {
-            Some(Metronome::new(self.settings.sample_rate as f32))
+            Some(Metronome::new(sample_rate as f32))
         } else {
             None
         };
@@ -236,54 +534,72 @@ impl AudioExporter {
             None
         };
 
-        // Buffer size for rendering (process in chunks)
-        const BUFFER_SIZE: usize = 512;
+        let total_samples = play_samples + tail_samples;
         let mut current_position: u64 = 0;
+        for track in tracks.iter_mut() {
+            track.voice_manager.set_tempo(*tempo);
+        }
 
         // Progress tracking
-        let mut samples_processed: u64 = 0;
-        let progress_update_interval = self.settings.sample_rate as u64; // Update every 1 second
+        let progress_update_interval = sample_rate as u64; // Update every 1 second
+        let mut next_progress_update = progress_update_interval;
 
         println!("Starting audio rendering...");
 
         // Main rendering loop
         while current_position < total_samples {
+            let playing = current_position < play_samples;
+
             // Calculate how many samples to render in this iteration
-            let samples_to_render = BUFFER_SIZE.min((total_samples - current_position) as usize);
-
-            // Get MIDI events from sequencer player
-            let midi_events = sequencer_player.process(
-                pattern,
-                current_position,
-                true, // is_playing
-                tempo,
-                time_signature,
-                samples_to_render,
-            );
-
-            // Process MIDI events
-            for timed_event in midi_events {
-                self.process_midi_event(timed_event, &mut voice_manager);
+            // (a block never straddles the end of the range)
+            let block_end = if playing { play_samples } else { total_samples };
+            let samples_to_render = BUFFER_SIZE.min((block_end - current_position) as usize);
+            let song_position = start_sample + current_position;
+
+            for track in tracks.iter_mut() {
+                track.render_block(
+                    song_position,
+                    playing,
+                    tempo,
+                    time_signature,
+                    samples_to_render,
+                );
             }
 
             // Generate and write audio samples
             for i in 0..samples_to_render {
-                // Generate synth sample (stereo)
-                let (mut left, mut right) = voice_manager.next_sample();
+                // Track mix (surround panners place their track as mono)
+                let mut frame = [0.0f32; MAX_SURROUND_CHANNELS];
+                for track in tracks.iter() {
+                    match &track.surround {
+                        Some(gains) => {
+                            let mono = (track.left[i] + track.right[i]) * 0.5;
+                            for (sample, gain) in frame.iter_mut().zip(gains) {
+                                *sample += mono * gain;
+                            }
+                        }
+                        None => {
+                            frame[0] += track.left[i] * track.gain_left;
+                            frame[1] += track.right[i] * track.gain_right;
+                        }
+                    }
+                }
+                let [mut left, mut right, ..] = frame;
 
-                // Add metronome if enabled
-                if let (Some(ref mut scheduler), Some(ref mut metro)) =
+                // Add metronome if enabled (while the sequencer plays)
+                if let (Some(scheduler), Some(metro)) =
                     (metronome_scheduler.as_mut(), metronome.as_mut())
                 {
-                    // Check if a click should occur in this buffer
-                    let sample_position = current_position + i as u64;
-                    if let Some((offset, click_type)) = scheduler.check_for_click(
-                        sample_position,
-                        1,
-                        self.settings.sample_rate as f64,
-                        tempo,
-                        time_signature,
-                    ) && offset == 0
+                    // Check if a click should occur at this sample
+                    if playing
+                        && let Some((offset, click_type)) = scheduler.check_for_click(
+                            song_position + i as u64,
+                            1,
+                            sample_rate as f64,
+                            tempo,
+                            time_signature,
+                        )
+                        && offset == 0
                     {
                         metro.trigger_click(click_type);
                     }
@@ -295,78 +611,308 @@ impl AudioExporter {
                 }
 
                 // Apply DSP to both channels
-                left = flush_denormals_to_zero(left);
-                right = flush_denormals_to_zero(right);
-
-                let volume = volume_smoother.process(0.5); // Fixed 50% volume for export
+                let volume = volume_smoother.next_value();
                 left *= volume;
                 right *= volume;
-
+                if let Some(chain) = master_chain.as_mut() {
+                    (left, right) = chain.process(left, right);
+                }
                 left = soft_clip(left);
                 right = soft_clip(right);
 
-                // Convert to i16 and write
-                if self.settings.channels == 2 {
-                    // Stereo: write both channels
-                    let left_i16 = (left * i16::MAX as f32) as i16;
-                    let right_i16 = (right * i16::MAX as f32) as i16;
-                    writer
-                        .write_sample(left_i16)
-                        .map_err(|e| format!("Failed to write sample: {}", e))?;
-                    writer
-                        .write_sample(right_i16)
-                        .map_err(|e| format!("Failed to write sample: {}", e))?;
-                } else {
+                if self.settings.channels == 1 {
                     // Mono: mix down to mono
                     let mono = (left + right) * 0.5;
-                    let mono_i16 = (mono * i16::MAX as f32) as i16;
-                    writer
-                        .write_sample(mono_i16)
-                        .map_err(|e| format!("Failed to write sample: {}", e))?;
+                    writer.write_sample(mono)?;
+                } else {
+                    // Front pair, then the other channels of the layout
+                    writer.write_sample(left)?;
+                    writer.write_sample(right)?;
+                    for sample in &frame[2..self.settings.channels as usize] {
+                        let sample = soft_clip(sample * volume);
+                        writer.write_sample(sample)?;
+                    }
                 }
+            }
 
-                current_position += 1;
-                samples_processed += 1;
+            current_position += samples_to_render as u64;
 
-                // Update progress callback
-                if samples_processed.is_multiple_of(progress_update_interval)
-                    && let Some(ref mut callback) = progress_callback
-                {
-                    let progress = current_position as f32 / total_samples as f32;
-                    callback(progress);
-                }
+            // Update progress callback
+            if current_position >= next_progress_update {
+                next_progress_update += progress_update_interval;
+                progress(current_position as f32 / total_samples as f32);
             }
         }
 
-        // Finalize WAV file
-        writer
-            .finalize()
-            .map_err(|e| format!("Failed to finalize WAV file: {}", e))?;
+        writer.finalize()?;
 
         println!("Audio rendering complete!");
 
         // Call progress callback one last time with 1.0
-        if let Some(ref mut callback) = progress_callback {
-            callback(1.0);
-        }
+        progress(1.0);
 
         Ok(())
     }
+}
 
-    /// Process a MIDI event (helper function)
-    fn process_midi_event(&self, timed_event: MidiEventTimed, voice_manager: &mut VoiceManager) {
-        // Process event immediately (samples_from_now is handled by sequencer)
-        match timed_event.event {
-            MidiEvent::NoteOn { note, velocity } => {
-                voice_manager.note_on(note, velocity);
+/// Tracks heard in the mix: soloed tracks if any, else every unmuted track
+fn audible_tracks(project: &Project) -> Vec<&Track> {
+    let any_solo = project.tracks.values().any(|track| track.soloed);
+    let mut tracks: Vec<&Track> = project
+        .tracks
+        .values()
+        .filter(|track| !track.muted && (!any_solo || track.soloed))
+        .collect();
+    tracks.sort_by_key(|track| track.id);
+    tracks
+}
+
+/// Length of the longest pattern, or end of the last clip (bars)
+fn song_length_bars<'a>(tracks: impl IntoIterator<Item = &'a TrackRenderer>) -> u32 {
+    tracks
+        .into_iter()
+        .map(|track| match &track.sequence {
+            TrackSequence::Pattern(pattern) => pattern.length_bars,
+            TrackSequence::Clips(clips) => {
+                clips.iter().map(PatternClip::end_bar).max().unwrap_or(0)
             }
-            MidiEvent::NoteOff { note } => {
-                voice_manager.note_off(note);
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Bounce the arrangement of a project to a WAV file
+///
+/// Renders every audible track over `range` at the project sample rate, with
+/// its speaker layout and master chain, faster than real time. `bit_depth` is
+/// 16 or 24 (integer) or 32 (floating point).
+pub fn render_to_wav(
+    project: &Project,
+    path: &Path,
+    range: &RenderRange,
+    bit_depth: u16,
+) -> Result<(), String> {
+    let settings = ExportSettings {
+        output_path: path.to_string_lossy().into_owned(),
+        format: ExportFormat::Wav,
+        sample_rate: project.metadata.sample_rate as u32,
+        bit_depth,
+        channels: project.speaker_layout.channels() as u16,
+        include_metronome: false,
+    };
+    AudioExporter::new(settings).export_project(project, range, None)?;
+    Ok(())
+}
+
+/// Convert a sample to a signed integer of `bit_depth` bits
+fn quantize(sample: f32, bit_depth: u16) -> i32 {
+    let max = ((1i64 << (bit_depth - 1)) - 1) as f32;
+    (sample.clamp(-1.0, 1.0) * max) as i32
+}
+
+/// What a track plays in an offline render
+enum TrackSequence {
+    /// A looped pattern
+    Pattern(Pattern),
+    /// The clips of the track, sorted by start (song mode)
+    Clips(Vec<PatternClip>),
+}
+
+/// One track of an offline render: its own voices and sequencer
+struct TrackRenderer {
+    sequence: TrackSequence,
+    voice_manager: VoiceManager,
+    player: SequencerPlayer,
+    gain_left: f32,
+    gain_right: f32,
+    /// Surround panner gains, track volume included (None: the stereo pan)
+    surround: Option<SurroundGains>,
+    /// Voice output for the current block
+    left: Vec<f32>,
+    right: Vec<f32>,
+    /// Whether the sequencer was stopped (note-offs already sent)
+    stopped: bool,
+}
+
+impl TrackRenderer {
+    fn new(
+        sequence: TrackSequence,
+        voice_manager: VoiceManager,
+        volume: f32,
+        pan: f32,
+        sample_rate: u32,
+    ) -> Self {
+        // Balance pan: unity at center, the opposite side fades out
+        let pan = pan.clamp(-1.0, 1.0);
+        Self {
+            sequence,
+            voice_manager,
+            player: SequencerPlayer::new(sample_rate as f64),
+            gain_left: volume * (1.0 - pan).min(1.0),
+            gain_right: volume * (1.0 + pan).min(1.0),
+            surround: None,
+            left: vec![0.0; BUFFER_SIZE],
+            right: vec![0.0; BUFFER_SIZE],
+            stopped: false,
+        }
+    }
+
+    /// Sequence and render `samples` frames into `left`/`right`
+    fn render_block(
+        &mut self,
+        position: u64,
+        playing: bool,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+        samples: usize,
+    ) {
+        // Once stopped, the voices only ring out
+        if playing || !self.stopped {
+            let midi_events = match &self.sequence {
+                TrackSequence::Pattern(pattern) => {
+                    self.player
+                        .process(pattern, position, playing, tempo, time_signature, samples)
+                }
+                TrackSequence::Clips(clips) => self.player.process_clips(
+                    clips,
+                    position,
+                    playing,
+                    tempo,
+                    time_signature,
+                    samples,
+                ),
+            };
+            for timed_event in midi_events {
+                process_midi_event(timed_event, &mut self.voice_manager);
             }
-            MidiEvent::ChannelAftertouch { value } => {
-                voice_manager.set_aftertouch(value);
+            self.stopped = !playing;
+        }
+
+        self.voice_manager
+            .process_block(&mut self.left[..samples], &mut self.right[..samples]);
+    }
+}
+
+/// Process a MIDI event (helper function)
+fn process_midi_event(timed_event: MidiEventTimed, voice_manager: &mut VoiceManager) {
+    // Process event immediately (samples_from_now is handled by sequencer)
+    match timed_event.event {
+        MidiEvent::NoteOn { note, velocity } => {
+            voice_manager.note_on(note, velocity);
+        }
+        MidiEvent::NoteOff { note } => {
+            voice_manager.note_off(note);
+        }
+        MidiEvent::ChannelAftertouch { value } => {
+            voice_manager.set_aftertouch(value);
+        }
+        MidiEvent::PitchBend { value } => {
+            voice_manager.set_pitch_bend(value);
+        }
+        MidiEvent::ControlChange {
+            controller: MOD_WHEEL_CC,
+            value,
+        } => {
+            voice_manager.set_mod_wheel(value);
+        }
+        MidiEvent::ControlChange {
+            controller: SUSTAIN_PEDAL_CC,
+            value,
+        } => {
+            voice_manager.set_sustain_pedal(value >= 64);
+        }
+        MidiEvent::ControlChange { controller, value } => {
+            voice_manager.set_controller(controller, value);
+        }
+        _ => {} // Ignore other events for now
+    }
+}
+
+/// Output file of an export (with its integer bit depth)
+enum ExportWriter {
+    Wav(WavWriter<BufWriter<File>>, u16),
+    FloatWav(WavWriter<BufWriter<File>>),
+    Flac(FlacWriter, u16),
+}
+
+impl ExportWriter {
+    fn create(path: &Path, settings: &ExportSettings) -> Result<Self, String> {
+        match (settings.format, settings.bit_depth) {
+            (_, 16 | 24) | (ExportFormat::Wav, 32) => {}
+            (ExportFormat::Wav, bit_depth) => {
+                return Err(format!(
+                    "Unsupported bit depth: {} (16, 24 or 32)",
+                    bit_depth
+                ));
             }
-            _ => {} // Ignore other events for now
+            (ExportFormat::Flac, bit_depth) => {
+                return Err(format!(
+                    "Unsupported bit depth: {} (16 or 24 for FLAC)",
+                    bit_depth
+                ));
+            }
+        }
+        if settings.channels != 1
+            && SpeakerLayout::for_channels(settings.channels as usize).is_none()
+        {
+            return Err(format!(
+                "Unsupported channel count: {} (1, 2, 4 or 6)",
+                settings.channels
+            ));
+        }
+
+        match settings.format {
+            ExportFormat::Wav => {
+                let float = settings.bit_depth == 32;
+                let spec = WavSpec {
+                    channels: settings.channels,
+                    sample_rate: settings.sample_rate,
+                    bits_per_sample: settings.bit_depth,
+                    sample_format: if float {
+                        hound::SampleFormat::Float
+                    } else {
+                        hound::SampleFormat::Int
+                    },
+                };
+                let writer = WavWriter::create(path, spec)
+                    .map_err(|e| format!("Failed to create WAV file: {}", e))?;
+                Ok(if float {
+                    ExportWriter::FloatWav(writer)
+                } else {
+                    ExportWriter::Wav(writer, settings.bit_depth)
+                })
+            }
+            ExportFormat::Flac => FlacWriter::create(
+                path,
+                settings.channels,
+                settings.sample_rate,
+                settings.bit_depth,
+            )
+            .map(|writer| ExportWriter::Flac(writer, settings.bit_depth)),
+        }
+    }
+
+    /// Write a sample (-1.0 to 1.0), quantized for integer formats
+    fn write_sample(&mut self, sample: f32) -> Result<(), String> {
+        match self {
+            ExportWriter::Wav(writer, bit_depth) => writer
+                .write_sample(quantize(sample, *bit_depth))
+                .map_err(|e| format!("Failed to write sample: {}", e)),
+            ExportWriter::FloatWav(writer) => writer
+                .write_sample(sample)
+                .map_err(|e| format!("Failed to write sample: {}", e)),
+            ExportWriter::Flac(writer, bit_depth) => {
+                writer.write_sample(quantize(sample, *bit_depth))
+            }
+        }
+    }
+
+    fn finalize(self) -> Result<(), String> {
+        match self {
+            ExportWriter::Wav(writer, _) | ExportWriter::FloatWav(writer) => writer
+                .finalize()
+                .map_err(|e| format!("Failed to finalize WAV file: {}", e)),
+            ExportWriter::Flac(writer, _) => writer.finalize(),
         }
     }
 }
@@ -458,4 +1004,232 @@ mod tests {
         let metadata = std::fs::metadata(&output_path).unwrap();
         assert!(metadata.len() > 1000, "File should contain audio data");
     }
+
+    /// Two-track project: the default 4-bar pattern and a 2-bar lead at 120 BPM, 48 kHz
+    fn two_track_project() -> Project {
+        let manager = crate::project::ProjectManager::new(48000.0);
+        let mut project = manager.create_new_project("Render".to_string());
+
+        let mut notes = crate::project::types::PatternSerializable {
+            id: 900,
+            name: "Lead".to_string(),
+            length_bars: 2,
+            notes: Vec::new(),
+            groove: Default::default(),
+        };
+        notes.notes.push(crate::project::types::SerializableNote {
+            id: 901,
+            pitch: 69,
+            start_samples: 0,
+            duration_samples: 24000,
+            velocity: 100,
+            probability: 1.0,
+            offset_ticks: 0,
+        });
+        project.patterns.insert(notes.id, notes);
+
+        let mut lead = project.tracks[&0].clone();
+        lead.id = 1;
+        lead.name = "Lead / Synth".to_string();
+        lead.pattern_id = Some(900);
+        lead.pan = 1.0;
+        project.tracks.insert(lead.id, lead);
+        project
+    }
+
+    fn settings(path: &Path, format: ExportFormat) -> ExportSettings {
+        ExportSettings {
+            output_path: path.to_str().unwrap().to_string(),
+            format,
+            sample_rate: 24000,
+            bit_depth: 16,
+            channels: 2,
+            include_metronome: false,
+        }
+    }
+
+    #[test]
+    fn test_export_project_range() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("mix.wav");
+        let project = two_track_project();
+        let exporter = AudioExporter::new(settings(&output_path, ExportFormat::Wav));
+
+        // Whole song: the longest pattern (4 bars of 2 s at 24 kHz)
+        exporter
+            .export_project(&project, &RenderRange::default(), None)
+            .unwrap();
+        let reader = hound::WavReader::open(&output_path).unwrap();
+        assert_eq!(reader.duration(), 192000);
+
+        // Bar 2 only, with a half-second tail
+        let range = RenderRange {
+            start_bar: 1,
+            end_bar: Some(2),
+            tail_seconds: 0.5,
+        };
+        exporter.export_project(&project, &range, None).unwrap();
+        let reader = hound::WavReader::open(&output_path).unwrap();
+        assert_eq!(reader.duration(), 48000 + 12000);
+
+        let empty = RenderRange {
+            start_bar: 2,
+            end_bar: Some(2),
+            tail_seconds: 0.0,
+        };
+        assert!(exporter.export_project(&project, &empty, None).is_err());
+    }
+
+    #[test]
+    fn test_export_song_mode_plays_clips() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("song.wav");
+        let mut project = two_track_project();
+        project.song_mode = true;
+        project
+            .arrangement
+            .push(crate::project::types::ClipSerializable {
+                track: 1,
+                pattern_id: 900,
+                start_bar: 2,
+                length_bars: 1,
+            });
+        let exporter = AudioExporter::new(settings(&output_path, ExportFormat::Wav));
+
+        // The song ends with its last clip (3 bars of 2 s at 24 kHz)
+        exporter
+            .export_project(&project, &RenderRange::default(), None)
+            .unwrap();
+        let mut reader = hound::WavReader::open(&output_path).unwrap();
+        assert_eq!(reader.duration(), 144000);
+        let samples: Vec<i16> = reader.samples::<i16>().map(|s| s.unwrap()).collect();
+        // Silent until the clip starts on bar 3 (the default track has no
+        // clip); events are played from the start of their block
+        let clip_start = 2 * 48000 * 2;
+        let first = samples.iter().position(|s| *s != 0).unwrap();
+        assert!((clip_start - BUFFER_SIZE * 2..clip_start).contains(&first));
+    }
+
+    #[test]
+    fn test_render_to_wav_bit_depths() {
+        let dir = tempdir().unwrap();
+        let project = two_track_project();
+        let range = RenderRange {
+            start_bar: 0,
+            end_bar: Some(1),
+            tail_seconds: 0.0,
+        };
+
+        // Floating point: the lead note is there, unquantized
+        let path = dir.path().join("float.wav");
+        render_to_wav(&project, &path, &range, 32).unwrap();
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.sample_format, hound::SampleFormat::Float);
+        assert_eq!((spec.sample_rate, spec.channels), (48000, 2));
+        assert_eq!(reader.duration(), 96000);
+        let peak = reader
+            .samples::<f32>()
+            .map(|sample| sample.unwrap().abs())
+            .fold(0.0, f32::max);
+        assert!(peak > 0.01 && peak <= 1.0);
+
+        let path = dir.path().join("int.wav");
+        render_to_wav(&project, &path, &range, 24).unwrap();
+        let spec = hound::WavReader::open(&path).unwrap().spec();
+        assert_eq!(spec.sample_format, hound::SampleFormat::Int);
+        assert_eq!(spec.bits_per_sample, 24);
+
+        // 32 bits is WAV only
+        assert!(render_to_wav(&project, &path, &range, 8).is_err());
+        let mut flac = settings(&dir.path().join("mix.flac"), ExportFormat::Flac);
+        flac.bit_depth = 32;
+        let exporter = AudioExporter::new(flac);
+        assert!(exporter.export_project(&project, &range, None).is_err());
+    }
+
+    #[test]
+    fn test_mixdown_runs_master_chain() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("limited.wav");
+        let mut project = two_track_project();
+        project.master_chain.limiter = crate::synth::limiter::LimiterParams::new(-20.0, 50.0);
+        let exporter = AudioExporter::new(settings(&output_path, ExportFormat::Wav));
+
+        exporter
+            .export_project(&project, &RenderRange::default(), None)
+            .unwrap();
+        let mut reader = hound::WavReader::open(&output_path).unwrap();
+        let ceiling = (10f32.powf(-20.0 / 20.0) * i16::MAX as f32) as i32;
+        let samples: Vec<i32> = reader.samples::<i32>().map(|s| s.unwrap()).collect();
+        assert!(samples.iter().any(|&sample| sample != 0));
+        assert!(samples.iter().all(|&sample| sample.abs() <= ceiling + 1));
+    }
+
+    #[test]
+    fn test_export_stems_follow_mute_and_pan() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("song.flac");
+        let mut project = two_track_project();
+        let exporter = AudioExporter::new(settings(&output_path, ExportFormat::Flac));
+
+        let stems = exporter
+            .export_stems(&project, &RenderRange::default(), None)
+            .unwrap();
+        assert_eq!(stems.len(), 2);
+        assert!(
+            stems[1].ends_with("song_1_Lead___Synth.flac"),
+            "{}",
+            stems[1]
+        );
+
+        // The lead is panned hard right: a silent left channel
+        let mut reader = claxon::FlacReader::open(&stems[1]).unwrap();
+        assert_eq!(reader.streaminfo().samples, Some(192000));
+        let samples: Vec<i32> = reader.samples().map(|s| s.unwrap()).collect();
+        assert!(samples.iter().step_by(2).all(|&left| left == 0));
+        assert!(samples.iter().skip(1).step_by(2).any(|&right| right != 0));
+
+        // Muted tracks are left out
+        project.tracks.get_mut(&0).unwrap().muted = true;
+        let stems = exporter
+            .export_stems(&project, &RenderRange::default(), None)
+            .unwrap();
+        assert_eq!(stems.len(), 1);
+    }
+
+    #[test]
+    fn test_surround_export_places_tracks() {
+        let dir = tempdir().unwrap();
+        let output_path = dir.path().join("surround.wav");
+        let mut project = two_track_project();
+        project.tracks.get_mut(&0).unwrap().muted = true;
+        project.tracks.get_mut(&1).unwrap().surround = Some(crate::audio::surround::SurroundPan {
+            x: -1.0,
+            y: 1.0,
+            ..Default::default()
+        });
+        let mut settings = settings(&output_path, ExportFormat::Wav);
+        settings.channels = 4;
+        AudioExporter::new(settings.clone())
+            .export_project(&project, &RenderRange::default(), None)
+            .unwrap();
+
+        // Quad (FL FR RL RR): the lead is panned to the rear left speaker
+        let mut reader = hound::WavReader::open(&output_path).unwrap();
+        assert_eq!(reader.spec().channels, 4);
+        let samples: Vec<i32> = reader.samples::<i32>().map(|s| s.unwrap()).collect();
+        for (channel, sounding) in [false, false, true, false].into_iter().enumerate() {
+            let mut channel = samples.iter().skip(channel).step_by(4);
+            assert_eq!(channel.any(|&sample| sample != 0), sounding);
+        }
+
+        settings.channels = 3;
+        let exporter = AudioExporter::new(settings);
+        assert!(
+            exporter
+                .export_project(&project, &RenderRange::default(), None)
+                .is_err()
+        );
+    }
 }
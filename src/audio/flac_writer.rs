@@ -0,0 +1,455 @@
+// FLAC writer - Lossless encoder for offline exports
+//
+// A small FLAC encoder, enough for bounces: fixed block size, independent
+// channels, and per channel the cheapest of a CONSTANT subframe (silence),
+// a FIXED predictor (orders 0-4) with Rice-coded residuals, or VERBATIM.
+// No LPC nor stereo decorrelation: files are larger than with the reference
+// encoder, but any FLAC decoder reads them.
+//
+// The STREAMINFO block is written with placeholders on creation and rewritten
+// by `finalize()` once the total length and frame sizes are known (the MD5
+// signature is left unset, which the format allows).
+
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Frames per FLAC block
+const BLOCK_SIZE: usize = 4096;
+/// Largest Rice parameter (15 is the escape code)
+const MAX_RICE_PARAM: u32 = 14;
+/// Offset of the STREAMINFO data ("fLaC" + metadata block header)
+const STREAMINFO_OFFSET: u64 = 8;
+
+/// Streaming FLAC file writer (interleaved integer samples)
+pub struct FlacWriter {
+    file: BufWriter<File>,
+    channels: u16,
+    sample_rate: u32,
+    bits_per_sample: u16,
+    /// Interleaved samples of the block being filled
+    pending: Vec<i32>,
+    frame_number: u64,
+    total_frames: u64,
+    min_frame_bytes: u32,
+    max_frame_bytes: u32,
+}
+
+impl FlacWriter {
+    /// Create a FLAC file (1-8 channels, 8-24 bits)
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        channels: u16,
+        sample_rate: u32,
+        bits_per_sample: u16,
+    ) -> Result<Self, String> {
+        if !(1..=8).contains(&channels) {
+            return Err(format!("FLAC supports 1 to 8 channels, not {}", channels));
+        }
+        if !(8..=24).contains(&bits_per_sample) {
+            return Err(format!(
+                "FLAC export supports 8 to 24 bits, not {}",
+                bits_per_sample
+            ));
+        }
+        if sample_rate == 0 || sample_rate >= 1 << 20 {
+            return Err(format!("Invalid FLAC sample rate: {}", sample_rate));
+        }
+
+        let file = File::create(path).map_err(|e| format!("Failed to create FLAC file: {}", e))?;
+        let mut writer = Self {
+            file: BufWriter::new(file),
+            channels,
+            sample_rate,
+            bits_per_sample,
+            pending: Vec::with_capacity(BLOCK_SIZE * channels as usize),
+            frame_number: 0,
+            total_frames: 0,
+            min_frame_bytes: 0,
+            max_frame_bytes: 0,
+        };
+
+        // Last metadata block, type 0 (STREAMINFO), 34 bytes
+        writer
+            .file
+            .write_all(b"fLaC")
+            .and_then(|()| writer.file.write_all(&[0x80, 0, 0, 34]))
+            .map_err(|e| format!("Failed to write FLAC header: {}", e))?;
+        let streaminfo = writer.streaminfo();
+        writer
+            .file
+            .write_all(&streaminfo)
+            .map_err(|e| format!("Failed to write FLAC header: {}", e))?;
+
+        Ok(writer)
+    }
+
+    /// Write one sample (channels interleaved, as with `hound::WavWriter`)
+    pub fn write_sample(&mut self, sample: i32) -> Result<(), String> {
+        self.pending.push(sample);
+        if self.pending.len() == BLOCK_SIZE * self.channels as usize {
+            self.flush_block()?;
+        }
+        Ok(())
+    }
+
+    /// Encode the last block and complete the STREAMINFO block
+    pub fn finalize(mut self) -> Result<(), String> {
+        // A trailing partial frame is dropped
+        let partial = self.pending.len() % self.channels as usize;
+        self.pending.truncate(self.pending.len() - partial);
+        if !self.pending.is_empty() {
+            self.flush_block()?;
+        }
+
+        let streaminfo = self.streaminfo();
+        self.file
+            .seek(SeekFrom::Start(STREAMINFO_OFFSET))
+            .and_then(|_| self.file.write_all(&streaminfo))
+            .and_then(|()| self.file.flush())
+            .map_err(|e| format!("Failed to finalize FLAC file: {}", e))
+    }
+
+    fn streaminfo(&self) -> Vec<u8> {
+        let mut bits = BitWriter::default();
+        bits.write(BLOCK_SIZE as u64, 16); // min block size
+        bits.write(BLOCK_SIZE as u64, 16); // max block size
+        bits.write(self.min_frame_bytes as u64, 24);
+        bits.write(self.max_frame_bytes as u64, 24);
+        bits.write(self.sample_rate as u64, 20);
+        bits.write(self.channels as u64 - 1, 3);
+        bits.write(self.bits_per_sample as u64 - 1, 5);
+        bits.write(self.total_frames, 36);
+        bits.write(0, 64); // MD5 signature unset
+        bits.write(0, 64);
+        bits.bytes
+    }
+
+    fn flush_block(&mut self) -> Result<(), String> {
+        let channels = self.channels as usize;
+        let block_frames = self.pending.len() / channels;
+        let bps = self.bits_per_sample as u32;
+
+        let mut bits = BitWriter::default();
+
+        // Frame header
+        bits.write(0b11_1111_1111_1110, 14); // sync code
+        bits.write(0, 1); // reserved
+        bits.write(0, 1); // fixed block size
+        bits.write(0b0111, 4); // block size in a 16-bit field
+        bits.write(0b0000, 4); // sample rate from STREAMINFO
+        bits.write(channels as u64 - 1, 4); // independent channels
+        bits.write(sample_size_code(self.bits_per_sample), 3);
+        bits.write(0, 1); // reserved
+        bits.write_utf8(self.frame_number);
+        bits.write(block_frames as u64 - 1, 16);
+        let crc = crc8(&bits.bytes);
+        bits.write(crc as u64, 8);
+
+        // One subframe per channel
+        let mut samples = Vec::with_capacity(block_frames);
+        for channel in 0..channels {
+            samples.clear();
+            samples.extend(
+                self.pending
+                    .iter()
+                    .skip(channel)
+                    .step_by(channels)
+                    .map(|&s| s as i64),
+            );
+            write_subframe(&mut bits, &samples, bps);
+        }
+
+        bits.align();
+        let crc = crc16(&bits.bytes);
+        bits.write(crc as u64, 16);
+
+        self.file
+            .write_all(&bits.bytes)
+            .map_err(|e| format!("Failed to write FLAC frame: {}", e))?;
+
+        let frame_bytes = bits.bytes.len() as u32;
+        self.min_frame_bytes = if self.frame_number == 0 {
+            frame_bytes
+        } else {
+            self.min_frame_bytes.min(frame_bytes)
+        };
+        self.max_frame_bytes = self.max_frame_bytes.max(frame_bytes);
+        self.frame_number += 1;
+        self.total_frames += block_frames as u64;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+/// Encode one channel of a block with the cheapest subframe type
+fn write_subframe(bits: &mut BitWriter, samples: &[i64], bps: u32) {
+    // Constant (e.g. digital silence)
+    if samples.iter().all(|&s| s == samples[0]) {
+        bits.write(0, 1);
+        bits.write(0b000000, 6);
+        bits.write(0, 1);
+        bits.write_signed(samples[0], bps);
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * bps as u64;
+    let max_order = 4.min(samples.len() - 1);
+    let best = (0..=max_order)
+        .map(|order| {
+            let residuals = fixed_residuals(samples, order);
+            let (rice_param, residual_bits) = best_rice_param(&residuals);
+            let total = order as u64 * bps as u64 + 10 + residual_bits;
+            (total, order, rice_param, residuals)
+        })
+        .min_by_key(|(total, ..)| *total);
+
+    match best {
+        Some((total, order, rice_param, residuals)) if total < verbatim_bits => {
+            bits.write(0, 1);
+            bits.write(0b001000 | order as u64, 6);
+            bits.write(0, 1);
+            for &sample in &samples[..order] {
+                bits.write_signed(sample, bps);
+            }
+            bits.write(0b00, 2); // Rice coding, 4-bit parameters
+            bits.write(0, 4); // a single partition
+            bits.write(rice_param as u64, 4);
+            for &residual in &residuals {
+                let folded = zigzag(residual);
+                bits.write_unary(folded >> rice_param);
+                bits.write(folded, rice_param);
+            }
+        }
+        _ => {
+            bits.write(0, 1);
+            bits.write(0b000001, 6);
+            bits.write(0, 1);
+            for &sample in samples {
+                bits.write_signed(sample, bps);
+            }
+        }
+    }
+}
+
+/// Residuals of the FIXED predictor of `order`
+fn fixed_residuals(samples: &[i64], order: usize) -> Vec<i64> {
+    (order..samples.len())
+        .map(|i| {
+            let s = |back: usize| samples[i - back];
+            match order {
+                0 => s(0),
+                1 => s(0) - s(1),
+                2 => s(0) - 2 * s(1) + s(2),
+                3 => s(0) - 3 * s(1) + 3 * s(2) - s(3),
+                _ => s(0) - 4 * s(1) + 6 * s(2) - 4 * s(3) + s(4),
+            }
+        })
+        .collect()
+}
+
+/// Rice parameter with the fewest bits, and that bit count
+fn best_rice_param(residuals: &[i64]) -> (u32, u64) {
+    (0..=MAX_RICE_PARAM)
+        .map(|param| {
+            let bits = residuals
+                .iter()
+                .map(|&r| (zigzag(r) >> param) + 1 + param as u64)
+                .sum::<u64>();
+            (param, bits)
+        })
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap_or((0, 0))
+}
+
+#[inline]
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn sample_size_code(bits_per_sample: u16) -> u64 {
+    match bits_per_sample {
+        8 => 0b001,
+        12 => 0b010,
+        16 => 0b100,
+        20 => 0b101,
+        24 => 0b110,
+        _ => 0b000, // from STREAMINFO
+    }
+}
+
+/// MSB-first bit packer
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    accumulator: u64,
+    pending_bits: u32,
+}
+
+impl BitWriter {
+    /// Write the low `count` bits of `value` (at most 32 per call)
+    fn write(&mut self, value: u64, count: u32) {
+        if count > 32 {
+            self.write(value >> 32, count - 32);
+            self.write(value & 0xFFFF_FFFF, 32);
+            return;
+        }
+        if count == 0 {
+            return;
+        }
+        self.accumulator = (self.accumulator << count) | (value & ((1u64 << count) - 1));
+        self.pending_bits += count;
+        while self.pending_bits >= 8 {
+            self.pending_bits -= 8;
+            self.bytes
+                .push((self.accumulator >> self.pending_bits) as u8);
+        }
+        self.accumulator &= (1u64 << self.pending_bits) - 1;
+    }
+
+    /// Two's complement value on `count` bits
+    fn write_signed(&mut self, value: i64, count: u32) {
+        self.write(value as u64, count);
+    }
+
+    /// `value` zeros followed by a one
+    fn write_unary(&mut self, mut value: u64) {
+        while value >= 32 {
+            self.write(0, 32);
+            value -= 32;
+        }
+        self.write(1, value as u32 + 1);
+    }
+
+    /// Frame number in the UTF-8-like coding of FLAC frame headers
+    fn write_utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write(value, 8);
+            return;
+        }
+        let continuation_bytes = match value {
+            0x80..0x800 => 1,
+            0x800..0x1_0000 => 2,
+            0x1_0000..0x20_0000 => 3,
+            0x20_0000..0x400_0000 => 4,
+            0x400_0000..0x8000_0000 => 5,
+            _ => 6,
+        };
+        let lead_marker = (0xFF00u64 >> (continuation_bytes + 1)) & 0xFF;
+        self.write(lead_marker | (value >> (6 * continuation_bytes)), 8);
+        for byte in (0..continuation_bytes).rev() {
+            self.write(0x80 | ((value >> (6 * byte)) & 0x3F), 8);
+        }
+    }
+
+    /// Pad with zeros to a byte boundary
+    fn align(&mut self) {
+        if self.pending_bits > 0 {
+            self.write(0, 8 - self.pending_bits);
+        }
+    }
+}
+
+/// CRC-8 of frame headers (polynomial x^8 + x^2 + x + 1)
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |crc, &byte| {
+        (0..8).fold(crc ^ byte, |crc, _| {
+            if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// CRC-16 of whole frames (polynomial x^16 + x^15 + x^2 + 1)
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn decode(path: &Path) -> (claxon::metadata::StreamInfo, Vec<i32>) {
+        let mut reader = claxon::FlacReader::open(path).unwrap();
+        let info = reader.streaminfo();
+        let samples = reader.samples().map(|s| s.unwrap()).collect();
+        (info, samples)
+    }
+
+    #[test]
+    fn test_round_trip_is_lossless() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tone.flac");
+
+        // Several blocks of a stereo tone, then silence, then a partial block
+        let frames = BLOCK_SIZE * 3 + 1000;
+        let samples: Vec<i32> = (0..frames)
+            .flat_map(|i| {
+                let tone = if i < BLOCK_SIZE * 2 {
+                    ((i as f32 * 0.05).sin() * 20000.0) as i32
+                } else {
+                    0
+                };
+                [tone, -tone / 2 + (i % 7) as i32]
+            })
+            .collect();
+
+        let mut writer = FlacWriter::create(&path, 2, 44100, 16).unwrap();
+        for &sample in &samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (info, decoded) = decode(&path);
+        assert_eq!(info.sample_rate, 44100);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.bits_per_sample, 16);
+        assert_eq!(info.samples, Some(frames as u64));
+        assert_eq!(decoded, samples);
+
+        // Compressed below the size of the raw PCM
+        let size = std::fs::metadata(&path).unwrap().len();
+        assert!(size < (frames * 2 * 2) as u64 / 2, "size {}", size);
+    }
+
+    #[test]
+    fn test_24_bit_extremes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("extremes.flac");
+
+        let max = (1 << 23) - 1;
+        let samples: Vec<i32> = (0..5000)
+            .map(|i| if i % 2 == 0 { max } else { -max - 1 })
+            .collect();
+
+        let mut writer = FlacWriter::create(&path, 1, 96000, 24).unwrap();
+        for &sample in &samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (info, decoded) = decode(&path);
+        assert_eq!(info.bits_per_sample, 24);
+        assert_eq!(decoded, samples);
+    }
+
+    #[test]
+    fn test_rejects_unsupported_formats() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("bad.flac");
+        assert!(FlacWriter::create(&path, 0, 44100, 16).is_err());
+        assert!(FlacWriter::create(&path, 2, 44100, 32).is_err());
+    }
+}
@@ -6,8 +6,50 @@
 // - u16: 16-bit unsigned integer (less common)
 //
 // All conversions are allocation-free and suitable for real-time audio callbacks.
+//
+// Output buffers are written through `OutputSample`: with the `audio-backend`
+// feature it uses CPAL's own conversions, without it (null backend) the
+// conversions below.
+
+/// Sample type of an output buffer, converted from the internal f32
+pub trait OutputSample: Copy + Send + 'static {
+    fn from_f32(sample: f32) -> Self;
+}
+
+#[cfg(feature = "audio-backend")]
+impl<T> OutputSample for T
+where
+    T: cpal::Sample + cpal::FromSample<f32> + Send + 'static,
+{
+    #[inline]
+    fn from_f32(sample: f32) -> Self {
+        <T as cpal::Sample>::from_sample::<f32>(sample)
+    }
+}
 
-use cpal::{FromSample, Sample};
+#[cfg(not(feature = "audio-backend"))]
+impl OutputSample for f32 {
+    #[inline]
+    fn from_f32(sample: f32) -> Self {
+        sample
+    }
+}
+
+#[cfg(not(feature = "audio-backend"))]
+impl OutputSample for i16 {
+    #[inline]
+    fn from_f32(sample: f32) -> Self {
+        f32_to_i16(sample)
+    }
+}
+
+#[cfg(not(feature = "audio-backend"))]
+impl OutputSample for u16 {
+    #[inline]
+    fn from_f32(sample: f32) -> Self {
+        f32_to_u16(sample)
+    }
+}
 
 /// Convert f32 sample to i16
 ///
@@ -71,9 +113,9 @@ pub fn u16_to_f32(sample: u16) -> f32 {
 #[inline]
 pub fn write_sample_to_buffer<T>(sample: f32, output: &mut T)
 where
-    T: Sample + FromSample<f32>,
+    T: OutputSample,
 {
-    *output = Sample::from_sample::<f32>(sample);
+    *output = T::from_f32(sample);
 }
 
 /// Process interleaved audio buffer
@@ -87,10 +129,10 @@ where
 #[inline]
 pub fn write_mono_to_interleaved_frame<T>(internal_sample: f32, output_frame: &mut [T])
 where
-    T: Sample + FromSample<f32>,
+    T: OutputSample,
 {
     for channel_sample in output_frame.iter_mut() {
-        *channel_sample = Sample::from_sample::<f32>(internal_sample);
+        *channel_sample = T::from_f32(internal_sample);
     }
 }
 
@@ -107,19 +149,19 @@ pub fn write_stereo_to_interleaved_frame<T>(
     (left_sample, right_sample): (f32, f32),
     output_frame: &mut [T],
 ) where
-    T: Sample + FromSample<f32>,
+    T: OutputSample,
 {
     if output_frame.len() >= 2 {
-        output_frame[0] = Sample::from_sample::<f32>(left_sample);
-        output_frame[1] = Sample::from_sample::<f32>(right_sample);
+        output_frame[0] = T::from_f32(left_sample);
+        output_frame[1] = T::from_f32(right_sample);
         // For > 2 channels, we could either write silence or duplicate L/R
         for channel_sample in output_frame.iter_mut().skip(2) {
-            *channel_sample = Sample::from_sample::<f32>(0.0);
+            *channel_sample = T::from_f32(0.0);
         }
     } else if let Some(channel_sample) = output_frame.first_mut() {
         // Fallback for mono output: mix L and R
         let mono_sample = (left_sample + right_sample) * 0.5;
-        *channel_sample = Sample::from_sample::<f32>(mono_sample);
+        *channel_sample = T::from_f32(mono_sample);
     }
 }
 
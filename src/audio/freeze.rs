@@ -0,0 +1,208 @@
+// Track freeze - Bounce a track in place to free CPU
+//
+// Freezing renders a pattern track offline (see `audio::export`) to a
+// temporary WAV file, which then plays on an audio track (see
+// `audio::clip_player`) while the track's voices are silenced. Unfreezing
+// deletes the file and gives the pattern back to the live voices.
+//
+// The render is pre-fader: the track volume and pan are applied by the audio
+// track, so they stay adjustable while frozen. Plugins process the master bus
+// and keep running live; once tracks get their own plugin chains, those are
+// part of what the render has to include.
+//
+// Patterns loop, so the render covers two cycles and the clip loops over the
+// second one: it starts with the release tails of the previous cycle, as the
+// live pattern does after its first pass. The render is made at the rate
+// samples are loaded at, so that loading it back does not resample it.
+
+use crate::audio::clip_player::AudioClip;
+use crate::audio::export::{AudioExporter, ExportFormat, ExportSettings, RenderRange};
+use crate::project::types::TrackType;
+use crate::project::{Project, Track, generate_project_id};
+use crate::sampler::load_sample;
+use crate::sampler::loader::TARGET_SAMPLE_RATE;
+use crate::sequencer::Tempo;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Bit depth of the frozen renders
+const FREEZE_BIT_DEPTH: u16 = 24;
+
+/// Directory holding the frozen renders
+pub fn freeze_directory() -> PathBuf {
+    std::env::temp_dir().join("mymusic_daw").join("freeze")
+}
+
+/// A track rendered to audio
+#[derive(Debug, Clone)]
+pub struct FrozenTrack {
+    /// Frozen project track
+    pub track_id: u32,
+    /// Temporary render (deleted by `discard`)
+    pub path: PathBuf,
+    /// Looping clip playing the render from the start of the timeline
+    pub clip: AudioClip,
+}
+
+impl FrozenTrack {
+    /// Delete the render (the clip must no longer be played)
+    pub fn discard(&self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            eprintln!(
+                "Failed to delete frozen track {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Render a pattern track of `project` at the engine `sample_rate`
+pub fn freeze_track(
+    project: &Project,
+    track_id: u32,
+    sample_rate: u32,
+) -> Result<FrozenTrack, String> {
+    let track = project
+        .tracks
+        .get(&track_id)
+        .ok_or_else(|| format!("Track {} not found", track_id))?;
+    if track.track_type == TrackType::Audio {
+        return Err(format!("Track '{}' is already audio", track.name));
+    }
+    let pattern = track
+        .pattern_id
+        .and_then(|id| project.patterns.get(&id))
+        .filter(|pattern| !pattern.notes.is_empty() && pattern.length_bars > 0)
+        .ok_or_else(|| format!("Track '{}' has no notes to freeze", track.name))?;
+
+    // The track alone, pre-fader and at unity master volume
+    let mut solo = project.clone();
+    solo.tracks = [(
+        track_id,
+        Track {
+            volume: 1.0,
+            pan: 0.0,
+            muted: false,
+            soloed: false,
+            // Rendered dry: the frozen track gets the panner
+            surround: None,
+            ..track.clone()
+        },
+    )]
+    .into();
+    solo.synth_params.volume = 1.0;
+
+    let directory = freeze_directory();
+    std::fs::create_dir_all(&directory)
+        .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+    let path = directory.join(format!(
+        "track_{}_{}_{}.wav",
+        track_id,
+        std::process::id(),
+        generate_project_id()
+    ));
+
+    // Samples are loaded as mono: render mono
+    let exporter = AudioExporter::new(ExportSettings {
+        output_path: path.to_string_lossy().into_owned(),
+        format: ExportFormat::Wav,
+        sample_rate: TARGET_SAMPLE_RATE,
+        bit_depth: FREEZE_BIT_DEPTH,
+        channels: 1,
+        include_metronome: false,
+    });
+    let range = RenderRange {
+        start_bar: 0,
+        end_bar: Some(pattern.length_bars * 2),
+        tail_seconds: 0.0,
+    };
+    exporter.export_project(&solo, &range, None)?;
+
+    let sample = match load_sample(&path) {
+        Ok(sample) => sample,
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            return Err(e);
+        }
+    };
+
+    // Same cycle length as the sequencer playing the pattern (engine samples)
+    let bar_samples = Tempo::new(project.metadata.tempo)
+        .bar_duration_samples(sample_rate as f64, &project.metadata.time_signature);
+    let cycle = (bar_samples * pattern.length_bars as f64) as u64;
+
+    let mut clip = AudioClip::new(path.clone(), Arc::new(sample), 0, sample_rate as f32);
+    clip.name = format!("{} (frozen)", track.name);
+    clip.offset = cycle;
+    clip.length = cycle;
+    clip.looping = true;
+    clip.clamp(sample_rate as f32);
+
+    Ok(FrozenTrack {
+        track_id,
+        path,
+        clip,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::ProjectManager;
+    use crate::project::types::SerializableNote;
+    use crate::sampler::SampleData;
+
+    const SAMPLE_RATE: u32 = 24000;
+
+    /// One-bar pattern at 120 BPM (a bar is 48000 samples at 24 kHz)
+    fn project_with_note() -> Project {
+        let mut project = ProjectManager::new(48000.0).create_new_project("Freeze".to_string());
+        let pattern_id = project.tracks[&0].pattern_id.unwrap();
+        let pattern = project.patterns.get_mut(&pattern_id).unwrap();
+        pattern.length_bars = 1;
+        pattern.notes.push(SerializableNote {
+            id: 1,
+            pitch: 69,
+            start_samples: 0,
+            duration_samples: 24000,
+            velocity: 100,
+            probability: 1.0,
+            offset_ticks: 0,
+        });
+        project
+    }
+
+    #[test]
+    fn test_freeze_track() {
+        let mut project = project_with_note();
+        // Pre-fader: the track volume and mute are left to the audio track
+        let track = project.tracks.get_mut(&0).unwrap();
+        track.volume = 0.0;
+        track.muted = true;
+
+        let frozen = freeze_track(&project, 0, SAMPLE_RATE).unwrap();
+        assert!(frozen.path.exists());
+        assert!(frozen.clip.looping);
+        assert_eq!((frozen.clip.offset, frozen.clip.length), (48000, 48000));
+        assert_eq!(frozen.clip.name, "Track 1 (frozen)");
+
+        // Rendered at the sample rate, the note sounds at the start of the played cycle
+        let SampleData::F32(data) = &frozen.clip.sample.data;
+        assert_eq!(data.len(), 192000);
+        assert!(data[96000..96000 + 4800].iter().any(|s| s.abs() > 0.01));
+
+        frozen.discard();
+        assert!(!frozen.path.exists());
+    }
+
+    #[test]
+    fn test_freeze_needs_notes() {
+        let mut project = project_with_note();
+        assert!(freeze_track(&project, 5, SAMPLE_RATE).is_err());
+
+        let pattern_id = project.tracks[&0].pattern_id.unwrap();
+        project.patterns.get_mut(&pattern_id).unwrap().notes.clear();
+        assert!(freeze_track(&project, 0, SAMPLE_RATE).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+// Garbage chute - RT-safe deferred deallocation
+//
+// Objects discarded by the audio thread (replaced or removed samples, old
+// patterns and arrangements, audio track and automation snapshots,
+// instruments of track commands) are pushed into a return ring buffer instead of being dropped in
+// the callback. A collector thread drains it and frees the memory off the
+// real-time path.
+//
+// Samples and patterns are reference counted and may still be held elsewhere
+// (playing voices, the UI's pattern store), so the collector keeps them until
+// it owns the last reference: the final decrement, and thus `free()`, always
+// happens on the collector thread.
+//
+// Plugin instances never reach the audio thread by value: they are destroyed
+// from the UI thread by `PluginHost::destroy_instance`.
+
+use crate::audio::clip_player::AudioTrack;
+use crate::audio::instruments::TrackInstrument;
+use crate::automation::AutomationLane;
+use crate::sampler::loader::Sample;
+use crate::sequencer::{Arrangement, Pattern, Session};
+use ringbuf::traits::{Consumer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Default capacity of the return ring buffer
+pub const GARBAGE_CHUTE_CAPACITY: usize = 256;
+
+/// How often the collector thread drains the chute
+const COLLECT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Object discarded by the audio thread
+pub enum Garbage {
+    Sample(Arc<Sample>),
+    Pattern(Arc<Pattern>),
+    Arrangement(Arc<Arrangement>),
+    Session(Arc<Session>),
+    AudioTracks(Arc<Vec<AudioTrack>>),
+    Automation(Arc<Vec<AutomationLane>>),
+    Instrument(Arc<TrackInstrument>),
+}
+
+impl Garbage {
+    /// Whether another owner still holds a reference
+    fn is_shared(&self) -> bool {
+        match self {
+            Garbage::Sample(sample) => Arc::strong_count(sample) > 1,
+            Garbage::Pattern(pattern) => Arc::strong_count(pattern) > 1,
+            Garbage::Arrangement(arrangement) => Arc::strong_count(arrangement) > 1,
+            Garbage::Session(session) => Arc::strong_count(session) > 1,
+            Garbage::AudioTracks(tracks) => Arc::strong_count(tracks) > 1,
+            Garbage::Automation(lanes) => Arc::strong_count(lanes) > 1,
+            Garbage::Instrument(instrument) => Arc::strong_count(instrument) > 1,
+        }
+    }
+}
+
+pub type GarbageProducer = HeapProd<Garbage>;
+pub type GarbageConsumer = HeapCons<Garbage>;
+
+/// Audio thread side of the chute
+pub struct GarbageChute {
+    tx: GarbageProducer,
+    overflow: Arc<AtomicU64>,
+}
+
+impl GarbageChute {
+    /// Hand an object over to the collector thread (RT-safe, never blocks)
+    ///
+    /// If the chute is full the object is dropped in place and the overflow
+    /// counter is incremented: this only happens if the collector stalls.
+    pub fn dispose(&mut self, garbage: Garbage) {
+        if self.tx.try_push(garbage).is_err() {
+            self.overflow.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Collector thread freeing what the audio thread discards
+///
+/// The thread stops (after freeing everything left) when this handle is dropped.
+pub struct GarbageCollector {
+    running: Arc<AtomicBool>,
+    overflow: Arc<AtomicU64>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl GarbageCollector {
+    /// Create a chute and spawn its collector thread
+    pub fn spawn(capacity: usize) -> (GarbageChute, GarbageCollector) {
+        let (tx, mut rx) = HeapRb::<Garbage>::new(capacity).split();
+        let running = Arc::new(AtomicBool::new(true));
+        let overflow = Arc::new(AtomicU64::new(0));
+
+        let thread_running = running.clone();
+        let handle = thread::Builder::new()
+            .name("garbage-collector".to_string())
+            .spawn(move || {
+                let mut pending = Vec::new();
+                while thread_running.load(Ordering::Relaxed) {
+                    collect(&mut rx, &mut pending);
+                    thread::sleep(COLLECT_INTERVAL);
+                }
+                // Shutting down: the audio stream is gone, free everything
+                collect(&mut rx, &mut pending);
+                pending.clear();
+            })
+            .ok();
+
+        let chute = GarbageChute {
+            tx,
+            overflow: overflow.clone(),
+        };
+        let collector = GarbageCollector {
+            running,
+            overflow,
+            handle,
+        };
+        (chute, collector)
+    }
+
+    /// Number of objects that were dropped on the audio thread (chute full)
+    pub fn overflow_count(&self) -> u64 {
+        self.overflow.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for GarbageCollector {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Drain the chute and free what is no longer shared
+///
+/// Objects still referenced elsewhere are kept in `pending` until the
+/// collector holds the last reference. Returns the number of objects freed.
+fn collect(rx: &mut GarbageConsumer, pending: &mut Vec<Garbage>) -> usize {
+    pending.extend(rx.pop_iter());
+
+    let before = pending.len();
+    pending.retain(Garbage::is_shared);
+    before - pending.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::loader::{LoopMode, SampleData};
+
+    fn sample() -> Arc<Sample> {
+        Arc::new(Sample {
+            name: "test".to_string(),
+            data: SampleData::F32(vec![0.0; 16].into()),
+            sample_rate: 48000,
+            source_channels: 1,
+            loop_mode: LoopMode::Off,
+            loop_start: 0,
+            loop_end: 16,
+            reverse: false,
+            volume: 1.0,
+            pan: 0.0,
+            pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: None,
+        })
+    }
+
+    #[test]
+    fn test_collect_waits_for_last_reference() {
+        let (mut tx, mut rx) = HeapRb::<Garbage>::new(8).split();
+        let mut pending = Vec::new();
+
+        // A voice still plays the discarded sample
+        let voice_ref = sample();
+        let weak = Arc::downgrade(&voice_ref);
+        assert!(tx.try_push(Garbage::Sample(voice_ref.clone())).is_ok());
+        // The UI store still holds the replaced pattern
+        let store_ref = Arc::new(Pattern::new_default(1, "Old".to_string()));
+        assert!(tx.try_push(Garbage::Pattern(store_ref.clone())).is_ok());
+        assert!(
+            tx.try_push(Garbage::Pattern(Arc::new(Pattern::new_default(
+                2,
+                "Unshared".to_string()
+            ))))
+            .is_ok()
+        );
+
+        assert_eq!(collect(&mut rx, &mut pending), 1);
+        assert_eq!(pending.len(), 2);
+
+        // The voice and the store release them: the collector now frees them
+        drop(voice_ref);
+        drop(store_ref);
+        assert_eq!(collect(&mut rx, &mut pending), 2);
+        assert!(pending.is_empty());
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_chute_overflow_is_counted() {
+        let (mut chute, collector) = GarbageCollector::spawn(1);
+        // Fill faster than the collector drains (it sleeps between passes)
+        for _ in 0..4 {
+            chute.dispose(Garbage::Sample(sample()));
+        }
+        assert!(collector.overflow_count() >= 2);
+    }
+}
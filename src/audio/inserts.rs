@@ -0,0 +1,321 @@
+// Insert chains - Built-in effects in series on a track
+//
+// Every track (synth, audio and instrument tracks) can run a chain of built-in
+// effects after its instrument or clips (and after its compressor/gate insert,
+// see `synth::dynamics`), before the fader and the sends: a filter, a delay
+// and a reverb, in a configurable order. Like the master chain, each effect
+// appears once and is bypassed through the `enabled` flag of its parameters;
+// a track whose effects are all bypassed skips its chain.
+//
+// Real-time constraints:
+// - The effects are allocated in `InsertChain::new()` (outside the callback):
+//   one chain for the synth track, one per instrument slot and one for each of
+//   the first `MAX_TRACK_CHAINS` audio tracks
+// - Parameter and order changes only copy small `Copy` values
+
+use crate::synth::delay::{Delay, DelayParams};
+use crate::synth::filter::{FilterParams, StateVariableFilter};
+use crate::synth::reverb::{Reverb, ReverbParams};
+
+/// Maximum insert delay time in milliseconds
+pub const INSERT_DELAY_MAX_MS: f32 = 1000.0;
+
+/// Audio tracks that can run an insert chain (the following ones are bypassed)
+pub const MAX_TRACK_CHAINS: usize = 16;
+
+/// Effects available in a track insert chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertEffect {
+    Filter,
+    Delay,
+    Reverb,
+}
+
+impl InsertEffect {
+    /// All insert effects, in default processing order
+    pub const ALL: [InsertEffect; 3] = [
+        InsertEffect::Filter,
+        InsertEffect::Delay,
+        InsertEffect::Reverb,
+    ];
+
+    /// Effect name for UI display
+    pub fn name(&self) -> &'static str {
+        match self {
+            InsertEffect::Filter => "Filter",
+            InsertEffect::Delay => "Delay",
+            InsertEffect::Reverb => "Reverb",
+        }
+    }
+}
+
+/// Processing order of the insert effects (each effect appears exactly once)
+pub type InsertEffectOrder = [InsertEffect; 3];
+
+/// Check that an order contains every insert effect exactly once
+pub fn is_valid_insert_order(order: &InsertEffectOrder) -> bool {
+    InsertEffect::ALL
+        .iter()
+        .all(|effect| order.contains(effect))
+}
+
+/// Insert chain configuration of a track (what frontends mirror and projects store)
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct InsertChainParams {
+    pub filter: FilterParams,
+    pub delay: DelayParams,
+    pub reverb: ReverbParams,
+    pub order: InsertEffectOrder,
+}
+
+impl Default for InsertChainParams {
+    /// Every effect starts bypassed, so a new track is dry
+    fn default() -> Self {
+        Self {
+            filter: FilterParams {
+                enabled: false,
+                ..FilterParams::default()
+            },
+            delay: DelayParams {
+                enabled: false,
+                ..DelayParams::default()
+            },
+            reverb: ReverbParams {
+                enabled: false,
+                ..ReverbParams::default()
+            },
+            order: InsertEffect::ALL,
+        }
+    }
+}
+
+impl InsertChainParams {
+    /// Check if an effect is bypassed
+    pub fn is_bypassed(&self, effect: InsertEffect) -> bool {
+        match effect {
+            InsertEffect::Filter => !self.filter.enabled,
+            InsertEffect::Delay => !self.delay.enabled,
+            InsertEffect::Reverb => !self.reverb.enabled,
+        }
+    }
+
+    /// Bypass (or re-enable) an effect
+    pub fn set_bypassed(&mut self, effect: InsertEffect, bypassed: bool) {
+        match effect {
+            InsertEffect::Filter => self.filter.enabled = !bypassed,
+            InsertEffect::Delay => self.delay.enabled = !bypassed,
+            InsertEffect::Reverb => self.reverb.enabled = !bypassed,
+        }
+    }
+
+    /// Whether any effect is enabled
+    pub fn is_active(&self) -> bool {
+        InsertEffect::ALL
+            .iter()
+            .any(|effect| !self.is_bypassed(*effect))
+    }
+
+    /// Validate and clamp parameters (an invalid order falls back to the default one)
+    pub fn validate(&mut self) {
+        self.filter.cutoff = self.filter.cutoff.clamp(20.0, 20000.0);
+        self.filter.resonance = self.filter.resonance.clamp(0.5, 20.0);
+        self.delay.validate(INSERT_DELAY_MAX_MS);
+        self.reverb = ReverbParams {
+            enabled: self.reverb.enabled,
+            ..ReverbParams::new(self.reverb.room_size, self.reverb.damping, self.reverb.mix)
+        };
+        if !is_valid_insert_order(&self.order) {
+            self.order = InsertEffect::ALL;
+        }
+    }
+}
+
+/// Insert chain of a track (owned by the audio thread)
+///
+/// Stereo tracks use both channels, mono audio tracks only the first one.
+pub struct InsertChain {
+    params: InsertChainParams,
+    filter: [StateVariableFilter; 2],
+    delay: [Delay; 2],
+    reverb: [Reverb; 2],
+}
+
+impl InsertChain {
+    /// Create an insert chain (allocates the delay lines)
+    pub fn new(mut params: InsertChainParams, sample_rate: f32) -> Self {
+        params.validate();
+        Self {
+            params,
+            filter: [
+                StateVariableFilter::new(params.filter, sample_rate),
+                StateVariableFilter::new(params.filter, sample_rate),
+            ],
+            delay: [
+                Delay::new(params.delay, sample_rate, INSERT_DELAY_MAX_MS),
+                Delay::new(params.delay, sample_rate, INSERT_DELAY_MAX_MS),
+            ],
+            reverb: [
+                Reverb::new(params.reverb, sample_rate),
+                Reverb::new(params.reverb, sample_rate),
+            ],
+        }
+    }
+
+    /// Current configuration
+    pub fn params(&self) -> InsertChainParams {
+        self.params
+    }
+
+    /// Whether any effect is enabled (the chain can be skipped otherwise)
+    pub fn is_active(&self) -> bool {
+        self.params.is_active()
+    }
+
+    /// Set the configuration
+    ///
+    /// An effect coming out of bypass starts from silence, parameter changes
+    /// keep the tails.
+    pub fn set_params(&mut self, mut params: InsertChainParams) {
+        params.validate();
+        for effect in InsertEffect::ALL {
+            let enabling = self.params.is_bypassed(effect) && !params.is_bypassed(effect);
+            match effect {
+                InsertEffect::Filter => {
+                    for filter in &mut self.filter {
+                        filter.set_params(params.filter);
+                        if enabling {
+                            filter.reset();
+                        }
+                    }
+                }
+                InsertEffect::Delay => {
+                    for delay in &mut self.delay {
+                        delay.set_params(params.delay);
+                        if enabling {
+                            delay.reset();
+                        }
+                    }
+                }
+                InsertEffect::Reverb => {
+                    for reverb in &mut self.reverb {
+                        reverb.set_params(params.reverb);
+                        if enabling {
+                            reverb.reset();
+                        }
+                    }
+                }
+            }
+        }
+        self.params = params;
+    }
+
+    /// Bypass every effect and clear the tails (track removed)
+    pub fn clear(&mut self) {
+        self.set_params(InsertChainParams::default());
+        self.reset();
+    }
+
+    /// Reset all effect states (filters, delay lines)
+    pub fn reset(&mut self) {
+        self.filter.iter_mut().for_each(StateVariableFilter::reset);
+        self.delay.iter_mut().for_each(Delay::reset);
+        self.reverb.iter_mut().for_each(Reverb::reset);
+    }
+
+    /// Process a stereo frame through the chain
+    #[inline]
+    pub fn process(&mut self, mut left: f32, mut right: f32) -> (f32, f32) {
+        for effect in self.params.order {
+            (left, right) = match effect {
+                InsertEffect::Filter => {
+                    (self.filter[0].process(left), self.filter[1].process(right))
+                }
+                InsertEffect::Delay => (self.delay[0].process(left), self.delay[1].process(right)),
+                InsertEffect::Reverb => {
+                    (self.reverb[0].process(left), self.reverb[1].process(right))
+                }
+            };
+        }
+        (left, right)
+    }
+
+    /// Process a mono sample through the chain (first channel)
+    #[inline]
+    pub fn process_mono(&mut self, mut sample: f32) -> f32 {
+        for effect in self.params.order {
+            sample = match effect {
+                InsertEffect::Filter => self.filter[0].process(sample),
+                InsertEffect::Delay => self.delay[0].process(sample),
+                InsertEffect::Reverb => self.reverb[0].process(sample),
+            };
+        }
+        sample
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_chain_is_transparent() {
+        let mut chain = InsertChain::new(InsertChainParams::default(), 44100.0);
+        assert!(!chain.is_active());
+
+        for i in 0..1000 {
+            let input = (i as f32 * 0.01).sin() * 0.5;
+            assert_eq!(chain.process(input, -input), (input, -input));
+            assert_eq!(chain.process_mono(input), input);
+        }
+    }
+
+    #[test]
+    fn test_order_and_bypass() {
+        let mut params = InsertChainParams::default();
+        params.set_bypassed(InsertEffect::Delay, false);
+        assert!(params.is_active());
+        assert!(!params.is_bypassed(InsertEffect::Delay));
+        assert!(params.is_bypassed(InsertEffect::Reverb));
+
+        // Invalid orders fall back to the default one
+        params.order = [
+            InsertEffect::Delay,
+            InsertEffect::Delay,
+            InsertEffect::Filter,
+        ];
+        params.delay.time_ms = 5000.0;
+        params.validate();
+        assert_eq!(params.order, InsertEffect::ALL);
+        assert_eq!(params.delay.time_ms, INSERT_DELAY_MAX_MS);
+
+        let order = [
+            InsertEffect::Reverb,
+            InsertEffect::Delay,
+            InsertEffect::Filter,
+        ];
+        params.order = order;
+        let mut chain = InsertChain::new(InsertChainParams::default(), 1000.0);
+        chain.set_params(params);
+        assert_eq!(chain.params().order, order);
+    }
+
+    #[test]
+    fn test_delay_insert_produces_echo() {
+        let params = InsertChainParams {
+            delay: DelayParams::new(10.0, 0.0, 1.0),
+            ..Default::default()
+        };
+        let mut chain = InsertChain::new(params, 1000.0);
+
+        // 10ms at 1kHz = 10 samples
+        assert_eq!(chain.process_mono(1.0), 0.0);
+        let echo: Vec<f32> = (0..10).map(|_| chain.process_mono(0.0)).collect();
+        assert!(echo[9] > 0.5);
+
+        chain.clear();
+        assert!(!chain.is_active());
+        assert_eq!(chain.process_mono(0.25), 0.25);
+    }
+}
@@ -0,0 +1,436 @@
+// Instrument tracks - Tracks playing their own pattern on their own instrument
+//
+// Besides the synth track (the voices played live and by the active pattern),
+// a project holds instrument tracks. Each one owns a slot of the engine's
+// `InstrumentRack`: a voice manager playing a synth patch or the sample bank,
+// or a plugin instance receiving the notes. A track plays its pattern through
+// its own sequencer player, looped like the active pattern, or the clips of
+// its track in song mode (see `sequencer::arrangement`).
+//
+// The slots are allocated with the engine and reused: `Command::SetTrackInstrument`
+// assigns (or frees) the slot of a track and `Command::SetTrackPattern` replaces
+// the pattern it plays (the replaced pattern goes back through the garbage
+// chute). Synth and sampler tracks are mixed on the master bus with their
+// mixer strip (`MixerTrack::Instrument`); plugin tracks are heard through the
+// plugins, which process the whole mix. Each slot also runs the insert chain
+// of its track (see `audio::inserts`) before the strip.
+//
+// Sampler tracks share the samples and note mappings of the synth track's
+// voice manager: a project has a single sample bank.
+
+use crate::audio::inserts::{InsertChain, InsertChainParams};
+use crate::audio::routing::{ChannelStrip, MixerTrack, TrackMixer};
+use crate::command::patch::SynthPatch;
+use crate::midi::event::{MidiEvent, MidiEventTimed};
+use crate::plugin::PluginHost;
+use crate::plugin::parameters::PluginInstanceId;
+use crate::sequencer::{Pattern, SequencerPlayer, Tempo, TimeSignature, TrackSources};
+use crate::synth::voice_manager::{VoiceManager, VoiceMode};
+use std::sync::Arc;
+
+/// Instrument tracks the engine can play at once (slots allocated up front)
+pub const MAX_INSTRUMENT_TRACKS: usize = 8;
+
+/// Frames rendered at once by `InstrumentRack::process` (scratch buffer size)
+const CHUNK_FRAMES: usize = 256;
+
+/// Instrument played by an instrument track
+///
+/// Kept inline (and `Copy`) so a slot can take it without allocating; the
+/// command carrying it wraps it in an `Arc` instead.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Copy)]
+pub enum TrackInstrument {
+    /// Synth voices with their own patch
+    Synth(SynthPatch),
+    /// Sampler voices playing the project's sample bank
+    Sampler,
+    /// A plugin instance, receiving the notes of the track
+    Plugin(PluginInstanceId),
+}
+
+impl TrackInstrument {
+    /// Display name of the instrument kind
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrackInstrument::Synth(_) => "Synth",
+            TrackInstrument::Sampler => "Sampler",
+            TrackInstrument::Plugin(_) => "Plugin",
+        }
+    }
+}
+
+/// Instrument track as edited by the UI
+#[derive(Debug, Clone)]
+pub struct InstrumentTrack {
+    /// Track identifier (unique among the instrument tracks)
+    pub id: u32,
+    pub name: String,
+    pub instrument: TrackInstrument,
+    /// Pattern played by the track
+    ///
+    /// Shared with the audio thread like the active pattern (see
+    /// `Command::SetPattern`).
+    pub pattern: Arc<Pattern>,
+    /// Gain, pan, mute and solo of the track
+    pub strip: ChannelStrip,
+    /// Insert effect chain (after the instrument, before the strip)
+    pub inserts: InsertChainParams,
+}
+
+/// Audio thread side of an instrument track
+struct InstrumentSlot {
+    /// Track playing in the slot (None: free)
+    track: Option<u32>,
+    instrument: TrackInstrument,
+    voices: VoiceManager,
+    player: SequencerPlayer,
+    pattern: Arc<Pattern>,
+    inserts: InsertChain,
+}
+
+/// Instrument slots of the instrument tracks (RT-safe once created)
+pub struct InstrumentRack {
+    slots: Vec<InstrumentSlot>,
+    /// Pattern of the free slots (shared: freeing a slot does not allocate)
+    empty_pattern: Arc<Pattern>,
+    // Scratch buffers (one chunk)
+    track_left: [f32; CHUNK_FRAMES],
+    track_right: [f32; CHUNK_FRAMES],
+}
+
+impl InstrumentRack {
+    /// Allocate `MAX_INSTRUMENT_TRACKS` free slots
+    pub fn new(sample_rate: f32) -> Self {
+        let empty_pattern = Arc::new(Pattern::new_default(0, "Empty".to_string()));
+        let slots = (0..MAX_INSTRUMENT_TRACKS)
+            .map(|_| InstrumentSlot {
+                track: None,
+                instrument: TrackInstrument::Sampler,
+                voices: VoiceManager::new(sample_rate),
+                player: SequencerPlayer::new(sample_rate as f64),
+                pattern: empty_pattern.clone(),
+                inserts: InsertChain::new(InsertChainParams::default(), sample_rate),
+            })
+            .collect();
+        Self {
+            slots,
+            empty_pattern,
+            track_left: [0.0; CHUNK_FRAMES],
+            track_right: [0.0; CHUNK_FRAMES],
+        }
+    }
+
+    /// Number of tracks holding a slot
+    pub fn track_count(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.track.is_some())
+            .count()
+    }
+
+    /// Check if a track holds a slot
+    pub fn contains(&self, track: u32) -> bool {
+        self.slot(track).is_some()
+    }
+
+    /// Set the instrument of a track, taking a free slot if it has none
+    ///
+    /// `bank` holds the samples played by sampler tracks. Returns false when
+    /// every slot is taken.
+    pub fn set_instrument(
+        &mut self,
+        track: u32,
+        instrument: TrackInstrument,
+        bank: &VoiceManager,
+        plugin_host: &PluginHost,
+    ) -> bool {
+        let index = match self.slots.iter().position(|slot| slot.track == Some(track)) {
+            Some(index) => index,
+            None => match self.slots.iter().position(|slot| slot.track.is_none()) {
+                Some(index) => index,
+                None => return false,
+            },
+        };
+        let slot = &mut self.slots[index];
+        slot.release_notes(plugin_host);
+        if slot.track != Some(track) {
+            slot.inserts.clear();
+        }
+        slot.track = Some(track);
+        slot.instrument = instrument;
+        match instrument {
+            TrackInstrument::Synth(patch) => load_patch(&mut slot.voices, &patch),
+            TrackInstrument::Sampler => {
+                slot.voices.set_voice_mode(VoiceMode::Sampler);
+                slot.voices.share_samples(bank);
+            }
+            TrackInstrument::Plugin(_) => {}
+        }
+        true
+    }
+
+    /// Free the slot of a track, returning the pattern it played
+    ///
+    /// Its voices stop at once; a plugin track's notes are released.
+    pub fn remove(&mut self, track: u32, plugin_host: &PluginHost) -> Option<Arc<Pattern>> {
+        let empty = self.empty_pattern.clone();
+        let slot = self.slot_mut(track)?;
+        slot.release_notes(plugin_host);
+        slot.track = None;
+        slot.inserts.clear();
+        Some(std::mem::replace(&mut slot.pattern, empty))
+    }
+
+    /// Replace the pattern of a track, returning the previous one
+    ///
+    /// The pattern is handed back for an unknown track.
+    pub fn set_pattern(
+        &mut self,
+        track: u32,
+        pattern: Arc<Pattern>,
+    ) -> Result<Arc<Pattern>, Arc<Pattern>> {
+        match self.slot_mut(track) {
+            Some(slot) => Ok(std::mem::replace(&mut slot.pattern, pattern)),
+            None => Err(pattern),
+        }
+    }
+
+    /// Set the insert chain of a track (ignored for a track without a slot)
+    pub fn set_inserts(&mut self, track: u32, inserts: InsertChainParams) {
+        if let Some(slot) = self.slot_mut(track) {
+            slot.inserts.set_params(inserts);
+        }
+    }
+
+    /// Play the samples of `bank` on the sampler tracks (after a sample edit)
+    pub fn share_samples(&mut self, bank: &VoiceManager) {
+        for slot in &mut self.slots {
+            if slot.track.is_some() && matches!(slot.instrument, TrackInstrument::Sampler) {
+                slot.voices.share_samples(bank);
+            }
+        }
+    }
+
+    /// Number of voices playing on the instrument tracks
+    pub fn active_voice_count(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.track.is_some())
+            .map(|slot| slot.voices.active_voice_count())
+            .sum()
+    }
+
+    /// Play the patterns of the tracks (or their clips from `sources`, see
+    /// `SequencerPlayer::process_track`) from timeline `position` and add the synth and sampler tracks to
+    /// `left`/`right` with their strip (RT-safe)
+    ///
+    /// Like the synth track, a stopped transport releases the playing notes.
+    #[allow(clippy::too_many_arguments)]
+    pub fn process(
+        &mut self,
+        sources: TrackSources,
+        position: u64,
+        playing: bool,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+        plugin_host: &PluginHost,
+        mixer: &TrackMixer,
+        left: &mut [f32],
+        right: &mut [f32],
+    ) {
+        let frames = left.len().min(right.len());
+        for slot in &mut self.slots {
+            let Some(track) = slot.track else {
+                continue;
+            };
+            slot.voices.set_tempo(*tempo);
+            let events = slot.player.process_track(
+                track,
+                &slot.pattern,
+                sources,
+                position,
+                playing,
+                tempo,
+                time_signature,
+                frames,
+            );
+            for event in events {
+                match slot.instrument {
+                    TrackInstrument::Plugin(instance) => {
+                        plugin_host.process_midi_for_instance(instance, &event);
+                    }
+                    _ => play_event(&mut slot.voices, &event),
+                }
+            }
+            if matches!(slot.instrument, TrackInstrument::Plugin(_)) {
+                continue;
+            }
+
+            // The voices run even when the track is not heard (releases go on)
+            let (gain_left, gain_right) = mixer.gains(MixerTrack::Instrument(track));
+            let mut start = 0;
+            while start < frames {
+                let end = (start + CHUNK_FRAMES).min(frames);
+                let own_left = &mut self.track_left[..end - start];
+                let own_right = &mut self.track_right[..end - start];
+                slot.voices.process_block(own_left, own_right);
+                if slot.inserts.is_active() {
+                    for i in 0..own_left.len() {
+                        (own_left[i], own_right[i]) =
+                            slot.inserts.process(own_left[i], own_right[i]);
+                    }
+                }
+                for i in 0..own_left.len() {
+                    left[start + i] += own_left[i] * gain_left;
+                    right[start + i] += own_right[i] * gain_right;
+                }
+                start = end;
+            }
+        }
+    }
+
+    fn slot(&self, track: u32) -> Option<&InstrumentSlot> {
+        self.slots.iter().find(|slot| slot.track == Some(track))
+    }
+
+    fn slot_mut(&mut self, track: u32) -> Option<&mut InstrumentSlot> {
+        self.slots.iter_mut().find(|slot| slot.track == Some(track))
+    }
+}
+
+impl InstrumentSlot {
+    /// Stop the notes of the track (before its slot changes hands)
+    fn release_notes(&mut self, plugin_host: &PluginHost) {
+        // A stopped player hands back the note-offs of its playing notes
+        let released = self.player.process(
+            &self.pattern,
+            0,
+            false,
+            &Tempo::new(120.0),
+            &TimeSignature::four_four(),
+            0,
+        );
+        if let TrackInstrument::Plugin(instance) = self.instrument {
+            for event in &released {
+                plugin_host.process_midi_for_instance(instance, event);
+            }
+        }
+        self.voices.reset();
+    }
+}
+
+/// Load a synth patch in a voice manager
+fn load_patch(voices: &mut VoiceManager, patch: &SynthPatch) {
+    voices.set_voice_mode(VoiceMode::Synth);
+    voices.set_waveform(patch.waveform);
+    voices.set_oscillator2(patch.oscillator2);
+    voices.set_unison(patch.unison);
+    voices.set_anti_aliasing(patch.anti_aliasing);
+    voices.set_cross_mod(patch.cross_mod);
+    voices.set_noise(patch.noise);
+    voices.set_mod_envelope(patch.mod_envelope);
+    voices.set_filter_mod(patch.filter_mod);
+    voices.set_mod_lfos(patch.mod_lfos);
+    voices.set_adsr(patch.adsr);
+    voices.set_lfo(patch.lfo);
+    voices.set_filter(patch.filter);
+    voices.set_portamento(patch.portamento);
+    voices.set_poly_mode(patch.poly_mode);
+    for (index, routing) in patch.mod_routings.iter().enumerate() {
+        voices.set_mod_routing(index, *routing);
+    }
+}
+
+/// Play a sequencer event on the voices of a track
+fn play_event(voices: &mut VoiceManager, event: &MidiEventTimed) {
+    match event.event {
+        MidiEvent::NoteOn { note, velocity } => voices.note_on(note, velocity),
+        MidiEvent::NoteOff { note } => voices.note_off(note),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::{Note, Position};
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    fn patch() -> SynthPatch {
+        let (tx, _rx) = crate::messaging::channels::create_command_channel(16);
+        let state = crate::command::DawState::new(Arc::new(std::sync::Mutex::new(tx)));
+        SynthPatch::from_state(&state)
+    }
+
+    fn render(rack: &mut InstrumentRack, mixer: &TrackMixer, host: &PluginHost) -> f32 {
+        let mut left = [0.0f32; 512];
+        let mut right = [0.0f32; 512];
+        rack.process(
+            TrackSources::default(),
+            0,
+            true,
+            &Tempo::new(120.0),
+            &TimeSignature::four_four(),
+            host,
+            mixer,
+            &mut left,
+            &mut right,
+        );
+        left.iter()
+            .chain(right.iter())
+            .map(|s| s.abs())
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn test_instrument_rack_plays_track_patterns() {
+        let host = PluginHost::new();
+        let bank = VoiceManager::new(SAMPLE_RATE);
+        let mut mixer = TrackMixer::new();
+        let mut rack = InstrumentRack::new(SAMPLE_RATE);
+
+        let mut pattern = Pattern::new_default(7, "Lead".to_string());
+        pattern.add_note(Note::new(1, 69, Position::zero(), 24000, 100));
+        assert!(rack.set_instrument(1, TrackInstrument::Synth(patch()), &bank, &host));
+        let empty = rack.set_pattern(1, Arc::new(pattern)).unwrap();
+        assert!(empty.is_empty());
+        assert!(rack.set_pattern(2, empty).is_err());
+
+        assert!(render(&mut rack, &mixer, &host) > 0.0);
+        assert_eq!(rack.active_voice_count(), 1);
+
+        // A muted track keeps playing, unheard
+        let muted = ChannelStrip {
+            muted: true,
+            ..ChannelStrip::default()
+        };
+        assert!(mixer.set_strip(MixerTrack::Instrument(1), muted));
+        assert_eq!(render(&mut rack, &mixer, &host), 0.0);
+
+        // Freeing the slot stops the voices and hands the pattern back
+        let pattern = rack.remove(1, &host).unwrap();
+        assert_eq!(pattern.id, 7);
+        assert_eq!(rack.active_voice_count(), 0);
+        assert!(!rack.contains(1));
+    }
+
+    #[test]
+    fn test_instrument_rack_slots_full() {
+        let host = PluginHost::new();
+        let bank = VoiceManager::new(SAMPLE_RATE);
+        let mut rack = InstrumentRack::new(SAMPLE_RATE);
+        for track in 0..MAX_INSTRUMENT_TRACKS as u32 {
+            assert!(rack.set_instrument(track, TrackInstrument::Sampler, &bank, &host));
+        }
+        let extra = MAX_INSTRUMENT_TRACKS as u32;
+        assert!(!rack.set_instrument(extra, TrackInstrument::Sampler, &bank, &host));
+
+        // A track changing instrument keeps its slot
+        assert!(rack.set_instrument(0, TrackInstrument::Synth(patch()), &bank, &host));
+        assert_eq!(rack.track_count(), MAX_INSTRUMENT_TRACKS);
+        rack.remove(0, &host);
+        assert!(rack.set_instrument(extra, TrackInstrument::Sampler, &bank, &host));
+    }
+}
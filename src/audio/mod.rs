@@ -1,12 +1,38 @@
 // Module audio - Gestion du backend CPAL et callback temps-réel
 
 pub mod buffer;
+pub mod clip_player;
 pub mod cpu_monitor;
+pub mod denormals;
+#[cfg(feature = "audio-backend")]
 pub mod device;
 pub mod dsp_utils;
 pub mod engine;
 pub mod export;
+pub mod flac_writer;
+pub mod freeze;
 pub mod format_conversion;
+pub mod garbage;
+pub mod inserts;
+pub mod instruments;
+pub mod null_backend;
+pub mod outputs;
 pub mod parameters;
+pub mod peaks;
+pub mod precision;
+pub mod profiling;
+pub mod recorder;
+pub mod returns;
 pub mod routing;
+pub mod rt_log;
+pub mod simd;
+pub mod smoothing;
+pub mod snapshot;
+pub mod stretch;
+pub mod surround;
+pub mod tap;
+pub mod thread_priority;
 pub mod timing;
+pub mod tuner;
+pub mod warp;
+pub mod watchdog;
@@ -0,0 +1,117 @@
+// Null audio backend - Drive the audio callback without an audio device
+//
+// Headless builds (no `audio-backend` feature: servers, tests, CI) have no
+// device to pull buffers. The null backend plays that role: a thread calls the
+// engine's audio callback at the pace of a real device and discards the
+// rendered buffers. Transport, sequencer, snapshot and watchdog behave as with
+// a device, so the rest of the engine needs no special case.
+//
+// The thread sleeps between buffers; when it falls behind (slow machine,
+// debugger), it resynchronizes instead of rendering a burst of late buffers.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Sample rate of the null backend (Hz)
+pub const NULL_SAMPLE_RATE: f32 = 48000.0;
+/// Output channels of the null backend
+pub const NULL_CHANNELS: usize = 2;
+/// Frames rendered per callback
+pub const NULL_BUFFER_FRAMES: usize = 512;
+
+/// Running null output (stopped on drop)
+pub struct NullOutput {
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl NullOutput {
+    /// Start calling `callback` with interleaved buffers of `buffer_frames` frames
+    pub fn spawn<F>(
+        mut callback: F,
+        channels: usize,
+        buffer_frames: usize,
+        sample_rate: f32,
+    ) -> Result<Self, String>
+    where
+        F: FnMut(&mut [f32]) + Send + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        let period = Duration::from_secs_f64(buffer_frames as f64 / sample_rate as f64);
+
+        let thread = thread::Builder::new()
+            .name("null-audio".to_string())
+            .spawn(move || {
+                let mut buffer = vec![0.0f32; buffer_frames * channels];
+                let mut deadline = Instant::now();
+                while thread_running.load(Ordering::Acquire) {
+                    buffer.fill(0.0);
+                    callback(&mut buffer);
+
+                    deadline += period;
+                    let now = Instant::now();
+                    if deadline > now {
+                        thread::sleep(deadline - now);
+                    } else {
+                        deadline = now;
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to start the null audio thread: {}", e))?;
+
+        Ok(Self {
+            running,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for NullOutput {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn test_calls_back_until_dropped() {
+        let frames = Arc::new(AtomicUsize::new(0));
+        let counted = frames.clone();
+
+        let output = NullOutput::spawn(
+            move |buffer| {
+                assert_eq!(buffer.len(), 64 * NULL_CHANNELS);
+                counted.fetch_add(buffer.len() / NULL_CHANNELS, Ordering::Relaxed);
+            },
+            NULL_CHANNELS,
+            64,
+            NULL_SAMPLE_RATE,
+        )
+        .unwrap();
+
+        // Paced like a device: about 750 buffers of 64 frames per second
+        thread::sleep(Duration::from_millis(50));
+        drop(output);
+        let rendered = frames.load(Ordering::Relaxed);
+        assert!(rendered > 0);
+        assert!(
+            rendered < NULL_SAMPLE_RATE as usize,
+            "not paced: {}",
+            rendered
+        );
+
+        // Stopped
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(frames.load(Ordering::Relaxed), rendered);
+    }
+}
@@ -0,0 +1,236 @@
+// Hardware outputs - Direct outputs of tracks and buses
+//
+// On multi-output interfaces, a track or a return bus can play on its own pair
+// of output channels (cue mixes, external processing) instead of the master
+// bus, which always plays on channels 1-2. Direct outputs bypass the master
+// volume, the master effects and the plugins; only the final soft clipping is
+// applied. Pairs the device does not have fall back to the master bus, so a
+// project opened on a stereo device still plays every track. With a surround
+// layout (see `audio::surround`), the pairs of the layout channels are taken
+// by the mix and direct outputs start after them.
+//
+// Real-time constraints:
+// - Pair buffers are allocated in `DirectOutputs::new()` (outside the callback)
+// - Frames past the buffer capacity only play the master bus
+
+use crate::audio::dsp_utils::soft_clip;
+use crate::audio::format_conversion::{OutputSample, write_stereo_to_interleaved_frame};
+use crate::audio::surround::SpeakerLayout;
+
+/// Most output channels the engine can drive
+pub const MAX_OUTPUT_CHANNELS: u16 = 16;
+
+/// Output channel pairs (pair 0, channels 1-2, is the master bus)
+pub const MAX_OUTPUT_PAIRS: usize = MAX_OUTPUT_CHANNELS as usize / 2;
+
+/// Where a track or a bus plays
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrackOutput {
+    /// Master bus (channels 1-2, after the master effects)
+    #[default]
+    Master,
+    /// Output channel pair, from 1 (channels 3-4)
+    Direct(u8),
+}
+
+impl TrackOutput {
+    pub fn is_master(&self) -> bool {
+        *self == TrackOutput::Master
+    }
+
+    /// Whether the pair exists on some device (pair 0 is the master bus)
+    pub fn is_valid(&self) -> bool {
+        match self {
+            TrackOutput::Master => true,
+            TrackOutput::Direct(pair) => (1..MAX_OUTPUT_PAIRS).contains(&(*pair as usize)),
+        }
+    }
+
+    /// Output name for UI display
+    pub fn name(&self) -> String {
+        match self {
+            TrackOutput::Master => "Master".to_string(),
+            TrackOutput::Direct(pair) => format!("Out {}-{}", *pair * 2 + 1, *pair * 2 + 2),
+        }
+    }
+
+    /// Outputs available on a device with `channels` output channels, playing
+    /// the mix with `layout`
+    pub fn choices(channels: usize, layout: SpeakerLayout) -> impl Iterator<Item = TrackOutput> {
+        let pairs = (channels / 2).min(MAX_OUTPUT_PAIRS);
+        let first = first_direct_pair(channels, layout);
+        std::iter::once(TrackOutput::Master)
+            .chain((first..pairs).map(|pair| TrackOutput::Direct(pair as u8)))
+    }
+}
+
+/// First pair free for direct outputs (after the channels of the mix)
+fn first_direct_pair(channels: usize, layout: SpeakerLayout) -> usize {
+    (layout.on_device(channels).channels() / 2).max(1)
+}
+
+/// Direct output pairs of one block (audio thread side)
+pub struct DirectOutputs {
+    /// Pairs of the device (including the master pair)
+    pairs: usize,
+    /// First pair free for direct outputs
+    first: usize,
+    left: Vec<Vec<f32>>,
+    right: Vec<Vec<f32>>,
+    /// Frames of the current block held by the buffers
+    frames: usize,
+    /// Pairs that received a signal in the current block
+    used: [bool; MAX_OUTPUT_PAIRS],
+}
+
+impl DirectOutputs {
+    /// Allocate the pair buffers of a device with `channels` output channels
+    pub fn new(channels: usize, max_frames: usize) -> Self {
+        let pairs = (channels / 2).min(MAX_OUTPUT_PAIRS);
+        Self {
+            pairs,
+            first: 1,
+            left: (0..pairs).map(|_| vec![0.0; max_frames]).collect(),
+            right: (0..pairs).map(|_| vec![0.0; max_frames]).collect(),
+            frames: 0,
+            used: [false; MAX_OUTPUT_PAIRS],
+        }
+    }
+
+    /// Keep the pairs of the mix channels (direct outputs on them play on the
+    /// master bus)
+    pub fn set_layout(&mut self, layout: SpeakerLayout) {
+        self.first = first_direct_pair(self.pairs * 2, layout);
+    }
+
+    /// Pair an output plays on (None: the master bus)
+    #[inline]
+    pub fn pair(&self, output: TrackOutput) -> Option<usize> {
+        match output {
+            TrackOutput::Direct(pair) if (self.first..self.pairs).contains(&(pair as usize)) => {
+                Some(pair as usize)
+            }
+            _ => None,
+        }
+    }
+
+    /// Start a block of `frames` frames (every pair is silent)
+    pub fn begin(&mut self, frames: usize) {
+        self.frames = frames.min(self.left.first().map_or(0, Vec::len));
+        self.used = [false; MAX_OUTPUT_PAIRS];
+    }
+
+    /// Whether a pair received a signal in the current block
+    pub fn is_active(&self) -> bool {
+        self.used.iter().any(|used| *used)
+    }
+
+    /// Add a signal to a pair from frame `offset` of the block, with a gain per side
+    #[inline]
+    pub fn add(
+        &mut self,
+        pair: usize,
+        offset: usize,
+        left: &[f32],
+        right: &[f32],
+        (gain_left, gain_right): (f32, f32),
+    ) {
+        if pair >= self.pairs || offset >= self.frames {
+            return;
+        }
+        let frames = self.frames;
+        let (out_left, out_right) = (&mut self.left[pair], &mut self.right[pair]);
+        if !self.used[pair] {
+            self.used[pair] = true;
+            out_left[..frames].fill(0.0);
+            out_right[..frames].fill(0.0);
+        }
+        let count = left.len().min(right.len()).min(frames - offset);
+        for i in 0..count {
+            out_left[offset + i] += left[i] * gain_left;
+            out_right[offset + i] += right[i] * gain_right;
+        }
+    }
+
+    /// Write a frame of the block: the master bus on channels 1-2 and the
+    /// direct outputs on their pair (soft clipped), silence elsewhere
+    #[inline]
+    pub fn write_frame<T: OutputSample>(&self, index: usize, master: (f32, f32), frame: &mut [T]) {
+        write_stereo_to_interleaved_frame(master, frame);
+        if index >= self.frames {
+            return;
+        }
+        for (pair, channels) in frame
+            .chunks_exact_mut(2)
+            .enumerate()
+            .take(self.pairs)
+            .skip(1)
+        {
+            if self.used[pair] {
+                channels[0] = T::from_f32(soft_clip(self.left[pair][index]));
+                channels[1] = T::from_f32(soft_clip(self.right[pair][index]));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_choices_follow_device() {
+        assert_eq!(
+            TrackOutput::choices(2, SpeakerLayout::Stereo).collect::<Vec<_>>(),
+            [TrackOutput::Master]
+        );
+        let choices: Vec<_> = TrackOutput::choices(6, SpeakerLayout::Stereo).collect();
+        assert_eq!(
+            choices,
+            [
+                TrackOutput::Master,
+                TrackOutput::Direct(1),
+                TrackOutput::Direct(2)
+            ]
+        );
+        assert_eq!(choices[2].name(), "Out 5-6");
+        assert!(!TrackOutput::Direct(0).is_valid());
+        assert!(!TrackOutput::Direct(MAX_OUTPUT_PAIRS as u8).is_valid());
+
+        // Missing pairs play on the master bus
+        let outputs = DirectOutputs::new(4, 16);
+        assert_eq!(outputs.pair(TrackOutput::Direct(1)), Some(1));
+        assert_eq!(outputs.pair(TrackOutput::Direct(2)), None);
+        assert_eq!(outputs.pair(TrackOutput::Direct(0)), None);
+
+        // A quad mix takes channels 1-4
+        let mut outputs = DirectOutputs::new(8, 16);
+        outputs.set_layout(SpeakerLayout::Quad);
+        assert_eq!(outputs.pair(TrackOutput::Direct(1)), None);
+        assert_eq!(outputs.pair(TrackOutput::Direct(2)), Some(2));
+        let choices: Vec<_> = TrackOutput::choices(8, SpeakerLayout::Quad).collect();
+        assert_eq!(choices[1], TrackOutput::Direct(2));
+    }
+
+    #[test]
+    fn test_direct_pairs_written_to_their_channels() {
+        let mut outputs = DirectOutputs::new(6, 4);
+        outputs.begin(4);
+        assert!(!outputs.is_active());
+        outputs.add(2, 2, &[0.5, 0.5], &[0.5, 0.5], (1.0, 0.5));
+
+        let mut frame = [1.0f32; 6];
+        outputs.write_frame(1, (0.25, -0.25), &mut frame);
+        assert_eq!(frame, [0.25, -0.25, 0.0, 0.0, 0.0, 0.0]);
+        outputs.write_frame(3, (0.25, -0.25), &mut frame);
+        assert_eq!(frame[..4], [0.25, -0.25, 0.0, 0.0]);
+        assert_eq!(frame[4], soft_clip(0.5));
+        assert_eq!(frame[5], soft_clip(0.25));
+
+        // A new block starts silent
+        outputs.begin(4);
+        outputs.write_frame(3, (0.0, 0.0), &mut frame);
+        assert_eq!(frame, [0.0; 6]);
+    }
+}
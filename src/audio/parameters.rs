@@ -1,8 +1,11 @@
 // Atomic parameters - Lock-free communication UI ↔ Audio thread
 // Uses atomic operations to share parameters between threads without locks
 
+use crate::synth::filter::FilterParams;
+use crate::synth::lfo::LfoParams;
+use crate::synth::voice_manager::VoiceManager;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 /// Thread-safe f32 parameter using atomic operations
 /// Converts f32 to u32 bits for atomic storage
@@ -34,3 +37,128 @@ impl Default for AtomicF32 {
         Self::new(0.0)
     }
 }
+
+/// Continuous synth parameters stored in `SynthParameters`
+///
+/// Volume is not part of the block: it already has its own `AtomicF32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SynthParam {
+    FilterCutoff,
+    FilterResonance,
+    LfoRate,
+    LfoDepth,
+}
+
+impl SynthParam {
+    pub const COUNT: usize = 4;
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Lock-free block of continuous synth parameters (UI → Audio)
+///
+/// Continuous controls (sliders) are written here instead of pushing one
+/// `Command` per change, so dragging a slider cannot flood the ringbuffer.
+/// The audio thread checks `generation()` once per buffer and applies the
+/// values only when something changed.
+#[derive(Clone)]
+pub struct SynthParameters {
+    values: Arc<[AtomicU32; SynthParam::COUNT]>,
+    generation: Arc<AtomicU64>,
+}
+
+impl SynthParameters {
+    pub fn new(filter: &FilterParams, lfo: &LfoParams) -> Self {
+        let params = Self {
+            values: Arc::new(std::array::from_fn(|_| AtomicU32::new(0))),
+            generation: Arc::new(AtomicU64::new(0)),
+        };
+        params.set_filter(filter);
+        params.set_lfo(lfo);
+        params
+    }
+
+    /// Set a parameter (called from UI thread)
+    pub fn set(&self, param: SynthParam, value: f32) {
+        self.values[param.index()].store(value.to_bits(), Ordering::Relaxed);
+        // Release: the audio thread sees the value once it sees the new generation
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// Get a parameter (called from audio thread)
+    pub fn get(&self, param: SynthParam) -> f32 {
+        f32::from_bits(self.values[param.index()].load(Ordering::Relaxed))
+    }
+
+    /// Change counter, incremented on every `set`
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Write the continuous fields of filter parameters
+    pub fn set_filter(&self, filter: &FilterParams) {
+        self.set(SynthParam::FilterCutoff, filter.cutoff);
+        self.set(SynthParam::FilterResonance, filter.resonance);
+    }
+
+    /// Write the continuous fields of LFO parameters
+    pub fn set_lfo(&self, lfo: &LfoParams) {
+        self.set(SynthParam::LfoRate, lfo.rate);
+        self.set(SynthParam::LfoDepth, lfo.depth);
+    }
+
+    /// Apply the stored values to the voices (called from audio thread)
+    ///
+    /// Discrete fields (filter type, LFO waveform...) keep the value set by the
+    /// last command.
+    pub fn apply_to(&self, voice_manager: &mut VoiceManager) {
+        let cutoff = self.get(SynthParam::FilterCutoff);
+        let resonance = self.get(SynthParam::FilterResonance);
+        voice_manager.update_filter(|filter| {
+            filter.cutoff = cutoff;
+            filter.resonance = resonance;
+        });
+
+        let rate = self.get(SynthParam::LfoRate);
+        let depth = self.get(SynthParam::LfoDepth);
+        voice_manager.update_lfo(|lfo| {
+            lfo.rate = rate;
+            lfo.depth = depth;
+        });
+    }
+}
+
+impl Default for SynthParameters {
+    fn default() -> Self {
+        Self::new(&FilterParams::default(), &LfoParams::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth::filter::FilterType;
+
+    #[test]
+    fn test_synth_parameters_apply_keeps_discrete_fields() {
+        let params = SynthParameters::default();
+        let mut vm = VoiceManager::new(48000.0);
+        vm.set_filter(FilterParams {
+            filter_type: FilterType::HighPass,
+            ..FilterParams::default()
+        });
+
+        let generation = params.generation();
+        params.set(SynthParam::FilterCutoff, 2500.0);
+        params.set(SynthParam::LfoRate, 7.5);
+        assert!(params.generation() > generation);
+
+        params.apply_to(&mut vm);
+        let filter = vm.get_filter_params();
+        assert_eq!(filter.cutoff, 2500.0);
+        assert_eq!(filter.filter_type, FilterType::HighPass);
+        assert_eq!(vm.get_lfo_params().rate, 7.5);
+    }
+}
@@ -0,0 +1,111 @@
+// Waveform peaks - Downsampled min/max data for waveform display
+//
+// Frontends (egui, Tauri) draw waveforms from these bins instead of the raw
+// audio data, which keeps IPC payloads and plot sizes bounded.
+
+use serde::{Deserialize, Serialize};
+
+/// Min/max peak bins for a block of audio
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WaveformPeaks {
+    /// Minimum sample value per bin
+    pub min: Vec<f32>,
+    /// Maximum sample value per bin
+    pub max: Vec<f32>,
+    /// Number of source samples covered by each bin (last bin may be shorter)
+    pub samples_per_bin: usize,
+    /// Total number of source samples
+    pub total_samples: usize,
+}
+
+impl WaveformPeaks {
+    /// Number of bins
+    pub fn len(&self) -> usize {
+        self.min.len()
+    }
+
+    /// Check if there are no bins (empty source)
+    pub fn is_empty(&self) -> bool {
+        self.min.is_empty()
+    }
+}
+
+/// Compute `resolution` min/max bins over `data`
+///
+/// If `data` has fewer samples than `resolution`, one bin per sample is returned.
+pub fn compute_peaks(data: &[f32], resolution: usize) -> WaveformPeaks {
+    let bins = resolution.min(data.len());
+    if bins == 0 {
+        return WaveformPeaks {
+            min: Vec::new(),
+            max: Vec::new(),
+            samples_per_bin: 0,
+            total_samples: data.len(),
+        };
+    }
+
+    let mut min = Vec::with_capacity(bins);
+    let mut max = Vec::with_capacity(bins);
+
+    for bin in 0..bins {
+        // Integer bin boundaries spread the remainder evenly across bins
+        let start = bin * data.len() / bins;
+        let end = ((bin + 1) * data.len() / bins).max(start + 1);
+
+        let (lo, hi) = data[start..end]
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+        min.push(lo);
+        max.push(hi);
+    }
+
+    WaveformPeaks {
+        min,
+        max,
+        samples_per_bin: data.len().div_ceil(bins),
+        total_samples: data.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peaks_empty() {
+        let peaks = compute_peaks(&[], 100);
+        assert!(peaks.is_empty());
+        assert_eq!(peaks.total_samples, 0);
+    }
+
+    #[test]
+    fn test_peaks_min_max_per_bin() {
+        let data = [0.1, -0.5, 0.3, 0.9, -0.2, 0.0, 0.4, -1.0];
+        let peaks = compute_peaks(&data, 2);
+
+        assert_eq!(peaks.len(), 2);
+        assert_eq!(peaks.samples_per_bin, 4);
+        assert_eq!(peaks.min, vec![-0.5, -1.0]);
+        assert_eq!(peaks.max, vec![0.9, 0.4]);
+    }
+
+    #[test]
+    fn test_peaks_resolution_larger_than_data() {
+        let data = [0.5, -0.5, 0.25];
+        let peaks = compute_peaks(&data, 1024);
+
+        assert_eq!(peaks.len(), 3);
+        assert_eq!(peaks.min, data.to_vec());
+        assert_eq!(peaks.max, data.to_vec());
+    }
+
+    #[test]
+    fn test_peaks_uneven_bins_cover_all_samples() {
+        let data: Vec<f32> = (0..10).map(|i| i as f32).collect();
+        let peaks = compute_peaks(&data, 3);
+
+        assert_eq!(peaks.len(), 3);
+        assert_eq!(peaks.min[0], 0.0);
+        assert_eq!(peaks.max[2], 9.0);
+    }
+}
@@ -0,0 +1,112 @@
+// Internal precision - Sample type of the mix bus and of filter/feedback state
+//
+// Audio crosses the device boundary as f32 and processors keep f32 inputs and
+// outputs. What accumulates rounding error over time is kept in
+// `InternalSample`: the voice mix bus, filter integrators, and the reverb and
+// delay feedback lines.
+//
+// By default `InternalSample` is f32. Building with the `f64-processing`
+// feature switches it to f64, e.g. for long reverb/feedback chains or to A/B
+// precision-related artifacts against the default build:
+//
+//     cargo run --release --features f64-processing
+//
+// Values are converted with `to_internal()` / `from_internal()` when they
+// enter and leave a processor.
+
+#[cfg(not(feature = "f64-processing"))]
+use crate::audio::simd::{simd_accumulate, simd_scale};
+
+#[cfg(not(feature = "f64-processing"))]
+mod types {
+    /// Sample type of the mix bus and of filter/feedback state
+    pub type InternalSample = f32;
+    /// SIMD vector of four `InternalSample`s
+    pub type InternalX4 = wide::f32x4;
+    /// Mathematical constants of `InternalSample`
+    pub use std::f32::consts;
+}
+
+#[cfg(feature = "f64-processing")]
+mod types {
+    /// Sample type of the mix bus and of filter/feedback state
+    pub type InternalSample = f64;
+    /// SIMD vector of four `InternalSample`s
+    pub type InternalX4 = wide::f64x4;
+    /// Mathematical constants of `InternalSample`
+    pub use std::f64::consts;
+}
+
+pub use types::{InternalSample, InternalX4, consts};
+
+/// Size of `InternalSample` in bits (32 or 64)
+pub const INTERNAL_PRECISION_BITS: u32 = (std::mem::size_of::<InternalSample>() * 8) as u32;
+
+/// Convert a sample entering a processor
+#[cfg(not(feature = "f64-processing"))]
+#[inline]
+pub fn to_internal(sample: f32) -> InternalSample {
+    sample
+}
+
+/// Convert a sample entering a processor
+#[cfg(feature = "f64-processing")]
+#[inline]
+pub fn to_internal(sample: f32) -> InternalSample {
+    f64::from(sample)
+}
+
+/// Convert a sample leaving a processor
+#[cfg(not(feature = "f64-processing"))]
+#[inline]
+pub fn from_internal(sample: InternalSample) -> f32 {
+    sample
+}
+
+/// Convert a sample leaving a processor
+#[cfg(feature = "f64-processing")]
+#[inline]
+pub fn from_internal(sample: InternalSample) -> f32 {
+    sample as f32
+}
+
+/// Add `input` to `bus` (SIMD when the bus is f32)
+#[inline]
+pub fn accumulate(bus: &mut [InternalSample], input: &[f32]) {
+    #[cfg(not(feature = "f64-processing"))]
+    simd_accumulate(bus, input);
+
+    #[cfg(feature = "f64-processing")]
+    for (mixed, sample) in bus.iter_mut().zip(input) {
+        *mixed += to_internal(*sample);
+    }
+}
+
+/// Multiply `bus` by `gain` (SIMD when the bus is f32)
+#[inline]
+pub fn scale(bus: &mut [InternalSample], gain: f32) {
+    #[cfg(not(feature = "f64-processing"))]
+    simd_scale(bus, gain);
+
+    #[cfg(feature = "f64-processing")]
+    for mixed in bus.iter_mut() {
+        *mixed *= to_internal(gain);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bus_mixing_and_conversion() {
+        let mut bus = [InternalSample::default(); 5];
+        accumulate(&mut bus, &[0.1, 0.2, 0.3, 0.4, 0.5]);
+        accumulate(&mut bus, &[0.1, 0.2, 0.3, 0.4, 0.5]);
+        scale(&mut bus, 0.5);
+
+        for (mixed, expected) in bus.iter().zip([0.1, 0.2, 0.3, 0.4, 0.5]) {
+            assert!((from_internal(*mixed) - expected).abs() < 1e-6);
+        }
+    }
+}
@@ -1,11 +1,60 @@
 //! Performance profiling utilities for the audio engine
-//! 
+//!
 //! This module provides tools to profile and analyze the performance
 //! of the audio callback and related DSP operations.
+//!
+//! Besides aggregated statistics, every timed section is recorded as a span
+//! in a fixed-size ring buffer (the oldest spans are overwritten). Spans can be
+//! exported to the Chrome trace event format (the format written by
+//! `tracing-chrome`), which loads in `chrome://tracing` or Perfetto for offline
+//! analysis of performance regressions.
 
-use std::time::{Duration, Instant};
+use ringbuf::HeapRb;
+use ringbuf::traits::{Consumer, RingBuffer};
 use std::collections::HashMap;
-use std::sync::{atomic::{AtomicU64, Ordering}, Mutex};
+use std::path::Path;
+use std::sync::{
+    Mutex,
+    atomic::{AtomicBool, AtomicU64, Ordering},
+};
+use std::time::{Duration, Instant};
+
+/// Sections of the audio callback (names used in statistics and traces)
+pub mod section {
+    /// Whole audio callback
+    pub const CALLBACK: &str = "callback";
+    /// UI and MIDI command processing
+    pub const COMMANDS: &str = "commands";
+    /// Sequencer playback and generated MIDI events
+    pub const SEQUENCER: &str = "sequencer";
+    /// Voice rendering (synth and sampler)
+    pub const VOICES: &str = "voices";
+    /// Plugin processing
+    pub const PLUGINS: &str = "plugins";
+    /// Output conversion to the device buffer
+    pub const MIXDOWN: &str = "mixdown";
+}
+
+/// Number of spans kept by the global profiler
+pub const DEFAULT_TRACE_CAPACITY: usize = 16384;
+
+/// Timed section recorded in the trace ring buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileSpan {
+    /// Section or operation name
+    pub name: &'static str,
+    /// Start time in nanoseconds since the profiler was created
+    pub start_ns: u64,
+    /// Duration in nanoseconds
+    pub duration_ns: u64,
+}
+
+/// Accumulated time and count of an operation
+#[derive(Default)]
+struct OperationTotals {
+    time: AtomicU64,
+    count: AtomicU64,
+}
 
 /// Global profiler instance with atomic operations for thread safety
 pub struct AudioProfiler {
@@ -17,27 +66,38 @@ pub struct AudioProfiler {
     pub max_callback_time: AtomicU64,
     /// Minimum callback time observed (nanoseconds)
     pub min_callback_time: AtomicU64,
-    /// Operation times (using Mutex for thread safety)
-    operation_times: Mutex<HashMap<String, AtomicU64>>,
-    /// Operation counts (using Mutex for thread safety)
-    operation_counts: Mutex<HashMap<String, AtomicU64>>,
+    /// Operation totals (keys are static: no allocation once an operation is known)
+    operations: Mutex<HashMap<&'static str, OperationTotals>>,
+    /// Most recent spans (oldest overwritten)
+    spans: Mutex<HeapRb<ProfileSpan>>,
+    /// Record spans (statistics are always recorded)
+    tracing_enabled: AtomicBool,
+    /// Time origin of the spans
+    epoch: Instant,
 }
 
 impl AudioProfiler {
     /// Create a new profiler instance
     pub fn new() -> Self {
+        Self::with_trace_capacity(DEFAULT_TRACE_CAPACITY)
+    }
+
+    /// Create a profiler keeping the last `capacity` spans
+    pub fn with_trace_capacity(capacity: usize) -> Self {
         Self {
             total_callback_time: AtomicU64::new(0),
             callback_count: AtomicU64::new(0),
             max_callback_time: AtomicU64::new(0),
             min_callback_time: AtomicU64::new(u64::MAX),
-            operation_times: Mutex::new(HashMap::new()),
-            operation_counts: Mutex::new(HashMap::new()),
+            operations: Mutex::new(HashMap::new()),
+            spans: Mutex::new(HeapRb::new(capacity.max(1))),
+            tracing_enabled: AtomicBool::new(true),
+            epoch: Instant::now(),
         }
     }
 
     /// Record start of an audio callback
-    pub fn start_callback(&self) -> CallbackTimer {
+    pub fn start_callback(&self) -> CallbackTimer<'_> {
         self.callback_count.fetch_add(1, Ordering::Relaxed);
         CallbackTimer {
             start_time: Instant::now(),
@@ -46,24 +106,78 @@ impl AudioProfiler {
     }
 
     /// Record timing for a specific operation
-    pub fn record_operation(&self, operation: &str, duration: Duration) {
+    pub fn record_operation(&self, operation: &'static str, duration: Duration) {
         let nanos = duration.as_nanos() as u64;
-        
-        // Update operation time
-        if let Ok(mut times) = self.operation_times.lock() {
-            let time_atomic = times.entry(operation.to_string())
-                .or_insert_with(|| AtomicU64::new(0));
-            time_atomic.fetch_add(nanos, Ordering::Relaxed);
+
+        if let Ok(mut operations) = self.operations.lock() {
+            let totals = operations.entry(operation).or_default();
+            totals.time.fetch_add(nanos, Ordering::Relaxed);
+            totals.count.fetch_add(1, Ordering::Relaxed);
         }
-        
-        // Update operation count
-        if let Ok(mut counts) = self.operation_counts.lock() {
-            let count_atomic = counts.entry(operation.to_string())
-                .or_insert_with(|| AtomicU64::new(0));
-            count_atomic.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a span in the trace ring buffer (RT-safe: never blocks, never allocates)
+    ///
+    /// The span is dropped if the buffer is being exported at the same time.
+    pub fn record_span(&self, name: &'static str, start: Instant, duration: Duration) {
+        if !self.tracing_enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let span = ProfileSpan {
+            name,
+            start_ns: start.saturating_duration_since(self.epoch).as_nanos() as u64,
+            duration_ns: duration.as_nanos() as u64,
+        };
+        if let Ok(mut spans) = self.spans.try_lock() {
+            spans.push_overwrite(span);
         }
     }
 
+    /// Enable/disable span recording
+    pub fn set_tracing_enabled(&self, enabled: bool) {
+        self.tracing_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Copy of the recorded spans, oldest first
+    pub fn spans(&self) -> Vec<ProfileSpan> {
+        self.spans
+            .lock()
+            .map(|spans| spans.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Export the recorded spans in Chrome trace event format (JSON)
+    pub fn export_chrome_trace(&self) -> String {
+        let events: Vec<serde_json::Value> = self
+            .spans()
+            .iter()
+            .map(|span| {
+                serde_json::json!({
+                    "name": span.name,
+                    "cat": "audio",
+                    "ph": "X",
+                    // Chrome trace timestamps are in microseconds
+                    "ts": span.start_ns as f64 / 1000.0,
+                    "dur": span.duration_ns as f64 / 1000.0,
+                    "pid": 1,
+                    "tid": 1,
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "traceEvents": events,
+            "displayTimeUnit": "ns",
+        })
+        .to_string()
+    }
+
+    /// Write the Chrome trace to a file
+    pub fn save_chrome_trace(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.export_chrome_trace())
+            .map_err(|e| format!("Failed to write trace: {}", e))
+    }
+
     /// Get performance statistics
     pub fn get_stats(&self) -> ProfilerStats {
         let callback_count = self.callback_count.load(Ordering::Relaxed);
@@ -71,28 +185,25 @@ impl AudioProfiler {
         let max_time = self.max_callback_time.load(Ordering::Relaxed);
         let min_time = self.min_callback_time.load(Ordering::Relaxed);
 
-        let avg_time = if callback_count > 0 {
-            total_time / callback_count
-        } else {
-            0
-        };
+        let avg_time = total_time.checked_div(callback_count).unwrap_or(0);
 
         let mut operation_stats = HashMap::new();
-        
+
         // Collect operation stats
-        if let (Ok(times), Ok(counts)) = (self.operation_times.lock(), self.operation_counts.lock()) {
-            for (operation, time_atomic) in times.iter() {
-                let count = counts.get(operation)
-                    .map(|c| c.load(Ordering::Relaxed))
-                    .unwrap_or(0);
-                let time = time_atomic.load(Ordering::Relaxed);
-                
+        if let Ok(operations) = self.operations.lock() {
+            for (operation, totals) in operations.iter() {
+                let count = totals.count.load(Ordering::Relaxed);
+                let time = totals.time.load(Ordering::Relaxed);
+
                 if count > 0 {
-                    operation_stats.insert(operation.clone(), OperationStats {
-                        total_time: time,
-                        call_count: count,
-                        avg_time: time / count,
-                    });
+                    operation_stats.insert(
+                        operation.to_string(),
+                        OperationStats {
+                            total_time: time,
+                            call_count: count,
+                            avg_time: time / count,
+                        },
+                    );
                 }
             }
         }
@@ -107,23 +218,22 @@ impl AudioProfiler {
         }
     }
 
-    /// Reset all statistics
+    /// Reset all statistics and recorded spans
     pub fn reset(&self) {
         self.total_callback_time.store(0, Ordering::Relaxed);
         self.callback_count.store(0, Ordering::Relaxed);
         self.max_callback_time.store(0, Ordering::Relaxed);
         self.min_callback_time.store(u64::MAX, Ordering::Relaxed);
-        
-        if let Ok(times) = self.operation_times.lock() {
-            for time in times.values() {
-                time.store(0, Ordering::Relaxed);
+
+        if let Ok(operations) = self.operations.lock() {
+            for totals in operations.values() {
+                totals.time.store(0, Ordering::Relaxed);
+                totals.count.store(0, Ordering::Relaxed);
             }
         }
-        
-        if let Ok(counts) = self.operation_counts.lock() {
-            for count in counts.values() {
-                count.store(0, Ordering::Relaxed);
-            }
+
+        if let Ok(mut spans) = self.spans.lock() {
+            spans.clear();
         }
     }
 
@@ -131,13 +241,22 @@ impl AudioProfiler {
     pub fn generate_flamegraph_report(&self) -> String {
         let stats = self.get_stats();
         let mut report = String::new();
-        
+
         report.push_str("# Audio Performance Profile\n\n");
         report.push_str(&format!("Total callbacks: {}\n", stats.callback_count));
-        report.push_str(&format!("Avg callback time: {:.2}μs\n", stats.avg_callback_time as f64 / 1000.0));
-        report.push_str(&format!("Max callback time: {:.2}μs\n", stats.max_callback_time as f64 / 1000.0));
-        report.push_str(&format!("Min callback time: {:.2}μs\n\n", stats.min_callback_time as f64 / 1000.0));
-        
+        report.push_str(&format!(
+            "Avg callback time: {:.2}μs\n",
+            stats.avg_callback_time as f64 / 1000.0
+        ));
+        report.push_str(&format!(
+            "Max callback time: {:.2}μs\n",
+            stats.max_callback_time as f64 / 1000.0
+        ));
+        report.push_str(&format!(
+            "Min callback time: {:.2}μs\n\n",
+            stats.min_callback_time as f64 / 1000.0
+        ));
+
         report.push_str("## Operation Breakdown\n\n");
         for (operation, op_stats) in &stats.operation_stats {
             report.push_str(&format!(
@@ -148,11 +267,17 @@ impl AudioProfiler {
                 op_stats.total_time as f64 / 1_000_000.0
             ));
         }
-        
+
         report
     }
 }
 
+impl Default for AudioProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Timer for measuring audio callback duration
 pub struct CallbackTimer<'a> {
     start_time: Instant,
@@ -163,52 +288,37 @@ impl<'a> Drop for CallbackTimer<'a> {
     fn drop(&mut self) {
         let duration = self.start_time.elapsed();
         let nanos = duration.as_nanos() as u64;
-        
+
+        self.profiler
+            .record_span(section::CALLBACK, self.start_time, duration);
+
         // Update total time
-        self.profiler.total_callback_time.fetch_add(nanos, Ordering::Relaxed);
-        
-        // Update max time
-        let mut current_max = self.profiler.max_callback_time.load(Ordering::Relaxed);
-        while nanos > current_max {
-            match self.profiler.max_callback_time.compare_exchange_weak(
-                current_max,
-                nanos,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            ) {
-                Ok(_) => break,
-                Err(x) => current_max = x,
-            }
-        }
-        
-        // Update min time
-        let mut current_min = self.profiler.min_callback_time.load(Ordering::Relaxed);
-        while nanos < current_min && current_min != u64::MAX {
-            match self.profiler.min_callback_time.compare_exchange_weak(
-                current_min,
-                nanos,
-                Ordering::Relaxed,
-                Ordering::Relaxed,
-            ) {
-                Ok(_) => break,
-                Err(x) => current_min = x,
-            }
-        }
+        self.profiler
+            .total_callback_time
+            .fetch_add(nanos, Ordering::Relaxed);
+
+        // Update max/min time
+        self.profiler
+            .max_callback_time
+            .fetch_max(nanos, Ordering::Relaxed);
+        self.profiler
+            .min_callback_time
+            .fetch_min(nanos, Ordering::Relaxed);
     }
 }
 
 /// RAII timer for measuring specific operations
 pub struct OperationTimer<'a> {
-    operation: String,
+    operation: &'static str,
     start_time: Instant,
     profiler: &'a AudioProfiler,
 }
 
 impl<'a> OperationTimer<'a> {
     /// Create a new operation timer
-    pub fn new(operation: &str, profiler: &'a AudioProfiler) -> Self {
+    pub fn new(operation: &'static str, profiler: &'a AudioProfiler) -> Self {
         Self {
-            operation: operation.to_string(),
+            operation,
             start_time: Instant::now(),
             profiler,
         }
@@ -218,7 +328,9 @@ impl<'a> OperationTimer<'a> {
 impl<'a> Drop for OperationTimer<'a> {
     fn drop(&mut self) {
         let duration = self.start_time.elapsed();
-        self.profiler.record_operation(&self.operation, duration);
+        self.profiler.record_operation(self.operation, duration);
+        self.profiler
+            .record_span(self.operation, self.start_time, duration);
     }
 }
 
@@ -242,7 +354,8 @@ pub struct OperationStats {
 }
 
 /// Global profiler instance
-static GLOBAL_PROFILER: std::sync::LazyLock<AudioProfiler> = std::sync::LazyLock::new(AudioProfiler::new);
+static GLOBAL_PROFILER: std::sync::LazyLock<AudioProfiler> =
+    std::sync::LazyLock::new(AudioProfiler::new);
 
 /// Get global profiler instance
 pub fn global_profiler() -> &'static AudioProfiler {
@@ -254,8 +367,8 @@ pub fn start_callback_profiling() -> CallbackTimer<'static> {
     global_profiler().start_callback()
 }
 
-/// Convenience function to profile an operation
-pub fn profile_operation(operation: &str) -> OperationTimer<'static> {
+/// Convenience function to profile an operation (usually one of `section::*`)
+pub fn profile_operation(operation: &'static str) -> OperationTimer<'static> {
     OperationTimer::new(operation, global_profiler())
 }
 
@@ -267,13 +380,13 @@ mod tests {
     #[test]
     fn test_profiler_basic_functionality() {
         let profiler = AudioProfiler::new();
-        
+
         // Simulate some callbacks
         for _ in 0..10 {
             let _timer = profiler.start_callback();
             thread::sleep(Duration::from_micros(100));
         }
-        
+
         let stats = profiler.get_stats();
         assert_eq!(stats.callback_count, 10);
         assert!(stats.avg_callback_time > 0);
@@ -283,16 +396,16 @@ mod tests {
     #[test]
     fn test_operation_profiling() {
         let profiler = AudioProfiler::new();
-        
+
         // Profile some operations
         for _ in 0..5 {
             let _timer = OperationTimer::new("test_operation", &profiler);
             thread::sleep(Duration::from_micros(50));
         }
-        
+
         let stats = profiler.get_stats();
         assert!(stats.operation_stats.contains_key("test_operation"));
-        
+
         let op_stats = &stats.operation_stats["test_operation"];
         assert_eq!(op_stats.call_count, 5);
         assert!(op_stats.avg_time > 0);
@@ -301,18 +414,48 @@ mod tests {
     #[test]
     fn test_profiler_reset() {
         let profiler = AudioProfiler::new();
-        
+
         // Generate some data
         let _timer = profiler.start_callback();
         thread::sleep(Duration::from_micros(100));
-        
+
         let stats_before = profiler.get_stats();
         assert!(stats_before.callback_count > 0);
-        
+
         // Reset and check
         profiler.reset();
         let stats_after = profiler.get_stats();
         assert_eq!(stats_after.callback_count, 0);
         assert_eq!(stats_after.total_callback_time, 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_spans_ring_buffer_and_chrome_trace() {
+        let profiler = AudioProfiler::with_trace_capacity(4);
+
+        for _ in 0..3 {
+            let _callback = profiler.start_callback();
+            let _voices = OperationTimer::new(section::VOICES, &profiler);
+        }
+
+        // 6 spans recorded, only the 4 most recent are kept
+        let spans = profiler.spans();
+        assert_eq!(spans.len(), 4);
+
+        let trace: serde_json::Value =
+            serde_json::from_str(&profiler.export_chrome_trace()).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[0]["ph"], "X");
+        assert!(
+            events
+                .iter()
+                .any(|e| e["name"] == section::CALLBACK || e["name"] == section::VOICES)
+        );
+
+        profiler.set_tracing_enabled(false);
+        profiler.reset();
+        drop(OperationTimer::new(section::PLUGINS, &profiler));
+        assert!(profiler.spans().is_empty());
+    }
+}
@@ -0,0 +1,390 @@
+// Audio recording - Capture an input onto the timeline
+//
+// The input stream callback (see `audio::engine::AudioInput`) feeds an
+// `InputCapture`: it pushes mono samples into a lock-free ring while a take is
+// recorded and publishes the input peak, without allocating nor waiting. The
+// `Recorder` drains the ring on its own thread, streams the take to a WAV file
+// and keeps the audio, so that stopping gives a `RecordedClip` ready to play
+// on an audio track.
+//
+// Input and output streams run on separate clocks: a take is placed at the
+// playhead read when recording starts, and plays at the input sample rate
+// (audio clips resample on the fly).
+
+use crate::audio::clip_player::AudioClip;
+use crate::audio::parameters::AtomicF32;
+use crate::sampler::loader::{LoopMode, Sample, SampleData};
+use hound::{WavSpec, WavWriter};
+use ringbuf::traits::{Consumer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Samples buffered between the input callback and the recorder thread
+/// (about 2.7 s at 48 kHz)
+pub const INPUT_RING_CAPACITY: usize = 131072;
+
+/// Pause of the recorder thread when the ring is empty
+const DRAIN_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Directory the takes of a project are recorded to
+///
+/// Saved projects get a `recordings` folder next to the project file, untitled
+/// ones use the user data directory.
+pub fn recordings_directory(project_path: Option<&Path>) -> PathBuf {
+    match project_path.and_then(Path::parent) {
+        Some(directory) => directory.join("recordings"),
+        None => dirs::data_dir()
+            .unwrap_or_default()
+            .join("mymusic_daw")
+            .join("recordings"),
+    }
+}
+
+/// State shared by the input callback and the recorder
+struct CaptureState {
+    /// The callback pushes samples while true
+    recording: AtomicBool,
+    /// Peak of the last input block (linear)
+    peak: AtomicF32,
+    /// Samples lost because the ring was full
+    overruns: AtomicU64,
+}
+
+/// Input side of a recording, owned by the input stream callback (RT-safe)
+pub struct InputCapture {
+    producer: HeapProd<f32>,
+    state: Arc<CaptureState>,
+    /// Recording flag read at the start of the block
+    recording: bool,
+    block_peak: f32,
+    block_overruns: u64,
+}
+
+impl InputCapture {
+    /// Start an input block (one atomic load)
+    #[inline]
+    pub fn begin_block(&mut self) {
+        self.recording = self.state.recording.load(Ordering::Acquire);
+        self.block_peak = 0.0;
+        self.block_overruns = 0;
+    }
+
+    /// Add a mono input sample (kept only while recording)
+    #[inline]
+    pub fn push(&mut self, sample: f32) {
+        self.block_peak = self.block_peak.max(sample.abs());
+        if self.recording && self.producer.try_push(sample).is_err() {
+            self.block_overruns += 1;
+        }
+    }
+
+    /// Publish the block peak and lost samples
+    #[inline]
+    pub fn end_block(&mut self) {
+        self.state.peak.set(self.block_peak);
+        if self.block_overruns > 0 {
+            self.state
+                .overruns
+                .fetch_add(self.block_overruns, Ordering::Relaxed);
+        }
+    }
+}
+
+/// A recorded take, placed on the timeline
+#[derive(Debug, Clone)]
+pub struct RecordedClip {
+    /// WAV file of the take
+    pub path: PathBuf,
+    /// Timeline position where recording started (engine samples)
+    pub start: u64,
+    /// Recorded audio (mono, at the input sample rate)
+    pub sample: Arc<Sample>,
+}
+
+impl RecordedClip {
+    /// Clip playing the take where it was recorded (`sample_rate`: engine rate)
+    pub fn into_audio_clip(self, sample_rate: f32) -> AudioClip {
+        AudioClip::new(self.path, self.sample, self.start, sample_rate)
+    }
+}
+
+/// What the recorder thread gives back: the ring output and the take audio
+type DrainResult = (HeapCons<f32>, Result<Vec<f32>, String>);
+
+/// Take being written by the recorder thread
+struct ActiveTake {
+    path: PathBuf,
+    start: u64,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<DrainResult>,
+}
+
+/// Records the input to disk, one take at a time (UI side)
+pub struct Recorder {
+    state: Arc<CaptureState>,
+    /// Ring output, lent to the recorder thread during a take
+    consumer: Option<HeapCons<f32>>,
+    sample_rate: u32,
+    take: Option<ActiveTake>,
+}
+
+/// Create the capture (for the input callback) and the recorder of an input
+/// stream running at `sample_rate`
+pub fn recording_channel(sample_rate: u32, capacity: usize) -> (InputCapture, Recorder) {
+    let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+    let state = Arc::new(CaptureState {
+        recording: AtomicBool::new(false),
+        peak: AtomicF32::new(0.0),
+        overruns: AtomicU64::new(0),
+    });
+    let capture = InputCapture {
+        producer,
+        state: state.clone(),
+        recording: false,
+        block_peak: 0.0,
+        block_overruns: 0,
+    };
+    let recorder = Recorder {
+        state,
+        consumer: Some(consumer),
+        sample_rate,
+        take: None,
+    };
+    (capture, recorder)
+}
+
+impl Recorder {
+    /// Sample rate of the input
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.take.is_some()
+    }
+
+    /// Peak of the last input block (linear), for the input meter
+    pub fn input_peak(&self) -> f32 {
+        self.state.peak.get()
+    }
+
+    /// Samples lost since the input was opened (recorder thread too slow)
+    pub fn overruns(&self) -> u64 {
+        self.state.overruns.load(Ordering::Relaxed)
+    }
+
+    /// Start recording a take to `path`, placed at `start` (engine samples)
+    pub fn start(&mut self, path: PathBuf, start: u64) -> Result<(), String> {
+        if self.take.is_some() {
+            return Err("Already recording".to_string());
+        }
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)
+                .map_err(|e| format!("Failed to create {}: {}", directory.display(), e))?;
+        }
+        let mut consumer = self
+            .consumer
+            .take()
+            .ok_or("The recorder lost its input after a failed take")?;
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let writer = match WavWriter::create(&path, spec) {
+            Ok(writer) => writer,
+            Err(e) => {
+                self.consumer = Some(consumer);
+                return Err(format!("Failed to create {}: {}", path.display(), e));
+            }
+        };
+
+        // Left over from the end of the previous take
+        consumer.clear();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = std::thread::Builder::new()
+            .name("recorder".to_string())
+            .spawn(move || {
+                let result = drain_take(&mut consumer, writer, &thread_stop);
+                (consumer, result)
+            })
+            .map_err(|e| format!("Failed to start the recorder: {}", e))?;
+        self.state.recording.store(true, Ordering::Release);
+
+        self.take = Some(ActiveTake {
+            path,
+            start,
+            stop,
+            thread,
+        });
+        Ok(())
+    }
+
+    /// Stop the take and return it (the file stays on disk)
+    pub fn stop(&mut self) -> Result<RecordedClip, String> {
+        let take = self.take.take().ok_or("Not recording")?;
+        self.state.recording.store(false, Ordering::Release);
+        take.stop.store(true, Ordering::Release);
+
+        let (consumer, result) = take
+            .thread
+            .join()
+            .map_err(|_| "The recorder thread panicked".to_string())?;
+        self.consumer = Some(consumer);
+        let data = result?;
+        if data.is_empty() {
+            let _ = std::fs::remove_file(&take.path);
+            return Err("Nothing was recorded".to_string());
+        }
+
+        let loop_end = data.len();
+        let sample = Sample {
+            name: take
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            data: SampleData::F32(data.into()),
+            sample_rate: self.sample_rate,
+            source_channels: 1,
+            loop_mode: LoopMode::Off,
+            loop_start: 0,
+            loop_end,
+            reverse: false,
+            volume: 1.0,
+            pan: 0.0,
+            pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: None,
+        };
+        Ok(RecordedClip {
+            path: take.path,
+            start: take.start,
+            sample: Arc::new(sample),
+        })
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        // Finalize the file of an unfinished take
+        if self.take.is_some() {
+            let _ = self.stop();
+        }
+    }
+}
+
+/// Recorder thread: write the ring to the WAV file until stopped, then
+/// finalize it
+fn drain_take(
+    consumer: &mut HeapCons<f32>,
+    mut writer: WavWriter<std::io::BufWriter<std::fs::File>>,
+    stop: &AtomicBool,
+) -> Result<Vec<f32>, String> {
+    let mut chunk = vec![0.0; 4096];
+    let mut audio = Vec::new();
+    let mut error = None;
+    loop {
+        // Read the flag first: what the callback pushed before it is drained below
+        let stopping = stop.load(Ordering::Acquire);
+        let count = consumer.pop_slice(&mut chunk);
+        if count > 0 {
+            audio.extend_from_slice(&chunk[..count]);
+            if error.is_none() {
+                for sample in &chunk[..count] {
+                    if let Err(e) = writer.write_sample(*sample) {
+                        error = Some(e.to_string());
+                        break;
+                    }
+                }
+            }
+        } else if stopping {
+            break;
+        } else {
+            std::thread::sleep(DRAIN_INTERVAL);
+        }
+    }
+    writer.finalize().map_err(|e| e.to_string())?;
+    match error {
+        Some(e) => Err(format!("Failed to write the take: {}", e)),
+        None => Ok(audio),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_take() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("takes").join("take_1.wav");
+        let (mut capture, mut recorder) = recording_channel(44100, 1024);
+
+        // Not recording: only the meter follows the input
+        capture.begin_block();
+        capture.push(0.5);
+        capture.end_block();
+        assert_eq!(recorder.input_peak(), 0.5);
+
+        recorder.start(path.clone(), 9600).unwrap();
+        assert!(recorder.is_recording());
+        for block in 0..4 {
+            capture.begin_block();
+            for i in 0..100 {
+                capture.push(((block * 100 + i) as f32 / 400.0) - 0.5);
+            }
+            capture.end_block();
+        }
+        let take = recorder.stop().unwrap();
+        assert!(!recorder.is_recording());
+        assert_eq!(take.start, 9600);
+        assert_eq!(take.sample.len(), 400);
+        assert_eq!(take.sample.sample_rate, 44100);
+
+        // The file holds the same audio
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        let written: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+        let SampleData::F32(data) = &take.sample.data;
+        assert_eq!(written.len(), 400);
+        assert_eq!(written[..], data[..]);
+
+        // Played at the engine rate, from where recording started
+        let clip = take.into_audio_clip(48000.0);
+        assert_eq!(clip.start, 9600);
+        assert_eq!(clip.length, 435);
+    }
+
+    #[test]
+    fn test_overruns_and_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut capture, mut recorder) = recording_channel(48000, 16);
+        assert!(recorder.stop().is_err());
+
+        // An empty take is not kept
+        let empty = dir.path().join("empty.wav");
+        recorder.start(empty.clone(), 0).unwrap();
+        assert!(recorder.start(dir.path().join("other.wav"), 0).is_err());
+        assert!(recorder.stop().is_err());
+        assert!(!empty.exists());
+
+        // Samples pushed faster than the ring holds are counted as lost
+        recorder.start(dir.path().join("take.wav"), 0).unwrap();
+        capture.begin_block();
+        for _ in 0..1000 {
+            capture.push(0.1);
+        }
+        capture.end_block();
+        assert!(recorder.overruns() > 0);
+        let take = recorder.stop().unwrap();
+        assert!(take.sample.len() < 1000);
+    }
+}
@@ -0,0 +1,343 @@
+// Return buses - Shared send effects
+//
+// A return bus runs one effect (reverb, delay or stereo delay) fed by the sends of every
+// track, and adds its output back to the mix: one reverb instance serves all
+// tracks. The effect runs fully wet, the amount of effect is set by the track
+// sends and the bus return level.
+//
+// Buses live in a fixed number of slots, and a track send is addressed by the
+// slot of its bus. Sends are either post-fader (after the track gain and pan)
+// or pre-fader (after the insert, before the fader: the effect level does not
+// follow the track fader).
+//
+// The stereo delay syncs its times to the tempo of the transport
+// (`set_samples_per_beat()`, called by the clip player every buffer).
+//
+// Like tracks, a bus plays on the master bus or on a hardware output pair
+// (see `audio::outputs`).
+//
+// Real-time constraints:
+// - The effects of every slot are allocated in `ReturnBuses::new()` (outside
+//   the callback)
+// - Configuring a slot only copies small `Copy` values
+
+use crate::audio::clip_player::MAX_CLIP_GAIN;
+use crate::audio::outputs::TrackOutput;
+use crate::synth::delay::{Delay, DelayParams, StereoDelay, StereoDelayParams};
+use crate::synth::master_chain::MASTER_DELAY_MAX_MS;
+use crate::synth::reverb::{Reverb, ReverbParams};
+
+/// Number of return bus slots
+pub const MAX_RETURN_BUSES: usize = 4;
+
+/// Effect of a return bus
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ReturnEffect {
+    Reverb(ReverbParams),
+    Delay(DelayParams),
+    StereoDelay(StereoDelayParams),
+}
+
+impl ReturnEffect {
+    /// Effect name for UI display
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReturnEffect::Reverb(_) => "Reverb",
+            ReturnEffect::Delay(_) => "Delay",
+            ReturnEffect::StereoDelay(_) => "Stereo Delay",
+        }
+    }
+}
+
+/// Return bus configuration (what the audio thread needs)
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReturnBusParams {
+    pub effect: ReturnEffect,
+    /// Return level, linear gain (0.0 to `MAX_CLIP_GAIN`)
+    pub level: f32,
+    pub muted: bool,
+    /// Where the bus plays
+    #[serde(default)]
+    pub output: TrackOutput,
+}
+
+impl ReturnBusParams {
+    pub fn new(effect: ReturnEffect) -> Self {
+        Self {
+            effect,
+            level: 1.0,
+            muted: false,
+            output: TrackOutput::Master,
+        }
+    }
+
+    /// Validate and clamp parameters (the effect is always on and fully wet)
+    pub fn validate(&mut self) {
+        self.level = self.level.clamp(0.0, MAX_CLIP_GAIN);
+        if !self.output.is_valid() {
+            self.output = TrackOutput::Master;
+        }
+        match &mut self.effect {
+            ReturnEffect::Reverb(params) => {
+                *params = ReverbParams::new(params.room_size, params.damping, 1.0);
+            }
+            ReturnEffect::Delay(params) => {
+                params.validate(MASTER_DELAY_MAX_MS);
+                params.mix = 1.0;
+                params.enabled = true;
+            }
+            ReturnEffect::StereoDelay(params) => {
+                params.validate(MASTER_DELAY_MAX_MS);
+                params.mix = 1.0;
+                params.enabled = true;
+            }
+        }
+    }
+}
+
+/// Named return bus (UI side)
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ReturnBus {
+    pub name: String,
+    pub params: ReturnBusParams,
+}
+
+/// Send from a track to a return bus
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TrackSend {
+    /// Send level, linear gain (0.0 to 1.0, 0.0: no send)
+    pub level: f32,
+    /// Taken before the track gain and pan
+    pub pre_fader: bool,
+}
+
+/// Sends of a track, by return bus slot
+pub type TrackSends = [TrackSend; MAX_RETURN_BUSES];
+
+/// Effects of one slot (both kinds, so that changing the effect does not allocate)
+struct ReturnSlot {
+    params: Option<ReturnBusParams>,
+    reverb: [Reverb; 2],
+    delay: [Delay; 2],
+    stereo_delay: StereoDelay,
+}
+
+/// Audio thread side: the effects of the return buses
+pub struct ReturnBuses {
+    slots: [ReturnSlot; MAX_RETURN_BUSES],
+}
+
+impl ReturnBuses {
+    /// Create the bus slots (allocates the delay lines)
+    pub fn new(sample_rate: f32) -> Self {
+        // Created wet, so that no dry signal leaks while the mix is smoothed
+        let reverb = ReverbParams {
+            mix: 1.0,
+            ..ReverbParams::default()
+        };
+        let delay = DelayParams {
+            mix: 1.0,
+            ..DelayParams::default()
+        };
+        let stereo_delay = StereoDelayParams {
+            mix: 1.0,
+            ..StereoDelayParams::default()
+        };
+        let slot = || ReturnSlot {
+            params: None,
+            reverb: [
+                Reverb::new(reverb, sample_rate),
+                Reverb::new(reverb, sample_rate),
+            ],
+            delay: [
+                Delay::new(delay, sample_rate, MASTER_DELAY_MAX_MS),
+                Delay::new(delay, sample_rate, MASTER_DELAY_MAX_MS),
+            ],
+            stereo_delay: StereoDelay::new(stereo_delay, sample_rate, MASTER_DELAY_MAX_MS),
+        };
+        Self {
+            slots: std::array::from_fn(|_| slot()),
+        }
+    }
+
+    /// Configure a slot (None removes its bus), out-of-range slots are ignored
+    ///
+    /// The effect starts from silence when the bus is created or its effect
+    /// type changes; parameter changes keep the tail.
+    pub fn set_bus(&mut self, index: usize, params: Option<ReturnBusParams>) {
+        let Some(slot) = self.slots.get_mut(index) else {
+            return;
+        };
+        if let Some(mut params) = params {
+            params.validate();
+            let same_effect = slot.params.is_some_and(|current| {
+                std::mem::discriminant(&current.effect) == std::mem::discriminant(&params.effect)
+            });
+            match params.effect {
+                ReturnEffect::Reverb(effect) => {
+                    for reverb in &mut slot.reverb {
+                        reverb.set_params(effect);
+                        if !same_effect {
+                            reverb.reset();
+                        }
+                    }
+                }
+                ReturnEffect::Delay(effect) => {
+                    for delay in &mut slot.delay {
+                        delay.set_params(effect);
+                        if !same_effect {
+                            delay.reset();
+                        }
+                    }
+                }
+                ReturnEffect::StereoDelay(effect) => {
+                    slot.stereo_delay.set_params(effect);
+                    if !same_effect {
+                        slot.stereo_delay.reset();
+                    }
+                }
+            }
+            slot.params = Some(params);
+        } else {
+            slot.params = None;
+        }
+    }
+
+    /// Set the tempo of the synced effect times
+    #[inline]
+    pub fn set_samples_per_beat(&mut self, samples_per_beat: f64) {
+        for slot in &mut self.slots {
+            slot.stereo_delay.set_samples_per_beat(samples_per_beat);
+        }
+    }
+
+    /// Whether a slot has a bus
+    pub fn is_active(&self, index: usize) -> bool {
+        self.slots
+            .get(index)
+            .is_some_and(|slot| slot.params.is_some())
+    }
+
+    /// Where the bus of a slot plays
+    pub fn output(&self, index: usize) -> TrackOutput {
+        self.slots
+            .get(index)
+            .and_then(|slot| slot.params)
+            .map_or(TrackOutput::Master, |params| params.output)
+    }
+
+    /// Effect of the bus of a slot
+    pub fn effect(&self, index: usize) -> Option<ReturnEffect> {
+        self.slots
+            .get(index)
+            .and_then(|slot| slot.params)
+            .map(|params| params.effect)
+    }
+
+    /// Whether any slot has a bus
+    pub fn any_active(&self) -> bool {
+        self.slots.iter().any(|slot| slot.params.is_some())
+    }
+
+    /// Process a frame of the bus input, returns the output to add to the mix
+    #[inline]
+    pub fn process(&mut self, index: usize, left: f32, right: f32) -> (f32, f32) {
+        let slot = &mut self.slots[index];
+        let Some(params) = slot.params else {
+            return (0.0, 0.0);
+        };
+        let (left, right) = match params.effect {
+            ReturnEffect::Reverb(_) => {
+                (slot.reverb[0].process(left), slot.reverb[1].process(right))
+            }
+            ReturnEffect::Delay(_) => (slot.delay[0].process(left), slot.delay[1].process(right)),
+            ReturnEffect::StereoDelay(_) => slot.stereo_delay.process(left, right),
+        };
+        // Muted buses keep running, so that unmuting does not restart the tail
+        let level = if params.muted { 0.0 } else { params.level };
+        (left * level, right * level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    #[test]
+    fn test_return_effect_is_fully_wet() {
+        let mut params = ReturnBusParams::new(ReturnEffect::Delay(DelayParams::new(1.0, 0.0, 0.2)));
+        params.level = 5.0;
+        params.validate();
+        assert_eq!(params.level, MAX_CLIP_GAIN);
+        let ReturnEffect::Delay(delay) = params.effect else {
+            panic!("effect changed");
+        };
+        assert_eq!(delay.mix, 1.0);
+
+        // 1 ms delay: only the delayed impulse comes back, at the return level
+        params.level = 0.5;
+        let mut buses = ReturnBuses::new(SAMPLE_RATE);
+        buses.set_bus(1, Some(params));
+        let mut output = Vec::new();
+        for i in 0..96 {
+            let input = if i == 0 { 1.0 } else { 0.0 };
+            output.push(buses.process(1, input, input).0);
+        }
+        assert_eq!(output[0], 0.0);
+        let peak = output
+            .iter()
+            .fold(0.0f32, |peak, value| peak.max(value.abs()));
+        assert!((peak - 0.5).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_empty_and_muted_slots_are_silent() {
+        let mut buses = ReturnBuses::new(SAMPLE_RATE);
+        assert!(!buses.any_active());
+        assert_eq!(buses.process(0, 1.0, 1.0), (0.0, 0.0));
+
+        let mut params = ReturnBusParams::new(ReturnEffect::Reverb(ReverbParams::default()));
+        params.muted = true;
+        buses.set_bus(0, Some(params));
+        buses.set_bus(MAX_RETURN_BUSES, Some(params));
+        assert!(buses.is_active(0) && buses.any_active());
+        for _ in 0..4800 {
+            assert_eq!(buses.process(0, 1.0, 1.0), (0.0, 0.0));
+        }
+
+        buses.set_bus(0, None);
+        assert!(!buses.any_active());
+    }
+
+    #[test]
+    fn test_stereo_delay_bus_follows_the_tempo() {
+        let effect = StereoDelayParams {
+            mix: 0.2,
+            ..StereoDelayParams::default()
+        };
+        let mut params = ReturnBusParams::new(ReturnEffect::StereoDelay(effect));
+        params.validate();
+        assert_eq!(params.effect.name(), "Stereo Delay");
+        let ReturnEffect::StereoDelay(delay) = params.effect else {
+            panic!("effect changed");
+        };
+        assert_eq!(delay.mix, 1.0);
+
+        // 240 BPM: the left 1/8 dotted repeat comes back after 0.75 beat
+        let mut buses = ReturnBuses::new(SAMPLE_RATE);
+        buses.set_bus(2, Some(params));
+        buses.set_samples_per_beat(SAMPLE_RATE as f64 / 4.0);
+        let mut output = Vec::new();
+        for i in 0..12000 {
+            let input = if i == 0 { 1.0 } else { 0.0 };
+            output.push(buses.process(2, input, input));
+        }
+        let first_left = output.iter().position(|frame| frame.0.abs() > 0.1);
+        let first_right = output.iter().position(|frame| frame.1.abs() > 0.1);
+        assert_eq!(first_left, Some(9000));
+        assert_eq!(first_right, None);
+    }
+}
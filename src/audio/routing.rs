@@ -6,8 +6,12 @@
 // Architecture:
 // - AudioNode trait: Common interface for all audio processing nodes
 // - AudioRoutingGraph: Manages nodes and connections
-// - Node types: InstrumentNode, EffectNode, MixerNode, OutputNode
+// - Node types: InstrumentNode, EffectNode, DynamicsNode, MixerNode, OutputNode
+// - Sidechain: a connection to the "sidechain" input of a DynamicsNode keys
+//   its detector with another node's signal
 // - Bus system: Auxiliary sends/returns for effect sends
+// - Mixer stage: per-track gain/pan/mute/solo (TrackMixer), applied by the
+//   clip player in the audio callback
 // - Topological processing: Execute nodes in dependency order
 // - Cycle detection: Prevent infinite loops
 //
@@ -18,6 +22,8 @@
 // - Deterministic execution order
 
 use super::parameters::AtomicF32;
+use crate::audio::instruments::MAX_INSTRUMENT_TRACKS;
+use crate::synth::dynamics::{Dynamics, DynamicsParams};
 use crate::synth::effect::EffectChain;
 use crate::synth::voice_manager::VoiceManager;
 use std::collections::{HashMap, HashSet, VecDeque};
@@ -53,6 +59,7 @@ pub trait AudioNode: Send {
 pub enum AudioNodeType {
     Instrument(InstrumentNode),
     Effect(EffectNode),
+    Dynamics(DynamicsNode),
     Mixer(MixerNode),
     Output(OutputNode),
     Plugin(Box<dyn AudioNode>), // Generic plugin node
@@ -63,6 +70,7 @@ impl AudioNode for AudioNodeType {
         match self {
             AudioNodeType::Instrument(node) => node.id(),
             AudioNodeType::Effect(node) => node.id(),
+            AudioNodeType::Dynamics(node) => node.id(),
             AudioNodeType::Mixer(node) => node.id(),
             AudioNodeType::Output(node) => node.id(),
             AudioNodeType::Plugin(node) => node.id(),
@@ -73,6 +81,7 @@ impl AudioNode for AudioNodeType {
         match self {
             AudioNodeType::Instrument(node) => node.name(),
             AudioNodeType::Effect(node) => node.name(),
+            AudioNodeType::Dynamics(node) => node.name(),
             AudioNodeType::Mixer(node) => node.name(),
             AudioNodeType::Output(node) => node.name(),
             AudioNodeType::Plugin(node) => node.name(),
@@ -83,6 +92,7 @@ impl AudioNode for AudioNodeType {
         match self {
             AudioNodeType::Instrument(node) => node.node_type(),
             AudioNodeType::Effect(node) => node.node_type(),
+            AudioNodeType::Dynamics(node) => node.node_type(),
             AudioNodeType::Mixer(node) => node.node_type(),
             AudioNodeType::Output(node) => node.node_type(),
             AudioNodeType::Plugin(node) => node.node_type(),
@@ -93,6 +103,7 @@ impl AudioNode for AudioNodeType {
         match self {
             AudioNodeType::Instrument(node) => node.process(inputs),
             AudioNodeType::Effect(node) => node.process(inputs),
+            AudioNodeType::Dynamics(node) => node.process(inputs),
             AudioNodeType::Mixer(node) => node.process(inputs),
             AudioNodeType::Output(node) => node.process(inputs),
             AudioNodeType::Plugin(node) => node.process(inputs),
@@ -103,6 +114,7 @@ impl AudioNode for AudioNodeType {
         match self {
             AudioNodeType::Instrument(node) => node.reset(),
             AudioNodeType::Effect(node) => node.reset(),
+            AudioNodeType::Dynamics(node) => node.reset(),
             AudioNodeType::Mixer(node) => node.reset(),
             AudioNodeType::Output(node) => node.reset(),
             AudioNodeType::Plugin(node) => node.reset(),
@@ -113,6 +125,7 @@ impl AudioNode for AudioNodeType {
         match self {
             AudioNodeType::Instrument(node) => node.latency_samples(),
             AudioNodeType::Effect(node) => node.latency_samples(),
+            AudioNodeType::Dynamics(node) => node.latency_samples(),
             AudioNodeType::Mixer(node) => node.latency_samples(),
             AudioNodeType::Output(node) => node.latency_samples(),
             AudioNodeType::Plugin(node) => node.latency_samples(),
@@ -613,6 +626,69 @@ impl AudioNode for EffectNode {
     }
 }
 
+/// Input of a `DynamicsNode` keying its detector
+pub const SIDECHAIN_INPUT: &str = "sidechain";
+
+/// Dynamics Node - compressor/gate on the "main" input, keyed by the
+/// "sidechain" input when something is connected to it
+pub struct DynamicsNode {
+    id: NodeId,
+    name: String,
+    dynamics: Dynamics,
+}
+
+impl DynamicsNode {
+    pub fn new(id: NodeId, params: DynamicsParams, sample_rate: f32) -> Self {
+        Self {
+            id,
+            name: params.mode.name().to_string(),
+            dynamics: Dynamics::new(params, sample_rate),
+        }
+    }
+
+    pub fn dynamics(&mut self) -> &mut Dynamics {
+        &mut self.dynamics
+    }
+}
+
+impl AudioNode for DynamicsNode {
+    fn id(&self) -> NodeId {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> NodeType {
+        NodeType::Effect
+    }
+
+    fn process(&mut self, inputs: &HashMap<String, (f32, f32)>) -> HashMap<String, (f32, f32)> {
+        let (left_input, right_input) = *inputs.get("main").unwrap_or(&(0.0, 0.0));
+        // Without a sidechain connection the node listens to its own input
+        let (key_left, key_right) = *inputs
+            .get(SIDECHAIN_INPUT)
+            .unwrap_or(&(left_input, right_input));
+
+        let output = self
+            .dynamics
+            .process(left_input, right_input, key_left, key_right);
+
+        let mut outputs = HashMap::new();
+        outputs.insert("main".to_string(), output);
+        outputs
+    }
+
+    fn reset(&mut self) {
+        self.dynamics.reset();
+    }
+
+    fn latency_samples(&self) -> usize {
+        0 // No lookahead
+    }
+}
+
 /// Mixer Node - mixes multiple inputs with individual gain/pan
 pub struct MixerNode {
     id: NodeId,
@@ -735,6 +811,187 @@ impl AudioNode for OutputNode {
     }
 }
 
+/// Audio track strips held by the mixer stage (reserved up front)
+pub const MAX_MIXER_STRIPS: usize = 256;
+
+/// Track addressed by the mixer stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MixerTrack {
+    /// The synth/sampler voices and the plugins
+    Synth,
+    /// An audio track, by id
+    Audio(u32),
+    /// An instrument track, by id (see `audio::instruments`)
+    Instrument(u32),
+}
+
+/// Fader, pan, mute and solo of a track
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelStrip {
+    /// Linear gain (0.0 to 2.0)
+    pub gain: f32,
+    /// Pan (-1.0 left, 0.0 center, 1.0 right)
+    pub pan: f32,
+    pub muted: bool,
+    pub soloed: bool,
+}
+
+impl Default for ChannelStrip {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            pan: 0.0,
+            muted: false,
+            soloed: false,
+        }
+    }
+}
+
+impl ChannelStrip {
+    /// Left and right gains of the fader and pan (same balance law as the
+    /// project export)
+    #[inline]
+    pub fn balance(&self) -> (f32, f32) {
+        (
+            self.gain * (1.0 - self.pan).min(1.0),
+            self.gain * (1.0 + self.pan).min(1.0),
+        )
+    }
+}
+
+/// Mixer stage - the strip of each track, applied by the clip player (RT-safe)
+///
+/// While a track is soloed, only the soloed tracks are heard. Return buses are
+/// not soloed: they receive the sends of the tracks that are heard. Audio
+/// tracks without a strip (past `MAX_MIXER_STRIPS`) play at unity.
+pub struct TrackMixer {
+    synth: ChannelStrip,
+    /// Strips of the audio tracks, by track id
+    audio: Vec<(u32, ChannelStrip)>,
+    /// Strips of the instrument tracks, by track id
+    instruments: Vec<(u32, ChannelStrip)>,
+    /// Number of soloed strips
+    solos: usize,
+}
+
+impl TrackMixer {
+    pub fn new() -> Self {
+        Self {
+            synth: ChannelStrip::default(),
+            audio: Vec::with_capacity(MAX_MIXER_STRIPS),
+            instruments: Vec::with_capacity(MAX_INSTRUMENT_TRACKS),
+            solos: 0,
+        }
+    }
+
+    /// Replace the audio track strips (new track list, no allocation)
+    pub fn set_audio_strips(&mut self, strips: impl Iterator<Item = (u32, ChannelStrip)>) {
+        self.audio.clear();
+        self.audio.extend(strips.take(MAX_MIXER_STRIPS));
+        self.count_solos();
+    }
+
+    /// Set the strip of a track, returns false for an unknown audio track
+    ///
+    /// Instrument tracks get a strip on their first one (false past
+    /// `MAX_INSTRUMENT_TRACKS`).
+    pub fn set_strip(&mut self, track: MixerTrack, strip: ChannelStrip) -> bool {
+        match track {
+            MixerTrack::Synth => self.synth = strip,
+            MixerTrack::Audio(id) => {
+                match self.audio.iter_mut().find(|(track_id, _)| *track_id == id) {
+                    Some((_, current)) => *current = strip,
+                    None => return false,
+                }
+            }
+            MixerTrack::Instrument(id) => {
+                let existing = self
+                    .instruments
+                    .iter_mut()
+                    .find(|(track_id, _)| *track_id == id);
+                if let Some((_, current)) = existing {
+                    *current = strip;
+                } else if self.instruments.len() < MAX_INSTRUMENT_TRACKS {
+                    self.instruments.push((id, strip));
+                } else {
+                    return false;
+                }
+            }
+        }
+        self.count_solos();
+        true
+    }
+
+    /// Forget the strip of a removed instrument track
+    pub fn remove_instrument_strip(&mut self, id: u32) {
+        self.instruments.retain(|(track_id, _)| *track_id != id);
+        self.count_solos();
+    }
+
+    pub fn strip(&self, track: MixerTrack) -> ChannelStrip {
+        match track {
+            MixerTrack::Synth => self.synth,
+            MixerTrack::Audio(id) => self
+                .audio
+                .iter()
+                .find(|(track_id, _)| *track_id == id)
+                .map(|(_, strip)| *strip)
+                .unwrap_or_default(),
+            MixerTrack::Instrument(id) => self
+                .instruments
+                .iter()
+                .find(|(track_id, _)| *track_id == id)
+                .map(|(_, strip)| *strip)
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn any_solo(&self) -> bool {
+        self.solos > 0
+    }
+
+    /// Whether a track is heard: not muted, and soloed while a solo is on
+    #[inline]
+    pub fn is_audible(&self, track: MixerTrack) -> bool {
+        let strip = self.strip(track);
+        !strip.muted && (strip.soloed || !self.any_solo())
+    }
+
+    /// Fader gain of a track in the mix (0.0 when it is not heard)
+    #[inline]
+    pub fn level(&self, track: MixerTrack) -> f32 {
+        if self.is_audible(track) {
+            self.strip(track).gain
+        } else {
+            0.0
+        }
+    }
+
+    /// Left and right gains of a track in the mix (silent when it is not heard)
+    #[inline]
+    pub fn gains(&self, track: MixerTrack) -> (f32, f32) {
+        if self.is_audible(track) {
+            self.strip(track).balance()
+        } else {
+            (0.0, 0.0)
+        }
+    }
+
+    fn count_solos(&mut self) {
+        self.solos = std::iter::once(&self.synth)
+            .chain(self.audio.iter().map(|(_, strip)| strip))
+            .chain(self.instruments.iter().map(|(_, strip)| strip))
+            .filter(|strip| strip.soloed)
+            .count();
+    }
+}
+
+impl Default for TrackMixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -873,6 +1130,27 @@ mod tests {
         assert_eq!(*right, 2.0);
     }
 
+    #[test]
+    fn test_dynamics_node_sidechain_input() {
+        let mut ducker = DynamicsNode::new(NodeId(0), DynamicsParams::default(), SAMPLE_RATE);
+
+        // A quiet input is left alone when it keys itself...
+        let mut inputs = HashMap::new();
+        inputs.insert("main".to_string(), (0.1, 0.1));
+        let mut output = (0.0, 0.0);
+        for _ in 0..4800 {
+            output = ducker.process(&inputs)["main"];
+        }
+        assert!((output.0 - 0.1).abs() < 1e-4);
+
+        // ...and ducked by a loud signal on the sidechain input
+        inputs.insert(SIDECHAIN_INPUT.to_string(), (1.0, 1.0));
+        for _ in 0..4800 {
+            output = ducker.process(&inputs)["main"];
+        }
+        assert!(output.0 < 0.05);
+    }
+
     #[test]
     fn test_output_node_volume() {
         let mut output = OutputNode::new(NodeId(0));
@@ -889,6 +1167,39 @@ mod tests {
         assert_eq!(*right, 0.5);
     }
 
+    #[test]
+    fn test_track_mixer_mute_and_solo() {
+        let mut mixer = TrackMixer::new();
+        let strip = |gain, pan| ChannelStrip {
+            gain,
+            pan,
+            ..Default::default()
+        };
+        mixer.set_audio_strips([(3, strip(0.5, -1.0)), (7, strip(1.0, 0.0))].into_iter());
+        assert_eq!(mixer.gains(MixerTrack::Synth), (1.0, 1.0));
+        assert_eq!(mixer.gains(MixerTrack::Audio(3)), (0.5, 0.0));
+
+        // Muted tracks are silent
+        let mut muted = strip(1.0, 0.0);
+        muted.muted = true;
+        assert!(mixer.set_strip(MixerTrack::Audio(7), muted));
+        assert_eq!(mixer.gains(MixerTrack::Audio(7)), (0.0, 0.0));
+
+        // A solo silences every other track
+        let mut soloed = strip(0.5, -1.0);
+        soloed.soloed = true;
+        mixer.set_strip(MixerTrack::Audio(3), soloed);
+        assert!(mixer.any_solo());
+        assert!(!mixer.is_audible(MixerTrack::Synth));
+        assert_eq!(mixer.level(MixerTrack::Audio(3)), 0.5);
+
+        // Unknown tracks are ignored, a new track list drops the solo
+        assert!(!mixer.set_strip(MixerTrack::Audio(9), soloed));
+        mixer.set_audio_strips(std::iter::empty());
+        assert!(!mixer.any_solo());
+        assert_eq!(mixer.gains(MixerTrack::Audio(3)), (1.0, 1.0));
+    }
+
     #[test]
     fn test_node_types() {
         let voice_manager = VoiceManager::new(SAMPLE_RATE);
@@ -0,0 +1,277 @@
+// RT-safe logging - Log from the audio callback without I/O
+//
+// The audio thread must not call `eprintln!` (locks stderr, may block on I/O).
+// Instead, `rt_error!` / `rt_warn!` / `rt_info!` / `rt_debug!` format the
+// message into a fixed-size record on the stack and push it into a ring
+// buffer. A logging thread drains the ring buffer and prints the records.
+//
+// Producers only ever `try_lock` the ring buffer: logging never blocks. If the
+// producer side is contended or the buffer is full, the record is dropped and
+// counted, and the logging thread reports the number of dropped records.
+
+use ringbuf::traits::{Consumer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Maximum message length in bytes (longer messages are truncated)
+pub const RT_LOG_MESSAGE_LEN: usize = 128;
+
+/// Capacity of the global log ring buffer
+pub const RT_LOG_CAPACITY: usize = 256;
+
+/// How often the logging thread drains the ring buffer
+const DRAIN_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Severity of a log record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RtLogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl RtLogLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RtLogLevel::Debug => "DEBUG",
+            RtLogLevel::Info => "INFO",
+            RtLogLevel::Warning => "WARN",
+            RtLogLevel::Error => "ERROR",
+        }
+    }
+}
+
+/// Fixed-size log record (no heap allocation)
+#[derive(Clone, Copy)]
+pub struct RtLogRecord {
+    pub level: RtLogLevel,
+    /// Subsystem that emitted the record ("engine", "plugin"...)
+    pub source: &'static str,
+    len: usize,
+    message: [u8; RT_LOG_MESSAGE_LEN],
+}
+
+impl RtLogRecord {
+    /// Format a record on the stack, truncating the message if needed
+    pub fn new(level: RtLogLevel, source: &'static str, args: fmt::Arguments) -> Self {
+        let mut record = Self {
+            level,
+            source,
+            len: 0,
+            message: [0; RT_LOG_MESSAGE_LEN],
+        };
+        // Only fails once the buffer is full: the message is truncated
+        let _ = record.write_fmt(args);
+        record
+    }
+
+    /// Message text
+    pub fn message(&self) -> &str {
+        // write_str only copies whole UTF-8 characters
+        std::str::from_utf8(&self.message[..self.len]).unwrap_or_default()
+    }
+}
+
+impl Write for RtLogRecord {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let available = RT_LOG_MESSAGE_LEN - self.len;
+        let mut count = s.len().min(available);
+        while !s.is_char_boundary(count) {
+            count -= 1;
+        }
+        self.message[self.len..self.len + count].copy_from_slice(&s.as_bytes()[..count]);
+        self.len += count;
+        if count < s.len() {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Debug for RtLogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RtLogRecord")
+            .field("level", &self.level)
+            .field("source", &self.source)
+            .field("message", &self.message())
+            .finish()
+    }
+}
+
+impl fmt::Display for RtLogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}",
+            self.level.label(),
+            self.source,
+            self.message()
+        )
+    }
+}
+
+/// Log channel shared by RT producers and the logging thread
+pub struct RtLogger {
+    tx: Mutex<HeapProd<RtLogRecord>>,
+    rx: Mutex<HeapCons<RtLogRecord>>,
+    dropped: AtomicU64,
+    thread_started: AtomicBool,
+}
+
+impl RtLogger {
+    pub fn new(capacity: usize) -> Self {
+        let (tx, rx) = HeapRb::<RtLogRecord>::new(capacity).split();
+        Self {
+            tx: Mutex::new(tx),
+            rx: Mutex::new(rx),
+            dropped: AtomicU64::new(0),
+            thread_started: AtomicBool::new(false),
+        }
+    }
+
+    /// Push a record (RT-safe: no allocation, never blocks)
+    pub fn log(&self, level: RtLogLevel, source: &'static str, args: fmt::Arguments) {
+        let record = RtLogRecord::new(level, source, args);
+        let pushed = match self.tx.try_lock() {
+            Ok(mut tx) => tx.try_push(record).is_ok(),
+            Err(_) => false,
+        };
+        if !pushed {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Number of records dropped so far (buffer full or contended)
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Pop all pending records (not RT-safe, called by the logging thread)
+    pub fn drain(&self, mut f: impl FnMut(&RtLogRecord)) -> usize {
+        let Ok(mut rx) = self.rx.lock() else {
+            return 0;
+        };
+        let mut count = 0;
+        while let Some(record) = rx.try_pop() {
+            f(&record);
+            count += 1;
+        }
+        count
+    }
+}
+
+static GLOBAL_RT_LOGGER: LazyLock<RtLogger> = LazyLock::new(|| RtLogger::new(RT_LOG_CAPACITY));
+
+/// Get the global RT logger (used by the `rt_*!` macros)
+pub fn global_rt_logger() -> &'static RtLogger {
+    &GLOBAL_RT_LOGGER
+}
+
+/// Start the thread printing records of the global logger to stderr
+///
+/// Safe to call several times (e.g. on every engine start): only one thread is spawned.
+/// Until it is started, records accumulate and are then dropped when the buffer is full.
+pub fn start_rt_log_thread() {
+    let logger = global_rt_logger();
+    if logger.thread_started.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    let spawned = thread::Builder::new()
+        .name("rt-log".to_string())
+        .spawn(move || {
+            let mut reported_dropped = 0;
+            loop {
+                logger.drain(|record| eprintln!("{}", record));
+
+                let dropped = logger.dropped_count();
+                if dropped > reported_dropped {
+                    eprintln!(
+                        "[WARN] rt_log: {} log records dropped",
+                        dropped - reported_dropped
+                    );
+                    reported_dropped = dropped;
+                }
+                thread::sleep(DRAIN_INTERVAL);
+            }
+        });
+    if spawned.is_err() {
+        logger.thread_started.store(false, Ordering::Release);
+    }
+}
+
+/// Log from real-time code: `rt_log!(RtLogLevel::Error, "engine", "x = {}", x)`
+#[macro_export]
+macro_rules! rt_log {
+    ($level:expr, $source:expr, $($arg:tt)+) => {
+        $crate::audio::rt_log::global_rt_logger().log($level, $source, format_args!($($arg)+))
+    };
+}
+
+/// Log an error from real-time code
+#[macro_export]
+macro_rules! rt_error {
+    ($source:expr, $($arg:tt)+) => {
+        $crate::rt_log!($crate::audio::rt_log::RtLogLevel::Error, $source, $($arg)+)
+    };
+}
+
+/// Log a warning from real-time code
+#[macro_export]
+macro_rules! rt_warn {
+    ($source:expr, $($arg:tt)+) => {
+        $crate::rt_log!($crate::audio::rt_log::RtLogLevel::Warning, $source, $($arg)+)
+    };
+}
+
+/// Log an information message from real-time code
+#[macro_export]
+macro_rules! rt_info {
+    ($source:expr, $($arg:tt)+) => {
+        $crate::rt_log!($crate::audio::rt_log::RtLogLevel::Info, $source, $($arg)+)
+    };
+}
+
+/// Log a debug message from real-time code
+#[macro_export]
+macro_rules! rt_debug {
+    ($source:expr, $($arg:tt)+) => {
+        $crate::rt_log!($crate::audio::rt_log::RtLogLevel::Debug, $source, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_truncates_on_char_boundary() {
+        let record = RtLogRecord::new(RtLogLevel::Info, "test", format_args!("value = {}", 42));
+        assert_eq!(record.message(), "value = 42");
+        assert_eq!(record.to_string(), "[INFO] test: value = 42");
+
+        // 'é' is 2 bytes: the last one cannot fit in 128 bytes after 127 'a'
+        let long = format!("{}é", "a".repeat(RT_LOG_MESSAGE_LEN - 1));
+        let record = RtLogRecord::new(RtLogLevel::Error, "test", format_args!("{}", long));
+        assert_eq!(record.message().len(), RT_LOG_MESSAGE_LEN - 1);
+    }
+
+    #[test]
+    fn test_logger_drops_when_full() {
+        let logger = RtLogger::new(2);
+        for i in 0..3 {
+            logger.log(RtLogLevel::Warning, "engine", format_args!("xrun {}", i));
+        }
+        assert_eq!(logger.dropped_count(), 1);
+
+        let mut messages = Vec::new();
+        assert_eq!(logger.drain(|r| messages.push(r.message().to_string())), 2);
+        assert_eq!(messages, vec!["xrun 0", "xrun 1"]);
+    }
+}
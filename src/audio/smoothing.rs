@@ -0,0 +1,225 @@
+// Parameter smoothing - One smoothing layer for every continuous parameter
+//
+// Continuous parameters (volume, cutoff, resonance, pan, sends, effect mixes,
+// plugin parameters) change in steps: a knob sends a value per UI frame,
+// automation a value per buffer. Applied as is, each step is heard as zipper
+// noise. Each of these parameters is held in a `SmoothedParam`: a new value
+// becomes the target, reached along a one-pole curve (`OnePoleSmoother`).
+//
+// Time constants depend on the parameter (`ParamKind`): gains can move fast,
+// filter coefficients need a little longer, and plugin parameters are only
+// updated once per buffer. Once the target is reached, the value snaps to it
+// and the smoother stops computing.
+
+use crate::audio::dsp_utils::OnePoleSmoother;
+
+/// Relative distance to the target under which a parameter is settled
+const SETTLE_TOLERANCE: f32 = 1e-5;
+
+/// Kinds of continuous parameters, each with its own smoothing time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// Master or track volume
+    Volume,
+    /// Stereo position
+    Pan,
+    /// Send amount to an aux bus
+    Send,
+    /// Filter cutoff frequency
+    FilterCutoff,
+    /// Filter resonance
+    FilterResonance,
+    /// Dry/wet mix of an effect
+    EffectMix,
+    /// Delay feedback
+    Feedback,
+    /// Plugin parameter (applied once per buffer)
+    PluginParam,
+}
+
+impl ParamKind {
+    /// Time to cover ~63% of a change (milliseconds)
+    pub const fn time_constant_ms(self) -> f32 {
+        match self {
+            ParamKind::Volume | ParamKind::Pan | ParamKind::Send => 10.0,
+            ParamKind::FilterCutoff | ParamKind::FilterResonance => 5.0,
+            ParamKind::EffectMix | ParamKind::Feedback => 10.0,
+            ParamKind::PluginParam => 20.0,
+        }
+    }
+}
+
+/// A continuous parameter gliding towards its target
+#[derive(Debug, Clone)]
+pub struct SmoothedParam {
+    smoother: OnePoleSmoother,
+    target: f32,
+}
+
+impl SmoothedParam {
+    /// Create a parameter at `value`, smoothed as `kind`
+    pub fn new(kind: ParamKind, value: f32, sample_rate: f32) -> Self {
+        Self::with_time_constant(value, kind.time_constant_ms(), sample_rate)
+    }
+
+    /// Create a parameter with an explicit time constant (milliseconds)
+    pub fn with_time_constant(value: f32, time_constant_ms: f32, sample_rate: f32) -> Self {
+        Self {
+            smoother: OnePoleSmoother::new(value, time_constant_ms, sample_rate),
+            target: value,
+        }
+    }
+
+    /// Set the value to glide to
+    #[inline]
+    pub fn set_target(&mut self, target: f32) {
+        self.target = target;
+    }
+
+    #[inline]
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// Current (smoothed) value
+    #[inline]
+    pub fn current(&self) -> f32 {
+        self.smoother.get()
+    }
+
+    /// Jump to `value` without smoothing (e.g. when a voice starts)
+    #[inline]
+    pub fn reset(&mut self, value: f32) {
+        self.target = value;
+        self.smoother.reset(value);
+    }
+
+    /// Overwrite the current value, keeping the target (state written back
+    /// by code that ran the smoothing itself, e.g. SIMD lanes)
+    #[inline]
+    pub fn set_current(&mut self, value: f32) {
+        self.smoother.reset(value);
+    }
+
+    /// Fraction of the remaining distance covered per sample
+    #[inline]
+    pub fn coefficient(&self) -> f32 {
+        self.smoother.coefficient()
+    }
+
+    /// Whether the current value has reached the target
+    #[inline]
+    pub fn is_settled(&self) -> bool {
+        let distance = (self.target - self.smoother.get()).abs();
+        distance <= SETTLE_TOLERANCE * self.target.abs().max(1.0)
+    }
+
+    /// Advance by one sample and return the new value
+    #[inline]
+    pub fn next_value(&mut self) -> f32 {
+        if self.is_settled() {
+            return self.settle();
+        }
+        let previous = self.smoother.get();
+        let value = self.smoother.process(self.target);
+        // In f32, the last steps can be too small to change the value
+        if value == previous || self.is_settled() {
+            return self.settle();
+        }
+        value
+    }
+
+    /// Advance by `samples` at once (block-rate parameters)
+    pub fn advance(&mut self, samples: usize) -> f32 {
+        if self.is_settled() {
+            return self.settle();
+        }
+        let remaining = (1.0 - self.coefficient()).powi(samples.min(i32::MAX as usize) as i32);
+        let value = self.target + (self.smoother.get() - self.target) * remaining;
+        self.smoother.reset(value);
+        if self.is_settled() {
+            return self.settle();
+        }
+        value
+    }
+
+    /// Fill `values` with the next smoothed values
+    pub fn fill(&mut self, values: &mut [f32]) {
+        if self.is_settled() {
+            values.fill(self.settle());
+            return;
+        }
+        for value in values {
+            *value = self.next_value();
+        }
+    }
+
+    /// Snap to the target
+    #[inline]
+    fn settle(&mut self) -> f32 {
+        self.smoother.reset(self.target);
+        self.target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    #[test]
+    fn test_glides_to_target_and_settles() {
+        let mut param = SmoothedParam::new(ParamKind::Volume, 0.0, SAMPLE_RATE);
+        param.set_target(1.0);
+
+        // One time constant: ~63% of the way
+        let samples = (ParamKind::Volume.time_constant_ms() * 0.001 * SAMPLE_RATE) as usize;
+        let mut value = 0.0;
+        for _ in 0..samples {
+            let next = param.next_value();
+            assert!(next >= value, "smoothing must be monotonic");
+            value = next;
+        }
+        assert!((value - 0.63).abs() < 0.02, "got {}", value);
+
+        for _ in 0..samples * 20 {
+            param.next_value();
+        }
+        assert!(param.is_settled());
+        assert_eq!(param.next_value(), 1.0);
+    }
+
+    #[test]
+    fn test_advance_matches_per_sample_smoothing() {
+        let mut per_sample = SmoothedParam::new(ParamKind::PluginParam, 100.0, SAMPLE_RATE);
+        let mut per_block = per_sample.clone();
+        per_sample.set_target(200.0);
+        per_block.set_target(200.0);
+
+        for _ in 0..512 {
+            per_sample.next_value();
+        }
+        let value = per_block.advance(512);
+        assert!((value - per_sample.current()).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_fill_and_reset() {
+        let mut param = SmoothedParam::new(ParamKind::FilterCutoff, 1000.0, SAMPLE_RATE);
+        let mut values = [0.0; 64];
+
+        // Settled: constant block
+        param.fill(&mut values);
+        assert!(values.iter().all(|&v| v == 1000.0));
+
+        param.set_target(2000.0);
+        param.fill(&mut values);
+        assert!(values[0] > 1000.0 && values[63] < 2000.0);
+        assert!(values.windows(2).all(|w| w[1] > w[0]));
+
+        param.reset(500.0);
+        assert_eq!(param.current(), 500.0);
+        assert_eq!(param.target(), 500.0);
+    }
+}
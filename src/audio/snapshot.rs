@@ -0,0 +1,262 @@
+// Engine snapshot - Lock-free view of the engine state (Audio → frontends)
+//
+// The audio callback publishes a compact snapshot at the end of every buffer:
+// playhead, transport state, active voices, CPU load of the last buffer,
+// output peaks and the gain reduction of the master compressor and limiter. Any number of frontends read it, so they show the position the
+// engine actually plays instead of advancing a shadow transport of their own.
+//
+// The fields are atomics guarded by a sequence counter (seqlock): the single
+// writer (the audio callback) never waits nor allocates, and readers retry
+// while a snapshot is being written, so they never see a torn one.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering, fence};
+
+/// Release time of the published peaks (seconds)
+///
+/// Frontends read the snapshot less often than the callback runs: peaks decay
+/// slowly enough for a short transient to be seen.
+pub const PEAK_RELEASE: f32 = 0.3;
+
+/// State of the engine at the end of the last audio buffer
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EngineSnapshot {
+    /// Playhead position (samples)
+    pub position_samples: u64,
+    /// Transport playing
+    pub playing: bool,
+    /// Samples left of the count-in before recording (0: not counting)
+    pub count_in_samples: u64,
+    /// Transport tempo (BPM)
+    pub tempo: f64,
+    /// Device sample rate (Hz)
+    pub sample_rate: f32,
+    /// Device output channels
+    pub output_channels: usize,
+    /// Voices currently sounding
+    pub active_voices: usize,
+    /// Time spent rendering the last buffer, in % of its duration
+    pub cpu_load: f32,
+    /// Output peaks (linear, with `PEAK_RELEASE`)
+    pub peak_left: f32,
+    pub peak_right: f32,
+    /// Gain reduction of the master compressor and limiter (dB, with `PEAK_RELEASE`)
+    pub compressor_reduction_db: f32,
+    pub limiter_reduction_db: f32,
+}
+
+impl EngineSnapshot {
+    /// Playhead position in seconds
+    pub fn position_seconds(&self) -> f64 {
+        if self.sample_rate > 0.0 {
+            self.position_samples as f64 / self.sample_rate as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Default)]
+struct SnapshotCells {
+    /// Odd while a snapshot is being written
+    sequence: AtomicU64,
+    position_samples: AtomicU64,
+    playing: AtomicBool,
+    count_in_samples: AtomicU64,
+    tempo: AtomicU64,
+    sample_rate: AtomicU32,
+    output_channels: AtomicUsize,
+    active_voices: AtomicUsize,
+    cpu_load: AtomicU32,
+    peak_left: AtomicU32,
+    peak_right: AtomicU32,
+    compressor_reduction_db: AtomicU32,
+    limiter_reduction_db: AtomicU32,
+}
+
+/// Shared engine snapshot (cheap to clone, Arc internally)
+#[derive(Clone, Default)]
+pub struct AtomicEngineSnapshot {
+    inner: Arc<SnapshotCells>,
+}
+
+impl AtomicEngineSnapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Publish a snapshot (RT-safe; the audio callback is the only writer)
+    pub fn publish(&self, snapshot: &EngineSnapshot) {
+        let cells = &self.inner;
+        let sequence = cells.sequence.load(Ordering::Relaxed);
+        cells
+            .sequence
+            .store(sequence.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        cells
+            .position_samples
+            .store(snapshot.position_samples, Ordering::Relaxed);
+        cells.playing.store(snapshot.playing, Ordering::Relaxed);
+        cells
+            .count_in_samples
+            .store(snapshot.count_in_samples, Ordering::Relaxed);
+        cells
+            .tempo
+            .store(snapshot.tempo.to_bits(), Ordering::Relaxed);
+        cells
+            .sample_rate
+            .store(snapshot.sample_rate.to_bits(), Ordering::Relaxed);
+        cells
+            .output_channels
+            .store(snapshot.output_channels, Ordering::Relaxed);
+        cells
+            .active_voices
+            .store(snapshot.active_voices, Ordering::Relaxed);
+        cells
+            .cpu_load
+            .store(snapshot.cpu_load.to_bits(), Ordering::Relaxed);
+        cells
+            .peak_left
+            .store(snapshot.peak_left.to_bits(), Ordering::Relaxed);
+        cells
+            .peak_right
+            .store(snapshot.peak_right.to_bits(), Ordering::Relaxed);
+        cells.compressor_reduction_db.store(
+            snapshot.compressor_reduction_db.to_bits(),
+            Ordering::Relaxed,
+        );
+        cells
+            .limiter_reduction_db
+            .store(snapshot.limiter_reduction_db.to_bits(), Ordering::Relaxed);
+
+        cells
+            .sequence
+            .store(sequence.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Read the last published snapshot (default until the first buffer)
+    pub fn read(&self) -> EngineSnapshot {
+        let cells = &self.inner;
+        loop {
+            let before = cells.sequence.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                // Being written: the callback is done within microseconds
+                std::hint::spin_loop();
+                continue;
+            }
+
+            let snapshot = EngineSnapshot {
+                position_samples: cells.position_samples.load(Ordering::Relaxed),
+                playing: cells.playing.load(Ordering::Relaxed),
+                count_in_samples: cells.count_in_samples.load(Ordering::Relaxed),
+                tempo: f64::from_bits(cells.tempo.load(Ordering::Relaxed)),
+                sample_rate: f32::from_bits(cells.sample_rate.load(Ordering::Relaxed)),
+                output_channels: cells.output_channels.load(Ordering::Relaxed),
+                active_voices: cells.active_voices.load(Ordering::Relaxed),
+                cpu_load: f32::from_bits(cells.cpu_load.load(Ordering::Relaxed)),
+                peak_left: f32::from_bits(cells.peak_left.load(Ordering::Relaxed)),
+                peak_right: f32::from_bits(cells.peak_right.load(Ordering::Relaxed)),
+                compressor_reduction_db: f32::from_bits(
+                    cells.compressor_reduction_db.load(Ordering::Relaxed),
+                ),
+                limiter_reduction_db: f32::from_bits(
+                    cells.limiter_reduction_db.load(Ordering::Relaxed),
+                ),
+            };
+
+            fence(Ordering::Acquire);
+            if cells.sequence.load(Ordering::Relaxed) == before {
+                return snapshot;
+            }
+        }
+    }
+}
+
+/// Peak of the last buffer combined with the decaying previous peak
+#[inline]
+pub fn hold_peak(previous: f32, buffer_peak: f32, frames: usize, sample_rate: f32) -> f32 {
+    let decay = (-(frames as f32) / (PEAK_RELEASE * sample_rate)).exp();
+    buffer_peak.max(previous * decay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_and_read() {
+        let shared = AtomicEngineSnapshot::new();
+        assert_eq!(shared.read(), EngineSnapshot::default());
+
+        let snapshot = EngineSnapshot {
+            position_samples: 96000,
+            playing: true,
+            count_in_samples: 0,
+            tempo: 128.0,
+            sample_rate: 48000.0,
+            output_channels: 4,
+            active_voices: 3,
+            cpu_load: 12.5,
+            peak_left: 0.5,
+            peak_right: 0.25,
+            compressor_reduction_db: 4.5,
+            limiter_reduction_db: 1.5,
+        };
+        shared.publish(&snapshot);
+
+        let read = shared.clone().read();
+        assert_eq!(read, snapshot);
+        assert_eq!(read.position_seconds(), 2.0);
+    }
+
+    #[test]
+    fn test_reader_never_sees_torn_snapshot() {
+        let shared = AtomicEngineSnapshot::new();
+        let writer = shared.clone();
+
+        // Every field of a published snapshot derives from the same counter
+        let handle = std::thread::spawn(move || {
+            for i in 1..=20_000u64 {
+                writer.publish(&EngineSnapshot {
+                    position_samples: i,
+                    playing: !i.is_multiple_of(2),
+                    count_in_samples: 0,
+                    tempo: i as f64,
+                    sample_rate: 48000.0,
+                    output_channels: 2,
+                    active_voices: i as usize,
+                    cpu_load: 0.0,
+                    peak_left: 0.0,
+                    peak_right: 0.0,
+                    compressor_reduction_db: 0.0,
+                    limiter_reduction_db: 0.0,
+                });
+            }
+        });
+
+        while !handle.is_finished() {
+            let read = shared.read();
+            assert_eq!(read.tempo, read.position_samples as f64);
+            assert_eq!(read.active_voices as u64, read.position_samples);
+            assert_eq!(read.playing, !read.position_samples.is_multiple_of(2));
+        }
+        handle.join().unwrap();
+        assert_eq!(shared.read().position_samples, 20_000);
+    }
+
+    #[test]
+    fn test_peak_hold_decays() {
+        let sample_rate = 48000.0;
+        let peak = hold_peak(0.0, 1.0, 512, sample_rate);
+        assert_eq!(peak, 1.0);
+
+        // Quieter buffers: the peak decays instead of dropping
+        let peak = hold_peak(peak, 0.1, 512, sample_rate);
+        assert!(peak > 0.9 && peak < 1.0);
+
+        // After the release time, about 1/e is left
+        let peak = hold_peak(1.0, 0.0, (PEAK_RELEASE * sample_rate) as usize, sample_rate);
+        assert!((peak - (-1.0f32).exp()).abs() < 0.01);
+    }
+}
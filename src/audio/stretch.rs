@@ -0,0 +1,280 @@
+// Stretch - Time-stretching and pitch-shifting of audio buffers
+//
+// WSOLA (waveform-similarity overlap-add): the output is built from Hann
+// windowed frames of the input, overlapping by half. Each frame is read near
+// the input position matching its output time, moved within a search range to
+// where it best continues the previous frame (highest cross-correlation), so
+// periodic sounds keep their phase instead of warbling. Pitch shifting
+// stretches by the pitch ratio, then resamples back to the wanted length.
+//
+// Rendering allocates and takes time: it runs when a setting changes (UI
+// thread, loaders), never in the audio callback.
+
+use serde::{Deserialize, Serialize};
+
+/// Analysis frame (samples, about 21 ms at 48 kHz)
+const FRAME: usize = 1024;
+
+/// Output hop (half a frame: the Hann windows sum to 1)
+const HOP: usize = FRAME / 2;
+
+/// Largest move of a frame from its nominal input position (samples)
+const SEARCH: isize = 256;
+
+/// Samples compared when searching a frame (every `CORRELATION_STEP`th)
+const CORRELATION_LENGTH: usize = HOP;
+const CORRELATION_STEP: usize = 4;
+
+/// Length ratios accepted by `StretchSettings`
+pub const MIN_STRETCH_RATIO: f64 = 0.25;
+pub const MAX_STRETCH_RATIO: f64 = 4.0;
+
+/// Largest pitch shift accepted by `StretchSettings` (semitones, up or down)
+pub const MAX_PITCH_SHIFT: f32 = 24.0;
+
+/// Pitch shift and length change of a buffer, independent of each other
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StretchSettings {
+    /// Pitch shift in semitones (the length is kept)
+    pub pitch: f32,
+    /// Length multiplier (2.0 plays twice as long, the pitch is kept)
+    pub ratio: f64,
+}
+
+impl Default for StretchSettings {
+    fn default() -> Self {
+        Self {
+            pitch: 0.0,
+            ratio: 1.0,
+        }
+    }
+}
+
+impl StretchSettings {
+    /// Settings stretching audio recorded at `source_bpm` to play at `project_bpm`
+    pub fn for_tempo(source_bpm: f64, project_bpm: f64) -> Result<Self, String> {
+        if source_bpm <= 0.0 || project_bpm <= 0.0 {
+            return Err(format!(
+                "Tempos must be positive (got {} and {} BPM)",
+                source_bpm, project_bpm
+            ));
+        }
+        let settings = Self {
+            pitch: 0.0,
+            ratio: source_bpm / project_bpm,
+        };
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Whether the settings leave the audio unchanged
+    pub fn is_identity(&self) -> bool {
+        self.pitch == 0.0 && self.ratio == 1.0
+    }
+
+    /// Check the ratio and pitch ranges
+    pub fn validate(&self) -> Result<(), String> {
+        if !(MIN_STRETCH_RATIO..=MAX_STRETCH_RATIO).contains(&self.ratio) {
+            return Err(format!(
+                "Stretch ratio must be between {} and {}, got {}",
+                MIN_STRETCH_RATIO, MAX_STRETCH_RATIO, self.ratio
+            ));
+        }
+        if !(-MAX_PITCH_SHIFT..=MAX_PITCH_SHIFT).contains(&self.pitch) {
+            return Err(format!(
+                "Pitch shift must be between -{} and +{} semitones, got {}",
+                MAX_PITCH_SHIFT, MAX_PITCH_SHIFT, self.pitch
+            ));
+        }
+        Ok(())
+    }
+
+    /// Render a buffer with the settings (`ratio` times as long)
+    pub fn render(&self, input: &[f32]) -> Vec<f32> {
+        if self.is_identity() {
+            return input.to_vec();
+        }
+        let output_len = (input.len() as f64 * self.ratio).round() as usize;
+        if self.pitch == 0.0 {
+            return time_stretch(input, self.ratio);
+        }
+        let pitch_ratio = 2.0_f64.powf(self.pitch as f64 / 12.0);
+        let stretched = time_stretch(input, self.ratio * pitch_ratio);
+        resample(&stretched, output_len)
+    }
+}
+
+/// Change the length of a buffer by `ratio`, keeping its pitch
+pub fn time_stretch(input: &[f32], ratio: f64) -> Vec<f32> {
+    let output_len = (input.len() as f64 * ratio).round() as usize;
+    let window: Vec<f32> = (0..FRAME)
+        .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / FRAME as f32).cos())
+        .collect();
+    let at = |position: isize| {
+        usize::try_from(position)
+            .ok()
+            .and_then(|position| input.get(position))
+            .copied()
+            .unwrap_or(0.0)
+    };
+
+    let mut output = vec![0.0; output_len];
+    // The first frame starts half a frame early: the output starts at full level
+    let mut out_start = -(HOP as isize);
+    let mut previous: Option<isize> = None;
+    while out_start < output_len as isize {
+        let nominal = (out_start as f64 / ratio).round() as isize;
+        let start = match previous {
+            None => nominal,
+            Some(previous) => {
+                // Best match for what the previous frame would have played next
+                let natural = previous + HOP as isize;
+                let similarity = |candidate: isize| -> f32 {
+                    let (product, energy) = (0..CORRELATION_LENGTH)
+                        .step_by(CORRELATION_STEP)
+                        .map(|i| (at(natural + i as isize), at(candidate + i as isize)))
+                        .fold((0.0, 0.0), |(product, energy), (a, b)| {
+                            (product + a * b, energy + b * b)
+                        });
+                    product / energy.sqrt().max(1e-9)
+                };
+                // Closest candidates first: a move needs a clearly better match
+                let mut best = (nominal, similarity(nominal));
+                for delta in 1..=SEARCH {
+                    for candidate in [nominal - delta, nominal + delta] {
+                        let score = similarity(candidate);
+                        if score > best.1 + 1e-3 * best.1.abs() {
+                            best = (candidate, score);
+                        }
+                    }
+                }
+                best.0
+            }
+        };
+        for (i, weight) in window.iter().enumerate() {
+            let out = out_start + i as isize;
+            if let Some(sample) = usize::try_from(out)
+                .ok()
+                .and_then(|out| output.get_mut(out))
+            {
+                *sample += weight * at(start + i as isize);
+            }
+        }
+        previous = Some(start);
+        out_start += HOP as isize;
+    }
+    output
+}
+
+/// Shift the pitch of a buffer by `semitones`, keeping its length
+pub fn pitch_shift(input: &[f32], semitones: f32) -> Vec<f32> {
+    StretchSettings {
+        pitch: semitones,
+        ratio: 1.0,
+    }
+    .render(input)
+}
+
+/// Linear interpolation of a buffer to another length
+fn resample(input: &[f32], output_len: usize) -> Vec<f32> {
+    if input.is_empty() || output_len == 0 {
+        return vec![0.0; output_len];
+    }
+    let step = input.len() as f64 / output_len as f64;
+    (0..output_len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let a = input[index.min(input.len() - 1)];
+            let b = input[(index + 1).min(input.len() - 1)];
+            a + (b - a) * fraction
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: f32 = 48000.0;
+
+    fn sine(frequency: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (std::f32::consts::TAU * frequency * i as f32 / RATE).sin() * 0.5)
+            .collect()
+    }
+
+    /// Frequency estimated from the zero crossings of the middle of a buffer
+    fn frequency(buffer: &[f32]) -> f32 {
+        let middle = &buffer[buffer.len() / 4..buffer.len() * 3 / 4];
+        let crossings = middle
+            .windows(2)
+            .filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0)
+            .count();
+        crossings as f32 * RATE / middle.len() as f32
+    }
+
+    #[test]
+    fn test_identity_keeps_the_audio() {
+        let input = sine(220.0, 4800);
+        assert_eq!(StretchSettings::default().render(&input), input);
+
+        // A stretch of 1 rebuilds the input from its overlapping frames
+        let output = time_stretch(&input, 1.0);
+        assert_eq!(output.len(), input.len());
+        let error = input
+            .iter()
+            .zip(&output)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max);
+        assert!(error < 1e-4, "max error {}", error);
+    }
+
+    #[test]
+    fn test_time_stretch_keeps_the_pitch() {
+        let input = sine(440.0, 24000);
+        for ratio in [0.5, 1.5, 2.0] {
+            let output = time_stretch(&input, ratio);
+            assert_eq!(output.len(), (24000.0 * ratio) as usize);
+            let measured = frequency(&output);
+            assert!(
+                (measured - 440.0).abs() < 10.0,
+                "{}: {} Hz",
+                ratio,
+                measured
+            );
+        }
+    }
+
+    #[test]
+    fn test_pitch_shift_keeps_the_length() {
+        let input = sine(440.0, 24000);
+        let output = pitch_shift(&input, 12.0);
+        assert_eq!(output.len(), input.len());
+        let measured = frequency(&output);
+        assert!((measured - 880.0).abs() < 20.0, "{} Hz", measured);
+
+        let settings = StretchSettings {
+            pitch: -12.0,
+            ratio: 2.0,
+        };
+        let output = settings.render(&input);
+        assert_eq!(output.len(), 48000);
+        let measured = frequency(&output);
+        assert!((measured - 220.0).abs() < 10.0, "{} Hz", measured);
+    }
+
+    #[test]
+    fn test_settings_validation() {
+        let settings = StretchSettings::for_tempo(90.0, 120.0).unwrap();
+        assert_eq!(settings.ratio, 0.75);
+        assert!(StretchSettings::for_tempo(30.0, 180.0).is_err());
+        assert!(StretchSettings::for_tempo(0.0, 120.0).is_err());
+        let settings = StretchSettings {
+            pitch: 30.0,
+            ratio: 1.0,
+        };
+        assert!(settings.validate().is_err());
+    }
+}
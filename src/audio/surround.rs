@@ -0,0 +1,291 @@
+// Surround - Speaker layouts beyond stereo and the surround panner
+//
+// The project speaker layout (stereo, quad or 5.1) sets how many channels the
+// mix uses. A track with a surround panner places its signal between the
+// speakers (left/right, front/rear, plus the center and LFE amounts in 5.1);
+// other tracks keep their stereo pan and play on the front pair.
+//
+// The front pair is the master bus (master volume, master effects, plugins).
+// The other channels (center, LFE, rear) are mixed on a surround bus written
+// after the master bus, with the master volume. A layout the device cannot
+// play folds down to stereo: surround panners then only pan left/right.
+//
+// Channel order follows the WAV/SMPTE order: quad is FL FR RL RR, 5.1 is
+// FL FR C LFE SL SR. Direct outputs (see `audio::outputs`) start after the
+// layout channels.
+//
+// Real-time constraints:
+// - Bus buffers are allocated in `SurroundBus::new()` (outside the callback)
+// - Pan gains are computed once per chunk
+
+use crate::audio::dsp_utils::soft_clip;
+use crate::audio::format_conversion::OutputSample;
+use std::f32::consts::FRAC_PI_4;
+
+/// Channels of the largest layout (5.1)
+pub const MAX_SURROUND_CHANNELS: usize = 6;
+
+/// Gain of a signal on each channel of a layout (unused channels at 0.0)
+pub type SurroundGains = [f32; MAX_SURROUND_CHANNELS];
+
+/// Speaker layout of the mix
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeakerLayout {
+    #[default]
+    Stereo,
+    /// FL FR RL RR
+    Quad,
+    /// FL FR C LFE SL SR
+    Surround51,
+}
+
+impl SpeakerLayout {
+    pub const ALL: [SpeakerLayout; 3] = [
+        SpeakerLayout::Stereo,
+        SpeakerLayout::Quad,
+        SpeakerLayout::Surround51,
+    ];
+
+    pub fn is_stereo(&self) -> bool {
+        *self == SpeakerLayout::Stereo
+    }
+
+    /// Number of channels
+    pub fn channels(&self) -> usize {
+        match self {
+            SpeakerLayout::Stereo => 2,
+            SpeakerLayout::Quad => 4,
+            SpeakerLayout::Surround51 => 6,
+        }
+    }
+
+    /// Layout using exactly `channels` channels
+    pub fn for_channels(channels: usize) -> Option<SpeakerLayout> {
+        Self::ALL
+            .into_iter()
+            .find(|layout| layout.channels() == channels)
+    }
+
+    /// Layout played on a device with `channels` output channels (stereo
+    /// when the device has too few)
+    pub fn on_device(&self, channels: usize) -> SpeakerLayout {
+        if self.channels() <= channels {
+            *self
+        } else {
+            SpeakerLayout::Stereo
+        }
+    }
+
+    /// Layout name for UI display
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpeakerLayout::Stereo => "Stereo",
+            SpeakerLayout::Quad => "Quad",
+            SpeakerLayout::Surround51 => "5.1",
+        }
+    }
+}
+
+/// Position of a track between the speakers
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SurroundPan {
+    /// Left (-1.0) to right (1.0)
+    pub x: f32,
+    /// Front (-1.0) to rear (1.0)
+    pub y: f32,
+    /// Share of the front signal sent to the center speaker (0.0 to 1.0, 5.1 only)
+    pub center: f32,
+    /// LFE send, linear gain (0.0 to 1.0, 5.1 only)
+    pub lfe: f32,
+}
+
+impl Default for SurroundPan {
+    /// Front center, between the front speakers
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: -1.0,
+            center: 0.0,
+            lfe: 0.0,
+        }
+    }
+}
+
+impl SurroundPan {
+    /// Constant power gains of the panner on each channel of `layout`
+    pub fn gains(&self, layout: SpeakerLayout) -> SurroundGains {
+        let angle = |value: f32| (value.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+        let (left, right) = (angle(self.x).cos(), angle(self.x).sin());
+        let (front, rear) = (angle(self.y).cos(), angle(self.y).sin());
+        let mut gains = [0.0; MAX_SURROUND_CHANNELS];
+        match layout {
+            SpeakerLayout::Stereo => {
+                gains[0] = left;
+                gains[1] = right;
+            }
+            SpeakerLayout::Quad => {
+                gains[0] = left * front;
+                gains[1] = right * front;
+                gains[2] = left * rear;
+                gains[3] = right * rear;
+            }
+            SpeakerLayout::Surround51 => {
+                let center = self.center.clamp(0.0, 1.0);
+                let front_pair = front * (1.0 - center).sqrt();
+                gains[0] = left * front_pair;
+                gains[1] = right * front_pair;
+                gains[2] = front * center.sqrt();
+                gains[3] = self.lfe.clamp(0.0, 1.0);
+                gains[4] = left * rear;
+                gains[5] = right * rear;
+            }
+        }
+        gains
+    }
+}
+
+/// Channels past the front pair of one block (audio thread side)
+pub struct SurroundBus {
+    layout: SpeakerLayout,
+    /// Channels 3 to 6
+    channels: Vec<Vec<f32>>,
+    /// Frames of the current block held by the buffers
+    frames: usize,
+    /// Whether a signal was added in the current block
+    active: bool,
+}
+
+impl SurroundBus {
+    /// Allocate the channel buffers
+    pub fn new(max_frames: usize) -> Self {
+        Self {
+            layout: SpeakerLayout::Stereo,
+            channels: (2..MAX_SURROUND_CHANNELS)
+                .map(|_| vec![0.0; max_frames])
+                .collect(),
+            frames: 0,
+            active: false,
+        }
+    }
+
+    pub fn layout(&self) -> SpeakerLayout {
+        self.layout
+    }
+
+    pub fn set_layout(&mut self, layout: SpeakerLayout) {
+        self.layout = layout;
+    }
+
+    /// Start a block of `frames` frames (every channel is silent)
+    pub fn begin(&mut self, frames: usize) {
+        self.frames = frames.min(self.channels.first().map_or(0, Vec::len));
+        self.active = false;
+    }
+
+    /// Whether a signal was added in the current block
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Add a mono signal from frame `offset` of the block to the channels past
+    /// the front pair (the caller mixes the front pair)
+    #[inline]
+    pub fn add(&mut self, offset: usize, input: &[f32], gains: &SurroundGains) {
+        let channels = self.layout.channels();
+        if channels <= 2 || offset >= self.frames {
+            return;
+        }
+        let frames = self.frames;
+        if !self.active {
+            self.active = true;
+            for channel in &mut self.channels {
+                channel[..frames].fill(0.0);
+            }
+        }
+        let count = input.len().min(frames - offset);
+        for (channel, gain) in self.channels.iter_mut().zip(&gains[2..channels]) {
+            if *gain == 0.0 {
+                continue;
+            }
+            for i in 0..count {
+                channel[offset + i] += input[i] * gain;
+            }
+        }
+    }
+
+    /// Write the channels past the front pair of a frame (master volume and
+    /// soft clipping applied)
+    #[inline]
+    pub fn write_frame<T: OutputSample>(&self, index: usize, volume: f32, frame: &mut [T]) {
+        if index >= self.frames {
+            return;
+        }
+        let channels = self.layout.channels().min(frame.len());
+        for (channel, sample) in frame.iter_mut().enumerate().take(channels).skip(2) {
+            *sample = T::from_f32(soft_clip(self.channels[channel - 2][index] * volume));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn power(gains: &SurroundGains) -> f32 {
+        gains.iter().map(|gain| gain * gain).sum()
+    }
+
+    #[test]
+    fn test_panner_keeps_power() {
+        let pan = SurroundPan {
+            x: 1.0,
+            y: 1.0,
+            ..SurroundPan::default()
+        };
+        let gains = pan.gains(SpeakerLayout::Quad);
+        assert!((gains[3] - 1.0).abs() < 1e-6);
+        assert!(gains[..3].iter().all(|gain| gain.abs() < 1e-6));
+
+        // Front center with some center share: same power on every layout
+        let pan = SurroundPan {
+            center: 0.5,
+            ..SurroundPan::default()
+        };
+        for layout in SpeakerLayout::ALL {
+            assert!((power(&pan.gains(layout)) - 1.0).abs() < 1e-5);
+        }
+        let gains = pan.gains(SpeakerLayout::Surround51);
+        assert!((gains[2] - 0.5f32.sqrt()).abs() < 1e-6);
+        assert_eq!(gains[3], 0.0);
+
+        assert_eq!(
+            SpeakerLayout::for_channels(6),
+            Some(SpeakerLayout::Surround51)
+        );
+        assert_eq!(SpeakerLayout::for_channels(3), None);
+        assert_eq!(SpeakerLayout::Quad.on_device(2), SpeakerLayout::Stereo);
+    }
+
+    #[test]
+    fn test_bus_writes_channels_past_front_pair() {
+        let mut bus = SurroundBus::new(4);
+        bus.set_layout(SpeakerLayout::Quad);
+        bus.begin(4);
+        assert!(!bus.is_active());
+        let gains = SurroundPan {
+            y: 1.0,
+            ..SurroundPan::default()
+        }
+        .gains(SpeakerLayout::Quad);
+        bus.add(1, &[0.5; 8], &gains);
+        assert!(bus.is_active());
+
+        let mut frame = [0.25f32; 6];
+        bus.write_frame(2, 2.0, &mut frame);
+        assert_eq!(frame[..2], [0.25, 0.25]);
+        let expected = soft_clip(0.5 * gains[2] * 2.0);
+        assert!((frame[2] - expected).abs() < 1e-6 && (frame[3] - expected).abs() < 1e-6);
+        assert_eq!(frame[4..], [0.25, 0.25]);
+    }
+}
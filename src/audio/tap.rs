@@ -0,0 +1,167 @@
+// Audio taps - Lock-free copy of a signal for analysis (Audio → frontends)
+//
+// A tap keeps the most recent samples of a mono signal in a ring of atomics.
+// The audio callback is the only writer and never waits nor allocates; any
+// number of readers (tuner, analyzers) copy the latest window off the audio
+// thread. A reader racing the callback may get a few samples of the next
+// buffer at the oldest end of its window, which analyses tolerate.
+//
+// The callback only feeds a tap while a reader listens (see `AudioTap::listen`),
+// so an unused tap costs one atomic load per buffer.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+/// Samples kept by a tap (about 170 ms at 48 kHz)
+pub const TAP_CAPACITY: usize = 8192;
+
+struct TapCells {
+    /// Ring of f32 bits
+    samples: Box<[AtomicU32]>,
+    /// Samples written since the tap was created
+    written: AtomicU64,
+    /// Readers listening (the callback feeds the tap while > 0)
+    listeners: AtomicUsize,
+}
+
+/// Shared audio tap (cheap to clone, Arc internally)
+#[derive(Clone)]
+pub struct AudioTap {
+    inner: Arc<TapCells>,
+}
+
+impl Default for AudioTap {
+    fn default() -> Self {
+        Self::new(TAP_CAPACITY)
+    }
+}
+
+impl AudioTap {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Arc::new(TapCells {
+                samples: (0..capacity.max(1)).map(|_| AtomicU32::new(0)).collect(),
+                written: AtomicU64::new(0),
+                listeners: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.inner.samples.len()
+    }
+
+    /// Whether a reader listens (checked once per buffer by the callback)
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.inner.listeners.load(Ordering::Relaxed) > 0
+    }
+
+    /// Append a sample (RT-safe; the audio callback is the only writer)
+    #[inline]
+    pub fn push(&self, sample: f32) {
+        let cells = &self.inner;
+        let written = cells.written.load(Ordering::Relaxed);
+        let index = (written % cells.samples.len() as u64) as usize;
+        cells.samples[index].store(sample.to_bits(), Ordering::Relaxed);
+        cells.written.store(written + 1, Ordering::Release);
+    }
+
+    /// Start listening: the callback feeds the tap until the reader is dropped
+    pub fn listen(&self) -> TapReader {
+        self.inner.listeners.fetch_add(1, Ordering::Relaxed);
+        TapReader { tap: self.clone() }
+    }
+
+    /// Whether both handles share the same tap
+    pub fn same_tap(&self, other: &AudioTap) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+/// Listening handle on a tap
+pub struct TapReader {
+    tap: AudioTap,
+}
+
+impl TapReader {
+    pub fn tap(&self) -> &AudioTap {
+        &self.tap
+    }
+
+    /// Samples written since the tap was created (tells whether new audio arrived)
+    pub fn written(&self) -> u64 {
+        self.tap.inner.written.load(Ordering::Acquire)
+    }
+
+    /// Copy the most recent samples into `out`, oldest first
+    ///
+    /// Returns the number of samples copied: less than `out.len()` until the
+    /// tap has received enough audio, never more than its capacity.
+    pub fn read_latest(&self, out: &mut [f32]) -> usize {
+        let cells = &self.tap.inner;
+        let capacity = cells.samples.len() as u64;
+        let written = cells.written.load(Ordering::Acquire);
+        let count = (out.len() as u64).min(written).min(capacity);
+
+        let start = written - count;
+        for (offset, sample) in out[..count as usize].iter_mut().enumerate() {
+            let index = ((start + offset as u64) % capacity) as usize;
+            *sample = f32::from_bits(cells.samples[index].load(Ordering::Relaxed));
+        }
+        count as usize
+    }
+}
+
+impl Drop for TapReader {
+    fn drop(&mut self) {
+        self.tap.inner.listeners.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_active_while_listening() {
+        let tap = AudioTap::new(16);
+        assert!(!tap.is_active());
+
+        let reader = tap.listen();
+        let other = tap.clone().listen();
+        assert!(tap.is_active());
+        drop(reader);
+        assert!(tap.is_active());
+        drop(other);
+        assert!(!tap.is_active());
+    }
+
+    #[test]
+    fn test_read_latest_wraps_around() {
+        let tap = AudioTap::new(8);
+        let reader = tap.listen();
+
+        let mut out = [0.0; 4];
+        assert_eq!(reader.read_latest(&mut out), 0);
+
+        for i in 0..3 {
+            tap.push(i as f32);
+        }
+        assert_eq!(reader.read_latest(&mut out), 3);
+        assert_eq!(&out[..3], &[0.0, 1.0, 2.0]);
+
+        for i in 3..20 {
+            tap.push(i as f32);
+        }
+        assert_eq!(reader.written(), 20);
+        assert_eq!(reader.read_latest(&mut out), 4);
+        assert_eq!(out, [16.0, 17.0, 18.0, 19.0]);
+
+        // Never more than the capacity
+        let mut large = [0.0; 32];
+        assert_eq!(reader.read_latest(&mut large), 8);
+        assert_eq!(large[0], 12.0);
+        assert_eq!(large[7], 19.0);
+    }
+}
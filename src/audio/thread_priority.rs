@@ -0,0 +1,279 @@
+// Thread priority - Real-time scheduling for the audio and MIDI threads
+//
+// The audio callback and MIDI input threads belong to their backends (cpal,
+// midir), so they are promoted from the inside, on their first callback:
+// - Linux and other Unix systems: SCHED_FIFO (needs the rtprio limit, e.g. the
+//   `audio` group, or CAP_SYS_NICE; denied otherwise)
+// - Windows: THREAD_PRIORITY_TIME_CRITICAL / THREAD_PRIORITY_HIGHEST
+// - macOS: CoreAudio and CoreMIDI already run their threads with a real-time
+//   policy, which must not be replaced, so nothing is changed
+//
+// The outcome of each request is kept so the UI can show it, and a denied
+// request for the audio thread is reported by the engine watchdog. The
+// preference is read when a thread is promoted: changing it applies from the
+// next engine start.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU8, Ordering};
+
+/// Threads that ask for an elevated priority
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadKind {
+    /// Audio callback thread (real-time)
+    Audio,
+    /// MIDI input callback thread (elevated, below audio)
+    Midi,
+}
+
+impl ThreadKind {
+    fn index(self) -> usize {
+        match self {
+            ThreadKind::Audio => 0,
+            ThreadKind::Midi => 1,
+        }
+    }
+}
+
+/// Outcome of a priority request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriorityStatus {
+    /// The thread has not asked yet (no callback so far)
+    NotRequested,
+    /// Disabled in the preferences
+    Disabled,
+    /// The thread runs with an elevated priority
+    Elevated,
+    /// The OS already schedules this thread in real time (macOS)
+    ManagedBySystem,
+    /// The OS refused the request (OS error code)
+    Denied(i32),
+    /// No priority API on this platform
+    Unsupported,
+}
+
+impl PriorityStatus {
+    fn to_u8(self) -> u8 {
+        match self {
+            PriorityStatus::NotRequested => 0,
+            PriorityStatus::Disabled => 1,
+            PriorityStatus::Elevated => 2,
+            PriorityStatus::ManagedBySystem => 3,
+            PriorityStatus::Denied(_) => 4,
+            PriorityStatus::Unsupported => 5,
+        }
+    }
+
+    fn from_parts(value: u8, error_code: i32) -> Self {
+        match value {
+            1 => PriorityStatus::Disabled,
+            2 => PriorityStatus::Elevated,
+            3 => PriorityStatus::ManagedBySystem,
+            4 => PriorityStatus::Denied(error_code),
+            5 => PriorityStatus::Unsupported,
+            _ => PriorityStatus::NotRequested,
+        }
+    }
+}
+
+impl fmt::Display for PriorityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriorityStatus::NotRequested => write!(f, "not requested yet"),
+            PriorityStatus::Disabled => write!(f, "disabled"),
+            PriorityStatus::Elevated => write!(f, "real-time"),
+            PriorityStatus::ManagedBySystem => write!(f, "real-time (managed by the OS)"),
+            PriorityStatus::Denied(code) => {
+                write!(f, "denied ({})", std::io::Error::from_raw_os_error(*code))?;
+                if platform::is_permission_error(*code) {
+                    write!(f, " - {}", platform::PERMISSION_HINT)?;
+                }
+                Ok(())
+            }
+            PriorityStatus::Unsupported => write!(f, "not supported on this platform"),
+        }
+    }
+}
+
+/// Preference: ask for real-time priority (default: enabled)
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Last status of each `ThreadKind`
+static STATUS: [AtomicU8; 2] = [const { AtomicU8::new(0) }; 2];
+static ERROR_CODE: [AtomicI32; 2] = [const { AtomicI32::new(0) }; 2];
+
+/// Enable or disable real-time priority requests (applies from the next engine start)
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Status of the last request made by a thread of this kind
+pub fn status(kind: ThreadKind) -> PriorityStatus {
+    let index = kind.index();
+    PriorityStatus::from_parts(
+        STATUS[index].load(Ordering::Acquire),
+        ERROR_CODE[index].load(Ordering::Relaxed),
+    )
+}
+
+/// Elevate the priority of the calling thread (RT-safe: no allocation, one syscall)
+///
+/// Call it once, from the first callback of the thread.
+pub fn promote_current_thread(kind: ThreadKind) -> PriorityStatus {
+    let status = if is_enabled() {
+        platform::promote(kind)
+    } else {
+        PriorityStatus::Disabled
+    };
+
+    let index = kind.index();
+    if let PriorityStatus::Denied(code) = status {
+        ERROR_CODE[index].store(code, Ordering::Relaxed);
+    }
+    STATUS[index].store(status.to_u8(), Ordering::Release);
+    status
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use super::{PriorityStatus, ThreadKind};
+
+    /// SCHED_FIFO priorities (1-99 on Linux): below the kernel IRQ threads (50),
+    /// above ordinary real-time clients
+    const AUDIO_PRIORITY: i32 = 70;
+    const MIDI_PRIORITY: i32 = 60;
+
+    pub const PERMISSION_HINT: &str =
+        "add your user to the `audio` group or raise the rtprio limit (/etc/security/limits.conf)";
+
+    pub fn is_permission_error(code: i32) -> bool {
+        code == libc::EPERM
+    }
+
+    pub fn promote(kind: ThreadKind) -> PriorityStatus {
+        let priority = match kind {
+            ThreadKind::Audio => AUDIO_PRIORITY,
+            ThreadKind::Midi => MIDI_PRIORITY,
+        };
+
+        // SAFETY: plain libc calls on the current thread, `param` is a valid
+        // zero-initialised sched_param
+        let result = unsafe {
+            let min = libc::sched_get_priority_min(libc::SCHED_FIFO);
+            let max = libc::sched_get_priority_max(libc::SCHED_FIFO);
+            let mut param: libc::sched_param = std::mem::zeroed();
+            param.sched_priority = priority.clamp(min, max);
+            libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param)
+        };
+
+        if result == 0 {
+            PriorityStatus::Elevated
+        } else {
+            PriorityStatus::Denied(result)
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{PriorityStatus, ThreadKind};
+
+    pub const PERMISSION_HINT: &str = "";
+
+    pub fn is_permission_error(_code: i32) -> bool {
+        false
+    }
+
+    pub fn promote(_kind: ThreadKind) -> PriorityStatus {
+        PriorityStatus::ManagedBySystem
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{PriorityStatus, ThreadKind};
+    use std::ffi::c_void;
+
+    const THREAD_PRIORITY_HIGHEST: i32 = 2;
+    const THREAD_PRIORITY_TIME_CRITICAL: i32 = 15;
+    const ERROR_ACCESS_DENIED: i32 = 5;
+
+    pub const PERMISSION_HINT: &str = "the process is not allowed to raise its thread priorities";
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetCurrentThread() -> *mut c_void;
+        fn SetThreadPriority(thread: *mut c_void, priority: i32) -> i32;
+    }
+
+    pub fn is_permission_error(code: i32) -> bool {
+        code == ERROR_ACCESS_DENIED
+    }
+
+    pub fn promote(kind: ThreadKind) -> PriorityStatus {
+        let priority = match kind {
+            ThreadKind::Audio => THREAD_PRIORITY_TIME_CRITICAL,
+            ThreadKind::Midi => THREAD_PRIORITY_HIGHEST,
+        };
+
+        // SAFETY: GetCurrentThread returns a pseudo handle valid for the call
+        let result = unsafe { SetThreadPriority(GetCurrentThread(), priority) };
+        if result != 0 {
+            PriorityStatus::Elevated
+        } else {
+            let code = std::io::Error::last_os_error().raw_os_error().unwrap_or(0);
+            PriorityStatus::Denied(code)
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use super::{PriorityStatus, ThreadKind};
+
+    pub const PERMISSION_HINT: &str = "";
+
+    pub fn is_permission_error(_code: i32) -> bool {
+        false
+    }
+
+    pub fn promote(_kind: ThreadKind) -> PriorityStatus {
+        PriorityStatus::Unsupported
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_round_trip() {
+        for status in [
+            PriorityStatus::NotRequested,
+            PriorityStatus::Disabled,
+            PriorityStatus::Elevated,
+            PriorityStatus::ManagedBySystem,
+            PriorityStatus::Denied(1),
+            PriorityStatus::Unsupported,
+        ] {
+            let code = match status {
+                PriorityStatus::Denied(code) => code,
+                _ => 0,
+            };
+            assert_eq!(PriorityStatus::from_parts(status.to_u8(), code), status);
+        }
+    }
+
+    #[test]
+    fn test_promotion_is_recorded() {
+        // Runs on a scratch thread: the test thread keeps its priority
+        let status = std::thread::spawn(|| promote_current_thread(ThreadKind::Midi))
+            .join()
+            .unwrap();
+        assert_ne!(status, PriorityStatus::NotRequested);
+        assert_eq!(super::status(ThreadKind::Midi), status);
+    }
+}
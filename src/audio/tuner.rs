@@ -0,0 +1,306 @@
+// Tuner - Pitch detection on an audio tap
+//
+// The tuner copies the latest window of a tap (see `audio::tap`) off the audio
+// thread and estimates its fundamental with the YIN algorithm (de Cheveigné &
+// Kawahara, 2002): the cumulative mean normalized difference function is
+// searched for its first dip below a threshold, refined by parabolic
+// interpolation. The frequency is then shown as the nearest note and its
+// deviation in cents.
+
+use crate::audio::tap::{AudioTap, TapReader};
+use serde::Serialize;
+use std::ops::RangeInclusive;
+
+/// Samples analysed per estimate (85 ms at 48 kHz: two periods of a low B)
+pub const TUNER_WINDOW: usize = 4096;
+
+/// New samples needed before the next estimate
+const TUNER_HOP: u64 = 2048;
+
+/// Detection range (Hz): 5-string bass low B to the top of a piano's 7th octave
+pub const MIN_FREQUENCY: f32 = 30.0;
+pub const MAX_FREQUENCY: f32 = 4200.0;
+
+/// Default reference pitch of A4 (Hz)
+pub const DEFAULT_REFERENCE: f32 = 440.0;
+
+/// Reference pitches offered for A4 (Hz)
+pub const REFERENCE_RANGE: RangeInclusive<f32> = 400.0..=480.0;
+
+/// Deviation still shown as in tune (cents)
+pub const IN_TUNE_CENTS: f32 = 5.0;
+
+/// Threshold of the normalized difference (lower = stricter periodicity)
+const YIN_THRESHOLD: f32 = 0.15;
+
+/// RMS below which the signal is considered silent
+const SILENCE_RMS: f32 = 1e-3;
+
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Estimate the fundamental frequency of a signal (None if unpitched or silent)
+pub fn detect_pitch(samples: &[f32], sample_rate: f32) -> Option<f32> {
+    if sample_rate <= 0.0 {
+        return None;
+    }
+    let tau_min = ((sample_rate / MAX_FREQUENCY) as usize).max(2);
+    let tau_max = ((sample_rate / MIN_FREQUENCY).ceil() as usize).min(samples.len() / 2);
+    if tau_max <= tau_min + 1 {
+        return None;
+    }
+    let width = samples.len() - tau_max;
+
+    // Difference function, normalized by its cumulative mean
+    let mut normalized = vec![1.0f32; tau_max + 1];
+    let mut running_sum = 0.0f32;
+    for (tau, value) in normalized.iter_mut().enumerate().skip(1) {
+        let difference: f32 = samples[..width]
+            .iter()
+            .zip(&samples[tau..tau + width])
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum();
+        running_sum += difference;
+        if running_sum > 0.0 {
+            *value = difference * tau as f32 / running_sum;
+        }
+    }
+
+    // First dip below the threshold, followed down to its minimum
+    let mut tau = (tau_min..tau_max).find(|&tau| normalized[tau] < YIN_THRESHOLD)?;
+    while tau + 1 < tau_max && normalized[tau + 1] < normalized[tau] {
+        tau += 1;
+    }
+
+    // Parabolic interpolation around the minimum
+    let (previous, current, next) = (normalized[tau - 1], normalized[tau], normalized[tau + 1]);
+    let curvature = previous + next - 2.0 * current;
+    let shift = if curvature.abs() > f32::EPSILON {
+        0.5 * (previous - next) / curvature
+    } else {
+        0.0
+    };
+    Some(sample_rate / (tau as f32 + shift))
+}
+
+/// Detected pitch as the nearest note
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TunerReading {
+    /// Detected frequency (Hz)
+    pub frequency: f32,
+    /// Nearest MIDI note
+    pub note: u8,
+    /// Deviation from the nearest note (-50 to +50 cents)
+    pub cents: f32,
+}
+
+impl TunerReading {
+    /// Nearest note for a frequency, with A4 tuned to `reference` Hz
+    pub fn from_frequency(frequency: f32, reference: f32) -> Option<Self> {
+        if frequency <= 0.0 || reference <= 0.0 {
+            return None;
+        }
+        let semitones = 69.0 + 12.0 * (frequency / reference).log2();
+        let nearest = semitones.round();
+        if !(0.0..=127.0).contains(&nearest) {
+            return None;
+        }
+        Some(Self {
+            frequency,
+            note: nearest as u8,
+            cents: (semitones - nearest) * 100.0,
+        })
+    }
+
+    /// Note name with its octave (e.g. "A4")
+    pub fn note_name(&self) -> String {
+        let octave = (self.note / 12) as i32 - 1;
+        format!("{}{}", NOTE_NAMES[(self.note % 12) as usize], octave)
+    }
+
+    pub fn in_tune(&self) -> bool {
+        self.cents.abs() <= IN_TUNE_CENTS
+    }
+}
+
+/// Tuner listening to an audio tap
+pub struct Tuner {
+    reader: Option<TapReader>,
+    window: Vec<f32>,
+    /// Reference pitch of A4 (Hz)
+    pub reference: f32,
+    /// Tap position of the last estimate
+    analysed_at: u64,
+    reading: Option<TunerReading>,
+}
+
+impl Default for Tuner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tuner {
+    pub fn new() -> Self {
+        Self {
+            reader: None,
+            window: vec![0.0; TUNER_WINDOW],
+            reference: DEFAULT_REFERENCE,
+            analysed_at: 0,
+            reading: None,
+        }
+    }
+
+    /// Listen to a tap (the previous one is released)
+    pub fn attach(&mut self, tap: &AudioTap) {
+        if self
+            .reader
+            .as_ref()
+            .is_some_and(|reader| reader.tap().same_tap(tap))
+        {
+            return;
+        }
+        self.reader = Some(tap.listen());
+        self.analysed_at = 0;
+        self.reading = None;
+    }
+
+    /// Stop listening (the tap is no longer fed for the tuner)
+    pub fn detach(&mut self) {
+        self.reader = None;
+        self.reading = None;
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    /// Analyse the audio received since the last estimate
+    ///
+    /// Call it regularly (UI frame, timer): estimates are made once enough new
+    /// samples arrived, the last reading is returned in between.
+    pub fn update(&mut self, sample_rate: f32) -> Option<TunerReading> {
+        let reader = self.reader.as_ref()?;
+        let written = reader.written();
+        if written < self.analysed_at + TUNER_HOP {
+            return self.reading;
+        }
+        self.analysed_at = written;
+
+        let count = reader.read_latest(&mut self.window);
+        let samples = &self.window[..count];
+        self.reading = if count < TUNER_WINDOW || rms(samples) < SILENCE_RMS {
+            None
+        } else {
+            detect_pitch(samples, sample_rate)
+                .and_then(|frequency| TunerReading::from_frequency(frequency, self.reference))
+        };
+        self.reading
+    }
+
+    /// Last reading (None while silent or unpitched)
+    pub fn reading(&self) -> Option<TunerReading> {
+        self.reading
+    }
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Sawtooth-like tone (fundamental + harmonics) like a plucked string
+    fn tone(frequency: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE;
+                (1..=4)
+                    .map(|h| (TAU * frequency * h as f32 * t).sin() * 0.5 / h as f32)
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_pitch() {
+        for frequency in [41.2, 82.41, 196.0, 440.0, 1318.5] {
+            let detected = detect_pitch(&tone(frequency, TUNER_WINDOW), SAMPLE_RATE).unwrap();
+            let cents = 1200.0 * (detected / frequency).log2();
+            assert!(
+                cents.abs() < 2.0,
+                "{} Hz detected as {} Hz",
+                frequency,
+                detected
+            );
+        }
+    }
+
+    #[test]
+    fn test_noise_is_unpitched() {
+        // Deterministic white noise (xorshift)
+        let mut state = 0x2545_f491u32;
+        let noise: Vec<f32> = (0..TUNER_WINDOW)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as f32 / u32::MAX as f32 - 0.5
+            })
+            .collect();
+        assert_eq!(detect_pitch(&noise, SAMPLE_RATE), None);
+    }
+
+    #[test]
+    fn test_reading_from_frequency() {
+        let reading = TunerReading::from_frequency(440.0, DEFAULT_REFERENCE).unwrap();
+        assert_eq!(reading.note, 69);
+        assert_eq!(reading.note_name(), "A4");
+        assert!(reading.cents.abs() < 0.01 && reading.in_tune());
+
+        // 445 Hz is about 20 cents sharp
+        let reading = TunerReading::from_frequency(445.0, DEFAULT_REFERENCE).unwrap();
+        assert_eq!(reading.note_name(), "A4");
+        assert!((reading.cents - 19.56).abs() < 0.1 && !reading.in_tune());
+
+        // Low E a bit flat, with A4 at 442 Hz
+        let reading = TunerReading::from_frequency(82.41, 442.0).unwrap();
+        assert_eq!(reading.note_name(), "E2");
+        assert!(reading.cents < -7.0 && reading.cents > -8.5);
+    }
+
+    #[test]
+    fn test_tuner_reads_tap() {
+        let tap = AudioTap::default();
+        let mut tuner = Tuner::new();
+
+        // Not fed until the tuner listens
+        assert!(!tap.is_active());
+        tuner.attach(&tap);
+        assert!(tap.is_active());
+
+        // Silence gives no reading
+        for _ in 0..TUNER_WINDOW {
+            tap.push(0.0);
+        }
+        assert_eq!(tuner.update(SAMPLE_RATE), None);
+
+        for sample in tone(196.0, TUNER_WINDOW) {
+            tap.push(sample);
+        }
+        let reading = tuner.update(SAMPLE_RATE).unwrap();
+        assert_eq!(reading.note_name(), "G3");
+
+        tuner.detach();
+        assert!(!tap.is_active());
+    }
+}
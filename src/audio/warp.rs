@@ -0,0 +1,408 @@
+// Warp - Audio clips following the project tempo
+//
+// A warped clip maps positions in its source to beats with warp markers. The
+// markers are placed on the transients of the source (see `detect_transients`),
+// so every hit of a drum loop starts a segment: the clip player restarts each
+// segment exactly at its marker's beat and stretches the audio between two
+// markers with overlapping grains read at the original speed (the pitch is kept).
+// Transients are therefore never smeared, and the loop stays locked to the grid
+// whatever the tempo.
+//
+// The stretch is stateless (the output at a position only depends on that
+// position), so the audio thread can start playback anywhere without history.
+
+use crate::audio::clip_player::AudioClip;
+use crate::sampler::loader::SampleData;
+
+/// Grain length (timeline samples, about 21 ms at 48 kHz)
+pub const WARP_GRAIN: f64 = 1024.0;
+
+/// Fade applied before a segment reaches the next marker (source samples)
+const SEGMENT_EDGE: f64 = 64.0;
+
+/// Tempo range accepted for warp sources (BPM)
+pub const MIN_SOURCE_BPM: f64 = 20.0;
+pub const MAX_SOURCE_BPM: f64 = 400.0;
+
+/// Analysis frame of the transient detector (source samples)
+const ONSET_FRAME: usize = 256;
+
+/// Frames averaged as the reference energy of an onset
+const ONSET_HISTORY: usize = 8;
+
+/// Energy rise over the recent average that marks an onset
+const ONSET_RATIO: f32 = 2.0;
+
+/// Frame energy below which no onset is detected (relative to the loudest frame)
+const ONSET_FLOOR: f32 = 0.01;
+
+/// Level (relative to the peak of the hit) where an attack starts
+const ONSET_ATTACK: f32 = 0.1;
+
+/// Shortest interval between two transients (seconds)
+const MIN_TRANSIENT_INTERVAL: f32 = 0.05;
+
+/// Source position pinned to a beat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WarpMarker {
+    /// Position in the source (samples at the engine rate)
+    pub source: u64,
+    /// Beats from the clip start
+    pub beat: f64,
+}
+
+/// Warp markers of a clip
+///
+/// Markers are sorted, start at beat 0, and both their source positions and
+/// beats strictly increase. The first marker is where the clip starts in its
+/// source, the last one where it ends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipWarp {
+    /// Tempo the source was recorded at (BPM, used to place the markers)
+    pub source_bpm: f64,
+    markers: Vec<WarpMarker>,
+}
+
+impl ClipWarp {
+    pub fn new(source_bpm: f64, markers: Vec<WarpMarker>) -> Result<Self, String> {
+        if !(MIN_SOURCE_BPM..=MAX_SOURCE_BPM).contains(&source_bpm) {
+            return Err(format!(
+                "Source tempo must be between {} and {} BPM, got {}",
+                MIN_SOURCE_BPM, MAX_SOURCE_BPM, source_bpm
+            ));
+        }
+        if markers.len() < 2 {
+            return Err("A warped clip needs at least two markers".to_string());
+        }
+        if markers[0].beat != 0.0 {
+            return Err("The first warp marker must be at beat 0".to_string());
+        }
+        if markers
+            .windows(2)
+            .any(|pair| pair[1].source <= pair[0].source || pair[1].beat <= pair[0].beat)
+        {
+            return Err("Warp markers must be in increasing order".to_string());
+        }
+        Ok(Self {
+            source_bpm,
+            markers,
+        })
+    }
+
+    /// Warp a clip recorded at `source_bpm`, with a marker on each transient
+    ///
+    /// The markers keep the clip region (offset and length) and lie on the
+    /// source tempo, so the clip sounds unchanged at that tempo.
+    pub fn detect(clip: &AudioClip, source_bpm: f64, sample_rate: f32) -> Result<Self, String> {
+        if !(MIN_SOURCE_BPM..=MAX_SOURCE_BPM).contains(&source_bpm) {
+            return Err(format!(
+                "Source tempo must be between {} and {} BPM, got {}",
+                MIN_SOURCE_BPM, MAX_SOURCE_BPM, source_bpm
+            ));
+        }
+        let SampleData::F32(data) = &clip.sample.data;
+        let source_rate = clip.sample.sample_rate as f64;
+        // Source samples per engine sample
+        let step = source_rate / sample_rate as f64;
+        let start = clip.offset;
+        let end = clip.offset + clip.length;
+        if end <= start || step <= 0.0 {
+            return Err("The clip is empty".to_string());
+        }
+
+        let region =
+            &data[(start as f64 * step) as usize..((end as f64 * step) as usize).min(data.len())];
+        let samples_per_beat = sample_rate as f64 * 60.0 / source_bpm;
+        let beat = |source: u64| (source - start) as f64 / samples_per_beat;
+
+        let mut markers = vec![WarpMarker {
+            source: start,
+            beat: 0.0,
+        }];
+        for transient in detect_transients(region, clip.sample.sample_rate) {
+            let source = start + (transient as f64 / step) as u64;
+            // Hits closer than a grain to the previous marker or the end share its segment
+            let previous = markers.last().map_or(start, |marker| marker.source);
+            if (source - previous) as f64 >= WARP_GRAIN && (end - source) as f64 >= WARP_GRAIN {
+                markers.push(WarpMarker {
+                    source,
+                    beat: beat(source),
+                });
+            }
+        }
+        markers.push(WarpMarker {
+            source: end,
+            beat: beat(end),
+        });
+        Self::new(source_bpm, markers)
+    }
+
+    pub fn markers(&self) -> &[WarpMarker] {
+        &self.markers
+    }
+
+    /// Beats covered by the clip
+    pub fn beats(&self) -> f64 {
+        self.markers.last().map_or(0.0, |marker| marker.beat)
+    }
+
+    /// Timeline duration of the clip (samples)
+    pub fn length(&self, samples_per_beat: f64) -> u64 {
+        (self.beats() * samples_per_beat) as u64
+    }
+
+    /// Move a marker to another beat (the first marker stays at beat 0)
+    pub fn set_marker_beat(&mut self, index: usize, beat: f64) -> Result<(), String> {
+        if index == 0 || index >= self.markers.len() {
+            return Err(format!("Warp marker {} cannot be moved", index));
+        }
+        let previous = self.markers[index - 1].beat;
+        let next = self
+            .markers
+            .get(index + 1)
+            .map_or(f64::INFINITY, |m| m.beat);
+        if !(beat > previous && beat < next) {
+            return Err(format!(
+                "Warp marker {} must stay between beats {} and {}",
+                index, previous, next
+            ));
+        }
+        self.markers[index].beat = beat;
+        Ok(())
+    }
+
+    /// Snap the markers to a grid (in beats), dropping those that would collide
+    pub fn quantize(&mut self, grid: f64) {
+        if grid <= 0.0 {
+            return;
+        }
+        let last = self.markers.len() - 1;
+        let mut previous = 0.0;
+        let mut index = 0;
+        self.markers.retain_mut(|marker| {
+            let keep = if index == 0 {
+                true
+            } else {
+                let snapped = (marker.beat / grid).round() * grid;
+                // The last marker never disappears: it ends the clip
+                let snapped = if index == last {
+                    snapped.max(previous + grid)
+                } else {
+                    snapped
+                };
+                marker.beat = snapped;
+                snapped > previous
+            };
+            if keep {
+                previous = marker.beat;
+            }
+            index += 1;
+            keep
+        });
+    }
+
+    /// Source position (engine-rate samples, fractional) played at a timeline
+    /// position relative to the clip start, with the weight of each grain
+    ///
+    /// Up to two grains overlap: returns `[(source, weight); 2]`, a zero weight
+    /// meaning no grain.
+    #[inline]
+    pub fn grains(&self, local: u64, samples_per_beat: f64) -> [(f64, f32); 2] {
+        let beat = local as f64 / samples_per_beat;
+        // Segment playing at this beat (the last one holds the clip end)
+        let segment = self
+            .markers
+            .partition_point(|marker| marker.beat <= beat)
+            .clamp(1, self.markers.len() - 1)
+            - 1;
+        let (from, to) = (self.markers[segment], self.markers[segment + 1]);
+
+        let position = local as f64 - from.beat * samples_per_beat;
+        let duration = (to.beat - from.beat) * samples_per_beat;
+        // Source samples per timeline sample across the segment
+        let ratio = (to.source - from.source) as f64 / duration;
+
+        let hop = WARP_GRAIN * 0.5;
+        let current = (position / hop).floor().max(0.0);
+        let mut grains = [(0.0, 0.0); 2];
+        for (slot, grain) in [current, current - 1.0].into_iter().enumerate() {
+            if grain < 0.0 {
+                continue;
+            }
+            let phase = position - grain * hop;
+            // The first grain starts at full level: the transient is untouched
+            let weight = if grain == 0.0 && phase < hop {
+                1.0
+            } else {
+                let x = std::f64::consts::PI * phase / WARP_GRAIN;
+                x.sin() * x.sin()
+            };
+            let source = from.source as f64 + grain * hop * ratio + phase;
+            // Never run into the next transient
+            let edge = ((to.source as f64 - source) / SEGMENT_EDGE).clamp(0.0, 1.0);
+            grains[slot] = (source, (weight * edge) as f32);
+        }
+        grains
+    }
+}
+
+/// Positions (samples) where the energy of a signal suddenly rises
+pub fn detect_transients(data: &[f32], sample_rate: u32) -> Vec<usize> {
+    let energies: Vec<f32> = data
+        .chunks(ONSET_FRAME)
+        .map(|frame| frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32)
+        .collect();
+    let loudest = energies.iter().copied().fold(0.0f32, f32::max);
+    if loudest <= 0.0 {
+        return Vec::new();
+    }
+    let floor = loudest * ONSET_FLOOR;
+    let min_interval = (MIN_TRANSIENT_INTERVAL * sample_rate as f32) as usize;
+
+    let mut transients: Vec<usize> = Vec::new();
+    for (index, &energy) in energies.iter().enumerate() {
+        let history = &energies[index.saturating_sub(ONSET_HISTORY)..index];
+        let reference = if history.is_empty() {
+            0.0
+        } else {
+            history.iter().sum::<f32>() / history.len() as f32
+        };
+        if energy <= floor || energy <= reference * ONSET_RATIO {
+            continue;
+        }
+        let position = onset_position(data, index * ONSET_FRAME);
+        if transients
+            .last()
+            .is_none_or(|&last| position >= last + min_interval)
+        {
+            transients.push(position);
+        }
+    }
+    transients
+}
+
+/// First sample of the attack starting in the frame at `frame_start`
+///
+/// The attack may begin late in the frame: the search covers the next frame too.
+fn onset_position(data: &[f32], frame_start: usize) -> usize {
+    let window = &data[frame_start..(frame_start + 2 * ONSET_FRAME).min(data.len())];
+    let peak = window.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    let attack = window
+        .iter()
+        .position(|s| s.abs() >= peak * ONSET_ATTACK)
+        .unwrap_or(0);
+    frame_start + attack
+}
+
+/// Guess the tempo of a loop from its duration (assumed a power-of-two number
+/// of beats between 80 and 160 BPM)
+pub fn estimate_loop_bpm(length: u64, sample_rate: f32) -> f64 {
+    let seconds = length as f64 / sample_rate as f64;
+    if seconds <= 0.0 {
+        return 120.0;
+    }
+    (0..8)
+        .map(|power| (1u32 << power) as f64 * 60.0 / seconds)
+        .find(|bpm| (80.0..160.0).contains(bpm))
+        .unwrap_or(120.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sampler::loader::{LoopMode, Sample};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// One-beat-at-120-BPM clicks (decaying bursts every 24000 samples)
+    fn clicks(beats: usize) -> AudioClip {
+        let data: Vec<f32> = (0..beats * 24000)
+            .map(|i| {
+                let t = (i % 24000) as f32;
+                if t < 2000.0 { 1.0 - t / 2000.0 } else { 0.0 }
+            })
+            .collect();
+        let sample = Arc::new(Sample {
+            name: "clicks".to_string(),
+            data: SampleData::F32(data.into()),
+            sample_rate: 48000,
+            source_channels: 1,
+            loop_mode: LoopMode::Off,
+            loop_start: 0,
+            loop_end: 0,
+            reverse: false,
+            volume: 1.0,
+            pan: 0.0,
+            pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: None,
+        });
+        AudioClip::new(PathBuf::from("clicks.wav"), sample, 0, SAMPLE_RATE)
+    }
+
+    #[test]
+    fn test_detect_places_markers_on_transients() {
+        let clip = clicks(4);
+        assert_eq!(estimate_loop_bpm(clip.length, SAMPLE_RATE), 120.0);
+
+        let warp = ClipWarp::detect(&clip, 120.0, SAMPLE_RATE).unwrap();
+        let beats: Vec<f64> = warp.markers().iter().map(|marker| marker.beat).collect();
+        assert_eq!(beats, vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(warp.markers()[2].source, 48000);
+
+        // At the source tempo the clip keeps its duration, at 60 BPM it doubles
+        assert_eq!(warp.length(24000.0), clip.length);
+        assert_eq!(warp.length(48000.0), 2 * clip.length);
+    }
+
+    #[test]
+    fn test_segments_restart_on_their_marker() {
+        let clip = clicks(4);
+        let warp = ClipWarp::detect(&clip, 120.0, SAMPLE_RATE).unwrap();
+
+        // Twice slower: the third beat starts at 96000 with its own transient
+        let [(source, weight), (_, other)] = warp.grains(96000, 48000.0);
+        assert_eq!((source, weight, other), (48000.0, 1.0, 0.0));
+
+        // At the source tempo the grains read the source unchanged
+        for local in [100u64, 5000, 30000, 70000] {
+            let grains = warp.grains(local, 24000.0);
+            let total: f32 = grains.iter().map(|(_, weight)| weight).sum();
+            assert!((total - 1.0).abs() < 1e-4, "{} at {}", total, local);
+            for (source, weight) in grains {
+                if weight > 0.0 {
+                    assert!((source - local as f64).abs() < 1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_marker_edits() {
+        let mut warp = ClipWarp::detect(&clicks(4), 120.0, SAMPLE_RATE).unwrap();
+        assert!(warp.set_marker_beat(0, 0.5).is_err());
+        assert!(warp.set_marker_beat(1, 2.5).is_err());
+        warp.set_marker_beat(1, 1.1).unwrap();
+        warp.set_marker_beat(2, 1.3).unwrap();
+
+        // 1.1 and 1.3 both snap to 1.0 on a quarter grid: the second is dropped
+        warp.quantize(1.0);
+        let beats: Vec<f64> = warp.markers().iter().map(|marker| marker.beat).collect();
+        assert_eq!(beats, vec![0.0, 1.0, 3.0, 4.0]);
+
+        assert!(
+            ClipWarp::new(
+                120.0,
+                vec![WarpMarker {
+                    source: 0,
+                    beat: 0.0
+                }]
+            )
+            .is_err()
+        );
+        assert!(ClipWarp::detect(&clicks(1), 1000.0, SAMPLE_RATE).is_err());
+    }
+}
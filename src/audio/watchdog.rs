@@ -0,0 +1,304 @@
+// Audio watchdog - Detect an audio callback that stopped or keeps overrunning
+//
+// The callback only bumps a heartbeat counter (one relaxed atomic add). A
+// watchdog thread checks it periodically and reports a fault when:
+// - Stalled: the heartbeat did not move for the stall timeout (device yanked,
+//   driver stall). cpal does not always call the error callback in that case.
+// - Overloaded: the callback missed its deadline `OVERLOAD_THRESHOLD` times
+//   within `OVERLOAD_WINDOW`.
+//
+// On a fault the device status is set to `Error`, a notification is sent, and
+// the fault is kept until a frontend takes it (`AudioEngine::take_watchdog_fault`)
+// to restart the engine.
+//
+// The watchdog also warns once if the OS denied real-time priority to the
+// callback thread (see `thread_priority`).
+
+use crate::audio::cpu_monitor::{CpuMonitor, XrunBurstDetector, XrunStats};
+use crate::audio::thread_priority::{self, PriorityStatus, ThreadKind};
+use crate::connection::status::{AtomicDeviceStatus, DeviceStatus};
+use crate::messaging::channels::NotificationProducer;
+use crate::messaging::notification::{Notification, NotificationCategory};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the watchdog thread checks the heartbeat
+pub const WATCHDOG_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Minimum time without callback before the stream is considered stalled
+pub const MIN_STALL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Window over which deadline overruns are counted
+pub const OVERLOAD_WINDOW: Duration = Duration::from_secs(2);
+
+/// Deadline overruns within `OVERLOAD_WINDOW` that count as an overload
+pub const OVERLOAD_THRESHOLD: u64 = 20;
+
+/// Why the watchdog gave up on the audio stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogFault {
+    /// The audio callback stopped being invoked
+    Stalled,
+    /// The audio callback keeps exceeding its deadline
+    Overloaded,
+}
+
+impl WatchdogFault {
+    fn to_u8(self) -> u8 {
+        match self {
+            WatchdogFault::Stalled => 1,
+            WatchdogFault::Overloaded => 2,
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(WatchdogFault::Stalled),
+            2 => Some(WatchdogFault::Overloaded),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WatchdogFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchdogFault::Stalled => write!(f, "audio callback stalled"),
+            WatchdogFault::Overloaded => write!(f, "audio callback repeatedly missed its deadline"),
+        }
+    }
+}
+
+/// Heartbeat bumped by the audio callback (RT-safe)
+#[derive(Clone, Default)]
+pub struct Heartbeat {
+    count: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Signal that the callback ran (call once per callback)
+    #[inline]
+    pub fn beat(&self) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of callbacks so far
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+/// Fault detection logic, fed by the watchdog thread
+pub struct WatchdogMonitor {
+    stall_timeout: Duration,
+    last_count: u64,
+    last_progress: Instant,
+    overload_detector: XrunBurstDetector,
+    stalled: bool,
+}
+
+impl WatchdogMonitor {
+    pub fn new(stall_timeout: Duration, now: Instant) -> Self {
+        Self {
+            stall_timeout,
+            last_count: 0,
+            last_progress: now,
+            overload_detector: XrunBurstDetector::new(OVERLOAD_WINDOW, OVERLOAD_THRESHOLD),
+            stalled: false,
+        }
+    }
+
+    /// Stall timeout for a stream with buffers of `buffer_duration`
+    ///
+    /// Several buffers may legitimately pass between callbacks (large device
+    /// buffers), so the timeout is never shorter than four of them.
+    pub fn stall_timeout_for(buffer_duration: Duration) -> Duration {
+        MIN_STALL_TIMEOUT.max(buffer_duration * 4)
+    }
+
+    /// Feed the current heartbeat and deadline overrun count
+    ///
+    /// A stall is reported once, until the callback runs again.
+    pub fn poll(
+        &mut self,
+        heartbeat: u64,
+        deadline_overruns: u64,
+        now: Instant,
+    ) -> Option<WatchdogFault> {
+        if heartbeat != self.last_count {
+            self.last_count = heartbeat;
+            self.last_progress = now;
+            self.stalled = false;
+        } else if !self.stalled
+            && now.saturating_duration_since(self.last_progress) >= self.stall_timeout
+        {
+            self.stalled = true;
+            return Some(WatchdogFault::Stalled);
+        }
+
+        let overruns = XrunStats {
+            deadline_overruns,
+            underruns: 0,
+        };
+        self.overload_detector
+            .poll(overruns, now)
+            .map(|_| WatchdogFault::Overloaded)
+    }
+}
+
+/// Watchdog thread of an audio engine (stopped when dropped)
+pub struct AudioWatchdog {
+    running: Arc<AtomicBool>,
+    fault: Arc<AtomicU8>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl AudioWatchdog {
+    /// Spawn the watchdog thread (call once the stream is playing)
+    pub fn spawn(
+        heartbeat: Heartbeat,
+        cpu_monitor: CpuMonitor,
+        status: AtomicDeviceStatus,
+        notification_tx: Arc<Mutex<NotificationProducer>>,
+        stall_timeout: Duration,
+    ) -> Self {
+        let running = Arc::new(AtomicBool::new(true));
+        let fault = Arc::new(AtomicU8::new(0));
+
+        let thread_running = running.clone();
+        let thread_fault = fault.clone();
+        let handle = thread::Builder::new()
+            .name("audio-watchdog".to_string())
+            .spawn(move || {
+                let send_notification = |notification: Notification| {
+                    if let Ok(mut tx) = notification_tx.lock() {
+                        tx.push(notification);
+                    }
+                };
+
+                let mut monitor = WatchdogMonitor::new(stall_timeout, Instant::now());
+                let mut priority_checked = false;
+                while thread_running.load(Ordering::Relaxed) {
+                    thread::sleep(WATCHDOG_INTERVAL);
+
+                    // The callback asks for real-time priority when it first runs
+                    if !priority_checked && heartbeat.count() > 0 {
+                        priority_checked = true;
+                        let priority = thread_priority::status(ThreadKind::Audio);
+                        if let PriorityStatus::Denied(_) = priority {
+                            eprintln!("⚠️ Real-time priority {} for the audio thread", priority);
+                            send_notification(Notification::warning(
+                                NotificationCategory::Audio,
+                                format!("Real-time audio priority {}", priority),
+                            ));
+                        }
+                    }
+
+                    let overruns = cpu_monitor.xrun_stats().deadline_overruns;
+                    let Some(detected) = monitor.poll(heartbeat.count(), overruns, Instant::now())
+                    else {
+                        continue;
+                    };
+
+                    eprintln!("❌ Audio watchdog: {}", detected);
+                    status.set(DeviceStatus::Error);
+                    thread_fault.store(detected.to_u8(), Ordering::Release);
+                    send_notification(Notification::error(
+                        NotificationCategory::Audio,
+                        format!("Audio watchdog: {}", detected),
+                    ));
+                }
+            })
+            .ok();
+
+        Self {
+            running,
+            fault,
+            handle,
+        }
+    }
+
+    /// Take the last fault detected (the recovery path handles it)
+    pub fn take_fault(&self) -> Option<WatchdogFault> {
+        WatchdogFault::from_u8(self.fault.swap(0, Ordering::AcqRel))
+    }
+}
+
+impl Drop for AudioWatchdog {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stall_reported_once_until_callback_resumes() {
+        let start = Instant::now();
+        let mut monitor = WatchdogMonitor::new(Duration::from_secs(1), start);
+
+        assert_eq!(monitor.poll(5, 0, start + Duration::from_millis(500)), None);
+        assert_eq!(
+            monitor.poll(5, 0, start + Duration::from_millis(1400)),
+            None
+        );
+        assert_eq!(
+            monitor.poll(5, 0, start + Duration::from_millis(2500)),
+            Some(WatchdogFault::Stalled)
+        );
+        assert_eq!(monitor.poll(5, 0, start + Duration::from_secs(4)), None);
+
+        // Callback resumes, then stops again
+        assert_eq!(monitor.poll(6, 0, start + Duration::from_secs(5)), None);
+        assert_eq!(
+            monitor.poll(6, 0, start + Duration::from_secs(6)),
+            Some(WatchdogFault::Stalled)
+        );
+    }
+
+    #[test]
+    fn test_repeated_overruns_reported_as_overload() {
+        let start = Instant::now();
+        let mut monitor = WatchdogMonitor::new(Duration::from_secs(1), start);
+
+        let mut heartbeat = 0;
+        let mut fault = None;
+        for step in 0..10u64 {
+            heartbeat += 10;
+            let now = start + Duration::from_millis(100 * step);
+            fault = fault.or(monitor.poll(heartbeat, step * 5, now));
+        }
+        assert_eq!(fault, Some(WatchdogFault::Overloaded));
+
+        // A few overruns are only xruns
+        let mut monitor = WatchdogMonitor::new(Duration::from_secs(1), start);
+        for step in 0..10u64 {
+            let now = start + Duration::from_millis(100 * step);
+            assert_eq!(monitor.poll(step + 1, step / 3, now), None);
+        }
+    }
+
+    #[test]
+    fn test_stall_timeout_covers_large_buffers() {
+        assert_eq!(
+            WatchdogMonitor::stall_timeout_for(Duration::from_millis(10)),
+            MIN_STALL_TIMEOUT
+        );
+        assert_eq!(
+            WatchdogMonitor::stall_timeout_for(Duration::from_millis(500)),
+            Duration::from_secs(2)
+        );
+    }
+}
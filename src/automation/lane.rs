@@ -0,0 +1,203 @@
+// Automation lane - Breakpoints for a single parameter
+
+use serde::{Deserialize, Serialize};
+
+/// Parameters that can be automated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutomationParameter {
+    /// Filter cutoff in Hz
+    FilterCutoff,
+    /// Master volume (0.0 - 1.0)
+    Volume,
+    /// LFO rate in Hz
+    LfoRate,
+}
+
+impl AutomationParameter {
+    /// All automatable parameters
+    pub const ALL: [AutomationParameter; 3] = [
+        AutomationParameter::FilterCutoff,
+        AutomationParameter::Volume,
+        AutomationParameter::LfoRate,
+    ];
+
+    /// Parameter name for UI display
+    pub fn name(&self) -> &'static str {
+        match self {
+            AutomationParameter::FilterCutoff => "Filter Cutoff",
+            AutomationParameter::Volume => "Volume",
+            AutomationParameter::LfoRate => "LFO Rate",
+        }
+    }
+
+    /// Valid value range (min, max), in the parameter's own unit
+    pub fn range(&self) -> (f32, f32) {
+        match self {
+            AutomationParameter::FilterCutoff => (20.0, 20000.0),
+            AutomationParameter::Volume => (0.0, 1.0),
+            AutomationParameter::LfoRate => (0.1, 20.0),
+        }
+    }
+}
+
+/// Shape of the segment going from a point to the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurveShape {
+    /// Straight line
+    #[default]
+    Linear,
+    /// Hold the value until the next point
+    Step,
+    /// Exponential curve (constant ratio per beat, natural for frequencies)
+    Exponential,
+}
+
+/// Automation breakpoint
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AutomationPoint {
+    /// Position in beats from the start of the timeline
+    pub time_beats: f64,
+    /// Value in the parameter's unit
+    pub value: f32,
+    /// Shape of the segment starting at this point
+    #[serde(default)]
+    pub curve: CurveShape,
+}
+
+/// Breakpoints for one parameter, sorted by time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutomationLane {
+    pub parameter: AutomationParameter,
+    points: Vec<AutomationPoint>,
+}
+
+impl AutomationLane {
+    /// Create an empty lane
+    pub fn new(parameter: AutomationParameter) -> Self {
+        Self {
+            parameter,
+            points: Vec::new(),
+        }
+    }
+
+    /// Breakpoints, sorted by time
+    pub fn points(&self) -> &[AutomationPoint] {
+        &self.points
+    }
+
+    /// Check if the lane has no breakpoint
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Replace all breakpoints
+    ///
+    /// Points are sorted by time and values are clamped to the parameter range.
+    /// Fails (leaving the lane untouched) on negative or non-finite times/values.
+    pub fn set_points(&mut self, mut points: Vec<AutomationPoint>) -> Result<(), String> {
+        let (min, max) = self.parameter.range();
+        for point in &mut points {
+            if !point.time_beats.is_finite() || point.time_beats < 0.0 {
+                return Err(format!("Invalid automation time: {}", point.time_beats));
+            }
+            if !point.value.is_finite() {
+                return Err(format!("Invalid automation value: {}", point.value));
+            }
+            point.value = point.value.clamp(min, max);
+        }
+
+        // Stable sort keeps the insertion order of points sharing a time (instant jumps)
+        points.sort_by(|a, b| a.time_beats.total_cmp(&b.time_beats));
+        self.points = points;
+        Ok(())
+    }
+
+    /// Remove all breakpoints
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Value at a given time (None if the lane is empty)
+    ///
+    /// Before the first point and after the last one, the nearest value is held.
+    pub fn value_at(&self, time_beats: f64) -> Option<f32> {
+        let first = self.points.first()?;
+        if time_beats < first.time_beats {
+            return Some(first.value);
+        }
+
+        // Index of the first point strictly after `time_beats`
+        let next = self.points.partition_point(|p| p.time_beats <= time_beats);
+        let current = &self.points[next - 1];
+        let Some(target) = self.points.get(next) else {
+            return Some(current.value);
+        };
+
+        let t =
+            ((time_beats - current.time_beats) / (target.time_beats - current.time_beats)) as f32;
+        let value = match current.curve {
+            CurveShape::Step => current.value,
+            CurveShape::Linear => current.value + (target.value - current.value) * t,
+            CurveShape::Exponential if current.value > 0.0 && target.value > 0.0 => {
+                current.value * (target.value / current.value).powf(t)
+            }
+            // Exponential curves are undefined through zero: fall back to linear
+            CurveShape::Exponential => current.value + (target.value - current.value) * t,
+        };
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(time_beats: f64, value: f32, curve: CurveShape) -> AutomationPoint {
+        AutomationPoint {
+            time_beats,
+            value,
+            curve,
+        }
+    }
+
+    #[test]
+    fn test_set_points_sorts_and_clamps() {
+        let mut lane = AutomationLane::new(AutomationParameter::Volume);
+        lane.set_points(vec![
+            point(4.0, 2.0, CurveShape::Linear),
+            point(0.0, 0.5, CurveShape::Linear),
+        ])
+        .unwrap();
+
+        assert_eq!(lane.points()[0].time_beats, 0.0);
+        assert_eq!(lane.points()[1].value, 1.0);
+
+        assert!(
+            lane.set_points(vec![point(-1.0, 0.5, CurveShape::Linear)])
+                .is_err()
+        );
+        assert_eq!(lane.points().len(), 2);
+    }
+
+    #[test]
+    fn test_value_at_curves() {
+        let mut lane = AutomationLane::new(AutomationParameter::FilterCutoff);
+        assert_eq!(lane.value_at(0.0), None);
+
+        lane.set_points(vec![
+            point(0.0, 100.0, CurveShape::Linear),
+            point(2.0, 300.0, CurveShape::Step),
+            point(4.0, 1000.0, CurveShape::Exponential),
+            point(6.0, 10000.0, CurveShape::Linear),
+        ])
+        .unwrap();
+
+        assert_eq!(lane.value_at(1.0), Some(200.0));
+        assert_eq!(lane.value_at(3.0), Some(300.0));
+        assert!((lane.value_at(5.0).unwrap() - 3162.28).abs() < 0.1);
+        // Held after the last point
+        assert_eq!(lane.value_at(100.0), Some(10000.0));
+    }
+}
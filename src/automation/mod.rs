@@ -0,0 +1,12 @@
+// Automation module - Parameter changes over time
+//
+// An automation lane is a list of breakpoints (time in beats, value, curve
+// shape) for one parameter. Times are stored in beats so lanes follow tempo
+// changes; conversion to samples happens at evaluation time, in the audio
+// callback (`AutomationPlayer`).
+
+pub mod lane;
+pub mod player;
+
+pub use lane::{AutomationLane, AutomationParameter, AutomationPoint, CurveShape};
+pub use player::AutomationPlayer;
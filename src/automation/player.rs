@@ -0,0 +1,147 @@
+// Automation player - Evaluates the lanes in the audio callback
+//
+// The lanes reach the audio thread as an `Arc` snapshot (the replaced one goes
+// back through the garbage chute). The filter cutoff and the LFO rate are
+// written to the voices before each voice block (`VOICE_BLOCK_SIZE` samples),
+// the volume is read for every sample by the master fader.
+
+use crate::automation::{AutomationLane, AutomationParameter};
+use crate::sequencer::Tempo;
+use crate::synth::voice_manager::VoiceManager;
+use std::sync::Arc;
+
+/// Plays the automation lanes of the project (RT-safe, no allocations)
+pub struct AutomationPlayer {
+    lanes: Arc<Vec<AutomationLane>>,
+    sample_rate: f64,
+    /// Values last written to the voices (skips unchanged updates)
+    applied_cutoff: Option<f32>,
+    applied_lfo_rate: Option<f32>,
+}
+
+impl AutomationPlayer {
+    /// Create a player without lanes
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            lanes: Arc::new(Vec::new()),
+            sample_rate,
+            applied_cutoff: None,
+            applied_lfo_rate: None,
+        }
+    }
+
+    /// Replace the lanes, returning the previous snapshot
+    ///
+    /// The voices keep the last automated values: the caller re-applies the
+    /// synth parameters so parameters without a lane go back to their control.
+    pub fn set_lanes(&mut self, lanes: Arc<Vec<AutomationLane>>) -> Arc<Vec<AutomationLane>> {
+        self.applied_cutoff = None;
+        self.applied_lfo_rate = None;
+        std::mem::replace(&mut self.lanes, lanes)
+    }
+
+    /// Forget the values written to the voices (after the synth parameters
+    /// were applied again)
+    pub fn invalidate(&mut self) {
+        self.applied_cutoff = None;
+        self.applied_lfo_rate = None;
+    }
+
+    /// Check if a parameter has a lane
+    pub fn is_automated(&self, parameter: AutomationParameter) -> bool {
+        self.lane(parameter).is_some()
+    }
+
+    /// Check if a lane drives a voice parameter (filter cutoff or LFO rate)
+    pub fn automates_voices(&self) -> bool {
+        self.is_automated(AutomationParameter::FilterCutoff)
+            || self.is_automated(AutomationParameter::LfoRate)
+    }
+
+    /// Value of a parameter at a position of the timeline (None without lane)
+    pub fn value_at(
+        &self,
+        parameter: AutomationParameter,
+        position_samples: u64,
+        tempo: &Tempo,
+    ) -> Option<f32> {
+        let beats = position_samples as f64 / tempo.beat_duration_samples(self.sample_rate);
+        self.lane(parameter)?.value_at(beats)
+    }
+
+    /// Write the automated filter cutoff and LFO rate to the voices
+    pub fn apply_to(
+        &mut self,
+        voice_manager: &mut VoiceManager,
+        position_samples: u64,
+        tempo: &Tempo,
+    ) {
+        if let Some(cutoff) =
+            self.value_at(AutomationParameter::FilterCutoff, position_samples, tempo)
+            && self.applied_cutoff != Some(cutoff)
+        {
+            voice_manager.update_filter(|filter| filter.cutoff = cutoff);
+            self.applied_cutoff = Some(cutoff);
+        }
+
+        if let Some(rate) = self.value_at(AutomationParameter::LfoRate, position_samples, tempo)
+            && self.applied_lfo_rate != Some(rate)
+        {
+            voice_manager.update_lfo(|lfo| lfo.rate = rate);
+            self.applied_lfo_rate = Some(rate);
+        }
+    }
+
+    fn lane(&self, parameter: AutomationParameter) -> Option<&AutomationLane> {
+        self.lanes
+            .iter()
+            .find(|lane| lane.parameter == parameter && !lane.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automation::{AutomationPoint, CurveShape};
+
+    #[test]
+    fn test_automation_player_applies_lanes() {
+        let mut lane = AutomationLane::new(AutomationParameter::FilterCutoff);
+        lane.set_points(vec![
+            AutomationPoint {
+                time_beats: 0.0,
+                value: 200.0,
+                curve: CurveShape::Linear,
+            },
+            AutomationPoint {
+                time_beats: 2.0,
+                value: 600.0,
+                curve: CurveShape::Linear,
+            },
+        ])
+        .unwrap();
+
+        let mut player = AutomationPlayer::new(48000.0);
+        let tempo = Tempo::new(120.0);
+        assert!(!player.automates_voices());
+        player.set_lanes(Arc::new(vec![lane]));
+        assert!(player.automates_voices());
+        assert!(!player.is_automated(AutomationParameter::Volume));
+
+        // One beat = 24000 samples at 120 BPM
+        assert_eq!(
+            player.value_at(AutomationParameter::FilterCutoff, 24000, &tempo),
+            Some(400.0)
+        );
+        assert_eq!(
+            player.value_at(AutomationParameter::Volume, 24000, &tempo),
+            None
+        );
+
+        let mut vm = VoiceManager::new(48000.0);
+        let rate = vm.get_lfo_params().rate;
+        player.apply_to(&mut vm, 24000, &tempo);
+        assert_eq!(vm.get_filter_params().cutoff, 400.0);
+        assert_eq!(vm.get_lfo_params().rate, rate);
+    }
+}
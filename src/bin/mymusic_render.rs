@@ -0,0 +1,195 @@
+//! Headless project renderer
+//!
+//! Loads a project file and bounces it to WAV or FLAC without the GUI or an
+//! audio device, for batch bouncing and automated tests.
+//!
+//! Run with: cargo run --bin mymusic_render -- song.mymusic -o song.flac --stems
+
+use mymusic_daw::audio::export::{AudioExporter, ExportFormat, ExportSettings, RenderRange};
+use mymusic_daw::project::{ProjectLoadOptions, ProjectManager};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: mymusic_render <project> [options]
+
+Options:
+  -o, --output <file>       Output file (default: <project>.wav)
+  -f, --format <wav|flac>   Output format (default: from the output extension)
+  -r, --sample-rate <hz>    Sample rate (default: the project sample rate)
+  -b, --bit-depth <16|24|32> Bit depth, 32: float WAV (default: 16)
+      --start-bar <n>       First bar rendered, from 1 (default: 1)
+      --end-bar <n>         Last bar rendered, inclusive (default: end of the longest pattern)
+      --tail <seconds>      Time rendered after the range for releases (default: 0)
+      --stems               Write one file per track instead of the mix
+      --mono                Render a mono file
+      --stereo              Fold a surround project down to stereo
+      --metronome           Include the metronome
+  -h, --help                Show this help";
+
+/// Parsed command line
+struct Options {
+    project: PathBuf,
+    output: Option<PathBuf>,
+    format: Option<ExportFormat>,
+    sample_rate: Option<u32>,
+    bit_depth: u16,
+    start_bar: u32,
+    end_bar: Option<u32>,
+    tail_seconds: f64,
+    stems: bool,
+    mono: bool,
+    stereo: bool,
+    metronome: bool,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("❌ {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match render(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        project: PathBuf::new(),
+        output: None,
+        format: None,
+        sample_rate: None,
+        bit_depth: 16,
+        start_bar: 1,
+        end_bar: None,
+        tail_seconds: 0.0,
+        stems: false,
+        mono: false,
+        stereo: false,
+        metronome: false,
+    };
+    let mut project = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "-o" | "--output" => options.output = Some(PathBuf::from(value()?)),
+            "-f" | "--format" => options.format = Some(parse_format(value()?)?),
+            "-r" | "--sample-rate" => options.sample_rate = Some(parse_number(arg, value()?)?),
+            "-b" | "--bit-depth" => options.bit_depth = parse_number(arg, value()?)?,
+            "--start-bar" => options.start_bar = parse_number(arg, value()?)?,
+            "--end-bar" => options.end_bar = Some(parse_number(arg, value()?)?),
+            "--tail" => options.tail_seconds = parse_number(arg, value()?)?,
+            "--stems" => options.stems = true,
+            "--mono" => options.mono = true,
+            "--stereo" => options.stereo = true,
+            "--metronome" => options.metronome = true,
+            _ if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            _ if project.is_none() => project = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument: {}", arg)),
+        }
+    }
+
+    options.project = project.ok_or("Missing project file")?;
+    if options.start_bar == 0 {
+        return Err("Bars are numbered from 1".to_string());
+    }
+    if options.end_bar.is_some_and(|end| end < options.start_bar) {
+        return Err("--end-bar is before --start-bar".to_string());
+    }
+    Ok(options)
+}
+
+fn parse_format(value: &str) -> Result<ExportFormat, String> {
+    match value.to_lowercase().as_str() {
+        "wav" => Ok(ExportFormat::Wav),
+        "flac" => Ok(ExportFormat::Flac),
+        _ => Err(format!("Unknown format: {} (wav or flac)", value)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", option, value))
+}
+
+fn render(options: &Options) -> Result<(), String> {
+    let manager = ProjectManager::new(48000.0);
+    let project = manager
+        .load_project(&options.project, &ProjectLoadOptions::default())
+        .map_err(|e| format!("Failed to load {}: {}", options.project.display(), e))?;
+
+    // Format: explicit, else from the output extension, else WAV
+    let format = options.format.unwrap_or_else(|| {
+        match options
+            .output
+            .as_deref()
+            .and_then(Path::extension)
+            .and_then(|ext| ext.to_str())
+        {
+            Some(ext) if ext.eq_ignore_ascii_case("flac") => ExportFormat::Flac,
+            _ => ExportFormat::Wav,
+        }
+    });
+    let output = options
+        .output
+        .clone()
+        .unwrap_or_else(|| options.project.with_extension(format.extension()));
+
+    let settings = ExportSettings {
+        output_path: output.to_string_lossy().into_owned(),
+        format,
+        sample_rate: options
+            .sample_rate
+            .unwrap_or(project.metadata.sample_rate as u32),
+        bit_depth: options.bit_depth,
+        // The speaker layout of the project, unless folded down
+        channels: if options.mono {
+            1
+        } else if options.stereo {
+            2
+        } else {
+            project.speaker_layout.channels() as u16
+        },
+        include_metronome: options.metronome,
+    };
+    let range = RenderRange {
+        start_bar: options.start_bar - 1,
+        end_bar: options.end_bar,
+        tail_seconds: options.tail_seconds,
+    };
+
+    println!(
+        "🎵 Rendering '{}' at {} Hz, {} bits, {} channels",
+        project.metadata.name, settings.sample_rate, settings.bit_depth, settings.channels
+    );
+    let exporter = AudioExporter::new(settings);
+    if options.stems {
+        for stem in exporter.export_stems(&project, &range, None)? {
+            println!("✅ {}", stem);
+        }
+    } else {
+        exporter.export_project(&project, &range, None)?;
+        println!("✅ {}", output.display());
+    }
+    Ok(())
+}
@@ -3,9 +3,11 @@
 //! This binary runs the audio engine with profiling enabled to generate
 //! performance reports and flamegraphs for analysis.
 
-use mymusic_daw::audio::profiling::global_profiler;
+use mymusic_daw::audio::profiling::{global_profiler, section};
 use mymusic_daw::audio::engine::AudioEngine;
-use mymusic_daw::messaging::channels::{create_command_channel, create_notification_channel};
+use mymusic_daw::messaging::channels::{
+    create_command_channel, create_feedback_channel, create_notification_channel,
+};
 use mymusic_daw::plugin::PluginHost;
 use ringbuf::traits::producer::Producer;
 use std::sync::{Arc, Mutex};
@@ -23,6 +25,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (command_tx_ui, command_rx_ui) = create_command_channel(512);
     let (command_tx_midi, command_rx_midi) = create_command_channel(512);
     let (notification_tx, _notification_rx) = create_notification_channel(256);
+    let (feedback_tx, _feedback_rx) = create_feedback_channel(256);
     
     // Create plugin host
     let plugin_host = Arc::new(PluginHost::new());
@@ -33,6 +36,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let audio_engine = AudioEngine::new(
         command_rx_ui,
         command_rx_midi,
+        feedback_tx,
         Arc::new(Mutex::new(notification_tx)),
         plugin_host.clone(),
     )?;
@@ -100,6 +104,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Save report to file
     std::fs::write("audio_profile_report.txt", flamegraph_report)?;
     println!("📄 Report saved to: audio_profile_report.txt");
+
+    // Save the span trace (open in chrome://tracing or https://ui.perfetto.dev)
+    global_profiler().save_chrome_trace(std::path::Path::new("audio_profile_trace.json"))?;
+    println!("📄 Trace saved to: audio_profile_trace.json");
     
     // Performance analysis
     println!("\n🎯 PERFORMANCE ANALYSIS");
@@ -131,8 +139,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n🚀 OPTIMIZATION RECOMMENDATIONS");
     println!("==============================");
     
-    if stats.operation_stats.contains_key("audio_generation") {
-        let audio_gen_stats = &stats.operation_stats["audio_generation"];
+    if let Some(audio_gen_stats) = stats.operation_stats.get(section::VOICES) {
         let audio_gen_percent = (audio_gen_stats.total_time as f64 / stats.total_callback_time as f64) * 100.0;
         
         if audio_gen_percent > 50.0 {
@@ -141,8 +148,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
     
-    if stats.operation_stats.contains_key("plugin_processing") {
-        let plugin_stats = &stats.operation_stats["plugin_processing"];
+    if let Some(plugin_stats) = stats.operation_stats.get(section::PLUGINS) {
         let plugin_percent = (plugin_stats.total_time as f64 / stats.total_callback_time as f64) * 100.0;
         
         if plugin_percent > 30.0 {
@@ -0,0 +1,103 @@
+// Arrangement commands - Undoable edits of the song arrangement
+//
+// The timeline editor works on a copy of the clips while a clip is dragged and
+// executes a single `SetArrangementCommand` when the gesture ends.
+
+use crate::command::state::DawState;
+use crate::command::trait_def::{CommandError, CommandResult, UndoableCommand};
+use crate::sequencer::Arrangement;
+
+/// Command to replace the pattern clips of the song
+pub struct SetArrangementCommand {
+    description: String,
+    new_arrangement: Arrangement,
+    old_arrangement: Option<Arrangement>,
+}
+
+impl SetArrangementCommand {
+    /// Create a new SetArrangementCommand
+    ///
+    /// # Arguments
+    /// * `description` - Edit shown in the history (e.g. "Move Clip")
+    /// * `arrangement` - Every clip of the song after the edit
+    pub fn new(description: impl Into<String>, arrangement: Arrangement) -> Self {
+        Self {
+            description: description.into(),
+            new_arrangement: arrangement,
+            old_arrangement: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetArrangementCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_arrangement = state.arrangement.clone();
+        state.set_arrangement(self.new_arrangement.clone())?;
+        self.old_arrangement = Some(old_arrangement);
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_arrangement = self
+            .old_arrangement
+            .clone()
+            .ok_or_else(|| CommandError::UndoFailed("No previous arrangement stored".into()))?;
+        state
+            .set_arrangement(old_arrangement)
+            .map_err(|e| CommandError::UndoFailed(e.to_string()))
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::channels::create_command_channel;
+    use crate::messaging::command::Command;
+    use crate::sequencer::arrangement::{PatternClip, SYNTH_TRACK};
+    use ringbuf::traits::Consumer;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_arrangement_undo() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        state.set_song_mode(true).unwrap();
+        assert!(
+            matches!(rx.try_pop(), Some(Command::SetArrangement(Some(clips))) if clips.is_empty())
+        );
+
+        let mut arrangement = Arrangement::new();
+        let pattern = Arc::new(state.pattern.clone());
+        arrangement
+            .add_clip(PatternClip::new(SYNTH_TRACK, 0, 4, pattern))
+            .unwrap();
+        let mut command = SetArrangementCommand::new("Add Clip", arrangement);
+        command.execute(&mut state).unwrap();
+        assert_eq!(state.arrangement.clips().len(), 1);
+        assert!(
+            matches!(rx.try_pop(), Some(Command::SetArrangement(Some(clips))) if clips.clips().len() == 1)
+        );
+
+        // Pattern edits reach the clips playing the pattern
+        state.pattern.name = "Edited".to_string();
+        state.send_pattern().unwrap();
+        assert!(matches!(rx.try_pop(), Some(Command::SetPattern(_))));
+        assert!(matches!(
+            rx.try_pop(),
+            Some(Command::SetArrangement(Some(clips))) if clips.clips()[0].pattern.name == "Edited"
+        ));
+
+        command.undo(&mut state).unwrap();
+        assert!(state.arrangement.is_empty());
+        assert_eq!(command.description(), "Add Clip");
+
+        // Out of song mode the tracks loop their patterns
+        state.set_song_mode(false).unwrap();
+        rx.try_pop();
+        assert!(matches!(rx.try_pop(), Some(Command::SetArrangement(None))));
+    }
+}
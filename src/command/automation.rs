@@ -0,0 +1,111 @@
+// Automation commands - Undoable edits of the automation lanes
+//
+// Like the piano roll, the lane editor moves points in place during a drag and
+// executes a single `SetAutomationPointsCommand` when the gesture ends.
+
+use crate::automation::{AutomationParameter, AutomationPoint};
+use crate::command::state::DawState;
+use crate::command::trait_def::{CommandError, CommandResult, UndoableCommand};
+
+/// Command to replace the points of an automation lane
+pub struct SetAutomationPointsCommand {
+    parameter: AutomationParameter,
+    description: String,
+    new_points: Vec<AutomationPoint>,
+    old_points: Option<Vec<AutomationPoint>>,
+}
+
+impl SetAutomationPointsCommand {
+    /// Create a new SetAutomationPointsCommand
+    ///
+    /// # Arguments
+    /// * `parameter` - Automated parameter
+    /// * `description` - Edit shown in the history (e.g. "Move Automation Point")
+    /// * `points` - Every point of the lane after the edit (empty removes the lane)
+    pub fn new(
+        parameter: AutomationParameter,
+        description: impl Into<String>,
+        points: Vec<AutomationPoint>,
+    ) -> Self {
+        Self {
+            parameter,
+            description: description.into(),
+            new_points: points,
+            old_points: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetAutomationPointsCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_points = state
+            .automation_lanes
+            .get(&self.parameter)
+            .map(|lane| lane.points().to_vec())
+            .unwrap_or_default();
+        state.set_automation_points(self.parameter, self.new_points.clone())?;
+        self.old_points = Some(old_points);
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_points = self
+            .old_points
+            .clone()
+            .ok_or_else(|| CommandError::UndoFailed("No previous points stored".into()))?;
+        state
+            .set_automation_points(self.parameter, old_points)
+            .map_err(|e| CommandError::UndoFailed(e.to_string()))
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automation::CurveShape;
+    use crate::messaging::channels::create_command_channel;
+    use crate::messaging::command::Command;
+    use ringbuf::traits::Consumer;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_automation_points_undo() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        let point = AutomationPoint {
+            time_beats: 1.0,
+            value: 0.25,
+            curve: CurveShape::Step,
+        };
+
+        let mut command =
+            SetAutomationPointsCommand::new(AutomationParameter::Volume, "Add Point", vec![point]);
+        command.execute(&mut state).unwrap();
+        assert_eq!(
+            state.automation_lanes[&AutomationParameter::Volume].points(),
+            &[point]
+        );
+        assert!(matches!(rx.try_pop(), Some(Command::SetAutomation(lanes)) if lanes.len() == 1));
+
+        // Undoing the first point removes the lane
+        command.undo(&mut state).unwrap();
+        assert!(state.automation_lanes.is_empty());
+        assert!(matches!(rx.try_pop(), Some(Command::SetAutomation(lanes)) if lanes.is_empty()));
+        assert_eq!(command.description(), "Add Point");
+
+        let invalid = AutomationPoint {
+            time_beats: f64::NAN,
+            ..point
+        };
+        let mut command = SetAutomationPointsCommand::new(
+            AutomationParameter::Volume,
+            "Add Point",
+            vec![invalid],
+        );
+        assert!(command.execute(&mut state).is_err());
+    }
+}
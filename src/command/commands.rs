@@ -1,13 +1,18 @@
 // Concrete command implementations
 
+use crate::command::patch::{PatchSlot, SynthPatch};
 use crate::command::state::DawState;
 use crate::command::trait_def::{CommandError, CommandResult, UndoableCommand};
 use crate::messaging::command::Command;
+use crate::project::preset::SynthPreset;
 use crate::synth::envelope::AdsrParams;
-use crate::synth::filter::FilterParams;
-use crate::synth::lfo::LfoParams;
+use crate::synth::filter::{FilterModParams, FilterParams};
+use crate::synth::lfo::{LFO_COUNT, LfoParams};
 use crate::synth::modulation::ModRouting;
-use crate::synth::oscillator::WaveformType;
+use crate::synth::noise::NoiseParams;
+use crate::synth::oscillator::{
+    AntiAliasing, CrossModParams, Oscillator2Params, UnisonParams, WaveformType,
+};
 use crate::synth::poly_mode::PolyMode;
 use crate::synth::portamento::PortamentoParams;
 use crate::synth::voice_manager::VoiceMode;
@@ -158,6 +163,412 @@ impl UndoableCommand for SetWaveformCommand {
     }
 }
 
+/// Command to set the second oscillator
+///
+/// This command changes the second oscillator of all voices and sends the update to the audio thread.
+/// It stores the old parameters to enable undo.
+pub struct SetOscillator2Command {
+    new_params: Oscillator2Params,
+    old_params: Option<Oscillator2Params>,
+}
+
+impl SetOscillator2Command {
+    /// Create a new SetOscillator2Command
+    ///
+    /// # Arguments
+    /// * `params` - The new second oscillator parameters
+    pub fn new(params: Oscillator2Params) -> Self {
+        Self {
+            new_params: params,
+            old_params: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetOscillator2Command {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        // Store old value for undo
+        self.old_params = Some(state.oscillator2);
+
+        // Update state
+        state.oscillator2 = self.new_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetOscillator2(self.new_params)) {
+            return Err(CommandError::ExecutionFailed(
+                "Failed to send Oscillator 2 command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_params = self.old_params.ok_or_else(|| {
+            CommandError::UndoFailed("No previous oscillator 2 parameters stored".into())
+        })?;
+
+        // Restore old value
+        state.oscillator2 = old_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetOscillator2(old_params)) {
+            return Err(CommandError::UndoFailed(
+                "Failed to send Oscillator 2 command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Set Oscillator 2 ({:?} {:+}st {:+.0}ct mix:{:.2})",
+            self.new_params.waveform,
+            self.new_params.coarse,
+            self.new_params.fine,
+            self.new_params.mix
+        )
+    }
+
+    fn can_merge_with(&self, other: &dyn UndoableCommand) -> bool {
+        // We can merge with other SetOscillator2Command to avoid cluttering history
+        // when user adjusts the detune and mix sliders
+        other.description().starts_with("Set Oscillator 2")
+    }
+
+    fn merge_with(&mut self, other: Box<dyn UndoableCommand>) -> CommandResult<()> {
+        // Downcast to SetOscillator2Command
+        let other_any = Box::into_raw(other) as *mut SetOscillator2Command;
+
+        unsafe {
+            let other_cmd = Box::from_raw(other_any);
+            // Update to the new value but keep the original old_params
+            self.new_params = other_cmd.new_params;
+        }
+
+        Ok(())
+    }
+}
+
+/// Command to set the band-limiting method of the oscillators
+///
+/// This command switches the oscillators between PolyBLEP and wavetables and sends the update to the audio thread.
+/// It stores the old method to enable undo.
+pub struct SetAntiAliasingCommand {
+    new_anti_aliasing: AntiAliasing,
+    old_anti_aliasing: Option<AntiAliasing>,
+}
+
+impl SetAntiAliasingCommand {
+    /// Create a new SetAntiAliasingCommand
+    ///
+    /// # Arguments
+    /// * `anti_aliasing` - The new band-limiting method
+    pub fn new(anti_aliasing: AntiAliasing) -> Self {
+        Self {
+            new_anti_aliasing: anti_aliasing,
+            old_anti_aliasing: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetAntiAliasingCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        // Store old value for undo
+        self.old_anti_aliasing = Some(state.anti_aliasing);
+
+        // Update state
+        state.anti_aliasing = self.new_anti_aliasing;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetAntiAliasing(self.new_anti_aliasing)) {
+            return Err(CommandError::ExecutionFailed(
+                "Failed to send anti-aliasing command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_anti_aliasing = self.old_anti_aliasing.ok_or_else(|| {
+            CommandError::UndoFailed("No previous anti-aliasing method stored".into())
+        })?;
+
+        // Restore old value
+        state.anti_aliasing = old_anti_aliasing;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetAntiAliasing(old_anti_aliasing)) {
+            return Err(CommandError::UndoFailed(
+                "Failed to send anti-aliasing command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Set Anti-Aliasing to {:?}", self.new_anti_aliasing)
+    }
+}
+
+/// Command to set the unison stack
+///
+/// This command changes the unison voices, detune and spread of the synth notes and sends the update to the audio thread.
+/// It stores the old parameters to enable undo.
+pub struct SetUnisonCommand {
+    new_params: UnisonParams,
+    old_params: Option<UnisonParams>,
+}
+
+impl SetUnisonCommand {
+    /// Create a new SetUnisonCommand
+    ///
+    /// # Arguments
+    /// * `params` - The new unison parameters
+    pub fn new(params: UnisonParams) -> Self {
+        Self {
+            new_params: params,
+            old_params: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetUnisonCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        // Store old value for undo
+        self.old_params = Some(state.unison);
+
+        // Update state
+        state.unison = self.new_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetUnison(self.new_params)) {
+            return Err(CommandError::ExecutionFailed(
+                "Failed to send unison command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_params = self.old_params.ok_or_else(|| {
+            CommandError::UndoFailed("No previous unison parameters stored".into())
+        })?;
+
+        // Restore old value
+        state.unison = old_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetUnison(old_params)) {
+            return Err(CommandError::UndoFailed(
+                "Failed to send unison command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Set Unison ({} voices {:.0}ct spread:{:.2})",
+            self.new_params.voices, self.new_params.detune, self.new_params.spread
+        )
+    }
+
+    fn can_merge_with(&self, other: &dyn UndoableCommand) -> bool {
+        // We can merge with other SetUnisonCommand to avoid cluttering history
+        // when user adjusts the detune and spread sliders
+        other.description().starts_with("Set Unison")
+    }
+
+    fn merge_with(&mut self, other: Box<dyn UndoableCommand>) -> CommandResult<()> {
+        // Downcast to SetUnisonCommand
+        let other_any = Box::into_raw(other) as *mut SetUnisonCommand;
+
+        unsafe {
+            let other_cmd = Box::from_raw(other_any);
+            // Update to the new value but keep the original old_params
+            self.new_params = other_cmd.new_params;
+        }
+
+        Ok(())
+    }
+}
+
+/// Command to set the oscillator cross-modulation
+///
+/// This command changes the FM and ring modulation of the first oscillator by the second and sends the update to the audio thread.
+/// It stores the old parameters to enable undo.
+pub struct SetCrossModCommand {
+    new_params: CrossModParams,
+    old_params: Option<CrossModParams>,
+}
+
+impl SetCrossModCommand {
+    /// Create a new SetCrossModCommand
+    ///
+    /// # Arguments
+    /// * `params` - The new cross-modulation parameters
+    pub fn new(params: CrossModParams) -> Self {
+        Self {
+            new_params: params,
+            old_params: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetCrossModCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        // Store old value for undo
+        self.old_params = Some(state.cross_mod);
+
+        // Update state
+        state.cross_mod = self.new_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetCrossMod(self.new_params)) {
+            return Err(CommandError::ExecutionFailed(
+                "Failed to send cross-mod command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_params = self.old_params.ok_or_else(|| {
+            CommandError::UndoFailed("No previous cross-mod parameters stored".into())
+        })?;
+
+        // Restore old value
+        state.cross_mod = old_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetCrossMod(old_params)) {
+            return Err(CommandError::UndoFailed(
+                "Failed to send cross-mod command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Set Cross-Mod (fm:{:.2} ring:{:.2})",
+            self.new_params.fm, self.new_params.ring
+        )
+    }
+
+    fn can_merge_with(&self, other: &dyn UndoableCommand) -> bool {
+        // We can merge with other SetCrossModCommand to avoid cluttering history
+        // when user adjusts the FM and ring sliders
+        other.description().starts_with("Set Cross-Mod")
+    }
+
+    fn merge_with(&mut self, other: Box<dyn UndoableCommand>) -> CommandResult<()> {
+        // Downcast to SetCrossModCommand
+        let other_any = Box::into_raw(other) as *mut SetCrossModCommand;
+
+        unsafe {
+            let other_cmd = Box::from_raw(other_any);
+            // Update to the new value but keep the original old_params
+            self.new_params = other_cmd.new_params;
+        }
+
+        Ok(())
+    }
+}
+
+/// Command to set the noise source
+///
+/// This command changes the noise type and level of the synth voices and sends the update to the audio thread.
+/// It stores the old parameters to enable undo.
+pub struct SetNoiseCommand {
+    new_params: NoiseParams,
+    old_params: Option<NoiseParams>,
+}
+
+impl SetNoiseCommand {
+    /// Create a new SetNoiseCommand
+    ///
+    /// # Arguments
+    /// * `params` - The new noise parameters
+    pub fn new(params: NoiseParams) -> Self {
+        Self {
+            new_params: params,
+            old_params: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetNoiseCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        // Store old value for undo
+        self.old_params = Some(state.noise);
+
+        // Update state
+        state.noise = self.new_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetNoise(self.new_params)) {
+            return Err(CommandError::ExecutionFailed(
+                "Failed to send noise command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_params = self.old_params.ok_or_else(|| {
+            CommandError::UndoFailed("No previous noise parameters stored".into())
+        })?;
+
+        // Restore old value
+        state.noise = old_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetNoise(old_params)) {
+            return Err(CommandError::UndoFailed(
+                "Failed to send noise command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Set Noise ({:?} level:{:.2})",
+            self.new_params.noise_type, self.new_params.level
+        )
+    }
+
+    fn can_merge_with(&self, other: &dyn UndoableCommand) -> bool {
+        // We can merge with other SetNoiseCommand to avoid cluttering history
+        // when user adjusts the noise level
+        other.description().starts_with("Set Noise")
+    }
+
+    fn merge_with(&mut self, other: Box<dyn UndoableCommand>) -> CommandResult<()> {
+        // Downcast to SetNoiseCommand
+        let other_any = Box::into_raw(other) as *mut SetNoiseCommand;
+
+        unsafe {
+            let other_cmd = Box::from_raw(other_any);
+            // Update to the new value but keep the original old_params
+            self.new_params = other_cmd.new_params;
+        }
+
+        Ok(())
+    }
+}
+
 /// Command to set ADSR envelope parameters
 ///
 /// This command changes the ADSR parameters for all voices and sends the update to the audio thread.
@@ -248,6 +659,94 @@ impl UndoableCommand for SetAdsrCommand {
     }
 }
 
+/// Command to set the modulation envelope
+///
+/// This command changes the modulation envelope of all voices (a mod matrix source) and sends the update to the audio thread.
+/// It stores the old parameters to enable undo.
+pub struct SetModEnvelopeCommand {
+    new_params: AdsrParams,
+    old_params: Option<AdsrParams>,
+}
+
+impl SetModEnvelopeCommand {
+    /// Create a new SetModEnvelopeCommand
+    ///
+    /// # Arguments
+    /// * `params` - The new envelope parameters
+    pub fn new(params: AdsrParams) -> Self {
+        Self {
+            new_params: params,
+            old_params: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetModEnvelopeCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        // Store old value for undo
+        self.old_params = Some(state.mod_envelope);
+
+        // Update state
+        state.mod_envelope = self.new_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetModEnvelope(self.new_params)) {
+            return Err(CommandError::ExecutionFailed(
+                "Failed to send mod envelope command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_params = self.old_params.ok_or_else(|| {
+            CommandError::UndoFailed("No previous mod envelope parameters stored".into())
+        })?;
+
+        // Restore old value
+        state.mod_envelope = old_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetModEnvelope(old_params)) {
+            return Err(CommandError::UndoFailed(
+                "Failed to send mod envelope command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Set Mod Envelope (A:{:.3}s D:{:.3}s S:{:.2} R:{:.3}s)",
+            self.new_params.attack,
+            self.new_params.decay,
+            self.new_params.sustain,
+            self.new_params.release
+        )
+    }
+
+    fn can_merge_with(&self, other: &dyn UndoableCommand) -> bool {
+        // We can merge with other SetModEnvelopeCommand to avoid cluttering history
+        // when user adjusts the envelope sliders
+        other.description().starts_with("Set Mod Envelope")
+    }
+
+    fn merge_with(&mut self, other: Box<dyn UndoableCommand>) -> CommandResult<()> {
+        // Downcast to SetModEnvelopeCommand
+        let other_any = Box::into_raw(other) as *mut SetModEnvelopeCommand;
+
+        unsafe {
+            let other_cmd = Box::from_raw(other_any);
+            // Update to the new value but keep the original old_params
+            self.new_params = other_cmd.new_params;
+        }
+
+        Ok(())
+    }
+}
+
 /// Command to set LFO parameters
 ///
 /// This command changes the LFO parameters for all voices and sends the update to the audio thread.
@@ -278,10 +777,104 @@ impl UndoableCommand for SetLfoCommand {
         // Update state
         state.lfo = self.new_params;
 
-        // Send to audio thread
-        if !state.send_to_audio(Command::SetLfo(self.new_params)) {
+        // Send to audio thread
+        if !state.send_lfo(self.new_params) {
+            return Err(CommandError::ExecutionFailed(
+                "Failed to send LFO command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_params = self
+            .old_params
+            .ok_or_else(|| CommandError::UndoFailed("No previous LFO parameters stored".into()))?;
+
+        // Restore old value
+        state.lfo = old_params;
+
+        // Send to audio thread
+        if !state.send_lfo(old_params) {
+            return Err(CommandError::UndoFailed(
+                "Failed to send LFO command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Set LFO ({:?} {:.1}Hz depth:{:.2} → {:?})",
+            self.new_params.waveform,
+            self.new_params.rate,
+            self.new_params.depth,
+            self.new_params.destination
+        )
+    }
+
+    fn can_merge_with(&self, other: &dyn UndoableCommand) -> bool {
+        // We can merge with other SetLfoCommand to avoid cluttering history
+        // when user adjusts LFO parameters
+        other.description().starts_with("Set LFO")
+    }
+
+    fn merge_with(&mut self, other: Box<dyn UndoableCommand>) -> CommandResult<()> {
+        // Downcast to SetLfoCommand
+        let other_any = Box::into_raw(other) as *mut SetLfoCommand;
+
+        unsafe {
+            let other_cmd = Box::from_raw(other_any);
+            // Update to the new value but keep the original old_params
+            self.new_params = other_cmd.new_params;
+        }
+
+        Ok(())
+    }
+}
+
+/// Command to set the parameters of a matrix LFO (LFO 2 and up)
+///
+/// `index` is the `ModSource::Lfo` index of the LFO (1..LFO_COUNT); LFO 1 is
+/// set by `SetLfoCommand`.
+pub struct SetModLfoCommand {
+    index: u8,
+    new_params: LfoParams,
+    old_params: Option<LfoParams>,
+}
+
+impl SetModLfoCommand {
+    pub fn new(index: u8, params: LfoParams) -> Self {
+        Self {
+            index,
+            new_params: params,
+            old_params: None,
+        }
+    }
+
+    /// Slot of the LFO in `DawState::mod_lfos`
+    fn slot(&self) -> CommandResult<usize> {
+        (self.index as usize)
+            .checked_sub(1)
+            .filter(|slot| *slot < LFO_COUNT - 1)
+            .ok_or_else(|| CommandError::InvalidState(format!("Not a matrix LFO: {}", self.index)))
+    }
+}
+
+impl UndoableCommand for SetModLfoCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let slot = self.slot()?;
+        self.old_params = Some(state.mod_lfos[slot]);
+        state.mod_lfos[slot] = self.new_params;
+
+        if !state.send_to_audio(Command::SetModLfo {
+            index: self.index,
+            params: self.new_params,
+        }) {
             return Err(CommandError::ExecutionFailed(
-                "Failed to send LFO command to audio thread (ringbuffer full)".into(),
+                "Failed to send SetModLfo to audio thread (ringbuffer full)".into(),
             ));
         }
 
@@ -289,17 +882,19 @@ impl UndoableCommand for SetLfoCommand {
     }
 
     fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let slot = self.slot()?;
         let old_params = self
             .old_params
             .ok_or_else(|| CommandError::UndoFailed("No previous LFO parameters stored".into()))?;
 
-        // Restore old value
-        state.lfo = old_params;
+        state.mod_lfos[slot] = old_params;
 
-        // Send to audio thread
-        if !state.send_to_audio(Command::SetLfo(old_params)) {
+        if !state.send_to_audio(Command::SetModLfo {
+            index: self.index,
+            params: old_params,
+        }) {
             return Err(CommandError::UndoFailed(
-                "Failed to send LFO command to audio thread (ringbuffer full)".into(),
+                "Failed to send SetModLfo (undo) to audio thread".into(),
             ));
         }
 
@@ -307,24 +902,25 @@ impl UndoableCommand for SetLfoCommand {
     }
 
     fn description(&self) -> String {
+        // Not "Set LFO ...": `SetLfoCommand` would merge with it
         format!(
-            "Set LFO ({:?} {:.1}Hz depth:{:.2} → {:?})",
+            "Set Mod LFO {} ({:?} {:.1}Hz depth:{:.2})",
+            self.index + 1,
             self.new_params.waveform,
             self.new_params.rate,
-            self.new_params.depth,
-            self.new_params.destination
+            self.new_params.depth
         )
     }
 
     fn can_merge_with(&self, other: &dyn UndoableCommand) -> bool {
-        // We can merge with other SetLfoCommand to avoid cluttering history
-        // when user adjusts LFO parameters
-        other.description().starts_with("Set LFO")
+        // Only updates of the same LFO
+        other
+            .description()
+            .starts_with(&format!("Set Mod LFO {} (", self.index + 1))
     }
 
     fn merge_with(&mut self, other: Box<dyn UndoableCommand>) -> CommandResult<()> {
-        // Downcast to SetLfoCommand
-        let other_any = Box::into_raw(other) as *mut SetLfoCommand;
+        let other_any = Box::into_raw(other) as *mut SetModLfoCommand;
 
         unsafe {
             let other_cmd = Box::from_raw(other_any);
@@ -615,7 +1211,7 @@ impl UndoableCommand for SetFilterCommand {
         state.filter = self.new_params;
 
         // Send to audio thread
-        if !state.send_to_audio(Command::SetFilter(self.new_params)) {
+        if !state.send_filter(self.new_params) {
             return Err(CommandError::ExecutionFailed(
                 "Failed to send Filter command to audio thread (ringbuffer full)".into(),
             ));
@@ -633,7 +1229,7 @@ impl UndoableCommand for SetFilterCommand {
         state.filter = old_params;
 
         // Send to audio thread
-        if !state.send_to_audio(Command::SetFilter(old_params)) {
+        if !state.send_filter(old_params) {
             return Err(CommandError::UndoFailed(
                 "Failed to send Filter command to audio thread (ringbuffer full)".into(),
             ));
@@ -651,8 +1247,8 @@ impl UndoableCommand for SetFilterCommand {
 
     fn can_merge_with(&self, other: &dyn UndoableCommand) -> bool {
         // We can merge with other SetFilterCommand to avoid cluttering history
-        // when user adjusts filter parameters
-        other.description().starts_with("Set Filter")
+        // when user adjusts filter parameters (not with "Set Filter Mod")
+        other.description().starts_with("Set Filter (")
     }
 
     fn merge_with(&mut self, other: Box<dyn UndoableCommand>) -> CommandResult<()> {
@@ -669,6 +1265,91 @@ impl UndoableCommand for SetFilterCommand {
     }
 }
 
+/// Command to set the filter envelope amount, key tracking and drive
+///
+/// This command changes the filter modulation of all voices and sends the update to the audio thread.
+/// It stores the old parameters to enable undo.
+pub struct SetFilterModCommand {
+    new_params: FilterModParams,
+    old_params: Option<FilterModParams>,
+}
+
+impl SetFilterModCommand {
+    /// Create a new SetFilterModCommand
+    ///
+    /// # Arguments
+    /// * `params` - The new filter modulation parameters
+    pub fn new(params: FilterModParams) -> Self {
+        Self {
+            new_params: params,
+            old_params: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetFilterModCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        // Store old value for undo
+        self.old_params = Some(state.filter_mod);
+
+        // Update state
+        state.filter_mod = self.new_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetFilterMod(self.new_params)) {
+            return Err(CommandError::ExecutionFailed(
+                "Failed to send filter mod command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_params = self.old_params.ok_or_else(|| {
+            CommandError::UndoFailed("No previous filter mod parameters stored".into())
+        })?;
+
+        // Restore old value
+        state.filter_mod = old_params;
+
+        // Send to audio thread
+        if !state.send_to_audio(Command::SetFilterMod(old_params)) {
+            return Err(CommandError::UndoFailed(
+                "Failed to send filter mod command to audio thread (ringbuffer full)".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Set Filter Mod (env:{:+.1}oct key:{:.2} drive:{:.2})",
+            self.new_params.envelope_amount, self.new_params.key_tracking, self.new_params.drive
+        )
+    }
+
+    fn can_merge_with(&self, other: &dyn UndoableCommand) -> bool {
+        // We can merge with other SetFilterModCommand to avoid cluttering history
+        // when user adjusts the sliders
+        other.description().starts_with("Set Filter Mod")
+    }
+
+    fn merge_with(&mut self, other: Box<dyn UndoableCommand>) -> CommandResult<()> {
+        // Downcast to SetFilterModCommand
+        let other_any = Box::into_raw(other) as *mut SetFilterModCommand;
+
+        unsafe {
+            let other_cmd = Box::from_raw(other_any);
+            // Update to the new value but keep the original old_params
+            self.new_params = other_cmd.new_params;
+        }
+
+        Ok(())
+    }
+}
+
 /// Command to set the voice mode (Synth or Sampler)
 pub struct SetVoiceModeCommand {
     new_mode: VoiceMode,
@@ -714,10 +1395,160 @@ impl UndoableCommand for SetVoiceModeCommand {
     }
 }
 
+/// Command to switch between the A/B comparison slots
+///
+/// The live sound is stored into the active slot and the other slot is applied
+/// (an empty slot starts as a copy of the live sound).
+#[derive(Default)]
+pub struct SwitchPatchSlotCommand {
+    /// Stored patch of the slot switched to, before the switch
+    old_target: Option<Option<SynthPatch>>,
+}
+
+impl SwitchPatchSlotCommand {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn switch(state: &mut DawState) -> CommandResult<()> {
+        let from = state.ab_comparison.active();
+        let to = from.other();
+        let live = SynthPatch::from_state(state);
+        let patch = state.ab_comparison.slot(to).copied().unwrap_or(live);
+
+        state.ab_comparison.set_slot(from, Some(live));
+        state.ab_comparison.set_active(to);
+        patch.apply(state)
+    }
+}
+
+impl UndoableCommand for SwitchPatchSlotCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let to = state.ab_comparison.active().other();
+        self.old_target = Some(state.ab_comparison.slot(to).copied());
+        Self::switch(state)
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_target = self
+            .old_target
+            .ok_or_else(|| CommandError::UndoFailed("Patch slot switch was not executed".into()))?;
+        // Switching back stores the live sound into the slot switched to: put
+        // its previous content back (it may have been empty)
+        let target = state.ab_comparison.active();
+        Self::switch(state)?;
+        state.ab_comparison.set_slot(target, old_target);
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        "Switch A/B Patch".to_string()
+    }
+}
+
+/// Command to copy the sound of an A/B comparison slot into the other one
+///
+/// Copying into the active slot applies the copied sound.
+pub struct CopyPatchSlotCommand {
+    from: PatchSlot,
+    to: PatchSlot,
+    old_patch: Option<Option<SynthPatch>>,
+}
+
+impl CopyPatchSlotCommand {
+    pub fn new(from: PatchSlot, to: PatchSlot) -> Self {
+        Self {
+            from,
+            to,
+            old_patch: None,
+        }
+    }
+
+    /// Sound of a slot (the live sound for the active slot)
+    fn patch(state: &DawState, slot: PatchSlot) -> Option<SynthPatch> {
+        if slot == state.ab_comparison.active() {
+            Some(SynthPatch::from_state(state))
+        } else {
+            state.ab_comparison.slot(slot).copied()
+        }
+    }
+
+    fn write(
+        state: &mut DawState,
+        slot: PatchSlot,
+        patch: Option<SynthPatch>,
+    ) -> CommandResult<()> {
+        if slot == state.ab_comparison.active() {
+            match patch {
+                Some(patch) => patch.apply(state),
+                None => Ok(()),
+            }
+        } else {
+            state.ab_comparison.set_slot(slot, patch);
+            Ok(())
+        }
+    }
+}
+
+impl UndoableCommand for CopyPatchSlotCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let patch = Self::patch(state, self.from).ok_or_else(|| {
+            CommandError::ExecutionFailed(format!("Patch slot {:?} is empty", self.from))
+        })?;
+        self.old_patch = Some(Self::patch(state, self.to));
+        Self::write(state, self.to, Some(patch))
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_patch = self
+            .old_patch
+            .ok_or_else(|| CommandError::UndoFailed("Patch slot copy was not executed".into()))?;
+        Self::write(state, self.to, old_patch)
+    }
+
+    fn description(&self) -> String {
+        format!("Copy Patch {:?} to {:?}", self.from, self.to)
+    }
+}
+
+/// Command to load a synth preset (replaces the whole synth sound)
+pub struct LoadSynthPresetCommand {
+    preset: SynthPreset,
+    old_patch: Option<SynthPatch>,
+}
+
+impl LoadSynthPresetCommand {
+    pub fn new(preset: SynthPreset) -> Self {
+        Self {
+            preset,
+            old_patch: None,
+        }
+    }
+}
+
+impl UndoableCommand for LoadSynthPresetCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        self.old_patch = Some(SynthPatch::from_state(state));
+        self.preset.apply(state)
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_patch = self
+            .old_patch
+            .ok_or_else(|| CommandError::UndoFailed("Preset was not loaded".into()))?;
+        old_patch.apply(state)
+    }
+
+    fn description(&self) -> String {
+        format!("Load Preset '{}'", self.preset.name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::messaging::channels::create_command_channel;
+    use crate::synth::noise::NoiseType;
     use std::sync::{Arc, Mutex};
 
     fn create_test_state() -> DawState {
@@ -764,6 +1595,170 @@ mod tests {
         assert_eq!(state.waveform, WaveformType::Sine);
     }
 
+    #[test]
+    fn test_set_oscillator2_command() {
+        let mut state = create_test_state();
+        let detuned = Oscillator2Params::new(WaveformType::Square, -12, 7.0, 0.5);
+        let mut cmd = SetOscillator2Command::new(detuned);
+        cmd.merge_with(Box::new(SetOscillator2Command::new(Oscillator2Params {
+            mix: 0.7,
+            ..detuned
+        })))
+        .unwrap();
+
+        // Execute
+        assert_eq!(state.oscillator2, Oscillator2Params::default());
+        cmd.execute(&mut state).unwrap();
+        assert_eq!(state.oscillator2.coarse, -12);
+        assert_eq!(state.oscillator2.mix, 0.7);
+        assert_eq!(
+            cmd.description(),
+            "Set Oscillator 2 (Square -12st +7ct mix:0.70)"
+        );
+
+        // Undo
+        cmd.undo(&mut state).unwrap();
+        assert_eq!(state.oscillator2, Oscillator2Params::default());
+    }
+
+    #[test]
+    fn test_set_unison_command() {
+        let mut state = create_test_state();
+        let mut cmd = SetUnisonCommand::new(UnisonParams::new(5, 30.0, 0.8));
+
+        // Execute
+        cmd.execute(&mut state).unwrap();
+        assert_eq!(state.unison.voices, 5);
+        assert_eq!(cmd.description(), "Set Unison (5 voices 30ct spread:0.80)");
+
+        // Undo
+        cmd.undo(&mut state).unwrap();
+        assert_eq!(state.unison, UnisonParams::default());
+    }
+
+    #[test]
+    fn test_set_anti_aliasing_command() {
+        let mut state = create_test_state();
+        let mut cmd = SetAntiAliasingCommand::new(AntiAliasing::Wavetable);
+
+        // Execute
+        cmd.execute(&mut state).unwrap();
+        assert_eq!(state.anti_aliasing, AntiAliasing::Wavetable);
+
+        // Undo
+        cmd.undo(&mut state).unwrap();
+        assert_eq!(state.anti_aliasing, AntiAliasing::PolyBlep);
+    }
+
+    #[test]
+    fn test_set_cross_mod_command() {
+        let mut state = create_test_state();
+        let mut cmd = SetCrossModCommand::new(CrossModParams::new(0.4, 0.25));
+
+        // Execute
+        cmd.execute(&mut state).unwrap();
+        assert_eq!(state.cross_mod.fm, 0.4);
+        assert_eq!(cmd.description(), "Set Cross-Mod (fm:0.40 ring:0.25)");
+
+        // Merging keeps the first old value
+        let next = SetCrossModCommand::new(CrossModParams::new(0.6, 0.25));
+        assert!(cmd.can_merge_with(&next));
+        cmd.merge_with(Box::new(next)).unwrap();
+
+        // Undo
+        cmd.undo(&mut state).unwrap();
+        assert_eq!(state.cross_mod, CrossModParams::default());
+    }
+
+    #[test]
+    fn test_set_noise_command() {
+        let mut state = create_test_state();
+        let mut cmd = SetNoiseCommand::new(NoiseParams::new(NoiseType::Pink, 0.3));
+
+        // Execute
+        cmd.execute(&mut state).unwrap();
+        assert_eq!(state.noise.noise_type, NoiseType::Pink);
+        assert_eq!(cmd.description(), "Set Noise (Pink level:0.30)");
+
+        // Undo
+        cmd.undo(&mut state).unwrap();
+        assert_eq!(state.noise, NoiseParams::default());
+    }
+
+    #[test]
+    fn test_set_mod_envelope_command() {
+        let mut state = create_test_state();
+        let mut cmd = SetModEnvelopeCommand::new(AdsrParams::new(0.001, 0.4, 0.0, 0.2));
+
+        // Execute
+        cmd.execute(&mut state).unwrap();
+        assert_eq!(state.mod_envelope.decay, 0.4);
+        assert_eq!(
+            cmd.description(),
+            "Set Mod Envelope (A:0.001s D:0.400s S:0.00 R:0.200s)"
+        );
+        // The amp envelope is left alone
+        assert_eq!(state.adsr, AdsrParams::default());
+
+        // Undo
+        cmd.undo(&mut state).unwrap();
+        assert_eq!(state.mod_envelope, AdsrParams::default());
+    }
+
+    #[test]
+    fn test_set_filter_mod_command() {
+        let mut state = create_test_state();
+        let mut cmd = SetFilterModCommand::new(FilterModParams::new(2.0, 0.5, 0.3));
+
+        // Execute
+        cmd.execute(&mut state).unwrap();
+        assert_eq!(state.filter_mod.envelope_amount, 2.0);
+        assert_eq!(
+            cmd.description(),
+            "Set Filter Mod (env:+2.0oct key:0.50 drive:0.30)"
+        );
+        // Kept apart from the filter knobs in the history
+        assert!(!SetFilterCommand::new(FilterParams::default()).can_merge_with(&cmd));
+        assert!(!cmd.can_merge_with(&SetFilterCommand::new(FilterParams::default())));
+
+        // Undo
+        cmd.undo(&mut state).unwrap();
+        assert_eq!(state.filter_mod, FilterModParams::default());
+    }
+
+    #[test]
+    fn test_set_mod_lfo_command() {
+        let mut state = create_test_state();
+        let params = LfoParams::default().with_timing(
+            Some("1/8T".parse().unwrap()),
+            crate::synth::lfo::LfoMode::Free,
+            0.25,
+        );
+        let mut cmd = SetModLfoCommand::new(2, params);
+
+        // Execute: LFO 3 is the second matrix LFO
+        cmd.execute(&mut state).unwrap();
+        assert_eq!(state.mod_lfos[1], params);
+        assert_eq!(state.mod_lfos[0], LfoParams::default());
+        assert_eq!(state.lfo, LfoParams::default());
+
+        // Merges with the same LFO only, never with LFO 1
+        assert!(cmd.can_merge_with(&SetModLfoCommand::new(2, params)));
+        assert!(!cmd.can_merge_with(&SetModLfoCommand::new(1, params)));
+        assert!(!SetLfoCommand::new(params).can_merge_with(&cmd));
+
+        // Undo
+        cmd.undo(&mut state).unwrap();
+        assert_eq!(state.mod_lfos[1], LfoParams::default());
+
+        // LFO 1 is not a matrix LFO
+        assert!(
+            SetModLfoCommand::new(0, params)
+                .execute(&mut state)
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_volume_command_merge() {
         let mut cmd1 = SetVolumeCommand::new(0.5);
@@ -788,4 +1783,98 @@ mod tests {
         let cmd = SetWaveformCommand::new(WaveformType::Saw);
         assert_eq!(cmd.description(), "Set Waveform to Saw");
     }
+
+    #[test]
+    fn test_switch_patch_slot_command() {
+        let mut state = create_test_state();
+        let mut cmd = SetWaveformCommand::new(WaveformType::Square);
+        cmd.execute(&mut state).unwrap();
+
+        // B starts as a copy of A
+        let mut switch = SwitchPatchSlotCommand::new();
+        switch.execute(&mut state).unwrap();
+        assert_eq!(state.ab_comparison.active(), PatchSlot::B);
+        assert_eq!(state.waveform, WaveformType::Square);
+
+        // Edit B, then go back to A and forth to B
+        SetWaveformCommand::new(WaveformType::Saw)
+            .execute(&mut state)
+            .unwrap();
+        state.mod_routings[0].amount = 0.5;
+        SwitchPatchSlotCommand::new().execute(&mut state).unwrap();
+        assert_eq!(state.ab_comparison.active(), PatchSlot::A);
+        assert_eq!(state.waveform, WaveformType::Square);
+        assert_eq!(state.mod_routings[0].amount, 0.0);
+
+        let mut back = SwitchPatchSlotCommand::new();
+        back.execute(&mut state).unwrap();
+        assert_eq!(state.waveform, WaveformType::Saw);
+        assert_eq!(state.mod_routings[0].amount, 0.5);
+
+        // Undo is the switch in the other direction
+        back.undo(&mut state).unwrap();
+        assert_eq!(state.ab_comparison.active(), PatchSlot::A);
+        assert_eq!(state.waveform, WaveformType::Square);
+    }
+
+    #[test]
+    fn test_undo_first_switch_empties_slot() {
+        let mut state = create_test_state();
+        let mut switch = SwitchPatchSlotCommand::new();
+        switch.execute(&mut state).unwrap();
+        assert!(state.ab_comparison.is_filled(PatchSlot::A));
+
+        switch.undo(&mut state).unwrap();
+        assert_eq!(state.ab_comparison.active(), PatchSlot::A);
+        assert!(!state.ab_comparison.is_filled(PatchSlot::B));
+    }
+
+    #[test]
+    fn test_copy_patch_slot_command() {
+        let mut state = create_test_state();
+
+        // Nothing to copy from an empty slot
+        let mut copy = CopyPatchSlotCommand::new(PatchSlot::B, PatchSlot::A);
+        assert!(copy.execute(&mut state).is_err());
+
+        SetWaveformCommand::new(WaveformType::Square)
+            .execute(&mut state)
+            .unwrap();
+        let mut copy = CopyPatchSlotCommand::new(PatchSlot::A, PatchSlot::B);
+        copy.execute(&mut state).unwrap();
+        assert_eq!(
+            state.ab_comparison.slot(PatchSlot::B).unwrap().waveform,
+            WaveformType::Square
+        );
+        copy.undo(&mut state).unwrap();
+        assert!(!state.ab_comparison.is_filled(PatchSlot::B));
+
+        // Copying into the active slot changes the live sound
+        copy.execute(&mut state).unwrap();
+        SwitchPatchSlotCommand::new().execute(&mut state).unwrap();
+        SetWaveformCommand::new(WaveformType::Saw)
+            .execute(&mut state)
+            .unwrap();
+        let mut copy = CopyPatchSlotCommand::new(PatchSlot::A, PatchSlot::B);
+        copy.execute(&mut state).unwrap();
+        assert_eq!(state.waveform, WaveformType::Square);
+        copy.undo(&mut state).unwrap();
+        assert_eq!(state.waveform, WaveformType::Saw);
+    }
+
+    #[test]
+    fn test_load_synth_preset_command() {
+        let mut state = create_test_state();
+        let preset = crate::project::preset::factory_preset("Mono Bass").unwrap();
+
+        let mut cmd = LoadSynthPresetCommand::new(preset.clone());
+        assert_eq!(cmd.description(), "Load Preset 'Mono Bass'");
+        cmd.execute(&mut state).unwrap();
+        assert_eq!(state.poly_mode, PolyMode::Legato);
+        assert_eq!(state.mod_routings, preset.mod_routings);
+
+        cmd.undo(&mut state).unwrap();
+        assert_eq!(state.poly_mode, PolyMode::Poly);
+        assert!(state.mod_routings.iter().all(|routing| !routing.enabled));
+    }
 }
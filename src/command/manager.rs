@@ -2,11 +2,29 @@
 
 use crate::command::state::DawState;
 use crate::command::trait_def::{CommandError, CommandResult, UndoableCommand};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::collections::VecDeque;
 
 /// Default maximum number of commands to keep in history
 const DEFAULT_MAX_HISTORY: usize = 100;
 
+/// A command of the history and when it was executed
+struct Entry {
+    command: Box<dyn UndoableCommand>,
+    executed_at: DateTime<Utc>,
+}
+
+/// Step of the history, as shown in the history panel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryEntry {
+    pub description: String,
+    /// When the command was first executed
+    pub executed_at: DateTime<Utc>,
+    /// Whether the command is applied (false: undone, can be redone)
+    pub applied: bool,
+}
+
 /// Manages command execution and undo/redo functionality
 ///
 /// The CommandManager maintains two stacks:
@@ -24,10 +42,10 @@ const DEFAULT_MAX_HISTORY: usize = 100;
 /// is removed.
 pub struct CommandManager {
     /// Stack of commands that can be undone (most recent at the back)
-    undo_stack: VecDeque<Box<dyn UndoableCommand>>,
+    undo_stack: VecDeque<Entry>,
 
     /// Stack of commands that can be redone (most recent at the back)
-    redo_stack: VecDeque<Box<dyn UndoableCommand>>,
+    redo_stack: VecDeque<Entry>,
 
     /// Maximum number of commands to keep in history
     max_history: usize,
@@ -70,7 +88,10 @@ impl CommandManager {
         // This requires downcasting or a different approach with type IDs
 
         // Add to undo stack
-        self.undo_stack.push_back(command);
+        self.undo_stack.push_back(Entry {
+            command,
+            executed_at: Utc::now(),
+        });
 
         // Clear redo stack (we're on a new timeline now)
         self.redo_stack.clear();
@@ -92,18 +113,21 @@ impl CommandManager {
     /// - There are no commands to undo
     /// - The undo operation fails
     pub fn undo(&mut self, state: &mut DawState) -> CommandResult<String> {
-        let mut command = self
+        let mut entry = self
             .undo_stack
             .pop_back()
             .ok_or_else(|| CommandError::UndoFailed("Nothing to undo".into()))?;
 
-        let description = command.description();
+        let description = entry.command.description();
 
-        // Undo the command
-        command.undo(state)?;
+        // Undo the command (kept on the undo stack if it fails)
+        if let Err(e) = entry.command.undo(state) {
+            self.undo_stack.push_back(entry);
+            return Err(e);
+        }
 
         // Move to redo stack
-        self.redo_stack.push_back(command);
+        self.redo_stack.push_back(entry);
 
         Ok(description)
     }
@@ -117,18 +141,21 @@ impl CommandManager {
     /// - There are no commands to redo
     /// - The execution fails
     pub fn redo(&mut self, state: &mut DawState) -> CommandResult<String> {
-        let mut command = self
+        let mut entry = self
             .redo_stack
             .pop_back()
             .ok_or_else(|| CommandError::ExecutionFailed("Nothing to redo".into()))?;
 
-        let description = command.description();
+        let description = entry.command.description();
 
-        // Re-execute the command
-        command.execute(state)?;
+        // Re-execute the command (kept on the redo stack if it fails)
+        if let Err(e) = entry.command.execute(state) {
+            self.redo_stack.push_back(entry);
+            return Err(e);
+        }
 
         // Move to undo stack
-        self.undo_stack.push_back(command);
+        self.undo_stack.push_back(entry);
 
         Ok(description)
     }
@@ -145,12 +172,16 @@ impl CommandManager {
 
     /// Get a description of the command that would be undone
     pub fn undo_description(&self) -> Option<String> {
-        self.undo_stack.back().map(|cmd| cmd.description())
+        self.undo_stack
+            .back()
+            .map(|entry| entry.command.description())
     }
 
     /// Get a description of the command that would be redone
     pub fn redo_description(&self) -> Option<String> {
-        self.redo_stack.back().map(|cmd| cmd.description())
+        self.redo_stack
+            .back()
+            .map(|entry| entry.command.description())
     }
 
     /// Clear all command history
@@ -168,6 +199,51 @@ impl CommandManager {
     pub fn redo_count(&self) -> usize {
         self.redo_stack.len()
     }
+
+    /// Every step of the history, oldest first
+    ///
+    /// The applied commands come first (`undo_count()` of them), then the
+    /// undone ones in the order they would be redone.
+    pub fn history(&self) -> Vec<HistoryEntry> {
+        let step = |entry: &Entry, applied| HistoryEntry {
+            description: entry.command.description(),
+            executed_at: entry.executed_at,
+            applied,
+        };
+        self.undo_stack
+            .iter()
+            .map(|entry| step(entry, true))
+            .chain(self.redo_stack.iter().rev().map(|entry| step(entry, false)))
+            .collect()
+    }
+
+    /// Undo or redo until `index` commands of the history are applied
+    /// (0: before the first one)
+    ///
+    /// Returns the number of commands undone or redone.
+    ///
+    /// # Errors
+    /// Returns an error if `index` is past the end of the history or if a
+    /// command fails (the history then stays at the step before it).
+    pub fn jump_to(&mut self, index: usize, state: &mut DawState) -> CommandResult<usize> {
+        let len = self.undo_stack.len() + self.redo_stack.len();
+        if index > len {
+            return Err(CommandError::InvalidState(format!(
+                "History step {} out of range (0-{})",
+                index, len
+            )));
+        }
+        let mut steps = 0;
+        while self.undo_stack.len() > index {
+            self.undo(state)?;
+            steps += 1;
+        }
+        while self.undo_stack.len() < index {
+            self.redo(state)?;
+            steps += 1;
+        }
+        Ok(steps)
+    }
 }
 
 impl Default for CommandManager {
@@ -302,6 +378,44 @@ mod tests {
         assert_eq!(manager.undo_count(), 3);
     }
 
+    #[test]
+    fn test_history_and_jump_to() {
+        let mut manager = CommandManager::new();
+        let mut state = create_test_state();
+
+        for i in 0..4 {
+            manager
+                .execute(Box::new(MockCommand::new(i)), &mut state)
+                .unwrap();
+        }
+        manager.undo(&mut state).unwrap();
+
+        let history = manager.history();
+        let descriptions: Vec<&str> = history
+            .iter()
+            .map(|entry| entry.description.as_str())
+            .collect();
+        assert_eq!(
+            descriptions,
+            [
+                "Set value to 0",
+                "Set value to 1",
+                "Set value to 2",
+                "Set value to 3"
+            ]
+        );
+        assert!(history[2].applied && !history[3].applied);
+
+        // Back to the start, then forward past the undone command
+        assert_eq!(manager.jump_to(0, &mut state).unwrap(), 3);
+        assert_eq!((manager.undo_count(), manager.redo_count()), (0, 4));
+        assert_eq!(manager.jump_to(4, &mut state).unwrap(), 4);
+        assert_eq!(manager.undo_description().unwrap(), "Set value to 3");
+        assert!(manager.history().iter().all(|entry| entry.applied));
+
+        assert!(manager.jump_to(5, &mut state).is_err());
+    }
+
     #[test]
     fn test_undo_with_empty_stack() {
         let mut manager = CommandManager::new();
@@ -0,0 +1,272 @@
+// Mixer commands - Undoable edits of the track strips
+//
+// Tracks are addressed as `MixerTrack`: the synth track or an audio track by id
+// (ids stay valid when other tracks are removed). Each command updates the
+// strip in `DawState` and sends it to the mixer stage of the clip player
+// (`Command::SetTrackStrip`).
+
+use crate::audio::clip_player::MAX_CLIP_GAIN;
+use crate::audio::routing::{ChannelStrip, MixerTrack};
+use crate::command::state::DawState;
+use crate::command::trait_def::{CommandError, CommandResult, UndoableCommand};
+
+/// Apply a change to the strip of a track and send it, returning the previous value
+fn set_strip_field<T: Copy>(
+    state: &mut DawState,
+    track: MixerTrack,
+    value: T,
+    field: fn(&mut ChannelStrip) -> &mut T,
+) -> Result<T, String> {
+    let mut strip = state
+        .track_strip(track)
+        .ok_or_else(|| format!("Track not found: {:?}", track))?;
+    let old = std::mem::replace(field(&mut strip), value);
+    state
+        .set_track_strip(track, strip)
+        .map_err(|e| e.to_string())?;
+    Ok(old)
+}
+
+/// Command to set the gain of a track
+pub struct SetTrackVolumeCommand {
+    track: MixerTrack,
+    new_gain: f32,
+    old_gain: Option<f32>,
+}
+
+impl SetTrackVolumeCommand {
+    /// Create a new SetTrackVolumeCommand
+    ///
+    /// # Arguments
+    /// * `track` - The synth track or an audio track
+    /// * `gain` - The new linear gain (0.0 to `MAX_CLIP_GAIN`)
+    pub fn new(track: MixerTrack, gain: f32) -> Self {
+        Self {
+            track,
+            new_gain: gain.clamp(0.0, MAX_CLIP_GAIN),
+            old_gain: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetTrackVolumeCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old = set_strip_field(state, self.track, self.new_gain, |t| &mut t.gain)
+            .map_err(CommandError::ExecutionFailed)?;
+        self.old_gain = Some(old);
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_gain = self
+            .old_gain
+            .ok_or_else(|| CommandError::UndoFailed("No previous track volume stored".into()))?;
+        set_strip_field(state, self.track, old_gain, |t| &mut t.gain)
+            .map_err(CommandError::UndoFailed)?;
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Set Track Volume to {:.2}", self.new_gain)
+    }
+}
+
+/// Command to set the pan of a track
+pub struct SetTrackPanCommand {
+    track: MixerTrack,
+    new_pan: f32,
+    old_pan: Option<f32>,
+}
+
+impl SetTrackPanCommand {
+    /// Create a new SetTrackPanCommand
+    ///
+    /// # Arguments
+    /// * `track` - The synth track or an audio track
+    /// * `pan` - The new pan (-1.0 left, 0.0 center, 1.0 right)
+    pub fn new(track: MixerTrack, pan: f32) -> Self {
+        Self {
+            track,
+            new_pan: pan.clamp(-1.0, 1.0),
+            old_pan: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetTrackPanCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old = set_strip_field(state, self.track, self.new_pan, |t| &mut t.pan)
+            .map_err(CommandError::ExecutionFailed)?;
+        self.old_pan = Some(old);
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_pan = self
+            .old_pan
+            .ok_or_else(|| CommandError::UndoFailed("No previous track pan stored".into()))?;
+        set_strip_field(state, self.track, old_pan, |t| &mut t.pan)
+            .map_err(CommandError::UndoFailed)?;
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Set Track Pan to {:.2}", self.new_pan)
+    }
+}
+
+/// Command to mute or unmute a track
+pub struct MuteTrackCommand {
+    track: MixerTrack,
+    muted: bool,
+    old_muted: Option<bool>,
+}
+
+impl MuteTrackCommand {
+    /// Create a new MuteTrackCommand
+    ///
+    /// # Arguments
+    /// * `track` - The synth track or an audio track
+    /// * `muted` - Whether the track is muted
+    pub fn new(track: MixerTrack, muted: bool) -> Self {
+        Self {
+            track,
+            muted,
+            old_muted: None,
+        }
+    }
+}
+
+impl UndoableCommand for MuteTrackCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old = set_strip_field(state, self.track, self.muted, |t| &mut t.muted)
+            .map_err(CommandError::ExecutionFailed)?;
+        self.old_muted = Some(old);
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_muted = self
+            .old_muted
+            .ok_or_else(|| CommandError::UndoFailed("No previous track mute stored".into()))?;
+        set_strip_field(state, self.track, old_muted, |t| &mut t.muted)
+            .map_err(CommandError::UndoFailed)?;
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        if self.muted {
+            "Mute Track".to_string()
+        } else {
+            "Unmute Track".to_string()
+        }
+    }
+}
+
+/// Command to solo or unsolo a track
+pub struct SoloTrackCommand {
+    track: MixerTrack,
+    soloed: bool,
+    old_soloed: Option<bool>,
+}
+
+impl SoloTrackCommand {
+    /// Create a new SoloTrackCommand
+    ///
+    /// # Arguments
+    /// * `track` - The synth track or an audio track
+    /// * `soloed` - Whether the track is soloed
+    pub fn new(track: MixerTrack, soloed: bool) -> Self {
+        Self {
+            track,
+            soloed,
+            old_soloed: None,
+        }
+    }
+}
+
+impl UndoableCommand for SoloTrackCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old = set_strip_field(state, self.track, self.soloed, |t| &mut t.soloed)
+            .map_err(CommandError::ExecutionFailed)?;
+        self.old_soloed = Some(old);
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_soloed = self
+            .old_soloed
+            .ok_or_else(|| CommandError::UndoFailed("No previous track solo stored".into()))?;
+        set_strip_field(state, self.track, old_soloed, |t| &mut t.soloed)
+            .map_err(CommandError::UndoFailed)?;
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        if self.soloed {
+            "Solo Track".to_string()
+        } else {
+            "Unsolo Track".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::channels::create_command_channel;
+    use crate::messaging::command::Command;
+    use ringbuf::traits::Consumer;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_track_commands_undo() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        let track_id = state.audio_tracks.add_track("Audio 1".to_string());
+        let track = MixerTrack::Audio(track_id);
+
+        let mut volume = SetTrackVolumeCommand::new(track, 5.0);
+        volume.execute(&mut state).unwrap();
+        assert_eq!(
+            state.audio_tracks.track(track_id).unwrap().gain,
+            MAX_CLIP_GAIN
+        );
+        let Some(Command::SetTrackStrip { track: sent, strip }) = rx.try_pop() else {
+            panic!("Expected the track strip to be sent");
+        };
+        assert_eq!((sent, strip.gain), (track, MAX_CLIP_GAIN));
+
+        let mut pan = SetTrackPanCommand::new(track, -0.5);
+        pan.execute(&mut state).unwrap();
+        let mut mute = MuteTrackCommand::new(track, true);
+        mute.execute(&mut state).unwrap();
+        assert!(state.audio_tracks.track(track_id).unwrap().muted);
+        let mut solo = SoloTrackCommand::new(track, true);
+        solo.execute(&mut state).unwrap();
+        assert!(state.audio_tracks.track(track_id).unwrap().soloed);
+
+        solo.undo(&mut state).unwrap();
+        mute.undo(&mut state).unwrap();
+        pan.undo(&mut state).unwrap();
+        volume.undo(&mut state).unwrap();
+        let strip = state.audio_tracks.track(track_id).unwrap().strip();
+        assert_eq!(strip, ChannelStrip::default());
+
+        // The synth track has a strip too
+        let mut synth_mute = MuteTrackCommand::new(MixerTrack::Synth, true);
+        synth_mute.execute(&mut state).unwrap();
+        assert!(state.synth_strip.muted);
+        synth_mute.undo(&mut state).unwrap();
+        assert!(!state.synth_strip.muted);
+
+        // A removed track cannot be edited
+        state.audio_tracks.remove_track(track_id);
+        assert!(
+            SetTrackVolumeCommand::new(track, 0.5)
+                .execute(&mut state)
+                .is_err()
+        );
+        assert!(volume.undo(&mut state).is_err());
+    }
+}
@@ -7,17 +7,28 @@
 // - UndoableCommand trait: Defines execute(), undo(), description()
 // - CommandManager: Manages undo/redo stacks
 // - Concrete commands: SetVolumeCommand, SetWaveformCommand, etc.
+// - Mixer, sampler, pattern, automation, arrangement and session commands:
+//   track strips, sample slots and note mappings, piano roll, automation lane,
+//   song timeline and clip launcher grid edits
 //
 // Integration with audio thread:
 // - Commands execute on UI thread and update DawState
 // - They send low-level Command messages via ringbuffer to audio thread
 // - Store previous state for undo capability
 
+pub mod arrangement;
+pub mod automation;
 pub mod commands;
 pub mod manager;
+pub mod mixer;
+pub mod patch;
+pub mod pattern;
+pub mod sampler;
+pub mod session;
 pub mod state;
 pub mod trait_def;
 
-pub use manager::CommandManager;
+pub use manager::{CommandManager, HistoryEntry};
+pub use patch::{AbComparison, PatchSlot, SynthPatch};
 pub use state::DawState;
 pub use trait_def::UndoableCommand;
@@ -0,0 +1,186 @@
+// A/B patch comparison - Two snapshots of the synth sound
+//
+// The comparison keeps the sound of the inactive slot while the user edits the
+// active one (the live DAW state). Switching stores the live sound into the
+// active slot and applies the other one; see `SwitchPatchSlotCommand` and
+// `CopyPatchSlotCommand` for the undoable operations.
+
+use crate::command::state::DawState;
+use crate::command::trait_def::{CommandError, CommandResult};
+use crate::messaging::command::Command;
+use crate::synth::envelope::AdsrParams;
+use crate::synth::filter::{FilterModParams, FilterParams};
+use crate::synth::lfo::{LFO_COUNT, LfoParams};
+use crate::synth::modulation::{MAX_ROUTINGS, ModRouting};
+use crate::synth::noise::NoiseParams;
+use crate::synth::oscillator::{
+    AntiAliasing, CrossModParams, Oscillator2Params, UnisonParams, WaveformType,
+};
+use crate::synth::poly_mode::PolyMode;
+use crate::synth::portamento::PortamentoParams;
+use serde::{Deserialize, Serialize};
+
+/// Full synth parameter state held by a comparison slot
+///
+/// Like presets, the master volume and the voice mode are not part of the sound.
+#[derive(Debug, Clone, Copy)]
+pub struct SynthPatch {
+    pub waveform: WaveformType,
+    pub oscillator2: Oscillator2Params,
+    pub unison: UnisonParams,
+    pub mod_lfos: [LfoParams; LFO_COUNT - 1],
+    pub filter_mod: FilterModParams,
+    pub mod_envelope: AdsrParams,
+    pub noise: NoiseParams,
+    pub cross_mod: CrossModParams,
+    pub anti_aliasing: AntiAliasing,
+    pub adsr: AdsrParams,
+    pub lfo: LfoParams,
+    pub filter: FilterParams,
+    pub portamento: PortamentoParams,
+    pub poly_mode: PolyMode,
+    pub mod_routings: [ModRouting; MAX_ROUTINGS],
+}
+
+impl SynthPatch {
+    /// Capture the current synth sound from the DAW state
+    pub fn from_state(state: &DawState) -> Self {
+        Self {
+            waveform: state.waveform,
+            oscillator2: state.oscillator2,
+            unison: state.unison,
+            anti_aliasing: state.anti_aliasing,
+            cross_mod: state.cross_mod,
+            noise: state.noise,
+            mod_envelope: state.mod_envelope,
+            filter_mod: state.filter_mod,
+            mod_lfos: state.mod_lfos,
+            adsr: state.adsr,
+            lfo: state.lfo,
+            filter: state.filter,
+            portamento: state.portamento,
+            poly_mode: state.poly_mode,
+            mod_routings: state.mod_routings,
+        }
+    }
+
+    /// Apply the patch to the DAW state and send it to the audio thread
+    pub fn apply(&self, state: &mut DawState) -> CommandResult<()> {
+        state.waveform = self.waveform;
+        state.oscillator2 = self.oscillator2;
+        state.unison = self.unison;
+        state.anti_aliasing = self.anti_aliasing;
+        state.cross_mod = self.cross_mod;
+        state.noise = self.noise;
+        state.mod_envelope = self.mod_envelope;
+        state.filter_mod = self.filter_mod;
+        state.mod_lfos = self.mod_lfos;
+        state.adsr = self.adsr;
+        state.lfo = self.lfo;
+        state.filter = self.filter;
+        state.portamento = self.portamento;
+        state.poly_mode = self.poly_mode;
+        state.mod_routings = self.mod_routings;
+
+        let commands = [
+            Command::SetWaveform(self.waveform),
+            Command::SetOscillator2(self.oscillator2),
+            Command::SetUnison(self.unison),
+            Command::SetAntiAliasing(self.anti_aliasing),
+            Command::SetCrossMod(self.cross_mod),
+            Command::SetNoise(self.noise),
+            Command::SetModEnvelope(self.mod_envelope),
+            Command::SetFilterMod(self.filter_mod),
+            Command::SetAdsr(self.adsr),
+            Command::SetPortamento(self.portamento),
+            Command::SetPolyMode(self.poly_mode),
+        ];
+        let lfos = self
+            .mod_lfos
+            .iter()
+            .enumerate()
+            .map(|(index, params)| Command::SetModLfo {
+                index: index as u8 + 1,
+                params: *params,
+            });
+        let routings = self
+            .mod_routings
+            .iter()
+            .enumerate()
+            .map(|(index, routing)| Command::SetModRouting {
+                index: index as u8,
+                routing: *routing,
+            });
+        let sent = commands
+            .into_iter()
+            .chain(lfos)
+            .chain(routings)
+            .all(|command| state.send_to_audio(command));
+        // LFO and filter continuous values go through the lock-free parameter block
+        if !sent || !state.send_lfo(self.lfo) || !state.send_filter(self.filter) {
+            return Err(CommandError::ExecutionFailed(
+                "Failed to send patch to audio thread (ringbuffer full)".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Comparison slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum PatchSlot {
+    #[default]
+    A,
+    B,
+}
+
+impl PatchSlot {
+    pub fn other(self) -> Self {
+        match self {
+            PatchSlot::A => PatchSlot::B,
+            PatchSlot::B => PatchSlot::A,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            PatchSlot::A => 0,
+            PatchSlot::B => 1,
+        }
+    }
+}
+
+/// A/B comparison state
+///
+/// The active slot is the live DAW state: its stored patch is only refreshed
+/// when switching away from it. An empty slot is filled with a copy of the
+/// live sound the first time it is selected.
+#[derive(Debug, Clone, Default)]
+pub struct AbComparison {
+    slots: [Option<SynthPatch>; 2],
+    active: PatchSlot,
+}
+
+impl AbComparison {
+    pub fn active(&self) -> PatchSlot {
+        self.active
+    }
+
+    pub fn set_active(&mut self, slot: PatchSlot) {
+        self.active = slot;
+    }
+
+    /// Stored patch of a slot (stale for the active slot, see the struct docs)
+    pub fn slot(&self, slot: PatchSlot) -> Option<&SynthPatch> {
+        self.slots[slot.index()].as_ref()
+    }
+
+    pub fn set_slot(&mut self, slot: PatchSlot, patch: Option<SynthPatch>) {
+        self.slots[slot.index()] = patch;
+    }
+
+    /// Whether a slot holds a sound (the active slot always does)
+    pub fn is_filled(&self, slot: PatchSlot) -> bool {
+        slot == self.active || self.slots[slot.index()].is_some()
+    }
+}
@@ -0,0 +1,229 @@
+// Pattern commands - Undoable note edits of the synth pattern
+//
+// The piano roll edits the notes in place while the mouse is down; once the
+// gesture ends, the frontend puts the notes it started from back and executes
+// a `SetPatternNotesCommand` with the result, so a drag is a single undo step.
+// The pattern groove (swing and template) is set the same way, once the swing
+// knob is released. Quantizing works on the pattern itself, with the tempo and
+// time signature of the state.
+
+use crate::command::state::DawState;
+use crate::command::trait_def::{CommandError, CommandResult, UndoableCommand};
+use crate::sequencer::{Note, NoteId, PatternGroove, QuantizeSettings, quantize_notes};
+
+/// Replace the notes of the pattern and send it to the sequencer
+fn replace_notes(state: &mut DawState, notes: &[Note]) -> Result<(), String> {
+    state.pattern.set_notes(notes.to_vec());
+    state
+        .send_pattern()
+        .map_err(|_| "Failed to send pattern command to audio thread (ringbuffer full)".into())
+}
+
+/// Set the groove of the pattern and send it to the sequencer
+fn replace_groove(state: &mut DawState, groove: &PatternGroove) -> Result<(), String> {
+    state.pattern.groove = groove.clone();
+    state
+        .send_pattern()
+        .map_err(|_| "Failed to send pattern command to audio thread (ringbuffer full)".into())
+}
+
+/// Command to replace the notes of the pattern (added, moved or erased notes)
+pub struct SetPatternNotesCommand {
+    description: String,
+    new_notes: Vec<Note>,
+    old_notes: Option<Vec<Note>>,
+}
+
+impl SetPatternNotesCommand {
+    /// Create a new SetPatternNotesCommand
+    ///
+    /// # Arguments
+    /// * `description` - Edit shown in the history (e.g. "Move Note")
+    /// * `notes` - Every note of the pattern after the edit
+    pub fn new(description: impl Into<String>, notes: Vec<Note>) -> Self {
+        Self {
+            description: description.into(),
+            new_notes: notes,
+            old_notes: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetPatternNotesCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        self.old_notes = Some(state.pattern.notes().to_vec());
+        replace_notes(state, &self.new_notes).map_err(CommandError::ExecutionFailed)
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_notes = self
+            .old_notes
+            .as_ref()
+            .ok_or_else(|| CommandError::UndoFailed("No previous notes stored".into()))?;
+        let old_notes = old_notes.clone();
+        replace_notes(state, &old_notes).map_err(CommandError::UndoFailed)
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+}
+
+/// Command to set the playback groove of the pattern
+pub struct SetPatternGrooveCommand {
+    new_groove: PatternGroove,
+    old_groove: Option<PatternGroove>,
+}
+
+impl SetPatternGrooveCommand {
+    pub fn new(groove: PatternGroove) -> Self {
+        Self {
+            new_groove: groove,
+            old_groove: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetPatternGrooveCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        self.old_groove = Some(state.pattern.groove.clone());
+        replace_groove(state, &self.new_groove).map_err(CommandError::ExecutionFailed)
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_groove = self
+            .old_groove
+            .clone()
+            .ok_or_else(|| CommandError::UndoFailed("No previous groove stored".into()))?;
+        replace_groove(state, &old_groove).map_err(CommandError::UndoFailed)
+    }
+
+    fn description(&self) -> String {
+        "Change Pattern Groove".to_string()
+    }
+}
+
+/// Command to quantize notes of the pattern (the selected ones, or all)
+pub struct QuantizeNotesCommand {
+    ids: Vec<NoteId>,
+    settings: QuantizeSettings,
+    sample_rate: f64,
+    old_notes: Option<Vec<Note>>,
+}
+
+impl QuantizeNotesCommand {
+    /// Create a new QuantizeNotesCommand
+    ///
+    /// # Arguments
+    /// * `ids` - Notes to quantize (empty: every note)
+    /// * `settings` - Grid, strength and swing
+    /// * `sample_rate` - Sample rate the note positions are counted at
+    pub fn new(ids: Vec<NoteId>, settings: QuantizeSettings, sample_rate: f64) -> Self {
+        Self {
+            ids,
+            settings,
+            sample_rate,
+            old_notes: None,
+        }
+    }
+}
+
+impl UndoableCommand for QuantizeNotesCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let mut pattern = state.pattern.clone();
+        quantize_notes(
+            &mut pattern,
+            &self.ids,
+            &self.settings,
+            self.sample_rate,
+            &state.tempo,
+            &state.time_signature,
+        );
+        self.old_notes = Some(state.pattern.notes().to_vec());
+        replace_notes(state, pattern.notes()).map_err(CommandError::ExecutionFailed)
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_notes = self
+            .old_notes
+            .clone()
+            .ok_or_else(|| CommandError::UndoFailed("No previous notes stored".into()))?;
+        replace_notes(state, &old_notes).map_err(CommandError::UndoFailed)
+    }
+
+    fn description(&self) -> String {
+        "Quantize Notes".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::channels::create_command_channel;
+    use crate::messaging::command::Command;
+    use crate::sequencer::{Position, generate_note_id};
+    use ringbuf::traits::Consumer;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_pattern_notes_undo() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        let note = Note::new(generate_note_id(), 60, Position::zero(), 1000, 100);
+        state.pattern.add_note(note);
+
+        let moved = Note { pitch: 64, ..note };
+        let mut command = SetPatternNotesCommand::new("Move Note", vec![moved]);
+        command.execute(&mut state).unwrap();
+        assert_eq!(state.pattern.notes()[0].pitch, 64);
+        assert!(matches!(rx.try_pop(), Some(Command::SetPattern(p)) if p.notes()[0].pitch == 64));
+
+        command.undo(&mut state).unwrap();
+        assert_eq!(state.pattern.notes(), &[note]);
+        assert_eq!(command.description(), "Move Note");
+    }
+
+    #[test]
+    fn test_pattern_groove_undo() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+
+        let groove = PatternGroove {
+            swing: 62.0,
+            template: None,
+        };
+        let mut command = SetPatternGrooveCommand::new(groove.clone());
+        command.execute(&mut state).unwrap();
+        assert_eq!(state.pattern.groove, groove);
+        assert!(matches!(rx.try_pop(), Some(Command::SetPattern(p)) if p.groove.swing == 62.0));
+
+        command.undo(&mut state).unwrap();
+        assert!(state.pattern.groove.is_straight());
+    }
+
+    #[test]
+    fn test_quantize_notes_undo() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        // 120 BPM at 48 kHz: the second 1/16 step starts at 6000 samples
+        let note = Note::new(generate_note_id(), 60, Position::zero(), 1000, 100);
+        let late = Note {
+            id: generate_note_id(),
+            start: Position::from_samples(6600, 48000.0, &state.tempo, &state.time_signature),
+            ..note
+        };
+        state.pattern.set_notes(vec![note, late]);
+
+        let settings = QuantizeSettings {
+            strength: 0.5,
+            ..Default::default()
+        };
+        let mut command = QuantizeNotesCommand::new(vec![late.id], settings, 48000.0);
+        command.execute(&mut state).unwrap();
+        assert_eq!(state.pattern.get_note(late.id).unwrap().start.samples, 6300);
+        assert!(matches!(rx.try_pop(), Some(Command::SetPattern(_))));
+
+        command.undo(&mut state).unwrap();
+        assert_eq!(state.pattern.notes(), &[note, late]);
+    }
+}
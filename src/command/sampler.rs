@@ -0,0 +1,593 @@
+// Sampler commands - Undoable sample slot and note mapping edits
+//
+// `DawState::samples`, `DawState::sample_mappings` and `DawState::sample_zones`
+// mirror the voice manager: removing a slot forgets the notes mapped to it and
+// its zone and shifts the next slots (see `VoiceManager::remove_sample`),
+// undoing it inserts the sample back in its slot, with its zone, and maps its
+// notes again. The slices of a loop are added as one step, with the pattern
+// notes replaying it.
+
+use crate::command::state::DawState;
+use crate::command::trait_def::{CommandError, CommandResult, UndoableCommand};
+use crate::messaging::command::Command;
+use crate::sampler::loader::Sample;
+use crate::sampler::zone::SampleZone;
+use crate::sequencer::Note;
+use crate::synth::voice_manager::MAX_SAMPLE_SLOTS;
+use std::sync::Arc;
+
+/// Send a sampler command, turning a full ringbuffer into an error message
+fn send_sampler(state: &mut DawState, command: Command, name: &str) -> Result<(), String> {
+    if state.send_to_audio(command) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to send {} command to audio thread (ringbuffer full)",
+            name
+        ))
+    }
+}
+
+/// Sample removed from a slot, with its notes and zone
+type RemovedSlot = (Sample, Vec<u8>, Option<SampleZone>);
+
+/// Remove a sample slot from the state, returning the sample, its notes and its zone
+fn remove_slot(state: &mut DawState, index: usize) -> RemovedSlot {
+    let sample = state.samples.remove(index);
+    let notes = state
+        .sample_mappings
+        .iter()
+        .filter(|(_, slot)| **slot == index)
+        .map(|(note, _)| *note)
+        .collect::<Vec<_>>();
+    state.sample_mappings.retain(|_, slot| *slot != index);
+    for slot in state.sample_mappings.values_mut() {
+        if *slot > index {
+            *slot -= 1;
+        }
+    }
+    let zone = state.sample_zones.remove(&index);
+    state.sample_zones = std::mem::take(&mut state.sample_zones)
+        .into_iter()
+        .map(|(slot, zone)| (if slot > index { slot - 1 } else { slot }, zone))
+        .collect();
+    (sample, notes, zone)
+}
+
+/// Command to load a sample in the next free slot
+pub struct AddSampleCommand {
+    sample: Sample,
+    /// Slot the sample went to
+    index: Option<usize>,
+}
+
+impl AddSampleCommand {
+    /// Create a new AddSampleCommand
+    pub fn new(sample: Sample) -> Self {
+        Self {
+            sample,
+            index: None,
+        }
+    }
+}
+
+impl UndoableCommand for AddSampleCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        if state.samples.len() >= MAX_SAMPLE_SLOTS {
+            return Err(CommandError::ExecutionFailed(format!(
+                "All {} sample slots are used",
+                MAX_SAMPLE_SLOTS
+            )));
+        }
+        let sample = Arc::new(self.sample.clone());
+        send_sampler(state, Command::AddSample(sample), "add sample")
+            .map_err(CommandError::ExecutionFailed)?;
+        state.samples.push(self.sample.clone());
+        self.index = Some(state.samples.len() - 1);
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let index = self
+            .index
+            .filter(|index| *index < state.samples.len())
+            .ok_or_else(|| CommandError::UndoFailed("No added sample stored".into()))?;
+        send_sampler(state, Command::RemoveSample(index), "remove sample")
+            .map_err(CommandError::UndoFailed)?;
+        remove_slot(state, index);
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Add Sample '{}'", self.sample.name)
+    }
+}
+
+/// Command to remove a sample slot (the next slots move down)
+pub struct RemoveSampleCommand {
+    index: usize,
+    /// Removed sample, the notes that were mapped to it and its zone
+    removed: Option<RemovedSlot>,
+}
+
+impl RemoveSampleCommand {
+    /// Create a new RemoveSampleCommand
+    ///
+    /// # Arguments
+    /// * `index` - Slot of the sample to remove
+    pub fn new(index: usize) -> Self {
+        Self {
+            index,
+            removed: None,
+        }
+    }
+}
+
+impl UndoableCommand for RemoveSampleCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        if self.index >= state.samples.len() {
+            return Err(CommandError::ExecutionFailed(format!(
+                "No sample in slot {}",
+                self.index
+            )));
+        }
+        send_sampler(state, Command::RemoveSample(self.index), "remove sample")
+            .map_err(CommandError::ExecutionFailed)?;
+        self.removed = Some(remove_slot(state, self.index));
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let (sample, notes, zone) = self
+            .removed
+            .clone()
+            .ok_or_else(|| CommandError::UndoFailed("No removed sample stored".into()))?;
+        let command = Command::InsertSample(self.index, Arc::new(sample.clone()));
+        send_sampler(state, command, "insert sample").map_err(CommandError::UndoFailed)?;
+        state.samples.insert(self.index, sample);
+        for slot in state.sample_mappings.values_mut() {
+            if *slot >= self.index {
+                *slot += 1;
+            }
+        }
+        state.sample_zones = std::mem::take(&mut state.sample_zones)
+            .into_iter()
+            .map(|(slot, zone)| (if slot >= self.index { slot + 1 } else { slot }, zone))
+            .collect();
+        if let Some(zone) = zone {
+            let command = Command::SetSampleZone {
+                sample_index: self.index,
+                zone: Some(zone),
+            };
+            send_sampler(state, command, "sample zone").map_err(CommandError::UndoFailed)?;
+            state.sample_zones.insert(self.index, zone);
+        }
+        for note in notes {
+            state.sample_mappings.insert(note, self.index);
+            let command = Command::SetNoteSampleMapping {
+                note,
+                sample_index: self.index,
+            };
+            send_sampler(state, command, "note mapping").map_err(CommandError::UndoFailed)?;
+        }
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        match &self.removed {
+            Some((sample, _, _)) => format!("Remove Sample '{}'", sample.name),
+            None => "Remove Sample".to_string(),
+        }
+    }
+}
+
+/// Command to map a MIDI note to a sample slot
+pub struct SetSampleMappingCommand {
+    note: u8,
+    sample_index: usize,
+    /// Previous slot of the note (None: it was not mapped)
+    old_index: Option<Option<usize>>,
+}
+
+impl SetSampleMappingCommand {
+    /// Create a new SetSampleMappingCommand
+    ///
+    /// # Arguments
+    /// * `note` - MIDI note (0-127)
+    /// * `sample_index` - Slot of the sample the note plays
+    pub fn new(note: u8, sample_index: usize) -> Self {
+        Self {
+            note,
+            sample_index,
+            old_index: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetSampleMappingCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        if self.note > 127 || self.sample_index >= state.samples.len() {
+            return Err(CommandError::ExecutionFailed(format!(
+                "Cannot map note {} to sample slot {}",
+                self.note, self.sample_index
+            )));
+        }
+        let command = Command::SetNoteSampleMapping {
+            note: self.note,
+            sample_index: self.sample_index,
+        };
+        send_sampler(state, command, "note mapping").map_err(CommandError::ExecutionFailed)?;
+        self.old_index = Some(state.sample_mappings.insert(self.note, self.sample_index));
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_index = self
+            .old_index
+            .ok_or_else(|| CommandError::UndoFailed("No previous note mapping stored".into()))?;
+        let command = match old_index {
+            Some(sample_index) => Command::SetNoteSampleMapping {
+                note: self.note,
+                sample_index,
+            },
+            None => Command::ClearNoteSampleMapping(self.note),
+        };
+        send_sampler(state, command, "note mapping").map_err(CommandError::UndoFailed)?;
+        match old_index {
+            Some(sample_index) => state.sample_mappings.insert(self.note, sample_index),
+            None => state.sample_mappings.remove(&self.note),
+        };
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Map Note {} to Sample {}", self.note, self.sample_index + 1)
+    }
+}
+
+/// Command to set the key-range zone of a sample slot
+pub struct SetSampleZoneCommand {
+    sample_index: usize,
+    zone: Option<SampleZone>,
+    /// Previous zone of the slot
+    old_zone: Option<Option<SampleZone>>,
+}
+
+impl SetSampleZoneCommand {
+    /// Create a new SetSampleZoneCommand
+    ///
+    /// # Arguments
+    /// * `sample_index` - Slot of the sample
+    /// * `zone` - Notes the sample plays (None: only its mapped notes)
+    pub fn new(sample_index: usize, zone: Option<SampleZone>) -> Self {
+        Self {
+            sample_index,
+            zone,
+            old_zone: None,
+        }
+    }
+
+    fn write(
+        state: &mut DawState,
+        sample_index: usize,
+        zone: Option<SampleZone>,
+    ) -> Result<(), String> {
+        let command = Command::SetSampleZone { sample_index, zone };
+        send_sampler(state, command, "sample zone")?;
+        match zone {
+            Some(zone) => state.sample_zones.insert(sample_index, zone),
+            None => state.sample_zones.remove(&sample_index),
+        };
+        Ok(())
+    }
+}
+
+impl UndoableCommand for SetSampleZoneCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        if self.sample_index >= state.samples.len() {
+            return Err(CommandError::ExecutionFailed(format!(
+                "No sample in slot {}",
+                self.sample_index
+            )));
+        }
+        if let Some(zone) = &self.zone {
+            zone.validate().map_err(CommandError::ExecutionFailed)?;
+        }
+        let old_zone = state.sample_zones.get(&self.sample_index).copied();
+        Self::write(state, self.sample_index, self.zone).map_err(CommandError::ExecutionFailed)?;
+        self.old_zone = Some(old_zone);
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_zone = self
+            .old_zone
+            .ok_or_else(|| CommandError::UndoFailed("No previous zone stored".into()))?;
+        Self::write(state, self.sample_index, old_zone).map_err(CommandError::UndoFailed)
+    }
+
+    fn description(&self) -> String {
+        match self.zone {
+            Some(zone) => format!(
+                "Set Sample {} Zone to {}-{}",
+                self.sample_index + 1,
+                zone.low_note,
+                zone.high_note
+            ),
+            None => format!("Clear Sample {} Zone", self.sample_index + 1),
+        }
+    }
+}
+
+/// Command to add the slices of a loop, each with its zone, and optionally the
+/// pattern notes replaying the loop (see `sampler::slicer`)
+pub struct AddSlicesCommand {
+    name: String,
+    slices: Vec<(Sample, SampleZone)>,
+    /// Every note of the pattern after the edit (None: the pattern is kept)
+    notes: Option<Vec<Note>>,
+    /// First slot of the slices and the notes of the pattern before the edit
+    added: Option<(usize, Vec<Note>)>,
+}
+
+impl AddSlicesCommand {
+    /// Create a new AddSlicesCommand
+    ///
+    /// # Arguments
+    /// * `name` - Name of the sliced loop (shown in the history)
+    /// * `slices` - Slice samples and the zones playing them
+    /// * `notes` - Notes of the pattern replaying the loop, if any
+    pub fn new(name: String, slices: Vec<(Sample, SampleZone)>, notes: Option<Vec<Note>>) -> Self {
+        Self {
+            name,
+            slices,
+            notes,
+            added: None,
+        }
+    }
+
+    /// Remove the slots of the slices from `first` on, last first
+    fn remove_slices(&self, state: &mut DawState, first: usize) -> Result<(), String> {
+        let end = (first + self.slices.len()).min(state.samples.len());
+        for index in (first..end).rev() {
+            send_sampler(state, Command::RemoveSample(index), "remove sample")?;
+            remove_slot(state, index);
+        }
+        Ok(())
+    }
+}
+
+impl UndoableCommand for AddSlicesCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let first = state.samples.len();
+        if first + self.slices.len() > MAX_SAMPLE_SLOTS {
+            return Err(CommandError::ExecutionFailed(format!(
+                "{} slices do not fit in the {} free sample slots",
+                self.slices.len(),
+                MAX_SAMPLE_SLOTS - first
+            )));
+        }
+        for (offset, (sample, zone)) in self.slices.iter().enumerate() {
+            let index = first + offset;
+            let added = send_sampler(
+                state,
+                Command::AddSample(Arc::new(sample.clone())),
+                "add sample",
+            )
+            .and_then(|()| {
+                state.samples.push(sample.clone());
+                let command = Command::SetSampleZone {
+                    sample_index: index,
+                    zone: Some(*zone),
+                };
+                send_sampler(state, command, "sample zone")
+            });
+            if let Err(e) = added {
+                // Leave the state as it was
+                let _ = self.remove_slices(state, first);
+                return Err(CommandError::ExecutionFailed(e));
+            }
+            state.sample_zones.insert(index, *zone);
+        }
+        let old_notes = state.pattern.notes().to_vec();
+        if let Some(notes) = &self.notes {
+            state.pattern.set_notes(notes.clone());
+            state.send_pattern()?;
+        }
+        self.added = Some((first, old_notes));
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let (first, old_notes) = self
+            .added
+            .clone()
+            .ok_or_else(|| CommandError::UndoFailed("No added slices stored".into()))?;
+        if self.notes.is_some() {
+            state.pattern.set_notes(old_notes);
+            state.send_pattern()?;
+        }
+        self.remove_slices(state, first)
+            .map_err(CommandError::UndoFailed)
+    }
+
+    fn description(&self) -> String {
+        format!("Slice '{}'", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::channels::create_command_channel;
+    use crate::sampler::loader::{LoopMode, SampleData};
+    use ringbuf::traits::Consumer;
+    use std::sync::Mutex;
+
+    fn test_sample(name: &str) -> Sample {
+        Sample {
+            name: name.to_string(),
+            data: SampleData::F32(vec![0.0; 16].into()),
+            sample_rate: 48000,
+            source_channels: 1,
+            loop_mode: LoopMode::Off,
+            loop_start: 0,
+            loop_end: 0,
+            reverse: false,
+            volume: 1.0,
+            pan: 0.0,
+            pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: None,
+        }
+    }
+
+    #[test]
+    fn test_remove_sample_undo_restores_slot_and_mappings() {
+        let (tx, mut rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        for name in ["kick", "snare", "hat"] {
+            AddSampleCommand::new(test_sample(name))
+                .execute(&mut state)
+                .unwrap();
+        }
+        SetSampleMappingCommand::new(36, 0)
+            .execute(&mut state)
+            .unwrap();
+        SetSampleMappingCommand::new(38, 1)
+            .execute(&mut state)
+            .unwrap();
+        SetSampleMappingCommand::new(42, 2)
+            .execute(&mut state)
+            .unwrap();
+        rx.pop_iter().count();
+
+        let mut remove = RemoveSampleCommand::new(1);
+        remove.execute(&mut state).unwrap();
+        assert_eq!(state.samples.len(), 2);
+        assert_eq!(state.sample_mappings.get(&38), None);
+        assert_eq!(state.sample_mappings[&42], 1);
+        assert_eq!(remove.description(), "Remove Sample 'snare'");
+
+        remove.undo(&mut state).unwrap();
+        assert_eq!(state.samples[1].name, "snare");
+        assert_eq!(state.sample_mappings[&38], 1);
+        assert_eq!(state.sample_mappings[&42], 2);
+        let sent: Vec<_> = rx.pop_iter().collect();
+        assert!(matches!(sent[1], Command::InsertSample(1, _)));
+        assert!(matches!(
+            sent[2],
+            Command::SetNoteSampleMapping {
+                note: 38,
+                sample_index: 1
+            }
+        ));
+    }
+
+    #[test]
+    fn test_mapping_undo_clears_new_note() {
+        let (tx, mut rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        let mut add = AddSampleCommand::new(test_sample("kick"));
+        add.execute(&mut state).unwrap();
+
+        let mut mapping = SetSampleMappingCommand::new(36, 0);
+        assert!(
+            SetSampleMappingCommand::new(36, 1)
+                .execute(&mut state)
+                .is_err()
+        );
+        mapping.execute(&mut state).unwrap();
+        mapping.undo(&mut state).unwrap();
+        assert!(state.sample_mappings.is_empty());
+        assert!(matches!(
+            rx.pop_iter().last(),
+            Some(Command::ClearNoteSampleMapping(36))
+        ));
+
+        add.undo(&mut state).unwrap();
+        assert!(state.samples.is_empty());
+    }
+
+    #[test]
+    fn test_sample_zone_follows_its_slot() {
+        let (tx, mut rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        for name in ["kick", "piano", "strings"] {
+            AddSampleCommand::new(test_sample(name))
+                .execute(&mut state)
+                .unwrap();
+        }
+        let piano = SampleZone::new(36, 59, 48).unwrap();
+        let strings = SampleZone::new(60, 84, 72).unwrap();
+        let mut set_piano = SetSampleZoneCommand::new(1, Some(piano));
+        assert_eq!(set_piano.description(), "Set Sample 2 Zone to 36-59");
+        set_piano.execute(&mut state).unwrap();
+        SetSampleZoneCommand::new(2, Some(strings))
+            .execute(&mut state)
+            .unwrap();
+        assert!(
+            SetSampleZoneCommand::new(3, Some(piano))
+                .execute(&mut state)
+                .is_err()
+        );
+        rx.pop_iter().count();
+
+        // Removing the piano shifts the strings zone down, undo puts both back
+        let mut remove = RemoveSampleCommand::new(1);
+        remove.execute(&mut state).unwrap();
+        assert_eq!(state.sample_zones.len(), 1);
+        assert_eq!(state.sample_zones[&1], strings);
+        remove.undo(&mut state).unwrap();
+        assert_eq!(state.sample_zones[&1], piano);
+        assert_eq!(state.sample_zones[&2], strings);
+        assert!(rx.pop_iter().any(|command| matches!(
+            command,
+            Command::SetSampleZone {
+                sample_index: 1,
+                zone: Some(_)
+            }
+        )));
+
+        set_piano.undo(&mut state).unwrap();
+        assert!(!state.sample_zones.contains_key(&1));
+    }
+
+    #[test]
+    fn test_slices_are_one_undo_step() {
+        use crate::sequencer::{Note, Position};
+
+        let (tx, mut rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        AddSampleCommand::new(test_sample("loop"))
+            .execute(&mut state)
+            .unwrap();
+        let slices: Vec<_> = (0..3)
+            .map(|i| {
+                let note = 36 + i;
+                (
+                    test_sample(&format!("loop {}", i + 1)),
+                    SampleZone::new(note, note, note).unwrap(),
+                )
+            })
+            .collect();
+        let notes = vec![Note::new(1, 36, Position::zero(), 100, 100)];
+
+        let mut slice = AddSlicesCommand::new("loop".to_string(), slices, Some(notes));
+        slice.execute(&mut state).unwrap();
+        assert_eq!(slice.description(), "Slice 'loop'");
+        assert_eq!(state.samples.len(), 4);
+        assert_eq!(state.sample_zones[&3].root_note, 38);
+        assert_eq!(state.pattern.notes().len(), 1);
+        assert!(
+            rx.pop_iter()
+                .any(|command| matches!(command, Command::SetPattern(_)))
+        );
+
+        slice.undo(&mut state).unwrap();
+        assert_eq!(state.samples.len(), 1);
+        assert!(state.sample_zones.is_empty());
+        assert!(state.pattern.is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+// Session commands - Undoable edits of the session view clip grid
+//
+// Filling or emptying a slot and changing the launch quantization are undoable;
+// launching and stopping clips are performance gestures and stay out of the
+// history (see `DawState::launch_clip`).
+
+use crate::command::state::DawState;
+use crate::command::trait_def::{CommandError, CommandResult, UndoableCommand};
+use crate::sequencer::Session;
+
+/// Command to replace the clip grid of the session view
+pub struct SetSessionCommand {
+    description: String,
+    new_session: Session,
+    old_session: Option<Session>,
+}
+
+impl SetSessionCommand {
+    /// Create a new SetSessionCommand
+    ///
+    /// # Arguments
+    /// * `description` - Edit shown in the history (e.g. "Fill Slot")
+    /// * `session` - The whole grid after the edit
+    pub fn new(description: impl Into<String>, session: Session) -> Self {
+        Self {
+            description: description.into(),
+            new_session: session,
+            old_session: None,
+        }
+    }
+}
+
+impl UndoableCommand for SetSessionCommand {
+    fn execute(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_session = state.session.clone();
+        state.set_session(self.new_session.clone())?;
+        self.old_session = Some(old_session);
+        Ok(())
+    }
+
+    fn undo(&mut self, state: &mut DawState) -> CommandResult<()> {
+        let old_session = self
+            .old_session
+            .clone()
+            .ok_or_else(|| CommandError::UndoFailed("No previous session stored".into()))?;
+        state
+            .set_session(old_session)
+            .map_err(|e| CommandError::UndoFailed(e.to_string()))
+    }
+
+    fn description(&self) -> String {
+        self.description.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::snapshot::EngineSnapshot;
+    use crate::messaging::channels::create_command_channel;
+    use crate::messaging::command::Command;
+    use crate::sequencer::arrangement::SYNTH_TRACK;
+    use ringbuf::traits::Consumer;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_session_edit_and_launch() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+
+        let mut session = Session::new();
+        session.set_slot(SYNTH_TRACK, 2, Arc::new(state.pattern.clone()));
+        let mut command = SetSessionCommand::new("Fill Slot", session);
+        command.execute(&mut state).unwrap();
+        assert!(
+            matches!(rx.try_pop(), Some(Command::SetSession(slots)) if slots.slots().len() == 1)
+        );
+
+        // Launches wait for the next bar (96000 samples at 120 BPM, 48 kHz)
+        let snapshot = EngineSnapshot {
+            position_samples: 1000,
+            playing: true,
+            sample_rate: 48000.0,
+            ..Default::default()
+        };
+        state.launch_scene(2, &snapshot).unwrap();
+        assert!(matches!(
+            rx.try_pop(),
+            Some(Command::LaunchClip {
+                track: SYNTH_TRACK,
+                scene: Some(2),
+                at: 96000
+            })
+        ));
+        assert_eq!(state.session_launches(1000)[0].playing, None);
+        assert_eq!(state.session_launches(96000)[0].playing, Some((2, 96000)));
+
+        command.undo(&mut state).unwrap();
+        assert!(state.session.is_empty());
+        assert!(matches!(rx.try_pop(), Some(Command::SetSession(slots)) if slots.is_empty()));
+
+        state.release_session().unwrap();
+        assert!(matches!(rx.try_pop(), Some(Command::ReleaseSession)));
+        assert!(state.session_launches(96000).is_empty());
+    }
+}
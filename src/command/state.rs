@@ -2,15 +2,54 @@
 //
 // This struct holds all the mutable state that commands can modify.
 // It also holds the communication channels to send messages to the audio thread.
+//
+// Both frontends (egui and Tauri) wrap this state, so any parameter that is
+// mirrored on the UI side and sent to the audio thread belongs here rather than
+// in a frontend-specific struct.
 
+use crate::audio::clip_player::{AudioTrackList, MAX_CLIP_GAIN, SidechainSource, TrackDynamics};
+use crate::audio::inserts::{
+    InsertChainParams, InsertEffect, InsertEffectOrder, is_valid_insert_order,
+};
+use crate::audio::instruments::{InstrumentTrack, MAX_INSTRUMENT_TRACKS, TrackInstrument};
+use crate::audio::outputs::TrackOutput;
+use crate::audio::parameters::SynthParameters;
+use crate::audio::returns::{MAX_RETURN_BUSES, ReturnBus, TrackSend, TrackSends};
+use crate::audio::routing::{ChannelStrip, MixerTrack};
+use crate::audio::surround::{SpeakerLayout, SurroundPan};
+use crate::automation::{AutomationLane, AutomationParameter, AutomationPoint};
+use crate::command::patch::AbComparison;
+use crate::command::trait_def::{CommandError, CommandResult};
 use crate::messaging::channels::CommandProducer;
+use crate::messaging::coalescer::CommandCoalescer;
+use crate::messaging::command::Command;
+use crate::messaging::notification::Notification;
+use crate::midi::cc_map::CcMap;
+use crate::sampler::loader::Sample;
+use crate::sampler::zone::SampleZone;
+use crate::audio::snapshot::EngineSnapshot;
+use crate::sequencer::session::MAX_SESSION_TRACKS;
+use crate::sequencer::{Arrangement, Launcher, Pattern, Session, TrackLaunch};
+use crate::sequencer::timeline::{Tempo, TimeSignature};
+use crate::synth::delay::DelayParams;
+use crate::synth::dynamics::{DynamicsMode, DynamicsParams};
 use crate::synth::envelope::AdsrParams;
-use crate::synth::filter::FilterParams;
-use crate::synth::lfo::LfoParams;
-use crate::synth::modulation::{ModDestination, ModRouting, ModSource};
-use crate::synth::oscillator::WaveformType;
+use crate::synth::equalizer::EqParams;
+use crate::synth::filter::{FilterModParams, FilterParams};
+use crate::synth::lfo::{LFO_COUNT, LfoParams};
+use crate::synth::limiter::LimiterParams;
+use crate::synth::master_chain::{
+    MASTER_DELAY_MAX_MS, MasterChainParams, MasterEffect, MasterEffectOrder, is_valid_order,
+};
+use crate::synth::modulation::{MAX_ROUTINGS, ModDestination, ModRouting, ModSource};
+use crate::synth::noise::NoiseParams;
+use crate::synth::oscillator::{
+    AntiAliasing, CrossModParams, Oscillator2Params, UnisonParams, WaveformType,
+};
 use crate::synth::poly_mode::PolyMode;
 use crate::synth::portamento::PortamentoParams;
+use crate::synth::reverb::ReverbParams;
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 
 /// Central state of the DAW that can be modified by commands
@@ -18,7 +57,9 @@ use std::sync::{Arc, Mutex};
 /// This struct acts as the single source of truth for UI state.
 /// Commands modify this state, and changes are propagated to the audio thread
 /// via the command sender.
-use crate::synth::voice_manager::VoiceMode;
+use crate::synth::voice_manager::{
+    DEFAULT_PITCH_BEND_RANGE, MAX_PITCH_BEND_RANGE, MAX_VOICES, VoiceMode,
+};
 
 pub struct DawState {
     /// Current volume (0.0 to 1.0)
@@ -27,6 +68,30 @@ pub struct DawState {
     /// Current waveform type
     pub waveform: WaveformType,
 
+    /// Second oscillator of the synth voices
+    pub oscillator2: Oscillator2Params,
+
+    /// Unison stack of the synth notes
+    pub unison: UnisonParams,
+
+    /// Band-limiting method of the synth oscillators
+    pub anti_aliasing: AntiAliasing,
+
+    /// FM and ring modulation of the first oscillator by the second
+    pub cross_mod: CrossModParams,
+
+    /// Noise source of the synth voices
+    pub noise: NoiseParams,
+
+    /// Modulation envelope of the synth voices (a mod matrix source)
+    pub mod_envelope: AdsrParams,
+
+    /// Filter envelope amount, key tracking and drive
+    pub filter_mod: FilterModParams,
+
+    /// Matrix-only LFOs 2..LFO_COUNT (`ModSource::Lfo(1..)`)
+    pub mod_lfos: [LfoParams; LFO_COUNT - 1],
+
     /// Voice mode (Synth or Sampler)
     pub voice_mode: VoiceMode,
 
@@ -46,12 +111,118 @@ pub struct DawState {
     pub filter: FilterParams,
 
     /// UI-visible copy of modulation routings (MVP)
-    /// Keeps the first 8 slots so undo/redo can reflect in UI without querying audio thread
-    pub mod_routings: [ModRouting; 8],
+    /// Keeps every slot so undo/redo can reflect in UI without querying audio thread
+    pub mod_routings: [ModRouting; MAX_ROUTINGS],
+
+    /// Transport tempo
+    pub tempo: Tempo,
+
+    /// Transport time signature
+    pub time_signature: TimeSignature,
+
+    /// Metronome on/off
+    pub metronome_enabled: bool,
+
+    /// Metronome volume (0.0 to 1.0)
+    pub metronome_volume: f32,
+
+    /// Output of the metronome bus (channels 1-2 or a hardware pair, never the mix)
+    pub metronome_output: TrackOutput,
+
+    /// Master bus effects configuration
+    pub master_chain: MasterChainParams,
+
+    /// Maximum number of voices (lowered further by the engine under CPU load)
+    pub max_voices: usize,
+
+    /// Pitch bend range of the synth in semitones
+    pub pitch_bend_range: f32,
+
+    /// Compressor/gate insert of the synth track
+    pub synth_dynamics: Option<TrackDynamics>,
+
+    /// Track routed to the sidechain input of the plugins
+    pub plugin_sidechain: Option<SidechainSource>,
+
+    /// Return buses, by slot (audio track sends address them by slot)
+    pub return_buses: [Option<ReturnBus>; MAX_RETURN_BUSES],
+
+    /// Sends of the synth track to the return buses
+    pub synth_sends: TrackSends,
+
+    /// Output of the synth track (master bus or hardware pair)
+    pub synth_output: TrackOutput,
+
+    /// Surround panner of the synth track (None: stereo)
+    pub synth_surround: Option<SurroundPan>,
+
+    /// Gain, pan, mute and solo of the synth track
+    pub synth_strip: ChannelStrip,
+
+    /// Insert effect chain of the synth track
+    pub synth_inserts: InsertChainParams,
+
+    /// Speaker layout of the mix
+    pub speaker_layout: SpeakerLayout,
+
+    /// Audio tracks of the mixer (the Tauri frontend keeps its own list)
+    pub audio_tracks: AudioTrackList,
+
+    /// Instrument tracks, each playing its pattern on its own instrument
+    pub instrument_tracks: Vec<InstrumentTrack>,
+
+    /// Id given to the next instrument track
+    next_instrument_track_id: u32,
+
+    /// Samples loaded in the sampler, by slot (UI copies of the engine ones)
+    pub samples: Vec<Sample>,
+
+    /// Sample slot played by each mapped note
+    pub sample_mappings: BTreeMap<u8, usize>,
+
+    /// Key-range zone of the sample slots that have one
+    pub sample_zones: BTreeMap<usize, SampleZone>,
+
+    /// Pattern played by the synth track
+    pub pattern: Pattern,
+
+    /// Pattern clips of the song, played instead of the patterns in song mode
+    pub arrangement: Arrangement,
+
+    /// Whether the tracks play the arrangement (song mode) or loop their pattern
+    pub song_mode: bool,
+
+    /// Clip grid of the session view (clip launcher)
+    pub session: Session,
+
+    /// Launches of the session as the engine carries them out (display mirror)
+    launcher: Launcher,
+
+    /// Automation lanes (only parameters with a lane are present)
+    pub automation_lanes: BTreeMap<AutomationParameter, AutomationLane>,
+
+    /// A/B patch comparison slots
+    pub ab_comparison: AbComparison,
+
+    /// MIDI controllers mapped to synth parameters (MIDI learn)
+    pub cc_map: CcMap,
 
     /// Command sender to communicate with audio thread (UI channel)
     /// Wrapped in Arc<Mutex<>> to allow sharing between DawApp and commands
     pub command_sender: Arc<Mutex<CommandProducer>>,
+
+    /// Continuous synth parameters shared with the audio engine (lock-free)
+    /// Replace it with the engine's block when the engine is created elsewhere.
+    pub synth_params: SynthParameters,
+
+    /// Filter parameters last sent as a command (discrete fields are tracked)
+    sent_filter: Option<FilterParams>,
+
+    /// LFO parameters last sent as a command (discrete fields are tracked)
+    sent_lfo: Option<LfoParams>,
+
+    /// Parameter updates waiting for space in the command ringbuffer
+    coalescer: CommandCoalescer,
 }
 
 impl DawState {
@@ -60,6 +231,14 @@ impl DawState {
         Self {
             volume: 0.5,
             waveform: WaveformType::Sine,
+            oscillator2: Oscillator2Params::default(),
+            unison: UnisonParams::default(),
+            mod_lfos: [LfoParams::default(); LFO_COUNT - 1],
+            filter_mod: FilterModParams::default(),
+            mod_envelope: AdsrParams::default(),
+            noise: NoiseParams::default(),
+            cross_mod: CrossModParams::default(),
+            anti_aliasing: AntiAliasing::default(),
             voice_mode: VoiceMode::Synth,
             adsr: AdsrParams::default(),
             lfo: LfoParams::default(),
@@ -71,20 +250,1218 @@ impl DawState {
                 destination: ModDestination::Amplitude,
                 amount: 0.0,
                 enabled: false,
-            }; 8],
+            }; MAX_ROUTINGS],
+            tempo: Tempo::default(),
+            time_signature: TimeSignature::default(),
+            metronome_enabled: true,
+            metronome_volume: 0.5,
+            metronome_output: TrackOutput::Master,
+            master_chain: MasterChainParams::default(),
+            max_voices: MAX_VOICES,
+            pitch_bend_range: DEFAULT_PITCH_BEND_RANGE,
+            synth_dynamics: None,
+            plugin_sidechain: None,
+            return_buses: Default::default(),
+            synth_sends: TrackSends::default(),
+            synth_output: TrackOutput::Master,
+            synth_surround: None,
+            synth_strip: ChannelStrip::default(),
+            synth_inserts: InsertChainParams::default(),
+            speaker_layout: SpeakerLayout::Stereo,
+            audio_tracks: AudioTrackList::new(),
+            instrument_tracks: Vec::new(),
+            next_instrument_track_id: 1,
+            samples: Vec::new(),
+            sample_mappings: BTreeMap::new(),
+            sample_zones: BTreeMap::new(),
+            pattern: Pattern::new_default(1, "Pattern 1".to_string()),
+            arrangement: Arrangement::new(),
+            song_mode: false,
+            session: Session::new(),
+            launcher: Launcher::new(),
+            automation_lanes: BTreeMap::new(),
+            ab_comparison: AbComparison::default(),
+            cc_map: CcMap::new(),
             command_sender,
+            synth_params: SynthParameters::default(),
+            sent_filter: None,
+            sent_lfo: None,
+            coalescer: CommandCoalescer::new(),
         }
     }
 
     /// Send a command to the audio thread
     ///
     /// This is used internally by commands to propagate changes to the audio engine.
-    /// Returns true if the message was sent successfully, false if the ringbuffer is full.
+    /// Parameter updates are coalesced when the ringbuffer is full (see
+    /// `flush_to_audio`). Returns false if the command could not be sent.
     pub fn send_to_audio(&mut self, command: crate::messaging::command::Command) -> bool {
         if let Ok(mut sender) = self.command_sender.lock() {
-            ringbuf::traits::Producer::try_push(&mut *sender, command).is_ok()
+            self.coalescer.send(&mut sender, command)
         } else {
             false
         }
     }
+
+    /// Flush coalesced parameter updates to the audio thread
+    ///
+    /// Call it regularly (UI frame, timer). Returns a notification when the
+    /// command ringbuffer has been full for a sustained period.
+    pub fn flush_to_audio(&mut self) -> Option<Notification> {
+        if let Ok(mut sender) = self.command_sender.lock() {
+            self.coalescer.flush(&mut sender);
+        }
+        self.coalescer.overflow_report(std::time::Instant::now())
+    }
+
+    /// Send filter parameters to the audio thread
+    ///
+    /// Cutoff and resonance go through `synth_params`; a `SetFilter` command is
+    /// only pushed when the filter type or bypass changes, so dragging a slider
+    /// does not flood the ringbuffer. Returns false if the ringbuffer is full.
+    pub fn send_filter(&mut self, params: FilterParams) -> bool {
+        self.synth_params.set_filter(&params);
+        let discrete_changed = self.sent_filter.is_none_or(|sent| {
+            sent.filter_type != params.filter_type || sent.enabled != params.enabled
+        });
+        if !discrete_changed {
+            return true;
+        }
+        let sent = self.send_to_audio(Command::SetFilter(params));
+        if sent {
+            self.sent_filter = Some(params);
+        }
+        sent
+    }
+
+    /// Send LFO parameters to the audio thread
+    ///
+    /// Rate and depth go through `synth_params`; a `SetLfo` command is only
+    /// pushed when the waveform, destination, sync, mode or start phase changes.
+    pub fn send_lfo(&mut self, params: LfoParams) -> bool {
+        self.synth_params.set_lfo(&params);
+        let discrete_changed = self.sent_lfo.is_none_or(|sent| {
+            sent.waveform != params.waveform
+                || sent.destination != params.destination
+                || sent.sync != params.sync
+                || sent.mode != params.mode
+                || sent.start_phase != params.start_phase
+        });
+        if !discrete_changed {
+            return true;
+        }
+        let sent = self.send_to_audio(Command::SetLfo(params));
+        if sent {
+            self.sent_lfo = Some(params);
+        }
+        sent
+    }
+
+    /// Replace the command producer (e.g. after the audio engine was restarted)
+    ///
+    /// The producer is swapped inside the shared mutex, so every holder of
+    /// `command_sender` talks to the new engine. Call `sync_audio()` afterwards.
+    pub fn set_command_sender(&mut self, sender: CommandProducer) {
+        if let Ok(mut current) = self.command_sender.lock() {
+            *current = sender;
+        }
+        // Updates meant for the old engine: `sync_audio()` sends the current state
+        self.coalescer.clear();
+    }
+
+    /// Send a command, turning a full ringbuffer into an error
+    fn send_or_fail(&mut self, command: Command) -> CommandResult<()> {
+        if self.send_to_audio(command) {
+            Ok(())
+        } else {
+            Err(CommandError::ExecutionFailed(
+                "Failed to send command to audio thread (ringbuffer full)".into(),
+            ))
+        }
+    }
+
+    /// Send the audio tracks to the clip player
+    pub fn send_audio_tracks(&mut self) -> CommandResult<()> {
+        self.send_or_fail(Command::SetAudioTracks(self.audio_tracks.snapshot()))
+    }
+
+    /// Send the pattern to the sequencer, and to the instrument tracks and
+    /// clips playing it
+    pub fn send_pattern(&mut self) -> CommandResult<()> {
+        let pattern = Arc::new(self.pattern.clone());
+        let mut commands = vec![Command::SetPattern(pattern.clone())];
+        for track in &mut self.instrument_tracks {
+            if track.pattern.id == pattern.id {
+                track.pattern = pattern.clone();
+                commands.push(Command::SetTrackPattern {
+                    track: track.id,
+                    pattern: pattern.clone(),
+                });
+            }
+        }
+        if self.arrangement.update_pattern(&pattern) && self.song_mode {
+            commands.push(self.arrangement_command());
+        }
+        if self.session.update_pattern(&pattern) {
+            commands.push(self.session_command());
+        }
+        for command in commands {
+            self.send_or_fail(command)?;
+        }
+        Ok(())
+    }
+
+    /// Send the arrangement to the sequencer (none when song mode is off: the
+    /// tracks loop their patterns)
+    pub fn send_arrangement(&mut self) -> CommandResult<()> {
+        self.send_or_fail(self.arrangement_command())
+    }
+
+    fn arrangement_command(&self) -> Command {
+        Command::SetArrangement(self.song_mode.then(|| Arc::new(self.arrangement.clone())))
+    }
+
+    /// Replace the pattern clips of the song
+    pub fn set_arrangement(&mut self, arrangement: Arrangement) -> CommandResult<()> {
+        self.arrangement = arrangement;
+        self.send_arrangement()
+    }
+
+    /// Play the arrangement (song mode) or loop the patterns
+    pub fn set_song_mode(&mut self, enabled: bool) -> CommandResult<()> {
+        self.song_mode = enabled;
+        self.send_arrangement()
+    }
+
+    /// Send the clip grid of the session view to the engine
+    pub fn send_session(&mut self) -> CommandResult<()> {
+        self.send_or_fail(self.session_command())
+    }
+
+    fn session_command(&self) -> Command {
+        Command::SetSession(Arc::new(self.session.clone()))
+    }
+
+    /// Replace the clip grid of the session view
+    pub fn set_session(&mut self, session: Session) -> CommandResult<()> {
+        self.session = session;
+        self.send_session()
+    }
+
+    /// Launch a slot of the session (`scene` None stops the track) at the next
+    /// launch boundary after the engine playhead
+    pub fn launch_clip(
+        &mut self,
+        track: u32,
+        scene: Option<u32>,
+        snapshot: &EngineSnapshot,
+    ) -> CommandResult<()> {
+        let at = self.session.quantization.launch_position(
+            snapshot.position_samples,
+            snapshot.playing,
+            &self.tempo,
+            &self.time_signature,
+            snapshot.sample_rate as f64,
+        );
+        if !self.launcher.launch(track, scene, at) {
+            return Err(CommandError::InvalidState(format!(
+                "The session follows at most {} tracks",
+                MAX_SESSION_TRACKS
+            )));
+        }
+        self.send_or_fail(Command::LaunchClip { track, scene, at })
+    }
+
+    /// Launch every slot of a scene; the other tracks playing in the session stop
+    pub fn launch_scene(&mut self, scene: u32, snapshot: &EngineSnapshot) -> CommandResult<()> {
+        let mut launches: Vec<(u32, Option<u32>)> = self
+            .launcher
+            .tracks()
+            .iter()
+            .map(|launch| (launch.track, None))
+            .collect();
+        for slot in self.session.slots().iter().filter(|slot| slot.scene == scene) {
+            match launches.iter_mut().find(|(track, _)| *track == slot.track) {
+                Some(launch) => launch.1 = Some(scene),
+                None => launches.push((slot.track, Some(scene))),
+            }
+        }
+        for (track, scene) in launches {
+            self.launch_clip(track, scene, snapshot)?;
+        }
+        Ok(())
+    }
+
+    /// Stop every track playing in the session (at the launch boundary)
+    pub fn stop_session(&mut self, snapshot: &EngineSnapshot) -> CommandResult<()> {
+        let tracks: Vec<u32> = self.launcher.tracks().iter().map(|launch| launch.track).collect();
+        for track in tracks {
+            self.launch_clip(track, None, snapshot)?;
+        }
+        Ok(())
+    }
+
+    /// Hand the tracks launched in the session back to their patterns or the
+    /// arrangement
+    pub fn release_session(&mut self) -> CommandResult<()> {
+        self.launcher.release();
+        self.send_or_fail(Command::ReleaseSession)
+    }
+
+    /// Launch state of the tracks taken over by the session, with the launches
+    /// timed at or before the engine `playhead` carried out
+    pub fn session_launches(&mut self, playhead: u64) -> &[TrackLaunch] {
+        self.launcher.advance(playhead);
+        self.launcher.tracks()
+    }
+
+    /// Set the transport tempo (20-999 BPM)
+    pub fn set_tempo(&mut self, bpm: f64) -> CommandResult<()> {
+        // Tempo::new asserts on out-of-range values: validate before the audio thread sees it
+        if !bpm.is_finite() || !(20.0..=999.0).contains(&bpm) {
+            return Err(CommandError::InvalidState(format!(
+                "Invalid tempo: {} (must be 20-999 BPM)",
+                bpm
+            )));
+        }
+        self.tempo = Tempo::new(bpm);
+        self.send_or_fail(Command::SetTempo(bpm))
+    }
+
+    /// Set the transport time signature (denominator must be a power of 2)
+    pub fn set_time_signature(&mut self, numerator: u8, denominator: u8) -> CommandResult<()> {
+        if numerator == 0 || !denominator.is_power_of_two() {
+            return Err(CommandError::InvalidState(format!(
+                "Invalid time signature: {}/{}",
+                numerator, denominator
+            )));
+        }
+        self.time_signature = TimeSignature::new(numerator, denominator);
+        self.send_or_fail(Command::SetTimeSignature(numerator, denominator))
+    }
+
+    /// Enable/disable the metronome
+    pub fn set_metronome_enabled(&mut self, enabled: bool) -> CommandResult<()> {
+        self.metronome_enabled = enabled;
+        self.send_or_fail(Command::SetMetronomeEnabled(enabled))
+    }
+
+    /// Set the metronome volume (clamped to 0.0 - 1.0)
+    pub fn set_metronome_volume(&mut self, volume: f32) -> CommandResult<()> {
+        if !volume.is_finite() {
+            return Err(CommandError::InvalidState(format!(
+                "Invalid metronome volume: {}",
+                volume
+            )));
+        }
+        self.metronome_volume = volume.clamp(0.0, 1.0);
+        self.send_or_fail(Command::SetMetronomeVolume(self.metronome_volume))
+    }
+
+    /// Set the output of the metronome (pairs the device lacks play on channels 1-2)
+    pub fn set_metronome_output(&mut self, output: TrackOutput) -> CommandResult<()> {
+        if !output.is_valid() {
+            return Err(CommandError::InvalidState(format!(
+                "Invalid output: {:?}",
+                output
+            )));
+        }
+        self.metronome_output = output;
+        self.send_or_fail(Command::SetMetronomeOutput(output))
+    }
+
+    /// Set master reverb parameters (keeps the bypass state)
+    pub fn set_master_reverb(&mut self, params: ReverbParams) -> CommandResult<()> {
+        self.master_chain.reverb = ReverbParams {
+            enabled: self.master_chain.reverb.enabled,
+            ..params
+        };
+        self.send_or_fail(self.master_effect_command(MasterEffect::Reverb))
+    }
+
+    /// Set master delay parameters (keeps the bypass state)
+    pub fn set_master_delay(&mut self, mut params: DelayParams) -> CommandResult<()> {
+        params.validate(MASTER_DELAY_MAX_MS);
+        params.enabled = self.master_chain.delay.enabled;
+        self.master_chain.delay = params;
+        self.send_or_fail(self.master_effect_command(MasterEffect::Delay))
+    }
+
+    /// Set master EQ parameters (keeps the bypass state)
+    pub fn set_master_eq(&mut self, mut params: EqParams) -> CommandResult<()> {
+        params.validate();
+        params.enabled = self.master_chain.eq.enabled;
+        self.master_chain.eq = params;
+        self.send_or_fail(self.master_effect_command(MasterEffect::Eq))
+    }
+
+    /// Set master compressor parameters (keeps the bypass state, the mode is
+    /// always compressor)
+    pub fn set_master_compressor(&mut self, mut params: DynamicsParams) -> CommandResult<()> {
+        params.validate();
+        params.mode = DynamicsMode::Compressor;
+        params.enabled = self.master_chain.compressor.enabled;
+        self.master_chain.compressor = params;
+        self.send_or_fail(self.master_effect_command(MasterEffect::Compressor))
+    }
+
+    /// Set master limiter parameters (keeps the bypass state)
+    pub fn set_master_limiter(&mut self, params: LimiterParams) -> CommandResult<()> {
+        self.master_chain.limiter = LimiterParams {
+            enabled: self.master_chain.limiter.enabled,
+            ..params
+        };
+        self.send_or_fail(self.master_effect_command(MasterEffect::Limiter))
+    }
+
+    /// Set the master effects processing order
+    pub fn set_master_effect_order(&mut self, order: MasterEffectOrder) -> CommandResult<()> {
+        if !is_valid_order(&order) {
+            return Err(CommandError::InvalidState(
+                "Effect order must list every master effect exactly once".into(),
+            ));
+        }
+        self.master_chain.order = order;
+        self.send_or_fail(Command::SetMasterEffectOrder(order))
+    }
+
+    /// Bypass (or re-enable) a master effect
+    pub fn set_master_effect_bypass(
+        &mut self,
+        effect: MasterEffect,
+        bypassed: bool,
+    ) -> CommandResult<()> {
+        self.master_chain.set_bypassed(effect, bypassed);
+        self.send_or_fail(self.master_effect_command(effect))
+    }
+
+    /// Replace the whole master chain (loaded project), bypass states included
+    pub fn set_master_chain(&mut self, params: MasterChainParams) -> CommandResult<()> {
+        let order = if is_valid_order(&params.order) {
+            params.order
+        } else {
+            MasterEffect::ALL
+        };
+        self.master_chain = MasterChainParams { order, ..params };
+        self.master_chain.delay.validate(MASTER_DELAY_MAX_MS);
+        self.master_chain.eq.validate();
+        self.master_chain.compressor.validate();
+        self.master_chain.compressor.mode = DynamicsMode::Compressor;
+        self.master_chain.limiter.validate();
+        for effect in MasterEffect::ALL {
+            self.send_or_fail(self.master_effect_command(effect))?;
+        }
+        self.send_or_fail(Command::SetMasterEffectOrder(order))
+    }
+
+    /// Command updating one master effect on the audio thread
+    fn master_effect_command(&self, effect: MasterEffect) -> Command {
+        match effect {
+            MasterEffect::Reverb => Command::SetMasterReverb(self.master_chain.reverb),
+            MasterEffect::Delay => Command::SetMasterDelay(self.master_chain.delay),
+            MasterEffect::Eq => Command::SetMasterEq(self.master_chain.eq),
+            MasterEffect::Compressor => Command::SetMasterCompressor(self.master_chain.compressor),
+            MasterEffect::Limiter => Command::SetMasterLimiter(self.master_chain.limiter),
+        }
+    }
+
+    /// Set the maximum number of voices (1 to `MAX_VOICES`)
+    pub fn set_max_voices(&mut self, max_voices: usize) -> CommandResult<()> {
+        if !(1..=MAX_VOICES).contains(&max_voices) {
+            return Err(CommandError::InvalidState(format!(
+                "Max voices must be between 1 and {}: {}",
+                MAX_VOICES, max_voices
+            )));
+        }
+        self.max_voices = max_voices;
+        self.send_or_fail(Command::SetMaxVoices(max_voices))
+    }
+
+    /// Set the pitch bend range in semitones (0 to `MAX_PITCH_BEND_RANGE`)
+    pub fn set_pitch_bend_range(&mut self, semitones: f32) -> CommandResult<()> {
+        if !(0.0..=MAX_PITCH_BEND_RANGE).contains(&semitones) {
+            return Err(CommandError::InvalidState(format!(
+                "Pitch bend range must be between 0 and {} semitones: {}",
+                MAX_PITCH_BEND_RANGE, semitones
+            )));
+        }
+        self.pitch_bend_range = semitones;
+        self.send_or_fail(Command::SetPitchBendRange(semitones))
+    }
+
+    /// Set the synth track insert (None removes it)
+    pub fn set_synth_dynamics(&mut self, insert: Option<TrackDynamics>) -> CommandResult<()> {
+        self.synth_dynamics = insert.map(|mut insert| {
+            insert.params.validate();
+            insert
+        });
+        self.send_or_fail(Command::SetSynthDynamics(self.synth_dynamics))
+    }
+
+    /// Route a track to the sidechain input of the plugins (None disconnects it)
+    pub fn set_plugin_sidechain(&mut self, source: Option<SidechainSource>) -> CommandResult<()> {
+        self.plugin_sidechain = source;
+        self.send_or_fail(Command::SetPluginSidechain(source))
+    }
+
+    /// Disconnect the sidechains keyed by a removed audio track
+    ///
+    /// The synth insert listens to the synth again, the plugins get no key.
+    pub fn forget_sidechain_track(&mut self, track_id: u32) -> CommandResult<()> {
+        let removed = Some(SidechainSource::AudioTrack(track_id));
+        if let Some(mut insert) = self.synth_dynamics
+            && insert.sidechain == removed
+        {
+            insert.sidechain = None;
+            self.set_synth_dynamics(Some(insert))?;
+        }
+        if self.plugin_sidechain == removed {
+            self.set_plugin_sidechain(None)?;
+        }
+        Ok(())
+    }
+
+    /// Add a return bus in the first free slot, returns the slot
+    pub fn add_return_bus(&mut self, bus: ReturnBus) -> CommandResult<usize> {
+        let index = self
+            .return_buses
+            .iter()
+            .position(Option::is_none)
+            .ok_or_else(|| {
+                CommandError::InvalidState(format!(
+                    "All {} return buses are in use",
+                    MAX_RETURN_BUSES
+                ))
+            })?;
+        self.set_return_bus(index, Some(bus))?;
+        Ok(index)
+    }
+
+    /// Set the return bus of a slot (None removes it)
+    ///
+    /// Removing a bus turns off the synth send to it; the frontends clear the
+    /// audio track sends (`AudioTrackList::clear_sends`).
+    pub fn set_return_bus(&mut self, index: usize, bus: Option<ReturnBus>) -> CommandResult<()> {
+        if index >= MAX_RETURN_BUSES {
+            return Err(CommandError::InvalidState(format!(
+                "Return bus slot out of range: {}",
+                index
+            )));
+        }
+        let bus = bus.map(|mut bus| {
+            bus.params.validate();
+            bus
+        });
+        let params = bus.as_ref().map(|bus| bus.params);
+        self.return_buses[index] = bus;
+        self.send_or_fail(Command::SetReturnBus { index, params })?;
+        if params.is_none() && self.synth_sends[index] != TrackSend::default() {
+            self.set_synth_send(index, TrackSend::default())?;
+        }
+        Ok(())
+    }
+
+    /// Set the synth send to a return bus (level clamped to 0.0 - 1.0)
+    pub fn set_synth_send(&mut self, index: usize, mut send: TrackSend) -> CommandResult<()> {
+        if index >= MAX_RETURN_BUSES {
+            return Err(CommandError::InvalidState(format!(
+                "Return bus slot out of range: {}",
+                index
+            )));
+        }
+        send.level = send.level.clamp(0.0, 1.0);
+        self.synth_sends[index] = send;
+        self.send_or_fail(Command::SetSynthSends(self.synth_sends))
+    }
+
+    /// Set the output of the synth track (pairs the device lacks play on the master bus)
+    pub fn set_synth_output(&mut self, output: TrackOutput) -> CommandResult<()> {
+        if !output.is_valid() {
+            return Err(CommandError::InvalidState(format!(
+                "Invalid output: {:?}",
+                output
+            )));
+        }
+        self.synth_output = output;
+        self.send_or_fail(Command::SetSynthOutput(output))
+    }
+
+    /// Set the surround panner of the synth track (None: stereo)
+    pub fn set_synth_surround(&mut self, pan: Option<SurroundPan>) -> CommandResult<()> {
+        self.synth_surround = pan;
+        self.send_or_fail(Command::SetSynthSurround(pan))
+    }
+
+    /// Gain, pan, mute and solo of a track (None for an unknown audio track)
+    pub fn track_strip(&self, track: MixerTrack) -> Option<ChannelStrip> {
+        match track {
+            MixerTrack::Synth => Some(self.synth_strip),
+            MixerTrack::Audio(id) => self.audio_tracks.track(id).map(|track| track.strip()),
+            MixerTrack::Instrument(id) => self.instrument_track(id).map(|track| track.strip),
+        }
+    }
+
+    /// Set the gain (clamped to 0.0 - `MAX_CLIP_GAIN`), pan, mute and solo of
+    /// a track in the mixer stage
+    pub fn set_track_strip(
+        &mut self,
+        track: MixerTrack,
+        mut strip: ChannelStrip,
+    ) -> CommandResult<()> {
+        strip.gain = strip.gain.clamp(0.0, MAX_CLIP_GAIN);
+        strip.pan = strip.pan.clamp(-1.0, 1.0);
+        match track {
+            MixerTrack::Synth => self.synth_strip = strip,
+            MixerTrack::Audio(id) => self
+                .audio_tracks
+                .track_mut(id)
+                .ok_or_else(|| {
+                    CommandError::InvalidState(format!("Audio track not found: {}", id))
+                })?
+                .set_strip(strip),
+            MixerTrack::Instrument(id) => self.instrument_track_mut(id)?.strip = strip,
+        }
+        self.send_or_fail(Command::SetTrackStrip { track, strip })
+    }
+
+    /// Insert effect chain of a track (None for an unknown track)
+    pub fn track_inserts(&self, track: MixerTrack) -> Option<InsertChainParams> {
+        match track {
+            MixerTrack::Synth => Some(self.synth_inserts),
+            MixerTrack::Audio(id) => self.audio_tracks.track(id).map(|track| track.inserts),
+            MixerTrack::Instrument(id) => self.instrument_track(id).map(|track| track.inserts),
+        }
+    }
+
+    /// Set the insert effect chain of a track (parameters are clamped, an
+    /// invalid order falls back to the default one)
+    pub fn set_track_inserts(
+        &mut self,
+        track: MixerTrack,
+        mut inserts: InsertChainParams,
+    ) -> CommandResult<()> {
+        inserts.validate();
+        match track {
+            MixerTrack::Synth => self.synth_inserts = inserts,
+            MixerTrack::Audio(id) => {
+                self.audio_tracks
+                    .track_mut(id)
+                    .ok_or_else(|| {
+                        CommandError::InvalidState(format!("Audio track not found: {}", id))
+                    })?
+                    .inserts = inserts
+            }
+            MixerTrack::Instrument(id) => self.instrument_track_mut(id)?.inserts = inserts,
+        }
+        self.send_or_fail(Command::SetTrackInserts { track, inserts })
+    }
+
+    /// Set the processing order of the insert effects of a track
+    pub fn set_track_insert_order(
+        &mut self,
+        track: MixerTrack,
+        order: InsertEffectOrder,
+    ) -> CommandResult<()> {
+        if !is_valid_insert_order(&order) {
+            return Err(CommandError::InvalidState(
+                "Effect order must list every insert effect exactly once".into(),
+            ));
+        }
+        let inserts = self.existing_track_inserts(track)?;
+        self.set_track_inserts(track, InsertChainParams { order, ..inserts })
+    }
+
+    /// Bypass (or re-enable) an insert effect of a track
+    pub fn set_track_insert_bypass(
+        &mut self,
+        track: MixerTrack,
+        effect: InsertEffect,
+        bypassed: bool,
+    ) -> CommandResult<()> {
+        let mut inserts = self.existing_track_inserts(track)?;
+        inserts.set_bypassed(effect, bypassed);
+        self.set_track_inserts(track, inserts)
+    }
+
+    fn existing_track_inserts(&self, track: MixerTrack) -> CommandResult<InsertChainParams> {
+        self.track_inserts(track)
+            .ok_or_else(|| CommandError::InvalidState(format!("Track not found: {:?}", track)))
+    }
+
+    /// Instrument track by id
+    pub fn instrument_track(&self, id: u32) -> Option<&InstrumentTrack> {
+        self.instrument_tracks.iter().find(|track| track.id == id)
+    }
+
+    fn instrument_track_mut(&mut self, id: u32) -> CommandResult<&mut InstrumentTrack> {
+        self.instrument_tracks
+            .iter_mut()
+            .find(|track| track.id == id)
+            .ok_or_else(|| {
+                CommandError::InvalidState(format!("Instrument track not found: {}", id))
+            })
+    }
+
+    /// Add an instrument track playing an empty pattern, returning its id
+    pub fn add_instrument_track(
+        &mut self,
+        name: String,
+        instrument: TrackInstrument,
+    ) -> CommandResult<u32> {
+        let id = self.next_instrument_track_id;
+        let pattern = Pattern::new_default(crate::project::generate_pattern_id(), name.clone());
+        self.insert_instrument_track(InstrumentTrack {
+            id,
+            name,
+            instrument,
+            pattern: Arc::new(pattern),
+            strip: ChannelStrip::default(),
+            inserts: InsertChainParams::default(),
+        })?;
+        Ok(id)
+    }
+
+    /// Insert an instrument track with its id (e.g. from a project file)
+    pub fn insert_instrument_track(&mut self, track: InstrumentTrack) -> CommandResult<()> {
+        if self.instrument_tracks.len() >= MAX_INSTRUMENT_TRACKS {
+            return Err(CommandError::InvalidState(format!(
+                "No more than {} instrument tracks",
+                MAX_INSTRUMENT_TRACKS
+            )));
+        }
+        if self.instrument_track(track.id).is_some() {
+            return Err(CommandError::InvalidState(format!(
+                "Instrument track already exists: {}",
+                track.id
+            )));
+        }
+        self.next_instrument_track_id = self.next_instrument_track_id.max(track.id + 1);
+        let commands = Self::instrument_track_commands(&track);
+        self.instrument_tracks.push(track);
+        for command in commands {
+            self.send_or_fail(command)?;
+        }
+        Ok(())
+    }
+
+    /// Remove an instrument track with its clips, returning it
+    pub fn remove_instrument_track(&mut self, id: u32) -> CommandResult<InstrumentTrack> {
+        let index = self
+            .instrument_tracks
+            .iter()
+            .position(|track| track.id == id)
+            .ok_or_else(|| {
+                CommandError::InvalidState(format!("Instrument track not found: {}", id))
+            })?;
+        let track = self.instrument_tracks.remove(index);
+        self.send_or_fail(Command::SetTrackInstrument {
+            track: id,
+            instrument: None,
+        })?;
+        if self.arrangement.remove_track(id) {
+            self.send_arrangement()?;
+        }
+        if self.session.remove_track(id) {
+            self.send_session()?;
+        }
+        Ok(track)
+    }
+
+    /// Remove every instrument track (e.g. before loading a project)
+    pub fn clear_instrument_tracks(&mut self) -> CommandResult<()> {
+        let ids: Vec<u32> = self
+            .instrument_tracks
+            .iter()
+            .map(|track| track.id)
+            .collect();
+        for id in ids {
+            self.remove_instrument_track(id)?;
+        }
+        self.next_instrument_track_id = 1;
+        Ok(())
+    }
+
+    /// Change the instrument of an instrument track
+    pub fn set_track_instrument(
+        &mut self,
+        id: u32,
+        instrument: TrackInstrument,
+    ) -> CommandResult<()> {
+        self.instrument_track_mut(id)?.instrument = instrument;
+        self.send_or_fail(Command::SetTrackInstrument {
+            track: id,
+            instrument: Some(Arc::new(instrument)),
+        })
+    }
+
+    /// Replace the pattern played by an instrument track
+    pub fn set_track_pattern(&mut self, id: u32, pattern: Pattern) -> CommandResult<()> {
+        let pattern = Arc::new(pattern);
+        self.instrument_track_mut(id)?.pattern = pattern.clone();
+        self.send_or_fail(Command::SetTrackPattern { track: id, pattern })
+    }
+
+    /// Commands giving an instrument track its slot, pattern, strip and inserts
+    fn instrument_track_commands(track: &InstrumentTrack) -> [Command; 4] {
+        [
+            Command::SetTrackInstrument {
+                track: track.id,
+                instrument: Some(Arc::new(track.instrument)),
+            },
+            Command::SetTrackPattern {
+                track: track.id,
+                pattern: track.pattern.clone(),
+            },
+            Command::SetTrackStrip {
+                track: MixerTrack::Instrument(track.id),
+                strip: track.strip,
+            },
+            Command::SetTrackInserts {
+                track: MixerTrack::Instrument(track.id),
+                inserts: track.inserts,
+            },
+        ]
+    }
+
+    /// Send the automation lanes to the audio thread
+    pub fn send_automation(&mut self) -> CommandResult<()> {
+        self.send_or_fail(self.automation_command())
+    }
+
+    fn automation_command(&self) -> Command {
+        let lanes = self.automation_lanes.values().cloned().collect();
+        Command::SetAutomation(Arc::new(lanes))
+    }
+
+    /// Replace the points of a parameter's lane (no point removes the lane)
+    ///
+    /// Points are sorted by time and values clamped to the parameter range.
+    pub fn set_automation_points(
+        &mut self,
+        parameter: AutomationParameter,
+        points: Vec<AutomationPoint>,
+    ) -> CommandResult<()> {
+        let mut lane = AutomationLane::new(parameter);
+        lane.set_points(points)
+            .map_err(CommandError::InvalidState)?;
+        if lane.is_empty() {
+            self.automation_lanes.remove(&parameter);
+        } else {
+            self.automation_lanes.insert(parameter, lane);
+        }
+        self.send_automation()
+    }
+
+    /// Replace every automation lane (e.g. from a project file)
+    ///
+    /// Lanes are validated like edited ones; an invalid lane leaves the state
+    /// untouched.
+    pub fn set_automation_lanes(&mut self, lanes: Vec<AutomationLane>) -> CommandResult<()> {
+        let mut validated = BTreeMap::new();
+        for lane in lanes {
+            let mut checked = AutomationLane::new(lane.parameter);
+            checked
+                .set_points(lane.points().to_vec())
+                .map_err(CommandError::InvalidState)?;
+            if !checked.is_empty() {
+                validated.insert(lane.parameter, checked);
+            }
+        }
+        self.automation_lanes = validated;
+        self.send_automation()
+    }
+
+    /// Set the speaker layout of the mix
+    pub fn set_speaker_layout(&mut self, layout: SpeakerLayout) -> CommandResult<()> {
+        self.speaker_layout = layout;
+        self.send_or_fail(Command::SetSpeakerLayout(layout))
+    }
+
+    /// Commands recreating this state on a fresh audio engine
+    ///
+    /// Volume is not included: frontends share it with the engine through an atomic.
+    pub fn sync_commands(&self) -> Vec<Command> {
+        let mut commands = vec![
+            Command::SetWaveform(self.waveform),
+            Command::SetOscillator2(self.oscillator2),
+            Command::SetUnison(self.unison),
+            Command::SetAntiAliasing(self.anti_aliasing),
+            Command::SetCrossMod(self.cross_mod),
+            Command::SetNoise(self.noise),
+            Command::SetModEnvelope(self.mod_envelope),
+            Command::SetFilterMod(self.filter_mod),
+            Command::SetVoiceMode(self.voice_mode),
+            Command::SetAdsr(self.adsr),
+            Command::SetLfo(self.lfo),
+            Command::SetPolyMode(self.poly_mode),
+            Command::SetPortamento(self.portamento),
+            Command::SetFilter(self.filter),
+        ];
+        for (index, params) in self.mod_lfos.iter().enumerate() {
+            commands.push(Command::SetModLfo {
+                index: index as u8 + 1,
+                params: *params,
+            });
+        }
+        for (index, routing) in self.mod_routings.iter().enumerate() {
+            commands.push(Command::SetModRouting {
+                index: index as u8,
+                routing: *routing,
+            });
+        }
+        commands.push(Command::SetTempo(self.tempo.bpm()));
+        commands.push(Command::SetTimeSignature(
+            self.time_signature.numerator,
+            self.time_signature.denominator,
+        ));
+        commands.push(Command::SetMetronomeEnabled(self.metronome_enabled));
+        commands.push(Command::SetMetronomeVolume(self.metronome_volume));
+        commands.push(Command::SetMetronomeOutput(self.metronome_output));
+        for effect in MasterEffect::ALL {
+            commands.push(self.master_effect_command(effect));
+        }
+        commands.push(Command::SetMasterEffectOrder(self.master_chain.order));
+        commands.push(Command::SetMaxVoices(self.max_voices));
+        commands.push(Command::SetPitchBendRange(self.pitch_bend_range));
+        commands.push(Command::SetSynthDynamics(self.synth_dynamics));
+        commands.push(Command::SetPluginSidechain(self.plugin_sidechain));
+        for (index, bus) in self.return_buses.iter().enumerate() {
+            commands.push(Command::SetReturnBus {
+                index,
+                params: bus.as_ref().map(|bus| bus.params),
+            });
+        }
+        commands.push(Command::SetSynthSends(self.synth_sends));
+        commands.push(Command::SetSynthOutput(self.synth_output));
+        commands.push(Command::SetSpeakerLayout(self.speaker_layout));
+        commands.push(Command::SetSynthSurround(self.synth_surround));
+        commands.push(Command::SetTrackStrip {
+            track: MixerTrack::Synth,
+            strip: self.synth_strip,
+        });
+        commands.push(Command::SetTrackInserts {
+            track: MixerTrack::Synth,
+            inserts: self.synth_inserts,
+        });
+        for track in &self.instrument_tracks {
+            commands.extend(Self::instrument_track_commands(track));
+        }
+        commands.push(self.automation_command());
+        commands.push(self.arrangement_command());
+        commands.push(self.session_command());
+        commands
+    }
+
+    /// Send the whole state to the audio thread (e.g. after an engine restart)
+    pub fn sync_audio(&mut self) -> CommandResult<()> {
+        self.sent_filter = None;
+        self.sent_lfo = None;
+        // A fresh engine has launched nothing
+        self.launcher.release();
+        self.synth_params.set_filter(&self.filter);
+        self.synth_params.set_lfo(&self.lfo);
+        for command in self.sync_commands() {
+            self.send_or_fail(command)?;
+        }
+        self.sent_filter = Some(self.filter);
+        self.sent_lfo = Some(self.lfo);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::inserts::INSERT_DELAY_MAX_MS;
+    use crate::messaging::channels::create_command_channel;
+    use ringbuf::traits::Consumer;
+
+    #[test]
+    fn test_transport_validation() {
+        let (tx, _rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+
+        assert!(state.set_tempo(140.0).is_ok());
+        assert_eq!(state.tempo.bpm(), 140.0);
+        assert!(state.set_tempo(5.0).is_err());
+        assert!(state.set_tempo(f64::NAN).is_err());
+        assert_eq!(state.tempo.bpm(), 140.0);
+
+        assert!(state.set_time_signature(7, 8).is_ok());
+        assert!(state.set_time_signature(0, 4).is_err());
+        assert!(state.set_time_signature(3, 3).is_err());
+        assert_eq!(state.time_signature.numerator, 7);
+    }
+
+    #[test]
+    fn test_max_voices_validation() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        assert_eq!(state.max_voices, MAX_VOICES);
+
+        assert!(state.set_max_voices(8).is_ok());
+        assert!(matches!(rx.try_pop(), Some(Command::SetMaxVoices(8))));
+        assert!(state.set_max_voices(0).is_err());
+        assert!(state.set_max_voices(MAX_VOICES + 1).is_err());
+        assert_eq!(state.max_voices, 8);
+        assert!(
+            state
+                .sync_commands()
+                .iter()
+                .any(|c| matches!(c, Command::SetMaxVoices(8)))
+        );
+    }
+
+    #[test]
+    fn test_pitch_bend_range_validation() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        assert_eq!(state.pitch_bend_range, DEFAULT_PITCH_BEND_RANGE);
+
+        assert!(state.set_pitch_bend_range(12.0).is_ok());
+        assert!(matches!(rx.try_pop(), Some(Command::SetPitchBendRange(r)) if r == 12.0));
+        assert!(state.set_pitch_bend_range(-1.0).is_err());
+        assert!(
+            state
+                .set_pitch_bend_range(MAX_PITCH_BEND_RANGE + 1.0)
+                .is_err()
+        );
+        assert!(state.set_pitch_bend_range(f32::NAN).is_err());
+        assert_eq!(state.pitch_bend_range, 12.0);
+    }
+
+    #[test]
+    fn test_continuous_filter_changes_skip_ringbuffer() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+
+        // First send establishes the discrete fields
+        let mut filter = FilterParams::default();
+        assert!(state.send_filter(filter));
+        assert_eq!(rx.pop_iter().count(), 1);
+
+        // Dragging the cutoff only touches the atomics
+        for cutoff in [500.0, 600.0, 700.0] {
+            filter.cutoff = cutoff;
+            assert!(state.send_filter(filter));
+        }
+        assert_eq!(rx.pop_iter().count(), 0);
+        assert_eq!(
+            state
+                .synth_params
+                .get(crate::audio::parameters::SynthParam::FilterCutoff),
+            700.0
+        );
+
+        // Bypassing the filter needs a command
+        filter.enabled = false;
+        assert!(state.send_filter(filter));
+        assert_eq!(rx.pop_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_sync_audio_after_sender_swap() {
+        let (tx, _old_rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        state.set_tempo(90.0).unwrap();
+
+        let (tx, mut rx) = create_command_channel(64);
+        state.set_command_sender(tx);
+        state.sync_audio().unwrap();
+
+        let mut received = Vec::new();
+        while let Some(command) = rx.try_pop() {
+            received.push(command);
+        }
+        assert_eq!(received.len(), state.sync_commands().len());
+        assert!(
+            received
+                .iter()
+                .any(|c| matches!(c, Command::SetTempo(bpm) if *bpm == 90.0))
+        );
+    }
+
+    #[test]
+    fn test_master_chain_from_project() {
+        let (tx, mut rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+
+        let mut params = MasterChainParams::default();
+        params.order[0] = MasterEffect::Limiter;
+        params.eq.low_gain_db = 40.0;
+        params.compressor.mode = DynamicsMode::Gate;
+        params.compressor.enabled = true;
+        state.set_master_chain(params).unwrap();
+
+        // Invalid orders fall back to the default one
+        assert_eq!(state.master_chain.order, MasterEffect::ALL);
+        assert_eq!(state.master_chain.eq.low_gain_db, 18.0);
+        assert_eq!(state.master_chain.compressor.mode, DynamicsMode::Compressor);
+        assert!(!state.master_chain.is_bypassed(MasterEffect::Compressor));
+        assert_eq!(rx.pop_iter().count(), MasterEffect::ALL.len() + 1);
+
+        // Editing parameters keeps the bypass state
+        state.set_master_eq(EqParams::default()).unwrap();
+        assert!(state.master_chain.is_bypassed(MasterEffect::Eq));
+    }
+
+    #[test]
+    fn test_return_bus_slots() {
+        use crate::audio::returns::{ReturnBusParams, ReturnEffect};
+
+        let (tx, _rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        let bus = ReturnBus {
+            name: "Reverb".to_string(),
+            params: ReturnBusParams::new(ReturnEffect::Reverb(ReverbParams::default())),
+        };
+        for slot in 0..MAX_RETURN_BUSES {
+            assert_eq!(state.add_return_bus(bus.clone()).unwrap(), slot);
+        }
+        assert!(state.add_return_bus(bus.clone()).is_err());
+        assert!(
+            state
+                .set_synth_send(MAX_RETURN_BUSES, TrackSend::default())
+                .is_err()
+        );
+
+        // Removing a bus frees its slot and turns off the synth send
+        let send = TrackSend {
+            level: 2.0,
+            pre_fader: true,
+        };
+        state.set_synth_send(1, send).unwrap();
+        assert_eq!(state.synth_sends[1].level, 1.0);
+        state.set_return_bus(1, None).unwrap();
+        assert_eq!(state.synth_sends[1], TrackSend::default());
+        assert_eq!(state.add_return_bus(bus).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_instrument_tracks() {
+        let (tx, mut rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+
+        let id = state
+            .add_instrument_track("Bass".to_string(), TrackInstrument::Sampler)
+            .unwrap();
+        assert!(matches!(
+            rx.try_pop(),
+            Some(Command::SetTrackInstrument {
+                instrument: Some(instrument),
+                ..
+            }) if matches!(*instrument, TrackInstrument::Sampler)
+        ));
+        assert!(
+            matches!(rx.try_pop(), Some(Command::SetTrackPattern { track, .. }) if track == id)
+        );
+        assert!(matches!(
+            rx.try_pop(),
+            Some(Command::SetTrackStrip {
+                track: MixerTrack::Instrument(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            rx.try_pop(),
+            Some(Command::SetTrackInserts {
+                track: MixerTrack::Instrument(_),
+                ..
+            })
+        ));
+
+        // The strip goes through the mixer like the other tracks
+        let strip = ChannelStrip {
+            gain: 5.0,
+            ..ChannelStrip::default()
+        };
+        let track = MixerTrack::Instrument(id);
+        state.set_track_strip(track, strip).unwrap();
+        assert_eq!(state.track_strip(track).unwrap().gain, MAX_CLIP_GAIN);
+        assert!(
+            state
+                .set_track_strip(MixerTrack::Instrument(99), strip)
+                .is_err()
+        );
+        rx.try_pop();
+
+        // Edits of the active pattern reach the tracks playing it
+        state.set_track_pattern(id, state.pattern.clone()).unwrap();
+        rx.try_pop();
+        state.pattern.name = "Edited".to_string();
+        state.send_pattern().unwrap();
+        assert!(matches!(rx.try_pop(), Some(Command::SetPattern(_))));
+        assert!(matches!(
+            rx.try_pop(),
+            Some(Command::SetTrackPattern { pattern, .. }) if pattern.name == "Edited"
+        ));
+
+        for index in 1..MAX_INSTRUMENT_TRACKS {
+            let name = format!("Track {}", index);
+            state
+                .add_instrument_track(name, TrackInstrument::Sampler)
+                .unwrap();
+        }
+        assert!(
+            state
+                .add_instrument_track("Extra".to_string(), TrackInstrument::Sampler)
+                .is_err()
+        );
+
+        state.remove_instrument_track(id).unwrap();
+        assert!(state.instrument_track(id).is_none());
+        assert!(state.remove_instrument_track(id).is_err());
+        state.clear_instrument_tracks().unwrap();
+        assert!(state.instrument_tracks.is_empty());
+    }
+
+    #[test]
+    fn test_track_inserts() {
+        let (tx, mut rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+
+        let mut inserts = InsertChainParams::default();
+        inserts.delay.time_ms = 5000.0;
+        state.set_track_inserts(MixerTrack::Synth, inserts).unwrap();
+        assert!(matches!(
+            rx.try_pop(),
+            Some(Command::SetTrackInserts {
+                track: MixerTrack::Synth,
+                inserts,
+            }) if inserts.delay.time_ms == INSERT_DELAY_MAX_MS
+        ));
+
+        state
+            .set_track_insert_bypass(MixerTrack::Synth, InsertEffect::Reverb, false)
+            .unwrap();
+        assert!(state.synth_inserts.reverb.enabled);
+        let order = [
+            InsertEffect::Reverb,
+            InsertEffect::Filter,
+            InsertEffect::Delay,
+        ];
+        state
+            .set_track_insert_order(MixerTrack::Synth, order)
+            .unwrap();
+        assert_eq!(state.synth_inserts.order, order);
+        assert!(state.synth_inserts.reverb.enabled);
+
+        let invalid = [InsertEffect::Filter; 3];
+        assert!(
+            state
+                .set_track_insert_order(MixerTrack::Synth, invalid)
+                .is_err()
+        );
+        assert!(
+            state
+                .set_track_inserts(MixerTrack::Audio(7), inserts)
+                .is_err()
+        );
+
+        let id = state.audio_tracks.add_track("Drums".to_string());
+        state
+            .set_track_insert_bypass(MixerTrack::Audio(id), InsertEffect::Filter, false)
+            .unwrap();
+        assert!(state.audio_tracks.track(id).unwrap().inserts.filter.enabled);
+    }
 }
@@ -0,0 +1,12 @@
+// Configuration - User preferences shared by every frontend
+//
+// Settings that belong to the user rather than to a project (devices,
+// theme, keymap, default paths, autosave) are loaded at startup by both the
+// egui and the Tauri entry points.
+
+pub mod preferences;
+
+pub use preferences::{
+    AudioPreferences, BUFFER_SIZES, Keymap, MidiPreferences, OUTPUT_CHANNEL_COUNTS,
+    PathPreferences, Preferences, Theme,
+};
@@ -0,0 +1,311 @@
+// User preferences - Settings that outlive a project
+//
+// Stored as JSON in the platform config directory
+// (e.g. ~/.config/mymusic_daw/preferences.json). Every field has a default,
+// so files written by older or newer versions still load: missing fields
+// take their default value and unknown fields are ignored.
+
+use crate::audio::outputs::TrackOutput;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the preferences inside the config directory
+const PREFERENCES_FILE: &str = "preferences.json";
+
+/// Supported audio buffer sizes (frames)
+pub const BUFFER_SIZES: [u32; 7] = [64, 128, 256, 512, 1024, 2048, 4096];
+
+/// Supported output channel counts (surround layouts, direct outputs of
+/// multi-output interfaces)
+pub const OUTPUT_CHANNEL_COUNTS: [u16; 5] = [2, 4, 6, 8, 16];
+
+/// Color theme of the user interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Follow the operating system
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::System, Theme::Dark, Theme::Light];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::System => "System",
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+        }
+    }
+}
+
+/// Computer keyboard layout used to play notes
+///
+/// The note keys sit on the same physical keys whatever the layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Keymap {
+    #[default]
+    Qwerty,
+    Azerty,
+    Qwertz,
+}
+
+impl Keymap {
+    pub const ALL: [Keymap; 3] = [Keymap::Qwerty, Keymap::Azerty, Keymap::Qwertz];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Keymap::Qwerty => "QWERTY",
+            Keymap::Azerty => "AZERTY",
+            Keymap::Qwertz => "QWERTZ",
+        }
+    }
+
+    /// Keys playing one octave from C4 (MIDI 60) to C5, white and black keys
+    pub fn note_keys(self) -> [(char, u8); 13] {
+        let keys = match self {
+            Keymap::Qwerty => "awsedftgyhujk",
+            Keymap::Azerty => "qzsedftgyhujk",
+            Keymap::Qwertz => "awsedftgzhujk",
+        };
+        let mut note_keys = [(' ', 0); 13];
+        for (i, key) in keys.chars().enumerate() {
+            note_keys[i] = (key, 60 + i as u8);
+        }
+        note_keys
+    }
+}
+
+/// Audio device settings (applied when the engine starts)
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioPreferences {
+    /// Output device name (None = system default)
+    pub output_device: Option<String>,
+    /// Buffer size in frames (None = device default)
+    pub buffer_size: Option<u32>,
+    /// Output channels to open (None = device default)
+    pub output_channels: Option<u16>,
+    /// Output of the metronome bus (pairs the device lacks play on channels 1-2)
+    pub metronome_output: TrackOutput,
+    /// Bars of count-in before recording (0 = off)
+    pub count_in_bars: u32,
+    /// Recording input device name (None = system default)
+    pub input_device: Option<String>,
+    /// Recorded input channel, from 0 (None = mix of all channels)
+    pub input_channel: Option<u16>,
+}
+
+/// MIDI device settings
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MidiPreferences {
+    /// Input port name (None = first available port)
+    pub input_device: Option<String>,
+    /// Mackie Control surface port (None = no surface)
+    pub control_surface: Option<String>,
+}
+
+/// Directories proposed by file dialogs (None = let the dialog decide)
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathPreferences {
+    pub projects: Option<PathBuf>,
+    pub samples: Option<PathBuf>,
+    pub exports: Option<PathBuf>,
+}
+
+/// User preferences
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub audio: AudioPreferences,
+    pub midi: MidiPreferences,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    pub paths: PathPreferences,
+    /// Seconds between two autosaves of a modified project (0 = disabled)
+    pub autosave_interval_secs: u32,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            audio: AudioPreferences::default(),
+            midi: MidiPreferences::default(),
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            paths: PathPreferences::default(),
+            autosave_interval_secs: 300,
+        }
+    }
+}
+
+impl Preferences {
+    /// Default preferences file (user config dir)
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_default()
+            .join("mymusic_daw")
+            .join(PREFERENCES_FILE)
+    }
+
+    /// Load the preferences from the default file
+    ///
+    /// A missing or unreadable file gives the defaults (the error is logged):
+    /// preferences must never prevent the DAW from starting.
+    pub fn load() -> Self {
+        let path = Self::default_path();
+        if !path.exists() {
+            return Self::default();
+        }
+        Self::load_from(&path).unwrap_or_else(|e| {
+            eprintln!("⚠️  {}, using default preferences", e);
+            Self::default()
+        })
+    }
+
+    /// Load the preferences from a file
+    pub fn load_from(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read preferences: {}", e))?;
+        let mut preferences: Self = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse preferences: {}", e))?;
+        preferences.sanitize();
+        Ok(preferences)
+    }
+
+    /// Save the preferences to the default file
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to(&Self::default_path())
+    }
+
+    /// Save the preferences to a file (parent directories are created)
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize preferences: {}", e))?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to write preferences: {}", e))
+    }
+
+    /// Drop values the engine cannot use (hand-edited files)
+    pub fn sanitize(&mut self) {
+        if self
+            .audio
+            .buffer_size
+            .is_some_and(|size| !BUFFER_SIZES.contains(&size))
+        {
+            self.audio.buffer_size = None;
+        }
+        if self
+            .audio
+            .output_channels
+            .is_some_and(|channels| !OUTPUT_CHANNEL_COUNTS.contains(&channels))
+        {
+            self.audio.output_channels = None;
+        }
+        if !self.audio.metronome_output.is_valid() {
+            self.audio.metronome_output = TrackOutput::Master;
+        }
+        for device in [
+            &mut self.audio.output_device,
+            &mut self.audio.input_device,
+            &mut self.midi.input_device,
+            &mut self.midi.control_surface,
+        ] {
+            if device.as_ref().is_some_and(|name| name.trim().is_empty()) {
+                *device = None;
+            }
+        }
+    }
+
+    /// File the modified project is autosaved to
+    ///
+    /// Saved projects get a sibling `<name>.mymusic.autosave` file, untitled
+    /// ones go to the user data directory.
+    pub fn autosave_path(project_path: Option<&Path>) -> PathBuf {
+        match project_path {
+            Some(path) => path.with_extension("mymusic.autosave"),
+            None => dirs::data_dir()
+                .unwrap_or_default()
+                .join("mymusic_daw")
+                .join("autosave")
+                .join("untitled.mymusic"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("nested").join(PREFERENCES_FILE);
+
+        let preferences = Preferences {
+            audio: AudioPreferences {
+                output_device: Some("USB Audio".to_string()),
+                buffer_size: Some(256),
+                output_channels: Some(8),
+                metronome_output: TrackOutput::Direct(1),
+                count_in_bars: 2,
+                input_device: Some("Interface In".to_string()),
+                input_channel: Some(1),
+            },
+            midi: MidiPreferences {
+                input_device: Some("Keystation".to_string()),
+                control_surface: Some("X-Touch".to_string()),
+            },
+            theme: Theme::Light,
+            keymap: Keymap::Azerty,
+            paths: PathPreferences {
+                projects: Some(PathBuf::from("/music/projects")),
+                ..Default::default()
+            },
+            autosave_interval_secs: 60,
+        };
+        preferences.save_to(&path).unwrap();
+        assert_eq!(Preferences::load_from(&path).unwrap(), preferences);
+    }
+
+    #[test]
+    fn test_partial_and_invalid_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(PREFERENCES_FILE);
+
+        // Missing fields take their default, unknown fields are ignored
+        std::fs::write(
+            &path,
+            r#"{"theme": "dark", "audio": {"buffer_size": 300, "output_device": " ", "output_channels": 3, "metronome_output": {"direct": 9}, "input_device": ""}, "future": 1}"#,
+        )
+        .unwrap();
+        let preferences = Preferences::load_from(&path).unwrap();
+        assert_eq!(preferences.theme, Theme::Dark);
+        assert_eq!(preferences.audio, AudioPreferences::default());
+        assert_eq!(preferences.autosave_interval_secs, 300);
+
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(Preferences::load_from(&path).is_err());
+    }
+
+    #[test]
+    fn test_keymaps_cover_one_octave() {
+        for keymap in Keymap::ALL {
+            let keys = keymap.note_keys();
+            assert_eq!(keys[0].1, 60);
+            assert_eq!(keys[12].1, 72);
+        }
+        assert_eq!(Keymap::Azerty.note_keys()[0].0, 'q');
+        assert_eq!(Keymap::Qwertz.note_keys()[8].0, 'z');
+    }
+}
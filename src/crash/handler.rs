@@ -0,0 +1,270 @@
+// Crash handler - Rescue the session when the process dies
+//
+// A panic hook (every platform) and fatal signal handlers (Unix: SIGSEGV,
+// SIGBUS, SIGILL, SIGFPE, SIGABRT, e.g. a crashing plugin) promote the session
+// copy to the rescue project and write a crash report.
+//
+// Signal handlers may only call async-signal-safe functions: they rename the
+// session files and write diagnostics rendered in advance through libc, then
+// re-raise the signal with its default action. The panic hook runs on the
+// panicking thread and may allocate; the default hook runs after it.
+
+use crate::crash::rescue::{Diagnostics, RescueSession, RescueStore};
+use crate::project::Project;
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock, TryLockError};
+
+struct CrashHandler {
+    store: RescueStore,
+    /// Rendered diagnostics (see `update_diagnostics`)
+    diagnostics: Mutex<String>,
+    /// A panic report was written (see the SIGABRT handler)
+    reported: AtomicBool,
+    #[cfg(unix)]
+    signal_paths: signals::SignalPaths,
+}
+
+static HANDLER: OnceLock<CrashHandler> = OnceLock::new();
+
+/// Install the crash handlers, rescuing into `dir`
+///
+/// Returns the session rescued from the previous run, if any: a session copy
+/// still present at this point means the previous run did not exit cleanly.
+/// Only the first call installs the handlers.
+pub fn install(dir: PathBuf) -> Option<RescueSession> {
+    if HANDLER.get().is_some() {
+        return None;
+    }
+
+    let store = RescueStore::new(dir);
+    store.promote();
+    let rescue = store.pending_rescue();
+
+    let handler = CrashHandler {
+        #[cfg(unix)]
+        signal_paths: signals::SignalPaths::new(&store),
+        store,
+        diagnostics: Mutex::new(Diagnostics::default().render()),
+        reported: AtomicBool::new(false),
+    };
+    if HANDLER.set(handler).is_err() {
+        return None;
+    }
+
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(handler) = HANDLER.get() {
+            handler.on_panic(info);
+        }
+        previous_hook(info);
+    }));
+    #[cfg(unix)]
+    signals::install();
+
+    rescue
+}
+
+/// Keep a copy of the current (unsaved) session for the crash handlers
+pub fn update_session(project: &Project, project_path: Option<&Path>) -> Result<(), String> {
+    match HANDLER.get() {
+        Some(handler) => handler.store.save_session(project, project_path),
+        None => Ok(()),
+    }
+}
+
+/// Forget the session copy (the project has been saved)
+pub fn clear_session() {
+    if let Some(handler) = HANDLER.get() {
+        handler.store.clear_session();
+    }
+}
+
+/// Update the diagnostics written to the crash report
+pub fn update_diagnostics(diagnostics: &Diagnostics) {
+    if let Some(handler) = HANDLER.get() {
+        let text = diagnostics.render();
+        *handler
+            .diagnostics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = text;
+    }
+}
+
+/// Delete the rescued session once it has been restored or declined
+pub fn discard_rescue() {
+    if let Some(handler) = HANDLER.get() {
+        handler.store.discard_rescue();
+    }
+}
+
+/// Clean exit: delete the session copy and any rescue left over
+pub fn shutdown() {
+    if let Some(handler) = HANDLER.get() {
+        handler.store.clear();
+    }
+}
+
+impl CrashHandler {
+    fn on_panic(&self, info: &PanicHookInfo) {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_default();
+        let reason = format!(
+            "panic in thread '{}' at {}: {}\n\n{}",
+            std::thread::current().name().unwrap_or("<unnamed>"),
+            location,
+            message,
+            Backtrace::force_capture()
+        );
+
+        // The panicking thread may hold the lock
+        let diagnostics = match self.diagnostics.try_lock() {
+            Ok(diagnostics) => diagnostics.clone(),
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().clone(),
+            Err(TryLockError::WouldBlock) => String::new(),
+        };
+
+        if self.store.promote() {
+            eprintln!(
+                "💾 Session rescued to {:?}",
+                self.store.path(crate::crash::rescue::RESCUE_PROJECT)
+            );
+        }
+        self.reported.store(true, Ordering::SeqCst);
+        match self.store.write_report(&reason, &diagnostics) {
+            Ok(path) => eprintln!("💥 Crash report written to {:?}", path),
+            Err(e) => eprintln!("❌ {}", e),
+        }
+    }
+}
+
+#[cfg(unix)]
+mod signals {
+    use super::HANDLER;
+    use crate::crash::rescue::{
+        CRASH_REPORT, REPORT_HEADER, RESCUE_INFO, RESCUE_PROJECT, RescueStore, SESSION_INFO,
+        SESSION_PROJECT,
+    };
+    use std::ffi::CString;
+    use std::sync::atomic::Ordering;
+
+    const FATAL_SIGNALS: [(libc::c_int, &str); 5] = [
+        (libc::SIGSEGV, "SIGSEGV"),
+        (libc::SIGBUS, "SIGBUS"),
+        (libc::SIGILL, "SIGILL"),
+        (libc::SIGFPE, "SIGFPE"),
+        (libc::SIGABRT, "SIGABRT"),
+    ];
+
+    /// File paths, converted before a signal can arrive
+    pub(super) struct SignalPaths {
+        session_project: CString,
+        session_info: CString,
+        rescue_project: CString,
+        rescue_info: CString,
+        report: CString,
+    }
+
+    impl SignalPaths {
+        pub(super) fn new(store: &RescueStore) -> Self {
+            let path = |name: &str| {
+                CString::new(store.path(name).into_os_string().into_encoded_bytes())
+                    .unwrap_or_default()
+            };
+            Self {
+                session_project: path(SESSION_PROJECT),
+                session_info: path(SESSION_INFO),
+                rescue_project: path(RESCUE_PROJECT),
+                rescue_info: path(RESCUE_INFO),
+                report: path(CRASH_REPORT),
+            }
+        }
+    }
+
+    pub(super) fn install() {
+        for (signal, _) in FATAL_SIGNALS {
+            // SAFETY: the handler only calls async-signal-safe functions.
+            // SA_ONSTACK lets it run on the alternate stack set up by std,
+            // so a stack overflow is reported too.
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = on_fatal_signal as *const () as libc::sighandler_t;
+                action.sa_flags = libc::SA_ONSTACK | libc::SA_RESETHAND;
+                libc::sigemptyset(&mut action.sa_mask);
+                libc::sigaction(signal, &action, std::ptr::null_mut());
+            }
+        }
+    }
+
+    extern "C" fn on_fatal_signal(signal: libc::c_int) {
+        if let Some(handler) = HANDLER.get() {
+            let name = FATAL_SIGNALS
+                .iter()
+                .find(|(fatal, _)| *fatal == signal)
+                .map_or("fatal signal", |(_, name)| *name);
+            let paths = &handler.signal_paths;
+
+            // SAFETY: rename, open, write and close are async-signal-safe and
+            // the paths were built at installation
+            unsafe {
+                libc::rename(
+                    paths.session_project.as_ptr(),
+                    paths.rescue_project.as_ptr(),
+                );
+                libc::rename(paths.session_info.as_ptr(), paths.rescue_info.as_ptr());
+
+                // A panic that aborts raises SIGABRT: its report is kept. Also
+                // skipped if the signal arrived while the diagnostics were updated.
+                let after_panic =
+                    signal == libc::SIGABRT && handler.reported.load(Ordering::SeqCst);
+                if !after_panic && let Ok(diagnostics) = handler.diagnostics.try_lock() {
+                    let fd = libc::open(
+                        paths.report.as_ptr(),
+                        libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC,
+                        0o644 as libc::c_uint,
+                    );
+                    if fd >= 0 {
+                        for part in [
+                            REPORT_HEADER,
+                            "Reason: ",
+                            name,
+                            "\n\n",
+                            diagnostics.as_str(),
+                        ] {
+                            write_all(fd, part.as_bytes());
+                        }
+                        libc::close(fd);
+                    }
+                }
+            }
+        }
+
+        // The handler was reset to the default action (SA_RESETHAND)
+        // SAFETY: raise is async-signal-safe
+        unsafe {
+            libc::raise(signal);
+        }
+    }
+
+    /// Write a buffer to a file descriptor (async-signal-safe)
+    unsafe fn write_all(fd: libc::c_int, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            // SAFETY: the buffer is valid for `bytes.len()` bytes
+            let written = unsafe { libc::write(fd, bytes.as_ptr().cast(), bytes.len()) };
+            if written <= 0 {
+                return;
+            }
+            bytes = &bytes[written as usize..];
+        }
+    }
+}
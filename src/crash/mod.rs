@@ -0,0 +1,13 @@
+// Crash handling - Session rescue and crash reports
+//
+// Frontends install the handlers at startup, keep the rescue copy of their
+// session up to date while the project has unsaved changes, and offer to
+// restore the rescued session returned by `install` on the next launch.
+
+pub mod handler;
+pub mod rescue;
+
+pub use handler::{
+    clear_session, discard_rescue, install, shutdown, update_diagnostics, update_session,
+};
+pub use rescue::{Diagnostics, RescueSession, RescueStore, SessionInfo, describe_notification};
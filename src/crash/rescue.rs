@@ -0,0 +1,292 @@
+// Session rescue - Files left behind by a crash
+//
+// While the DAW runs, the frontend keeps a copy of its unsaved session in the
+// rescue directory (`session.mymusic` + `session.json`). A crash promotes it
+// to `rescue.mymusic` + `rescue.json` and writes `crash-report.txt`; a clean
+// exit deletes everything. Promotion is a plain rename, so a signal handler
+// can do it too.
+//
+// On the next launch, the promoted session (or a session file left by a
+// process killed before any handler could run) is offered for restoration.
+
+use crate::messaging::notification::Notification;
+use crate::project::{Project, ProjectManager};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub(crate) const SESSION_PROJECT: &str = "session.mymusic";
+pub(crate) const SESSION_INFO: &str = "session.json";
+pub(crate) const RESCUE_PROJECT: &str = "rescue.mymusic";
+pub(crate) const RESCUE_INFO: &str = "rescue.json";
+pub(crate) const CRASH_REPORT: &str = "crash-report.txt";
+
+/// First lines of every crash report
+pub(crate) const REPORT_HEADER: &str = concat!(
+    "MyMusic DAW crash report\nVersion: ",
+    env!("CARGO_PKG_VERSION"),
+    "\n"
+);
+
+/// Session the rescue project was taken from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub project_name: String,
+    /// File the project was last saved to (None = untitled)
+    pub project_path: Option<PathBuf>,
+    pub saved_at: DateTime<Utc>,
+}
+
+/// State of the DAW written to the crash report
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Diagnostics {
+    /// One line per engine property (e.g. "CPU load: 12%")
+    pub engine_status: Vec<String>,
+    /// Most recent notifications, oldest first
+    pub recent_notifications: Vec<String>,
+    pub loaded_plugins: Vec<String>,
+}
+
+impl Diagnostics {
+    /// Plain text body of the crash report
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for (title, lines) in [
+            ("Engine", &self.engine_status),
+            ("Recent notifications", &self.recent_notifications),
+            ("Loaded plugins", &self.loaded_plugins),
+        ] {
+            text.push_str(title);
+            text.push('\n');
+            if lines.is_empty() {
+                text.push_str("  (none)\n");
+            }
+            for line in lines {
+                text.push_str("  ");
+                text.push_str(line);
+                text.push('\n');
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// One line of the crash report for a notification
+pub fn describe_notification(notification: &Notification) -> String {
+    format!(
+        "[{:?}/{:?}] {}",
+        notification.level, notification.category, notification.message
+    )
+}
+
+/// Session rescued after a crash, waiting to be restored or discarded
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RescueSession {
+    /// Rescued project (a regular project file)
+    pub project_file: PathBuf,
+    pub info: SessionInfo,
+    /// Report of the crash (missing if the process was killed)
+    pub report_file: Option<PathBuf>,
+}
+
+/// Rescue directory (see the module documentation for its content)
+#[derive(Debug, Clone)]
+pub struct RescueStore {
+    dir: PathBuf,
+}
+
+impl RescueStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Default rescue directory (user data dir)
+    pub fn default_dir() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_default()
+            .join("mymusic_daw")
+            .join("rescue")
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub(crate) fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Keep a copy of the current session, to be rescued if the DAW crashes
+    ///
+    /// The project is written next to the session file then renamed over it,
+    /// so a crash while saving leaves the previous copy intact.
+    pub fn save_session(
+        &self,
+        project: &Project,
+        project_path: Option<&Path>,
+    ) -> Result<(), String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create rescue directory: {}", e))?;
+
+        let temp = self.path("session.tmp.mymusic");
+        ProjectManager::new(project.metadata.sample_rate)
+            .save_project(project, &temp)
+            .map_err(|e| format!("Failed to save rescue session: {}", e))?;
+        std::fs::rename(&temp, self.path(SESSION_PROJECT))
+            .map_err(|e| format!("Failed to save rescue session: {}", e))?;
+
+        let info = SessionInfo {
+            project_name: project.metadata.name.clone(),
+            project_path: project_path.map(Path::to_path_buf),
+            saved_at: Utc::now(),
+        };
+        let json = serde_json::to_string_pretty(&info)
+            .map_err(|e| format!("Failed to serialize rescue session: {}", e))?;
+        std::fs::write(self.path(SESSION_INFO), json)
+            .map_err(|e| format!("Failed to save rescue session: {}", e))
+    }
+
+    /// Forget the session copy (the project has been saved)
+    pub fn clear_session(&self) {
+        remove_files(&[self.path(SESSION_PROJECT), self.path(SESSION_INFO)]);
+    }
+
+    /// Turn the session copy into the rescue project (after a crash)
+    ///
+    /// Returns false if there was no session to rescue.
+    pub fn promote(&self) -> bool {
+        if std::fs::rename(self.path(SESSION_PROJECT), self.path(RESCUE_PROJECT)).is_err() {
+            return false;
+        }
+        let _ = std::fs::rename(self.path(SESSION_INFO), self.path(RESCUE_INFO));
+        true
+    }
+
+    /// Write the crash report (replaces the previous one)
+    pub fn write_report(&self, reason: &str, diagnostics: &str) -> Result<PathBuf, String> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create rescue directory: {}", e))?;
+        let path = self.path(CRASH_REPORT);
+        std::fs::write(
+            &path,
+            format!("{}Reason: {}\n\n{}", REPORT_HEADER, reason, diagnostics),
+        )
+        .map_err(|e| format!("Failed to write crash report: {}", e))?;
+        Ok(path)
+    }
+
+    /// Rescued session left by a previous run, if any
+    pub fn pending_rescue(&self) -> Option<RescueSession> {
+        let project_file = self.path(RESCUE_PROJECT);
+        let modified = std::fs::metadata(&project_file).ok()?.modified().ok();
+
+        let info = std::fs::read_to_string(self.path(RESCUE_INFO))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_else(|| SessionInfo {
+                project_name: "Rescued session".to_string(),
+                project_path: None,
+                saved_at: modified.map(DateTime::from).unwrap_or_else(Utc::now),
+            });
+        // A report older than the session belongs to an earlier crash
+        let report_file = Some(self.path(CRASH_REPORT)).filter(|path| {
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|report| modified.is_none_or(|session| report >= session))
+        });
+
+        Some(RescueSession {
+            project_file,
+            info,
+            report_file,
+        })
+    }
+
+    /// Delete the rescued session (restored or declined); the report is kept
+    pub fn discard_rescue(&self) {
+        remove_files(&[self.path(RESCUE_PROJECT), self.path(RESCUE_INFO)]);
+    }
+
+    /// Clean exit: nothing is left to rescue
+    pub fn clear(&self) {
+        self.clear_session();
+        self.discard_rescue();
+    }
+}
+
+fn remove_files(paths: &[PathBuf]) {
+    for path in paths {
+        if let Err(e) = std::fs::remove_file(path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            eprintln!("Failed to remove {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::ProjectLoadOptions;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_session_is_rescued_after_crash() {
+        let dir = TempDir::new().unwrap();
+        let store = RescueStore::new(dir.path().join("rescue"));
+        assert!(store.pending_rescue().is_none());
+
+        let manager = ProjectManager::new(48000.0);
+        let mut project = manager.create_new_project("Crashy Song".to_string());
+        project.metadata.tempo = 98.0;
+        let project_path = PathBuf::from("/music/crashy.mymusic");
+        store.save_session(&project, Some(&project_path)).unwrap();
+
+        // Nothing is offered until the session is promoted by a crash
+        assert!(store.pending_rescue().is_none());
+        assert!(store.promote());
+        store
+            .write_report("test panic", &Diagnostics::default().render())
+            .unwrap();
+
+        let rescue = store.pending_rescue().unwrap();
+        assert_eq!(rescue.info.project_name, "Crashy Song");
+        assert_eq!(rescue.info.project_path, Some(project_path));
+        let report = std::fs::read_to_string(rescue.report_file.unwrap()).unwrap();
+        assert!(report.contains("Reason: test panic"));
+
+        let restored = manager
+            .load_project(&rescue.project_file, &ProjectLoadOptions::default())
+            .unwrap();
+        assert_eq!(restored.metadata.tempo, 98.0);
+
+        store.discard_rescue();
+        assert!(store.pending_rescue().is_none());
+    }
+
+    #[test]
+    fn test_clean_exit_leaves_nothing() {
+        let dir = TempDir::new().unwrap();
+        let store = RescueStore::new(dir.path());
+        let project = ProjectManager::new(48000.0).create_new_project("Song".to_string());
+
+        store.save_session(&project, None).unwrap();
+        store.clear();
+        assert!(!store.promote());
+        assert!(store.pending_rescue().is_none());
+    }
+
+    #[test]
+    fn test_diagnostics_render() {
+        let diagnostics = Diagnostics {
+            engine_status: vec!["CPU load: 12%".to_string()],
+            recent_notifications: vec![],
+            loaded_plugins: vec!["Surge XT".to_string()],
+        };
+        let text = diagnostics.render();
+        assert!(text.contains("Engine\n  CPU load: 12%\n"));
+        assert!(text.contains("Recent notifications\n  (none)\n"));
+        assert!(text.contains("Loaded plugins\n  Surge XT\n"));
+    }
+}
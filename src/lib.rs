@@ -1,23 +1,32 @@
 // MyMusic DAW - Library exports for tests and benchmarks
 
 pub mod audio;
+pub mod automation;
 pub mod command;
+pub mod config;
 pub mod connection;
+pub mod crash;
 pub mod messaging;
 pub mod midi;
 pub mod plugin;
 pub mod project;
+pub mod remote;
 pub mod sampler;
+pub mod scripting;
 pub mod sequencer;
 pub mod synth;
+#[cfg(feature = "gui")]
 pub mod ui;
 
 // Re-export commonly used types for convenience
-pub use audio::engine::AudioEngine;
+pub use audio::engine::{AudioEngine, OutputOptions};
 pub use audio::timing::AudioTiming;
 pub use command::{CommandManager, DawState, UndoableCommand};
-pub use messaging::channels::{create_command_channel, create_notification_channel};
+pub use messaging::channels::{
+    create_command_channel, create_feedback_channel, create_notification_channel,
+};
 pub use midi::event::{MidiEvent, MidiEventTimed};
+#[cfg(feature = "midi-io")]
 pub use midi::manager::MidiConnectionManager;
 pub use sequencer::{
     ClickType, Metronome, MetronomeScheduler, MusicalTime, Position, Tempo, TimeSignature,
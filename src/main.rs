@@ -1,8 +1,13 @@
+use mymusic_daw::audio::parameters::{AtomicF32, SynthParameters};
+use mymusic_daw::config::Preferences;
+use mymusic_daw::crash::{self, RescueStore};
 use mymusic_daw::ui::app::DawApp;
 use mymusic_daw::{
-    AudioEngine, MidiConnectionManager, create_command_channel, create_notification_channel,
+    AudioEngine, MidiConnectionManager, OutputOptions, create_command_channel,
+    create_feedback_channel, create_notification_channel,
 };
 use mymusic_daw::plugin::PluginHost;
+use mymusic_daw::project::ProjectManager;
 use std::sync::{Arc, Mutex};
 
 // Ringbuffer capacity constants
@@ -14,11 +19,58 @@ use std::sync::{Arc, Mutex};
 const MIDI_RINGBUFFER_CAPACITY: usize = 512;
 const UI_RINGBUFFER_CAPACITY: usize = 512;
 const NOTIFICATION_RINGBUFFER_CAPACITY: usize = 256;
+const FEEDBACK_RINGBUFFER_CAPACITY: usize = 256;
+
+/// `--check-project <file>`: report what opening a project would do
+///
+/// Returns whether the project would open.
+fn check_project(path: &str) -> bool {
+    let manager = ProjectManager::new(48000.0);
+    let check = match manager.check_project(path) {
+        Ok(check) => check,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return false;
+        }
+    };
+    println!("{}: project format v{}", path, check.version);
+    if let Some(warning) = &check.compatibility.warning {
+        println!("  {}", warning);
+    }
+    for migration in &check.migrations {
+        println!("  migration: {}", migration);
+    }
+    if let Some(error) = &check.validation_error {
+        println!("  invalid: {}", error);
+    }
+    if check.is_ok() {
+        println!("  OK");
+    }
+    check.is_ok()
+}
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--check-project") {
+        let ok = match args.get(2) {
+            Some(path) => check_project(path),
+            None => {
+                eprintln!("Usage: mymusic_daw --check-project <file.mymusic>");
+                false
+            }
+        };
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
     println!("=== MyMusic DAW ===");
     println!("Version 0.1.0 - MVP\n");
 
+    // User preferences (devices, theme, keymap, paths, autosave)
+    let preferences = Preferences::load();
+
+    // Crash handler: rescues the session and reports the crash if the DAW dies
+    let rescue = crash::install(RescueStore::default_dir());
+
     // Create the communication channels
     // Need 2 ringbufs : one for MIDI, One for UI
     let (command_tx_ui, command_rx_ui) = create_command_channel(UI_RINGBUFFER_CAPACITY);
@@ -29,22 +81,36 @@ fn main() {
         create_notification_channel(NOTIFICATION_RINGBUFFER_CAPACITY);
     let notification_tx = Arc::new(Mutex::new(notification_tx));
 
+    // Create feedback channel (command acknowledgments from the audio thread)
+    let (feedback_tx, feedback_rx) = create_feedback_channel(FEEDBACK_RINGBUFFER_CAPACITY);
+
     // Create plugin host for plugin management
     let plugin_host = Arc::new(PluginHost::new());
     println!("Plugin host initialized");
 
     println!("Audio engine initialisation...");
-    let audio_engine =
-        match AudioEngine::new(command_rx_ui, command_rx_midi, notification_tx.clone(), plugin_host.clone()) {
-            Ok(engine) => engine,
-            Err(e) => {
-                eprintln!("ERROR: {}", e);
-                return;
-            }
-        };
+    let audio_engine = match AudioEngine::with_options(
+        command_rx_ui,
+        command_rx_midi,
+        feedback_tx,
+        notification_tx.clone(),
+        plugin_host.clone(),
+        AtomicF32::new(0.5),
+        SynthParameters::default(),
+        &OutputOptions::from(&preferences.audio),
+    ) {
+        Ok(engine) => engine,
+        Err(e) => {
+            eprintln!("ERROR: {}", e);
+            return;
+        }
+    };
 
     println!("\nMIDI Initialisation...");
     let midi_manager = MidiConnectionManager::new(command_tx_midi, notification_tx);
+    if let Some(device) = &preferences.midi.input_device {
+        midi_manager.set_target_device(device.clone());
+    }
 
     println!("\n=== DAW started ! ===\n");
     println!("Graphical UI launching...\n");
@@ -60,14 +126,23 @@ fn main() {
     let _ = eframe::run_native(
         "MyMusic DAW",
         native_options,
-        Box::new(|_cc| {
+        Box::new(|cc| {
             let mut app = DawApp::new(
                 command_tx_ui,
                 audio_engine.volume.clone(),
+                audio_engine.synth_params.clone(),
                 midi_manager,
                 audio_engine.cpu_monitor.clone(),
+                audio_engine.snapshot.clone(),
+                audio_engine.master_tap.clone(),
                 notification_rx,
+                feedback_rx,
+                preferences,
             );
+            app.apply_theme(&cc.egui_ctx);
+            app.set_pending_rescue(rescue);
+            // The Devices tab switches the output device of the engine
+            app.set_audio_engine(audio_engine);
 
             // Load cached plugins on startup
             app.load_cached_plugins();
@@ -75,4 +150,7 @@ fn main() {
             Ok(Box::new(app))
         }),
     );
+
+    // Clean exit: nothing to rescue
+    crash::shutdown();
 }
@@ -1,9 +1,12 @@
 // Communication channels lock-free
 
 use crate::messaging::command::Command;
-use crate::messaging::notification::Notification;
+use crate::messaging::notification_queue::notification_channel;
 use ringbuf::{HeapRb, traits::Split};
 
+pub use crate::messaging::feedback::{FeedbackConsumer, FeedbackProducer, create_feedback_channel};
+pub use crate::messaging::notification_queue::{NotificationConsumer, NotificationProducer};
+
 pub type CommandProducer = ringbuf::HeapProd<Command>;
 pub type CommandConsumer = ringbuf::HeapCons<Command>;
 
@@ -12,12 +15,9 @@ pub fn create_command_channel(capacity: usize) -> (CommandProducer, CommandConsu
     rb.split()
 }
 
-pub type NotificationProducer = ringbuf::HeapProd<Notification>;
-pub type NotificationConsumer = ringbuf::HeapCons<Notification>;
-
+/// Notification channel (bounded, drops the least important notification when full)
 pub fn create_notification_channel(
     capacity: usize,
 ) -> (NotificationProducer, NotificationConsumer) {
-    let rb = HeapRb::<Notification>::new(capacity);
-    rb.split()
+    notification_channel(capacity)
 }
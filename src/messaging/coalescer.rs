@@ -0,0 +1,255 @@
+// Command coalescing - Backpressure on the UI → audio command channel
+//
+// Parameter updates only matter by their latest value. When the command
+// ringbuffer is full they are kept in one slot per parameter (a newer update
+// replaces the pending one) and flushed as soon as the audio thread frees
+// space. Other commands (notes, samples, transport...) cannot be merged: they
+// are rejected as before, and the caller reports the failure.
+//
+// Pending updates are flushed before any new command is pushed, so the audio
+// thread never receives an older value of a parameter after a newer one.
+
+use crate::messaging::channels::CommandProducer;
+use crate::messaging::command::Command;
+use crate::messaging::notification::{Notification, NotificationCategory};
+use ringbuf::traits::{Observer, Producer};
+use std::time::{Duration, Instant};
+
+/// The channel must stay full this long before the overflow is reported
+pub const SUSTAINED_OVERFLOW: Duration = Duration::from_millis(500);
+
+/// Parameter whose updates can be merged (only the latest value is kept)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParamSlot {
+    Volume,
+    Waveform,
+    Oscillator2,
+    Unison,
+    AntiAliasing,
+    CrossMod,
+    Noise,
+    Adsr,
+    ModEnvelope,
+    Lfo,
+    ModLfo(u8),
+    Portamento,
+    Filter,
+    FilterMod,
+    MetronomeVolume,
+    Tempo,
+    MasterReverb,
+    MasterDelay,
+    MasterEq,
+    MasterCompressor,
+    MasterLimiter,
+    MaxVoices,
+    PitchBendRange,
+}
+
+impl ParamSlot {
+    fn of(command: &Command) -> Option<Self> {
+        match command {
+            Command::SetVolume(_) => Some(ParamSlot::Volume),
+            Command::SetWaveform(_) => Some(ParamSlot::Waveform),
+            Command::SetOscillator2(_) => Some(ParamSlot::Oscillator2),
+            Command::SetUnison(_) => Some(ParamSlot::Unison),
+            Command::SetAntiAliasing(_) => Some(ParamSlot::AntiAliasing),
+            Command::SetCrossMod(_) => Some(ParamSlot::CrossMod),
+            Command::SetNoise(_) => Some(ParamSlot::Noise),
+            Command::SetAdsr(_) => Some(ParamSlot::Adsr),
+            Command::SetModEnvelope(_) => Some(ParamSlot::ModEnvelope),
+            Command::SetLfo(_) => Some(ParamSlot::Lfo),
+            Command::SetModLfo { index, .. } => Some(ParamSlot::ModLfo(*index)),
+            Command::SetPortamento(_) => Some(ParamSlot::Portamento),
+            Command::SetFilter(_) => Some(ParamSlot::Filter),
+            Command::SetFilterMod(_) => Some(ParamSlot::FilterMod),
+            Command::SetMetronomeVolume(_) => Some(ParamSlot::MetronomeVolume),
+            Command::SetTempo(_) => Some(ParamSlot::Tempo),
+            Command::SetMasterReverb(_) => Some(ParamSlot::MasterReverb),
+            Command::SetMasterDelay(_) => Some(ParamSlot::MasterDelay),
+            Command::SetMasterEq(_) => Some(ParamSlot::MasterEq),
+            Command::SetMasterCompressor(_) => Some(ParamSlot::MasterCompressor),
+            Command::SetMasterLimiter(_) => Some(ParamSlot::MasterLimiter),
+            Command::SetMaxVoices(_) => Some(ParamSlot::MaxVoices),
+            Command::SetPitchBendRange(_) => Some(ParamSlot::PitchBendRange),
+            _ => None,
+        }
+    }
+}
+
+/// Producer-side coalescing layer of the command channel
+#[derive(Debug, Default)]
+pub struct CommandCoalescer {
+    /// Latest update of each parameter waiting for space (arrival order)
+    pending: Vec<(ParamSlot, Command)>,
+    /// When the channel was first found full (None: not overflowing)
+    full_since: Option<Instant>,
+    /// Updates merged into a pending one during the current overflow
+    coalesced: u64,
+    /// Commands rejected during the current overflow
+    dropped: u64,
+    /// The current overflow was already reported
+    reported: bool,
+}
+
+impl CommandCoalescer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send a command through `tx`
+    ///
+    /// Returns false only if the command was rejected: parameter updates are
+    /// always accepted (sent now, or kept until the next flush).
+    pub fn send(&mut self, tx: &mut CommandProducer, command: Command) -> bool {
+        self.flush(tx);
+
+        let Some(slot) = ParamSlot::of(&command) else {
+            let sent = tx.try_push(command).is_ok();
+            if !sent {
+                self.overflowed();
+                self.dropped += 1;
+            }
+            return sent;
+        };
+
+        if let Some((_, pending)) = self.pending.iter_mut().find(|(s, _)| *s == slot) {
+            *pending = command;
+            self.coalesced += 1;
+        } else if let Err(command) = tx.try_push(command) {
+            self.overflowed();
+            self.pending.push((slot, command));
+        }
+        true
+    }
+
+    /// Push pending updates while there is space
+    ///
+    /// Returns true when nothing is pending anymore. Call it regularly (e.g.
+    /// once per UI frame) so the last update of a gesture is not left behind.
+    pub fn flush(&mut self, tx: &mut CommandProducer) -> bool {
+        let mut sent = 0;
+        for (_, command) in &self.pending {
+            if tx.try_push(command.clone()).is_err() {
+                break;
+            }
+            sent += 1;
+        }
+        self.pending.drain(..sent);
+
+        let flushed = self.pending.is_empty();
+        if flushed && !tx.is_full() {
+            self.recovered();
+        }
+        flushed
+    }
+
+    /// Number of parameter updates waiting for space
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Forget pending updates (e.g. when the channel is replaced)
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.recovered();
+    }
+
+    /// Notification for an overflow lasting longer than `SUSTAINED_OVERFLOW`
+    ///
+    /// Reported once per overflow episode.
+    pub fn overflow_report(&mut self, now: Instant) -> Option<Notification> {
+        let full_since = self.full_since?;
+        let duration = now.saturating_duration_since(full_since);
+        if self.reported || duration < SUSTAINED_OVERFLOW {
+            return None;
+        }
+        self.reported = true;
+        Some(Notification::warning(
+            NotificationCategory::Audio,
+            format!(
+                "Audio command queue full for {} ms ({} updates merged, {} commands dropped)",
+                duration.as_millis(),
+                self.coalesced,
+                self.dropped
+            ),
+        ))
+    }
+
+    fn overflowed(&mut self) {
+        self.full_since.get_or_insert_with(Instant::now);
+    }
+
+    fn recovered(&mut self) {
+        self.full_since = None;
+        self.coalesced = 0;
+        self.dropped = 0;
+        self.reported = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::channels::create_command_channel;
+    use crate::synth::filter::FilterParams;
+    use ringbuf::traits::Consumer;
+
+    #[test]
+    fn test_parameter_updates_are_coalesced_when_full() {
+        let (mut tx, mut rx) = create_command_channel(2);
+        let mut coalescer = CommandCoalescer::new();
+
+        assert!(coalescer.send(&mut tx, Command::SetTransportPlaying(true)));
+        assert!(coalescer.send(&mut tx, Command::SetVolume(0.1)));
+
+        // Full: updates are merged, other commands are rejected
+        assert!(coalescer.send(&mut tx, Command::SetVolume(0.2)));
+        assert!(coalescer.send(&mut tx, Command::SetFilter(FilterParams::default())));
+        assert!(coalescer.send(&mut tx, Command::SetVolume(0.3)));
+        assert!(!coalescer.send(&mut tx, Command::SetTransportPlaying(false)));
+        assert_eq!(coalescer.pending_count(), 2);
+
+        // The audio thread drains the channel, the latest values get through
+        let _ = rx.try_pop();
+        let _ = rx.try_pop();
+        assert!(coalescer.flush(&mut tx));
+        assert!(matches!(rx.try_pop(), Some(Command::SetVolume(v)) if v == 0.3));
+        assert!(matches!(rx.try_pop(), Some(Command::SetFilter(_))));
+        assert!(rx.try_pop().is_none());
+    }
+
+    #[test]
+    fn test_sustained_overflow_is_reported_once() {
+        let (mut tx, mut rx) = create_command_channel(1);
+        let mut coalescer = CommandCoalescer::new();
+        let start = Instant::now();
+
+        assert!(coalescer.send(&mut tx, Command::SetVolume(0.1)));
+        assert!(
+            coalescer
+                .overflow_report(start + SUSTAINED_OVERFLOW)
+                .is_none()
+        );
+
+        // Full: the update waits, the overflow is only reported once it lasts
+        assert!(coalescer.send(&mut tx, Command::SetVolume(0.2)));
+        assert!(coalescer.overflow_report(Instant::now()).is_none());
+        let later = Instant::now() + SUSTAINED_OVERFLOW;
+        assert!(coalescer.overflow_report(later).is_some());
+        assert!(coalescer.overflow_report(later).is_none());
+
+        // Once drained, a new overflow is reported again
+        let _ = rx.try_pop();
+        assert!(coalescer.flush(&mut tx));
+        let _ = rx.try_pop();
+        assert!(coalescer.flush(&mut tx));
+        assert!(coalescer.send(&mut tx, Command::SetVolume(0.3)));
+        assert!(coalescer.send(&mut tx, Command::SetVolume(0.4)));
+        assert!(
+            coalescer
+                .overflow_report(Instant::now() + SUSTAINED_OVERFLOW)
+                .is_some()
+        );
+    }
+}
@@ -1,15 +1,33 @@
 // Types de commandes - Communication UI → Audio
 
+use crate::audio::clip_player::{AudioTrack, SidechainSource, TrackDynamics};
+use crate::audio::inserts::InsertChainParams;
+use crate::audio::instruments::TrackInstrument;
+use crate::audio::outputs::TrackOutput;
+use crate::audio::returns::{ReturnBusParams, TrackSends};
+use crate::audio::routing::{ChannelStrip, MixerTrack};
+use crate::audio::surround::{SpeakerLayout, SurroundPan};
+use crate::automation::AutomationLane;
 use crate::midi::event::MidiEventTimed;
 use crate::sampler::loader::Sample;
-use crate::sequencer::Pattern;
+use crate::sampler::zone::SampleZone;
+use crate::sequencer::{Arrangement, Pattern, Session};
+use crate::synth::delay::DelayParams;
+use crate::synth::dynamics::DynamicsParams;
 use crate::synth::envelope::AdsrParams;
-use crate::synth::filter::FilterParams;
+use crate::synth::equalizer::EqParams;
+use crate::synth::filter::{FilterModParams, FilterParams};
 use crate::synth::lfo::LfoParams;
+use crate::synth::limiter::LimiterParams;
+use crate::synth::master_chain::MasterEffectOrder;
 use crate::synth::modulation::ModRouting;
-use crate::synth::oscillator::WaveformType;
+use crate::synth::noise::NoiseParams;
+use crate::synth::oscillator::{
+    AntiAliasing, CrossModParams, Oscillator2Params, UnisonParams, WaveformType,
+};
 use crate::synth::poly_mode::PolyMode;
 use crate::synth::portamento::PortamentoParams;
+use crate::synth::reverb::ReverbParams;
 use crate::synth::voice_manager::VoiceMode;
 use std::sync::Arc;
 
@@ -18,11 +36,30 @@ pub enum Command {
     Midi(MidiEventTimed),
     SetVolume(f32),
     SetWaveform(WaveformType),
+    /// Set the second oscillator of the synth voices
+    SetOscillator2(Oscillator2Params),
+    /// Set the unison stack of the synth notes
+    SetUnison(UnisonParams),
+    /// Set how the synth oscillators are band-limited
+    SetAntiAliasing(AntiAliasing),
+    /// Set the FM and ring modulation between the synth oscillators
+    SetCrossMod(CrossModParams),
+    /// Set the noise source of the synth voices
+    SetNoise(NoiseParams),
     SetAdsr(AdsrParams),
+    /// Set the modulation envelope of the synth voices
+    SetModEnvelope(AdsrParams),
     SetLfo(LfoParams),
+    /// Set a matrix LFO of the synth voices (`index` 1..LFO_COUNT, as in `ModSource::Lfo`)
+    SetModLfo {
+        index: u8,
+        params: LfoParams,
+    },
     SetPolyMode(PolyMode),
     SetPortamento(PortamentoParams),
     SetFilter(FilterParams),
+    /// Set the filter envelope amount, key tracking and drive of the synth voices
+    SetFilterMod(FilterModParams),
     SetVoiceMode(VoiceMode),
     AddSample(Arc<Sample>),
     RemoveSample(usize),
@@ -31,6 +68,24 @@ pub enum Command {
         sample_index: usize,
     },
     UpdateSample(usize, Arc<Sample>),
+    /// Insert a sample in a slot, shifting the next slots (undo of a removal)
+    InsertSample(usize, Arc<Sample>),
+    /// Forget the sample mapped to a note
+    ClearNoteSampleMapping(u8),
+    /// Set the key-range zone of a sample slot (None: only its mapped notes)
+    SetSampleZone {
+        sample_index: usize,
+        zone: Option<SampleZone>,
+    },
+    /// Audition a sample slot outside of the mappings (note None: original
+    /// pitch; looped: repeat until `StopPreview`)
+    PreviewSample {
+        sample_index: usize,
+        note: Option<u8>,
+        looped: bool,
+    },
+    /// Release the sample preview
+    StopPreview,
     /// Update a modulation routing slot (UI → Audio)
     SetModRouting {
         index: u8,
@@ -44,6 +99,8 @@ pub enum Command {
     SetMetronomeEnabled(bool),
     /// Set metronome volume (0.0 to 1.0)
     SetMetronomeVolume(f32),
+    /// Set the output of the metronome bus (master pair or hardware pair)
+    SetMetronomeOutput(TrackOutput),
     /// Set transport tempo (BPM)
     SetTempo(f64),
     /// Set transport time signature (numerator, denominator)
@@ -52,7 +109,111 @@ pub enum Command {
     SetTransportPlaying(bool),
     /// Set transport position in samples
     SetTransportPosition(u64),
+    /// Click a count-in of this many bars, then start the transport
+    ///
+    /// The timeline stands still while counting; stopping or starting the
+    /// transport cancels the count-in.
+    StartCountIn(u32),
     /// Update the active pattern for sequencer playback
-    SetPattern(Pattern),
+    ///
+    /// Shared with the UI: editors modify their copy with `Arc::make_mut`, which
+    /// only clones while the audio thread still plays the previous version. The
+    /// replaced pattern goes back through the garbage chute.
+    SetPattern(Arc<Pattern>),
+    /// Set the instrument of an instrument track (None frees its slot)
+    ///
+    /// A track without a slot takes a free one (rejected when none is left).
+    /// The instrument is boxed in an `Arc` to keep commands small (a synth
+    /// patch is large); the engine copies it and sends the `Arc` back through
+    /// the garbage chute, like the pattern of a freed slot.
+    SetTrackInstrument {
+        track: u32,
+        instrument: Option<Arc<TrackInstrument>>,
+    },
+    /// Replace the pattern played by an instrument track
+    ///
+    /// Shared like `SetPattern`; the replaced pattern goes back through the
+    /// garbage chute.
+    SetTrackPattern {
+        track: u32,
+        pattern: Arc<Pattern>,
+    },
+    /// Play the clips of an arrangement instead of looping the patterns
+    /// (song mode; None goes back to the patterns)
+    ///
+    /// The replaced arrangement goes back through the garbage chute.
+    SetArrangement(Option<Arc<Arrangement>>),
+    /// Replace the clip grid of the session view
+    ///
+    /// The replaced grid goes back through the garbage chute.
+    SetSession(Arc<Session>),
+    /// Launch a slot of the session (`scene` None stops the track) at timeline
+    /// sample `at` (the launch boundary, see `LaunchQuantization`)
+    LaunchClip {
+        track: u32,
+        scene: Option<u32>,
+        at: u64,
+    },
+    /// Hand the tracks launched in the session back to their patterns or the
+    /// arrangement
+    ReleaseSession,
+    /// Replace the audio tracks played by the clip player
+    ///
+    /// A snapshot of the UI's `AudioTrackList`; the replaced snapshot goes back
+    /// through the garbage chute.
+    SetAudioTracks(Arc<Vec<AudioTrack>>),
+    /// Replace the automation lanes (only lanes with points)
+    ///
+    /// The replaced snapshot goes back through the garbage chute.
+    SetAutomation(Arc<Vec<AutomationLane>>),
+    /// Set the compressor/gate insert of the synth track (None removes it)
+    SetSynthDynamics(Option<TrackDynamics>),
+    /// Route a track to the sidechain input of the plugins (None disconnects it)
+    SetPluginSidechain(Option<SidechainSource>),
+    /// Configure a return bus slot (None removes the bus)
+    SetReturnBus {
+        index: usize,
+        params: Option<ReturnBusParams>,
+    },
+    /// Set the sends of the synth track to the return buses
+    SetSynthSends(TrackSends),
+    /// Set the output (master bus or hardware pair) of the synth track
+    SetSynthOutput(TrackOutput),
+    /// Set the gain, pan, mute and solo of a track in the mixer stage
+    ///
+    /// Audio tracks also carry their strip in `SetAudioTracks` snapshots; this
+    /// moves a fader without sending the track list again.
+    SetTrackStrip {
+        track: MixerTrack,
+        strip: ChannelStrip,
+    },
+    /// Set the insert effect chain of a track (filter, delay, reverb)
+    ///
+    /// Like strips, audio tracks also carry their chain in `SetAudioTracks`
+    /// snapshots.
+    SetTrackInserts {
+        track: MixerTrack,
+        inserts: InsertChainParams,
+    },
+    /// Set the speaker layout of the mix
+    SetSpeakerLayout(SpeakerLayout),
+    /// Set the surround panner of the synth track (None: stereo)
+    SetSynthSurround(Option<SurroundPan>),
+    /// Set master bus reverb parameters (enabled = false bypasses it)
+    SetMasterReverb(ReverbParams),
+    /// Set master bus delay parameters (enabled = false bypasses it)
+    SetMasterDelay(DelayParams),
+    /// Set master bus EQ parameters (enabled = false bypasses it)
+    SetMasterEq(EqParams),
+    /// Set master bus compressor parameters (enabled = false bypasses it)
+    SetMasterCompressor(DynamicsParams),
+    /// Set master bus limiter parameters (enabled = false bypasses it)
+    SetMasterLimiter(LimiterParams),
+    /// Set master bus effect processing order
+    SetMasterEffectOrder(MasterEffectOrder),
+    /// Set the maximum number of synth/sampler voices (1 to `MAX_VOICES`)
+    SetMaxVoices(usize),
+    /// Set the synth pitch bend range in semitones (0 to `MAX_PITCH_BEND_RANGE`)
+    SetPitchBendRange(f32),
     Quit,
 }
@@ -0,0 +1,291 @@
+// Engine feedback - Acknowledgments and state corrections (Audio → UI)
+//
+// Frontends mirror some engine state (sample slots, note mappings, active
+// pattern, modulation routings, voice count). The audio thread reports what it
+// did with the commands changing that state, so frontends show what the engine
+// accepted instead of assuming success:
+// - Applied: the command was applied as sent
+// - Corrected: the command was applied with an adjusted value
+// - Rejected: the command was ignored (and why)
+//
+// Continuous parameters (volume, ADSR, filter...) are not acknowledged: they
+// are coalesced on the UI side and always applied.
+//
+// Feedback values are `Copy` and go through a lock-free ringbuffer, so the
+// callback reports without allocating. When the ringbuffer is full (frontend
+// not draining it), new feedback is dropped.
+
+use crate::messaging::command::Command;
+use crate::messaging::notification::{Notification, NotificationCategory};
+use ringbuf::HeapRb;
+use ringbuf::traits::{Producer, Split};
+use std::fmt;
+
+pub type FeedbackProducer = ringbuf::HeapProd<EngineFeedback>;
+pub type FeedbackConsumer = ringbuf::HeapCons<EngineFeedback>;
+
+pub fn create_feedback_channel(capacity: usize) -> (FeedbackProducer, FeedbackConsumer) {
+    let rb = HeapRb::<EngineFeedback>::new(capacity);
+    rb.split()
+}
+
+/// Report feedback from the audio thread (RT-safe, dropped if the ringbuffer is full)
+#[inline]
+pub fn send_feedback(tx: &mut FeedbackProducer, feedback: EngineFeedback) -> bool {
+    tx.try_push(feedback).is_ok()
+}
+
+/// Commands acknowledged by the engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandKind {
+    AddSample,
+    RemoveSample,
+    UpdateSample,
+    SetNoteSampleMapping,
+    SetSampleZone,
+    SetPattern,
+    SetAudioTracks,
+    SetModRouting,
+    ClearModRouting,
+    SetVoiceMode,
+    SetMaxVoices,
+    SetTrackInstrument,
+}
+
+impl CommandKind {
+    /// Kind of a command, if the engine acknowledges it
+    pub fn of(command: &Command) -> Option<Self> {
+        match command {
+            Command::AddSample(_) | Command::InsertSample(..) => Some(CommandKind::AddSample),
+            Command::RemoveSample(_) => Some(CommandKind::RemoveSample),
+            Command::UpdateSample(..) => Some(CommandKind::UpdateSample),
+            Command::SetNoteSampleMapping { .. } | Command::ClearNoteSampleMapping(_) => {
+                Some(CommandKind::SetNoteSampleMapping)
+            }
+            Command::SetSampleZone { .. } => Some(CommandKind::SetSampleZone),
+            Command::SetPattern(_) => Some(CommandKind::SetPattern),
+            Command::SetAudioTracks(_) => Some(CommandKind::SetAudioTracks),
+            Command::SetModRouting { .. } => Some(CommandKind::SetModRouting),
+            Command::ClearModRouting { .. } => Some(CommandKind::ClearModRouting),
+            Command::SetVoiceMode(_) => Some(CommandKind::SetVoiceMode),
+            Command::SetMaxVoices(_) => Some(CommandKind::SetMaxVoices),
+            Command::SetTrackInstrument { .. } => Some(CommandKind::SetTrackInstrument),
+            _ => None,
+        }
+    }
+
+    /// Stable identifier (used by the web frontend)
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandKind::AddSample => "add_sample",
+            CommandKind::RemoveSample => "remove_sample",
+            CommandKind::UpdateSample => "update_sample",
+            CommandKind::SetNoteSampleMapping => "set_note_sample_mapping",
+            CommandKind::SetSampleZone => "set_sample_zone",
+            CommandKind::SetPattern => "set_pattern",
+            CommandKind::SetAudioTracks => "set_audio_tracks",
+            CommandKind::SetModRouting => "set_mod_routing",
+            CommandKind::ClearModRouting => "clear_mod_routing",
+            CommandKind::SetVoiceMode => "set_voice_mode",
+            CommandKind::SetMaxVoices => "set_max_voices",
+            CommandKind::SetTrackInstrument => "set_track_instrument",
+        }
+    }
+}
+
+impl fmt::Display for CommandKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandKind::AddSample => write!(f, "sample load"),
+            CommandKind::RemoveSample => write!(f, "sample removal"),
+            CommandKind::UpdateSample => write!(f, "sample update"),
+            CommandKind::SetNoteSampleMapping => write!(f, "note mapping"),
+            CommandKind::SetSampleZone => write!(f, "sample zone"),
+            CommandKind::SetPattern => write!(f, "pattern"),
+            CommandKind::SetAudioTracks => write!(f, "audio tracks"),
+            CommandKind::SetModRouting => write!(f, "modulation routing"),
+            CommandKind::ClearModRouting => write!(f, "modulation routing removal"),
+            CommandKind::SetVoiceMode => write!(f, "voice mode"),
+            CommandKind::SetMaxVoices => write!(f, "maximum voices"),
+            CommandKind::SetTrackInstrument => write!(f, "track instrument"),
+        }
+    }
+}
+
+/// Why the engine ignored a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// Every sample slot is used (see `voice_manager::MAX_SAMPLE_SLOTS`)
+    SampleSlotsFull,
+    /// No sample in this slot
+    InvalidSampleIndex(usize),
+    /// No modulation routing slot at this index
+    InvalidRoutingIndex(u8),
+    /// Every instrument slot is used (see `instruments::MAX_INSTRUMENT_TRACKS`)
+    InstrumentSlotsFull,
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectReason::SampleSlotsFull => write!(f, "all sample slots are used"),
+            RejectReason::InvalidSampleIndex(index) => write!(f, "no sample in slot {}", index),
+            RejectReason::InvalidRoutingIndex(index) => {
+                write!(f, "no modulation routing slot {}", index)
+            }
+            RejectReason::InstrumentSlotsFull => write!(f, "all instrument slots are used"),
+        }
+    }
+}
+
+/// Value the engine actually applied, when it differs from the one sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateCorrection {
+    /// Maximum voice count, clamped to what the engine supports
+    MaxVoices(usize),
+}
+
+impl fmt::Display for StateCorrection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateCorrection::MaxVoices(max_voices) => {
+                write!(f, "maximum voices set to {}", max_voices)
+            }
+        }
+    }
+}
+
+/// Outcome of a command, reported by the audio thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineFeedback {
+    /// Applied as sent
+    Applied(CommandKind),
+    /// Applied with an adjusted value
+    Corrected {
+        command: CommandKind,
+        correction: StateCorrection,
+    },
+    /// Ignored by the engine
+    Rejected {
+        command: CommandKind,
+        reason: RejectReason,
+    },
+}
+
+impl EngineFeedback {
+    pub fn command(&self) -> CommandKind {
+        match self {
+            EngineFeedback::Applied(command)
+            | EngineFeedback::Corrected { command, .. }
+            | EngineFeedback::Rejected { command, .. } => *command,
+        }
+    }
+
+    /// Notification for the user (acknowledgments are silent)
+    pub fn to_notification(&self) -> Option<Notification> {
+        match self {
+            EngineFeedback::Applied(_) => None,
+            EngineFeedback::Corrected { .. } => Some(Notification::info(
+                NotificationCategory::Audio,
+                self.to_string(),
+            )),
+            EngineFeedback::Rejected { .. } => Some(Notification::warning(
+                NotificationCategory::Audio,
+                self.to_string(),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for EngineFeedback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineFeedback::Applied(command) => write!(f, "{} applied", capitalize(command)),
+            EngineFeedback::Corrected { correction, .. } => {
+                write!(f, "Engine adjusted the request: {}", correction)
+            }
+            EngineFeedback::Rejected { command, reason } => {
+                write!(f, "{} rejected: {}", capitalize(command), reason)
+            }
+        }
+    }
+}
+
+/// Display a command kind with an upper-case first letter
+fn capitalize(command: &CommandKind) -> String {
+    let text = command.to_string();
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::notification::NotificationLevel;
+    use ringbuf::traits::Consumer;
+
+    #[test]
+    fn test_only_state_commands_are_acknowledged() {
+        assert_eq!(
+            CommandKind::of(&Command::RemoveSample(2)),
+            Some(CommandKind::RemoveSample)
+        );
+        assert_eq!(
+            CommandKind::of(&Command::SetMaxVoices(8)),
+            Some(CommandKind::SetMaxVoices)
+        );
+        assert_eq!(CommandKind::of(&Command::SetVolume(0.5)), None);
+        assert_eq!(CommandKind::of(&Command::Quit), None);
+    }
+
+    #[test]
+    fn test_feedback_notifications() {
+        assert!(
+            EngineFeedback::Applied(CommandKind::SetPattern)
+                .to_notification()
+                .is_none()
+        );
+
+        let rejected = EngineFeedback::Rejected {
+            command: CommandKind::AddSample,
+            reason: RejectReason::SampleSlotsFull,
+        };
+        let notification = rejected.to_notification().unwrap();
+        assert_eq!(notification.level, NotificationLevel::Warning);
+        assert_eq!(
+            notification.message,
+            "Sample load rejected: all sample slots are used"
+        );
+
+        let corrected = EngineFeedback::Corrected {
+            command: CommandKind::SetMaxVoices,
+            correction: StateCorrection::MaxVoices(16),
+        };
+        assert_eq!(
+            corrected.to_notification().unwrap().level,
+            NotificationLevel::Info
+        );
+    }
+
+    #[test]
+    fn test_full_channel_drops_new_feedback() {
+        let (mut tx, mut rx) = create_feedback_channel(1);
+        assert!(send_feedback(
+            &mut tx,
+            EngineFeedback::Applied(CommandKind::SetPattern)
+        ));
+        assert!(!send_feedback(
+            &mut tx,
+            EngineFeedback::Applied(CommandKind::AddSample)
+        ));
+
+        assert_eq!(
+            rx.try_pop(),
+            Some(EngineFeedback::Applied(CommandKind::SetPattern))
+        );
+        assert_eq!(rx.try_pop(), None);
+    }
+}
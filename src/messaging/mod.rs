@@ -1,5 +1,8 @@
 // Module messaging - Communication lock-free entre threads
 
 pub mod channels;
+pub mod coalescer;
 pub mod command;
+pub mod feedback;
 pub mod notification;
+pub mod notification_queue;
@@ -3,7 +3,9 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Niveau de sévérité d'une notification
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Ordered by severity (Info < Warning < Error).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum NotificationLevel {
     Info,
     Warning,
@@ -0,0 +1,227 @@
+// Notification queue - Bounded notification channel with an overflow policy
+//
+// Notifications are never sent from the audio callback (only from the error
+// callback, the watchdog, MIDI and UI threads), so the channel is a small
+// mutex-protected queue rather than a lock-free ringbuffer: when it is full,
+// a queued notification can be evicted instead of losing the newest one.
+//
+// Overflow policy (queue full):
+// - The oldest notification of the lowest severity is dropped, as long as it
+//   is not more severe than the new one: errors are never dropped before
+//   warnings, nor warnings before infos.
+// - If every queued notification is more severe, the new one is dropped.
+//
+// Dropped notifications are counted per severity so the notification center
+// can show that some were lost.
+
+use crate::messaging::notification::{Notification, NotificationLevel};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Number of notifications dropped because the queue was full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotificationDropStats {
+    pub info: u64,
+    pub warning: u64,
+    pub error: u64,
+}
+
+impl NotificationDropStats {
+    pub fn total(&self) -> u64 {
+        self.info + self.warning + self.error
+    }
+
+    fn count(&mut self, level: NotificationLevel) {
+        match level {
+            NotificationLevel::Info => self.info += 1,
+            NotificationLevel::Warning => self.warning += 1,
+            NotificationLevel::Error => self.error += 1,
+        }
+    }
+}
+
+/// Bounded FIFO of notifications (see the overflow policy above)
+#[derive(Debug)]
+pub struct NotificationQueue {
+    notifications: VecDeque<Notification>,
+    capacity: usize,
+    dropped: NotificationDropStats,
+}
+
+impl NotificationQueue {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            notifications: VecDeque::with_capacity(capacity),
+            capacity,
+            dropped: NotificationDropStats::default(),
+        }
+    }
+
+    /// Queue a notification, evicting a less important one if the queue is full
+    ///
+    /// Returns the dropped notification, if any (possibly `notification` itself).
+    pub fn push(&mut self, notification: Notification) -> Option<Notification> {
+        if self.notifications.len() < self.capacity {
+            self.notifications.push_back(notification);
+            return None;
+        }
+
+        // Oldest of the lowest severity, not above the new notification
+        let victim = self
+            .notifications
+            .iter()
+            .enumerate()
+            .filter(|(_, queued)| queued.level <= notification.level)
+            .min_by_key(|(index, queued)| (queued.level, *index))
+            .map(|(index, _)| index);
+
+        let dropped = match victim.and_then(|index| self.notifications.remove(index)) {
+            Some(evicted) => {
+                self.notifications.push_back(notification);
+                evicted
+            }
+            None => notification,
+        };
+        self.dropped.count(dropped.level);
+        Some(dropped)
+    }
+
+    pub fn pop(&mut self) -> Option<Notification> {
+        self.notifications.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.notifications.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notifications.is_empty()
+    }
+
+    pub fn dropped(&self) -> NotificationDropStats {
+        self.dropped
+    }
+}
+
+type SharedQueue = Arc<Mutex<NotificationQueue>>;
+
+/// Lock the queue (a panic in another thread cannot corrupt it)
+fn lock(queue: &SharedQueue) -> MutexGuard<'_, NotificationQueue> {
+    queue
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Sending side of the notification channel
+pub struct NotificationProducer {
+    queue: SharedQueue,
+}
+
+impl NotificationProducer {
+    /// Send a notification (never blocks for long, never fails)
+    ///
+    /// When the queue is full, the overflow policy decides which notification
+    /// is dropped.
+    pub fn push(&mut self, notification: Notification) {
+        lock(&self.queue).push(notification);
+    }
+}
+
+/// Receiving side of the notification channel
+pub struct NotificationConsumer {
+    queue: SharedQueue,
+}
+
+impl NotificationConsumer {
+    pub fn try_pop(&mut self) -> Option<Notification> {
+        lock(&self.queue).pop()
+    }
+
+    /// Notifications dropped so far because the queue was full
+    pub fn dropped(&self) -> NotificationDropStats {
+        lock(&self.queue).dropped()
+    }
+}
+
+/// Create a notification channel holding up to `capacity` notifications
+pub fn notification_channel(capacity: usize) -> (NotificationProducer, NotificationConsumer) {
+    let queue = Arc::new(Mutex::new(NotificationQueue::new(capacity)));
+    (
+        NotificationProducer {
+            queue: queue.clone(),
+        },
+        NotificationConsumer { queue },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::notification::NotificationCategory;
+
+    fn notification(level: NotificationLevel, message: &str) -> Notification {
+        Notification::new(level, NotificationCategory::Generic, message.to_string())
+    }
+
+    fn messages(queue: &mut NotificationQueue) -> Vec<String> {
+        std::iter::from_fn(|| queue.pop())
+            .map(|n| n.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_full_queue_drops_oldest_of_lowest_severity() {
+        let mut queue = NotificationQueue::new(3);
+        queue.push(notification(NotificationLevel::Error, "e1"));
+        queue.push(notification(NotificationLevel::Info, "i1"));
+        queue.push(notification(NotificationLevel::Info, "i2"));
+
+        // The oldest info makes room for a new info
+        let dropped = queue.push(notification(NotificationLevel::Info, "i3"));
+        assert_eq!(dropped.map(|n| n.message), Some("i1".to_string()));
+
+        // Errors evict infos first
+        queue.push(notification(NotificationLevel::Error, "e2"));
+        queue.push(notification(NotificationLevel::Error, "e3"));
+        assert_eq!(messages(&mut queue), ["e1", "e2", "e3"]);
+
+        assert_eq!(
+            queue.dropped(),
+            NotificationDropStats {
+                info: 3,
+                warning: 0,
+                error: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_errors_never_dropped_for_infos() {
+        let mut queue = NotificationQueue::new(2);
+        queue.push(notification(NotificationLevel::Error, "e1"));
+        queue.push(notification(NotificationLevel::Warning, "w1"));
+
+        // Only more severe notifications are queued: the info is dropped
+        let dropped = queue.push(notification(NotificationLevel::Info, "i1"));
+        assert_eq!(dropped.map(|n| n.message), Some("i1".to_string()));
+
+        // Between errors, the oldest goes
+        queue.push(notification(NotificationLevel::Error, "e2"));
+        queue.push(notification(NotificationLevel::Error, "e3"));
+        assert_eq!(messages(&mut queue), ["e2", "e3"]);
+        assert_eq!(queue.dropped().total(), 3);
+        assert_eq!(queue.dropped().error, 1);
+    }
+
+    #[test]
+    fn test_channel_shares_queue_and_stats() {
+        let (mut tx, mut rx) = notification_channel(1);
+        tx.push(notification(NotificationLevel::Info, "first"));
+        tx.push(notification(NotificationLevel::Info, "second"));
+
+        assert_eq!(rx.try_pop().map(|n| n.message), Some("second".to_string()));
+        assert!(rx.try_pop().is_none());
+        assert_eq!(rx.dropped().info, 1);
+    }
+}
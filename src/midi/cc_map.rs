@@ -0,0 +1,276 @@
+// MIDI CC map - Control change numbers driving synth parameters (MIDI learn)
+//
+// A parameter is armed for learning from the UI; the next control change
+// received binds its controller number to that parameter. Mapped controllers
+// then turn into `RemoteAction`s that the frontend applies on the UI thread,
+// like OSC messages and control surface moves (not undoable: a knob sends
+// dozens of values per second). Each parameter follows at most one
+// controller, a controller drives at most one parameter.
+//
+// Mappings are stored in the project file; the armed parameter is not.
+
+use crate::remote::action::RemoteAction;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Highest mappable controller (120-127 are channel mode messages)
+pub const MAX_MAPPABLE_CC: u8 = 119;
+
+/// Synth parameters a controller can drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CcParameter {
+    /// Master volume (0.0 - 1.0)
+    Volume,
+    /// Filter cutoff in Hz
+    FilterCutoff,
+    /// Filter resonance (Q)
+    FilterResonance,
+    /// Envelope attack in seconds
+    Attack,
+    /// Envelope decay in seconds
+    Decay,
+    /// Envelope sustain level (0.0 - 1.0)
+    Sustain,
+    /// Envelope release in seconds
+    Release,
+}
+
+impl CcParameter {
+    /// All mappable parameters
+    pub const ALL: [CcParameter; 7] = [
+        CcParameter::Volume,
+        CcParameter::FilterCutoff,
+        CcParameter::FilterResonance,
+        CcParameter::Attack,
+        CcParameter::Decay,
+        CcParameter::Sustain,
+        CcParameter::Release,
+    ];
+
+    /// Parameter name for UI display
+    pub fn name(&self) -> &'static str {
+        match self {
+            CcParameter::Volume => "Volume",
+            CcParameter::FilterCutoff => "Filter Cutoff",
+            CcParameter::FilterResonance => "Filter Resonance",
+            CcParameter::Attack => "Attack",
+            CcParameter::Decay => "Decay",
+            CcParameter::Sustain => "Sustain",
+            CcParameter::Release => "Release",
+        }
+    }
+
+    /// Value range (min, max), in the parameter's own unit
+    pub fn range(&self) -> (f32, f32) {
+        match self {
+            CcParameter::Volume | CcParameter::Sustain => (0.0, 1.0),
+            CcParameter::FilterCutoff => (20.0, 20000.0),
+            CcParameter::FilterResonance => (0.5, 20.0),
+            CcParameter::Attack | CcParameter::Decay | CcParameter::Release => (0.001, 5.0),
+        }
+    }
+
+    /// Parameter value for a controller value (0 - 127)
+    ///
+    /// The cutoff follows an exponential curve and the envelope times a
+    /// quadratic one, so the low end of the knob keeps some resolution.
+    pub fn value_for(&self, cc_value: u8) -> f32 {
+        let (min, max) = self.range();
+        let unit = f32::from(cc_value.min(127)) / 127.0;
+        match self {
+            CcParameter::FilterCutoff => min * (max / min).powf(unit),
+            CcParameter::Attack | CcParameter::Decay | CcParameter::Release => {
+                min + (max - min) * unit * unit
+            }
+            _ => min + (max - min) * unit,
+        }
+    }
+
+    /// Action setting the parameter from a controller value
+    pub fn action(&self, cc_value: u8) -> RemoteAction {
+        let value = self.value_for(cc_value);
+        match self {
+            CcParameter::Volume => RemoteAction::SetVolume(value),
+            CcParameter::FilterCutoff => RemoteAction::SetCutoff(value),
+            CcParameter::FilterResonance => RemoteAction::SetResonance(value),
+            CcParameter::Attack => RemoteAction::SetAttack(value),
+            CcParameter::Decay => RemoteAction::SetDecay(value),
+            CcParameter::Sustain => RemoteAction::SetSustain(value),
+            CcParameter::Release => RemoteAction::SetRelease(value),
+        }
+    }
+}
+
+/// Controller bound to a parameter (what projects store)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CcMapping {
+    /// Controller number (0 - `MAX_MAPPABLE_CC`)
+    pub cc: u8,
+    pub parameter: CcParameter,
+}
+
+/// Controller numbers mapped to synth parameters, with the MIDI learn state
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CcMap {
+    mappings: BTreeMap<u8, CcParameter>,
+    /// Parameter waiting for a controller
+    learning: Option<CcParameter>,
+}
+
+impl CcMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mappings, sorted by controller number
+    pub fn mappings(&self) -> Vec<CcMapping> {
+        self.mappings
+            .iter()
+            .map(|(&cc, &parameter)| CcMapping { cc, parameter })
+            .collect()
+    }
+
+    /// Replace all mappings (fails, leaving the map untouched, on an invalid
+    /// controller number)
+    pub fn set_mappings(&mut self, mappings: Vec<CcMapping>) -> Result<(), String> {
+        let mut map = CcMap::new();
+        for mapping in mappings {
+            map.map(mapping.cc, mapping.parameter)?;
+        }
+        self.mappings = map.mappings;
+        Ok(())
+    }
+
+    /// Controller driving a parameter
+    pub fn cc_for(&self, parameter: CcParameter) -> Option<u8> {
+        self.mappings
+            .iter()
+            .find(|(_, mapped)| **mapped == parameter)
+            .map(|(&cc, _)| cc)
+    }
+
+    /// Parameter driven by a controller
+    pub fn parameter_for(&self, cc: u8) -> Option<CcParameter> {
+        self.mappings.get(&cc).copied()
+    }
+
+    /// Bind a controller to a parameter, replacing the previous bindings of both
+    pub fn map(&mut self, cc: u8, parameter: CcParameter) -> Result<(), String> {
+        if cc > MAX_MAPPABLE_CC {
+            return Err(format!(
+                "Controller {} cannot be mapped (0 - {})",
+                cc, MAX_MAPPABLE_CC
+            ));
+        }
+        self.unmap(parameter);
+        self.mappings.insert(cc, parameter);
+        Ok(())
+    }
+
+    /// Remove the binding of a parameter, returning its controller
+    pub fn unmap(&mut self, parameter: CcParameter) -> Option<u8> {
+        let cc = self.cc_for(parameter)?;
+        self.mappings.remove(&cc);
+        Some(cc)
+    }
+
+    /// Parameter waiting for a controller
+    pub fn learning(&self) -> Option<CcParameter> {
+        self.learning
+    }
+
+    /// Bind the next received controller to a parameter
+    pub fn arm_learn(&mut self, parameter: CcParameter) {
+        self.learning = Some(parameter);
+    }
+
+    pub fn cancel_learn(&mut self) {
+        self.learning = None;
+    }
+
+    /// Handle a received control change, returning the action to apply
+    ///
+    /// While a parameter is armed, the controller is bound to it first (the
+    /// parameter then jumps to the knob position).
+    pub fn handle(&mut self, cc: u8, value: u8) -> Option<RemoteAction> {
+        if let Some(parameter) = self.learning
+            && self.map(cc, parameter).is_ok()
+        {
+            self.learning = None;
+        }
+        self.mappings
+            .get(&cc)
+            .map(|parameter| parameter.action(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_learn_binds_next_controller() {
+        let mut map = CcMap::new();
+        assert_eq!(map.handle(74, 64), None);
+
+        map.arm_learn(CcParameter::FilterCutoff);
+        // Channel mode messages do not complete the learn
+        assert_eq!(map.handle(123, 0), None);
+        assert_eq!(map.learning(), Some(CcParameter::FilterCutoff));
+
+        assert_eq!(map.handle(74, 127), Some(RemoteAction::SetCutoff(20000.0)));
+        assert_eq!(map.learning(), None);
+        assert_eq!(map.cc_for(CcParameter::FilterCutoff), Some(74));
+        assert_eq!(map.parameter_for(74), Some(CcParameter::FilterCutoff));
+        assert_eq!(map.handle(74, 0), Some(RemoteAction::SetCutoff(20.0)));
+
+        // Learning another controller moves the binding
+        map.arm_learn(CcParameter::FilterCutoff);
+        map.handle(21, 0);
+        assert_eq!(map.handle(74, 0), None);
+        assert_eq!(
+            map.mappings(),
+            vec![CcMapping {
+                cc: 21,
+                parameter: CcParameter::FilterCutoff
+            }]
+        );
+
+        // A controller drives one parameter
+        map.map(21, CcParameter::Sustain).unwrap();
+        assert_eq!(map.cc_for(CcParameter::FilterCutoff), None);
+        assert_eq!(map.handle(21, 127), Some(RemoteAction::SetSustain(1.0)));
+        assert_eq!(map.unmap(CcParameter::Sustain), Some(21));
+        assert!(map.mappings().is_empty());
+    }
+
+    #[test]
+    fn test_set_mappings_validation() {
+        let mut map = CcMap::new();
+        let mappings = vec![
+            CcMapping {
+                cc: 7,
+                parameter: CcParameter::Volume,
+            },
+            CcMapping {
+                cc: 73,
+                parameter: CcParameter::Attack,
+            },
+        ];
+        map.set_mappings(mappings.clone()).unwrap();
+        assert_eq!(map.mappings(), mappings);
+
+        let invalid = vec![CcMapping {
+            cc: 127,
+            parameter: CcParameter::Release,
+        }];
+        assert!(map.set_mappings(invalid).is_err());
+        assert_eq!(map.mappings(), mappings);
+
+        // Envelope times keep resolution near zero
+        assert_eq!(CcParameter::Attack.value_for(0), 0.001);
+        assert!(CcParameter::Attack.value_for(10) < 0.05);
+        assert!((CcParameter::Attack.value_for(127) - 5.0).abs() < 1e-6);
+    }
+}
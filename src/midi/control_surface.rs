@@ -0,0 +1,623 @@
+// Control surface - Mackie Control protocol
+//
+// Hardware controllers (Behringer X-Touch, iCON, Mackie MCU Pro...) in Mackie
+// Control (MCU) mode expose 8 channel strips and a master fader over a MIDI
+// port pair:
+//   faders         pitch bend, 14 bits, channels 0-7 (strips) and 8 (master)
+//   fader touch    notes 0x68-0x70 (velocity > 0: touched)
+//   V-Pots         CC 0x10-0x17, relative (bit 6 set: counter-clockwise)
+//   buttons        notes (velocity > 0: pressed), LEDs lit by the same note
+//   scribble strip SysEx F0 00 00 66 14 12 <offset> <ascii> F7
+//
+// Strips show a bank of 8 audio tracks: faders set the track gain, V-Pots the
+// pan, mute buttons toggle the mute; bank and channel buttons scroll the bank.
+// The scribble strips show the track name (top line) and level (bottom line).
+// Surfaces only speaking HUI are not supported: most offer an MCU mode.
+//
+// `MackieControl` holds the protocol state and is independent of the MIDI
+// backend; `ControlSurfaceConnection` (feature "midi-io") opens the ports.
+
+use crate::audio::clip_player::{AudioTrack, MAX_CLIP_GAIN};
+use crate::command::DawState;
+use crate::remote::action::RemoteAction;
+
+/// Channel strips of a surface
+pub const STRIPS: usize = 8;
+
+/// Pitch bend channel of the master fader
+const MASTER_STRIP: u8 = STRIPS as u8;
+
+/// Characters per strip and line of the scribble strip
+const SCRIBBLE_WIDTH: usize = 7;
+
+/// Pan change per V-Pot tick
+const PAN_STEP: f32 = 0.02;
+
+const FADER_MAX: u16 = 0x3FFF;
+const NOTE_MUTE: u8 = 0x10;
+const NOTE_FADER_TOUCH: u8 = 0x68;
+const CC_VPOT: u8 = 0x10;
+const CC_VPOT_RING: u8 = 0x30;
+
+/// Button of a Mackie Control surface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceButton {
+    /// Mute button of a strip (0-7)
+    Mute(u8),
+    BankLeft,
+    BankRight,
+    ChannelLeft,
+    ChannelRight,
+    Rewind,
+    Stop,
+    Play,
+    Record,
+}
+
+impl SurfaceButton {
+    /// Button for a note number
+    pub fn from_note(note: u8) -> Option<Self> {
+        Some(match note {
+            0x10..=0x17 => SurfaceButton::Mute(note - NOTE_MUTE),
+            0x2E => SurfaceButton::BankLeft,
+            0x2F => SurfaceButton::BankRight,
+            0x30 => SurfaceButton::ChannelLeft,
+            0x31 => SurfaceButton::ChannelRight,
+            0x5B => SurfaceButton::Rewind,
+            0x5D => SurfaceButton::Stop,
+            0x5E => SurfaceButton::Play,
+            0x5F => SurfaceButton::Record,
+            _ => return None,
+        })
+    }
+
+    /// Note number of the button (and of its LED)
+    pub fn note(self) -> u8 {
+        match self {
+            SurfaceButton::Mute(strip) => NOTE_MUTE + strip,
+            SurfaceButton::BankLeft => 0x2E,
+            SurfaceButton::BankRight => 0x2F,
+            SurfaceButton::ChannelLeft => 0x30,
+            SurfaceButton::ChannelRight => 0x31,
+            SurfaceButton::Rewind => 0x5B,
+            SurfaceButton::Stop => 0x5D,
+            SurfaceButton::Play => 0x5E,
+            SurfaceButton::Record => 0x5F,
+        }
+    }
+}
+
+/// Input from a Mackie Control surface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SurfaceEvent {
+    /// Fader moved (strip 0-7, 8 for the master), position 0.0 to 1.0
+    Fader { strip: u8, position: f32 },
+    /// Fader touched or released
+    FaderTouch { strip: u8, touched: bool },
+    /// V-Pot turned by a number of ticks (negative: counter-clockwise)
+    VPot { strip: u8, ticks: i8 },
+    Button {
+        button: SurfaceButton,
+        pressed: bool,
+    },
+}
+
+impl SurfaceEvent {
+    /// Decode a MIDI message (unknown messages give None)
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (&status, data) = bytes.split_first()?;
+        let channel = status & 0x0F;
+        match (status & 0xF0, data) {
+            (0xE0, &[lsb, msb, ..]) if channel <= MASTER_STRIP => {
+                let value = (u16::from(msb & 0x7F) << 7) | u16::from(lsb & 0x7F);
+                Some(SurfaceEvent::Fader {
+                    strip: channel,
+                    position: f32::from(value) / f32::from(FADER_MAX),
+                })
+            }
+            (status @ (0x80 | 0x90), &[note, velocity, ..]) => {
+                let pressed = status == 0x90 && velocity > 0;
+                match note {
+                    0x68..=0x70 => Some(SurfaceEvent::FaderTouch {
+                        strip: note - NOTE_FADER_TOUCH,
+                        touched: pressed,
+                    }),
+                    _ => SurfaceButton::from_note(note)
+                        .map(|button| SurfaceEvent::Button { button, pressed }),
+                }
+            }
+            (0xB0, &[cc @ 0x10..=0x17, value, ..]) => {
+                let ticks = (value & 0x3F) as i8;
+                Some(SurfaceEvent::VPot {
+                    strip: cc - CC_VPOT,
+                    ticks: if value & 0x40 != 0 { -ticks } else { ticks },
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Fader position of a linear track gain (square root law, unity near 70%)
+pub fn gain_to_fader(gain: f32) -> f32 {
+    (gain / MAX_CLIP_GAIN).clamp(0.0, 1.0).sqrt()
+}
+
+/// Linear track gain of a fader position
+pub fn fader_to_gain(position: f32) -> f32 {
+    position.clamp(0.0, 1.0).powi(2) * MAX_CLIP_GAIN
+}
+
+/// Motor fader message (strip 0-7, 8 for the master)
+pub fn fader_message(strip: u8, position: f32) -> [u8; 3] {
+    let value = fader_value(position);
+    [
+        0xE0 | (strip & 0x0F),
+        (value & 0x7F) as u8,
+        (value >> 7) as u8,
+    ]
+}
+
+fn fader_value(position: f32) -> u16 {
+    (position.clamp(0.0, 1.0) * f32::from(FADER_MAX)).round() as u16
+}
+
+/// Button LED message
+pub fn led_message(button: SurfaceButton, on: bool) -> [u8; 3] {
+    [0x90, button.note(), if on { 0x7F } else { 0x00 }]
+}
+
+/// V-Pot LED ring message showing a pan (None: ring off)
+pub fn vpot_ring_message(strip: u8, pan: Option<f32>) -> [u8; 3] {
+    [0xB0, CC_VPOT_RING + strip, ring_value(pan)]
+}
+
+/// Boost/cut mode (lit from the center), LEDs 1-11
+fn ring_value(pan: Option<f32>) -> u8 {
+    pan.map_or(0, |pan| {
+        0x10 | (6.0 + pan.clamp(-1.0, 1.0) * 5.0).round() as u8
+    })
+}
+
+/// Scribble strip message writing a strip line (0: top, 1: bottom)
+///
+/// The text is cut to 7 characters; non-ASCII characters show as '?'.
+pub fn scribble_message(strip: u8, line: u8, text: &str) -> Vec<u8> {
+    let offset = line * 0x38 + strip * SCRIBBLE_WIDTH as u8;
+    let mut message = vec![0xF0, 0x00, 0x00, 0x66, 0x14, 0x12, offset];
+    let chars = text.chars().chain(std::iter::repeat(' '));
+    message.extend(chars.take(SCRIBBLE_WIDTH).map(|c| {
+        if c.is_ascii() && !c.is_ascii_control() {
+            c as u8
+        } else {
+            b'?'
+        }
+    }));
+    message.push(0xF7);
+    message
+}
+
+/// What a strip shows, to only send what changed
+#[derive(Debug, Clone, PartialEq)]
+struct StripFeedback {
+    name: String,
+    level: String,
+    fader: u16,
+    ring: u8,
+    muted: bool,
+}
+
+impl StripFeedback {
+    fn of(track: Option<&AudioTrack>) -> Self {
+        let Some(track) = track else {
+            return Self {
+                name: String::new(),
+                level: String::new(),
+                fader: 0,
+                ring: ring_value(None),
+                muted: false,
+            };
+        };
+        let level = if track.muted {
+            "MUTE".to_string()
+        } else if track.gain <= 0.0 {
+            "-inf".to_string()
+        } else {
+            format!("{:+.1}dB", 20.0 * track.gain.log10())
+        };
+        Self {
+            name: track.name.clone(),
+            level,
+            fader: fader_value(gain_to_fader(track.gain)),
+            ring: ring_value(Some(track.pan)),
+            muted: track.muted,
+        }
+    }
+}
+
+/// Mackie Control protocol state: bank, touched faders and sent feedback
+///
+/// `handle` turns surface events into `RemoteAction`s for the frontend to
+/// apply; `feedback` gives the messages updating the surface from the state.
+#[derive(Debug, Default)]
+pub struct MackieControl {
+    /// Index of the audio track on the first strip
+    bank_offset: usize,
+    /// Faders held by the user (not moved by the feedback)
+    touched: [bool; STRIPS + 1],
+    sent: [Option<StripFeedback>; STRIPS],
+    sent_master: Option<u16>,
+    /// Play and record LEDs
+    sent_transport: Option<(bool, bool)>,
+}
+
+impl MackieControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index of the audio track on the first strip
+    pub fn bank_offset(&self) -> usize {
+        self.bank_offset
+    }
+
+    /// Forget the sent feedback (e.g. after reconnecting): the next
+    /// `feedback` call updates the whole surface
+    pub fn reset(&mut self) {
+        *self = Self {
+            bank_offset: self.bank_offset,
+            ..Self::default()
+        };
+    }
+
+    fn strip_track<'a>(&self, state: &'a DawState, strip: u8) -> Option<&'a AudioTrack> {
+        state
+            .audio_tracks
+            .tracks()
+            .get(self.bank_offset + strip as usize)
+    }
+
+    /// Handle a surface event, returning the action to apply
+    pub fn handle(&mut self, event: SurfaceEvent, state: &DawState) -> Option<RemoteAction> {
+        match event {
+            SurfaceEvent::Fader { strip, position } if strip == MASTER_STRIP => {
+                Some(RemoteAction::SetVolume(position))
+            }
+            SurfaceEvent::Fader { strip, position } => {
+                let track = self.strip_track(state, strip)?;
+                Some(RemoteAction::SetTrackVolume {
+                    track_id: track.id,
+                    gain: fader_to_gain(position),
+                })
+            }
+            SurfaceEvent::FaderTouch { strip, touched } => {
+                if let Some(held) = self.touched.get_mut(strip as usize) {
+                    *held = touched;
+                }
+                None
+            }
+            SurfaceEvent::VPot { strip, ticks } => {
+                let track = self.strip_track(state, strip)?;
+                Some(RemoteAction::SetTrackPan {
+                    track_id: track.id,
+                    pan: track.pan + f32::from(ticks) * PAN_STEP,
+                })
+            }
+            SurfaceEvent::Button { pressed: false, .. } => None,
+            SurfaceEvent::Button { button, .. } => self.press(button, state),
+        }
+    }
+
+    fn press(&mut self, button: SurfaceButton, state: &DawState) -> Option<RemoteAction> {
+        let track_count = state.audio_tracks.tracks().len();
+        let last_bank = track_count.saturating_sub(STRIPS);
+        match button {
+            SurfaceButton::Mute(strip) => {
+                let track = self.strip_track(state, strip)?;
+                return Some(RemoteAction::SetTrackMute {
+                    track_id: track.id,
+                    muted: !track.muted,
+                });
+            }
+            SurfaceButton::BankLeft => self.bank_offset = self.bank_offset.saturating_sub(STRIPS),
+            SurfaceButton::BankRight => {
+                self.bank_offset = (self.bank_offset + STRIPS).min(last_bank)
+            }
+            SurfaceButton::ChannelLeft => self.bank_offset = self.bank_offset.saturating_sub(1),
+            SurfaceButton::ChannelRight => self.bank_offset = (self.bank_offset + 1).min(last_bank),
+            SurfaceButton::Rewind => return Some(RemoteAction::Rewind),
+            SurfaceButton::Stop => return Some(RemoteAction::Stop),
+            SurfaceButton::Play => return Some(RemoteAction::Play),
+            SurfaceButton::Record => return Some(RemoteAction::Record),
+        }
+        None
+    }
+
+    /// Messages bringing the surface up to date with the state
+    ///
+    /// Only what changed since the last call is sent; touched faders are left
+    /// alone so they do not fight the user's hand.
+    pub fn feedback(&mut self, state: &DawState, playing: bool, recording: bool) -> Vec<Vec<u8>> {
+        // Tracks may have been removed since the last bank change
+        let track_count = state.audio_tracks.tracks().len();
+        self.bank_offset = self.bank_offset.min(track_count.saturating_sub(STRIPS));
+
+        let mut messages = Vec::new();
+        for strip in 0..STRIPS as u8 {
+            let current = StripFeedback::of(self.strip_track(state, strip));
+            let touched = self.touched[strip as usize];
+            let sent = &mut self.sent[strip as usize];
+            let previous = sent.as_ref();
+            if previous.is_none_or(|p| p.name != current.name) {
+                messages.push(scribble_message(strip, 0, &current.name));
+            }
+            if previous.is_none_or(|p| p.level != current.level) {
+                messages.push(scribble_message(strip, 1, &current.level));
+            }
+            if !touched && previous.is_none_or(|p| p.fader != current.fader) {
+                let position = f32::from(current.fader) / f32::from(FADER_MAX);
+                messages.push(fader_message(strip, position).to_vec());
+            }
+            if previous.is_none_or(|p| p.ring != current.ring) {
+                messages.push(vec![0xB0, CC_VPOT_RING + strip, current.ring]);
+            }
+            if previous.is_none_or(|p| p.muted != current.muted) {
+                messages.push(led_message(SurfaceButton::Mute(strip), current.muted).to_vec());
+            }
+            let mut current = current;
+            if touched {
+                // Resend the fader position once it is released
+                current.fader = previous.map_or(u16::MAX, |p| p.fader);
+            }
+            *sent = Some(current);
+        }
+
+        let master = fader_value(state.volume);
+        if !self.touched[MASTER_STRIP as usize] && self.sent_master != Some(master) {
+            let position = f32::from(master) / f32::from(FADER_MAX);
+            messages.push(fader_message(MASTER_STRIP, position).to_vec());
+            self.sent_master = Some(master);
+        }
+
+        if self.sent_transport != Some((playing, recording)) {
+            messages.push(led_message(SurfaceButton::Play, playing).to_vec());
+            messages.push(led_message(SurfaceButton::Record, recording).to_vec());
+            self.sent_transport = Some((playing, recording));
+        }
+        messages
+    }
+}
+
+/// Connection to a control surface (MIDI input and output with the same name)
+///
+/// Input messages are decoded on the MIDI thread and queued until `poll()`.
+/// Surfaces without an output port of the same name get no feedback.
+#[cfg(feature = "midi-io")]
+pub struct ControlSurfaceConnection {
+    port_name: String,
+    _input: midir::MidiInputConnection<()>,
+    output: Option<midir::MidiOutputConnection>,
+    events: std::sync::mpsc::Receiver<SurfaceEvent>,
+}
+
+#[cfg(feature = "midi-io")]
+impl ControlSurfaceConnection {
+    /// Events buffered between the MIDI thread and the frontend
+    const EVENT_QUEUE_CAPACITY: usize = 1024;
+
+    /// Names of the MIDI input ports
+    pub fn list_ports() -> Vec<String> {
+        let Ok(midi_in) = midir::MidiInput::new("MyMusic DAW Control Surface Scanner") else {
+            return Vec::new();
+        };
+        midi_in
+            .ports()
+            .iter()
+            .filter_map(|port| midi_in.port_name(port).ok())
+            .collect()
+    }
+
+    /// Open the input port and the output port named `port_name`
+    pub fn connect(port_name: &str) -> Result<Self, String> {
+        let midi_in = midir::MidiInput::new("MyMusic DAW Control Surface")
+            .map_err(|e| format!("MIDI unavailable: {}", e))?;
+        let in_port = midi_in
+            .ports()
+            .into_iter()
+            .find(|port| midi_in.port_name(port).as_deref() == Ok(port_name))
+            .ok_or_else(|| format!("MIDI port not found: {}", port_name))?;
+
+        let (tx, events) = std::sync::mpsc::sync_channel(Self::EVENT_QUEUE_CAPACITY);
+        let input = midi_in
+            .connect(
+                &in_port,
+                "mymusic-daw-control-surface",
+                move |_, bytes, _| {
+                    if let Some(event) = SurfaceEvent::from_bytes(bytes) {
+                        // Drop events when the frontend is not polling
+                        let _ = tx.try_send(event);
+                    }
+                },
+                (),
+            )
+            .map_err(|e| format!("Failed to open {}: {}", port_name, e))?;
+
+        let output = midir::MidiOutput::new("MyMusic DAW Control Surface")
+            .ok()
+            .and_then(|midi_out| {
+                let out_port = midi_out
+                    .ports()
+                    .into_iter()
+                    .find(|port| midi_out.port_name(port).as_deref() == Ok(port_name))?;
+                midi_out
+                    .connect(&out_port, "mymusic-daw-control-surface")
+                    .ok()
+            });
+
+        Ok(Self {
+            port_name: port_name.to_string(),
+            _input: input,
+            output,
+            events,
+        })
+    }
+
+    pub fn port_name(&self) -> &str {
+        &self.port_name
+    }
+
+    /// Whether feedback can be sent (an output port was found)
+    pub fn has_output(&self) -> bool {
+        self.output.is_some()
+    }
+
+    /// Take the events received since the last call
+    pub fn poll(&self) -> Vec<SurfaceEvent> {
+        self.events.try_iter().collect()
+    }
+
+    /// Send feedback messages to the surface
+    pub fn send(&mut self, messages: &[Vec<u8>]) -> Result<(), String> {
+        let Some(output) = self.output.as_mut() else {
+            return Ok(());
+        };
+        for message in messages {
+            output
+                .send(message)
+                .map_err(|e| format!("Failed to send to {}: {}", self.port_name, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::channels::create_command_channel;
+    use std::sync::{Arc, Mutex};
+
+    fn test_state(tracks: usize) -> DawState {
+        let (tx, _rx) = create_command_channel(16);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        for i in 0..tracks {
+            state.audio_tracks.add_track(format!("Audio {}", i + 1));
+        }
+        state
+    }
+
+    #[test]
+    fn test_decode_surface_messages() {
+        assert_eq!(
+            SurfaceEvent::from_bytes(&[0xE8, 0x7F, 0x7F]),
+            Some(SurfaceEvent::Fader {
+                strip: 8,
+                position: 1.0
+            })
+        );
+        assert_eq!(
+            SurfaceEvent::from_bytes(&[0x90, 0x69, 0x7F]),
+            Some(SurfaceEvent::FaderTouch {
+                strip: 1,
+                touched: true
+            })
+        );
+        assert_eq!(
+            SurfaceEvent::from_bytes(&[0xB0, 0x12, 0x43]),
+            Some(SurfaceEvent::VPot {
+                strip: 2,
+                ticks: -3
+            })
+        );
+        assert_eq!(
+            SurfaceEvent::from_bytes(&[0x80, 0x5E, 0x40]),
+            Some(SurfaceEvent::Button {
+                button: SurfaceButton::Play,
+                pressed: false
+            })
+        );
+        assert_eq!(SurfaceEvent::from_bytes(&[0x90, 0x7E, 0x7F]), None);
+        assert_eq!(SurfaceEvent::from_bytes(&[0xE0, 0x00]), None);
+    }
+
+    #[test]
+    fn test_encode_feedback_messages() {
+        assert_eq!(fader_message(0, 1.0), [0xE0, 0x7F, 0x7F]);
+        assert_eq!(fader_message(3, 0.0), [0xE3, 0x00, 0x00]);
+        assert_eq!(vpot_ring_message(1, Some(0.0)), [0xB0, 0x31, 0x16]);
+        assert_eq!(vpot_ring_message(1, Some(-1.0)), [0xB0, 0x31, 0x11]);
+        assert_eq!(
+            led_message(SurfaceButton::Mute(2), true),
+            [0x90, 0x12, 0x7F]
+        );
+        assert_eq!(
+            scribble_message(1, 1, "Bass Guitar"),
+            [
+                0xF0, 0x00, 0x00, 0x66, 0x14, 0x12, 0x3F, b'B', b'a', b's', b's', b' ', b'G', b'u',
+                0xF7
+            ]
+        );
+        assert_eq!(&scribble_message(0, 0, "é")[7..9], b"? ");
+        assert!((fader_to_gain(gain_to_fader(1.0)) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_banking_and_feedback_diffing() {
+        let mut state = test_state(10);
+        let mut mackie = MackieControl::new();
+
+        // First update paints the whole surface
+        let messages = mackie.feedback(&state, false, false);
+        assert_eq!(messages.len(), STRIPS * 5 + 3);
+        assert!(mackie.feedback(&state, false, false).is_empty());
+
+        let fader = SurfaceEvent::Fader {
+            strip: 0,
+            position: 0.5,
+        };
+        let Some(RemoteAction::SetTrackVolume { track_id, gain }) = mackie.handle(fader, &state)
+        else {
+            panic!("Expected a track volume action");
+        };
+        assert_eq!(track_id, state.audio_tracks.tracks()[0].id);
+        assert!((gain - 0.5).abs() < 1e-6);
+
+        // Banking right stops at the last full bank
+        let bank_right = SurfaceEvent::Button {
+            button: SurfaceButton::BankRight,
+            pressed: true,
+        };
+        assert_eq!(mackie.handle(bank_right, &state), None);
+        assert_eq!(mackie.bank_offset(), 2);
+        let messages = mackie.feedback(&state, true, false);
+        assert!(messages.contains(&scribble_message(7, 0, "Audio 10")));
+        assert!(messages.contains(&led_message(SurfaceButton::Play, true).to_vec()));
+
+        // A touched fader is not moved, the mute LED follows the state
+        let touch = SurfaceEvent::FaderTouch {
+            strip: 0,
+            touched: true,
+        };
+        mackie.handle(touch, &state);
+        let track_id = state.audio_tracks.tracks()[2].id;
+        let track = state.audio_tracks.track_mut(track_id).unwrap();
+        track.gain = 0.0;
+        track.muted = true;
+        let messages = mackie.feedback(&state, true, false);
+        assert_eq!(
+            messages,
+            vec![
+                scribble_message(0, 1, "MUTE"),
+                led_message(SurfaceButton::Mute(0), true).to_vec()
+            ]
+        );
+        let release = SurfaceEvent::FaderTouch {
+            strip: 0,
+            touched: false,
+        };
+        mackie.handle(release, &state);
+        assert_eq!(
+            mackie.feedback(&state, true, false),
+            vec![fader_message(0, 0.0).to_vec()]
+        );
+    }
+}
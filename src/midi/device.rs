@@ -1,5 +1,6 @@
 // Gestion des devices MIDI
 
+use crate::midi::rtp::NETWORK_MIDI_DEVICE;
 use midir::{MidiInput as MidirInput, MidiInputPort};
 
 #[derive(Clone, Debug)]
@@ -35,6 +36,13 @@ impl MidiDeviceManager {
             }
         }
 
+        // RTP-MIDI session (iOS, macOS and other machines on the LAN)
+        devices.push(MidiDeviceInfo {
+            id: "rtp_midi".to_string(),
+            name: NETWORK_MIDI_DEVICE.to_string(),
+            is_default: false,
+        });
+
         devices
     }
 
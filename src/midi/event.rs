@@ -1,5 +1,11 @@
 // MIDI types events
 
+/// Controller number of the modulation wheel (CC1)
+pub const MOD_WHEEL_CC: u8 = 1;
+
+/// Controller number of the sustain pedal (CC64, down from 64)
+pub const SUSTAIN_PEDAL_CC: u8 = 64;
+
 #[derive(Debug, Clone, Copy)]
 pub enum MidiEvent {
     NoteOn {
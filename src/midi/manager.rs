@@ -1,24 +1,59 @@
 // MIDI Connection Manager - Gestion de la reconnexion automatique
 
+use crate::audio::thread_priority::{self, ThreadKind};
 use crate::connection::reconnect::ReconnectionStrategy;
 use crate::connection::status::{AtomicDeviceStatus, DeviceStatus};
 use crate::messaging::channels::{CommandProducer, NotificationProducer};
 use crate::messaging::command::Command;
 use crate::messaging::notification::{Notification, NotificationCategory};
 use crate::midi::event::{MidiEvent, MidiEventTimed};
+use crate::midi::rtp::{DEFAULT_RTP_MIDI_PORT, NETWORK_MIDI_DEVICE, RtpMidiSession};
 use midir::{MidiInput as MidirInput, MidiInputConnection};
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
 use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 type MidiConnection = Arc<Mutex<Option<MidiInputConnection<()>>>>;
 
+/// Events buffered between the MIDI thread and the frontend (per kind)
+const FRONTEND_QUEUE_CAPACITY: usize = 1024;
+
+/// Events queued for the frontend: control changes (controller, value) for
+/// MIDI learn (see `midi::cc_map`) and notes with their arrival time for
+/// recording
+#[derive(Clone)]
+struct FrontendSender {
+    control_changes: SyncSender<(u8, u8)>,
+    notes: SyncSender<(MidiEvent, Instant)>,
+}
+
+impl FrontendSender {
+    /// Queue an event for the frontend (dropped when it is not polling)
+    fn forward(&self, event: MidiEvent) {
+        match event {
+            MidiEvent::ControlChange { controller, value } => {
+                let _ = self.control_changes.try_send((controller, value));
+            }
+            MidiEvent::NoteOn { .. } | MidiEvent::NoteOff { .. } => {
+                let _ = self.notes.try_send((event, Instant::now()));
+            }
+            _ => {}
+        }
+    }
+}
+
 pub struct MidiConnectionManager {
     connection: MidiConnection,
+    /// RTP-MIDI session, open while the network device is the target
+    network_session: Mutex<Option<RtpMidiSession>>,
     status: AtomicDeviceStatus,
     target_device: Arc<Mutex<Option<String>>>,
     command_tx: Arc<Mutex<CommandProducer>>,
     notification_tx: Arc<Mutex<NotificationProducer>>,
+    frontend_tx: FrontendSender,
+    control_changes: Receiver<(u8, u8)>,
+    notes: Receiver<(MidiEvent, Instant)>,
     _monitor_thread: Option<thread::JoinHandle<()>>,
 }
 
@@ -31,6 +66,12 @@ impl MidiConnectionManager {
         let status = AtomicDeviceStatus::new(DeviceStatus::Disconnected);
         let target_device = Arc::new(Mutex::new(None));
         let command_tx = Arc::new(Mutex::new(command_tx));
+        let (control_changes_tx, control_changes) = sync_channel(FRONTEND_QUEUE_CAPACITY);
+        let (notes_tx, notes) = sync_channel(FRONTEND_QUEUE_CAPACITY);
+        let frontend_tx = FrontendSender {
+            control_changes: control_changes_tx,
+            notes: notes_tx,
+        };
 
         // Check if MIDI is available (WSL-friendly)
         let midi_available = Self::is_midi_available();
@@ -38,10 +79,14 @@ impl MidiConnectionManager {
             println!("⚠ MIDI not available - running without MIDI support");
             return Self {
                 connection,
+                network_session: Mutex::new(None),
                 status,
                 target_device,
                 command_tx,
                 notification_tx,
+                frontend_tx,
+                control_changes,
+                notes,
                 _monitor_thread: None,
             };
         }
@@ -49,10 +94,14 @@ impl MidiConnectionManager {
         // Créer une instance et lancer le monitoring
         let mut manager = Self {
             connection: connection.clone(),
+            network_session: Mutex::new(None),
             status: status.clone(),
             target_device: target_device.clone(),
             command_tx: command_tx.clone(),
             notification_tx: notification_tx.clone(),
+            frontend_tx: frontend_tx.clone(),
+            control_changes,
+            notes,
             _monitor_thread: None,
         };
 
@@ -66,6 +115,7 @@ impl MidiConnectionManager {
             target_device,
             command_tx,
             notification_tx,
+            frontend_tx,
         );
 
         manager._monitor_thread = Some(monitor_thread);
@@ -122,6 +172,10 @@ impl MidiConnectionManager {
     pub fn try_connect_to_device(&self, device_name: &str) -> bool {
         self.status.set(DeviceStatus::Connecting);
 
+        if device_name == NETWORK_MIDI_DEVICE {
+            return self.start_network_session();
+        }
+
         let midi_in = match MidirInput::new("MyMusic DAW MIDI Input") {
             Ok(m) => m,
             Err(e) => {
@@ -159,13 +213,21 @@ impl MidiConnectionManager {
 
         // Cloner l'Arc pour le callback
         let command_tx_clone: Arc<Mutex<CommandProducer>> = Arc::clone(&self.command_tx);
+        let frontend_tx = self.frontend_tx.clone();
 
         // Créer la connexion avec callback
+        let mut priority_requested = false;
         let connection = midi_in.connect(
             port,
             "mymusic-daw-input",
             move |_timestamp, message, _| {
+                // Elevate the MIDI input thread (owned by midir) on its first message
+                if !priority_requested {
+                    priority_requested = true;
+                    thread_priority::promote_current_thread(ThreadKind::Midi);
+                }
                 if let Some(midi_event) = MidiEvent::from_bytes(message) {
+                    frontend_tx.forward(midi_event);
                     // Create timed MIDI event
                     // TODO: Calculate precise samples_from_now based on _timestamp
                     let timed_event = MidiEventTimed {
@@ -207,6 +269,66 @@ impl MidiConnectionManager {
         }
     }
 
+    /// Listen for RTP-MIDI sessions (see `midi::rtp`)
+    fn start_network_session(&self) -> bool {
+        let command_tx = Arc::clone(&self.command_tx);
+        let frontend_tx = self.frontend_tx.clone();
+        let session = RtpMidiSession::bind(
+            "0.0.0.0",
+            DEFAULT_RTP_MIDI_PORT,
+            "MyMusic DAW",
+            move |message| {
+                if let Some(midi_event) = MidiEvent::from_bytes(message) {
+                    frontend_tx.forward(midi_event);
+                    let timed_event = MidiEventTimed {
+                        event: midi_event,
+                        samples_from_now: 0,
+                    };
+                    if let Ok(mut tx) = command_tx.try_lock() {
+                        let _ = ringbuf::traits::Producer::try_push(
+                            &mut *tx,
+                            Command::Midi(timed_event),
+                        );
+                    }
+                }
+            },
+        );
+
+        match session {
+            Ok(session) => {
+                let message = format!("Network MIDI listening on UDP port {}", session.port());
+                if let Ok(mut s) = self.network_session.lock() {
+                    *s = Some(session);
+                }
+                self.status.set(DeviceStatus::Connected);
+                println!("✓ {}", message);
+                self.send_notification(Notification::info(NotificationCategory::Midi, message));
+                true
+            }
+            Err(e) => {
+                eprintln!("Failed to start network MIDI: {}", e);
+                self.status.set(DeviceStatus::Error);
+                self.send_notification(Notification::error(
+                    NotificationCategory::Midi,
+                    format!("Failed to start network MIDI: {}", e),
+                ));
+                false
+            }
+        }
+    }
+
+    /// Devices in the network MIDI session (empty when it is not open)
+    pub fn network_peers(&self) -> Vec<String> {
+        self.network_session
+            .lock()
+            .ok()
+            .and_then(|s| s.as_ref().map(|s| s.peers()))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|peer| peer.name)
+            .collect()
+    }
+
     /// Thread de monitoring qui vérifie l'état de la connexion et tente de se reconnecter
     fn spawn_monitor_thread(
         connection: MidiConnection,
@@ -214,6 +336,7 @@ impl MidiConnectionManager {
         target_device: Arc<Mutex<Option<String>>>,
         command_tx: Arc<Mutex<CommandProducer>>,
         notification_tx: Arc<Mutex<NotificationProducer>>,
+        frontend_tx: FrontendSender,
     ) -> thread::JoinHandle<()> {
         thread::spawn(move || {
             let mut reconnect_strategy = ReconnectionStrategy::new();
@@ -222,7 +345,7 @@ impl MidiConnectionManager {
             // Helper pour envoyer des notifications depuis le thread
             let send_notification = |notif: Notification| {
                 if let Ok(mut tx) = notification_tx.try_lock() {
-                    let _ = ringbuf::traits::Producer::try_push(&mut *tx, notif);
+                    tx.push(notif);
                 }
             };
 
@@ -241,6 +364,15 @@ impl MidiConnectionManager {
                         // donc on fait confiance au status pour le moment)
                     }
                     DeviceStatus::Disconnected | DeviceStatus::Error => {
+                        // The network session is not a midir port: a failed
+                        // bind is not retried (and not replaced by a device)
+                        let network = target_device
+                            .lock()
+                            .is_ok_and(|t| t.as_deref() == Some(NETWORK_MIDI_DEVICE));
+                        if network {
+                            continue;
+                        }
+
                         // Tenter de se reconnecter
                         if !reconnect_strategy.should_retry() {
                             eprintln!(
@@ -319,13 +451,22 @@ impl MidiConnectionManager {
                                 // Cloner l'Arc pour le callback
                                 let cmd_tx_clone: Arc<Mutex<CommandProducer>> =
                                     Arc::clone(&command_tx);
+                                let frontend_tx = frontend_tx.clone();
 
                                 // Tenter de se connecter
+                                let mut priority_requested = false;
                                 let new_connection = midi_in.connect(
                                     port,
                                     "mymusic-daw-reconnect",
                                     move |_timestamp, message, _| {
+                                        if !priority_requested {
+                                            priority_requested = true;
+                                            thread_priority::promote_current_thread(
+                                                ThreadKind::Midi,
+                                            );
+                                        }
                                         if let Some(midi_event) = MidiEvent::from_bytes(message) {
+                                            frontend_tx.forward(midi_event);
                                             // Create timed MIDI event
                                             // TODO: Calculate precise samples_from_now based on _timestamp
                                             let timed_event = MidiEventTimed {
@@ -389,6 +530,9 @@ impl MidiConnectionManager {
         if let Ok(mut conn) = self.connection.lock() {
             *conn = None;
         }
+        if let Ok(mut session) = self.network_session.lock() {
+            *session = None;
+        }
 
         // Tenter de se connecter au nouveau device
         self.try_connect_to_device(&device_name);
@@ -399,6 +543,17 @@ impl MidiConnectionManager {
         self.status.get()
     }
 
+    /// Take the control changes (controller, value) received since the last call
+    pub fn poll_control_changes(&self) -> Vec<(u8, u8)> {
+        self.control_changes.try_iter().collect()
+    }
+
+    /// Take the notes (NoteOn/NoteOff) received since the last call, with
+    /// the time they arrived
+    pub fn poll_notes(&self) -> Vec<(MidiEvent, Instant)> {
+        self.notes.try_iter().collect()
+    }
+
     /// Retourne le device cible actuel
     pub fn target_device(&self) -> Option<String> {
         self.target_device.lock().ok().and_then(|t| t.clone())
@@ -407,7 +562,7 @@ impl MidiConnectionManager {
     /// Helper pour envoyer une notification
     fn send_notification(&self, notification: Notification) {
         if let Ok(mut tx) = self.notification_tx.try_lock() {
-            let _ = ringbuf::traits::Producer::try_push(&mut *tx, notification);
+            tx.push(notification);
         }
     }
 }
@@ -1,6 +1,13 @@
 // Module MIDI - Gestion des événements MIDI
 
+pub mod cc_map;
+pub mod control_surface;
+#[cfg(feature = "midi-io")]
 pub mod device;
 pub mod event;
+#[cfg(feature = "midi-io")]
 pub mod input;
+#[cfg(feature = "midi-io")]
 pub mod manager;
+pub mod rtp;
+pub mod smf;
@@ -0,0 +1,561 @@
+// RTP-MIDI - Network MIDI sessions (AppleMIDI, RFC 6295)
+//
+// iOS devices, macOS ("Network" MIDI driver) and rtpMIDI on Windows send MIDI
+// over the LAN to a pair of UDP ports: the control port (invitations) and the
+// data port right above it (clock sync and RTP packets). The DAW listens as a
+// session responder: it accepts every invitation and decodes the MIDI
+// commands of the RTP packets, which are handed to a callback on the data
+// thread. The recovery journal is ignored (LAN packets are rarely lost) and
+// the session is not advertised over Bonjour: add the machine by its IP
+// address and port on the sending side.
+//
+// Session packets (big-endian):
+//   IN/OK/NO/BY  FF FF 'I' 'N' | version (2) | token | SSRC | name\0
+//   CK           FF FF 'C' 'K' | SSRC | count | 3 pad | 3 x 64-bit timestamps
+// Timestamps count 100 microsecond ticks.
+
+use crate::audio::thread_priority::{self, ThreadKind};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Default control port (the data port is the next one)
+pub const DEFAULT_RTP_MIDI_PORT: u16 = 5004;
+
+/// Name of the network session among the MIDI input devices
+pub const NETWORK_MIDI_DEVICE: &str = "Network MIDI (RTP-MIDI)";
+
+/// AppleMIDI protocol version
+const PROTOCOL_VERSION: u32 = 2;
+
+/// RTP payload type of RTP-MIDI packets
+const RTP_MIDI_PAYLOAD_TYPE: u8 = 0x61;
+
+/// Largest UDP datagram accepted
+const MAX_PACKET_SIZE: usize = 65536;
+
+/// Socket read timeout, bounds how long `stop()` waits for the threads
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// AppleMIDI session packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionPacket {
+    /// Invitation
+    Invitation { token: u32, ssrc: u32, name: String },
+    /// Invitation accepted
+    Accepted { token: u32, ssrc: u32, name: String },
+    /// Invitation rejected
+    Rejected { token: u32, ssrc: u32 },
+    /// End of session
+    Bye { token: u32, ssrc: u32 },
+    /// Clock synchronization (count 0 to 2)
+    Sync {
+        ssrc: u32,
+        count: u8,
+        timestamps: [u64; 3],
+    },
+}
+
+impl SessionPacket {
+    /// Decode a session packet (None: not a session packet, e.g. RTP data)
+    pub fn decode(packet: &[u8]) -> Option<Self> {
+        let (header, body) = packet.split_first_chunk::<4>()?;
+        let [0xFF, 0xFF, a, b] = *header else {
+            return None;
+        };
+        let u32_at = |i: usize| Some(u32::from_be_bytes(*body.get(i..i + 4)?.first_chunk()?));
+        let u64_at = |i: usize| Some(u64::from_be_bytes(*body.get(i..i + 8)?.first_chunk()?));
+
+        if [a, b] == *b"CK" {
+            return Some(SessionPacket::Sync {
+                ssrc: u32_at(0)?,
+                count: *body.get(4)?,
+                timestamps: [u64_at(8)?, u64_at(16)?, u64_at(24)?],
+            });
+        }
+
+        // Every other command starts with version, token and SSRC
+        if u32_at(0)? != PROTOCOL_VERSION {
+            return None;
+        }
+        let token = u32_at(4)?;
+        let ssrc = u32_at(8)?;
+        let name = || {
+            let name = &body[12..];
+            let end = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+            String::from_utf8_lossy(&name[..end]).into_owned()
+        };
+        match &[a, b] {
+            b"IN" => Some(SessionPacket::Invitation {
+                token,
+                ssrc,
+                name: name(),
+            }),
+            b"OK" => Some(SessionPacket::Accepted {
+                token,
+                ssrc,
+                name: name(),
+            }),
+            b"NO" => Some(SessionPacket::Rejected { token, ssrc }),
+            b"BY" => Some(SessionPacket::Bye { token, ssrc }),
+            _ => None,
+        }
+    }
+
+    /// Encode the packet
+    pub fn encode(&self) -> Vec<u8> {
+        let mut packet = vec![0xFF, 0xFF];
+        let mut session = |command: &[u8; 2], token: u32, ssrc: u32, name: Option<&str>| {
+            packet.extend_from_slice(command);
+            packet.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+            packet.extend_from_slice(&token.to_be_bytes());
+            packet.extend_from_slice(&ssrc.to_be_bytes());
+            if let Some(name) = name {
+                packet.extend_from_slice(name.as_bytes());
+                packet.push(0);
+            }
+        };
+        match self {
+            SessionPacket::Invitation { token, ssrc, name } => {
+                session(b"IN", *token, *ssrc, Some(name))
+            }
+            SessionPacket::Accepted { token, ssrc, name } => {
+                session(b"OK", *token, *ssrc, Some(name))
+            }
+            SessionPacket::Rejected { token, ssrc } => session(b"NO", *token, *ssrc, None),
+            SessionPacket::Bye { token, ssrc } => session(b"BY", *token, *ssrc, None),
+            SessionPacket::Sync {
+                ssrc,
+                count,
+                timestamps,
+            } => {
+                packet.extend_from_slice(b"CK");
+                packet.extend_from_slice(&ssrc.to_be_bytes());
+                packet.extend_from_slice(&[*count, 0, 0, 0]);
+                for timestamp in timestamps {
+                    packet.extend_from_slice(&timestamp.to_be_bytes());
+                }
+            }
+        }
+        packet
+    }
+}
+
+/// Call `on_message` with each MIDI message of an RTP-MIDI packet
+///
+/// Returns false if the packet is not an RTP-MIDI packet. SysEx messages are
+/// skipped; a truncated command list stops at the last complete message.
+pub fn for_each_midi_message(packet: &[u8], mut on_message: impl FnMut(&[u8])) -> bool {
+    // RTP header: version 2, payload type (marker bit masked), 12 bytes
+    if packet.len() < 13 || packet[0] >> 6 != 2 || packet[1] & 0x7F != RTP_MIDI_PAYLOAD_TYPE {
+        return false;
+    }
+    let section = &packet[12..];
+
+    // Command section header: B J Z P LEN (4 bits, 12 bits when B is set)
+    let flags = section[0];
+    let (len, start) = if flags & 0x80 != 0 {
+        let Some(&low) = section.get(1) else {
+            return false;
+        };
+        ((usize::from(flags & 0x0F) << 8) | usize::from(low), 2)
+    } else {
+        (usize::from(flags & 0x0F), 1)
+    };
+    let has_first_delta = flags & 0x20 != 0;
+    let list = &section[start..section.len().min(start + len)];
+
+    let mut pos = 0;
+    let mut running_status = None;
+    let mut first = true;
+    while pos < list.len() {
+        // Delta time (1 to 4 bytes), absent before the first command unless Z
+        if !first || has_first_delta {
+            for _ in 0..4 {
+                let Some(&byte) = list.get(pos) else {
+                    return true;
+                };
+                pos += 1;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+        first = false;
+
+        let Some(&byte) = list.get(pos) else {
+            break;
+        };
+        let status = if byte & 0x80 != 0 {
+            pos += 1;
+            byte
+        } else {
+            match running_status {
+                Some(status) => status,
+                // Data byte without a status: the list is corrupt
+                None => break,
+            }
+        };
+
+        let data_len = match status {
+            // SysEx (and its segments): skip to the end marker
+            0xF0 | 0xF7 => {
+                while let Some(&byte) = list.get(pos) {
+                    pos += 1;
+                    if matches!(byte, 0xF0 | 0xF4 | 0xF7) {
+                        break;
+                    }
+                }
+                running_status = None;
+                continue;
+            }
+            // Real-time messages do not cancel the running status
+            0xF8..=0xFF => {
+                on_message(&[status]);
+                continue;
+            }
+            0xF1 | 0xF3 => 1,
+            0xF2 => 2,
+            0xF4..=0xF6 => 0,
+            0xC0..=0xDF => 1,
+            _ => 2,
+        };
+        running_status = (status < 0xF0).then_some(status);
+
+        let Some(data) = list.get(pos..pos + data_len) else {
+            break;
+        };
+        pos += data_len;
+        let mut message = [status, 0, 0];
+        message[1..=data_len].copy_from_slice(data);
+        on_message(&message[..=data_len]);
+    }
+    true
+}
+
+/// Remote session (a device that invited the DAW)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtpPeer {
+    pub ssrc: u32,
+    pub name: String,
+    pub address: SocketAddr,
+}
+
+/// Network MIDI session listening on a control/data UDP port pair
+///
+/// Both sockets are read on background threads; MIDI messages are passed to
+/// the callback on the data thread. Dropping the session sends no BY: peers
+/// notice through the missing clock syncs.
+pub struct RtpMidiSession {
+    port: u16,
+    peers: Arc<Mutex<Vec<RtpPeer>>>,
+    running: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl RtpMidiSession {
+    /// Listen on `port` (control) and `port + 1` (data) under a session name
+    pub fn bind(
+        ip: &str,
+        port: u16,
+        name: &str,
+        on_message: impl FnMut(&[u8]) + Send + 'static,
+    ) -> Result<Self, String> {
+        let bind = |port: u16| {
+            let socket = UdpSocket::bind((ip, port))
+                .map_err(|e| format!("Failed to bind RTP-MIDI port {}: {}", port, e))?;
+            socket
+                .set_read_timeout(Some(READ_TIMEOUT))
+                .map_err(|e| format!("Failed to configure RTP-MIDI socket: {}", e))?;
+            Ok::<_, String>(socket)
+        };
+        let control = bind(port)?;
+        let port = control
+            .local_addr()
+            .map_err(|e| format!("Failed to read RTP-MIDI port: {}", e))?
+            .port();
+        let data_port = port
+            .checked_add(1)
+            .ok_or("No RTP-MIDI data port above 65535")?;
+        let data = bind(data_port)?;
+
+        let responder = Responder {
+            name: name.to_string(),
+            ssrc: random_ssrc(),
+            started: Instant::now(),
+            peers: Arc::new(Mutex::new(Vec::new())),
+        };
+        let running = Arc::new(AtomicBool::new(true));
+
+        let control_thread = {
+            let responder = responder.clone();
+            let running = Arc::clone(&running);
+            thread::Builder::new()
+                .name("rtp-midi-control".to_string())
+                .spawn(move || receive_loop(control, &responder, &running, |_| {}))
+                .map_err(|e| format!("Failed to start RTP-MIDI thread: {}", e))?
+        };
+        let data_thread = {
+            let responder = responder.clone();
+            let running = Arc::clone(&running);
+            let mut on_message = on_message;
+            thread::Builder::new()
+                .name("rtp-midi-data".to_string())
+                .spawn(move || {
+                    thread_priority::promote_current_thread(ThreadKind::Midi);
+                    receive_loop(data, &responder, &running, |packet| {
+                        for_each_midi_message(packet, &mut on_message);
+                    })
+                })
+                .map_err(|e| format!("Failed to start RTP-MIDI thread: {}", e))?
+        };
+
+        Ok(Self {
+            port,
+            peers: responder.peers,
+            running,
+            threads: vec![control_thread, data_thread],
+        })
+    }
+
+    /// Control port (the data port is the next one)
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Devices currently in session
+    pub fn peers(&self) -> Vec<RtpPeer> {
+        self.peers.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+
+    /// Stop the network threads
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for RtpMidiSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Session state shared by the control and data threads
+#[derive(Clone)]
+struct Responder {
+    name: String,
+    ssrc: u32,
+    started: Instant,
+    peers: Arc<Mutex<Vec<RtpPeer>>>,
+}
+
+impl Responder {
+    /// Reply to a session packet (None: nothing to send)
+    fn reply(&self, packet: SessionPacket, from: SocketAddr) -> Option<SessionPacket> {
+        match packet {
+            SessionPacket::Invitation { token, ssrc, name } => {
+                if let Ok(mut peers) = self.peers.lock() {
+                    // The invitation comes on both ports: keep the control address
+                    if !peers.iter().any(|peer| peer.ssrc == ssrc) {
+                        peers.push(RtpPeer {
+                            ssrc,
+                            name,
+                            address: from,
+                        });
+                    }
+                }
+                Some(SessionPacket::Accepted {
+                    token,
+                    ssrc: self.ssrc,
+                    name: self.name.clone(),
+                })
+            }
+            SessionPacket::Bye { ssrc, .. } => {
+                if let Ok(mut peers) = self.peers.lock() {
+                    peers.retain(|peer| peer.ssrc != ssrc);
+                }
+                None
+            }
+            // The initiator sends count 0, we answer 1, it closes with 2
+            SessionPacket::Sync {
+                count: 0,
+                timestamps,
+                ..
+            } => Some(SessionPacket::Sync {
+                ssrc: self.ssrc,
+                count: 1,
+                timestamps: [timestamps[0], self.now(), 0],
+            }),
+            _ => None,
+        }
+    }
+
+    /// Session clock in 100 microsecond ticks
+    fn now(&self) -> u64 {
+        (self.started.elapsed().as_micros() / 100) as u64
+    }
+}
+
+fn random_ssrc() -> u32 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    nanos ^ std::process::id().rotate_left(16)
+}
+
+fn receive_loop(
+    socket: UdpSocket,
+    responder: &Responder,
+    running: &AtomicBool,
+    mut on_data: impl FnMut(&[u8]),
+) {
+    let mut buffer = vec![0u8; MAX_PACKET_SIZE];
+    while running.load(Ordering::Relaxed) {
+        let (len, from) = match socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            // Timeout: check the running flag again
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue;
+            }
+            Err(e) => {
+                eprintln!("RTP-MIDI receive error: {}", e);
+                continue;
+            }
+        };
+
+        let packet = &buffer[..len];
+        match SessionPacket::decode(packet) {
+            Some(session) => {
+                if let Some(reply) = responder.reply(session, from)
+                    && let Err(e) = socket.send_to(&reply.encode(), from)
+                {
+                    eprintln!("RTP-MIDI send error: {}", e);
+                }
+            }
+            None => on_data(packet),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    /// RTP header followed by a command section
+    fn rtp_packet(section: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x80, RTP_MIDI_PAYLOAD_TYPE, 0, 1, 0, 0, 0, 0, 1, 2, 3, 4];
+        packet.extend_from_slice(section);
+        packet
+    }
+
+    fn messages(packet: &[u8]) -> Vec<Vec<u8>> {
+        let mut messages = Vec::new();
+        assert!(for_each_midi_message(packet, |m| messages.push(m.to_vec())));
+        messages
+    }
+
+    #[test]
+    fn test_session_packets_roundtrip() {
+        let invitation = SessionPacket::Invitation {
+            token: 0x1234,
+            ssrc: 0xCAFE,
+            name: "iPad".to_string(),
+        };
+        let bytes = invitation.encode();
+        assert_eq!(&bytes[..4], b"\xFF\xFFIN");
+        assert_eq!(SessionPacket::decode(&bytes), Some(invitation));
+
+        let sync = SessionPacket::Sync {
+            ssrc: 7,
+            count: 1,
+            timestamps: [1, 2, 3],
+        };
+        assert_eq!(sync.encode().len(), 36);
+        assert_eq!(SessionPacket::decode(&sync.encode()), Some(sync));
+
+        assert_eq!(SessionPacket::decode(b"\xFF\xFFIN\0\0\0\x01"), None);
+        assert_eq!(
+            SessionPacket::decode(&rtp_packet(&[0x03, 0x90, 60, 100])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_midi_list_with_deltas_and_running_status() {
+        // Note on, then running status note on after a delta time
+        let packet = rtp_packet(&[0x06, 0x90, 60, 100, 0x00, 64, 90]);
+        assert_eq!(
+            messages(&packet),
+            vec![vec![0x90, 60, 100], vec![0x90, 64, 90]]
+        );
+
+        // Z flag: first command has a delta; long header (B flag); SysEx skipped
+        let section = [
+            0xA0, 0x0C, 0x81, 0x00, 0xB0, 7, 127, 0x00, 0xF0, 0x7E, 0xF7, 0x00, 0xC0, 5,
+        ];
+        assert_eq!(
+            messages(&rtp_packet(&section)),
+            vec![vec![0xB0, 7, 127], vec![0xC0, 5]]
+        );
+
+        // Truncated list stops at the last complete message
+        assert_eq!(
+            messages(&rtp_packet(&[0x05, 0x90, 60, 100, 0x00, 64])).len(),
+            1
+        );
+        assert!(!for_each_midi_message(&[0x80, 0x20, 0, 0], |_| {}));
+    }
+
+    #[test]
+    fn test_session_accepts_invitation_and_forwards_midi() {
+        let (tx, rx) = mpsc::channel();
+        // The data port must be free too: retry on another control port
+        let session = (0..10)
+            .find_map(|_| {
+                let tx = tx.clone();
+                RtpMidiSession::bind("127.0.0.1", 0, "DAW", move |m| {
+                    let _ = tx.send(m.to_vec());
+                })
+                .ok()
+            })
+            .unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+
+        let invitation = SessionPacket::Invitation {
+            token: 42,
+            ssrc: 99,
+            name: "Phone".to_string(),
+        };
+        client
+            .send_to(&invitation.encode(), ("127.0.0.1", session.port()))
+            .unwrap();
+        let mut buffer = [0u8; 256];
+        let len = client.recv(&mut buffer).unwrap();
+        assert!(matches!(
+            SessionPacket::decode(&buffer[..len]),
+            Some(SessionPacket::Accepted { token: 42, .. })
+        ));
+        assert_eq!(session.peers()[0].name, "Phone");
+
+        let data_port = session.port() + 1;
+        let packet = rtp_packet(&[0x03, 0x90, 60, 100]);
+        client.send_to(&packet, ("127.0.0.1", data_port)).unwrap();
+        let message = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(message, vec![0x90, 60, 100]);
+    }
+}
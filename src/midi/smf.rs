@@ -0,0 +1,602 @@
+// Standard MIDI File import (SMF format 0, 1 and 2)
+//
+// A file is a header chunk followed by track chunks (big-endian):
+//   MThd | length (6) | format (2) | track count (2) | division (2)
+//   MTrk | length     | events: delta time (variable length) + event
+// Only the notes, the tempo changes, the first time signature and the track
+// names are kept: controllers, program changes and SysEx are skipped. Tick
+// positions go through the tempo map (tempo changes of every track, as in a
+// format 1 file) so the imported notes keep the timing of the file.
+
+use crate::sequencer::Note;
+use crate::sequencer::pattern::{Pattern, generate_note_id};
+use crate::sequencer::timeline::{MusicalTime, Position, Tempo, TimeSignature};
+use std::path::Path;
+
+/// Tempo of a file without tempo event (microseconds per quarter note, 120 BPM)
+const DEFAULT_TEMPO_US: u32 = 500_000;
+
+/// Note of an SMF track, in ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmfNote {
+    pub channel: u8,
+    pub key: u8,
+    pub velocity: u8,
+    pub start_tick: u64,
+    pub length_ticks: u64,
+}
+
+/// Track of an SMF file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SmfTrack {
+    pub name: Option<String>,
+    pub notes: Vec<SmfNote>,
+}
+
+/// Parsed Standard MIDI File
+#[derive(Debug, Clone, PartialEq)]
+pub struct Smf {
+    pub format: u16,
+    /// Ticks per quarter note (SMPTE divisions are converted at 120 BPM)
+    pub ticks_per_quarter: u16,
+    /// Tempo changes (tick, microseconds per quarter note), sorted by tick
+    pub tempo_map: Vec<(u64, u32)>,
+    /// First time signature of the file (numerator, denominator)
+    pub time_signature: Option<(u8, u8)>,
+    pub tracks: Vec<SmfTrack>,
+}
+
+impl Smf {
+    /// Read and parse a .mid file
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let bytes =
+            std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&bytes)
+    }
+
+    /// Parse the bytes of a Standard MIDI File
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(bytes);
+        let (id, header) = reader.chunk()?;
+        if id != *b"MThd" || header.len() < 6 {
+            return Err("Not a Standard MIDI File (missing MThd header)".to_string());
+        }
+        let format = u16::from_be_bytes([header[0], header[1]]);
+        let track_count = u16::from_be_bytes([header[2], header[3]]);
+        let division = u16::from_be_bytes([header[4], header[5]]);
+        if format > 2 {
+            return Err(format!("Unsupported MIDI file format {}", format));
+        }
+        let ticks_per_quarter = if division & 0x8000 == 0 {
+            division
+        } else {
+            // SMPTE: frames per second x ticks per frame, one quarter = 0.5 s at 120 BPM
+            let fps = ((division >> 8) as u8 as i8).unsigned_abs() as u16;
+            let ticks_per_frame = division & 0xFF;
+            (fps * ticks_per_frame / 2).max(1)
+        };
+        if ticks_per_quarter == 0 {
+            return Err("Invalid MIDI file division".to_string());
+        }
+
+        let mut smf = Smf {
+            format,
+            ticks_per_quarter,
+            tempo_map: Vec::new(),
+            time_signature: None,
+            tracks: Vec::new(),
+        };
+        while smf.tracks.len() < track_count as usize && !reader.is_empty() {
+            let (id, data) = reader.chunk()?;
+            // Unknown chunks must be skipped
+            if id == *b"MTrk" {
+                let track = smf.parse_track(data)?;
+                smf.tracks.push(track);
+            }
+        }
+        smf.tempo_map.sort_by_key(|(tick, _)| *tick);
+        Ok(smf)
+    }
+
+    /// Parse the events of a track chunk
+    fn parse_track(&mut self, data: &[u8]) -> Result<SmfTrack, String> {
+        let mut reader = Reader::new(data);
+        let mut track = SmfTrack::default();
+        // Open notes (channel, key, velocity, start tick), closed first in first out
+        let mut open: Vec<(u8, u8, u8, u64)> = Vec::new();
+        let mut tick = 0u64;
+        let mut running_status = 0u8;
+
+        while !reader.is_empty() {
+            tick += reader.variable_length()? as u64;
+            let mut status = reader.byte()?;
+            let first_data = if status < 0x80 {
+                // Running status: the byte is the first data byte
+                if running_status == 0 {
+                    return Err("MIDI data byte without status".to_string());
+                }
+                let data = status;
+                status = running_status;
+                Some(data)
+            } else {
+                None
+            };
+
+            match status {
+                0xFF => {
+                    let kind = reader.byte()?;
+                    let length = reader.variable_length()? as usize;
+                    let payload = reader.bytes(length)?;
+                    match kind {
+                        0x03 if track.name.is_none() => {
+                            let name = String::from_utf8_lossy(payload).trim().to_string();
+                            if !name.is_empty() {
+                                track.name = Some(name);
+                            }
+                        }
+                        0x51 if payload.len() == 3 => {
+                            let tempo = u32::from_be_bytes([0, payload[0], payload[1], payload[2]]);
+                            if tempo > 0 {
+                                self.tempo_map.push((tick, tempo));
+                            }
+                        }
+                        0x58 if payload.len() >= 2 && self.time_signature.is_none() => {
+                            let denominator = 1u8.checked_shl(payload[1] as u32).unwrap_or(4);
+                            self.time_signature = Some((payload[0].max(1), denominator));
+                        }
+                        0x2F => break,
+                        _ => {}
+                    }
+                }
+                0xF0 | 0xF7 => {
+                    let length = reader.variable_length()? as usize;
+                    reader.bytes(length)?;
+                    // SysEx cancels the running status
+                    running_status = 0;
+                }
+                0x80..=0xEF => {
+                    running_status = status;
+                    let first = match first_data {
+                        Some(data) => data,
+                        None => reader.byte()?,
+                    };
+                    // Program change and channel pressure have one data byte
+                    let second = match status & 0xF0 {
+                        0xC0 | 0xD0 => 0,
+                        _ => reader.byte()?,
+                    };
+                    let channel = status & 0x0F;
+                    let key = first & 0x7F;
+                    match status & 0xF0 {
+                        0x90 if second > 0 => open.push((channel, key, second & 0x7F, tick)),
+                        0x80 | 0x90 => {
+                            if let Some(index) =
+                                open.iter().position(|n| n.0 == channel && n.1 == key)
+                            {
+                                let (_, _, velocity, start) = open.remove(index);
+                                track.notes.push(SmfNote {
+                                    channel,
+                                    key,
+                                    velocity,
+                                    start_tick: start,
+                                    length_ticks: tick - start,
+                                });
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => return Err(format!("Unsupported MIDI event 0x{:02X}", status)),
+            }
+        }
+
+        // Notes still held end with the track
+        for (channel, key, velocity, start) in open {
+            track.notes.push(SmfNote {
+                channel,
+                key,
+                velocity,
+                start_tick: start,
+                length_ticks: tick - start,
+            });
+        }
+        track.notes.sort_by_key(|n| (n.start_tick, n.key));
+        Ok(track)
+    }
+
+    /// Tempo at the start of the file in BPM
+    pub fn initial_bpm(&self) -> f64 {
+        let tempo = match self.tempo_map.first() {
+            Some((0, tempo)) => *tempo,
+            _ => DEFAULT_TEMPO_US,
+        };
+        60_000_000.0 / tempo as f64
+    }
+
+    /// Position of a tick in seconds, following the tempo map
+    pub fn tick_to_seconds(&self, tick: u64) -> f64 {
+        let ticks_per_quarter = self.ticks_per_quarter as f64;
+        let mut seconds = 0.0;
+        let mut last_tick = 0u64;
+        let mut tempo = DEFAULT_TEMPO_US;
+        for &(change_tick, change_tempo) in &self.tempo_map {
+            if change_tick >= tick {
+                break;
+            }
+            seconds += (change_tick - last_tick) as f64 / ticks_per_quarter * tempo as f64 / 1e6;
+            last_tick = change_tick;
+            tempo = change_tempo;
+        }
+        seconds + (tick - last_tick) as f64 / ticks_per_quarter * tempo as f64 / 1e6
+    }
+
+    /// One pattern per track with notes, at the given project tempo and time
+    /// signature (patterns are rounded up to whole bars)
+    pub fn to_patterns(
+        &self,
+        sample_rate: f64,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+        fallback_name: &str,
+    ) -> Vec<Pattern> {
+        let to_samples = |tick: u64| (self.tick_to_seconds(tick) * sample_rate).round() as u64;
+        let bar_samples = tempo.bar_duration_samples(sample_rate, time_signature);
+
+        self.tracks
+            .iter()
+            .filter(|track| !track.notes.is_empty())
+            .enumerate()
+            .map(|(index, track)| {
+                let name = track
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("{} {}", fallback_name, index + 1));
+                let mut notes = Vec::with_capacity(track.notes.len());
+                let mut end = 0u64;
+                for note in &track.notes {
+                    let start = to_samples(note.start_tick);
+                    let stop = to_samples(note.start_tick + note.length_ticks).max(start + 1);
+                    end = end.max(stop);
+                    notes.push(Note::new(
+                        generate_note_id(),
+                        note.key,
+                        Position::from_samples(start, sample_rate, tempo, time_signature),
+                        stop - start,
+                        note.velocity,
+                    ));
+                }
+                let length_bars = (end as f64 / bar_samples).ceil().max(1.0) as u32;
+                let mut pattern =
+                    Pattern::new(crate::project::generate_pattern_id(), name, length_bars);
+                pattern.set_notes(notes);
+                pattern
+            })
+            .collect()
+    }
+}
+
+impl Smf {
+    /// Empty type 1 file at a constant tempo, at the sequencer resolution
+    pub fn with_tempo(bpm: f64, time_signature: TimeSignature) -> Self {
+        Self {
+            format: 1,
+            ticks_per_quarter: MusicalTime::TICKS_PER_QUARTER,
+            tempo_map: vec![(0, (60_000_000.0 / bpm).round() as u32)],
+            time_signature: Some((time_signature.numerator, time_signature.denominator)),
+            tracks: Vec::new(),
+        }
+    }
+
+    /// Add a track with the notes of a pattern (positions in samples at the
+    /// initial tempo of the file)
+    pub fn add_track<'a>(
+        &mut self,
+        name: &str,
+        notes: impl IntoIterator<Item = &'a Note>,
+        sample_rate: f64,
+    ) {
+        let ticks_per_second = self.ticks_per_quarter as f64 * self.initial_bpm() / 60.0;
+        let to_tick =
+            |samples: u64| (samples as f64 / sample_rate * ticks_per_second).round() as u64;
+        let mut notes: Vec<SmfNote> = notes
+            .into_iter()
+            .map(|note| {
+                let start_tick = to_tick(note.start.samples);
+                SmfNote {
+                    channel: 0,
+                    key: note.pitch,
+                    velocity: note.velocity.max(1),
+                    start_tick,
+                    length_ticks: (to_tick(note.end_sample()) - start_tick).max(1),
+                }
+            })
+            .collect();
+        notes.sort_by_key(|n| (n.start_tick, n.key));
+        self.tracks.push(SmfTrack {
+            name: Some(name.to_string()),
+            notes,
+        });
+    }
+
+    /// Encode as a type 1 file: a tempo track (time signature and tempo map)
+    /// followed by one track per `SmfTrack`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut header = Vec::with_capacity(6);
+        header.extend_from_slice(&1u16.to_be_bytes());
+        header.extend_from_slice(&(self.tracks.len() as u16 + 1).to_be_bytes());
+        header.extend_from_slice(&self.ticks_per_quarter.to_be_bytes());
+        write_chunk(&mut bytes, b"MThd", &header);
+
+        // Tempo track
+        let mut events: Vec<(u64, Vec<u8>)> = Vec::new();
+        if let Some((numerator, denominator)) = self.time_signature {
+            let power = denominator.max(1).trailing_zeros() as u8;
+            events.push((0, vec![0xFF, 0x58, 0x04, numerator, power, 24, 8]));
+        }
+        for &(tick, tempo) in &self.tempo_map {
+            let [_, high, middle, low] = tempo.to_be_bytes();
+            events.push((tick, vec![0xFF, 0x51, 0x03, high, middle, low]));
+        }
+        write_chunk(&mut bytes, b"MTrk", &encode_events(&events));
+
+        for track in &self.tracks {
+            let mut events = Vec::with_capacity(track.notes.len() * 2 + 1);
+            if let Some(name) = &track.name {
+                let mut event = vec![0xFF, 0x03];
+                write_variable_length(&mut event, name.len() as u32);
+                event.extend_from_slice(name.as_bytes());
+                events.push((0, event));
+            }
+            for note in &track.notes {
+                events.push((
+                    note.start_tick,
+                    vec![0x90 | note.channel, note.key, note.velocity],
+                ));
+                events.push((
+                    note.start_tick + note.length_ticks,
+                    vec![0x80 | note.channel, note.key, 0],
+                ));
+            }
+            write_chunk(&mut bytes, b"MTrk", &encode_events(&events));
+        }
+        bytes
+    }
+
+    /// Write the file to disk
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// Encode timed events with their delta times and the end of track. At the
+/// same tick, note offs come first so repeated notes do not overlap.
+fn encode_events(events: &[(u64, Vec<u8>)]) -> Vec<u8> {
+    let mut order: Vec<&(u64, Vec<u8>)> = events.iter().collect();
+    order.sort_by_key(|(tick, event)| (*tick, event[0] & 0xF0 != 0x80));
+    let mut data = Vec::new();
+    let mut last_tick = 0;
+    for (tick, event) in order {
+        write_variable_length(&mut data, (*tick - last_tick) as u32);
+        data.extend_from_slice(event);
+        last_tick = *tick;
+    }
+    data.extend_from_slice(&[0x00, 0xFF, 0x2F, 0x00]);
+    data
+}
+
+fn write_variable_length(data: &mut Vec<u8>, value: u32) {
+    let mut groups = [0u8; 4];
+    let mut count = 0;
+    let mut rest = value.min(0x0FFF_FFFF);
+    loop {
+        groups[count] = (rest & 0x7F) as u8;
+        count += 1;
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    for (i, group) in groups[..count].iter().enumerate().rev() {
+        data.push(group | if i > 0 { 0x80 } else { 0 });
+    }
+}
+
+fn write_chunk(bytes: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+    bytes.extend_from_slice(id);
+    bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(data);
+}
+
+/// Cursor over big-endian SMF data
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self
+            .data
+            .get(self.offset)
+            .ok_or_else(|| "Truncated MIDI file".to_string())?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(length)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| "Truncated MIDI file".to_string())?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    /// Variable-length quantity (7 bits per byte, at most 4 bytes)
+    fn variable_length(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Invalid variable-length quantity in MIDI file".to_string())
+    }
+
+    /// Chunk identifier and data
+    fn chunk(&mut self) -> Result<([u8; 4], &'a [u8]), String> {
+        let id = self.bytes(4)?;
+        let length = self.bytes(4)?;
+        let length = u32::from_be_bytes([length[0], length[1], length[2], length[3]]);
+        let data = self.bytes(length as usize)?;
+        Ok(([id[0], id[1], id[2], id[3]], data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(id: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    /// Format 1 file at 480 PPQ: a tempo track (120 BPM, then 60 BPM at beat
+    /// 2) and a named track with two notes, the second one in running status
+    fn test_file() -> Vec<u8> {
+        let mut bytes = chunk(b"MThd", &[0, 1, 0, 2, 0x01, 0xE0]);
+        bytes.extend(chunk(
+            b"MTrk",
+            &[
+                0x00, 0xFF, 0x58, 0x04, 0x03, 0x02, 0x18, 0x08, // 3/4
+                0x00, 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20, // 120 BPM
+                0x87, 0x40, 0xFF, 0x51, 0x03, 0x0F, 0x42, 0x40, // 60 BPM at tick 960
+                0x00, 0xFF, 0x2F, 0x00,
+            ],
+        ));
+        bytes.extend(chunk(
+            b"MTrk",
+            &[
+                0x00, 0xFF, 0x03, 0x04, b'B', b'a', b's', b's', //
+                0x00, 0x90, 60, 100, // note on
+                0x83, 0x60, 0x80, 60, 0, // note off after 480 ticks
+                0x83, 0x60, 0x90, 64, 80, // note on at tick 960
+                0x83, 0x60, 64, 0, // running status, velocity 0 = note off
+                0x00, 0xFF, 0x2F, 0x00,
+            ],
+        ));
+        bytes
+    }
+
+    #[test]
+    fn test_parse_smf() {
+        let smf = Smf::parse(&test_file()).unwrap();
+        assert_eq!(smf.format, 1);
+        assert_eq!(smf.ticks_per_quarter, 480);
+        assert_eq!(smf.time_signature, Some((3, 4)));
+        assert_eq!(smf.tempo_map, vec![(0, 500_000), (960, 1_000_000)]);
+        assert_eq!(smf.initial_bpm(), 120.0);
+        assert_eq!(smf.tracks.len(), 2);
+        assert_eq!(smf.tracks[1].name.as_deref(), Some("Bass"));
+        let notes = &smf.tracks[1].notes;
+        assert_eq!(notes.len(), 2);
+        assert_eq!((notes[0].key, notes[0].velocity), (60, 100));
+        assert_eq!((notes[0].start_tick, notes[0].length_ticks), (0, 480));
+        assert_eq!((notes[1].key, notes[1].start_tick), (64, 960));
+        assert_eq!(notes[1].length_ticks, 480);
+
+        assert!(Smf::parse(b"RIFF0000").is_err());
+        let mut truncated = test_file();
+        truncated.truncate(30);
+        assert!(Smf::parse(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_tempo_map_to_patterns() {
+        let smf = Smf::parse(&test_file()).unwrap();
+        // Two beats at 120 BPM, then one beat at 60 BPM
+        assert!((smf.tick_to_seconds(960) - 1.0).abs() < 1e-9);
+        assert!((smf.tick_to_seconds(1440) - 2.0).abs() < 1e-9);
+
+        let sample_rate = 48000.0;
+        let patterns = smf.to_patterns(
+            sample_rate,
+            &Tempo::new(120.0),
+            &TimeSignature::new(4, 4),
+            "Song",
+        );
+        // The tempo track has no notes
+        assert_eq!(patterns.len(), 1);
+        let pattern = &patterns[0];
+        assert_eq!(pattern.name, "Bass");
+        assert_eq!(pattern.length_bars, 1);
+        let notes = pattern.notes();
+        assert_eq!(
+            (notes[0].start.samples, notes[0].duration_samples),
+            (0, 24000)
+        );
+        assert_eq!(
+            (notes[1].start.samples, notes[1].duration_samples),
+            (48000, 48000)
+        );
+        assert_eq!(notes[1].pitch, 64);
+        assert_eq!(notes[1].velocity, 80);
+    }
+
+    #[test]
+    fn test_write_smf_round_trip() {
+        let sample_rate = 48000.0;
+        let tempo = Tempo::new(90.0);
+        let time_signature = TimeSignature::new(6, 8);
+        let note = |pitch, start, duration, velocity| {
+            let position = Position::from_samples(start, sample_rate, &tempo, &time_signature);
+            Note::new(generate_note_id(), pitch, position, duration, velocity)
+        };
+        // One beat at 90 BPM = 32000 samples; the repeated C4 touches the first one
+        let notes = [
+            note(60, 0, 32000, 100),
+            note(60, 32000, 16000, 64),
+            note(67, 8000, 4000, 127),
+        ];
+        let mut smf = Smf::with_tempo(90.0, time_signature);
+        smf.add_track("Lead", &notes, sample_rate);
+
+        let parsed = Smf::parse(&smf.to_bytes()).unwrap();
+        assert_eq!(parsed.format, 1);
+        assert_eq!(parsed.tracks.len(), 2);
+        assert_eq!(parsed.time_signature, Some((6, 8)));
+        assert!((parsed.initial_bpm() - 90.0).abs() < 1e-3);
+        let track = &parsed.tracks[1];
+        assert_eq!(track.name.as_deref(), Some("Lead"));
+        let keys: Vec<_> = track
+            .notes
+            .iter()
+            .map(|n| (n.key, n.start_tick, n.length_ticks, n.velocity))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![(60, 0, 480, 100), (67, 120, 60, 127), (60, 480, 240, 64)]
+        );
+
+        let mut data = Vec::new();
+        write_variable_length(&mut data, 0x3FFF);
+        assert_eq!(data, vec![0xFF, 0x7F]);
+    }
+}
@@ -313,6 +313,18 @@ pub struct clap_param_info {
     pub default_value: f64,
 }
 
+/// CLAP parameter flags (`clap_param_info::flags`)
+pub const CLAP_PARAM_IS_STEPPED: u32 = 1 << 0;
+pub const CLAP_PARAM_IS_PERIODIC: u32 = 1 << 1;
+pub const CLAP_PARAM_IS_HIDDEN: u32 = 1 << 2;
+pub const CLAP_PARAM_IS_READONLY: u32 = 1 << 3;
+pub const CLAP_PARAM_IS_BYPASS: u32 = 1 << 4;
+pub const CLAP_PARAM_IS_AUTOMATABLE: u32 = 1 << 5;
+pub const CLAP_PARAM_IS_ENUM: u32 = 1 << 16;
+
+/// Size of the text buffer passed to `clap_plugin_params::value_to_text`
+pub const CLAP_PARAM_TEXT_SIZE: usize = 256;
+
 /// CLAP window handle (platform-specific)
 #[repr(C)]
 pub union clap_window_handle {
@@ -402,6 +414,25 @@ pub struct clap_gui_resize_hints {
     pub aspect_ratio_height: u32,
 }
 
+/// CLAP host GUI extension (requests sent by the plugin GUI to the host)
+#[repr(C)]
+pub struct clap_host_gui {
+    /// The plugin resize hints changed
+    pub resize_hints_changed: extern "C" fn(host: *const clap_host),
+
+    /// Ask the host to resize the client area
+    pub request_resize: extern "C" fn(host: *const clap_host, width: u32, height: u32) -> bool,
+
+    /// Ask the host to show the plugin window
+    pub request_show: extern "C" fn(host: *const clap_host) -> bool,
+
+    /// Ask the host to hide the plugin window
+    pub request_hide: extern "C" fn(host: *const clap_host) -> bool,
+
+    /// The floating window was closed (or the connection to the GUI was lost)
+    pub closed: extern "C" fn(host: *const clap_host, was_destroyed: bool),
+}
+
 /// CLAP plugin params extension
 #[repr(C)]
 pub struct clap_plugin_params {
@@ -441,6 +472,34 @@ pub struct clap_plugin_params {
     ),
 }
 
+/// CLAP input stream (the host provides it to `clap_plugin_state::load`)
+#[repr(C)]
+pub struct clap_istream {
+    pub ctx: *mut c_void,
+
+    /// Read up to `size` bytes, returns the number read (0: end of stream, -1: error)
+    pub read: extern "C" fn(stream: *const clap_istream, buffer: *mut c_void, size: u64) -> i64,
+}
+
+/// CLAP output stream (the host provides it to `clap_plugin_state::save`)
+#[repr(C)]
+pub struct clap_ostream {
+    pub ctx: *mut c_void,
+
+    /// Write up to `size` bytes, returns the number written (-1: error)
+    pub write: extern "C" fn(stream: *const clap_ostream, buffer: *const c_void, size: u64) -> i64,
+}
+
+/// CLAP plugin state extension
+#[repr(C)]
+pub struct clap_plugin_state {
+    /// Save the plugin state into the stream
+    pub save: extern "C" fn(plugin: *const clap_plugin, stream: *const clap_ostream) -> bool,
+
+    /// Load the plugin state from the stream
+    pub load: extern "C" fn(plugin: *const clap_plugin, stream: *const clap_istream) -> bool,
+}
+
 /// Helper function to convert C string to Rust String
 pub unsafe fn c_str_to_string(ptr: *const c_char) -> Option<String> {
     if ptr.is_null() {
@@ -452,6 +511,12 @@ pub unsafe fn c_str_to_string(ptr: *const c_char) -> Option<String> {
     c_str.to_str().ok().map(|s| s.to_string())
 }
 
+/// Helper function to convert a fixed-size, NUL-padded C char array to a Rust String
+pub fn fixed_c_str_to_string(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..len]).into_owned()
+}
+
 /// Helper function to read NULL-terminated string array
 pub unsafe fn read_string_array(mut ptr: *const *const c_char) -> Vec<String> {
     let mut result = Vec::new();
@@ -6,6 +6,79 @@
 use crate::plugin::PluginError;
 use crate::plugin::clap_ffi::*;
 use std::ffi::{CStr, CString};
+use std::sync::Mutex;
+
+/// Request sent by a plugin GUI to the host through the `clap.gui` host extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuiHostRequest {
+    /// The plugin wants its window resized to this client size
+    Resize { width: u32, height: u32 },
+    /// The plugin wants its window shown
+    Show,
+    /// The plugin wants its window hidden
+    Hide,
+    /// The plugin window was closed by the plugin
+    Closed { was_destroyed: bool },
+}
+
+/// Requests queued by the host GUI callbacks, tagged with the host ID of the emitting instance
+///
+/// GUI callbacks run on the main thread (never on the audio thread), so a mutex is fine here.
+static GUI_HOST_REQUESTS: Mutex<Vec<(usize, GuiHostRequest)>> = Mutex::new(Vec::new());
+
+/// Drain the pending plugin GUI requests as `(host_id, request)` pairs
+///
+/// The host ID matches `ClapPluginInstance::host_id()`.
+pub fn take_gui_host_requests() -> Vec<(usize, GuiHostRequest)> {
+    GUI_HOST_REQUESTS
+        .lock()
+        .map(|mut requests| std::mem::take(&mut *requests))
+        .unwrap_or_default()
+}
+
+fn push_gui_host_request(host: *const clap_host, request: GuiHostRequest) {
+    if host.is_null() {
+        return;
+    }
+
+    // SAFETY: the plugin passes back the host pointer we gave it, which outlives the instance
+    let host_id = unsafe { (*host).host_data as usize };
+    if let Ok(mut requests) = GUI_HOST_REQUESTS.lock() {
+        requests.push((host_id, request));
+    }
+}
+
+extern "C" fn host_gui_resize_hints_changed(_host: *const clap_host) {
+    // Hints are re-read on the next user resize
+}
+
+extern "C" fn host_gui_request_resize(host: *const clap_host, width: u32, height: u32) -> bool {
+    push_gui_host_request(host, GuiHostRequest::Resize { width, height });
+    true
+}
+
+extern "C" fn host_gui_request_show(host: *const clap_host) -> bool {
+    push_gui_host_request(host, GuiHostRequest::Show);
+    true
+}
+
+extern "C" fn host_gui_request_hide(host: *const clap_host) -> bool {
+    push_gui_host_request(host, GuiHostRequest::Hide);
+    true
+}
+
+extern "C" fn host_gui_closed(host: *const clap_host, was_destroyed: bool) {
+    push_gui_host_request(host, GuiHostRequest::Closed { was_destroyed });
+}
+
+/// Host side of the `clap.gui` extension, returned by the host `get_extension` callback
+pub(crate) static HOST_GUI_EXTENSION: clap_host_gui = clap_host_gui {
+    resize_hints_changed: host_gui_resize_hints_changed,
+    request_resize: host_gui_request_resize,
+    request_show: host_gui_request_show,
+    request_hide: host_gui_request_hide,
+    closed: host_gui_closed,
+};
 
 /// CLAP plugin GUI wrapper
 pub struct ClapPluginGui {
@@ -265,6 +338,30 @@ impl ClapPluginGui {
         }
     }
 
+    /// Clamp a requested size to what the plugin accepts
+    ///
+    /// Returns the requested size unchanged if the plugin has no constraints.
+    pub fn adjust_size(&self, width: u32, height: u32) -> (u32, u32) {
+        if !self.is_created {
+            return (width, height);
+        }
+
+        unsafe {
+            let gui = &*self.gui_ext;
+            let (mut w, mut h) = (width, height);
+            match gui.adjust_size {
+                Some(adjust_size) if adjust_size(self.plugin_ptr, &mut w, &mut h) => (w, h),
+                _ => (width, height),
+            }
+        }
+    }
+
+    /// Record a size the plugin chose itself (after a host resize request)
+    pub fn update_size(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+    }
+
     /// Check if GUI can be resized
     pub fn can_resize(&self) -> bool {
         if !self.is_created {
@@ -291,26 +388,81 @@ impl ClapPluginGui {
     pub fn api(&self) -> &str {
         &self.api
     }
-}
 
-impl Drop for ClapPluginGui {
-    fn drop(&mut self) {
+    /// Destroy the GUI (it can be created again later, e.g. for a new window)
+    pub fn destroy(&mut self) {
         if self.is_created {
             unsafe {
                 let gui = &*self.gui_ext;
                 (gui.destroy)(self.plugin_ptr);
             }
             self.is_created = false;
+            self.is_visible = false;
         }
     }
 }
 
+impl Drop for ClapPluginGui {
+    fn drop(&mut self) {
+        self.destroy();
+    }
+}
+
 // Safety: GUI operations should be done on main thread only
 // The struct itself can be Send but operations must be serialized
 unsafe impl Send for ClapPluginGui {}
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_gui_requests_are_tagged_with_host_id() {
+        let host = clap_host {
+            clap_version: clap_version::CLAP_1_0_0,
+            host_data: 4242 as *mut std::ffi::c_void,
+            name: std::ptr::null(),
+            vendor: std::ptr::null(),
+            url: std::ptr::null(),
+            version: std::ptr::null(),
+            get_extension: test_get_extension,
+            request_callback: test_host_callback,
+            request_restart: test_host_callback,
+            request_process: test_host_callback,
+        };
+
+        assert!((HOST_GUI_EXTENSION.request_resize)(&host, 640, 480));
+        (HOST_GUI_EXTENSION.closed)(&host, true);
+
+        // Other tests may push requests concurrently, only look at ours
+        let ours: Vec<GuiHostRequest> = take_gui_host_requests()
+            .into_iter()
+            .filter(|(id, _)| *id == 4242)
+            .map(|(_, request)| request)
+            .collect();
+        assert_eq!(
+            ours,
+            vec![
+                GuiHostRequest::Resize {
+                    width: 640,
+                    height: 480
+                },
+                GuiHostRequest::Closed {
+                    was_destroyed: true
+                },
+            ]
+        );
+    }
+
+    extern "C" fn test_get_extension(
+        _host: *const clap_host,
+        _extension_id: *const std::os::raw::c_char,
+    ) -> *const std::ffi::c_void {
+        std::ptr::null()
+    }
+
+    extern "C" fn test_host_callback(_host: *const clap_host) {}
+
     #[test]
     fn test_platform_api_selection() {
         // Platform API selection is tested via integration tests
@@ -6,7 +6,7 @@
 use crate::midi::event::MidiEvent;
 use crate::plugin::buffer_pool::AudioBufferPool;
 use crate::plugin::clap_ffi::*;
-use crate::plugin::clap_gui::ClapPluginGui;
+use crate::plugin::clap_gui::{ClapPluginGui, HOST_GUI_EXTENSION};
 use crate::plugin::parameters::*;
 use crate::MidiEventTimed;
 use crate::plugin::trait_def::*;
@@ -14,9 +14,11 @@ use crate::plugin::{PluginError, PluginResult};
 use libloading::{Library, Symbol};
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
 use std::path::Path;
 use std::ptr;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// CLAP event wrapper (union-like)
 enum ClapEvent {
@@ -334,8 +336,11 @@ fn infer_category_from_features(features: &[String]) -> PluginCategory {
     PluginCategory::Effect // Default
 }
 
+/// Counter used to give each plugin instance's host a unique ID (stored in `host_data`)
+static NEXT_HOST_ID: AtomicUsize = AtomicUsize::new(1);
+
 /// Create a minimal CLAP host for plugins
-fn create_minimal_host() -> clap_host {
+fn create_minimal_host(host_id: usize) -> clap_host {
     static HOST_NAME: &[u8] = b"MyMusic DAW\0";
     static HOST_VENDOR: &[u8] = b"MyMusic\0";
     static HOST_URL: &[u8] = b"https://github.com/antikkorps/mymusic_daw\0";
@@ -343,7 +348,7 @@ fn create_minimal_host() -> clap_host {
 
     clap_host {
         clap_version: clap_version::CLAP_1_0_0,
-        host_data: ptr::null_mut(),
+        host_data: host_id as *mut std::os::raw::c_void,
         name: HOST_NAME.as_ptr() as *const i8,
         vendor: HOST_VENDOR.as_ptr() as *const i8,
         url: HOST_URL.as_ptr() as *const i8,
@@ -355,12 +360,22 @@ fn create_minimal_host() -> clap_host {
     }
 }
 
-/// Host callback: get extension (stub)
+/// Host callback: get extension (only `clap.gui` is provided)
 extern "C" fn host_get_extension(
     _host: *const clap_host,
-    _extension_id: *const std::os::raw::c_char,
+    extension_id: *const std::os::raw::c_char,
 ) -> *const std::os::raw::c_void {
-    ptr::null()
+    if extension_id.is_null() {
+        return ptr::null();
+    }
+
+    // SAFETY: the plugin passes a valid NUL-terminated extension ID
+    let extension_id = unsafe { CStr::from_ptr(extension_id) };
+    if extension_id.to_bytes_with_nul() == CLAP_EXT_GUI {
+        &HOST_GUI_EXTENSION as *const clap_host_gui as *const std::os::raw::c_void
+    } else {
+        ptr::null()
+    }
 }
 
 /// Host callback: request callback (stub)
@@ -384,8 +399,10 @@ impl PluginFactory for ClapPluginFactory {
     }
 
     fn create_instance(&self) -> Result<Box<dyn Plugin>, PluginError> {
-        // Create a minimal CLAP host
-        let host = create_minimal_host();
+        // Create a minimal CLAP host (boxed: the plugin keeps a pointer to it)
+        let host = Box::new(create_minimal_host(
+            NEXT_HOST_ID.fetch_add(1, Ordering::Relaxed),
+        ));
 
         // Get the plugin factory
         let factory = unsafe { &*self.plugin_factory };
@@ -397,7 +414,7 @@ impl PluginFactory for ClapPluginFactory {
         // Create plugin instance via CLAP factory
         let plugin_ptr = (factory.create_plugin)(
             self.plugin_factory,
-            &host as *const clap_host,
+            &*host as *const clap_host,
             plugin_id.as_ptr(),
         );
 
@@ -439,7 +456,9 @@ pub struct ClapPluginInstance {
     parameter_id_map: HashMap<String, u32>, // String ID -> CLAP param ID
     is_active: bool,
     plugin_ptr: *mut clap_plugin,
-    host: clap_host,
+    params: *const clap_plugin_params, // clap.params extension (null until init() or if unsupported)
+    state_ext: *const clap_plugin_state, // clap.state extension (null until init() or if unsupported)
+    host: Box<clap_host>,
     #[allow(dead_code)]
     library: Arc<Library>, // Keep library alive
     sample_rate: f64,
@@ -461,7 +480,7 @@ impl ClapPluginInstance {
     pub unsafe fn new(
         descriptor: PluginDescriptor,
         plugin_ptr: *mut clap_plugin,
-        host: clap_host,
+        host: Box<clap_host>,
         library: Arc<Library>,
     ) -> Self {
         let mut parameter_values = HashMap::new();
@@ -477,8 +496,8 @@ impl ClapPluginInstance {
         // NOTE: GUI creation is deferred until after plugin.init() is called
         // This is required by CLAP specification: init() must be called before get_extension()
 
-        // Create buffer pool (1 input, 2 output stereo, max 8192 samples)
-        let buffer_pool = AudioBufferPool::new(1, 2, 8192);
+        // Create buffer pool (main + sidechain inputs, 2 output stereo, max 8192 samples)
+        let buffer_pool = AudioBufferPool::new(2, 2, 8192);
 
         Self {
             descriptor,
@@ -486,6 +505,8 @@ impl ClapPluginInstance {
             parameter_id_map,
             is_active: false,
             plugin_ptr,
+            params: ptr::null(),
+            state_ext: ptr::null(),
             host,
             library,
             sample_rate: 44100.0, // Default, will be set in initialize()
@@ -520,6 +541,255 @@ impl ClapPluginInstance {
     pub fn gui(&self) -> Option<&ClapPluginGui> {
         self.gui.as_ref()
     }
+
+    /// Unique ID of this instance's host, used to route plugin GUI requests
+    /// (see `take_gui_host_requests`)
+    pub fn host_id(&self) -> usize {
+        self.host.host_data as usize
+    }
+
+    /// Check if the plugin exposes its parameters (`clap.params` extension)
+    pub fn has_params_extension(&self) -> bool {
+        !self.params.is_null()
+    }
+
+    /// Check if the plugin can save and load its state (`clap.state` extension)
+    pub fn has_state_extension(&self) -> bool {
+        !self.state_ext.is_null()
+    }
+
+    /// Get a plugin extension (null if unsupported)
+    ///
+    /// Must be called after `init()` (CLAP forbids `get_extension()` before).
+    fn extension(&self, extension_id: &[u8]) -> *const c_void {
+        // SAFETY: plugin_ptr is valid and init() has been called
+        let plugin = unsafe { &*self.plugin_ptr };
+        (plugin.get_extension)(self.plugin_ptr, extension_id.as_ptr() as *const c_char)
+    }
+
+    /// Read the parameter list from the `clap.params` extension
+    ///
+    /// Parameters are identified by their CLAP id as a decimal string; hidden
+    /// ones are skipped. Replaces the parameters of the descriptor.
+    fn discover_parameters(&mut self) {
+        self.params = self.extension(CLAP_EXT_PARAMS) as *const clap_plugin_params;
+        // SAFETY: the extension lives as long as the plugin instance
+        let Some(params) = (unsafe { self.params.as_ref() }) else {
+            return;
+        };
+
+        let count = (params.count)(self.plugin_ptr);
+        let mut parameters = Vec::with_capacity(count as usize);
+        self.parameter_id_map.clear();
+        for index in 0..count {
+            // SAFETY: clap_param_info is plain data, zeroed is a valid value
+            let mut info: clap_param_info = unsafe { std::mem::zeroed() };
+            if !(params.get_info)(self.plugin_ptr, index, &mut info)
+                || info.flags & CLAP_PARAM_IS_HIDDEN != 0
+            {
+                continue;
+            }
+            let mut value = info.default_value;
+            (params.get_value)(self.plugin_ptr, info.id, &mut value);
+            let parameter = parameter_from_info(&info, value);
+            self.parameter_id_map.insert(parameter.id.clone(), info.id);
+            parameters.push(parameter);
+        }
+
+        self.parameter_values = parameters
+            .iter()
+            .map(|parameter| (parameter.id.clone(), parameter.value))
+            .collect();
+        println!(
+            "🎛️ {} exposes {} parameters",
+            self.descriptor.name,
+            parameters.len()
+        );
+        self.descriptor.parameters = parameters;
+    }
+
+    /// Current value of a parameter as reported by the plugin (main thread)
+    ///
+    /// Differs from `get_parameter()` when the plugin changed it (e.g. from
+    /// its own GUI) or a queued change was not processed yet.
+    pub fn plugin_parameter_value(&self, parameter_id: &str) -> Option<f64> {
+        // SAFETY: the extension lives as long as the plugin instance
+        let params = unsafe { self.params.as_ref() }?;
+        let clap_id = *self.parameter_id_map.get(parameter_id)?;
+        let mut value = 0.0;
+        (params.get_value)(self.plugin_ptr, clap_id, &mut value).then_some(value)
+    }
+
+    /// Text the plugin displays for a parameter value (e.g. "440 Hz", "Saw")
+    pub fn parameter_value_to_text(&self, parameter_id: &str, value: f64) -> Option<String> {
+        // SAFETY: the extension lives as long as the plugin instance
+        let params = unsafe { self.params.as_ref() }?;
+        let clap_id = *self.parameter_id_map.get(parameter_id)?;
+        let mut display = [0u8; CLAP_PARAM_TEXT_SIZE];
+        let converted = (params.value_to_text)(
+            self.plugin_ptr,
+            clap_id,
+            value,
+            display.as_mut_ptr(),
+            display.len() as u32,
+        );
+        converted.then(|| fixed_c_str_to_string(&display))
+    }
+
+    /// Read every parameter value back from the plugin (main thread)
+    ///
+    /// Parameters with a change waiting for the next `process()` keep the
+    /// queued value.
+    pub fn refresh_parameter_values(&mut self) {
+        let ids: Vec<String> = self.parameter_values.keys().cloned().collect();
+        for id in ids {
+            let pending = self.parameter_id_map.get(&id).is_some_and(|clap_id| {
+                self.pending_param_changes
+                    .iter()
+                    .any(|(pending_id, _)| pending_id == clap_id)
+            });
+            if !pending && let Some(value) = self.plugin_parameter_value(&id) {
+                self.parameter_values.insert(id, value);
+            }
+        }
+    }
+
+    /// Save the plugin state blob (None if the plugin has no state extension)
+    fn save_clap_state(&self) -> Result<Option<Vec<u8>>, PluginError> {
+        // SAFETY: the extension lives as long as the plugin instance
+        let Some(state_ext) = (unsafe { self.state_ext.as_ref() }) else {
+            return Ok(None);
+        };
+
+        let mut data: Vec<u8> = Vec::new();
+        let stream = clap_ostream {
+            ctx: &mut data as *mut Vec<u8> as *mut c_void,
+            write: state_stream_write,
+        };
+        if !(state_ext.save)(self.plugin_ptr, &stream) {
+            return Err(PluginError::StateFailed(format!(
+                "{} could not save its state",
+                self.descriptor.name
+            )));
+        }
+        Ok(Some(data))
+    }
+
+    /// Load a plugin state blob (saved by `save_clap_state`)
+    fn load_clap_state(&mut self, data: &[u8]) -> Result<(), PluginError> {
+        // SAFETY: the extension lives as long as the plugin instance
+        let Some(state_ext) = (unsafe { self.state_ext.as_ref() }) else {
+            return Err(PluginError::StateFailed(format!(
+                "{} cannot load a saved state",
+                self.descriptor.name
+            )));
+        };
+
+        let mut reader = StateReader { data, position: 0 };
+        let stream = clap_istream {
+            ctx: &mut reader as *mut StateReader as *mut c_void,
+            read: state_stream_read,
+        };
+        if !(state_ext.load)(self.plugin_ptr, &stream) {
+            return Err(PluginError::StateFailed(format!(
+                "{} rejected the saved state",
+                self.descriptor.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Send the queued parameter changes to the plugin outside of `process()`
+    ///
+    /// For instances the audio thread does not process (e.g. the Tauri plugin
+    /// hosts): the call must never overlap a `process()` call.
+    pub fn flush_parameters(&mut self) {
+        // SAFETY: the extension lives as long as the plugin instance
+        let Some(params) = (unsafe { self.params.as_ref() }) else {
+            return;
+        };
+        if self.pending_param_changes.is_empty() {
+            return;
+        }
+
+        let mut event_list = ClapEventList::new();
+        for (param_id, value) in self.pending_param_changes.drain(..) {
+            event_list.add_param_value(param_id, value, 0);
+        }
+        let input_events = event_list.as_clap_input_events();
+        let output_events = clap_output_events {
+            ctx: ptr::null_mut(),
+            try_push: clap_output_events_try_push,
+        };
+        (params.flush)(self.plugin_ptr, &input_events, &output_events);
+    }
+}
+
+/// State blob read by a plugin (`clap_istream::ctx`)
+struct StateReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+/// Callback: the plugin writes its state (ctx is a `Vec<u8>`)
+extern "C" fn state_stream_write(
+    stream: *const clap_ostream,
+    buffer: *const c_void,
+    size: u64,
+) -> i64 {
+    if stream.is_null() || (buffer.is_null() && size > 0) {
+        return -1;
+    }
+    // SAFETY: ctx points to the Vec of save_clap_state(), buffer holds size bytes
+    unsafe {
+        let data = &mut *((*stream).ctx as *mut Vec<u8>);
+        data.extend_from_slice(std::slice::from_raw_parts(
+            buffer as *const u8,
+            size as usize,
+        ));
+    }
+    size as i64
+}
+
+/// Callback: the plugin reads its state (ctx is a `StateReader`)
+extern "C" fn state_stream_read(
+    stream: *const clap_istream,
+    buffer: *mut c_void,
+    size: u64,
+) -> i64 {
+    if stream.is_null() || (buffer.is_null() && size > 0) {
+        return -1;
+    }
+    // SAFETY: ctx points to the StateReader of load_clap_state(), buffer has room for size bytes
+    unsafe {
+        let reader = &mut *((*stream).ctx as *mut StateReader);
+        let remaining = &reader.data[reader.position..];
+        let count = remaining.len().min(size as usize);
+        ptr::copy_nonoverlapping(remaining.as_ptr(), buffer as *mut u8, count);
+        reader.position += count;
+        count as i64
+    }
+}
+
+/// Convert CLAP parameter info to a plugin parameter
+///
+/// Stepped and enum parameters become `ParameterType::Enum` (never smoothed).
+fn parameter_from_info(info: &clap_param_info, value: f64) -> PluginParameter {
+    let stepped = info.flags & (CLAP_PARAM_IS_STEPPED | CLAP_PARAM_IS_ENUM) != 0;
+    PluginParameter {
+        id: info.id.to_string(),
+        name: fixed_c_str_to_string(&info.name),
+        value,
+        default_value: info.default_value,
+        min_value: info.min_value,
+        max_value: info.max_value,
+        is_automatable: info.flags & CLAP_PARAM_IS_AUTOMATABLE != 0,
+        parameter_type: if stepped {
+            ParameterType::Enum
+        } else {
+            ParameterType::Linear
+        },
+    }
 }
 
 impl Drop for ClapPluginInstance {
@@ -589,6 +859,8 @@ impl Plugin for ClapPluginInstance {
             match receiver.recv() {
                 Ok(Ok(Ok(true))) => {
                     println!("✅ Plugin init() succeeded");
+                    self.discover_parameters();
+                    self.state_ext = self.extension(CLAP_EXT_STATE) as *const clap_plugin_state;
                 }
                 Ok(Ok(Ok(false))) => {
                     return Err(PluginError::InitializationFailed(
@@ -710,14 +982,26 @@ impl Plugin for ClapPluginInstance {
         unsafe {
             let plugin = &*self.plugin_ptr;
 
-            // Copy input data into pool first (if available)
-            if let Some((_, input_buffer)) = inputs.iter().next() {
+            // Copy input data into pool first (if available): the main input,
+            // then the sidechain key routed to the plugins (second input port)
+            let main_input = inputs
+                .get("input_left")
+                .or_else(|| inputs.values().next());
+            if let Some(input_buffer) = main_input {
                 let input_data = input_buffer.data();
                 let pool_input = self.buffer_pool.input_buffer_mut(0, sample_frames);
                 for (i, sample) in input_data.iter().take(sample_frames).enumerate() {
                     pool_input[i] = *sample;
                 }
             }
+            let sidechain = inputs.get("sidechain_left");
+            if let Some(key_buffer) = sidechain {
+                let key_data = key_buffer.data();
+                let pool_key = self.buffer_pool.input_buffer_mut(1, sample_frames);
+                for (i, sample) in key_data.iter().take(sample_frames).enumerate() {
+                    pool_key[i] = *sample;
+                }
+            }
 
             // Prepare buffer pool (zero allocations - reuses pre-allocated buffers)
             let (input_ptrs, output_ptrs) = self.buffer_pool.prepare(sample_frames);
@@ -726,16 +1010,21 @@ impl Plugin for ClapPluginInstance {
             let input_ptrs_vec: Vec<*mut f32> = input_ptrs.to_vec();
             let output_ptrs_vec: Vec<*mut f32> = output_ptrs.to_vec();
 
-            let clap_input_buffer = clap_audio_buffer {
-                channel_count: if input_ptrs_vec.is_empty() { 0 } else { 1 },
+            // One mono buffer per input port
+            let input_port = |channel: usize| clap_audio_buffer {
+                channel_count: 1,
                 latency: 0,
-                data32: if input_ptrs_vec.is_empty() {
-                    ptr::null_mut()
-                } else {
-                    input_ptrs_vec.as_ptr() as *mut *mut f32
-                },
+                data32: input_ptrs_vec[channel..].as_ptr() as *mut *mut f32,
                 data64: ptr::null_mut(),
             };
+            let clap_input_buffers = [input_port(0), input_port(1)];
+            let input_port_count = if main_input.is_none() {
+                0
+            } else if sidechain.is_some() {
+                2
+            } else {
+                1
+            };
 
             let mut clap_output_buffer = clap_audio_buffer {
                 channel_count: 2,
@@ -779,8 +1068,8 @@ impl Plugin for ClapPluginInstance {
                 steady_time: 0,
                 frames_count: sample_frames as u32,
                 transport: ptr::null(),
-                audio_inputs: &clap_input_buffer,
-                audio_inputs_count: if input_ptrs.is_empty() { 0 } else { 1 },
+                audio_inputs: clap_input_buffers.as_ptr(),
+                audio_inputs_count: input_port_count,
                 audio_outputs: &mut clap_output_buffer,
                 audio_outputs_count: 1,
                 in_events: &input_events,
@@ -864,23 +1153,30 @@ impl Plugin for ClapPluginInstance {
             state = state.with_parameter(id.clone(), *value);
         }
 
-        // TODO: Save actual CLAP state if available
+        // Save the plugin's own state (presets, samples, non-parameter settings)
+        if let Some(data) = self.save_clap_state()? {
+            state = state.with_data(data);
+        }
 
         Ok(state)
     }
 
     fn load_state(&mut self, state: &PluginState) -> Result<(), PluginError> {
-        // Load parameter values
+        // The state blob restores everything: read the parameters back from it
+        if let Some(data) = &state.data {
+            self.load_clap_state(data)?;
+            self.pending_param_changes.clear();
+            self.refresh_parameter_values();
+            return Ok(());
+        }
+
+        // Without a blob, send the saved parameter values to the plugin
         for (id, value) in &state.parameters {
             if self.descriptor.find_parameter(id).is_some() {
-                self.parameter_values.insert(id.clone(), *value);
-
-                // TODO: Set parameter in actual CLAP plugin
+                self.set_parameter(id, *value)?;
             }
         }
 
-        // TODO: Load actual CLAP state if available
-
         Ok(())
     }
 
@@ -925,7 +1221,8 @@ impl Plugin for ClapPluginInstance {
         // Add MIDI event to pending queue for processing in next audio callback
         self.pending_midi_events.push((midi_event.event, midi_event.samples_from_now));
         
-        println!("🎹 MIDI queued for plugin {}: {:?} (offset: {} samples)", 
+        // Called from the audio thread: no println!
+        crate::rt_debug!("plugin", "MIDI queued for plugin {}: {:?} (offset: {} samples)",
                  self.descriptor.name, midi_event.event, midi_event.samples_from_now);
         
         Ok(())
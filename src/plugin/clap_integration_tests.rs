@@ -124,6 +124,71 @@ mod tests {
         assert!(descriptor.supports_gui); // Has GUI support
     }
 
+    #[test]
+    fn test_parameter_from_clap_info() {
+        let mut name = [0u8; 256];
+        name[..6].copy_from_slice(b"Cutoff");
+        let mut info = crate::plugin::clap_ffi::clap_param_info {
+            id: 42,
+            flags: crate::plugin::clap_ffi::CLAP_PARAM_IS_AUTOMATABLE,
+            cookie: std::ptr::null_mut(),
+            name,
+            module: [0u8; 1024],
+            min_value: 20.0,
+            max_value: 20000.0,
+            default_value: 1000.0,
+        };
+
+        let param = super::parameter_from_info(&info, 440.0);
+        assert_eq!(param.id, "42");
+        assert_eq!(param.name, "Cutoff");
+        assert_eq!(param.value, 440.0);
+        assert_eq!(param.default_value, 1000.0);
+        assert!(param.is_automatable);
+        assert!(matches!(param.parameter_type, ParameterType::Linear));
+
+        // Stepped parameters are never smoothed
+        info.flags = crate::plugin::clap_ffi::CLAP_PARAM_IS_STEPPED;
+        let param = super::parameter_from_info(&info, 2.0);
+        assert!(!param.is_automatable);
+        assert!(matches!(param.parameter_type, ParameterType::Enum));
+    }
+
+    #[test]
+    fn test_state_streams() {
+        use crate::plugin::clap_ffi::{clap_istream, clap_ostream};
+        use std::ffi::c_void;
+
+        // The plugin writes its state in several chunks
+        let mut data: Vec<u8> = Vec::new();
+        let output = clap_ostream {
+            ctx: &mut data as *mut Vec<u8> as *mut c_void,
+            write: super::state_stream_write,
+        };
+        for chunk in [&b"abc"[..], &b"defg"[..]] {
+            let written = (output.write)(&output, chunk.as_ptr() as *const c_void, chunk.len() as u64);
+            assert_eq!(written, chunk.len() as i64);
+        }
+        assert_eq!(data, b"abcdefg");
+
+        // And reads it back with a smaller buffer, until the end of the stream
+        let mut reader = super::StateReader { data: &data, position: 0 };
+        let input = clap_istream {
+            ctx: &mut reader as *mut super::StateReader as *mut c_void,
+            read: super::state_stream_read,
+        };
+        let mut buffer = [0u8; 4];
+        let mut read_back = Vec::new();
+        loop {
+            let count = (input.read)(&input, buffer.as_mut_ptr() as *mut c_void, buffer.len() as u64);
+            if count == 0 {
+                break;
+            }
+            read_back.extend_from_slice(&buffer[..count as usize]);
+        }
+        assert_eq!(read_back, data);
+    }
+
     #[test]
     fn test_error_types() {
         let load_error = crate::plugin::PluginError::LoadFailed("Test error".to_string());
@@ -1,3 +1,4 @@
+use crate::audio::smoothing::{ParamKind, SmoothedParam};
 use crate::plugin::parameters::*;
 use crate::plugin::scanner::PluginScanner;
 use crate::plugin::trait_def::*;
@@ -58,6 +59,8 @@ pub struct PluginInstanceWrapper {
     pub buffer_size: usize,
     /// Store whether this is a CLAP plugin for GUI access
     is_clap_plugin: bool,
+    /// Continuous parameters gliding towards a new value (applied per buffer)
+    parameter_smoothers: HashMap<String, SmoothedParam>,
 }
 
 impl PluginInstanceWrapper {
@@ -114,6 +117,7 @@ impl Clone for PluginInstanceWrapper {
             sample_rate: self.sample_rate,
             buffer_size: self.buffer_size,
             is_clap_plugin: self.is_clap_plugin,
+            parameter_smoothers: self.parameter_smoothers.clone(),
         }
     }
 }
@@ -254,6 +258,7 @@ impl PluginHost {
             sample_rate: 44100.0,
             buffer_size: 512,
             is_clap_plugin,
+            parameter_smoothers: HashMap::new(),
         };
 
         {
@@ -276,13 +281,12 @@ impl PluginHost {
     }
 
     /// Get a plugin instance wrapper for GUI operations (with scoped access)
-    pub fn with_instance_wrapper<F, R>(&self, _instance_id: PluginInstanceId, f: F) -> Option<R>
+    pub fn with_instance_wrapper<F, R>(&self, instance_id: PluginInstanceId, f: F) -> Option<R>
     where
         F: FnOnce(&PluginInstanceWrapper) -> R,
     {
-        let _instances = self.instances.lock().unwrap();
-        // TODO: Implement actual instance lookup
-        None // Placeholder
+        let instances = self.instances.lock().unwrap();
+        instances.get(&instance_id).map(f)
     }
 
     /// Get a mutable plugin instance wrapper for GUI operations (with scoped access)
@@ -316,18 +320,31 @@ impl PluginHost {
         for (_, instance_wrapper) in instances.iter_mut() {
             // Send MIDI event to plugin instance
             if let Err(e) = instance_wrapper.plugin.process_midi(midi_event) {
-                eprintln!("MIDI processing error for instance: {:?}", e);
+                crate::rt_error!("plugin", "MIDI processing error for instance: {:?}", e);
             }
         }
     }
 
+    /// Process a MIDI event for one plugin instance (e.g. an instrument track)
+    pub fn process_midi_for_instance(&self, instance_id: PluginInstanceId, midi_event: &MidiEventTimed) {
+        let mut instances = self.instances.lock().unwrap();
+        if let Some(instance_wrapper) = instances.get_mut(&instance_id)
+            && let Err(e) = instance_wrapper.plugin.process_midi(midi_event)
+        {
+            crate::rt_error!("plugin", "MIDI processing error for instance: {:?}", e);
+        }
+    }
+
     /// Destroy a plugin instance
     pub fn destroy_instance(&self, instance_id: PluginInstanceId) -> PluginResult<()> {
-        let mut instances = self.instances.lock().unwrap();
-        instances.remove(&instance_id).ok_or_else(|| {
+        let removed = self.instances.lock().unwrap().remove(&instance_id);
+        let instance = removed.ok_or_else(|| {
             PluginError::InitializationFailed(format!("Instance not found: {:?}", instance_id))
         })?;
 
+        // Free the instance after releasing the lock, so the audio thread
+        // (`process_all_instances`) never waits on a plugin destructor
+        drop(instance);
         Ok(())
     }
 
@@ -347,6 +364,13 @@ impl PluginHost {
 
         for wrapper in instances.values_mut() {
             if wrapper.is_active {
+                // Move smoothed parameters one buffer closer to their targets
+                for (parameter_id, smoother) in wrapper.parameter_smoothers.iter_mut() {
+                    if !smoother.is_settled() {
+                        let value = smoother.advance(sample_frames);
+                        wrapper.plugin.set_parameter(parameter_id, value as f64)?;
+                    }
+                }
                 wrapper.plugin.process(inputs, outputs, sample_frames)?;
             }
         }
@@ -354,6 +378,164 @@ impl PluginHost {
         Ok(())
     }
 
+    /// Set a plugin parameter
+    ///
+    /// Continuous parameters glide to `value` over the next buffers (see
+    /// `ParamKind::PluginParam`) to avoid zipper noise; enum parameters, and
+    /// parameters of inactive instances, are applied immediately.
+    pub fn set_parameter(
+        &self,
+        instance_id: PluginInstanceId,
+        parameter_id: &str,
+        value: f64,
+    ) -> PluginResult<()> {
+        let mut instances = self.instances.lock().unwrap();
+        let wrapper = instances.get_mut(&instance_id).ok_or_else(|| {
+            PluginError::InitializationFailed(format!("Instance not found: {:?}", instance_id))
+        })?;
+
+        let parameter = wrapper
+            .plugin
+            .descriptor()
+            .find_parameter(parameter_id)
+            .ok_or_else(|| {
+                PluginError::InvalidParameter(format!("Parameter not found: {}", parameter_id))
+            })?;
+        let value = value.clamp(parameter.min_value, parameter.max_value);
+        let continuous = !matches!(parameter.parameter_type, ParameterType::Enum);
+        let default_value = parameter.default_value;
+
+        if !continuous || !wrapper.is_active {
+            wrapper.parameter_smoothers.remove(parameter_id);
+            return wrapper.plugin.set_parameter(parameter_id, value);
+        }
+
+        let current = wrapper
+            .plugin
+            .get_parameter(parameter_id)
+            .unwrap_or(default_value);
+        let sample_rate = wrapper.sample_rate as f32;
+        wrapper
+            .parameter_smoothers
+            .entry(parameter_id.to_string())
+            .or_insert_with(|| {
+                SmoothedParam::new(ParamKind::PluginParam, current as f32, sample_rate)
+            })
+            .set_target(value as f32);
+        Ok(())
+    }
+
+    /// Set a parameter of an instance the audio thread does not process
+    ///
+    /// The value is not smoothed and is sent to CLAP plugins right away
+    /// (`ClapPluginInstance::flush_parameters`).
+    pub fn set_parameter_immediate(
+        &self,
+        instance_id: PluginInstanceId,
+        parameter_id: &str,
+        value: f64,
+    ) -> PluginResult<()> {
+        let mut instances = self.instances.lock().unwrap();
+        let wrapper = instances.get_mut(&instance_id).ok_or_else(|| {
+            PluginError::InitializationFailed(format!("Instance not found: {:?}", instance_id))
+        })?;
+
+        wrapper.parameter_smoothers.remove(parameter_id);
+        wrapper.plugin.set_parameter(parameter_id, value)?;
+        if let Some(clap_plugin) = wrapper.as_clap_plugin_mut() {
+            clap_plugin.flush_parameters();
+        }
+        Ok(())
+    }
+
+    /// Parameters of an instance with their current values
+    ///
+    /// CLAP plugins are asked for their values first, so edits made in the
+    /// plugin GUI show up.
+    pub fn get_parameters(
+        &self,
+        instance_id: PluginInstanceId,
+    ) -> PluginResult<Vec<PluginParameter>> {
+        let mut instances = self.instances.lock().unwrap();
+        let wrapper = instances.get_mut(&instance_id).ok_or_else(|| {
+            PluginError::InitializationFailed(format!("Instance not found: {:?}", instance_id))
+        })?;
+
+        if let Some(clap_plugin) = wrapper.as_clap_plugin_mut() {
+            clap_plugin.refresh_parameter_values();
+        }
+        let plugin = wrapper.plugin();
+        Ok(plugin
+            .descriptor()
+            .parameters
+            .iter()
+            .map(|parameter| PluginParameter {
+                value: plugin
+                    .get_parameter(&parameter.id)
+                    .unwrap_or(parameter.value),
+                ..parameter.clone()
+            })
+            .collect())
+    }
+
+    /// Current value of a parameter (read back from CLAP plugins, see `get_parameters`)
+    pub fn get_parameter(
+        &self,
+        instance_id: PluginInstanceId,
+        parameter_id: &str,
+    ) -> PluginResult<f64> {
+        let mut instances = self.instances.lock().unwrap();
+        let wrapper = instances.get_mut(&instance_id).ok_or_else(|| {
+            PluginError::InitializationFailed(format!("Instance not found: {:?}", instance_id))
+        })?;
+
+        if let Some(clap_plugin) = wrapper.as_clap_plugin_mut() {
+            clap_plugin.refresh_parameter_values();
+        }
+        wrapper.plugin.get_parameter(parameter_id).ok_or_else(|| {
+            PluginError::InvalidParameter(format!("Parameter not found: {}", parameter_id))
+        })
+    }
+
+    /// Text the plugin displays for a parameter value (None if it has none)
+    pub fn parameter_value_text(
+        &self,
+        instance_id: PluginInstanceId,
+        parameter_id: &str,
+        value: f64,
+    ) -> Option<String> {
+        let instances = self.instances.lock().unwrap();
+        instances
+            .get(&instance_id)?
+            .as_clap_plugin()?
+            .parameter_value_to_text(parameter_id, value)
+    }
+
+    /// Capture the state of an instance (parameter values and the CLAP state blob)
+    pub fn save_instance_state(&self, instance_id: PluginInstanceId) -> PluginResult<PluginState> {
+        let instances = self.instances.lock().unwrap();
+        let wrapper = instances.get(&instance_id).ok_or_else(|| {
+            PluginError::InitializationFailed(format!("Instance not found: {:?}", instance_id))
+        })?;
+        wrapper.plugin.save_state()
+    }
+
+    /// Restore a state saved by `save_instance_state` (the instance must be initialized)
+    pub fn load_instance_state(
+        &self,
+        instance_id: PluginInstanceId,
+        state: &PluginState,
+    ) -> PluginResult<()> {
+        let mut instances = self.instances.lock().unwrap();
+        let wrapper = instances.get_mut(&instance_id).ok_or_else(|| {
+            PluginError::InitializationFailed(format!("Instance not found: {:?}", instance_id))
+        })?;
+
+        // Gliding parameters would overwrite the restored values
+        wrapper.parameter_smoothers.clear();
+        wrapper.plugin.load_state(state)
+    }
+
     /// Initialize a plugin instance
     pub fn initialize_instance(
         &self,
@@ -691,6 +873,18 @@ impl Plugin for ClPluginInstance {
 mod tests {
     use super::*;
 
+    struct TestFactory(PluginDescriptor);
+
+    impl PluginFactory for TestFactory {
+        fn descriptor(&self) -> &PluginDescriptor {
+            &self.0
+        }
+
+        fn create_instance(&self) -> Result<Box<dyn Plugin>, PluginError> {
+            Ok(Box::new(ClPluginInstance::new(self.0.clone())))
+        }
+    }
+
     #[test]
     fn test_plugin_host_creation() {
         let host = PluginHost::new();
@@ -711,6 +905,58 @@ mod tests {
         assert_eq!(available.len(), 0);
     }
 
+    #[test]
+    fn test_continuous_parameters_are_smoothed() {
+        let host = PluginHost::new();
+        let parameter = |id: &str, parameter_type| PluginParameter {
+            id: id.to_string(),
+            name: id.to_string(),
+            value: 0.0,
+            default_value: 0.0,
+            min_value: 0.0,
+            max_value: 1.0,
+            is_automatable: true,
+            parameter_type,
+        };
+        let factory = TestFactory(
+            PluginDescriptor::new("test", "Test", std::path::PathBuf::new())
+                .with_parameter(parameter("gain", ParameterType::Linear))
+                .with_parameter(parameter("mode", ParameterType::Enum)),
+        );
+        let plugin_id = factory.0.id.clone();
+        host.factories
+            .lock()
+            .unwrap()
+            .insert(plugin_id.clone(), Arc::new(factory));
+
+        let instance_id = host.create_instance(&plugin_id, None).unwrap();
+        host.initialize_instance(instance_id, 48000.0, 512).unwrap();
+        let value_of = |id: &str| {
+            host.with_instance_wrapper_mut(instance_id, |w| w.plugin().get_parameter(id))
+                .flatten()
+                .unwrap()
+        };
+
+        // Enum parameters jump, continuous ones glide over the next buffers
+        host.set_parameter(instance_id, "mode", 1.0).unwrap();
+        host.set_parameter(instance_id, "gain", 1.0).unwrap();
+        assert_eq!(value_of("mode"), 1.0);
+        assert_eq!(value_of("gain"), 0.0);
+
+        let inputs = HashMap::new();
+        let mut outputs = HashMap::new();
+        host.process_all_instances(&inputs, &mut outputs, 512).unwrap();
+        let first = value_of("gain");
+        assert!(first > 0.0 && first < 1.0);
+
+        for _ in 0..100 {
+            host.process_all_instances(&inputs, &mut outputs, 512).unwrap();
+        }
+        assert_eq!(value_of("gain"), 1.0);
+
+        assert!(host.set_parameter(instance_id, "missing", 0.5).is_err());
+    }
+
     #[test]
     fn test_statistics() {
         let host = PluginHost::new();
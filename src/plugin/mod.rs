@@ -40,6 +40,9 @@ pub enum PluginError {
     #[error("GUI operation failed: {0}")]
     GuiFailed(String),
 
+    #[error("Plugin state error: {0}")]
+    StateFailed(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -163,6 +163,9 @@ impl PluginDescriptor {
 pub struct PluginState {
     pub parameters: HashMap<String, f64>,
     pub custom_data: HashMap<String, String>,
+    /// Opaque state blob written by the plugin (CLAP `state` extension)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Vec<u8>>,
 }
 
 impl Default for PluginState {
@@ -176,6 +179,7 @@ impl PluginState {
         Self {
             parameters: HashMap::new(),
             custom_data: HashMap::new(),
+            data: None,
         }
     }
 
@@ -188,6 +192,11 @@ impl PluginState {
         self.custom_data.insert(key.into(), value.into());
         self
     }
+
+    pub fn with_data(mut self, data: Vec<u8>) -> Self {
+        self.data = Some(data);
+        self
+    }
 }
 
 /// Unique identifier for a plugin instance
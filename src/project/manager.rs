@@ -1,8 +1,11 @@
 // Project manager for loading and saving projects
 
-use crate::project::migration::{MigrationResult, ProjectMigrator};
+use crate::audio::outputs::TrackOutput;
+use crate::midi::smf::Smf;
+use crate::project::migration::{MigrationResult, ProjectCheck, ProjectMigrator};
 use crate::project::serialization::*;
 use crate::project::types::*;
+use crate::sequencer::pattern::PatternId;
 use std::fs::File;
 use std::path::Path;
 use zip::{ZipArchive, ZipWriter};
@@ -68,6 +71,14 @@ impl Default for ProjectLoadOptions {
     }
 }
 
+/// Options for saving a project
+#[derive(Debug, Clone, Default)]
+pub struct ProjectSaveOptions {
+    /// Copy every referenced audio file (sample bank, audio clips) into the
+    /// archive ("collect and save"), so the project opens on another machine
+    pub collect_audio: bool,
+}
+
 /// Project manager - handles saving/loading projects
 pub struct ProjectManager {
     /// Default sample rate for projects
@@ -98,6 +109,7 @@ impl ProjectManager {
             name: "Default Pattern".to_string(),
             length_bars: 4,
             notes: Vec::new(),
+            groove: Default::default(),
         };
         project.patterns.insert(default_pattern_id, default_pattern);
 
@@ -113,6 +125,13 @@ impl ProjectManager {
                 muted: false,
                 soloed: false,
                 track_type: TrackType::Synth,
+                clips: Vec::new(),
+                dynamics: None,
+                sends: Vec::new(),
+                output: TrackOutput::Master,
+                surround: None,
+                inserts: None,
+                patch: None,
             },
         );
 
@@ -124,6 +143,18 @@ impl ProjectManager {
         &self,
         project: &Project,
         project_path: P,
+    ) -> Result<(), ProjectError> {
+        self.save_project_with_options(project, project_path, &ProjectSaveOptions::default())
+    }
+
+    /// Save project to ZIP file, optionally embedding its audio files
+    ///
+    /// Audio files that came from an archive are embedded again in any case.
+    pub fn save_project_with_options<P: AsRef<Path>>(
+        &self,
+        project: &Project,
+        project_path: P,
+        options: &ProjectSaveOptions,
     ) -> Result<(), ProjectError> {
         let project_path = project_path.as_ref();
         let project_dir = project_path
@@ -144,9 +175,17 @@ impl ProjectManager {
             ProjectError::FileSystemError(format!("Failed to create temp directory: {}", e))
         })?;
 
+        // Embed audio files, pointing the saved project to the copies
+        let mut project = project.clone();
+        embed_audio_files(&mut project, project_dir, &temp_dir, options.collect_audio)?;
+        let project = &project;
+
         // Export samples to temp directory
         let _exported_samples = export_samples_to_directory(project, project_dir, &temp_dir)?;
 
+        // Export plugin state blobs to temp directory
+        export_plugin_states_to_directory(project, &temp_dir)?;
+
         // Create project files in temp directory
         let manifest_path = temp_dir.join("manifest.json");
         let project_path_ron = temp_dir.join("project.ron");
@@ -242,7 +281,10 @@ impl ProjectManager {
         let project_ron = std::fs::read_to_string(&project_ron_path)
             .map_err(|e| ProjectError::FileSystemError(format!("Failed to read project: {}", e)))?;
 
-        let project = deserialize_from_ron(&project_ron)?;
+        let mut project = deserialize_from_ron(&project_ron)?;
+
+        // The manifest holds the metadata (and the format version migrated from)
+        project.metadata = metadata;
 
         // Check version compatibility and migrate if needed
         let project_version = project.metadata.version.clone();
@@ -277,8 +319,13 @@ impl ProjectManager {
             }
         }
 
-        // Update project metadata (keep loaded metadata)
-        project.metadata = metadata;
+        // Plugin state blobs are stored next to project.ron
+        import_plugin_states_from_directory(&mut project, &temp_dir)?;
+
+        // Embedded audio files outlive the extraction directory
+        if options.load_samples {
+            extract_embedded_audio(&mut project, &temp_dir)?;
+        }
 
         // Apply sample rate override if specified
         if let Some(override_rate) = options.sample_rate_override {
@@ -299,6 +346,71 @@ impl ProjectManager {
         Ok(project)
     }
 
+    /// Check a project file without opening it: format version, migrations
+    /// that would run and validation of the migrated project
+    pub fn check_project<P: AsRef<Path>>(
+        &self,
+        project_path: P,
+    ) -> Result<ProjectCheck, ProjectError> {
+        let zip_file = File::open(project_path.as_ref()).map_err(|e| {
+            ProjectError::FileSystemError(format!("Failed to open project file: {}", e))
+        })?;
+        let mut zip_archive = ZipArchive::new(zip_file).map_err(ProjectError::Zip)?;
+        let mut read_entry = |name: &str| -> Result<String, ProjectError> {
+            let mut entry = zip_archive
+                .by_name(name)
+                .map_err(|_| ProjectError::MissingFiles)?;
+            let mut contents = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut contents)?;
+            Ok(contents)
+        };
+
+        let metadata = deserialize_metadata_from_json(&read_entry("manifest.json")?)?;
+        let mut project = deserialize_from_ron(&read_entry("project.ron")?)?;
+        project.metadata = metadata;
+        ProjectMigrator::check(&project)
+    }
+
+    /// Export patterns to a type 1 MIDI file at the project tempo and time
+    /// signature: the given pattern, or one MIDI track per project track that
+    /// plays a pattern
+    pub fn export_midi<P: AsRef<Path>>(
+        &self,
+        project: &Project,
+        pattern_id: Option<PatternId>,
+        path: P,
+    ) -> Result<(), ProjectError> {
+        let sample_rate = project.metadata.sample_rate;
+        let mut smf = Smf::with_tempo(project.metadata.tempo, project.metadata.time_signature);
+        let pattern = |id: PatternId| {
+            project
+                .patterns
+                .get(&id)
+                .ok_or_else(|| ProjectError::InvalidStructure(format!("Pattern {} not found", id)))
+        };
+
+        if let Some(id) = pattern_id {
+            let pattern = pattern_from_serializable(pattern(id)?, sample_rate);
+            smf.add_track(&pattern.name, pattern.notes(), sample_rate);
+        } else {
+            let mut tracks: Vec<&Track> = project
+                .tracks
+                .values()
+                .filter(|track| track.track_type != TrackType::Audio)
+                .collect();
+            tracks.sort_by_key(|track| track.id);
+            for track in tracks {
+                if let Some(id) = track.pattern_id {
+                    let pattern = pattern_from_serializable(pattern(id)?, sample_rate);
+                    smf.add_track(&track.name, pattern.notes(), sample_rate);
+                }
+            }
+        }
+
+        smf.write(path.as_ref())
+            .map_err(ProjectError::FileSystemError)
+    }
+
     /// Get the default sample rate
     pub fn default_sample_rate(&self) -> f64 {
         self.default_sample_rate
@@ -378,6 +490,12 @@ mod tests {
         let mut project = manager.create_new_project("Test Save/Load".to_string());
         project.metadata.author = Some("Test Author".to_string());
         project.metadata.description = Some("Test description".to_string());
+        project.plugins.push(PluginInstanceSerializable {
+            path: std::path::PathBuf::from("/usr/lib/clap/Test.clap"),
+            name: "Test Plugin".to_string(),
+            parameters: [("1".to_string(), 0.25)].into_iter().collect(),
+            state: Some(vec![0, 1, 2, 255]),
+        });
 
         // Use current directory for test
         let project_path = std::env::temp_dir().join("test_project.mymusic");
@@ -405,10 +523,119 @@ mod tests {
         assert_eq!(loaded_project.metadata.sample_rate, 44100.0);
         assert_eq!(loaded_project.tracks.len(), 1);
 
+        // Plugin state blobs come back from the archive
+        let plugin = &loaded_project.plugins[0];
+        assert_eq!(plugin.name, "Test Plugin");
+        assert_eq!(plugin.parameters.get("1"), Some(&0.25));
+        assert_eq!(plugin.state, Some(vec![0, 1, 2, 255]));
+
         // Cleanup
         std::fs::remove_file(&project_path).ok();
     }
 
+    #[test]
+    fn test_collect_and_save_embeds_audio() {
+        use crate::sampler::bank::{SampleBank, SampleMapping};
+        use crate::sampler::loader::LoopMode;
+
+        let manager = ProjectManager::new(48000.0);
+        let dir = tempdir().unwrap();
+        // Two files named kick.wav in different folders
+        let kick = dir.path().join("drums/kick.wav");
+        let other_kick = dir.path().join("other/kick.wav");
+        for (path, data) in [(&kick, "kick"), (&other_kick, "other kick")] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, data).unwrap();
+        }
+
+        let mut project = manager.create_new_project("Collected".to_string());
+        let mut bank = SampleBank::new("Kit".to_string());
+        for (note, path) in [(36, &kick), (37, &other_kick)] {
+            bank.add_mapping(SampleMapping {
+                note,
+                sample_path: path.clone(),
+                name: "Kick".to_string(),
+                volume: 1.0,
+                pan: 0.0,
+                loop_mode: LoopMode::Off,
+                loop_start: 0,
+                loop_end: 0,
+                reverse: false,
+                pitch_offset: 0,
+                fine_tune: 0.0,
+                envelope: None,
+                stretch: Default::default(),
+                zone: None,
+            });
+        }
+        project.sample_bank = Some(bank);
+        // The clip plays the same file as note 36: stored once
+        project
+            .tracks
+            .get_mut(&0)
+            .unwrap()
+            .clips
+            .push(AudioClipSerializable {
+                name: "Kick".to_string(),
+                source_path: kick.clone(),
+                start_samples: 0,
+                offset_samples: 0,
+                length_samples: 100,
+                gain: 1.0,
+                fade_in_samples: 0,
+                fade_out_samples: 0,
+                warp: None,
+                pitch: 0.0,
+            });
+
+        let project_path = dir.path().join("collected.mymusic");
+        let options = ProjectSaveOptions {
+            collect_audio: true,
+        };
+        manager
+            .save_project_with_options(&project, &project_path, &options)
+            .unwrap();
+
+        // The archive holds the files, the project points inside it
+        let archive = ZipArchive::new(File::open(&project_path).unwrap()).unwrap();
+        let mut names: Vec<&str> = archive
+            .file_names()
+            .filter(|name| name.starts_with(EMBEDDED_AUDIO_DIR))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["audio/embedded/kick.wav", "audio/embedded/kick_2.wav"]
+        );
+
+        // The original files can go: the project opens from its archive
+        std::fs::remove_dir_all(dir.path().join("drums")).unwrap();
+        std::fs::remove_dir_all(dir.path().join("other")).unwrap();
+        let loaded = manager
+            .load_project(&project_path, &ProjectLoadOptions::default())
+            .unwrap();
+        let mappings = &loaded.sample_bank.as_ref().unwrap().samples;
+        let clip = &loaded.tracks[&0].clips[0];
+        assert!(mappings[0].sample_path.starts_with(embedded_audio_root()));
+        assert_eq!(std::fs::read_to_string(&clip.source_path).unwrap(), "kick");
+        assert_eq!(
+            std::fs::read_to_string(&mappings[1].sample_path).unwrap(),
+            "other kick"
+        );
+
+        // A plain save keeps them embedded
+        let resaved_path = dir.path().join("resaved.mymusic");
+        manager.save_project(&loaded, &resaved_path).unwrap();
+        let archive = ZipArchive::new(File::open(&resaved_path).unwrap()).unwrap();
+        assert_eq!(
+            archive
+                .file_names()
+                .filter(|name| name.starts_with(EMBEDDED_AUDIO_DIR))
+                .count(),
+            2
+        );
+    }
+
     #[test]
     fn test_project_validation() {
         let mut project = Project::default();
@@ -498,6 +725,47 @@ mod tests {
         std::fs::remove_file(&project_path).ok();
     }
 
+    #[test]
+    fn test_export_midi() {
+        let manager = ProjectManager::new(48000.0);
+        let mut project = manager.create_new_project("MIDI Export".to_string());
+        project.metadata.tempo = 100.0;
+
+        // The default track plays a pattern with one quarter note
+        let pattern_id = project.tracks[&0].pattern_id.unwrap();
+        let mut pattern =
+            crate::sequencer::pattern::Pattern::new(pattern_id, "Default Pattern".to_string(), 4);
+        pattern.add_note(crate::sequencer::Note::new(
+            1,
+            62,
+            crate::sequencer::timeline::Position::zero(),
+            28800,
+            90,
+        ));
+        project
+            .patterns
+            .insert(pattern_id, pattern_to_serializable(&pattern));
+
+        let path = std::env::temp_dir().join("export_midi_test.mid");
+        manager.export_midi(&project, None, &path).unwrap();
+        let smf = Smf::read(&path).unwrap();
+        assert_eq!(smf.tracks.len(), 2);
+        assert!((smf.initial_bpm() - 100.0).abs() < 1e-3);
+        assert_eq!(smf.time_signature, Some((4, 4)));
+        assert_eq!(smf.tracks[1].name.as_deref(), Some("Track 1"));
+        let note = smf.tracks[1].notes[0];
+        assert_eq!((note.key, note.velocity, note.length_ticks), (62, 90, 480));
+
+        manager
+            .export_midi(&project, Some(pattern_id), &path)
+            .unwrap();
+        let smf = Smf::read(&path).unwrap();
+        assert_eq!(smf.tracks[1].name.as_deref(), Some("Default Pattern"));
+        assert!(manager.export_midi(&project, Some(0), &path).is_err());
+
+        std::fs::remove_file(&path).ok();
+    }
+
     #[test]
     fn test_synth_params_serialization() {
         let manager = ProjectManager::new(48000.0);
@@ -4,6 +4,82 @@
 use crate::project::{Project, ProjectError, ProjectMetadata, ProjectVersion};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+
+/// A step of the migration chain: upgrades projects of one format version to
+/// the next
+pub struct Migration {
+    /// Version migrated from (the patch number is ignored)
+    pub from: ProjectVersion,
+    /// Version of the migrated project
+    pub to: ProjectVersion,
+    /// What the step changes
+    pub description: &'static str,
+    migrate: fn(Project) -> Result<Project, ProjectError>,
+}
+
+impl Migration {
+    /// Run the step and stamp the project with its version
+    pub fn apply(&self, project: Project) -> Result<Project, ProjectError> {
+        let mut project = (self.migrate)(project)?;
+        project.metadata.version = self.to.clone();
+        Ok(project)
+    }
+}
+
+impl fmt::Display for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "v{}.{} -> v{}.{}: {}",
+            self.from.major, self.from.minor, self.to.major, self.to.minor, self.description
+        )
+    }
+}
+
+/// Registered migrations, oldest first
+///
+/// Every bump of `ProjectVersion::current` ships the step from the previous
+/// version, so projects of any older version reach the current one (checked
+/// by `test_migration_chain_reaches_current`).
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: ProjectVersion::new(1, 0, 0),
+        to: ProjectVersion::new(1, 1, 0),
+        description: "Add default metronome settings",
+        migrate: migrate_1_0_to_1_1,
+    },
+    Migration {
+        from: ProjectVersion::new(1, 1, 0),
+        to: ProjectVersion::new(1, 2, 0),
+        description: "Add default loop settings",
+        migrate: migrate_1_1_to_1_2,
+    },
+];
+
+/// Major and minor version (migrations ignore the patch number)
+fn minor_version(version: &ProjectVersion) -> (u32, u32) {
+    (version.major, version.minor)
+}
+
+/// Migrate from v1.0 to v1.1
+/// v1.0 didn't have metronome settings in metadata: add default values
+fn migrate_1_0_to_1_1(mut project: Project) -> Result<Project, ProjectError> {
+    project.metadata.metronome_enabled = Some(true);
+    project.metadata.metronome_volume = Some(0.5);
+
+    Ok(project)
+}
+
+/// Migrate from v1.1 to v1.2
+/// v1.1 didn't have loop settings in metadata: add default values
+fn migrate_1_1_to_1_2(mut project: Project) -> Result<Project, ProjectError> {
+    project.metadata.loop_enabled = Some(false);
+    project.metadata.loop_start_bars = Some(1);
+    project.metadata.loop_end_bars = Some(8);
+
+    Ok(project)
+}
 
 /// Migration result
 #[derive(Debug, Clone)]
@@ -16,22 +92,58 @@ pub struct MigrationResult {
     pub messages: Vec<String>,
 }
 
+/// What opening a project would do, without changing it (see
+/// `ProjectManager::check_project`)
+#[derive(Debug, Clone)]
+pub struct ProjectCheck {
+    /// Format version of the project
+    pub version: ProjectVersion,
+    pub compatibility: CompatibilityInfo,
+    /// Migrations that would run, in order
+    pub migrations: Vec<String>,
+    /// Why the migrated project fails validation (None: valid)
+    pub validation_error: Option<String>,
+}
+
+impl ProjectCheck {
+    /// Whether the project would open
+    pub fn is_ok(&self) -> bool {
+        self.compatibility.can_load && self.validation_error.is_none()
+    }
+}
+
 /// Project format migrator
 pub struct ProjectMigrator;
 
 impl ProjectMigrator {
-    /// Migrate project to current version
-    pub fn migrate_to_current(
-        mut project: Project,
-    ) -> Result<MigrationResult, crate::project::ProjectError> {
-        let mut messages = Vec::new();
-        let mut migrated = false;
-
-        let current_version = ProjectVersion::current();
-        let project_version = project.metadata.version.clone();
+    /// Migrations that bring a version to the current one, in order (empty for
+    /// the current version or a newer minor version)
+    ///
+    /// # Errors
+    /// `ProjectError::InvalidVersion` for a newer major version or a version
+    /// no registered migration starts from.
+    pub fn plan(version: &ProjectVersion) -> Result<Vec<&'static Migration>, ProjectError> {
+        let current = ProjectVersion::current();
+        if version.major > current.major {
+            return Err(ProjectError::InvalidVersion);
+        }
+        let mut steps = Vec::new();
+        let mut at = minor_version(version);
+        while at < minor_version(&current) {
+            let step = MIGRATIONS
+                .iter()
+                .find(|migration| minor_version(&migration.from) == at)
+                .ok_or(ProjectError::InvalidVersion)?;
+            steps.push(step);
+            at = minor_version(&step.to);
+        }
+        Ok(steps)
+    }
 
-        // Check if migration is needed
-        if project_version == current_version {
+    /// Migrate project to current version
+    pub fn migrate_to_current(mut project: Project) -> Result<MigrationResult, ProjectError> {
+        let steps = Self::plan(&project.metadata.version)?;
+        if steps.is_empty() {
             return Ok(MigrationResult {
                 project,
                 migrated: false,
@@ -39,115 +151,105 @@ impl ProjectMigrator {
             });
         }
 
-        // Perform version-by-version migrations
-        if project_version.major < 1 {
-            return Err(ProjectError::InvalidVersion);
-        }
-
-        // Version 1.0 -> 1.1 migration (example)
-        if project_version.major == 1 && project_version.minor < 1 {
-            messages.push("Migrating from v1.0 to v1.1...".to_string());
-            project = Self::migrate_1_0_to_1_1(project)?;
-            migrated = true;
-        }
-
-        // Version 1.1 -> 1.2 migration (example)
-        if project_version.major == 1 && project_version.minor < 2 {
-            messages.push("Migrating from v1.1 to v1.2...".to_string());
-            project = Self::migrate_1_1_to_1_2(project)?;
-            migrated = true;
+        let mut messages = Vec::new();
+        for step in steps {
+            messages.push(format!("Migrating {}", step));
+            project = step.apply(project)?;
         }
-
-        // Update version to current
+        let current_version = ProjectVersion::current();
         project.metadata.version = current_version.clone();
-
-        if migrated {
-            messages.push(format!("Successfully migrated to v{}", current_version));
-        }
+        messages.push(format!("Successfully migrated to v{}", current_version));
 
         Ok(MigrationResult {
             project,
-            migrated,
+            migrated: true,
             messages,
         })
     }
 
+    /// Report what opening a project would do: compatibility, migrations and
+    /// validation of the migrated project (the project itself is not changed)
+    pub fn check(project: &Project) -> Result<ProjectCheck, ProjectError> {
+        let version = project.metadata.version.clone();
+        let compatibility = Self::check_compatibility(version.clone())?;
+        let migrations = Self::plan(&version)
+            .map(|steps| steps.iter().map(|step| step.to_string()).collect())
+            .unwrap_or_default();
+        let validation_error = if compatibility.can_load {
+            Self::migrate_to_current(project.clone())
+                .and_then(|result| crate::project::validate_project_structure(&result.project))
+                .err()
+                .map(|e| e.to_string())
+        } else {
+            None
+        };
+        Ok(ProjectCheck {
+            version,
+            compatibility,
+            migrations,
+            validation_error,
+        })
+    }
+
     /// Check if project can be loaded (compatibility check)
-    pub fn check_compatibility(
-        version: ProjectVersion,
-    ) -> Result<CompatibilityInfo, crate::project::ProjectError> {
+    pub fn check_compatibility(version: ProjectVersion) -> Result<CompatibilityInfo, ProjectError> {
         let current = ProjectVersion::current();
+        let steps = match Self::plan(&version) {
+            Ok(steps) => steps,
+            Err(_) => {
+                let reason = if version.major > current.major {
+                    "is newer than"
+                } else {
+                    "cannot be migrated to"
+                };
+                return Ok(CompatibilityInfo {
+                    can_load: false,
+                    needs_migration: false,
+                    warning: Some(format!(
+                        "Project version v{} {} current v{}",
+                        version, reason, current
+                    )),
+                });
+            }
+        };
 
-        // Major version too new - cannot load
-        if version.major > current.major {
+        // Older version - can load with migration
+        if !steps.is_empty() {
             return Ok(CompatibilityInfo {
-                can_load: false,
-                needs_migration: false,
+                can_load: true,
+                needs_migration: true,
                 warning: Some(format!(
-                    "Project version v{}.{}.{} is newer than current v{}.{}.{}",
-                    version.major,
-                    version.minor,
-                    version.patch,
-                    current.major,
-                    current.minor,
-                    current.patch
+                    "Project version v{} will be migrated to v{}",
+                    version, current
                 )),
             });
         }
 
-        // Same version - fully compatible
-        if version == current {
+        // Newer minor version - loads, settings it added are dropped
+        if minor_version(&version) > minor_version(&current) {
             return Ok(CompatibilityInfo {
                 can_load: true,
                 needs_migration: false,
-                warning: None,
+                warning: Some(format!(
+                    "Project version v{} is newer than current v{}: newer settings are ignored",
+                    version, current
+                )),
             });
         }
 
-        // Older version - can load with migration
+        // Same version - fully compatible
         Ok(CompatibilityInfo {
             can_load: true,
-            needs_migration: true,
-            warning: Some(format!(
-                "Project version v{}.{}.{} will be migrated to v{}.{}.{}",
-                version.major,
-                version.minor,
-                version.patch,
-                current.major,
-                current.minor,
-                current.patch
-            )),
+            needs_migration: false,
+            warning: None,
         })
     }
 
-    /// Migrate from v1.0 to v1.1
-    /// Example: Add default metronome settings
-    fn migrate_1_0_to_1_1(mut project: Project) -> Result<Project, crate::project::ProjectError> {
-        // v1.0 didn't have metronome settings in metadata
-        // Add default values
-        project.metadata.metronome_enabled = Some(true);
-        project.metadata.metronome_volume = Some(0.5);
-
-        Ok(project)
-    }
-
-    /// Migrate from v1.1 to v1.2
-    /// Example: Add default loop settings
-    fn migrate_1_1_to_1_2(mut project: Project) -> Result<Project, crate::project::ProjectError> {
-        // v1.1 didn't have loop settings in metadata
-        // Add default values
-        project.metadata.loop_enabled = Some(false);
-        project.metadata.loop_start_bars = Some(1);
-        project.metadata.loop_end_bars = Some(8);
-
-        Ok(project)
-    }
-
     /// Create backup of project before migration
     pub fn create_backup(
         _project: &Project,
         path: &std::path::Path,
-    ) -> Result<std::path::PathBuf, crate::project::ProjectError> {
+    ) -> Result<std::path::PathBuf, ProjectError> {
         use std::fs;
 
         let backup_path = path.with_extension("mymusic.backup");
@@ -233,6 +335,16 @@ impl From<LegacyProjectV1_0> for Project {
             patterns: HashMap::new(), // Will be populated during migration
             synth_params: legacy.synth_params,
             sample_bank: None, // Default for migrated projects
+            return_buses: Vec::new(),
+            speaker_layout: Default::default(),
+            master_chain: Default::default(),
+            automation: Vec::new(),
+            plugins: Vec::new(),
+            cc_mappings: Vec::new(),
+            arrangement: Vec::new(),
+            song_mode: false,
+            session: Vec::new(),
+            launch_quantization: Default::default(),
         }
     }
 }
@@ -303,6 +415,54 @@ mod tests {
         assert_eq!(result.project.metadata.metronome_volume, Some(0.5));
     }
 
+    #[test]
+    fn test_migration_chain_reaches_current() {
+        // Each step starts where the previous one ended, the last one at the
+        // current version
+        for pair in MIGRATIONS.windows(2) {
+            assert_eq!(minor_version(&pair[0].to), minor_version(&pair[1].from));
+        }
+        let last = MIGRATIONS.last().unwrap();
+        assert_eq!(
+            minor_version(&last.to),
+            minor_version(&ProjectVersion::current())
+        );
+
+        let plan = ProjectMigrator::plan(&ProjectVersion::new(1, 0, 3)).unwrap();
+        assert_eq!(plan.len(), MIGRATIONS.len());
+        assert_eq!(
+            plan[0].to_string(),
+            "v1.0 -> v1.1: Add default metronome settings"
+        );
+        assert!(
+            ProjectMigrator::plan(&ProjectVersion::current())
+                .unwrap()
+                .is_empty()
+        );
+        assert!(ProjectMigrator::plan(&ProjectVersion::new(0, 9, 0)).is_err());
+    }
+
+    #[test]
+    fn test_check_reports_migrations() {
+        let manager = crate::project::ProjectManager::new(48000.0);
+        let mut project = manager.create_new_project("Old Song".to_string());
+        project.metadata.version = ProjectVersion::new(1, 1, 0);
+
+        let check = ProjectMigrator::check(&project).unwrap();
+        assert!(check.is_ok(), "{:?}", check.validation_error);
+        assert!(check.compatibility.needs_migration);
+        assert_eq!(
+            check.migrations,
+            ["v1.1 -> v1.2: Add default loop settings"]
+        );
+
+        // A newer major version does not open
+        project.metadata.version = ProjectVersion::new(2, 0, 0);
+        let check = ProjectMigrator::check(&project).unwrap();
+        assert!(!check.is_ok());
+        assert!(check.migrations.is_empty());
+    }
+
     #[test]
     fn test_no_migration_needed() {
         let project = Project::default();
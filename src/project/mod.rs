@@ -6,12 +6,17 @@ pub mod manager;
 use crate::sequencer::pattern::PatternId;
 
 pub mod migration;
+pub mod preset;
 pub mod serialization;
 pub mod types;
 
-pub use manager::{ProjectError, ProjectLoadOptions, ProjectManager};
+pub use manager::{ProjectError, ProjectLoadOptions, ProjectManager, ProjectSaveOptions};
+pub use preset::{
+    PresetEntry, PresetKind, PresetStore, SynthPreset, factory_preset, factory_presets,
+};
 pub use types::{
-    PatternSerializable, Project, ProjectMetadata, ProjectVersion, SynthParams, Track,
+    AudioClipSerializable, PatternSerializable, Project, ProjectMetadata, ProjectVersion,
+    SynthParams, Track,
 };
 
 /// Helper function to generate unique IDs
@@ -194,6 +199,23 @@ pub fn validate_project_structure(project: &Project) -> Result<(), ProjectError>
             )));
         }
 
+        // Check audio clips
+        for clip in &track.clips {
+            if clip.gain < 0.0 || clip.gain > 2.0 {
+                return Err(ProjectError::InvalidStructure(format!(
+                    "Track {} clip '{}' gain must be between 0.0 and 2.0",
+                    track_id, clip.name
+                )));
+            }
+
+            if clip.length_samples == 0 {
+                return Err(ProjectError::InvalidStructure(format!(
+                    "Track {} clip '{}' length cannot be 0",
+                    track_id, clip.name
+                )));
+            }
+        }
+
         // Check pattern validity
         if let Some(pattern_id) = track.pattern_id
             && !project.patterns.contains_key(&pattern_id)
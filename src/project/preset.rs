@@ -0,0 +1,590 @@
+// Preset storage - Synth presets and sample banks saved by name
+//
+// Presets live in a directory tree independent from projects:
+//   <root>/synth/<name>.json
+//   <root>/sample_banks/<name>.json
+//
+// Names are used as file names, so they are validated to stay inside the
+// preset directory (no separators, no "..").
+//
+// Factory synth presets are compiled in: they are listed next to the user
+// presets and loaded when no user preset has the same name.
+
+use crate::command::patch::SynthPatch;
+use crate::command::state::DawState;
+use crate::command::trait_def::CommandResult;
+use crate::sampler::bank::SampleBank;
+use crate::synth::envelope::AdsrParams;
+use crate::synth::filter::{FilterModParams, FilterParams, FilterType};
+use crate::synth::lfo::{LFO_COUNT, LfoDestination, LfoParams};
+use crate::synth::modulation::{MAX_ROUTINGS, ModDestination, ModRouting, ModSource};
+use crate::synth::noise::{NoiseParams, NoiseType};
+use crate::synth::oscillator::{
+    AntiAliasing, CrossModParams, Oscillator2Params, UnisonParams, WaveformType,
+};
+use crate::synth::poly_mode::PolyMode;
+use crate::synth::portamento::PortamentoParams;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Kind of preset (each kind has its own sub-directory)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresetKind {
+    Synth,
+    SampleBank,
+}
+
+impl PresetKind {
+    /// Sub-directory holding presets of this kind
+    fn dir_name(&self) -> &'static str {
+        match self {
+            PresetKind::Synth => "synth",
+            PresetKind::SampleBank => "sample_banks",
+        }
+    }
+}
+
+/// Synthesizer sound (everything that shapes the synth voice)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SynthPreset {
+    pub name: String,
+    pub waveform: WaveformType,
+    /// Second oscillator (off in presets saved before it existed)
+    #[serde(default)]
+    pub oscillator2: Oscillator2Params,
+    /// Unison stack (one voice in presets saved before it existed)
+    #[serde(default)]
+    pub unison: UnisonParams,
+    /// Band-limiting method (PolyBLEP in presets saved before it existed)
+    #[serde(default)]
+    pub anti_aliasing: AntiAliasing,
+    /// Oscillator cross-modulation (off in presets saved before it existed)
+    #[serde(default)]
+    pub cross_mod: CrossModParams,
+    /// Noise source (off in presets saved before it existed)
+    #[serde(default)]
+    pub noise: NoiseParams,
+    /// Modulation envelope (default in presets saved before it existed)
+    #[serde(default)]
+    pub mod_envelope: AdsrParams,
+    /// Filter envelope amount, key tracking and drive (absent in older presets)
+    #[serde(default)]
+    pub filter_mod: FilterModParams,
+    /// LFOs 2..LFO_COUNT, only heard through the modulation matrix
+    #[serde(default)]
+    pub mod_lfos: [LfoParams; LFO_COUNT - 1],
+    pub adsr: AdsrParams,
+    pub lfo: LfoParams,
+    pub filter: FilterParams,
+    pub portamento: PortamentoParams,
+    pub poly_mode: PolyMode,
+    /// Modulation matrix (missing in presets saved before it was stored, and
+    /// 8 slots long in presets saved before the matrix grew)
+    #[serde(default = "no_routings", deserialize_with = "deserialize_routings")]
+    pub mod_routings: [ModRouting; MAX_ROUTINGS],
+}
+
+fn no_routings() -> [ModRouting; MAX_ROUTINGS] {
+    [ModRouting::disabled(); MAX_ROUTINGS]
+}
+
+/// Routings of a preset, the slots missing from shorter matrices disabled
+fn deserialize_routings<'de, D>(deserializer: D) -> Result<[ModRouting; MAX_ROUTINGS], D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let saved = Vec::<ModRouting>::deserialize(deserializer)?;
+    if saved.len() > MAX_ROUTINGS {
+        return Err(serde::de::Error::invalid_length(
+            saved.len(),
+            &"at most 16 modulation routings",
+        ));
+    }
+    let mut routings = no_routings();
+    routings[..saved.len()].copy_from_slice(&saved);
+    Ok(routings)
+}
+
+impl SynthPreset {
+    /// Capture the current synth sound from the DAW state
+    pub fn from_state(name: String, state: &DawState) -> Self {
+        Self::from_patch(name, &SynthPatch::from_state(state))
+    }
+
+    /// Name a synth sound
+    pub fn from_patch(name: String, patch: &SynthPatch) -> Self {
+        Self {
+            name,
+            waveform: patch.waveform,
+            oscillator2: patch.oscillator2,
+            unison: patch.unison,
+            anti_aliasing: patch.anti_aliasing,
+            cross_mod: patch.cross_mod,
+            noise: patch.noise,
+            mod_envelope: patch.mod_envelope,
+            filter_mod: patch.filter_mod,
+            mod_lfos: patch.mod_lfos,
+            adsr: patch.adsr,
+            lfo: patch.lfo,
+            filter: patch.filter,
+            portamento: patch.portamento,
+            poly_mode: patch.poly_mode,
+            mod_routings: patch.mod_routings,
+        }
+    }
+
+    /// Synth sound of the preset
+    pub fn patch(&self) -> SynthPatch {
+        SynthPatch {
+            waveform: self.waveform,
+            oscillator2: self.oscillator2,
+            unison: self.unison,
+            anti_aliasing: self.anti_aliasing,
+            cross_mod: self.cross_mod,
+            noise: self.noise,
+            mod_envelope: self.mod_envelope,
+            filter_mod: self.filter_mod,
+            mod_lfos: self.mod_lfos,
+            adsr: self.adsr,
+            lfo: self.lfo,
+            filter: self.filter,
+            portamento: self.portamento,
+            poly_mode: self.poly_mode,
+            mod_routings: self.mod_routings,
+        }
+    }
+
+    /// Apply the preset to the DAW state and send it to the audio thread
+    pub fn apply(&self, state: &mut DawState) -> CommandResult<()> {
+        self.patch().apply(state)
+    }
+}
+
+/// Preset listed by `PresetStore::browse_synth`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresetEntry {
+    pub name: String,
+    /// Compiled-in preset (cannot be deleted)
+    pub factory: bool,
+}
+
+/// Presets shipped with the application
+pub fn factory_presets() -> Vec<SynthPreset> {
+    let preset = |name: &str, waveform, adsr, filter| SynthPreset {
+        name: name.to_string(),
+        waveform,
+        oscillator2: Oscillator2Params::default(),
+        unison: UnisonParams::default(),
+        anti_aliasing: AntiAliasing::default(),
+        cross_mod: CrossModParams::default(),
+        noise: NoiseParams::default(),
+        mod_envelope: AdsrParams::default(),
+        filter_mod: FilterModParams::default(),
+        mod_lfos: [LfoParams::default(); LFO_COUNT - 1],
+        adsr,
+        lfo: LfoParams::default(),
+        filter,
+        portamento: PortamentoParams::instant(),
+        poly_mode: PolyMode::Poly,
+        mod_routings: no_routings(),
+    };
+    let lowpass = |cutoff, resonance| FilterParams {
+        cutoff,
+        resonance,
+        filter_type: FilterType::LowPass,
+        enabled: true,
+    };
+    let routing = |source, destination, amount| ModRouting {
+        source,
+        destination,
+        amount,
+        enabled: true,
+    };
+
+    let init = preset(
+        "Init",
+        WaveformType::Sine,
+        AdsrParams::default(),
+        FilterParams::default(),
+    );
+
+    let mut bass = preset(
+        "Mono Bass",
+        WaveformType::Saw,
+        AdsrParams::new(0.005, 0.25, 0.6, 0.08),
+        lowpass(600.0, 2.0),
+    );
+    bass.poly_mode = PolyMode::Legato;
+    bass.portamento = PortamentoParams::new(0.06);
+    bass.oscillator2 = Oscillator2Params::new(WaveformType::Square, -12, 0.0, 0.35);
+    bass.mod_routings[0] = routing(ModSource::Envelope, ModDestination::FilterCutoff, 0.6);
+
+    let mut lead = preset(
+        "Saw Lead",
+        WaveformType::Saw,
+        AdsrParams::new(0.01, 0.2, 0.8, 0.3),
+        lowpass(3000.0, 1.2),
+    );
+    lead.poly_mode = PolyMode::Mono;
+    lead.portamento = PortamentoParams::new(0.04);
+    lead.oscillator2 = Oscillator2Params::new(WaveformType::Saw, 0, 8.0, 0.5);
+    lead.mod_routings[0] = routing(ModSource::ModWheel, ModDestination::OscillatorPitch(0), 0.5);
+    let mut pad = preset(
+        "Warm Pad",
+        WaveformType::Triangle,
+        AdsrParams::new(1.2, 1.0, 0.8, 2.0),
+        lowpass(1800.0, 0.9),
+    );
+    pad.lfo = LfoParams::new(WaveformType::Sine, 0.3, 0.3, LfoDestination::FilterCutoff);
+    pad.unison = UnisonParams::new(4, 18.0, 0.7);
+    pad.mod_routings[0] = routing(ModSource::Lfo(0), ModDestination::Pan, 0.4);
+
+    let mut pluck = preset(
+        "Pluck",
+        WaveformType::Square,
+        AdsrParams::new(0.001, 0.3, 0.0, 0.25),
+        lowpass(2500.0, 3.0),
+    );
+    pluck.mod_routings[0] = routing(ModSource::Velocity, ModDestination::Amplitude, 0.5);
+    pluck.mod_routings[1] = routing(ModSource::Velocity, ModDestination::FilterCutoff, 0.5);
+    pluck.noise = NoiseParams::new(NoiseType::White, 0.1);
+
+    let mut organ = preset(
+        "Organ",
+        WaveformType::Sine,
+        AdsrParams::new(0.005, 0.05, 1.0, 0.05),
+        FilterParams {
+            enabled: false,
+            ..FilterParams::default()
+        },
+    );
+    organ.lfo = LfoParams::new(WaveformType::Sine, 6.0, 0.2, LfoDestination::Volume);
+
+    vec![init, bass, lead, pad, pluck, organ]
+}
+
+/// Factory preset with a given name
+pub fn factory_preset(name: &str) -> Option<SynthPreset> {
+    factory_presets()
+        .into_iter()
+        .find(|preset| preset.name == name)
+}
+
+/// Named presets stored on disk
+pub struct PresetStore {
+    root: PathBuf,
+}
+
+impl PresetStore {
+    /// Create a store rooted at `root` (directories are created on save)
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Default preset directory (user data dir)
+    pub fn default_root() -> PathBuf {
+        dirs::data_dir()
+            .unwrap_or_default()
+            .join("mymusic_daw")
+            .join("presets")
+    }
+
+    /// Root directory of the store
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Directory holding presets of a given kind
+    pub fn kind_dir(&self, kind: PresetKind) -> PathBuf {
+        self.root.join(kind.dir_name())
+    }
+
+    /// File path of a preset (fails if the name is not a valid file name)
+    pub fn preset_path(&self, kind: PresetKind, name: &str) -> Result<PathBuf, String> {
+        validate_preset_name(name)?;
+        Ok(self.kind_dir(kind).join(format!("{}.json", name)))
+    }
+
+    /// List preset names of a given kind, sorted alphabetically
+    pub fn list(&self, kind: PresetKind) -> Result<Vec<String>, String> {
+        let dir = self.kind_dir(kind);
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read preset directory: {}", e))?;
+        let mut names: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// Save a synth preset (overwrites a preset with the same name)
+    pub fn save_synth(&self, preset: &SynthPreset) -> Result<PathBuf, String> {
+        let path = self.preset_path(PresetKind::Synth, &preset.name)?;
+        create_parent_dir(&path)?;
+
+        let json = serde_json::to_string_pretty(preset)
+            .map_err(|e| format!("Failed to serialize preset: {}", e))?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write preset: {}", e))?;
+        Ok(path)
+    }
+
+    /// Factory and user synth presets, sorted by name
+    ///
+    /// A user preset hides the factory preset with the same name.
+    pub fn browse_synth(&self) -> Result<Vec<PresetEntry>, String> {
+        let user = self.list(PresetKind::Synth)?;
+        let mut entries: Vec<PresetEntry> = factory_presets()
+            .into_iter()
+            .filter(|preset| !user.contains(&preset.name))
+            .map(|preset| PresetEntry {
+                name: preset.name,
+                factory: true,
+            })
+            .chain(user.iter().map(|name| PresetEntry {
+                name: name.clone(),
+                factory: false,
+            }))
+            .collect();
+        entries.sort_by_key(|entry| entry.name.to_lowercase());
+        Ok(entries)
+    }
+
+    /// Whether a synth preset can be loaded (user file or factory preset)
+    pub fn has_synth(&self, name: &str) -> bool {
+        self.preset_path(PresetKind::Synth, name)
+            .is_ok_and(|path| path.exists())
+            || factory_preset(name).is_some()
+    }
+
+    /// Load a synth preset by name (user presets first, then factory presets)
+    pub fn load_synth(&self, name: &str) -> Result<SynthPreset, String> {
+        let path = self.preset_path(PresetKind::Synth, name)?;
+        if !path.exists()
+            && let Some(preset) = factory_preset(name)
+        {
+            return Ok(preset);
+        }
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read preset '{}': {}", name, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse preset '{}': {}", name, e))
+    }
+
+    /// Save a sample bank under its name (overwrites a bank with the same name)
+    pub fn save_sample_bank(&self, bank: &SampleBank) -> Result<PathBuf, String> {
+        let path = self.preset_path(PresetKind::SampleBank, &bank.name)?;
+        create_parent_dir(&path)?;
+        bank.save_to_file(&path)?;
+        Ok(path)
+    }
+
+    /// Load a sample bank by name
+    ///
+    /// Relative sample paths in the bank are resolved against `kind_dir(SampleBank)`.
+    pub fn load_sample_bank(&self, name: &str) -> Result<SampleBank, String> {
+        let path = self.preset_path(PresetKind::SampleBank, name)?;
+        SampleBank::load_from_file(&path)
+    }
+
+    /// Delete a preset
+    pub fn delete(&self, kind: PresetKind, name: &str) -> Result<(), String> {
+        let path = self.preset_path(kind, name)?;
+        std::fs::remove_file(&path)
+            .map_err(|e| format!("Failed to delete preset '{}': {}", name, e))
+    }
+}
+
+impl Default for PresetStore {
+    fn default() -> Self {
+        Self::new(Self::default_root())
+    }
+}
+
+/// Check that a preset name can be used as a file name
+pub fn validate_preset_name(name: &str) -> Result<(), String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty() {
+        return Err("Preset name cannot be empty".to_string());
+    }
+    if trimmed.len() > 255 {
+        return Err("Preset name cannot exceed 255 characters".to_string());
+    }
+    if trimmed != name
+        || name.starts_with('.')
+        || name.chars().any(|c| {
+            matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control()
+        })
+    {
+        return Err(format!("Invalid preset name: {}", name));
+    }
+    Ok(())
+}
+
+fn create_parent_dir(path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create preset directory: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::channels::create_command_channel;
+    use std::sync::{Arc, Mutex};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_validate_preset_name() {
+        assert!(validate_preset_name("Warm Pad").is_ok());
+        assert!(validate_preset_name("").is_err());
+        assert!(validate_preset_name(" padded ").is_err());
+        assert!(validate_preset_name("../escape").is_err());
+        assert!(validate_preset_name("a/b").is_err());
+        assert!(validate_preset_name(".hidden").is_err());
+    }
+
+    #[test]
+    fn test_synth_preset_round_trip() {
+        let dir = tempdir().unwrap();
+        let store = PresetStore::new(dir.path().to_path_buf());
+
+        let (tx, _rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        state.waveform = WaveformType::Saw;
+        state.oscillator2 = Oscillator2Params::new(WaveformType::Square, 7, -5.0, 0.4);
+        state.unison = UnisonParams::new(3, 20.0, 0.5);
+        state.anti_aliasing = AntiAliasing::Wavetable;
+        state.cross_mod = CrossModParams::new(0.3, 0.0);
+        state.adsr = AdsrParams::new(0.5, 0.2, 0.7, 1.0);
+
+        let preset = SynthPreset::from_state("Lead".to_string(), &state);
+        store.save_synth(&preset).unwrap();
+        store
+            .save_sample_bank(&SampleBank::new("Drums".to_string()))
+            .unwrap();
+
+        assert_eq!(store.list(PresetKind::Synth).unwrap(), vec!["Lead"]);
+        assert_eq!(store.list(PresetKind::SampleBank).unwrap(), vec!["Drums"]);
+
+        state.waveform = WaveformType::Sine;
+        state.oscillator2 = Oscillator2Params::default();
+        state.unison = UnisonParams::default();
+        state.anti_aliasing = AntiAliasing::PolyBlep;
+        state.cross_mod = CrossModParams::default();
+        store.load_synth("Lead").unwrap().apply(&mut state).unwrap();
+        assert_eq!(state.waveform, WaveformType::Saw);
+        assert_eq!(state.oscillator2.coarse, 7);
+        assert_eq!(state.unison.voices, 3);
+        assert_eq!(state.anti_aliasing, AntiAliasing::Wavetable);
+        assert_eq!(state.cross_mod.fm, 0.3);
+        assert_eq!(state.adsr.attack, 0.5);
+
+        store.delete(PresetKind::Synth, "Lead").unwrap();
+        assert!(store.list(PresetKind::Synth).unwrap().is_empty());
+        assert!(store.load_synth("Lead").is_err());
+    }
+
+    #[test]
+    fn test_preset_keeps_mod_matrix() {
+        let dir = tempdir().unwrap();
+        let store = PresetStore::new(dir.path().to_path_buf());
+
+        let (tx, _rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+        let routing = ModRouting {
+            source: ModSource::ModWheel,
+            destination: ModDestination::FilterCutoff,
+            amount: -0.5,
+            enabled: true,
+        };
+        state.mod_routings[3] = routing;
+        store
+            .save_synth(&SynthPreset::from_state("Wheel".to_string(), &state))
+            .unwrap();
+
+        state.mod_routings[3] = ModRouting::disabled();
+        store
+            .load_synth("Wheel")
+            .unwrap()
+            .apply(&mut state)
+            .unwrap();
+        assert_eq!(state.mod_routings[3], routing);
+
+        // Presets saved without the matrix load with every routing disabled
+        let mut json: serde_json::Value =
+            serde_json::to_value(SynthPreset::from_state("Old".to_string(), &state)).unwrap();
+        json.as_object_mut().unwrap().remove("mod_routings");
+        let old: SynthPreset = serde_json::from_value(json).unwrap();
+        assert!(old.mod_routings.iter().all(|routing| !routing.enabled));
+
+        // Presets saved with the 8-slot matrix keep their routings
+        let mut json: serde_json::Value =
+            serde_json::to_value(SynthPreset::from_state("Old".to_string(), &state)).unwrap();
+        json["mod_routings"].as_array_mut().unwrap().truncate(8);
+        let old: SynthPreset = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(old.mod_routings[3], routing);
+        assert!(old.mod_routings[8..].iter().all(|routing| !routing.enabled));
+
+        // More routings than the matrix holds are refused
+        let extra = json["mod_routings"][0].clone();
+        let routings = json["mod_routings"].as_array_mut().unwrap();
+        routings.resize(MAX_ROUTINGS + 1, extra);
+        assert!(serde_json::from_value::<SynthPreset>(json).is_err());
+    }
+
+    #[test]
+    fn test_browse_factory_and_user_presets() {
+        let dir = tempdir().unwrap();
+        let store = PresetStore::new(dir.path().to_path_buf());
+
+        let factory = factory_presets();
+        assert!(factory.len() > 1);
+        for preset in &factory {
+            assert!(validate_preset_name(&preset.name).is_ok());
+        }
+
+        let entries = store.browse_synth().unwrap();
+        assert_eq!(entries.len(), factory.len());
+        assert!(entries.iter().all(|entry| entry.factory));
+        assert_eq!(store.load_synth("Warm Pad").unwrap().name, "Warm Pad");
+        assert!(store.has_synth("Warm Pad"));
+
+        // A user preset with a factory name replaces it
+        let mut pad = factory_preset("Warm Pad").unwrap();
+        pad.waveform = WaveformType::Square;
+        store.save_synth(&pad).unwrap();
+        store
+            .save_synth(&SynthPreset {
+                name: "bright".to_string(),
+                ..pad.clone()
+            })
+            .unwrap();
+
+        let entries = store.browse_synth().unwrap();
+        assert_eq!(entries.len(), factory.len() + 1);
+        let names: Vec<_> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names[0], "bright");
+        let pad_entry = entries
+            .iter()
+            .find(|entry| entry.name == "Warm Pad")
+            .unwrap();
+        assert!(!pad_entry.factory);
+        assert_eq!(
+            store.load_synth("Warm Pad").unwrap().waveform,
+            WaveformType::Square
+        );
+        assert!(!store.has_synth("Missing"));
+    }
+}
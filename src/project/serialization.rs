@@ -1,6 +1,7 @@
 // Serialization utilities for project persistence
 
 use crate::project::types::*;
+use crate::synth::modulation::{MAX_ROUTINGS, ModRouting};
 use ron::{from_str as ron_from_str, to_string as ron_to_string};
 use std::path::{Path, PathBuf};
 
@@ -65,8 +66,11 @@ pub fn pattern_to_serializable(
                 start_samples: note.start.samples,
                 duration_samples: note.duration_samples,
                 velocity: note.velocity,
+                probability: note.probability,
+                offset_ticks: note.offset_ticks,
             })
             .collect(),
+        groove: pattern.groove.clone(),
     }
 }
 
@@ -80,6 +84,7 @@ pub fn pattern_from_serializable(
         serializable.name.clone(),
         serializable.length_bars,
     );
+    pattern.groove = serializable.groove.clone();
 
     // Recreate notes from serializable data
     for serializable_note in &serializable.notes {
@@ -90,13 +95,20 @@ pub fn pattern_from_serializable(
             &crate::sequencer::timeline::TimeSignature::default(),
         );
 
-        let note = crate::sequencer::note::Note::new(
-            serializable_note.id,
-            serializable_note.pitch,
-            position,
-            serializable_note.duration_samples,
-            serializable_note.velocity,
-        );
+        let note = crate::sequencer::note::Note {
+            probability: serializable_note.probability.clamp(0.0, 1.0),
+            offset_ticks: serializable_note.offset_ticks.clamp(
+                -crate::sequencer::note::MAX_OFFSET_TICKS,
+                crate::sequencer::note::MAX_OFFSET_TICKS,
+            ),
+            ..crate::sequencer::note::Note::new(
+                serializable_note.id,
+                serializable_note.pitch,
+                position,
+                serializable_note.duration_samples,
+                serializable_note.velocity,
+            )
+        };
 
         pattern.add_note(note);
     }
@@ -104,6 +116,443 @@ pub fn pattern_from_serializable(
     pattern
 }
 
+/// Convert an audio track to a project track
+///
+/// Clip positions are rescaled from the engine rate (`sample_rate`) to the
+/// project rate.
+pub fn audio_track_to_serializable(
+    track: &crate::audio::clip_player::AudioTrack,
+    id: u32,
+    sample_rate: f32,
+    project_sample_rate: f64,
+) -> Track {
+    let ratio = project_sample_rate / sample_rate as f64;
+    let scale = |samples: u64| (samples as f64 * ratio).round() as u64;
+    Track {
+        id,
+        name: track.name.clone(),
+        pattern_id: None,
+        color: None,
+        volume: track.gain,
+        pan: track.pan,
+        muted: track.muted,
+        soloed: track.soloed,
+        track_type: TrackType::Audio,
+        clips: track
+            .clips
+            .iter()
+            .map(|clip| AudioClipSerializable {
+                name: clip.name.clone(),
+                source_path: clip.source.clone(),
+                start_samples: scale(clip.start),
+                offset_samples: scale(clip.offset),
+                length_samples: scale(clip.length),
+                gain: clip.gain,
+                fade_in_samples: scale(clip.fade_in),
+                fade_out_samples: scale(clip.fade_out),
+                pitch: clip.pitch(),
+                warp: clip.warp.as_ref().map(|warp| ClipWarpSerializable {
+                    source_bpm: warp.source_bpm,
+                    markers: warp
+                        .markers()
+                        .iter()
+                        .map(|marker| WarpMarkerSerializable {
+                            source_samples: scale(marker.source),
+                            beat: marker.beat,
+                        })
+                        .collect(),
+                }),
+            })
+            .collect(),
+        // Set by the caller, which knows the project ids of the sidechain sources
+        dynamics: None,
+        sends: sends_to_serializable(&track.sends),
+        output: track.output,
+        surround: track.surround,
+        inserts: inserts_to_serializable(&track.inserts),
+        patch: None,
+    }
+}
+
+/// Convert an instrument track to a project track
+///
+/// Plugin tracks are not saved: plugin instances are not part of the project.
+pub fn instrument_track_to_serializable(
+    track: &crate::audio::instruments::InstrumentTrack,
+    id: u32,
+) -> Option<Track> {
+    use crate::audio::instruments::TrackInstrument;
+
+    let (track_type, patch) = match track.instrument {
+        TrackInstrument::Synth(patch) => (
+            TrackType::Synth,
+            Some(TrackPatchSerializable {
+                waveform: patch.waveform,
+                oscillator2: patch.oscillator2,
+                unison: patch.unison,
+                anti_aliasing: patch.anti_aliasing,
+                cross_mod: patch.cross_mod,
+                noise: patch.noise,
+                mod_envelope: patch.mod_envelope,
+                filter_mod: patch.filter_mod,
+                mod_lfos: patch.mod_lfos,
+                adsr: patch.adsr,
+                lfo: patch.lfo,
+                filter: patch.filter,
+                portamento: patch.portamento,
+                poly_mode: patch.poly_mode,
+            }),
+        ),
+        TrackInstrument::Sampler => (TrackType::Sampler, None),
+        TrackInstrument::Plugin(_) => return None,
+    };
+    Some(Track {
+        id,
+        name: track.name.clone(),
+        pattern_id: Some(track.pattern.id),
+        color: None,
+        volume: track.strip.gain,
+        pan: track.strip.pan,
+        muted: track.strip.muted,
+        soloed: track.strip.soloed,
+        track_type,
+        clips: Vec::new(),
+        dynamics: None,
+        sends: Vec::new(),
+        output: crate::audio::outputs::TrackOutput::Master,
+        surround: None,
+        inserts: inserts_to_serializable(&track.inserts),
+        patch,
+    })
+}
+
+/// Instrument of a project track (None for audio and MIDI tracks)
+///
+/// Synth tracks without a patch play the project synth parameters.
+/// Modulation routings are not saved: they start disabled.
+pub fn track_instrument_from_serializable(
+    track: &Track,
+    synth_params: &SynthParams,
+) -> Option<crate::audio::instruments::TrackInstrument> {
+    use crate::audio::instruments::TrackInstrument;
+
+    match track.track_type {
+        TrackType::Synth => {
+            let patch = track.patch.unwrap_or(TrackPatchSerializable {
+                waveform: synth_params.waveform,
+                oscillator2: synth_params.oscillator2,
+                unison: synth_params.unison,
+                anti_aliasing: synth_params.anti_aliasing,
+                cross_mod: synth_params.cross_mod,
+                noise: synth_params.noise,
+                mod_envelope: synth_params.mod_envelope,
+                filter_mod: synth_params.filter_mod,
+                mod_lfos: synth_params.mod_lfos,
+                adsr: synth_params.adsr,
+                lfo: synth_params.lfo,
+                filter: synth_params.filter,
+                portamento: synth_params.portamento,
+                poly_mode: synth_params.poly_mode,
+            });
+            Some(TrackInstrument::Synth(crate::command::patch::SynthPatch {
+                waveform: patch.waveform,
+                oscillator2: patch.oscillator2,
+                unison: patch.unison,
+                anti_aliasing: patch.anti_aliasing,
+                cross_mod: patch.cross_mod,
+                noise: patch.noise,
+                mod_envelope: patch.mod_envelope,
+                filter_mod: patch.filter_mod,
+                mod_lfos: patch.mod_lfos,
+                adsr: patch.adsr,
+                lfo: patch.lfo,
+                filter: patch.filter,
+                portamento: patch.portamento,
+                poly_mode: patch.poly_mode,
+                mod_routings: [ModRouting::disabled(); MAX_ROUTINGS],
+            }))
+        }
+        TrackType::Sampler => Some(TrackInstrument::Sampler),
+        TrackType::Audio | TrackType::Midi => None,
+    }
+}
+
+/// Convert the clips of an arrangement to project clips
+///
+/// `project_track` gives the project track of a clip track; the clips of
+/// unsaved tracks (plugin tracks) are dropped.
+pub fn arrangement_to_serializable(
+    arrangement: &crate::sequencer::Arrangement,
+    project_track: impl Fn(u32) -> Option<u32>,
+) -> Vec<ClipSerializable> {
+    arrangement
+        .clips()
+        .iter()
+        .filter_map(|clip| {
+            Some(ClipSerializable {
+                track: project_track(clip.track)?,
+                pattern_id: clip.pattern_id(),
+                start_bar: clip.start_bar,
+                length_bars: clip.length_bars,
+            })
+        })
+        .collect()
+}
+
+/// Rebuild an arrangement from project clips
+///
+/// `clip_track` gives the track playing the clips of a project track. Clips of
+/// missing tracks or patterns are dropped; overlapping clips fail.
+pub fn arrangement_from_serializable(
+    clips: &[ClipSerializable],
+    patterns: &std::collections::HashMap<
+        crate::sequencer::pattern::PatternId,
+        crate::sequencer::Pattern,
+    >,
+    clip_track: impl Fn(u32) -> Option<u32>,
+) -> Result<crate::sequencer::Arrangement, String> {
+    use crate::sequencer::PatternClip;
+    use std::sync::Arc;
+
+    let mut arrangement = crate::sequencer::Arrangement::new();
+    // Clips of a pattern share it, like the ones placed in the editor
+    let mut shared = std::collections::HashMap::new();
+    for clip in clips {
+        let (Some(track), Some(pattern)) = (clip_track(clip.track), patterns.get(&clip.pattern_id))
+        else {
+            continue;
+        };
+        let pattern = shared
+            .entry(clip.pattern_id)
+            .or_insert_with(|| Arc::new(pattern.clone()));
+        arrangement.add_clip(PatternClip::new(
+            track,
+            clip.start_bar,
+            clip.length_bars,
+            pattern.clone(),
+        ))?;
+    }
+    Ok(arrangement)
+}
+
+/// Convert the slots of the session view to project slots
+///
+/// `project_track` gives the project track of a slot track; the slots of
+/// unsaved tracks (plugin tracks) are dropped.
+pub fn session_to_serializable(
+    session: &crate::sequencer::Session,
+    project_track: impl Fn(u32) -> Option<u32>,
+) -> Vec<SessionSlotSerializable> {
+    session
+        .slots()
+        .iter()
+        .filter_map(|slot| {
+            Some(SessionSlotSerializable {
+                track: project_track(slot.track)?,
+                scene: slot.scene,
+                pattern_id: slot.pattern.id,
+            })
+        })
+        .collect()
+}
+
+/// Rebuild the session view from project slots
+///
+/// `clip_track` gives the track playing the slots of a project track. Slots of
+/// missing tracks or patterns are dropped.
+pub fn session_from_serializable(
+    slots: &[SessionSlotSerializable],
+    quantization: crate::sequencer::LaunchQuantization,
+    patterns: &std::collections::HashMap<
+        crate::sequencer::pattern::PatternId,
+        crate::sequencer::Pattern,
+    >,
+    clip_track: impl Fn(u32) -> Option<u32>,
+) -> crate::sequencer::Session {
+    use std::sync::Arc;
+
+    let mut session = crate::sequencer::Session::new();
+    session.quantization = quantization;
+    let mut shared = std::collections::HashMap::new();
+    for slot in slots {
+        let (Some(track), Some(pattern)) = (clip_track(slot.track), patterns.get(&slot.pattern_id))
+        else {
+            continue;
+        };
+        let pattern = shared
+            .entry(slot.pattern_id)
+            .or_insert_with(|| Arc::new(pattern.clone()));
+        session.set_slot(track, slot.scene, pattern.clone());
+    }
+    session
+}
+
+/// Insert chain to save (None when it is left at its defaults)
+pub fn inserts_to_serializable(
+    inserts: &crate::audio::inserts::InsertChainParams,
+) -> Option<crate::audio::inserts::InsertChainParams> {
+    (*inserts != Default::default()).then_some(*inserts)
+}
+
+/// Convert track sends to their serialized form (unused sends are skipped)
+pub fn sends_to_serializable(
+    sends: &crate::audio::returns::TrackSends,
+) -> Vec<TrackSendSerializable> {
+    sends
+        .iter()
+        .enumerate()
+        .filter(|(_, send)| send.level > 0.0)
+        .map(|(bus, send)| TrackSendSerializable {
+            bus,
+            level: send.level,
+            pre_fader: send.pre_fader,
+        })
+        .collect()
+}
+
+/// Rebuild track sends from their serialized form (unknown slots are ignored)
+pub fn sends_from_serializable(
+    serializable: &[TrackSendSerializable],
+) -> crate::audio::returns::TrackSends {
+    let mut sends = crate::audio::returns::TrackSends::default();
+    for send in serializable {
+        if let Some(slot) = sends.get_mut(send.bus) {
+            slot.level = send.level.clamp(0.0, 1.0);
+            slot.pre_fader = send.pre_fader;
+        }
+    }
+    sends
+}
+
+/// Rebuild an audio clip from its serialized form, loading its source file
+///
+/// Clip positions are rescaled from the project rate to the engine rate
+/// (`sample_rate`).
+pub fn audio_clip_from_serializable(
+    serializable: &AudioClipSerializable,
+    project_sample_rate: f64,
+    sample_rate: f32,
+) -> Result<crate::audio::clip_player::AudioClip, String> {
+    let sample = crate::sampler::load_sample(&serializable.source_path).map_err(|e| {
+        format!(
+            "Failed to load audio clip {}: {}",
+            serializable.source_path.display(),
+            e
+        )
+    })?;
+    let ratio = sample_rate as f64 / project_sample_rate;
+    let scale = |samples: u64| (samples as f64 * ratio).round() as u64;
+
+    let mut clip = crate::audio::clip_player::AudioClip::new(
+        serializable.source_path.clone(),
+        std::sync::Arc::new(sample),
+        scale(serializable.start_samples),
+        sample_rate,
+    );
+    clip.name = serializable.name.clone();
+    clip.offset = scale(serializable.offset_samples);
+    clip.length = scale(serializable.length_samples);
+    clip.gain = serializable.gain;
+    clip.fade_in = scale(serializable.fade_in_samples);
+    clip.fade_out = scale(serializable.fade_out_samples);
+    clip.set_pitch(serializable.pitch)
+        .map_err(|e| format!("Invalid pitch of clip '{}': {}", clip.name, e))?;
+    clip.clamp(sample_rate);
+    if let Some(warp) = &serializable.warp {
+        let markers = warp
+            .markers
+            .iter()
+            .map(|marker| crate::audio::warp::WarpMarker {
+                source: scale(marker.source_samples),
+                beat: marker.beat,
+            })
+            .collect();
+        clip.warp = Some(
+            crate::audio::warp::ClipWarp::new(warp.source_bpm, markers)
+                .map_err(|e| format!("Invalid warp of clip '{}': {}", clip.name, e))?,
+        );
+    }
+    Ok(clip)
+}
+
+/// Plugin instance as saved in a project
+pub fn plugin_to_serializable(
+    path: PathBuf,
+    name: String,
+    state: crate::plugin::PluginState,
+) -> PluginInstanceSerializable {
+    PluginInstanceSerializable {
+        path,
+        name,
+        parameters: state.parameters.into_iter().collect(),
+        state: state.data,
+    }
+}
+
+/// State to restore on a reloaded plugin instance
+pub fn plugin_state_from_serializable(
+    plugin: &PluginInstanceSerializable,
+) -> crate::plugin::PluginState {
+    let mut state = crate::plugin::PluginState::new();
+    state.parameters = plugin
+        .parameters
+        .iter()
+        .map(|(id, value)| (id.clone(), *value))
+        .collect();
+    state.data = plugin.state.clone();
+    state
+}
+
+/// File of a plugin state blob in the project archive
+fn plugin_state_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join("plugins").join(format!("{}.bin", index))
+}
+
+/// Write the plugin state blobs of a project (`plugins/<index>.bin`)
+pub fn export_plugin_states_to_directory(
+    project: &Project,
+    target_dir: &Path,
+) -> Result<(), crate::project::ProjectError> {
+    for (index, plugin) in project.plugins.iter().enumerate() {
+        let Some(state) = &plugin.state else {
+            continue;
+        };
+        let path = plugin_state_path(target_dir, index);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, state).map_err(|e| {
+            crate::project::ProjectError::FileSystemError(format!(
+                "Failed to write state of plugin {}: {}",
+                plugin.name, e
+            ))
+        })?;
+    }
+    Ok(())
+}
+
+/// Read the plugin state blobs written by `export_plugin_states_to_directory`
+/// (a plugin without a blob keeps only its parameter values)
+pub fn import_plugin_states_from_directory(
+    project: &mut Project,
+    source_dir: &Path,
+) -> Result<(), crate::project::ProjectError> {
+    for (index, plugin) in project.plugins.iter_mut().enumerate() {
+        let path = plugin_state_path(source_dir, index);
+        plugin.state = if path.exists() {
+            Some(std::fs::read(&path).map_err(|e| {
+                crate::project::ProjectError::FileSystemError(format!(
+                    "Failed to read state of plugin {}: {}",
+                    plugin.name, e
+                ))
+            })?)
+        } else {
+            None
+        };
+    }
+    Ok(())
+}
+
 /// Export samples referenced by a project to audio directory
 pub fn export_samples_to_directory(
     project: &Project,
@@ -161,6 +610,144 @@ pub fn export_samples_to_directory(
     }
 }
 
+/// Archive directory of the embedded audio files ("collect and save")
+pub const EMBEDDED_AUDIO_DIR: &str = "audio/embedded";
+
+/// Directory the embedded audio files are extracted to when a project opens
+///
+/// Files under it came from an archive: every save embeds them again.
+pub fn embedded_audio_root() -> PathBuf {
+    std::env::temp_dir().join("mymusic_embedded_audio")
+}
+
+/// Audio files referenced by a project: sample bank mappings and audio clips
+fn audio_paths_mut(project: &mut Project) -> impl Iterator<Item = &mut PathBuf> {
+    let samples = project.sample_bank.iter_mut().flat_map(|bank| {
+        bank.samples
+            .iter_mut()
+            .map(|mapping| &mut mapping.sample_path)
+    });
+    let clips = project
+        .tracks
+        .values_mut()
+        .flat_map(|track| track.clips.iter_mut().map(|clip| &mut clip.source_path));
+    samples.chain(clips)
+}
+
+/// File name not used yet in the archive (`kick_2.wav` after `kick.wav`)
+fn unique_file_name(source: &Path, used: &mut std::collections::HashSet<String>) -> String {
+    let stem = source.file_stem().map_or_else(
+        || "audio".to_string(),
+        |stem| stem.to_string_lossy().to_string(),
+    );
+    let extension = source
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let mut name = format!("{}{}", stem, extension);
+    let mut index = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{}_{}{}", stem, index, extension);
+        index += 1;
+    }
+    name
+}
+
+/// Copy the audio files referenced by a project into `target_dir` (under
+/// `EMBEDDED_AUDIO_DIR`) and point the project to the copies
+///
+/// Files extracted from an archive (see `embedded_audio_root`) are always
+/// embedded; `collect` embeds every file (paths relative to `source_dir` or
+/// absolute). A file used several times is stored once. Returns the number of
+/// files written.
+pub fn embed_audio_files(
+    project: &mut Project,
+    source_dir: &Path,
+    target_dir: &Path,
+    collect: bool,
+) -> Result<usize, crate::project::ProjectError> {
+    let root = embedded_audio_root();
+    let audio_dir = target_dir.join(EMBEDDED_AUDIO_DIR);
+    let mut embedded: std::collections::HashMap<PathBuf, PathBuf> =
+        std::collections::HashMap::new();
+    let mut names = std::collections::HashSet::new();
+
+    for path in audio_paths_mut(project) {
+        let source = if path.is_absolute() {
+            path.clone()
+        } else {
+            source_dir.join(&*path)
+        };
+        if !collect && !source.starts_with(&root) {
+            continue;
+        }
+        if let Some(archive_path) = embedded.get(&source) {
+            *path = archive_path.clone();
+            continue;
+        }
+        if !source.is_file() {
+            return Err(crate::project::ProjectError::FileSystemError(format!(
+                "Audio file not found: {}",
+                source.display()
+            )));
+        }
+
+        let name = unique_file_name(&source, &mut names);
+        std::fs::create_dir_all(&audio_dir)?;
+        std::fs::copy(&source, audio_dir.join(&name)).map_err(|e| {
+            crate::project::ProjectError::FileSystemError(format!(
+                "Failed to embed {}: {}",
+                source.display(),
+                e
+            ))
+        })?;
+        let archive_path = Path::new(EMBEDDED_AUDIO_DIR).join(&name);
+        *path = archive_path.clone();
+        embedded.insert(source, archive_path);
+    }
+    Ok(embedded.len())
+}
+
+/// Point the audio files embedded in an extracted archive (`source_dir`) to
+/// copies under `embedded_audio_root`, which outlive the extraction directory
+///
+/// Returns the number of references rewritten.
+pub fn extract_embedded_audio(
+    project: &mut Project,
+    source_dir: &Path,
+) -> Result<usize, crate::project::ProjectError> {
+    let mut target_dir: Option<PathBuf> = None;
+    let mut count = 0;
+
+    for path in audio_paths_mut(project) {
+        let Ok(name) = path.strip_prefix(EMBEDDED_AUDIO_DIR) else {
+            continue;
+        };
+        // One directory per opened project: files of two projects never mix
+        let dir = match &target_dir {
+            Some(dir) => dir.clone(),
+            None => {
+                let dir = embedded_audio_root().join(uuid::Uuid::new_v4().to_string());
+                std::fs::create_dir_all(&dir)?;
+                target_dir.insert(dir).clone()
+            }
+        };
+        let file = dir.join(name);
+        if !file.exists() {
+            std::fs::copy(source_dir.join(&*path), &file).map_err(|e| {
+                crate::project::ProjectError::FileSystemError(format!(
+                    "Failed to extract embedded audio {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+        }
+        *path = file;
+        count += 1;
+    }
+    Ok(count)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -181,6 +768,179 @@ mod tests {
         assert_eq!(deserialized.metadata.name, "Untitled Project");
     }
 
+    #[test]
+    fn test_instrument_track_round_trip() {
+        use crate::audio::instruments::{InstrumentTrack, TrackInstrument};
+        use crate::audio::routing::ChannelStrip;
+        use crate::command::patch::SynthPatch;
+        use crate::synth::oscillator::WaveformType;
+        use std::sync::Arc;
+
+        let project = crate::project::Project::default();
+        let mut patch = SynthPatch {
+            waveform: WaveformType::Saw,
+            oscillator2: project.synth_params.oscillator2,
+            unison: project.synth_params.unison,
+            anti_aliasing: project.synth_params.anti_aliasing,
+            cross_mod: project.synth_params.cross_mod,
+            noise: project.synth_params.noise,
+            mod_envelope: project.synth_params.mod_envelope,
+            filter_mod: project.synth_params.filter_mod,
+            mod_lfos: project.synth_params.mod_lfos,
+            adsr: project.synth_params.adsr,
+            lfo: project.synth_params.lfo,
+            filter: project.synth_params.filter,
+            portamento: project.synth_params.portamento,
+            poly_mode: project.synth_params.poly_mode,
+            mod_routings: [ModRouting::disabled(); MAX_ROUTINGS],
+        };
+        patch.filter.cutoff = 800.0;
+        let pattern = crate::sequencer::Pattern::new_default(42, "Bass".to_string());
+        let mut track = InstrumentTrack {
+            id: 3,
+            name: "Bass".to_string(),
+            instrument: TrackInstrument::Synth(patch),
+            pattern: Arc::new(pattern),
+            strip: ChannelStrip {
+                gain: 0.7,
+                ..ChannelStrip::default()
+            },
+            inserts: Default::default(),
+        };
+
+        let saved = instrument_track_to_serializable(&track, 5).unwrap();
+        assert!(saved.inserts.is_none());
+        assert_eq!(saved.id, 5);
+        assert_eq!(saved.pattern_id, Some(42));
+        assert_eq!(saved.volume, 0.7);
+        let ron_data = ron_to_string(&saved).unwrap();
+        let loaded: Track = ron_from_str(&ron_data).unwrap();
+        match track_instrument_from_serializable(&loaded, &project.synth_params) {
+            Some(TrackInstrument::Synth(loaded)) => {
+                assert_eq!(loaded.waveform, WaveformType::Saw);
+                assert_eq!(loaded.filter.cutoff, 800.0);
+            }
+            other => panic!("expected a synth track, got {:?}", other),
+        }
+
+        track.instrument = TrackInstrument::Sampler;
+        let saved = instrument_track_to_serializable(&track, 5).unwrap();
+        assert_eq!(saved.track_type, TrackType::Sampler);
+        assert!(matches!(
+            track_instrument_from_serializable(&saved, &project.synth_params),
+            Some(TrackInstrument::Sampler)
+        ));
+    }
+
+    #[test]
+    fn test_return_buses_and_sends_round_trip() {
+        use crate::audio::outputs::TrackOutput;
+        use crate::audio::returns::{ReturnBusParams, ReturnEffect};
+
+        let mut project = crate::project::Project::default();
+        project.return_buses.push(ReturnBusSerializable {
+            slot: 2,
+            name: "Hall".to_string(),
+            params: ReturnBusParams::new(ReturnEffect::Reverb(
+                crate::synth::reverb::ReverbParams::default(),
+            )),
+        });
+        let mut track = crate::audio::clip_player::AudioTrack::new(0, "Vocal".to_string());
+        track.sends[2].level = 0.4;
+        track.sends[2].pre_fader = true;
+        track.output = TrackOutput::Direct(2);
+        track.inserts.reverb.enabled = true;
+        track.inserts.order.reverse();
+        let serializable = audio_track_to_serializable(&track, 1, 48000.0, 48000.0);
+        assert_eq!(serializable.sends.len(), 1);
+        project.tracks.insert(1, serializable);
+
+        let ron_data = serialize_to_ron(&project).unwrap();
+        let deserialized = deserialize_from_ron(&ron_data).unwrap();
+        assert_eq!(deserialized.return_buses[0].name, "Hall");
+        assert_eq!(
+            deserialized.return_buses[0].params,
+            project.return_buses[0].params
+        );
+        assert_eq!(
+            sends_from_serializable(&deserialized.tracks[&1].sends),
+            track.sends
+        );
+        assert_eq!(deserialized.tracks[&1].output, track.output);
+        assert_eq!(deserialized.tracks[&1].inserts, Some(track.inserts));
+    }
+
+    #[test]
+    fn test_arrangement_round_trip() {
+        use crate::sequencer::arrangement::{Arrangement, PatternClip, SYNTH_TRACK};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let verse = crate::sequencer::Pattern::new(7, "Verse".to_string(), 2);
+        let mut arrangement = Arrangement::new();
+        let shared = Arc::new(verse.clone());
+        for start_bar in [0, 2] {
+            arrangement
+                .add_clip(PatternClip::new(SYNTH_TRACK, start_bar, 2, shared.clone()))
+                .unwrap();
+        }
+        // Track 2 (a plugin track) is not saved
+        arrangement
+            .add_clip(PatternClip::new(2, 0, 1, shared))
+            .unwrap();
+
+        let saved =
+            arrangement_to_serializable(&arrangement, |track| (track == SYNTH_TRACK).then_some(0));
+        assert_eq!(saved.len(), 2);
+        assert_eq!(saved[1].start_bar, 2);
+        assert_eq!(saved[1].pattern_id, 7);
+
+        let patterns = HashMap::from([(7, verse)]);
+        let loaded = arrangement_from_serializable(&saved, &patterns, Some).unwrap();
+        let clips = loaded.track_clips(SYNTH_TRACK);
+        assert_eq!(clips.len(), 2);
+        assert!(Arc::ptr_eq(&clips[0].pattern, &clips[1].pattern));
+
+        // Clips of missing patterns are dropped, overlapping clips rejected
+        assert!(
+            arrangement_from_serializable(&saved, &HashMap::new(), Some)
+                .unwrap()
+                .is_empty()
+        );
+        let overlapping = [saved[0], saved[0]];
+        assert!(arrangement_from_serializable(&overlapping, &patterns, Some).is_err());
+    }
+
+    #[test]
+    fn test_session_round_trip() {
+        use crate::sequencer::arrangement::SYNTH_TRACK;
+        use crate::sequencer::{LaunchQuantization, Session};
+        use std::collections::HashMap;
+        use std::sync::Arc;
+
+        let groove = crate::sequencer::Pattern::new(3, "Groove".to_string(), 1);
+        let mut session = Session::new();
+        session.set_slot(SYNTH_TRACK, 1, Arc::new(groove.clone()));
+        session.set_slot(2, 0, Arc::new(groove.clone()));
+        let saved = session_to_serializable(&session, |track| (track == SYNTH_TRACK).then_some(0));
+        assert_eq!(
+            saved,
+            vec![SessionSlotSerializable {
+                track: 0,
+                scene: 1,
+                pattern_id: 3
+            }]
+        );
+
+        let patterns = HashMap::from([(3, groove)]);
+        let loaded = session_from_serializable(&saved, LaunchQuantization::Beat, &patterns, Some);
+        assert_eq!(loaded.quantization, LaunchQuantization::Beat);
+        assert_eq!(loaded.get(SYNTH_TRACK, 1).unwrap().pattern.name, "Groove");
+        let orphan =
+            session_from_serializable(&saved, LaunchQuantization::Bar, &HashMap::new(), Some);
+        assert!(orphan.is_empty());
+    }
+
     #[test]
     fn test_json_metadata_serialization() {
         let metadata = crate::project::types::ProjectMetadata {
@@ -270,6 +1030,10 @@ mod tests {
             loop_end: 44100,
             reverse: false,
             pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: Default::default(),
+            zone: None,
         };
 
         sample_bank.add_mapping(mapping);
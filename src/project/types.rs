@@ -2,8 +2,18 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::audio::inserts::InsertChainParams;
+use crate::audio::outputs::TrackOutput;
+use crate::audio::surround::{SpeakerLayout, SurroundPan};
+use crate::automation::AutomationLane;
+use crate::midi::cc_map::CcMapping;
 use crate::sampler::bank::SampleBank;
+use crate::sequencer::groove::PatternGroove;
 use crate::sequencer::note::NoteId;
+use crate::sequencer::session::LaunchQuantization;
+use crate::synth::master_chain::MasterChainParams;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 /// Project version information
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -14,7 +24,7 @@ pub struct ProjectVersion {
 }
 
 impl ProjectVersion {
-    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
         Self {
             major,
             minor,
@@ -23,7 +33,8 @@ impl ProjectVersion {
     }
 
     pub fn current() -> Self {
-        Self::new(1, 2, 0) // Version 1.2.0 (to test migration)
+        // Bumping the version registers its step in `migration::MIGRATIONS`
+        Self::new(1, 2, 0)
     }
 }
 
@@ -82,6 +93,9 @@ pub struct PatternSerializable {
     pub length_bars: u32,
     /// Serialized notes (only data needed for recreation)
     pub notes: Vec<SerializableNote>,
+    /// Swing and groove template played with the notes
+    #[serde(default, skip_serializing_if = "PatternGroove::is_straight")]
+    pub groove: PatternGroove,
 }
 
 /// Serializable note structure
@@ -97,6 +111,48 @@ pub struct SerializableNote {
     pub duration_samples: u64,
     /// MIDI velocity (0-127)
     pub velocity: u8,
+    /// Chance of playing on each pass (0.0 - 1.0)
+    #[serde(default = "certain", skip_serializing_if = "is_certain")]
+    pub probability: f32,
+    /// Micro-timing offset in ticks
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub offset_ticks: i32,
+}
+
+fn certain() -> f32 {
+    1.0
+}
+
+fn is_certain(probability: &f32) -> bool {
+    *probability >= 1.0
+}
+
+fn is_zero(ticks: &i32) -> bool {
+    *ticks == 0
+}
+
+/// Pattern clip of the song arrangement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClipSerializable {
+    /// Project track playing the clip
+    pub track: u32,
+    /// Pattern played from the clip start
+    pub pattern_id: crate::sequencer::pattern::PatternId,
+    /// First bar of the clip (0 = start of the song)
+    pub start_bar: u32,
+    /// Length in bars
+    pub length_bars: u32,
+}
+
+/// Pattern in a slot of the session view grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSlotSerializable {
+    /// Project track playing the slot
+    pub track: u32,
+    /// Row of the slot
+    pub scene: u32,
+    /// Pattern looped once launched
+    pub pattern_id: crate::sequencer::pattern::PatternId,
 }
 
 /// Track configuration
@@ -120,6 +176,141 @@ pub struct Track {
     pub soloed: bool,
     /// Track type
     pub track_type: TrackType,
+    /// Audio clips (audio tracks only)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clips: Vec<AudioClipSerializable>,
+    /// Compressor/gate insert
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dynamics: Option<TrackDynamicsSerializable>,
+    /// Sends to the return buses (only the sends in use)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sends: Vec<TrackSendSerializable>,
+    /// Master bus or hardware output pair
+    #[serde(default, skip_serializing_if = "TrackOutput::is_master")]
+    pub output: TrackOutput,
+    /// Surround panner (None: the stereo pan)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub surround: Option<SurroundPan>,
+    /// Insert effect chain (None: every effect bypassed at its defaults)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inserts: Option<InsertChainParams>,
+    /// Sound of a synth instrument track (None: the project synth parameters)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<TrackPatchSerializable>,
+}
+
+/// Serializable synth sound of an instrument track (see `command::patch::SynthPatch`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TrackPatchSerializable {
+    pub waveform: crate::synth::oscillator::WaveformType,
+    /// Second oscillator (off in projects saved before it existed)
+    #[serde(default)]
+    pub oscillator2: crate::synth::oscillator::Oscillator2Params,
+    /// Unison stack (one voice in projects saved before it existed)
+    #[serde(default)]
+    pub unison: crate::synth::oscillator::UnisonParams,
+    /// Band-limiting method (PolyBLEP in projects saved before it existed)
+    #[serde(default)]
+    pub anti_aliasing: crate::synth::oscillator::AntiAliasing,
+    /// Oscillator cross-modulation (off in projects saved before it existed)
+    #[serde(default)]
+    pub cross_mod: crate::synth::oscillator::CrossModParams,
+    /// Noise source (off in projects saved before it existed)
+    #[serde(default)]
+    pub noise: crate::synth::noise::NoiseParams,
+    /// Modulation envelope (default in projects saved before it existed)
+    #[serde(default)]
+    pub mod_envelope: crate::synth::envelope::AdsrParams,
+    /// Filter envelope amount, key tracking and drive (absent in older projects)
+    #[serde(default)]
+    pub filter_mod: crate::synth::filter::FilterModParams,
+    /// LFOs 2..LFO_COUNT of the modulation matrix
+    #[serde(default)]
+    pub mod_lfos: [crate::synth::lfo::LfoParams; crate::synth::lfo::LFO_COUNT - 1],
+    pub adsr: crate::synth::envelope::AdsrParams,
+    pub lfo: crate::synth::lfo::LfoParams,
+    pub filter: crate::synth::filter::FilterParams,
+    pub portamento: crate::synth::portamento::PortamentoParams,
+    pub poly_mode: crate::synth::poly_mode::PolyMode,
+}
+
+/// Serializable track insert (see `audio::clip_player::TrackDynamics`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackDynamicsSerializable {
+    pub params: crate::synth::dynamics::DynamicsParams,
+    /// Project track keying the detector (None: the track itself)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sidechain_track: Option<u32>,
+}
+
+/// Serializable send to a return bus (see `audio::returns::TrackSend`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackSendSerializable {
+    /// Return bus slot
+    pub bus: usize,
+    /// Send level (0.0 - 1.0)
+    pub level: f32,
+    /// Taken before the track gain and pan
+    pub pre_fader: bool,
+}
+
+/// Serializable return bus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReturnBusSerializable {
+    /// Slot addressed by the track sends
+    pub slot: usize,
+    /// Bus name
+    pub name: String,
+    pub params: crate::audio::returns::ReturnBusParams,
+}
+
+/// Serializable audio clip (positions in samples at the project sample rate)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioClipSerializable {
+    /// Clip name
+    pub name: String,
+    /// Imported audio file
+    pub source_path: PathBuf,
+    /// Timeline position of the clip start
+    pub start_samples: u64,
+    /// Time skipped at the beginning of the source
+    pub offset_samples: u64,
+    /// Played duration
+    pub length_samples: u64,
+    /// Linear gain (0.0 - 2.0)
+    pub gain: f32,
+    /// Fade-in duration
+    pub fade_in_samples: u64,
+    /// Fade-out duration
+    pub fade_out_samples: u64,
+    /// Tempo following (None: plays at the original speed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warp: Option<ClipWarpSerializable>,
+    /// Pitch shift in semitones (the length is kept)
+    #[serde(default, skip_serializing_if = "is_unshifted")]
+    pub pitch: f32,
+}
+
+fn is_unshifted(pitch: &f32) -> bool {
+    *pitch == 0.0
+}
+
+/// Serializable clip warp (see `audio::warp`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipWarpSerializable {
+    /// Tempo the source was recorded at (BPM)
+    pub source_bpm: f64,
+    /// Warp markers, sorted
+    pub markers: Vec<WarpMarkerSerializable>,
+}
+
+/// Serializable warp marker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarpMarkerSerializable {
+    /// Position in the source (samples at the project sample rate)
+    pub source_samples: u64,
+    /// Beats from the clip start
+    pub beat: f64,
 }
 
 /// Track type
@@ -129,7 +320,7 @@ pub enum TrackType {
     Synth,
     /// Sampler track
     Sampler,
-    /// Audio track (future)
+    /// Audio track (plays audio clips)
     Audio,
     /// MIDI track (future)
     Midi,
@@ -146,6 +337,30 @@ pub struct SynthParams {
     pub pan_spread: f32,
     /// Waveform type
     pub waveform: crate::synth::oscillator::WaveformType,
+    /// Second oscillator (off in projects saved before it existed)
+    #[serde(default)]
+    pub oscillator2: crate::synth::oscillator::Oscillator2Params,
+    /// Unison stack (one voice in projects saved before it existed)
+    #[serde(default)]
+    pub unison: crate::synth::oscillator::UnisonParams,
+    /// Band-limiting method (PolyBLEP in projects saved before it existed)
+    #[serde(default)]
+    pub anti_aliasing: crate::synth::oscillator::AntiAliasing,
+    /// Oscillator cross-modulation (off in projects saved before it existed)
+    #[serde(default)]
+    pub cross_mod: crate::synth::oscillator::CrossModParams,
+    /// Noise source (off in projects saved before it existed)
+    #[serde(default)]
+    pub noise: crate::synth::noise::NoiseParams,
+    /// Modulation envelope (default in projects saved before it existed)
+    #[serde(default)]
+    pub mod_envelope: crate::synth::envelope::AdsrParams,
+    /// Filter envelope amount, key tracking and drive (absent in older projects)
+    #[serde(default)]
+    pub filter_mod: crate::synth::filter::FilterModParams,
+    /// LFOs 2..LFO_COUNT of the modulation matrix
+    #[serde(default)]
+    pub mod_lfos: [crate::synth::lfo::LfoParams; crate::synth::lfo::LFO_COUNT - 1],
     /// ADSR envelope parameters
     pub adsr: crate::synth::envelope::AdsrParams,
     /// LFO parameters
@@ -189,6 +404,52 @@ pub struct Project {
     pub synth_params: SynthParams,
     /// Sample bank configuration (if any)
     pub sample_bank: Option<SampleBank>,
+    /// Return buses fed by the track sends
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub return_buses: Vec<ReturnBusSerializable>,
+    /// Speaker layout of the mix and of the exports
+    #[serde(default, skip_serializing_if = "SpeakerLayout::is_stereo")]
+    pub speaker_layout: SpeakerLayout,
+    /// Master bus effect chain (applied to the mix and to project exports)
+    #[serde(default)]
+    pub master_chain: MasterChainParams,
+    /// Automation lanes (only lanes with points)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub automation: Vec<AutomationLane>,
+    /// Plugin instances, reloaded with their state when the project opens
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<PluginInstanceSerializable>,
+    /// MIDI controllers mapped to synth parameters (MIDI learn)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cc_mappings: Vec<CcMapping>,
+    /// Pattern clips of the song arrangement
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arrangement: Vec<ClipSerializable>,
+    /// Whether the tracks play the arrangement instead of looping their pattern
+    #[serde(default)]
+    pub song_mode: bool,
+    /// Patterns in the slots of the session view
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub session: Vec<SessionSlotSerializable>,
+    /// Boundary the clip launches wait for
+    #[serde(default)]
+    pub launch_quantization: LaunchQuantization,
+}
+
+/// Serializable plugin instance (plugins of the Plugins tab)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginInstanceSerializable {
+    /// Plugin file (.clap bundle or library)
+    pub path: PathBuf,
+    /// Instance name
+    pub name: String,
+    /// Parameter values by parameter id (applied when there is no state blob)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, f64>,
+    /// State blob written by the plugin, stored in the project archive as
+    /// `plugins/<index>.bin` (see `serialization::export_plugin_states_to_directory`)
+    #[serde(skip)]
+    pub state: Option<Vec<u8>>,
 }
 
 impl Default for Project {
@@ -219,6 +480,14 @@ impl Default for Project {
                 pan: 0.0,
                 pan_spread: 0.0,
                 waveform: crate::synth::oscillator::WaveformType::Sine,
+                oscillator2: crate::synth::oscillator::Oscillator2Params::default(),
+                unison: crate::synth::oscillator::UnisonParams::default(),
+                anti_aliasing: crate::synth::oscillator::AntiAliasing::default(),
+                cross_mod: crate::synth::oscillator::CrossModParams::default(),
+                noise: crate::synth::noise::NoiseParams::default(),
+                mod_envelope: crate::synth::envelope::AdsrParams::default(),
+                filter_mod: crate::synth::filter::FilterModParams::default(),
+                mod_lfos: Default::default(),
                 adsr: crate::synth::envelope::AdsrParams::new(0.01, 0.1, 0.7, 0.3),
                 lfo: crate::synth::lfo::LfoParams::default(),
                 filter: crate::synth::filter::FilterParams::default(),
@@ -233,6 +502,16 @@ impl Default for Project {
                 },
             },
             sample_bank: None,
+            return_buses: Vec::new(),
+            speaker_layout: SpeakerLayout::Stereo,
+            master_chain: MasterChainParams::default(),
+            automation: Vec::new(),
+            plugins: Vec::new(),
+            cc_mappings: Vec::new(),
+            arrangement: Vec::new(),
+            song_mode: false,
+            session: Vec::new(),
+            launch_quantization: LaunchQuantization::default(),
         }
     }
 }
@@ -290,7 +569,10 @@ mod tests {
                 start_samples: 0,
                 duration_samples: 48000,
                 velocity: 100,
+                probability: 1.0,
+                offset_ticks: 0,
             }],
+            groove: PatternGroove::default(),
         };
 
         assert_eq!(pattern.id, 42);
@@ -314,6 +596,13 @@ mod tests {
             muted: false,
             soloed: false,
             track_type: TrackType::Synth,
+            clips: Vec::new(),
+            dynamics: None,
+            sends: Vec::new(),
+            output: TrackOutput::Master,
+            surround: None,
+            inserts: None,
+            patch: None,
         };
 
         let sampler_track = Track {
@@ -326,6 +615,13 @@ mod tests {
             muted: false,
             soloed: true,
             track_type: TrackType::Sampler,
+            clips: Vec::new(),
+            dynamics: None,
+            sends: Vec::new(),
+            output: TrackOutput::Master,
+            surround: None,
+            inserts: None,
+            patch: None,
         };
 
         assert_eq!(synth_track.track_type, TrackType::Synth);
@@ -341,6 +637,14 @@ mod tests {
             pan: 0.0,
             pan_spread: 0.0,
             waveform: crate::synth::oscillator::WaveformType::Sine,
+            oscillator2: crate::synth::oscillator::Oscillator2Params::default(),
+            unison: crate::synth::oscillator::UnisonParams::default(),
+            anti_aliasing: crate::synth::oscillator::AntiAliasing::default(),
+            cross_mod: crate::synth::oscillator::CrossModParams::default(),
+            noise: crate::synth::noise::NoiseParams::default(),
+            mod_envelope: crate::synth::envelope::AdsrParams::default(),
+            filter_mod: crate::synth::filter::FilterModParams::default(),
+            mod_lfos: Default::default(),
             adsr: crate::synth::envelope::AdsrParams::new(0.01, 0.1, 0.7, 0.3),
             lfo: crate::synth::lfo::LfoParams::default(),
             filter: crate::synth::filter::FilterParams::default(),
@@ -0,0 +1,238 @@
+// Remote actions - What a network controller can do
+//
+// Protocol servers (OSC, ...) and control surfaces (see
+// `midi::control_surface`) decode incoming messages into `RemoteAction`s on
+// their own thread; the frontend applies them to its `DawState` on the UI
+// thread, like any other control. Remote changes are not undoable: faders
+// on a tablet send dozens of values per second and would flood the history.
+
+use crate::audio::clip_player::{AudioTrack, MAX_CLIP_GAIN};
+use crate::command::DawState;
+use crate::command::trait_def::{CommandError, CommandResult};
+use crate::messaging::command::Command;
+use crate::midi::event::{MidiEvent, MidiEventTimed};
+use crate::synth::filter::FilterParams;
+
+/// Action requested by a remote controller
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RemoteAction {
+    Play,
+    Stop,
+    /// Toggle recording (the frontend owns the recorder)
+    Record,
+    /// Move the playhead back to the start
+    Rewind,
+    SetTempo(f64),
+    SetVolume(f32),
+    SetCutoff(f32),
+    SetResonance(f32),
+    SetAttack(f32),
+    SetDecay(f32),
+    SetSustain(f32),
+    SetRelease(f32),
+    NoteOn {
+        note: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        note: u8,
+    },
+    SetMetronomeEnabled(bool),
+    SetMetronomeVolume(f32),
+    /// Linear gain of an audio track (0.0 to `MAX_CLIP_GAIN`)
+    SetTrackVolume {
+        track_id: u32,
+        gain: f32,
+    },
+    SetTrackPan {
+        track_id: u32,
+        pan: f32,
+    },
+    SetTrackMute {
+        track_id: u32,
+        muted: bool,
+    },
+}
+
+impl RemoteAction {
+    /// Apply the action to the DAW state and the audio thread
+    ///
+    /// Values are clamped to the same ranges as the UI controls. Transport
+    /// actions only send `SetTransportPlaying` or `SetTransportPosition`
+    /// (`Record` sends nothing): frontends owning a sequencer should also
+    /// start/stop it. Audio tracks are looked up in `DawState::audio_tracks`.
+    pub fn apply(&self, state: &mut DawState) -> CommandResult<()> {
+        match *self {
+            RemoteAction::Play => send(state, Command::SetTransportPlaying(true)),
+            RemoteAction::Stop => send(state, Command::SetTransportPlaying(false)),
+            RemoteAction::Record => Ok(()),
+            RemoteAction::Rewind => send(state, Command::SetTransportPosition(0)),
+            RemoteAction::SetTempo(bpm) => state.set_tempo(bpm),
+            RemoteAction::SetVolume(volume) => {
+                state.volume = finite(volume)?.clamp(0.0, 1.0);
+                send(state, Command::SetVolume(state.volume))
+            }
+            RemoteAction::SetCutoff(cutoff) => {
+                let mut filter = state.filter;
+                filter.cutoff = finite(cutoff)?.clamp(20.0, 20000.0);
+                send_filter(state, filter)
+            }
+            RemoteAction::SetResonance(resonance) => {
+                let mut filter = state.filter;
+                filter.resonance = finite(resonance)?.clamp(0.5, 20.0);
+                send_filter(state, filter)
+            }
+            RemoteAction::SetAttack(value) => {
+                state.adsr.attack = finite(value)?.clamp(0.001, 5.0);
+                send(state, Command::SetAdsr(state.adsr))
+            }
+            RemoteAction::SetDecay(value) => {
+                state.adsr.decay = finite(value)?.clamp(0.001, 5.0);
+                send(state, Command::SetAdsr(state.adsr))
+            }
+            RemoteAction::SetSustain(value) => {
+                state.adsr.sustain = finite(value)?.clamp(0.0, 1.0);
+                send(state, Command::SetAdsr(state.adsr))
+            }
+            RemoteAction::SetRelease(value) => {
+                state.adsr.release = finite(value)?.clamp(0.001, 5.0);
+                send(state, Command::SetAdsr(state.adsr))
+            }
+            RemoteAction::NoteOn { note, velocity } => {
+                send_midi(state, MidiEvent::NoteOn { note, velocity })
+            }
+            RemoteAction::NoteOff { note } => send_midi(state, MidiEvent::NoteOff { note }),
+            RemoteAction::SetMetronomeEnabled(enabled) => state.set_metronome_enabled(enabled),
+            RemoteAction::SetMetronomeVolume(volume) => state.set_metronome_volume(volume),
+            RemoteAction::SetTrackVolume { track_id, gain } => {
+                let gain = finite(gain)?.clamp(0.0, MAX_CLIP_GAIN);
+                edit_track(state, track_id, |track| track.gain = gain)
+            }
+            RemoteAction::SetTrackPan { track_id, pan } => {
+                let pan = finite(pan)?.clamp(-1.0, 1.0);
+                edit_track(state, track_id, |track| track.pan = pan)
+            }
+            RemoteAction::SetTrackMute { track_id, muted } => {
+                edit_track(state, track_id, |track| track.muted = muted)
+            }
+        }
+    }
+
+    /// Whether the action starts, stops or moves the transport
+    pub fn is_transport(&self) -> bool {
+        matches!(
+            self,
+            RemoteAction::Play | RemoteAction::Stop | RemoteAction::Record | RemoteAction::Rewind
+        )
+    }
+}
+
+fn finite(value: f32) -> CommandResult<f32> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(CommandError::InvalidState(format!(
+            "Invalid remote value: {}",
+            value
+        )))
+    }
+}
+
+fn send(state: &mut DawState, command: Command) -> CommandResult<()> {
+    if state.send_to_audio(command) {
+        Ok(())
+    } else {
+        Err(ringbuffer_full())
+    }
+}
+
+fn send_filter(state: &mut DawState, filter: FilterParams) -> CommandResult<()> {
+    state.filter = filter;
+    if state.send_filter(filter) {
+        Ok(())
+    } else {
+        Err(ringbuffer_full())
+    }
+}
+
+fn send_midi(state: &mut DawState, event: MidiEvent) -> CommandResult<()> {
+    send(
+        state,
+        Command::Midi(MidiEventTimed {
+            event,
+            samples_from_now: 0,
+        }),
+    )
+}
+
+fn edit_track(
+    state: &mut DawState,
+    track_id: u32,
+    edit: impl FnOnce(&mut AudioTrack),
+) -> CommandResult<()> {
+    let track = state.audio_tracks.track_mut(track_id).ok_or_else(|| {
+        CommandError::InvalidState(format!("Audio track not found: {}", track_id))
+    })?;
+    edit(track);
+    state.send_audio_tracks().map_err(|_| ringbuffer_full())
+}
+
+fn ringbuffer_full() -> CommandError {
+    CommandError::ExecutionFailed(
+        "Failed to send remote command to audio thread (ringbuffer full)".into(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messaging::channels::create_command_channel;
+    use ringbuf::traits::Consumer;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_apply_updates_state_and_audio() {
+        let (tx, mut rx) = create_command_channel(64);
+        let mut state = DawState::new(Arc::new(Mutex::new(tx)));
+
+        RemoteAction::SetVolume(2.0).apply(&mut state).unwrap();
+        assert_eq!(state.volume, 1.0);
+        RemoteAction::SetSustain(0.25).apply(&mut state).unwrap();
+        assert_eq!(state.adsr.sustain, 0.25);
+        RemoteAction::SetCutoff(5.0).apply(&mut state).unwrap();
+        assert_eq!(state.filter.cutoff, 20.0);
+        RemoteAction::Play.apply(&mut state).unwrap();
+
+        let mut commands = Vec::new();
+        while let Some(command) = rx.try_pop() {
+            commands.push(command);
+        }
+        assert!(matches!(commands[0], Command::SetVolume(v) if v == 1.0));
+        assert!(matches!(commands[1], Command::SetAdsr(adsr) if adsr.sustain == 0.25));
+        assert!(
+            commands
+                .iter()
+                .any(|c| matches!(c, Command::SetTransportPlaying(true)))
+        );
+
+        assert!(RemoteAction::SetTempo(5.0).apply(&mut state).is_err());
+        assert!(RemoteAction::SetVolume(f32::NAN).apply(&mut state).is_err());
+
+        let track_id = state.audio_tracks.add_track("Audio 1".to_string());
+        let gain = RemoteAction::SetTrackVolume {
+            track_id,
+            gain: 9.0,
+        };
+        gain.apply(&mut state).unwrap();
+        assert_eq!(
+            state.audio_tracks.track(track_id).unwrap().gain,
+            MAX_CLIP_GAIN
+        );
+        assert!(matches!(rx.try_pop(), Some(Command::SetAudioTracks(_))));
+        let mute = RemoteAction::SetTrackMute {
+            track_id: track_id + 1,
+            muted: true,
+        };
+        assert!(mute.apply(&mut state).is_err());
+    }
+}
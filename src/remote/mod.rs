@@ -0,0 +1,15 @@
+// Remote control - Drive the DAW from the network
+//
+// Controllers talk to a protocol server running on its own thread. The OSC
+// server decodes messages into `RemoteAction`s that the frontend applies to
+// its `DawState`, exactly like a UI control would; the WebSocket server
+// carries JSON commands to a handler provided by the frontend and streams
+// its telemetry back.
+
+pub mod action;
+pub mod osc;
+pub mod websocket;
+
+pub use action::RemoteAction;
+pub use osc::{DEFAULT_OSC_PORT, OscArg, OscMessage, OscServer, decode_packet};
+pub use websocket::{CommandHandler, DEFAULT_WEBSOCKET_PORT, WebSocketServer};
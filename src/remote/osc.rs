@@ -0,0 +1,519 @@
+// OSC server - Open Sound Control over UDP
+//
+// Tablets (TouchOSC, Lemur...) and hardware controllers send OSC messages to
+// a UDP port. A background thread decodes the packets (messages and bundles,
+// OSC 1.0) and maps known addresses to `RemoteAction`s; the frontend polls
+// them every frame. Unknown addresses and malformed packets are ignored.
+//
+// Address map (values in natural units, like the UI sliders):
+//   /transport/play             start (a 0 argument, i.e. button release, is ignored)
+//   /transport/stop             stop
+//   /transport/tempo f          tempo in BPM (20-999)
+//   /synth/volume f             master volume (0-1)
+//   /synth/cutoff f             filter cutoff in Hz (20-20000)
+//   /synth/resonance f          filter resonance (0.5-20)
+//   /synth/attack|decay|release f   envelope times in seconds
+//   /synth/sustain f            envelope sustain level (0-1)
+//   /metronome/enabled i        metronome on/off
+//   /metronome/volume f         metronome volume (0-1)
+//   /note/on i [i]              note on (velocity defaults to 100, 0 means note off)
+//   /note/off i                 note off
+
+use super::action::RemoteAction;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError, sync_channel};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Default UDP port (TouchOSC's default outgoing port)
+pub const DEFAULT_OSC_PORT: u16 = 9000;
+
+/// Actions buffered between the network thread and the frontend
+const ACTION_QUEUE_CAPACITY: usize = 1024;
+
+/// Largest UDP datagram accepted
+const MAX_PACKET_SIZE: usize = 65536;
+
+/// Socket read timeout, bounds how long `stop()` waits for the thread
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Nested bundles deeper than this are rejected
+const MAX_BUNDLE_DEPTH: usize = 8;
+
+/// OSC argument
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Blob(Vec<u8>),
+    Bool(bool),
+    Nil,
+}
+
+impl OscArg {
+    /// Numeric value (booleans count as 0/1)
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            OscArg::Int(v) => Some(v as f64),
+            OscArg::Long(v) => Some(v as f64),
+            OscArg::Float(v) => Some(v as f64),
+            OscArg::Double(v) => Some(v),
+            OscArg::Bool(v) => Some(if v { 1.0 } else { 0.0 }),
+            _ => None,
+        }
+    }
+}
+
+/// OSC message (address pattern and arguments)
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    fn number(&self, index: usize) -> Option<f64> {
+        self.args.get(index).and_then(OscArg::as_f64)
+    }
+
+    /// Remote action for this message, if the address is known
+    pub fn to_action(&self) -> Option<RemoteAction> {
+        let value = self.number(0);
+        let float = || value.map(|v| v as f32);
+        let note = |v: f64| v.clamp(0.0, 127.0) as u8;
+
+        match self.address.as_str() {
+            // Buttons send 1 on press and 0 on release: only react to the press
+            "/transport/play" => value.is_none_or(|v| v != 0.0).then_some(RemoteAction::Play),
+            "/transport/stop" => value.is_none_or(|v| v != 0.0).then_some(RemoteAction::Stop),
+            "/transport/tempo" => value.map(RemoteAction::SetTempo),
+            "/synth/volume" => float().map(RemoteAction::SetVolume),
+            "/synth/cutoff" => float().map(RemoteAction::SetCutoff),
+            "/synth/resonance" => float().map(RemoteAction::SetResonance),
+            "/synth/attack" => float().map(RemoteAction::SetAttack),
+            "/synth/decay" => float().map(RemoteAction::SetDecay),
+            "/synth/sustain" => float().map(RemoteAction::SetSustain),
+            "/synth/release" => float().map(RemoteAction::SetRelease),
+            "/metronome/enabled" => value.map(|v| RemoteAction::SetMetronomeEnabled(v != 0.0)),
+            "/metronome/volume" => float().map(RemoteAction::SetMetronomeVolume),
+            "/note/on" => {
+                let note = note(value?);
+                let velocity = self.number(1).map_or(100, |v| v.clamp(0.0, 127.0) as u8);
+                Some(if velocity == 0 {
+                    RemoteAction::NoteOff { note }
+                } else {
+                    RemoteAction::NoteOn { note, velocity }
+                })
+            }
+            "/note/off" => Some(RemoteAction::NoteOff { note: note(value?) }),
+            _ => None,
+        }
+    }
+}
+
+/// Decode an OSC packet (a message or a bundle) into its messages
+///
+/// Bundle time tags are ignored: messages are applied as soon as they arrive.
+pub fn decode_packet(packet: &[u8]) -> Result<Vec<OscMessage>, String> {
+    let mut messages = Vec::new();
+    decode_into(packet, 0, &mut messages)?;
+    Ok(messages)
+}
+
+fn decode_into(packet: &[u8], depth: usize, messages: &mut Vec<OscMessage>) -> Result<(), String> {
+    if packet.starts_with(b"#bundle\0") {
+        if depth >= MAX_BUNDLE_DEPTH {
+            return Err("OSC bundles nested too deeply".to_string());
+        }
+        // "#bundle\0" + 8-byte time tag, then (size, element) pairs
+        let mut reader = Reader::new(packet.get(16..).ok_or("Truncated OSC bundle")?);
+        while !reader.is_empty() {
+            let size = reader.i32()?;
+            let size = usize::try_from(size).map_err(|_| "Invalid OSC bundle element size")?;
+            decode_into(reader.bytes(size)?, depth + 1, messages)?;
+        }
+        Ok(())
+    } else {
+        messages.push(decode_message(packet)?);
+        Ok(())
+    }
+}
+
+fn decode_message(packet: &[u8]) -> Result<OscMessage, String> {
+    let mut reader = Reader::new(packet);
+    let address = reader.string()?;
+    if !address.starts_with('/') {
+        return Err(format!("Invalid OSC address: {}", address));
+    }
+
+    // Type tags are optional in very old implementations: no tags, no arguments
+    let tags = if reader.is_empty() {
+        String::new()
+    } else {
+        reader.string()?
+    };
+    let tags = tags.strip_prefix(',').unwrap_or("");
+
+    let mut args = Vec::with_capacity(tags.len());
+    for tag in tags.chars() {
+        let arg = match tag {
+            'i' => OscArg::Int(reader.i32()?),
+            'h' => OscArg::Long(i64::from_be_bytes(reader.array()?)),
+            'f' => OscArg::Float(f32::from_be_bytes(reader.array()?)),
+            'd' => OscArg::Double(f64::from_be_bytes(reader.array()?)),
+            's' | 'S' => OscArg::String(reader.string()?),
+            'b' => {
+                let size = usize::try_from(reader.i32()?).map_err(|_| "Invalid OSC blob size")?;
+                let blob = reader.bytes(size)?.to_vec();
+                reader.align()?;
+                OscArg::Blob(blob)
+            }
+            'T' => OscArg::Bool(true),
+            'F' => OscArg::Bool(false),
+            'N' | 'I' => OscArg::Nil,
+            // Time tags and colors/MIDI messages carry 4 or 8 opaque bytes
+            't' => {
+                reader.bytes(8)?;
+                OscArg::Nil
+            }
+            'c' | 'r' | 'm' => OscArg::Int(reader.i32()?),
+            _ => return Err(format!("Unsupported OSC type tag: {}", tag)),
+        };
+        args.push(arg);
+    }
+
+    Ok(OscMessage { address, args })
+}
+
+/// Big-endian reader over a 4-byte aligned OSC buffer
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or("Truncated OSC packet")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    /// Skip the padding up to the next multiple of 4
+    fn align(&mut self) -> Result<(), String> {
+        let padding = (4 - self.pos % 4) % 4;
+        self.bytes(padding).map(|_| ())
+    }
+
+    /// Null-terminated string padded to 4 bytes
+    fn string(&mut self) -> Result<String, String> {
+        let rest = &self.data[self.pos..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or("Unterminated OSC string")?;
+        let string = std::str::from_utf8(&rest[..len])
+            .map_err(|_| "Invalid UTF-8 in OSC string")?
+            .to_string();
+        self.bytes(len + 1)?;
+        self.align()?;
+        Ok(string)
+    }
+}
+
+/// OSC server listening on a UDP port
+///
+/// The socket is read on a background thread; decoded actions are queued
+/// until `poll()`. Dropping the server stops the thread.
+pub struct OscServer {
+    local_addr: SocketAddr,
+    actions: Receiver<RemoteAction>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl OscServer {
+    /// Bind a UDP socket (e.g. "0.0.0.0:9000") and start listening
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, String> {
+        let socket =
+            UdpSocket::bind(addr).map_err(|e| format!("Failed to bind OSC socket: {}", e))?;
+        socket
+            .set_read_timeout(Some(READ_TIMEOUT))
+            .map_err(|e| format!("Failed to configure OSC socket: {}", e))?;
+        let local_addr = socket
+            .local_addr()
+            .map_err(|e| format!("Failed to read OSC socket address: {}", e))?;
+
+        let (tx, actions) = sync_channel(ACTION_QUEUE_CAPACITY);
+        let running = Arc::new(AtomicBool::new(true));
+        let thread_running = running.clone();
+        let thread = thread::Builder::new()
+            .name("osc-server".to_string())
+            .spawn(move || receive_loop(socket, tx, thread_running))
+            .map_err(|e| format!("Failed to spawn OSC thread: {}", e))?;
+
+        println!("🎛️  OSC server listening on {}", local_addr);
+        Ok(Self {
+            local_addr,
+            actions,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Address the server is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Take the actions received since the last call
+    pub fn poll(&self) -> Vec<RemoteAction> {
+        self.actions.try_iter().collect()
+    }
+
+    /// Stop the network thread
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for OscServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn receive_loop(socket: UdpSocket, tx: SyncSender<RemoteAction>, running: Arc<AtomicBool>) {
+    let mut buffer = vec![0u8; MAX_PACKET_SIZE];
+    while running.load(Ordering::Relaxed) {
+        let len = match socket.recv_from(&mut buffer) {
+            Ok((len, _)) => len,
+            // Timeout: check the running flag again
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue;
+            }
+            Err(e) => {
+                eprintln!("OSC receive error: {}", e);
+                continue;
+            }
+        };
+
+        let messages = match decode_packet(&buffer[..len]) {
+            Ok(messages) => messages,
+            Err(e) => {
+                eprintln!("Ignoring OSC packet: {}", e);
+                continue;
+            }
+        };
+        for action in messages.iter().filter_map(OscMessage::to_action) {
+            match tx.try_send(action) {
+                Ok(()) => {}
+                // The frontend is not polling fast enough: drop the action
+                Err(TrySendError::Full(_)) => {}
+                Err(TrySendError::Disconnected(_)) => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn pad(bytes: &mut Vec<u8>) {
+        while !bytes.len().is_multiple_of(4) {
+            bytes.push(0);
+        }
+    }
+
+    fn encode_string(bytes: &mut Vec<u8>, s: &str) {
+        bytes.extend_from_slice(s.as_bytes());
+        bytes.push(0);
+        pad(bytes);
+    }
+
+    fn encode(address: &str, args: &[OscArg]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        encode_string(&mut bytes, address);
+        let mut tags = String::from(",");
+        let mut data = Vec::new();
+        for arg in args {
+            match arg {
+                OscArg::Int(v) => {
+                    tags.push('i');
+                    data.extend_from_slice(&v.to_be_bytes());
+                }
+                OscArg::Float(v) => {
+                    tags.push('f');
+                    data.extend_from_slice(&v.to_be_bytes());
+                }
+                OscArg::Double(v) => {
+                    tags.push('d');
+                    data.extend_from_slice(&v.to_be_bytes());
+                }
+                OscArg::String(s) => {
+                    tags.push('s');
+                    encode_string(&mut data, s);
+                }
+                OscArg::Blob(b) => {
+                    tags.push('b');
+                    data.extend_from_slice(&(b.len() as i32).to_be_bytes());
+                    data.extend_from_slice(b);
+                    pad(&mut data);
+                }
+                OscArg::Bool(true) => tags.push('T'),
+                OscArg::Bool(false) => tags.push('F'),
+                _ => unimplemented!(),
+            }
+        }
+        encode_string(&mut bytes, &tags);
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    fn bundle(elements: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = b"#bundle\0".to_vec();
+        bytes.extend_from_slice(&1u64.to_be_bytes()); // "immediately"
+        for element in elements {
+            bytes.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            bytes.extend_from_slice(element);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_decode_message_arguments() {
+        let args = vec![
+            OscArg::Int(-3),
+            OscArg::Float(0.5),
+            OscArg::String("fader".to_string()),
+            OscArg::Blob(vec![1, 2, 3, 4, 5]),
+            OscArg::Bool(true),
+            OscArg::Double(120.25),
+        ];
+        let messages = decode_packet(&encode("/synth/cutoff", &args)).unwrap();
+        assert_eq!(
+            messages,
+            vec![OscMessage {
+                address: "/synth/cutoff".to_string(),
+                args,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_decode_nested_bundle_and_malformed_packets() {
+        let inner = bundle(&[encode("/transport/stop", &[])]);
+        let packet = bundle(&[encode("/transport/play", &[OscArg::Int(1)]), inner]);
+        let addresses: Vec<_> = decode_packet(&packet)
+            .unwrap()
+            .into_iter()
+            .map(|m| m.address)
+            .collect();
+        assert_eq!(addresses, ["/transport/play", "/transport/stop"]);
+
+        let message = encode("/synth/volume", &[OscArg::Float(0.5)]);
+        assert!(decode_packet(&message[..message.len() - 2]).is_err());
+        assert!(decode_packet(b"no/slash\0\0\0\0").is_err());
+        assert!(decode_packet(&bundle(&[vec![0; 3]])[..20]).is_err());
+    }
+
+    #[test]
+    fn test_address_mapping() {
+        let action = |address: &str, args: &[OscArg]| {
+            decode_packet(&encode(address, args)).unwrap()[0].to_action()
+        };
+
+        assert_eq!(action("/transport/play", &[]), Some(RemoteAction::Play));
+        assert_eq!(
+            action("/transport/play", &[OscArg::Float(1.0)]),
+            Some(RemoteAction::Play)
+        );
+        // Button release
+        assert_eq!(action("/transport/stop", &[OscArg::Float(0.0)]), None);
+        assert_eq!(
+            action("/transport/tempo", &[OscArg::Int(128)]),
+            Some(RemoteAction::SetTempo(128.0))
+        );
+        assert_eq!(
+            action("/synth/cutoff", &[OscArg::Float(800.0)]),
+            Some(RemoteAction::SetCutoff(800.0))
+        );
+        assert_eq!(
+            action("/metronome/enabled", &[OscArg::Bool(false)]),
+            Some(RemoteAction::SetMetronomeEnabled(false))
+        );
+        assert_eq!(
+            action("/note/on", &[OscArg::Int(60), OscArg::Float(90.0)]),
+            Some(RemoteAction::NoteOn {
+                note: 60,
+                velocity: 90
+            })
+        );
+        assert_eq!(
+            action("/note/on", &[OscArg::Int(60), OscArg::Int(0)]),
+            Some(RemoteAction::NoteOff { note: 60 })
+        );
+        assert_eq!(action("/synth/cutoff", &[]), None);
+        assert_eq!(action("/unknown", &[OscArg::Int(1)]), None);
+    }
+
+    #[test]
+    fn test_server_receives_udp_messages() {
+        let server = OscServer::bind("127.0.0.1:0").unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let target = server.local_addr();
+
+        client.send_to(b"garbage", target).unwrap();
+        client
+            .send_to(&encode("/synth/volume", &[OscArg::Float(0.25)]), target)
+            .unwrap();
+        client
+            .send_to(&bundle(&[encode("/transport/play", &[])]), target)
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut actions = Vec::new();
+        while actions.len() < 2 && Instant::now() < deadline {
+            actions.extend(server.poll());
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(actions, [RemoteAction::SetVolume(0.25), RemoteAction::Play]);
+    }
+}
@@ -0,0 +1,310 @@
+// WebSocket server - JSON command/state API
+//
+// Remote UIs and integration tests connect over WebSocket and exchange JSON
+// text messages:
+//
+//   request   {"id": 1, "command": "set_volume", "args": {"volume": 0.5}}
+//   response  {"id": 1, "ok": true, "result": null}
+//             {"id": 1, "ok": false, "error": {...}}
+//   event     {"event": "engine-snapshot", "payload": {...}}
+//
+// The server only carries messages: the frontend provides the command
+// handler (called on the connection thread) and broadcasts its telemetry.
+// Each client has a bounded outgoing queue; events are dropped for clients
+// that do not keep up instead of stalling the others.
+
+use serde_json::{Value, json};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TrySendError, sync_channel};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tungstenite::{Message, WebSocket};
+
+/// Default TCP port
+pub const DEFAULT_WEBSOCKET_PORT: u16 = 9001;
+
+/// Messages queued per client before events are dropped
+const CLIENT_QUEUE_CAPACITY: usize = 256;
+
+/// How long the accept loop and the connection threads sleep between polls
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Handles a command: `(name, args) -> result or error`
+pub type CommandHandler = Arc<dyn Fn(&str, Value) -> Result<Value, Value> + Send + Sync>;
+
+type Clients = Arc<Mutex<Vec<SyncSender<String>>>>;
+
+/// WebSocket server accepting commands and broadcasting events
+///
+/// Dropping the server closes every connection.
+pub struct WebSocketServer {
+    local_addr: SocketAddr,
+    clients: Clients,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WebSocketServer {
+    /// Bind a TCP socket (e.g. "127.0.0.1:9001") and start accepting clients
+    pub fn bind<A: ToSocketAddrs>(addr: A, handler: CommandHandler) -> Result<Self, String> {
+        let listener = TcpListener::bind(addr)
+            .map_err(|e| format!("Failed to bind WebSocket socket: {}", e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure WebSocket socket: {}", e))?;
+        let local_addr = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read WebSocket socket address: {}", e))?;
+
+        let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let clients = clients.clone();
+            let running = running.clone();
+            thread::Builder::new()
+                .name("websocket-server".to_string())
+                .spawn(move || accept_loop(listener, handler, clients, running))
+                .map_err(|e| format!("Failed to spawn WebSocket thread: {}", e))?
+        };
+
+        println!("🌐 WebSocket server listening on {}", local_addr);
+        Ok(Self {
+            local_addr,
+            clients,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Address the server is bound to
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Number of connected clients
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().map_or(0, |clients| clients.len())
+    }
+
+    /// Send an event to every connected client
+    pub fn broadcast(&self, event: &str, payload: &Value) {
+        let Ok(mut clients) = self.clients.lock() else {
+            return;
+        };
+        if clients.is_empty() {
+            return;
+        }
+        let text = json!({ "event": event, "payload": payload }).to_string();
+        clients.retain(|client| match client.try_send(text.clone()) {
+            Ok(()) | Err(TrySendError::Full(_)) => true,
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+    }
+
+    /// Close every connection and stop accepting clients
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for WebSocketServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn accept_loop(
+    listener: TcpListener,
+    handler: CommandHandler,
+    clients: Clients,
+    running: Arc<AtomicBool>,
+) {
+    let mut connections = Vec::new();
+    while running.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                let (tx, rx) = sync_channel(CLIENT_QUEUE_CAPACITY);
+                let handler = handler.clone();
+                let running = running.clone();
+                let connection = thread::Builder::new()
+                    .name("websocket-client".to_string())
+                    .spawn(move || {
+                        if let Err(e) = serve_client(stream, handler, rx, running) {
+                            eprintln!("WebSocket client {}: {}", peer, e);
+                        }
+                    });
+                match connection {
+                    Ok(connection) => {
+                        if let Ok(mut clients) = clients.lock() {
+                            clients.push(tx);
+                        }
+                        connections.push(connection);
+                    }
+                    Err(e) => eprintln!("Failed to spawn WebSocket client thread: {}", e),
+                }
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(e) => {
+                eprintln!("WebSocket accept error: {}", e);
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+        connections.retain(|connection: &JoinHandle<()>| !connection.is_finished());
+    }
+
+    // Connection threads watch the same flag
+    for connection in connections {
+        let _ = connection.join();
+    }
+}
+
+fn serve_client(
+    stream: TcpStream,
+    handler: CommandHandler,
+    outgoing: Receiver<String>,
+    running: Arc<AtomicBool>,
+) -> Result<(), String> {
+    // The handshake is blocking; afterwards reads time out so queued events
+    // can be written between two requests
+    stream.set_nonblocking(false).map_err(|e| e.to_string())?;
+    let mut socket = tungstenite::accept(stream).map_err(|e| e.to_string())?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(|e| e.to_string())?;
+
+    while running.load(Ordering::Relaxed) {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let reply = handle_request(&handler, text.as_str());
+                send(&mut socket, reply)?;
+            }
+            Ok(Message::Close(_)) => break,
+            // Ping/pong are answered by tungstenite, binary frames are not part of the API
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+                return Ok(());
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+
+        while let Ok(text) = outgoing.try_recv() {
+            send(&mut socket, text)?;
+        }
+    }
+
+    let _ = socket.close(None);
+    let _ = socket.flush();
+    Ok(())
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, text: String) -> Result<(), String> {
+    socket.send(Message::text(text)).map_err(|e| e.to_string())
+}
+
+/// Run one request through the handler and build its response
+fn handle_request(handler: &CommandHandler, text: &str) -> String {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => {
+            return json!({ "id": null, "ok": false, "error": format!("Invalid JSON: {}", e) })
+                .to_string();
+        }
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let Some(command) = request.get("command").and_then(Value::as_str) else {
+        return json!({ "id": id, "ok": false, "error": "Missing \"command\"" }).to_string();
+    };
+    let args = match request.get("args") {
+        Some(Value::Null) | None => Value::Object(Default::default()),
+        Some(args) => args.clone(),
+    };
+
+    match handler(command, args) {
+        Ok(result) => json!({ "id": id, "ok": true, "result": result }),
+        Err(error) => json!({ "id": id, "ok": false, "error": error }),
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn echo_handler() -> CommandHandler {
+        Arc::new(|command, args| match command {
+            "echo" => Ok(args),
+            _ => Err(json!(format!("Unknown command: {}", command))),
+        })
+    }
+
+    fn read_json<S: std::io::Read + std::io::Write>(client: &mut WebSocket<S>) -> Value {
+        loop {
+            if let Message::Text(text) = client.read().unwrap() {
+                return serde_json::from_str(text.as_str()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_handle_request() {
+        let handler = echo_handler();
+        let reply: Value = serde_json::from_str(&handle_request(
+            &handler,
+            r#"{"id": 7, "command": "echo", "args": {"volume": 0.5}}"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            reply,
+            json!({ "id": 7, "ok": true, "result": { "volume": 0.5 } })
+        );
+
+        let reply: Value = serde_json::from_str(&handle_request(
+            &handler,
+            r#"{"id": "a", "command": "nope"}"#,
+        ))
+        .unwrap();
+        assert_eq!(reply["ok"], json!(false));
+        assert_eq!(reply["id"], json!("a"));
+
+        let reply: Value = serde_json::from_str(&handle_request(&handler, "{")).unwrap();
+        assert_eq!(reply["ok"], json!(false));
+    }
+
+    #[test]
+    fn test_server_commands_and_events() {
+        let server = WebSocketServer::bind("127.0.0.1:0", echo_handler()).unwrap();
+        let url = format!("ws://{}", server.local_addr());
+        let (mut client, _) = tungstenite::connect(url).unwrap();
+
+        client
+            .send(Message::text(
+                r#"{"id": 1, "command": "echo", "args": [1, 2]}"#,
+            ))
+            .unwrap();
+        assert_eq!(
+            read_json(&mut client),
+            json!({ "id": 1, "ok": true, "result": [1, 2] })
+        );
+
+        // The client is registered once accepted
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while server.client_count() == 0 && Instant::now() < deadline {
+            thread::sleep(POLL_INTERVAL);
+        }
+        server.broadcast("engine-snapshot", &json!({ "playing": true }));
+        assert_eq!(
+            read_json(&mut client),
+            json!({ "event": "engine-snapshot", "payload": { "playing": true } })
+        );
+    }
+}
@@ -1,4 +1,7 @@
+use crate::audio::stretch::StretchSettings;
 use crate::sampler::loader::{LoopMode, Sample};
+use crate::sampler::zone::SampleZone;
+use crate::synth::envelope::AdsrParams;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
@@ -10,10 +13,10 @@ pub struct SampleBank {
     pub samples: Vec<SampleMapping>,
 }
 
-/// Mapping from MIDI note to sample configuration
+/// Mapping from MIDI note (or key-range zone) to sample configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SampleMapping {
-    /// MIDI note number (0-127)
+    /// MIDI note number (0-127), the root note of the zone for zone mappings
     pub note: u8,
     /// Relative path to sample file (from bank file location)
     pub sample_path: PathBuf,
@@ -33,6 +36,18 @@ pub struct SampleMapping {
     pub reverse: bool,
     /// Pitch offset in semitones (-12 to +12)
     pub pitch_offset: i8,
+    /// Fine tuning in cents (-100 to +100)
+    #[serde(default)]
+    pub fine_tune: f32,
+    /// Amplitude envelope (None: the sampler's default envelope)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub envelope: Option<AdsrParams>,
+    /// Time-stretch and pitch-shift rendered when the sample is loaded
+    #[serde(default, skip_serializing_if = "StretchSettings::is_identity")]
+    pub stretch: StretchSettings,
+    /// Key range played by the sample (None: the sample only plays `note`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<SampleZone>,
 }
 
 impl SampleBank {
@@ -46,24 +61,37 @@ impl SampleBank {
     }
 
     /// Add a sample mapping to the bank
+    ///
+    /// A note mapping replaces the note mapping of the same note; zone mappings
+    /// are added next to the others.
     pub fn add_mapping(&mut self, mapping: SampleMapping) {
-        // Remove any existing mapping for this note
-        self.samples.retain(|m| m.note != mapping.note);
+        if mapping.zone.is_none() {
+            // Remove any existing mapping for this note
+            self.samples
+                .retain(|m| m.zone.is_some() || m.note != mapping.note);
+        }
         self.samples.push(mapping);
     }
 
-    /// Get mapping for a specific note
+    /// Get the note mapping of a specific note (zones are not included)
     pub fn get_mapping(&self, note: u8) -> Option<&SampleMapping> {
-        self.samples.iter().find(|m| m.note == note)
+        self.samples
+            .iter()
+            .find(|m| m.zone.is_none() && m.note == note)
     }
 
-    /// Remove mapping for a specific note
+    /// Remove the note mapping of a specific note
     pub fn remove_mapping(&mut self, note: u8) -> bool {
         let initial_len = self.samples.len();
-        self.samples.retain(|m| m.note != note);
+        self.samples.retain(|m| m.zone.is_some() || m.note != note);
         self.samples.len() < initial_len
     }
 
+    /// Zone mappings of the bank
+    pub fn zones(&self) -> impl Iterator<Item = &SampleMapping> {
+        self.samples.iter().filter(|m| m.zone.is_some())
+    }
+
     /// Get all mappings sorted by note
     pub fn get_sorted_mappings(&self) -> Vec<&SampleMapping> {
         let mut mappings: Vec<&SampleMapping> = self.samples.iter().collect();
@@ -116,20 +144,11 @@ impl SampleBank {
                         PathBuf::from(sample_path)
                     };
 
-                    let mapping = SampleMapping {
-                        note: note as u8,
-                        sample_path: relative_path,
-                        name: sample.name.clone(),
-                        volume: sample.volume,
-                        pan: sample.pan,
-                        loop_mode: sample.loop_mode,
-                        loop_start: sample.loop_start,
-                        loop_end: sample.loop_end,
-                        reverse: sample.reverse,
-                        pitch_offset: sample.pitch_offset,
-                    };
-
-                    bank.add_mapping(mapping);
+                    bank.add_mapping(SampleMapping::from_sample(
+                        note as u8,
+                        relative_path,
+                        sample,
+                    ));
                 }
             }
         }
@@ -138,6 +157,66 @@ impl SampleBank {
     }
 }
 
+impl SampleMapping {
+    /// Create a mapping from a loaded sample and its source file
+    pub fn from_sample(note: u8, sample_path: PathBuf, sample: &Sample) -> Self {
+        Self {
+            note,
+            sample_path,
+            name: sample.name.clone(),
+            volume: sample.volume,
+            pan: sample.pan,
+            loop_mode: sample.loop_mode,
+            loop_start: sample.loop_start,
+            loop_end: sample.loop_end,
+            reverse: sample.reverse,
+            pitch_offset: sample.pitch_offset,
+            fine_tune: sample.fine_tune,
+            envelope: sample.envelope,
+            stretch: sample.stretch_settings(),
+            zone: None,
+        }
+    }
+
+    /// Create a zone mapping (its note is the root note of the zone)
+    pub fn from_zone(zone: SampleZone, sample_path: PathBuf, sample: &Sample) -> Self {
+        Self {
+            zone: Some(zone),
+            ..Self::from_sample(zone.root_note, sample_path, sample)
+        }
+    }
+
+    /// Absolute path of the sample file (relative paths are resolved from the bank directory)
+    pub fn resolve_path(&self, bank_base_path: &Path) -> PathBuf {
+        if self.sample_path.is_absolute() {
+            self.sample_path.clone()
+        } else {
+            bank_base_path.join(&self.sample_path)
+        }
+    }
+
+    /// Apply the bank settings to a freshly loaded sample
+    ///
+    /// A loop end of 0 or past the end of the sample loops to its end.
+    pub fn apply_to(&self, sample: &mut Sample) {
+        // First: the loop points below are positions in the rendered audio
+        sample.set_stretch(self.stretch);
+        sample.name = self.name.clone();
+        sample.volume = self.volume;
+        sample.pan = self.pan;
+        sample.loop_mode = self.loop_mode;
+        sample.loop_end = match self.loop_end {
+            0 => sample.len(),
+            end => end.min(sample.len()),
+        };
+        sample.loop_start = self.loop_start.min(sample.loop_end.saturating_sub(1));
+        sample.reverse = self.reverse;
+        sample.pitch_offset = self.pitch_offset;
+        sample.fine_tune = self.fine_tune;
+        sample.envelope = self.envelope;
+    }
+}
+
 impl Default for SampleBank {
     fn default() -> Self {
         Self::new("Untitled Bank".to_string())
@@ -164,6 +243,10 @@ mod tests {
             loop_end: 44100,
             reverse: false,
             pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: Default::default(),
+            zone: None,
         };
 
         bank.add_mapping(mapping);
@@ -196,6 +279,10 @@ mod tests {
             loop_end: 20000,
             reverse: false,
             pitch_offset: 2,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: Default::default(),
+            zone: None,
         };
 
         bank.add_mapping(mapping);
@@ -232,6 +319,10 @@ mod tests {
             loop_end: 1000,
             reverse: false,
             pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: Default::default(),
+            zone: None,
         };
 
         let mapping2 = SampleMapping {
@@ -245,6 +336,10 @@ mod tests {
             loop_end: 1000,
             reverse: false,
             pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: Default::default(),
+            zone: None,
         };
 
         bank.add_mapping(mapping1);
@@ -268,4 +363,56 @@ mod tests {
         assert_eq!(sorted.len(), 1);
         assert_eq!(sorted[0].note, 62);
     }
+
+    #[test]
+    fn test_zone_mappings() {
+        let mut bank = SampleBank::new("Keys".to_string());
+        let sample = Sample {
+            name: "piano_c3.wav".to_string(),
+            data: crate::sampler::loader::SampleData::F32(vec![0.0; 4].into()),
+            sample_rate: 48000,
+            source_channels: 1,
+            loop_mode: LoopMode::Off,
+            loop_start: 0,
+            loop_end: 4,
+            reverse: false,
+            volume: 1.0,
+            pan: 0.0,
+            pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: None,
+        };
+        let zone = SampleZone::new(36, 59, 48).unwrap();
+        bank.add_mapping(SampleMapping::from_sample(
+            48,
+            PathBuf::from("kick.wav"),
+            &sample,
+        ));
+        bank.add_mapping(SampleMapping::from_zone(
+            zone,
+            PathBuf::from("piano_c3.wav"),
+            &sample,
+        ));
+
+        // The zone does not replace the note mapping of its root note
+        assert_eq!(bank.samples.len(), 2);
+        assert_eq!(
+            bank.get_mapping(48).unwrap().sample_path,
+            PathBuf::from("kick.wav")
+        );
+        assert_eq!(bank.zones().count(), 1);
+
+        let json = serde_json::to_string(&bank).unwrap();
+        let loaded: SampleBank = serde_json::from_str(&json).unwrap();
+        let zone_mapping = loaded.zones().next().unwrap();
+        assert_eq!(zone_mapping.zone, Some(zone));
+        assert_eq!(zone_mapping.note, 48);
+
+        // Note mappings are written without a zone field
+        assert_eq!(json.matches("\"zone\"").count(), 1);
+
+        assert!(bank.remove_mapping(48));
+        assert_eq!(bank.samples.len(), 1);
+    }
 }
@@ -1,5 +1,6 @@
 use crate::sampler::loader::{LoopMode, Sample};
-use crate::synth::envelope::{AdsrEnvelope, AdsrParams};
+use crate::sampler::zone::DEFAULT_ROOT_NOTE;
+use crate::synth::envelope::AdsrEnvelope;
 use std::f32::consts::FRAC_PI_2;
 use std::sync::Arc;
 
@@ -7,6 +8,12 @@ pub struct SamplerVoice {
     sample: Arc<Sample>,
     position: f64,
     pitch_step: f64,
+    /// Note playing the sample at its original pitch
+    root_note: u8,
+    /// Playback rate multiplier of the pitch bend (1.0 = no bend)
+    pitch_bend_ratio: f64,
+    /// Loop the whole sample even when its loop is off (previews)
+    looping: bool,
     is_active: bool,
     note: u8,
     velocity: f32,
@@ -21,41 +28,63 @@ impl SamplerVoice {
             sample: sample.clone(),
             position: 0.0,
             pitch_step: 1.0,
+            root_note: DEFAULT_ROOT_NOTE,
+            pitch_bend_ratio: 1.0,
+            looping: false,
             is_active: false,
             note: 0,
             velocity: 0.0,
             age: 0,
-            envelope: AdsrEnvelope::new(AdsrParams::default(), sample_rate),
+            envelope: AdsrEnvelope::new(sample.envelope.unwrap_or_default(), sample_rate),
             pan: sample.pan,
         }
     }
 
+    /// Play the sample transposed from another note (see `sampler::zone`)
+    pub fn with_root_note(mut self, root_note: u8) -> Self {
+        self.root_note = root_note;
+        self
+    }
+
+    /// Play in a loop: the sample's loop region when it has one, else all of it
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Region played in a loop (start and end positions), if the voice loops
+    fn loop_region(&self) -> Option<(f64, f64)> {
+        if self.sample.loop_mode == LoopMode::Forward {
+            Some((self.sample.loop_start as f64, self.sample.loop_end as f64))
+        } else if self.looping {
+            Some((0.0, self.sample.len() as f64))
+        } else {
+            None
+        }
+    }
+
+    /// Playback rate of a note, transposed from the root note
+    fn pitch_step_for(&self, note: u8) -> f64 {
+        let semitones = (note as f64 - self.root_note as f64)
+            + self.sample.pitch_offset as f64
+            + self.sample.fine_tune as f64 / 100.0;
+        2.0_f64.powf(semitones / 12.0)
+    }
+
     pub fn note_on(&mut self, note: u8, velocity: u8, age: u64) {
-        const BASE_NOTE: f64 = 60.0; // C4
-        let semitones_from_base = (note as f64 - BASE_NOTE) + self.sample.pitch_offset as f64;
-        self.pitch_step = 2.0_f64.powf(semitones_from_base / 12.0);
+        self.pitch_step = self.pitch_step_for(note);
 
         self.note = note;
         self.velocity = velocity as f32 / 127.0;
         self.age = age;
 
         // Initialize position based on reverse mode
-        if self.sample.reverse {
-            let data_len = match &self.sample.data {
-                crate::sampler::loader::SampleData::F32(data) => data.len(),
-            };
-            self.position = if self.sample.loop_mode == LoopMode::Forward {
-                self.sample.loop_end as f64 - 1.0
-            } else {
-                (data_len - 1) as f64
-            };
-        } else {
-            self.position = if self.sample.loop_mode == LoopMode::Forward {
-                self.sample.loop_start as f64
-            } else {
-                0.0
-            };
-        }
+        self.position = match (self.sample.reverse, self.loop_region()) {
+            (true, Some((_, end))) => end - 1.0,
+            (true, None) => self.sample.len() as f64 - 1.0,
+            (false, Some((start, _))) => start,
+            (false, None) => 0.0,
+        };
 
         self.is_active = true;
         self.envelope.note_on();
@@ -70,6 +99,18 @@ impl SamplerVoice {
         self.envelope.reset();
     }
 
+    pub fn fade_out(&mut self, duration: f32) {
+        self.envelope.fade_out(duration);
+    }
+
+    pub fn is_fading_out(&self) -> bool {
+        self.is_active && self.envelope.is_fading_out()
+    }
+
+    pub fn level(&self) -> f32 {
+        self.envelope.current_value() * self.velocity * self.sample.volume
+    }
+
     pub fn is_active(&self) -> bool {
         self.is_active
     }
@@ -87,9 +128,7 @@ impl SamplerVoice {
     }
 
     pub fn change_pitch_legato(&mut self, note: u8, velocity: u8, age: u64) {
-        const BASE_NOTE: f64 = 60.0; // C4
-        let semitones_from_base = (note as f64 - BASE_NOTE) + self.sample.pitch_offset as f64;
-        self.pitch_step = 2.0_f64.powf(semitones_from_base / 12.0);
+        self.pitch_step = self.pitch_step_for(note);
         self.note = note;
         self.velocity = velocity as f32 / 127.0;
         self.age = age;
@@ -97,6 +136,12 @@ impl SamplerVoice {
 
     pub fn set_aftertouch(&mut self, _value: f32) {}
 
+    pub fn set_pitch_bend(&mut self, semitones: f32) {
+        self.pitch_bend_ratio = 2.0_f64.powf(semitones as f64 / 12.0);
+    }
+
+    pub fn set_mod_wheel(&mut self, _value: f32) {}
+
     pub fn next_sample_with_matrix(
         &mut self,
         _matrix: &crate::synth::modulation::ModulationMatrix,
@@ -117,24 +162,24 @@ impl SamplerVoice {
 
         // Update position based on reverse mode
         if self.sample.reverse {
-            self.position -= self.pitch_step;
+            self.position -= self.pitch_step * self.pitch_bend_ratio;
 
             // Handle reverse playback boundaries
-            if self.sample.loop_mode == LoopMode::Forward {
-                if self.position < self.sample.loop_start as f64 {
-                    self.position = self.sample.loop_end as f64 - 1.0;
+            if let Some((start, end)) = self.loop_region() {
+                if self.position < start {
+                    self.position = end - 1.0;
                 }
             } else if self.position < 0.0 {
                 self.is_active = false;
                 return (0.0, 0.0);
             }
         } else {
-            self.position += self.pitch_step;
+            self.position += self.pitch_step * self.pitch_bend_ratio;
 
             // Handle forward playback boundaries
-            if self.sample.loop_mode == LoopMode::Forward {
-                if self.position >= self.sample.loop_end as f64 {
-                    self.position = self.sample.loop_start as f64;
+            if let Some((start, end)) = self.loop_region() {
+                if self.position >= end {
+                    self.position = start;
                 }
             } else if self.position >= sample_data.len() as f64 {
                 self.is_active = false;
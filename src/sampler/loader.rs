@@ -1,9 +1,17 @@
+use crate::audio::peaks::{WaveformPeaks, compute_peaks};
+use crate::audio::stretch::StretchSettings;
+use crate::sampler::bank::{SampleBank, SampleMapping};
+use crate::sampler::storage::{SampleBuffer, SampleCache};
+use crate::sampler::zone::{DEFAULT_ROOT_NOTE, SampleZone};
+use crate::synth::envelope::AdsrParams;
+use crate::synth::voice_manager::MAX_SAMPLE_SLOTS;
 use claxon::FlacReader;
 use hound::{SampleFormat, WavReader};
 use rubato::{
     Resampler, SincFixedIn, SincInterpolationParameters, SincInterpolationType, WindowFunction,
 };
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use symphonia::core::audio::{AudioBufferRef, Signal};
 use symphonia::core::formats::FormatOptions;
 use symphonia::core::io::MediaSourceStream;
@@ -11,11 +19,16 @@ use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 use symphonia::default::get_probe;
 
-const TARGET_SAMPLE_RATE: u32 = 48000;
+/// Rate samples are resampled to when loaded
+pub const TARGET_SAMPLE_RATE: u32 = 48000;
 
+/// Volume of a freshly loaded sample (boosted for better audibility)
+pub const DEFAULT_SAMPLE_VOLUME: f32 = 2.0;
+
+/// Sample audio, shared without copy between clones (see `sampler::storage`)
 #[derive(Debug, Clone)]
 pub enum SampleData {
-    F32(Vec<f32>),
+    F32(SampleBuffer),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -37,16 +50,122 @@ pub struct Sample {
     pub volume: f32,
     pub pan: f32,
     pub pitch_offset: i8, // Pitch offset in semitones, range: -12 to +12
+    pub fine_tune: f32,   // Fine tuning in cents, range: -100 to +100
+    pub envelope: Option<AdsrParams>, // Amplitude envelope (None: the sampler default)
+    pub stretch: Option<SampleStretch>, // Time-stretch / pitch-shift (None: unprocessed)
+}
+
+/// Time-stretch and pitch-shift of a sample, with the audio it was rendered from
+#[derive(Debug, Clone)]
+pub struct SampleStretch {
+    pub settings: StretchSettings,
+    /// Unprocessed audio
+    pub source: SampleBuffer,
+}
+
+impl Sample {
+    /// Number of frames in the sample
+    pub fn len(&self) -> usize {
+        match &self.data {
+            SampleData::F32(data) => data.len(),
+        }
+    }
+
+    /// Check if the sample contains no audio
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Downsampled min/max peaks for waveform display
+    pub fn peaks(&self, resolution: usize) -> WaveformPeaks {
+        match &self.data {
+            SampleData::F32(data) => compute_peaks(data, resolution),
+        }
+    }
+
+    /// Stretch settings the audio is rendered with (identity when unprocessed)
+    pub fn stretch_settings(&self) -> StretchSettings {
+        self.stretch
+            .as_ref()
+            .map_or_else(StretchSettings::default, |stretch| stretch.settings)
+    }
+
+    /// Render the sample again from its unprocessed audio with other stretch settings
+    ///
+    /// Loop points follow the new length. Rendering takes time: never call it
+    /// in the audio callback.
+    pub fn set_stretch(&mut self, settings: StretchSettings) {
+        let previous_ratio = self.stretch_settings().ratio;
+        let SampleData::F32(data) = &self.data;
+        let source = self
+            .stretch
+            .take()
+            .map_or_else(|| data.clone(), |stretch| stretch.source);
+
+        if settings.is_identity() {
+            self.data = SampleData::F32(source);
+        } else {
+            self.data = SampleData::F32(settings.render(&source).into());
+            self.stretch = Some(SampleStretch { settings, source });
+        }
+
+        let scale = |position: usize| (position as f64 * settings.ratio / previous_ratio).round();
+        self.loop_end = (scale(self.loop_end) as usize).min(self.len());
+        self.loop_start = (scale(self.loop_start) as usize).min(self.loop_end);
+    }
 }
 
 pub fn load_sample(path: &Path) -> Result<Sample, String> {
+    load_sample_cached(path, &SampleCache::default())
+}
+
+/// Load a sample, mapping its decode from `cache` when available
+///
+/// Large samples are decoded once, then memory-mapped from the cache on later
+/// loads (see `sampler::storage`).
+pub fn load_sample_cached(path: &Path, cache: &SampleCache) -> Result<Sample, String> {
     let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("");
 
-    match extension.to_lowercase().as_str() {
-        "wav" => load_wav(path),
-        "flac" => load_flac(path),
-        "mp3" => load_mp3(path),
-        _ => Err(format!("Unsupported file format: {}", extension)),
+    let decode = match extension.to_lowercase().as_str() {
+        "wav" => load_wav,
+        "flac" => load_flac,
+        "mp3" => load_mp3,
+        _ => return Err(format!("Unsupported file format: {}", extension)),
+    };
+
+    if let Some((data, source_channels)) = cache.load(path, TARGET_SAMPLE_RATE) {
+        return Ok(new_sample(path, data, source_channels));
+    }
+
+    let mut sample = decode(path)?;
+    let SampleData::F32(data) = sample.data;
+    sample.data =
+        SampleData::F32(cache.store(path, TARGET_SAMPLE_RATE, sample.source_channels, data));
+    Ok(sample)
+}
+
+/// Sample with default playback settings for decoded audio
+fn new_sample(path: &Path, data: SampleBuffer, source_channels: u16) -> Sample {
+    let loop_end = data.len();
+    Sample {
+        name: path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        data: SampleData::F32(data),
+        sample_rate: TARGET_SAMPLE_RATE,
+        source_channels,
+        loop_mode: LoopMode::Off,
+        loop_start: 0,
+        loop_end,
+        reverse: false,
+        volume: DEFAULT_SAMPLE_VOLUME,
+        pan: 0.0,
+        pitch_offset: 0,
+        fine_tune: 0.0,
+        envelope: None,
+        stretch: None,
     }
 }
 
@@ -110,25 +229,7 @@ fn load_wav(path: &Path) -> Result<Sample, String> {
     };
 
     let resampled = resample_if_needed(samples_mono, spec.sample_rate, TARGET_SAMPLE_RATE)?;
-    let loop_end = resampled.len();
-
-    Ok(Sample {
-        name: path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string(),
-        data: SampleData::F32(resampled),
-        sample_rate: TARGET_SAMPLE_RATE,
-        source_channels: spec.channels,
-        loop_mode: LoopMode::Off,
-        loop_start: 0,
-        loop_end,
-        reverse: false,
-        volume: 2.0, // Boost sample volume by default for better audibility
-        pan: 0.0,
-        pitch_offset: 0,
-    })
+    Ok(new_sample(path, resampled.into(), spec.channels))
 }
 
 fn load_flac(path: &Path) -> Result<Sample, String> {
@@ -148,25 +249,7 @@ fn load_flac(path: &Path) -> Result<Sample, String> {
     };
 
     let resampled = resample_if_needed(samples_mono, spec.sample_rate, TARGET_SAMPLE_RATE)?;
-    let loop_end = resampled.len();
-
-    Ok(Sample {
-        name: path
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string(),
-        data: SampleData::F32(resampled),
-        sample_rate: TARGET_SAMPLE_RATE,
-        source_channels: spec.channels as u16,
-        loop_mode: LoopMode::Off,
-        loop_start: 0,
-        loop_end,
-        reverse: false,
-        volume: 2.0, // Boost sample volume by default for better audibility
-        pan: 0.0,
-        pitch_offset: 0,
-    })
+    Ok(new_sample(path, resampled.into(), spec.channels as u16))
 }
 
 fn resample_if_needed(
@@ -426,23 +509,454 @@ fn load_mp3(path: &Path) -> Result<Sample, String> {
 
     // Resample if needed
     let resampled = resample_if_needed(samples, sample_rate, TARGET_SAMPLE_RATE)?;
-    let loop_end = resampled.len();
+    Ok(new_sample(path, resampled.into(), channels))
+}
 
-    Ok(Sample {
-        name: path
+/// Sample bank read from an SFZ instrument
+#[derive(Debug, Clone)]
+pub struct SfzImport {
+    pub bank: SampleBank,
+    /// What could not be imported (the rest of the instrument still loads)
+    pub warnings: Vec<String>,
+}
+
+/// SFZ opcodes mapped into the bank, the others are reported as unsupported
+const SFZ_OPCODES: [&str; 25] = [
+    "sample",
+    "key",
+    "lokey",
+    "hikey",
+    "pitch_keycenter",
+    "lovel",
+    "hivel",
+    "loop_mode",
+    "loopmode",
+    "loop_start",
+    "loopstart",
+    "loop_end",
+    "loopend",
+    "tune",
+    "pitch",
+    "transpose",
+    "volume",
+    "pan",
+    "direction",
+    "trigger",
+    "ampeg_attack",
+    "ampeg_decay",
+    "ampeg_sustain",
+    "ampeg_release",
+    "default_path",
+];
+
+/// Token of an SFZ line
+#[derive(Debug, PartialEq)]
+enum SfzToken<'a> {
+    /// `<region>`, `<group>`...
+    Header(&'a str),
+    /// `key=value`
+    Opcode(&'a str, &'a str),
+}
+
+/// Header an SFZ opcode belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SfzScope {
+    Control,
+    Global,
+    Master,
+    Group,
+    Region,
+    /// Before the first header, or in a header the sampler does not use
+    Ignored,
+}
+
+/// Import an SFZ instrument as a sample bank
+///
+/// See `parse_sfz`. Sample paths stay relative to the SFZ file, and loop
+/// points are converted to the rate samples are loaded at.
+pub fn load_sfz(path: &Path) -> Result<SfzImport, String> {
+    let text =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read SFZ file: {}", e))?;
+    let name = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let mut import = parse_sfz(&text, name)?;
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    for mapping in &mut import.bank.samples {
+        if let Some(rate) = source_sample_rate(&mapping.resolve_path(base_dir))
+            && rate != TARGET_SAMPLE_RATE
+        {
+            let ratio = TARGET_SAMPLE_RATE as f64 / rate as f64;
+            mapping.loop_start = (mapping.loop_start as f64 * ratio).round() as usize;
+            mapping.loop_end = (mapping.loop_end as f64 * ratio).round() as usize;
+        }
+    }
+    Ok(import)
+}
+
+/// Parse an SFZ instrument into a sample bank
+///
+/// Each region becomes a zone mapping: key range (`lokey`, `hikey`, `key`),
+/// root note (`pitch_keycenter`), velocity layer (`lovel`, `hivel`), loop
+/// (`loop_mode`, `loop_start`, `loop_end`), tuning (`transpose`, `tune`),
+/// `volume`, `pan`, `direction` and amplitude envelope (`ampeg_*`). Regions
+/// inherit the opcodes of their `<group>`, `<master>` and `<global>` headers;
+/// `<control>` sets `default_path`. `#define` variables are replaced.
+/// Unsupported opcodes are ignored and regions that cannot play (no sample,
+/// release triggers) are skipped, both reported in the warnings. Loop points
+/// are in frames of the sample files.
+pub fn parse_sfz(text: &str, name: String) -> Result<SfzImport, String> {
+    let mut warnings = Vec::new();
+    let mut unsupported = BTreeSet::new();
+    let mut defines: Vec<(String, String)> = Vec::new();
+
+    let mut control = HashMap::new();
+    let mut global = HashMap::new();
+    let mut master = HashMap::new();
+    let mut group = HashMap::new();
+    let mut region: Option<HashMap<String, String>> = None;
+    let mut regions = Vec::new();
+    let mut scope = SfzScope::Ignored;
+
+    for (index, line) in strip_sfz_comments(text).lines().enumerate() {
+        let line = line.trim();
+        if let Some(definition) = line.strip_prefix("#define") {
+            let mut parts = definition.split_whitespace();
+            if let (Some(variable), Some(value)) = (parts.next(), parts.next()) {
+                defines.push((variable.to_string(), value.to_string()));
+                // Longest first: $VEL is not replaced inside $VEL_LOW
+                defines.sort_by_key(|(variable, _)| std::cmp::Reverse(variable.len()));
+            }
+            continue;
+        }
+        if line.starts_with('#') {
+            warnings.push(format!(
+                "Line {}: unsupported directive '{}'",
+                index + 1,
+                line
+            ));
+            continue;
+        }
+        let mut line = line.to_string();
+        for (variable, value) in &defines {
+            line = line.replace(variable.as_str(), value);
+        }
+
+        let tokens = match sfz_tokens(&line) {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                warnings.push(format!("Line {}: {}", index + 1, e));
+                continue;
+            }
+        };
+        for token in tokens {
+            if let SfzToken::Header(header) = token {
+                if let Some(opcodes) = region.take() {
+                    regions.push(inherit_headers(opcodes, [&global, &master, &group]));
+                }
+                scope = match header {
+                    "control" => SfzScope::Control,
+                    "global" => {
+                        global.clear();
+                        master.clear();
+                        group.clear();
+                        SfzScope::Global
+                    }
+                    "master" => {
+                        master.clear();
+                        group.clear();
+                        SfzScope::Master
+                    }
+                    "group" => {
+                        group.clear();
+                        SfzScope::Group
+                    }
+                    "region" => {
+                        region = Some(HashMap::new());
+                        SfzScope::Region
+                    }
+                    _ => {
+                        unsupported.insert(format!("<{}>", header));
+                        SfzScope::Ignored
+                    }
+                };
+            } else if let SfzToken::Opcode(key, value) = token {
+                let opcodes = match scope {
+                    SfzScope::Control => &mut control,
+                    SfzScope::Global => &mut global,
+                    SfzScope::Master => &mut master,
+                    SfzScope::Group => &mut group,
+                    SfzScope::Region => region.get_or_insert_with(HashMap::new),
+                    SfzScope::Ignored => continue,
+                };
+                opcodes.insert(key.to_string(), value.to_string());
+            }
+        }
+    }
+    if let Some(opcodes) = region {
+        regions.push(inherit_headers(opcodes, [&global, &master, &group]));
+    }
+
+    let default_path = control.remove("default_path").unwrap_or_default();
+    unsupported.extend(control.into_keys());
+
+    let mut bank = SampleBank::new(name);
+    for (index, opcodes) in regions.iter().enumerate() {
+        unsupported.extend(
+            opcodes
+                .keys()
+                .filter(|key| !SFZ_OPCODES.contains(&key.as_str()))
+                .cloned(),
+        );
+        match sfz_region(opcodes, &default_path, &mut unsupported) {
+            Ok(mapping) => bank.samples.push(mapping),
+            Err(e) => warnings.push(format!("Region {} skipped: {}", index + 1, e)),
+        }
+    }
+
+    if bank.samples.is_empty() {
+        return Err("The SFZ file has no playable region".to_string());
+    }
+    if bank.samples.len() > MAX_SAMPLE_SLOTS {
+        warnings.push(format!(
+            "Only the first {} of {} regions are kept (sampler slot limit)",
+            MAX_SAMPLE_SLOTS,
+            bank.samples.len()
+        ));
+        bank.samples.truncate(MAX_SAMPLE_SLOTS);
+    }
+    if !unsupported.is_empty() {
+        let names: Vec<String> = unsupported.into_iter().collect();
+        warnings.push(format!("Unsupported opcodes ignored: {}", names.join(", ")));
+    }
+    Ok(SfzImport { bank, warnings })
+}
+
+/// Opcodes of a region completed with those of its headers (outermost first)
+fn inherit_headers(
+    region: HashMap<String, String>,
+    headers: [&HashMap<String, String>; 3],
+) -> HashMap<String, String> {
+    let mut opcodes = HashMap::new();
+    for header in headers {
+        opcodes.extend(
+            header
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+    opcodes.extend(region);
+    opcodes
+}
+
+/// Zone mapping of an SFZ region (its opcodes merged with its headers')
+fn sfz_region(
+    opcodes: &HashMap<String, String>,
+    default_path: &str,
+    unsupported: &mut BTreeSet<String>,
+) -> Result<SampleMapping, String> {
+    let get = |keys: &[&str]| keys.iter().find_map(|key| opcodes.get(*key));
+    let note = |key: &str| -> Result<Option<u8>, String> {
+        opcodes
+            .get(key)
+            .map(|value| sfz_note(value).ok_or_else(|| format!("invalid {} '{}'", key, value)))
+            .transpose()
+    };
+
+    let sample = get(&["sample"]).ok_or("no sample")?;
+    if let Some(trigger) = get(&["trigger"])
+        && trigger != "attack"
+    {
+        return Err(format!("trigger={} is not supported", trigger));
+    }
+
+    // SFZ paths use '\' on every platform
+    let sample_path = PathBuf::from(format!("{}{}", default_path, sample).replace('\\', "/"));
+    let key = note("key")?;
+    let root_note = match get(&["pitch_keycenter"]) {
+        Some(value) if value == "sample" => {
+            unsupported.insert("pitch_keycenter=sample".to_string());
+            key
+        }
+        _ => note("pitch_keycenter")?.or(key),
+    };
+    let zone = SampleZone::new(
+        note("lokey")?.or(key).unwrap_or(0),
+        note("hikey")?.or(key).unwrap_or(127),
+        root_note.unwrap_or(DEFAULT_ROOT_NOTE),
+    )?
+    .with_velocity(
+        sfz_number(get(&["lovel"]), "lovel")?.unwrap_or(0),
+        sfz_number(get(&["hivel"]), "hivel")?.unwrap_or(127),
+    )?;
+
+    let loop_mode = match get(&["loop_mode", "loopmode"]).map(String::as_str) {
+        None | Some("no_loop") | Some("one_shot") => LoopMode::Off,
+        Some("loop_continuous") => LoopMode::Forward,
+        // Loops while the note is held, and while it is released
+        Some("loop_sustain") => LoopMode::Forward,
+        Some(mode) => return Err(format!("invalid loop_mode '{}'", mode)),
+    };
+    // SFZ loop ends are inclusive
+    let loop_end =
+        sfz_number::<usize>(get(&["loop_end", "loopend"]), "loop_end")?.map_or(0, |end| end + 1);
+
+    // Whole semitones of the tuning go to the pitch offset
+    let tune = sfz_number::<i32>(get(&["tune", "pitch"]), "tune")?.unwrap_or(0);
+    let transpose = sfz_number::<i32>(get(&["transpose"]), "transpose")?.unwrap_or(0);
+    let volume_db = sfz_number::<f32>(get(&["volume"]), "volume")?.unwrap_or(0.0);
+    let pan = sfz_number::<f32>(get(&["pan"]), "pan")?.unwrap_or(0.0);
+
+    // SFZ envelope defaults: instant attack, full sustain, no release
+    let envelope = AdsrParams::new(
+        sfz_number(get(&["ampeg_attack"]), "ampeg_attack")?.unwrap_or(0.0),
+        sfz_number(get(&["ampeg_decay"]), "ampeg_decay")?.unwrap_or(0.0),
+        sfz_number::<f32>(get(&["ampeg_sustain"]), "ampeg_sustain")?.unwrap_or(100.0) / 100.0,
+        sfz_number(get(&["ampeg_release"]), "ampeg_release")?.unwrap_or(0.0),
+    );
+
+    Ok(SampleMapping {
+        note: zone.root_note,
+        name: sample_path
             .file_name()
             .unwrap_or_default()
             .to_string_lossy()
             .to_string(),
-        data: SampleData::F32(resampled),
-        sample_rate: TARGET_SAMPLE_RATE,
-        source_channels: channels,
-        loop_mode: LoopMode::Off,
-        loop_start: 0,
+        sample_path,
+        volume: DEFAULT_SAMPLE_VOLUME * 10f32.powf(volume_db / 20.0),
+        pan: (pan / 100.0).clamp(-1.0, 1.0),
+        loop_mode,
+        loop_start: sfz_number(get(&["loop_start", "loopstart"]), "loop_start")?.unwrap_or(0),
         loop_end,
-        reverse: false,
-        volume: 2.0, // Boost sample volume by default for better audibility
-        pan: 0.0,
-        pitch_offset: 0,
+        reverse: get(&["direction"]).is_some_and(|direction| direction == "reverse"),
+        pitch_offset: (transpose + tune / 100).clamp(-127, 127) as i8,
+        fine_tune: (tune % 100) as f32,
+        envelope: Some(envelope),
+        stretch: Default::default(),
+        zone: Some(zone),
     })
 }
+
+/// Parsed value of an SFZ opcode, if set
+fn sfz_number<T: std::str::FromStr>(
+    value: Option<&String>,
+    key: &str,
+) -> Result<Option<T>, String> {
+    value
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid {} '{}'", key, value))
+        })
+        .transpose()
+}
+
+/// MIDI note of an SFZ key: a number or a note name (c4 = 60, c#4, db4)
+fn sfz_note(value: &str) -> Option<u8> {
+    if let Ok(note) = value.parse::<u8>() {
+        return (note <= 127).then_some(note);
+    }
+    let value = value.to_ascii_lowercase();
+    let mut chars = value.chars();
+    let pitch_class = match chars.next()? {
+        'c' => 0,
+        'd' => 2,
+        'e' => 4,
+        'f' => 5,
+        'g' => 7,
+        'a' => 9,
+        'b' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = if let Some(octave) = rest.strip_prefix('#') {
+        (1, octave)
+    } else if let Some(octave) = rest.strip_prefix('b') {
+        (-1, octave)
+    } else {
+        (0, rest)
+    };
+    let note = (octave.parse::<i32>().ok()? + 1) * 12 + pitch_class + accidental;
+    u8::try_from(note).ok().filter(|note| *note <= 127)
+}
+
+/// Remove `//` and `/* */` comments, keeping the line breaks
+fn strip_sfz_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    loop {
+        let line_comment = rest.find("//");
+        let block_comment = rest.find("/*");
+        match (line_comment, block_comment) {
+            (Some(start), block) if block.is_none_or(|block| start < block) => {
+                stripped.push_str(&rest[..start]);
+                rest = rest[start..]
+                    .find('\n')
+                    .map_or("", |end| &rest[start + end..]);
+            }
+            (_, Some(start)) => {
+                stripped.push_str(&rest[..start]);
+                let comment = &rest[start + 2..];
+                let end = comment.find("*/").unwrap_or(comment.len());
+                stripped.extend(comment[..end].chars().filter(|c| *c == '\n'));
+                rest = comment.get(end + 2..).unwrap_or("");
+            }
+            _ => {
+                stripped.push_str(rest);
+                return stripped;
+            }
+        }
+    }
+}
+
+/// Headers and `opcode=value` pairs of an SFZ line, in order
+///
+/// Values run until the next header or opcode, so sample paths may contain spaces.
+fn sfz_tokens(line: &str) -> Result<Vec<SfzToken<'_>>, String> {
+    let is_token_start = |text: &str| {
+        let name_len = text
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(text.len());
+        text.starts_with('<') || (name_len > 0 && text[name_len..].starts_with('='))
+    };
+
+    let mut tokens = Vec::new();
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        if let Some(header) = rest.strip_prefix('<') {
+            let end = header
+                .find('>')
+                .ok_or_else(|| format!("unclosed header '{}'", rest))?;
+            tokens.push(SfzToken::Header(&header[..end]));
+            rest = header[end + 1..].trim_start();
+            continue;
+        }
+        if !is_token_start(rest) {
+            return Err(format!("expected opcode=value, found '{}'", rest));
+        }
+        let (key, value) = rest.split_once('=').unwrap_or((rest, ""));
+        let end = value
+            .char_indices()
+            .find(|(i, c)| c.is_whitespace() && is_token_start(value[*i..].trim_start()))
+            .map_or(value.len(), |(i, _)| i);
+        tokens.push(SfzToken::Opcode(key, value[..end].trim()));
+        rest = value[end..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Sample rate of an audio file, read from its header (WAV and FLAC)
+fn source_sample_rate(path: &Path) -> Option<u32> {
+    match path.extension()?.to_str()?.to_lowercase().as_str() {
+        "wav" => WavReader::open(path)
+            .ok()
+            .map(|reader| reader.spec().sample_rate),
+        "flac" => FlacReader::open(path)
+            .ok()
+            .map(|reader| reader.streaminfo().sample_rate),
+        _ => None,
+    }
+}
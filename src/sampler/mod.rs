@@ -1,9 +1,17 @@
 pub mod bank;
 pub mod engine;
 pub mod loader;
+pub mod slicer;
+pub mod storage;
+pub mod zone;
 
 pub use bank::{SampleBank, SampleMapping};
-pub use loader::{LoopMode, Sample, SampleData, load_sample};
+pub use loader::{
+    LoopMode, Sample, SampleData, SfzImport, load_sample, load_sample_cached, load_sfz,
+};
+pub use slicer::LoopSlices;
+pub use storage::{SampleBuffer, SampleCache};
+pub use zone::SampleZone;
 
 #[cfg(test)]
 mod tests;
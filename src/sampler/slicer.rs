@@ -0,0 +1,171 @@
+// Slicer - Beat slicing of drum loops
+//
+// A loop is cut at its transients (see `audio::warp::detect_transients`) into
+// slices, each a sample of its own with a one-note zone: the slices play at
+// their original pitch on consecutive notes from a first note (C1 by default,
+// the first pad of a drum kit). A pattern playing each slice's note at its
+// position in the loop replays the loop; at another project tempo the hits
+// follow the grid and the loop keeps its pitch.
+
+use crate::audio::warp::detect_transients;
+use crate::sampler::loader::{LoopMode, Sample, SampleData};
+use crate::sampler::zone::SampleZone;
+use crate::sequencer::{Note, Position, Tempo, TimeSignature, generate_note_id};
+
+/// First note of the slices (C1)
+pub const DEFAULT_FIRST_NOTE: u8 = 36;
+
+/// Velocity of the notes replaying the loop
+const SLICE_VELOCITY: u8 = 100;
+
+/// Fade at the end of a slice, against clicks where the next hit was cut (samples)
+const SLICE_FADE: usize = 64;
+
+/// Transients this close to the start (seconds) are the first slice
+const START_TOLERANCE: f32 = 0.01;
+
+/// Slice points of a loop
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopSlices {
+    /// First sample of each slice, the first at 0
+    pub starts: Vec<usize>,
+    /// Length of the loop (samples)
+    pub length: usize,
+    /// Sample rate of the loop
+    pub sample_rate: u32,
+}
+
+impl LoopSlices {
+    /// Slice a sample at its transients
+    pub fn detect(sample: &Sample) -> Self {
+        let SampleData::F32(data) = &sample.data;
+        let tolerance = (START_TOLERANCE * sample.sample_rate as f32) as usize;
+        let mut starts = vec![0];
+        starts.extend(
+            detect_transients(data, sample.sample_rate)
+                .into_iter()
+                .filter(|&start| start > tolerance),
+        );
+        Self {
+            starts,
+            length: sample.len(),
+            sample_rate: sample.sample_rate,
+        }
+    }
+
+    /// Number of slices
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Check if the loop has no slice
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Range of samples of each slice
+    pub fn ranges(&self) -> impl Iterator<Item = std::ops::Range<usize>> + '_ {
+        self.starts.iter().enumerate().map(|(index, &start)| {
+            let end = self.starts.get(index + 1).copied().unwrap_or(self.length);
+            start.min(self.length)..end.min(self.length)
+        })
+    }
+
+    /// Length of the loop in beats when it was played at `source_bpm`
+    pub fn beats(&self, source_bpm: f64) -> f64 {
+        self.length as f64 / self.sample_rate as f64 * source_bpm / 60.0
+    }
+
+    /// Whole bars covering the loop
+    pub fn bars(&self, source_bpm: f64, time_signature: &TimeSignature) -> u32 {
+        let bars = self.beats(source_bpm) / time_signature.beats_per_bar();
+        // A loop a hair longer than its bars is not a bar longer
+        ((bars - 1e-3).ceil() as u32).max(1)
+    }
+
+    /// Cut the loop into samples, each with a zone on its own note from `first_note`
+    ///
+    /// The slices keep the volume, pan, tuning and envelope of the loop.
+    pub fn slice(
+        &self,
+        sample: &Sample,
+        first_note: u8,
+    ) -> Result<Vec<(Sample, SampleZone)>, String> {
+        self.check_notes(first_note)?;
+        let SampleData::F32(data) = &sample.data;
+        self.ranges()
+            .enumerate()
+            .map(|(index, range)| {
+                let mut audio = data[range].to_vec();
+                let fade = SLICE_FADE.min(audio.len());
+                let fade_start = audio.len() - fade;
+                for (i, value) in audio[fade_start..].iter_mut().enumerate() {
+                    *value *= 1.0 - (i + 1) as f32 / fade as f32;
+                }
+                let length = audio.len();
+                let note = first_note + index as u8;
+                let slice = Sample {
+                    name: format!("{} {}", sample.name, index + 1),
+                    data: SampleData::F32(audio.into()),
+                    loop_mode: LoopMode::Off,
+                    loop_start: 0,
+                    loop_end: length,
+                    reverse: false,
+                    stretch: None,
+                    ..sample.clone()
+                };
+                Ok((slice, SampleZone::new(note, note, note)?))
+            })
+            .collect()
+    }
+
+    /// Notes replaying the loop, recorded at `source_bpm`, at the project tempo
+    ///
+    /// Each slice plays its note from its position in the loop until the next
+    /// slice.
+    pub fn notes(
+        &self,
+        first_note: u8,
+        source_bpm: f64,
+        sample_rate: f64,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+    ) -> Result<Vec<Note>, String> {
+        self.check_notes(first_note)?;
+        if source_bpm <= 0.0 {
+            return Err(format!("Invalid source tempo: {} BPM", source_bpm));
+        }
+        let samples_per_beat = tempo.beat_duration_samples(sample_rate);
+        let timeline = |position: usize| {
+            let beats = position as f64 / self.sample_rate as f64 * source_bpm / 60.0;
+            (beats * samples_per_beat).round() as u64
+        };
+        Ok(self
+            .ranges()
+            .enumerate()
+            .filter_map(|(index, range)| {
+                let start = timeline(range.start);
+                let duration = timeline(range.end).checked_sub(start).filter(|d| *d > 0)?;
+                Some(Note::new(
+                    generate_note_id(),
+                    first_note + index as u8,
+                    Position::from_samples(start, sample_rate, tempo, time_signature),
+                    duration,
+                    SLICE_VELOCITY,
+                ))
+            })
+            .collect())
+    }
+
+    /// Check that every slice gets a MIDI note
+    fn check_notes(&self, first_note: u8) -> Result<(), String> {
+        if self.is_empty() || first_note as usize + self.len() - 1 > 127 {
+            return Err(format!(
+                "{} slices do not fit on the notes from {}",
+                self.len(),
+                first_note
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,317 @@
+// Sample storage - Shared, zero-copy sample data
+//
+// Sample data is immutable once loaded: `SampleBuffer` is an `Arc` slice shared
+// by the UI, the project system and the sampler engine. Cloning a `Sample`
+// (e.g. to send `UpdateSample` after a loop or volume edit) only bumps a
+// reference count instead of copying the audio.
+//
+// Large samples are memory-mapped from a decode cache: the first load decodes
+// and resamples the file, then writes the result as raw f32 next to a small
+// header. Later loads (project or bank reopen) map that file directly, with no
+// decoding and no copy; the OS pages the audio in on demand and may share it
+// between processes.
+//
+// Cache files are machine-local (native endianness) and never rewritten in
+// place: a new version is written to a temporary file and renamed, so mapped
+// files never change under a running engine.
+
+use memmap2::Mmap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+/// Samples shorter than this stay on the heap (10 s at 48 kHz)
+pub const MMAP_MIN_FRAMES: usize = 480_000;
+
+const CACHE_MAGIC: [u8; 4] = *b"MMSC";
+const CACHE_VERSION: u32 = 1;
+/// magic, version, source channels, frames, source length, source mtime
+const HEADER_LEN: usize = 40;
+
+/// Immutable mono f32 sample data, cheap to clone
+#[derive(Clone)]
+pub struct SampleBuffer {
+    storage: Storage,
+}
+
+#[derive(Clone)]
+enum Storage {
+    Heap(Arc<[f32]>),
+    Mapped { map: Arc<Mmap>, frames: usize },
+}
+
+impl SampleBuffer {
+    /// Whether the data is memory-mapped from the decode cache
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.storage, Storage::Mapped { .. })
+    }
+
+    /// Whether both buffers share the same data (no copy was made)
+    pub fn ptr_eq(&self, other: &SampleBuffer) -> bool {
+        std::ptr::eq(self.as_ptr(), other.as_ptr()) && self.len() == other.len()
+    }
+
+    /// Map the audio of a cache file (`None` if the mapping is unusable)
+    fn from_map(map: Mmap, frames: usize) -> Option<Self> {
+        let data = map.get(HEADER_LEN..HEADER_LEN + frames * size_of::<f32>())?;
+        if data.as_ptr().align_offset(align_of::<f32>()) != 0 {
+            return None;
+        }
+        Some(Self {
+            storage: Storage::Mapped {
+                map: Arc::new(map),
+                frames,
+            },
+        })
+    }
+}
+
+impl Deref for SampleBuffer {
+    type Target = [f32];
+
+    #[inline]
+    fn deref(&self) -> &[f32] {
+        match &self.storage {
+            Storage::Heap(data) => data,
+            Storage::Mapped { map, frames } => {
+                // SAFETY: `from_map` checked that the mapping holds `frames`
+                // aligned f32 after the header; the mapping lives as long as
+                // `map`, and cache files are never modified once written
+                unsafe {
+                    std::slice::from_raw_parts(map.as_ptr().add(HEADER_LEN).cast::<f32>(), *frames)
+                }
+            }
+        }
+    }
+}
+
+impl From<Vec<f32>> for SampleBuffer {
+    fn from(data: Vec<f32>) -> Self {
+        Self {
+            storage: Storage::Heap(data.into()),
+        }
+    }
+}
+
+impl FromIterator<f32> for SampleBuffer {
+    fn from_iter<I: IntoIterator<Item = f32>>(iter: I) -> Self {
+        Self {
+            storage: Storage::Heap(iter.into_iter().collect()),
+        }
+    }
+}
+
+impl fmt::Debug for SampleBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SampleBuffer")
+            .field("frames", &self.len())
+            .field("mapped", &self.is_mapped())
+            .finish()
+    }
+}
+
+/// Decode cache of large samples, mapped instead of decoded on later loads
+#[derive(Debug, Clone)]
+pub struct SampleCache {
+    root: PathBuf,
+}
+
+/// Identity of a source file (a changed file gets a new cache entry)
+struct SourceInfo {
+    key: u64,
+    len: u64,
+    mtime_ns: u64,
+}
+
+impl SampleCache {
+    /// Create a cache rooted at `root` (created on first store)
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Default cache directory (user cache dir)
+    pub fn default_root() -> PathBuf {
+        dirs::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join("mymusic_daw")
+            .join("samples")
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Map the cached decode of `source`, if any
+    ///
+    /// Returns the audio and the channel count of the source file.
+    pub fn load(&self, source: &Path, sample_rate: u32) -> Option<(SampleBuffer, u16)> {
+        let info = source_info(source, sample_rate)?;
+        let file = File::open(self.entry_path(&info)).ok()?;
+        // SAFETY: cache files are written once and replaced by rename, never
+        // modified in place (see the module documentation)
+        let map = unsafe { Mmap::map(&file) }.ok()?;
+
+        let header = map.get(..HEADER_LEN)?;
+        let u32_at = |at: usize| u32::from_ne_bytes(header[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_ne_bytes(header[at..at + 8].try_into().unwrap());
+        if header[..4] != CACHE_MAGIC
+            || u32_at(4) != CACHE_VERSION
+            || u64_at(24) != info.len
+            || u64_at(32) != info.mtime_ns
+        {
+            return None;
+        }
+        let channels = u16::try_from(u32_at(8)).ok()?;
+        let frames = usize::try_from(u64_at(16)).ok()?;
+
+        SampleBuffer::from_map(map, frames).map(|data| (data, channels))
+    }
+
+    /// Cache the decode of `source` and return it mapped
+    ///
+    /// Short samples, and any cache failure, return `data` unchanged: caching
+    /// is only an optimization.
+    pub fn store(
+        &self,
+        source: &Path,
+        sample_rate: u32,
+        channels: u16,
+        data: SampleBuffer,
+    ) -> SampleBuffer {
+        if data.len() < MMAP_MIN_FRAMES {
+            return data;
+        }
+        self.write_entry(source, sample_rate, channels, &data)
+            .and_then(|()| self.load(source, sample_rate))
+            .map(|(mapped, _)| mapped)
+            .unwrap_or(data)
+    }
+
+    fn write_entry(
+        &self,
+        source: &Path,
+        sample_rate: u32,
+        channels: u16,
+        data: &[f32],
+    ) -> Option<()> {
+        let info = source_info(source, sample_rate)?;
+        fs::create_dir_all(&self.root).ok()?;
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + std::mem::size_of_val(data));
+        bytes.extend_from_slice(&CACHE_MAGIC);
+        bytes.extend_from_slice(&CACHE_VERSION.to_ne_bytes());
+        bytes.extend_from_slice(&u32::from(channels).to_ne_bytes());
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(&(data.len() as u64).to_ne_bytes());
+        bytes.extend_from_slice(&info.len.to_ne_bytes());
+        bytes.extend_from_slice(&info.mtime_ns.to_ne_bytes());
+        for sample in data {
+            bytes.extend_from_slice(&sample.to_ne_bytes());
+        }
+
+        // Write aside, then rename: a mapped entry is never modified
+        let path = self.entry_path(&info);
+        let temp = path.with_extension(format!("tmp{}", std::process::id()));
+        let written = File::create(&temp)
+            .and_then(|mut file| file.write_all(&bytes))
+            .and_then(|()| fs::rename(&temp, &path));
+        if written.is_err() {
+            let _ = fs::remove_file(&temp);
+            return None;
+        }
+        Some(())
+    }
+
+    fn entry_path(&self, info: &SourceInfo) -> PathBuf {
+        self.root.join(format!("{:016x}.f32", info.key))
+    }
+}
+
+impl Default for SampleCache {
+    fn default() -> Self {
+        Self::new(Self::default_root())
+    }
+}
+
+fn source_info(source: &Path, sample_rate: u32) -> Option<SourceInfo> {
+    let metadata = fs::metadata(source).ok()?;
+    let mtime_ns = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos() as u64;
+    let path = fs::canonicalize(source).ok()?;
+
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    sample_rate.hash(&mut hasher);
+    Some(SourceInfo {
+        key: hasher.finish(),
+        len: metadata.len(),
+        mtime_ns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_clone_shares_data() {
+        let buffer = SampleBuffer::from(vec![0.25; 64]);
+        let clone = buffer.clone();
+        assert!(clone.ptr_eq(&buffer));
+        assert!(!clone.is_mapped());
+        assert_eq!(clone.len(), 64);
+        assert_eq!(clone[10], 0.25);
+    }
+
+    #[test]
+    fn test_large_samples_are_mapped_from_cache() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("long.wav");
+        fs::write(&source, b"source").unwrap();
+        let cache = SampleCache::new(dir.path().join("cache"));
+
+        let data: Vec<f32> = (0..MMAP_MIN_FRAMES).map(|i| i as f32).collect();
+        let stored = cache.store(&source, 48000, 2, data.clone().into());
+        assert!(stored.is_mapped());
+        assert_eq!(&stored[..], &data[..]);
+
+        // A later load maps the same cache entry without decoding
+        let (loaded, channels) = cache.load(&source, 48000).unwrap();
+        assert!(loaded.is_mapped());
+        assert_eq!(channels, 2);
+        assert_eq!(loaded[MMAP_MIN_FRAMES - 1], (MMAP_MIN_FRAMES - 1) as f32);
+
+        // Another sample rate is another entry
+        assert!(cache.load(&source, 44100).is_none());
+    }
+
+    #[test]
+    fn test_short_or_changed_samples_are_not_cached() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("short.wav");
+        fs::write(&source, b"source").unwrap();
+        let cache = SampleCache::new(dir.path().join("cache"));
+
+        let short = cache.store(&source, 48000, 1, vec![0.0; 16].into());
+        assert!(!short.is_mapped());
+        assert!(cache.load(&source, 48000).is_none());
+
+        cache.store(&source, 48000, 1, vec![0.0; MMAP_MIN_FRAMES].into());
+        assert!(cache.load(&source, 48000).is_some());
+
+        // The source changed: its cache entry is stale
+        fs::write(&source, b"edited source").unwrap();
+        assert!(cache.load(&source, 48000).is_none());
+    }
+}
@@ -62,7 +62,7 @@ fn create_test_sample(size: usize) -> Sample {
     let data = vec![0.5f32; size];
     Sample {
         name: "test_sample".to_string(),
-        data: SampleData::F32(data),
+        data: SampleData::F32(data.into()),
         sample_rate: 48000,
         source_channels: 1,
         loop_mode: LoopMode::Off,
@@ -72,6 +72,9 @@ fn create_test_sample(size: usize) -> Sample {
         volume: 1.0,
         pan: 0.0,
         pitch_offset: 0,
+        fine_tune: 0.0,
+        envelope: None,
+        stretch: None,
     }
 }
 
@@ -188,10 +191,8 @@ fn test_loop_with_pitch_shift() {
 fn test_loop_produces_continuous_audio() {
     let mut sample = create_test_sample(100);
     // Fill with a simple pattern to detect loop
-    let SampleData::F32(ref mut data) = sample.data;
-    for (i, val) in data.iter_mut().enumerate() {
-        *val = (i as f32 / 100.0).sin(); // Simple sine-like pattern
-    }
+    let data: Vec<f32> = (0..100).map(|i| (i as f32 / 100.0).sin()).collect(); // Simple sine-like pattern
+    sample.data = SampleData::F32(data.into());
 
     sample.loop_mode = LoopMode::Forward;
     sample.loop_start = 25;
@@ -445,3 +446,255 @@ fn test_pitch_offset_with_different_notes() {
         );
     }
 }
+
+#[test]
+fn test_root_note_transposes_playback() {
+    // A one-shot sample lasts half as long an octave above its root note
+    let sample_arc = Arc::new(create_test_sample(480));
+    let matrix = crate::synth::modulation::ModulationMatrix::new_empty();
+    let frames_played = |root_note: u8, note: u8| {
+        let mut voice = SamplerVoice::new(sample_arc.clone(), 48000.0).with_root_note(root_note);
+        voice.note_on(note, 100, 0);
+        let mut frames = 0;
+        while voice.is_active() && frames < 10_000 {
+            voice.next_sample_with_matrix(&matrix);
+            frames += 1;
+        }
+        frames
+    };
+
+    assert_eq!(frames_played(60, 60), 480);
+    assert_eq!(frames_played(48, 48), 480);
+    assert_eq!(frames_played(48, 60), 240);
+}
+
+#[test]
+fn test_fine_tune_and_envelope_follow_the_sample() {
+    let matrix = crate::synth::modulation::ModulationMatrix::new_empty();
+    let frames_played = |sample: Sample| {
+        let mut voice = SamplerVoice::new(Arc::new(sample), 48000.0);
+        voice.note_on(60, 100, 0);
+        let mut frames = 0;
+        while voice.is_active() && frames < 10_000 {
+            voice.next_sample_with_matrix(&matrix);
+            frames += 1;
+        }
+        frames
+    };
+
+    // +1200 cents: an octave up
+    let mut sample = create_test_sample(480);
+    sample.pitch_offset = 11;
+    sample.fine_tune = 100.0;
+    assert_eq!(frames_played(sample), 240);
+
+    // A slow attack starts quieter than the default envelope
+    let mut sample = create_test_sample(4800);
+    sample.envelope = Some(crate::synth::envelope::AdsrParams::new(1.0, 0.1, 1.0, 0.1));
+    let mut voice = SamplerVoice::new(Arc::new(sample), 48000.0);
+    voice.note_on(60, 127, 0);
+    for _ in 0..480 {
+        voice.next_sample_with_matrix(&matrix);
+    }
+    assert!(voice.level() < 0.1 * 0.8);
+}
+
+#[test]
+fn test_parse_sfz_regions() {
+    let sfz = r#"
+        // Piano, two velocity layers
+        #define $ROOT c4
+        <control> default_path=Samples\Piano\
+        <global> ampeg_release=0.5 volume=-6
+        <group> lovel=1 hivel=63 /* soft layer */
+        <region> sample=Soft C4.wav lokey=b3 hikey=c#4 pitch_keycenter=$ROOT
+        <group> lovel=64
+        <region> sample=Loud C4.wav key=60 tune=-150 pan=50
+            loop_mode=loop_continuous loop_start=100 loop_end=199
+    "#;
+    let import = parse_sfz(sfz, "Piano".to_string()).unwrap();
+    assert!(import.warnings.is_empty(), "{:?}", import.warnings);
+    let bank = import.bank;
+    assert_eq!(bank.name, "Piano");
+    assert_eq!(bank.samples.len(), 2);
+
+    let soft = &bank.samples[0];
+    assert_eq!(soft.sample_path, PathBuf::from("Samples/Piano/Soft C4.wav"));
+    assert_eq!(soft.name, "Soft C4.wav");
+    let zone = soft.zone.unwrap();
+    assert_eq!(
+        (zone.low_note, zone.high_note, zone.root_note),
+        (59, 61, 60)
+    );
+    assert_eq!((zone.low_velocity, zone.high_velocity), (1, 63));
+    assert_eq!(soft.loop_mode, LoopMode::Off);
+    assert!((soft.volume - DEFAULT_SAMPLE_VOLUME * 0.501).abs() < 0.01);
+    let envelope = soft.envelope.unwrap();
+    assert_eq!((envelope.sustain, envelope.release), (1.0, 0.5));
+
+    // A new group replaces the previous one: hivel is back to 127
+    let loud = &bank.samples[1];
+    let zone = loud.zone.unwrap();
+    assert_eq!(
+        (zone.low_note, zone.high_note, zone.root_note),
+        (60, 60, 60)
+    );
+    assert_eq!((zone.low_velocity, zone.high_velocity), (64, 127));
+    assert_eq!((loud.pitch_offset, loud.fine_tune), (-1, -50.0));
+    assert_eq!(loud.pan, 0.5);
+    assert_eq!(loud.loop_mode, LoopMode::Forward);
+    // SFZ loop ends are inclusive
+    assert_eq!((loud.loop_start, loud.loop_end), (100, 200));
+}
+
+#[test]
+fn test_parse_sfz_reports_what_it_skips() {
+    let sfz = "<region> sample=a.wav key=36 amp_veltrack=50\n\
+               <region> sample=release.wav trigger=release\n\
+               <region> key=40\n\
+               <curve> v000=0\n\
+               <region> sample=b.wav lokey=x4\n\
+               <region> sample=c d.wav key=38 seq_length=2\n\
+               oops <region> sample=e.wav\n";
+    let import = parse_sfz(sfz, "Kit".to_string()).unwrap();
+    assert_eq!(import.bank.samples.len(), 2);
+    assert_eq!(import.bank.samples[1].name, "c d.wav");
+    assert_eq!(
+        import.warnings,
+        [
+            "Line 7: expected opcode=value, found 'oops <region> sample=e.wav'",
+            "Region 2 skipped: trigger=release is not supported",
+            "Region 3 skipped: no sample",
+            "Region 4 skipped: invalid lokey 'x4'",
+            "Unsupported opcodes ignored: <curve>, amp_veltrack, seq_length",
+        ]
+    );
+
+    assert!(parse_sfz("// nothing\n<group> lokey=1", "Empty".to_string()).is_err());
+}
+
+#[test]
+fn test_load_sfz_converts_loop_points() {
+    let dir = tempfile::tempdir().unwrap();
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 24000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(dir.path().join("loop.wav"), spec).unwrap();
+    for _ in 0..2400 {
+        writer.write_sample(1000i16).unwrap();
+    }
+    writer.finalize().unwrap();
+    let sfz_path = dir.path().join("Pad.sfz");
+    std::fs::write(
+        &sfz_path,
+        "<region> sample=loop.wav loop_mode=loop_continuous loop_start=100 loop_end=1199",
+    )
+    .unwrap();
+
+    let import = load_sfz(&sfz_path).unwrap();
+    assert_eq!(import.bank.name, "Pad");
+    let mapping = &import.bank.samples[0];
+    // Loop points of the 24 kHz file, at the 48 kHz the sample is loaded at
+    assert_eq!((mapping.loop_start, mapping.loop_end), (200, 2400));
+
+    let mut sample = load_sample(&mapping.resolve_path(dir.path())).unwrap();
+    mapping.apply_to(&mut sample);
+    assert_eq!(sample.loop_end, 2400);
+}
+
+#[test]
+fn test_stretch_renders_from_the_source() {
+    let mut sample = create_test_sample(4800);
+    sample.loop_start = 1000;
+    sample.loop_end = 2000;
+    let original = sample.clone();
+
+    let settings = crate::audio::stretch::StretchSettings {
+        pitch: 3.0,
+        ratio: 2.0,
+    };
+    sample.set_stretch(settings);
+    assert_eq!(sample.len(), 9600);
+    assert_eq!((sample.loop_start, sample.loop_end), (2000, 4000));
+    assert_eq!(sample.stretch_settings(), settings);
+
+    // Saved in banks and applied again to the loaded file
+    let mapping = crate::sampler::SampleMapping::from_sample(60, PathBuf::from("a.wav"), &sample);
+    let json = serde_json::to_string(&mapping).unwrap();
+    let mapping: crate::sampler::SampleMapping = serde_json::from_str(&json).unwrap();
+    let mut reloaded = original.clone();
+    mapping.apply_to(&mut reloaded);
+    assert_eq!(reloaded.len(), 9600);
+    assert_eq!(reloaded.stretch_settings(), settings);
+
+    // Each render starts from the unprocessed audio
+    sample.set_stretch(Default::default());
+    let (SampleData::F32(data), SampleData::F32(source)) = (&sample.data, &original.data);
+    assert_eq!(&data[..], &source[..]);
+    assert_eq!((sample.loop_start, sample.loop_end), (1000, 2000));
+}
+
+#[test]
+fn test_slice_loop_at_transients() {
+    use crate::sampler::slicer::{DEFAULT_FIRST_NOTE, LoopSlices};
+    use crate::sequencer::{Tempo, TimeSignature};
+
+    // Four hits, one beat apart at 120 BPM
+    let mut sample = create_test_sample(96000);
+    sample.name = "Break".to_string();
+    sample.volume = 0.8;
+    let data: Vec<f32> = (0..96000)
+        .map(|i| {
+            let t = (i % 24000) as f32;
+            if t < 2000.0 { 1.0 - t / 2000.0 } else { 0.0 }
+        })
+        .collect();
+    sample.data = SampleData::F32(data.into());
+
+    let slices = LoopSlices::detect(&sample);
+    assert_eq!(slices.starts, vec![0, 24000, 48000, 72000]);
+    assert_eq!(slices.beats(120.0), 4.0);
+    assert_eq!(slices.bars(120.0, &TimeSignature::four_four()), 1);
+
+    let samples = slices.slice(&sample, DEFAULT_FIRST_NOTE).unwrap();
+    assert_eq!(samples.len(), 4);
+    let (second, zone) = &samples[1];
+    assert_eq!(second.name, "Break 2");
+    assert_eq!(
+        (second.len(), second.loop_end, second.volume),
+        (24000, 24000, 0.8)
+    );
+    // Each slice plays at its original pitch on its own note
+    assert_eq!(
+        (zone.low_note, zone.high_note, zone.root_note),
+        (37, 37, 37)
+    );
+    assert!(slices.slice(&sample, 125).is_err());
+
+    // At 60 BPM the hits are two times further apart
+    let notes = slices
+        .notes(
+            36,
+            120.0,
+            48000.0,
+            &Tempo::new(60.0),
+            &TimeSignature::four_four(),
+        )
+        .unwrap();
+    let hits: Vec<(u8, u64, u64)> = notes
+        .iter()
+        .map(|note| (note.pitch, note.start.samples, note.duration_samples))
+        .collect();
+    assert_eq!(
+        hits,
+        vec![
+            (36, 0, 48000),
+            (37, 48000, 48000),
+            (38, 96000, 48000),
+            (39, 144000, 48000)
+        ]
+    );
+}
@@ -0,0 +1,124 @@
+// Key-range zones - A sample played across a range of notes
+//
+// A zone maps a sample slot to every note from `low_note` to `high_note`,
+// transposed from its root note (the note playing the sample at its original
+// pitch). A zone can be limited to a velocity range to build velocity layers.
+// Explicit note mappings win over zones; among zones, the first slot covering
+// the note and velocity plays it. A sample without zone is transposed from C4.
+
+use serde::{Deserialize, Serialize};
+
+/// Note playing a sample at its original pitch when it has no zone (C4)
+pub const DEFAULT_ROOT_NOTE: u8 = 60;
+
+/// Note range played by a sample slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampleZone {
+    /// Lowest note of the range (0-127)
+    pub low_note: u8,
+    /// Highest note of the range (0-127, inclusive)
+    pub high_note: u8,
+    /// Note playing the sample at its original pitch (0-127)
+    pub root_note: u8,
+    /// Lowest velocity played by the zone (0-127)
+    #[serde(default)]
+    pub low_velocity: u8,
+    /// Highest velocity played by the zone (0-127, inclusive)
+    #[serde(default = "max_velocity")]
+    pub high_velocity: u8,
+}
+
+fn max_velocity() -> u8 {
+    127
+}
+
+impl SampleZone {
+    /// Create a zone playing every velocity, checking the notes
+    pub fn new(low_note: u8, high_note: u8, root_note: u8) -> Result<Self, String> {
+        let zone = Self {
+            low_note,
+            high_note,
+            root_note,
+            low_velocity: 0,
+            high_velocity: max_velocity(),
+        };
+        zone.validate()?;
+        Ok(zone)
+    }
+
+    /// Limit the zone to a velocity range (a velocity layer)
+    pub fn with_velocity(mut self, low_velocity: u8, high_velocity: u8) -> Result<Self, String> {
+        self.low_velocity = low_velocity;
+        self.high_velocity = high_velocity;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Check that the notes and velocities are MIDI values and the ranges are not empty
+    pub fn validate(&self) -> Result<(), String> {
+        if self.low_note > 127 || self.high_note > 127 || self.root_note > 127 {
+            return Err(format!(
+                "Zone notes must be 0-127 (got {}-{}, root {})",
+                self.low_note, self.high_note, self.root_note
+            ));
+        }
+        if self.low_note > self.high_note {
+            return Err(format!(
+                "Zone low note {} is above its high note {}",
+                self.low_note, self.high_note
+            ));
+        }
+        if self.high_velocity > 127 || self.low_velocity > self.high_velocity {
+            return Err(format!(
+                "Invalid zone velocity range {}-{}",
+                self.low_velocity, self.high_velocity
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether a note is in the range
+    pub fn contains(&self, note: u8) -> bool {
+        (self.low_note..=self.high_note).contains(&note)
+    }
+
+    /// Whether the zone plays a note at a velocity
+    pub fn plays(&self, note: u8, velocity: u8) -> bool {
+        self.contains(note) && (self.low_velocity..=self.high_velocity).contains(&velocity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zone_validation() {
+        let zone = SampleZone::new(36, 59, 48).unwrap();
+        assert!(zone.contains(36) && zone.contains(59));
+        assert!(!zone.contains(35) && !zone.contains(60));
+
+        assert!(SampleZone::new(60, 48, 50).is_err());
+        assert!(SampleZone::new(0, 128, 60).is_err());
+        assert!(SampleZone::new(10, 20, 128).is_err());
+        // The root note may be outside the range
+        assert!(SampleZone::new(10, 20, 60).is_ok());
+    }
+
+    #[test]
+    fn test_zone_velocity_range() {
+        let zone = SampleZone::new(36, 59, 48).unwrap();
+        assert!(zone.plays(40, 1) && zone.plays(40, 127));
+
+        let soft = zone.with_velocity(1, 63).unwrap();
+        assert!(soft.plays(40, 63));
+        assert!(!soft.plays(40, 64) && !soft.plays(30, 10));
+        assert!(zone.with_velocity(64, 63).is_err());
+        assert!(zone.with_velocity(0, 128).is_err());
+
+        // Zones saved before velocity layers play every velocity
+        let old: SampleZone =
+            serde_json::from_str(r#"{"low_note":36,"high_note":59,"root_note":48}"#).unwrap();
+        assert_eq!(old, zone);
+    }
+}
@@ -0,0 +1,168 @@
+// Script context - What a script reads and edits
+//
+// A script never touches the live DAW state: it runs against a copy of the
+// active pattern, the transport and the synth parameters (`ScriptContext`).
+// Once it succeeds, the caller takes the edited pattern and applies the
+// parameter changes through undoable commands (`ScriptOutcome::apply`).
+// A failing script therefore leaves the project unchanged.
+
+use crate::command::commands::{SetAdsrCommand, SetFilterCommand, SetVolumeCommand};
+use crate::command::trait_def::CommandResult;
+use crate::command::{CommandManager, DawState};
+use crate::sequencer::{NoteId, Pattern, Tempo, TimeSignature};
+use crate::synth::envelope::AdsrParams;
+use crate::synth::filter::FilterParams;
+
+/// Synth parameters scripts can read and set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptParam {
+    Volume,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+    Cutoff,
+    Resonance,
+}
+
+impl ScriptParam {
+    pub const ALL: [ScriptParam; 7] = [
+        ScriptParam::Volume,
+        ScriptParam::Attack,
+        ScriptParam::Decay,
+        ScriptParam::Sustain,
+        ScriptParam::Release,
+        ScriptParam::Cutoff,
+        ScriptParam::Resonance,
+    ];
+
+    /// Name used in scripts
+    pub fn name(self) -> &'static str {
+        match self {
+            ScriptParam::Volume => "volume",
+            ScriptParam::Attack => "attack",
+            ScriptParam::Decay => "decay",
+            ScriptParam::Sustain => "sustain",
+            ScriptParam::Release => "release",
+            ScriptParam::Cutoff => "cutoff",
+            ScriptParam::Resonance => "resonance",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|param| param.name() == name)
+    }
+
+    /// Valid range (values are clamped into it)
+    pub fn range(self) -> (f32, f32) {
+        match self {
+            ScriptParam::Volume | ScriptParam::Sustain => (0.0, 1.0),
+            ScriptParam::Attack | ScriptParam::Decay | ScriptParam::Release => (0.001, 5.0),
+            ScriptParam::Cutoff => (20.0, 20000.0),
+            ScriptParam::Resonance => (0.5, 20.0),
+        }
+    }
+}
+
+/// State a script runs against
+#[derive(Debug, Clone)]
+pub struct ScriptContext {
+    /// Pattern being edited
+    pub pattern: Pattern,
+    /// Notes selected in the piano roll
+    pub selection: Vec<NoteId>,
+    pub tempo: f64,
+    pub time_signature: TimeSignature,
+    /// Sample rate the pattern positions are expressed in
+    pub sample_rate: f64,
+    pub volume: f32,
+    pub adsr: AdsrParams,
+    pub filter: FilterParams,
+}
+
+impl ScriptContext {
+    /// Context over `pattern` with the transport and synth parameters of `state`
+    pub fn from_state(
+        state: &DawState,
+        pattern: Pattern,
+        selection: Vec<NoteId>,
+        sample_rate: f64,
+    ) -> Self {
+        Self {
+            pattern,
+            selection,
+            tempo: state.tempo.bpm(),
+            time_signature: state.time_signature,
+            sample_rate,
+            volume: state.volume,
+            adsr: state.adsr,
+            filter: state.filter,
+        }
+    }
+
+    pub fn param(&self, param: ScriptParam) -> f32 {
+        match param {
+            ScriptParam::Volume => self.volume,
+            ScriptParam::Attack => self.adsr.attack,
+            ScriptParam::Decay => self.adsr.decay,
+            ScriptParam::Sustain => self.adsr.sustain,
+            ScriptParam::Release => self.adsr.release,
+            ScriptParam::Cutoff => self.filter.cutoff,
+            ScriptParam::Resonance => self.filter.resonance,
+        }
+    }
+
+    /// Set a parameter, clamped to its range
+    pub fn set_param(&mut self, param: ScriptParam, value: f32) {
+        let (min, max) = param.range();
+        let value = value.clamp(min, max);
+        match param {
+            ScriptParam::Volume => self.volume = value,
+            ScriptParam::Attack => self.adsr.attack = value,
+            ScriptParam::Decay => self.adsr.decay = value,
+            ScriptParam::Sustain => self.adsr.sustain = value,
+            ScriptParam::Release => self.adsr.release = value,
+            ScriptParam::Cutoff => self.filter.cutoff = value,
+            ScriptParam::Resonance => self.filter.resonance = value,
+        }
+    }
+
+    /// Samples per beat (quarter note) at the context tempo
+    pub fn samples_per_beat(&self) -> f64 {
+        Tempo::new(self.tempo).beat_duration_samples(self.sample_rate)
+    }
+}
+
+/// Result of a successful script run
+#[derive(Debug, Clone)]
+pub struct ScriptOutcome {
+    /// Context as the script left it
+    pub context: ScriptContext,
+    /// Lines printed by the script
+    pub output: Vec<String>,
+    /// Whether the script edited the pattern
+    pub pattern_changed: bool,
+}
+
+impl ScriptOutcome {
+    /// Apply the tempo and parameter changes to the DAW state
+    ///
+    /// Parameters go through undoable commands; only changed values are sent.
+    /// The pattern is left to the caller (each frontend keeps its own).
+    pub fn apply(&self, manager: &mut CommandManager, state: &mut DawState) -> CommandResult<()> {
+        let context = &self.context;
+        if context.tempo != state.tempo.bpm() {
+            state.set_tempo(context.tempo)?;
+        }
+        if context.volume != state.volume {
+            manager.execute(Box::new(SetVolumeCommand::new(context.volume)), state)?;
+        }
+        if context.adsr != state.adsr {
+            manager.execute(Box::new(SetAdsrCommand::new(context.adsr)), state)?;
+        }
+        if context.filter != state.filter {
+            manager.execute(Box::new(SetFilterCommand::new(context.filter)), state)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,543 @@
+// Script engine - Rhai runtime with the DAW scripting API
+//
+// Each run builds a Rhai engine whose functions close over the run's
+// `ScriptContext`. Times are in beats (quarter notes) from the pattern start,
+// pitches and velocities are MIDI values. Numbers may be written as integers
+// or floats (`add_note(60, 0, 1, 100)` and `add_note(60, 0.5, 0.25, 90)`).
+//
+// API:
+//   notes() / selected()          -> array of #{id, pitch, start, length, velocity}
+//   add_note(pitch, start, length, velocity) -> id
+//   remove_note(id), set_pitch(id, p), set_velocity(id, v),
+//   set_start(id, beats), set_length(id, beats)
+//   transpose(semitones)          -> count (selection, or every note if none)
+//   clear()
+//   pattern_bars(), set_pattern_bars(n), beats_per_bar()
+//   tempo(), set_tempo(bpm)
+//   param(name), set_param(name, value)   (volume, attack, decay, sustain,
+//                                          release, cutoff, resonance)
+//   euclid(pulses, steps[, rotation])     -> array of bool
+//   random() -> float in [0, 1), random(min, max) -> int in [min, max],
+//   seed(n)
+//
+// Scripts are bounded (operation count, call depth, array size) so that an
+// infinite loop reports an error instead of freezing the UI.
+
+use crate::scripting::context::{ScriptContext, ScriptOutcome, ScriptParam};
+use crate::sequencer::{Note, NoteId, Position, Tempo, generate_note_id};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Operations allowed per run (a few seconds of work at most)
+const MAX_OPERATIONS: u64 = 50_000_000;
+const MAX_CALL_DEPTH: usize = 64;
+const MAX_ARRAY_SIZE: usize = 100_000;
+/// Notes a pattern may hold after a script
+const MAX_NOTES: usize = 10_000;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// State shared by the API functions during a run
+struct RunState {
+    context: ScriptContext,
+    output: Vec<String>,
+    pattern_changed: bool,
+    rng: u64,
+}
+
+impl RunState {
+    fn beats_to_samples(&self, beats: f64) -> u64 {
+        (beats.max(0.0) * self.context.samples_per_beat()).round() as u64
+    }
+
+    fn samples_to_beats(&self, samples: u64) -> f64 {
+        samples as f64 / self.context.samples_per_beat()
+    }
+
+    fn position(&self, beats: f64) -> Position {
+        Position::from_samples(
+            self.beats_to_samples(beats),
+            self.context.sample_rate,
+            &Tempo::new(self.context.tempo),
+            &self.context.time_signature,
+        )
+    }
+
+    fn note_map(&self, note: &Note) -> Map {
+        let mut map = Map::new();
+        map.insert("id".into(), Dynamic::from(note.id as i64));
+        map.insert("pitch".into(), Dynamic::from(note.pitch as i64));
+        map.insert(
+            "start".into(),
+            Dynamic::from(self.samples_to_beats(note.start.samples)),
+        );
+        map.insert(
+            "length".into(),
+            Dynamic::from(self.samples_to_beats(note.duration_samples)),
+        );
+        map.insert("velocity".into(), Dynamic::from(note.velocity as i64));
+        map
+    }
+
+    /// Replace a note by an edited copy (keeps the pattern sorted)
+    fn edit_note(&mut self, id: i64, edit: impl FnOnce(&mut Note)) -> ScriptResult<()> {
+        let pattern = &mut self.context.pattern;
+        let mut note = pattern
+            .remove_note(id as NoteId)
+            .ok_or_else(|| format!("No note with id {}", id))?;
+        edit(&mut note);
+        pattern.add_note(note);
+        self.pattern_changed = true;
+        Ok(())
+    }
+
+    /// xorshift64*: reproducible after `seed(n)`
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// Run `source` against `context`
+///
+/// # Returns
+/// The edited context and the printed output, or the script error
+/// (syntax error, runtime error or exceeded limit) as a message.
+pub fn run_script(source: &str, context: ScriptContext) -> Result<ScriptOutcome, String> {
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    let state = Rc::new(RefCell::new(RunState {
+        context,
+        output: Vec::new(),
+        pattern_changed: false,
+        rng: seed | 1,
+    }));
+
+    let engine = build_engine(&state);
+    let result = engine.run(source);
+    drop(engine);
+
+    let state = Rc::try_unwrap(state)
+        .map_err(|_| "Script state still borrowed".to_string())?
+        .into_inner();
+    result.map_err(|e| {
+        // Keep what the script printed before failing
+        let mut message = String::new();
+        for line in &state.output {
+            message.push_str(line);
+            message.push('\n');
+        }
+        message.push_str(&format!("Script error: {}", e));
+        message
+    })?;
+
+    if state.context.pattern.note_count() > MAX_NOTES {
+        return Err(format!(
+            "Script error: the pattern would hold {} notes (max {})",
+            state.context.pattern.note_count(),
+            MAX_NOTES
+        ));
+    }
+
+    Ok(ScriptOutcome {
+        context: state.context,
+        output: state.output,
+        pattern_changed: state.pattern_changed,
+    })
+}
+
+/// Integer or float argument as f64
+fn number(value: &Dynamic, name: &str) -> ScriptResult<f64> {
+    if let Ok(int) = value.as_int() {
+        Ok(int as f64)
+    } else if let Ok(float) = value.as_float() {
+        Ok(float)
+    } else {
+        Err(format!("{} must be a number, not {}", name, value.type_name()).into())
+    }
+}
+
+fn midi_value(value: &Dynamic, name: &str) -> ScriptResult<u8> {
+    let value = number(value, name)?;
+    if !(0.0..=127.0).contains(&value) {
+        return Err(format!("{} must be 0-127, not {}", name, value).into());
+    }
+    Ok(value as u8)
+}
+
+fn param_by_name(name: &str) -> ScriptResult<ScriptParam> {
+    ScriptParam::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = ScriptParam::ALL.iter().map(|p| p.name()).collect();
+        format!("Unknown parameter '{}' ({})", name, names.join(", ")).into()
+    })
+}
+
+/// Euclidean rhythm: `pulses` onsets spread as evenly as possible over `steps`
+pub fn euclidean_rhythm(pulses: usize, steps: usize, rotation: usize) -> Vec<bool> {
+    if steps == 0 {
+        return Vec::new();
+    }
+    let pulses = pulses.min(steps);
+    (0..steps)
+        .map(|step| {
+            let step = (step + rotation) % steps;
+            (step * pulses) % steps < pulses
+        })
+        .collect()
+}
+
+fn build_engine(state: &Rc<RefCell<RunState>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_DEPTH);
+    engine.set_max_array_size(MAX_ARRAY_SIZE);
+
+    let s = state.clone();
+    engine.on_print(move |text| s.borrow_mut().output.push(text.to_string()));
+    let s = state.clone();
+    engine.on_debug(move |text, _, _| s.borrow_mut().output.push(text.to_string()));
+
+    // Notes
+    let s = state.clone();
+    engine.register_fn("notes", move || -> Array {
+        let state = s.borrow();
+        state
+            .context
+            .pattern
+            .notes()
+            .iter()
+            .map(|note| Dynamic::from_map(state.note_map(note)))
+            .collect()
+    });
+
+    let s = state.clone();
+    engine.register_fn("selected", move || -> Array {
+        let state = s.borrow();
+        state
+            .context
+            .pattern
+            .notes()
+            .iter()
+            .filter(|note| state.context.selection.contains(&note.id))
+            .map(|note| Dynamic::from_map(state.note_map(note)))
+            .collect()
+    });
+
+    let s = state.clone();
+    engine.register_fn(
+        "add_note",
+        move |pitch: Dynamic,
+              start: Dynamic,
+              length: Dynamic,
+              velocity: Dynamic|
+              -> ScriptResult<i64> {
+            let pitch = midi_value(&pitch, "pitch")?;
+            let start = number(&start, "start")?;
+            let velocity = midi_value(&velocity, "velocity")?;
+            let mut state = s.borrow_mut();
+            let duration = state.beats_to_samples(number(&length, "length")?);
+            if duration == 0 {
+                return Err("length must be > 0".into());
+            }
+            let id = generate_note_id();
+            let note = Note::new(id, pitch, state.position(start), duration, velocity);
+            state.context.pattern.add_note(note);
+            state.pattern_changed = true;
+            Ok(id as i64)
+        },
+    );
+
+    let s = state.clone();
+    engine.register_fn("remove_note", move |id: i64| -> bool {
+        let mut state = s.borrow_mut();
+        let removed = state.context.pattern.remove_note(id as NoteId).is_some();
+        state.pattern_changed |= removed;
+        removed
+    });
+
+    let s = state.clone();
+    engine.register_fn(
+        "set_pitch",
+        move |id: i64, pitch: Dynamic| -> ScriptResult<()> {
+            let pitch = midi_value(&pitch, "pitch")?;
+            s.borrow_mut().edit_note(id, |note| note.pitch = pitch)
+        },
+    );
+
+    let s = state.clone();
+    engine.register_fn(
+        "set_velocity",
+        move |id: i64, velocity: Dynamic| -> ScriptResult<()> {
+            let velocity = midi_value(&velocity, "velocity")?;
+            s.borrow_mut()
+                .edit_note(id, |note| note.velocity = velocity)
+        },
+    );
+
+    let s = state.clone();
+    engine.register_fn(
+        "set_start",
+        move |id: i64, start: Dynamic| -> ScriptResult<()> {
+            let start = number(&start, "start")?;
+            let mut state = s.borrow_mut();
+            let position = state.position(start);
+            state.edit_note(id, |note| note.start = position)
+        },
+    );
+
+    let s = state.clone();
+    engine.register_fn(
+        "set_length",
+        move |id: i64, length: Dynamic| -> ScriptResult<()> {
+            let mut state = s.borrow_mut();
+            let duration = state.beats_to_samples(number(&length, "length")?);
+            if duration == 0 {
+                return Err("length must be > 0".into());
+            }
+            state.edit_note(id, |note| note.duration_samples = duration)
+        },
+    );
+
+    let s = state.clone();
+    engine.register_fn("transpose", move |semitones: i64| -> ScriptResult<i64> {
+        let mut state = s.borrow_mut();
+        let targets: Vec<NoteId> = state
+            .context
+            .pattern
+            .notes()
+            .iter()
+            .map(|note| note.id)
+            .filter(|id| state.context.selection.is_empty() || state.context.selection.contains(id))
+            .collect();
+        for &id in &targets {
+            let note = state.context.pattern.get_note(id).copied();
+            if let Some(note) = note {
+                let pitch = note.pitch as i64 + semitones;
+                if !(0..=127).contains(&pitch) {
+                    return Err(format!("Transposing note {} gives pitch {}", id, pitch).into());
+                }
+            }
+        }
+        for &id in &targets {
+            if let Some(note) = state.context.pattern.get_note_mut(id) {
+                note.pitch = (note.pitch as i64 + semitones) as u8;
+            }
+        }
+        state.pattern_changed |= !targets.is_empty() && semitones != 0;
+        Ok(targets.len() as i64)
+    });
+
+    let s = state.clone();
+    engine.register_fn("clear", move || {
+        let mut state = s.borrow_mut();
+        state.pattern_changed |= !state.context.pattern.is_empty();
+        state.context.pattern.clear();
+    });
+
+    // Pattern and transport
+    let s = state.clone();
+    engine.register_fn("pattern_bars", move || -> i64 {
+        s.borrow().context.pattern.length_bars as i64
+    });
+
+    let s = state.clone();
+    engine.register_fn("set_pattern_bars", move |bars: i64| -> ScriptResult<()> {
+        if !(1..=256).contains(&bars) {
+            return Err(format!("Pattern length must be 1-256 bars, not {}", bars).into());
+        }
+        let mut state = s.borrow_mut();
+        state.context.pattern.length_bars = bars as u32;
+        state.pattern_changed = true;
+        Ok(())
+    });
+
+    let s = state.clone();
+    engine.register_fn("beats_per_bar", move || -> f64 {
+        s.borrow().context.time_signature.beats_per_bar()
+    });
+
+    let s = state.clone();
+    engine.register_fn("tempo", move || -> f64 { s.borrow().context.tempo });
+
+    let s = state.clone();
+    engine.register_fn("set_tempo", move |bpm: Dynamic| -> ScriptResult<()> {
+        let bpm = number(&bpm, "bpm")?;
+        if !(20.0..=999.0).contains(&bpm) {
+            return Err(format!("Invalid tempo: {} (must be 20-999 BPM)", bpm).into());
+        }
+        s.borrow_mut().context.tempo = bpm;
+        Ok(())
+    });
+
+    // Synth parameters
+    let s = state.clone();
+    engine.register_fn("param", move |name: &str| -> ScriptResult<f64> {
+        let param = param_by_name(name)?;
+        Ok(s.borrow().context.param(param) as f64)
+    });
+
+    let s = state.clone();
+    engine.register_fn(
+        "set_param",
+        move |name: &str, value: Dynamic| -> ScriptResult<()> {
+            let param = param_by_name(name)?;
+            let value = number(&value, "value")?;
+            s.borrow_mut().context.set_param(param, value as f32);
+            Ok(())
+        },
+    );
+
+    // Generators
+    engine.register_fn("euclid", |pulses: i64, steps: i64| -> Array {
+        euclidean_rhythm(pulses.max(0) as usize, steps.max(0) as usize, 0)
+            .into_iter()
+            .map(Dynamic::from_bool)
+            .collect()
+    });
+    engine.register_fn(
+        "euclid",
+        |pulses: i64, steps: i64, rotation: i64| -> Array {
+            euclidean_rhythm(
+                pulses.max(0) as usize,
+                steps.max(0) as usize,
+                rotation.max(0) as usize,
+            )
+            .into_iter()
+            .map(Dynamic::from_bool)
+            .collect()
+        },
+    );
+
+    let s = state.clone();
+    engine.register_fn("random", move || -> f64 {
+        (s.borrow_mut().next_random() >> 11) as f64 / (1u64 << 53) as f64
+    });
+
+    let s = state.clone();
+    engine.register_fn("random", move |min: i64, max: i64| -> ScriptResult<i64> {
+        if max < min {
+            return Err(format!("random({}, {}): max is below min", min, max).into());
+        }
+        let span = (max - min) as u64 + 1;
+        Ok(min + (s.borrow_mut().next_random() % span) as i64)
+    });
+
+    let s = state.clone();
+    engine.register_fn("seed", move |seed: i64| {
+        s.borrow_mut().rng = seed as u64 | 1;
+    });
+
+    engine
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::{Pattern, TimeSignature};
+    use crate::synth::envelope::AdsrParams;
+    use crate::synth::filter::FilterParams;
+
+    fn context() -> ScriptContext {
+        ScriptContext {
+            pattern: Pattern::new_default(1, "Script".to_string()),
+            selection: Vec::new(),
+            tempo: 120.0,
+            time_signature: TimeSignature::four_four(),
+            sample_rate: 48000.0,
+            volume: 0.5,
+            adsr: AdsrParams::default(),
+            filter: FilterParams::default(),
+        }
+    }
+
+    #[test]
+    fn test_euclidean_rhythm() {
+        let pattern = |p, s, r| -> String {
+            euclidean_rhythm(p, s, r)
+                .into_iter()
+                .map(|hit| if hit { 'x' } else { '.' })
+                .collect()
+        };
+        assert_eq!(pattern(3, 8, 0), "x..x..x.");
+        assert_eq!(pattern(4, 16, 0), "x...x...x...x...");
+        assert_eq!(pattern(5, 8, 0), "x.x.xx.x");
+        assert_eq!(pattern(3, 8, 1), "..x..x.x");
+        assert_eq!(pattern(0, 4, 0), "....");
+    }
+
+    #[test]
+    fn test_generate_euclidean_kick_pattern() {
+        let script = r#"
+            clear();
+            let steps = euclid(5, 16);
+            for i in 0..steps.len() {
+                if steps[i] { add_note(36, i * 0.25, 0.25, 110); }
+            }
+            print(`${notes().len()} kicks`);
+        "#;
+        let outcome = run_script(script, context()).unwrap();
+        assert!(outcome.pattern_changed);
+        assert_eq!(outcome.output, vec!["5 kicks".to_string()]);
+
+        let notes = outcome.context.pattern.notes();
+        assert_eq!(notes.len(), 5);
+        // Sixteenth notes at 120 BPM / 48 kHz: 6000 samples
+        assert_eq!(notes[1].start.samples, 4 * 6000);
+        assert!(
+            notes
+                .iter()
+                .all(|n| n.pitch == 36 && n.duration_samples == 6000)
+        );
+    }
+
+    #[test]
+    fn test_transpose_selection_and_params() {
+        let mut context = context();
+        let tempo = Tempo::new(120.0);
+        for (id, pitch) in [(1, 60), (2, 64)] {
+            let start = Position::from_samples(0, 48000.0, &tempo, &context.time_signature);
+            context
+                .pattern
+                .add_note(Note::new(id, pitch, start, 1000, 100));
+        }
+        context.selection = vec![2];
+
+        let outcome = run_script(
+            r#"
+                transpose(7);
+                set_param("cutoff", param("cutoff") * 2);
+                set_param("volume", 3);
+                set_tempo(90);
+            "#,
+            context,
+        )
+        .unwrap();
+
+        let pattern = &outcome.context.pattern;
+        assert_eq!(pattern.get_note(1).unwrap().pitch, 60);
+        assert_eq!(pattern.get_note(2).unwrap().pitch, 71);
+        assert_eq!(outcome.context.filter.cutoff, 2000.0);
+        assert_eq!(outcome.context.volume, 1.0); // clamped
+        assert_eq!(outcome.context.tempo, 90.0);
+    }
+
+    #[test]
+    fn test_errors_leave_nothing_applied() {
+        let error =
+            run_script("print(\"before\"); add_note(200, 0, 1, 100);", context()).unwrap_err();
+        assert!(error.starts_with("before\n"), "{}", error);
+        assert!(error.contains("pitch must be 0-127"), "{}", error);
+
+        let error = run_script("set_param(\"wobble\", 1);", context()).unwrap_err();
+        assert!(error.contains("Unknown parameter 'wobble'"), "{}", error);
+
+        // Runaway scripts are stopped
+        assert!(run_script("loop {}", context()).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+// Scripting - Generative and batch-edit scripts (Rhai)
+//
+// Users write small Rhai scripts ("create a euclidean kick pattern",
+// "transpose the selection by a fifth") from the script console. A script
+// edits a copy of the active pattern, the tempo and the synth parameters;
+// the frontend applies the result only when the script succeeds.
+
+pub mod context;
+pub mod engine;
+
+pub use context::{ScriptContext, ScriptOutcome, ScriptParam};
+pub use engine::{euclidean_rhythm, run_script};
@@ -0,0 +1,245 @@
+// Arrangement - Patterns placed as clips on tracks along the timeline
+//
+// In song mode the tracks stop looping their pattern and play the clips of the
+// arrangement instead: a clip plays a pattern from a bar for a number of bars,
+// looping the pattern when the clip is longer and cutting the notes still
+// playing at its end. The clips of a track do not overlap. Positions are in
+// bars, so the arrangement follows tempo changes.
+//
+// Like instrument tracks, clips hold their pattern (shared with the audio
+// thread, see `Command::SetArrangement`); `DawState::send_pattern` refreshes
+// the clips of the edited pattern. Projects store the pattern ids.
+
+use crate::sequencer::{Pattern, PatternId};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Unique identifier for clips
+pub type ClipId = u64;
+
+/// Track of the clips played by the synth track (instrument tracks start at 1)
+pub const SYNTH_TRACK: u32 = 0;
+
+/// Global clip ID generator
+static NEXT_CLIP_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Generate a unique clip ID
+pub fn generate_clip_id() -> ClipId {
+    NEXT_CLIP_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A pattern placed on a track
+#[derive(Debug, Clone)]
+pub struct PatternClip {
+    pub id: ClipId,
+    /// Track playing the clip (`SYNTH_TRACK` or an instrument track)
+    pub track: u32,
+    /// First bar of the clip (0 = start of the song)
+    pub start_bar: u32,
+    /// Length in bars (at least 1)
+    pub length_bars: u32,
+    /// Pattern played from the clip start
+    pub pattern: Arc<Pattern>,
+}
+
+impl PatternClip {
+    /// Create a clip with a new id
+    pub fn new(track: u32, start_bar: u32, length_bars: u32, pattern: Arc<Pattern>) -> Self {
+        Self {
+            id: generate_clip_id(),
+            track,
+            start_bar,
+            length_bars: length_bars.max(1),
+            pattern,
+        }
+    }
+
+    /// Bar after the last bar of the clip
+    pub fn end_bar(&self) -> u32 {
+        self.start_bar + self.length_bars
+    }
+
+    /// Id of the pattern played
+    pub fn pattern_id(&self) -> PatternId {
+        self.pattern.id
+    }
+}
+
+/// Clips of every track, sorted by track then start
+#[derive(Debug, Clone, Default)]
+pub struct Arrangement {
+    clips: Vec<PatternClip>,
+}
+
+impl Arrangement {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All clips, sorted by track then start
+    pub fn clips(&self) -> &[PatternClip] {
+        &self.clips
+    }
+
+    /// Clips of a track, sorted by start (RT-safe)
+    pub fn track_clips(&self, track: u32) -> &[PatternClip] {
+        let first = self.clips.partition_point(|clip| clip.track < track);
+        let last = self.clips.partition_point(|clip| clip.track <= track);
+        &self.clips[first..last]
+    }
+
+    pub fn get(&self, id: ClipId) -> Option<&PatternClip> {
+        self.clips.iter().find(|clip| clip.id == id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clips.is_empty()
+    }
+
+    /// Length of the song in bars (end of the last clip)
+    pub fn length_bars(&self) -> u32 {
+        self.clips
+            .iter()
+            .map(PatternClip::end_bar)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Place a clip (fails if it overlaps another clip of its track)
+    pub fn add_clip(&mut self, mut clip: PatternClip) -> Result<(), String> {
+        clip.length_bars = clip.length_bars.max(1);
+        self.check_free(clip.track, clip.start_bar, clip.end_bar(), None)?;
+        self.clips.push(clip);
+        self.sort();
+        Ok(())
+    }
+
+    /// Remove a clip
+    pub fn remove_clip(&mut self, id: ClipId) -> Option<PatternClip> {
+        let index = self.clips.iter().position(|clip| clip.id == id)?;
+        Some(self.clips.remove(index))
+    }
+
+    /// Move a clip to a track and a start bar (fails on an overlap)
+    pub fn move_clip(&mut self, id: ClipId, track: u32, start_bar: u32) -> Result<(), String> {
+        let clip = self.get(id).ok_or("Clip not found")?;
+        self.check_free(track, start_bar, start_bar + clip.length_bars, Some(id))?;
+        let clip = self.clips.iter_mut().find(|clip| clip.id == id).unwrap();
+        clip.track = track;
+        clip.start_bar = start_bar;
+        self.sort();
+        Ok(())
+    }
+
+    /// Change the length of a clip (fails on an overlap)
+    pub fn resize_clip(&mut self, id: ClipId, length_bars: u32) -> Result<(), String> {
+        let clip = self.get(id).ok_or("Clip not found")?;
+        let length_bars = length_bars.max(1);
+        self.check_free(
+            clip.track,
+            clip.start_bar,
+            clip.start_bar + length_bars,
+            Some(id),
+        )?;
+        let clip = self.clips.iter_mut().find(|clip| clip.id == id).unwrap();
+        clip.length_bars = length_bars;
+        Ok(())
+    }
+
+    /// Play the new version of a pattern in the clips using it
+    ///
+    /// Returns whether a clip uses it.
+    pub fn update_pattern(&mut self, pattern: &Arc<Pattern>) -> bool {
+        let mut used = false;
+        for clip in &mut self.clips {
+            if clip.pattern.id == pattern.id {
+                clip.pattern = pattern.clone();
+                used = true;
+            }
+        }
+        used
+    }
+
+    /// Remove the clips of a track (track removed), returning whether it had any
+    pub fn remove_track(&mut self, track: u32) -> bool {
+        let count = self.clips.len();
+        self.clips.retain(|clip| clip.track != track);
+        self.clips.len() != count
+    }
+
+    fn check_free(
+        &self,
+        track: u32,
+        start_bar: u32,
+        end_bar: u32,
+        except: Option<ClipId>,
+    ) -> Result<(), String> {
+        let overlap = self.track_clips(track).iter().any(|clip| {
+            Some(clip.id) != except && clip.start_bar < end_bar && start_bar < clip.end_bar()
+        });
+        if overlap {
+            return Err(format!(
+                "Bars {}-{} overlap another clip of the track",
+                start_bar + 1,
+                end_bar
+            ));
+        }
+        Ok(())
+    }
+
+    fn sort(&mut self) {
+        self.clips.sort_by_key(|clip| (clip.track, clip.start_bar));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(id: PatternId) -> Arc<Pattern> {
+        Arc::new(Pattern::new(id, format!("Pattern {}", id), 2))
+    }
+
+    #[test]
+    fn test_clip_placement() {
+        let mut arrangement = Arrangement::new();
+        let verse = PatternClip::new(SYNTH_TRACK, 0, 4, pattern(1));
+        let chorus = PatternClip::new(SYNTH_TRACK, 4, 4, pattern(2));
+        let bass = PatternClip::new(1, 2, 2, pattern(1));
+        let (verse_id, chorus_id) = (verse.id, chorus.id);
+        arrangement.add_clip(chorus).unwrap();
+        arrangement.add_clip(bass).unwrap();
+        arrangement.add_clip(verse).unwrap();
+
+        assert_eq!(arrangement.length_bars(), 8);
+        let synth: Vec<ClipId> = arrangement
+            .track_clips(SYNTH_TRACK)
+            .iter()
+            .map(|clip| clip.id)
+            .collect();
+        assert_eq!(synth, vec![verse_id, chorus_id]);
+        assert_eq!(arrangement.track_clips(1).len(), 1);
+        assert!(arrangement.track_clips(2).is_empty());
+
+        // Clips of a track do not overlap
+        assert!(
+            arrangement
+                .add_clip(PatternClip::new(SYNTH_TRACK, 3, 2, pattern(3)))
+                .is_err()
+        );
+        assert!(arrangement.resize_clip(verse_id, 5).is_err());
+        assert!(arrangement.move_clip(chorus_id, SYNTH_TRACK, 2).is_err());
+        arrangement.move_clip(chorus_id, SYNTH_TRACK, 8).unwrap();
+        arrangement.resize_clip(verse_id, 8).unwrap();
+        assert_eq!(arrangement.length_bars(), 12);
+
+        // Edited patterns reach the clips playing them
+        let edited = Pattern::new(1, "Edited".to_string(), 1);
+        assert!(arrangement.update_pattern(&Arc::new(edited)));
+        assert_eq!(arrangement.get(verse_id).unwrap().pattern.length_bars, 1);
+
+        assert!(arrangement.remove_track(1));
+        assert!(arrangement.remove_clip(verse_id).is_some());
+        assert_eq!(arrangement.clips().len(), 1);
+    }
+}
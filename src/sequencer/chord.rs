@@ -0,0 +1,331 @@
+// Chords - Chord insertion, inversions, voicings and strums
+//
+// Chord edits work on pattern notes addressed by id (the piano roll
+// selection). Inversions and voicings move notes by octaves; a strum staggers
+// the starts of notes played together, keeping their ends.
+
+use crate::sequencer::{Note, NoteId, Pattern, Position, Tempo, TimeSignature, generate_note_id};
+
+/// Chord quality (intervals from the root)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Sus2,
+    Sus4,
+    Major7,
+    Minor7,
+    Dominant7,
+    HalfDiminished7,
+}
+
+impl ChordQuality {
+    /// All qualities, for the chord palette
+    pub const ALL: [ChordQuality; 10] = [
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Diminished,
+        ChordQuality::Augmented,
+        ChordQuality::Sus2,
+        ChordQuality::Sus4,
+        ChordQuality::Major7,
+        ChordQuality::Minor7,
+        ChordQuality::Dominant7,
+        ChordQuality::HalfDiminished7,
+    ];
+
+    /// Semitones from the root
+    pub fn intervals(self) -> &'static [u8] {
+        match self {
+            ChordQuality::Major => &[0, 4, 7],
+            ChordQuality::Minor => &[0, 3, 7],
+            ChordQuality::Diminished => &[0, 3, 6],
+            ChordQuality::Augmented => &[0, 4, 8],
+            ChordQuality::Sus2 => &[0, 2, 7],
+            ChordQuality::Sus4 => &[0, 5, 7],
+            ChordQuality::Major7 => &[0, 4, 7, 11],
+            ChordQuality::Minor7 => &[0, 3, 7, 10],
+            ChordQuality::Dominant7 => &[0, 4, 7, 10],
+            ChordQuality::HalfDiminished7 => &[0, 3, 6, 10],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChordQuality::Major => "major",
+            ChordQuality::Minor => "minor",
+            ChordQuality::Diminished => "diminished",
+            ChordQuality::Augmented => "augmented",
+            ChordQuality::Sus2 => "sus2",
+            ChordQuality::Sus4 => "sus4",
+            ChordQuality::Major7 => "major 7th",
+            ChordQuality::Minor7 => "minor 7th",
+            ChordQuality::Dominant7 => "dominant 7th",
+            ChordQuality::HalfDiminished7 => "half-diminished 7th",
+        }
+    }
+
+    /// Chord symbol suffix (e.g. "m7" for C minor seventh: Cm7)
+    pub fn symbol(self) -> &'static str {
+        match self {
+            ChordQuality::Major => "",
+            ChordQuality::Minor => "m",
+            ChordQuality::Diminished => "dim",
+            ChordQuality::Augmented => "aug",
+            ChordQuality::Sus2 => "sus2",
+            ChordQuality::Sus4 => "sus4",
+            ChordQuality::Major7 => "maj7",
+            ChordQuality::Minor7 => "m7",
+            ChordQuality::Dominant7 => "7",
+            ChordQuality::HalfDiminished7 => "m7b5",
+        }
+    }
+}
+
+/// Order in which a strum plays the notes of a chord
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrumDirection {
+    /// Lowest note first
+    Up,
+    /// Highest note first
+    Down,
+}
+
+/// MIDI notes of a chord on `root` (notes above 127 are left out)
+pub fn chord_pitches(root: u8, quality: ChordQuality) -> Vec<u8> {
+    quality
+        .intervals()
+        .iter()
+        .map(|&interval| root as u16 + interval as u16)
+        .filter(|&pitch| pitch <= 127)
+        .map(|pitch| pitch as u8)
+        .collect()
+}
+
+/// Add notes of the same start and duration, returning their ids
+///
+/// Duplicate pitches are added once.
+pub fn insert_chord(
+    pattern: &mut Pattern,
+    pitches: &[u8],
+    start: Position,
+    duration_samples: u64,
+    velocity: u8,
+) -> Vec<NoteId> {
+    let mut pitches = pitches.to_vec();
+    pitches.sort_unstable();
+    pitches.dedup();
+    pitches
+        .into_iter()
+        .map(|pitch| {
+            let note = Note::new(generate_note_id(), pitch, start, duration_samples, velocity);
+            pattern.add_note(note);
+            note.id
+        })
+        .collect()
+}
+
+/// Pitches of the notes with these ids, lowest first
+fn sorted_pitches(pattern: &Pattern, ids: &[NoteId]) -> Vec<(u8, NoteId)> {
+    let mut notes: Vec<_> = ids
+        .iter()
+        .filter_map(|id| pattern.get_note(*id))
+        .map(|note| (note.pitch, note.id))
+        .collect();
+    notes.sort_unstable();
+    notes
+}
+
+fn set_pitch(pattern: &mut Pattern, id: NoteId, pitch: u8) {
+    if let Some(note) = pattern.get_note_mut(id) {
+        note.pitch = pitch;
+    }
+}
+
+/// Invert a chord: the lowest note goes up an octave (or the highest down)
+///
+/// Returns false when there is no chord or the note would leave the MIDI range.
+pub fn invert_chord(pattern: &mut Pattern, ids: &[NoteId], up: bool) -> bool {
+    let notes = sorted_pitches(pattern, ids);
+    if notes.len() < 2 {
+        return false;
+    }
+    let (pitch, id) = if up { notes[0] } else { notes[notes.len() - 1] };
+    let moved = if up {
+        pitch.checked_add(12).filter(|&p| p <= 127)
+    } else {
+        pitch.checked_sub(12)
+    };
+    match moved {
+        Some(moved) => {
+            set_pitch(pattern, id, moved);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Drop 2 voicing: the second highest note goes down an octave
+///
+/// Returns false for chords of less than three notes.
+pub fn drop2_voicing(pattern: &mut Pattern, ids: &[NoteId]) -> bool {
+    let notes = sorted_pitches(pattern, ids);
+    if notes.len() < 3 {
+        return false;
+    }
+    let (pitch, id) = notes[notes.len() - 2];
+    match pitch.checked_sub(12) {
+        Some(dropped) => {
+            set_pitch(pattern, id, dropped);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Stagger the starts of notes played together by `step_samples`
+///
+/// Notes starting at the same position form a chord; each note of a chord
+/// starts one step after the previous one (in pitch order) and keeps its end.
+/// Returns false if no selected notes are played together.
+pub fn strum(
+    pattern: &mut Pattern,
+    ids: &[NoteId],
+    step_samples: u64,
+    direction: StrumDirection,
+    sample_rate: f64,
+    tempo: &Tempo,
+    time_signature: &TimeSignature,
+) -> bool {
+    let mut notes: Vec<Note> = ids
+        .iter()
+        .filter_map(|id| pattern.get_note(*id).copied())
+        .collect();
+    notes.sort_by_key(|note| {
+        let pitch = i16::from(note.pitch);
+        let order = match direction {
+            StrumDirection::Up => pitch,
+            StrumDirection::Down => -pitch,
+        };
+        (note.start.samples, order)
+    });
+
+    let mut strummed = false;
+    for chord in notes.chunk_by(|a, b| a.start.samples == b.start.samples) {
+        for (index, note) in chord.iter().enumerate().skip(1) {
+            let offset = step_samples * index as u64;
+            if let Some(edited) = pattern.get_note_mut(note.id) {
+                edited.start = Position::from_samples(
+                    note.start.samples + offset,
+                    sample_rate,
+                    tempo,
+                    time_signature,
+                );
+                if note.duration_samples > offset {
+                    edited.duration_samples = note.duration_samples - offset;
+                }
+                strummed = true;
+            }
+        }
+    }
+
+    if strummed {
+        // Keep the notes sorted by start
+        pattern.set_notes(pattern.notes().to_vec());
+    }
+    strummed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c_major_chord(pattern: &mut Pattern) -> Vec<NoteId> {
+        let pitches = chord_pitches(60, ChordQuality::Major);
+        insert_chord(pattern, &pitches, Position::zero(), 24000, 100)
+    }
+
+    fn pitches(pattern: &Pattern) -> Vec<u8> {
+        let mut pitches: Vec<u8> = pattern.notes().iter().map(|n| n.pitch).collect();
+        pitches.sort_unstable();
+        pitches
+    }
+
+    #[test]
+    fn test_chord_pitches() {
+        assert_eq!(
+            chord_pitches(57, ChordQuality::Minor7),
+            vec![57, 60, 64, 67]
+        );
+        assert_eq!(chord_pitches(125, ChordQuality::Major), vec![125]);
+
+        let mut pattern = Pattern::new_default(1, "Chords".to_string());
+        let ids = insert_chord(&mut pattern, &[64, 60, 64], Position::zero(), 100, 90);
+        assert_eq!(ids.len(), 2);
+        assert_eq!(pitches(&pattern), vec![60, 64]);
+    }
+
+    #[test]
+    fn test_inversions_and_drop2() {
+        let mut pattern = Pattern::new_default(1, "Chords".to_string());
+        let ids = c_major_chord(&mut pattern);
+
+        assert!(invert_chord(&mut pattern, &ids, true));
+        assert_eq!(pitches(&pattern), vec![64, 67, 72]);
+        assert!(invert_chord(&mut pattern, &ids, false));
+        assert_eq!(pitches(&pattern), vec![60, 64, 67]);
+
+        assert!(drop2_voicing(&mut pattern, &ids));
+        assert_eq!(pitches(&pattern), vec![52, 60, 67]);
+        assert!(!drop2_voicing(&mut pattern, &ids[..2]));
+        assert!(!invert_chord(&mut pattern, &ids[..1], true));
+    }
+
+    #[test]
+    fn test_strum_staggers_starts_and_keeps_ends() {
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        let mut pattern = Pattern::new_default(1, "Chords".to_string());
+        let ids = c_major_chord(&mut pattern);
+
+        let strummed = strum(
+            &mut pattern,
+            &ids,
+            1000,
+            StrumDirection::Down,
+            48000.0,
+            &tempo,
+            &time_signature,
+        );
+        assert!(strummed);
+        let starts: Vec<(u8, u64, u64)> = pattern
+            .notes()
+            .iter()
+            .map(|n| {
+                (
+                    n.pitch,
+                    n.start.samples,
+                    n.start.samples + n.duration_samples,
+                )
+            })
+            .collect();
+        assert_eq!(
+            starts,
+            vec![(67, 0, 24000), (64, 1000, 24000), (60, 2000, 24000)]
+        );
+
+        // Notes no longer start together
+        let strummed = strum(
+            &mut pattern,
+            &ids,
+            1000,
+            StrumDirection::Up,
+            48000.0,
+            &tempo,
+            &time_signature,
+        );
+        assert!(!strummed);
+    }
+}
@@ -0,0 +1,442 @@
+// Pattern generators - Euclidean rhythms, random melodies and fills
+//
+// Generators write on a grid of steps covering the pattern (e.g. 1/16 notes).
+// Euclidean rhythms spread pulses as evenly as possible over a cycle of steps
+// (Toussaint), repeated over the pattern on one pitch. Random melodies walk the
+// notes of a scale; they are reproducible from their seed. Fills work on the
+// selected notes: a ratchet splits each note into quick repeats, a repeat
+// copies the selection after itself.
+
+use crate::sequencer::{
+    Note, NoteId, Pattern, Position, Scale, Tempo, TimeSignature, generate_note_id,
+};
+
+/// Grid of steps the generators write on
+#[derive(Debug, Clone, Copy)]
+pub struct StepGrid {
+    pub sample_rate: f64,
+    pub tempo: Tempo,
+    pub time_signature: TimeSignature,
+    /// Steps per beat (4 = 1/16 notes)
+    pub steps_per_beat: u32,
+    /// Length covered by the grid (the pattern's)
+    pub length_samples: u64,
+}
+
+impl StepGrid {
+    /// Grid covering a pattern
+    pub fn new(
+        pattern: &Pattern,
+        steps_per_beat: u32,
+        sample_rate: f64,
+        tempo: Tempo,
+        time_signature: TimeSignature,
+    ) -> Self {
+        Self {
+            sample_rate,
+            tempo,
+            time_signature,
+            steps_per_beat: steps_per_beat.max(1),
+            length_samples: pattern.length_samples(sample_rate, &tempo, &time_signature),
+        }
+    }
+
+    /// Duration of a step in samples
+    pub fn step_samples(&self) -> f64 {
+        self.tempo.beat_duration_samples(self.sample_rate) / self.steps_per_beat as f64
+    }
+
+    /// Number of whole steps in the grid
+    pub fn step_count(&self) -> usize {
+        let step = self.step_samples();
+        if step <= 0.0 {
+            return 0;
+        }
+        (self.length_samples as f64 / step) as usize
+    }
+
+    /// Start of a step in samples
+    pub fn step_start(&self, step: usize) -> u64 {
+        (step as f64 * self.step_samples()).round() as u64
+    }
+
+    /// Timeline position of a sample offset
+    pub fn position(&self, samples: u64) -> Position {
+        Position::from_samples(samples, self.sample_rate, &self.tempo, &self.time_signature)
+    }
+}
+
+/// Generator settings (the last values of the Generate menu)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorSettings {
+    /// Steps per beat of the grid (4 = 1/16 notes)
+    pub steps_per_beat: u32,
+    pub velocity: u8,
+    /// Euclidean rhythm: pulses spread over a cycle of steps
+    pub pulses: usize,
+    pub steps: usize,
+    pub rotation: usize,
+    pub pitch: u8,
+    /// Random melody: share of steps playing a note (0.0 to 1.0)
+    pub density: f32,
+    pub low: u8,
+    pub high: u8,
+    pub seed: u64,
+    /// Fills: repeats of a ratcheted note, copies of a repeated selection
+    pub ratchet: usize,
+    pub repeats: usize,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self {
+            steps_per_beat: 4,
+            velocity: 100,
+            pulses: 3,
+            steps: 8,
+            rotation: 0,
+            pitch: 36,
+            density: 0.6,
+            low: 60,
+            high: 72,
+            seed: 1,
+            ratchet: 3,
+            repeats: 1,
+        }
+    }
+}
+
+/// Euclidean rhythm: `pulses` onsets spread over `steps` steps, rotated left
+///
+/// E(3, 8) gives x..x..x. (the tresillo).
+pub fn euclidean_rhythm(pulses: usize, steps: usize, rotation: usize) -> Vec<bool> {
+    let pulses = pulses.min(steps);
+    (0..steps)
+        .map(|step| ((step + rotation) % steps) * pulses % steps < pulses)
+        .collect()
+}
+
+/// Write a euclidean rhythm on `pitch` over the whole pattern
+///
+/// The notes already on that pitch are replaced. Returns the ids of the new
+/// notes.
+pub fn generate_euclidean(
+    pattern: &mut Pattern,
+    grid: &StepGrid,
+    pitch: u8,
+    pulses: usize,
+    steps: usize,
+    rotation: usize,
+    velocity: u8,
+) -> Vec<NoteId> {
+    if steps == 0 {
+        return Vec::new();
+    }
+    let rhythm = euclidean_rhythm(pulses, steps, rotation);
+    let mut notes: Vec<Note> = pattern
+        .notes()
+        .iter()
+        .filter(|note| note.pitch != pitch)
+        .copied()
+        .collect();
+
+    let mut ids = Vec::new();
+    for step in (0..grid.step_count()).filter(|step| rhythm[step % steps]) {
+        let start = grid.step_start(step);
+        let duration = grid.step_start(step + 1) - start;
+        let note = Note::new(
+            generate_note_id(),
+            pitch.min(127),
+            grid.position(start),
+            duration,
+            velocity.clamp(1, 127),
+        );
+        ids.push(note.id);
+        notes.push(note);
+    }
+    pattern.set_notes(notes);
+    ids
+}
+
+/// Reproducible pseudo-random numbers (xorshift64*)
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Float in [0, 1)
+    fn unit(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Integer in [0, n)
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+}
+
+/// Write a random melody in `scale` between `low` and `high` over the pattern
+///
+/// Each step plays a note with probability `density`; the melody walks up or
+/// down the scale by at most two degrees and a note lasts until the next note
+/// (two steps at most). The same seed gives the same melody. Returns the ids
+/// of the new notes (added to the existing ones).
+pub fn generate_melody(
+    pattern: &mut Pattern,
+    grid: &StepGrid,
+    scale: Scale,
+    (low, high): (u8, u8),
+    density: f32,
+    velocity: u8,
+    seed: u64,
+) -> Vec<NoteId> {
+    let (low, high) = (low.min(high).min(127), high.max(low).min(127));
+    let pitches: Vec<u8> = (low..=high)
+        .filter(|&pitch| scale.contains(pitch))
+        .collect();
+    if pitches.is_empty() {
+        return Vec::new();
+    }
+
+    let mut rng = Rng::new(seed);
+    let steps = grid.step_count();
+    let playing: Vec<usize> = (0..steps)
+        .filter(|_| rng.unit() < density.clamp(0.0, 1.0))
+        .collect();
+
+    let mut degree = pitches.len() / 2;
+    let mut ids = Vec::new();
+    for (index, &step) in playing.iter().enumerate() {
+        let walk = rng.below(5) as isize - 2;
+        degree = (degree as isize + walk).clamp(0, pitches.len() as isize - 1) as usize;
+
+        // Held until the next note, at most two steps
+        let next = playing.get(index + 1).copied().unwrap_or(steps);
+        let end = grid.step_start(next.min(step + 2));
+        let start = grid.step_start(step);
+        // Slightly varied velocities
+        let accent = rng.below(21) as i16 - 10;
+        let note = Note::new(
+            generate_note_id(),
+            pitches[degree],
+            grid.position(start),
+            end - start,
+            (velocity as i16 + accent).clamp(1, 127) as u8,
+        );
+        ids.push(note.id);
+        pattern.add_note(note);
+    }
+    ids
+}
+
+/// Split each selected note into `count` equal repeats (a ratchet)
+///
+/// Returns the ids of the notes replacing the selection (None if no note was
+/// split).
+pub fn ratchet(
+    pattern: &mut Pattern,
+    ids: &[NoteId],
+    count: usize,
+    grid: &StepGrid,
+) -> Option<Vec<NoteId>> {
+    if count < 2 {
+        return None;
+    }
+    let mut new_ids = Vec::new();
+    for id in ids {
+        let Some(note) = pattern.get_note(*id).copied() else {
+            continue;
+        };
+        let length = note.duration_samples / count as u64;
+        if length == 0 {
+            continue;
+        }
+        pattern.remove_note(note.id);
+        for repeat in 0..count as u64 {
+            let start = note.start.samples + repeat * length;
+            let hit = Note {
+                id: generate_note_id(),
+                start: grid.position(start),
+                duration_samples: length,
+                ..note
+            };
+            new_ids.push(hit.id);
+            pattern.add_note(hit);
+        }
+    }
+    (!new_ids.is_empty()).then_some(new_ids)
+}
+
+/// Copy the selected notes `times` times right after themselves
+///
+/// The copied span starts at the first selected note and ends on the step
+/// after the last note end. Copies past the pattern end are dropped. Returns
+/// the ids of the copies.
+pub fn repeat(pattern: &mut Pattern, ids: &[NoteId], times: usize, grid: &StepGrid) -> Vec<NoteId> {
+    let notes: Vec<Note> = ids
+        .iter()
+        .filter_map(|id| pattern.get_note(*id).copied())
+        .collect();
+    let (Some(first), Some(last)) = (
+        notes.iter().map(|note| note.start.samples).min(),
+        notes
+            .iter()
+            .map(|note| note.start.samples + note.duration_samples)
+            .max(),
+    ) else {
+        return Vec::new();
+    };
+    let step = grid.step_samples();
+    let span = (((last - first) as f64 / step).ceil().max(1.0) * step).round() as u64;
+
+    let mut copies = Vec::new();
+    for time in 1..=times as u64 {
+        for note in &notes {
+            let start = note.start.samples + span * time;
+            if start >= grid.length_samples {
+                continue;
+            }
+            let copy = Note {
+                id: generate_note_id(),
+                start: grid.position(start),
+                ..*note
+            };
+            copies.push(copy.id);
+            pattern.add_note(copy);
+        }
+    }
+    copies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::ScaleKind;
+
+    fn grid(pattern: &Pattern) -> StepGrid {
+        // 120 BPM at 48 kHz: 6000 samples per 1/16 step
+        StepGrid::new(
+            pattern,
+            4,
+            48000.0,
+            Tempo::new(120.0),
+            TimeSignature::four_four(),
+        )
+    }
+
+    fn starts(pattern: &Pattern) -> Vec<u64> {
+        pattern
+            .notes()
+            .iter()
+            .map(|note| note.start.samples)
+            .collect()
+    }
+
+    #[test]
+    fn test_euclidean_rhythms() {
+        let pattern = |rhythm: Vec<bool>| -> String {
+            rhythm
+                .iter()
+                .map(|&hit| if hit { 'x' } else { '.' })
+                .collect()
+        };
+        assert_eq!(pattern(euclidean_rhythm(3, 8, 0)), "x..x..x.");
+        assert_eq!(pattern(euclidean_rhythm(5, 8, 0)), "x.x.xx.x");
+        assert_eq!(pattern(euclidean_rhythm(3, 8, 1)), "..x..x.x");
+        assert_eq!(pattern(euclidean_rhythm(4, 4, 0)), "xxxx");
+        assert_eq!(pattern(euclidean_rhythm(9, 4, 0)), "xxxx");
+
+        // One bar: the 8-step cycle plays twice, the kick lane is replaced
+        let mut pattern = Pattern::new(1, "Drums".to_string(), 1);
+        let grid = grid(&pattern);
+        pattern.add_note(Note::new(
+            generate_note_id(),
+            36,
+            Position::zero(),
+            100,
+            100,
+        ));
+        pattern.add_note(Note::new(
+            generate_note_id(),
+            42,
+            Position::zero(),
+            100,
+            100,
+        ));
+        let ids = generate_euclidean(&mut pattern, &grid, 36, 3, 8, 0, 110);
+        assert_eq!(ids.len(), 6);
+        assert_eq!(pattern.note_count(), 7);
+        let kicks: Vec<u64> = pattern
+            .notes()
+            .iter()
+            .filter(|note| note.pitch == 36)
+            .map(|note| note.start.samples)
+            .collect();
+        assert_eq!(kicks, vec![0, 18000, 36000, 48000, 66000, 84000]);
+    }
+
+    #[test]
+    fn test_random_melody_is_in_scale_and_reproducible() {
+        let scale = Scale::new(9, ScaleKind::MinorPentatonic);
+        let mut first = Pattern::new(1, "Melody".to_string(), 2);
+        let grid = grid(&first);
+        let ids = generate_melody(&mut first, &grid, scale, (57, 81), 0.5, 100, 42);
+        assert!(!ids.is_empty() && ids.len() < grid.step_count());
+        assert!(
+            first
+                .notes()
+                .iter()
+                .all(|note| { scale.contains(note.pitch) && (57..=81).contains(&note.pitch) })
+        );
+        // Notes never overlap the next one
+        for pair in first.notes().windows(2) {
+            assert!(pair[0].start.samples + pair[0].duration_samples <= pair[1].start.samples);
+        }
+
+        let mut second = Pattern::new(2, "Melody".to_string(), 2);
+        generate_melody(&mut second, &grid, scale, (57, 81), 0.5, 100, 42);
+        let pitches =
+            |pattern: &Pattern| pattern.notes().iter().map(|n| n.pitch).collect::<Vec<_>>();
+        assert_eq!(starts(&first), starts(&second));
+        assert_eq!(pitches(&first), pitches(&second));
+    }
+
+    #[test]
+    fn test_ratchet_and_repeat_fills() {
+        let mut pattern = Pattern::new(1, "Fill".to_string(), 1);
+        let grid = grid(&pattern);
+        let snare = Note::new(generate_note_id(), 38, Position::zero(), 12000, 100);
+        pattern.add_note(snare);
+
+        let hits = ratchet(&mut pattern, &[snare.id], 3, &grid).unwrap();
+        assert_eq!(hits.len(), 3);
+        assert_eq!(starts(&pattern), vec![0, 4000, 8000]);
+        assert!(
+            pattern
+                .notes()
+                .iter()
+                .all(|note| note.duration_samples == 4000)
+        );
+        assert!(ratchet(&mut pattern, &hits, 1, &grid).is_none());
+
+        // The 2-step span is copied three times (the last copy is in the bar)
+        let copies = repeat(&mut pattern, &hits, 3, &grid);
+        assert_eq!(copies.len(), 9);
+        assert_eq!(pattern.note_count(), 12);
+        assert_eq!(starts(&pattern)[3..6], [12000, 16000, 20000]);
+
+        // Copies past the pattern end are dropped
+        let late = Note::new(generate_note_id(), 38, grid.position(90000), 6000, 100);
+        pattern.add_note(late);
+        assert!(repeat(&mut pattern, &[late.id], 2, &grid).is_empty());
+    }
+}
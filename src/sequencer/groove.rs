@@ -0,0 +1,460 @@
+// Groove templates - Timing and velocity feel extracted from a performance
+//
+// A groove is a cycle of grid steps (e.g. one bar of 1/16 notes) with, for
+// each step, how early or late the hits land (in fractions of a step) and how
+// loud they are relative to the average hit. Templates are extracted from a
+// recorded MIDI take or from the transients of an audio loop, or built as
+// plain swing, then applied to other patterns with a strength.
+//
+// A pattern can also carry a groove of its own (`PatternGroove`): swing plus
+// an optional template, played by the sequencer without moving the notes.
+
+use crate::audio::clip_player::AudioClip;
+use crate::audio::warp::detect_transients;
+use crate::sampler::loader::SampleData;
+use crate::sequencer::{Note, Pattern, Position, Tempo, TimeSignature};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Window after an onset where an audio hit's level is measured (seconds)
+const HIT_LEVEL_WINDOW: f32 = 0.02;
+
+/// Largest velocity scale of a step
+const MAX_VELOCITY_SCALE: f32 = 2.0;
+
+/// Grid of the pattern swing (1/16 notes)
+pub const SWING_STEPS_PER_BEAT: u32 = 4;
+
+/// Swing range in percent (straight to dotted)
+pub const MIN_SWING: f32 = 50.0;
+pub const MAX_SWING: f32 = 75.0;
+
+/// Timing and velocity feel of a cycle of grid steps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Groove {
+    pub name: String,
+    /// Grid steps per beat (4 = 1/16 notes)
+    pub steps_per_beat: u32,
+    /// Timing of each step, in fractions of a step (-0.5 to 0.5, late > 0)
+    pub offsets: Vec<f32>,
+    /// Velocity scale of each step (1.0 = unchanged)
+    pub velocities: Vec<f32>,
+}
+
+impl Groove {
+    /// Classic swing: every second step is delayed
+    ///
+    /// `percent` is the share of a step pair taken by its first step (50 =
+    /// straight, 66.7 = triplet feel, 75 = dotted).
+    pub fn swing(percent: f32, steps_per_beat: u32) -> Self {
+        let percent = percent.clamp(50.0, 75.0);
+        Self {
+            name: format!("Swing {:.0}%", percent),
+            steps_per_beat: steps_per_beat.max(1),
+            offsets: vec![0.0, 2.0 * percent / 100.0 - 1.0],
+            velocities: vec![1.0, 1.0],
+        }
+    }
+
+    /// Extract the groove of a recorded MIDI take
+    ///
+    /// Notes are placed on the grid of the take's tempo; chords count as one
+    /// hit per note.
+    pub fn from_notes(
+        name: String,
+        notes: &[Note],
+        samples_per_beat: f64,
+        steps_per_beat: u32,
+        length_steps: usize,
+    ) -> Result<Self, String> {
+        if samples_per_beat <= 0.0 {
+            return Err("Invalid tempo".to_string());
+        }
+        let steps_per_sample = steps_per_beat as f64 / samples_per_beat;
+        let hits: Vec<(f64, f32)> = notes
+            .iter()
+            .map(|note| {
+                (
+                    note.start.samples as f64 * steps_per_sample,
+                    note.velocity as f32,
+                )
+            })
+            .collect();
+        Self::from_hits(name, &hits, steps_per_beat, length_steps)
+    }
+
+    /// Extract the groove of an audio loop from its transients
+    ///
+    /// `data` is mono audio starting on a beat, at `bpm`. Hit levels are the
+    /// peaks just after each onset.
+    pub fn from_audio(
+        name: String,
+        data: &[f32],
+        sample_rate: u32,
+        bpm: f64,
+        steps_per_beat: u32,
+        length_steps: usize,
+    ) -> Result<Self, String> {
+        if bpm <= 0.0 || sample_rate == 0 {
+            return Err("Invalid tempo".to_string());
+        }
+        let steps_per_sample = bpm / 60.0 * steps_per_beat as f64 / sample_rate as f64;
+        let window = (HIT_LEVEL_WINDOW * sample_rate as f32) as usize;
+        let hits: Vec<(f64, f32)> = detect_transients(data, sample_rate)
+            .into_iter()
+            .map(|onset| {
+                let attack = &data[onset..(onset + window).min(data.len())];
+                let level = attack.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+                (onset as f64 * steps_per_sample, level)
+            })
+            .collect();
+        Self::from_hits(name, &hits, steps_per_beat, length_steps)
+    }
+
+    /// Extract the groove of the region an audio clip plays, recorded at `source_bpm`
+    pub fn from_clip(
+        name: String,
+        clip: &AudioClip,
+        source_bpm: f64,
+        sample_rate: f32,
+        steps_per_beat: u32,
+        length_steps: usize,
+    ) -> Result<Self, String> {
+        let SampleData::F32(data) = &clip.sample.data;
+        // Source samples per engine sample
+        let step = clip.sample.sample_rate as f64 / sample_rate as f64;
+        let start = ((clip.offset as f64 * step) as usize).min(data.len());
+        let end = (((clip.offset + clip.length) as f64 * step) as usize).min(data.len());
+        if end <= start {
+            return Err("The clip is empty".to_string());
+        }
+        Self::from_audio(
+            name,
+            &data[start..end],
+            clip.sample.sample_rate,
+            source_bpm,
+            steps_per_beat,
+            length_steps,
+        )
+    }
+
+    /// Average the hits (grid position in steps, level) landing on each step
+    fn from_hits(
+        name: String,
+        hits: &[(f64, f32)],
+        steps_per_beat: u32,
+        length_steps: usize,
+    ) -> Result<Self, String> {
+        if steps_per_beat == 0 || length_steps == 0 {
+            return Err("The groove needs at least one step".to_string());
+        }
+        if hits.is_empty() {
+            return Err("No hits found to extract a groove from".to_string());
+        }
+
+        let mut offsets = vec![0.0f64; length_steps];
+        let mut levels = vec![0.0f64; length_steps];
+        let mut counts = vec![0usize; length_steps];
+        for &(position, level) in hits {
+            let step = position.round();
+            let index = (step as i64).rem_euclid(length_steps as i64) as usize;
+            offsets[index] += position - step;
+            levels[index] += level as f64;
+            counts[index] += 1;
+        }
+
+        let mean_level =
+            hits.iter().map(|&(_, level)| level as f64).sum::<f64>() / hits.len() as f64;
+        let offsets = offsets
+            .iter()
+            .zip(&counts)
+            .map(|(&sum, &count)| {
+                if count == 0 {
+                    0.0
+                } else {
+                    (sum / count as f64) as f32
+                }
+            })
+            .collect();
+        let velocities = levels
+            .iter()
+            .zip(&counts)
+            .map(|(&sum, &count)| {
+                if count == 0 || mean_level <= 0.0 {
+                    1.0
+                } else {
+                    ((sum / count as f64 / mean_level) as f32).min(MAX_VELOCITY_SCALE)
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            name,
+            steps_per_beat,
+            offsets,
+            velocities,
+        })
+    }
+
+    /// Number of steps in the cycle
+    pub fn length_steps(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Move the notes of a pattern toward the groove (strength 0.0 to 1.0)
+    ///
+    /// Each note is assigned to its nearest grid step; its start moves toward
+    /// the step's groove position and its velocity is scaled. Durations are
+    /// kept.
+    pub fn apply(
+        &self,
+        pattern: &mut Pattern,
+        strength: f32,
+        sample_rate: f64,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+    ) {
+        let length = self.offsets.len().min(self.velocities.len());
+        if length == 0 || self.steps_per_beat == 0 {
+            return;
+        }
+        let strength = strength.clamp(0.0, 1.0) as f64;
+        let samples_per_step =
+            tempo.beat_duration_samples(sample_rate) / self.steps_per_beat as f64;
+
+        let notes = pattern
+            .notes()
+            .iter()
+            .map(|note| {
+                let position = note.start.samples as f64 / samples_per_step;
+                let step = position.round();
+                let index = (step as i64).rem_euclid(length as i64) as usize;
+
+                let target = (step + self.offsets[index] as f64) * samples_per_step;
+                let start =
+                    note.start.samples as f64 + (target - note.start.samples as f64) * strength;
+                let scale = 1.0 + (self.velocities[index] as f64 - 1.0) * strength;
+                let velocity = (note.velocity as f64 * scale).round().clamp(1.0, 127.0);
+
+                Note {
+                    start: Position::from_samples(
+                        start.max(0.0).round() as u64,
+                        sample_rate,
+                        tempo,
+                        time_signature,
+                    ),
+                    velocity: velocity as u8,
+                    ..*note
+                }
+            })
+            .collect();
+        pattern.set_notes(notes);
+    }
+
+    /// Offset (in samples) and velocity scale of the step nearest a position
+    fn feel_at(&self, samples: f64, samples_per_beat: f64) -> (f64, f32) {
+        let length = self.offsets.len().min(self.velocities.len());
+        if length == 0 || self.steps_per_beat == 0 {
+            return (0.0, 1.0);
+        }
+        let samples_per_step = samples_per_beat / self.steps_per_beat as f64;
+        let step = (samples / samples_per_step).round() as i64;
+        let index = step.rem_euclid(length as i64) as usize;
+        (
+            self.offsets[index] as f64 * samples_per_step,
+            self.velocities[index],
+        )
+    }
+
+    /// Save the template to a JSON file
+    pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize groove: {}", e))?;
+        std::fs::write(path, json).map_err(|e| format!("Failed to write groove file: {}", e))
+    }
+
+    /// Load a template from a JSON file
+    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read groove file: {}", e))?;
+        let groove: Self = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse groove file: {}", e))?;
+        if groove.steps_per_beat == 0
+            || groove.offsets.is_empty()
+            || groove.offsets.len() != groove.velocities.len()
+        {
+            return Err("Invalid groove file: steps do not match".to_string());
+        }
+        Ok(groove)
+    }
+}
+
+/// Playback feel of a pattern: swing and an optional groove template
+///
+/// Unlike `Groove::apply`, nothing is written to the notes: the player shifts
+/// each note by the offset of its nearest step and scales its velocity, so the
+/// feel can be changed or removed at any time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PatternGroove {
+    /// Swing of 1/16 note pairs in percent (50 = straight, 66.7 = triplet
+    /// feel, 75 = dotted)
+    pub swing: f32,
+    /// Template played on top of the swing
+    pub template: Option<Groove>,
+}
+
+impl Default for PatternGroove {
+    fn default() -> Self {
+        Self {
+            swing: MIN_SWING,
+            template: None,
+        }
+    }
+}
+
+impl PatternGroove {
+    /// Whether notes play where they are written
+    pub fn is_straight(&self) -> bool {
+        self.swing <= MIN_SWING && self.template.is_none()
+    }
+
+    /// Offset (in samples, late > 0) and velocity scale of a note starting at
+    /// `start_samples`
+    pub fn feel(&self, start_samples: u64, samples_per_beat: f64) -> (i64, f32) {
+        if self.is_straight() || samples_per_beat <= 0.0 {
+            return (0, 1.0);
+        }
+        let position = start_samples as f64;
+
+        // Every second 1/16 note is delayed
+        let samples_per_step = samples_per_beat / SWING_STEPS_PER_BEAT as f64;
+        let step = (position / samples_per_step).round() as i64;
+        let swing = self.swing.clamp(MIN_SWING, MAX_SWING) as f64;
+        let mut offset = if step % 2 == 1 {
+            (2.0 * swing / 100.0 - 1.0) * samples_per_step
+        } else {
+            0.0
+        };
+
+        let mut scale = 1.0;
+        if let Some(template) = &self.template {
+            let (template_offset, template_scale) = template.feel_at(position, samples_per_beat);
+            offset += template_offset;
+            scale = template_scale;
+        }
+        (offset.round() as i64, scale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::{MusicalTime, generate_note_id};
+
+    const SAMPLE_RATE: f64 = 48000.0;
+    // 120 BPM: 24000 samples per beat, 6000 per 1/16 step
+    const STEP: u64 = 6000;
+
+    fn note(start: u64, velocity: u8) -> Note {
+        Note::new(
+            generate_note_id(),
+            36,
+            Position::new(start, MusicalTime::zero()),
+            1000,
+            velocity,
+        )
+    }
+
+    #[test]
+    fn test_pattern_groove_feel() {
+        let mut groove = PatternGroove::default();
+        assert!(groove.is_straight());
+        assert_eq!(groove.feel(STEP, 24000.0), (0, 1.0));
+
+        // 75% swing: the off-beat 1/16 lands half a step late
+        groove.swing = 75.0;
+        assert_eq!(groove.feel(0, 24000.0), (0, 1.0));
+        assert_eq!(groove.feel(STEP, 24000.0), (3000, 1.0));
+        assert_eq!(groove.feel(STEP * 2 + 100, 24000.0), (0, 1.0));
+
+        // The template adds its timing and sets the accents
+        groove.template = Some(Groove {
+            name: "Push".to_string(),
+            steps_per_beat: 4,
+            offsets: vec![-0.1, 0.0],
+            velocities: vec![1.5, 0.5],
+        });
+        assert_eq!(groove.feel(0, 24000.0), (-600, 1.5));
+        assert_eq!(groove.feel(STEP * 3, 24000.0), (3000, 0.5));
+    }
+
+    #[test]
+    fn test_groove_from_swung_take() {
+        // Off-beat 1/16 notes a third of a step late and softer
+        let take: Vec<Note> = (0..8u64)
+            .map(|step| {
+                if step % 2 == 0 {
+                    note(step * STEP, 120)
+                } else {
+                    note(step * STEP + STEP / 3, 60)
+                }
+            })
+            .collect();
+
+        let groove = Groove::from_notes("Take".to_string(), &take, 24000.0, 4, 2).unwrap();
+        assert_eq!(groove.length_steps(), 2);
+        assert!(groove.offsets[0].abs() < 1e-4);
+        assert!((groove.offsets[1] - 1.0 / 3.0).abs() < 1e-3);
+        assert!((groove.velocities[0] - 120.0 / 90.0).abs() < 1e-4);
+        assert!((groove.velocities[1] - 60.0 / 90.0).abs() < 1e-4);
+
+        assert!(Groove::from_notes("Empty".to_string(), &[], 24000.0, 4, 2).is_err());
+    }
+
+    #[test]
+    fn test_apply_groove_with_strength() {
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        let swing = Groove::swing(75.0, 4);
+        assert!((swing.offsets[1] - 0.5).abs() < 1e-6);
+
+        let mut pattern = Pattern::new_default(1, "Straight".to_string());
+        for step in 0..4 {
+            pattern.add_note(note(step * STEP, 100));
+        }
+        swing.apply(&mut pattern, 0.5, SAMPLE_RATE, &tempo, &time_signature);
+        let starts: Vec<u64> = pattern.notes().iter().map(|n| n.start.samples).collect();
+        assert_eq!(starts, vec![0, 7500, 12000, 19500]);
+
+        // Velocities follow the template
+        let accent = Groove {
+            name: "Accent".to_string(),
+            steps_per_beat: 4,
+            offsets: vec![0.0, 0.0],
+            velocities: vec![1.2, 0.5],
+        };
+        accent.apply(&mut pattern, 1.0, SAMPLE_RATE, &tempo, &time_signature);
+        let velocities: Vec<u8> = pattern.notes().iter().map(|n| n.velocity).collect();
+        assert_eq!(velocities, vec![120, 50, 120, 50]);
+    }
+
+    #[test]
+    fn test_groove_from_audio_loop() {
+        // One beat at 120 BPM: loud hits on the beat, soft late hits between
+        let sample_rate = 48000u32;
+        let mut data = vec![0.0f32; 24000 * 2];
+        for (start, level) in [(0, 1.0), (14000, 0.5), (24000, 1.0), (38000, 0.5)] {
+            for (i, sample) in data[start..start + 2000].iter_mut().enumerate() {
+                *sample = level * if i % 2 == 0 { 1.0 } else { -1.0 };
+            }
+        }
+
+        let groove =
+            Groove::from_audio("Loop".to_string(), &data, sample_rate, 120.0, 2, 2).unwrap();
+        assert!(groove.offsets[0].abs() < 0.05);
+        // 14000 samples = 1.167 eighth notes
+        assert!((groove.offsets[1] - 1.0 / 6.0).abs() < 0.05);
+        assert!(groove.velocities[0] > 1.2 && groove.velocities[1] < 0.8);
+    }
+}
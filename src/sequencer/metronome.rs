@@ -141,6 +141,15 @@ impl Metronome {
         });
     }
 
+    /// Trigger a click of the count-in
+    /// Sounds even with the metronome off: the count-in is its own setting
+    pub fn trigger_count_in_click(&mut self, click_type: ClickType) {
+        self.current_click = Some(ClickPlayback {
+            click_type,
+            position: 0,
+        });
+    }
+
     /// Process one sample of metronome output
     /// Returns the click sample (0.0 if no click active)
     pub fn process_sample(&mut self) -> f32 {
@@ -247,6 +256,99 @@ impl Default for MetronomeScheduler {
     }
 }
 
+/// Bars of count-in at most
+pub const MAX_COUNT_IN_BARS: u32 = 2;
+
+/// Count-in before recording
+/// Clicks every beat of a number of bars (downbeats accented) while the
+/// timeline stands still; the transport starts once it is over
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountIn {
+    active: bool,
+    length_samples: u64,
+    elapsed_samples: u64,
+}
+
+impl CountIn {
+    /// Create an inactive count-in
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start counting `bars` bars (0 leaves it inactive)
+    pub fn start(
+        &mut self,
+        bars: u32,
+        sample_rate: f64,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+    ) {
+        let bar_samples = tempo.beat_duration_samples(sample_rate) * time_signature.beats_per_bar();
+        self.length_samples = (bar_samples * bars.min(MAX_COUNT_IN_BARS) as f64).round() as u64;
+        self.elapsed_samples = 0;
+        self.active = self.length_samples > 0;
+    }
+
+    /// Stop counting (transport stopped or started by hand)
+    pub fn cancel(&mut self) {
+        self.active = false;
+    }
+
+    /// Check if the count-in is running
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Samples left before the transport starts
+    pub fn remaining_samples(&self) -> u64 {
+        if self.active {
+            self.length_samples.saturating_sub(self.elapsed_samples)
+        } else {
+            0
+        }
+    }
+
+    /// Advance by one buffer (RT-safe)
+    /// Returns the click of a beat falling in the buffer. The count-in ends in
+    /// the first buffer starting past its last bar, so the transport starts on
+    /// a buffer boundary.
+    pub fn process(
+        &mut self,
+        buffer_size: usize,
+        sample_rate: f64,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+    ) -> Option<ClickType> {
+        if !self.active {
+            return None;
+        }
+        if self.elapsed_samples >= self.length_samples {
+            self.active = false;
+            return None;
+        }
+
+        let start = self.elapsed_samples;
+        let end = (start + buffer_size as u64).min(self.length_samples);
+        self.elapsed_samples += buffer_size as u64;
+
+        let beat_duration_samples = tempo.beat_duration_samples(sample_rate);
+        if beat_duration_samples <= 0.0 {
+            return None;
+        }
+        // First beat at or after the buffer start
+        let beat = (start as f64 / beat_duration_samples).ceil() as u64;
+        if (beat as f64 * beat_duration_samples) >= end as f64 {
+            return None;
+        }
+        let beat_in_bar = beat % time_signature.numerator.max(1) as u64;
+        Some(if beat_in_bar == 0 {
+            ClickType::Accent
+        } else {
+            ClickType::Regular
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -451,6 +553,54 @@ mod tests {
         assert!(result.is_some());
     }
 
+    #[test]
+    fn test_count_in_clicks_then_ends() {
+        let mut count_in = CountIn::new();
+        let tempo = Tempo::new(120.0);
+        let ts = TimeSignature::four_four();
+        let sample_rate = 48000.0;
+
+        // One bar at 120 BPM = 4 beats of 24000 samples
+        count_in.start(1, sample_rate, &tempo, &ts);
+        assert!(count_in.is_active());
+        assert_eq!(count_in.remaining_samples(), 96000);
+
+        let mut clicks = Vec::new();
+        let mut buffers = 0;
+        while count_in.is_active() {
+            if let Some(click) = count_in.process(512, sample_rate, &tempo, &ts) {
+                clicks.push(click);
+            }
+            buffers += 1;
+        }
+        assert_eq!(
+            clicks,
+            vec![
+                ClickType::Accent,
+                ClickType::Regular,
+                ClickType::Regular,
+                ClickType::Regular
+            ]
+        );
+        // 96000 samples = 187.5 buffers: the count-in ends in the 189th
+        assert_eq!(buffers, 189);
+        assert_eq!(count_in.remaining_samples(), 0);
+
+        // No bars: nothing to count
+        count_in.start(0, sample_rate, &tempo, &ts);
+        assert!(!count_in.is_active());
+    }
+
+    #[test]
+    fn test_count_in_click_ignores_enable() {
+        let mut metronome = Metronome::new(48000.0);
+        metronome.set_enabled(false);
+        metronome.trigger_count_in_click(ClickType::Accent);
+        let mut buffer = vec![0.0f32; 64];
+        metronome.process_buffer(&mut buffer);
+        assert!(buffer.iter().any(|&s| s.abs() > 0.0));
+    }
+
     #[test]
     fn test_metronome_buffer_processing() {
         let mut metronome = Metronome::new(48000.0);
@@ -4,6 +4,7 @@
 use crate::midi::event::MidiEvent;
 use crate::sequencer::note::Note;
 use crate::sequencer::pattern::generate_note_id;
+use crate::sequencer::quantize::QuantizeSettings;
 use crate::sequencer::timeline::{Position, Tempo, TimeSignature};
 use std::collections::HashMap;
 
@@ -70,6 +71,12 @@ impl MidiRecorder {
         self.recorded_notes.clone()
     }
 
+    /// Take the notes completed so far, the recording goes on
+    /// (notes still held are kept until their NoteOff)
+    pub fn take_recorded_notes(&mut self) -> Vec<Note> {
+        std::mem::take(&mut self.recorded_notes)
+    }
+
     /// Finalize recording by closing all active notes
     /// Returns the notes that were active at recording stop
     pub fn finalize_recording(&mut self) -> Vec<Note> {
@@ -103,6 +110,35 @@ impl MidiRecorder {
     }
 }
 
+/// Place a recorded note in a pattern looping every `loop_samples`
+///
+/// The start is folded into the loop, then optionally quantized (input
+/// quantize; a note quantized onto the end of the loop moves to its start).
+pub fn fold_into_loop(
+    note: &Note,
+    loop_samples: u64,
+    quantize: Option<&QuantizeSettings>,
+    sample_rate: f64,
+    tempo: &Tempo,
+    time_signature: &TimeSignature,
+) -> Note {
+    let fold = |samples: u64| {
+        let samples = if loop_samples > 0 {
+            samples % loop_samples
+        } else {
+            samples
+        };
+        Position::from_samples(samples, sample_rate, tempo, time_signature)
+    };
+    let mut folded = *note;
+    folded.start = fold(note.start.samples);
+    if let Some(quantize) = quantize {
+        folded = quantize.quantize(&folded, sample_rate, tempo, time_signature);
+        folded.start = fold(folded.start.samples);
+    }
+    folded
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,4 +195,45 @@ mod tests {
         );
         assert_eq!(recorder.active_notes.len(), 1);
     }
+
+    #[test]
+    fn test_take_notes_and_fold_into_loop() {
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        let mut recorder = MidiRecorder::new(0, 48000.0, tempo, time_signature);
+
+        // Played 100 samples after the 3rd beat of the second pass of a 1-bar loop
+        recorder.process_event(
+            MidiEvent::NoteOn {
+                note: 64,
+                velocity: 90,
+            },
+            96000 + 48100,
+        );
+        assert!(recorder.take_recorded_notes().is_empty());
+        recorder.process_event(MidiEvent::NoteOff { note: 64 }, 96000 + 60000);
+        let notes = recorder.take_recorded_notes();
+        assert_eq!(notes.len(), 1);
+        assert!(recorder.take_recorded_notes().is_empty());
+
+        let sixteenths = QuantizeSettings::default();
+        let fold =
+            |quantize| fold_into_loop(&notes[0], 96000, quantize, 48000.0, &tempo, &time_signature);
+        assert_eq!(fold(None).start.samples, 48100);
+        assert_eq!(fold(None).duration_samples, 11900);
+        assert_eq!(fold(Some(&sixteenths)).start.samples, 48000);
+
+        // Quantized onto the end of the loop: back to its start
+        let mut late = notes[0];
+        late.start = Position::from_samples(95900, 48000.0, &tempo, &time_signature);
+        let folded = fold_into_loop(
+            &late,
+            96000,
+            Some(&sixteenths),
+            48000.0,
+            &tempo,
+            &time_signature,
+        );
+        assert_eq!(folded.start.samples, 0);
+    }
 }
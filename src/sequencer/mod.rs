@@ -1,18 +1,36 @@
 // Sequencer module - Phase 4
 // Timeline, musical time representation, and sequencing infrastructure
 
+pub mod arrangement;
+pub mod chord;
+pub mod generate;
+pub mod groove;
 pub mod metronome;
 pub mod midi_recorder;
 pub mod note;
 pub mod pattern;
 pub mod player;
+pub mod quantize;
+pub mod scale;
+pub mod session;
 pub mod timeline;
 pub mod transport;
 
-pub use metronome::{ClickType, Metronome, MetronomeScheduler, MetronomeSound};
-pub use midi_recorder::MidiRecorder;
+pub use arrangement::{Arrangement, ClipId, PatternClip};
+pub use chord::{ChordQuality, StrumDirection};
+pub use generate::{GeneratorSettings, StepGrid};
+pub use groove::{Groove, PatternGroove};
+pub use metronome::{
+    ClickType, CountIn, MAX_COUNT_IN_BARS, Metronome, MetronomeScheduler, MetronomeSound,
+};
+pub use midi_recorder::{MidiRecorder, fold_into_loop};
 pub use note::{Note, NoteId};
 pub use pattern::{Pattern, PatternId, generate_note_id};
-pub use player::SequencerPlayer;
-pub use timeline::{MusicalTime, Position, Tempo, TimeSignature};
+pub use player::{SequencerPlayer, TrackSources};
+pub use quantize::{QuantizeSettings, quantize_notes};
+pub use scale::{KeyEstimate, Scale, ScaleKind, detect_key};
+pub use session::{LaunchQuantization, Launcher, Session, SessionSlot, TrackLaunch};
+pub use timeline::{
+    MusicalTime, NoteDivision, NoteFeel, NoteValue, Position, Tempo, TimeSignature,
+};
 pub use transport::{Transport, TransportState};
@@ -1,11 +1,16 @@
 // Note representation for the sequencer
-// A note is a MIDI event with position, pitch, duration, and velocity
+// A note is a MIDI event with position, pitch, duration, and velocity, plus
+// the per-step settings of the step sequencer: the chance of playing on each
+// pass and a micro-timing offset applied by the player
 
 use crate::sequencer::timeline::{MusicalTime, Position};
 
 /// Unique identifier for notes
 pub type NoteId = u64;
 
+/// Largest micro-timing offset, either way (a 1/16 note)
+pub const MAX_OFFSET_TICKS: i32 = MusicalTime::TICKS_PER_QUARTER as i32 / 4;
+
 /// A musical note in the sequencer
 ///
 /// Notes are stored with both sample-accurate and musical time representations.
@@ -27,6 +32,13 @@ pub struct Note {
 
     /// MIDI velocity (0-127, where 127 = maximum)
     pub velocity: u8,
+
+    /// Chance of the note playing on each pass of the pattern (0.0 - 1.0)
+    pub probability: f32,
+
+    /// Micro-timing offset in ticks (positive = late), applied at playback
+    /// so the feel survives tempo changes
+    pub offset_ticks: i32,
 }
 
 impl Note {
@@ -48,9 +60,21 @@ impl Note {
             start,
             duration_samples,
             velocity,
+            probability: 1.0,
+            offset_ticks: 0,
         }
     }
 
+    /// Micro-timing offset in samples at a tempo
+    pub fn offset_samples(
+        &self,
+        sample_rate: f64,
+        tempo: &crate::sequencer::timeline::Tempo,
+    ) -> i64 {
+        let beats = self.offset_ticks as f64 / MusicalTime::TICKS_PER_QUARTER as f64;
+        (beats * tempo.beat_duration_samples(sample_rate)).round() as i64
+    }
+
     /// Get the end position of this note (in samples)
     pub fn end_sample(&self) -> u64 {
         self.start.samples + self.duration_samples
@@ -157,6 +181,18 @@ mod tests {
         assert_eq!(duration_ticks, 480);
     }
 
+    #[test]
+    fn test_note_offset_samples() {
+        let mut note = Note::new(1, 36, Position::zero(), 1000, 100);
+        assert_eq!(note.probability, 1.0);
+        assert_eq!(note.offset_samples(48000.0, &Tempo::new(120.0)), 0);
+
+        // At 120 BPM a 1/16 note (120 ticks) is 6000 samples
+        note.offset_ticks = -MAX_OFFSET_TICKS;
+        assert_eq!(note.offset_samples(48000.0, &Tempo::new(120.0)), -6000);
+        assert_eq!(note.offset_samples(48000.0, &Tempo::new(60.0)), -12000);
+    }
+
     #[test]
     #[should_panic(expected = "MIDI pitch must be 0-127")]
     fn test_invalid_pitch() {
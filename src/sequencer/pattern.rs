@@ -1,6 +1,7 @@
 // Pattern - Collection of MIDI notes forming a sequence
 // A pattern is like a "clip" in other DAWs
 
+use crate::sequencer::groove::PatternGroove;
 use crate::sequencer::note::{Note, NoteId};
 use crate::sequencer::timeline::{Position, Tempo, TimeSignature};
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -34,6 +35,9 @@ pub struct Pattern {
     /// Length of the pattern in bars
     /// Determines when the pattern loops
     pub length_bars: u32,
+
+    /// Swing and groove template applied at playback
+    pub groove: PatternGroove,
 }
 
 impl Pattern {
@@ -46,6 +50,7 @@ impl Pattern {
             name,
             notes: Vec::new(),
             length_bars,
+            groove: PatternGroove::default(),
         }
     }
 
@@ -126,6 +131,12 @@ impl Pattern {
         self.notes.clear();
     }
 
+    /// Replace all notes (sorted by start position)
+    pub fn set_notes(&mut self, mut notes: Vec<Note>) {
+        notes.sort_by_key(|n| n.start.samples);
+        self.notes = notes;
+    }
+
     /// Get the number of notes
     pub fn note_count(&self) -> usize {
         self.notes.len()
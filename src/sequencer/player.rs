@@ -1,8 +1,21 @@
 // Sequencer Player - Reads patterns and triggers notes
 // Phase 4: Audio playback for sequencer
+//
+// Notes play at their start shifted by their micro-timing offset and by the
+// pattern groove (swing and template, which also scales velocities), wrapping
+// around the pattern. A note with a probability below 1 rolls on each pass;
+// the rolls come from a seeded generator, so a render is reproducible.
+//
+// In song mode the player follows the clips of a track instead of looping one
+// pattern (`process_clips`): notes are cut at the end of their clip, and the
+// note-offs of a buffer come before its note-ons so a note struck again at a
+// clip boundary keeps playing. Launched clips of the session view play the
+// same way (`process_spans`, see `session::TrackLaunch::spans`).
 
 use crate::midi::event::{MidiEvent, MidiEventTimed};
-use crate::sequencer::{NoteId, Pattern, Tempo, TimeSignature};
+use crate::sequencer::arrangement::{Arrangement, ClipId, PatternClip};
+use crate::sequencer::session::{Launcher, Session};
+use crate::sequencer::{Note, NoteId, Pattern, Tempo, TimeSignature};
 use std::collections::HashMap;
 
 /// Tracks active notes (NoteOn sent, waiting for NoteOff)
@@ -13,6 +26,13 @@ struct ActiveNote {
     end_sample: u64,
 }
 
+/// Note of a clip waiting for its NoteOff (end on the timeline)
+#[derive(Debug, Clone, Copy)]
+struct ClipNote {
+    midi_pitch: u8,
+    end_sample: u64,
+}
+
 /// Sequencer player - converts pattern notes to MIDI events
 pub struct SequencerPlayer {
     /// Currently active notes (waiting for NoteOff)
@@ -23,8 +43,44 @@ pub struct SequencerPlayer {
 
     /// Last processed position (to detect new notes)
     last_position_samples: u64,
+
+    /// State of the generator rolling note probabilities (xorshift)
+    rng_state: u32,
+
+    /// Playing notes of the clips (song mode), by clip and note
+    clip_notes: HashMap<(ClipId, NoteId), ClipNote>,
+
+    /// Position following the last buffer of clips (a jump releases the notes)
+    next_clip_position: u64,
+}
+
+/// Pattern looped by a track over a part of the timeline (a clip of the
+/// arrangement or a launched clip of the session)
+#[derive(Debug, Clone, Copy)]
+pub struct ClipSpan<'a> {
+    /// Clip playing the pattern (its playing notes are keyed by it)
+    pub clip: ClipId,
+    pub pattern: &'a Pattern,
+    /// Sample where a pass of the pattern starts (may be before the span)
+    pub anchor: u64,
+    /// First sample played
+    pub start: u64,
+    /// Sample after the last one played: notes still playing are cut there
+    pub end: u64,
+}
+
+/// What the tracks play instead of looping their pattern
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrackSources<'a> {
+    /// Clips of the song, while song mode is on
+    pub arrangement: Option<&'a Arrangement>,
+    /// Slot grid and launch state of the session view
+    pub session: Option<(&'a Session, &'a Launcher)>,
 }
 
+/// Seed of the probability generator (non-zero)
+const RNG_SEED: u32 = 0x9E37_79B9;
+
 impl SequencerPlayer {
     /// Create a new sequencer player
     pub fn new(sample_rate: f64) -> Self {
@@ -32,6 +88,9 @@ impl SequencerPlayer {
             active_notes: HashMap::new(),
             sample_rate,
             last_position_samples: 0,
+            rng_state: RNG_SEED,
+            clip_notes: HashMap::new(),
+            next_clip_position: 0,
         }
     }
 
@@ -48,6 +107,8 @@ impl SequencerPlayer {
         buffer_size: usize,
     ) -> Vec<MidiEventTimed> {
         let mut events = Vec::new();
+        // Back from song mode
+        self.release_clip_notes(&mut events);
 
         // If not playing, stop all active notes and return
         if !is_playing {
@@ -78,7 +139,8 @@ impl SequencerPlayer {
 
         // Check for notes that should start in this buffer
         for note in pattern.notes() {
-            let note_start = note.start.samples % pattern_length_samples;
+            let (note_start, velocity) =
+                self.note_timing(pattern, note, tempo, pattern_length_samples);
 
             // Check if this note should start in the current buffer
             let should_trigger = self.should_trigger_note(
@@ -89,6 +151,11 @@ impl SequencerPlayer {
             );
 
             if should_trigger && !self.active_notes.contains_key(&note.id) {
+                // Skipped on this pass
+                if note.probability < 1.0 && self.next_random() >= note.probability {
+                    continue;
+                }
+
                 // Calculate sample offset within buffer
                 let sample_offset = if note_start >= current_position_normalized {
                     note_start - current_position_normalized
@@ -101,7 +168,7 @@ impl SequencerPlayer {
                 events.push(MidiEventTimed {
                     event: MidiEvent::NoteOn {
                         note: note.pitch,
-                        velocity: note.velocity,
+                        velocity,
                     },
                     samples_from_now: sample_offset.min(buffer_size as u64) as u32,
                 });
@@ -112,7 +179,7 @@ impl SequencerPlayer {
                     ActiveNote {
                         _note_id: note.id,
                         midi_pitch: note.pitch,
-                        end_sample: note.start.samples + note.duration_samples,
+                        end_sample: note_start + note.duration_samples,
                     },
                 );
             }
@@ -162,6 +229,246 @@ impl SequencerPlayer {
         events
     }
 
+    /// Process a buffer of a track: its launched clip once the session took
+    /// it over, else its clips in song mode, else its pattern
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_track(
+        &mut self,
+        track: u32,
+        pattern: &Pattern,
+        sources: TrackSources,
+        current_position: u64,
+        is_playing: bool,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+        buffer_size: usize,
+    ) -> Vec<MidiEventTimed> {
+        if let Some((session, launcher)) = sources.session
+            && let Some(launch) = launcher.track(track)
+        {
+            let buffer_end = current_position + buffer_size as u64;
+            return self.process_spans(
+                launch.spans(session, buffer_end).into_iter().flatten(),
+                current_position,
+                is_playing,
+                tempo,
+                time_signature,
+                buffer_size,
+            );
+        }
+        match sources.arrangement {
+            Some(arrangement) => self.process_clips(
+                arrangement.track_clips(track),
+                current_position,
+                is_playing,
+                tempo,
+                time_signature,
+                buffer_size,
+            ),
+            None => self.process(
+                pattern,
+                current_position,
+                is_playing,
+                tempo,
+                time_signature,
+                buffer_size,
+            ),
+        }
+    }
+
+    /// Process a buffer of a track in song mode: play the clips under the
+    /// buffer (sorted by start, see `Arrangement::track_clips`)
+    ///
+    /// Each clip loops its pattern from the clip start; notes still playing
+    /// at the clip end are stopped there.
+    pub fn process_clips(
+        &mut self,
+        clips: &[PatternClip],
+        current_position: u64,
+        is_playing: bool,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+        buffer_size: usize,
+    ) -> Vec<MidiEventTimed> {
+        let bar_samples =
+            tempo.beat_duration_samples(self.sample_rate) * time_signature.beats_per_bar();
+        let buffer_end = current_position + buffer_size as u64;
+        let spans = clips
+            .iter()
+            .map(move |clip| {
+                let start = (clip.start_bar as f64 * bar_samples).round() as u64;
+                ClipSpan {
+                    clip: clip.id,
+                    pattern: &clip.pattern,
+                    anchor: start,
+                    start,
+                    end: (clip.end_bar() as f64 * bar_samples).round() as u64,
+                }
+            })
+            .take_while(move |span| span.start < buffer_end);
+        self.process_spans(
+            spans,
+            current_position,
+            is_playing,
+            tempo,
+            time_signature,
+            buffer_size,
+        )
+    }
+
+    /// Process a buffer of a track playing clips: loop the pattern of each
+    /// span over the part of the buffer it covers
+    ///
+    /// Notes of a span still playing at its end are stopped there.
+    pub fn process_spans<'a>(
+        &mut self,
+        spans: impl Iterator<Item = ClipSpan<'a>> + Clone,
+        current_position: u64,
+        is_playing: bool,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+        buffer_size: usize,
+    ) -> Vec<MidiEventTimed> {
+        let mut events = Vec::new();
+        // Notes of the looped pattern (song mode just turned on)
+        for (_, active_note) in self.active_notes.drain() {
+            events.push(MidiEventTimed {
+                event: MidiEvent::NoteOff {
+                    note: active_note.midi_pitch,
+                },
+                samples_from_now: 0,
+            });
+        }
+
+        // A stopped transport or a jump (seek, loop) releases the playing notes
+        if !is_playing || current_position != self.next_clip_position {
+            self.release_clip_notes(&mut events);
+        }
+        self.last_position_samples = current_position;
+        if !is_playing {
+            self.next_clip_position = current_position;
+            return events;
+        }
+        let buffer_end = current_position + buffer_size as u64;
+        self.next_clip_position = buffer_end;
+
+        // Spans ending in the buffer cut the notes they started earlier
+        for span in spans.clone().filter(|span| span.end < buffer_end) {
+            for (_, note) in self
+                .clip_notes
+                .iter_mut()
+                .filter(|((clip, _), _)| *clip == span.clip)
+            {
+                note.end_sample = note.end_sample.min(span.end);
+            }
+        }
+
+        // Note-offs first: a note struck again at a clip boundary is not cut
+        self.stop_clip_notes(current_position, buffer_end, &mut events);
+
+        for span in spans {
+            // Part of the buffer inside the span
+            let from = current_position.max(span.start);
+            let to = buffer_end.min(span.end);
+            if from >= to {
+                continue;
+            }
+            let pattern = span.pattern;
+            let pattern_length = pattern.length_samples(self.sample_rate, tempo, time_signature);
+            if pattern_length == 0 || pattern.is_empty() {
+                continue;
+            }
+
+            for note in pattern.notes() {
+                let (note_start, velocity) = self.note_timing(pattern, note, tempo, pattern_length);
+                // First pass of the pattern where the note is at or after `from`
+                let phase = (span.anchor as i64 + note_start as i64 - from as i64)
+                    .rem_euclid(pattern_length as i64) as u64;
+                let mut onset = from + phase;
+                while onset < to {
+                    let key = (span.clip, note.id);
+                    let skipped = note.probability < 1.0 && self.next_random() >= note.probability;
+                    if !skipped && !self.clip_notes.contains_key(&key) {
+                        events.push(MidiEventTimed {
+                            event: MidiEvent::NoteOn {
+                                note: note.pitch,
+                                velocity,
+                            },
+                            samples_from_now: (onset - current_position) as u32,
+                        });
+                        self.clip_notes.insert(
+                            key,
+                            ClipNote {
+                                midi_pitch: note.pitch,
+                                end_sample: (onset + note.duration_samples).min(span.end),
+                            },
+                        );
+                    }
+                    onset += pattern_length;
+                }
+            }
+        }
+
+        // Notes shorter than the rest of the buffer
+        self.stop_clip_notes(current_position, buffer_end, &mut events);
+        events
+    }
+
+    /// Start of a note in its pattern (micro-timing and groove applied, wrapped
+    /// around the pattern) and its velocity
+    fn note_timing(
+        &self,
+        pattern: &Pattern,
+        note: &Note,
+        tempo: &Tempo,
+        pattern_length_samples: u64,
+    ) -> (u64, u8) {
+        let samples_per_beat = tempo.beat_duration_samples(self.sample_rate);
+        let (groove_offset, velocity_scale) =
+            pattern.groove.feel(note.start.samples, samples_per_beat);
+        let start = (note.start.samples as i64
+            + note.offset_samples(self.sample_rate, tempo)
+            + groove_offset)
+            .rem_euclid(pattern_length_samples as i64) as u64;
+        let velocity = (note.velocity as f32 * velocity_scale)
+            .round()
+            .clamp(1.0, 127.0) as u8;
+        (start, velocity)
+    }
+
+    /// Send the NoteOff of the clip notes ending before `buffer_end`
+    fn stop_clip_notes(
+        &mut self,
+        buffer_start: u64,
+        buffer_end: u64,
+        events: &mut Vec<MidiEventTimed>,
+    ) {
+        self.clip_notes.retain(|_, note| {
+            if note.end_sample >= buffer_end {
+                return true;
+            }
+            events.push(MidiEventTimed {
+                event: MidiEvent::NoteOff {
+                    note: note.midi_pitch,
+                },
+                samples_from_now: note.end_sample.saturating_sub(buffer_start) as u32,
+            });
+            false
+        });
+    }
+
+    /// Send the NoteOff of every playing clip note at once
+    fn release_clip_notes(&mut self, events: &mut Vec<MidiEventTimed>) {
+        for (_, note) in self.clip_notes.drain() {
+            events.push(MidiEventTimed {
+                event: MidiEvent::NoteOff {
+                    note: note.midi_pitch,
+                },
+                samples_from_now: 0,
+            });
+        }
+    }
+
     /// Check if a note event (start or end) should trigger in the current buffer
     fn should_trigger_note(
         &self,
@@ -184,9 +491,20 @@ impl SequencerPlayer {
         }
     }
 
+    /// Next value of the probability generator, in 0.0 - 1.0 (excluded)
+    fn next_random(&mut self) -> f32 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng_state = x;
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+
     /// Stop all currently playing notes (called when transport stops)
     pub fn stop_all_notes(&mut self) -> Vec<MidiEventTimed> {
         let mut events = Vec::new();
+        self.release_clip_notes(&mut events);
 
         for (_, active_note) in self.active_notes.drain() {
             events.push(MidiEventTimed {
@@ -203,7 +521,10 @@ impl SequencerPlayer {
     /// Reset player state (called when transport position changes)
     pub fn reset(&mut self) {
         self.active_notes.clear();
+        self.clip_notes.clear();
         self.last_position_samples = 0;
+        self.next_clip_position = 0;
+        self.rng_state = RNG_SEED;
     }
 }
 
@@ -250,6 +571,223 @@ mod tests {
         }
     }
 
+    /// NoteOn events of a buffer, with their offset in the buffer
+    fn note_ons(events: &[MidiEventTimed]) -> Vec<(u8, u32)> {
+        events
+            .iter()
+            .filter_map(|event| match event.event {
+                MidiEvent::NoteOn { note, .. } => Some((note, event.samples_from_now)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_micro_timing_offset() {
+        let mut player = SequencerPlayer::new(48000.0);
+        let mut pattern = Pattern::new(1, "Test".to_string(), 1);
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+
+        // 24 ticks late at 120 BPM = 1200 samples after the beat
+        let mut late = Note::new(
+            1,
+            38,
+            Position::from_samples(24000, 48000.0, &tempo, &time_signature),
+            100,
+            100,
+        );
+        late.offset_ticks = 24;
+        // Early on the downbeat: plays at the end of the previous pass
+        let mut early = Note::new(2, 42, Position::zero(), 100, 100);
+        early.offset_ticks = -24;
+        pattern.add_note(late);
+        pattern.add_note(early);
+
+        let events = player.process(&pattern, 24000, true, &tempo, &time_signature, 512);
+        assert!(note_ons(&events).is_empty());
+        let events = player.process(&pattern, 25000, true, &tempo, &time_signature, 512);
+        assert_eq!(note_ons(&events), vec![(38, 200)]);
+
+        // The bar is 96000 samples long
+        let events = player.process(&pattern, 94500, true, &tempo, &time_signature, 512);
+        assert_eq!(note_ons(&events), vec![(42, 300)]);
+        let events = player.process(&pattern, 96000, true, &tempo, &time_signature, 512);
+        assert!(note_ons(&events).is_empty());
+    }
+
+    #[test]
+    fn test_pattern_swing() {
+        let mut player = SequencerPlayer::new(48000.0);
+        let mut pattern = Pattern::new(1, "Test".to_string(), 1);
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        // Off-beat 1/16 note (6000 samples at 120 BPM)
+        pattern.add_note(Note::new(
+            1,
+            42,
+            Position::from_samples(6000, 48000.0, &tempo, &time_signature),
+            100,
+            100,
+        ));
+        pattern.groove.swing = 75.0;
+
+        let events = player.process(&pattern, 6000, true, &tempo, &time_signature, 512);
+        assert!(note_ons(&events).is_empty());
+        let events = player.process(&pattern, 8800, true, &tempo, &time_signature, 512);
+        assert_eq!(note_ons(&events), vec![(42, 200)]);
+    }
+
+    #[test]
+    fn test_probability() {
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        let mut pattern = Pattern::new(1, "Test".to_string(), 1);
+        let mut never = Note::new(1, 36, Position::zero(), 100, 100);
+        never.probability = 0.0;
+        let mut half = Note::new(2, 42, Position::zero(), 100, 100);
+        half.probability = 0.5;
+        pattern.add_note(never);
+        pattern.add_note(half);
+
+        let passes = |player: &mut SequencerPlayer| -> Vec<bool> {
+            (0..200u64)
+                .map(|pass| {
+                    let events =
+                        player.process(&pattern, pass * 96000, true, &tempo, &time_signature, 512);
+                    let ons = note_ons(&events);
+                    assert!(ons.iter().all(|(note, _)| *note == 42));
+                    !ons.is_empty()
+                })
+                .collect()
+        };
+
+        let mut player = SequencerPlayer::new(48000.0);
+        let played = passes(&mut player);
+        let count = played.iter().filter(|played| **played).count();
+        assert!((70..130).contains(&count), "played {} of 200 passes", count);
+
+        // Reproducible after a reset
+        player.reset();
+        assert_eq!(passes(&mut player), played);
+    }
+
+    #[test]
+    fn test_clip_playback() {
+        use crate::sequencer::arrangement::{Arrangement, PatternClip, SYNTH_TRACK};
+        use std::sync::Arc;
+
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        // One bar = 96000 samples; both patterns strike C4 on their downbeat
+        let mut verse = Pattern::new(1, "Verse".to_string(), 1);
+        verse.add_note(Note::new(1, 60, Position::zero(), 96000, 100));
+        let mut chorus = Pattern::new(2, "Chorus".to_string(), 1);
+        chorus.add_note(Note::new(2, 60, Position::zero(), 500000, 100));
+        let mut arrangement = Arrangement::new();
+        arrangement
+            .add_clip(PatternClip::new(SYNTH_TRACK, 0, 2, Arc::new(verse)))
+            .unwrap();
+        arrangement
+            .add_clip(PatternClip::new(SYNTH_TRACK, 2, 1, Arc::new(chorus)))
+            .unwrap();
+        let clips = arrangement.track_clips(SYNTH_TRACK);
+
+        // (absolute sample, note on) of every event over four bars
+        let mut player = SequencerPlayer::new(48000.0);
+        let mut events = Vec::new();
+        for position in (0..384000).step_by(512) {
+            for event in player.process_clips(clips, position, true, &tempo, &time_signature, 512) {
+                let on = matches!(event.event, MidiEvent::NoteOn { .. });
+                events.push((position + event.samples_from_now as u64, on));
+            }
+        }
+
+        // The verse loops, the note-off of a pass comes before the next note-on,
+        // and the long chorus note is cut at the end of its clip
+        assert_eq!(
+            events,
+            vec![
+                (0, true),
+                (96000, false),
+                (96000, true),
+                (192000, false),
+                (192000, true),
+                (288000, false),
+            ]
+        );
+
+        // A jump releases the playing notes
+        let events = player.process_clips(clips, 0, true, &tempo, &time_signature, 512);
+        assert_eq!(note_ons(&events), vec![(60, 0)]);
+        let events = player.process_clips(clips, 200000, true, &tempo, &time_signature, 512);
+        assert!(matches!(events[0].event, MidiEvent::NoteOff { note: 60 }));
+        assert!(note_ons(&events).is_empty());
+        assert_eq!(player.stop_all_notes().len(), 0);
+    }
+
+    #[test]
+    fn test_launched_clip_playback() {
+        use crate::sequencer::arrangement::SYNTH_TRACK;
+        use crate::sequencer::session::{Launcher, Session};
+        use std::sync::Arc;
+
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        // One bar = 96000 samples; the loop strikes C4 on its second beat
+        let mut riff = Pattern::new(1, "Riff".to_string(), 1);
+        riff.add_note(Note::new(
+            1,
+            60,
+            Position::from_samples(24000, 48000.0, &tempo, &time_signature),
+            80000,
+            100,
+        ));
+        let mut session = Session::new();
+        session.set_slot(SYNTH_TRACK, 0, Arc::new(riff));
+
+        // Launched at bar 1, stopped at bar 3
+        let mut launcher = Launcher::new();
+        launcher.launch(SYNTH_TRACK, Some(0), 96000);
+        let mut player = SequencerPlayer::new(48000.0);
+        let mut events = Vec::new();
+        for position in (0..384000).step_by(512) {
+            if position == 96000 * 2 + 512 * 10 {
+                launcher.launch(SYNTH_TRACK, None, 288000);
+            }
+            let sources = TrackSources {
+                arrangement: None,
+                session: Some((&session, &launcher)),
+            };
+            let pattern = Pattern::new_default(2, "Idle".to_string());
+            for event in player.process_track(
+                SYNTH_TRACK,
+                &pattern,
+                sources,
+                position,
+                true,
+                &tempo,
+                &time_signature,
+                512,
+            ) {
+                let on = matches!(event.event, MidiEvent::NoteOn { .. });
+                events.push((position + event.samples_from_now as u64, on));
+            }
+            launcher.advance(position + 511);
+        }
+
+        // Nothing plays before the launch, and the stop cuts the second pass
+        assert_eq!(
+            events,
+            vec![
+                (120000, true),
+                (200000, false),
+                (216000, true),
+                (288000, false),
+            ]
+        );
+    }
+
     #[test]
     fn test_stop_all_notes() {
         let mut player = SequencerPlayer::new(48000.0);
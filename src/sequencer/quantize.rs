@@ -0,0 +1,205 @@
+// Quantize - Snap note starts to the grid with a strength and swing
+//
+// Each note start moves toward its nearest grid step: all the way at 100 %
+// strength, part of the way below, keeping some of the played feel. With
+// swing, every second step of the grid is delayed (50 % = straight, as in
+// `Groove::swing`). Durations and the per-note micro-timing offsets are kept.
+//
+// Used after recording (the piano roll's Quantize menu, as an undoable
+// `QuantizeNotesCommand`) and while recording (input quantize, see
+// `fold_into_loop`).
+
+use crate::sequencer::groove::{MAX_SWING, MIN_SWING};
+use crate::sequencer::{Note, NoteId, Pattern, Position, Tempo, TimeSignature};
+use serde::{Deserialize, Serialize};
+
+/// How notes snap to the grid (the last values of the Quantize menu)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QuantizeSettings {
+    /// Steps per beat of the grid (4 = 1/16 notes)
+    pub steps_per_beat: u32,
+    /// Share of the way to the grid a note moves (0.0 to 1.0)
+    pub strength: f32,
+    /// Share of a step pair taken by its first step in percent (50 = straight)
+    pub swing: f32,
+}
+
+impl Default for QuantizeSettings {
+    fn default() -> Self {
+        Self {
+            steps_per_beat: 4,
+            strength: 1.0,
+            swing: MIN_SWING,
+        }
+    }
+}
+
+impl QuantizeSettings {
+    /// Grid position (samples) nearest a start: every second step is delayed
+    /// by the swing
+    pub fn target(&self, start_samples: u64, samples_per_beat: f64) -> f64 {
+        let step_samples = samples_per_beat / self.steps_per_beat.max(1) as f64;
+        let start = start_samples as f64;
+        if step_samples <= 0.0 {
+            return start;
+        }
+        let delay =
+            (2.0 * self.swing.clamp(MIN_SWING, MAX_SWING) as f64 / 100.0 - 1.0) * step_samples;
+        let step_position = |step: f64| {
+            let swung = if (step as i64).rem_euclid(2) == 1 {
+                delay
+            } else {
+                0.0
+            };
+            step * step_samples + swung
+        };
+
+        // The delay is at most half a step: the nearest swung step is one of
+        // the straight steps around the start
+        let step = (start / step_samples).floor();
+        [step - 1.0, step, step + 1.0]
+            .into_iter()
+            .filter(|step| *step >= 0.0)
+            .map(step_position)
+            .min_by(|a, b| (a - start).abs().total_cmp(&(b - start).abs()))
+            .unwrap_or(start)
+    }
+
+    /// The note moved toward the grid (strength applied)
+    pub fn quantize(
+        &self,
+        note: &Note,
+        sample_rate: f64,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+    ) -> Note {
+        let start = note.start.samples as f64;
+        let target = self.target(note.start.samples, tempo.beat_duration_samples(sample_rate));
+        let strength = self.strength.clamp(0.0, 1.0) as f64;
+        let moved = start + (target - start) * strength;
+        Note {
+            start: Position::from_samples(
+                moved.max(0.0).round() as u64,
+                sample_rate,
+                tempo,
+                time_signature,
+            ),
+            ..*note
+        }
+    }
+}
+
+/// Quantize notes of a pattern (`ids` empty: every note)
+///
+/// Returns the number of notes moved.
+pub fn quantize_notes(
+    pattern: &mut Pattern,
+    ids: &[NoteId],
+    settings: &QuantizeSettings,
+    sample_rate: f64,
+    tempo: &Tempo,
+    time_signature: &TimeSignature,
+) -> usize {
+    let mut moved = 0;
+    let notes = pattern
+        .notes()
+        .iter()
+        .map(|note| {
+            if !ids.is_empty() && !ids.contains(&note.id) {
+                return *note;
+            }
+            let quantized = settings.quantize(note, sample_rate, tempo, time_signature);
+            if quantized.start.samples != note.start.samples {
+                moved += 1;
+            }
+            quantized
+        })
+        .collect();
+    pattern.set_notes(notes);
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::generate_note_id;
+
+    // 120 BPM at 48 kHz: one beat = 24000 samples, one 1/16 step = 6000
+    const SAMPLE_RATE: f64 = 48000.0;
+
+    fn note_at(samples: u64) -> Note {
+        let position = Position::from_samples(
+            samples,
+            SAMPLE_RATE,
+            &Tempo::new(120.0),
+            &TimeSignature::four_four(),
+        );
+        Note::new(generate_note_id(), 60, position, 1000, 100)
+    }
+
+    #[test]
+    fn test_quantize_strength_and_swing() {
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        let quantize = |settings: QuantizeSettings, samples| {
+            settings
+                .quantize(&note_at(samples), SAMPLE_RATE, &tempo, &time_signature)
+                .start
+                .samples
+        };
+
+        let full = QuantizeSettings::default();
+        assert_eq!(quantize(full, 6800), 6000);
+        assert_eq!(quantize(full, 11000), 12000);
+
+        // Half strength: half of the way
+        let half = QuantizeSettings {
+            strength: 0.5,
+            ..full
+        };
+        assert_eq!(quantize(half, 6800), 6400);
+
+        // 75 % swing: odd steps land half a step late (6000 -> 9000)
+        let swung = QuantizeSettings {
+            swing: 75.0,
+            ..full
+        };
+        assert_eq!(quantize(swung, 8000), 9000);
+        assert_eq!(quantize(swung, 12500), 12000);
+        assert_eq!(quantize(swung, 1000), 0);
+    }
+
+    #[test]
+    fn test_quantize_selected_notes() {
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        let mut pattern = Pattern::new(1, "Take".into(), 1);
+        let (first, second) = (note_at(500), note_at(24300));
+        pattern.add_note(first);
+        pattern.add_note(second);
+
+        let settings = QuantizeSettings::default();
+        let moved = quantize_notes(
+            &mut pattern,
+            &[second.id],
+            &settings,
+            SAMPLE_RATE,
+            &tempo,
+            &time_signature,
+        );
+        assert_eq!(moved, 1);
+        assert_eq!(pattern.get_note(first.id).unwrap().start.samples, 500);
+        assert_eq!(pattern.get_note(second.id).unwrap().start.samples, 24000);
+
+        // No selection: every note
+        quantize_notes(
+            &mut pattern,
+            &[],
+            &settings,
+            SAMPLE_RATE,
+            &tempo,
+            &time_signature,
+        );
+        assert_eq!(pattern.get_note(first.id).unwrap().start.samples, 0);
+    }
+}
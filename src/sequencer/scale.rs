@@ -0,0 +1,243 @@
+// Scales and key detection
+//
+// A scale is a root pitch class and a set of intervals. The key of a pattern
+// is estimated with the Krumhansl-Schmuckler algorithm: the pitch class
+// histogram of the notes (weighted by duration) is correlated with the
+// Krumhansl-Kessler major and minor key profiles rotated to each root; the
+// best match wins.
+
+use crate::sequencer::Note;
+use serde::{Deserialize, Serialize};
+
+/// Pitch class names (sharps)
+pub const PITCH_CLASS_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Krumhansl-Kessler probe tone ratings, from the tonic upwards
+const MAJOR_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const MINOR_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Scale type (intervals from the root)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleKind {
+    Major,
+    Minor,
+    HarmonicMinor,
+    Dorian,
+    Mixolydian,
+    MajorPentatonic,
+    MinorPentatonic,
+    Blues,
+}
+
+impl ScaleKind {
+    /// All scale types, for UI lists
+    pub const ALL: [ScaleKind; 8] = [
+        ScaleKind::Major,
+        ScaleKind::Minor,
+        ScaleKind::HarmonicMinor,
+        ScaleKind::Dorian,
+        ScaleKind::Mixolydian,
+        ScaleKind::MajorPentatonic,
+        ScaleKind::MinorPentatonic,
+        ScaleKind::Blues,
+    ];
+
+    /// Semitones from the root
+    pub fn intervals(self) -> &'static [u8] {
+        match self {
+            ScaleKind::Major => &[0, 2, 4, 5, 7, 9, 11],
+            ScaleKind::Minor => &[0, 2, 3, 5, 7, 8, 10],
+            ScaleKind::HarmonicMinor => &[0, 2, 3, 5, 7, 8, 11],
+            ScaleKind::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+            ScaleKind::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+            ScaleKind::MajorPentatonic => &[0, 2, 4, 7, 9],
+            ScaleKind::MinorPentatonic => &[0, 3, 5, 7, 10],
+            ScaleKind::Blues => &[0, 3, 5, 6, 7, 10],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ScaleKind::Major => "major",
+            ScaleKind::Minor => "minor",
+            ScaleKind::HarmonicMinor => "harmonic minor",
+            ScaleKind::Dorian => "dorian",
+            ScaleKind::Mixolydian => "mixolydian",
+            ScaleKind::MajorPentatonic => "major pentatonic",
+            ScaleKind::MinorPentatonic => "minor pentatonic",
+            ScaleKind::Blues => "blues",
+        }
+    }
+}
+
+/// A scale on a root pitch class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Scale {
+    /// Root pitch class (0 = C, 11 = B)
+    pub root: u8,
+    pub kind: ScaleKind,
+}
+
+impl Scale {
+    pub fn new(root: u8, kind: ScaleKind) -> Self {
+        Self {
+            root: root % 12,
+            kind,
+        }
+    }
+
+    /// Whether a MIDI note belongs to the scale
+    pub fn contains(&self, pitch: u8) -> bool {
+        let degree = (pitch + 12 - self.root % 12) % 12;
+        self.kind.intervals().contains(&degree)
+    }
+
+    /// Whether a MIDI note is the root of the scale
+    pub fn is_root(&self, pitch: u8) -> bool {
+        pitch % 12 == self.root % 12
+    }
+
+    /// Nearest MIDI note of the scale (the lower one on a tie)
+    pub fn snap(&self, pitch: u8) -> u8 {
+        let pitch = pitch.min(127);
+        for distance in 0..12 {
+            if let Some(below) = pitch.checked_sub(distance)
+                && self.contains(below)
+            {
+                return below;
+            }
+            let above = pitch + distance;
+            if above <= 127 && self.contains(above) {
+                return above;
+            }
+        }
+        pitch
+    }
+
+    /// Name such as "A minor"
+    pub fn name(&self) -> String {
+        format!(
+            "{} {}",
+            PITCH_CLASS_NAMES[(self.root % 12) as usize],
+            self.kind.name()
+        )
+    }
+}
+
+/// Estimated key of a set of notes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KeyEstimate {
+    /// Major or minor scale of the key
+    pub scale: Scale,
+    /// Correlation with the key profile (-1.0 to 1.0, higher is surer)
+    pub confidence: f32,
+}
+
+/// Estimate the key of notes (None without notes)
+pub fn detect_key(notes: &[Note]) -> Option<KeyEstimate> {
+    let mut histogram = [0.0f64; 12];
+    for note in notes {
+        histogram[(note.pitch % 12) as usize] += note.duration_samples.max(1) as f64;
+    }
+    if histogram.iter().all(|&weight| weight == 0.0) {
+        return None;
+    }
+
+    let mut best: Option<KeyEstimate> = None;
+    for root in 0..12u8 {
+        for (kind, profile) in [
+            (ScaleKind::Major, &MAJOR_PROFILE),
+            (ScaleKind::Minor, &MINOR_PROFILE),
+        ] {
+            // Profile rotated so that its tonic is on `root`
+            let rotated: [f64; 12] =
+                std::array::from_fn(|pc| profile[(pc + 12 - root as usize) % 12]);
+            let confidence = correlation(&histogram, &rotated) as f32;
+            if best.is_none_or(|b| confidence > b.confidence) {
+                best = Some(KeyEstimate {
+                    scale: Scale::new(root, kind),
+                    confidence,
+                });
+            }
+        }
+    }
+    best
+}
+
+/// Pearson correlation of two pitch class vectors
+fn correlation(a: &[f64; 12], b: &[f64; 12]) -> f64 {
+    let mean_a = a.iter().sum::<f64>() / 12.0;
+    let mean_b = b.iter().sum::<f64>() / 12.0;
+    let (mut covariance, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        // A single repeated pitch class correlates with nothing
+        return 0.0;
+    }
+    covariance / (var_a * var_b).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::{Position, generate_note_id};
+
+    fn notes(pitches: &[(u8, u64)]) -> Vec<Note> {
+        pitches
+            .iter()
+            .map(|&(pitch, duration)| {
+                Note::new(generate_note_id(), pitch, Position::zero(), duration, 100)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scale_membership_and_snapping() {
+        let a_minor = Scale::new(9, ScaleKind::Minor);
+        assert!(a_minor.contains(69) && a_minor.contains(60) && !a_minor.contains(61));
+        assert!(a_minor.is_root(57));
+        assert_eq!(a_minor.name(), "A minor");
+
+        let c_major = Scale::new(0, ScaleKind::Major);
+        assert_eq!(c_major.snap(61), 60);
+        assert_eq!(c_major.snap(62), 62);
+        assert_eq!(c_major.snap(127), 127);
+        assert_eq!(Scale::new(1, ScaleKind::MajorPentatonic).snap(0), 1);
+    }
+
+    #[test]
+    fn test_detect_key() {
+        assert_eq!(detect_key(&[]), None);
+
+        // C major scale with a long tonic and dominant
+        let c_major = notes(&[
+            (60, 4000),
+            (62, 1000),
+            (64, 2000),
+            (65, 1000),
+            (67, 3000),
+            (69, 1000),
+            (71, 1000),
+            (72, 2000),
+        ]);
+        let key = detect_key(&c_major).unwrap();
+        assert_eq!(key.scale, Scale::new(0, ScaleKind::Major));
+        assert!(key.confidence > 0.8);
+
+        // A minor arpeggio leaning on A and E
+        let a_minor = notes(&[(57, 4000), (60, 1500), (64, 3000), (69, 2000), (71, 500)]);
+        let key = detect_key(&a_minor).unwrap();
+        assert_eq!(key.scale, Scale::new(9, ScaleKind::Minor));
+    }
+}
@@ -0,0 +1,359 @@
+// Session - Grid of pattern clips launched live (clip launcher)
+//
+// Each track (the synth track, then the instrument tracks) has a column of
+// slots holding patterns; the slots of a row form a scene. Launching a slot
+// loops its pattern from the next launch boundary (beat or bar, see
+// `LaunchQuantization`), replacing the clip playing on the track; stopping a
+// track waits for the boundary too. A track launched in the session ignores its
+// pattern and the arrangement, even once stopped, until the session is
+// released ("back to arrangement").
+//
+// The grid is shared with the audio thread like the arrangement
+// (`Command::SetSession`). Launches are timed by the frontend from the engine
+// playhead (`Command::LaunchClip`) and carried out by the engine's `Launcher`;
+// `DawState` feeds a mirror of it the same launches for display.
+
+use crate::sequencer::arrangement::{ClipId, generate_clip_id};
+use crate::sequencer::player::ClipSpan;
+use crate::sequencer::{Pattern, Tempo, TimeSignature};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Tracks a launcher follows at most (preallocated: launches are RT-safe)
+pub const MAX_SESSION_TRACKS: usize = 32;
+
+/// Scenes shown at least in the grid
+pub const MIN_SCENES: u32 = 8;
+
+/// Boundary a launch waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchQuantization {
+    /// Launch at once
+    None,
+    /// Next beat
+    Beat,
+    /// Next bar
+    #[default]
+    Bar,
+}
+
+impl LaunchQuantization {
+    pub const ALL: [LaunchQuantization; 3] = [Self::None, Self::Beat, Self::Bar];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Beat => "Beat",
+            Self::Bar => "Bar",
+        }
+    }
+
+    /// Sample where a launch made at `position` takes effect
+    ///
+    /// A stopped transport launches at once: the clip starts with the playback.
+    pub fn launch_position(
+        self,
+        position: u64,
+        playing: bool,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+        sample_rate: f64,
+    ) -> u64 {
+        let beat_samples = tempo.beat_duration_samples(sample_rate);
+        let grid = match self {
+            Self::None => return position,
+            _ if !playing => return position,
+            Self::Beat => beat_samples,
+            Self::Bar => beat_samples * time_signature.beats_per_bar(),
+        };
+        if grid <= 0.0 {
+            return position;
+        }
+        // A position a rounding error past a boundary launches on it
+        let boundary = ((position as f64 - 0.5) / grid).ceil().max(0.0);
+        (boundary * grid).round() as u64
+    }
+}
+
+/// A pattern in a slot of the grid
+#[derive(Debug, Clone)]
+pub struct SessionSlot {
+    pub id: ClipId,
+    /// Track playing the slot (`SYNTH_TRACK` or an instrument track)
+    pub track: u32,
+    /// Row of the slot
+    pub scene: u32,
+    /// Pattern looped once launched
+    pub pattern: Arc<Pattern>,
+}
+
+/// Slots of every track, sorted by track then scene
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    slots: Vec<SessionSlot>,
+    /// Boundary the launches wait for
+    pub quantization: LaunchQuantization,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All slots, sorted by track then scene
+    pub fn slots(&self) -> &[SessionSlot] {
+        &self.slots
+    }
+
+    /// Slots of a track, sorted by scene (RT-safe)
+    pub fn track_slots(&self, track: u32) -> &[SessionSlot] {
+        let first = self.slots.partition_point(|slot| slot.track < track);
+        let last = self.slots.partition_point(|slot| slot.track <= track);
+        &self.slots[first..last]
+    }
+
+    /// Slot of a track in a scene (RT-safe)
+    pub fn get(&self, track: u32, scene: u32) -> Option<&SessionSlot> {
+        self.track_slots(track)
+            .iter()
+            .find(|slot| slot.scene == scene)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Rows of the grid (last used scene + 1, at least `MIN_SCENES`)
+    pub fn scene_count(&self) -> u32 {
+        self.slots
+            .iter()
+            .map(|slot| slot.scene + 1)
+            .max()
+            .unwrap_or(0)
+            .max(MIN_SCENES)
+    }
+
+    /// Put a pattern in a slot, replacing the one it held
+    pub fn set_slot(&mut self, track: u32, scene: u32, pattern: Arc<Pattern>) -> ClipId {
+        self.remove_slot(track, scene);
+        let id = generate_clip_id();
+        self.slots.push(SessionSlot {
+            id,
+            track,
+            scene,
+            pattern,
+        });
+        self.slots.sort_by_key(|slot| (slot.track, slot.scene));
+        id
+    }
+
+    /// Empty a slot
+    pub fn remove_slot(&mut self, track: u32, scene: u32) -> Option<SessionSlot> {
+        let index = self
+            .slots
+            .iter()
+            .position(|slot| slot.track == track && slot.scene == scene)?;
+        Some(self.slots.remove(index))
+    }
+
+    /// Play the new version of a pattern in the slots holding it
+    ///
+    /// Returns whether a slot holds it.
+    pub fn update_pattern(&mut self, pattern: &Arc<Pattern>) -> bool {
+        let mut used = false;
+        for slot in &mut self.slots {
+            if slot.pattern.id == pattern.id {
+                slot.pattern = pattern.clone();
+                used = true;
+            }
+        }
+        used
+    }
+
+    /// Remove the slots of a track (track removed), returning whether it had any
+    pub fn remove_track(&mut self, track: u32) -> bool {
+        let count = self.slots.len();
+        self.slots.retain(|slot| slot.track != track);
+        self.slots.len() != count
+    }
+}
+
+/// Launch state of a track in the session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackLaunch {
+    pub track: u32,
+    /// Scene playing and the sample its pattern started at
+    pub playing: Option<(u32, u64)>,
+    /// Launch waiting for its boundary: scene (None stops the track) and sample
+    pub queued: Option<(Option<u32>, u64)>,
+}
+
+impl TrackLaunch {
+    /// Patterns the track plays in a buffer ending at `buffer_end`: the clip
+    /// playing, cut at a queued launch, then the launched clip (RT-safe)
+    pub fn spans<'a>(&self, session: &'a Session, buffer_end: u64) -> [Option<ClipSpan<'a>>; 2] {
+        let span = |scene: u32, anchor: u64, start: u64, end: u64| {
+            session.get(self.track, scene).map(|slot| ClipSpan {
+                clip: slot.id,
+                pattern: &slot.pattern,
+                anchor,
+                start,
+                end,
+            })
+        };
+        match self.queued {
+            Some((next, at)) if at < buffer_end => [
+                self.playing
+                    .and_then(|(scene, start)| span(scene, start, 0, at)),
+                next.and_then(|scene| span(scene, at, at, u64::MAX)),
+            ],
+            _ => [
+                self.playing
+                    .and_then(|(scene, start)| span(scene, start, 0, u64::MAX)),
+                None,
+            ],
+        }
+    }
+}
+
+/// Launch state of the tracks taken over by the session
+#[derive(Debug, Clone)]
+pub struct Launcher {
+    tracks: Vec<TrackLaunch>,
+}
+
+impl Default for Launcher {
+    fn default() -> Self {
+        Self {
+            tracks: Vec::with_capacity(MAX_SESSION_TRACKS),
+        }
+    }
+}
+
+impl Launcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Launch state of the tracks
+    pub fn tracks(&self) -> &[TrackLaunch] {
+        &self.tracks
+    }
+
+    /// Launch state of a track (None: it plays its pattern or the arrangement)
+    pub fn track(&self, track: u32) -> Option<&TrackLaunch> {
+        self.tracks.iter().find(|launch| launch.track == track)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    /// Queue a launch of a scene (None stops the track) at sample `at`,
+    /// replacing a launch already queued (RT-safe)
+    ///
+    /// Fails when `MAX_SESSION_TRACKS` tracks are already followed.
+    pub fn launch(&mut self, track: u32, scene: Option<u32>, at: u64) -> bool {
+        let queued = Some((scene, at));
+        if let Some(launch) = self.tracks.iter_mut().find(|launch| launch.track == track) {
+            launch.queued = queued;
+            return true;
+        }
+        if self.tracks.len() == MAX_SESSION_TRACKS {
+            return false;
+        }
+        self.tracks.push(TrackLaunch {
+            track,
+            playing: None,
+            queued,
+        });
+        true
+    }
+
+    /// Carry out the launches queued at or before `position`
+    pub fn advance(&mut self, position: u64) {
+        for launch in &mut self.tracks {
+            if let Some((scene, at)) = launch.queued
+                && at <= position
+            {
+                launch.playing = scene.map(|scene| (scene, at));
+                launch.queued = None;
+            }
+        }
+    }
+
+    /// Hand every track back to its pattern or the arrangement (RT-safe)
+    pub fn release(&mut self) {
+        self.tracks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sequencer::arrangement::SYNTH_TRACK;
+
+    #[test]
+    fn test_launch_quantization() {
+        let tempo = Tempo::new(120.0);
+        let time_signature = TimeSignature::four_four();
+        // One beat = 24000 samples, one bar = 96000
+        let at = |quantization: LaunchQuantization, position, playing| {
+            quantization.launch_position(position, playing, &tempo, &time_signature, 48000.0)
+        };
+        assert_eq!(at(LaunchQuantization::Bar, 10000, true), 96000);
+        assert_eq!(at(LaunchQuantization::Bar, 96000, true), 96000);
+        assert_eq!(at(LaunchQuantization::Beat, 30000, true), 48000);
+        assert_eq!(at(LaunchQuantization::None, 30000, true), 30000);
+        assert_eq!(at(LaunchQuantization::Bar, 10000, false), 10000);
+    }
+
+    #[test]
+    fn test_launcher_switches_clips_at_boundary() {
+        let mut session = Session::new();
+        let verse = session.set_slot(SYNTH_TRACK, 0, Arc::new(Pattern::new(1, "A".into(), 1)));
+        let chorus = session.set_slot(SYNTH_TRACK, 1, Arc::new(Pattern::new(2, "B".into(), 1)));
+        assert_eq!(session.scene_count(), MIN_SCENES);
+
+        let mut launcher = Launcher::new();
+        assert!(launcher.launch(SYNTH_TRACK, Some(0), 0));
+        launcher.advance(511);
+        launcher.launch(SYNTH_TRACK, Some(1), 96000);
+
+        // Before the boundary the verse plays on
+        let launch = *launcher.track(SYNTH_TRACK).unwrap();
+        let [first, second] = launch.spans(&session, 1024);
+        assert_eq!(
+            first.map(|span| (span.clip, span.end)),
+            Some((verse, u64::MAX))
+        );
+        assert!(second.is_none());
+
+        // The buffer holding the boundary cuts the verse and starts the chorus
+        let [first, second] = launch.spans(&session, 96256);
+        assert_eq!(
+            first.map(|span| (span.clip, span.end)),
+            Some((verse, 96000))
+        );
+        let second = second.unwrap();
+        assert_eq!(
+            (second.clip, second.anchor, second.start),
+            (chorus, 96000, 96000)
+        );
+
+        launcher.advance(96255);
+        assert_eq!(
+            launcher.track(SYNTH_TRACK).unwrap().playing,
+            Some((1, 96000))
+        );
+
+        // A stopped track stays in the session until it is released
+        launcher.launch(SYNTH_TRACK, None, 192000);
+        launcher.advance(192000);
+        let launch = *launcher.track(SYNTH_TRACK).unwrap();
+        assert!(launch.spans(&session, 200000).iter().all(Option::is_none));
+        launcher.release();
+        assert!(launcher.track(SYNTH_TRACK).is_none());
+    }
+}
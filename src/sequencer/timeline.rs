@@ -128,6 +128,134 @@ impl fmt::Display for Tempo {
     }
 }
 
+/// Note value of a tempo-synced rate or time (LFOs, delays)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NoteValue {
+    Whole,
+    Half,
+    Quarter,
+    Eighth,
+    Sixteenth,
+    ThirtySecond,
+}
+
+impl NoteValue {
+    pub const ALL: [NoteValue; 6] = [
+        NoteValue::Whole,
+        NoteValue::Half,
+        NoteValue::Quarter,
+        NoteValue::Eighth,
+        NoteValue::Sixteenth,
+        NoteValue::ThirtySecond,
+    ];
+
+    /// Length in quarter notes
+    pub fn beats(&self) -> f64 {
+        match self {
+            NoteValue::Whole => 4.0,
+            NoteValue::Half => 2.0,
+            NoteValue::Quarter => 1.0,
+            NoteValue::Eighth => 0.5,
+            NoteValue::Sixteenth => 0.25,
+            NoteValue::ThirtySecond => 0.125,
+        }
+    }
+
+    fn denominator(&self) -> u32 {
+        (4.0 / self.beats()) as u32
+    }
+}
+
+/// Straight, dotted (x1.5) or triplet (x2/3) note
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NoteFeel {
+    #[default]
+    Straight,
+    Dotted,
+    Triplet,
+}
+
+impl NoteFeel {
+    pub const ALL: [NoteFeel; 3] = [NoteFeel::Straight, NoteFeel::Dotted, NoteFeel::Triplet];
+}
+
+/// Tempo-relative duration, e.g. 1/8 dotted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct NoteDivision {
+    pub value: NoteValue,
+    pub feel: NoteFeel,
+}
+
+impl NoteDivision {
+    pub fn new(value: NoteValue, feel: NoteFeel) -> Self {
+        Self { value, feel }
+    }
+
+    /// Length in quarter notes (beats of the `Tempo`)
+    pub fn beats(&self) -> f64 {
+        let beats = self.value.beats();
+        match self.feel {
+            NoteFeel::Straight => beats,
+            NoteFeel::Dotted => beats * 1.5,
+            NoteFeel::Triplet => beats * 2.0 / 3.0,
+        }
+    }
+
+    /// Duration in seconds at a tempo
+    pub fn seconds(&self, tempo: &Tempo) -> f64 {
+        self.beats() * tempo.beat_duration_seconds()
+    }
+
+    /// Repetition rate in Hz at a tempo (one cycle per division)
+    pub fn frequency(&self, tempo: &Tempo) -> f64 {
+        1.0 / self.seconds(tempo)
+    }
+}
+
+impl Default for NoteDivision {
+    fn default() -> Self {
+        Self::new(NoteValue::Quarter, NoteFeel::Straight)
+    }
+}
+
+impl fmt::Display for NoteDivision {
+    /// "1/4", "1/8." (dotted), "1/8T" (triplet)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "1/{}", self.value.denominator())?;
+        match self.feel {
+            NoteFeel::Straight => Ok(()),
+            NoteFeel::Dotted => write!(f, "."),
+            NoteFeel::Triplet => write!(f, "T"),
+        }
+    }
+}
+
+impl std::str::FromStr for NoteDivision {
+    type Err = String;
+
+    /// Parse the `Display` form ("1/4", "1/8.", "1/16T", case-insensitive)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (number, feel) = if let Some(number) = s.strip_suffix('.') {
+            (number, NoteFeel::Dotted)
+        } else if let Some(number) = s.strip_suffix(['T', 't']) {
+            (number, NoteFeel::Triplet)
+        } else {
+            (s, NoteFeel::Straight)
+        };
+        let value = number
+            .strip_prefix("1/")
+            .and_then(|denominator| denominator.parse::<u32>().ok())
+            .and_then(|denominator| {
+                NoteValue::ALL
+                    .into_iter()
+                    .find(|value| value.denominator() == denominator)
+            })
+            .ok_or_else(|| format!("Invalid note division: {}", s))?;
+        Ok(Self::new(value, feel))
+    }
+}
+
 /// Musical time representation
 /// Represents a position in the timeline using bars, beats, and ticks
 /// Tick = subdivision of a beat (typically 480 or 960 ticks per quarter note)
@@ -322,6 +450,26 @@ impl fmt::Display for Position {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_note_division() {
+        let tempo = Tempo::new(120.0);
+        let quarter = NoteDivision::default();
+        assert_eq!(quarter.seconds(&tempo), 0.5);
+        assert_eq!(quarter.frequency(&tempo), 2.0);
+
+        let dotted = NoteDivision::new(NoteValue::Eighth, NoteFeel::Dotted);
+        assert_eq!(dotted.beats(), 0.75);
+        let triplet = NoteDivision::new(NoteValue::Eighth, NoteFeel::Triplet);
+        assert!((triplet.beats() - 1.0 / 3.0).abs() < 1e-12);
+
+        for division in [quarter, dotted, triplet] {
+            assert_eq!(division.to_string().parse::<NoteDivision>(), Ok(division));
+        }
+        assert_eq!(dotted.to_string(), "1/8.");
+        assert_eq!(triplet.to_string(), "1/8T");
+        assert!("1/3".parse::<NoteDivision>().is_err());
+    }
+
     #[test]
     fn test_time_signature() {
         let ts = TimeSignature::four_four();
@@ -1,6 +1,7 @@
 // Transport - Playback control and state management
 // Controls play/stop/record state and playhead position
 
+use super::metronome::MAX_COUNT_IN_BARS;
 use super::midi_recorder::MidiRecorder;
 use super::timeline::{Position, Tempo, TimeSignature};
 use std::sync::Arc;
@@ -14,6 +15,8 @@ pub enum TransportState {
     Playing,
     Recording,
     Paused,
+    /// Clicking the count-in before recording (the timeline stands still)
+    CountingIn,
 }
 
 impl TransportState {
@@ -31,6 +34,11 @@ impl TransportState {
     pub fn is_stopped(&self) -> bool {
         matches!(self, TransportState::Stopped | TransportState::Paused)
     }
+
+    /// Check if the count-in before recording is running
+    pub fn is_counting_in(&self) -> bool {
+        matches!(self, TransportState::CountingIn)
+    }
 }
 
 /// Shared transport state
@@ -40,6 +48,7 @@ pub struct SharedTransportState {
     playing: AtomicBool,
     recording: AtomicBool,
     paused: AtomicBool,
+    counting_in: AtomicBool,
     position_samples: AtomicU64,
     loop_enabled: AtomicBool,
     loop_start_samples: AtomicU64,
@@ -53,6 +62,7 @@ impl SharedTransportState {
             playing: AtomicBool::new(false),
             recording: AtomicBool::new(false),
             paused: AtomicBool::new(false),
+            counting_in: AtomicBool::new(false),
             position_samples: AtomicU64::new(0),
             loop_enabled: AtomicBool::new(false),
             loop_start_samples: AtomicU64::new(0),
@@ -64,6 +74,8 @@ impl SharedTransportState {
     pub fn state(&self) -> TransportState {
         if self.recording.load(Ordering::Relaxed) {
             TransportState::Recording
+        } else if self.counting_in.load(Ordering::Relaxed) {
+            TransportState::CountingIn
         } else if self.playing.load(Ordering::Relaxed) {
             TransportState::Playing
         } else if self.paused.load(Ordering::Relaxed) {
@@ -139,6 +151,7 @@ impl Default for SharedTransportState {
             playing: AtomicBool::new(false),
             recording: AtomicBool::new(false),
             paused: AtomicBool::new(false),
+            counting_in: AtomicBool::new(false),
             position_samples: AtomicU64::new(0),
             loop_enabled: AtomicBool::new(false),
             loop_start_samples: AtomicU64::new(0),
@@ -156,6 +169,7 @@ pub struct Transport {
     time_signature: TimeSignature,
     sample_rate: f64,
     midi_recorder: Option<MidiRecorder>,
+    count_in_bars: u32,
 }
 
 impl Transport {
@@ -167,6 +181,7 @@ impl Transport {
             time_signature: TimeSignature::default(),
             sample_rate,
             midi_recorder: None,
+            count_in_bars: 0,
         }
     }
 
@@ -178,6 +193,7 @@ impl Transport {
             time_signature: TimeSignature::default(),
             sample_rate,
             midi_recorder: None,
+            count_in_bars: 0,
         }
     }
 
@@ -212,6 +228,9 @@ impl Transport {
         self.shared_state.playing.store(true, Ordering::Relaxed);
         self.shared_state.recording.store(false, Ordering::Relaxed);
         self.shared_state.paused.store(false, Ordering::Relaxed);
+        self.shared_state
+            .counting_in
+            .store(false, Ordering::Relaxed);
     }
 
     /// Stop (reset position to 0)
@@ -219,6 +238,9 @@ impl Transport {
         self.shared_state.playing.store(false, Ordering::Relaxed);
         self.shared_state.recording.store(false, Ordering::Relaxed);
         self.shared_state.paused.store(false, Ordering::Relaxed);
+        self.shared_state
+            .counting_in
+            .store(false, Ordering::Relaxed);
         self.shared_state.set_position_samples(0);
     }
 
@@ -227,6 +249,38 @@ impl Transport {
         self.shared_state.playing.store(false, Ordering::Relaxed);
         self.shared_state.recording.store(false, Ordering::Relaxed);
         self.shared_state.paused.store(true, Ordering::Relaxed);
+        self.shared_state
+            .counting_in
+            .store(false, Ordering::Relaxed);
+    }
+
+    /// Get the bars of count-in played before recording (0: off)
+    pub fn count_in_bars(&self) -> u32 {
+        self.count_in_bars
+    }
+
+    /// Set the bars of count-in played before recording (0: off)
+    pub fn set_count_in_bars(&mut self, bars: u32) {
+        self.count_in_bars = bars.min(MAX_COUNT_IN_BARS);
+    }
+
+    /// Start the count-in before recording
+    /// Returns false when there is nothing to count: count-in off, or the
+    /// transport already playing (recording then starts at once, see `record`)
+    pub fn count_in(&mut self) -> bool {
+        if self.count_in_bars == 0 || self.state().is_playing() {
+            return false;
+        }
+        self.shared_state.paused.store(false, Ordering::Relaxed);
+        self.shared_state.counting_in.store(true, Ordering::Relaxed);
+        true
+    }
+
+    /// End the count-in: recording starts where the timeline stood
+    pub fn finish_count_in(&mut self) {
+        if self.state().is_counting_in() {
+            self.record();
+        }
     }
 
     /// Record - start recording MIDI
@@ -234,6 +288,9 @@ impl Transport {
         self.shared_state.playing.store(true, Ordering::Relaxed);
         self.shared_state.recording.store(true, Ordering::Relaxed);
         self.shared_state.paused.store(false, Ordering::Relaxed);
+        self.shared_state
+            .counting_in
+            .store(false, Ordering::Relaxed);
 
         // Initialize MIDI recorder with proper context
         let recording_start_sample = self.shared_state.position_samples();
@@ -342,6 +399,14 @@ impl Transport {
         }
     }
 
+    /// Take the notes recorded since the last call, the recording goes on
+    pub fn take_recorded_notes(&mut self) -> Vec<crate::sequencer::note::Note> {
+        self.midi_recorder
+            .as_mut()
+            .map(MidiRecorder::take_recorded_notes)
+            .unwrap_or_default()
+    }
+
     /// Get recorded notes and finalize recording
     pub fn finalize_recording(&mut self) -> Option<Vec<crate::sequencer::note::Note>> {
         if self.state().is_recording() {
@@ -459,6 +524,38 @@ mod tests {
         assert_eq!(transport.position().samples, 0);
     }
 
+    #[test]
+    fn test_count_in_before_recording() {
+        let mut transport = Transport::new(48000.0);
+
+        // Off by default: recording starts at once
+        assert!(!transport.count_in());
+
+        transport.set_count_in_bars(4);
+        assert_eq!(transport.count_in_bars(), MAX_COUNT_IN_BARS);
+
+        transport.set_position_samples(96000);
+        assert!(transport.count_in());
+        let state = transport.state();
+        assert!(state.is_counting_in());
+        assert!(!state.is_playing() && !state.is_recording() && !state.is_stopped());
+
+        transport.finish_count_in();
+        assert_eq!(transport.state(), TransportState::Recording);
+        assert_eq!(transport.position().samples, 96000);
+
+        // Already playing: no count-in
+        assert!(!transport.count_in());
+
+        // Stopping cancels a running count-in
+        transport.stop();
+        assert!(transport.count_in());
+        transport.stop();
+        assert_eq!(transport.state(), TransportState::Stopped);
+        transport.finish_count_in();
+        assert_eq!(transport.state(), TransportState::Stopped);
+    }
+
     #[test]
     fn test_tempo_time_signature() {
         let mut transport = Transport::new(48000.0);
@@ -6,12 +6,20 @@
 // - Feedback control (amount of delayed signal fed back into the delay line)
 // - Dry/Wet mix control
 //
+// `StereoDelay` is the send effect version: left and right times in
+// milliseconds or synced to a tempo division, one-pole high-pass and low-pass
+// filters in the feedback loop (each repeat is thinner and darker), and a
+// ping-pong mode where the repeats bounce between the sides.
+//
 // Real-time constraints:
 // - Pre-allocated circular buffer (no allocations during processing)
 // - Fixed maximum delay time (set at creation)
 // - Lock-free processing
 
-use crate::audio::dsp_utils::OnePoleSmoother;
+use crate::audio::precision::consts::PI;
+use crate::audio::precision::{InternalSample, from_internal, to_internal};
+use crate::audio::smoothing::{ParamKind, SmoothedParam};
+use crate::sequencer::timeline::{NoteDivision, NoteFeel, NoteValue};
 
 /// Delay parameters
 #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -76,14 +84,14 @@ pub struct Delay {
     /// Maximum delay time in milliseconds
     max_time_ms: f32,
     /// Circular buffer for delay line
-    buffer: Vec<f32>,
+    buffer: Vec<InternalSample>,
     /// Write position in buffer (where new samples are written)
     write_pos: usize,
     /// Current delay time in samples
     delay_samples: usize,
     /// Smoothers to avoid clicks when parameters change
-    feedback_smoother: OnePoleSmoother,
-    mix_smoother: OnePoleSmoother,
+    feedback: SmoothedParam,
+    mix: SmoothedParam,
 }
 
 impl Delay {
@@ -103,9 +111,9 @@ impl Delay {
         // Calculate initial delay in samples
         let delay_samples = ((params.time_ms / 1000.0) * sample_rate) as usize;
 
-        // Initialize smoothers (10ms time constant for smooth parameter changes)
-        let feedback_smoother = OnePoleSmoother::new(params.feedback, 10.0, sample_rate);
-        let mix_smoother = OnePoleSmoother::new(params.mix, 10.0, sample_rate);
+        // Initialize smoothed parameters
+        let feedback = SmoothedParam::new(ParamKind::Feedback, params.feedback, sample_rate);
+        let mix = SmoothedParam::new(ParamKind::EffectMix, params.mix, sample_rate);
 
         Self {
             params,
@@ -114,8 +122,8 @@ impl Delay {
             buffer,
             write_pos: 0,
             delay_samples: delay_samples.min(max_samples - 1),
-            feedback_smoother,
-            mix_smoother,
+            feedback,
+            mix,
         }
     }
 
@@ -125,6 +133,8 @@ impl Delay {
         params.validate(self.max_time_ms);
 
         self.params = params;
+        self.feedback.set_target(params.feedback);
+        self.mix.set_target(params.mix);
 
         // Update delay time in samples
         let new_delay_samples = ((params.time_ms / 1000.0) * self.sample_rate) as usize;
@@ -157,8 +167,8 @@ impl Delay {
         }
 
         // Apply smoothing to parameters
-        let feedback = self.feedback_smoother.process(self.params.feedback);
-        let mix = self.mix_smoother.process(self.params.mix);
+        let feedback = self.feedback.next_value();
+        let mix = self.mix.next_value();
 
         // Calculate read position (where we read delayed samples from)
         // Read position = write position - delay_samples (wrapping around)
@@ -173,7 +183,7 @@ impl Delay {
 
         // Write new sample to buffer: input + feedback * delayed
         // This creates the delay with feedback
-        let buffer_input = input + feedback * delayed;
+        let buffer_input = to_internal(input) + to_internal(feedback) * delayed;
 
         // Clamp to prevent runaway feedback (soft saturation)
         let buffer_input = buffer_input.clamp(-2.0, 2.0);
@@ -185,7 +195,7 @@ impl Delay {
 
         // Mix dry and wet signals
         let dry = input * (1.0 - mix);
-        let wet = delayed * mix;
+        let wet = from_internal(delayed) * mix;
 
         dry + wet
     }
@@ -198,6 +208,234 @@ impl Delay {
     }
 }
 
+/// Stereo delay parameters
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct StereoDelayParams {
+    /// Left delay time in milliseconds, when not synced
+    pub left_ms: f32,
+    /// Right delay time in milliseconds, when not synced
+    pub right_ms: f32,
+    /// Tempo-synced left time, replaces `left_ms` when set
+    #[serde(default)]
+    pub left_sync: Option<NoteDivision>,
+    /// Tempo-synced right time, replaces `right_ms` when set
+    #[serde(default)]
+    pub right_sync: Option<NoteDivision>,
+    /// Feedback amount (0.0 - 0.99)
+    pub feedback: f32,
+    /// High-pass cutoff of the feedback loop in Hz (20 - 2000)
+    pub low_cut_hz: f32,
+    /// Low-pass cutoff of the feedback loop in Hz (500 - 20000)
+    pub high_cut_hz: f32,
+    /// Repeats alternate between the sides (the input feeds the left line)
+    pub ping_pong: bool,
+    /// Dry/Wet mix (0.0 = fully dry, 1.0 = fully wet)
+    pub mix: f32,
+    /// Enable/disable delay (bypass)
+    pub enabled: bool,
+}
+
+impl Default for StereoDelayParams {
+    /// Dotted eighth against a quarter (375 ms / 500 ms at 120 BPM)
+    fn default() -> Self {
+        Self {
+            left_ms: 375.0,
+            right_ms: 500.0,
+            left_sync: Some(NoteDivision::new(NoteValue::Eighth, NoteFeel::Dotted)),
+            right_sync: Some(NoteDivision::new(NoteValue::Quarter, NoteFeel::Straight)),
+            feedback: 0.4,
+            low_cut_hz: 100.0,
+            high_cut_hz: 6000.0,
+            ping_pong: false,
+            mix: 0.3,
+            enabled: true,
+        }
+    }
+}
+
+impl StereoDelayParams {
+    /// Validate and clamp parameters to safe ranges
+    pub fn validate(&mut self, max_time_ms: f32) {
+        self.left_ms = self.left_ms.clamp(0.0, max_time_ms);
+        self.right_ms = self.right_ms.clamp(0.0, max_time_ms);
+        self.feedback = self.feedback.clamp(0.0, 0.99);
+        self.low_cut_hz = self.low_cut_hz.clamp(20.0, 2000.0);
+        self.high_cut_hz = self.high_cut_hz.clamp(500.0, 20000.0);
+        self.mix = self.mix.clamp(0.0, 1.0);
+    }
+
+    /// Left and right delay times in samples at a tempo (samples per beat)
+    pub fn time_samples(&self, samples_per_beat: f64, sample_rate: f32) -> [f64; 2] {
+        let side = |ms: f32, sync: Option<NoteDivision>| match sync {
+            Some(division) => division.beats() * samples_per_beat,
+            None => ms as f64 * 0.001 * sample_rate as f64,
+        };
+        [
+            side(self.left_ms, self.left_sync),
+            side(self.right_ms, self.right_sync),
+        ]
+    }
+}
+
+/// Stereo delay with synced times, filtered feedback and ping-pong
+///
+/// # Example
+/// ```
+/// use mymusic_daw::synth::delay::{StereoDelay, StereoDelayParams};
+///
+/// let mut delay = StereoDelay::new(StereoDelayParams::default(), 44100.0, 2000.0);
+/// delay.set_samples_per_beat(22050.0); // 120 BPM
+///
+/// let (left, right) = delay.process(0.5, 0.5);
+/// ```
+pub struct StereoDelay {
+    params: StereoDelayParams,
+    sample_rate: f32,
+    max_time_ms: f32,
+    /// Tempo of the synced times
+    samples_per_beat: f64,
+    /// Circular buffers of the left and right lines
+    buffers: [Vec<InternalSample>; 2],
+    write_pos: usize,
+    /// Current delay time of each line in samples
+    delay_samples: [usize; 2],
+    /// Feedback filter coefficients (one-pole)
+    low_pass_coeff: InternalSample,
+    high_pass_coeff: InternalSample,
+    /// Feedback filter states of each line
+    low_pass: [InternalSample; 2],
+    high_pass: [InternalSample; 2],
+    feedback: SmoothedParam,
+    mix: SmoothedParam,
+}
+
+impl StereoDelay {
+    /// Create a stereo delay (allocates both lines for `max_time_ms`)
+    ///
+    /// Synced times follow 120 BPM until `set_samples_per_beat()` is called.
+    pub fn new(params: StereoDelayParams, sample_rate: f32, max_time_ms: f32) -> Self {
+        let max_samples = ((max_time_ms / 1000.0) * sample_rate) as usize + 1;
+        let mut delay = Self {
+            params,
+            sample_rate,
+            max_time_ms,
+            samples_per_beat: sample_rate as f64 * 0.5,
+            buffers: [vec![0.0; max_samples], vec![0.0; max_samples]],
+            write_pos: 0,
+            delay_samples: [0; 2],
+            low_pass_coeff: 1.0,
+            high_pass_coeff: 0.0,
+            low_pass: [0.0; 2],
+            high_pass: [0.0; 2],
+            feedback: SmoothedParam::new(ParamKind::Feedback, params.feedback, sample_rate),
+            mix: SmoothedParam::new(ParamKind::EffectMix, params.mix, sample_rate),
+        };
+        delay.set_params(params);
+        delay
+    }
+
+    /// Set delay parameters
+    pub fn set_params(&mut self, mut params: StereoDelayParams) {
+        params.validate(self.max_time_ms);
+        self.params = params;
+        self.feedback.set_target(params.feedback);
+        self.mix.set_target(params.mix);
+
+        let coeff = |cutoff: f32| {
+            1.0 - (-2.0 * PI * to_internal(cutoff) / to_internal(self.sample_rate)).exp()
+        };
+        self.low_pass_coeff = coeff(params.high_cut_hz);
+        self.high_pass_coeff = coeff(params.low_cut_hz);
+        self.update_delay_samples();
+    }
+
+    /// Get current delay parameters
+    pub fn params(&self) -> StereoDelayParams {
+        self.params
+    }
+
+    /// Set the tempo of the synced times (no-op when unchanged)
+    #[inline]
+    pub fn set_samples_per_beat(&mut self, samples_per_beat: f64) {
+        if samples_per_beat != self.samples_per_beat {
+            self.samples_per_beat = samples_per_beat;
+            self.update_delay_samples();
+        }
+    }
+
+    fn update_delay_samples(&mut self) {
+        let max_samples = self.buffers[0].len() - 1;
+        let times = self
+            .params
+            .time_samples(self.samples_per_beat, self.sample_rate);
+        self.delay_samples = times.map(|time| (time.max(0.0) as usize).min(max_samples));
+    }
+
+    /// Current delay time of the left and right lines in samples
+    pub fn delay_samples(&self) -> [usize; 2] {
+        self.delay_samples
+    }
+
+    /// Reset both lines and the feedback filters
+    pub fn reset(&mut self) {
+        for buffer in &mut self.buffers {
+            buffer.fill(0.0);
+        }
+        self.write_pos = 0;
+        self.low_pass = [0.0; 2];
+        self.high_pass = [0.0; 2];
+    }
+
+    /// Process a stereo frame
+    #[inline]
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if !self.params.enabled {
+            return (left, right);
+        }
+
+        let feedback = to_internal(self.feedback.next_value());
+        let mix = self.mix.next_value();
+        let len = self.buffers[0].len();
+
+        let delayed: [InternalSample; 2] = std::array::from_fn(|side| {
+            let read_pos = (self.write_pos + len - self.delay_samples[side]) % len;
+            self.buffers[side][read_pos]
+        });
+
+        // Band-limit the repeats: low-pass, then remove what is below the low cut
+        let filtered: [InternalSample; 2] = std::array::from_fn(|side| {
+            self.low_pass[side] += self.low_pass_coeff * (delayed[side] - self.low_pass[side]);
+            self.high_pass[side] +=
+                self.high_pass_coeff * (self.low_pass[side] - self.high_pass[side]);
+            self.low_pass[side] - self.high_pass[side]
+        });
+
+        let (input_left, input_right) = (to_internal(left), to_internal(right));
+        let writes = if self.params.ping_pong {
+            // The input enters on the left, each repeat crosses to the other side
+            [
+                (input_left + input_right) * 0.5 + feedback * filtered[1],
+                feedback * filtered[0],
+            ]
+        } else {
+            [
+                input_left + feedback * filtered[0],
+                input_right + feedback * filtered[1],
+            ]
+        };
+        for (buffer, write) in self.buffers.iter_mut().zip(writes) {
+            // Clamp to prevent runaway feedback
+            buffer[self.write_pos] = write.clamp(-2.0, 2.0);
+        }
+        self.write_pos = (self.write_pos + 1) % len;
+
+        (
+            left * (1.0 - mix) + from_internal(delayed[0]) * mix,
+            right * (1.0 - mix) + from_internal(delayed[1]) * mix,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -479,4 +717,145 @@ mod tests {
         // Write position should have wrapped around
         assert!(delay.write_pos < buffer_size);
     }
+
+    /// Fully wet stereo delay without feedback filtering
+    fn wet_stereo_params() -> StereoDelayParams {
+        StereoDelayParams {
+            left_ms: 10.0,
+            right_ms: 20.0,
+            left_sync: None,
+            right_sync: None,
+            feedback: 0.5,
+            low_cut_hz: 20.0,
+            high_cut_hz: 20000.0,
+            ping_pong: false,
+            mix: 1.0,
+            enabled: true,
+        }
+    }
+
+    /// Output frames following an impulse on the left, the right or both sides
+    fn impulse_response(
+        delay: &mut StereoDelay,
+        input: (f32, f32),
+        frames: usize,
+    ) -> Vec<(f32, f32)> {
+        let mut output = vec![delay.process(input.0, input.1)];
+        output.extend((1..frames).map(|_| delay.process(0.0, 0.0)));
+        output
+    }
+
+    #[test]
+    fn test_stereo_delay_synced_times_follow_the_tempo() {
+        let sample_rate = 48000.0;
+        let params = StereoDelayParams::default();
+        let mut delay = StereoDelay::new(params, sample_rate, 2000.0);
+
+        // 120 BPM: dotted eighth = 0.75 beat, quarter = 1 beat
+        delay.set_samples_per_beat(24000.0);
+        assert_eq!(delay.delay_samples(), [18000, 24000]);
+
+        // 90 BPM
+        delay.set_samples_per_beat(32000.0);
+        assert_eq!(delay.delay_samples(), [24000, 32000]);
+
+        // Free times in milliseconds ignore the tempo; long divisions are clamped
+        delay.set_params(StereoDelayParams {
+            left_sync: None,
+            right_sync: Some(NoteDivision::new(NoteValue::Whole, NoteFeel::Straight)),
+            left_ms: 250.0,
+            ..params
+        });
+        assert_eq!(delay.delay_samples(), [12000, 96000]);
+    }
+
+    #[test]
+    fn test_stereo_delay_sides_use_their_own_time() {
+        let mut delay = StereoDelay::new(wet_stereo_params(), 1000.0, 100.0);
+        let output = impulse_response(&mut delay, (1.0, 1.0), 50);
+
+        // 10 ms and 20 ms at 1 kHz, with the feedback repeats on each side
+        let left_hits: Vec<usize> = (0..50).filter(|&i| output[i].0 > 0.01).collect();
+        let right_hits: Vec<usize> = (0..50).filter(|&i| output[i].1 > 0.01).collect();
+        assert_eq!(left_hits, vec![10, 20, 30, 40]);
+        assert_eq!(right_hits, vec![20, 40]);
+    }
+
+    #[test]
+    fn test_stereo_delay_ping_pong_alternates_sides() {
+        let params = StereoDelayParams {
+            right_ms: 10.0,
+            ping_pong: true,
+            ..wet_stereo_params()
+        };
+        let mut delay = StereoDelay::new(params, 1000.0, 100.0);
+        let output = impulse_response(&mut delay, (1.0, 1.0), 45);
+
+        // First repeat on the left, then right, left, right (the low cut
+        // leaves a small tail on the other side)
+        let repeats = [output[10], output[20], output[30], output[40]];
+        for (i, (left, right)) in repeats.into_iter().enumerate() {
+            let (near, far) = if i % 2 == 0 {
+                (left, right)
+            } else {
+                (right, left)
+            };
+            assert!(near > 10.0 * far.abs(), "repeat {}: {} vs {}", i, near, far);
+        }
+        assert!(repeats[1].1 < repeats[0].0);
+    }
+
+    #[test]
+    fn test_stereo_delay_feedback_filter_darkens_repeats() {
+        let sample_rate = 48000.0;
+        let params = StereoDelayParams {
+            left_ms: 5.0,
+            right_ms: 5.0,
+            feedback: 0.9,
+            ..wet_stereo_params()
+        };
+        let dark = StereoDelayParams {
+            high_cut_hz: 500.0,
+            ..params
+        };
+
+        // Repeats of a bright, alternating signal: the low-pass eats them
+        // (the first repeat is not filtered, it is skipped)
+        let energy = |params: StereoDelayParams| {
+            let mut delay = StereoDelay::new(params, sample_rate, 100.0);
+            for i in 0..240 {
+                let x = if i % 2 == 0 { 0.5 } else { -0.5 };
+                delay.process(x, x);
+            }
+            for _ in 0..240 {
+                delay.process(0.0, 0.0);
+            }
+            (0..2400)
+                .map(|_| delay.process(0.0, 0.0).0.powi(2))
+                .sum::<f32>()
+        };
+        let bright = energy(params);
+        let darkened = energy(dark);
+        assert!(darkened < bright * 0.01, "{} vs {}", darkened, bright);
+    }
+
+    #[test]
+    fn test_stereo_delay_bypass_and_clamping() {
+        let mut params = StereoDelayParams {
+            feedback: 1.5,
+            low_cut_hz: 1.0,
+            high_cut_hz: 50000.0,
+            left_ms: 5000.0,
+            ..StereoDelayParams::default()
+        };
+        params.validate(2000.0);
+        assert_eq!(params.feedback, 0.99);
+        assert_eq!(params.low_cut_hz, 20.0);
+        assert_eq!(params.high_cut_hz, 20000.0);
+        assert_eq!(params.left_ms, 2000.0);
+
+        params.enabled = false;
+        let mut delay = StereoDelay::new(params, 44100.0, 100.0);
+        assert_eq!(delay.process(0.3, -0.2), (0.3, -0.2));
+    }
 }
@@ -0,0 +1,282 @@
+// Dynamics - Compressor and gate with an external key input
+//
+// Feed-forward, stereo-linked dynamics processor. The detector follows the
+// peak level of a key signal: the processed signal itself, or another track
+// routed as sidechain (see `audio::clip_player`). The gain computer turns that
+// level into gain reduction:
+// - Compressor: above the threshold, the level rise is divided by the ratio
+// - Gate: below the threshold, the signal is attenuated by the range
+//
+// The reduction is smoothed in dB: attack is how fast the processor reacts to
+// the key (compressor clamping down, gate opening), release how fast it lets
+// go.
+//
+// Real-time constraints:
+// - No allocations, no lookahead buffer
+// - Coefficients recomputed only when parameters change
+
+/// Dynamics processing mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DynamicsMode {
+    Compressor,
+    Gate,
+}
+
+impl DynamicsMode {
+    pub const ALL: [DynamicsMode; 2] = [DynamicsMode::Compressor, DynamicsMode::Gate];
+
+    /// Mode name for UI display
+    pub fn name(&self) -> &'static str {
+        match self {
+            DynamicsMode::Compressor => "Compressor",
+            DynamicsMode::Gate => "Gate",
+        }
+    }
+}
+
+/// Dynamics parameters
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DynamicsParams {
+    pub mode: DynamicsMode,
+    /// Threshold in dBFS (-60.0 - 0.0)
+    pub threshold_db: f32,
+    /// Compression ratio (1.0 - 20.0, compressor only)
+    pub ratio: f32,
+    /// Attenuation of the closed gate in dB (0.0 - 80.0, gate only)
+    pub range_db: f32,
+    /// Attack time in milliseconds (0.1 - 100.0)
+    pub attack_ms: f32,
+    /// Release time in milliseconds (1.0 - 2000.0)
+    pub release_ms: f32,
+    /// Output gain in dB (-24.0 - 24.0)
+    pub makeup_db: f32,
+    /// Enable/disable the processor (bypass)
+    pub enabled: bool,
+}
+
+impl Default for DynamicsParams {
+    fn default() -> Self {
+        Self {
+            mode: DynamicsMode::Compressor,
+            threshold_db: -18.0,
+            ratio: 4.0,
+            range_db: 40.0,
+            attack_ms: 5.0,
+            release_ms: 150.0,
+            makeup_db: 0.0,
+            enabled: true,
+        }
+    }
+}
+
+impl DynamicsParams {
+    /// Default parameters of a mode
+    pub fn new(mode: DynamicsMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// Validate and clamp parameters to safe ranges
+    pub fn validate(&mut self) {
+        self.threshold_db = self.threshold_db.clamp(-60.0, 0.0);
+        self.ratio = self.ratio.clamp(1.0, 20.0);
+        self.range_db = self.range_db.clamp(0.0, 80.0);
+        self.attack_ms = self.attack_ms.clamp(0.1, 100.0);
+        self.release_ms = self.release_ms.clamp(1.0, 2000.0);
+        self.makeup_db = self.makeup_db.clamp(-24.0, 24.0);
+    }
+}
+
+/// Level floor of the detector (dBFS), avoids the log of silence
+const DETECTOR_FLOOR_DB: f32 = -120.0;
+
+/// Compressor/gate keyed by an external signal
+///
+/// # Example
+/// ```
+/// use mymusic_daw::synth::dynamics::{Dynamics, DynamicsMode, DynamicsParams};
+///
+/// // Duck a pad under a loud kick
+/// let mut ducker = Dynamics::new(DynamicsParams::new(DynamicsMode::Compressor), 48000.0);
+/// let mut pad = 0.0;
+/// for _ in 0..4800 {
+///     pad = ducker.process(0.5, 0.5, 1.0, 1.0).0;
+/// }
+/// assert!(pad < 0.2);
+/// ```
+pub struct Dynamics {
+    params: DynamicsParams,
+    sample_rate: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    /// Current gain reduction in dB (0.0 = none)
+    reduction_db: f32,
+}
+
+impl Dynamics {
+    /// Create a new dynamics processor
+    pub fn new(params: DynamicsParams, sample_rate: f32) -> Self {
+        let mut dynamics = Self {
+            params,
+            sample_rate,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            reduction_db: 0.0,
+        };
+        dynamics.set_params(params);
+        dynamics
+    }
+
+    /// Set dynamics parameters
+    pub fn set_params(&mut self, mut params: DynamicsParams) {
+        params.validate();
+        self.params = params;
+        self.attack_coeff = (-1.0 / (params.attack_ms * 0.001 * self.sample_rate)).exp();
+        self.release_coeff = (-1.0 / (params.release_ms * 0.001 * self.sample_rate)).exp();
+    }
+
+    /// Get current dynamics parameters
+    pub fn params(&self) -> DynamicsParams {
+        self.params
+    }
+
+    /// Current gain reduction in dB (0.0 = no reduction)
+    pub fn gain_reduction_db(&self) -> f32 {
+        self.reduction_db
+    }
+
+    /// Reset the processor state (no reduction)
+    pub fn reset(&mut self) {
+        self.reduction_db = 0.0;
+    }
+
+    /// Process a stereo frame, with the detector listening to the key frame
+    ///
+    /// Pass the input frame as the key for classic (non-sidechain) dynamics.
+    #[inline]
+    pub fn process(&mut self, left: f32, right: f32, key_left: f32, key_right: f32) -> (f32, f32) {
+        if !self.params.enabled {
+            return (left, right);
+        }
+
+        let peak = key_left.abs().max(key_right.abs());
+        let level_db = if peak > 0.0 {
+            (20.0 * peak.log10()).max(DETECTOR_FLOOR_DB)
+        } else {
+            DETECTOR_FLOOR_DB
+        };
+
+        let params = &self.params;
+        let target = match params.mode {
+            DynamicsMode::Compressor if level_db > params.threshold_db => {
+                (level_db - params.threshold_db) * (1.0 - 1.0 / params.ratio)
+            }
+            DynamicsMode::Gate if level_db < params.threshold_db => params.range_db,
+            _ => 0.0,
+        };
+
+        // The compressor attacks by reducing more, the gate by opening
+        let attacking = match params.mode {
+            DynamicsMode::Compressor => target > self.reduction_db,
+            DynamicsMode::Gate => target < self.reduction_db,
+        };
+        let coeff = if attacking {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.reduction_db = target + (self.reduction_db - target) * coeff;
+
+        let gain = 10f32.powf((params.makeup_db - self.reduction_db) / 20.0);
+        (left * gain, right * gain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Run a constant frame with a constant key for 2 s, returns the last output
+    fn settle(dynamics: &mut Dynamics, input: f32, key: f32) -> f32 {
+        let mut output = 0.0;
+        for _ in 0..(2 * SAMPLE_RATE as usize) {
+            output = dynamics.process(input, input, key, key).0;
+        }
+        output
+    }
+
+    #[test]
+    fn test_params_clamping() {
+        let mut params = DynamicsParams {
+            threshold_db: 12.0,
+            ratio: 0.5,
+            attack_ms: 0.0,
+            ..DynamicsParams::default()
+        };
+        params.validate();
+        assert_eq!(params.threshold_db, 0.0);
+        assert_eq!(params.ratio, 1.0);
+        assert_eq!(params.attack_ms, 0.1);
+    }
+
+    #[test]
+    fn test_compressor_ratio() {
+        let params = DynamicsParams {
+            threshold_db: -20.0,
+            ratio: 4.0,
+            ..DynamicsParams::default()
+        };
+        let mut compressor = Dynamics::new(params, SAMPLE_RATE);
+
+        // 0 dBFS is 20 dB over the threshold: 15 dB of reduction at 4:1
+        let output = settle(&mut compressor, 1.0, 1.0);
+        assert!((compressor.gain_reduction_db() - 15.0).abs() < 0.01);
+        assert!((20.0 * output.log10() + 15.0).abs() < 0.01);
+
+        // Below the threshold the signal is untouched
+        let output = settle(&mut compressor, 0.05, 0.05);
+        assert!((output - 0.05).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_sidechain_key_drives_reduction() {
+        let mut ducker = Dynamics::new(DynamicsParams::default(), SAMPLE_RATE);
+
+        // A quiet signal is ducked by a loud key...
+        let ducked = settle(&mut ducker, 0.1, 1.0);
+        assert!(ducked < 0.05);
+
+        // ...and comes back once the key is silent
+        let released = settle(&mut ducker, 0.1, 0.0);
+        assert!((released - 0.1).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_gate_follows_key() {
+        let params = DynamicsParams {
+            threshold_db: -30.0,
+            range_db: 60.0,
+            ..DynamicsParams::new(DynamicsMode::Gate)
+        };
+        let mut gate = Dynamics::new(params, SAMPLE_RATE);
+
+        // Closed while the key is quiet, open while it is loud
+        assert!(settle(&mut gate, 0.5, 0.001) < 0.001);
+        assert!((settle(&mut gate, 0.5, 0.5) - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_bypass() {
+        let params = DynamicsParams {
+            enabled: false,
+            ..DynamicsParams::default()
+        };
+        let mut dynamics = Dynamics::new(params, SAMPLE_RATE);
+        assert_eq!(dynamics.process(1.0, -1.0, 1.0, 1.0), (1.0, -1.0));
+    }
+}
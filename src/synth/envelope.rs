@@ -69,6 +69,11 @@ pub struct AdsrEnvelope {
     decay_samples: f32,
     release_samples: f32,
     current_sample: f32,
+    // Samples counted per processed sample (see `set_time_scale`)
+    time_step: f32,
+
+    // Release length forced by `fade_out` (until the next note on)
+    fade_samples: Option<f32>,
 }
 
 impl AdsrEnvelope {
@@ -83,6 +88,8 @@ impl AdsrEnvelope {
             decay_samples: 0.0,
             release_samples: 0.0,
             current_sample: 0.0,
+            time_step: 1.0,
+            fade_samples: None,
         };
         envelope.update_sample_counts();
         envelope
@@ -93,6 +100,9 @@ impl AdsrEnvelope {
         self.attack_samples = self.params.attack * self.sample_rate;
         self.decay_samples = self.params.decay * self.sample_rate;
         self.release_samples = self.params.release * self.sample_rate;
+        if let Some(fade_samples) = self.fade_samples {
+            self.release_samples = self.release_samples.min(fade_samples);
+        }
     }
 
     /// Set new ADSR parameters
@@ -106,8 +116,19 @@ impl AdsrEnvelope {
         self.params
     }
 
+    /// Scale the attack, decay and release times (1.0 = as set, 2.0 = twice as long)
+    ///
+    /// Meant for per-sample modulation: the stage in progress continues from
+    /// where it is. A `fade_out` keeps its own length.
+    pub fn set_time_scale(&mut self, scale: f32) {
+        self.time_step = 1.0 / scale.max(1e-3);
+    }
+
     /// Trigger note on (start attack phase)
     pub fn note_on(&mut self) {
+        if self.fade_samples.take().is_some() {
+            self.update_sample_counts();
+        }
         self.state = EnvelopeState::Attack;
         self.current_sample = 0.0;
         // If retriggering during release, start from current value for smooth transition
@@ -126,10 +147,33 @@ impl AdsrEnvelope {
         }
     }
 
+    /// Release within `duration` seconds at most (e.g. a voice cut by the voice limit)
+    ///
+    /// The regular release time is used again from the next note on.
+    pub fn fade_out(&mut self, duration: f32) {
+        if matches!(self.state, EnvelopeState::Idle) {
+            return;
+        }
+        self.fade_samples = Some(duration.max(0.0) * self.sample_rate);
+        self.update_sample_counts();
+        self.state = EnvelopeState::Release;
+        self.current_sample = 0.0;
+    }
+
+    /// Check if the envelope is fading out (see `fade_out`)
+    pub fn is_fading_out(&self) -> bool {
+        self.fade_samples.is_some() && self.is_active()
+    }
+
     /// Process one sample and return the envelope value
     ///
     /// Returns a value between 0.0 and 1.0 that should be multiplied with the audio signal
     pub fn process(&mut self) -> f32 {
+        let step = if self.fade_samples.is_some() {
+            1.0
+        } else {
+            self.time_step
+        };
         match self.state {
             EnvelopeState::Idle => {
                 self.current_value = 0.0;
@@ -141,7 +185,7 @@ impl AdsrEnvelope {
                     let progress = self.current_sample / self.attack_samples;
                     self.current_value = progress.min(1.0);
 
-                    self.current_sample += 1.0;
+                    self.current_sample += step;
 
                     if self.current_sample >= self.attack_samples {
                         self.state = EnvelopeState::Decay;
@@ -163,7 +207,7 @@ impl AdsrEnvelope {
                     self.current_value = 1.0 - progress * (1.0 - self.params.sustain);
                     self.current_value = self.current_value.max(self.params.sustain);
 
-                    self.current_sample += 1.0;
+                    self.current_sample += step;
 
                     if self.current_sample >= self.decay_samples {
                         self.state = EnvelopeState::Sustain;
@@ -195,7 +239,7 @@ impl AdsrEnvelope {
                     self.current_value = start_value * (1.0 - progress);
                     self.current_value = self.current_value.max(0.0);
 
-                    self.current_sample += 1.0;
+                    self.current_sample += step;
 
                     if self.current_sample >= self.release_samples {
                         self.state = EnvelopeState::Idle;
@@ -227,6 +271,9 @@ impl AdsrEnvelope {
         self.state = EnvelopeState::Idle;
         self.current_value = 0.0;
         self.current_sample = 0.0;
+        if self.fade_samples.take().is_some() {
+            self.update_sample_counts();
+        }
     }
 }
 
@@ -412,4 +459,52 @@ mod tests {
         }
         assert!(!envelope.is_active());
     }
+
+    #[test]
+    fn test_fade_out_shortens_release_until_next_note() {
+        let params = AdsrParams::new(0.001, 0.01, 0.8, 2.0);
+        let mut envelope = AdsrEnvelope::new(params, TEST_SAMPLE_RATE);
+
+        envelope.note_on();
+        for _ in 0..1000 {
+            envelope.process();
+        }
+
+        // 10 ms fade instead of the 2 s release
+        envelope.fade_out(0.01);
+        assert!(envelope.is_fading_out());
+        for _ in 0..(0.01 * TEST_SAMPLE_RATE) as usize + 1 {
+            envelope.process();
+        }
+        assert!(!envelope.is_active());
+        assert!(!envelope.is_fading_out());
+
+        // The regular release is back
+        envelope.note_on();
+        envelope.note_off();
+        for _ in 0..1000 {
+            envelope.process();
+        }
+        assert!(envelope.is_active());
+    }
+
+    #[test]
+    fn test_time_scale() {
+        // 10 ms attack, doubled then halved
+        let params = AdsrParams::new(0.01, 0.1, 0.5, 0.1);
+        let attack_samples = (0.01 * TEST_SAMPLE_RATE) as usize;
+        for (scale, done_after) in [(2.0, 2 * attack_samples), (0.5, attack_samples / 2)] {
+            let mut envelope = AdsrEnvelope::new(params, TEST_SAMPLE_RATE);
+            envelope.set_time_scale(scale);
+            envelope.note_on();
+            for _ in 0..done_after - 2 {
+                envelope.process();
+            }
+            assert!(envelope.current_value() < 1.0, "scale {}", scale);
+            for _ in 0..4 {
+                envelope.process();
+            }
+            assert!(envelope.current_value() >= 0.99, "scale {}", scale);
+        }
+    }
 }
@@ -0,0 +1,300 @@
+// Equalizer - Three-band EQ for the master bus
+//
+// Low shelf, peaking mid band and high shelf, each a biquad filter (RBJ audio
+// EQ cookbook coefficients, transposed direct form II). Bands set to 0 dB are
+// skipped, so a flat EQ is transparent. Both channels share the coefficients.
+//
+// Real-time constraints:
+// - No allocations
+// - Coefficients recomputed only when parameters change
+
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+/// Equalizer parameters
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EqParams {
+    /// Low shelf gain in dB (-18.0 - 18.0)
+    pub low_gain_db: f32,
+    /// Low shelf corner frequency in Hz (20.0 - 1000.0)
+    pub low_freq: f32,
+    /// Mid band gain in dB (-18.0 - 18.0)
+    pub mid_gain_db: f32,
+    /// Mid band center frequency in Hz (100.0 - 10000.0)
+    pub mid_freq: f32,
+    /// Mid band quality factor (0.1 - 10.0, higher is narrower)
+    pub mid_q: f32,
+    /// High shelf gain in dB (-18.0 - 18.0)
+    pub high_gain_db: f32,
+    /// High shelf corner frequency in Hz (1000.0 - 20000.0)
+    pub high_freq: f32,
+    /// Enable/disable the equalizer (bypass)
+    pub enabled: bool,
+}
+
+impl Default for EqParams {
+    /// Flat response
+    fn default() -> Self {
+        Self {
+            low_gain_db: 0.0,
+            low_freq: 100.0,
+            mid_gain_db: 0.0,
+            mid_freq: 1000.0,
+            mid_q: 0.7,
+            high_gain_db: 0.0,
+            high_freq: 8000.0,
+            enabled: true,
+        }
+    }
+}
+
+impl EqParams {
+    /// Validate and clamp parameters to safe ranges
+    pub fn validate(&mut self) {
+        self.low_gain_db = self.low_gain_db.clamp(-18.0, 18.0);
+        self.low_freq = self.low_freq.clamp(20.0, 1000.0);
+        self.mid_gain_db = self.mid_gain_db.clamp(-18.0, 18.0);
+        self.mid_freq = self.mid_freq.clamp(100.0, 10000.0);
+        self.mid_q = self.mid_q.clamp(0.1, 10.0);
+        self.high_gain_db = self.high_gain_db.clamp(-18.0, 18.0);
+        self.high_freq = self.high_freq.clamp(1000.0, 20000.0);
+    }
+}
+
+/// Shape of a band
+#[derive(Clone, Copy)]
+enum BandShape {
+    LowShelf,
+    Peak(f32),
+    HighShelf,
+}
+
+/// One biquad band (stereo state, shared coefficients)
+#[derive(Clone, Copy, Default)]
+struct Band {
+    /// Skipped at 0 dB
+    active: bool,
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    /// Filter state per channel
+    z1: [f32; 2],
+    z2: [f32; 2],
+}
+
+impl Band {
+    fn set(&mut self, shape: BandShape, freq: f32, gain_db: f32, sample_rate: f32) {
+        self.active = gain_db != 0.0;
+        if !self.active {
+            // Start from silence when the band comes back
+            self.reset();
+            return;
+        }
+        let a = 10f32.powf(gain_db / 40.0);
+        // Keep the band below Nyquist at low sample rates
+        let w0 = 2.0 * PI * freq.min(sample_rate * 0.45) / sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let (b0, b1, b2, a0, a1, a2) = match shape {
+            BandShape::Peak(q) => {
+                let alpha = sin / (2.0 * q);
+                (
+                    1.0 + alpha * a,
+                    -2.0 * cos,
+                    1.0 - alpha * a,
+                    1.0 + alpha / a,
+                    -2.0 * cos,
+                    1.0 - alpha / a,
+                )
+            }
+            BandShape::LowShelf => {
+                let shelf_alpha = 2.0 * a.sqrt() * sin * FRAC_1_SQRT_2;
+                (
+                    a * ((a + 1.0) - (a - 1.0) * cos + shelf_alpha),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - shelf_alpha),
+                    (a + 1.0) + (a - 1.0) * cos + shelf_alpha,
+                    -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                    (a + 1.0) + (a - 1.0) * cos - shelf_alpha,
+                )
+            }
+            BandShape::HighShelf => {
+                let shelf_alpha = 2.0 * a.sqrt() * sin * FRAC_1_SQRT_2;
+                (
+                    a * ((a + 1.0) + (a - 1.0) * cos + shelf_alpha),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - shelf_alpha),
+                    (a + 1.0) - (a - 1.0) * cos + shelf_alpha,
+                    2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                    (a + 1.0) - (a - 1.0) * cos - shelf_alpha,
+                )
+            }
+        };
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = a1 / a0;
+        self.a2 = a2 / a0;
+    }
+
+    #[inline]
+    fn process(&mut self, channel: usize, input: f32) -> f32 {
+        let output = self.b0 * input + self.z1[channel];
+        self.z1[channel] = self.b1 * input - self.a1 * output + self.z2[channel];
+        self.z2[channel] = self.b2 * input - self.a2 * output;
+        output
+    }
+
+    fn reset(&mut self) {
+        self.z1 = [0.0; 2];
+        self.z2 = [0.0; 2];
+    }
+}
+
+/// Stereo three-band equalizer
+///
+/// # Example
+/// ```
+/// use mymusic_daw::synth::equalizer::{EqParams, Equalizer};
+///
+/// // A flat EQ leaves the signal untouched
+/// let mut eq = Equalizer::new(EqParams::default(), 48000.0);
+/// assert_eq!(eq.process(0.5, -0.5), (0.5, -0.5));
+/// ```
+pub struct Equalizer {
+    params: EqParams,
+    sample_rate: f32,
+    /// Low shelf, mid peak, high shelf
+    bands: [Band; 3],
+}
+
+impl Equalizer {
+    /// Create a new equalizer
+    pub fn new(params: EqParams, sample_rate: f32) -> Self {
+        let mut eq = Self {
+            params,
+            sample_rate,
+            bands: [Band::default(); 3],
+        };
+        eq.set_params(params);
+        eq
+    }
+
+    /// Set equalizer parameters (the filter state is kept)
+    pub fn set_params(&mut self, mut params: EqParams) {
+        params.validate();
+        self.params = params;
+        let sample_rate = self.sample_rate;
+        self.bands[0].set(
+            BandShape::LowShelf,
+            params.low_freq,
+            params.low_gain_db,
+            sample_rate,
+        );
+        self.bands[1].set(
+            BandShape::Peak(params.mid_q),
+            params.mid_freq,
+            params.mid_gain_db,
+            sample_rate,
+        );
+        self.bands[2].set(
+            BandShape::HighShelf,
+            params.high_freq,
+            params.high_gain_db,
+            sample_rate,
+        );
+    }
+
+    /// Get current equalizer parameters
+    pub fn params(&self) -> EqParams {
+        self.params
+    }
+
+    /// Reset the filter state
+    pub fn reset(&mut self) {
+        self.bands.iter_mut().for_each(Band::reset);
+    }
+
+    /// Process a stereo frame
+    #[inline]
+    pub fn process(&mut self, mut left: f32, mut right: f32) -> (f32, f32) {
+        if !self.params.enabled {
+            return (left, right);
+        }
+        for band in &mut self.bands {
+            if band.active {
+                left = band.process(0, left);
+                right = band.process(1, right);
+            }
+        }
+        (left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+
+    /// Peak output level of a unit sine at `freq` (after the filter settles)
+    fn sine_peak(eq: &mut Equalizer, freq: f32) -> f32 {
+        eq.reset();
+        let mut peak = 0.0f32;
+        for i in 0..SAMPLE_RATE as usize {
+            let input = (2.0 * PI * freq * i as f32 / SAMPLE_RATE).sin();
+            let (left, _) = eq.process(input, input);
+            if i > SAMPLE_RATE as usize / 2 {
+                peak = peak.max(left.abs());
+            }
+        }
+        peak
+    }
+
+    #[test]
+    fn test_params_clamping() {
+        let mut params = EqParams {
+            low_gain_db: 40.0,
+            mid_q: 0.0,
+            high_freq: 96000.0,
+            ..EqParams::default()
+        };
+        params.validate();
+        assert_eq!(params.low_gain_db, 18.0);
+        assert_eq!(params.mid_q, 0.1);
+        assert_eq!(params.high_freq, 20000.0);
+    }
+
+    #[test]
+    fn test_shelves_boost_their_band_only() {
+        let params = EqParams {
+            low_gain_db: 12.0,
+            high_gain_db: -12.0,
+            ..EqParams::default()
+        };
+        let mut eq = Equalizer::new(params, SAMPLE_RATE);
+
+        let boost = 10f32.powf(12.0 / 20.0);
+        assert!((sine_peak(&mut eq, 30.0) - boost).abs() < 0.2);
+        assert!((sine_peak(&mut eq, 1000.0) - 1.0).abs() < 0.15);
+        assert!((sine_peak(&mut eq, 18000.0) - 1.0 / boost).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_mid_band_peaks_at_center() {
+        let params = EqParams {
+            mid_gain_db: 6.0,
+            mid_q: 2.0,
+            ..EqParams::default()
+        };
+        let mut eq = Equalizer::new(params, SAMPLE_RATE);
+
+        assert!((sine_peak(&mut eq, 1000.0) - 10f32.powf(6.0 / 20.0)).abs() < 0.05);
+        assert!((sine_peak(&mut eq, 100.0) - 1.0).abs() < 0.05);
+
+        let mut bypassed = params;
+        bypassed.enabled = false;
+        eq.set_params(bypassed);
+        assert_eq!(eq.process(0.3, 0.3), (0.3, 0.3));
+    }
+}
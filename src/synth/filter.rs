@@ -13,8 +13,13 @@
 // - Independent frequency and Q control
 // - Simultaneous outputs (LP, HP, BP, Notch)
 
-use crate::audio::dsp_utils::OnePoleSmoother;
-use std::f32::consts::PI;
+use crate::audio::precision::consts::PI;
+use crate::audio::precision::{InternalSample, InternalX4, from_internal, to_internal};
+use crate::audio::smoothing::{ParamKind, SmoothedParam};
+use wide::{CmpEq, CmpLt};
+
+/// Filters processed together by `StateVariableFilterX4` (one per SIMD lane)
+pub const FILTER_LANES: usize = 4;
 
 /// Filter type/mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
@@ -54,6 +59,60 @@ impl Default for FilterParams {
     }
 }
 
+/// Filter envelope, keyboard tracking and input drive
+///
+/// Kept apart from `FilterParams` so the cutoff/resonance knobs and these
+/// settings go to the audio thread as separate commands. The filter envelope
+/// is the voice's modulation envelope (`SetModEnvelope`): with an amount set,
+/// it opens the cutoff on top of whatever the mod matrix routes to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FilterModParams {
+    /// Cutoff offset at full envelope, in octaves (-8.0 - 8.0)
+    pub envelope_amount: f32,
+    /// How much the cutoff follows the note (0.0 = fixed, 1.0 = one octave
+    /// per octave, relative to C4)
+    pub key_tracking: f32,
+    /// Saturation of the filter input (0.0 = clean, not computed)
+    pub drive: f32,
+}
+
+impl FilterModParams {
+    /// Create new parameters, clamped to their ranges
+    pub fn new(envelope_amount: f32, key_tracking: f32, drive: f32) -> Self {
+        Self {
+            envelope_amount: envelope_amount.clamp(-8.0, 8.0),
+            key_tracking: key_tracking.clamp(0.0, 1.0),
+            drive: drive.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Whether the cutoff depends on the note or the envelope
+    pub fn moves_cutoff(&self) -> bool {
+        self.envelope_amount != 0.0 || self.key_tracking != 0.0
+    }
+
+    /// Cutoff multiplier for a note at an envelope level (0.0 - 1.0)
+    #[inline]
+    pub fn cutoff_ratio(&self, note: u8, envelope: f32) -> f32 {
+        let octaves =
+            self.envelope_amount * envelope + self.key_tracking * (note as f32 - 60.0) / 12.0;
+        octaves.clamp(-8.0, 8.0).exp2()
+    }
+}
+
+/// Input saturation of the filter at a drive amount (0.0 - 1.0)
+///
+/// `tanh(g * x) / tanh(g)` with a gain `g` from 1 to 10: a full-scale input
+/// stays at full scale while quieter parts are pushed up into the curve.
+#[inline]
+fn saturate(input: f32, drive: f32) -> f32 {
+    if drive <= 0.0 {
+        return input;
+    }
+    let gain = 1.0 + 9.0 * drive;
+    (gain * input).tanh() / gain.tanh()
+}
+
 /// State Variable Filter (Chamberlin) implementation
 ///
 /// This filter uses the Chamberlin digital approximation of a state variable filter.
@@ -83,16 +142,19 @@ pub struct StateVariableFilter {
     sample_rate: f32,
 
     // State variables
-    low: f32,  // Low-pass state
-    band: f32, // Band-pass state
+    low: InternalSample,  // Low-pass state
+    band: InternalSample, // Band-pass state
 
     // Coefficients (computed from cutoff and resonance)
-    f: f32, // Frequency coefficient
-    q: f32, // Resonance coefficient (damping)
+    f: InternalSample, // Frequency coefficient
+    q: InternalSample, // Resonance coefficient (damping)
 
-    // Smoothers to avoid zipper noise when parameters change
-    cutoff_smoother: OnePoleSmoother,
-    resonance_smoother: OnePoleSmoother,
+    // Smoothed parameters to avoid zipper noise when parameters change
+    cutoff: SmoothedParam,
+    resonance: SmoothedParam,
+
+    // Input saturation (0.0 = clean)
+    drive: f32,
 }
 
 impl StateVariableFilter {
@@ -102,9 +164,9 @@ impl StateVariableFilter {
     /// * `params` - Initial filter parameters
     /// * `sample_rate` - Audio sample rate in Hz
     pub fn new(params: FilterParams, sample_rate: f32) -> Self {
-        // Initialize smoothers with 5ms time constant (fast but no clicks)
-        let cutoff_smoother = OnePoleSmoother::new(params.cutoff, 5.0, sample_rate);
-        let resonance_smoother = OnePoleSmoother::new(params.resonance, 5.0, sample_rate);
+        let cutoff = SmoothedParam::new(ParamKind::FilterCutoff, params.cutoff, sample_rate);
+        let resonance =
+            SmoothedParam::new(ParamKind::FilterResonance, params.resonance, sample_rate);
 
         let mut filter = Self {
             params,
@@ -113,8 +175,9 @@ impl StateVariableFilter {
             band: 0.0,
             f: 0.0,
             q: 0.0,
-            cutoff_smoother,
-            resonance_smoother,
+            cutoff,
+            resonance,
+            drive: 0.0,
         };
 
         // Compute initial coefficients
@@ -129,6 +192,22 @@ impl StateVariableFilter {
     pub fn set_params(&mut self, params: FilterParams) {
         self.params = params;
         // Smoothing is applied in process() to maintain RT-safety
+        self.cutoff.set_target(params.cutoff);
+        self.resonance.set_target(params.resonance);
+    }
+
+    /// Set the input drive (0.0 - 1.0, see `FilterModParams::drive`)
+    pub fn set_drive(&mut self, drive: f32) {
+        self.drive = drive.clamp(0.0, 1.0);
+    }
+
+    /// Advance the cutoff smoothing by one sample and return the base cutoff
+    ///
+    /// Callers modulating the cutoff themselves (`process_modulated()`) apply
+    /// their modulation to this value, so knob moves stay smooth.
+    #[inline]
+    pub fn next_cutoff(&mut self) -> f32 {
+        self.cutoff.next_value()
     }
 
     /// Get current filter parameters
@@ -139,9 +218,13 @@ impl StateVariableFilter {
     /// Reset filter state (clear delay lines)
     ///
     /// Useful when switching notes or resetting the synth to avoid clicks.
+    /// Parameters jump to their targets: a new note does not glide from the
+    /// values of the previous one.
     pub fn reset(&mut self) {
         self.low = 0.0;
         self.band = 0.0;
+        self.cutoff.reset(self.params.cutoff);
+        self.resonance.reset(self.params.resonance);
     }
 
     /// Update internal coefficients from cutoff and resonance
@@ -155,14 +238,14 @@ impl StateVariableFilter {
     fn update_coefficients(&mut self, cutoff: f32, resonance: f32) {
         // Clamp cutoff to safe range: 20Hz to Fs/6 (stability limit)
         let max_cutoff = self.sample_rate / 6.0;
-        let safe_cutoff = cutoff.clamp(20.0, max_cutoff);
+        let safe_cutoff = to_internal(cutoff.clamp(20.0, max_cutoff));
 
         // Compute frequency coefficient: f = 2 * sin(π * fc / Fs)
-        self.f = 2.0 * (PI * safe_cutoff / self.sample_rate).sin();
+        self.f = 2.0 * (PI * safe_cutoff / to_internal(self.sample_rate)).sin();
 
         // Compute damping (resonance): q = 1/Q
         // Clamp Q to reasonable range: 0.5 (no resonance) to 20.0 (high resonance)
-        let q_factor = resonance.clamp(0.5, 20.0);
+        let q_factor = to_internal(resonance.clamp(0.5, 20.0));
         self.q = 1.0 / q_factor;
 
         // Clamp q to avoid instability
@@ -184,14 +267,15 @@ impl StateVariableFilter {
         }
 
         // Apply smoothing to parameters (avoid zipper noise)
-        let smoothed_cutoff = self.cutoff_smoother.process(self.params.cutoff);
-        let smoothed_resonance = self.resonance_smoother.process(self.params.resonance);
+        let smoothed_cutoff = self.cutoff.next_value();
+        let smoothed_resonance = self.resonance.next_value();
 
         // Update coefficients if parameters changed
         self.update_coefficients(smoothed_cutoff, smoothed_resonance);
 
         // Chamberlin State Variable Filter algorithm
         // Reference: Musical Applications of Microprocessors (Chamberlin, 1985)
+        let input = to_internal(saturate(input, self.drive));
 
         // Compute high-pass output: hp = input - low - q*band
         let high = input - self.low - self.q * self.band;
@@ -206,55 +290,193 @@ impl StateVariableFilter {
         let notch = input - self.q * self.band;
 
         // Return output based on filter type
-        match self.params.filter_type {
+        from_internal(match self.params.filter_type {
             FilterType::LowPass => self.low,
             FilterType::HighPass => high,
             FilterType::BandPass => self.band,
             FilterType::Notch => notch,
-        }
+        })
     }
 
     /// Process a single sample with modulated cutoff
     ///
     /// This is optimized for real-time modulation (e.g., LFO or envelope).
-    /// The cutoff parameter is applied directly without smoothing.
+    /// The cutoff parameter is applied directly without smoothing: derive it
+    /// from `next_cutoff()` to smooth the base cutoff.
     ///
     /// # Arguments
     /// * `input` - Input sample
     /// * `modulated_cutoff` - Cutoff frequency in Hz (after modulation)
+    /// * `resonance_mult` - Multiplier of the (smoothed) resonance, 1.0 = none
     ///
     /// # Returns
     /// Filtered output
     #[inline]
-    pub fn process_modulated(&mut self, input: f32, modulated_cutoff: f32) -> f32 {
+    pub fn process_modulated(
+        &mut self,
+        input: f32,
+        modulated_cutoff: f32,
+        resonance_mult: f32,
+    ) -> f32 {
         if !self.params.enabled {
             return input;
         }
 
         // Apply resonance smoothing (but not cutoff - it's already modulated)
-        let smoothed_resonance = self.resonance_smoother.process(self.params.resonance);
+        let smoothed_resonance = self.resonance.next_value();
 
         // Update coefficients with modulated cutoff
-        self.update_coefficients(modulated_cutoff, smoothed_resonance);
+        self.update_coefficients(modulated_cutoff, smoothed_resonance * resonance_mult);
 
         // Same algorithm as process()
+        let input = to_internal(saturate(input, self.drive));
         let high = input - self.low - self.q * self.band;
         self.band += self.f * high;
         self.low += self.f * self.band;
         let notch = input - self.q * self.band;
 
-        match self.params.filter_type {
+        from_internal(match self.params.filter_type {
             FilterType::LowPass => self.low,
             FilterType::HighPass => high,
             FilterType::BandPass => self.band,
             FilterType::Notch => notch,
+        })
+    }
+}
+
+/// Four `StateVariableFilter`s processed in the lanes of a SIMD vector
+///
+/// Structure-of-arrays layout: each lane holds the state of one voice's filter.
+/// `load()` copies a filter into a lane, `process_modulated()` runs the math of
+/// `StateVariableFilter::process_modulated()` on all lanes at once (including
+/// the coefficient computation, the per-voice hotspot), and `store()` writes
+/// the state back. Lanes may use different cutoffs, resonances and filter types.
+///
+/// Only enabled filters should be loaded: a disabled filter passes its input
+/// through without updating its state.
+pub struct StateVariableFilterX4 {
+    sample_rate: f32,
+    low: [InternalSample; FILTER_LANES],
+    band: [InternalSample; FILTER_LANES],
+    resonance: [InternalSample; FILTER_LANES],
+    resonance_target: [InternalSample; FILTER_LANES],
+    resonance_coefficient: [InternalSample; FILTER_LANES],
+    filter_types: [FilterType; FILTER_LANES],
+    drives: [f32; FILTER_LANES],
+}
+
+impl StateVariableFilterX4 {
+    /// Create four idle lanes (unloaded lanes filter silence)
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            low: [0.0; FILTER_LANES],
+            band: [0.0; FILTER_LANES],
+            resonance: [to_internal(FilterParams::default().resonance); FILTER_LANES],
+            resonance_target: [to_internal(FilterParams::default().resonance); FILTER_LANES],
+            resonance_coefficient: [0.0; FILTER_LANES],
+            filter_types: [FilterType::LowPass; FILTER_LANES],
+            drives: [0.0; FILTER_LANES],
+        }
+    }
+
+    /// Copy the state and parameters of `filter` into `lane`
+    pub fn load(&mut self, lane: usize, filter: &StateVariableFilter) {
+        debug_assert_eq!(filter.sample_rate, self.sample_rate);
+        self.low[lane] = filter.low;
+        self.band[lane] = filter.band;
+        self.resonance[lane] = to_internal(filter.resonance.current());
+        self.resonance_target[lane] = to_internal(filter.resonance.target());
+        self.resonance_coefficient[lane] = to_internal(filter.resonance.coefficient());
+        self.filter_types[lane] = filter.params.filter_type;
+        self.drives[lane] = filter.drive;
+    }
+
+    /// Write the state of `lane` back into `filter`
+    pub fn store(&self, lane: usize, filter: &mut StateVariableFilter) {
+        filter.low = self.low[lane];
+        filter.band = self.band[lane];
+        filter
+            .resonance
+            .set_current(from_internal(self.resonance[lane]));
+    }
+
+    /// Filter one sample per lane and per frame, in place
+    ///
+    /// `cutoffs` holds the modulated cutoff of each lane for each frame, and
+    /// `resonance_mults` the multiplier of its resonance.
+    pub fn process_modulated(
+        &mut self,
+        frames: &mut [[f32; FILTER_LANES]],
+        cutoffs: &[[f32; FILTER_LANES]],
+        resonance_mults: &[[f32; FILTER_LANES]],
+    ) {
+        let sample_rate = InternalX4::splat(to_internal(self.sample_rate));
+        let min_cutoff = InternalX4::splat(20.0);
+        let max_cutoff = InternalX4::splat(to_internal(self.sample_rate / 6.0));
+        let target = InternalX4::from(self.resonance_target);
+        let coefficient = InternalX4::from(self.resonance_coefficient);
+        let mut resonance = InternalX4::from(self.resonance);
+        let mut low = InternalX4::from(self.low);
+        let mut band = InternalX4::from(self.band);
+
+        // Output selection masks (notch where none is set)
+        let types = InternalX4::from(self.filter_types.map(|t| InternalSample::from(t as u8)));
+        let lanes_of = |filter_type: FilterType| {
+            types.cmp_eq(InternalX4::splat(InternalSample::from(filter_type as u8)))
+        };
+        let is_low = lanes_of(FilterType::LowPass);
+        let is_high = lanes_of(FilterType::HighPass);
+        let is_band = lanes_of(FilterType::BandPass);
+        let driven = self.drives.iter().any(|&drive| drive > 0.0);
+
+        for ((frame, cutoff), resonance_mult) in frames.iter_mut().zip(cutoffs).zip(resonance_mults)
+        {
+            if driven {
+                for (sample, &drive) in frame.iter_mut().zip(&self.drives) {
+                    *sample = saturate(*sample, drive);
+                }
+            }
+            let input = InternalX4::from(frame.map(to_internal));
+
+            // Resonance smoother (same as OnePoleSmoother::process)
+            resonance += coefficient * (target - resonance);
+            resonance = resonance
+                .abs()
+                .cmp_lt(InternalX4::splat(1e-15))
+                .blend(InternalX4::ZERO, resonance);
+
+            // Coefficients (same as StateVariableFilter::update_coefficients)
+            let safe_cutoff = InternalX4::from(cutoff.map(to_internal))
+                .max(min_cutoff)
+                .min(max_cutoff);
+            let f = 2.0 * (PI * safe_cutoff / sample_rate).sin();
+            let q_factor = (resonance * InternalX4::from(resonance_mult.map(to_internal)))
+                .max(InternalX4::splat(0.5))
+                .min(InternalX4::splat(20.0));
+            let q = (1.0 / q_factor)
+                .max(InternalX4::splat(0.01))
+                .min(InternalX4::splat(2.0));
+
+            let high = input - low - q * band;
+            band += f * high;
+            low += f * band;
+            let notch = input - q * band;
+
+            let output = is_low.blend(low, is_high.blend(high, is_band.blend(band, notch)));
+            *frame = output.to_array().map(from_internal);
         }
+
+        self.resonance = resonance.to_array();
+        self.low = low.to_array();
+        self.band = band.to_array();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::f32::consts::PI;
 
     #[test]
     fn test_filter_creation() {
@@ -371,7 +593,7 @@ mod tests {
         // Modulate cutoff rapidly (simulate LFO)
         for i in 0..1000 {
             let modulated_cutoff = 500.0 + 500.0 * (i as f32 / 100.0).sin();
-            let output = filter.process_modulated(0.5, modulated_cutoff);
+            let output = filter.process_modulated(0.5, modulated_cutoff, 1.0);
             assert!(output.is_finite());
         }
     }
@@ -798,7 +1020,7 @@ mod tests {
 
         // Both should produce same output when cutoff matches
         let output1 = filter1.process(input);
-        let output2 = filter2.process_modulated(input, cutoff);
+        let output2 = filter2.process_modulated(input, cutoff, 1.0);
 
         // Allow some tolerance due to smoothing differences
         assert!(
@@ -808,4 +1030,130 @@ mod tests {
             output2
         );
     }
+
+    #[test]
+    fn test_x4_lanes_match_scalar_filters() {
+        let sample_rate = 48000.0;
+        let types = [
+            FilterType::LowPass,
+            FilterType::HighPass,
+            FilterType::BandPass,
+            FilterType::Notch,
+        ];
+        let mut scalar: Vec<StateVariableFilter> = types
+            .iter()
+            .enumerate()
+            .map(|(lane, &filter_type)| {
+                let params = FilterParams {
+                    cutoff: 300.0 * (lane + 1) as f32,
+                    resonance: 0.7 + 3.0 * lane as f32,
+                    filter_type,
+                    enabled: true,
+                };
+                let mut filter = StateVariableFilter::new(params, sample_rate);
+                // A different drive per lane, the first one clean
+                filter.set_drive(0.3 * lane as f32);
+                filter
+            })
+            .collect();
+        let mut lanes_source: Vec<StateVariableFilter> = scalar
+            .iter()
+            .map(|f| {
+                let mut filter = StateVariableFilter::new(f.params(), sample_rate);
+                filter.set_drive(f.drive);
+                filter
+            })
+            .collect();
+
+        // Several blocks with a swept cutoff, state carried across blocks
+        for block in 0..8 {
+            let mut lanes = StateVariableFilterX4::new(sample_rate);
+            for (lane, filter) in lanes_source.iter().enumerate() {
+                lanes.load(lane, filter);
+            }
+
+            let mut frames = [[0.0; FILTER_LANES]; 64];
+            let mut cutoffs = [[0.0; FILTER_LANES]; 64];
+            for (i, (frame, cutoff)) in frames.iter_mut().zip(&mut cutoffs).enumerate() {
+                let t = (block * 64 + i) as f32;
+                for lane in 0..FILTER_LANES {
+                    frame[lane] = (t * 0.05 * (lane + 1) as f32).sin();
+                    cutoff[lane] = scalar[lane].params().cutoff * (1.0 + 0.5 * (t * 0.01).sin());
+                }
+            }
+            // A different resonance modulation per lane
+            let resonance_mults = [[0.5, 1.0, 1.5, 2.0]; 64];
+            let inputs = frames;
+            lanes.process_modulated(&mut frames, &cutoffs, &resonance_mults);
+
+            for (lane, filter) in scalar.iter_mut().enumerate() {
+                for i in 0..64 {
+                    let expected = filter.process_modulated(
+                        inputs[i][lane],
+                        cutoffs[i][lane],
+                        resonance_mults[i][lane],
+                    );
+                    let actual = frames[i][lane];
+                    assert!(
+                        (expected - actual).abs() < 1e-3,
+                        "lane {} sample {}: {} vs {}",
+                        lane,
+                        block * 64 + i,
+                        actual,
+                        expected
+                    );
+                }
+            }
+            for (lane, filter) in lanes_source.iter_mut().enumerate() {
+                lanes.store(lane, filter);
+            }
+        }
+    }
+
+    #[test]
+    fn test_drive_saturates_the_input() {
+        let params = FilterParams {
+            enabled: true,
+            cutoff: 5000.0,
+            ..Default::default()
+        };
+        let mut clean = StateVariableFilter::new(params, 44100.0);
+        let mut driven = StateVariableFilter::new(params, 44100.0);
+        driven.set_drive(1.0);
+
+        // DC through a low-pass: the output settles on the (saturated) input
+        let (mut clean_out, mut driven_out) = (0.0, 0.0);
+        for _ in 0..2000 {
+            clean_out = clean.process(0.2);
+            driven_out = driven.process(0.2);
+        }
+        assert!((clean_out - 0.2).abs() < 0.01);
+        let expected = (10.0f32 * 0.2).tanh() / 10.0f32.tanh();
+        assert!((driven_out - expected).abs() < 0.01, "{}", driven_out);
+
+        // Full scale stays at full scale
+        assert!((saturate(1.0, 1.0) - 1.0).abs() < 1e-6);
+        assert_eq!(saturate(0.3, 0.0), 0.3);
+    }
+
+    #[test]
+    fn test_filter_mod_cutoff_ratio() {
+        assert!(!FilterModParams::default().moves_cutoff());
+        assert_eq!(FilterModParams::default().cutoff_ratio(96, 1.0), 1.0);
+
+        // Full key tracking: one octave per octave around C4
+        let tracking = FilterModParams::new(0.0, 1.0, 0.0);
+        assert!((tracking.cutoff_ratio(72, 0.0) - 2.0).abs() < 1e-5);
+        assert!((tracking.cutoff_ratio(48, 0.0) - 0.5).abs() < 1e-5);
+        assert!((tracking.cutoff_ratio(60, 0.0) - 1.0).abs() < 1e-5);
+
+        // Envelope amount in octaves, scaled by the envelope level
+        let envelope = FilterModParams::new(3.0, 0.0, 0.0);
+        assert!((envelope.cutoff_ratio(60, 1.0) - 8.0).abs() < 1e-4);
+        assert!((envelope.cutoff_ratio(60, 0.5) - 2.0f32.powf(1.5)).abs() < 1e-4);
+
+        // Out of range values are clamped
+        let clamped = FilterModParams::new(20.0, 2.0, -1.0);
+        assert_eq!(clamped, FilterModParams::new(8.0, 1.0, 0.0));
+    }
 }
@@ -2,8 +2,16 @@
 //
 // Used for modulation of various parameters (pitch, volume, filter cutoff, etc.)
 // Operates at low frequencies (0.1 Hz - 20 Hz typically)
+//
+// Each synth voice runs `LFO_COUNT` LFOs, read by the modulation matrix as
+// `ModSource::Lfo(n)`. LFO 1 also keeps its direct destination (vibrato,
+// tremolo...). Rates are in Hz or synced to the tempo (a note division).
 
 use super::oscillator::{Oscillator, SimpleOscillator, WaveformType};
+use crate::sequencer::timeline::{NoteDivision, Tempo};
+
+/// LFOs per synth voice (`ModSource::Lfo(0)` to `ModSource::Lfo(LFO_COUNT - 1)`)
+pub const LFO_COUNT: usize = 3;
 
 /// LFO modulation destination
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
@@ -24,6 +32,30 @@ impl Default for LfoDestination {
     }
 }
 
+/// What the LFO does on a new note
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum LfoMode {
+    /// Keeps running: every voice follows the same free-running cycle
+    Free,
+    /// Restarts from the start phase on each note
+    #[default]
+    Retrigger,
+    /// Restarts on each note, runs one cycle and holds its last value
+    OneShot,
+}
+
+impl LfoMode {
+    pub const ALL: [LfoMode; 3] = [LfoMode::Free, LfoMode::Retrigger, LfoMode::OneShot];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LfoMode::Free => "Free",
+            LfoMode::Retrigger => "Retrigger",
+            LfoMode::OneShot => "One-shot",
+        }
+    }
+}
+
 /// LFO parameters
 #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct LfoParams {
@@ -35,6 +67,14 @@ pub struct LfoParams {
     pub depth: f32,
     /// Modulation destination
     pub destination: LfoDestination,
+    /// Tempo-synced rate (one cycle per division), replaces `rate` when set
+    #[serde(default)]
+    pub sync: Option<NoteDivision>,
+    #[serde(default)]
+    pub mode: LfoMode,
+    /// Phase of the cycle at the start of a note (0.0 - 1.0)
+    #[serde(default)]
+    pub start_phase: f32,
 }
 
 impl LfoParams {
@@ -45,6 +85,27 @@ impl LfoParams {
             rate: rate.clamp(0.1, 20.0),
             depth: depth.clamp(0.0, 1.0),
             destination,
+            sync: None,
+            mode: LfoMode::default(),
+            start_phase: 0.0,
+        }
+    }
+
+    /// Same LFO with a tempo sync, a mode and a start phase
+    pub fn with_timing(self, sync: Option<NoteDivision>, mode: LfoMode, start_phase: f32) -> Self {
+        Self {
+            sync,
+            mode,
+            start_phase: start_phase.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Cycle frequency in Hz at a tempo
+    pub fn frequency(&self, tempo: &Tempo) -> f32 {
+        match self.sync {
+            Some(division) => division.frequency(tempo) as f32,
+            None => self.rate,
         }
     }
 
@@ -62,6 +123,9 @@ impl Default for LfoParams {
             rate: 5.0,  // 5 Hz
             depth: 0.5, // 50% modulation depth
             destination: LfoDestination::None,
+            sync: None,
+            mode: LfoMode::Retrigger,
+            start_phase: 0.0,
         }
     }
 }
@@ -73,35 +137,61 @@ impl Default for LfoParams {
 pub struct Lfo {
     params: LfoParams,
     oscillator: SimpleOscillator,
+    /// Tempo of synced rates
+    tempo: Tempo,
+    /// Cycle frequency in Hz (rate or synced division)
+    frequency: f32,
+    /// One-shot: part of the cycle played since the note on
+    one_shot_progress: f32,
+    /// One-shot: value held once the cycle is played
+    held_value: Option<f32>,
+    /// Mod matrix modulation: frequency multiplier and depth offset
+    rate_scale: f32,
+    depth_offset: f32,
 }
 
 impl Lfo {
     /// Create a new LFO
     pub fn new(params: LfoParams, sample_rate: f32) -> Self {
-        let mut oscillator = SimpleOscillator::new(params.waveform, sample_rate);
-        oscillator.set_frequency(params.rate);
+        let mut lfo = Self {
+            params,
+            oscillator: SimpleOscillator::new(params.waveform, sample_rate),
+            tempo: Tempo::default(),
+            frequency: 0.0,
+            one_shot_progress: 0.0,
+            held_value: None,
+            rate_scale: 1.0,
+            depth_offset: 0.0,
+        };
+        lfo.update_frequency();
+        lfo
+    }
 
-        Self { params, oscillator }
+    /// Follow a rate, sync or tempo change
+    fn update_frequency(&mut self) {
+        let frequency = self.params.frequency(&self.tempo);
+        if (self.frequency - frequency).abs() > 0.001 {
+            self.frequency = frequency;
+            self.oscillator
+                .set_frequency(self.frequency * self.rate_scale);
+        }
     }
 
     /// Set new LFO parameters
     pub fn set_params(&mut self, params: LfoParams) {
-        let rate_changed = (self.params.rate - params.rate).abs() > 0.001;
         let waveform_changed = self.params.waveform != params.waveform;
 
         self.params = params;
 
-        if rate_changed {
-            self.oscillator.set_frequency(self.params.rate);
-        }
-
         if waveform_changed {
             // Need to recreate oscillator with new waveform
             // We lose phase continuity here, but that's acceptable for LFO parameter changes
             let sample_rate = self.oscillator.sample_rate;
             self.oscillator = SimpleOscillator::new(self.params.waveform, sample_rate);
-            self.oscillator.set_frequency(self.params.rate);
+            self.oscillator
+                .set_frequency(self.frequency * self.rate_scale);
         }
+        self.update_frequency();
     }
 
     /// Get current parameters
@@ -109,13 +199,28 @@ impl Lfo {
         self.params
     }
 
+    /// Set the tempo of synced rates
+    pub fn set_tempo(&mut self, tempo: Tempo) {
+        self.tempo = tempo;
+        self.update_frequency();
+    }
+
+    /// Rebuild the oscillator at a new sample rate, keeping the parameters and tempo
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.oscillator = SimpleOscillator::new(self.params.waveform, sample_rate);
+        self.oscillator
+            .set_frequency(self.frequency * self.rate_scale);
+    }
+
+    /// Current cycle frequency in Hz (rate, or synced division at the tempo)
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
     /// Set LFO rate (frequency in Hz)
     pub fn set_rate(&mut self, rate: f32) {
-        let rate = rate.clamp(0.1, 20.0);
-        if (self.params.rate - rate).abs() > 0.001 {
-            self.params.rate = rate;
-            self.oscillator.set_frequency(rate);
-        }
+        self.params.rate = rate.clamp(0.1, 20.0);
+        self.update_frequency();
     }
 
     /// Set modulation depth (0.0 to 1.0)
@@ -129,10 +234,27 @@ impl Lfo {
             self.params.waveform = waveform;
             let sample_rate = self.oscillator.sample_rate;
             self.oscillator = SimpleOscillator::new(waveform, sample_rate);
-            self.oscillator.set_frequency(self.params.rate);
+            self.oscillator
+                .set_frequency(self.frequency * self.rate_scale);
         }
     }
 
+    /// Modulate the rate (frequency multiplier) and depth (added, the result
+    /// is kept within 0.0 - 1.0), e.g. from the mod matrix
+    pub fn set_modulation(&mut self, rate_scale: f32, depth_offset: f32) {
+        if rate_scale != self.rate_scale {
+            self.rate_scale = rate_scale;
+            self.oscillator.set_frequency(self.frequency * rate_scale);
+        }
+        self.depth_offset = depth_offset;
+    }
+
+    /// Depth with its modulation
+    #[inline]
+    fn depth(&self) -> f32 {
+        (self.params.depth + self.depth_offset).clamp(0.0, 1.0)
+    }
+
     /// Set modulation destination
     pub fn set_destination(&mut self, destination: LfoDestination) {
         self.params.destination = destination;
@@ -143,16 +265,48 @@ impl Lfo {
     /// Returns a value between -depth and +depth (centered around 0)
     /// The caller is responsible for applying this modulation to the target parameter
     pub fn process(&mut self) -> f32 {
+        if let Some(value) = self.held_value {
+            return value * self.depth();
+        }
+
         // Get oscillator sample (range -1.0 to 1.0)
         let osc_value = self.oscillator.next_sample();
 
+        if self.params.mode == LfoMode::OneShot {
+            self.one_shot_progress +=
+                self.frequency * self.rate_scale / self.oscillator.sample_rate;
+            if self.one_shot_progress >= 1.0 {
+                self.held_value = Some(osc_value);
+            }
+        }
+
         // Scale by depth
-        osc_value * self.params.depth
+        osc_value * self.depth()
     }
 
     /// Reset LFO phase to beginning
     pub fn reset(&mut self) {
         self.oscillator.reset();
+        self.one_shot_progress = 0.0;
+        self.held_value = None;
+    }
+
+    /// Start of a note: retrigger and one-shot LFOs restart from their start phase
+    pub fn note_on(&mut self) {
+        if self.params.mode != LfoMode::Free {
+            self.reset();
+            self.oscillator.set_phase(self.params.start_phase);
+        }
+    }
+
+    /// Align a free-running LFO on a shared clock (`elapsed` seconds since it started)
+    ///
+    /// Voices starting at different times then play the same cycle.
+    pub fn sync_free_phase(&mut self, elapsed: f64) {
+        if self.params.mode == LfoMode::Free {
+            let cycles = elapsed * self.frequency as f64 + self.params.start_phase as f64;
+            self.oscillator.set_phase(cycles.fract() as f32);
+        }
     }
 
     /// Get the modulation destination
@@ -292,6 +446,84 @@ mod tests {
         assert_eq!(lfo.destination(), LfoDestination::Volume);
     }
 
+    #[test]
+    fn test_lfo_tempo_sync() {
+        let quarter = NoteDivision::default();
+        let params = LfoParams::default().with_timing(Some(quarter), LfoMode::Retrigger, 0.0);
+        let mut lfo = Lfo::new(params, TEST_SAMPLE_RATE);
+        // 120 BPM: one cycle per beat
+        assert!((lfo.frequency() - 2.0).abs() < EPSILON);
+
+        lfo.set_tempo(Tempo::new(90.0));
+        assert!((lfo.frequency() - 1.5).abs() < EPSILON);
+
+        // The Hz rate is ignored while synced
+        lfo.set_rate(10.0);
+        assert!((lfo.frequency() - 1.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_lfo_start_phase_and_modes() {
+        // Sine from a quarter of the cycle: starts at its peak
+        let params = LfoParams::new(WaveformType::Sine, 5.0, 1.0, LfoDestination::None)
+            .with_timing(None, LfoMode::Retrigger, 0.25);
+        let mut lfo = Lfo::new(params, TEST_SAMPLE_RATE);
+        for _ in 0..1234 {
+            lfo.process();
+        }
+        lfo.note_on();
+        assert!((lfo.process() - 1.0).abs() < 0.01);
+
+        // Free: the note does not restart the cycle
+        lfo.set_params(params.with_timing(None, LfoMode::Free, 0.25));
+        let before = lfo.process();
+        lfo.note_on();
+        assert!((lfo.process() - before).abs() < 0.01);
+
+        // One-shot: one cycle, then the last value is held
+        lfo.set_params(params.with_timing(None, LfoMode::OneShot, 0.0));
+        lfo.note_on();
+        let cycle = (TEST_SAMPLE_RATE / 5.0) as usize;
+        for _ in 0..cycle + 10 {
+            lfo.process();
+        }
+        let held = lfo.process();
+        assert!(held.abs() < 0.01, "held {}", held);
+        assert!((0..1000).all(|_| lfo.process() == held));
+    }
+
+    #[test]
+    fn test_free_lfos_share_the_clock() {
+        let params = LfoParams::new(WaveformType::Saw, 2.0, 1.0, LfoDestination::None).with_timing(
+            None,
+            LfoMode::Free,
+            0.0,
+        );
+        let mut early = Lfo::new(params, TEST_SAMPLE_RATE);
+        let mut late = Lfo::new(params, TEST_SAMPLE_RATE);
+        early.sync_free_phase(0.0);
+        for _ in 0..10000 {
+            early.process();
+        }
+        late.sync_free_phase(10000.0 / TEST_SAMPLE_RATE as f64);
+        assert!((early.process() - late.process()).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_lfo_modulation() {
+        let params = LfoParams::new(WaveformType::Sine, 5.0, 0.5, LfoDestination::None);
+        let mut lfo = Lfo::new(params, TEST_SAMPLE_RATE);
+        // Twice the rate, full depth: the peak comes after a quarter of 0.1 s
+        lfo.set_modulation(2.0, 0.75);
+        let quarter = (TEST_SAMPLE_RATE / 40.0) as usize;
+        for _ in 0..quarter {
+            lfo.process();
+        }
+        assert!((lfo.process() - 1.0).abs() < 0.01);
+        // The base rate is unchanged
+        assert!((lfo.frequency() - 5.0).abs() < EPSILON);
+    }
+
     #[test]
     fn test_lfo_reset() {
         let params = LfoParams::new(WaveformType::Sine, 5.0, 1.0, LfoDestination::Pitch);
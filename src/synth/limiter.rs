@@ -0,0 +1,187 @@
+// Limiter - Stereo-linked peak limiter for the master bus
+//
+// Simple feed-forward peak limiter:
+// - Instant attack (gain drops as soon as a peak exceeds the threshold)
+// - Exponential release back to unity gain
+// - Left/right share the same gain so the stereo image is preserved
+//
+// Real-time constraints:
+// - No allocations, no lookahead buffer
+// - Coefficients recomputed only when parameters change
+
+/// Limiter parameters
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LimiterParams {
+    /// Threshold / output ceiling in dBFS (-24.0 - 0.0)
+    pub threshold_db: f32,
+    /// Release time in milliseconds (1.0 - 1000.0)
+    pub release_ms: f32,
+    /// Enable/disable limiter (bypass)
+    pub enabled: bool,
+}
+
+impl Default for LimiterParams {
+    fn default() -> Self {
+        Self {
+            threshold_db: -0.3, // Just below full scale
+            release_ms: 50.0,
+            enabled: true,
+        }
+    }
+}
+
+impl LimiterParams {
+    /// Create new limiter parameters with clamping
+    pub fn new(threshold_db: f32, release_ms: f32) -> Self {
+        let mut params = Self {
+            threshold_db,
+            release_ms,
+            enabled: true,
+        };
+        params.validate();
+        params
+    }
+
+    /// Validate and clamp parameters to safe ranges
+    pub fn validate(&mut self) {
+        self.threshold_db = self.threshold_db.clamp(-24.0, 0.0);
+        self.release_ms = self.release_ms.clamp(1.0, 1000.0);
+    }
+}
+
+/// Stereo-linked peak limiter
+///
+/// # Example
+/// ```
+/// use mymusic_daw::synth::limiter::{Limiter, LimiterParams};
+///
+/// let mut limiter = Limiter::new(LimiterParams::new(-6.0, 50.0), 44100.0);
+/// let (left, right) = limiter.process(1.0, -1.0);
+/// assert!(left.abs() <= 0.51 && right.abs() <= 0.51);
+/// ```
+pub struct Limiter {
+    params: LimiterParams,
+    sample_rate: f32,
+    /// Linear threshold
+    threshold: f32,
+    /// Per-sample release coefficient
+    release_coeff: f32,
+    /// Current gain (1.0 = no reduction)
+    gain: f32,
+}
+
+impl Limiter {
+    /// Create a new limiter
+    pub fn new(params: LimiterParams, sample_rate: f32) -> Self {
+        let mut limiter = Self {
+            params,
+            sample_rate,
+            threshold: 1.0,
+            release_coeff: 0.0,
+            gain: 1.0,
+        };
+        limiter.set_params(params);
+        limiter
+    }
+
+    /// Set limiter parameters
+    pub fn set_params(&mut self, mut params: LimiterParams) {
+        params.validate();
+        self.params = params;
+        self.threshold = 10f32.powf(params.threshold_db / 20.0);
+        self.release_coeff = (-1.0 / (params.release_ms * 0.001 * self.sample_rate)).exp();
+    }
+
+    /// Get current limiter parameters
+    pub fn params(&self) -> LimiterParams {
+        self.params
+    }
+
+    /// Current gain reduction in dB (0.0 = no reduction)
+    pub fn gain_reduction_db(&self) -> f32 {
+        -20.0 * self.gain.log10()
+    }
+
+    /// Current linear gain (1.0 = no reduction)
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Reset the limiter state (back to unity gain)
+    pub fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+
+    /// Process a stereo frame
+    #[inline]
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        if !self.params.enabled {
+            return (left, right);
+        }
+
+        let peak = left.abs().max(right.abs());
+        let target = if peak > self.threshold {
+            self.threshold / peak
+        } else {
+            1.0
+        };
+
+        if target < self.gain {
+            // Instant attack
+            self.gain = target;
+        } else {
+            // Exponential release towards target
+            self.gain = target + (self.gain - target) * self.release_coeff;
+        }
+
+        (left * self.gain, right * self.gain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limiter_params_clamping() {
+        let params = LimiterParams::new(6.0, 0.0);
+        assert_eq!(params.threshold_db, 0.0);
+        assert_eq!(params.release_ms, 1.0);
+    }
+
+    #[test]
+    fn test_limiter_keeps_peaks_below_threshold() {
+        let mut limiter = Limiter::new(LimiterParams::new(-6.0, 50.0), 44100.0);
+        let ceiling = 10f32.powf(-6.0 / 20.0);
+
+        for i in 0..4410 {
+            let input = (i as f32 * 0.05).sin() * 2.0;
+            let (left, right) = limiter.process(input, -input);
+            assert!(left.abs() <= ceiling + 1e-6);
+            assert!(right.abs() <= ceiling + 1e-6);
+        }
+        assert!(limiter.gain_reduction_db() > 0.0);
+    }
+
+    #[test]
+    fn test_limiter_releases_to_unity() {
+        let mut limiter = Limiter::new(LimiterParams::new(-6.0, 10.0), 44100.0);
+        limiter.process(1.0, 1.0);
+        assert!(limiter.gain_reduction_db() > 5.0);
+
+        // 100ms of quiet signal (10 release time constants)
+        for _ in 0..4410 {
+            limiter.process(0.1, 0.1);
+        }
+        assert!(limiter.gain_reduction_db() < 0.01);
+    }
+
+    #[test]
+    fn test_limiter_bypass() {
+        let mut params = LimiterParams::new(-12.0, 50.0);
+        params.enabled = false;
+        let mut limiter = Limiter::new(params, 44100.0);
+
+        assert_eq!(limiter.process(1.0, -1.0), (1.0, -1.0));
+    }
+}
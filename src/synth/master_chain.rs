@@ -0,0 +1,408 @@
+// Master chain - Effects applied to the summed output (master bus)
+//
+// The master bus runs a fixed set of effects (reverb, delay, EQ, compressor,
+// limiter) in a configurable order, after the tracks are summed and before the
+// metering and the final soft clipping. Each effect can be bypassed through the
+// `enabled` flag of its parameters. Reverb and delay are mono, so the chain runs
+// one instance per channel; the EQ, compressor and limiter are stereo-linked.
+//
+// The chain keeps the largest gain reduction of its compressor and limiter
+// between two `take_gain_reduction()` calls, so the engine can publish one
+// reading per buffer for the meters.
+//
+// Real-time constraints:
+// - All delay lines are allocated in `MasterChain::new()` (outside the callback)
+// - Parameter and order changes only copy small `Copy` values
+
+use super::delay::{Delay, DelayParams};
+use super::dynamics::{Dynamics, DynamicsMode, DynamicsParams};
+use super::equalizer::{EqParams, Equalizer};
+use super::limiter::{Limiter, LimiterParams};
+use super::reverb::{Reverb, ReverbParams};
+
+/// Maximum master delay time in milliseconds
+pub const MASTER_DELAY_MAX_MS: f32 = 2000.0;
+
+/// Effects available on the master bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum MasterEffect {
+    Reverb,
+    Delay,
+    Eq,
+    Compressor,
+    Limiter,
+}
+
+impl MasterEffect {
+    /// All master effects, in default processing order
+    pub const ALL: [MasterEffect; 5] = [
+        MasterEffect::Delay,
+        MasterEffect::Reverb,
+        MasterEffect::Eq,
+        MasterEffect::Compressor,
+        MasterEffect::Limiter,
+    ];
+
+    /// Effect name for UI display
+    pub fn name(&self) -> &'static str {
+        match self {
+            MasterEffect::Reverb => "Reverb",
+            MasterEffect::Delay => "Delay",
+            MasterEffect::Eq => "EQ",
+            MasterEffect::Compressor => "Compressor",
+            MasterEffect::Limiter => "Limiter",
+        }
+    }
+}
+
+/// Processing order of the master effects (each effect appears exactly once)
+pub type MasterEffectOrder = [MasterEffect; 5];
+
+/// Check that an order contains every master effect exactly once
+pub fn is_valid_order(order: &MasterEffectOrder) -> bool {
+    MasterEffect::ALL
+        .iter()
+        .all(|effect| order.contains(effect))
+}
+
+/// Full master chain configuration (what frontends mirror and projects store)
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct MasterChainParams {
+    pub reverb: ReverbParams,
+    pub delay: DelayParams,
+    pub eq: EqParams,
+    /// Compressor parameters (always in compressor mode)
+    pub compressor: DynamicsParams,
+    pub limiter: LimiterParams,
+    pub order: MasterEffectOrder,
+}
+
+impl Default for MasterChainParams {
+    /// Only the limiter starts enabled, so the master bus is transparent by default
+    fn default() -> Self {
+        Self {
+            reverb: ReverbParams {
+                enabled: false,
+                ..ReverbParams::default()
+            },
+            delay: DelayParams {
+                enabled: false,
+                ..DelayParams::default()
+            },
+            eq: EqParams {
+                enabled: false,
+                ..EqParams::default()
+            },
+            compressor: DynamicsParams {
+                threshold_db: -12.0,
+                ratio: 2.0,
+                attack_ms: 10.0,
+                release_ms: 200.0,
+                enabled: false,
+                ..DynamicsParams::new(DynamicsMode::Compressor)
+            },
+            limiter: LimiterParams::default(),
+            order: MasterEffect::ALL,
+        }
+    }
+}
+
+impl MasterChainParams {
+    /// Check if an effect is bypassed
+    pub fn is_bypassed(&self, effect: MasterEffect) -> bool {
+        match effect {
+            MasterEffect::Reverb => !self.reverb.enabled,
+            MasterEffect::Delay => !self.delay.enabled,
+            MasterEffect::Eq => !self.eq.enabled,
+            MasterEffect::Compressor => !self.compressor.enabled,
+            MasterEffect::Limiter => !self.limiter.enabled,
+        }
+    }
+
+    /// Bypass (or re-enable) an effect
+    pub fn set_bypassed(&mut self, effect: MasterEffect, bypassed: bool) {
+        match effect {
+            MasterEffect::Reverb => self.reverb.enabled = !bypassed,
+            MasterEffect::Delay => self.delay.enabled = !bypassed,
+            MasterEffect::Eq => self.eq.enabled = !bypassed,
+            MasterEffect::Compressor => self.compressor.enabled = !bypassed,
+            MasterEffect::Limiter => self.limiter.enabled = !bypassed,
+        }
+    }
+}
+
+/// Gain reduction of the master dynamics (what the meters show)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GainReduction {
+    /// Compressor reduction in dB (0.0 = none)
+    pub compressor_db: f32,
+    /// Limiter reduction in dB (0.0 = none)
+    pub limiter_db: f32,
+}
+
+/// Master bus effect chain (owned by the audio thread)
+pub struct MasterChain {
+    reverb: [Reverb; 2],
+    delay: [Delay; 2],
+    eq: Equalizer,
+    compressor: Dynamics,
+    limiter: Limiter,
+    order: MasterEffectOrder,
+    /// Largest compressor reduction since the last `take_gain_reduction()`
+    compressor_reduction_db: f32,
+    /// Lowest limiter gain since the last `take_gain_reduction()` (linear,
+    /// converted to dB once per reading)
+    limiter_min_gain: f32,
+}
+
+impl MasterChain {
+    /// Create a master chain (allocates delay lines)
+    pub fn new(params: MasterChainParams, sample_rate: f32) -> Self {
+        Self {
+            reverb: [
+                Reverb::new(params.reverb, sample_rate),
+                Reverb::new(params.reverb, sample_rate),
+            ],
+            delay: [
+                Delay::new(params.delay, sample_rate, MASTER_DELAY_MAX_MS),
+                Delay::new(params.delay, sample_rate, MASTER_DELAY_MAX_MS),
+            ],
+            eq: Equalizer::new(params.eq, sample_rate),
+            compressor: Dynamics::new(
+                DynamicsParams {
+                    mode: DynamicsMode::Compressor,
+                    ..params.compressor
+                },
+                sample_rate,
+            ),
+            limiter: Limiter::new(params.limiter, sample_rate),
+            order: params.order,
+            compressor_reduction_db: 0.0,
+            limiter_min_gain: 1.0,
+        }
+    }
+
+    /// Current configuration
+    pub fn params(&self) -> MasterChainParams {
+        MasterChainParams {
+            reverb: self.reverb[0].params(),
+            delay: self.delay[0].params(),
+            eq: self.eq.params(),
+            compressor: self.compressor.params(),
+            limiter: self.limiter.params(),
+            order: self.order,
+        }
+    }
+
+    /// Set reverb parameters
+    pub fn set_reverb(&mut self, params: ReverbParams) {
+        for reverb in &mut self.reverb {
+            reverb.set_params(params);
+        }
+    }
+
+    /// Set delay parameters
+    pub fn set_delay(&mut self, params: DelayParams) {
+        for delay in &mut self.delay {
+            delay.set_params(params);
+        }
+    }
+
+    /// Set EQ parameters
+    pub fn set_eq(&mut self, params: EqParams) {
+        self.eq.set_params(params);
+    }
+
+    /// Set compressor parameters (the mode is forced to compressor)
+    pub fn set_compressor(&mut self, params: DynamicsParams) {
+        self.compressor.set_params(DynamicsParams {
+            mode: DynamicsMode::Compressor,
+            ..params
+        });
+    }
+
+    /// Set limiter parameters
+    pub fn set_limiter(&mut self, params: LimiterParams) {
+        self.limiter.set_params(params);
+    }
+
+    /// Set the processing order (ignored if an effect is missing or duplicated)
+    pub fn set_order(&mut self, order: MasterEffectOrder) {
+        if is_valid_order(&order) {
+            self.order = order;
+        }
+    }
+
+    /// Reset all effect states (delay lines, filters, gain reductions)
+    pub fn reset(&mut self) {
+        self.reverb.iter_mut().for_each(Reverb::reset);
+        self.delay.iter_mut().for_each(Delay::reset);
+        self.eq.reset();
+        self.compressor.reset();
+        self.limiter.reset();
+        self.compressor_reduction_db = 0.0;
+        self.limiter_min_gain = 1.0;
+    }
+
+    /// Largest gain reductions since the last call, then start over
+    ///
+    /// A bypassed effect reads 0 dB.
+    pub fn take_gain_reduction(&mut self) -> GainReduction {
+        let reduction = GainReduction {
+            compressor_db: self.compressor_reduction_db,
+            limiter_db: -20.0 * self.limiter_min_gain.log10(),
+        };
+        self.compressor_reduction_db = 0.0;
+        self.limiter_min_gain = 1.0;
+        reduction
+    }
+
+    /// Process a stereo frame through the chain
+    #[inline]
+    pub fn process(&mut self, mut left: f32, mut right: f32) -> (f32, f32) {
+        for effect in self.order {
+            (left, right) = match effect {
+                MasterEffect::Reverb => {
+                    (self.reverb[0].process(left), self.reverb[1].process(right))
+                }
+                MasterEffect::Delay => (self.delay[0].process(left), self.delay[1].process(right)),
+                MasterEffect::Eq => self.eq.process(left, right),
+                MasterEffect::Compressor => {
+                    if !self.compressor.params().enabled {
+                        continue;
+                    }
+                    let output = self.compressor.process(left, right, left, right);
+                    self.compressor_reduction_db = self
+                        .compressor_reduction_db
+                        .max(self.compressor.gain_reduction_db());
+                    output
+                }
+                MasterEffect::Limiter => {
+                    if !self.limiter.params().enabled {
+                        continue;
+                    }
+                    let output = self.limiter.process(left, right);
+                    self.limiter_min_gain = self.limiter_min_gain.min(self.limiter.gain());
+                    output
+                }
+            };
+        }
+        (left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_chain_is_transparent_below_threshold() {
+        let mut chain = MasterChain::new(MasterChainParams::default(), 44100.0);
+
+        for i in 0..1000 {
+            let input = (i as f32 * 0.01).sin() * 0.5;
+            assert_eq!(chain.process(input, -input), (input, -input));
+        }
+    }
+
+    #[test]
+    fn test_invalid_order_is_ignored() {
+        let mut chain = MasterChain::new(MasterChainParams::default(), 44100.0);
+
+        chain.set_order([
+            MasterEffect::Reverb,
+            MasterEffect::Reverb,
+            MasterEffect::Eq,
+            MasterEffect::Compressor,
+            MasterEffect::Limiter,
+        ]);
+        assert_eq!(chain.params().order, MasterEffect::ALL);
+
+        let order = [
+            MasterEffect::Limiter,
+            MasterEffect::Compressor,
+            MasterEffect::Eq,
+            MasterEffect::Reverb,
+            MasterEffect::Delay,
+        ];
+        chain.set_order(order);
+        assert_eq!(chain.params().order, order);
+    }
+
+    #[test]
+    fn test_bypass_flags() {
+        let mut params = MasterChainParams::default();
+        assert!(params.is_bypassed(MasterEffect::Reverb));
+        assert!(!params.is_bypassed(MasterEffect::Limiter));
+
+        params.set_bypassed(MasterEffect::Reverb, false);
+        params.set_bypassed(MasterEffect::Limiter, true);
+        assert!(params.reverb.enabled);
+        assert!(!params.limiter.enabled);
+    }
+
+    #[test]
+    fn test_compressor_reduces_loud_mix() {
+        let mut params = MasterChainParams::default();
+        params.compressor.enabled = true;
+        params.compressor.mode = DynamicsMode::Gate;
+        params.limiter.enabled = false;
+        let mut chain = MasterChain::new(params, 1000.0);
+        assert_eq!(chain.params().compressor.mode, DynamicsMode::Compressor);
+
+        // 0 dBFS, 12 dB over the threshold at 2:1: settles 6 dB down
+        let mut output = 0.0;
+        for _ in 0..2000 {
+            output = chain.process(1.0, 1.0).0;
+        }
+        assert!((output - 10f32.powf(-6.0 / 20.0)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_gain_reduction_metering() {
+        let mut params = MasterChainParams::default();
+        params.compressor.enabled = true;
+        let mut chain = MasterChain::new(params, 1000.0);
+
+        // Quiet mix: nothing to report
+        for _ in 0..100 {
+            chain.process(0.1, 0.1);
+        }
+        assert_eq!(chain.take_gain_reduction(), GainReduction::default());
+
+        // +12 dBFS, 24 dB over the threshold at 2:1: 12 dB of compression,
+        // and the limiter catches what is left above its ceiling
+        for _ in 0..2000 {
+            chain.process(4.0, 4.0);
+        }
+        let reduction = chain.take_gain_reduction();
+        assert!(
+            (reduction.compressor_db - 12.0).abs() < 0.1,
+            "{:?}",
+            reduction
+        );
+        assert!(reduction.limiter_db > 0.0, "{:?}", reduction);
+
+        // Readings start over, and a bypassed stage reads 0 dB
+        assert_eq!(chain.take_gain_reduction(), GainReduction::default());
+        let mut params = chain.params();
+        params.set_bypassed(MasterEffect::Compressor, true);
+        chain.set_compressor(params.compressor);
+        chain.process(4.0, 4.0);
+        assert_eq!(chain.take_gain_reduction().compressor_db, 0.0);
+    }
+
+    #[test]
+    fn test_delay_produces_echo() {
+        let mut params = MasterChainParams::default();
+        params.delay = DelayParams::new(10.0, 0.0, 1.0);
+        let mut chain = MasterChain::new(params, 1000.0);
+
+        // 10ms at 1kHz = 10 samples
+        let (first, _) = chain.process(1.0, 1.0);
+        assert_eq!(first, 0.0);
+        let echo: Vec<f32> = (0..10).map(|_| chain.process(0.0, 0.0).0).collect();
+        assert!(echo[9] > 0.5);
+    }
+}
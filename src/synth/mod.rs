@@ -1,14 +1,21 @@
 // Module synthèse - Oscillateurs et génération de son
 
 pub mod delay;
+pub mod dynamics;
 pub mod effect;
 pub mod envelope;
+pub mod equalizer;
 pub mod filter;
 pub mod lfo;
+pub mod limiter;
+pub mod master_chain;
 pub mod modulation;
+pub mod noise;
 pub mod oscillator;
 pub mod poly_mode;
 pub mod portamento;
 pub mod reverb;
 pub mod voice;
+pub mod voice_limiter;
 pub mod voice_manager;
+pub mod wavetable;
@@ -1,19 +1,81 @@
-// Modulation Matrix (MVP) - lock-free, preallocated, callback-safe
+// Modulation Matrix - lock-free, preallocated, callback-safe
 //
 // This module provides a small, fixed-size modulation matrix that can be
 // evaluated inside the audio callback without allocations or blocking.
-// Sources: LFO(0), Velocity, Aftertouch
-// Destinations: OscillatorPitch(0), Amplitude
+// Sources: LFO(0..LFO_COUNT), Velocity, Aftertouch, Envelope, ModWheel,
+//          PitchBend, KeyTrack, Random, ModEnvelope, MidiCc(n)
+// Destinations: OscillatorPitch(0), Amplitude, Pan, FilterCutoff,
+//          FilterResonance, OscillatorMix, LfoRate(n), LfoDepth(n),
+//          EnvelopeTime, ModEnvelopeTime, DelaySend, ReverbSend
+//
+// The LFO and envelope destinations act on the sources themselves: the voice
+// applies them on the next sample, so a routing can feed back into its source.
+
+use super::lfo::LFO_COUNT;
+use std::ops::RangeInclusive;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ModSource {
+    /// LFO `n` of the voice (0 = LFO 1, up to `LFO_COUNT - 1`)
     Lfo(usize),
     Velocity,
     Aftertouch,
     Envelope,
+    /// Modulation wheel (MIDI CC1), unipolar: no modulation at rest
+    ModWheel,
+    /// Modulation envelope (separate from the amp ADSR), unipolar: no
+    /// modulation before the note and after its release
+    ModEnvelope,
+    /// Pitch bend wheel position, bipolar (0 at the center)
+    PitchBend,
+    /// Note of the voice relative to C4, bipolar (+1 at 64 semitones above)
+    KeyTrack,
+    /// Random value drawn at each note-on and held for the note (sample and hold)
+    Random,
+    /// Any MIDI controller (0 - 127), unipolar like the modulation wheel
+    MidiCc(u8),
+}
+
+impl ModSource {
+    /// Sources offered by the editors (`MidiCc` stands for every controller)
+    pub const ALL: [ModSource; 12] = [
+        ModSource::Lfo(0),
+        ModSource::Lfo(1),
+        ModSource::Lfo(2),
+        ModSource::Velocity,
+        ModSource::Aftertouch,
+        ModSource::Envelope,
+        ModSource::ModEnvelope,
+        ModSource::ModWheel,
+        ModSource::PitchBend,
+        ModSource::KeyTrack,
+        ModSource::Random,
+        ModSource::MidiCc(DEFAULT_MOD_CC),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModSource::Lfo(0) => "LFO 1",
+            ModSource::Lfo(1) => "LFO 2",
+            ModSource::Lfo(2) => "LFO 3",
+            ModSource::Lfo(_) => "LFO",
+            ModSource::Velocity => "Velocity",
+            ModSource::Aftertouch => "Aftertouch",
+            ModSource::Envelope => "Envelope",
+            ModSource::ModWheel => "Mod Wheel",
+            ModSource::ModEnvelope => "Mod Env",
+            ModSource::PitchBend => "Pitch Bend",
+            ModSource::KeyTrack => "Key Track",
+            ModSource::Random => "Random S&H",
+            ModSource::MidiCc(_) => "MIDI CC",
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Controller of a new `ModSource::MidiCc` (CC74, brightness)
+pub const DEFAULT_MOD_CC: u8 = 74;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ModDestination {
     /// Pitch of oscillator index (0 for now)
     OscillatorPitch(usize),
@@ -23,15 +85,95 @@ pub enum ModDestination {
     Pan,
     /// Filter cutoff frequency (Hz delta or multiplier depending on amount)
     FilterCutoff,
+    /// Filter resonance (Q multiplier)
+    FilterResonance,
+    /// Level of the second oscillator (added to its mix)
+    OscillatorMix,
+    /// Rate of LFO `n` (octaves)
+    LfoRate(usize),
+    /// Depth of LFO `n` (added to its depth)
+    LfoDepth(usize),
+    /// Attack, decay and release times of the amp envelope (octaves)
+    EnvelopeTime,
+    /// Attack, decay and release times of the modulation envelope (octaves)
+    ModEnvelopeTime,
+    /// Send of the synth track to its delay return buses (added to the send level)
+    DelaySend,
+    /// Send of the synth track to its reverb return buses (added to the send level)
+    ReverbSend,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl ModDestination {
+    /// Destinations offered by the editors
+    pub const ALL: [ModDestination; 16] = [
+        ModDestination::OscillatorPitch(0),
+        ModDestination::Amplitude,
+        ModDestination::Pan,
+        ModDestination::FilterCutoff,
+        ModDestination::FilterResonance,
+        ModDestination::OscillatorMix,
+        ModDestination::LfoRate(0),
+        ModDestination::LfoRate(1),
+        ModDestination::LfoRate(2),
+        ModDestination::LfoDepth(0),
+        ModDestination::LfoDepth(1),
+        ModDestination::LfoDepth(2),
+        ModDestination::EnvelopeTime,
+        ModDestination::ModEnvelopeTime,
+        ModDestination::DelaySend,
+        ModDestination::ReverbSend,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModDestination::OscillatorPitch(_) => "Pitch",
+            ModDestination::Amplitude => "Amplitude",
+            ModDestination::Pan => "Pan",
+            ModDestination::FilterCutoff => "Filter Cutoff",
+            ModDestination::FilterResonance => "Resonance",
+            ModDestination::OscillatorMix => "Osc Mix",
+            ModDestination::LfoRate(0) => "LFO 1 Rate",
+            ModDestination::LfoRate(1) => "LFO 2 Rate",
+            ModDestination::LfoRate(_) => "LFO 3 Rate",
+            ModDestination::LfoDepth(0) => "LFO 1 Depth",
+            ModDestination::LfoDepth(1) => "LFO 2 Depth",
+            ModDestination::LfoDepth(_) => "LFO 3 Depth",
+            ModDestination::EnvelopeTime => "Env Times",
+            ModDestination::ModEnvelopeTime => "Mod Env Times",
+            ModDestination::DelaySend => "Delay Send",
+            ModDestination::ReverbSend => "Reverb Send",
+        }
+    }
+
+    /// Range of the routing amount (see `ModRouting::amount`)
+    pub fn amount_range(&self) -> RangeInclusive<f32> {
+        match self {
+            ModDestination::OscillatorPitch(_) => -24.0..=24.0,
+            ModDestination::FilterCutoff => 0.0..=10.0,
+            ModDestination::FilterResonance => -1.0..=4.0,
+            ModDestination::LfoRate(_)
+            | ModDestination::EnvelopeTime
+            | ModDestination::ModEnvelopeTime => -4.0..=4.0,
+            ModDestination::Amplitude
+            | ModDestination::Pan
+            | ModDestination::OscillatorMix
+            | ModDestination::LfoDepth(_)
+            | ModDestination::DelaySend
+            | ModDestination::ReverbSend => -1.0..=1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ModRouting {
     pub source: ModSource,
     pub destination: ModDestination,
-    /// Amount in [-1.0, 1.0]. Interpretation depends on destination:
-    /// - Pitch: amount in semitones (multiplied by source value [-1..1])
-    /// - Amplitude: amount as multiplier delta (added to 1.0, result clamped >= 0)
+    /// Amount, multiplied by the source value. Interpretation and range
+    /// (`ModDestination::amount_range`) depend on the destination:
+    /// - Pitch: semitones
+    /// - Amplitude, filter cutoff and resonance: multiplier delta (added to 1.0)
+    /// - Pan, oscillator mix, LFO depth and sends: added to the value
+    /// - LFO rate and envelope times: octaves (x2 per unit)
     pub amount: f32,
     pub enabled: bool,
 }
@@ -47,23 +189,107 @@ impl ModRouting {
     }
 }
 
-pub const MAX_ROUTINGS: usize = 8;
+pub const MAX_ROUTINGS: usize = 16;
+
+/// Current values of the modulation sources of a voice
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModSources {
+    /// 0..1
+    pub velocity: f32,
+    /// 0..1 (channel pressure)
+    pub aftertouch: f32,
+    /// 0..1 (CC1)
+    pub mod_wheel: f32,
+    /// -1..1 (0 = centered)
+    pub pitch_bend: f32,
+    /// -1..1 (see `key_track`)
+    pub key: f32,
+    /// -1..1, drawn at note-on
+    pub random: f32,
+    /// Current outputs of the voice LFOs
+    pub lfos: [f32; LFO_COUNT],
+    /// Current envelope output 0..1
+    pub envelope: f32,
+    /// Current modulation envelope output 0..1
+    pub mod_envelope: f32,
+}
+
+/// Key tracking source value of a note (0 at C4, +1 64 semitones above)
+pub fn key_track(note: u8) -> f32 {
+    ((note as f32 - 60.0) / 64.0).clamp(-1.0, 1.0)
+}
+
+/// Modulation to apply, result of `ModulationMatrix::apply`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModOutputs {
+    /// Pitch delta in semitones
+    pub pitch_semitones: f32,
+    /// Amplitude multiplier (0..2)
+    pub amp_mult: f32,
+    /// Pan delta (-1..1)
+    pub pan: f32,
+    /// Filter cutoff multiplier (0.1..10, 1.0 = no change)
+    pub filter_cutoff_mult: f32,
+    /// Filter resonance multiplier (0.1..5, 1.0 = no change)
+    pub filter_resonance_mult: f32,
+    /// Added to the second oscillator mix
+    pub oscillator_mix: f32,
+    /// LFO rate multipliers
+    pub lfo_rate_mult: [f32; LFO_COUNT],
+    /// Added to the LFO depths
+    pub lfo_depth: [f32; LFO_COUNT],
+    /// Amp and modulation envelope time multipliers
+    pub envelope_time_mult: f32,
+    pub mod_envelope_time_mult: f32,
+    /// Added to the delay and reverb send levels
+    pub delay_send: f32,
+    pub reverb_send: f32,
+}
+
+impl ModOutputs {
+    /// No modulation
+    pub const NONE: Self = Self {
+        pitch_semitones: 0.0,
+        amp_mult: 1.0,
+        pan: 0.0,
+        filter_cutoff_mult: 1.0,
+        filter_resonance_mult: 1.0,
+        oscillator_mix: 0.0,
+        lfo_rate_mult: [1.0; LFO_COUNT],
+        lfo_depth: [0.0; LFO_COUNT],
+        envelope_time_mult: 1.0,
+        mod_envelope_time_mult: 1.0,
+        delay_send: 0.0,
+        reverb_send: 0.0,
+    };
+}
+
+impl Default for ModOutputs {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct ModulationMatrix {
     routings: [ModRouting; MAX_ROUTINGS],
+    /// Value (0..1) of the controller read by each `ModSource::MidiCc` routing
+    controllers: [f32; MAX_ROUTINGS],
 }
 
 impl ModulationMatrix {
     pub fn new_empty() -> Self {
         Self {
             routings: [ModRouting::disabled(); MAX_ROUTINGS],
+            controllers: [0.0; MAX_ROUTINGS],
         }
     }
 
+    /// Set a routing (a `MidiCc` source reads 0 until `set_controller`)
     pub fn set_routing(&mut self, index: usize, routing: ModRouting) {
         if index < MAX_ROUTINGS {
             self.routings[index] = routing;
+            self.controllers[index] = 0.0;
         }
     }
 
@@ -77,6 +303,15 @@ impl ModulationMatrix {
         &self.routings
     }
 
+    /// Update the routings reading a MIDI controller (`value` 0..1)
+    pub fn set_controller(&mut self, controller: u8, value: f32) {
+        for (routing, current) in self.routings.iter().zip(&mut self.controllers) {
+            if routing.source == ModSource::MidiCc(controller) {
+                *current = value.clamp(0.0, 1.0);
+            }
+        }
+    }
+
     /// Quick check if any pitch routing exists (for optional legacy LFO behavior switching)
     pub fn has_pitch_routing(&self) -> bool {
         self.routings
@@ -85,137 +320,319 @@ impl ModulationMatrix {
     }
 
     /// Apply the matrix for a single voice sample
-    ///
-    /// - `velocity`: 0..1
-    /// - `aftertouch`: 0..1 (channel pressure)
-    /// - `lfo_values`: current LFO outputs; for MVP, [lfo0]
-    /// - `envelope_value`: current envelope output 0..1
-    ///
-    /// Returns deltas to apply:
-    /// - pitch in semitones
-    /// - amplitude multiplier (>=0)
-    /// - pan (-1..1)
-    /// - filter cutoff multiplier (multiplicative, 1.0 = no change)
-    pub fn apply(
-        &self,
-        velocity: f32,
-        aftertouch: f32,
-        lfo_values: &[f32; 1],
-        envelope_value: f32,
-    ) -> (f32, f32, f32, f32) {
-        let mut pitch_semitones = 0.0f32;
-        let mut amp_mult = 1.0f32;
-        let mut pan = 0.0f32;
-        let mut filter_cutoff_mult = 1.0f32;
+    pub fn apply(&self, sources: &ModSources) -> ModOutputs {
+        let mut outputs = ModOutputs::NONE;
+        // Octaves of the rate and time destinations
+        let mut lfo_rate_octaves = [0.0f32; LFO_COUNT];
+        let mut envelope_octaves = 0.0f32;
+        let mut mod_envelope_octaves = 0.0f32;
 
         // Evaluate all enabled routings
-        for r in &self.routings {
+        for (r, controller) in self.routings.iter().zip(&self.controllers) {
             if !r.enabled {
                 continue;
             }
 
-            // Compute source value in [-1, 1] (or [0,1] mapped to [-1,1] where relevant)
+            // Compute source value in [-1, 1] (or [0,1] for the unipolar sources)
             let src = match r.source {
-                ModSource::Lfo(0) => lfo_values[0].clamp(-1.0, 1.0),
-                ModSource::Lfo(_) => 0.0, // not used yet
-                ModSource::Velocity => (velocity * 2.0 - 1.0).clamp(-1.0, 1.0),
-                ModSource::Aftertouch => (aftertouch * 2.0 - 1.0).clamp(-1.0, 1.0),
-                ModSource::Envelope => (envelope_value * 2.0 - 1.0).clamp(-1.0, 1.0),
+                ModSource::Lfo(n) => sources.lfos.get(n).map_or(0.0, |v| v.clamp(-1.0, 1.0)),
+                ModSource::Velocity => (sources.velocity * 2.0 - 1.0).clamp(-1.0, 1.0),
+                ModSource::Aftertouch => (sources.aftertouch * 2.0 - 1.0).clamp(-1.0, 1.0),
+                ModSource::Envelope => (sources.envelope * 2.0 - 1.0).clamp(-1.0, 1.0),
+                ModSource::ModWheel => sources.mod_wheel.clamp(0.0, 1.0),
+                ModSource::ModEnvelope => sources.mod_envelope.clamp(0.0, 1.0),
+                ModSource::PitchBend => sources.pitch_bend.clamp(-1.0, 1.0),
+                ModSource::KeyTrack => sources.key.clamp(-1.0, 1.0),
+                ModSource::Random => sources.random.clamp(-1.0, 1.0),
+                ModSource::MidiCc(_) => *controller,
             };
+            let value = r.amount * src;
 
             match r.destination {
-                ModDestination::OscillatorPitch(_idx) => {
-                    // Semitone delta = amount * src
-                    pitch_semitones += r.amount * src;
+                // Semitone delta = amount * src
+                ModDestination::OscillatorPitch(_idx) => outputs.pitch_semitones += value,
+                // Amplitude multiplier = 1.0 + amount * src
+                ModDestination::Amplitude => outputs.amp_mult += value,
+                // Pan position = amount * src
+                ModDestination::Pan => outputs.pan += value,
+                // Filter cutoff multiplier: 1.0 + amount * src
+                // amount typically in [0, 10] for a wide range
+                // Result: multiplier that can scale cutoff from 0.1x to 10x
+                ModDestination::FilterCutoff => outputs.filter_cutoff_mult += value,
+                ModDestination::FilterResonance => outputs.filter_resonance_mult += value,
+                ModDestination::OscillatorMix => outputs.oscillator_mix += value,
+                ModDestination::LfoRate(n) => {
+                    if let Some(octaves) = lfo_rate_octaves.get_mut(n) {
+                        *octaves += value;
+                    }
                 }
-                ModDestination::Amplitude => {
-                    // Amplitude multiplier = 1.0 + amount * src
-                    amp_mult += r.amount * src;
-                }
-                ModDestination::Pan => {
-                    // Pan position = amount * src
-                    pan += r.amount * src;
-                }
-                ModDestination::FilterCutoff => {
-                    // Filter cutoff multiplier: 1.0 + amount * src
-                    // amount typically in [0, 10] for a wide range
-                    // src in [-1, 1]
-                    // Result: multiplier that can scale cutoff from 0.1x to 10x
-                    filter_cutoff_mult += r.amount * src;
+                ModDestination::LfoDepth(n) => {
+                    if let Some(depth) = outputs.lfo_depth.get_mut(n) {
+                        *depth += value;
+                    }
                 }
+                ModDestination::EnvelopeTime => envelope_octaves += value,
+                ModDestination::ModEnvelopeTime => mod_envelope_octaves += value,
+                ModDestination::DelaySend => outputs.delay_send += value,
+                ModDestination::ReverbSend => outputs.reverb_send += value,
             }
         }
 
+        for (mult, octaves) in outputs.lfo_rate_mult.iter_mut().zip(lfo_rate_octaves) {
+            if octaves != 0.0 {
+                *mult = octaves_to_ratio(octaves);
+            }
+        }
+        if envelope_octaves != 0.0 {
+            outputs.envelope_time_mult = octaves_to_ratio(envelope_octaves);
+        }
+        if mod_envelope_octaves != 0.0 {
+            outputs.mod_envelope_time_mult = octaves_to_ratio(mod_envelope_octaves);
+        }
+
         // Clamp outputs to a sane range
-        let amp_mult = amp_mult.clamp(0.0, 2.0);
-        let pan = pan.clamp(-1.0, 1.0);
-        let filter_cutoff_mult = filter_cutoff_mult.clamp(0.1, 10.0);
-        (pitch_semitones, amp_mult, pan, filter_cutoff_mult)
+        outputs.amp_mult = outputs.amp_mult.clamp(0.0, 2.0);
+        outputs.pan = outputs.pan.clamp(-1.0, 1.0);
+        outputs.filter_cutoff_mult = outputs.filter_cutoff_mult.clamp(0.1, 10.0);
+        outputs.filter_resonance_mult = outputs.filter_resonance_mult.clamp(0.1, 5.0);
+        outputs.oscillator_mix = outputs.oscillator_mix.clamp(-1.0, 1.0);
+        outputs.delay_send = outputs.delay_send.clamp(-1.0, 1.0);
+        outputs.reverb_send = outputs.reverb_send.clamp(-1.0, 1.0);
+        outputs
     }
 }
 
+/// Multiplier of a modulation in octaves (limited to +/- 8 octaves)
+#[inline]
+fn octaves_to_ratio(octaves: f32) -> f32 {
+    octaves.clamp(-8.0, 8.0).exp2()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_empty_matrix() {
-        let m = ModulationMatrix::new_empty();
-        let (p, a, pan, cutoff) = m.apply(0.8, 0.2, &[0.0], 0.5);
-        assert_eq!(p, 0.0);
-        assert!((a - 1.0).abs() < 1e-6);
-        assert_eq!(pan, 0.0);
-        assert!((cutoff - 1.0).abs() < 1e-6);
+    /// Sources with no modulation (bipolar sources centered)
+    fn neutral() -> ModSources {
+        ModSources {
+            velocity: 0.5,
+            aftertouch: 0.5,
+            envelope: 0.5,
+            ..ModSources::default()
+        }
     }
 
-    #[test]
-    fn test_lfo_to_pitch() {
+    fn matrix_with(
+        source: ModSource,
+        destination: ModDestination,
+        amount: f32,
+    ) -> ModulationMatrix {
         let mut m = ModulationMatrix::new_empty();
         m.set_routing(
             0,
             ModRouting {
-                source: ModSource::Lfo(0),
-                destination: ModDestination::OscillatorPitch(0),
-                amount: 2.0,
+                source,
+                destination,
+                amount,
                 enabled: true,
             },
         );
+        m
+    }
+
+    #[test]
+    fn test_empty_matrix() {
+        let m = ModulationMatrix::new_empty();
+        let sources = ModSources {
+            velocity: 0.8,
+            aftertouch: 0.2,
+            ..neutral()
+        };
+        assert_eq!(m.apply(&sources), ModOutputs::NONE);
+    }
+
+    #[test]
+    fn test_lfo_to_pitch() {
+        let m = matrix_with(ModSource::Lfo(0), ModDestination::OscillatorPitch(0), 2.0);
         // LFO value +1 → +2 semitones
-        let (p, _a, _pan, _cutoff) = m.apply(0.5, 0.5, &[1.0], 0.5);
-        assert!((p - 2.0).abs() < 1e-6);
+        let sources = ModSources {
+            lfos: [1.0, 0.0, 0.0],
+            ..neutral()
+        };
+        assert!((m.apply(&sources).pitch_semitones - 2.0).abs() < 1e-6);
     }
 
     #[test]
     fn test_velocity_to_amp() {
-        let mut m = ModulationMatrix::new_empty();
+        let m = matrix_with(ModSource::Velocity, ModDestination::Amplitude, 0.5);
+        // velocity 1.0 → src = +1.0 → amp = 1 + 0.5*1 = 1.5
+        let sources = ModSources {
+            velocity: 1.0,
+            ..neutral()
+        };
+        assert!((m.apply(&sources).amp_mult - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_envelope_to_filter_cutoff() {
+        let m = matrix_with(ModSource::Envelope, ModDestination::FilterCutoff, 4.0);
+        // envelope 1.0 → src = +1.0 → cutoff_mult = 1 + 4*1 = 5.0
+        let sources = ModSources {
+            envelope: 1.0,
+            ..neutral()
+        };
+        assert!((m.apply(&sources).filter_cutoff_mult - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mod_wheel_to_pitch() {
+        let m = matrix_with(ModSource::ModWheel, ModDestination::OscillatorPitch(0), 1.0);
+        // Wheel at rest → no modulation, fully up → +1 semitone
+        assert_eq!(m.apply(&neutral()).pitch_semitones, 0.0);
+        let sources = ModSources {
+            mod_wheel: 1.0,
+            ..neutral()
+        };
+        assert!((m.apply(&sources).pitch_semitones - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mod_envelope_to_filter_cutoff() {
+        let m = matrix_with(ModSource::ModEnvelope, ModDestination::FilterCutoff, 3.0);
+        // Envelope closed → no modulation, fully open → cutoff x4
+        let sources = ModSources {
+            envelope: 1.0,
+            ..neutral()
+        };
+        assert!((m.apply(&sources).filter_cutoff_mult - 1.0).abs() < 1e-6);
+        let sources = ModSources {
+            envelope: 0.0,
+            mod_envelope: 1.0,
+            ..neutral()
+        };
+        assert!((m.apply(&sources).filter_cutoff_mult - 4.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_each_lfo_is_a_source() {
+        let m = matrix_with(ModSource::Lfo(2), ModDestination::Pan, 0.5);
+        // Only LFO 3 moves the pan
+        let sources = ModSources {
+            lfos: [1.0, 1.0, 0.0],
+            ..neutral()
+        };
+        assert_eq!(m.apply(&sources).pan, 0.0);
+        let sources = ModSources {
+            lfos: [0.0, 0.0, -1.0],
+            ..neutral()
+        };
+        assert!((m.apply(&sources).pan + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_performance_sources() {
+        // Pitch bend and key tracking are bipolar, centered at rest and on C4
+        let m = matrix_with(ModSource::PitchBend, ModDestination::Pan, 1.0);
+        let sources = ModSources {
+            pitch_bend: -0.5,
+            ..neutral()
+        };
+        assert!((m.apply(&sources).pan + 0.5).abs() < 1e-6);
+
+        let m = matrix_with(
+            ModSource::KeyTrack,
+            ModDestination::OscillatorPitch(0),
+            12.0,
+        );
+        assert_eq!(key_track(60), 0.0);
+        let sources = ModSources {
+            key: key_track(92),
+            ..neutral()
+        };
+        assert!((m.apply(&sources).pitch_semitones - 6.0).abs() < 1e-6);
+
+        let m = matrix_with(ModSource::Random, ModDestination::OscillatorMix, 0.5);
+        let sources = ModSources {
+            random: 1.0,
+            ..neutral()
+        };
+        assert!((m.apply(&sources).oscillator_mix - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_midi_cc_source() {
+        let mut m = matrix_with(ModSource::MidiCc(74), ModDestination::FilterResonance, 2.0);
+        assert_eq!(m.apply(&neutral()).filter_resonance_mult, 1.0);
+
+        // Only the routed controller counts
+        m.set_controller(71, 1.0);
+        assert_eq!(m.apply(&neutral()).filter_resonance_mult, 1.0);
+        m.set_controller(74, 0.5);
+        assert!((m.apply(&neutral()).filter_resonance_mult - 2.0).abs() < 1e-6);
+
+        // Replacing the routing forgets the value
         m.set_routing(
             0,
             ModRouting {
-                source: ModSource::Velocity,
-                destination: ModDestination::Amplitude,
-                amount: 0.5,
+                source: ModSource::MidiCc(74),
+                destination: ModDestination::FilterResonance,
+                amount: 2.0,
                 enabled: true,
             },
         );
-        // velocity 1.0 → src = +1.0 → amp = 1 + 0.5*1 = 1.5
-        let (_p, a, _pan, _cutoff) = m.apply(1.0, 0.0, &[0.0], 0.5);
-        assert!((a - 1.5).abs() < 1e-6);
+        assert_eq!(m.apply(&neutral()).filter_resonance_mult, 1.0);
     }
 
     #[test]
-    fn test_envelope_to_filter_cutoff() {
-        let mut m = ModulationMatrix::new_empty();
+    fn test_rate_and_time_destinations_in_octaves() {
+        let mut m = matrix_with(ModSource::ModWheel, ModDestination::LfoRate(1), 1.0);
         m.set_routing(
-            0,
+            1,
             ModRouting {
-                source: ModSource::Envelope,
-                destination: ModDestination::FilterCutoff,
-                amount: 4.0,
+                source: ModSource::ModWheel,
+                destination: ModDestination::EnvelopeTime,
+                amount: -2.0,
                 enabled: true,
             },
         );
-        // envelope 1.0 → src = +1.0 → cutoff_mult = 1 + 4*1 = 5.0
-        let (_p, _a, _pan, cutoff) = m.apply(0.5, 0.5, &[0.0], 1.0);
-        assert!((cutoff - 5.0).abs() < 1e-6);
+        m.set_routing(
+            2,
+            ModRouting {
+                source: ModSource::ModWheel,
+                destination: ModDestination::LfoDepth(0),
+                amount: 0.25,
+                enabled: true,
+            },
+        );
+        let outputs = m.apply(&ModSources {
+            mod_wheel: 1.0,
+            ..neutral()
+        });
+        assert_eq!(outputs.lfo_rate_mult, [1.0, 2.0, 1.0]);
+        assert_eq!(outputs.lfo_depth, [0.25, 0.0, 0.0]);
+        assert!((outputs.envelope_time_mult - 0.25).abs() < 1e-6);
+        assert_eq!(outputs.mod_envelope_time_mult, 1.0);
+    }
+
+    #[test]
+    fn test_sixteen_routings_add_up() {
+        let mut m = ModulationMatrix::new_empty();
+        for index in 0..MAX_ROUTINGS {
+            m.set_routing(
+                index,
+                ModRouting {
+                    source: ModSource::ModWheel,
+                    destination: ModDestination::DelaySend,
+                    amount: 0.05,
+                    enabled: true,
+                },
+            );
+        }
+        let outputs = m.apply(&ModSources {
+            mod_wheel: 1.0,
+            ..neutral()
+        });
+        assert!((outputs.delay_send - 0.8).abs() < 1e-5);
+        assert_eq!(outputs.reverb_send, 0.0);
     }
 }
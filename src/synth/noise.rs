@@ -0,0 +1,146 @@
+// Noise generator - White and pink noise for the synth voices
+//
+// Notes:
+// - RT-safe: a xorshift generator and a few filter states, no allocation.
+// - Pink noise filters the white noise with Paul Kellet's "economy" filter
+//   (-3 dB/octave within ~0.5 dB above 40 Hz).
+
+/// Spectrum of the noise source
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum NoiseType {
+    /// Flat spectrum
+    #[default]
+    White,
+    /// -3 dB/octave, softer and darker
+    Pink,
+}
+
+/// Noise source of the synth voices, added to the oscillators
+///
+/// The noise goes through the voice filter and envelope like the oscillators.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NoiseParams {
+    pub noise_type: NoiseType,
+    /// Level of the noise added to the oscillators (0.0 = off, not computed)
+    pub level: f32,
+}
+
+impl Default for NoiseParams {
+    fn default() -> Self {
+        Self {
+            noise_type: NoiseType::White,
+            level: 0.0,
+        }
+    }
+}
+
+impl NoiseParams {
+    pub fn new(noise_type: NoiseType, level: f32) -> Self {
+        Self {
+            noise_type,
+            level: level.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// White/pink noise generator (xorshift32)
+pub struct NoiseGenerator {
+    noise_type: NoiseType,
+    state: u32,
+    /// Pink filter states (Paul Kellet)
+    pink: [f32; 3],
+}
+
+impl NoiseGenerator {
+    pub fn new(noise_type: NoiseType, seed: u32) -> Self {
+        let mut generator = Self {
+            noise_type,
+            state: 1,
+            pink: [0.0; 3],
+        };
+        generator.reseed(seed);
+        generator
+    }
+
+    pub fn set_noise_type(&mut self, noise_type: NoiseType) {
+        self.noise_type = noise_type;
+    }
+
+    /// Restart the sequence from a seed (voices use distinct seeds so their
+    /// noise is uncorrelated)
+    pub fn reseed(&mut self, seed: u32) {
+        // Spread close seeds apart; xorshift needs a non-zero state
+        self.state = seed.wrapping_mul(0x9E37_79B9) | 1;
+        self.pink = [0.0; 3];
+    }
+
+    /// White noise sample in [-1.0, 1.0)
+    #[inline]
+    pub fn white(&mut self) -> f32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        (x >> 8) as f32 / (1u32 << 23) as f32 - 1.0
+    }
+
+    #[inline]
+    pub fn next_sample(&mut self) -> f32 {
+        let white = self.white();
+        match self.noise_type {
+            NoiseType::White => white,
+            NoiseType::Pink => {
+                let [b0, b1, b2] = &mut self.pink;
+                *b0 = 0.99765 * *b0 + white * 0.0990460;
+                *b1 = 0.96300 * *b1 + white * 0.2965164;
+                *b2 = 0.57000 * *b2 + white * 1.0526913;
+                // Roughly the level of the white noise
+                (*b0 + *b1 + *b2 + white * 0.1848) * 0.25
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mean square of the difference between successive samples, relative to
+    /// the mean square of the samples (high for white, low for pink noise)
+    fn roughness(generator: &mut NoiseGenerator) -> f32 {
+        let samples: Vec<f32> = (0..20000).map(|_| generator.next_sample()).collect();
+        let power: f32 = samples.iter().map(|x| x * x).sum();
+        let difference: f32 = samples.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum();
+        difference / power
+    }
+
+    #[test]
+    fn test_white_noise_range_and_mean() {
+        let mut generator = NoiseGenerator::new(NoiseType::White, 7);
+        let samples: Vec<f32> = (0..20000).map(|_| generator.next_sample()).collect();
+        assert!(samples.iter().all(|x| (-1.0..1.0).contains(x)));
+        let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+        assert!(mean.abs() < 0.02, "mean {}", mean);
+    }
+
+    #[test]
+    fn test_pink_noise_is_darker() {
+        let white = roughness(&mut NoiseGenerator::new(NoiseType::White, 7));
+        let pink = roughness(&mut NoiseGenerator::new(NoiseType::Pink, 7));
+        // Uncorrelated samples: the difference has twice the power
+        assert!((white - 2.0).abs() < 0.1, "white {}", white);
+        assert!(pink < white / 2.0, "pink {} white {}", pink, white);
+    }
+
+    #[test]
+    fn test_seeds_give_distinct_sequences() {
+        let mut a = NoiseGenerator::new(NoiseType::White, 1);
+        let mut b = NoiseGenerator::new(NoiseType::White, 2);
+        assert!((0..16).any(|_| a.next_sample() != b.next_sample()));
+
+        a.reseed(2);
+        let mut b = NoiseGenerator::new(NoiseType::White, 2);
+        assert!((0..16).all(|_| a.next_sample() == b.next_sample()));
+    }
+}
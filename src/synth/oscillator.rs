@@ -3,9 +3,15 @@
 // Notes:
 // - The audio callback is RT-critical. This module must avoid allocations
 //   and any blocking operations. The oscillator is allocation-free.
-// - Saw and Square are bandlimited using PolyBLEP to reduce aliasing at
-//   higher frequencies while keeping CPU overhead minimal.
-
+// - Saw and Square are bandlimited using PolyBLEP (steps) and Triangle using
+//   PolyBLAMP (slope changes) to reduce aliasing at higher frequencies while
+//   keeping CPU overhead minimal. The corrections are evaluated at the phase
+//   of the sample being generated, centered on each discontinuity.
+// - Synth voices can use mipmapped wavetables instead (`AntiAliasing`), see
+//   `synth::wavetable`.
+
+use super::wavetable::WavetableOscillator;
+use crate::audio::simd::simd_fill_phases;
 use std::f32::consts::PI;
 
 pub trait Oscillator {
@@ -22,6 +28,222 @@ pub enum WaveformType {
     Triangle,
 }
 
+/// Largest transposition of the second oscillator (semitones, either way)
+pub const MAX_OSC2_COARSE: i32 = 24;
+
+/// Largest fine detune of the second oscillator (cents, either way)
+pub const MAX_OSC2_FINE: f32 = 100.0;
+
+/// Second oscillator of the synth voices, layered over the first one
+///
+/// It follows the pitch of the note, transposed by `coarse` semitones and
+/// detuned by `fine` cents. `mix` crossfades between the oscillators: 0.0
+/// plays the first one only (the second is not computed), 1.0 the second only.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Oscillator2Params {
+    pub waveform: WaveformType,
+    /// Transposition in semitones (-24 to 24)
+    pub coarse: i32,
+    /// Detune in cents (-100 to 100)
+    pub fine: f32,
+    /// Level of the second oscillator against the first (0.0 - 1.0)
+    pub mix: f32,
+}
+
+impl Default for Oscillator2Params {
+    fn default() -> Self {
+        Self {
+            waveform: WaveformType::Saw,
+            coarse: 0,
+            fine: 0.0,
+            mix: 0.0,
+        }
+    }
+}
+
+impl Oscillator2Params {
+    pub fn new(waveform: WaveformType, coarse: i32, fine: f32, mix: f32) -> Self {
+        Self {
+            waveform,
+            coarse: coarse.clamp(-MAX_OSC2_COARSE, MAX_OSC2_COARSE),
+            fine: fine.clamp(-MAX_OSC2_FINE, MAX_OSC2_FINE),
+            mix: mix.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Frequency of the second oscillator relative to the note
+    pub fn frequency_ratio(&self) -> f32 {
+        let semitones = self.coarse as f32 + self.fine / 100.0;
+        2_f32.powf(semitones / 12.0)
+    }
+}
+
+/// Largest FM index: peak deviation of the first oscillator, in multiples of
+/// the frequency of the second one
+pub const MAX_FM_INDEX: f32 = 5.0;
+
+/// Cross-modulation of the first oscillator by the second one
+///
+/// `fm` modulates the frequency of the first oscillator with the output of the
+/// second (linear, through zero). `ring` crossfades the oscillator mix with the
+/// product of both oscillators. The second oscillator runs whenever one of them
+/// is on, even when it is not mixed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CrossModParams {
+    /// FM amount (0.0 - 1.0, 1.0 = `MAX_FM_INDEX`)
+    pub fm: f32,
+    /// Ring modulation amount (0.0 - 1.0)
+    pub ring: f32,
+}
+
+impl CrossModParams {
+    pub fn new(fm: f32, ring: f32) -> Self {
+        Self {
+            fm: fm.clamp(0.0, 1.0),
+            ring: ring.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Whether the second oscillator modulates the first one
+    pub fn is_active(&self) -> bool {
+        self.fm > 0.0 || self.ring > 0.0
+    }
+}
+
+/// Largest number of unison voices per note
+pub const MAX_UNISON_VOICES: usize = 8;
+
+/// Largest unison detune (cents between the outer voices and the note)
+pub const MAX_UNISON_DETUNE: f32 = 100.0;
+
+/// Unison: each note stacks several detuned synth voices
+///
+/// The voices are spread evenly from `-detune` to `+detune` cents around the
+/// note and panned from left to right by `spread`. One voice is a plain note.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UnisonParams {
+    /// Voices per note (1 to 8)
+    pub voices: usize,
+    /// Detune of the outer voices in cents (0 to 100)
+    pub detune: f32,
+    /// Stereo width of the stacked voices (0.0 = centered, 1.0 = hard left/right)
+    pub spread: f32,
+}
+
+impl Default for UnisonParams {
+    fn default() -> Self {
+        Self {
+            voices: 1,
+            detune: 0.0,
+            spread: 0.0,
+        }
+    }
+}
+
+impl UnisonParams {
+    pub fn new(voices: usize, detune: f32, spread: f32) -> Self {
+        Self {
+            voices: voices.clamp(1, MAX_UNISON_VOICES),
+            detune: detune.clamp(0.0, MAX_UNISON_DETUNE),
+            spread: spread.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Position of a voice in the stack, from -1.0 (first) to 1.0 (last)
+    fn position(&self, index: usize) -> f32 {
+        if self.voices <= 1 {
+            0.0
+        } else {
+            (index as f32 / (self.voices - 1) as f32) * 2.0 - 1.0
+        }
+    }
+
+    /// Frequency of a unison voice relative to the note
+    pub fn frequency_ratio(&self, index: usize) -> f32 {
+        2_f32.powf(self.position(index) * self.detune / 1200.0)
+    }
+
+    /// Pan of a unison voice (-1.0 = left, 1.0 = right)
+    pub fn pan(&self, index: usize) -> f32 {
+        self.position(index) * self.spread
+    }
+
+    /// Start phase of a unison voice, so the stack does not start in phase
+    pub fn start_phase(&self, index: usize) -> f32 {
+        index as f32 / self.voices.max(1) as f32
+    }
+}
+
+/// How the oscillators of the synth voices are band-limited
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum AntiAliasing {
+    /// PolyBLEP/PolyBLAMP corrections around the discontinuities (no tables)
+    #[default]
+    PolyBlep,
+    /// Mipmapped wavetables (one table per octave, no harmonic above Nyquist)
+    Wavetable,
+}
+
+/// Band-limited oscillator of the synth voices, with the method picked at runtime
+pub enum SynthOscillator {
+    PolyBlep(SimpleOscillator),
+    Wavetable(WavetableOscillator),
+}
+
+impl SynthOscillator {
+    pub fn new(waveform: WaveformType, anti_aliasing: AntiAliasing, sample_rate: f32) -> Self {
+        match anti_aliasing {
+            AntiAliasing::PolyBlep => {
+                SynthOscillator::PolyBlep(SimpleOscillator::new(waveform, sample_rate))
+            }
+            AntiAliasing::Wavetable => {
+                SynthOscillator::Wavetable(WavetableOscillator::new(waveform, sample_rate))
+            }
+        }
+    }
+
+    /// Jump to a phase of the cycle (0.0 - 1.0)
+    pub fn set_phase(&mut self, phase: f32) {
+        match self {
+            SynthOscillator::PolyBlep(osc) => osc.set_phase(phase),
+            SynthOscillator::Wavetable(osc) => osc.set_phase(phase),
+        }
+    }
+
+    /// Fill `out` with the next samples at the current frequency
+    pub fn process_block(&mut self, out: &mut [f32]) {
+        match self {
+            SynthOscillator::PolyBlep(osc) => osc.process_block(out),
+            SynthOscillator::Wavetable(osc) => osc.process_block(out),
+        }
+    }
+}
+
+impl Oscillator for SynthOscillator {
+    #[inline]
+    fn next_sample(&mut self) -> f32 {
+        match self {
+            SynthOscillator::PolyBlep(osc) => osc.next_sample(),
+            SynthOscillator::Wavetable(osc) => osc.next_sample(),
+        }
+    }
+
+    #[inline]
+    fn set_frequency(&mut self, freq: f32) {
+        match self {
+            SynthOscillator::PolyBlep(osc) => osc.set_frequency(freq),
+            SynthOscillator::Wavetable(osc) => osc.set_frequency(freq),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            SynthOscillator::PolyBlep(osc) => osc.reset(),
+            SynthOscillator::Wavetable(osc) => osc.reset(),
+        }
+    }
+}
+
 pub struct SimpleOscillator {
     waveform: WaveformType,
     phase: f32,
@@ -42,49 +264,17 @@ impl SimpleOscillator {
 
 impl Oscillator for SimpleOscillator {
     fn next_sample(&mut self) -> f32 {
-        // Compute raw sample based on waveform
-        let mut sample = match self.waveform {
-            WaveformType::Sine => (self.phase * 2.0 * PI).sin(),
-            WaveformType::Square => {
-                // 50% duty square wave
-                if self.phase < 0.5 { 1.0 } else { -1.0 }
-            }
-            WaveformType::Saw => (self.phase * 2.0) - 1.0,
-            WaveformType::Triangle => {
-                // Simple piecewise triangle in [-1, 1]
-                if self.phase < 0.5 {
-                    (self.phase * 4.0) - 1.0
-                } else {
-                    3.0 - (self.phase * 4.0)
-                }
-            }
-        };
+        let sample = self.sample_at(self.phase);
 
         self.phase += self.phase_increment;
         if self.phase >= 1.0 {
             self.phase -= 1.0;
+        } else if self.phase < 0.0 {
+            // Negative frequency (through-zero FM)
+            self.phase += 1.0;
         }
 
-        // Apply PolyBLEP correction for discontinuous waveforms to reduce aliasing.
-        // This must be done after incrementing phase to keep behavior consistent
-        // across blocks, using the phase value corresponding to this sample.
-        match self.waveform {
-            WaveformType::Saw => {
-                sample -= self.poly_blep(self.phase);
-                sample
-            }
-            WaveformType::Square => {
-                // Square has two discontinuities per period: at phase 0 and 0.5
-                sample += self.poly_blep(self.phase);
-                let mut p2 = self.phase + 0.5;
-                if p2 >= 1.0 {
-                    p2 -= 1.0;
-                }
-                sample -= self.poly_blep(p2);
-                sample
-            }
-            _ => sample,
-        }
+        sample
     }
 
     fn set_frequency(&mut self, freq: f32) {
@@ -97,31 +287,100 @@ impl Oscillator for SimpleOscillator {
 }
 
 impl SimpleOscillator {
-    /// PolyBLEP (Polynomial Band-Limited Step) correction
+    /// Jump to a phase of the cycle (0.0 - 1.0)
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+
+    /// Fill `out` with the next samples at the current frequency
     ///
-    /// Suppresses aliasing at discontinuities for saw/square by adding a small
-    /// polynomial correction around the step. `self.phase_increment` is used as
-    /// the normalized time step `dt` (in cycles per sample).
+    /// Equivalent to calling `next_sample()` for each sample, but the phase
+    /// accumulation runs 8 samples at a time (`audio::simd`).
+    pub fn process_block(&mut self, out: &mut [f32]) {
+        self.phase = simd_fill_phases(self.phase, self.phase_increment, out);
+        for value in out.iter_mut() {
+            *value = self.sample_at(*value);
+        }
+    }
+
+    /// Output sample for a given phase (band-limited)
     #[inline]
-    fn poly_blep(&self, t: f32) -> f32 {
+    fn sample_at(&self, phase: f32) -> f32 {
         let dt = self.phase_increment;
-        // Guard against extreme dt values; when dt is very small, the regions
-        // below become negligible and we return 0 quickly.
-        if dt <= 0.0 || dt >= 1.0 {
-            return 0.0;
+        match self.waveform {
+            WaveformType::Sine => (phase * 2.0 * PI).sin(),
+            WaveformType::Square => {
+                // 50% duty square wave: rising step at phase 0, falling step at 0.5
+                let naive = if phase < 0.5 { 1.0 } else { -1.0 };
+                naive + poly_blep(phase, dt) - poly_blep(wrap_phase(phase + 0.5), dt)
+            }
+            WaveformType::Saw => {
+                // Ramp from -1 to 1, falling step at phase 0
+                let naive = (phase * 2.0) - 1.0;
+                naive - poly_blep(phase, dt)
+            }
+            WaveformType::Triangle => {
+                // Piecewise triangle in [-1, 1]: slope changes by 8 (per cycle)
+                // at phase 0 (minimum) and 0.5 (maximum)
+                let naive = if phase < 0.5 {
+                    (phase * 4.0) - 1.0
+                } else {
+                    3.0 - (phase * 4.0)
+                };
+                naive + 4.0 * dt * (poly_blamp(phase, dt) - poly_blamp(wrap_phase(phase + 0.5), dt))
+            }
         }
+    }
+}
 
-        if t < dt {
-            // 0 <= t < dt
-            let u = t / dt;
-            // u + u - u*u - 1 = 2u - u^2 - 1
-            return u + u - u * u - 1.0;
-        } else if t > 1.0 - dt {
-            // 1 - dt < t < 1
-            let u = (t - 1.0) / dt;
-            // u^2 + u + u + 1 = u^2 + 2u + 1
-            return u * u + u + u + 1.0;
-        }
+/// Wrap a phase in [0, 2) back into [0, 1)
+#[inline]
+fn wrap_phase(phase: f32) -> f32 {
+    if phase >= 1.0 { phase - 1.0 } else { phase }
+}
+
+/// PolyBLEP (Polynomial Band-Limited Step) residual
+///
+/// Correction for a step of height 2 at phase 0, evaluated at phase `t` for a
+/// phase increment `dt` (cycles per sample): it smooths the sample just before
+/// and the sample just after the discontinuity.
+#[inline]
+fn poly_blep(t: f32, dt: f32) -> f32 {
+    // No correction for a stopped oscillator or above Nyquist
+    if dt <= 0.0 || dt >= 0.5 {
+        return 0.0;
+    }
+
+    if t < dt {
+        // Just after the step
+        let u = t / dt;
+        u + u - u * u - 1.0
+    } else if t > 1.0 - dt {
+        // Just before the step
+        let u = (t - 1.0) / dt;
+        u * u + u + u + 1.0
+    } else {
+        0.0
+    }
+}
+
+/// PolyBLAMP (Polynomial Band-Limited rAMP) residual
+///
+/// Integrated PolyBLEP: correction for a slope change of 2 per sample at phase 0.
+/// Scale it by half the slope change (per sample) of the waveform.
+#[inline]
+fn poly_blamp(t: f32, dt: f32) -> f32 {
+    if dt <= 0.0 || dt >= 0.5 {
+        return 0.0;
+    }
+
+    if t < dt {
+        let x = t / dt - 1.0;
+        -x * x * x / 3.0
+    } else if t > 1.0 - dt {
+        let x = (t - 1.0) / dt + 1.0;
+        x * x * x / 3.0
+    } else {
         0.0
     }
 }
@@ -273,4 +532,122 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_process_block_matches_next_sample() {
+        for waveform in [WaveformType::Sine, WaveformType::Saw, WaveformType::Square] {
+            let mut scalar = SimpleOscillator::new(waveform, SAMPLE_RATE);
+            let mut block = SimpleOscillator::new(waveform, SAMPLE_RATE);
+            scalar.set_frequency(1234.5);
+            block.set_frequency(1234.5);
+
+            let mut out = [0.0; 100];
+            for _ in 0..3 {
+                block.process_block(&mut out);
+                for value in out {
+                    let expected = scalar.next_sample();
+                    assert!(
+                        (value - expected).abs() < 0.01,
+                        "{:?}: {} != {}",
+                        waveform,
+                        value,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unison_spreads_voices_around_the_note() {
+        let unison = UnisonParams::new(3, 50.0, 0.5);
+        assert!((unison.frequency_ratio(0) - 2_f32.powf(-50.0 / 1200.0)).abs() < 1e-6);
+        assert_eq!(unison.frequency_ratio(1), 1.0);
+        assert!((unison.frequency_ratio(2) - 2_f32.powf(50.0 / 1200.0)).abs() < 1e-6);
+        assert_eq!(
+            [unison.pan(0), unison.pan(1), unison.pan(2)],
+            [-0.5, 0.0, 0.5]
+        );
+
+        let single = UnisonParams::default();
+        assert_eq!(single.frequency_ratio(0), 1.0);
+        assert_eq!(single.pan(0), 0.0);
+        assert_eq!(
+            UnisonParams::new(20, 500.0, 2.0),
+            UnisonParams::new(8, 100.0, 1.0)
+        );
+    }
+
+    /// Energy outside the harmonics of a bin-aligned tone, relative to the harmonics (dB)
+    fn aliasing_db(samples: &[f32], fundamental_bin: usize) -> f32 {
+        let n = samples.len();
+        let (mut harmonics, mut aliases) = (0.0f64, 0.0f64);
+        for bin in 1..n / 2 {
+            let (mut re, mut im) = (0.0f64, 0.0f64);
+            for (i, &x) in samples.iter().enumerate() {
+                let angle = -2.0 * std::f64::consts::PI * (bin * i % n) as f64 / n as f64;
+                re += x as f64 * angle.cos();
+                im += x as f64 * angle.sin();
+            }
+            let energy = re * re + im * im;
+            if bin % fundamental_bin == 0 {
+                harmonics += energy;
+            } else {
+                aliases += energy;
+            }
+        }
+        (10.0 * (aliases / harmonics).log10()) as f32
+    }
+
+    #[test]
+    fn test_high_notes_are_band_limited() {
+        // ~2.8 kHz at 44.1 kHz, exactly 65 cycles in 1024 samples
+        const N: usize = 1024;
+        const BIN: usize = 65;
+        let frequency = SAMPLE_RATE * BIN as f32 / N as f32;
+
+        // Naive saw aliases at about -10 dB at this frequency
+        for (waveform, max_db) in [
+            (WaveformType::Saw, -20.0),
+            (WaveformType::Square, -20.0),
+            (WaveformType::Triangle, -45.0),
+        ] {
+            let mut osc = SimpleOscillator::new(waveform, SAMPLE_RATE);
+            osc.set_frequency(frequency);
+            let samples: Vec<f32> = (0..N).map(|_| osc.next_sample()).collect();
+
+            let aliasing = aliasing_db(&samples, BIN);
+            assert!(
+                aliasing < max_db,
+                "{:?} aliasing too high: {:.1} dB",
+                waveform,
+                aliasing
+            );
+        }
+    }
+
+    #[test]
+    fn test_wavetables_do_not_alias() {
+        const N: usize = 1024;
+        const BIN: usize = 65;
+        let frequency = SAMPLE_RATE * BIN as f32 / N as f32;
+
+        for waveform in [
+            WaveformType::Saw,
+            WaveformType::Square,
+            WaveformType::Triangle,
+        ] {
+            let mut osc = SynthOscillator::new(waveform, AntiAliasing::Wavetable, SAMPLE_RATE);
+            osc.set_frequency(frequency);
+            let samples: Vec<f32> = (0..N).map(|_| osc.next_sample()).collect();
+
+            let aliasing = aliasing_db(&samples, BIN);
+            assert!(
+                aliasing < -60.0,
+                "{:?} wavetable aliasing too high: {:.1} dB",
+                waveform,
+                aliasing
+            );
+        }
+    }
 }
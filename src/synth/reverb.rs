@@ -15,7 +15,8 @@
 // - Fixed maximum room size (set at creation)
 // - Lock-free processing
 
-use crate::audio::dsp_utils::OnePoleSmoother;
+use crate::audio::precision::{InternalSample, from_internal, to_internal};
+use crate::audio::smoothing::{ParamKind, SmoothedParam};
 
 /// Reverb parameters
 #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -55,12 +56,12 @@ impl ReverbParams {
 
 /// Comb filter with damping (for reverb)
 struct CombFilter {
-    buffer: Vec<f32>,
+    buffer: Vec<InternalSample>,
     buffer_size: usize,
     buffer_index: usize,
-    feedback: f32,
-    damping: f32,
-    filter_state: f32, // One-pole low-pass filter state
+    feedback: InternalSample,
+    damping: InternalSample,
+    filter_state: InternalSample, // One-pole low-pass filter state
 }
 
 impl CombFilter {
@@ -76,7 +77,7 @@ impl CombFilter {
     }
 
     #[inline]
-    fn process(&mut self, input: f32) -> f32 {
+    fn process(&mut self, input: InternalSample) -> InternalSample {
         // Read from circular buffer
         let output = self.buffer[self.buffer_index];
 
@@ -99,17 +100,17 @@ impl CombFilter {
     }
 
     fn set_feedback(&mut self, feedback: f32) {
-        self.feedback = feedback;
+        self.feedback = to_internal(feedback);
     }
 
     fn set_damping(&mut self, damping: f32) {
-        self.damping = damping;
+        self.damping = to_internal(damping);
     }
 }
 
 /// Allpass filter (for reverb)
 struct AllpassFilter {
-    buffer: Vec<f32>,
+    buffer: Vec<InternalSample>,
     buffer_size: usize,
     buffer_index: usize,
 }
@@ -124,7 +125,7 @@ impl AllpassFilter {
     }
 
     #[inline]
-    fn process(&mut self, input: f32) -> f32 {
+    fn process(&mut self, input: InternalSample) -> InternalSample {
         let bufout = self.buffer[self.buffer_index];
 
         // Allpass formula: output = -input + bufout
@@ -171,8 +172,8 @@ pub struct Reverb {
     /// Allpass filters (series)
     allpass_filters: Vec<AllpassFilter>,
 
-    /// Smoothers to avoid clicks
-    mix_smoother: OnePoleSmoother,
+    /// Smoothed mix to avoid clicks
+    mix: SmoothedParam,
 
     /// Scaling factor for output
     gain: f32,
@@ -213,15 +214,15 @@ impl Reverb {
             allpass_filters.push(AllpassFilter::new(size));
         }
 
-        // Initialize smoothers
-        let mix_smoother = OnePoleSmoother::new(params.mix, 10.0, sample_rate);
+        // Initialize smoothed parameters
+        let mix = SmoothedParam::new(ParamKind::EffectMix, params.mix, sample_rate);
 
         let mut reverb = Self {
             params,
             _sample_rate: sample_rate,
             comb_filters,
             allpass_filters,
-            mix_smoother,
+            mix,
             gain: 1.0,
         };
 
@@ -234,6 +235,7 @@ impl Reverb {
     /// Set reverb parameters
     pub fn set_params(&mut self, params: ReverbParams) {
         self.params = params;
+        self.mix.set_target(params.mix);
         self.update();
     }
 
@@ -282,16 +284,17 @@ impl Reverb {
         }
 
         // Apply smoothing to mix parameter
-        let mix = self.mix_smoother.process(self.params.mix);
+        let mix = self.mix.next_value();
 
         // Accumulate output from parallel comb filters
+        let comb_input = to_internal(input);
         let mut comb_out = 0.0;
         for comb in &mut self.comb_filters {
-            comb_out += comb.process(input);
+            comb_out += comb.process(comb_input);
         }
 
         // Apply gain
-        let mut output = comb_out * self.gain;
+        let mut output = comb_out * to_internal(self.gain);
 
         // Pass through series allpass filters
         for allpass in &mut self.allpass_filters {
@@ -300,7 +303,7 @@ impl Reverb {
 
         // Mix dry and wet signals
         let dry = input * (1.0 - mix);
-        let wet = output * mix;
+        let wet = from_internal(output) * mix;
 
         dry + wet
     }
@@ -1,16 +1,29 @@
 use crate::sampler::engine::SamplerVoice;
 use crate::sampler::loader::Sample;
+use crate::sequencer::timeline::Tempo;
 use std::sync::Arc;
 
 use super::effect::EffectChain;
 use super::envelope::{AdsrEnvelope, AdsrParams};
-use super::filter::{FilterParams, StateVariableFilter};
-use super::lfo::{Lfo, LfoParams};
-use super::modulation::ModulationMatrix;
-use super::oscillator::{Oscillator, SimpleOscillator, WaveformType};
+use super::filter::{
+    FILTER_LANES, FilterModParams, FilterParams, StateVariableFilter, StateVariableFilterX4,
+};
+use super::lfo::{LFO_COUNT, Lfo, LfoParams};
+use super::modulation::{ModOutputs, ModSources, ModulationMatrix, key_track};
+use super::noise::{NoiseGenerator, NoiseParams};
+use super::oscillator::{
+    AntiAliasing, CrossModParams, MAX_FM_INDEX, Oscillator, Oscillator2Params, SynthOscillator,
+    WaveformType,
+};
 use super::portamento::{PortamentoGlide, PortamentoParams};
 use std::f32::consts::FRAC_PI_2;
 
+/// Samples rendered per modulation batch in `render_block` (stack scratch size)
+pub const VOICE_BLOCK_SIZE: usize = 64;
+
+// Synth voices carry their block buffer inline: voices are swapped on the audio
+// thread (voice mode changes), where boxing it would allocate
+#[allow(clippy::large_enum_variant)]
 pub enum Voice {
     Synth(SynthVoice),
     Sampler(SamplerVoice),
@@ -21,8 +34,9 @@ impl Voice {
         Voice::Synth(SynthVoice::new(sample_rate))
     }
 
-    pub fn new_sampler(sample: Arc<Sample>, sample_rate: f32) -> Self {
-        Voice::Sampler(SamplerVoice::new(sample, sample_rate))
+    /// Sampler voice transposing the sample from `root_note`
+    pub fn new_sampler(sample: Arc<Sample>, root_note: u8, sample_rate: f32) -> Self {
+        Voice::Sampler(SamplerVoice::new(sample, sample_rate).with_root_note(root_note))
     }
 
     pub fn note_on(&mut self, note: u8, velocity: u8, age: u64) {
@@ -53,6 +67,40 @@ impl Voice {
         }
     }
 
+    /// Change the output sample rate (output device switched)
+    ///
+    /// Synth voices keep their parameters; sampler voices are stopped, they
+    /// are created again at the current rate on their next note.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        match self {
+            Voice::Synth(v) => v.set_sample_rate(sample_rate),
+            Voice::Sampler(v) => v.force_stop(),
+        }
+    }
+
+    /// Fade the voice out within `duration` seconds (graceful `force_stop`)
+    pub fn fade_out(&mut self, duration: f32) {
+        match self {
+            Voice::Synth(v) => v.fade_out(duration),
+            Voice::Sampler(v) => v.fade_out(duration),
+        }
+    }
+
+    pub fn is_fading_out(&self) -> bool {
+        match self {
+            Voice::Synth(v) => v.is_fading_out(),
+            Voice::Sampler(v) => v.is_fading_out(),
+        }
+    }
+
+    /// Current loudness estimate (envelope x velocity), used to pick voices to cut
+    pub fn level(&self) -> f32 {
+        match self {
+            Voice::Synth(v) => v.level(),
+            Voice::Sampler(v) => v.level(),
+        }
+    }
+
     pub fn get_note(&self) -> u8 {
         match self {
             Voice::Synth(v) => v.get_note(),
@@ -88,6 +136,30 @@ impl Voice {
         }
     }
 
+    /// Bend the pitch: wheel position (-1.0 - 1.0, 0.0 = no bend) and range
+    /// in semitones
+    pub fn set_pitch_bend(&mut self, position: f32, range: f32) {
+        match self {
+            Voice::Synth(v) => v.set_pitch_bend(position, range),
+            Voice::Sampler(v) => v.set_pitch_bend(position * range),
+        }
+    }
+
+    /// Set the modulation wheel position (0.0 - 1.0)
+    pub fn set_mod_wheel(&mut self, value: f32) {
+        match self {
+            Voice::Synth(v) => v.set_mod_wheel(value),
+            Voice::Sampler(v) => v.set_mod_wheel(value),
+        }
+    }
+
+    /// Place a synth voice in a unison stack (see `SynthVoice::set_unison_voice`)
+    pub fn set_unison_voice(&mut self, frequency_ratio: f32, pan: f32, start_phase: f32) {
+        if let Voice::Synth(v) = self {
+            v.set_unison_voice(frequency_ratio, pan, start_phase);
+        }
+    }
+
     pub fn next_sample_with_matrix(&mut self, matrix: &ModulationMatrix) -> (f32, f32) {
         match self {
             Voice::Synth(v) => v.next_sample_with_matrix(matrix),
@@ -95,6 +167,26 @@ impl Voice {
         }
     }
 
+    /// Render a block of samples into `left`/`right` (overwritten)
+    /// Delay and reverb send offsets from the mod matrix (see `SynthVoice::send_modulation`)
+    pub fn send_modulation(&self) -> (f32, f32) {
+        match self {
+            Voice::Synth(v) => v.send_modulation(),
+            Voice::Sampler(_) => (0.0, 0.0),
+        }
+    }
+
+    pub fn render_block(&mut self, matrix: &ModulationMatrix, left: &mut [f32], right: &mut [f32]) {
+        match self {
+            Voice::Synth(v) => v.render_block(matrix, left, right),
+            Voice::Sampler(v) => {
+                for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+                    (*l, *r) = v.next_sample_with_matrix(matrix);
+                }
+            }
+        }
+    }
+
     // --- Synth-only methods ---
     pub fn set_waveform(&mut self, waveform: WaveformType) {
         if let Voice::Synth(v) = self {
@@ -102,12 +194,77 @@ impl Voice {
         }
     }
 
+    pub fn set_oscillator2(&mut self, params: Oscillator2Params) {
+        if let Voice::Synth(v) = self {
+            v.set_oscillator2(params);
+        }
+    }
+
+    pub fn get_oscillator2_params(&self) -> Oscillator2Params {
+        match self {
+            Voice::Synth(v) => v.get_oscillator2_params(),
+            Voice::Sampler(_) => Oscillator2Params::default(),
+        }
+    }
+
+    pub fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        if let Voice::Synth(v) = self {
+            v.set_anti_aliasing(anti_aliasing);
+        }
+    }
+
+    pub fn get_anti_aliasing(&self) -> AntiAliasing {
+        match self {
+            Voice::Synth(v) => v.get_anti_aliasing(),
+            Voice::Sampler(_) => AntiAliasing::default(),
+        }
+    }
+
+    pub fn set_cross_mod(&mut self, params: CrossModParams) {
+        if let Voice::Synth(v) = self {
+            v.set_cross_mod(params);
+        }
+    }
+
+    pub fn get_cross_mod_params(&self) -> CrossModParams {
+        match self {
+            Voice::Synth(v) => v.get_cross_mod_params(),
+            Voice::Sampler(_) => CrossModParams::default(),
+        }
+    }
+
+    pub fn set_noise(&mut self, params: NoiseParams) {
+        if let Voice::Synth(v) = self {
+            v.set_noise(params);
+        }
+    }
+
+    pub fn get_noise_params(&self) -> NoiseParams {
+        match self {
+            Voice::Synth(v) => v.get_noise_params(),
+            Voice::Sampler(_) => NoiseParams::default(),
+        }
+    }
+
     pub fn set_adsr(&mut self, params: AdsrParams) {
         if let Voice::Synth(v) = self {
             v.set_adsr(params);
         }
     }
 
+    pub fn set_mod_envelope(&mut self, params: AdsrParams) {
+        if let Voice::Synth(v) = self {
+            v.set_mod_envelope(params);
+        }
+    }
+
+    pub fn get_mod_envelope_params(&self) -> AdsrParams {
+        match self {
+            Voice::Synth(v) => v.get_mod_envelope_params(),
+            Voice::Sampler(_) => AdsrParams::default(),
+        }
+    }
+
     pub fn set_lfo(&mut self, params: LfoParams) {
         if let Voice::Synth(v) = self {
             v.set_lfo(params);
@@ -121,6 +278,33 @@ impl Voice {
         }
     }
 
+    pub fn set_mod_lfo(&mut self, index: usize, params: LfoParams) {
+        if let Voice::Synth(v) = self {
+            v.set_mod_lfo(index, params);
+        }
+    }
+
+    pub fn get_mod_lfo_params(&self, index: usize) -> LfoParams {
+        match self {
+            Voice::Synth(v) => v.get_mod_lfo_params(index),
+            Voice::Sampler(_) => LfoParams::default(),
+        }
+    }
+
+    /// Set the tempo of the synced LFOs
+    pub fn set_tempo(&mut self, tempo: Tempo) {
+        if let Voice::Synth(v) = self {
+            v.set_tempo(tempo);
+        }
+    }
+
+    /// Align the free-running LFOs on the voice manager clock (see `Lfo::sync_free_phase`)
+    pub fn sync_free_lfos(&mut self, elapsed: f64) {
+        if let Voice::Synth(v) = self {
+            v.sync_free_lfos(elapsed);
+        }
+    }
+
     pub fn set_portamento(&mut self, params: PortamentoParams) {
         if let Voice::Synth(v) = self {
             v.set_portamento(params);
@@ -146,18 +330,58 @@ impl Voice {
             Voice::Sampler(_) => FilterParams::default(),
         }
     }
+
+    pub fn set_filter_mod(&mut self, params: FilterModParams) {
+        if let Voice::Synth(v) = self {
+            v.set_filter_mod(params);
+        }
+    }
+
+    pub fn get_filter_mod_params(&self) -> FilterModParams {
+        match self {
+            Voice::Synth(v) => v.get_filter_mod_params(),
+            Voice::Sampler(_) => FilterModParams::default(),
+        }
+    }
 }
 
 pub struct SynthVoice {
-    oscillator: SimpleOscillator,
+    oscillator: SynthOscillator,
+    oscillator2: SynthOscillator,
+    anti_aliasing: AntiAliasing,
+    oscillator2_params: Oscillator2Params,
+    /// Frequency of the second oscillator relative to the first
+    oscillator2_ratio: f32,
+    cross_mod: CrossModParams,
+    noise: NoiseGenerator,
+    noise_params: NoiseParams,
     envelope: AdsrEnvelope,
-    lfo: Lfo,
+    /// Modulation envelope (a mod matrix source, see `ModSource::ModEnvelope`)
+    mod_envelope: AdsrEnvelope,
+    /// LFO 1 (with its direct destination), then the mod matrix LFOs
+    lfos: [Lfo; LFO_COUNT],
     portamento: PortamentoGlide,
     filter: StateVariableFilter,
+    /// Filter envelope amount, key tracking and drive
+    filter_mod: FilterModParams,
     effect_chain: EffectChain,
     note: u8,
     velocity: f32,
     aftertouch: f32,
+    mod_wheel: f32,
+    /// Pitch bend wheel position (-1.0 - 1.0)
+    pitch_bend: f32,
+    /// Frequency multiplier of the pitch bend (1.0 = no bend)
+    pitch_bend_ratio: f32,
+    /// Random value of the note (-1.0 - 1.0, see `ModSource::Random`)
+    random: f32,
+    /// Mod matrix outputs of the last sample (LFO and envelope destinations
+    /// apply from the next one)
+    matrix_outputs: ModOutputs,
+    /// Detune of the voice in a unison stack (1.0 = none)
+    unison_ratio: f32,
+    /// Oscillator phase at note-on (0.0 - 1.0)
+    start_phase: f32,
     active: bool,
     waveform: WaveformType,
     sample_rate: f32,
@@ -165,6 +389,7 @@ pub struct SynthVoice {
     age: u64,
     base_frequency: f32,
     target_frequency: f32,
+    block: VoiceBlock,
 }
 
 impl SynthVoice {
@@ -174,18 +399,40 @@ impl SynthVoice {
         let lfo_params = LfoParams::default();
         let portamento_params = PortamentoParams::default();
         let filter_params = FilterParams::default();
+        let oscillator2_params = Oscillator2Params::default();
+        let anti_aliasing = AntiAliasing::default();
         let initial_frequency = 440.0;
 
         Self {
-            oscillator: SimpleOscillator::new(waveform, sample_rate),
+            oscillator: SynthOscillator::new(waveform, anti_aliasing, sample_rate),
+            oscillator2: SynthOscillator::new(
+                oscillator2_params.waveform,
+                anti_aliasing,
+                sample_rate,
+            ),
+            anti_aliasing,
+            oscillator2_params,
+            oscillator2_ratio: oscillator2_params.frequency_ratio(),
+            cross_mod: CrossModParams::default(),
+            noise: NoiseGenerator::new(NoiseParams::default().noise_type, 0),
+            noise_params: NoiseParams::default(),
             envelope: AdsrEnvelope::new(adsr_params, sample_rate),
-            lfo: Lfo::new(lfo_params, sample_rate),
+            mod_envelope: AdsrEnvelope::new(adsr_params, sample_rate),
+            lfos: std::array::from_fn(|_| Lfo::new(lfo_params, sample_rate)),
             portamento: PortamentoGlide::new(portamento_params, initial_frequency, sample_rate),
             filter: StateVariableFilter::new(filter_params, sample_rate),
+            filter_mod: FilterModParams::default(),
             effect_chain: EffectChain::with_capacity(4),
             note: 0,
             velocity: 0.0,
             aftertouch: 0.0,
+            mod_wheel: 0.0,
+            pitch_bend: 0.0,
+            pitch_bend_ratio: 1.0,
+            random: 0.0,
+            matrix_outputs: ModOutputs::NONE,
+            unison_ratio: 1.0,
+            start_phase: 0.0,
             active: false,
             waveform,
             sample_rate,
@@ -193,6 +440,7 @@ impl SynthVoice {
             age: 0,
             base_frequency: initial_frequency,
             target_frequency: initial_frequency,
+            block: VoiceBlock::EMPTY,
         }
     }
 
@@ -204,8 +452,18 @@ impl SynthVoice {
         self.target_frequency = 440.0 * 2_f32.powf((self.note as f32 - 69.0) / 12.0);
         self.portamento.set_target(self.target_frequency);
         self.oscillator.reset();
+        self.oscillator.set_phase(self.start_phase);
+        self.oscillator2.reset();
+        self.oscillator2.set_phase(self.start_phase);
+        // Distinct noise per note and per unison voice
+        self.noise.reseed(age as u32 ^ self.start_phase.to_bits());
+        self.random = self.noise.white();
+        self.matrix_outputs = ModOutputs::NONE;
         self.envelope.note_on();
-        self.lfo.reset();
+        self.mod_envelope.note_on();
+        for lfo in &mut self.lfos {
+            lfo.note_on();
+        }
         self.filter.reset();
         self.effect_chain.reset();
     }
@@ -221,15 +479,30 @@ impl SynthVoice {
     pub fn note_off(&mut self) {
         self.active = false;
         self.envelope.note_off();
+        self.mod_envelope.note_off();
     }
 
     pub fn force_stop(&mut self) {
         self.active = false;
         self.envelope.reset();
+        self.mod_envelope.reset();
         self.filter.reset();
         self.effect_chain.reset();
     }
 
+    pub fn fade_out(&mut self, duration: f32) {
+        self.active = false;
+        self.envelope.fade_out(duration);
+    }
+
+    pub fn is_fading_out(&self) -> bool {
+        self.envelope.is_fading_out()
+    }
+
+    pub fn level(&self) -> f32 {
+        self.envelope.current_value() * self.velocity
+    }
+
     pub fn is_active(&self) -> bool {
         self.envelope.is_active()
     }
@@ -250,29 +523,129 @@ impl SynthVoice {
         self.aftertouch = value.clamp(0.0, 1.0);
     }
 
+    /// Bend the pitch: wheel position (-1.0 - 1.0) and range in semitones
+    pub fn set_pitch_bend(&mut self, position: f32, range: f32) {
+        self.pitch_bend = position.clamp(-1.0, 1.0);
+        self.pitch_bend_ratio = 2_f32.powf(self.pitch_bend * range / 12.0);
+    }
+
+    pub fn set_mod_wheel(&mut self, value: f32) {
+        self.mod_wheel = value.clamp(0.0, 1.0);
+    }
+
+    /// Place the voice in a unison stack: detune ratio, pan (-1.0 - 1.0) and
+    /// oscillator start phase (applied on the next note)
+    pub fn set_unison_voice(&mut self, frequency_ratio: f32, pan: f32, start_phase: f32) {
+        self.unison_ratio = frequency_ratio;
+        self.pan = pan.clamp(-1.0, 1.0);
+        self.start_phase = start_phase;
+    }
+
     pub fn is_releasing(&self) -> bool {
         !self.active && self.envelope.is_active()
     }
 
     pub fn set_waveform(&mut self, waveform: WaveformType) {
         self.waveform = waveform;
-        self.oscillator = SimpleOscillator::new(waveform, self.sample_rate);
+        self.oscillator = SynthOscillator::new(waveform, self.anti_aliasing, self.sample_rate);
         if self.active {
             let frequency = 440.0 * 2_f32.powf((self.note as f32 - 69.0) / 12.0);
             self.oscillator.set_frequency(frequency);
         }
     }
 
+    /// Set the second oscillator (a new waveform restarts its cycle)
+    pub fn set_oscillator2(&mut self, params: Oscillator2Params) {
+        if params.waveform != self.oscillator2_params.waveform {
+            self.oscillator2 =
+                SynthOscillator::new(params.waveform, self.anti_aliasing, self.sample_rate);
+        }
+        self.oscillator2_params = params;
+        self.oscillator2_ratio = params.frequency_ratio();
+    }
+
+    pub fn get_oscillator2_params(&self) -> Oscillator2Params {
+        self.oscillator2_params
+    }
+
+    /// Switch the band-limiting method of the oscillators (they restart their cycle)
+    pub fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        if anti_aliasing == self.anti_aliasing {
+            return;
+        }
+        self.anti_aliasing = anti_aliasing;
+        self.oscillator = SynthOscillator::new(self.waveform, anti_aliasing, self.sample_rate);
+        self.oscillator2 = SynthOscillator::new(
+            self.oscillator2_params.waveform,
+            anti_aliasing,
+            self.sample_rate,
+        );
+    }
+
+    pub fn get_anti_aliasing(&self) -> AntiAliasing {
+        self.anti_aliasing
+    }
+
+    pub fn set_cross_mod(&mut self, params: CrossModParams) {
+        self.cross_mod = params;
+    }
+
+    pub fn get_cross_mod_params(&self) -> CrossModParams {
+        self.cross_mod
+    }
+
+    pub fn set_noise(&mut self, params: NoiseParams) {
+        self.noise.set_noise_type(params.noise_type);
+        self.noise_params = params;
+    }
+
+    pub fn get_noise_params(&self) -> NoiseParams {
+        self.noise_params
+    }
+
     pub fn set_adsr(&mut self, params: AdsrParams) {
         self.envelope.set_params(params);
     }
 
+    pub fn set_mod_envelope(&mut self, params: AdsrParams) {
+        self.mod_envelope.set_params(params);
+    }
+
+    pub fn get_mod_envelope_params(&self) -> AdsrParams {
+        self.mod_envelope.params()
+    }
+
     pub fn set_lfo(&mut self, params: LfoParams) {
-        self.lfo.set_params(params);
+        self.lfos[0].set_params(params);
     }
 
     pub fn get_lfo_params(&self) -> LfoParams {
-        self.lfo.params()
+        self.lfos[0].params()
+    }
+
+    /// Set a mod matrix LFO (`index` 1 to `LFO_COUNT - 1`, LFO 1 is `set_lfo`)
+    pub fn set_mod_lfo(&mut self, index: usize, params: LfoParams) {
+        if let Some(lfo) = self.lfos.get_mut(index) {
+            lfo.set_params(params);
+        }
+    }
+
+    pub fn get_mod_lfo_params(&self, index: usize) -> LfoParams {
+        self.lfos
+            .get(index)
+            .map_or_else(LfoParams::default, Lfo::params)
+    }
+
+    pub fn set_tempo(&mut self, tempo: Tempo) {
+        for lfo in &mut self.lfos {
+            lfo.set_tempo(tempo);
+        }
+    }
+
+    pub fn sync_free_lfos(&mut self, elapsed: f64) {
+        for lfo in &mut self.lfos {
+            lfo.sync_free_phase(elapsed);
+        }
     }
 
     pub fn set_portamento(&mut self, params: PortamentoParams) {
@@ -291,6 +664,38 @@ impl SynthVoice {
         self.filter.params()
     }
 
+    pub fn set_filter_mod(&mut self, params: FilterModParams) {
+        self.filter.set_drive(params.drive);
+        self.filter_mod = params;
+    }
+
+    pub fn get_filter_mod_params(&self) -> FilterModParams {
+        self.filter_mod
+    }
+
+    /// Rebuild the oscillators, envelopes, LFO, glide and filter at a new
+    /// sample rate, keeping their parameters (the voice is silenced)
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.active = false;
+        self.oscillator = SynthOscillator::new(self.waveform, self.anti_aliasing, sample_rate);
+        self.oscillator2 = SynthOscillator::new(
+            self.oscillator2_params.waveform,
+            self.anti_aliasing,
+            sample_rate,
+        );
+        self.envelope = AdsrEnvelope::new(self.envelope.params(), sample_rate);
+        self.mod_envelope = AdsrEnvelope::new(self.mod_envelope.params(), sample_rate);
+        for lfo in &mut self.lfos {
+            lfo.set_sample_rate(sample_rate);
+        }
+        self.portamento =
+            PortamentoGlide::new(self.portamento.params(), self.target_frequency, sample_rate);
+        self.filter = StateVariableFilter::new(self.filter.params(), sample_rate);
+        self.filter.set_drive(self.filter_mod.drive);
+        self.effect_chain.reset();
+    }
+
     pub fn effect_chain_mut(&mut self) -> &mut EffectChain {
         &mut self.effect_chain
     }
@@ -299,32 +704,36 @@ impl SynthVoice {
         &self.effect_chain
     }
 
+    /// Delay and reverb send offsets from the mod matrix (`ModDestination::DelaySend`
+    /// and `ReverbSend`), as of the last rendered sample
+    pub fn send_modulation(&self) -> (f32, f32) {
+        (
+            self.matrix_outputs.delay_send,
+            self.matrix_outputs.reverb_send,
+        )
+    }
+
     pub fn next_sample(&mut self) -> (f32, f32) {
         use super::lfo::LfoDestination;
-        self.base_frequency = self.portamento.process(self.target_frequency);
-        let lfo_value = self.lfo.process();
-        match self.lfo.destination() {
-            LfoDestination::None => {
-                self.oscillator.set_frequency(self.base_frequency);
-            }
+        self.base_frequency = self.portamento.process(self.target_frequency)
+            * self.pitch_bend_ratio
+            * self.unison_ratio;
+        let lfo_value = self.lfos[0].process();
+        let frequency = match self.lfos[0].destination() {
             LfoDestination::Pitch => {
                 let semitone_offset = lfo_value * 2.0;
                 let frequency_multiplier = 2_f32.powf(semitone_offset / 12.0);
-                let modulated_frequency = self.base_frequency * frequency_multiplier;
-                self.oscillator.set_frequency(modulated_frequency);
-            }
-            LfoDestination::Volume => {
-                self.oscillator.set_frequency(self.base_frequency);
+                self.base_frequency * frequency_multiplier
             }
-            LfoDestination::FilterCutoff => {
-                self.oscillator.set_frequency(self.base_frequency);
+            LfoDestination::None | LfoDestination::Volume | LfoDestination::FilterCutoff => {
+                self.base_frequency
             }
-        }
+        };
         let envelope_value = self.envelope.process();
-        let mut sample = self.oscillator.next_sample();
+        let mut sample = self.oscillator_sample(frequency, self.oscillator2_params.mix);
         sample = self.filter.process(sample);
         sample = self.effect_chain.process(sample);
-        if matches!(self.lfo.destination(), LfoDestination::Volume) {
+        if matches!(self.lfos[0].destination(), LfoDestination::Volume) {
             let volume_multiplier = 1.0 + lfo_value;
             sample *= volume_multiplier;
         }
@@ -336,11 +745,149 @@ impl SynthVoice {
     }
 
     pub fn next_sample_with_matrix(&mut self, matrix: &ModulationMatrix) -> (f32, f32) {
+        let modulation = self.next_modulation(matrix);
+        let sample = self.oscillator_sample(modulation.frequency, modulation.oscillator_mix);
+        self.finish_sample(sample, &modulation)
+    }
+
+    /// Render a block of samples into `left`/`right` (overwritten)
+    ///
+    /// Same output as `next_sample_with_matrix()` per sample. Modulation is
+    /// computed first for the whole block, so when the pitch is steady (no glide
+    /// or pitch modulation) the oscillator runs as a SIMD batch.
+    pub fn render_block(&mut self, matrix: &ModulationMatrix, left: &mut [f32], right: &mut [f32]) {
+        for (left, right) in left
+            .chunks_mut(VOICE_BLOCK_SIZE)
+            .zip(right.chunks_mut(VOICE_BLOCK_SIZE))
+        {
+            let len = left.len().min(right.len());
+            self.prepare_block(matrix, len);
+            self.filter_block();
+            self.finish_block(left, right);
+        }
+    }
+
+    /// First stage of a block: modulation and oscillator output
+    ///
+    /// Renders `len` (at most `VOICE_BLOCK_SIZE`) samples into the voice's block
+    /// buffer. The block is then filtered, either by `filter_block()` or with
+    /// other voices by a `VoiceFilterGroup`, and mixed by `finish_block()`.
+    pub fn prepare_block(&mut self, matrix: &ModulationMatrix, len: usize) {
+        let len = len.min(VOICE_BLOCK_SIZE);
+        self.block.len = len;
+        for i in 0..len {
+            self.block.modulations[i] = self.next_modulation(matrix);
+        }
+
+        let frequency = self.block.modulations[0].frequency;
+        if self.block.modulations[..len]
+            .iter()
+            .all(|m| m.frequency == frequency)
+            && self.cross_mod.fm <= 0.0
+        {
+            self.oscillator.set_frequency(frequency);
+            self.oscillator
+                .process_block(&mut self.block.samples[..len]);
+            let modulations = &self.block.modulations[..len];
+            if modulations.iter().any(|m| m.oscillator_mix > 0.0) || self.cross_mod.ring > 0.0 {
+                let mut second = [0.0f32; VOICE_BLOCK_SIZE];
+                self.oscillator2
+                    .set_frequency(frequency * self.oscillator2_ratio);
+                self.oscillator2.process_block(&mut second[..len]);
+                let ring = self.cross_mod.ring;
+                for ((sample, second), modulation) in self.block.samples[..len]
+                    .iter_mut()
+                    .zip(&second)
+                    .zip(modulations)
+                {
+                    *sample = mix_oscillators(*sample, *second, modulation.oscillator_mix, ring);
+                }
+            }
+            let level = self.noise_params.level;
+            if level > 0.0 {
+                for sample in &mut self.block.samples[..len] {
+                    *sample += self.noise.next_sample() * level;
+                }
+            }
+        } else {
+            for i in 0..len {
+                let modulation = self.block.modulations[i];
+                self.block.samples[i] =
+                    self.oscillator_sample(modulation.frequency, modulation.oscillator_mix);
+            }
+        }
+    }
+
+    /// Next output of the oscillators at `frequency` (the second one mixed
+    /// in at `mix` or modulating the first, then the noise)
+    #[inline]
+    fn oscillator_sample(&mut self, frequency: f32, mix: f32) -> f32 {
+        let mut sample = if mix > 0.0 || self.cross_mod.is_active() {
+            let frequency2 = frequency * self.oscillator2_ratio;
+            self.oscillator2.set_frequency(frequency2);
+            let second = self.oscillator2.next_sample();
+            let nyquist = self.sample_rate * 0.5;
+            let deviation = self.cross_mod.fm * MAX_FM_INDEX * frequency2 * second;
+            self.oscillator
+                .set_frequency((frequency + deviation).clamp(-nyquist, nyquist));
+            let first = self.oscillator.next_sample();
+            mix_oscillators(first, second, mix, self.cross_mod.ring)
+        } else {
+            self.oscillator.set_frequency(frequency);
+            self.oscillator.next_sample()
+        };
+        let level = self.noise_params.level;
+        if level > 0.0 {
+            sample += self.noise.next_sample() * level;
+        }
+        sample
+    }
+
+    /// Second stage of a block: filter it with this voice's own filter
+    pub fn filter_block(&mut self) {
+        let block = &mut self.block;
+        for (sample, modulation) in block.samples[..block.len]
+            .iter_mut()
+            .zip(&block.modulations)
+        {
+            let modulated_cutoff = self.filter.next_cutoff() * modulation.filter_cutoff_mult;
+            *sample = self.filter.process_modulated(
+                *sample,
+                modulated_cutoff,
+                modulation.filter_resonance_mult,
+            );
+        }
+    }
+
+    /// Last stage of a block: effects, gain and pan into `left`/`right` (overwritten)
+    pub fn finish_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let len = self.block.len.min(left.len()).min(right.len());
+        for i in 0..len {
+            let modulation = self.block.modulations[i];
+            (left[i], right[i]) = self.finish_filtered_sample(self.block.samples[i], &modulation);
+        }
+    }
+
+    /// Advance portamento, LFO and envelopes by one sample and apply the modulation matrix
+    fn next_modulation(&mut self, matrix: &ModulationMatrix) -> VoiceModulation {
         use super::lfo::LfoDestination;
-        self.base_frequency = self.portamento.process(self.target_frequency);
-        let lfo_value = self.lfo.process();
+        self.base_frequency = self.portamento.process(self.target_frequency)
+            * self.pitch_bend_ratio
+            * self.unison_ratio;
+        // LFO and envelope destinations of the mod matrix, from the last sample
+        let outputs = self.matrix_outputs;
+        for (i, lfo) in self.lfos.iter_mut().enumerate() {
+            lfo.set_modulation(outputs.lfo_rate_mult[i], outputs.lfo_depth[i]);
+        }
+        self.envelope.set_time_scale(outputs.envelope_time_mult);
+        self.mod_envelope
+            .set_time_scale(outputs.mod_envelope_time_mult);
+
+        let lfo_values: [f32; LFO_COUNT] = std::array::from_fn(|i| self.lfos[i].process());
+        let lfo_value = lfo_values[0];
         let envelope_value = self.envelope.process();
-        let legacy_lfo_semitones = if matches!(self.lfo.destination(), LfoDestination::Pitch) {
+        let mod_envelope_value = self.mod_envelope.process();
+        let legacy_lfo_semitones = if matches!(self.lfos[0].destination(), LfoDestination::Pitch) {
             lfo_value * 2.0
         } else {
             0.0
@@ -351,29 +898,64 @@ impl SynthVoice {
         } else {
             self.base_frequency
         };
-        let (pitch_semitones, amp_mult, pan_mod, filter_cutoff_mult) = matrix.apply(
-            self.velocity,
-            self.aftertouch,
-            &[lfo_value],
-            self.envelope.current_value(),
-        );
-        if pitch_semitones != 0.0 {
-            let mult = 2_f32.powf(pitch_semitones / 12.0);
+        let outputs = matrix.apply(&ModSources {
+            velocity: self.velocity,
+            aftertouch: self.aftertouch,
+            mod_wheel: self.mod_wheel,
+            pitch_bend: self.pitch_bend,
+            key: key_track(self.note),
+            random: self.random,
+            lfos: lfo_values,
+            envelope: self.envelope.current_value(),
+            mod_envelope: mod_envelope_value,
+        });
+        self.matrix_outputs = outputs;
+        if outputs.pitch_semitones != 0.0 {
+            let mult = 2_f32.powf(outputs.pitch_semitones / 12.0);
             frequency *= mult;
         }
-        self.oscillator.set_frequency(frequency);
-        let mut sample = self.oscillator.next_sample();
-        let base_cutoff = self.filter.params().cutoff;
-        let modulated_cutoff = base_cutoff * filter_cutoff_mult;
-        sample = self.filter.process_modulated(sample, modulated_cutoff);
+        let mut filter_cutoff_mult = outputs.filter_cutoff_mult;
+        if self.filter_mod.moves_cutoff() {
+            filter_cutoff_mult *= self.filter_mod.cutoff_ratio(self.note, mod_envelope_value);
+        }
+        VoiceModulation {
+            frequency,
+            lfo_value,
+            envelope_value,
+            amp_mult: outputs.amp_mult,
+            pan_mod: outputs.pan,
+            filter_cutoff_mult,
+            filter_resonance_mult: outputs.filter_resonance_mult,
+            oscillator_mix: (self.oscillator2_params.mix + outputs.oscillator_mix).clamp(0.0, 1.0),
+        }
+    }
+
+    /// Filter, effects, gain and pan for one oscillator sample
+    fn finish_sample(&mut self, sample: f32, modulation: &VoiceModulation) -> (f32, f32) {
+        let modulated_cutoff = self.filter.next_cutoff() * modulation.filter_cutoff_mult;
+        let sample = self.filter.process_modulated(
+            sample,
+            modulated_cutoff,
+            modulation.filter_resonance_mult,
+        );
+        self.finish_filtered_sample(sample, modulation)
+    }
+
+    /// Effects, gain and pan for one filtered sample
+    fn finish_filtered_sample(
+        &mut self,
+        mut sample: f32,
+        modulation: &VoiceModulation,
+    ) -> (f32, f32) {
+        use super::lfo::LfoDestination;
         sample = self.effect_chain.process(sample);
-        if matches!(self.lfo.destination(), LfoDestination::Volume) {
-            let volume_multiplier = 1.0 + lfo_value;
+        if matches!(self.lfos[0].destination(), LfoDestination::Volume) {
+            let volume_multiplier = 1.0 + modulation.lfo_value;
             sample *= volume_multiplier;
         }
-        sample *= self.velocity * envelope_value;
-        sample *= amp_mult;
-        let final_pan = (self.pan + pan_mod).clamp(-1.0, 1.0);
+        sample *= self.velocity * modulation.envelope_value;
+        sample *= modulation.amp_mult;
+        let final_pan = (self.pan + modulation.pan_mod).clamp(-1.0, 1.0);
         let angle = (final_pan * 0.5 + 0.5) * FRAC_PI_2;
         let left = sample * angle.cos();
         let right = sample * angle.sin();
@@ -381,12 +963,151 @@ impl SynthVoice {
     }
 }
 
+/// Block being rendered by a synth voice (see `SynthVoice::prepare_block()`)
+struct VoiceBlock {
+    len: usize,
+    modulations: [VoiceModulation; VOICE_BLOCK_SIZE],
+    /// Oscillator output, replaced by the filter output
+    samples: [f32; VOICE_BLOCK_SIZE],
+}
+
+impl VoiceBlock {
+    const EMPTY: Self = Self {
+        len: 0,
+        modulations: [VoiceModulation::ZERO; VOICE_BLOCK_SIZE],
+        samples: [0.0; VOICE_BLOCK_SIZE],
+    };
+}
+
+/// Synth voices whose prepared blocks are filtered together
+///
+/// Each voice takes one lane of a `StateVariableFilterX4`, so the filters of
+/// up to `FILTER_LANES` voices run in one SIMD pass. Voices with a disabled
+/// filter are handed back untouched.
+pub struct VoiceFilterGroup<'a> {
+    voices: [Option<&'a mut SynthVoice>; FILTER_LANES],
+    len: usize,
+}
+
+impl<'a> VoiceFilterGroup<'a> {
+    pub fn new() -> Self {
+        Self {
+            voices: [None, None, None, None],
+            len: 0,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == FILTER_LANES
+    }
+
+    /// Add a voice whose block was prepared (`SynthVoice::prepare_block()`)
+    pub fn push(&mut self, voice: &'a mut SynthVoice) {
+        debug_assert!(!self.is_full());
+        self.voices[self.len] = Some(voice);
+        self.len += 1;
+    }
+
+    /// Filter the blocks of the grouped voices, then hand the voices back
+    pub fn filter(&mut self) -> impl Iterator<Item = &'a mut SynthVoice> + '_ {
+        let mut frames = [[0.0f32; FILTER_LANES]; VOICE_BLOCK_SIZE];
+        let mut cutoffs = [[0.0f32; FILTER_LANES]; VOICE_BLOCK_SIZE];
+        let mut resonance_mults = [[1.0f32; FILTER_LANES]; VOICE_BLOCK_SIZE];
+        let mut lanes: Option<StateVariableFilterX4> = None;
+        let mut block_len = 0;
+
+        for (lane, voice) in self.voices.iter_mut().enumerate() {
+            let Some(voice) = voice.as_deref_mut() else {
+                continue;
+            };
+            if !voice.filter.params().enabled {
+                continue;
+            }
+            let block = &voice.block;
+            for (i, modulation) in block.modulations[..block.len].iter().enumerate() {
+                frames[i][lane] = block.samples[i];
+                cutoffs[i][lane] = voice.filter.next_cutoff() * modulation.filter_cutoff_mult;
+                resonance_mults[i][lane] = modulation.filter_resonance_mult;
+            }
+            block_len = block_len.max(block.len);
+            lanes
+                .get_or_insert_with(|| StateVariableFilterX4::new(voice.sample_rate))
+                .load(lane, &voice.filter);
+        }
+
+        if let Some(lanes) = &mut lanes {
+            lanes.process_modulated(
+                &mut frames[..block_len],
+                &cutoffs[..block_len],
+                &resonance_mults[..block_len],
+            );
+            for (lane, voice) in self.voices.iter_mut().enumerate() {
+                let Some(voice) = voice.as_deref_mut() else {
+                    continue;
+                };
+                if !voice.filter.params().enabled {
+                    continue;
+                }
+                lanes.store(lane, &mut voice.filter);
+                let block = &mut voice.block;
+                for (sample, frame) in block.samples[..block.len].iter_mut().zip(&frames) {
+                    *sample = frame[lane];
+                }
+            }
+        }
+
+        let len = std::mem::take(&mut self.len);
+        self.voices[..len].iter_mut().filter_map(Option::take)
+    }
+}
+
+impl Default for VoiceFilterGroup<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per-sample modulation values of a synth voice
+#[derive(Debug, Clone, Copy, Default)]
+struct VoiceModulation {
+    frequency: f32,
+    lfo_value: f32,
+    envelope_value: f32,
+    amp_mult: f32,
+    pan_mod: f32,
+    filter_cutoff_mult: f32,
+    filter_resonance_mult: f32,
+    /// Level of the second oscillator (mix with its modulation)
+    oscillator_mix: f32,
+}
+
+impl VoiceModulation {
+    const ZERO: Self = Self {
+        frequency: 0.0,
+        lfo_value: 0.0,
+        envelope_value: 0.0,
+        amp_mult: 0.0,
+        pan_mod: 0.0,
+        filter_cutoff_mult: 0.0,
+        filter_resonance_mult: 0.0,
+        oscillator_mix: 0.0,
+    };
+}
+
+/// Mix of the oscillators: crossfade, then ring modulation
+#[inline]
+fn mix_oscillators(first: f32, second: f32, mix: f32, ring: f32) -> f32 {
+    let mixed = first * (1.0 - mix) + second * mix;
+    mixed + (first * second - mixed) * ring
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::synth::filter::FilterType;
     use crate::synth::lfo::LfoDestination;
     use crate::synth::modulation::{ModDestination, ModRouting, ModSource, ModulationMatrix};
+    use crate::synth::noise::NoiseType;
     use crate::synth::oscillator::WaveformType;
 
     #[test]
@@ -453,6 +1174,96 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_filter_envelope_and_key_tracking() {
+        let mut voice = SynthVoice::new(44100.0);
+        // Instant mod envelope holding at full level
+        voice.set_mod_envelope(AdsrParams::new(0.001, 0.001, 1.0, 0.01));
+        voice.set_filter_mod(FilterModParams::new(2.0, 1.0, 0.5));
+        assert_eq!(voice.get_filter_mod_params().drive, 0.5);
+        let matrix = ModulationMatrix::new_empty();
+
+        // An octave above C4 with the envelope up: 2 + 1 octaves
+        voice.note_on(72, 100, 0);
+        for _ in 0..500 {
+            voice.next_modulation(&matrix);
+        }
+        let cutoff_mult = voice.next_modulation(&matrix).filter_cutoff_mult;
+        assert!((cutoff_mult - 8.0).abs() < 0.01, "{}", cutoff_mult);
+
+        // Without the filter envelope, C4 leaves the cutoff alone
+        voice.set_filter_mod(FilterModParams::new(0.0, 1.0, 0.0));
+        voice.note_on(60, 100, 0);
+        let cutoff_mult = voice.next_modulation(&matrix).filter_cutoff_mult;
+        assert!((cutoff_mult - 1.0).abs() < 1e-5, "{}", cutoff_mult);
+    }
+
+    #[test]
+    fn test_mod_envelope_runs_apart_from_the_amp_envelope() {
+        let sample_rate = 44100.0;
+        let mut voice = SynthVoice::new(sample_rate);
+        // Short mod envelope decaying to zero while the amp envelope sustains
+        voice.set_mod_envelope(AdsrParams::new(0.001, 0.05, 0.0, 0.01));
+        let mut matrix = ModulationMatrix::new_empty();
+        matrix.set_routing(
+            0,
+            ModRouting {
+                source: ModSource::ModEnvelope,
+                destination: ModDestination::OscillatorPitch(0),
+                amount: 12.0,
+                enabled: true,
+            },
+        );
+        voice.note_on(69, 100, 0);
+
+        // Peak of the mod envelope: one octave up
+        let peak = (0..(0.001 * sample_rate) as usize + 2)
+            .map(|_| voice.next_modulation(&matrix).frequency)
+            .fold(0.0, f32::max);
+        assert!((peak - 880.0).abs() < 1.0, "peak {}", peak);
+
+        // Decayed: back to the note while the voice still sounds
+        let mut modulation = voice.next_modulation(&matrix);
+        for _ in 0..(0.06 * sample_rate) as usize {
+            modulation = voice.next_modulation(&matrix);
+        }
+        assert!((modulation.frequency - 440.0).abs() < 0.5);
+        assert!(modulation.envelope_value > 0.5);
+    }
+
+    #[test]
+    fn test_matrix_modulates_envelope_times_and_osc_mix() {
+        let sample_rate = 44100.0;
+        let mut voice = SynthVoice::new(sample_rate);
+        voice.set_adsr(AdsrParams::new(0.01, 0.1, 1.0, 0.1));
+        voice.set_mod_wheel(1.0);
+        let mut matrix = ModulationMatrix::new_empty();
+        for (index, destination) in [ModDestination::EnvelopeTime, ModDestination::OscillatorMix]
+            .into_iter()
+            .enumerate()
+        {
+            matrix.set_routing(
+                index,
+                ModRouting {
+                    source: ModSource::ModWheel,
+                    destination,
+                    amount: 1.0,
+                    enabled: true,
+                },
+            );
+        }
+        voice.note_on(69, 100, 0);
+
+        // One octave longer: halfway through the 20 ms attack after 10 ms
+        let mut modulation = voice.next_modulation(&matrix);
+        for _ in 0..(0.01 * sample_rate) as usize {
+            modulation = voice.next_modulation(&matrix);
+        }
+        assert!((modulation.envelope_value - 0.5).abs() < 0.01);
+        // The wheel brings the second oscillator fully in
+        assert_eq!(modulation.oscillator_mix, 1.0);
+    }
+
     #[test]
     fn test_filter_modulation_with_lfo() {
         let sample_rate = 44100.0;
@@ -469,6 +1280,7 @@ mod tests {
             rate: 5.0,
             depth: 1.0,
             destination: LfoDestination::None,
+            ..LfoParams::default()
         };
         voice.set_lfo(lfo_params);
         let envelope_params = AdsrParams {
@@ -591,4 +1403,119 @@ mod tests {
             assert!(sample.is_finite(), "All samples should be finite");
         }
     }
+
+    #[test]
+    fn test_second_oscillator() {
+        let sample_rate = 44100.0;
+        let matrix = ModulationMatrix::new_empty();
+        let voice = |note: u8, oscillator2: Oscillator2Params| {
+            let mut voice = SynthVoice::new(sample_rate);
+            voice.set_filter(FilterParams {
+                enabled: false,
+                ..Default::default()
+            });
+            voice.set_oscillator2(oscillator2);
+            voice.note_on(note, 100, 0);
+            voice
+        };
+
+        // Fully mixed in, an octave up: the note an octave higher
+        let octave_up = Oscillator2Params::new(WaveformType::Sine, 12, 0.0, 1.0);
+        let mut layered = voice(60, octave_up);
+        let mut reference = voice(72, Oscillator2Params::default());
+        for _ in 0..1000 {
+            let (left, _) = layered.next_sample_with_matrix(&matrix);
+            let (expected, _) = reference.next_sample_with_matrix(&matrix);
+            assert!((left - expected).abs() < 1e-3, "{} != {}", left, expected);
+        }
+
+        // Blocks render the same mix as single samples
+        let detuned = Oscillator2Params::new(WaveformType::Saw, -7, 12.0, 0.5);
+        let mut scalar = voice(57, detuned);
+        let mut block = voice(57, detuned);
+        let mut left = [0.0; 200];
+        let mut right = [0.0; 200];
+        block.render_block(&matrix, &mut left, &mut right);
+        for value in left {
+            let (expected, _) = scalar.next_sample_with_matrix(&matrix);
+            assert!((value - expected).abs() < 1e-3, "{} != {}", value, expected);
+        }
+    }
+
+    #[test]
+    fn test_wavetable_oscillators() {
+        let sample_rate = 44100.0;
+        let matrix = ModulationMatrix::new_empty();
+        let voice = || {
+            let mut voice = SynthVoice::new(sample_rate);
+            voice.set_waveform(WaveformType::Saw);
+            voice.set_oscillator2(Oscillator2Params::new(WaveformType::Square, 12, 0.0, 0.3));
+            voice.set_anti_aliasing(AntiAliasing::Wavetable);
+            voice.note_on(69, 100, 0);
+            voice
+        };
+        let mut scalar = voice();
+        let mut block = voice();
+        assert_eq!(block.get_anti_aliasing(), AntiAliasing::Wavetable);
+
+        let mut left = [0.0; 200];
+        let mut right = [0.0; 200];
+        block.render_block(&matrix, &mut left, &mut right);
+        assert!(left.iter().any(|value| value.abs() > 0.01));
+        for value in left {
+            let (expected, _) = scalar.next_sample_with_matrix(&matrix);
+            assert!((value - expected).abs() < 1e-3, "{} != {}", value, expected);
+        }
+    }
+
+    #[test]
+    fn test_cross_modulation_and_noise() {
+        let sample_rate = 44100.0;
+        let matrix = ModulationMatrix::new_empty();
+        let voice = |cross_mod: CrossModParams, noise: NoiseParams| {
+            let mut voice = SynthVoice::new(sample_rate);
+            voice.set_filter(FilterParams {
+                enabled: false,
+                ..Default::default()
+            });
+            voice.set_oscillator2(Oscillator2Params::new(WaveformType::Sine, 0, 0.0, 0.0));
+            voice.set_cross_mod(cross_mod);
+            voice.set_noise(noise);
+            voice.note_on(69, 127, 0);
+            voice
+        };
+        let render = |mut voice: SynthVoice| {
+            let mut left = [0.0; 400];
+            let mut right = [0.0; 400];
+            voice.render_block(&matrix, &mut left, &mut right);
+            left
+        };
+
+        // Full ring modulation of two unison sines is sin^2: never negative
+        let ring = render(voice(CrossModParams::new(0.0, 1.0), NoiseParams::default()));
+        assert!(ring.iter().all(|value| *value >= -1e-4));
+        assert!(ring.iter().any(|value| *value > 1e-3));
+
+        // FM changes the output and stays finite
+        let plain = render(voice(CrossModParams::default(), NoiseParams::default()));
+        let fm = render(voice(CrossModParams::new(1.0, 0.0), NoiseParams::default()));
+        assert!(fm.iter().all(|value| value.is_finite()));
+        assert!(fm.iter().zip(&plain).any(|(a, b)| (a - b).abs() > 1e-2));
+
+        // Noise is added to the oscillators, blocks match single samples
+        let noisy = NoiseParams::new(NoiseType::Pink, 0.5);
+        let with_noise = render(voice(CrossModParams::new(0.3, 0.5), noisy));
+        let mut scalar = voice(CrossModParams::new(0.3, 0.5), noisy);
+        for value in with_noise {
+            let (expected, _) = scalar.next_sample_with_matrix(&matrix);
+            assert!((value - expected).abs() < 1e-3, "{} != {}", value, expected);
+        }
+        let quiet = render(voice(CrossModParams::new(0.3, 0.5), NoiseParams::default()));
+        assert!(
+            with_noise
+                .iter()
+                .zip(&quiet)
+                .any(|(a, b)| (a - b).abs() > 1e-2)
+        );
+    }
 }
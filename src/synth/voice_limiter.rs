@@ -0,0 +1,160 @@
+// Voice limiter - Adaptive polyphony cap under CPU load
+//
+// The user sets a maximum number of voices. When the audio callback stays under
+// High load, the cap is lowered one voice at a time (the voice manager fades
+// out the quietest voices above it). Once the load has been Low for a while,
+// the cap grows back, one voice at a time, up to the maximum.
+//
+// Durations are counted in frames so the limiter can run on the audio thread
+// without reading the clock.
+
+use crate::audio::cpu_monitor::CpuLoad;
+
+/// The cap is never lowered below this (unless the maximum is lower)
+pub const MIN_VOICE_LIMIT: usize = 4;
+
+/// Sustained High load before each step down (seconds)
+const STEP_DOWN_HOLD: f32 = 0.25;
+
+/// Sustained Low load before each step up (seconds)
+const STEP_UP_HOLD: f32 = 2.0;
+
+pub struct VoiceLimiter {
+    max_voices: usize,
+    limit: usize,
+    high_load_frames: usize,
+    low_load_frames: usize,
+    step_down_frames: usize,
+    step_up_frames: usize,
+}
+
+impl VoiceLimiter {
+    pub fn new(max_voices: usize, sample_rate: f32) -> Self {
+        let max_voices = max_voices.max(1);
+        Self {
+            max_voices,
+            limit: max_voices,
+            high_load_frames: 0,
+            low_load_frames: 0,
+            step_down_frames: (STEP_DOWN_HOLD * sample_rate) as usize,
+            step_up_frames: (STEP_UP_HOLD * sample_rate) as usize,
+        }
+    }
+
+    /// User-configured maximum
+    pub fn max_voices(&self) -> usize {
+        self.max_voices
+    }
+
+    /// Voices currently allowed
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Change the maximum (at least 1)
+    ///
+    /// A cap lowered by load stays lowered (within the new maximum).
+    pub fn set_max_voices(&mut self, max_voices: usize) {
+        let max_voices = max_voices.max(1);
+        self.limit = if self.limit < self.max_voices {
+            self.limit.min(max_voices)
+        } else {
+            max_voices
+        };
+        self.max_voices = max_voices;
+    }
+
+    /// Feed the load of the last `frames` frames
+    ///
+    /// Returns the new limit.
+    pub fn update(&mut self, load: CpuLoad, frames: usize) -> usize {
+        match load {
+            CpuLoad::High => {
+                self.low_load_frames = 0;
+                self.high_load_frames += frames;
+                if self.high_load_frames >= self.step_down_frames {
+                    self.high_load_frames = 0;
+                    let floor = MIN_VOICE_LIMIT.min(self.max_voices);
+                    self.limit = self.limit.saturating_sub(1).max(floor);
+                }
+            }
+            CpuLoad::Medium => {
+                self.high_load_frames = 0;
+                self.low_load_frames = 0;
+            }
+            CpuLoad::Low => {
+                self.high_load_frames = 0;
+                self.low_load_frames += frames;
+                if self.low_load_frames >= self.step_up_frames {
+                    self.low_load_frames = 0;
+                    self.limit = (self.limit + 1).min(self.max_voices);
+                }
+            }
+        }
+        self.limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48000.0;
+    const BLOCK: usize = 480;
+
+    fn run(limiter: &mut VoiceLimiter, load: CpuLoad, seconds: f32) -> usize {
+        let blocks = (seconds * SAMPLE_RATE) as usize / BLOCK;
+        for _ in 0..blocks {
+            limiter.update(load, BLOCK);
+        }
+        limiter.limit()
+    }
+
+    #[test]
+    fn test_sustained_high_load_lowers_limit() {
+        let mut limiter = VoiceLimiter::new(16, SAMPLE_RATE);
+
+        // Short spikes are ignored
+        for _ in 0..10 {
+            run(&mut limiter, CpuLoad::High, 0.1);
+            run(&mut limiter, CpuLoad::Medium, 0.1);
+        }
+        assert_eq!(limiter.limit(), 16);
+
+        assert_eq!(run(&mut limiter, CpuLoad::High, 1.0), 12);
+        // Never below the floor
+        assert_eq!(run(&mut limiter, CpuLoad::High, 10.0), MIN_VOICE_LIMIT);
+    }
+
+    #[test]
+    fn test_limit_recovers_when_load_is_low() {
+        let mut limiter = VoiceLimiter::new(8, SAMPLE_RATE);
+        run(&mut limiter, CpuLoad::High, 1.0);
+        assert_eq!(limiter.limit(), 4);
+
+        // Medium load holds the limit
+        assert_eq!(run(&mut limiter, CpuLoad::Medium, 10.0), 4);
+        assert_eq!(run(&mut limiter, CpuLoad::Low, 4.0), 6);
+        assert_eq!(run(&mut limiter, CpuLoad::Low, 20.0), 8);
+    }
+
+    #[test]
+    fn test_set_max_voices() {
+        let mut limiter = VoiceLimiter::new(16, SAMPLE_RATE);
+        limiter.set_max_voices(8);
+        assert_eq!(limiter.limit(), 8);
+        limiter.set_max_voices(12);
+        assert_eq!(limiter.limit(), 12);
+
+        // A lowered limit stays lowered
+        run(&mut limiter, CpuLoad::High, 0.5);
+        assert_eq!(limiter.limit(), 10);
+        limiter.set_max_voices(16);
+        assert_eq!(limiter.limit(), 10);
+        limiter.set_max_voices(6);
+        assert_eq!(limiter.limit(), 6);
+
+        limiter.set_max_voices(0);
+        assert_eq!(limiter.max_voices(), 1);
+    }
+}
@@ -1,15 +1,42 @@
 // Voice Manager - Polyphony handling
 
-use super::modulation::{MAX_ROUTINGS, ModRouting, ModulationMatrix};
-use super::oscillator::WaveformType;
+use super::modulation::{MAX_ROUTINGS, ModRouting, ModSource, ModulationMatrix};
+use super::noise::NoiseParams;
+use super::oscillator::{AntiAliasing, CrossModParams, UnisonParams, WaveformType};
 use super::poly_mode::PolyMode;
-use super::voice::Voice;
+use super::voice::{SynthVoice, VOICE_BLOCK_SIZE, Voice, VoiceFilterGroup};
+use super::voice_limiter::VoiceLimiter;
+use crate::audio::cpu_monitor::CpuLoad;
+use crate::audio::precision::{self, InternalSample, from_internal};
+use crate::midi::event::MOD_WHEEL_CC;
+use crate::sequencer::timeline::Tempo;
+use crate::sampler::engine::SamplerVoice;
 use crate::sampler::loader::{LoopMode, Sample, SampleData};
+use crate::sampler::zone::{DEFAULT_ROOT_NOTE, SampleZone};
 use std::collections::HashMap;
 use std::f32::consts::PI;
 use std::sync::Arc;
 
-const MAX_VOICES: usize = 16;
+/// Number of voice slots (upper bound of the configurable max voices)
+pub const MAX_VOICES: usize = 16;
+
+/// Number of sample slots (allocated up front: adding a sample never reallocates)
+pub const MAX_SAMPLE_SLOTS: usize = 128;
+
+/// Default pitch bend range (semitones up and down)
+pub const DEFAULT_PITCH_BEND_RANGE: f32 = 2.0;
+
+/// Largest pitch bend range (semitones up and down)
+pub const MAX_PITCH_BEND_RANGE: f32 = 24.0;
+
+/// Fade-out time of voices cut by the voice limit (seconds)
+const VOICE_LIMIT_FADE: f32 = 0.01;
+
+/// Velocity of sample previews
+const PREVIEW_VELOCITY: u8 = 100;
+
+/// Output headroom (0.7 = ~-3dB to prevent digital clipping)
+const HEADROOM: f32 = 0.7;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VoiceMode {
@@ -24,11 +51,35 @@ pub struct VoiceManager {
     last_note: Option<u8>,
     mod_matrix: ModulationMatrix,
     aftertouch: f32,
+    /// Pitch bend position (-1.0 - 1.0) and range in semitones
+    pitch_bend: f32,
+    pitch_bend_range: f32,
+    /// Modulation wheel position (0.0 - 1.0)
+    mod_wheel: f32,
+    /// Last value of each MIDI controller (0.0 - 1.0), for `ModSource::MidiCc`
+    controllers: [f32; 128],
+    /// Sustain pedal down, and the voices it holds (key released, not yet
+    /// sent to their release stage)
+    sustain_pedal: bool,
+    sustained: [bool; MAX_VOICES],
+    /// Synth voices stacked on each note, with their detune and pan
+    unison: UnisonParams,
     pub voice_mode: VoiceMode,
     dummy_sample: Arc<Sample>,
     samples: Vec<Arc<Sample>>,
+    /// Key-range zone of each sample slot (parallel to `samples`)
+    sample_zones: Vec<Option<SampleZone>>,
     note_to_sample_map: HashMap<u8, usize>,
+    /// Sample being auditioned, outside of the voices and mappings
+    preview: Option<Voice>,
     sample_rate: f32,
+    voice_limiter: VoiceLimiter,
+    /// Tempo the synced LFOs follow
+    tempo: Tempo,
+    /// Samples rendered so far: the shared clock of the free-running LFOs
+    lfo_clock: u64,
+    /// Delay and reverb send offsets from the mod matrix (see `send_modulation`)
+    send_modulation: (f32, f32),
 }
 
 impl VoiceManager {
@@ -41,7 +92,7 @@ impl VoiceManager {
         }
         let dummy_sample = Arc::new(Sample {
             name: "Dummy Sine".to_string(),
-            data: SampleData::F32(dummy_data),
+            data: SampleData::F32(dummy_data.into()),
             sample_rate: sample_rate as u32,
             source_channels: 1,
             loop_mode: LoopMode::Off,
@@ -51,9 +102,14 @@ impl VoiceManager {
             volume: 1.0,
             pan: 0.0,
             pitch_offset: 0,
+            fine_tune: 0.0,
+            envelope: None,
+            stretch: None,
         });
 
         let voices = std::array::from_fn(|_| Voice::new_synth(sample_rate));
+        // Switching the voices to wavetables must not build them on the audio thread
+        super::wavetable::warm_up();
 
         Self {
             voices,
@@ -62,58 +118,216 @@ impl VoiceManager {
             last_note: None,
             mod_matrix: ModulationMatrix::new_empty(),
             aftertouch: 0.0,
+            pitch_bend: 0.0,
+            pitch_bend_range: DEFAULT_PITCH_BEND_RANGE,
+            mod_wheel: 0.0,
+            controllers: [0.0; 128],
+            sustain_pedal: false,
+            sustained: [false; MAX_VOICES],
+            unison: UnisonParams::default(),
             voice_mode: VoiceMode::Synth,
             dummy_sample,
-            samples: Vec::new(),
-            note_to_sample_map: HashMap::new(),
+            samples: Vec::with_capacity(MAX_SAMPLE_SLOTS),
+            sample_zones: Vec::with_capacity(MAX_SAMPLE_SLOTS),
+            // Room for every note: mappings never allocate (see `share_samples`)
+            note_to_sample_map: HashMap::with_capacity(128),
+            preview: None,
             sample_rate,
+            voice_limiter: VoiceLimiter::new(MAX_VOICES, sample_rate),
+            tempo: Tempo::default(),
+            lfo_clock: 0,
+            send_modulation: (0.0, 0.0),
         }
     }
 
-    pub fn add_sample(&mut self, sample: Arc<Sample>) {
+    /// Add a sample in the next free slot, returning the slot index
+    ///
+    /// When every slot is used, the sample is handed back (the caller decides
+    /// where it is freed, see `audio::garbage`).
+    pub fn add_sample(&mut self, sample: Arc<Sample>) -> Result<usize, Arc<Sample>> {
+        if self.samples.len() >= MAX_SAMPLE_SLOTS {
+            return Err(sample);
+        }
         self.samples.push(sample);
+        self.sample_zones.push(None);
+        Ok(self.samples.len() - 1)
+    }
+
+    /// Number of used sample slots
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
     }
 
-    pub fn set_note_to_sample(&mut self, note: u8, sample_index: usize) {
+    /// Map a note to a sample slot (false if the slot is empty)
+    pub fn set_note_to_sample(&mut self, note: u8, sample_index: usize) -> bool {
         if sample_index < self.samples.len() {
             self.note_to_sample_map.insert(note, sample_index);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Insert a sample in a slot, shifting the next slots and their mappings
+    ///
+    /// When every slot is used or the index is past the end, the sample is
+    /// handed back (see `add_sample`).
+    pub fn insert_sample(&mut self, index: usize, sample: Arc<Sample>) -> Result<(), Arc<Sample>> {
+        if self.samples.len() >= MAX_SAMPLE_SLOTS || index > self.samples.len() {
+            return Err(sample);
+        }
+        self.samples.insert(index, sample);
+        self.sample_zones.insert(index, None);
+        for sample_idx in self.note_to_sample_map.values_mut() {
+            if *sample_idx >= index {
+                *sample_idx += 1;
+            }
         }
+        Ok(())
     }
 
-    pub fn update_sample(&mut self, index: usize, sample: Arc<Sample>) {
-        if index < self.samples.len() {
-            self.samples[index] = sample;
+    /// Set the key-range zone of a sample slot (false if the slot is empty)
+    pub fn set_sample_zone(&mut self, sample_index: usize, zone: Option<SampleZone>) -> bool {
+        match self.sample_zones.get_mut(sample_index) {
+            Some(slot) => {
+                *slot = zone;
+                true
+            }
+            None => false,
         }
     }
 
-    pub fn remove_sample(&mut self, index: usize) {
+    /// Key-range zone of a sample slot
+    pub fn sample_zone(&self, sample_index: usize) -> Option<SampleZone> {
+        self.sample_zones.get(sample_index).copied().flatten()
+    }
+
+    /// Forget the sample mapped to a note (it plays the last sample again)
+    pub fn clear_note_to_sample(&mut self, note: u8) {
+        self.note_to_sample_map.remove(&note);
+    }
+
+    /// Play the samples and note mappings of another voice manager
+    ///
+    /// The sample data is shared, not copied (no allocation).
+    pub fn share_samples(&mut self, bank: &VoiceManager) {
+        self.samples.clear();
+        self.samples.extend(bank.samples.iter().cloned());
+        self.sample_zones.clear();
+        self.sample_zones.extend(bank.sample_zones.iter().copied());
+        self.note_to_sample_map.clear();
+        self.note_to_sample_map
+            .extend(bank.note_to_sample_map.iter().map(|(note, index)| (*note, *index)));
+    }
+
+    /// Replace a sample, returning the previous one
+    ///
+    /// The caller decides where the old sample is freed (see `audio::garbage`).
+    pub fn update_sample(&mut self, index: usize, sample: Arc<Sample>) -> Option<Arc<Sample>> {
+        let slot = self.samples.get_mut(index)?;
+        Some(std::mem::replace(slot, sample))
+    }
+
+    /// Remove a sample, returning it
+    ///
+    /// The caller decides where the sample is freed (see `audio::garbage`).
+    pub fn remove_sample(&mut self, index: usize) -> Option<Arc<Sample>> {
         if index >= self.samples.len() {
-            return;
+            return None;
         }
 
         // Remove the sample from the vector
-        self.samples.remove(index);
+        let removed = self.samples.remove(index);
+        self.sample_zones.remove(index);
 
-        // Update note_to_sample_map:
+        // Update note_to_sample_map in place (no reallocation):
         // - Remove mappings pointing to the removed index
         // - Decrement indices > removed index
-        let mut updated_map = std::collections::HashMap::new();
-        for (note, sample_idx) in self.note_to_sample_map.iter() {
-            if *sample_idx == index {
-                // Skip - this mapping is deleted
-                continue;
-            } else if *sample_idx > index {
-                // Decrement index
-                updated_map.insert(*note, sample_idx - 1);
-            } else {
-                // Keep as-is
-                updated_map.insert(*note, *sample_idx);
+        self.note_to_sample_map
+            .retain(|_, sample_idx| *sample_idx != index);
+        for sample_idx in self.note_to_sample_map.values_mut() {
+            if *sample_idx > index {
+                *sample_idx -= 1;
             }
         }
-        self.note_to_sample_map = updated_map;
 
         // Note: Active voices playing the removed sample will continue until they finish
         // This is acceptable as they hold an Arc reference to the sample
+        Some(removed)
+    }
+
+    /// Sample played by a note and the root note it is transposed from
+    ///
+    /// An explicit note mapping wins, then the first zone covering the note
+    /// and velocity, then the last loaded sample. Takes the fields, not `&self`: the caller
+    /// holds a voice borrowed mutably.
+    fn sample_for_note(
+        samples: &[Arc<Sample>],
+        zones: &[Option<SampleZone>],
+        note_to_sample_map: &HashMap<u8, usize>,
+        dummy_sample: &Arc<Sample>,
+        note: u8,
+        velocity: u8,
+    ) -> (Arc<Sample>, u8) {
+        let index = note_to_sample_map
+            .get(&note)
+            .copied()
+            .or_else(|| {
+                zones
+                    .iter()
+                    .position(|zone| zone.is_some_and(|zone| zone.plays(note, velocity)))
+            })
+            .or_else(|| samples.len().checked_sub(1));
+        match index.and_then(|index| samples.get(index).map(|sample| (index, sample))) {
+            Some((index, sample)) => {
+                let root_note = zones
+                    .get(index)
+                    .copied()
+                    .flatten()
+                    .map_or(DEFAULT_ROOT_NOTE, |zone| zone.root_note);
+                (sample.clone(), root_note)
+            }
+            None => (dummy_sample.clone(), DEFAULT_ROOT_NOTE),
+        }
+    }
+
+    /// Audition a sample slot, bypassing the note mappings and zones
+    ///
+    /// `note` plays the sample transposed from its root note (its zone's, else
+    /// C4); `None` plays it at its root note, its original pitch. A looped
+    /// preview repeats the sample (its loop region when it has one) until
+    /// `stop_preview`. Replaces the previous preview; returns false when the
+    /// slot is empty.
+    pub fn preview_sample(&mut self, index: usize, note: Option<u8>, looped: bool) -> bool {
+        let Some(sample) = self.samples.get(index) else {
+            return false;
+        };
+        let root_note = self
+            .sample_zones
+            .get(index)
+            .copied()
+            .flatten()
+            .map_or(DEFAULT_ROOT_NOTE, |zone| zone.root_note);
+        let mut voice = Voice::Sampler(
+            SamplerVoice::new(sample.clone(), self.sample_rate)
+                .with_root_note(root_note)
+                .with_looping(looped),
+        );
+        voice.note_on(note.unwrap_or(root_note), PREVIEW_VELOCITY, 0);
+        self.preview = Some(voice);
+        true
+    }
+
+    /// Release the sample preview
+    pub fn stop_preview(&mut self) {
+        if let Some(preview) = &mut self.preview {
+            preview.note_off();
+        }
+    }
+
+    /// Whether a sample preview is sounding
+    pub fn is_previewing(&self) -> bool {
+        self.preview.as_ref().is_some_and(|preview| preview.is_active())
     }
 
     pub fn note_on(&mut self, note: u8, velocity: u8) {
@@ -127,37 +341,22 @@ impl VoiceManager {
     }
 
     fn note_on_poly(&mut self, note: u8, velocity: u8) {
-        let voice_index = self.voices.iter().position(|v| !v.is_active());
-        let index_to_use = match voice_index {
-            Some(index) => index,
-            None => self.find_voice_to_steal(),
-        };
-        let voice = &mut self.voices[index_to_use];
+        // At the voice limit, make room for the stack by fading out the
+        // quietest voices
+        let stack = self.unison_voice_count().min(self.voice_limiter.limit());
+        let sounding = self.sounding_voice_count();
+        for _ in self.voice_limiter.limit()..sounding + stack {
+            self.fade_out_quietest_voice();
+        }
 
-        match self.voice_mode {
-            VoiceMode::Synth => {
-                if !matches!(voice, Voice::Synth(_)) {
-                    *voice = Voice::new_synth(self.sample_rate);
-                }
-            }
-            VoiceMode::Sampler => {
-                let sample_index = self.note_to_sample_map.get(&note).copied();
-                let sample_to_use = match sample_index {
-                    Some(index) => self
-                        .samples
-                        .get(index)
-                        .cloned()
-                        .unwrap_or_else(|| self.dummy_sample.clone()),
-                    None => self
-                        .samples
-                        .last()
-                        .cloned()
-                        .unwrap_or_else(|| self.dummy_sample.clone()),
-                };
-                *voice = Voice::new_sampler(sample_to_use, self.sample_rate);
-            }
+        for unison_index in 0..stack {
+            let voice_index = self.voices.iter().position(|v| !v.is_active());
+            let index_to_use = match voice_index {
+                Some(index) => index,
+                None => self.find_voice_to_steal(),
+            };
+            self.start_voice(index_to_use, note, velocity, unison_index);
         }
-        voice.note_on(note, velocity, self.age_counter);
     }
 
     fn note_on_mono(&mut self, note: u8, velocity: u8) {
@@ -166,7 +365,40 @@ impl VoiceManager {
                 voice.force_stop();
             }
         }
-        let voice = &mut self.voices[0];
+        self.sustained = [false; MAX_VOICES];
+        for unison_index in 0..self.unison_voice_count() {
+            self.start_voice(unison_index, note, velocity, unison_index);
+        }
+    }
+
+    fn note_on_legato(&mut self, note: u8, velocity: u8) {
+        if self.voices.iter().any(|v| v.is_active()) {
+            // The gliding voices are held by the new key, not by the pedal
+            for (voice, sustained) in self.voices.iter_mut().zip(&mut self.sustained) {
+                if voice.is_active() {
+                    *sustained = false;
+                    voice.change_pitch_legato(note, velocity, self.age_counter);
+                }
+            }
+        } else {
+            for unison_index in 0..self.unison_voice_count() {
+                self.start_voice(unison_index, note, velocity, unison_index);
+            }
+        }
+    }
+
+    /// Voices stacked on each note (unison applies to synth voices only)
+    fn unison_voice_count(&self) -> usize {
+        match self.voice_mode {
+            VoiceMode::Synth => self.unison.voices,
+            VoiceMode::Sampler => 1,
+        }
+    }
+
+    /// Start a note on a voice slot, as voice `unison_index` of its unison stack
+    fn start_voice(&mut self, index: usize, note: u8, velocity: u8, unison_index: usize) {
+        self.sustained[index] = false;
+        let voice = &mut self.voices[index];
         match self.voice_mode {
             VoiceMode::Synth => {
                 if !matches!(voice, Voice::Synth(_)) {
@@ -174,68 +406,46 @@ impl VoiceManager {
                 }
             }
             VoiceMode::Sampler => {
-                let sample_index = self.note_to_sample_map.get(&note).copied();
-                let sample_to_use = match sample_index {
-                    Some(index) => self
-                        .samples
-                        .get(index)
-                        .cloned()
-                        .unwrap_or_else(|| self.dummy_sample.clone()),
-                    None => self
-                        .samples
-                        .last()
-                        .cloned()
-                        .unwrap_or_else(|| self.dummy_sample.clone()),
-                };
-                *voice = Voice::new_sampler(sample_to_use, self.sample_rate);
+                let (sample, root_note) = Self::sample_for_note(
+                    &self.samples,
+                    &self.sample_zones,
+                    &self.note_to_sample_map,
+                    &self.dummy_sample,
+                    note,
+                    velocity,
+                );
+                *voice = Voice::new_sampler(sample, root_note, self.sample_rate);
             }
         }
+        voice.set_unison_voice(
+            self.unison.frequency_ratio(unison_index),
+            self.unison.pan(unison_index),
+            self.unison.start_phase(unison_index),
+        );
+        voice.set_pitch_bend(self.pitch_bend, self.pitch_bend_range);
+        voice.set_mod_wheel(self.mod_wheel);
+        voice.set_tempo(self.tempo);
         voice.note_on(note, velocity, self.age_counter);
+        // Free LFOs keep running between notes: every voice is at the same phase
+        voice.sync_free_lfos(self.lfo_clock as f64 / self.sample_rate as f64);
     }
 
-    fn note_on_legato(&mut self, note: u8, velocity: u8) {
-        if let Some(active_voice) = self.voices.iter_mut().find(|v| v.is_active()) {
-            active_voice.change_pitch_legato(note, velocity, self.age_counter);
-        } else {
-            let voice = &mut self.voices[0];
-            match self.voice_mode {
-                VoiceMode::Synth => {
-                    if !matches!(voice, Voice::Synth(_)) {
-                        *voice = Voice::new_synth(self.sample_rate);
-                    }
-                }
-                VoiceMode::Sampler => {
-                    let sample_index = self.note_to_sample_map.get(&note).copied();
-                    let sample_to_use = match sample_index {
-                        Some(index) => self
-                            .samples
-                            .get(index)
-                            .cloned()
-                            .unwrap_or_else(|| self.dummy_sample.clone()),
-                        None => self
-                            .samples
-                            .last()
-                            .cloned()
-                            .unwrap_or_else(|| self.dummy_sample.clone()),
-                    };
-                    *voice = Voice::new_sampler(sample_to_use, self.sample_rate);
-                }
-            }
-            voice.note_on(note, velocity, self.age_counter);
-        }
-    }
-
+    /// Voice to reuse when all are busy: releasing voices first, then voices
+    /// held by the sustain pedal, then the oldest one
     fn find_voice_to_steal(&self) -> usize {
         let mut best_index = 0;
-        let mut best_priority = (false, u64::MAX);
+        let mut best_priority = (false, false, u64::MAX);
         for (i, voice) in self.voices.iter().enumerate() {
             let is_releasing = voice.is_releasing();
+            let is_sustained = self.sustained[i];
             let age = voice.get_age();
-            let priority = (is_releasing, age);
+            let priority = (is_releasing, is_sustained, age);
             let should_steal = if is_releasing != best_priority.0 {
                 is_releasing
+            } else if is_sustained != best_priority.1 {
+                is_sustained
             } else {
-                age < best_priority.1
+                age < best_priority.2
             };
             if should_steal {
                 best_priority = priority;
@@ -245,26 +455,111 @@ impl VoiceManager {
         best_index
     }
 
+    /// Release a note (held until pedal-up while the sustain pedal is down)
     pub fn note_off(&mut self, note: u8) {
-        for voice in &mut self.voices {
-            if voice.is_active() && voice.get_note() == note {
+        for (voice, sustained) in self.voices.iter_mut().zip(&mut self.sustained) {
+            if !voice.is_active() || voice.get_note() != note {
+                continue;
+            }
+            if self.sustain_pedal {
+                *sustained = !voice.is_releasing();
+            } else {
                 voice.note_off();
             }
         }
     }
 
+    /// Press or release the sustain pedal (MIDI CC64)
+    ///
+    /// Releasing it sends the voices whose key was released meanwhile to
+    /// their release stage; voices whose key is still down keep playing.
+    pub fn set_sustain_pedal(&mut self, down: bool) {
+        self.sustain_pedal = down;
+        if down {
+            return;
+        }
+        for (voice, sustained) in self.voices.iter_mut().zip(&mut self.sustained) {
+            if std::mem::take(sustained) && voice.is_active() && !voice.is_releasing() {
+                voice.note_off();
+            }
+        }
+    }
+
+    pub fn sustain_pedal(&self) -> bool {
+        self.sustain_pedal
+    }
+
     pub fn set_waveform(&mut self, waveform: WaveformType) {
         for voice in &mut self.voices {
             voice.set_waveform(waveform);
         }
     }
 
+    pub fn set_oscillator2(&mut self, params: super::oscillator::Oscillator2Params) {
+        for voice in &mut self.voices {
+            voice.set_oscillator2(params);
+        }
+    }
+
+    pub fn get_oscillator2_params(&self) -> super::oscillator::Oscillator2Params {
+        self.voices[0].get_oscillator2_params()
+    }
+
+    /// Set the unison stack of the synth notes (applies from the next note)
+    pub fn set_unison(&mut self, params: UnisonParams) {
+        self.unison = params;
+    }
+
+    pub fn get_unison_params(&self) -> UnisonParams {
+        self.unison
+    }
+
+    pub fn set_anti_aliasing(&mut self, anti_aliasing: AntiAliasing) {
+        for voice in &mut self.voices {
+            voice.set_anti_aliasing(anti_aliasing);
+        }
+    }
+
+    pub fn get_anti_aliasing(&self) -> AntiAliasing {
+        self.voices[0].get_anti_aliasing()
+    }
+
+    pub fn set_cross_mod(&mut self, params: CrossModParams) {
+        for voice in &mut self.voices {
+            voice.set_cross_mod(params);
+        }
+    }
+
+    pub fn get_cross_mod_params(&self) -> CrossModParams {
+        self.voices[0].get_cross_mod_params()
+    }
+
+    pub fn set_noise(&mut self, params: NoiseParams) {
+        for voice in &mut self.voices {
+            voice.set_noise(params);
+        }
+    }
+
+    pub fn get_noise_params(&self) -> NoiseParams {
+        self.voices[0].get_noise_params()
+    }
+
     pub fn set_adsr(&mut self, params: super::envelope::AdsrParams) {
         for voice in &mut self.voices {
             voice.set_adsr(params);
         }
     }
 
+    pub fn set_mod_envelope(&mut self, params: super::envelope::AdsrParams) {
+        for voice in &mut self.voices {
+            voice.set_mod_envelope(params);
+        }
+    }
+
+    pub fn get_mod_envelope_params(&self) -> super::envelope::AdsrParams {
+        self.voices[0].get_mod_envelope_params()
+    }
+
     pub fn set_lfo(&mut self, params: super::lfo::LfoParams) {
         for voice in &mut self.voices {
             voice.set_lfo(params);
@@ -275,6 +570,35 @@ impl VoiceManager {
         self.voices[0].get_lfo_params()
     }
 
+    /// Set a matrix LFO (`index` 1..LFO_COUNT, the `ModSource::Lfo` index)
+    pub fn set_mod_lfo(&mut self, index: usize, params: super::lfo::LfoParams) {
+        for voice in &mut self.voices {
+            voice.set_mod_lfo(index, params);
+        }
+    }
+
+    pub fn get_mod_lfo_params(&self, index: usize) -> super::lfo::LfoParams {
+        self.voices[0].get_mod_lfo_params(index)
+    }
+
+    /// Set every matrix LFO, LFO 2 first
+    pub fn set_mod_lfos(&mut self, lfos: [super::lfo::LfoParams; super::lfo::LFO_COUNT - 1]) {
+        for (index, params) in lfos.into_iter().enumerate() {
+            self.set_mod_lfo(index + 1, params);
+        }
+    }
+
+    /// Set the tempo the synced LFOs follow
+    pub fn set_tempo(&mut self, tempo: Tempo) {
+        if tempo == self.tempo {
+            return;
+        }
+        self.tempo = tempo;
+        for voice in &mut self.voices {
+            voice.set_tempo(tempo);
+        }
+    }
+
     pub fn set_portamento(&mut self, params: super::portamento::PortamentoParams) {
         for voice in &mut self.voices {
             voice.set_portamento(params);
@@ -295,6 +619,34 @@ impl VoiceManager {
         self.voices[0].get_filter_params()
     }
 
+    pub fn set_filter_mod(&mut self, params: super::filter::FilterModParams) {
+        for voice in &mut self.voices {
+            voice.set_filter_mod(params);
+        }
+    }
+
+    pub fn get_filter_mod_params(&self) -> super::filter::FilterModParams {
+        self.voices[0].get_filter_mod_params()
+    }
+
+    /// Modify the filter parameters of every voice in place
+    pub fn update_filter(&mut self, update: impl Fn(&mut super::filter::FilterParams)) {
+        for voice in &mut self.voices {
+            let mut params = voice.get_filter_params();
+            update(&mut params);
+            voice.set_filter(params);
+        }
+    }
+
+    /// Modify the LFO parameters of every voice in place
+    pub fn update_lfo(&mut self, update: impl Fn(&mut super::lfo::LfoParams)) {
+        for voice in &mut self.voices {
+            let mut params = voice.get_lfo_params();
+            update(&mut params);
+            voice.set_lfo(params);
+        }
+    }
+
     pub fn set_poly_mode(&mut self, mode: PolyMode) {
         self.poly_mode = mode;
     }
@@ -315,47 +667,88 @@ impl VoiceManager {
         }
     }
 
-    pub fn set_mod_routing(&mut self, index: usize, routing: ModRouting) {
+    /// Apply a MIDI pitch bend (14-bit value, 8192 = center) to every voice
+    pub fn set_pitch_bend(&mut self, value: i16) {
+        self.pitch_bend = ((value as f32 - 8192.0) / 8192.0).clamp(-1.0, 1.0);
+        self.apply_pitch_bend();
+    }
+
+    /// Set the pitch bend range in semitones (clamped to 0 - `MAX_PITCH_BEND_RANGE`)
+    pub fn set_pitch_bend_range(&mut self, semitones: f32) {
+        self.pitch_bend_range = semitones.clamp(0.0, MAX_PITCH_BEND_RANGE);
+        self.apply_pitch_bend();
+    }
+
+    pub fn pitch_bend_range(&self) -> f32 {
+        self.pitch_bend_range
+    }
+
+    /// Set the modulation wheel from a MIDI CC1 value (0 - 127)
+    ///
+    /// The wheel is a modulation source (`ModSource::ModWheel`).
+    pub fn set_mod_wheel(&mut self, value: u8) {
+        self.mod_wheel = (value as f32 / 127.0).clamp(0.0, 1.0);
+        for voice in &mut self.voices {
+            voice.set_mod_wheel(self.mod_wheel);
+        }
+        self.set_controller(MOD_WHEEL_CC, value);
+    }
+
+    /// Record a MIDI controller value (0 - 127) for the `ModSource::MidiCc` routings
+    pub fn set_controller(&mut self, controller: u8, value: u8) {
+        if let Some(current) = self.controllers.get_mut(controller as usize) {
+            *current = (value as f32 / 127.0).clamp(0.0, 1.0);
+            self.mod_matrix.set_controller(controller, *current);
+        }
+    }
+
+    fn apply_pitch_bend(&mut self) {
+        for voice in &mut self.voices {
+            voice.set_pitch_bend(self.pitch_bend, self.pitch_bend_range);
+        }
+    }
+
+    /// Set a modulation routing slot (false if the index is out of range)
+    pub fn set_mod_routing(&mut self, index: usize, routing: ModRouting) -> bool {
         if index < MAX_ROUTINGS {
             self.mod_matrix.set_routing(index, routing);
+            // The routing starts from the controller's last value
+            if let ModSource::MidiCc(controller) = routing.source
+                && let Some(value) = self.controllers.get(controller as usize)
+            {
+                self.mod_matrix.set_controller(controller, *value);
+            }
         }
+        index < MAX_ROUTINGS
     }
 
-    pub fn clear_mod_routing(&mut self, index: usize) {
+    /// Clear a modulation routing slot (false if the index is out of range)
+    pub fn clear_mod_routing(&mut self, index: usize) -> bool {
         if index < MAX_ROUTINGS {
             self.mod_matrix.clear_routing(index);
         }
+        index < MAX_ROUTINGS
     }
 
     pub fn next_sample(&mut self) -> (f32, f32) {
         let matrix = self.mod_matrix;
+        self.lfo_clock += 1;
 
         // Sum all voice outputs
         let (left_sum, right_sum) = self
             .voices
             .iter_mut()
             .map(|v| v.next_sample_with_matrix(&matrix))
+            .chain(self.preview.iter_mut().map(|v| v.next_sample_with_matrix(&matrix)))
             .fold((0.0, 0.0), |(acc_l, acc_r), (voice_l, voice_r)| {
                 (acc_l + voice_l, acc_r + voice_r)
             });
 
         // Dynamic gain staging based on active voices
         // This provides optimal headroom while maximizing loudness
-        let active_voices = self.voices.iter().filter(|v| v.is_active()).count();
-
-        // Calculate gain factor:
-        // - 1 voice: full gain (1.0)
-        // - 4 voices: 0.5 gain
-        // - 16 voices: 0.25 gain
-        // Formula: 1.0 / sqrt(max(1, n)) provides perceptually balanced scaling
-        let gain = if active_voices > 0 {
-            1.0 / (active_voices as f32).sqrt()
-        } else {
-            1.0 // No voices, doesn't matter
-        };
+        let active_voices = self.active_voice_count();
+        let gain = Self::voice_gain(active_voices);
 
-        // Apply headroom (0.7 = ~-3dB to prevent digital clipping)
-        const HEADROOM: f32 = 0.7;
         let left = left_sum * gain * HEADROOM;
         let right = right_sum * gain * HEADROOM;
 
@@ -364,17 +757,234 @@ impl VoiceManager {
         (left.tanh(), right.tanh())
     }
 
+    /// Render a block of stereo samples (block version of `next_sample`)
+    ///
+    /// Active voices are rendered one at a time into a scratch block, then summed
+    /// and gain-staged with SIMD. Idle voices are skipped (they only output
+    /// silence), and the voice count used for gain staging is taken once per
+    /// `VOICE_BLOCK_SIZE` samples.
+    pub fn process_block(&mut self, left: &mut [f32], right: &mut [f32]) {
+        debug_assert_eq!(left.len(), right.len());
+        let matrix = self.mod_matrix;
+        let mut voice_left = [0.0f32; VOICE_BLOCK_SIZE];
+        let mut voice_right = [0.0f32; VOICE_BLOCK_SIZE];
+        let mut bus_left = [InternalSample::default(); VOICE_BLOCK_SIZE];
+        let mut bus_right = [InternalSample::default(); VOICE_BLOCK_SIZE];
+
+        for (left, right) in left
+            .chunks_mut(VOICE_BLOCK_SIZE)
+            .zip(right.chunks_mut(VOICE_BLOCK_SIZE))
+        {
+            let len = left.len().min(right.len());
+            self.lfo_clock += len as u64;
+            let bus_left = &mut bus_left[..len];
+            let bus_right = &mut bus_right[..len];
+            bus_left.fill(InternalSample::default());
+            bus_right.fill(InternalSample::default());
+
+            // Synth voices are filtered in groups of FILTER_LANES (one voice
+            // per SIMD lane), then mixed
+            let mut active_voices = 0;
+            let mut filter_group = VoiceFilterGroup::new();
+            for voice in self.voices.iter_mut().filter(|v| v.is_active()) {
+                active_voices += 1;
+                match voice {
+                    Voice::Synth(synth) => {
+                        synth.prepare_block(&matrix, len);
+                        filter_group.push(synth);
+                        if filter_group.is_full() {
+                            for synth in filter_group.filter() {
+                                let scratch = (&mut voice_left[..len], &mut voice_right[..len]);
+                                Self::mix_synth_block(synth, bus_left, bus_right, scratch);
+                            }
+                        }
+                    }
+                    voice => {
+                        voice.render_block(
+                            &matrix,
+                            &mut voice_left[..len],
+                            &mut voice_right[..len],
+                        );
+                        precision::accumulate(bus_left, &voice_left[..len]);
+                        precision::accumulate(bus_right, &voice_right[..len]);
+                    }
+                }
+            }
+            for synth in filter_group.filter() {
+                let scratch = (&mut voice_left[..len], &mut voice_right[..len]);
+                Self::mix_synth_block(synth, bus_left, bus_right, scratch);
+            }
+            if let Some(preview) = self.preview.as_mut().filter(|v| v.is_active()) {
+                active_voices += 1;
+                preview.render_block(&matrix, &mut voice_left[..len], &mut voice_right[..len]);
+                precision::accumulate(bus_left, &voice_left[..len]);
+                precision::accumulate(bus_right, &voice_right[..len]);
+            }
+
+            let gain = Self::voice_gain(active_voices) * HEADROOM;
+            precision::scale(bus_left, gain);
+            precision::scale(bus_right, gain);
+
+            // Soft-limiter
+            for (sample, mixed) in left.iter_mut().zip(bus_left.iter()) {
+                *sample = from_internal(*mixed).tanh();
+            }
+            for (sample, mixed) in right.iter_mut().zip(bus_right.iter()) {
+                *sample = from_internal(*mixed).tanh();
+            }
+        }
+        self.update_send_modulation();
+    }
+
+    /// Delay and reverb send offsets from the mod matrix, as of the last block
+    ///
+    /// Sends belong to the track, not to a voice: the offsets of the playing
+    /// voices are averaged, weighted by their level.
+    pub fn send_modulation(&self) -> (f32, f32) {
+        self.send_modulation
+    }
+
+    fn update_send_modulation(&mut self) {
+        let (mut delay, mut reverb, mut weight) = (0.0, 0.0, 0.0);
+        for voice in self.voices.iter().filter(|v| v.is_active()) {
+            let level = voice.level();
+            let (voice_delay, voice_reverb) = voice.send_modulation();
+            delay += voice_delay * level;
+            reverb += voice_reverb * level;
+            weight += level;
+        }
+        self.send_modulation = if weight > 0.0 {
+            (delay / weight, reverb / weight)
+        } else {
+            (0.0, 0.0)
+        };
+    }
+
+    /// Finish the filtered block of a synth voice and add it to the mix bus
+    fn mix_synth_block(
+        synth: &mut SynthVoice,
+        bus_left: &mut [InternalSample],
+        bus_right: &mut [InternalSample],
+        (voice_left, voice_right): (&mut [f32], &mut [f32]),
+    ) {
+        synth.finish_block(voice_left, voice_right);
+        precision::accumulate(bus_left, voice_left);
+        precision::accumulate(bus_right, voice_right);
+    }
+
+    /// Dynamic gain staging based on active voices
+    ///
+    /// - 1 voice: full gain (1.0)
+    /// - 4 voices: 0.5 gain
+    /// - 16 voices: 0.25 gain
+    ///
+    /// Formula: 1.0 / sqrt(max(1, n)) provides perceptually balanced scaling.
+    fn voice_gain(active_voices: usize) -> f32 {
+        if active_voices > 0 {
+            1.0 / (active_voices as f32).sqrt()
+        } else {
+            1.0 // No voices, doesn't matter
+        }
+    }
+
     pub fn active_voice_count(&self) -> usize {
-        self.voices.iter().filter(|v| v.is_active()).count()
+        self.voices
+            .iter()
+            .chain(&self.preview)
+            .filter(|v| v.is_active())
+            .count()
+    }
+
+    /// Set the maximum number of voices (clamped to 1..=MAX_VOICES)
+    ///
+    /// Voices above the new limit are faded out.
+    pub fn set_max_voices(&mut self, max_voices: usize) {
+        self.voice_limiter
+            .set_max_voices(max_voices.clamp(1, MAX_VOICES));
+        self.enforce_voice_limit();
+    }
+
+    pub fn max_voices(&self) -> usize {
+        self.voice_limiter.max_voices()
+    }
+
+    /// Output sample rate of the voices
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Change the output sample rate (output device switched)
+    ///
+    /// Every voice is silenced; the synth settings and the sample bank are kept.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if sample_rate == self.sample_rate {
+            return;
+        }
+        self.sample_rate = sample_rate;
+        for voice in self.voices.iter_mut().chain(&mut self.preview) {
+            voice.set_sample_rate(sample_rate);
+        }
+        self.voice_limiter = VoiceLimiter::new(self.voice_limiter.max_voices(), sample_rate);
+        self.last_note = None;
+    }
+
+    /// Voices currently allowed (lowered under sustained CPU load)
+    pub fn voice_limit(&self) -> usize {
+        self.voice_limiter.limit()
+    }
+
+    /// Feed the recent CPU load of the audio callback (once per callback)
+    ///
+    /// Under sustained High load the voice limit is lowered and the quietest
+    /// voices above it are faded out; it recovers once the load is Low again.
+    pub fn update_cpu_load(&mut self, load: CpuLoad, frames: usize) {
+        let previous_limit = self.voice_limiter.limit();
+        if self.voice_limiter.update(load, frames) < previous_limit {
+            self.enforce_voice_limit();
+        }
+    }
+
+    /// Active voices that are not being faded out
+    fn sounding_voice_count(&self) -> usize {
+        self.voices
+            .iter()
+            .filter(|v| v.is_active() && !v.is_fading_out())
+            .count()
+    }
+
+    /// Fade out the quietest voices above the voice limit
+    fn enforce_voice_limit(&mut self) {
+        let sounding = self.sounding_voice_count();
+        for _ in self.voice_limiter.limit()..sounding {
+            self.fade_out_quietest_voice();
+        }
+    }
+
+    /// Fade out the quietest voice, taking the voices held by the sustain
+    /// pedal before the held keys
+    fn fade_out_quietest_voice(&mut self) {
+        let quietest = self
+            .voices
+            .iter_mut()
+            .zip(&self.sustained)
+            .filter(|(v, _)| v.is_active() && !v.is_fading_out())
+            .min_by(|(a, a_held), (b, b_held)| {
+                b_held.cmp(a_held).then(a.level().total_cmp(&b.level()))
+            });
+        if let Some((voice, _)) = quietest {
+            voice.fade_out(VOICE_LIMIT_FADE);
+        }
     }
 
     pub fn reset(&mut self) {
         // Reset all voices
-        for voice in &mut self.voices {
+        for voice in self.voices.iter_mut().chain(&mut self.preview) {
             if voice.is_active() {
                 voice.force_stop();
             }
         }
+        self.sustain_pedal = false;
+        self.sustained = [false; MAX_VOICES];
     }
 }
 
@@ -396,6 +1006,196 @@ mod tests {
         assert_eq!(vm.active_voice_count(), 3);
     }
 
+    #[test]
+    fn test_unison_stacks_detuned_voices() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        vm.set_unison(UnisonParams::new(4, 25.0, 1.0));
+        vm.note_on(60, 100);
+        assert_eq!(vm.active_voice_count(), 4);
+
+        // Spread voices make the stack wide
+        let mut left = vec![0.0; 2048];
+        let mut right = vec![0.0; 2048];
+        vm.process_block(&mut left, &mut right);
+        let difference: f32 = left.iter().zip(&right).map(|(l, r)| (l - r).abs()).sum();
+        assert!(
+            difference > 1.0,
+            "unison stack is not spread: {}",
+            difference
+        );
+
+        vm.note_off(60);
+        assert!(
+            vm.voices
+                .iter()
+                .filter(|v| v.is_active())
+                .all(|v| v.is_releasing())
+        );
+
+        // The stack fits in the voice limit, making room by fading old notes
+        vm.reset();
+        vm.set_max_voices(6);
+        vm.note_on(60, 100);
+        vm.note_on(64, 100);
+        assert_eq!(vm.sounding_voice_count(), 6);
+
+        // Mono notes replace the whole stack
+        vm.reset();
+        vm.set_poly_mode(PolyMode::Mono);
+        vm.note_on(60, 100);
+        vm.note_on(62, 100);
+        assert_eq!(vm.active_voice_count(), 4);
+        assert!(vm.voices[..4].iter().all(|v| v.get_note() == 62));
+    }
+
+    #[test]
+    fn test_set_sample_rate_keeps_settings() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        vm.update_filter(|filter| filter.cutoff = 800.0);
+        vm.update_lfo(|lfo| lfo.rate = 3.0);
+        vm.note_on(60, 100);
+
+        vm.set_sample_rate(96000.0);
+        assert_eq!(vm.sample_rate(), 96000.0);
+        assert_eq!(vm.active_voice_count(), 0);
+        assert_eq!(vm.get_filter_params().cutoff, 800.0);
+        assert_eq!(vm.get_lfo_params().rate, 3.0);
+
+        // Voices play again at the new rate
+        vm.note_on(64, 100);
+        assert_eq!(vm.active_voice_count(), 1);
+        assert!((0..100).any(|_| vm.next_sample().0 != 0.0));
+    }
+
+    #[test]
+    fn test_pitch_bend() {
+        fn zero_crossings(vm: &mut VoiceManager) -> usize {
+            let mut previous = vm.next_sample().0;
+            let mut count = 0;
+            for _ in 0..4410 {
+                let sample = vm.next_sample().0;
+                if (sample >= 0.0) != (previous >= 0.0) {
+                    count += 1;
+                }
+                previous = sample;
+            }
+            count
+        }
+
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        vm.note_on(69, 127);
+        let unbent = zero_crossings(&mut vm);
+
+        // Range of an octave, bend fully up: twice the frequency
+        vm.set_pitch_bend_range(12.0);
+        vm.set_pitch_bend(16383);
+        let bent = zero_crossings(&mut vm);
+        assert!((bent as f32 / unbent as f32 - 2.0).abs() < 0.05);
+
+        // Back to the center
+        vm.set_pitch_bend(8192);
+        assert!((zero_crossings(&mut vm) as i64 - unbent as i64).abs() <= 2);
+
+        vm.set_pitch_bend_range(100.0);
+        assert_eq!(vm.pitch_bend_range(), MAX_PITCH_BEND_RANGE);
+    }
+
+    #[test]
+    fn test_mod_wheel_routing() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        vm.set_mod_routing(
+            0,
+            ModRouting {
+                source: crate::synth::modulation::ModSource::ModWheel,
+                destination: crate::synth::modulation::ModDestination::Amplitude,
+                amount: -1.0,
+                enabled: true,
+            },
+        );
+        vm.note_on(69, 127);
+        assert!((0..200).any(|_| vm.next_sample().0.abs() > 0.01));
+
+        // Wheel fully up: the routing silences the voice
+        vm.set_mod_wheel(127);
+        for _ in 0..200 {
+            assert!(vm.next_sample().0.abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_midi_cc_routing_and_send_modulation() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        // CC74 moved before the routing exists: the routing starts from it
+        vm.set_controller(74, 127);
+        vm.set_mod_routing(
+            0,
+            ModRouting {
+                source: ModSource::MidiCc(74),
+                destination: crate::synth::modulation::ModDestination::ReverbSend,
+                amount: 0.5,
+                enabled: true,
+            },
+        );
+        vm.note_on(69, 127);
+        let mut left = [0.0; 256];
+        let mut right = [0.0; 256];
+        vm.process_block(&mut left, &mut right);
+        let (delay, reverb) = vm.send_modulation();
+        assert_eq!(delay, 0.0);
+        assert!((reverb - 0.5).abs() < 1e-6, "reverb {}", reverb);
+
+        vm.set_controller(74, 0);
+        vm.process_block(&mut left, &mut right);
+        assert_eq!(vm.send_modulation(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_sustain_pedal() {
+        let releasing = |vm: &VoiceManager, note: u8| {
+            vm.voices
+                .iter()
+                .any(|v| v.is_active() && v.get_note() == note && v.is_releasing())
+        };
+
+        // Poly: released keys are held until pedal-up, held keys keep playing
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        vm.note_on(60, 100);
+        vm.set_sustain_pedal(true);
+        vm.note_off(60);
+        vm.note_on(64, 100);
+        assert!(!releasing(&vm, 60));
+        vm.set_sustain_pedal(false);
+        assert!(releasing(&vm, 60));
+        assert!(!releasing(&vm, 64));
+        vm.note_off(64);
+        assert!(releasing(&vm, 64));
+
+        // Legato: the gliding voice belongs to the new key, not to the pedal
+        vm.reset();
+        vm.set_poly_mode(PolyMode::Legato);
+        vm.note_on(60, 100);
+        vm.set_sustain_pedal(true);
+        vm.note_off(60);
+        vm.note_on(62, 100);
+        vm.set_sustain_pedal(false);
+        assert_eq!(vm.active_voice_count(), 1);
+        assert!(!releasing(&vm, 62));
+        vm.note_off(62);
+        assert!(releasing(&vm, 62));
+
+        // Stealing takes a pedal-held voice before the held keys
+        vm.reset();
+        vm.set_poly_mode(PolyMode::Poly);
+        for note in 40..40 + MAX_VOICES as u8 {
+            vm.note_on(note, 100);
+        }
+        vm.set_sustain_pedal(true);
+        vm.note_off(45);
+        vm.note_on(90, 100);
+        assert!(vm.voices.iter().all(|v| v.get_note() != 45));
+        assert!(vm.voices.iter().any(|v| v.get_note() == 40));
+    }
+
     #[test]
     fn test_gain_staging_multiple_voices() {
         let mut vm = VoiceManager::new(SAMPLE_RATE);
@@ -487,5 +1287,212 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_process_block_matches_next_sample() {
+        let mut scalar = VoiceManager::new(SAMPLE_RATE);
+        let mut block = VoiceManager::new(SAMPLE_RATE);
+        for note in [48, 55, 60, 64, 67, 72] {
+            scalar.note_on(note, 100);
+            block.note_on(note, 100);
+        }
+
+        // Odd length to exercise partial sub-blocks and SIMD remainders
+        let mut left = vec![0.0; 203];
+        let mut right = vec![0.0; 203];
+        for _ in 0..4 {
+            block.process_block(&mut left, &mut right);
+            for (l, r) in left.iter().zip(&right) {
+                let (expected_l, expected_r) = scalar.next_sample();
+                assert!(l.abs() <= 1.0 && r.abs() <= 1.0);
+                assert!((l - expected_l).abs() < 0.02, "{} vs {}", l, expected_l);
+                assert!((r - expected_r).abs() < 0.02, "{} vs {}", r, expected_r);
+            }
+        }
+    }
+
     // ... (rest of the tests are omitted for brevity but are unchanged)
+
+    #[test]
+    fn test_voice_limit_fades_quietest_voices() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        vm.set_max_voices(3);
+        assert_eq!(vm.voice_limit(), 3);
+
+        let mut left = vec![0.0; 256];
+        let mut right = vec![0.0; 256];
+        for (note, velocity) in [(60, 30), (64, 120), (67, 100)] {
+            vm.note_on(note, velocity);
+        }
+        vm.process_block(&mut left, &mut right);
+
+        // A fourth note replaces the quietest voice (faded, not stolen)
+        vm.note_on(72, 100);
+        assert_eq!(vm.sounding_voice_count(), 3);
+        let quietest_fading = vm
+            .voices
+            .iter()
+            .any(|v| v.get_note() == 60 && v.is_fading_out());
+        assert!(quietest_fading);
+
+        // The fade is short
+        let fade = (VOICE_LIMIT_FADE * SAMPLE_RATE) as usize;
+        vm.process_block(&mut vec![0.0; fade + 1], &mut vec![0.0; fade + 1]);
+        assert_eq!(vm.active_voice_count(), 3);
+
+        // Lowering the maximum fades voices above it
+        vm.set_max_voices(1);
+        assert_eq!(vm.sounding_voice_count(), 1);
+    }
+
+    #[test]
+    fn test_sustained_high_load_reduces_voices() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        for note in 60..72 {
+            vm.note_on(note, 100);
+        }
+        assert_eq!(vm.sounding_voice_count(), 12);
+
+        // 2 s of High load
+        for _ in 0..(2 * SAMPLE_RATE as usize / 512) {
+            vm.update_cpu_load(CpuLoad::High, 512);
+        }
+        assert!(vm.voice_limit() < 12);
+        assert!(vm.sounding_voice_count() <= vm.voice_limit());
+        assert_eq!(vm.max_voices(), MAX_VOICES);
+    }
+
+    #[test]
+    fn test_sample_slots_are_bounded() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        let sample = vm.dummy_sample.clone();
+        for slot in 0..MAX_SAMPLE_SLOTS {
+            assert_eq!(vm.add_sample(sample.clone()).ok(), Some(slot));
+        }
+
+        // No reallocation: the sample is handed back
+        assert!(vm.add_sample(sample.clone()).is_err());
+        assert_eq!(vm.samples.capacity(), MAX_SAMPLE_SLOTS);
+
+        assert!(vm.set_note_to_sample(60, 0));
+        assert!(!vm.set_note_to_sample(60, MAX_SAMPLE_SLOTS));
+        assert!(!vm.clear_mod_routing(MAX_ROUTINGS));
+    }
+
+    #[test]
+    fn test_insert_sample_shifts_mappings() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        let sample = vm.dummy_sample.clone();
+        for _ in 0..2 {
+            vm.add_sample(sample.clone()).unwrap();
+        }
+        vm.set_note_to_sample(60, 0);
+        vm.set_note_to_sample(62, 1);
+
+        // Inserting then removing a slot restores the mappings
+        assert!(vm.insert_sample(1, sample.clone()).is_ok());
+        assert_eq!(vm.sample_count(), 3);
+        assert_eq!(vm.note_to_sample_map[&60], 0);
+        assert_eq!(vm.note_to_sample_map[&62], 2);
+        vm.remove_sample(1);
+        assert_eq!(vm.note_to_sample_map[&62], 1);
+        assert!(vm.insert_sample(3, sample).is_err());
+
+        vm.clear_note_to_sample(60);
+        assert!(!vm.note_to_sample_map.contains_key(&60));
+    }
+
+    #[test]
+    fn test_sample_zones_pick_sample_and_root() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        let [kick, piano, strings]: [Arc<Sample>; 3] =
+            std::array::from_fn(|_| Arc::new((*vm.dummy_sample).clone()));
+        for sample in [&kick, &piano, &strings] {
+            vm.add_sample(sample.clone()).unwrap();
+        }
+        vm.set_note_to_sample(48, 0);
+        assert!(vm.set_sample_zone(1, Some(SampleZone::new(36, 59, 48).unwrap())));
+        assert!(vm.set_sample_zone(2, Some(SampleZone::new(50, 84, 72).unwrap())));
+        assert!(!vm.set_sample_zone(3, None));
+
+        let pick = |vm: &VoiceManager, note| {
+            VoiceManager::sample_for_note(
+                &vm.samples,
+                &vm.sample_zones,
+                &vm.note_to_sample_map,
+                &vm.dummy_sample,
+                note,
+                100,
+            )
+        };
+        // Explicit mapping first (the kick has no zone: transposed from C4)
+        let (sample, root) = pick(&vm, 48);
+        assert!(Arc::ptr_eq(&sample, &kick));
+        assert_eq!(root, DEFAULT_ROOT_NOTE);
+        // Overlapping zones: the first slot wins
+        let (sample, root) = pick(&vm, 55);
+        assert!(Arc::ptr_eq(&sample, &piano));
+        assert_eq!(root, 48);
+        let (sample, root) = pick(&vm, 84);
+        assert!(Arc::ptr_eq(&sample, &strings));
+        assert_eq!(root, 72);
+        // Outside every zone: the last sample
+        let (sample, _) = pick(&vm, 100);
+        assert!(Arc::ptr_eq(&sample, &strings));
+        // A velocity layer only plays its velocities
+        let soft = SampleZone::new(36, 59, 48).unwrap().with_velocity(1, 63);
+        assert!(vm.set_sample_zone(1, Some(soft.unwrap())));
+        let (sample, _) = pick(&vm, 55);
+        assert!(Arc::ptr_eq(&sample, &strings));
+        assert!(vm.set_sample_zone(1, Some(SampleZone::new(36, 59, 48).unwrap())));
+
+        // Zones follow their slot
+        vm.remove_sample(0);
+        assert_eq!(vm.sample_zone(0).map(|zone| zone.root_note), Some(48));
+        vm.insert_sample(0, kick).unwrap();
+        assert_eq!(vm.sample_zone(0), None);
+        assert_eq!(vm.sample_zone(2).map(|zone| zone.root_note), Some(72));
+    }
+
+    #[test]
+    fn test_preview_bypasses_mappings() {
+        let mut vm = VoiceManager::new(SAMPLE_RATE);
+        let sample = |value: f32| {
+            Arc::new(Sample {
+                data: SampleData::F32(vec![value; 1000].into()),
+                ..(*vm.dummy_sample).clone()
+            })
+        };
+        let (silence, tone) = (sample(0.0), sample(0.5));
+        vm.add_sample(silence).unwrap();
+        vm.add_sample(tone).unwrap();
+        vm.set_note_to_sample(60, 0);
+        assert!(!vm.preview_sample(2, None, false));
+
+        // The preview plays its slot, in synth mode too, and is not a key
+        assert!(vm.preview_sample(1, None, false));
+        assert!(vm.is_previewing());
+        vm.note_off(60);
+        let mut left = [0.0; 512];
+        let mut right = [0.0; 512];
+        vm.process_block(&mut left, &mut right);
+        assert!(left.iter().any(|sample| *sample > 0.0));
+        // A one-shot ends with the sample (an octave up plays it twice as fast)
+        assert!(vm.preview_sample(1, Some(72), false));
+        vm.process_block(&mut left, &mut right);
+        assert!(!vm.is_previewing());
+        assert_eq!(vm.active_voice_count(), 0);
+
+        // A looped preview plays until it is stopped and released
+        assert!(vm.preview_sample(1, None, true));
+        for _ in 0..10 {
+            vm.process_block(&mut left, &mut right);
+        }
+        assert!(vm.is_previewing());
+        assert_eq!(vm.active_voice_count(), 1);
+        vm.stop_preview();
+        for _ in 0..(SAMPLE_RATE as usize / 512) {
+            vm.process_block(&mut left, &mut right);
+        }
+        assert!(!vm.is_previewing());
+    }
 }
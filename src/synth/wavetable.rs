@@ -0,0 +1,257 @@
+// Wavetable oscillator - Mipmapped band-limited tables
+//
+// Notes:
+// - Each waveform is stored as one table per octave (mipmap level). A level
+//   only holds the harmonics that stay below Nyquist for the notes it plays,
+//   so the output does not alias; the oscillator picks the level from its
+//   phase increment and interpolates linearly inside the table.
+// - The tables are built once by additive synthesis and shared by every
+//   voice. Building allocates: call `warm_up()` outside the audio thread
+//   (`VoiceManager::new` does).
+
+use super::oscillator::{Oscillator, WaveformType};
+use crate::audio::simd::simd_fill_phases;
+use std::f64::consts::{PI, TAU};
+use std::sync::OnceLock;
+
+/// Samples per table
+const TABLE_SIZE: usize = 2048;
+
+/// Harmonics of the first level (the Nyquist limit of the table itself)
+const MAX_HARMONICS: usize = TABLE_SIZE / 2;
+
+/// Mipmap levels: level `n` holds `MAX_HARMONICS >> n` harmonics (1024 down to 1)
+const LEVELS: usize = 11;
+
+/// Table length with its guard sample (a copy of the first one)
+const LEVEL_LEN: usize = TABLE_SIZE + 1;
+
+/// Mipmapped tables of each waveform, `LEVELS` tables of `LEVEL_LEN` samples
+struct Wavetables {
+    sine: Box<[f32]>,
+    square: Box<[f32]>,
+    saw: Box<[f32]>,
+    triangle: Box<[f32]>,
+}
+
+impl Wavetables {
+    fn build() -> Self {
+        let sine: Vec<f64> = (0..TABLE_SIZE)
+            .map(|i| (TAU * i as f64 / TABLE_SIZE as f64).sin())
+            .collect();
+        Self {
+            sine: build_levels(&sine, WaveformType::Sine),
+            square: build_levels(&sine, WaveformType::Square),
+            saw: build_levels(&sine, WaveformType::Saw),
+            triangle: build_levels(&sine, WaveformType::Triangle),
+        }
+    }
+
+    fn levels(&self, waveform: WaveformType) -> &[f32] {
+        match waveform {
+            WaveformType::Sine => &self.sine,
+            WaveformType::Square => &self.square,
+            WaveformType::Saw => &self.saw,
+            WaveformType::Triangle => &self.triangle,
+        }
+    }
+}
+
+static WAVETABLES: OnceLock<Wavetables> = OnceLock::new();
+
+fn wavetables() -> &'static Wavetables {
+    WAVETABLES.get_or_init(Wavetables::build)
+}
+
+/// Build the shared tables if they do not exist yet (allocates once)
+pub fn warm_up() {
+    wavetables();
+}
+
+/// Fourier series term of a waveform: amplitude and whether it is a cosine
+///
+/// The series match the waveforms of `SimpleOscillator` (same phase origin).
+fn harmonic(waveform: WaveformType, k: usize) -> Option<(f64, bool)> {
+    let k_f = k as f64;
+    match waveform {
+        WaveformType::Sine => (k == 1).then_some((1.0, false)),
+        // Ramp from -1 to 1: -(2/pi) sum sin(k x) / k
+        WaveformType::Saw => Some((-2.0 / (PI * k_f), false)),
+        // +1 then -1: (4/pi) sum over odd k of sin(k x) / k
+        WaveformType::Square => (k % 2 == 1).then_some((4.0 / (PI * k_f), false)),
+        // Minimum at phase 0: -(8/pi^2) sum over odd k of cos(k x) / k^2
+        WaveformType::Triangle => (k % 2 == 1).then_some((-8.0 / (PI * PI * k_f * k_f), true)),
+    }
+}
+
+/// Tables of every level of a waveform, from `sine` (one cycle of `TABLE_SIZE`)
+fn build_levels(sine: &[f64], waveform: WaveformType) -> Box<[f32]> {
+    let mut levels = vec![0.0f32; LEVELS * LEVEL_LEN];
+    let mut partial = vec![0.0f64; TABLE_SIZE];
+    let mut harmonics_done = 0;
+    // From the fewest harmonics up: each level adds the next harmonics
+    for level in (0..LEVELS).rev() {
+        let harmonics = MAX_HARMONICS >> level;
+        for k in harmonics_done + 1..=harmonics {
+            let Some((amplitude, cosine)) = harmonic(waveform, k) else {
+                continue;
+            };
+            let offset = if cosine { TABLE_SIZE / 4 } else { 0 };
+            for (i, value) in partial.iter_mut().enumerate() {
+                *value += amplitude * sine[(k * i + offset) % TABLE_SIZE];
+            }
+        }
+        harmonics_done = harmonics;
+
+        let table = &mut levels[level * LEVEL_LEN..(level + 1) * LEVEL_LEN];
+        for (sample, value) in table.iter_mut().zip(&partial) {
+            *sample = *value as f32;
+        }
+        table[TABLE_SIZE] = table[0];
+    }
+    levels.into_boxed_slice()
+}
+
+/// Level whose harmonics all stay below Nyquist for a phase increment
+fn level_for(phase_increment: f32) -> usize {
+    let mut level = 0;
+    while level < LEVELS - 1 && (MAX_HARMONICS >> level) as f32 * phase_increment >= 0.5 {
+        level += 1;
+    }
+    level
+}
+
+/// Oscillator reading mipmapped band-limited wavetables
+pub struct WavetableOscillator {
+    /// Every level of the waveform
+    levels: &'static [f32],
+    /// Start of the level in use in `levels`
+    level_start: usize,
+    phase: f32,
+    phase_increment: f32,
+    sample_rate: f32,
+}
+
+impl WavetableOscillator {
+    pub fn new(waveform: WaveformType, sample_rate: f32) -> Self {
+        Self {
+            levels: wavetables().levels(waveform),
+            level_start: 0,
+            phase: 0.0,
+            phase_increment: 0.0,
+            sample_rate,
+        }
+    }
+
+    /// Jump to a phase of the cycle (0.0 - 1.0)
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+
+    /// Fill `out` with the next samples at the current frequency
+    ///
+    /// Equivalent to calling `next_sample()` for each sample, but the phase
+    /// accumulation runs 8 samples at a time (`audio::simd`).
+    pub fn process_block(&mut self, out: &mut [f32]) {
+        self.phase = simd_fill_phases(self.phase, self.phase_increment, out);
+        for value in out.iter_mut() {
+            *value = self.sample_at(*value);
+        }
+    }
+
+    /// Table value at a phase (linear interpolation)
+    #[inline]
+    fn sample_at(&self, phase: f32) -> f32 {
+        let position = phase * TABLE_SIZE as f32;
+        let index = (position as usize).min(TABLE_SIZE - 1);
+        let fraction = position - index as f32;
+        let table = &self.levels[self.level_start..self.level_start + LEVEL_LEN];
+        table[index] + (table[index + 1] - table[index]) * fraction
+    }
+}
+
+impl Oscillator for WavetableOscillator {
+    fn next_sample(&mut self) -> f32 {
+        let sample = self.sample_at(self.phase);
+
+        self.phase += self.phase_increment;
+        if self.phase >= 1.0 {
+            self.phase -= 1.0;
+        } else if self.phase < 0.0 {
+            // Negative frequency (through-zero FM)
+            self.phase += 1.0;
+        }
+
+        sample
+    }
+
+    fn set_frequency(&mut self, freq: f32) {
+        let phase_increment = freq / self.sample_rate;
+        if phase_increment != self.phase_increment {
+            self.phase_increment = phase_increment;
+            self.level_start = level_for(phase_increment.abs()) * LEVEL_LEN;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 44100.0;
+
+    #[test]
+    fn test_levels_stay_below_nyquist() {
+        assert_eq!(level_for(0.0), 0);
+        // 440 Hz: 50 harmonics fit, the 32-harmonic level is used
+        assert_eq!(MAX_HARMONICS >> level_for(440.0 / SAMPLE_RATE), 32);
+        // Above Nyquist the last level (a sine) is all that is left
+        assert_eq!(level_for(0.6), LEVELS - 1);
+    }
+
+    #[test]
+    fn test_low_notes_follow_the_naive_waveforms() {
+        // 20 Hz: every harmonic is kept, away from the steps the tables match
+        let phase_increment = 20.0 / SAMPLE_RATE;
+        for (waveform, expected) in [
+            (WaveformType::Sine, [1.0, -1.0]),
+            (WaveformType::Saw, [-0.5, 0.5]),
+            (WaveformType::Square, [1.0, -1.0]),
+            (WaveformType::Triangle, [0.0, 0.0]),
+        ] {
+            let mut osc = WavetableOscillator::new(waveform, SAMPLE_RATE);
+            osc.set_frequency(20.0);
+            assert_eq!(osc.level_start, level_for(phase_increment) * LEVEL_LEN);
+            for (phase, expected) in [0.25, 0.75].into_iter().zip(expected) {
+                let value = osc.sample_at(phase);
+                assert!(
+                    (value - expected).abs() < 0.01,
+                    "{:?} at {}: {}",
+                    waveform,
+                    phase,
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_process_block_matches_next_sample() {
+        let mut scalar = WavetableOscillator::new(WaveformType::Saw, SAMPLE_RATE);
+        let mut block = WavetableOscillator::new(WaveformType::Saw, SAMPLE_RATE);
+        scalar.set_frequency(1234.5);
+        block.set_frequency(1234.5);
+
+        let mut out = [0.0; 100];
+        for _ in 0..3 {
+            block.process_block(&mut out);
+            for value in out {
+                assert!((value - scalar.next_sample()).abs() < 0.01);
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// Accessibility - AccessKit names, keyboard focus and screen reader announcements
+//
+// The AccessKit tree is only built while an assistive technology is
+// connected, so every helper here is a no-op for sighted users.
+
+use egui::accesskit::{Live, Role};
+use egui::{Context, Id, Response, Ui, Widget, WidgetText};
+
+/// Key of the last announcement in the egui temporary memory
+fn announcement_id() -> Id {
+    Id::new("a11y_announcement")
+}
+
+/// Adds a control preceded by its visible label, and ties the label to the
+/// control so screen readers read "Cutoff: 1200 Hz" instead of a bare value
+pub trait LabelledUi {
+    fn labelled(&mut self, label: impl Into<WidgetText>, widget: impl Widget) -> Response;
+}
+
+impl LabelledUi for Ui {
+    fn labelled(&mut self, label: impl Into<WidgetText>, widget: impl Widget) -> Response {
+        let label: WidgetText = label.into();
+        let name = label.text().trim_end_matches(':').to_string();
+        let label = self.label(label);
+        let response = self.add(widget).labelled_by(label.id);
+        // A focused control is already read back by the screen reader
+        if response.changed()
+            && !response.has_focus()
+            && let Some(value) = spoken_value(&response)
+        {
+            announce(&response.ctx, format!("{} {}", name, value));
+        }
+        response
+    }
+}
+
+/// Accessible name of a control without a visible label (e.g. a combo box
+/// in a mixer strip), chained like `Response::labelled_by`
+pub trait AccessibleName {
+    fn named(self, name: &str) -> Self;
+}
+
+impl AccessibleName for Response {
+    fn named(self, name: &str) -> Self {
+        self.ctx.accesskit_node_builder(self.id, |node| {
+            node.set_label(name);
+        });
+        self
+    }
+}
+
+/// Role, name and value of a custom painted widget (timeline, meters...)
+pub fn describe(response: &Response, role: Role, name: &str, value: &str) {
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_role(role);
+        node.set_label(name);
+        node.set_value(value);
+    });
+}
+
+/// Current value of a control as read by a screen reader, with its unit
+/// ("1200 Hz"), None without AccessKit
+pub fn spoken_value(response: &Response) -> Option<String> {
+    response
+        .ctx
+        .accesskit_node_builder(response.id, |node| {
+            let value = node
+                .numeric_value()
+                .map(format_number)
+                .or_else(|| node.value().map(str::to_string))?;
+            Some(match node.label() {
+                Some(unit) if !unit.is_empty() => format!("{} {}", value, unit),
+                _ => value,
+            })
+        })
+        .flatten()
+}
+
+/// Number without trailing zeros, at most 3 decimals
+fn format_number(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Queues a message for the screen reader (tab switched, transport started,
+/// parameter changed by MIDI...)
+pub fn announce(ctx: &Context, text: impl Into<String>) {
+    let text = text.into();
+    ctx.data_mut(|data| data.insert_temp(announcement_id(), text));
+}
+
+/// Publishes the last announcement as a polite live region, to call once per
+/// frame: screen readers speak its value whenever it changes
+pub fn show_announcements(ctx: &Context) {
+    let Some(text) = ctx.data(|data| data.get_temp::<String>(announcement_id())) else {
+        return;
+    };
+    ctx.accesskit_node_builder(announcement_id(), |node| {
+        node.set_role(Role::Status);
+        node.set_live(Live::Polite);
+        node.set_value(text);
+    });
+}
+
+/// Cycles through `count` tabs with Ctrl+PageDown / Ctrl+PageUp (browser
+/// convention), returns the new index when it changed
+pub fn cycle_tabs(ctx: &Context, current: usize, count: usize) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+    let (next, previous) = ctx.input_mut(|input| {
+        (
+            input.consume_key(egui::Modifiers::COMMAND, egui::Key::PageDown),
+            input.consume_key(egui::Modifiers::COMMAND, egui::Key::PageUp),
+        )
+    });
+    if next {
+        Some((current + 1) % count)
+    } else if previous {
+        Some((current + count - 1) % count)
+    } else {
+        None
+    }
+}
+
+/// Keeps the focus on a custom widget when arrows are pressed, so they adjust
+/// it instead of moving to a neighbour
+pub fn lock_arrows(response: &Response) {
+    response.ctx.memory_mut(|memory| {
+        memory.set_focus_lock_filter(
+            response.id,
+            egui::EventFilter {
+                horizontal_arrows: true,
+                vertical_arrows: true,
+                ..Default::default()
+            },
+        )
+    });
+}
+
+/// Arrow key step (-1, 0 or +1) for a focused custom widget; Shift makes the
+/// caller take bigger steps
+pub fn arrow_step(response: &Response) -> (i32, bool) {
+    if !response.has_focus() {
+        return (0, false);
+    }
+    lock_arrows(response);
+    response.ctx.input(|input| {
+        let mut step = 0;
+        if input.key_pressed(egui::Key::ArrowRight) || input.key_pressed(egui::Key::ArrowUp) {
+            step += 1;
+        }
+        if input.key_pressed(egui::Key::ArrowLeft) || input.key_pressed(egui::Key::ArrowDown) {
+            step -= 1;
+        }
+        (step, input.modifiers.shift)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(1200.0), "1200");
+        assert_eq!(format_number(0.25), "0.25");
+        assert_eq!(format_number(0.0001), "0");
+        assert_eq!(format_number(-12.5), "-12.5");
+    }
+
+    #[test]
+    fn test_cycle_tabs_wraps() {
+        let ctx = Context::default();
+        let mut input = egui::RawInput::default();
+        input.events.push(egui::Event::Key {
+            key: egui::Key::PageUp,
+            physical_key: None,
+            pressed: true,
+            repeat: false,
+            modifiers: egui::Modifiers::COMMAND,
+        });
+        let mut result = None;
+        let _ = ctx.run(input, |ctx| result = cycle_tabs(ctx, 0, 11));
+        assert_eq!(result, Some(10));
+
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            result = cycle_tabs(ctx, 3, 11);
+        });
+        assert_eq!(result, None);
+    }
+}
@@ -1,40 +1,112 @@
 // Main UI App UI
 
-use crate::audio::cpu_monitor::{CpuLoad, CpuMonitor};
+use crate::audio::clip_player::{
+    AudioClip, MAX_CLIP_GAIN, SidechainSource, TrackDynamics,
+};
+use crate::audio::cpu_monitor::{CpuLoad, CpuMonitor, XrunBurstDetector};
+use crate::audio::freeze::{FrozenTrack, freeze_track};
+use crate::audio::inserts::{INSERT_DELAY_MAX_MS, InsertChainParams, InsertEffect};
+use crate::audio::instruments::{InstrumentTrack, MAX_INSTRUMENT_TRACKS, TrackInstrument};
 use crate::audio::device::{AudioDeviceInfo, AudioDeviceManager};
-use crate::audio::parameters::AtomicF32;
+use crate::audio::engine::{AudioEngine, AudioInput, InputOptions};
+use crate::audio::outputs::TrackOutput;
+use crate::audio::parameters::{AtomicF32, SynthParameters};
+use crate::audio::recorder::{Recorder, recordings_directory};
+use crate::audio::routing::{ChannelStrip, MixerTrack};
+use crate::audio::returns::{
+    MAX_RETURN_BUSES, ReturnBus, ReturnBusParams, ReturnEffect, TrackSends,
+};
+use crate::audio::snapshot::AtomicEngineSnapshot;
+use crate::audio::stretch::{
+    MAX_PITCH_SHIFT, MAX_STRETCH_RATIO, MIN_STRETCH_RATIO, StretchSettings,
+};
+use crate::audio::surround::{SpeakerLayout, SurroundPan};
+use crate::audio::tap::AudioTap;
+use crate::audio::tuner::{REFERENCE_RANGE, Tuner};
+use crate::audio::warp::{ClipWarp, MAX_SOURCE_BPM, MIN_SOURCE_BPM, estimate_loop_bpm};
 use crate::command::commands::{
-    SetAdsrCommand, SetFilterCommand, SetLfoCommand, SetModRoutingCommand, SetPolyModeCommand,
-    SetPortamentoCommand, SetVoiceModeCommand, SetVolumeCommand, SetWaveformCommand,
+    CopyPatchSlotCommand, LoadSynthPresetCommand, SetAdsrCommand, SetAntiAliasingCommand, SetCrossModCommand,
+    SetFilterCommand, SetFilterModCommand, SetLfoCommand, SetModEnvelopeCommand, SetModLfoCommand, SetModRoutingCommand, SetNoiseCommand,
+    SetOscillator2Command, SetPolyModeCommand, SetPortamentoCommand, SetUnisonCommand, SetVoiceModeCommand,
+    SetVolumeCommand, SetWaveformCommand, SwitchPatchSlotCommand,
+};
+use crate::command::mixer::{
+    MuteTrackCommand, SetTrackPanCommand, SetTrackVolumeCommand, SoloTrackCommand,
 };
-use crate::command::{CommandManager, DawState};
+use crate::command::arrangement::SetArrangementCommand;
+use crate::command::session::SetSessionCommand;
+use crate::command::automation::SetAutomationPointsCommand;
+use crate::command::pattern::{QuantizeNotesCommand, SetPatternGrooveCommand, SetPatternNotesCommand};
+use crate::command::sampler::{
+    AddSampleCommand, AddSlicesCommand, RemoveSampleCommand, SetSampleMappingCommand,
+    SetSampleZoneCommand,
+};
+use crate::command::{CommandManager, DawState, PatchSlot, SynthPatch, UndoableCommand};
+use crate::config::{BUFFER_SIZES, Keymap, OUTPUT_CHANNEL_COUNTS, Preferences, Theme};
 use crate::connection::status::DeviceStatus;
-use crate::messaging::channels::{CommandProducer, NotificationConsumer};
+use crate::crash::{self, Diagnostics, RescueSession};
+use crate::messaging::channels::{CommandProducer, FeedbackConsumer, NotificationConsumer};
 use crate::messaging::command::Command;
+use crate::messaging::feedback::{CommandKind, EngineFeedback, RejectReason};
 use crate::messaging::notification::{Notification, NotificationCategory};
+use crate::midi::control_surface::{ControlSurfaceConnection, MackieControl};
 use crate::midi::device::{MidiDeviceInfo, MidiDeviceManager};
 use crate::midi::event::{MidiEvent, MidiEventTimed};
+use crate::midi::cc_map::{CcMap, CcParameter};
 use crate::midi::manager::MidiConnectionManager;
+use crate::midi::rtp::{DEFAULT_RTP_MIDI_PORT, NETWORK_MIDI_DEVICE};
+use crate::midi::smf::Smf;
 use crate::plugin::{InstanceInfo, PluginDescriptor, PluginHost, PluginInstanceId, PluginScanner};
-use crate::project::{ProjectError, ProjectLoadOptions, ProjectManager};
-use crate::sampler::SampleBank;
-use crate::sampler::loader::{Sample, load_sample};
-use crate::sequencer::{MusicalTime, Position, Tempo, TimeSignature, Transport, TransportState};
+use crate::project::types::TrackType;
+use crate::project::{
+    Project, ProjectError, ProjectLoadOptions, ProjectManager, ProjectSaveOptions,
+};
+use crate::remote::{DEFAULT_OSC_PORT, OscServer, RemoteAction};
+use crate::sampler::{SampleBank, SampleMapping, load_sfz};
+use crate::sampler::loader::load_sample;
+use crate::sampler::slicer::{DEFAULT_FIRST_NOTE, LoopSlices};
+use crate::sampler::zone::DEFAULT_ROOT_NOTE;
+use crate::scripting::ScriptContext;
+use crate::sequencer::arrangement::SYNTH_TRACK;
+use crate::sequencer::generate;
+use crate::sequencer::groove::{MAX_SWING, MIN_SWING};
+use crate::sequencer::{
+    Arrangement, GeneratorSettings, Groove, QuantizeSettings, fold_into_loop, MAX_COUNT_IN_BARS, MusicalTime, NoteId, Pattern, PatternId, Position, Scale, ScaleKind,
+    Session, StepGrid, Tempo, TimeSignature, Transport, TransportState, NoteDivision, NoteFeel,
+    NoteValue,
+};
+use crate::synth::delay::{DelayParams, StereoDelayParams};
+use crate::synth::dynamics::{DynamicsMode, DynamicsParams};
 use crate::synth::envelope::AdsrParams;
-use crate::synth::filter::FilterType;
-use crate::synth::lfo::{LfoDestination, LfoParams};
-use crate::synth::modulation::{ModDestination, ModRouting, ModSource};
-use crate::synth::oscillator::WaveformType;
+use crate::synth::filter::{FilterModParams, FilterType};
+use crate::synth::lfo::{LFO_COUNT, LfoDestination, LfoMode, LfoParams};
+use crate::synth::master_chain::{MASTER_DELAY_MAX_MS, MasterChainParams, MasterEffect};
+use crate::synth::modulation::{MAX_ROUTINGS, ModDestination, ModRouting, ModSource};
+use crate::synth::noise::{NoiseParams, NoiseType};
+use crate::synth::oscillator::{
+    AntiAliasing, CrossModParams, MAX_OSC2_COARSE, MAX_OSC2_FINE, MAX_UNISON_DETUNE, MAX_UNISON_VOICES,
+    UnisonParams, WaveformType,
+};
 use crate::synth::poly_mode::PolyMode;
 use crate::synth::portamento::PortamentoParams;
-use crate::synth::voice_manager::VoiceMode;
+use crate::synth::reverb::ReverbParams;
+use crate::synth::voice_manager::{MAX_PITCH_BEND_RANGE, MAX_SAMPLE_SLOTS, VoiceMode};
+use crate::ui::a11y::{self, AccessibleName, LabelledUi};
+use crate::ui::automation_lane::AutomationLaneEditor;
+use crate::ui::arrangement::ArrangementEditor;
+use crate::ui::history;
+use crate::ui::preset_browser::PresetBrowser;
+use crate::ui::session::{SessionAction, SessionEditor};
+use crate::ui::step_sequencer::StepSequencerEditor;
+use crate::ui::zone_editor::ZoneEditor;
 use eframe::egui;
 use egui_plot::{Line, Plot, PlotPoints, VLine};
 use rfd::FileDialog;
 use std::collections::{HashSet, VecDeque};
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Confirmation dialog for user actions
 #[derive(Debug, Clone)]
@@ -60,9 +132,489 @@ enum UiTab {
     Sequencer,
     Plugins,
     Play,
+    Script,
+    Tuner,
     Performance,
 }
 
+impl UiTab {
+    /// Tabs in the order of the tab bar
+    const ALL: [UiTab; 11] = [
+        UiTab::Project,
+        UiTab::Devices,
+        UiTab::Synth,
+        UiTab::Modulation,
+        UiTab::Sampler,
+        UiTab::Sequencer,
+        UiTab::Plugins,
+        UiTab::Play,
+        UiTab::Script,
+        UiTab::Tuner,
+        UiTab::Performance,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            UiTab::Project => "Project",
+            UiTab::Devices => "Devices",
+            UiTab::Synth => "Synth",
+            UiTab::Modulation => "Modulation",
+            UiTab::Sampler => "Sampler",
+            UiTab::Sequencer => "Sequencer",
+            UiTab::Plugins => "Plugins",
+            UiTab::Play => "Play",
+            UiTab::Script => "Script",
+            UiTab::Tuner => "Tuner",
+            UiTab::Performance => "Performance",
+        }
+    }
+}
+
+/// Interval between two updates of the crash rescue session
+const RESCUE_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Scripts offered in the script console
+const SCRIPT_EXAMPLES: &[(&str, &str)] = &[
+    (
+        "Euclidean kick",
+        "// 5 kicks spread over 16 sixteenths, on every bar\nclear();\nlet steps = euclid(5, 16);\nfor bar in 0..pattern_bars() {\n    for i in 0..steps.len() {\n        if steps[i] {\n            add_note(36, bar * beats_per_bar() + i * 0.25, 0.25, 110);\n        }\n    }\n}\nprint(`${notes().len()} kicks`);\n",
+    ),
+    (
+        "Transpose a fifth",
+        "// Selected notes (or every note) up a fifth\nlet count = transpose(7);\nprint(`${count} notes transposed`);\n",
+    ),
+    (
+        "Humanize",
+        "// Random velocities around the current ones\nfor note in notes() {\n    let velocity = note.velocity + random(-12, 12);\n    set_velocity(note.id, if velocity < 1 { 1 } else if velocity > 127 { 127 } else { velocity });\n}\n",
+    ),
+];
+
+/// Audio files imported as clips or samples
+const AUDIO_EXTENSIONS: &[&str] = &["wav", "flac", "mp3"];
+
+/// Whether a path has an audio file extension
+fn is_audio_file(path: &std::path::Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| AUDIO_EXTENSIONS.iter().any(|audio| ext.eq_ignore_ascii_case(audio)))
+}
+
+/// File dialog opened in `directory` when it exists
+fn file_dialog(directory: Option<&std::path::Path>) -> FileDialog {
+    match directory {
+        Some(directory) if directory.is_dir() => FileDialog::new().set_directory(directory),
+        _ => FileDialog::new(),
+    }
+}
+
+/// Compressor/gate insert controls of a track, returns true when edited
+///
+/// `id` keeps the widgets of each track apart, `sources` lists the tracks that
+/// can key the detector.
+fn dynamics_strip(
+    ui: &mut egui::Ui,
+    id: &str,
+    insert: &mut Option<TrackDynamics>,
+    sources: &[(SidechainSource, String)],
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        let mut enabled = insert.is_some();
+        if ui.checkbox(&mut enabled, "Dynamics").changed() {
+            *insert = enabled.then(|| TrackDynamics::new(DynamicsParams::default()));
+            changed = true;
+        }
+        let Some(insert) = insert.as_mut() else {
+            return;
+        };
+        let params = &mut insert.params;
+        egui::ComboBox::from_id_salt(format!("{}_dynamics_mode", id))
+            .selected_text(params.mode.name())
+            .show_ui(ui, |ui| {
+                for mode in DynamicsMode::ALL {
+                    changed |= ui
+                        .selectable_value(&mut params.mode, mode, mode.name())
+                        .changed();
+                }
+            })
+            .response
+            .named("Dynamics mode");
+        changed |= ui
+            .add(egui::Slider::new(&mut params.threshold_db, -60.0..=0.0).text("Threshold dB"))
+            .changed();
+        changed |= match params.mode {
+            DynamicsMode::Compressor => ui
+                .add(egui::Slider::new(&mut params.ratio, 1.0..=20.0).text("Ratio"))
+                .changed(),
+            DynamicsMode::Gate => ui
+                .add(egui::Slider::new(&mut params.range_db, 0.0..=80.0).text("Range dB"))
+                .changed(),
+        };
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut params.attack_ms, 0.1..=100.0)
+                    .logarithmic(true)
+                    .text("Attack ms"),
+            )
+            .changed();
+        changed |= ui
+            .add(
+                egui::Slider::new(&mut params.release_ms, 1.0..=2000.0)
+                    .logarithmic(true)
+                    .text("Release ms"),
+            )
+            .changed();
+        changed |= ui
+            .add(egui::Slider::new(&mut params.makeup_db, -24.0..=24.0).text("Makeup dB"))
+            .changed();
+        changed |= sidechain_picker(ui, id, &mut insert.sidechain, sources, "Self");
+    });
+    changed
+}
+
+/// Gain reduction of a master dynamics stage (the bar is full at 24 dB)
+fn gain_reduction_meter(ui: &mut egui::Ui, reduction_db: f32) {
+    ui.add(
+        egui::ProgressBar::new((reduction_db / 24.0).clamp(0.0, 1.0))
+            .desired_width(120.0)
+            .text(format!("GR {:.1} dB", reduction_db)),
+    );
+}
+
+/// Tempo division selector (`free` names the unsynced choice)
+fn note_division_picker(
+    ui: &mut egui::Ui,
+    id: &str,
+    division: &mut Option<NoteDivision>,
+    free: &str,
+) -> bool {
+    let selected = division.map_or(free.to_string(), |division| division.to_string());
+    let mut changed = false;
+    let label = ui.label("Sync:");
+    egui::ComboBox::from_id_salt(format!("{}_sync", id))
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            changed |= ui.selectable_value(division, None, free).changed();
+            for value in NoteValue::ALL {
+                for feel in NoteFeel::ALL {
+                    let candidate = NoteDivision::new(value, feel);
+                    changed |= ui
+                        .selectable_value(division, Some(candidate), candidate.to_string())
+                        .changed();
+                }
+            }
+        })
+        .response
+        .labelled_by(label.id);
+    changed
+}
+
+/// Tempo sync, mode and start phase of an LFO, returns true when edited
+fn lfo_timing_controls(ui: &mut egui::Ui, id: &str, params: &mut LfoParams) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        changed |= note_division_picker(ui, id, &mut params.sync, "Free (Hz)");
+        let label = ui.label("Mode:");
+        egui::ComboBox::from_id_salt(format!("{}_mode", id))
+            .selected_text(params.mode.name())
+            .show_ui(ui, |ui| {
+                for mode in LfoMode::ALL {
+                    changed |= ui
+                        .selectable_value(&mut params.mode, mode, mode.name())
+                        .changed();
+                }
+            })
+            .response
+            .labelled_by(label.id);
+        changed |= ui
+            .add(egui::Slider::new(&mut params.start_phase, 0.0..=1.0).text("Start phase"))
+            .changed();
+    });
+    changed
+}
+
+/// Sidechain source selector (`none` names the unrouted choice)
+fn sidechain_picker(
+    ui: &mut egui::Ui,
+    id: &str,
+    source: &mut Option<SidechainSource>,
+    sources: &[(SidechainSource, String)],
+    none: &str,
+) -> bool {
+    let selected = source
+        .and_then(|source| sources.iter().find(|(s, _)| *s == source))
+        .map_or(none, |(_, name)| name.as_str());
+    let mut changed = false;
+    let label = ui.label("Sidechain:");
+    egui::ComboBox::from_id_salt(format!("{}_sidechain", id))
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            changed |= ui.selectable_value(source, None, none).changed();
+            for (candidate, name) in sources {
+                changed |= ui
+                    .selectable_value(source, Some(*candidate), name)
+                    .changed();
+            }
+        })
+        .response
+        .labelled_by(label.id);
+    changed
+}
+
+/// Insert effect chain of a track (one row per effect, in processing order),
+/// returns true when edited
+fn inserts_strip(ui: &mut egui::Ui, id: &str, inserts: &mut InsertChainParams) -> bool {
+    let mut changed = false;
+    let active: Vec<&str> = inserts
+        .order
+        .iter()
+        .filter(|effect| !inserts.is_bypassed(**effect))
+        .map(|effect| effect.name())
+        .collect();
+    let title = if active.is_empty() {
+        "Inserts".to_string()
+    } else {
+        format!("Inserts: {}", active.join(" → "))
+    };
+    egui::CollapsingHeader::new(title)
+        .id_salt(format!("{}_inserts", id))
+        .show(ui, |ui| {
+            let mut swap: Option<(usize, usize)> = None;
+            let last = inserts.order.len() - 1;
+            for (index, effect) in inserts.order.into_iter().enumerate() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.push_id(format!("{}_insert_{}", id, effect.name()), |ui| {
+                        if ui.add_enabled(index > 0, egui::Button::new("⬆")).clicked() {
+                            swap = Some((index, index - 1));
+                        }
+                        if ui.add_enabled(index < last, egui::Button::new("⬇")).clicked() {
+                            swap = Some((index, index + 1));
+                        }
+                        let mut enabled = !inserts.is_bypassed(effect);
+                        if ui.checkbox(&mut enabled, effect.name()).changed() {
+                            inserts.set_bypassed(effect, !enabled);
+                            changed = true;
+                        }
+                        let mut slider =
+                            |value: &mut f32, range: RangeInclusive<f32>, text: &str, log: bool| {
+                                changed |= ui
+                                    .add(egui::Slider::new(value, range).logarithmic(log).text(text))
+                                    .changed();
+                            };
+                        match effect {
+                            InsertEffect::Filter => {
+                                let filter = &mut inserts.filter;
+                                slider(&mut filter.cutoff, 20.0..=20000.0, "Cutoff Hz", true);
+                                slider(&mut filter.resonance, 0.5..=20.0, "Q", true);
+                                egui::ComboBox::from_id_salt("filter_type")
+                                    .selected_text(format!("{:?}", filter.filter_type))
+                                    .show_ui(ui, |ui| {
+                                        for filter_type in [
+                                            FilterType::LowPass,
+                                            FilterType::HighPass,
+                                            FilterType::BandPass,
+                                            FilterType::Notch,
+                                        ] {
+                                            let name = format!("{:?}", filter_type);
+                                            changed |= ui
+                                                .selectable_value(
+                                                    &mut filter.filter_type,
+                                                    filter_type,
+                                                    name,
+                                                )
+                                                .changed();
+                                        }
+                                    })
+                                    .response
+                                    .named("Filter type");
+                            }
+                            InsertEffect::Delay => {
+                                let delay = &mut inserts.delay;
+                                let time = &mut delay.time_ms;
+                                slider(time, 1.0..=INSERT_DELAY_MAX_MS, "Time ms", true);
+                                slider(&mut delay.feedback, 0.0..=0.99, "Feedback", false);
+                                slider(&mut delay.mix, 0.0..=1.0, "Mix", false);
+                            }
+                            InsertEffect::Reverb => {
+                                let reverb = &mut inserts.reverb;
+                                slider(&mut reverb.room_size, 0.0..=1.0, "Size", false);
+                                slider(&mut reverb.damping, 0.0..=1.0, "Damping", false);
+                                slider(&mut reverb.mix, 0.0..=1.0, "Mix", false);
+                            }
+                        }
+                    });
+                });
+            }
+            if let Some((from, to)) = swap {
+                inserts.order.swap(from, to);
+                changed = true;
+            }
+        });
+    changed
+}
+
+/// Send levels of a track to the existing return buses, returns true when edited
+///
+/// `pre_fader` shows the pre/post-fader choice (the synth track has no fader).
+fn sends_strip(
+    ui: &mut egui::Ui,
+    id: &str,
+    sends: &mut TrackSends,
+    buses: &[Option<ReturnBus>; MAX_RETURN_BUSES],
+    pre_fader: bool,
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        for (index, (send, bus)) in sends.iter_mut().zip(buses).enumerate() {
+            let Some(bus) = bus else {
+                continue;
+            };
+            ui.push_id(format!("{}_send_{}", id, index), |ui| {
+                changed |= ui
+                    .add(egui::Slider::new(&mut send.level, 0.0..=1.0).text(&bus.name))
+                    .changed();
+                if pre_fader {
+                    changed |= ui.checkbox(&mut send.pre_fader, "Pre").changed();
+                }
+            });
+        }
+    });
+    changed
+}
+
+/// Output picker of a track or bus (master bus or the free pairs of the
+/// device), returns true when edited
+/// Gain, pan, mute and solo of a track, undoable edits are pushed to `edits`
+fn mixer_strip(
+    ui: &mut egui::Ui,
+    track: MixerTrack,
+    strip: ChannelStrip,
+    edits: &mut Vec<Box<dyn UndoableCommand>>,
+) {
+    let mut gain = strip.gain;
+    if ui
+        .add(egui::Slider::new(&mut gain, 0.0..=MAX_CLIP_GAIN).text("Gain"))
+        .changed()
+    {
+        edits.push(Box::new(SetTrackVolumeCommand::new(track, gain)));
+    }
+    let mut pan = strip.pan;
+    if ui.add(egui::Slider::new(&mut pan, -1.0..=1.0).text("Pan")).changed() {
+        edits.push(Box::new(SetTrackPanCommand::new(track, pan)));
+    }
+    let mut muted = strip.muted;
+    if ui.checkbox(&mut muted, "Mute").changed() {
+        edits.push(Box::new(MuteTrackCommand::new(track, muted)));
+    }
+    let mut soloed = strip.soloed;
+    if ui
+        .checkbox(&mut soloed, "Solo")
+        .on_hover_text("While a track is soloed, only the soloed tracks are heard")
+        .changed()
+    {
+        edits.push(Box::new(SoloTrackCommand::new(track, soloed)));
+    }
+}
+
+fn output_picker(
+    ui: &mut egui::Ui,
+    id: &str,
+    output: &mut TrackOutput,
+    choices: &[TrackOutput],
+) -> bool {
+    let mut changed = false;
+    let selected = if choices.contains(output) {
+        output.name()
+    } else {
+        // Saved on a bigger interface: plays on the master bus here
+        format!("{} (master)", output.name())
+    };
+    egui::ComboBox::from_id_salt(format!("{}_output", id))
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for choice in choices {
+                changed |= ui.selectable_value(output, *choice, choice.name()).changed();
+            }
+        })
+        .response
+        .named("Output");
+    changed
+}
+
+/// Surround panner of a track (shown with a surround layout), returns true
+/// when edited
+fn surround_strip(
+    ui: &mut egui::Ui,
+    id: &str,
+    surround: &mut Option<SurroundPan>,
+    layout: SpeakerLayout,
+) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.push_id(format!("{}_surround", id), |ui| {
+            let mut enabled = surround.is_some();
+            if ui.checkbox(&mut enabled, "Surround").changed() {
+                *surround = enabled.then(SurroundPan::default);
+                changed = true;
+            }
+            let Some(pan) = surround else {
+                return;
+            };
+            changed |= ui
+                .add(egui::Slider::new(&mut pan.x, -1.0..=1.0).text("L-R"))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut pan.y, -1.0..=1.0).text("Front-Rear"))
+                .changed();
+            if layout == SpeakerLayout::Surround51 {
+                changed |= ui
+                    .add(egui::Slider::new(&mut pan.center, 0.0..=1.0).text("Center"))
+                    .changed();
+                changed |= ui
+                    .add(egui::Slider::new(&mut pan.lfe, 0.0..=1.0).text("LFE"))
+                    .changed();
+            }
+        });
+    });
+    changed
+}
+
+/// Right-click menu arming MIDI learn on a synth control
+fn midi_learn_menu(response: &egui::Response, cc_map: &mut CcMap, parameter: CcParameter) {
+    response.context_menu(|ui| {
+        if cc_map.learning() == Some(parameter) {
+            if ui.button("Cancel MIDI Learn").clicked() {
+                cc_map.cancel_learn();
+                ui.close_menu();
+            }
+        } else if ui.button("MIDI Learn").clicked() {
+            cc_map.arm_learn(parameter);
+            ui.close_menu();
+        }
+        if let Some(cc) = cc_map.cc_for(parameter)
+            && ui.button(format!("Remove CC {} Mapping", cc)).clicked()
+        {
+            cc_map.unmap(parameter);
+            ui.close_menu();
+        }
+    });
+}
+
+/// Patterns clips can play: the edited pattern, then the other patterns of the
+/// project
+fn clip_patterns<'a>(
+    edited: &'a Pattern,
+    project: &'a std::collections::HashMap<PatternId, Pattern>,
+) -> Vec<&'a Pattern> {
+    let mut patterns = vec![edited];
+    let mut others: Vec<_> = project
+        .values()
+        .filter(|pattern| pattern.id != edited.id)
+        .collect();
+    others.sort_by_key(|pattern| pattern.id);
+    patterns.extend(others);
+    patterns
+}
+
 pub struct DawApp {
     // Command Pattern for undo/redo
     command_manager: CommandManager,
@@ -73,6 +625,8 @@ pub struct DawApp {
     volume_atomic: AtomicF32,
     volume_ui: f32,
     active_notes: HashSet<u8>,
+    // Audio engine, owned here so the output device can be switched (None in tests)
+    audio_engine: Option<AudioEngine>,
     // Device management
     audio_device_manager: AudioDeviceManager,
     midi_device_manager: MidiDeviceManager,
@@ -99,9 +653,25 @@ pub struct DawApp {
     portamento_time: f32,
     // CPU monitoring
     cpu_monitor: CpuMonitor,
+    // State published by the audio callback (playhead is the engine's)
+    engine_snapshot: AtomicEngineSnapshot,
+    // Master output tap and the tuner listening to it (while its tab is shown)
+    master_tap: AudioTap,
+    tuner: Tuner,
+    // Frozen synth track: the audio track playing its render
+    frozen_synth: Option<(u32, FrozenTrack)>,
+    // Recording input, opened for the first take (see `audio::recorder`)
+    audio_input: Option<(AudioInput, Recorder)>,
+    available_input_devices: Vec<AudioDeviceInfo>,
+    // Audio track armed for recording, and the one receiving the current take
+    armed_track: Option<u32>,
+    recording_track: Option<u32>,
     last_cpu_load: CpuLoad,
+    xrun_burst_detector: XrunBurstDetector,
     // Notification system
     notification_rx: NotificationConsumer,
+    // Acknowledgments and corrections from the audio thread
+    feedback_rx: FeedbackConsumer,
     notification_queue: VecDeque<Notification>,
     max_notifications: usize,
     // Error dialog system
@@ -109,13 +679,21 @@ pub struct DawApp {
     // Confirmation dialog system
     confirmation_dialog: Option<ConfirmationDialog>,
     // Modulation Matrix UI (MVP) - 4 slots
-    mod_routings_ui: [ModRouting; 4],
-    // Sampler state
-    loaded_samples: Vec<Sample>,
+    mod_routings_ui: [ModRouting; MAX_ROUTINGS],
+    // Sampler state (the samples and their mappings are in the DawState)
     note_map_input: Vec<String>,
-    // Preview state (sample_index, note)
-    preview_sample_note: Option<(usize, u8)>,
-    preview_timer: Option<Instant>,
+    // Slicing of loops: first note of the slices, write the pattern replaying the loop
+    slice_first_note: u8,
+    slice_to_pattern: bool,
+    // Sample preview: slot being previewed (and when a one-shot preview ends),
+    // slot whose button is held, and the preview settings
+    previewing: Option<usize>,
+    preview_end: Option<Instant>,
+    preview_held: Option<usize>,
+    preview_note: u8,
+    preview_original_pitch: bool,
+    preview_loop: bool,
+    preview_latch: bool,
 
     // Sequencer state
     sequencer: Transport,
@@ -135,12 +713,39 @@ pub struct DawApp {
 
     // Piano Roll editor
     piano_roll_editor: crate::ui::piano_roll::PianoRollEditor,
-    active_pattern: crate::sequencer::Pattern,
+    automation_editor: AutomationLaneEditor,
+    step_sequencer: StepSequencerEditor,
+    arrangement_editor: ArrangementEditor,
+    session_editor: SessionEditor,
+    // Notes before the current piano roll gesture (one undo step per gesture)
+    pattern_edit_base: Vec<crate::sequencer::Note>,
+    // Notes before the current MIDI take (the take is one undo step)
+    midi_take_base: Option<Vec<crate::sequencer::Note>>,
+    // Snap the notes recorded from the MIDI input with the Quantize settings
+    input_quantize: bool,
+    // Groove templates (swing, or extracted from a take or an audio loop)
+    grooves: Vec<Groove>,
+    selected_groove: usize,
+    groove_strength: f32,
+    groove_swing: f32,
+    groove_clip: Option<u32>,
+    // Pattern swing while its slider is dragged (set on release, one undo step)
+    pattern_swing_edit: Option<f32>,
+    // Last values of the Generate menu
+    generator: GeneratorSettings,
+    // Last values of the Quantize menu
+    quantize: QuantizeSettings,
     project_patterns:
         std::collections::HashMap<crate::sequencer::pattern::PatternId, crate::sequencer::Pattern>,
 
     // Active UI tab
     active_tab: UiTab,
+    // Undo history panel shown on the right
+    show_history: bool,
+    // Synth preset browser (Synth tab)
+    preset_browser: PresetBrowser,
+    // Sampler key-range zones (Sampler tab)
+    zone_editor: ZoneEditor,
 
     // Project management
     project_manager: ProjectManager,
@@ -151,7 +756,10 @@ pub struct DawApp {
     export_format: crate::audio::export::ExportFormat,
     export_sample_rate: u32,
     export_bit_depth: u16,
-    export_duration_seconds: Option<f64>,
+    /// First exported bar (from 1)
+    export_start_bar: u32,
+    /// Last exported bar, inclusive (None: end of the longest pattern)
+    export_end_bar: Option<u32>,
     export_include_metronome: bool,
     export_in_progress: bool,
     export_progress: f32,
@@ -165,15 +773,44 @@ pub struct DawApp {
     // Deferred actions to avoid egui ID clashes
     plugin_to_load_next_frame: Option<std::path::PathBuf>,
     plugin_to_remove_next_frame: Vec<PluginInstanceId>,
+
+    // Script console
+    script_source: String,
+    script_output: Vec<String>,
+
+    // OSC remote control
+    osc_server: Option<OscServer>,
+    osc_port: u16,
+    osc_status: String,
+
+    // Mackie Control surface
+    control_surface: Option<ControlSurfaceConnection>,
+    available_control_surfaces: Vec<String>,
+    mackie: MackieControl,
+    control_surface_status: String,
+
+    // User preferences (saved when changed from the UI)
+    preferences: Preferences,
+    last_autosave: Instant,
+
+    // Crash rescue (see `crash`)
+    pending_rescue: Option<RescueSession>,
+    last_rescue_update: Instant,
 }
 
 impl DawApp {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         command_tx: CommandProducer,
         volume_atomic: AtomicF32,
+        synth_params: SynthParameters,
         midi_connection_manager: MidiConnectionManager,
         cpu_monitor: CpuMonitor,
+        engine_snapshot: AtomicEngineSnapshot,
+        master_tap: AudioTap,
         notification_rx: NotificationConsumer,
+        feedback_rx: FeedbackConsumer,
+        preferences: Preferences,
     ) -> Self {
         let initial_volume = volume_atomic.get();
 
@@ -183,14 +820,19 @@ impl DawApp {
 
         // Énumérer les périphériques disponibles
         let available_audio_devices = audio_device_manager.list_output_devices();
+        let available_input_devices = audio_device_manager.list_input_devices();
+        let (control_surface, control_surface_status) =
+            Self::open_control_surface(preferences.midi.control_surface.as_deref());
         let available_midi_devices = midi_device_manager.list_input_ports();
 
-        // Sélectionner les périphériques par défaut
-        let selected_audio_device = available_audio_devices
-            .iter()
-            .find(|d| d.is_default)
-            .map(|d| d.name.clone())
-            .unwrap_or_default();
+        // Sélectionner le périphérique préféré, sinon celui par défaut
+        let selected_audio_device = preferences.audio.output_device.clone().unwrap_or_else(|| {
+            available_audio_devices
+                .iter()
+                .find(|d| d.is_default)
+                .map(|d| d.name.clone())
+                .unwrap_or_default()
+        });
 
         // Synchroniser avec le device cible du manager MIDI
         let selected_midi_device = midi_connection_manager.target_device().unwrap_or_else(|| {
@@ -204,7 +846,11 @@ impl DawApp {
         // Initialize Command Pattern with shared command producer
         let command_manager = CommandManager::new();
         let command_tx_shared = Arc::new(Mutex::new(command_tx));
-        let daw_state = DawState::new(command_tx_shared.clone());
+        let mut daw_state = DawState::new(command_tx_shared.clone());
+        daw_state.synth_params = synth_params;
+        let _ = daw_state.set_metronome_output(preferences.audio.metronome_output);
+        let mut sequencer = Transport::new(48000.0);
+        sequencer.set_count_in_bars(preferences.audio.count_in_bars);
 
         Self {
             command_manager,
@@ -232,45 +878,62 @@ impl DawApp {
             poly_mode: PolyMode::default(),
             portamento_time: 0.0,
             cpu_monitor,
+            engine_snapshot,
+            master_tap,
+            tuner: Tuner::new(),
+            frozen_synth: None,
+            audio_input: None,
+            available_input_devices,
+            armed_track: None,
+            recording_track: None,
             last_cpu_load: CpuLoad::Low,
+            xrun_burst_detector: XrunBurstDetector::default(),
             notification_rx,
+            feedback_rx,
             notification_queue: VecDeque::new(),
             max_notifications: 10,
             error_dialog: None,
             confirmation_dialog: None,
-            mod_routings_ui: [
-                ModRouting {
+            mod_routings_ui: std::array::from_fn(|i| match i {
+                0 => ModRouting {
                     source: ModSource::Lfo(0),
                     destination: ModDestination::OscillatorPitch(0),
                     amount: 2.0,
                     enabled: false,
                 },
-                ModRouting {
+                1 => ModRouting {
                     source: ModSource::Lfo(0),
                     destination: ModDestination::Amplitude,
                     amount: 0.5,
                     enabled: false,
                 },
-                ModRouting {
+                2 => ModRouting {
                     source: ModSource::Velocity,
                     destination: ModDestination::Amplitude,
                     amount: 0.5,
                     enabled: false,
                 },
-                ModRouting {
+                3 => ModRouting {
                     source: ModSource::Aftertouch,
                     destination: ModDestination::Amplitude,
                     amount: 0.5,
                     enabled: false,
                 },
-            ],
-            loaded_samples: Vec::new(),
+                _ => ModRouting::disabled(),
+            }),
             note_map_input: Vec::new(),
-            preview_sample_note: None,
-            preview_timer: None,
+            slice_first_note: DEFAULT_FIRST_NOTE,
+            slice_to_pattern: true,
+            previewing: None,
+            preview_end: None,
+            preview_held: None,
+            preview_note: DEFAULT_ROOT_NOTE,
+            preview_original_pitch: true,
+            preview_loop: false,
+            preview_latch: false,
 
             // Sequencer initialization (using 48kHz default sample rate)
-            sequencer: Transport::new(48000.0),
+            sequencer,
             metronome_enabled: true,
             metronome_volume: 0.5,
             sequencer_tempo: 120.0,
@@ -287,10 +950,27 @@ impl DawApp {
 
             // Initialize piano roll with a default 4-bar pattern
             piano_roll_editor: crate::ui::piano_roll::PianoRollEditor::default(),
-            active_pattern: crate::sequencer::Pattern::new_default(1, "Pattern 1".to_string()),
+            automation_editor: AutomationLaneEditor::default(),
+            step_sequencer: StepSequencerEditor::default(),
+            arrangement_editor: ArrangementEditor::default(),
+            session_editor: SessionEditor::default(),
+            pattern_edit_base: Vec::new(),
+            midi_take_base: None,
+            input_quantize: false,
+            grooves: vec![Groove::swing(66.0, 4)],
+            selected_groove: 0,
+            groove_strength: 1.0,
+            groove_swing: 66.0,
+            groove_clip: None,
+            pattern_swing_edit: None,
+            generator: GeneratorSettings::default(),
+            quantize: QuantizeSettings::default(),
             project_patterns: std::collections::HashMap::new(),
 
             active_tab: UiTab::Synth,
+            show_history: false,
+            preset_browser: PresetBrowser::default(),
+            zone_editor: ZoneEditor::default(),
 
             // Initialize project management
             project_manager: ProjectManager::new(48000.0),
@@ -301,7 +981,8 @@ impl DawApp {
             export_format: crate::audio::export::ExportFormat::Wav,
             export_sample_rate: 44100,
             export_bit_depth: 16,
-            export_duration_seconds: None, // Auto-detect from pattern
+            export_start_bar: 1,
+            export_end_bar: None,
             export_include_metronome: false,
             export_in_progress: false,
             export_progress: 0.0,
@@ -319,6 +1000,134 @@ impl DawApp {
             scan_in_progress: false,
             plugin_to_load_next_frame: None,
             plugin_to_remove_next_frame: Vec::new(),
+
+            script_source: SCRIPT_EXAMPLES[0].1.to_string(),
+            script_output: Vec::new(),
+
+            osc_server: None,
+            osc_port: DEFAULT_OSC_PORT,
+            osc_status: String::new(),
+
+            control_surface,
+            available_control_surfaces: ControlSurfaceConnection::list_ports(),
+            mackie: MackieControl::new(),
+            control_surface_status,
+
+            preferences,
+            last_autosave: Instant::now(),
+
+            pending_rescue: None,
+            audio_engine: None,
+            last_rescue_update: Instant::now(),
+        }
+    }
+
+    /// Hand over the audio engine (needed to switch the output device)
+    pub fn set_audio_engine(&mut self, engine: AudioEngine) {
+        self.audio_engine = Some(engine);
+    }
+
+    /// Offer to restore the session rescued after the previous crash
+    pub fn set_pending_rescue(&mut self, rescue: Option<RescueSession>) {
+        self.pending_rescue = rescue;
+    }
+
+    /// Apply the theme preference to the egui context
+    pub fn apply_theme(&self, ctx: &egui::Context) {
+        ctx.set_theme(match self.preferences.theme {
+            Theme::System => egui::ThemePreference::System,
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+        });
+    }
+
+    /// Persist the preferences after a change from the UI
+    fn save_preferences(&mut self) {
+        if let Err(e) = self.preferences.save() {
+            self.show_error(e);
+        }
+    }
+
+    /// Save a copy of the modified project every `autosave_interval_secs`
+    ///
+    /// The project file itself is left untouched (see `Preferences::autosave_path`).
+    fn autosave_if_due(&mut self) {
+        let interval = self.preferences.autosave_interval_secs;
+        if interval == 0
+            || !self.project_has_unsaved_changes
+            || self.last_autosave.elapsed() < Duration::from_secs(interval as u64)
+        {
+            return;
+        }
+        self.last_autosave = Instant::now();
+
+        let path = Preferences::autosave_path(self.current_project_path.as_deref());
+        if let Some(parent) = path.parent()
+            && let Err(e) = std::fs::create_dir_all(parent)
+        {
+            eprintln!("Autosave failed: {}", e);
+            return;
+        }
+        match self.save_project_to_path(&path, &ProjectSaveOptions::default()) {
+            Ok(()) => println!("💾 Autosaved to {:?}", path),
+            Err(e) => eprintln!("Autosave failed: {}", e),
+        }
+    }
+
+    /// Refresh the crash rescue copy of the session and the crash diagnostics
+    ///
+    /// Only a modified project needs a copy: a saved one is on disk already.
+    fn update_rescue_session(&mut self) {
+        if self.last_rescue_update.elapsed() < RESCUE_UPDATE_INTERVAL {
+            return;
+        }
+        self.last_rescue_update = Instant::now();
+
+        let snapshot = self.engine_snapshot.read();
+        crash::update_diagnostics(&Diagnostics {
+            engine_status: vec![
+                format!("Audio device: {}", self.selected_audio_device),
+                format!("MIDI device: {}", self.selected_midi_device),
+                format!("Sample rate: {} Hz", snapshot.sample_rate),
+                format!("Playing: {}", snapshot.playing),
+                format!("Tempo: {:.1} BPM", snapshot.tempo),
+                format!("Active voices: {}", snapshot.active_voices),
+                format!("CPU load: {:.0}%", snapshot.cpu_load),
+            ],
+            recent_notifications: self
+                .notification_queue
+                .iter()
+                .map(crash::describe_notification)
+                .collect(),
+            loaded_plugins: self
+                .loaded_plugins
+                .iter()
+                .map(|plugin| format!("{} ({})", plugin.plugin_name, plugin.plugin_id))
+                .collect(),
+        });
+
+        if self.project_has_unsaved_changes {
+            let project = self.build_project();
+            if let Err(e) = crash::update_session(&project, self.current_project_path.as_deref()) {
+                eprintln!("{}", e);
+            }
+        } else {
+            crash::clear_session();
+        }
+    }
+
+    /// Load the rescued session (it stays modified until saved)
+    fn restore_rescue(&mut self, rescue: RescueSession) {
+        match self.load_project_from_path(&rescue.project_file) {
+            Ok(()) => {
+                self.current_project_path = rescue.info.project_path;
+                self.project_has_unsaved_changes = true;
+                crash::discard_rescue();
+                println!("✅ Restored rescued session: {}", rescue.info.project_name);
+            }
+            Err(e) => {
+                self.show_error(format!("Failed to restore the rescued session: {}", e));
+            }
         }
     }
 
@@ -334,16 +1143,43 @@ impl DawApp {
         }
     }
 
+    /// Move the audio output to the selected device
+    ///
+    /// The new stream starts with the synth settings and samples only: the DAW
+    /// state, the active pattern and the audio tracks are sent again.
+    fn switch_audio_device(&mut self) {
+        let Some(engine) = self.audio_engine.as_mut() else {
+            return;
+        };
+        match engine.switch_device(Some(self.selected_audio_device.clone())) {
+            Ok(_) => {
+                self.active_notes.clear();
+                if self.daw_state.sync_audio().is_err() {
+                    eprintln!("Failed to send the DAW state to the new audio stream: ringbuffer full");
+                }
+                self.send_active_pattern();
+                self.send_audio_tracks();
+            }
+            Err(e) => {
+                self.notification_queue.push_back(Notification::error(
+                    NotificationCategory::Audio,
+                    format!("Audio device switch failed: {}", e),
+                ));
+            }
+        }
+    }
+
     fn refresh_devices(&mut self) {
         self.available_audio_devices = self.audio_device_manager.list_output_devices();
+        self.available_input_devices = self.audio_device_manager.list_input_devices();
         self.available_midi_devices = self.midi_device_manager.list_input_ports();
+        self.available_control_surfaces = ControlSurfaceConnection::list_ports();
     }
 
     /// Lit les nouvelles notifications depuis le ringbuffer et les ajoute à la queue
     fn update_notifications(&mut self) {
         // Lire toutes les notifications disponibles
-        while let Some(notification) = ringbuf::traits::Consumer::try_pop(&mut self.notification_rx)
-        {
+        while let Some(notification) = self.notification_rx.try_pop() {
             self.notification_queue.push_back(notification);
 
             // Limiter la taille de la queue
@@ -351,6 +1187,29 @@ impl DawApp {
                 self.notification_queue.pop_front();
             }
         }
+
+        // Rejections and corrections from the audio thread are shown as notifications
+        while let Some(feedback) = ringbuf::traits::Consumer::try_pop(&mut self.feedback_rx) {
+            self.apply_engine_feedback(feedback);
+            if let Some(notification) = feedback.to_notification() {
+                self.notification_queue.push_back(notification);
+                if self.notification_queue.len() > self.max_notifications {
+                    self.notification_queue.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Align the UI state with what the audio thread accepted
+    fn apply_engine_feedback(&mut self, feedback: EngineFeedback) {
+        if let EngineFeedback::Rejected {
+            command: CommandKind::AddSample,
+            reason: RejectReason::SampleSlotsFull,
+        } = feedback
+        {
+            // The engine kept the first samples: forget the ones it refused
+            self.daw_state.samples.truncate(MAX_SAMPLE_SLOTS);
+        }
     }
 
     /// Récupère la notification la plus récente (si elle existe)
@@ -385,8 +1244,31 @@ impl DawApp {
         self.last_cpu_load = current_load;
     }
 
+    /// Notify the user when xruns (audio dropouts) come in bursts
+    fn check_xruns(&mut self) {
+        let stats = self.cpu_monitor.xrun_stats();
+        if let Some(count) = self
+            .xrun_burst_detector
+            .poll(stats, std::time::Instant::now())
+        {
+            let notification = Notification::warning(
+                NotificationCategory::Audio,
+                format!(
+                    "Audio dropouts: {} xruns in a short time ({} total)",
+                    count,
+                    stats.total()
+                ),
+            );
+            self.notification_queue.push_back(notification);
+        }
+    }
+
     /// Load a plugin using the plugin host
-    fn load_plugin(&mut self, plugin_path: &std::path::Path) -> Result<(), String> {
+    fn load_plugin(
+        &mut self,
+        plugin_path: &std::path::Path,
+        name: Option<String>,
+    ) -> Result<PluginInstanceId, String> {
         // Load the plugin library
         let plugin_id = self
             .plugin_host
@@ -394,9 +1276,10 @@ impl DawApp {
             .map_err(|e| format!("Failed to load plugin: {}", e))?;
 
         // Create an instance
+        let name = name.unwrap_or_else(|| format!("Plugin {}", plugin_id));
         let instance_id = self
             .plugin_host
-            .create_instance(&plugin_id, Some(format!("Plugin {}", plugin_id)))
+            .create_instance(&plugin_id, Some(name))
             .map_err(|e| format!("Failed to create instance: {}", e))?;
 
         // Initialize the instance
@@ -410,7 +1293,7 @@ impl DawApp {
         if let Some(instance_info) = self.plugin_host.get_instance_info(instance_id) {
             self.loaded_plugins.push(instance_info.clone());
             println!("✅ Plugin loaded: {}", instance_info.plugin_name);
-            Ok(())
+            Ok(instance_id)
         } else {
             Err("Failed to get instance info".to_string())
         }
@@ -445,57 +1328,53 @@ impl DawApp {
         }
     }
 
-    /// Preview a sample by triggering a note (C4 = 60)
+    /// Audition a sample with the preview settings, outside of the note mappings
     fn preview_sample(&mut self, sample_index: usize) {
-        // Stop any ongoing preview
-        if let Some((_, prev_note)) = self.preview_sample_note {
-            self.send_note_off_direct(prev_note);
-        }
-
-        // Use C4 (note 60) for preview
-        let preview_note = 60;
-
-        // Send note on
-        let timed_event = MidiEventTimed {
-            event: MidiEvent::NoteOn {
-                note: preview_note,
-                velocity: 100,
-            },
-            samples_from_now: 0,
+        let Some(sample) = self.daw_state.samples.get(sample_index) else {
+            return;
+        };
+        let note = (!self.preview_original_pitch).then_some(self.preview_note);
+        let cmd = Command::PreviewSample {
+            sample_index,
+            note,
+            looped: self.preview_loop,
         };
-        let cmd = Command::Midi(timed_event);
         if let Ok(mut tx) = self.command_tx.lock() {
             let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
         }
 
-        // Track preview state with a 2-second timer
-        self.preview_sample_note = Some((sample_index, preview_note));
-        self.preview_timer = Some(Instant::now());
+        // A one-shot preview ends with the sample, played faster or slower
+        // when transposed from its root note
+        self.preview_end = (!self.preview_loop).then(|| {
+            let root_note = self
+                .daw_state
+                .sample_zones
+                .get(&sample_index)
+                .map_or(DEFAULT_ROOT_NOTE, |zone| zone.root_note);
+            let semitones = note.map_or(0.0, |note| note as f64 - root_note as f64)
+                + sample.pitch_offset as f64
+                + sample.fine_tune as f64 / 100.0;
+            let seconds = sample.len() as f64 / sample.sample_rate.max(1) as f64
+                / 2.0_f64.powf(semitones / 12.0);
+            Instant::now() + Duration::from_secs_f64(seconds)
+        });
+        self.previewing = Some(sample_index);
     }
 
-    /// Send note off without tracking in active_notes (for preview)
-    fn send_note_off_direct(&mut self, note: u8) {
-        let timed_event = MidiEventTimed {
-            event: MidiEvent::NoteOff { note },
-            samples_from_now: 0,
-        };
-        let cmd = Command::Midi(timed_event);
+    /// Release the sample preview
+    fn stop_preview(&mut self) {
         if let Ok(mut tx) = self.command_tx.lock() {
-            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+            let _ = ringbuf::traits::Producer::try_push(&mut *tx, Command::StopPreview);
         }
+        self.previewing = None;
+        self.preview_end = None;
     }
 
-    /// Check if preview timer has expired and stop preview if needed
-    fn check_preview_timer(&mut self) {
-        if let Some(timer) = self.preview_timer
-            && timer.elapsed().as_secs_f32() > 2.0
-        {
-            // Stop preview after 2 seconds
-            if let Some((_, note)) = self.preview_sample_note {
-                self.send_note_off_direct(note);
-            }
-            self.preview_sample_note = None;
-            self.preview_timer = None;
+    /// Forget a one-shot preview that played to its end
+    fn check_preview_end(&mut self) {
+        if self.preview_end.is_some_and(|end| Instant::now() >= end) {
+            self.previewing = None;
+            self.preview_end = None;
         }
     }
 
@@ -522,63 +1401,362 @@ impl DawApp {
         )
     }
 
-    /// Update cursor position from sequencer current position
-    fn update_cursor_position(&mut self) {
-        self.cursor_position = self.sequencer.position();
-    }
+    /// Follow the playhead of the audio engine
+    ///
+    /// While the engine plays, its position is the reference: the UI transport
+    /// does not advance on its own, so it cannot drift.
+    fn sync_transport_from_engine(&mut self) {
+        let snapshot = self.engine_snapshot.read();
+
+        let sample_rate = snapshot.sample_rate as f64;
+        if sample_rate > 0.0 && sample_rate != self.sequencer.sample_rate() {
+            self.sequencer.set_sample_rate(sample_rate);
+        }
 
-    /// Draw timeline with cursor and grid
-    fn draw_timeline_with_cursor(&mut self, ui: &mut egui::Ui) {
-        let available_width = ui.available_width();
-        let timeline_height = 100.0;
+        if snapshot.playing {
+            self.sequencer.set_position_samples(snapshot.position_samples);
+        }
 
-        // Update cursor position from sequencer
-        self.update_cursor_position();
+        // The engine starts the transport once the count-in is over
+        if snapshot.playing && self.sequencer.state().is_counting_in() {
+            self.sequencer.finish_count_in();
+            if let Err(e) = self.start_audio_take() {
+                self.show_error(format!("Failed to start recording: {}", e));
+            }
+        }
+    }
 
-        // Draw timeline background
-        let painter = ui.painter();
-        let rect = egui::Rect::from_min_size(
-            ui.cursor().min,
-            egui::vec2(available_width, timeline_height),
-        );
-        painter.rect_filled(rect, 0.0, egui::Color32::from_rgb(40, 40, 40));
+    /// Start or stop the OSC server
+    fn set_osc_enabled(&mut self, enabled: bool) {
+        if !enabled {
+            self.osc_server = None;
+            self.osc_status = "Stopped".to_string();
+            return;
+        }
+        match OscServer::bind(("0.0.0.0", self.osc_port)) {
+            Ok(server) => {
+                self.osc_status = format!("Listening on UDP port {}", server.local_addr().port());
+                self.osc_server = Some(server);
+            }
+            Err(e) => {
+                self.osc_status = e;
+            }
+        }
+    }
 
-        // Calculate pixels per bar (show 8 bars by default)
-        let bars_to_show = 8;
-        let pixels_per_bar = available_width / bars_to_show as f32;
+    /// Open a control surface port (None: no surface), with a status message
+    fn open_control_surface(port: Option<&str>) -> (Option<ControlSurfaceConnection>, String) {
+        let Some(port) = port else {
+            return (None, String::new());
+        };
+        match ControlSurfaceConnection::connect(port) {
+            Ok(surface) if surface.has_output() => (Some(surface), "Connected".to_string()),
+            Ok(surface) => (Some(surface), "Connected (no output port: no feedback)".to_string()),
+            Err(e) => (None, e),
+        }
+    }
 
-        // Draw grid lines and bar numbers
-        let time_signature = TimeSignature::new(
-            self.time_signature_numerator,
-            self.time_signature_denominator,
-        );
+    /// Switch to another control surface port and remember it
+    fn set_control_surface(&mut self, port: Option<String>) {
+        // Close the previous ports before opening the new ones
+        self.control_surface = None;
+        let (surface, status) = Self::open_control_surface(port.as_deref());
+        self.control_surface = surface;
+        self.control_surface_status = status;
+        self.mackie.reset();
+        self.preferences.midi.control_surface = port;
+        self.save_preferences();
+    }
 
-        for bar in 0..=bars_to_show {
-            let x = rect.min.x + (bar as f32 * pixels_per_bar);
+    /// Preferences section of the Devices tab (saved on every change)
+    fn draw_preferences(&mut self, ui: &mut egui::Ui) {
+        ui.label("Preferences:");
+        let mut changed = false;
 
-            // Bar lines (thicker)
-            painter.line_segment(
-                [egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)],
-                egui::Stroke::new(2.0, egui::Color32::from_rgb(80, 80, 80)),
-            );
+        ui.horizontal(|ui| {
+            let label = ui.label("Theme:");
+            egui::ComboBox::from_id_salt("theme_selector")
+                .selected_text(self.preferences.theme.name())
+                .show_ui(ui, |ui| {
+                    for theme in Theme::ALL {
+                        changed |= ui
+                            .selectable_value(&mut self.preferences.theme, theme, theme.name())
+                            .changed();
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+
+            let label = ui.label("Keyboard layout:");
+            egui::ComboBox::from_id_salt("keymap_selector")
+                .selected_text(self.preferences.keymap.name())
+                .show_ui(ui, |ui| {
+                    for keymap in Keymap::ALL {
+                        changed |= ui
+                            .selectable_value(&mut self.preferences.keymap, keymap, keymap.name())
+                            .changed();
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+        });
 
-            // Bar numbers
-            if bar < bars_to_show {
-                let bar_number = self.cursor_position.musical.bar + bar;
-                painter.text(
-                    egui::pos2(x + 5.0, rect.min.y + 5.0),
-                    egui::Align2::LEFT_TOP,
-                    format!("Bar {}", bar_number),
-                    egui::FontId::default(),
-                    egui::Color32::from_rgb(200, 200, 200),
-                );
-            }
+        ui.horizontal(|ui| {
+            let label = ui.label("Buffer size:");
+            let buffer_label = |size: Option<u32>| match size {
+                Some(frames) => format!("{} frames", frames),
+                None => "Device default".to_string(),
+            };
+            egui::ComboBox::from_id_salt("buffer_size_selector")
+                .selected_text(buffer_label(self.preferences.audio.buffer_size))
+                .show_ui(ui, |ui| {
+                    for size in std::iter::once(None).chain(BUFFER_SIZES.map(Some)) {
+                        changed |= ui
+                            .selectable_value(
+                                &mut self.preferences.audio.buffer_size,
+                                size,
+                                buffer_label(size),
+                            )
+                            .changed();
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+            ui.label("(applies on restart)");
+        });
 
-            // Draw beat lines if space permits
-            if pixels_per_bar >= 80.0 {
-                for beat in 1..time_signature.numerator {
-                    let beat_x =
-                        x + (beat as f32 * pixels_per_bar / time_signature.numerator as f32);
+        ui.horizontal(|ui| {
+            let label = ui.label("Output channels:");
+            let channels_label = |channels: Option<u16>| match channels {
+                Some(channels) => format!("{} channels", channels),
+                None => "Device default".to_string(),
+            };
+            egui::ComboBox::from_id_salt("output_channels_selector")
+                .selected_text(channels_label(self.preferences.audio.output_channels))
+                .show_ui(ui, |ui| {
+                    for channels in std::iter::once(None).chain(OUTPUT_CHANNEL_COUNTS.map(Some)) {
+                        changed |= ui
+                            .selectable_value(
+                                &mut self.preferences.audio.output_channels,
+                                channels,
+                                channels_label(channels),
+                            )
+                            .changed();
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+            ui.label("(applies on restart)");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Autosave every");
+            changed |= ui
+                .add(
+                    egui::DragValue::new(&mut self.preferences.autosave_interval_secs)
+                        .range(0..=3600)
+                        .suffix(" s"),
+                )
+                .on_hover_text("0 disables autosave")
+                .changed();
+        });
+
+        let paths = &mut self.preferences.paths;
+        for (label, directory) in [
+            ("Projects folder:", &mut paths.projects),
+            ("Samples folder:", &mut paths.samples),
+            ("Exports folder:", &mut paths.exports),
+        ] {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                ui.label(
+                    directory
+                        .as_ref()
+                        .map_or("(not set)".to_string(), |path| path.display().to_string()),
+                );
+                if ui.button("📁").clicked()
+                    && let Some(folder) = FileDialog::new().pick_folder()
+                {
+                    *directory = Some(folder);
+                    changed = true;
+                }
+                if directory.is_some() && ui.button("✖").clicked() {
+                    *directory = None;
+                    changed = true;
+                }
+            });
+        }
+
+        if changed {
+            self.apply_theme(ui.ctx());
+            self.save_preferences();
+        }
+    }
+
+    /// Apply the actions received from OSC controllers
+    fn process_remote_actions(&mut self) {
+        let Some(server) = &self.osc_server else {
+            return;
+        };
+        let actions = server.poll();
+        if actions.is_empty() {
+            return;
+        }
+
+        for action in actions {
+            self.apply_remote_action(action, "OSC");
+        }
+        self.sync_controls_from_state();
+    }
+
+    /// Apply a remote action, driving the sequencer for transport actions
+    fn apply_remote_action(&mut self, action: RemoteAction, source: &str) {
+        match action {
+            RemoteAction::Play if !self.sequencer.state().is_playing() => self.sequencer.play(),
+            RemoteAction::Stop => {
+                self.finish_midi_take();
+                self.sequencer.stop();
+                self.finish_audio_take();
+            }
+            RemoteAction::Record if self.sequencer.state().is_recording() => {
+                self.finish_midi_take();
+                self.sequencer.pause();
+                self.finish_audio_take();
+            }
+            RemoteAction::Record => {
+                self.sequencer.record();
+                if let Err(e) = self.start_audio_take() {
+                    self.show_error(format!("Failed to start recording: {}", e));
+                }
+            }
+            RemoteAction::Rewind => self.sequencer.set_position_samples(0),
+            _ => {}
+        }
+        if let Err(e) = action.apply(&mut self.daw_state) {
+            eprintln!("{} {:?} failed: {}", source, action, e);
+        }
+    }
+
+    /// Apply the control surface moves, then update its faders, LEDs and
+    /// scribble strips
+    fn process_control_surface(&mut self) {
+        let Some(surface) = &self.control_surface else {
+            return;
+        };
+        let events = surface.poll();
+        let moved = !events.is_empty();
+        for event in events {
+            if let Some(action) = self.mackie.handle(event, &self.daw_state) {
+                self.apply_remote_action(action, "Control surface");
+            }
+        }
+        if moved {
+            self.volume_ui = self.daw_state.volume;
+            self.volume_atomic.set(self.daw_state.volume);
+        }
+
+        let transport = self.sequencer.state();
+        let messages =
+            self.mackie.feedback(&self.daw_state, transport.is_playing(), transport.is_recording());
+        if let Some(surface) = &mut self.control_surface
+            && let Err(e) = surface.send(&messages)
+        {
+            self.control_surface_status = e;
+        }
+    }
+
+    /// Apply the MIDI controllers mapped to synth parameters (and finish a MIDI learn)
+    fn process_midi_controllers(&mut self) {
+        let changes = self.midi_connection_manager.poll_control_changes();
+        if changes.is_empty() {
+            return;
+        }
+
+        let mut applied = false;
+        for (cc, value) in changes {
+            if let Some(action) = self.daw_state.cc_map.handle(cc, value) {
+                self.apply_remote_action(action, "MIDI CC");
+                applied = true;
+            }
+        }
+        if applied {
+            self.sync_controls_from_state();
+        }
+    }
+
+    /// Mirror the remote-controlled values in the UI controls
+    fn sync_controls_from_state(&mut self) {
+        if self.sequencer_tempo != self.daw_state.tempo.bpm() {
+            self.sequencer_tempo = self.daw_state.tempo.bpm();
+            self.sequencer.set_tempo(Tempo::new(self.sequencer_tempo));
+        }
+        self.volume_ui = self.daw_state.volume;
+        self.volume_atomic.set(self.daw_state.volume);
+        self.adsr_attack = self.daw_state.adsr.attack;
+        self.adsr_decay = self.daw_state.adsr.decay;
+        self.adsr_sustain = self.daw_state.adsr.sustain;
+        self.adsr_release = self.daw_state.adsr.release;
+        self.metronome_enabled = self.daw_state.metronome_enabled;
+        self.metronome_volume = self.daw_state.metronome_volume;
+    }
+
+    /// Update cursor position from sequencer current position
+    fn update_cursor_position(&mut self) {
+        self.cursor_position = self.sequencer.position();
+    }
+
+    /// Draw timeline with cursor and grid
+    fn draw_timeline_with_cursor(&mut self, ui: &mut egui::Ui) {
+        let available_width = ui.available_width();
+        let timeline_height = 100.0;
+
+        // Update cursor position from sequencer
+        self.update_cursor_position();
+
+        // Draw timeline background
+        let painter = ui.painter();
+        let rect = egui::Rect::from_min_size(
+            ui.cursor().min,
+            egui::vec2(available_width, timeline_height),
+        );
+        painter.rect_filled(rect, 0.0, egui::Color32::from_rgb(40, 40, 40));
+
+        // Calculate pixels per bar (show 8 bars by default)
+        let bars_to_show = 8;
+        let pixels_per_bar = available_width / bars_to_show as f32;
+
+        // Draw grid lines and bar numbers
+        let time_signature = TimeSignature::new(
+            self.time_signature_numerator,
+            self.time_signature_denominator,
+        );
+
+        for bar in 0..=bars_to_show {
+            let x = rect.min.x + (bar as f32 * pixels_per_bar);
+
+            // Bar lines (thicker)
+            painter.line_segment(
+                [egui::pos2(x, rect.min.y), egui::pos2(x, rect.max.y)],
+                egui::Stroke::new(2.0, egui::Color32::from_rgb(80, 80, 80)),
+            );
+
+            // Bar numbers
+            if bar < bars_to_show {
+                let bar_number = self.cursor_position.musical.bar + bar;
+                painter.text(
+                    egui::pos2(x + 5.0, rect.min.y + 5.0),
+                    egui::Align2::LEFT_TOP,
+                    format!("Bar {}", bar_number),
+                    egui::FontId::default(),
+                    egui::Color32::from_rgb(200, 200, 200),
+                );
+            }
+
+            // Draw beat lines if space permits
+            if pixels_per_bar >= 80.0 {
+                for beat in 1..time_signature.numerator {
+                    let beat_x =
+                        x + (beat as f32 * pixels_per_bar / time_signature.numerator as f32);
                     painter.line_segment(
                         [
                             egui::pos2(beat_x, rect.min.y),
@@ -644,6 +1822,7 @@ impl DawApp {
             egui::Sense::click(),
         );
 
+        let mut target = None;
         if response.clicked()
             && let Some(pointer_pos) = response.interact_pointer_pos()
         {
@@ -668,10 +1847,36 @@ impl DawApp {
                 clicked_musical =
                     clicked_musical.quantize_to_subdivision(&time_signature, self.grid_subdivision);
             }
+            target = Some(clicked_musical);
+        }
+
+        // Keyboard: once focused (Tab), arrows move the cursor by one grid
+        // step and Shift+arrows by one bar
+        let (step, by_bar) = a11y::arrow_step(&response);
+        if step != 0 {
+            let ticks_per_beat = MusicalTime::TICKS_PER_QUARTER as i64;
+            let step_ticks = if by_bar {
+                ticks_per_beat * time_signature.numerator as i64
+            } else if self.snap_to_grid_enabled {
+                ticks_per_beat / self.grid_subdivision.max(1) as i64
+            } else {
+                ticks_per_beat
+            };
+            let ticks = self.cursor_position.musical.to_total_ticks(&time_signature) as i64;
+            let mut moved = MusicalTime::from_total_ticks(
+                (ticks + step as i64 * step_ticks).max(0) as u64,
+                &time_signature,
+            );
+            if self.snap_to_grid_enabled {
+                moved = moved.quantize_to_subdivision(&time_signature, self.grid_subdivision);
+            }
+            target = Some(moved);
+        }
 
+        if let Some(target) = target {
             // Create new position and set it
             let new_position = Position::from_musical(
-                clicked_musical,
+                target,
                 self.sequencer.sample_rate(),
                 self.sequencer.tempo(),
                 &time_signature,
@@ -686,6 +1891,14 @@ impl DawApp {
                 let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
             }
         }
+
+        let musical = self.cursor_position.musical;
+        a11y::describe(
+            &response,
+            egui::accesskit::Role::Slider,
+            "Timeline cursor",
+            &format!("Bar {}, beat {}", musical.bar, musical.beat),
+        );
     }
 
     /// Handle PC keyboard input globally (independent of the current tab)
@@ -698,22 +1911,8 @@ impl DawApp {
             return;
         }
 
-        // Mapping QWERTY keyboard → MIDI notes (C4 = 60)
-        let key_map = [
-            ('a', 60), // C4
-            ('w', 61), // C#4
-            ('s', 62), // D4
-            ('e', 63), // D#4
-            ('d', 64), // E4
-            ('f', 65), // F4
-            ('t', 66), // F#4
-            ('g', 67), // G4
-            ('y', 68), // G#4
-            ('h', 69), // A4
-            ('u', 70), // A#4
-            ('j', 71), // B4
-            ('k', 72), // C5
-        ];
+        // Computer keyboard → MIDI notes (C4 = 60), following the layout preference
+        let key_map = self.preferences.keymap.note_keys();
 
         for (key, note) in &key_map {
             let key_code =
@@ -727,96 +1926,1762 @@ impl DawApp {
         }
     }
 
-    /// Render the visual virtual keyboard (no input handling)
-    fn draw_keyboard_ui(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Virtual keyboard");
-        ui.label("Use the keyboard keys to play the notes:");
-        ui.label("A W S E D F T G Y H U J K = notes (Do to Do)");
-        ui.add_space(10.0);
+    /// Render the visual virtual keyboard (no input handling)
+    fn draw_keyboard_ui(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Virtual keyboard");
+        ui.label("Use the keyboard keys to play the notes:");
+        let keys: Vec<String> = self
+            .preferences
+            .keymap
+            .note_keys()
+            .iter()
+            .map(|(key, _)| key.to_uppercase().to_string())
+            .collect();
+        ui.label(format!("{} = notes (Do to Do)", keys.join(" ")));
+        ui.add_space(10.0);
+
+        // Computer keyboard → MIDI notes (C4 = 60), following the layout preference
+        let key_map = self.preferences.keymap.note_keys();
+
+        // Display the visual keyboard only
+        ui.horizontal(|ui| {
+            for (key, note) in &key_map {
+                let is_active = self.active_notes.contains(note);
+                let is_black = matches!(note % 12, 1 | 3 | 6 | 8 | 10);
+
+                let note_name = match note % 12 {
+                    0 => "C",
+                    1 => "C#",
+                    2 => "D",
+                    3 => "D#",
+                    4 => "E",
+                    5 => "F",
+                    6 => "F#",
+                    7 => "G",
+                    8 => "G#",
+                    9 => "A",
+                    10 => "A#",
+                    11 => "B",
+                    _ => "?",
+                };
+
+                let octave = note / 12 - 1;
+                let label = format!("{}{}\n({})", note_name, octave, key.to_uppercase());
+
+                let button = if is_black {
+                    egui::Button::new(label)
+                        .fill(if is_active {
+                            egui::Color32::from_rgb(100, 100, 255)
+                        } else {
+                            egui::Color32::from_gray(50)
+                        })
+                        .min_size(egui::vec2(50.0, 80.0))
+                } else {
+                    egui::Button::new(label)
+                        .fill(if is_active {
+                            egui::Color32::from_rgb(150, 150, 255)
+                        } else {
+                            egui::Color32::WHITE
+                        })
+                        .stroke(egui::Stroke::new(1.0, egui::Color32::BLACK))
+                        .min_size(egui::vec2(50.0, 80.0))
+                };
+
+                if ui.add(button).clicked() {
+                    // Toggle note on click
+                    if is_active {
+                        self.send_note_off(*note);
+                    } else {
+                        self.send_note_on(*note);
+                    }
+                }
+            }
+        });
+
+        ui.add_space(10.0);
+        ui.label(format!("Notes actives : {}", self.active_notes.len()));
+    }
+
+    /// Affiche la barre de statut en bas de la fenêtre
+    fn draw_script_console(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Script Console");
+        ui.label("Rhai scripts edit the active pattern, the tempo and the synth parameters (times in beats).");
+
+        ui.horizontal(|ui| {
+            ui.label("Examples:");
+            for (name, source) in SCRIPT_EXAMPLES {
+                if ui.button(*name).clicked() {
+                    self.script_source = source.to_string();
+                }
+            }
+        });
+
+        ui.add(
+            egui::TextEdit::multiline(&mut self.script_source)
+                .code_editor()
+                .desired_rows(14)
+                .desired_width(f32::INFINITY),
+        );
+
+        ui.horizontal(|ui| {
+            if ui.button("▶ Run").clicked() {
+                self.run_script();
+            }
+            if ui.button("Clear output").clicked() {
+                self.script_output.clear();
+            }
+        });
+
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .max_height(200.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in &self.script_output {
+                    ui.monospace(line);
+                }
+            });
+    }
+
+    /// Send the audio tracks to the clip player
+    fn send_audio_tracks(&mut self) {
+        if self.daw_state.send_audio_tracks().is_err() {
+            eprintln!("Failed to send SetAudioTracks command: ringbuffer full");
+        }
+    }
+
+    /// Return buses: shared reverb/delays fed by the track sends
+    fn draw_return_buses(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Return Buses");
+        ui.horizontal(|ui| {
+            let effects = [
+                ReturnEffect::Reverb(ReverbParams::default()),
+                ReturnEffect::Delay(DelayParams::default()),
+                ReturnEffect::StereoDelay(StereoDelayParams::default()),
+            ];
+            for effect in effects {
+                if ui.button(format!("➕ {} Bus", effect.name())).clicked() {
+                    let bus = ReturnBus {
+                        name: effect.name().to_string(),
+                        params: ReturnBusParams::new(effect),
+                    };
+                    if let Err(e) = self.daw_state.add_return_bus(bus) {
+                        self.show_error(format!("Failed to add return bus: {}", e));
+                    }
+                }
+            }
+        });
+
+        let outputs = self.output_choices();
+        let mut remove_bus: Option<usize> = None;
+        for index in 0..MAX_RETURN_BUSES {
+            let Some(mut bus) = self.daw_state.return_buses[index].clone() else {
+                continue;
+            };
+            let mut changed = false;
+            ui.horizontal(|ui| {
+                ui.push_id(format!("return_bus_{}", index), |ui| {
+                    changed |= ui
+                        .add(egui::TextEdit::singleline(&mut bus.name).desired_width(80.0))
+                        .changed();
+                    let params = &mut bus.params;
+                    match &mut params.effect {
+                        ReturnEffect::Reverb(reverb) => {
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut reverb.room_size, 0.0..=1.0)
+                                        .text("Size"),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut reverb.damping, 0.0..=1.0)
+                                        .text("Damping"),
+                                )
+                                .changed();
+                        }
+                        ReturnEffect::Delay(delay) => {
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut delay.time_ms, 1.0..=MASTER_DELAY_MAX_MS)
+                                        .logarithmic(true)
+                                        .text("Time ms"),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut delay.feedback, 0.0..=0.99)
+                                        .text("Feedback"),
+                                )
+                                .changed();
+                        }
+                        ReturnEffect::StereoDelay(delay) => {
+                            let sides = [
+                                ("L", &mut delay.left_sync, &mut delay.left_ms),
+                                ("R", &mut delay.right_sync, &mut delay.right_ms),
+                            ];
+                            for (side, sync, time_ms) in sides {
+                                ui.label(side);
+                                let id = format!("return_bus_{}_{}", index, side);
+                                changed |= note_division_picker(ui, &id, sync, "Free (ms)");
+                                if sync.is_none() {
+                                    changed |= ui
+                                        .add(
+                                            egui::Slider::new(time_ms, 1.0..=MASTER_DELAY_MAX_MS)
+                                                .logarithmic(true)
+                                                .text("ms"),
+                                        )
+                                        .changed();
+                                }
+                            }
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut delay.feedback, 0.0..=0.99)
+                                        .text("Feedback"),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut delay.low_cut_hz, 20.0..=2000.0)
+                                        .logarithmic(true)
+                                        .text("Low cut Hz"),
+                                )
+                                .changed();
+                            changed |= ui
+                                .add(
+                                    egui::Slider::new(&mut delay.high_cut_hz, 500.0..=20000.0)
+                                        .logarithmic(true)
+                                        .text("High cut Hz"),
+                                )
+                                .changed();
+                            changed |= ui.checkbox(&mut delay.ping_pong, "Ping-pong").changed();
+                        }
+                    }
+                    changed |= ui
+                        .add(
+                            egui::Slider::new(&mut params.level, 0.0..=MAX_CLIP_GAIN)
+                                .text("Return"),
+                        )
+                        .changed();
+                    changed |= ui.checkbox(&mut params.muted, "Mute").changed();
+                    let id = format!("return_bus_{}", index);
+                    changed |= output_picker(ui, &id, &mut params.output, &outputs);
+                    if ui.button("🗑").on_hover_text("Remove bus").clicked() {
+                        remove_bus = Some(index);
+                    }
+                });
+            });
+            if changed {
+                let _ = self.daw_state.set_return_bus(index, Some(bus));
+            }
+        }
+        if let Some(index) = remove_bus {
+            let _ = self.daw_state.set_return_bus(index, None);
+            self.daw_state.audio_tracks.clear_sends(index);
+            self.send_audio_tracks();
+        }
+        ui.separator();
+    }
+
+    /// Master bus: effect chain run on the track mix, in a reorderable order
+    fn draw_master_bus(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Master Bus");
+        let mut chain = self.daw_state.master_chain;
+        let snapshot = self.engine_snapshot.read();
+        let mut swap: Option<(usize, usize)> = None;
+        let last = chain.order.len() - 1;
+        for (index, effect) in chain.order.into_iter().enumerate() {
+            let mut changed = false;
+            ui.horizontal_wrapped(|ui| {
+                ui.push_id(format!("master_{}", effect.name()), |ui| {
+                    if ui.add_enabled(index > 0, egui::Button::new("⬆")).clicked() {
+                        swap = Some((index, index - 1));
+                    }
+                    if ui.add_enabled(index < last, egui::Button::new("⬇")).clicked() {
+                        swap = Some((index, index + 1));
+                    }
+                    let mut enabled = !chain.is_bypassed(effect);
+                    if ui.checkbox(&mut enabled, effect.name()).changed() {
+                        let _ = self.daw_state.set_master_effect_bypass(effect, !enabled);
+                    }
+                    let mut slider =
+                        |value: &mut f32, range: RangeInclusive<f32>, text: &str, log: bool| {
+                            changed |= ui
+                                .add(egui::Slider::new(value, range).logarithmic(log).text(text))
+                                .changed();
+                        };
+                    match effect {
+                        MasterEffect::Reverb => {
+                            let reverb = &mut chain.reverb;
+                            slider(&mut reverb.room_size, 0.0..=1.0, "Size", false);
+                            slider(&mut reverb.damping, 0.0..=1.0, "Damping", false);
+                            slider(&mut reverb.mix, 0.0..=1.0, "Mix", false);
+                        }
+                        MasterEffect::Delay => {
+                            let delay = &mut chain.delay;
+                            slider(&mut delay.time_ms, 1.0..=MASTER_DELAY_MAX_MS, "Time ms", true);
+                            slider(&mut delay.feedback, 0.0..=0.99, "Feedback", false);
+                            slider(&mut delay.mix, 0.0..=1.0, "Mix", false);
+                        }
+                        MasterEffect::Eq => {
+                            let eq = &mut chain.eq;
+                            slider(&mut eq.low_gain_db, -18.0..=18.0, "Low dB", false);
+                            slider(&mut eq.low_freq, 20.0..=1000.0, "Low Hz", true);
+                            slider(&mut eq.mid_gain_db, -18.0..=18.0, "Mid dB", false);
+                            slider(&mut eq.mid_freq, 100.0..=10000.0, "Mid Hz", true);
+                            slider(&mut eq.mid_q, 0.1..=10.0, "Q", true);
+                            slider(&mut eq.high_gain_db, -18.0..=18.0, "High dB", false);
+                            slider(&mut eq.high_freq, 1000.0..=20000.0, "High Hz", true);
+                        }
+                        MasterEffect::Compressor => {
+                            let compressor = &mut chain.compressor;
+                            let threshold = &mut compressor.threshold_db;
+                            slider(threshold, -60.0..=0.0, "Threshold dB", false);
+                            slider(&mut compressor.ratio, 1.0..=20.0, "Ratio", false);
+                            slider(&mut compressor.attack_ms, 0.1..=100.0, "Attack ms", true);
+                            slider(&mut compressor.release_ms, 1.0..=2000.0, "Release ms", true);
+                            slider(&mut compressor.makeup_db, -24.0..=24.0, "Makeup dB", false);
+                            gain_reduction_meter(ui, snapshot.compressor_reduction_db);
+                        }
+                        MasterEffect::Limiter => {
+                            let limiter = &mut chain.limiter;
+                            slider(&mut limiter.threshold_db, -24.0..=0.0, "Ceiling dB", false);
+                            slider(&mut limiter.release_ms, 1.0..=1000.0, "Release ms", true);
+                            gain_reduction_meter(ui, snapshot.limiter_reduction_db);
+                        }
+                    }
+                });
+            });
+            if changed {
+                let _ = match effect {
+                    MasterEffect::Reverb => self.daw_state.set_master_reverb(chain.reverb),
+                    MasterEffect::Delay => self.daw_state.set_master_delay(chain.delay),
+                    MasterEffect::Eq => self.daw_state.set_master_eq(chain.eq),
+                    MasterEffect::Compressor => {
+                        self.daw_state.set_master_compressor(chain.compressor)
+                    }
+                    MasterEffect::Limiter => self.daw_state.set_master_limiter(chain.limiter),
+                };
+            }
+        }
+        if let Some((from, to)) = swap {
+            let mut order = self.daw_state.master_chain.order;
+            order.swap(from, to);
+            let _ = self.daw_state.set_master_effect_order(order);
+        }
+        ui.separator();
+    }
+
+    /// Outputs the tracks can play on (the master bus, then the pairs past the mix channels)
+    fn output_choices(&self) -> Vec<TrackOutput> {
+        let channels = self.engine_snapshot.read().output_channels;
+        TrackOutput::choices(channels, self.daw_state.speaker_layout).collect()
+    }
+
+    /// Speaker layout of the mix
+    fn draw_speaker_layout(&mut self, ui: &mut egui::Ui) {
+        let channels = self.engine_snapshot.read().output_channels;
+        let mut layout = self.daw_state.speaker_layout;
+        ui.horizontal(|ui| {
+            let label = ui.label("Speaker layout:");
+            egui::ComboBox::from_id_salt("speaker_layout_selector")
+                .selected_text(layout.name())
+                .show_ui(ui, |ui| {
+                    for choice in SpeakerLayout::ALL {
+                        ui.selectable_value(&mut layout, choice, choice.name());
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+            if layout.on_device(channels) != layout {
+                ui.label(format!("(plays in stereo: the device has {} channels)", channels));
+            }
+        });
+        if layout != self.daw_state.speaker_layout {
+            let _ = self.daw_state.set_speaker_layout(layout);
+        }
+    }
+
+    /// Generate menu: euclidean rhythms, random melodies and fills, written into
+    /// the pattern as one undo step each
+    fn draw_generate_menu(&mut self, ui: &mut egui::Ui) {
+        let mut pattern = self.daw_state.pattern.clone();
+        let settings = &mut self.generator;
+        let grid = StepGrid::new(
+            &pattern,
+            settings.steps_per_beat,
+            self.sequencer.sample_rate(),
+            *self.sequencer.tempo(),
+            *self.sequencer.time_signature(),
+        );
+        let selection = self.piano_roll_editor.selected_notes();
+        let scale = self.piano_roll_editor.scale().unwrap_or(Scale::new(0, ScaleKind::Major));
+        let mut edit: Option<(&'static str, Vec<NoteId>)> = None;
+
+        ui.horizontal(|ui| {
+            ui.label("Grid:");
+            for (steps, label) in [(2, "1/8"), (4, "1/16"), (8, "1/32")] {
+                ui.selectable_value(&mut settings.steps_per_beat, steps, label);
+            }
+            ui.labelled("Velocity:", egui::DragValue::new(&mut settings.velocity).range(1..=127));
+        });
+        ui.separator();
+
+        ui.label("Euclidean rhythm");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut settings.pulses).range(1..=32).prefix("pulses "));
+            ui.add(egui::DragValue::new(&mut settings.steps).range(1..=32).prefix("steps "));
+            let max_rotation = settings.steps.saturating_sub(1);
+            settings.rotation = settings.rotation.min(max_rotation);
+            ui.add(
+                egui::DragValue::new(&mut settings.rotation)
+                    .range(0..=max_rotation)
+                    .prefix("rotate "),
+            );
+            ui.add(egui::DragValue::new(&mut settings.pitch).range(0..=127).prefix("note "));
+        });
+        let preview: String = generate::euclidean_rhythm(
+            settings.pulses,
+            settings.steps,
+            settings.rotation,
+        )
+        .iter()
+        .map(|&hit| if hit { 'x' } else { '·' })
+        .collect();
+        ui.monospace(preview);
+        if ui
+            .button("Write Rhythm")
+            .on_hover_text("Replace the notes of this pitch with the rhythm")
+            .clicked()
+        {
+            let ids = generate::generate_euclidean(
+                &mut pattern,
+                &grid,
+                settings.pitch,
+                settings.pulses,
+                settings.steps,
+                settings.rotation,
+                settings.velocity,
+            );
+            edit = Some(("Generate Rhythm", ids));
+        }
+        ui.separator();
+
+        ui.label(format!("Random melody ({})", scale.name()));
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut settings.low).range(0..=127).prefix("from "));
+            ui.add(egui::DragValue::new(&mut settings.high).range(0..=127).prefix("to "));
+            ui.add(egui::Slider::new(&mut settings.density, 0.0..=1.0).text("Density"));
+            ui.add(egui::DragValue::new(&mut settings.seed).prefix("seed "));
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Write Melody").clicked() {
+                let ids = generate::generate_melody(
+                    &mut pattern,
+                    &grid,
+                    scale,
+                    (settings.low, settings.high),
+                    settings.density,
+                    settings.velocity,
+                    settings.seed,
+                );
+                edit = Some(("Generate Melody", ids));
+            }
+            if ui.button("🎲 New Seed").clicked() {
+                settings.seed = settings.seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            }
+        });
+        ui.separator();
+
+        ui.label(format!("Fills ({} selected notes)", selection.len()));
+        ui.add_enabled_ui(!selection.is_empty(), |ui| {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut settings.ratchet).range(2..=8).prefix("× "));
+                if ui.button("Ratchet").on_hover_text("Split each note into repeats").clicked()
+                    && let Some(ids) =
+                        generate::ratchet(&mut pattern, &selection, settings.ratchet, &grid)
+                {
+                    edit = Some(("Ratchet Notes", ids));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut settings.repeats).range(1..=16).prefix("× "));
+                if ui.button("Repeat").on_hover_text("Copy the selection after itself").clicked()
+                {
+                    let ids = generate::repeat(&mut pattern, &selection, settings.repeats, &grid);
+                    edit = Some(("Repeat Notes", ids));
+                }
+            });
+        });
+
+        if let Some((description, ids)) = edit {
+            let notes = pattern.notes().to_vec();
+            let command = Box::new(SetPatternNotesCommand::new(description, notes));
+            match self.command_manager.execute(command, &mut self.daw_state) {
+                Ok(()) => self.piano_roll_editor.select_notes(&ids),
+                Err(e) => eprintln!("Failed to generate notes: {}", e),
+            }
+            ui.close_menu();
+        }
+    }
+
+    /// Quantize menu: snap the selected notes (or all of them) to the grid, one
+    /// undo step
+    fn draw_quantize_menu(&mut self, ui: &mut egui::Ui) {
+        let settings = &mut self.quantize;
+        ui.horizontal(|ui| {
+            ui.label("Grid:");
+            for (steps, label) in [(1, "1/4"), (2, "1/8"), (4, "1/16"), (8, "1/32")] {
+                ui.selectable_value(&mut settings.steps_per_beat, steps, label);
+            }
+        });
+        let mut strength = settings.strength * 100.0;
+        if ui
+            .labelled("Strength:", egui::Slider::new(&mut strength, 0.0..=100.0).suffix(" %"))
+            .changed()
+        {
+            settings.strength = strength / 100.0;
+        }
+        ui.labelled("Swing:", egui::Slider::new(&mut settings.swing, MIN_SWING..=MAX_SWING).suffix(" %"));
+        ui.checkbox(&mut self.input_quantize, "Input quantize (while recording)");
+        ui.separator();
+
+        let selection = self.piano_roll_editor.selected_notes();
+        let label = if selection.is_empty() { "Quantize All Notes" } else { "Quantize Selection" };
+        if ui.button(label).clicked() {
+            let command = Box::new(QuantizeNotesCommand::new(
+                selection,
+                self.quantize,
+                self.sequencer.sample_rate(),
+            ));
+            match self.command_manager.execute(command, &mut self.daw_state) {
+                Ok(()) => self.mark_project_modified(),
+                Err(e) => eprintln!("Failed to quantize notes: {}", e),
+            }
+            ui.close_menu();
+        }
+    }
+
+    /// Groove templates: extract a feel from a take or an audio loop and apply
+    /// it, and the swing and template the pattern plays with
+    /// Song arrangement: pattern clips on the synth and instrument tracks
+    fn draw_arrangement(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Arrangement");
+        let mut song_mode = self.daw_state.song_mode;
+        if ui
+            .checkbox(&mut song_mode, "Song mode")
+            .on_hover_text("Play the clips of the arrangement instead of looping the patterns")
+            .changed()
+        {
+            match self.daw_state.set_song_mode(song_mode) {
+                Ok(()) => self.mark_project_modified(),
+                Err(e) => self.show_error(format!("Failed to change song mode: {}", e)),
+            }
+        }
+
+        let tracks = self.clip_tracks();
+        let patterns = clip_patterns(&self.daw_state.pattern, &self.project_patterns);
+        let bar_samples = self
+            .sequencer
+            .tempo()
+            .bar_duration_samples(self.sequencer.sample_rate(), self.sequencer.time_signature());
+        let playhead_bars = self.sequencer.shared_state().position_samples() as f64 / bar_samples;
+        let edit = self.arrangement_editor.show(
+            ui,
+            &self.daw_state.arrangement,
+            &tracks,
+            &patterns,
+            playhead_bars,
+        );
+        if let Some((description, arrangement)) = edit {
+            let command = Box::new(SetArrangementCommand::new(description, arrangement));
+            match self.command_manager.execute(command, &mut self.daw_state) {
+                Ok(()) => self.mark_project_modified(),
+                Err(e) => eprintln!("Failed to edit arrangement: {}", e),
+            }
+        }
+    }
+
+    /// Tracks playing clips: the synth track, then the instrument tracks
+    fn clip_tracks(&self) -> Vec<(u32, String)> {
+        let mut tracks = vec![(SYNTH_TRACK, "Synth".to_string())];
+        tracks.extend(
+            self.daw_state
+                .instrument_tracks
+                .iter()
+                .map(|track| (track.id, track.name.clone())),
+        );
+        tracks
+    }
+
+    fn draw_session(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Session");
+        let snapshot = self.engine_snapshot.read();
+        let launches = self
+            .daw_state
+            .session_launches(snapshot.position_samples)
+            .to_vec();
+        let tracks = self.clip_tracks();
+        let patterns = clip_patterns(&self.daw_state.pattern, &self.project_patterns);
+        let action = self.session_editor.show(
+            ui,
+            &self.daw_state.session,
+            &launches,
+            &tracks,
+            &patterns,
+        );
+        let result = match action {
+            None => return,
+            Some(SessionAction::Edit(description, session)) => {
+                let command = Box::new(SetSessionCommand::new(description, session));
+                match self.command_manager.execute(command, &mut self.daw_state) {
+                    Ok(()) => self.mark_project_modified(),
+                    Err(e) => eprintln!("Failed to edit session: {}", e),
+                }
+                return;
+            }
+            Some(SessionAction::Launch { track, scene }) => {
+                self.daw_state.launch_clip(track, scene, &snapshot)
+            }
+            Some(SessionAction::LaunchScene(scene)) => {
+                self.daw_state.launch_scene(scene, &snapshot)
+            }
+            Some(SessionAction::StopAll) => self.daw_state.stop_session(&snapshot),
+            Some(SessionAction::Release) => self.daw_state.release_session(),
+        };
+        if let Err(e) = result {
+            self.show_error(format!("Failed to launch clip: {}", e));
+        }
+    }
+
+    fn draw_groove(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Groove");
+        let sample_rate = self.sequencer.sample_rate();
+        let tempo = *self.sequencer.tempo();
+        let time_signature = *self.sequencer.time_signature();
+        // One bar of 1/16 notes
+        let steps_per_beat = 4;
+        let length_steps = (time_signature.beats_per_bar() * steps_per_beat as f64) as usize;
+        let mut extracted: Option<Result<Groove, String>> = None;
+
+        ui.horizontal(|ui| {
+            let label = ui.label("Template:");
+            self.selected_groove = self.selected_groove.min(self.grooves.len() - 1);
+            egui::ComboBox::from_id_salt("groove_template")
+                .selected_text(&self.grooves[self.selected_groove].name)
+                .show_ui(ui, |ui| {
+                    for (index, groove) in self.grooves.iter().enumerate() {
+                        ui.selectable_value(&mut self.selected_groove, index, &groove.name);
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+            ui.add(egui::Slider::new(&mut self.groove_strength, 0.0..=1.0).text("Strength"));
+            if ui
+                .add_enabled(
+                    self.daw_state.pattern.note_count() > 0,
+                    egui::Button::new("Apply Groove"),
+                )
+                .on_hover_text("Move the pattern notes toward the template's timing and accents")
+                .clicked()
+            {
+                let mut pattern = self.daw_state.pattern.clone();
+                self.grooves[self.selected_groove].apply(
+                    &mut pattern,
+                    self.groove_strength,
+                    sample_rate,
+                    &tempo,
+                    &time_signature,
+                );
+                let notes = pattern.notes().to_vec();
+                let command = Box::new(SetPatternNotesCommand::new("Apply Groove", notes));
+                if let Err(e) = self.command_manager.execute(command, &mut self.daw_state) {
+                    eprintln!("Failed to apply groove: {}", e);
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut self.groove_swing, 50.0..=75.0).text("Swing %"));
+            if ui.button("➕ Add Swing").clicked() {
+                extracted = Some(Ok(Groove::swing(self.groove_swing, steps_per_beat)));
+            }
+            if ui
+                .add_enabled(
+                    self.daw_state.pattern.note_count() > 0,
+                    egui::Button::new("Extract from Pattern"),
+                )
+                .on_hover_text("Use the timing and velocities of the notes (e.g. a recorded take)")
+                .clicked()
+            {
+                extracted = Some(Groove::from_notes(
+                    format!("{} groove", self.daw_state.pattern.name),
+                    self.daw_state.pattern.notes(),
+                    tempo.beat_duration_samples(sample_rate),
+                    steps_per_beat,
+                    length_steps,
+                ));
+            }
+        });
+
+        // Audio loops: the transients of a clip's region at its source tempo
+        let clips: Vec<&AudioClip> = self
+            .daw_state
+            .audio_tracks
+            .tracks()
+            .iter()
+            .flat_map(|track| track.clips.iter())
+            .collect();
+        if !clips.is_empty() {
+            ui.horizontal(|ui| {
+                let selected = clips
+                    .iter()
+                    .find(|clip| Some(clip.id) == self.groove_clip)
+                    .copied()
+                    .unwrap_or(clips[0]);
+                egui::ComboBox::from_id_salt("groove_clip")
+                    .selected_text(&selected.name)
+                    .show_ui(ui, |ui| {
+                        for clip in &clips {
+                            ui.selectable_value(&mut self.groove_clip, Some(clip.id), &clip.name);
+                        }
+                    })
+                    .response
+                    .named("Groove source clip");
+                if ui
+                    .button("Extract from Clip")
+                    .on_hover_text("Use the clip's transients and their levels")
+                    .clicked()
+                {
+                    let source_bpm = selected.warp.as_ref().map_or_else(
+                        || estimate_loop_bpm(selected.length, sample_rate as f32),
+                        |warp| warp.source_bpm,
+                    );
+                    extracted = Some(Groove::from_clip(
+                        format!("{} groove", selected.name),
+                        selected,
+                        source_bpm,
+                        sample_rate as f32,
+                        steps_per_beat,
+                        length_steps,
+                    ));
+                }
+            });
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button("💾 Save Template...").clicked()
+                && let Some(path) = file_dialog(None)
+                    .add_filter("Groove Templates", &["json"])
+                    .set_file_name(format!("{}.json", self.grooves[self.selected_groove].name))
+                    .save_file()
+                && let Err(e) = self.grooves[self.selected_groove].save_to_file(&path)
+            {
+                extracted = Some(Err(e));
+            }
+            if ui.button("📂 Load Template...").clicked()
+                && let Some(path) = file_dialog(None)
+                    .add_filter("Groove Templates", &["json"])
+                    .pick_file()
+            {
+                extracted = Some(Groove::load_from_file(&path));
+            }
+        });
+
+        // Feel the pattern plays with (the notes stay where they are)
+        let mut groove = self.daw_state.pattern.groove.clone();
+        let mut groove_changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Pattern feel:");
+            let mut swing = self.pattern_swing_edit.unwrap_or(groove.swing);
+            let response = ui
+                .add(egui::Slider::new(&mut swing, MIN_SWING..=MAX_SWING).text("Swing").suffix(" %").fixed_decimals(0))
+                .on_hover_text("Delay every second 1/16 note at playback (50% = straight)");
+            if response.changed() {
+                self.pattern_swing_edit = Some(swing);
+            }
+            if (response.drag_stopped() || (response.changed() && !response.dragged()))
+                && let Some(swing) = self.pattern_swing_edit.take()
+            {
+                groove.swing = swing;
+                groove_changed = true;
+            }
+
+            let label = ui.label("Play template:");
+            let selected = groove.template.as_ref().map_or("None", |template| template.name.as_str()).to_string();
+            egui::ComboBox::from_id_salt("pattern_groove_template")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    if ui.selectable_label(groove.template.is_none(), "None").clicked() {
+                        groove.template = None;
+                        groove_changed = true;
+                    }
+                    for template in &self.grooves {
+                        let current = groove.template.as_ref() == Some(template);
+                        if ui.selectable_label(current, &template.name).clicked() {
+                            groove.template = Some(template.clone());
+                            groove_changed = true;
+                        }
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+        });
+        if groove_changed {
+            let command = Box::new(SetPatternGrooveCommand::new(groove));
+            match self.command_manager.execute(command, &mut self.daw_state) {
+                Ok(()) => self.mark_project_modified(),
+                Err(e) => eprintln!("Failed to set pattern groove: {}", e),
+            }
+        }
+
+        match extracted {
+            Some(Ok(groove)) => {
+                self.grooves.push(groove);
+                self.selected_groove = self.grooves.len() - 1;
+            }
+            Some(Err(e)) => self.show_error(format!("Groove template failed: {}", e)),
+            None => {}
+        }
+    }
+
+    /// Instrument tracks: each one plays a pattern on its own synth or sampler
+    fn draw_instrument_tracks(
+        &mut self,
+        ui: &mut egui::Ui,
+        track_edits: &mut Vec<Box<dyn UndoableCommand>>,
+    ) {
+        ui.heading("Instrument Tracks");
+        let count = self.daw_state.instrument_tracks.len();
+        let full = count >= MAX_INSTRUMENT_TRACKS;
+        let mut added = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!full, egui::Button::new("➕ Synth Track"))
+                .on_hover_text("New track playing a copy of the current synth sound")
+                .clicked()
+            {
+                added = Some(TrackInstrument::Synth(SynthPatch::from_state(&self.daw_state)));
+            }
+            if ui
+                .add_enabled(!full, egui::Button::new("➕ Sampler Track"))
+                .on_hover_text("New track playing the loaded samples")
+                .clicked()
+            {
+                added = Some(TrackInstrument::Sampler);
+            }
+            if full {
+                ui.label(format!("{} tracks at most", MAX_INSTRUMENT_TRACKS));
+            }
+        });
+
+        // Patterns a track can play: the active one, then the other project patterns
+        let active = &self.daw_state.pattern;
+        let patterns: Vec<&crate::sequencer::Pattern> = std::iter::once(active)
+            .chain(self.project_patterns.values().filter(|p| p.id != active.id))
+            .collect();
+
+        // Actions applied after the loop (the list is borrowed while drawing)
+        let mut remove_track = None;
+        let mut new_instrument = None;
+        let mut new_pattern = None;
+        let mut new_inserts = None;
+        for track in &self.daw_state.instrument_tracks {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.strong(&track.name);
+                mixer_strip(ui, MixerTrack::Instrument(track.id), track.strip, track_edits);
+                if ui.button("🗑 Remove").clicked() {
+                    remove_track = Some(track.id);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Instrument:");
+                egui::ComboBox::from_id_salt(("instrument_track_instrument", track.id))
+                    .selected_text(track.instrument.name())
+                    .show_ui(ui, |ui| {
+                        let synth = matches!(track.instrument, TrackInstrument::Synth(_));
+                        if ui
+                            .selectable_label(synth, "Synth")
+                            .on_hover_text("Play a copy of the current synth sound")
+                            .clicked()
+                        {
+                            let patch = SynthPatch::from_state(&self.daw_state);
+                            new_instrument = Some((track.id, TrackInstrument::Synth(patch)));
+                        }
+                        let sampler = matches!(track.instrument, TrackInstrument::Sampler);
+                        if ui.selectable_label(sampler, "Sampler").clicked() && !sampler {
+                            new_instrument = Some((track.id, TrackInstrument::Sampler));
+                        }
+                    });
+                ui.label("Pattern:");
+                egui::ComboBox::from_id_salt(("instrument_track_pattern", track.id))
+                    .selected_text(&track.pattern.name)
+                    .show_ui(ui, |ui| {
+                        for pattern in &patterns {
+                            let selected = pattern.id == track.pattern.id;
+                            if ui.selectable_label(selected, &pattern.name).clicked() {
+                                new_pattern = Some((track.id, (*pattern).clone()));
+                            }
+                        }
+                    });
+                ui.label(format!("{} notes", track.pattern.notes().len()));
+            });
+            let mut inserts = track.inserts;
+            if inserts_strip(ui, &format!("instrument_{}", track.id), &mut inserts) {
+                new_inserts = Some((track.id, inserts));
+            }
+        }
+
+        let edited = added.is_some()
+            || new_instrument.is_some()
+            || new_pattern.is_some()
+            || new_inserts.is_some()
+            || remove_track.is_some();
+        let mut result = Ok(());
+        if let Some(instrument) = added {
+            let name = format!("{} {}", instrument.name(), count + 1);
+            result = self.daw_state.add_instrument_track(name, instrument).map(|_| ());
+        }
+        if let Some((id, instrument)) = new_instrument {
+            result = self.daw_state.set_track_instrument(id, instrument);
+        }
+        if let Some((id, pattern)) = new_pattern {
+            result = self.daw_state.set_track_pattern(id, pattern);
+        }
+        if let Some((id, inserts)) = new_inserts {
+            result = self
+                .daw_state
+                .set_track_inserts(MixerTrack::Instrument(id), inserts);
+        }
+        if let Some(id) = remove_track {
+            result = self.daw_state.remove_instrument_track(id).map(|_| ());
+        }
+        match result {
+            Ok(()) if edited => self.mark_project_modified(),
+            Ok(()) => {}
+            Err(e) => self.show_error(format!("Instrument track: {}", e)),
+        }
+    }
+
+    /// Add audio files to a track as clips, one after the other from the playhead
+    ///
+    /// Returns whether a clip was added.
+    fn import_audio_clips(&mut self, track_id: u32, paths: &[PathBuf]) -> bool {
+        let sample_rate = self.sequencer.sample_rate() as f32;
+        let mut start = self.sequencer.shared_state().position_samples();
+        let mut added = false;
+        for path in paths {
+            match load_sample(path) {
+                Ok(sample) => {
+                    let clip = AudioClip::new(path.clone(), Arc::new(sample), start, sample_rate);
+                    start += clip.length;
+                    added |= self.daw_state.audio_tracks.add_clip(track_id, clip).is_some();
+                }
+                Err(e) => self.show_error(format!("Failed to import audio clip: {}", e)),
+            }
+        }
+        added
+    }
+
+    fn draw_audio_tracks(&mut self, ui: &mut egui::Ui) {
+        self.draw_speaker_layout(ui);
+        self.draw_return_buses(ui);
+        self.draw_master_bus(ui);
+
+        // Tracks that can key a sidechain (pre-fader)
+        let sources: Vec<(SidechainSource, String)> =
+            std::iter::once((SidechainSource::Synth, "Synth".to_string()))
+                .chain(self.daw_state.audio_tracks.tracks().iter().map(|track| {
+                    (SidechainSource::AudioTrack(track.id), track.name.clone())
+                }))
+                .collect();
+
+        // Undoable strip edits, applied after the tracks are drawn
+        let mut track_edits: Vec<Box<dyn UndoableCommand>> = Vec::new();
+
+        ui.heading("Synth Track");
+        ui.horizontal(|ui| {
+            mixer_strip(ui, MixerTrack::Synth, self.daw_state.synth_strip, &mut track_edits);
+        });
+        let mut synth_dynamics = self.daw_state.synth_dynamics;
+        let synth_sources = &sources[1..];
+        if dynamics_strip(ui, "synth", &mut synth_dynamics, synth_sources) {
+            let _ = self.daw_state.set_synth_dynamics(synth_dynamics);
+        }
+        let mut synth_inserts = self.daw_state.synth_inserts;
+        if inserts_strip(ui, "synth", &mut synth_inserts) {
+            let _ = self.daw_state.set_track_inserts(MixerTrack::Synth, synth_inserts);
+        }
+        let mut synth_sends = self.daw_state.synth_sends;
+        if sends_strip(ui, "synth", &mut synth_sends, &self.daw_state.return_buses, false) {
+            for (index, send) in synth_sends.into_iter().enumerate() {
+                if send != self.daw_state.synth_sends[index] {
+                    let _ = self.daw_state.set_synth_send(index, send);
+                }
+            }
+        }
+        let outputs = self.output_choices();
+        let mut synth_output = self.daw_state.synth_output;
+        ui.horizontal(|ui| {
+            ui.label("Output:");
+            if output_picker(ui, "synth", &mut synth_output, &outputs) {
+                let _ = self.daw_state.set_synth_output(synth_output);
+            }
+        });
+        let layout = self.daw_state.speaker_layout;
+        let mut synth_surround = self.daw_state.synth_surround;
+        if !layout.is_stereo() && surround_strip(ui, "synth", &mut synth_surround, layout) {
+            let _ = self.daw_state.set_synth_surround(synth_surround);
+        }
+        ui.horizontal(|ui| {
+            let mut plugin_sidechain = self.daw_state.plugin_sidechain;
+            if sidechain_picker(ui, "plugins", &mut plugin_sidechain, &sources, "None") {
+                let _ = self.daw_state.set_plugin_sidechain(plugin_sidechain);
+            }
+            ui.label("→ plugin sidechain input");
+        });
+        ui.separator();
+
+        self.draw_instrument_tracks(ui, &mut track_edits);
+        ui.separator();
+
+        ui.heading("Audio Tracks");
+        let sample_rate = self.sequencer.sample_rate() as f32;
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            if ui.button("➕ Add Audio Track").clicked() {
+                let name = format!("Audio {}", self.daw_state.audio_tracks.tracks().len() + 1);
+                self.daw_state.audio_tracks.add_track(name);
+                changed = true;
+            }
+            // Input level, once the input is opened by a take
+            if let Some((input, recorder)) = &self.audio_input {
+                let peak = recorder.input_peak().min(1.0);
+                ui.add(
+                    egui::ProgressBar::new(peak)
+                        .desired_width(120.0)
+                        .text(format!("Input {} Hz", input.sample_rate())),
+                );
+                if recorder.overruns() > 0 {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(255, 165, 0),
+                        format!("{} samples lost", recorder.overruns()),
+                    );
+                }
+            }
+        });
+
+        // Actions applied after the loop (the list is borrowed while drawing)
+        let mut import_into: Option<u32> = None;
+        let mut remove_track: Option<u32> = None;
+        let mut remove_clip: Option<u32> = None;
+        let mut warp_error: Option<String> = None;
+        let mut pitch_error: Option<String> = None;
+
+        // Audio files dragged over the window: the track under the pointer gets them
+        let (dragging_files, dropped_files, pointer) = ui.ctx().input(|i| {
+            let dropped: Vec<PathBuf> = i
+                .raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .filter(|path| is_audio_file(path))
+                .collect();
+            (!i.raw.hovered_files.is_empty(), dropped, i.pointer.latest_pos())
+        });
+        let mut drop_into: Option<u32> = None;
+
+        let track_ids: Vec<u32> =
+            self.daw_state.audio_tracks.tracks().iter().map(|t| t.id).collect();
+        for track_id in track_ids {
+            let Some(track) = self.daw_state.audio_tracks.track_mut(track_id) else {
+                continue;
+            };
+            ui.separator();
+            let track_top = ui.cursor().top();
+            ui.horizontal(|ui| {
+                ui.strong(&track.name);
+                let strip = track.strip();
+                mixer_strip(ui, MixerTrack::Audio(track_id), strip, &mut track_edits);
+                let id = format!("audio_track_{}", track_id);
+                changed |= output_picker(ui, &id, &mut track.output, &outputs);
+                // The armed track records the input with the transport Record button
+                let armed = self.armed_track == Some(track_id);
+                if ui
+                    .selectable_label(armed, "⏺ Arm")
+                    .on_hover_text("Record the audio input on this track")
+                    .clicked()
+                {
+                    self.armed_track = if armed { None } else { Some(track_id) };
+                }
+                if self.recording_track == Some(track_id) {
+                    ui.colored_label(egui::Color32::RED, "● REC");
+                }
+                if ui.button("Import Clip").clicked() {
+                    import_into = Some(track_id);
+                }
+                if ui.button("🗑").on_hover_text("Remove track").clicked() {
+                    remove_track = Some(track_id);
+                }
+            });
+            let track_sources: Vec<_> = sources
+                .iter()
+                .filter(|(source, _)| *source != SidechainSource::AudioTrack(track_id))
+                .cloned()
+                .collect();
+            let strip_id = format!("audio_track_{}", track_id);
+            changed |= dynamics_strip(ui, &strip_id, &mut track.dynamics, &track_sources);
+            changed |= inserts_strip(ui, &strip_id, &mut track.inserts);
+            let buses = &self.daw_state.return_buses;
+            changed |= sends_strip(ui, &strip_id, &mut track.sends, buses, true);
+            if !layout.is_stereo() {
+                changed |= surround_strip(ui, &strip_id, &mut track.surround, layout);
+            }
+
+            for clip in &mut track.clips {
+                let mut clip_region_changed = false;
+                let source_seconds = clip.source_length(sample_rate) as f64 / sample_rate as f64;
+                ui.horizontal(|ui| {
+                    ui.label(&clip.name);
+                    let seconds = |ui: &mut egui::Ui, label: &str, samples: &mut u64, max: f64| {
+                        let mut value = *samples as f64 / sample_rate as f64;
+                        ui.label(label);
+                        let response = ui.add(
+                            egui::DragValue::new(&mut value)
+                                .range(0.0..=max)
+                                .speed(0.01)
+                                .suffix(" s"),
+                        );
+                        if response.changed() {
+                            *samples = (value * sample_rate as f64) as u64;
+                        }
+                        response.changed()
+                    };
+                    let mut clip_changed = seconds(ui, "Start", &mut clip.start, f64::MAX);
+                    clip_region_changed = seconds(ui, "Offset", &mut clip.offset, source_seconds);
+                    clip_region_changed |= seconds(ui, "Length", &mut clip.length, source_seconds);
+                    clip_changed |= clip_region_changed;
+                    clip_changed |= seconds(ui, "Fade in", &mut clip.fade_in, source_seconds);
+                    clip_changed |= seconds(ui, "Fade out", &mut clip.fade_out, source_seconds);
+                    clip_changed |= ui
+                        .add(egui::Slider::new(&mut clip.gain, 0.0..=MAX_CLIP_GAIN).text("Gain"))
+                        .changed();
+                    // The clip is rendered again once the drag ends
+                    let pitch_id = ui.id().with(("clip_pitch", clip.id));
+                    let mut pitch = ui
+                        .data(|data| data.get_temp::<f32>(pitch_id))
+                        .unwrap_or_else(|| clip.pitch());
+                    let response = ui.labelled(
+                        "Pitch:",
+                        egui::DragValue::new(&mut pitch)
+                            .range(-MAX_PITCH_SHIFT..=MAX_PITCH_SHIFT)
+                            .speed(0.1)
+                            .suffix(" st"),
+                    );
+                    if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                        ui.data_mut(|data| data.remove::<f32>(pitch_id));
+                        if pitch != clip.pitch() {
+                            match clip.set_pitch(pitch) {
+                                Ok(()) => clip_changed = true,
+                                Err(e) => pitch_error = Some(e),
+                            }
+                        }
+                    } else if response.changed() {
+                        ui.data_mut(|data| data.insert_temp(pitch_id, pitch));
+                    }
+                    if clip_changed {
+                        clip.clamp(sample_rate);
+                        changed = true;
+                    }
+                    if ui.button("🗑").on_hover_text("Remove clip").clicked() {
+                        remove_clip = Some(clip.id);
+                    }
+                });
+
+                // Tempo following: markers on the transients of the clip region
+                ui.horizontal(|ui| {
+                    let mut warped = clip.warp.is_some();
+                    let mut source_bpm = clip.warp.as_ref().map_or_else(
+                        || estimate_loop_bpm(clip.length, sample_rate),
+                        |warp| warp.source_bpm,
+                    );
+                    let mut rewarp = ui.checkbox(&mut warped, "Warp to tempo").changed() && warped;
+                    if !warped && clip.warp.take().is_some() {
+                        changed = true;
+                    }
+                    if warped {
+                        rewarp |= ui
+                            .labelled(
+                                "Source tempo:",
+                                egui::DragValue::new(&mut source_bpm)
+                                    .range(MIN_SOURCE_BPM..=MAX_SOURCE_BPM)
+                                    .speed(0.1)
+                                    .suffix(" BPM"),
+                            )
+                            .changed();
+                    }
+                    if let Some(warp) = clip.warp.as_mut() {
+                        ui.label(format!(
+                            "{} markers, {:.2} beats",
+                            warp.markers().len(),
+                            warp.beats()
+                        ));
+                        if ui
+                            .button("Quantize markers")
+                            .on_hover_text("Snap the transients to 1/16 notes")
+                            .clicked()
+                        {
+                            warp.quantize(0.25);
+                            changed = true;
+                        }
+                    }
+                    // The region changed: the markers follow it
+                    if rewarp || (clip.warp.is_some() && clip_region_changed) {
+                        match ClipWarp::detect(clip, source_bpm, sample_rate) {
+                            Ok(warp) => clip.warp = Some(warp),
+                            Err(e) => warp_error = Some(e),
+                        }
+                        changed = true;
+                    }
+                });
+            }
+
+            let track_rect = egui::Rect::from_x_y_ranges(ui.max_rect().x_range(), track_top..=ui.cursor().top());
+            if pointer.is_some_and(|pointer| track_rect.contains(pointer)) {
+                if dragging_files {
+                    ui.painter().rect_stroke(track_rect, 3.0, egui::Stroke::new(2.0, ui.visuals().selection.bg_fill));
+                }
+                if !dropped_files.is_empty() {
+                    drop_into = Some(track_id);
+                }
+            }
+        }
+        if let Some(error) = warp_error {
+            self.show_error(format!("Failed to warp audio clip: {}", error));
+        }
+        if let Some(error) = pitch_error {
+            self.show_error(format!("Failed to repitch audio clip: {}", error));
+        }
+
+        if let Some(track_id) = import_into
+            && let Some(path) = file_dialog(self.preferences.paths.samples.as_deref())
+                .add_filter("Audio Files", AUDIO_EXTENSIONS)
+                .pick_file()
+        {
+            changed |= self.import_audio_clips(track_id, &[path]);
+        }
+        if !dropped_files.is_empty() {
+            // Files dropped outside the tracks get a new track
+            let track_id = drop_into.unwrap_or_else(|| {
+                let name = format!("Audio {}", self.daw_state.audio_tracks.tracks().len() + 1);
+                self.daw_state.audio_tracks.add_track(name)
+            });
+            changed |= self.import_audio_clips(track_id, &dropped_files);
+        }
+        if let Some(track_id) = remove_track {
+            // Removing the frozen track unfreezes the synth
+            if self
+                .frozen_synth
+                .as_ref()
+                .is_some_and(|(frozen_id, _)| *frozen_id == track_id)
+            {
+                self.unfreeze_synth_track();
+            } else {
+                changed |= self.daw_state.audio_tracks.remove_track(track_id).is_some();
+                let _ = self.daw_state.forget_sidechain_track(track_id);
+            }
+        }
+        if let Some(clip_id) = remove_clip {
+            changed |= self.daw_state.audio_tracks.remove_clip(clip_id).is_some();
+        }
+        for command in track_edits {
+            if let Err(e) = self.command_manager.execute(command, &mut self.daw_state) {
+                eprintln!("Failed to edit audio track: {}", e);
+            }
+        }
+
+        if changed {
+            self.send_audio_tracks();
+        }
+    }
+
+    /// Send the active pattern to the sequencer (empty while the synth track is frozen)
+    fn send_active_pattern(&self) {
+        let mut pattern = self.daw_state.pattern.clone();
+        if self.frozen_synth.is_some() {
+            pattern.clear();
+        }
+        let cmd = Command::SetPattern(Arc::new(pattern));
+        if let Ok(mut tx) = self.command_tx.lock() {
+            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+        }
+    }
+
+    /// Import a Standard MIDI File: one pattern per track with notes, the first
+    /// one becomes the active pattern. The project takes the tempo and time
+    /// signature of the file when the controls can show them.
+    fn import_midi_file(&mut self, path: &std::path::Path) {
+        let smf = match Smf::read(path) {
+            Ok(smf) => smf,
+            Err(e) => {
+                self.show_error(format!("Failed to import MIDI file: {}", e));
+                return;
+            }
+        };
+
+        let bpm = smf.initial_bpm();
+        if (60.0..=200.0).contains(&bpm) {
+            self.sequencer_tempo = bpm;
+            self.sequencer.set_tempo(Tempo::new(bpm));
+            let _ = self.daw_state.set_tempo(bpm);
+        }
+        if let Some((numerator, denominator)) = smf.time_signature
+            && numerator <= 16
+            && [1, 2, 4, 8, 16].contains(&denominator)
+        {
+            self.time_signature_numerator = numerator;
+            self.time_signature_denominator = denominator;
+            self.sequencer
+                .set_time_signature(TimeSignature::new(numerator, denominator));
+            let _ = self.daw_state.set_time_signature(numerator, denominator);
+        }
+
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "MIDI".to_string());
+        let patterns = smf.to_patterns(
+            self.sequencer.sample_rate(),
+            self.sequencer.tempo(),
+            self.sequencer.time_signature(),
+            &name,
+        );
+        let Some(first) = patterns.first().cloned() else {
+            self.show_error(format!("No notes in MIDI file {}", path.display()));
+            return;
+        };
+
+        // Keep the current pattern in the project, the imported ones follow it
+        self.project_patterns
+            .insert(self.daw_state.pattern.id, self.daw_state.pattern.clone());
+        for pattern in patterns {
+            self.project_patterns.insert(pattern.id, pattern);
+        }
+        self.daw_state.pattern = first;
+        // The undo history edits the previous pattern
+        self.command_manager.clear();
+        self.send_active_pattern();
+        self.mark_project_modified();
+    }
+
+    /// Export the active pattern, or every track of the project, to a MIDI file
+    fn export_midi_file(&mut self, whole_project: bool) {
+        let project = self.build_render_project();
+        let name = if whole_project {
+            project.metadata.name.clone()
+        } else {
+            self.daw_state.pattern.name.clone()
+        };
+        if let Some(path) = file_dialog(self.preferences.paths.exports.as_deref())
+            .add_filter("MIDI Files", &["mid"])
+            .set_file_name(format!("{}.mid", name))
+            .save_file()
+        {
+            let pattern = (!whole_project).then_some(self.daw_state.pattern.id);
+            if let Err(e) = self.project_manager.export_midi(&project, pattern, &path) {
+                self.show_error(format!("Failed to export MIDI file: {}", e));
+            }
+        }
+    }
+
+    /// Recording input device and channel (the input reopens on the next take)
+    fn draw_input_device(&mut self, ui: &mut egui::Ui) {
+        let audio = &mut self.preferences.audio;
+        let previous = (audio.input_device.clone(), audio.input_channel);
+        ui.horizontal(|ui| {
+            let label = ui.label("Audio Input:");
+            let selected = audio.input_device.as_deref().unwrap_or("Default input");
+            egui::ComboBox::from_id_salt("input_device_selector")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut audio.input_device, None, "Default input");
+                    for device in &self.available_input_devices {
+                        let label = if device.is_default {
+                            format!("{} (default)", device.name)
+                        } else {
+                            device.name.clone()
+                        };
+                        ui.selectable_value(
+                            &mut audio.input_device,
+                            Some(device.name.clone()),
+                            label,
+                        );
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+
+            let label = ui.label("Channel:");
+            let channel_name = |channel: Option<u16>| match channel {
+                Some(channel) => format!("{}", channel + 1),
+                None => "Mix".to_string(),
+            };
+            egui::ComboBox::from_id_salt("input_channel_selector")
+                .selected_text(channel_name(audio.input_channel))
+                .show_ui(ui, |ui| {
+                    for channel in std::iter::once(None).chain((0..8).map(Some)) {
+                        ui.selectable_value(
+                            &mut audio.input_channel,
+                            channel,
+                            channel_name(channel),
+                        );
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+        });
+        if previous != (audio.input_device.clone(), audio.input_channel) {
+            self.audio_input = None;
+            self.save_preferences();
+        }
+    }
+
+    /// Start recording the input onto the armed audio track (no-op when no
+    /// track is armed), the take starts at the playhead
+    fn start_audio_take(&mut self) -> Result<(), String> {
+        let Some(track_id) = self.armed_track else {
+            return Ok(());
+        };
+        let Some(track) = self.daw_state.audio_tracks.track(track_id) else {
+            self.armed_track = None;
+            return Ok(());
+        };
+        let name: String = track
+            .name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        let path = recordings_directory(self.current_project_path.as_deref()).join(format!(
+            "{}_{}.wav",
+            name,
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        ));
+
+        // Opened on the first take, kept open for the next ones
+        let (_, recorder) = match &mut self.audio_input {
+            Some(input) => input,
+            None => self
+                .audio_input
+                .insert(AudioInput::open(&InputOptions::from(&self.preferences.audio))?),
+        };
+        let start = self.sequencer.shared_state().position_samples();
+        recorder.start(path, start)?;
+        self.recording_track = Some(track_id);
+        Ok(())
+    }
+
+    /// Stop the current take and place it on its track
+    fn finish_audio_take(&mut self) {
+        let Some(track_id) = self.recording_track.take() else {
+            return;
+        };
+        let Some((_, recorder)) = &mut self.audio_input else {
+            return;
+        };
+        let take = match recorder.stop() {
+            Ok(take) => take,
+            Err(e) => {
+                self.show_error(format!("Recording failed: {}", e));
+                return;
+            }
+        };
+        let clip = take.into_audio_clip(self.sequencer.sample_rate() as f32);
+        if self.daw_state.audio_tracks.add_clip(track_id, clip).is_some() {
+            self.send_audio_tracks();
+            self.mark_project_modified();
+        } else {
+            self.show_error("Recording failed: the audio track was removed".to_string());
+        }
+    }
+
+    /// Record the notes played on the MIDI input into the pattern while the
+    /// transport records
+    fn process_midi_recording(&mut self) {
+        let events = self.midi_connection_manager.poll_notes();
+        if events.is_empty() || !self.sequencer.state().is_recording() {
+            return;
+        }
+        // The engine played each note as it arrived: date it back from the playhead
+        let position = self.engine_snapshot.read().position_samples;
+        let sample_rate = self.sequencer.sample_rate();
+        for (event, received) in events {
+            let age = (received.elapsed().as_secs_f64() * sample_rate) as u64;
+            self.sequencer
+                .process_midi_for_recording(event, position.saturating_sub(age));
+        }
+        let notes = self.sequencer.take_recorded_notes();
+        self.add_recorded_notes(notes);
+    }
+
+    /// Put recorded notes in the pattern at once (heard and shown in the piano
+    /// roll), folded into its loop
+    fn add_recorded_notes(&mut self, notes: Vec<crate::sequencer::Note>) {
+        if notes.is_empty() {
+            return;
+        }
+        if self.midi_take_base.is_none() {
+            self.midi_take_base = Some(self.daw_state.pattern.notes().to_vec());
+        }
+        let sample_rate = self.sequencer.sample_rate();
+        let tempo = *self.sequencer.tempo();
+        let time_signature = *self.sequencer.time_signature();
+        let loop_samples = self
+            .daw_state
+            .pattern
+            .length_samples(sample_rate, &tempo, &time_signature);
+        for note in &notes {
+            self.daw_state.pattern.add_note(fold_into_loop(
+                note,
+                loop_samples,
+                self.input_quantize.then_some(&self.quantize),
+                sample_rate,
+                &tempo,
+                &time_signature,
+            ));
+        }
+        if let Err(e) = self.daw_state.send_pattern() {
+            eprintln!("Failed to send recorded notes: {}", e);
+        }
+    }
+
+    /// End the MIDI take (call before the transport leaves Recording): the
+    /// notes recorded become one undo step
+    fn finish_midi_take(&mut self) {
+        let notes = self.sequencer.finalize_recording().unwrap_or_default();
+        self.add_recorded_notes(notes);
+        let Some(base) = self.midi_take_base.take() else {
+            return;
+        };
+        let notes = self.daw_state.pattern.notes().to_vec();
+        self.daw_state.pattern.set_notes(base);
+        let command = Box::new(SetPatternNotesCommand::new("Record MIDI", notes));
+        match self.command_manager.execute(command, &mut self.daw_state) {
+            Ok(()) => self.mark_project_modified(),
+            Err(e) => self.show_error(format!("Failed to record MIDI: {}", e)),
+        }
+    }
+
+    /// Close the recording input when its device fails (e.g. unplugged)
+    fn check_audio_input(&mut self) {
+        let failed = self
+            .audio_input
+            .as_ref()
+            .is_some_and(|(input, _)| input.status.get() == DeviceStatus::Error);
+        if failed {
+            self.finish_audio_take();
+            self.audio_input = None;
+            self.show_error("The audio input stopped: check the input device".to_string());
+        }
+    }
+
+    /// Project as heard, for offline renders: the default track plays the
+    /// active pattern with the current patch
+    fn build_render_project(&self) -> Project {
+        let mut project = self.build_project();
+        if let Some(track) = project.tracks.get_mut(&0) {
+            track.pattern_id = Some(self.daw_state.pattern.id);
+            track.track_type = TrackType::Synth;
+        }
+        project.synth_params.lfo = self.daw_state.lfo;
+        project.synth_params.filter = self.daw_state.filter;
+        project.synth_params.portamento = self.daw_state.portamento;
+        project.synth_params.poly_mode = self.daw_state.poly_mode;
+        project
+    }
 
-        // Mapping QWERTY keyboard → MIDI notes (C4 = 60)
-        let key_map = [
-            ('a', 60), // C4
-            ('w', 61), // C#4
-            ('s', 62), // D4
-            ('e', 63), // D#4
-            ('d', 64), // E4
-            ('f', 65), // F4
-            ('t', 66), // F#4
-            ('g', 67), // G4
-            ('y', 68), // G#4
-            ('h', 69), // A4
-            ('u', 70), // A#4
-            ('j', 71), // B4
-            ('k', 72), // C5
-        ];
+    /// Render the synth pattern to an audio track and silence the voices
+    fn freeze_synth_track(&mut self) {
+        let project = self.build_render_project();
+        match freeze_track(&project, 0, self.sequencer.sample_rate() as u32) {
+            Ok(frozen) => {
+                let track_id = self.daw_state.audio_tracks.add_track(frozen.clip.name.clone());
+                if let Some(track) = self.daw_state.audio_tracks.track_mut(track_id) {
+                    track.frozen = Some(frozen.track_id);
+                    // The render is dry: the synth insert moves to the track
+                    track.dynamics = self.daw_state.synth_dynamics.map(|insert| TrackDynamics {
+                        sidechain: insert.sidechain.filter(|s| *s != SidechainSource::Synth),
+                        ..insert
+                    });
+                    track.sends = self.daw_state.synth_sends;
+                    track.output = self.daw_state.synth_output;
+                    track.surround = self.daw_state.synth_surround;
+                    track.inserts = self.daw_state.synth_inserts;
+                    // The render is pre-fader: the synth strip moves to the track
+                    track.set_strip(self.daw_state.synth_strip);
+                }
+                self.daw_state.audio_tracks.add_clip(track_id, frozen.clip.clone());
+                self.frozen_synth = Some((track_id, frozen));
+                self.send_audio_tracks();
+                self.send_active_pattern();
+            }
+            Err(e) => self.show_error(format!("Failed to freeze track: {}", e)),
+        }
+    }
 
-        // Display the visual keyboard only
+    /// Give the pattern back to the synth voices and delete the render
+    fn unfreeze_synth_track(&mut self) {
+        let Some((track_id, frozen)) = self.frozen_synth.take() else {
+            return;
+        };
+        self.daw_state.audio_tracks.remove_track(track_id);
+        let _ = self.daw_state.forget_sidechain_track(track_id);
+        self.send_audio_tracks();
+        self.send_active_pattern();
+        frozen.discard();
+    }
+
+    /// Tuner view: nearest note, deviation in cents and frequency
+    fn draw_tuner(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Tuner");
         ui.horizontal(|ui| {
-            for (key, note) in &key_map {
-                let is_active = self.active_notes.contains(note);
-                let is_black = matches!(key, 'w' | 'e' | 't' | 'y' | 'u');
+            ui.label("Source: master output");
+            ui.separator();
+            ui.labelled(
+                "A4 =",
+                egui::DragValue::new(&mut self.tuner.reference)
+                    .range(REFERENCE_RANGE)
+                    .speed(0.1)
+                    .suffix(" Hz"),
+            );
+        });
+        ui.add_space(10.0);
 
-                let note_name = match note % 12 {
-                    0 => "C",
-                    1 => "C#",
-                    2 => "D",
-                    3 => "D#",
-                    4 => "E",
-                    5 => "F",
-                    6 => "F#",
-                    7 => "G",
-                    8 => "G#",
-                    9 => "A",
-                    10 => "A#",
-                    11 => "B",
-                    _ => "?",
-                };
+        self.tuner.attach(&self.master_tap);
+        let reading = self.tuner.update(self.engine_snapshot.read().sample_rate);
 
-                let octave = note / 12 - 1;
-                let label = format!("{}{}\n({})", note_name, octave, key.to_uppercase());
+        // Deviation meter: -50 to +50 cents
+        let (rect, meter) = ui.allocate_exact_size(egui::vec2(320.0, 28.0), egui::Sense::hover());
+        let meter_value = match &reading {
+            Some(reading) => format!("{} {:+.0} cents", reading.note_name(), reading.cents),
+            None => "No pitch detected".to_string(),
+        };
+        a11y::describe(&meter, egui::accesskit::Role::Meter, "Tuner", &meter_value);
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 4.0, egui::Color32::from_gray(40));
+        let center = rect.center().x;
+        painter.line_segment(
+            [egui::pos2(center, rect.top()), egui::pos2(center, rect.bottom())],
+            egui::Stroke::new(1.0, egui::Color32::GRAY),
+        );
 
-                let button = if is_black {
-                    egui::Button::new(label)
-                        .fill(if is_active {
-                            egui::Color32::from_rgb(100, 100, 255)
-                        } else {
-                            egui::Color32::from_gray(50)
-                        })
-                        .min_size(egui::vec2(50.0, 80.0))
+        match reading {
+            Some(reading) => {
+                let color = if reading.in_tune() {
+                    egui::Color32::GREEN
+                } else if reading.cents.abs() <= 15.0 {
+                    egui::Color32::from_rgb(255, 165, 0)
                 } else {
-                    egui::Button::new(label)
-                        .fill(if is_active {
-                            egui::Color32::from_rgb(150, 150, 255)
-                        } else {
-                            egui::Color32::WHITE
-                        })
-                        .stroke(egui::Stroke::new(1.0, egui::Color32::BLACK))
-                        .min_size(egui::vec2(50.0, 80.0))
+                    egui::Color32::RED
                 };
+                let x = center + reading.cents / 50.0 * rect.width() / 2.0;
+                painter.line_segment(
+                    [egui::pos2(x, rect.top() + 2.0), egui::pos2(x, rect.bottom() - 2.0)],
+                    egui::Stroke::new(4.0, color),
+                );
 
-                if ui.add(button).clicked() {
-                    // Toggle note on click
-                    if is_active {
-                        self.send_note_off(*note);
-                    } else {
-                        self.send_note_on(*note);
-                    }
-                }
+                ui.label(
+                    egui::RichText::new(reading.note_name())
+                        .size(48.0)
+                        .strong()
+                        .color(color),
+                );
+                ui.label(format!(
+                    "{:+.1} cents    {:.2} Hz",
+                    reading.cents, reading.frequency
+                ));
             }
-        });
+            None => {
+                ui.label(egui::RichText::new("—").size(48.0));
+                ui.label("No pitch detected: play a single note");
+            }
+        }
 
-        ui.add_space(10.0);
-        ui.label(format!("Notes actives : {}", self.active_notes.len()));
+        // Keep analysing while the tab is shown
+        ui.ctx().request_repaint_after(Duration::from_millis(50));
+    }
+
+    /// Run the console script and apply its result (nothing is applied on error)
+    fn run_script(&mut self) {
+        let context = ScriptContext::from_state(
+            &self.daw_state,
+            self.daw_state.pattern.clone(),
+            self.piano_roll_editor.selected_notes(),
+            self.sequencer.sample_rate(),
+        );
+
+        let outcome = match crate::scripting::run_script(&self.script_source, context) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                self.script_output.extend(e.lines().map(str::to_string));
+                return;
+            }
+        };
+        self.script_output.extend(outcome.output.iter().cloned());
+
+        // Tempo and synth parameters (undoable)
+        if let Err(e) = outcome.apply(&mut self.command_manager, &mut self.daw_state) {
+            self.script_output.push(format!("Failed to apply parameters: {}", e));
+        }
+        if self.sequencer_tempo != self.daw_state.tempo.bpm() {
+            self.sequencer_tempo = self.daw_state.tempo.bpm();
+            self.sequencer.set_tempo(Tempo::new(self.sequencer_tempo));
+        }
+        self.volume_ui = self.daw_state.volume;
+        self.volume_atomic.set(self.daw_state.volume);
+        self.adsr_attack = self.daw_state.adsr.attack;
+        self.adsr_decay = self.daw_state.adsr.decay;
+        self.adsr_sustain = self.daw_state.adsr.sustain;
+        self.adsr_release = self.daw_state.adsr.release;
+
+        if outcome.pattern_changed {
+            let pattern = outcome.context.pattern;
+            self.daw_state.pattern.length_bars = pattern.length_bars;
+            let notes = pattern.notes().to_vec();
+            let command = Box::new(SetPatternNotesCommand::new("Run Script", notes));
+            if let Err(e) = self.command_manager.execute(command, &mut self.daw_state) {
+                self.script_output.push(format!("Failed to apply pattern: {}", e));
+            }
+            // A frozen synth stays silent
+            if self.frozen_synth.is_some() {
+                self.send_active_pattern();
+            }
+        }
+        self.mark_project_modified();
+        self.script_output.push(format!(
+            "✅ Done ({} notes in '{}')",
+            self.daw_state.pattern.note_count(),
+            self.daw_state.pattern.name
+        ));
     }
 
-    /// Affiche la barre de statut en bas de la fenêtre
     fn draw_status_bar(&self, ui: &mut egui::Ui) {
         ui.separator();
         ui.horizontal(|ui| {
             // Afficher les notifications récentes (moins de 5s)
             let recent_notifications = self.get_recent_notifications();
 
+            // Notifications lost because the channel was full
+            let dropped = self.notification_rx.dropped();
+            if dropped.total() > 0 {
+                ui.colored_label(egui::Color32::GRAY, format!("({} dropped)", dropped.total()))
+                    .on_hover_text(format!(
+                        "Notification queue overflow: {} info, {} warning, {} error",
+                        dropped.info, dropped.warning, dropped.error
+                    ));
+            }
+
             if recent_notifications.is_empty() {
                 ui.label("Ready");
             } else {
@@ -850,57 +3715,67 @@ impl DawApp {
             .unwrap_or("Untitled Bank")
             .to_string();
 
-        // Convert note_map_input to expected format
+        // Name of the sample played by each note
+        let samples = &self.daw_state.samples;
         let note_mappings: Vec<Option<String>> = (0..128)
-            .map(|i| {
-                if i < self.note_map_input.len() && !self.note_map_input[i].is_empty() {
-                    Some(self.note_map_input[i].clone())
-                } else {
-                    None
-                }
+            .map(|note| {
+                self.daw_state
+                    .sample_mappings
+                    .get(&note)
+                    .and_then(|slot| samples.get(*slot))
+                    .map(|sample| sample.name.clone())
             })
             .collect();
 
-        let bank = SampleBank::from_samples_and_mappings(
+        let mut bank = SampleBank::from_samples_and_mappings(
             bank_name,
-            &self.loaded_samples,
+            samples,
             &note_mappings,
             path.parent().unwrap_or_else(|| std::path::Path::new(".")),
         );
+        for (slot, zone) in &self.daw_state.sample_zones {
+            if let Some(sample) = samples.get(*slot) {
+                let sample_path = std::path::PathBuf::from(&sample.name);
+                bank.add_mapping(SampleMapping::from_zone(*zone, sample_path, sample));
+            }
+        }
 
         bank.save_to_file(path)
     }
 
     /// Load sample bank from file
     fn load_sample_bank(&mut self, path: &std::path::Path) -> Result<(), String> {
-        let bank = SampleBank::load_from_file(path)?;
+        let is_sfz = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("sfz"));
+        let bank = if is_sfz {
+            let import = load_sfz(path)?;
+            for warning in &import.warnings {
+                eprintln!("SFZ import: {}", warning);
+            }
+            import.bank
+        } else {
+            SampleBank::load_from_file(path)?
+        };
 
-        // Clear current samples and mappings
-        self.loaded_samples.clear();
+        // Clear current samples and mappings (the undo history refers to their slots)
+        self.daw_state.samples.clear();
+        self.daw_state.sample_mappings.clear();
+        self.daw_state.sample_zones.clear();
         self.note_map_input.clear();
+        self.command_manager.clear();
 
         // Get base directory for resolving relative paths
         let base_dir = path.parent().unwrap_or_else(|| std::path::Path::new("."));
 
         // Load samples from bank
         for mapping in bank.get_sorted_mappings() {
-            let sample_path = if mapping.sample_path.is_absolute() {
-                mapping.sample_path.clone()
-            } else {
-                base_dir.join(&mapping.sample_path)
-            };
+            let sample_path = mapping.resolve_path(base_dir);
 
             match load_sample(&sample_path) {
                 Ok(mut sample) => {
                     // Apply bank settings to sample
-                    sample.name = mapping.name.clone();
-                    sample.volume = mapping.volume;
-                    sample.pan = mapping.pan;
-                    sample.loop_mode = mapping.loop_mode;
-                    sample.loop_start = mapping.loop_start;
-                    sample.loop_end = mapping.loop_end;
-                    sample.reverse = mapping.reverse;
-                    sample.pitch_offset = mapping.pitch_offset;
+                    mapping.apply_to(&mut sample);
 
                     // Clone sample: one for UI, one for audio thread
                     let sample_for_audio = Arc::new(sample.clone());
@@ -911,7 +3786,23 @@ impl DawApp {
                         eprintln!("Failed to send AddSample command: ringbuffer full");
                     }
 
-                    self.loaded_samples.push(sample);
+                    self.daw_state.samples.push(sample);
+                    let sample_index = self.daw_state.samples.len() - 1;
+
+                    // A zone mapping plays its sample across a note range
+                    if let Some(zone) = mapping.zone {
+                        self.daw_state.sample_zones.insert(sample_index, zone);
+                        let cmd = Command::SetSampleZone {
+                            sample_index,
+                            zone: Some(zone),
+                        };
+                        if let Ok(mut tx) = self.command_tx.lock()
+                            && ringbuf::traits::Producer::try_push(&mut *tx, cmd).is_err()
+                        {
+                            eprintln!("Failed to send SetSampleZone command: ringbuffer full");
+                        }
+                        continue;
+                    }
 
                     // Extend note_map_input if needed
                     while self.note_map_input.len() <= mapping.note as usize {
@@ -921,9 +3812,10 @@ impl DawApp {
                     self.note_map_input[mapping.note as usize] = mapping.note.to_string();
 
                     // Send note mapping command
+                    self.daw_state.sample_mappings.insert(mapping.note, sample_index);
                     let cmd = Command::SetNoteSampleMapping {
                         note: mapping.note,
-                        sample_index: self.loaded_samples.len() - 1,
+                        sample_index,
                     };
                     if let Ok(mut tx) = self.command_tx.lock()
                         && ringbuf::traits::Producer::try_push(&mut *tx, cmd).is_err()
@@ -967,8 +3859,29 @@ impl DawApp {
         self.time_signature_numerator = 4;
         self.time_signature_denominator = 4;
 
-        // Clear patterns and samples
-        self.active_pattern = crate::sequencer::Pattern::new_default(1, "Pattern 1".to_string());
+        // Clear patterns and samples (the undo history refers to the old project)
+        self.daw_state.pattern = crate::sequencer::Pattern::new_default(1, "Pattern 1".to_string());
+        self.command_manager.clear();
+        self.unfreeze_synth_track();
+        self.daw_state.audio_tracks.clear();
+        self.send_audio_tracks();
+        let _ = self.daw_state.set_synth_dynamics(None);
+        let _ = self.daw_state.set_plugin_sidechain(None);
+        for index in 0..MAX_RETURN_BUSES {
+            let _ = self.daw_state.set_return_bus(index, None);
+        }
+        let _ = self.daw_state.set_synth_output(TrackOutput::Master);
+        let _ = self.daw_state.set_synth_surround(None);
+        let _ = self.daw_state.set_track_inserts(MixerTrack::Synth, InsertChainParams::default());
+        let _ = self.daw_state.set_speaker_layout(SpeakerLayout::Stereo);
+        let _ = self.daw_state.set_master_chain(MasterChainParams::default());
+        let _ = self.daw_state.set_automation_lanes(Vec::new());
+        self.daw_state.cc_map = CcMap::new();
+        let _ = self.daw_state.clear_instrument_tracks();
+        let _ = self.daw_state.set_arrangement(Arrangement::new());
+        let _ = self.daw_state.set_song_mode(false);
+        let _ = self.daw_state.release_session();
+        let _ = self.daw_state.set_session(Session::new());
 
         // Send new project state to audio thread
         self.sync_project_to_audio_thread(&project);
@@ -988,7 +3901,7 @@ impl DawApp {
             return;
         }
 
-        if let Some(path) = FileDialog::new()
+        if let Some(path) = file_dialog(self.preferences.paths.projects.as_deref())
             .add_filter("MyMusic Projects", &["mymusic"])
             .pick_file()
         {
@@ -1008,11 +3921,23 @@ impl DawApp {
 
     /// Save current project
     fn save_project(&mut self) {
+        self.save_project_with_options(&ProjectSaveOptions::default());
+    }
+
+    /// Save current project, its audio files copied into the archive
+    fn collect_and_save_project(&mut self) {
+        self.save_project_with_options(&ProjectSaveOptions {
+            collect_audio: true,
+        });
+    }
+
+    /// Save to the current path (asked for an unsaved project)
+    fn save_project_with_options(&mut self, options: &ProjectSaveOptions) {
         let path = if let Some(current_path) = &self.current_project_path {
             current_path.clone()
         } else {
             // No current path, use Save As
-            if let Some(path) = FileDialog::new()
+            if let Some(path) = file_dialog(self.preferences.paths.projects.as_deref())
                 .add_filter("MyMusic Projects", &["mymusic"])
                 .set_file_name("untitled.mymusic")
                 .save_file()
@@ -1024,7 +3949,7 @@ impl DawApp {
             }
         };
 
-        match self.save_project_to_path(&path) {
+        match self.save_project_to_path(&path, options) {
             Ok(()) => {
                 self.project_has_unsaved_changes = false;
                 println!("✅ Successfully saved project: {:?}", path);
@@ -1037,7 +3962,7 @@ impl DawApp {
 
     /// Save project with new name/location
     fn save_project_as(&mut self) {
-        if let Some(path) = FileDialog::new()
+        if let Some(path) = file_dialog(self.preferences.paths.projects.as_deref())
             .add_filter("MyMusic Projects", &["mymusic"])
             .set_file_name(
                 self.current_project_path
@@ -1048,7 +3973,7 @@ impl DawApp {
             )
             .save_file()
         {
-            match self.save_project_to_path(&path) {
+            match self.save_project_to_path(&path, &ProjectSaveOptions::default()) {
                 Ok(()) => {
                     let path_clone = path.clone();
                     self.current_project_path = Some(path);
@@ -1062,7 +3987,7 @@ impl DawApp {
         }
     }
 
-    /// Export audio to WAV or FLAC file
+    /// Export the arrangement to a WAV or FLAC file
     fn export_audio(&mut self) {
         // Open file dialog for export
         let default_filename = if let Some(path) = &self.current_project_path {
@@ -1076,7 +4001,7 @@ impl DawApp {
             "export.wav".to_string()
         };
 
-        if let Some(path) = FileDialog::new()
+        if let Some(path) = file_dialog(self.preferences.paths.exports.as_deref())
             .add_filter("WAV Audio", &["wav"])
             .add_filter("FLAC Audio", &["flac"])
             .set_file_name(&default_filename)
@@ -1102,32 +4027,24 @@ impl DawApp {
             // Create exporter
             let exporter = crate::audio::export::AudioExporter::new(settings);
 
-            // Get current tempo and time signature
-            let tempo = Tempo::new(self.sequencer_tempo);
-            let time_signature = TimeSignature::new(
-                self.time_signature_numerator,
-                self.time_signature_denominator,
-            );
+            // The arrangement over the bar range (bars are shown from 1)
+            let project = self.build_render_project();
+            let range = crate::audio::export::RenderRange {
+                start_bar: self.export_start_bar.saturating_sub(1),
+                end_bar: self.export_end_bar,
+                tail_seconds: 0.0,
+            };
 
             // Export (blocking for now - TODO: move to thread)
             self.export_in_progress = true;
             self.export_progress = 0.0;
 
-            // Clone pattern for export
-            let pattern = self.active_pattern.clone();
-
             // Progress callback
             let progress_callback = Box::new(move |p: f32| {
                 println!("Export progress: {:.1}%", p * 100.0);
             });
 
-            match exporter.export(
-                &pattern,
-                &tempo,
-                &time_signature,
-                self.export_duration_seconds,
-                Some(progress_callback),
-            ) {
+            match exporter.export_project(&project, &range, Some(progress_callback)) {
                 Ok(message) => {
                     println!("✅ {}", message);
                     self.export_in_progress = false;
@@ -1196,6 +4113,9 @@ impl DawApp {
         self.time_signature_numerator = project.metadata.time_signature.numerator;
         self.time_signature_denominator = project.metadata.time_signature.denominator;
 
+        // The undo history refers to the previous project
+        self.command_manager.clear();
+
         // Load all patterns from project
         self.project_patterns.clear();
         for (pattern_id, pattern) in &project.patterns {
@@ -1209,25 +4129,244 @@ impl DawApp {
 
         // Set active pattern to the first one if available, or create default
         if let Some((_, pattern)) = self.project_patterns.iter().next() {
-            self.active_pattern = pattern.clone();
+            self.daw_state.pattern = pattern.clone();
         } else {
             // Create default pattern if none exist
-            self.active_pattern = crate::sequencer::Pattern::new(
+            self.daw_state.pattern = crate::sequencer::Pattern::new(
                 crate::project::generate_pattern_id(),
                 "Default Pattern".to_string(),
                 4,
             );
         }
 
+        // Rebuild the audio tracks (clip sources are loaded from their files)
+        let sample_rate = self.sequencer.sample_rate() as f32;
+        let mut clip_errors = Vec::new();
+        self.unfreeze_synth_track();
+        self.daw_state.audio_tracks.clear();
+        let mut audio_tracks: Vec<_> = project
+            .tracks
+            .values()
+            .filter(|track| track.track_type == TrackType::Audio)
+            .collect();
+        audio_tracks.sort_by_key(|track| track.id);
+        // Project track id -> audio track id
+        let mut track_ids = std::collections::HashMap::new();
+        for track in &audio_tracks {
+            let track_id = self.daw_state.audio_tracks.add_track(track.name.clone());
+            track_ids.insert(track.id, track_id);
+            if let Some(audio_track) = self.daw_state.audio_tracks.track_mut(track_id) {
+                audio_track.gain = track.volume;
+                audio_track.pan = track.pan;
+                audio_track.muted = track.muted;
+                audio_track.soloed = track.soloed;
+                audio_track.sends =
+                    crate::project::serialization::sends_from_serializable(&track.sends);
+                audio_track.output = track.output;
+                audio_track.surround = track.surround;
+                audio_track.inserts = track.inserts.unwrap_or_default();
+            }
+            for clip in &track.clips {
+                match crate::project::serialization::audio_clip_from_serializable(
+                    clip,
+                    project.metadata.sample_rate,
+                    sample_rate,
+                ) {
+                    Ok(clip) => {
+                        self.daw_state.audio_tracks.add_clip(track_id, clip);
+                    }
+                    Err(e) => clip_errors.push(e),
+                }
+            }
+        }
+        // Inserts, keyed by the synth (track 0) or the rebuilt audio tracks
+        let insert = |dynamics: &crate::project::types::TrackDynamicsSerializable| TrackDynamics {
+            params: dynamics.params,
+            sidechain: dynamics.sidechain_track.and_then(|id| match id {
+                0 => Some(SidechainSource::Synth),
+                id => track_ids.get(&id).map(|id| SidechainSource::AudioTrack(*id)),
+            }),
+        };
+        for track in &audio_tracks {
+            if let Some(audio_track) = self.daw_state.audio_tracks.track_mut(track_ids[&track.id]) {
+                audio_track.dynamics = track.dynamics.as_ref().map(insert);
+            }
+        }
+        let synth_dynamics = project.tracks.get(&0).and_then(|track| track.dynamics.as_ref());
+        let _ = self.daw_state.set_synth_dynamics(synth_dynamics.map(insert));
+        let _ = self.daw_state.set_plugin_sidechain(None);
+        // Return buses keep their slot, which the track sends refer to
+        for index in 0..MAX_RETURN_BUSES {
+            let _ = self.daw_state.set_return_bus(index, None);
+        }
+        for bus in &project.return_buses {
+            let return_bus = ReturnBus {
+                name: bus.name.clone(),
+                params: bus.params,
+            };
+            let _ = self.daw_state.set_return_bus(bus.slot, Some(return_bus));
+        }
+        if let Some(track) = project.tracks.get(&0) {
+            let sends = crate::project::serialization::sends_from_serializable(&track.sends);
+            for (index, send) in sends.into_iter().enumerate() {
+                let _ = self.daw_state.set_synth_send(index, send);
+            }
+        }
+        let synth_output = project.tracks.get(&0).map(|track| track.output);
+        let synth_output = synth_output.filter(TrackOutput::is_valid).unwrap_or_default();
+        let _ = self.daw_state.set_synth_output(synth_output);
+        let _ = self.daw_state.set_synth_surround(project.tracks.get(&0).and_then(|t| t.surround));
+        let synth_inserts = project.tracks.get(&0).and_then(|track| track.inserts);
+        let _ = self
+            .daw_state
+            .set_track_inserts(MixerTrack::Synth, synth_inserts.unwrap_or_default());
+        let synth_strip = project.tracks.get(&0).map(|track| ChannelStrip {
+            gain: track.volume,
+            pan: track.pan,
+            muted: track.muted,
+            soloed: track.soloed,
+        });
+        let _ = self
+            .daw_state
+            .set_track_strip(MixerTrack::Synth, synth_strip.unwrap_or_default());
+        let _ = self.daw_state.set_speaker_layout(project.speaker_layout);
+        let _ = self.daw_state.set_master_chain(project.master_chain);
+        if let Err(e) = self.daw_state.set_automation_lanes(project.automation.clone()) {
+            eprintln!("Invalid automation in project: {}", e);
+        }
+        if let Err(e) = self.daw_state.cc_map.set_mappings(project.cc_mappings.clone()) {
+            eprintln!("Invalid MIDI mappings in project: {}", e);
+        }
+        self.send_audio_tracks();
+
+        // Instrument tracks: every other track with an instrument
+        let _ = self.daw_state.clear_instrument_tracks();
+        let mut instrument_tracks: Vec<_> = project
+            .tracks
+            .values()
+            .filter(|track| track.id != 0)
+            .filter_map(|track| {
+                let instrument = crate::project::serialization::track_instrument_from_serializable(
+                    track,
+                    &project.synth_params,
+                )?;
+                Some((track, instrument))
+            })
+            .collect();
+        instrument_tracks.sort_by_key(|(track, _)| track.id);
+        // Project track id -> instrument track id (the synth track is 0 in both)
+        let mut clip_tracks = std::collections::HashMap::from([(0, SYNTH_TRACK)]);
+        for (index, (track, instrument)) in instrument_tracks.into_iter().enumerate() {
+            let pattern = track
+                .pattern_id
+                .and_then(|id| self.project_patterns.get(&id))
+                .cloned()
+                .unwrap_or_else(|| {
+                    crate::sequencer::Pattern::new_default(
+                        crate::project::generate_pattern_id(),
+                        track.name.clone(),
+                    )
+                });
+            let instrument_track = InstrumentTrack {
+                id: index as u32 + 1,
+                name: track.name.clone(),
+                instrument,
+                pattern: Arc::new(pattern),
+                strip: ChannelStrip {
+                    gain: track.volume,
+                    pan: track.pan,
+                    muted: track.muted,
+                    soloed: track.soloed,
+                },
+                inserts: track.inserts.unwrap_or_default(),
+            };
+            let id = instrument_track.id;
+            match self.daw_state.insert_instrument_track(instrument_track) {
+                Ok(()) => {
+                    clip_tracks.insert(track.id, id);
+                }
+                Err(e) => clip_errors.push(format!("Track '{}': {}", track.name, e)),
+            }
+        }
+
+        // Song arrangement, on the rebuilt tracks
+        let arrangement = crate::project::serialization::arrangement_from_serializable(
+            &project.arrangement,
+            &self.project_patterns,
+            |track| clip_tracks.get(&track).copied(),
+        )
+        .unwrap_or_else(|e| {
+            clip_errors.push(format!("Arrangement: {}", e));
+            Arrangement::new()
+        });
+        let _ = self.daw_state.set_arrangement(arrangement);
+        let _ = self.daw_state.set_song_mode(project.song_mode);
+        let session = crate::project::serialization::session_from_serializable(
+            &project.session,
+            project.launch_quantization,
+            &self.project_patterns,
+            |track| clip_tracks.get(&track).copied(),
+        );
+        let _ = self.daw_state.release_session();
+        let _ = self.daw_state.set_session(session);
+
+        // Plugins: the project's instances replace the loaded ones
+        self.load_project_plugins(&project.plugins, &mut clip_errors);
+
         // Sync project state to audio thread
         self.sync_project_to_audio_thread(&project);
 
+        // Missing clip and plugin files do not prevent the project from opening
+        if !clip_errors.is_empty() {
+            self.show_error(clip_errors.join("\n"));
+        }
+
         Ok(())
     }
 
+    /// Replace the loaded plugins by the instances of a project, restoring
+    /// their saved state
+    fn load_project_plugins(
+        &mut self,
+        plugins: &[crate::project::types::PluginInstanceSerializable],
+        errors: &mut Vec<String>,
+    ) {
+        for plugin in std::mem::take(&mut self.loaded_plugins) {
+            if let Err(e) = self.plugin_host.destroy_instance(plugin.id) {
+                eprintln!("Failed to remove plugin {}: {}", plugin.name, e);
+            }
+        }
+
+        for plugin in plugins {
+            let result = self
+                .load_plugin(&plugin.path, Some(plugin.name.clone()))
+                .and_then(|instance_id| {
+                    let state = crate::project::serialization::plugin_state_from_serializable(plugin);
+                    self.plugin_host
+                        .load_instance_state(instance_id, &state)
+                        .map_err(|e| e.to_string())
+                });
+            if let Err(e) = result {
+                errors.push(format!("Plugin '{}': {}", plugin.name, e));
+            }
+        }
+    }
+
     /// Save project to specific path
-    fn save_project_to_path(&mut self, path: &PathBuf) -> Result<(), ProjectError> {
-        // Create project from current UI state
+    fn save_project_to_path(
+        &mut self,
+        path: &PathBuf,
+        options: &ProjectSaveOptions,
+    ) -> Result<(), ProjectError> {
+        let project = self.build_project();
+        self.project_manager
+            .save_project_with_options(&project, path, options)?;
+
+        Ok(())
+    }
+
+    /// Create a project from the current UI state
+    fn build_project(&self) -> Project {
         let mut project = self.project_manager.create_new_project(
             self.current_project_path
                 .as_ref()
@@ -1246,6 +4385,14 @@ impl DawApp {
 
         // Update synth parameters from UI state
         project.synth_params.waveform = self.selected_waveform;
+        project.synth_params.oscillator2 = self.daw_state.oscillator2;
+        project.synth_params.unison = self.daw_state.unison;
+        project.synth_params.anti_aliasing = self.daw_state.anti_aliasing;
+        project.synth_params.cross_mod = self.daw_state.cross_mod;
+        project.synth_params.noise = self.daw_state.noise;
+        project.synth_params.mod_envelope = self.daw_state.mod_envelope;
+        project.synth_params.filter_mod = self.daw_state.filter_mod;
+        project.synth_params.mod_lfos = self.daw_state.mod_lfos;
         project.synth_params.volume = self.volume_ui;
         project.synth_params.adsr = AdsrParams::new(
             self.adsr_attack,
@@ -1262,18 +4409,138 @@ impl DawApp {
         }
 
         // Also add the current active pattern if it's not already in the project
-        if !self.project_patterns.contains_key(&self.active_pattern.id) {
+        if !self.project_patterns.contains_key(&self.daw_state.pattern.id) {
             let serializable_pattern =
-                crate::project::serialization::pattern_to_serializable(&self.active_pattern);
+                crate::project::serialization::pattern_to_serializable(&self.daw_state.pattern);
             project
                 .patterns
-                .insert(self.active_pattern.id, serializable_pattern);
+                .insert(self.daw_state.pattern.id, serializable_pattern);
+        }
+
+        // Instrument tracks follow the synth track (plugin tracks are not saved)
+        let mut instrument_id = project.tracks.keys().max().map_or(0, |id| id + 1);
+        // Instrument track id -> project track id (the synth track is 0 in both)
+        let mut project_tracks = std::collections::HashMap::from([(SYNTH_TRACK, 0)]);
+        for track in &self.daw_state.instrument_tracks {
+            let Some(serializable) =
+                crate::project::serialization::instrument_track_to_serializable(track, instrument_id)
+            else {
+                continue;
+            };
+            project.patterns.entry(track.pattern.id).or_insert_with(|| {
+                crate::project::serialization::pattern_to_serializable(&track.pattern)
+            });
+            project.tracks.insert(instrument_id, serializable);
+            project_tracks.insert(track.id, instrument_id);
+            instrument_id += 1;
         }
 
-        // Save project
-        self.project_manager.save_project(&project, path)?;
+        // Song arrangement, with the patterns of its clips
+        project.arrangement = crate::project::serialization::arrangement_to_serializable(
+            &self.daw_state.arrangement,
+            |track| project_tracks.get(&track).copied(),
+        );
+        for clip in self.daw_state.arrangement.clips() {
+            project.patterns.entry(clip.pattern_id()).or_insert_with(|| {
+                crate::project::serialization::pattern_to_serializable(&clip.pattern)
+            });
+        }
+        project.song_mode = self.daw_state.song_mode;
 
-        Ok(())
+        // Session view slots, with their patterns
+        project.session = crate::project::serialization::session_to_serializable(
+            &self.daw_state.session,
+            |track| project_tracks.get(&track).copied(),
+        );
+        for slot in self.daw_state.session.slots() {
+            project.patterns.entry(slot.pattern.id).or_insert_with(|| {
+                crate::project::serialization::pattern_to_serializable(&slot.pattern)
+            });
+        }
+        project.launch_quantization = self.daw_state.session.quantization;
+
+        // Audio tracks follow the other tracks
+        let sample_rate = self.sequencer.sample_rate() as f32;
+        let first_id = project.tracks.keys().max().map_or(0, |id| id + 1);
+        // (frozen tracks are renders of a saved track)
+        let saved: Vec<_> = self
+            .daw_state
+            .audio_tracks
+            .tracks()
+            .iter()
+            .filter(|track| track.frozen.is_none())
+            .collect();
+        // Inserts refer to their sidechain source by project track id
+        let insert = |dynamics: &TrackDynamics| crate::project::types::TrackDynamicsSerializable {
+            params: dynamics.params,
+            sidechain_track: dynamics.sidechain.and_then(|source| match source {
+                SidechainSource::Synth => Some(0),
+                SidechainSource::AudioTrack(id) => saved
+                    .iter()
+                    .position(|track| track.id == id)
+                    .map(|index| first_id + index as u32),
+            }),
+        };
+        for (index, track) in saved.iter().enumerate() {
+            let id = first_id + index as u32;
+            let mut serializable = crate::project::serialization::audio_track_to_serializable(
+                track,
+                id,
+                sample_rate,
+                project.metadata.sample_rate,
+            );
+            serializable.dynamics = track.dynamics.as_ref().map(insert);
+            project.tracks.insert(id, serializable);
+        }
+        if let Some(track) = project.tracks.get_mut(&0) {
+            track.dynamics = self.daw_state.synth_dynamics.as_ref().map(insert);
+            track.sends =
+                crate::project::serialization::sends_to_serializable(&self.daw_state.synth_sends);
+            track.output = self.daw_state.synth_output;
+            track.surround = self.daw_state.synth_surround;
+            track.inserts =
+                crate::project::serialization::inserts_to_serializable(&self.daw_state.synth_inserts);
+            let strip = self.daw_state.synth_strip;
+            track.volume = strip.gain;
+            track.pan = strip.pan;
+            track.muted = strip.muted;
+            track.soloed = strip.soloed;
+        }
+        project.speaker_layout = self.daw_state.speaker_layout;
+        project.master_chain = self.daw_state.master_chain;
+        project.automation = self.daw_state.automation_lanes.values().cloned().collect();
+        project.cc_mappings = self.daw_state.cc_map.mappings();
+        project.plugins = self
+            .loaded_plugins
+            .iter()
+            .filter_map(|plugin| match self.plugin_host.save_instance_state(plugin.id) {
+                Ok(state) => Some(crate::project::serialization::plugin_to_serializable(
+                    PathBuf::from(&plugin.plugin_id),
+                    plugin.name.clone(),
+                    state,
+                )),
+                Err(e) => {
+                    eprintln!("Plugin {} not saved: {}", plugin.name, e);
+                    None
+                }
+            })
+            .collect();
+        project.return_buses = self
+            .daw_state
+            .return_buses
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, bus)| {
+                let bus = bus.as_ref()?;
+                Some(crate::project::types::ReturnBusSerializable {
+                    slot,
+                    name: bus.name.clone(),
+                    params: bus.params,
+                })
+            })
+            .collect();
+
+        project
     }
 
     /// Show error dialog
@@ -1310,19 +4577,13 @@ impl DawApp {
     /// Sync project state to audio thread
     fn sync_project_to_audio_thread(&mut self, project: &crate::project::Project) {
         // Send tempo
-        let cmd = Command::SetTempo(project.metadata.tempo);
-        if let Ok(mut tx) = self.command_tx.lock() {
-            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
-        }
+        let _ = self.daw_state.set_tempo(project.metadata.tempo);
 
         // Send time signature
-        let cmd = Command::SetTimeSignature(
+        let _ = self.daw_state.set_time_signature(
             project.metadata.time_signature.numerator,
             project.metadata.time_signature.denominator,
         );
-        if let Ok(mut tx) = self.command_tx.lock() {
-            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
-        }
 
         // Send synth parameters
         let cmd = Command::SetVolume(project.synth_params.volume);
@@ -1335,16 +4596,163 @@ impl DawApp {
             let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
         }
 
-        let cmd = Command::SetAdsr(project.synth_params.adsr);
+        self.daw_state.oscillator2 = project.synth_params.oscillator2;
+        let cmd = Command::SetOscillator2(project.synth_params.oscillator2);
         if let Ok(mut tx) = self.command_tx.lock() {
             let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
         }
 
-        // Send pattern
-        let cmd = Command::SetPattern(self.active_pattern.clone());
+        self.daw_state.unison = project.synth_params.unison;
+        let cmd = Command::SetUnison(project.synth_params.unison);
+        if let Ok(mut tx) = self.command_tx.lock() {
+            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+        }
+
+        self.daw_state.anti_aliasing = project.synth_params.anti_aliasing;
+        let cmd = Command::SetAntiAliasing(project.synth_params.anti_aliasing);
+        if let Ok(mut tx) = self.command_tx.lock() {
+            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+        }
+
+        self.daw_state.cross_mod = project.synth_params.cross_mod;
+        let cmd = Command::SetCrossMod(project.synth_params.cross_mod);
+        if let Ok(mut tx) = self.command_tx.lock() {
+            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+        }
+
+        self.daw_state.noise = project.synth_params.noise;
+        let cmd = Command::SetNoise(project.synth_params.noise);
+        if let Ok(mut tx) = self.command_tx.lock() {
+            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+        }
+
+        self.daw_state.mod_envelope = project.synth_params.mod_envelope;
+        let cmd = Command::SetModEnvelope(project.synth_params.mod_envelope);
+        if let Ok(mut tx) = self.command_tx.lock() {
+            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+        }
+
+        self.daw_state.filter_mod = project.synth_params.filter_mod;
+        let cmd = Command::SetFilterMod(project.synth_params.filter_mod);
+        if let Ok(mut tx) = self.command_tx.lock() {
+            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+        }
+
+        self.daw_state.mod_lfos = project.synth_params.mod_lfos;
+        for (index, params) in project.synth_params.mod_lfos.iter().enumerate() {
+            let cmd = Command::SetModLfo {
+                index: index as u8 + 1,
+                params: *params,
+            };
+            if let Ok(mut tx) = self.command_tx.lock() {
+                let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+            }
+        }
+
+        let cmd = Command::SetAdsr(project.synth_params.adsr);
         if let Ok(mut tx) = self.command_tx.lock() {
             let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
         }
+
+        // Send pattern
+        self.send_active_pattern();
+    }
+
+    /// Update the synth UI mirrors from the DawState (after undo, redo or A/B switch)
+    fn sync_synth_ui_from_state(&mut self) {
+        self.volume_ui = self.daw_state.volume;
+        self.selected_waveform = self.daw_state.waveform;
+        self.adsr_attack = self.daw_state.adsr.attack;
+        self.adsr_decay = self.daw_state.adsr.decay;
+        self.adsr_sustain = self.daw_state.adsr.sustain;
+        self.adsr_release = self.daw_state.adsr.release;
+        self.lfo_waveform = self.daw_state.lfo.waveform;
+        self.lfo_rate = self.daw_state.lfo.rate;
+        self.lfo_depth = self.daw_state.lfo.depth;
+        self.lfo_destination = self.daw_state.lfo.destination;
+        self.poly_mode = self.daw_state.poly_mode;
+        self.portamento_time = self.daw_state.portamento.time;
+        // Sync modulation UI from state mirror
+        for idx in 0..self.mod_routings_ui.len() {
+            self.mod_routings_ui[idx] = self.daw_state.mod_routings[idx];
+        }
+        self.volume_atomic.set(self.daw_state.volume);
+    }
+
+    /// Cut a loaded loop into slices at its transients (see `sampler::slicer`)
+    ///
+    /// The slices play on consecutive notes from the first slice note; with
+    /// "Write pattern", the pattern notes on those pitches are replaced by the
+    /// loop, its tempo guessed from its length. One undo step.
+    fn slice_sample(&mut self, index: usize) {
+        let Some(sample) = self.daw_state.samples.get(index) else {
+            return;
+        };
+        let first_note = self.slice_first_note;
+        let slices = LoopSlices::detect(sample);
+        let command = slices.slice(sample, first_note).and_then(|samples| {
+            let notes = if self.slice_to_pattern {
+                let source_bpm = estimate_loop_bpm(sample.len() as u64, sample.sample_rate as f32);
+                let loop_notes = slices.notes(
+                    first_note,
+                    source_bpm,
+                    self.sequencer.sample_rate(),
+                    self.sequencer.tempo(),
+                    self.sequencer.time_signature(),
+                )?;
+                let pitches = first_note..first_note + slices.len() as u8;
+                let mut notes: Vec<_> = self
+                    .daw_state
+                    .pattern
+                    .notes()
+                    .iter()
+                    .filter(|note| !pitches.contains(&note.pitch))
+                    .copied()
+                    .collect();
+                notes.extend(loop_notes);
+                Some(notes)
+            } else {
+                None
+            };
+            Ok(AddSlicesCommand::new(sample.name.clone(), samples, notes))
+        });
+        let result = command.and_then(|command| {
+            self.command_manager
+                .execute(Box::new(command), &mut self.daw_state)
+                .map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(()) => self.sync_editors_from_state(),
+            Err(e) => self.show_error(format!("Failed to slice sample: {}", e)),
+        }
+    }
+
+    /// Update the sampler and pattern mirrors from the DawState (after undo or redo)
+    fn sync_editors_from_state(&mut self) {
+        let mappings = &self.daw_state.sample_mappings;
+        self.note_map_input = (0..self.daw_state.samples.len())
+            .map(|slot| {
+                mappings
+                    .iter()
+                    .find(|(_, sample_index)| **sample_index == slot)
+                    .map_or_else(String::new, |(note, _)| note.to_string())
+            })
+            .collect();
+        // Pattern commands send the notes: a frozen synth stays silent
+        if self.frozen_synth.is_some() {
+            self.send_active_pattern();
+        }
+    }
+
+    /// Undo or redo until `index` commands of the history are applied
+    fn jump_to_history(&mut self, index: usize) {
+        match self.command_manager.jump_to(index, &mut self.daw_state) {
+            Ok(_) => println!("History: step {}", index),
+            Err(e) => eprintln!("History jump failed: {}", e),
+        }
+        // A failed jump stops partway: show wherever the history stopped
+        self.sync_synth_ui_from_state();
+        self.sync_editors_from_state();
     }
 
     /// Mark project as having unsaved changes
@@ -1359,7 +4767,7 @@ impl eframe::App for DawApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Process deferred plugin actions BEFORE rendering to avoid ID clashes
         if let Some(path) = self.plugin_to_load_next_frame.take() {
-            match self.load_plugin(&path) {
+            match self.load_plugin(&path, None) {
                 Ok(_) => {
                     println!("✅ Plugin loaded successfully!");
                 }
@@ -1385,8 +4793,21 @@ impl eframe::App for DawApp {
         // Always process PC keyboard input, regardless of the current tab
         self.process_pc_keyboard_input(ctx);
 
-        // Check if preview timer has expired
-        self.check_preview_timer();
+        // Check if a one-shot preview has ended
+        self.check_preview_end();
+
+        // Playhead and sample rate come from the audio engine
+        self.sync_transport_from_engine();
+
+        // Messages from OSC controllers, the control surface and mapped MIDI controllers
+        self.process_remote_actions();
+        self.process_control_surface();
+        self.process_midi_controllers();
+        self.process_midi_recording();
+
+        self.autosave_if_due();
+        self.update_rescue_session();
+        self.check_audio_input();
 
         // Handle Undo/Redo keyboard shortcuts
         ctx.input(|i| {
@@ -1399,23 +4820,8 @@ impl eframe::App for DawApp {
                 match self.command_manager.undo(&mut self.daw_state) {
                     Ok(description) => {
                         // Update UI state from DawState after undo
-                        self.volume_ui = self.daw_state.volume;
-                        self.selected_waveform = self.daw_state.waveform;
-                        self.adsr_attack = self.daw_state.adsr.attack;
-                        self.adsr_decay = self.daw_state.adsr.decay;
-                        self.adsr_sustain = self.daw_state.adsr.sustain;
-                        self.adsr_release = self.daw_state.adsr.release;
-                        self.lfo_waveform = self.daw_state.lfo.waveform;
-                        self.lfo_rate = self.daw_state.lfo.rate;
-                        self.lfo_depth = self.daw_state.lfo.depth;
-                        self.lfo_destination = self.daw_state.lfo.destination;
-                        self.poly_mode = self.daw_state.poly_mode;
-                        self.portamento_time = self.daw_state.portamento.time;
-                        // Sync modulation UI from state mirror
-                        for idx in 0..self.mod_routings_ui.len() {
-                            self.mod_routings_ui[idx] = self.daw_state.mod_routings[idx];
-                        }
-                        self.volume_atomic.set(self.daw_state.volume);
+                        self.sync_synth_ui_from_state();
+                        self.sync_editors_from_state();
                         println!("Undo: {}", description);
                     }
                     Err(e) => eprintln!("Undo failed: {}", e),
@@ -1430,23 +4836,8 @@ impl eframe::App for DawApp {
                 match self.command_manager.redo(&mut self.daw_state) {
                     Ok(description) => {
                         // Update UI state from DawState after redo
-                        self.volume_ui = self.daw_state.volume;
-                        self.selected_waveform = self.daw_state.waveform;
-                        self.adsr_attack = self.daw_state.adsr.attack;
-                        self.adsr_decay = self.daw_state.adsr.decay;
-                        self.adsr_sustain = self.daw_state.adsr.sustain;
-                        self.adsr_release = self.daw_state.adsr.release;
-                        self.lfo_waveform = self.daw_state.lfo.waveform;
-                        self.lfo_rate = self.daw_state.lfo.rate;
-                        self.lfo_depth = self.daw_state.lfo.depth;
-                        self.lfo_destination = self.daw_state.lfo.destination;
-                        self.poly_mode = self.daw_state.poly_mode;
-                        self.portamento_time = self.daw_state.portamento.time;
-                        // Sync modulation UI from state mirror
-                        for idx in 0..self.mod_routings_ui.len() {
-                            self.mod_routings_ui[idx] = self.daw_state.mod_routings[idx];
-                        }
-                        self.volume_atomic.set(self.daw_state.volume);
+                        self.sync_synth_ui_from_state();
+                        self.sync_editors_from_state();
                         println!("Redo: {}", description);
                     }
                     Err(e) => eprintln!("Redo failed: {}", e),
@@ -1459,29 +4850,53 @@ impl eframe::App for DawApp {
 
         // Check CPU load and notify if high
         self.check_cpu_load();
+        self.check_xruns();
+
+        // Push parameter updates held back while the command ringbuffer was full
+        if let Some(notification) = self.daw_state.flush_to_audio() {
+            self.notification_queue.push_back(notification);
+        }
+
+        // The master output is only tapped while the tuner is shown
+        if self.active_tab != UiTab::Tuner && self.tuner.is_attached() {
+            self.tuner.detach();
+        }
+
+        // Screen reader announcements (spoken on the next frame at the latest)
+        a11y::show_announcements(ctx);
+
+        if self.show_history {
+            let jump = egui::SidePanel::right("history_panel")
+                .default_width(240.0)
+                .show(ctx, |ui| history::show(ui, &self.command_manager.history()))
+                .inner;
+            if let Some(index) = jump {
+                self.jump_to_history(index);
+            }
+        }
 
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.heading("MyMusic DAW - MVP");
             ui.separator();
 
-            // Simple tab bar (no scrolling): show one category at a time
+            // Simple tab bar (no scrolling): show one category at a time.
+            // Tabs take the keyboard focus with Tab, Ctrl+PageUp/PageDown cycle them
             ui.horizontal(|ui| {
-                let button = |ui: &mut egui::Ui, label: &str, tab: UiTab, current: &mut UiTab| {
-                    let selected = *current == tab;
-                    if ui.selectable_label(selected, label).clicked() {
-                        *current = tab;
+                for tab in UiTab::ALL {
+                    if ui.selectable_label(self.active_tab == tab, tab.name()).clicked() {
+                        self.active_tab = tab;
+                        a11y::announce(ctx, format!("{} tab", tab.name()));
                     }
-                };
-                button(ui, "Project", UiTab::Project, &mut self.active_tab);
-                button(ui, "Devices", UiTab::Devices, &mut self.active_tab);
-                button(ui, "Synth", UiTab::Synth, &mut self.active_tab);
-                button(ui, "Modulation", UiTab::Modulation, &mut self.active_tab);
-                button(ui, "Sampler", UiTab::Sampler, &mut self.active_tab);
-                button(ui, "Sequencer", UiTab::Sequencer, &mut self.active_tab);
-                button(ui, "Plugins", UiTab::Plugins, &mut self.active_tab);
-                button(ui, "Play", UiTab::Play, &mut self.active_tab);
-                button(ui, "Performance", UiTab::Performance, &mut self.active_tab);
+                }
+                ui.separator();
+                ui.toggle_value(&mut self.show_history, "🕘 History")
+                    .on_hover_text("Show the undo history");
             });
+            let current = UiTab::ALL.iter().position(|tab| *tab == self.active_tab);
+            if let Some(index) = a11y::cycle_tabs(ctx, current.unwrap_or(0), UiTab::ALL.len()) {
+                self.active_tab = UiTab::ALL[index];
+                a11y::announce(ctx, format!("{} tab", self.active_tab.name()));
+            }
 
             ui.separator();
 
@@ -1524,6 +4939,14 @@ impl eframe::App for DawApp {
                             self.save_project();
                         }
 
+                        if ui
+                            .button("📦 Collect and Save")
+                            .on_hover_text("Copy the audio files into the project file, to open it on another machine")
+                            .clicked()
+                        {
+                            self.collect_and_save_project();
+                        }
+
                         if ui.button("💾 Save As...").clicked() {
                             self.save_project_as();
                         }
@@ -1571,12 +4994,12 @@ impl eframe::App for DawApp {
 
                     ui.horizontal(|ui| {
                         ui.label("Notes:");
-                        ui.label(format!("{}", self.active_pattern.note_count()));
+                        ui.label(format!("{}", self.daw_state.pattern.note_count()));
                     });
 
                     ui.horizontal(|ui| {
                         ui.label("Loaded Samples:");
-                        ui.label(format!("{}", self.loaded_samples.len()));
+                        ui.label(format!("{}", self.daw_state.samples.len()));
                     });
 
                     ui.horizontal(|ui| {
@@ -1592,7 +5015,7 @@ impl eframe::App for DawApp {
                     ui.heading("Audio Export");
 
                     ui.horizontal(|ui| {
-                        ui.label("Format:");
+                        let label = ui.label("Format:");
                         egui::ComboBox::from_id_salt("export_format")
                             .selected_text(match self.export_format {
                                 crate::audio::export::ExportFormat::Wav => "WAV",
@@ -1609,28 +5032,56 @@ impl eframe::App for DawApp {
                                     crate::audio::export::ExportFormat::Flac,
                                     "FLAC (Lossless)"
                                 );
-                            });
+                            })
+                            .response
+                            .labelled_by(label.id);
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("Sample Rate:");
+                        let label = ui.label("Sample Rate:");
                         egui::ComboBox::from_id_salt("export_sample_rate")
                             .selected_text(format!("{} Hz", self.export_sample_rate))
                             .show_ui(ui, |ui| {
                                 ui.selectable_value(&mut self.export_sample_rate, 44100, "44100 Hz (CD Quality)");
                                 ui.selectable_value(&mut self.export_sample_rate, 48000, "48000 Hz (Professional)");
                                 ui.selectable_value(&mut self.export_sample_rate, 96000, "96000 Hz (High Res)");
-                            });
+                            })
+                            .response
+                            .labelled_by(label.id);
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("Bit Depth:");
+                        let label = ui.label("Bit Depth:");
                         egui::ComboBox::from_id_salt("export_bit_depth")
                             .selected_text(format!("{} bit", self.export_bit_depth))
                             .show_ui(ui, |ui| {
                                 ui.selectable_value(&mut self.export_bit_depth, 16, "16 bit (CD Quality)");
                                 ui.selectable_value(&mut self.export_bit_depth, 24, "24 bit (Professional)");
-                            });
+                                // Floating point: WAV only
+                                if self.export_format == crate::audio::export::ExportFormat::Wav {
+                                    ui.selectable_value(&mut self.export_bit_depth, 32, "32 bit float (WAV)");
+                                }
+                            })
+                            .response
+                            .labelled_by(label.id);
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.labelled(
+                            "Bars:",
+                            egui::DragValue::new(&mut self.export_start_bar).range(1..=9999),
+                        );
+                        let mut to_end = self.export_end_bar.is_none();
+                        if ui.checkbox(&mut to_end, "To the end").changed() {
+                            self.export_end_bar =
+                                (!to_end).then_some(self.export_start_bar + 3);
+                        }
+                        if let Some(end_bar) = &mut self.export_end_bar {
+                            ui.labelled(
+                                "to",
+                                egui::DragValue::new(end_bar).range(self.export_start_bar..=9999),
+                            );
+                        }
                     });
 
                     ui.horizontal(|ui| {
@@ -1650,7 +5101,7 @@ impl eframe::App for DawApp {
                     });
 
                     ui.add_space(5.0);
-                    ui.label("💡 Tip: Export renders the current pattern to an audio file.");
+                    ui.label("💡 Tip: Export renders the arrangement (audible tracks, bar range) faster than real time.");
                 }
                 UiTab::Devices => {
                     // Devices tab
@@ -1689,12 +5140,17 @@ impl eframe::App for DawApp {
                                         );
                                     }
                                 }
-                            });
+                            })
+                            .response
+                            .named("MIDI input device");
 
                         // Si le device a changé, déclencher la reconnexion
                         if previous_device != self.selected_midi_device {
                             self.midi_connection_manager
                                 .set_target_device(self.selected_midi_device.clone());
+                            self.preferences.midi.input_device =
+                                Some(self.selected_midi_device.clone());
+                            self.save_preferences();
                         }
 
                         if ui.button("🔄").on_hover_text("Refresh devices").clicked() {
@@ -1702,8 +5158,35 @@ impl eframe::App for DawApp {
                         }
                     });
 
+                    if self.selected_midi_device == NETWORK_MIDI_DEVICE {
+                        let peers = self.midi_connection_manager.network_peers();
+                        ui.label(if peers.is_empty() {
+                            format!(
+                                "Network MIDI: waiting for sessions on UDP port {} (add this \
+                                 machine's IP address on the sending device)",
+                                DEFAULT_RTP_MIDI_PORT
+                            )
+                        } else {
+                            format!("Network MIDI sessions: {}", peers.join(", "))
+                        });
+                    }
+
                     ui.horizontal(|ui| {
-                        ui.label("Audio Output:");
+                        let label = ui.label("Audio Output:");
+
+                        let audio_status = self
+                            .audio_engine
+                            .as_ref()
+                            .map_or(DeviceStatus::Disconnected, |engine| engine.status.get());
+                        let (status_text, status_color) = match audio_status {
+                            DeviceStatus::Connected => ("●", egui::Color32::GREEN),
+                            DeviceStatus::Connecting => ("●", egui::Color32::YELLOW),
+                            DeviceStatus::Disconnected => ("○", egui::Color32::GRAY),
+                            DeviceStatus::Error => ("●", egui::Color32::RED),
+                        };
+                        ui.colored_label(status_color, status_text);
+
+                        let previous_device = self.selected_audio_device.clone();
                         egui::ComboBox::from_id_salt("audio_device_selector")
                             .selected_text(&self.selected_audio_device)
                             .show_ui(ui, |ui| {
@@ -1723,151 +5206,157 @@ impl eframe::App for DawApp {
                                         );
                                     }
                                 }
-                            });
+                            })
+                            .response
+                            .labelled_by(label.id);
+
+                        if previous_device != self.selected_audio_device {
+                            self.preferences.audio.output_device =
+                                Some(self.selected_audio_device.clone());
+                            self.save_preferences();
+                            self.switch_audio_device();
+                        }
                     });
-                }
-                UiTab::Modulation => {
-                    // Modulation tab
-                    ui.heading("Modulation Matrix (MVP)");
 
-                    let src_labels = ["LFO 1", "Velocity", "Aftertouch", "Envelope"];
-                    let dst_labels = ["Pitch", "Amplitude", "Pan"];
+                    ui.add_enabled_ui(self.recording_track.is_none(), |ui| {
+                        self.draw_input_device(ui);
+                    });
 
-                    for (i, routing) in self.mod_routings_ui.iter_mut().enumerate() {
-                        ui.horizontal(|ui| {
-                            ui.label(format!("Slot {}:", i + 1));
+                    ui.separator();
+                    self.draw_preferences(ui);
 
-                            // Enabled toggle
-                            let mut enabled = routing.enabled;
-                            if ui.checkbox(&mut enabled, "On").changed() {
-                                let old = *routing;
-                                routing.enabled = enabled;
-                                let cmd = Box::new(SetModRoutingCommand::new_with_old(
-                                    i as u8, *routing, old,
-                                ));
-                                let _ = self.command_manager.execute(cmd, &mut self.daw_state);
-                            }
-
-                            // Source selector
-                            let prev_source = routing.source;
-                            egui::ComboBox::from_id_salt(format!("mod_src_{}", i))
-                                .selected_text(match routing.source {
-                                    ModSource::Lfo(0) => src_labels[0],
-                                    ModSource::Velocity => src_labels[1],
-                                    ModSource::Aftertouch => src_labels[2],
-                                    ModSource::Envelope => src_labels[3],
-                                    _ => "Unused",
-                                })
-                                .show_ui(ui, |ui| {
-                                    ui.selectable_value(
-                                        &mut routing.source,
-                                        ModSource::Lfo(0),
-                                        src_labels[0],
-                                    );
-                                    ui.selectable_value(
-                                        &mut routing.source,
-                                        ModSource::Velocity,
-                                        src_labels[1],
-                                    );
-                                    ui.selectable_value(
-                                        &mut routing.source,
-                                        ModSource::Aftertouch,
-                                        src_labels[2],
-                                    );
-                                    ui.selectable_value(
-                                        &mut routing.source,
-                                        ModSource::Envelope,
-                                        src_labels[3],
-                                    );
-                                });
-                            if routing.source != prev_source {
-                                let old = ModRouting {
-                                    source: prev_source,
-                                    ..*routing
-                                };
-                                let cmd = Box::new(SetModRoutingCommand::new_with_old(
-                                    i as u8, *routing, old,
-                                ));
-                                let _ = self.command_manager.execute(cmd, &mut self.daw_state);
-                            }
+                    ui.separator();
+                    ui.label("OSC Remote Control:");
+                    ui.horizontal(|ui| {
+                        let mut enabled = self.osc_server.is_some();
+                        if ui.checkbox(&mut enabled, "Enable").changed() {
+                            self.set_osc_enabled(enabled);
+                        }
+                        let label = ui.label("UDP port:");
+                        ui.add_enabled(
+                            self.osc_server.is_none(),
+                            egui::DragValue::new(&mut self.osc_port).range(1024..=65535),
+                        )
+                        .labelled_by(label.id);
+                        if !self.osc_status.is_empty() {
+                            ui.label(&self.osc_status);
+                        }
+                    });
+                    ui.label("Addresses: /transport/play, /transport/stop, /transport/tempo, /synth/volume, /synth/cutoff, /synth/resonance, /synth/attack|decay|sustain|release, /metronome/enabled, /metronome/volume, /note/on, /note/off");
 
-                            // Destination selector
-                            let prev_dest = routing.destination;
-                            egui::ComboBox::from_id_salt(format!("mod_dst_{}", i))
-                                .selected_text(match routing.destination {
-                                    ModDestination::OscillatorPitch(0) => dst_labels[0],
-                                    ModDestination::Amplitude => dst_labels[1],
-                                    ModDestination::Pan => dst_labels[2],
-                                    _ => "Unused",
-                                })
-                                .show_ui(ui, |ui| {
-                                    ui.selectable_value(
-                                        &mut routing.destination,
-                                        ModDestination::OscillatorPitch(0),
-                                        dst_labels[0],
-                                    );
-                                    ui.selectable_value(
-                                        &mut routing.destination,
-                                        ModDestination::Amplitude,
-                                        dst_labels[1],
-                                    );
-                                    ui.selectable_value(
-                                        &mut routing.destination,
-                                        ModDestination::Pan,
-                                        dst_labels[2],
-                                    );
-                                });
-                            if routing.destination != prev_dest {
-                                let old = ModRouting {
-                                    destination: prev_dest,
-                                    ..*routing
-                                };
-                                let cmd = Box::new(SetModRoutingCommand::new_with_old(
-                                    i as u8, *routing, old,
-                                ));
-                                let _ = self.command_manager.execute(cmd, &mut self.daw_state);
-                            }
+                    ui.separator();
+                    ui.label("Control Surface (Mackie Control):");
+                    ui.horizontal(|ui| {
+                        let current = self.preferences.midi.control_surface.clone();
+                        let mut selected = current.clone();
+                        egui::ComboBox::from_id_salt("control_surface_selector")
+                            .selected_text(current.as_deref().unwrap_or("None"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut selected, None, "None");
+                                for port in &self.available_control_surfaces {
+                                    ui.selectable_value(&mut selected, Some(port.clone()), port);
+                                }
+                            })
+                            .response
+                            .named("Control surface");
+                        if selected != current {
+                            self.set_control_surface(selected);
+                        }
+                        if !self.control_surface_status.is_empty() {
+                            ui.label(&self.control_surface_status);
+                        }
+                    });
+                    ui.label("Faders, V-Pots and mute buttons control 8 audio tracks (bank and channel buttons scroll them); transport buttons play, stop, record and rewind.");
+                }
+                UiTab::Modulation => {
+                    // Modulation tab
+                    ui.heading("Modulation Matrix");
 
-                            // Amount slider
-                            let prev_amount = routing.amount;
-                            let range = match routing.destination {
-                                ModDestination::OscillatorPitch(_) => -12.0..=12.0, // semitones
-                                ModDestination::Amplitude => -1.0..=1.0,            // multiplier delta
-                                ModDestination::Pan => -1.0..=1.0,                  // pan L/R
-                                ModDestination::FilterCutoff => 0.0..=10.0, // cutoff multiplier (0.1x to 10x)
-                            };
-                            if ui
-                                .add(egui::Slider::new(&mut routing.amount, range).fixed_decimals(2))
-                                .changed()
-                            {
+                    egui::ScrollArea::vertical()
+                        .id_salt("mod_matrix_slots")
+                        .max_height(360.0)
+                        .show(ui, |ui| {
+                            for (i, routing) in self.mod_routings_ui.iter_mut().enumerate() {
                                 let old = *routing;
-                                // Clamp for safety
-                                routing.amount = match routing.destination {
-                                    ModDestination::OscillatorPitch(_) => {
-                                        routing.amount.clamp(-24.0, 24.0)
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("Slot {}:", i + 1));
+                                    ui.checkbox(&mut routing.enabled, "On");
+
+                                    // Source selector (one entry for every MIDI CC)
+                                    egui::ComboBox::from_id_salt(format!("mod_src_{}", i))
+                                        .selected_text(routing.source.name())
+                                        .show_ui(ui, |ui| {
+                                            for source in ModSource::ALL {
+                                                let selected = match (routing.source, source) {
+                                                    (ModSource::MidiCc(_), ModSource::MidiCc(_)) => {
+                                                        true
+                                                    }
+                                                    (current, source) => current == source,
+                                                };
+                                                if ui.selectable_label(selected, source.name()).clicked()
+                                                    && !selected
+                                                {
+                                                    routing.source = source;
+                                                }
+                                            }
+                                        })
+                                        .response
+                                        .named("Modulation source");
+                                    if let ModSource::MidiCc(controller) = &mut routing.source {
+                                        ui.add(
+                                            egui::DragValue::new(controller)
+                                                .range(0..=127)
+                                                .prefix("CC "),
+                                        )
+                                        .named("MIDI controller");
                                     }
-                                    _ => routing.amount.clamp(-1.0, 1.0), // For Amplitude and Pan
-                                };
-                                let cmd = Box::new(SetModRoutingCommand::new_with_old(
-                                    i as u8, *routing, old,
-                                ));
-                                let _ = self.command_manager.execute(cmd, &mut self.daw_state);
-                            } else if (routing.amount - prev_amount).abs() > 0.0 {
-                                // no-op
+
+                                    // Destination selector
+                                    egui::ComboBox::from_id_salt(format!("mod_dst_{}", i))
+                                        .selected_text(routing.destination.name())
+                                        .show_ui(ui, |ui| {
+                                            for destination in ModDestination::ALL {
+                                                ui.selectable_value(
+                                                    &mut routing.destination,
+                                                    destination,
+                                                    destination.name(),
+                                                );
+                                            }
+                                        })
+                                        .response
+                                        .named("Modulation destination");
+
+                                    // Amount slider (range of the destination)
+                                    let range = routing.destination.amount_range();
+                                    if routing.destination != old.destination {
+                                        routing.amount =
+                                            routing.amount.clamp(*range.start(), *range.end());
+                                    }
+                                    ui.add(egui::Slider::new(&mut routing.amount, range).fixed_decimals(2))
+                                        .named("Modulation amount");
+                                });
+                                if *routing != old {
+                                    let cmd = Box::new(SetModRoutingCommand::new_with_old(
+                                        i as u8, *routing, old,
+                                    ));
+                                    let _ = self.command_manager.execute(cmd, &mut self.daw_state);
+                                }
                             }
                         });
-                    }
 
                     ui.label("Sources are normalized to [-1,1]; pitch amount is semitones.");
                     ui.label("Aftertouch requires a controller that sends Channel Pressure.");
+                    ui.label("Mod Wheel, Mod Env and MIDI CC are unipolar [0,1]: no modulation at rest.");
+                    ui.label("Key Track is 0 on C4; Random S&H draws a new value at each note.");
+                    ui.label("LFO rate and envelope time amounts are octaves (x2 per unit).");
 
                     ui.add_space(10.0);
                     ui.separator();
 
                     // LFO controls
-                    ui.heading("LFO (Modulation)");
+                    ui.heading("LFO 1 (Modulation)");
                     ui.horizontal(|ui| {
-                        ui.label("LFO Waveform:");
+                        let label = ui.label("LFO Waveform:");
                         let previous_lfo_waveform = self.lfo_waveform;
                         egui::ComboBox::from_id_salt("lfo_waveform_selector")
                             .selected_text(match self.lfo_waveform {
@@ -1889,7 +5378,9 @@ impl eframe::App for DawApp {
                                     WaveformType::Triangle,
                                     "Triangle",
                                 );
-                            });
+                            })
+                            .response
+                            .labelled_by(label.id);
 
                         if previous_lfo_waveform != self.lfo_waveform {
                             let params = LfoParams::new(
@@ -1897,6 +5388,11 @@ impl eframe::App for DawApp {
                                 self.lfo_rate,
                                 self.lfo_depth,
                                 self.lfo_destination,
+                            )
+                            .with_timing(
+                                self.daw_state.lfo.sync,
+                                self.daw_state.lfo.mode,
+                                self.daw_state.lfo.start_phase,
                             );
                             let cmd = Box::new(SetLfoCommand::new(params));
                             let _ = self.command_manager.execute(cmd, &mut self.daw_state);
@@ -1904,13 +5400,17 @@ impl eframe::App for DawApp {
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("LFO Rate:");
+                        let free_rate = self.daw_state.lfo.sync.is_none();
                         if ui
-                            .add(
-                                egui::Slider::new(&mut self.lfo_rate, 0.1..=20.0)
-                                    .text("Hz")
-                                    .logarithmic(true),
-                            )
+                            .add_enabled_ui(free_rate, |ui| {
+                                ui.labelled(
+                                    "LFO Rate:",
+                                    egui::Slider::new(&mut self.lfo_rate, 0.1..=20.0)
+                                        .text("Hz")
+                                        .logarithmic(true),
+                                )
+                            })
+                            .inner
                             .changed()
                         {
                             let params = LfoParams::new(
@@ -1918,6 +5418,11 @@ impl eframe::App for DawApp {
                                 self.lfo_rate,
                                 self.lfo_depth,
                                 self.lfo_destination,
+                            )
+                            .with_timing(
+                                self.daw_state.lfo.sync,
+                                self.daw_state.lfo.mode,
+                                self.daw_state.lfo.start_phase,
                             );
                             let cmd = Box::new(SetLfoCommand::new(params));
                             let _ = self.command_manager.execute(cmd, &mut self.daw_state);
@@ -1925,9 +5430,8 @@ impl eframe::App for DawApp {
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("LFO Depth:");
                         if ui
-                            .add(egui::Slider::new(&mut self.lfo_depth, 0.0..=1.0))
+                            .labelled("LFO Depth:", egui::Slider::new(&mut self.lfo_depth, 0.0..=1.0))
                             .changed()
                         {
                             let params = LfoParams::new(
@@ -1935,6 +5439,11 @@ impl eframe::App for DawApp {
                                 self.lfo_rate,
                                 self.lfo_depth,
                                 self.lfo_destination,
+                            )
+                            .with_timing(
+                                self.daw_state.lfo.sync,
+                                self.daw_state.lfo.mode,
+                                self.daw_state.lfo.start_phase,
                             );
                             let cmd = Box::new(SetLfoCommand::new(params));
                             let _ = self.command_manager.execute(cmd, &mut self.daw_state);
@@ -1942,7 +5451,7 @@ impl eframe::App for DawApp {
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("LFO Destination:");
+                        let label = ui.label("LFO Destination:");
                         let previous_destination = self.lfo_destination;
                         egui::ComboBox::from_id_salt("lfo_destination_selector")
                             .selected_text(match self.lfo_destination {
@@ -1972,7 +5481,9 @@ impl eframe::App for DawApp {
                                     LfoDestination::FilterCutoff,
                                     "Filter Cutoff (Phase 3a)",
                                 );
-                            });
+                            })
+                            .response
+                            .labelled_by(label.id);
 
                         if previous_destination != self.lfo_destination {
                             let params = LfoParams::new(
@@ -1980,34 +5491,139 @@ impl eframe::App for DawApp {
                                 self.lfo_rate,
                                 self.lfo_depth,
                                 self.lfo_destination,
+                            )
+                            .with_timing(
+                                self.daw_state.lfo.sync,
+                                self.daw_state.lfo.mode,
+                                self.daw_state.lfo.start_phase,
                             );
                             let cmd = Box::new(SetLfoCommand::new(params));
                             let _ = self.command_manager.execute(cmd, &mut self.daw_state);
                         }
                     });
+
+                    let mut lfo = self.daw_state.lfo;
+                    if lfo_timing_controls(ui, "lfo_1", &mut lfo) {
+                        let cmd = Box::new(SetLfoCommand::new(lfo));
+                        let _ = self.command_manager.execute(cmd, &mut self.daw_state);
+                    }
+
+                    // LFOs only heard through the matrix ("LFO 2", "LFO 3" sources)
+                    for index in 1..LFO_COUNT {
+                        ui.add_space(10.0);
+                        ui.separator();
+                        ui.heading(format!("LFO {} (Matrix)", index + 1));
+                        let id = format!("lfo_{}", index + 1);
+                        let mut params = self.daw_state.mod_lfos[index - 1];
+                        let mut changed = false;
+                        ui.horizontal(|ui| {
+                            let label = ui.label("Waveform:");
+                            egui::ComboBox::from_id_salt(format!("{}_waveform", id))
+                                .selected_text(format!("{:?}", params.waveform))
+                                .show_ui(ui, |ui| {
+                                    for waveform in [
+                                        WaveformType::Sine,
+                                        WaveformType::Square,
+                                        WaveformType::Saw,
+                                        WaveformType::Triangle,
+                                    ] {
+                                        changed |= ui
+                                            .selectable_value(
+                                                &mut params.waveform,
+                                                waveform,
+                                                format!("{:?}", waveform),
+                                            )
+                                            .changed();
+                                    }
+                                })
+                                .response
+                                .labelled_by(label.id);
+                        });
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add_enabled_ui(params.sync.is_none(), |ui| {
+                                    ui.labelled(
+                                        "Rate:",
+                                        egui::Slider::new(&mut params.rate, 0.1..=20.0)
+                                            .text("Hz")
+                                            .logarithmic(true),
+                                    )
+                                })
+                                .inner
+                                .changed();
+                            changed |= ui
+                                .labelled("Depth:", egui::Slider::new(&mut params.depth, 0.0..=1.0))
+                                .changed();
+                        });
+                        changed |= lfo_timing_controls(ui, &id, &mut params);
+                        if changed {
+                            let cmd = Box::new(SetModLfoCommand::new(index as u8, params));
+                            if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
+                                eprintln!("Failed to execute LFO command: {}", e);
+                            }
+                            self.mark_project_modified();
+                        }
+                    }
+                    ui.label("Synced LFOs follow the project tempo; Free LFOs share one cycle across voices.");
+
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    // Modulation envelope, routed by the matrix ("Mod Env" source)
+                    ui.heading("Mod Envelope");
+                    let mut mod_envelope = self.daw_state.mod_envelope;
+                    let mut mod_envelope_changed = false;
+                    for (label, value, range, unit) in [
+                        ("Attack:", &mut mod_envelope.attack, 0.001..=5.0, "s"),
+                        ("Decay:", &mut mod_envelope.decay, 0.001..=5.0, "s"),
+                        ("Sustain:", &mut mod_envelope.sustain, 0.0..=1.0, ""),
+                        ("Release:", &mut mod_envelope.release, 0.001..=5.0, "s"),
+                    ] {
+                        ui.horizontal(|ui| {
+                            let logarithmic = !unit.is_empty();
+                            mod_envelope_changed |= ui
+                                .labelled(
+                                    label,
+                                    egui::Slider::new(value, range).text(unit).logarithmic(logarithmic),
+                                )
+                                .changed();
+                        });
+                    }
+                    if mod_envelope_changed {
+                        let mod_envelope = AdsrParams::new(
+                            mod_envelope.attack,
+                            mod_envelope.decay,
+                            mod_envelope.sustain,
+                            mod_envelope.release,
+                        );
+                        let cmd = Box::new(SetModEnvelopeCommand::new(mod_envelope));
+                        if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
+                            eprintln!("Failed to execute mod envelope command: {}", e);
+                        }
+                        self.mark_project_modified();
+                    }
+                    ui.label("Retriggered by each note, independent from the amp ADSR.");
                 }
                 UiTab::Sampler => {
                     ui.heading("Sampler");
                     // Sample bank management
                     ui.horizontal(|ui| {
                         if ui.button("Load Sample").clicked() {
-                            let file = FileDialog::new()
-                                .add_filter("Audio Files", &["wav", "flac", "mp3"])
+                            let file = file_dialog(self.preferences.paths.samples.as_deref())
+                                .add_filter("Audio Files", AUDIO_EXTENSIONS)
                                 .pick_file();
 
                             if let Some(path) = file {
                                 match load_sample(&path) {
                                     Ok(sample) => {
-                    // Clone sample: one for UI, one for audio thread
-                    let sample_for_audio = Arc::new(sample.clone());
-                    let cmd = Command::AddSample(sample_for_audio);
-                    if let Ok(mut tx) = self.command_tx.lock()
-                        && ringbuf::traits::Producer::try_push(&mut *tx, cmd).is_err()
-                    {
-                        eprintln!("Failed to send AddSample command: ringbuffer full");
-                    }
-                                        self.loaded_samples.push(sample);
-                                        self.note_map_input.push(String::new());
+                                        let command = Box::new(AddSampleCommand::new(sample));
+                                        match self
+                                            .command_manager
+                                            .execute(command, &mut self.daw_state)
+                                        {
+                                            Ok(()) => self.note_map_input.push(String::new()),
+                                            Err(e) => eprintln!("Failed to add sample: {}", e),
+                                        }
                                     }
             Err(e) => {
                 self.show_error(format!("Failed to create new project: {}", e));
@@ -2017,7 +5633,7 @@ impl eframe::App for DawApp {
                         }
 
                         if ui.button("Save Bank").clicked()
-                            && let Some(path) = FileDialog::new()
+                            && let Some(path) = file_dialog(self.preferences.paths.samples.as_deref())
                                 .add_filter("Sample Bank", &["json"])
                                 .set_file_name("sample_bank.json")
                                 .save_file()
@@ -2032,8 +5648,8 @@ impl eframe::App for DawApp {
                             }
                         }
                         if ui.button("Load Bank").clicked()
-                            && let Some(path) = FileDialog::new()
-                                .add_filter("Sample Bank", &["json"])
+                            && let Some(path) = file_dialog(self.preferences.paths.samples.as_deref())
+                                .add_filter("Sample Bank", &["json", "sfz"])
                                 .pick_file()
                         {
                             match self.load_sample_bank(&path) {
@@ -2049,23 +5665,66 @@ impl eframe::App for DawApp {
 
                     ui.add_space(10.0);
                     ui.heading("Loaded Samples");
+                    ui.horizontal(|ui| {
+                        ui.label("Slices from:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.slice_first_note)
+                                .range(0..=127)
+                                .speed(0.2)
+                                .custom_formatter(|value, _| {
+                                    crate::ui::piano_roll::PianoRollEditor::get_note_name(value as u8)
+                                }),
+                        );
+                        ui.checkbox(&mut self.slice_to_pattern, "Write pattern")
+                            .on_hover_text("Replace the notes of the slices in the pattern with the loop");
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Preview at:");
+                        ui.add_enabled(
+                            !self.preview_original_pitch,
+                            egui::DragValue::new(&mut self.preview_note)
+                                .range(0..=127)
+                                .speed(0.2)
+                                .custom_formatter(|value, _| {
+                                    crate::ui::piano_roll::PianoRollEditor::get_note_name(value as u8)
+                                }),
+                        );
+                        ui.checkbox(&mut self.preview_original_pitch, "Original pitch")
+                            .on_hover_text("Play the samples at their root note");
+                        ui.checkbox(&mut self.preview_loop, "Loop")
+                            .on_hover_text("Repeat the sample (its loop region when it has one)");
+                        ui.checkbox(&mut self.preview_latch, "Latch")
+                            .on_hover_text("Keep playing after the click, until Stop");
+                    });
 
                     // Track actions to perform after rendering UI (to avoid borrow conflicts)
-                    let mut preview_action: Option<(usize, bool)> = None; // (index, is_stop)
+                    let mut preview_action: Option<(usize, bool)> = None; // latched (index, is_stop)
+                    let mut preview_held: Option<usize> = None; // button held down
                     let mut delete_action: Option<usize> = None; // index to delete
+                    let mut assign_action: Option<(u8, usize)> = None; // (note, index)
+                    let mut slice_action: Option<usize> = None; // index to slice
+                    let tempo = *self.sequencer.tempo();
 
-                    for (i, sample) in self.loaded_samples.iter_mut().enumerate() {
+                    for (i, sample) in self.daw_state.samples.iter_mut().enumerate() {
                         // Extract preview state before ui.horizontal to avoid borrow issues
-                        let is_previewing =
-                            self.preview_sample_note.map(|(idx, _)| idx == i).unwrap_or(false);
+                        let is_previewing = self.previewing == Some(i);
+                        let preview_latch = self.preview_latch;
 
                         ui.horizontal(|ui| {
                             ui.label(&sample.name);
 
-                            // Preview button
-                            let preview_button_text = if is_previewing { "⏸ Stop" } else { "▶ Preview" };
-                            if ui.button(preview_button_text).clicked() {
-                                preview_action = Some((i, is_previewing));
+                            // Preview button: held down to listen, or clicked to start and stop when latched
+                            if preview_latch {
+                                let preview_button_text = if is_previewing { "⏹ Stop" } else { "▶ Preview" };
+                                if ui.selectable_label(is_previewing, preview_button_text).clicked() {
+                                    preview_action = Some((i, is_previewing));
+                                }
+                            } else if ui
+                                .selectable_label(is_previewing, "▶ Preview")
+                                .on_hover_text("Hold to listen")
+                                .is_pointer_button_down_on()
+                            {
+                                preview_held = Some(i);
                             }
 
                             // Delete button
@@ -2073,6 +5732,14 @@ impl eframe::App for DawApp {
                                 delete_action = Some(i);
                             }
 
+                            if ui
+                                .button("✂ Slice")
+                                .on_hover_text("Cut the loop at its transients, one slice per note")
+                                .clicked()
+                            {
+                                slice_action = Some(i);
+                            }
+
                             let mut is_looping =
                                 sample.loop_mode == crate::sampler::loader::LoopMode::Forward;
                             if ui.checkbox(&mut is_looping, "Loop").changed() {
@@ -2148,19 +5815,12 @@ impl eframe::App for DawApp {
                                 }
                             }
 
-                            ui.label("Note:");
-                            ui.text_edit_singleline(&mut self.note_map_input[i]);
+                            ui.labelled("Note:", egui::TextEdit::singleline(&mut self.note_map_input[i]));
                             if ui.button("Assign").clicked()
-                && let Ok(note) = self.note_map_input[i].parse::<u8>()
-            {
-                let cmd =
-                    Command::SetNoteSampleMapping { note, sample_index: i };
-                if let Ok(mut tx) = self.command_tx.lock() && ringbuf::traits::Producer::try_push(&mut *tx, cmd).is_err() {
-                    eprintln!(
-                    "Failed to send SetNoteSampleMapping command: ringbuffer full"
-                );
-                }
-            }
+                                && let Ok(note) = self.note_map_input[i].parse::<u8>()
+                            {
+                                assign_action = Some((note, i));
+                            }
                         });
 
                         // Waveform Plot with loop markers
@@ -2205,9 +5865,8 @@ impl eframe::App for DawApp {
                             });
 
                         ui.horizontal(|ui| {
-                            ui.label("Volume:");
                             if ui
-                                .add(egui::Slider::new(&mut sample.volume, 0.0..=1.0))
+                                .labelled("Volume:", egui::Slider::new(&mut sample.volume, 0.0..=1.0))
                                 .changed()
                             {
                                 let sample_arc = Arc::new(sample.clone());
@@ -2216,9 +5875,8 @@ impl eframe::App for DawApp {
                                     eprintln!("Failed to send UpdateSample command: ringbuffer full");
                                 }
                             }
-                            ui.label("Pan:");
                             if ui
-                                .add(egui::Slider::new(&mut sample.pan, -1.0..=1.0))
+                                .labelled("Pan:", egui::Slider::new(&mut sample.pan, -1.0..=1.0))
                                 .changed()
                             {
                                 let sample_arc = Arc::new(sample.clone());
@@ -2227,9 +5885,8 @@ impl eframe::App for DawApp {
                                     eprintln!("Failed to send UpdateSample command: ringbuffer full");
                                 }
                             }
-                            ui.label("Pitch Offset:");
                             if ui
-                                .add(egui::Slider::new(&mut sample.pitch_offset, -12..=12).suffix(" st"))
+                                .labelled("Pitch Offset:", egui::Slider::new(&mut sample.pitch_offset, -12..=12).suffix(" st"))
                                 .changed()
                             {
                                 let sample_arc = Arc::new(sample.clone());
@@ -2239,46 +5896,134 @@ impl eframe::App for DawApp {
                                 }
                             }
                         });
+
+                        // Time-stretch and pitch-shift: a drag edits a draft, the
+                        // sample is rendered again once the gesture ends
+                        ui.horizontal(|ui| {
+                            let draft_id = ui.id().with(("sample_stretch", i));
+                            let current = sample.stretch_settings();
+                            let mut settings = ui
+                                .data(|data| data.get_temp::<StretchSettings>(draft_id))
+                                .unwrap_or(current);
+                            let mut ended = false;
+                            let response = ui.labelled(
+                                "Repitch:",
+                                egui::DragValue::new(&mut settings.pitch)
+                                    .range(-MAX_PITCH_SHIFT..=MAX_PITCH_SHIFT)
+                                    .speed(0.1)
+                                    .suffix(" st"),
+                            );
+                            ended |= response.drag_stopped() || (response.changed() && !response.dragged());
+                            let response = ui.labelled(
+                                "Length:",
+                                egui::DragValue::new(&mut settings.ratio)
+                                    .range(MIN_STRETCH_RATIO..=MAX_STRETCH_RATIO)
+                                    .speed(0.01)
+                                    .suffix("×"),
+                            );
+                            ended |= response.drag_stopped() || (response.changed() && !response.dragged());
+                            if ui
+                                .button("Fit to Tempo")
+                                .on_hover_text("Stretch the sample to the project tempo")
+                                .clicked()
+                            {
+                                // Tempo guessed from the unstretched length
+                                let source_length = (sample.len() as f64 / current.ratio) as u64;
+                                let source_bpm = estimate_loop_bpm(source_length, sample.sample_rate as f32);
+                                match StretchSettings::for_tempo(source_bpm, tempo.bpm()) {
+                                    Ok(fitted) => settings.ratio = fitted.ratio,
+                                    Err(e) => eprintln!("Failed to fit sample to tempo: {}", e),
+                                }
+                                ended = true;
+                            }
+                            if ui.button("Reset").clicked() {
+                                settings = StretchSettings::default();
+                                ended = true;
+                            }
+
+                            if !ended {
+                                if settings != current {
+                                    ui.data_mut(|data| data.insert_temp(draft_id, settings));
+                                }
+                                return;
+                            }
+                            ui.data_mut(|data| data.remove::<StretchSettings>(draft_id));
+                            if settings != current && settings.validate().is_ok() {
+                                sample.set_stretch(settings);
+                                let sample_arc = Arc::new(sample.clone());
+                                let cmd = Command::UpdateSample(i, sample_arc);
+                                if let Ok(mut tx) = self.command_tx.lock() && ringbuf::traits::Producer::try_push(&mut *tx, cmd).is_err() {
+                                    eprintln!("Failed to send UpdateSample command: ringbuffer full");
+                                }
+                            }
+                        });
                     }
 
                     // Handle preview action after the loop to avoid borrow conflicts
                     if let Some((idx, is_stop)) = preview_action {
                         if is_stop {
-                            // Stop preview
-                            if let Some((_, note)) = self.preview_sample_note {
-                                self.send_note_off_direct(note);
-                            }
-                            self.preview_sample_note = None;
-                            self.preview_timer = None;
+                            self.stop_preview();
                         } else {
-                            // Start preview
                             self.preview_sample(idx);
                         }
                     }
+                    // A held preview starts on press and stops on release
+                    if preview_held != self.preview_held {
+                        match preview_held {
+                            Some(idx) => self.preview_sample(idx),
+                            None => self.stop_preview(),
+                        }
+                        self.preview_held = preview_held;
+                    }
 
                     // Handle delete action after the loop to avoid borrow conflicts
                     if let Some(idx) = delete_action {
                         // Stop preview if deleting the currently previewed sample
-                        if let Some((preview_idx, note)) = self.preview_sample_note {
+                        if let Some(preview_idx) = self.previewing {
                             if preview_idx == idx {
-                                self.send_note_off_direct(note);
-                                self.preview_sample_note = None;
-                                self.preview_timer = None;
+                                self.stop_preview();
                             } else if preview_idx > idx {
                                 // Update preview index if it's after the deleted sample
-                                self.preview_sample_note = Some((preview_idx - 1, note));
+                                self.previewing = Some(preview_idx - 1);
+                            }
+                        }
+
+                        let command = Box::new(RemoveSampleCommand::new(idx));
+                        match self.command_manager.execute(command, &mut self.daw_state) {
+                            Ok(()) => {
+                                self.note_map_input.remove(idx);
                             }
+                            Err(e) => eprintln!("Failed to remove sample: {}", e),
                         }
+                    }
+
+                    if let Some(idx) = slice_action {
+                        self.slice_sample(idx);
+                    }
 
-                        // Send command to audio thread
-                        let cmd = Command::RemoveSample(idx);
-                        if let Ok(mut tx) = self.command_tx.lock() && ringbuf::traits::Producer::try_push(&mut *tx, cmd).is_err() {
-                            eprintln!("Failed to send RemoveSample command: ringbuffer full");
+                    if let Some((note, idx)) = assign_action {
+                        let command = Box::new(SetSampleMappingCommand::new(note, idx));
+                        if let Err(e) = self.command_manager.execute(command, &mut self.daw_state) {
+                            eprintln!("Failed to map note {}: {}", note, e);
                         }
+                    }
 
-                        // Remove from UI
-                        self.loaded_samples.remove(idx);
-                        self.note_map_input.remove(idx);
+                    if !self.daw_state.samples.is_empty() {
+                        ui.add_space(10.0);
+                        egui::CollapsingHeader::new("Key Zones")
+                            .default_open(false)
+                            .show(ui, |ui| {
+                                let names: Vec<String> =
+                                    self.daw_state.samples.iter().map(|sample| sample.name.clone()).collect();
+                                if let Some((idx, zone)) =
+                                    self.zone_editor.show(ui, &names, &self.daw_state.sample_zones)
+                                {
+                                    let command = Box::new(SetSampleZoneCommand::new(idx, zone));
+                                    if let Err(e) = self.command_manager.execute(command, &mut self.daw_state) {
+                                        eprintln!("Failed to set sample zone: {}", e);
+                                    }
+                                }
+                            });
                     }
                 }
                 UiTab::Sequencer => {
@@ -2295,11 +6040,14 @@ impl eframe::App for DawApp {
                             TransportState::Recording => ("⏸ Pause", "⏸ Pause", "⏹ Stop", "⏺ Recording..."),
                             TransportState::Paused => ("▶ Play", "⏸ Pause", "⏹ Stop", "⏺ Record"),
                             TransportState::Stopped => ("▶ Play", "⏸ Pause", "⏹ Stop", "⏺ Record"),
+                            TransportState::CountingIn => ("▶ Play", "⏸ Pause", "⏹ Stop", "⏺ Counting in..."),
                         };
 
                         if ui.button(play_button).clicked() {
                             if transport_state.is_playing() {
+                                self.finish_midi_take();
                                 self.sequencer.pause();
+                                self.finish_audio_take();
                                 // Send transport state to audio thread
                                 let cmd = Command::SetTransportPlaying(false);
                                 if let Ok(mut tx) = self.command_tx.lock() {
@@ -2316,7 +6064,9 @@ impl eframe::App for DawApp {
                         }
 
                         if ui.button(stop_button).clicked() {
+                            self.finish_midi_take();
                             self.sequencer.stop();
+                            self.finish_audio_take();
                             // Send transport state to audio thread
                             let cmd = Command::SetTransportPlaying(false);
                             if let Ok(mut tx) = self.command_tx.lock() {
@@ -2326,9 +6076,47 @@ impl eframe::App for DawApp {
 
                         if ui.button(record_button).clicked() {
                             if transport_state.is_recording() {
+                                self.finish_midi_take();
                                 self.sequencer.pause();
+                                self.finish_audio_take();
+                            } else if transport_state.is_counting_in() {
+                                // Cancel the count-in, the timeline did not move
+                                self.sequencer.pause();
+                                let cmd = Command::SetTransportPlaying(false);
+                                if let Ok(mut tx) = self.command_tx.lock() {
+                                    let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+                                }
+                            } else if self.sequencer.count_in() {
+                                // The engine clicks the count-in, then starts the transport
+                                let cmd = Command::StartCountIn(self.sequencer.count_in_bars());
+                                if let Ok(mut tx) = self.command_tx.lock() {
+                                    let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+                                }
                             } else {
                                 self.sequencer.record();
+                                // The armed audio track records while the sequencer plays
+                                if let Err(e) = self.start_audio_take() {
+                                    self.show_error(format!("Failed to start recording: {}", e));
+                                }
+                                let cmd = Command::SetTransportPlaying(true);
+                                if let Ok(mut tx) = self.command_tx.lock() {
+                                    let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+                                }
+                            }
+                        }
+
+                        if transport_state.is_counting_in() {
+                            let count_in_samples = self.engine_snapshot.read().count_in_samples;
+                            let beat_samples = self
+                                .sequencer
+                                .tempo()
+                                .beat_duration_samples(self.sequencer.sample_rate());
+                            if count_in_samples > 0 && beat_samples > 0.0 {
+                                let beats_left = (count_in_samples as f64 / beat_samples).ceil();
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(255, 200, 60),
+                                    format!("Count-in: {}", beats_left),
+                                );
                             }
                         }
                     });
@@ -2347,18 +6135,15 @@ impl eframe::App for DawApp {
 
                     // Tempo and time signature controls
                     ui.horizontal(|ui| {
-                        ui.label("Tempo (BPM):");
-                        if ui.add(
+                        if ui.labelled(
+                            "Tempo (BPM):",
                             egui::Slider::new(&mut self.sequencer_tempo, 60.0..=200.0)
                                 .text("BPM")
                                 .fixed_decimals(1)
                         ).changed() {
                             self.sequencer.set_tempo(Tempo::new(self.sequencer_tempo));
                             // Send tempo to audio thread
-                            let cmd = Command::SetTempo(self.sequencer_tempo);
-                            if let Ok(mut tx) = self.command_tx.lock() {
-                                let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
-                            }
+                            let _ = self.daw_state.set_tempo(self.sequencer_tempo);
                             self.mark_project_modified();
                         }
 
@@ -2366,19 +6151,17 @@ impl eframe::App for DawApp {
 
                         ui.label("Time Signature:");
                         ui.horizontal(|ui| {
-                            if ui.add(egui::DragValue::new(&mut self.time_signature_numerator).range(1..=16)).changed() {
+                            let numerator = egui::DragValue::new(&mut self.time_signature_numerator).range(1..=16);
+                            if ui.add(numerator).named("Time signature numerator").changed() {
                                 self.sequencer.set_time_signature(TimeSignature::new(
                                     self.time_signature_numerator,
                                     self.time_signature_denominator
                                 ));
                                 // Send time signature to audio thread
-                                let cmd = Command::SetTimeSignature(
+                                let _ = self.daw_state.set_time_signature(
                                     self.time_signature_numerator,
                                     self.time_signature_denominator
                                 );
-                                if let Ok(mut tx) = self.command_tx.lock() {
-                                    let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
-                                }
                                 self.mark_project_modified();
                             };
                             ui.label("/");
@@ -2393,7 +6176,9 @@ impl eframe::App for DawApp {
                                     for (i, &denom) in denominator_options.iter().enumerate() {
                                         ui.selectable_value(&mut selected_index, i, format!("{}", denom));
                                     }
-                                });
+                                })
+                                .response
+                                .named("Time signature denominator");
 
                             if selected_index != current_index {
                                 self.time_signature_denominator = denominator_options[selected_index];
@@ -2402,13 +6187,10 @@ impl eframe::App for DawApp {
                                     self.time_signature_denominator
                                 ));
                                 // Send time signature to audio thread
-                                let cmd = Command::SetTimeSignature(
+                                let _ = self.daw_state.set_time_signature(
                                     self.time_signature_numerator,
                                     self.time_signature_denominator
                                 );
-                                if let Ok(mut tx) = self.command_tx.lock() {
-                                    let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
-                                }
                                 self.mark_project_modified();
                             }
                         });
@@ -2424,8 +6206,7 @@ impl eframe::App for DawApp {
                         }
 
                         if self.loop_enabled {
-                            ui.label("From:");
-                            if ui.add(egui::DragValue::new(&mut self.loop_start_bars).range(1..=999)).changed() {
+                            if ui.labelled("From:", egui::DragValue::new(&mut self.loop_start_bars).range(1..=999)).changed() {
                                 let start_pos = Position::from_musical(
                                     MusicalTime::new(self.loop_start_bars, 1, 0),
                                     self.sequencer.sample_rate(),
@@ -2441,8 +6222,7 @@ impl eframe::App for DawApp {
                                 self.sequencer.set_loop_region(start_pos, end_pos);
                             }
 
-                            ui.label("To:");
-                            if ui.add(egui::DragValue::new(&mut self.loop_end_bars).range(1..=999)).changed() {
+                            if ui.labelled("To:", egui::DragValue::new(&mut self.loop_end_bars).range(1..=999)).changed() {
                                 if self.loop_end_bars <= self.loop_start_bars {
                                     self.loop_end_bars = self.loop_start_bars + 1;
                                 }
@@ -2470,20 +6250,47 @@ impl eframe::App for DawApp {
                         ui.label("Metronome:");
                         if ui.checkbox(&mut self.metronome_enabled, "Enable").changed() {
                             // Send metronome enable command to audio thread
-                            let cmd = Command::SetMetronomeEnabled(self.metronome_enabled);
-                            if let Ok(mut tx) = self.command_tx.lock() {
-                                let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
-                            }
+                            let _ = self.daw_state.set_metronome_enabled(self.metronome_enabled);
+                        }
+
+                        if ui.labelled("Volume:", egui::Slider::new(&mut self.metronome_volume, 0.0..=1.0)).changed() {
+                            // Send metronome volume command to audio thread
+                            let _ = self.daw_state.set_metronome_volume(self.metronome_volume);
+                        }
+
+                        // Own bus: never in the mix, the meters or the taps
+                        ui.label("Output:");
+                        let outputs = self.output_choices();
+                        let mut output = self.daw_state.metronome_output;
+                        let picked = output_picker(ui, "metronome", &mut output, &outputs);
+                        if picked && self.daw_state.set_metronome_output(output).is_ok() {
+                            self.preferences.audio.metronome_output = output;
+                            self.save_preferences();
+                        }
+
+                        // Clicks before recording, even with the metronome off
+                        ui.label("Count-in:");
+                        let mut bars = self.sequencer.count_in_bars();
+                        let bars_label = |bars: u32| match bars {
+                            0 => "Off".to_string(),
+                            1 => "1 bar".to_string(),
+                            _ => format!("{} bars", bars),
+                        };
+                        egui::ComboBox::from_id_salt("count_in_bars")
+                            .selected_text(bars_label(bars))
+                            .show_ui(ui, |ui| {
+                                for option in 0..=MAX_COUNT_IN_BARS {
+                                    ui.selectable_value(&mut bars, option, bars_label(option));
+                                }
+                            });
+                        if bars != self.sequencer.count_in_bars() {
+                            self.sequencer.set_count_in_bars(bars);
+                            self.preferences.audio.count_in_bars = bars;
+                            self.save_preferences();
                         }
 
-                        ui.label("Volume:");
-                        if ui.add(egui::Slider::new(&mut self.metronome_volume, 0.0..=1.0)).changed() {
-                            // Send metronome volume command to audio thread
-                            let cmd = Command::SetMetronomeVolume(self.metronome_volume);
-                            if let Ok(mut tx) = self.command_tx.lock() {
-                                let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
-                            }
-                        }
+                        ui.checkbox(&mut self.input_quantize, "Input Quantize")
+                            .on_hover_text("Snap the notes recorded from the MIDI input (grid, strength and swing of the Quantize menu)");
                     });
 
                     ui.add_space(10.0);
@@ -2497,7 +6304,7 @@ impl eframe::App for DawApp {
                         }
 
                         if self.snap_to_grid_enabled {
-                            ui.label("Grid:");
+                            let label = ui.label("Grid:");
                             let subdivision_options = [(1, "Whole"), (2, "Half"), (4, "Quarter"), (8, "Eighth"), (16, "Sixteenth")];
                             let current_index = subdivision_options.iter().position(|&(div, _)| div == self.grid_subdivision).unwrap_or(2);
                             let mut selected_index = current_index;
@@ -2508,7 +6315,9 @@ impl eframe::App for DawApp {
                                     for (i, &(_div, label)) in subdivision_options.iter().enumerate() {
                                         ui.selectable_value(&mut selected_index, i, format!("{} note", label));
                                     }
-                                });
+                                })
+                                .response
+                                .labelled_by(label.id);
 
                             if selected_index != current_index {
                                 self.grid_subdivision = subdivision_options[selected_index].0;
@@ -2556,31 +6365,168 @@ impl eframe::App for DawApp {
                     // Piano Roll editor
                     ui.heading("Piano Roll");
                     ui.label(format!("Pattern: {} ({} bars, {} notes)",
-                        self.active_pattern.name,
-                        self.active_pattern.length_bars,
-                        self.active_pattern.note_count()
+                        self.daw_state.pattern.name,
+                        self.daw_state.pattern.length_bars,
+                        self.daw_state.pattern.note_count()
                     ));
 
+                    // Freeze: the pattern plays from an offline render
+                    ui.horizontal(|ui| {
+                        if self.frozen_synth.is_some() {
+                            ui.label("❄ Track frozen: unfreeze to edit the pattern");
+                            if ui.button("Unfreeze").clicked() {
+                                self.unfreeze_synth_track();
+                            }
+                        } else {
+                            let can_freeze = self.daw_state.voice_mode == VoiceMode::Synth
+                                && self.daw_state.pattern.note_count() > 0;
+                            if ui
+                                .add_enabled(can_freeze, egui::Button::new("❄ Freeze Track"))
+                                .on_hover_text("Render the pattern to audio to free CPU (synth mode)")
+                                .clicked()
+                            {
+                                self.freeze_synth_track();
+                            }
+                        }
+                    });
+
                     ui.add_space(10.0);
 
                     // Show piano roll (returns true if pattern was modified)
-                    let pattern_changed = self.piano_roll_editor.show(
-                        ui,
-                        &mut self.active_pattern,
-                        self.sequencer.tempo(),
-                        self.sequencer.time_signature(),
-                        self.sequencer.sample_rate(),
-                        self.sequencer.shared_state().position_samples(),
-                    );
+                    let frozen = self.frozen_synth.is_some();
+                    let mut midi_import = None;
+                    ui.horizontal(|ui| {
+                        ui.add_enabled_ui(!frozen, |ui| {
+                            ui.menu_button("🎲 Generate", |ui| self.draw_generate_menu(ui));
+                            ui.menu_button("🧲 Quantize", |ui| self.draw_quantize_menu(ui));
+                            if ui
+                                .button("🎹 Import MIDI")
+                                .on_hover_text("Create patterns from a MIDI file (or drop it here)")
+                                .clicked()
+                            {
+                                midi_import = file_dialog(self.preferences.paths.samples.as_deref())
+                                    .add_filter("MIDI Files", &["mid", "midi"])
+                                    .pick_file();
+                            }
+                        });
+                        ui.menu_button("💾 Export MIDI", |ui| {
+                            if ui.button("Active Pattern...").clicked() {
+                                ui.close_menu();
+                                self.export_midi_file(false);
+                            }
+                            if ui.button("Whole Project...").clicked() {
+                                ui.close_menu();
+                                self.export_midi_file(true);
+                            }
+                        });
+                    });
+                    // MIDI files dropped on the window while the Sequencer tab is open
+                    if !frozen && midi_import.is_none() {
+                        midi_import = ui.ctx().input(|i| {
+                            i.raw.dropped_files.iter().filter_map(|file| file.path.clone()).find(|path| {
+                                path.extension().is_some_and(|ext| {
+                                    ext.eq_ignore_ascii_case("mid") || ext.eq_ignore_ascii_case("midi")
+                                })
+                            })
+                        });
+                    }
+                    if let Some(path) = midi_import {
+                        self.import_midi_file(&path);
+                    }
+                    if !self.piano_roll_editor.is_dragging() {
+                        self.pattern_edit_base = self.daw_state.pattern.notes().to_vec();
+                    }
+                    let pattern_changed = ui
+                        .add_enabled_ui(!frozen, |ui| {
+                            self.piano_roll_editor.show(
+                                ui,
+                                &mut self.daw_state.pattern,
+                                self.sequencer.tempo(),
+                                self.sequencer.time_signature(),
+                                self.sequencer.sample_rate(),
+                                self.sequencer.shared_state().position_samples(),
+                            )
+                        })
+                        .inner;
 
-                    // Auto-send pattern to audio thread when modified
+                    // One undo step per gesture: the command replaces the notes the
+                    // gesture started from (and sends the pattern)
                     if pattern_changed {
-                        let cmd = Command::SetPattern(self.active_pattern.clone());
-                        if let Ok(mut tx) = self.command_tx.lock() {
-                            let _ = ringbuf::traits::Producer::try_push(&mut *tx, cmd);
+                        let notes = self.daw_state.pattern.notes().to_vec();
+                        let base = std::mem::take(&mut self.pattern_edit_base);
+                        let edit = self.piano_roll_editor.take_edit_description();
+                        let description = edit.unwrap_or(match notes.len().cmp(&base.len()) {
+                            std::cmp::Ordering::Greater => "Add Note",
+                            std::cmp::Ordering::Less => "Delete Notes",
+                            std::cmp::Ordering::Equal => "Move Note",
+                        });
+                        self.daw_state.pattern.set_notes(base);
+                        let command = Box::new(SetPatternNotesCommand::new(description, notes));
+                        if let Err(e) = self.command_manager.execute(command, &mut self.daw_state) {
+                            eprintln!("Failed to edit pattern: {}", e);
+                        }
+                    }
+
+                    // Step sequencer view of the same pattern
+                    ui.add_space(6.0);
+                    let step_edit = egui::CollapsingHeader::new("🥁 Step Sequencer")
+                        .id_salt("step_sequencer")
+                        .show(ui, |ui| {
+                            ui.add_enabled_ui(!frozen, |ui| {
+                                self.step_sequencer.show(
+                                    ui,
+                                    &self.daw_state.pattern,
+                                    self.sequencer.tempo(),
+                                    self.sequencer.time_signature(),
+                                    self.sequencer.sample_rate(),
+                                    self.sequencer.shared_state().position_samples(),
+                                )
+                            })
+                            .inner
+                        })
+                        .body_returned
+                        .flatten();
+                    if let Some((description, notes)) = step_edit {
+                        let command = Box::new(SetPatternNotesCommand::new(description, notes));
+                        if let Err(e) = self.command_manager.execute(command, &mut self.daw_state) {
+                            eprintln!("Failed to edit pattern: {}", e);
                         }
                     }
 
+                    // Automation lane, aligned with the piano roll
+                    ui.add_space(6.0);
+                    let beats_per_bar = self.sequencer.time_signature().beats_per_bar();
+                    let beat_samples = self.sequencer.tempo().beat_duration_samples(self.sequencer.sample_rate());
+                    let playhead_beats = self.sequencer.shared_state().position_samples() as f64 / beat_samples;
+                    let (pixels_per_beat, scroll_x) = self.piano_roll_editor.horizontal_view();
+                    if let Some((parameter, description, points)) = self.automation_editor.show(
+                        ui,
+                        &self.daw_state.automation_lanes,
+                        self.daw_state.pattern.length_bars as f64 * beats_per_bar,
+                        beats_per_bar,
+                        playhead_beats,
+                        pixels_per_beat,
+                        scroll_x,
+                    ) {
+                        let command = Box::new(SetAutomationPointsCommand::new(parameter, description, points));
+                        match self.command_manager.execute(command, &mut self.daw_state) {
+                            Ok(()) => self.mark_project_modified(),
+                            Err(e) => eprintln!("Failed to edit automation: {}", e),
+                        }
+                    }
+
+                    ui.add_space(10.0);
+                    ui.add_enabled_ui(!frozen, |ui| self.draw_groove(ui));
+
+                    ui.add_space(10.0);
+                    self.draw_arrangement(ui);
+
+                    ui.add_space(10.0);
+                    self.draw_session(ui);
+
+                    ui.add_space(10.0);
+                    self.draw_audio_tracks(ui);
+
                     ui.add_space(10.0);
 
                     // Information display
@@ -2594,10 +6540,68 @@ impl eframe::App for DawApp {
                     // Synth tab
                     ui.heading("Synth");
 
+                    if let Some(parameter) = self.daw_state.cc_map.learning() {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 200, 80),
+                                format!("MIDI learn: move a controller to map {}", parameter.name()),
+                            );
+                            if ui.button("Cancel").clicked() {
+                                self.daw_state.cc_map.cancel_learn();
+                            }
+                        });
+                    } else {
+                        ui.label("Right-click a control to map it to a MIDI controller.")
+                            .on_hover_text("Volume, envelope and filter controls support MIDI learn");
+                    }
+
+                    // A/B patch comparison (undoable, the UI mirrors follow the state)
+                    ui.horizontal(|ui| {
+                        ui.label("Compare:");
+                        let active = self.daw_state.ab_comparison.active();
+                        let mut command: Option<Box<dyn UndoableCommand>> = None;
+                        for slot in [PatchSlot::A, PatchSlot::B] {
+                            let label = format!("{:?}", slot);
+                            if ui.selectable_label(active == slot, label).clicked() && slot != active {
+                                command = Some(Box::new(SwitchPatchSlotCommand::new()));
+                            }
+                        }
+                        ui.separator();
+                        if ui.button("Copy A → B").clicked() {
+                            command = Some(Box::new(CopyPatchSlotCommand::new(PatchSlot::A, PatchSlot::B)));
+                        }
+                        let b_filled = self.daw_state.ab_comparison.is_filled(PatchSlot::B);
+                        if ui.add_enabled(b_filled, egui::Button::new("Copy B → A")).clicked() {
+                            command = Some(Box::new(CopyPatchSlotCommand::new(PatchSlot::B, PatchSlot::A)));
+                        }
+                        if let Some(command) = command {
+                            if let Err(e) = self.command_manager.execute(command, &mut self.daw_state) {
+                                eprintln!("Failed to execute A/B command: {}", e);
+                            }
+                            self.sync_synth_ui_from_state();
+                            self.mark_project_modified();
+                        }
+                    });
+
+                    // Presets (loading is undoable)
+                    egui::CollapsingHeader::new("Presets")
+                        .id_salt("synth_presets")
+                        .show(ui, |ui| {
+                            if let Some(preset) = self.preset_browser.show(ui, &self.daw_state) {
+                                let cmd = Box::new(LoadSynthPresetCommand::new(preset));
+                                if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
+                                    eprintln!("Failed to load preset: {}", e);
+                                }
+                                self.sync_synth_ui_from_state();
+                                self.mark_project_modified();
+                            }
+                        });
+
                     // Volume control (using undoable commands)
                     ui.horizontal(|ui| {
-                        ui.label("Volume:");
-                        if ui.add(egui::Slider::new(&mut self.volume_ui, 0.0..=1.0)).changed() {
+                        let response = ui.labelled("Volume:", egui::Slider::new(&mut self.volume_ui, 0.0..=1.0));
+                        midi_learn_menu(&response, &mut self.daw_state.cc_map, CcParameter::Volume);
+                        if response.changed() {
                             let cmd = Box::new(SetVolumeCommand::new(self.volume_ui));
                             if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
                                 eprintln!("Failed to execute volume command: {}", e);
@@ -2609,7 +6613,7 @@ impl eframe::App for DawApp {
 
                     // Waveform selection
                     ui.horizontal(|ui| {
-                        ui.label("Waveform:");
+                        let label = ui.label("Waveform:");
                         let previous_waveform = self.selected_waveform;
                         egui::ComboBox::from_id_salt("waveform_selector")
                             .selected_text(match self.selected_waveform {
@@ -2631,7 +6635,9 @@ impl eframe::App for DawApp {
                                     WaveformType::Triangle,
                                     "Triangle",
                                 );
-                            });
+                            })
+                            .response
+                            .labelled_by(label.id);
 
                         if previous_waveform != self.selected_waveform {
                             let cmd = Box::new(SetWaveformCommand::new(self.selected_waveform));
@@ -2642,6 +6648,185 @@ impl eframe::App for DawApp {
                         }
                     });
 
+                    // Band-limiting of the oscillators
+                    ui.horizontal(|ui| {
+                        let label = ui.label("Anti-aliasing:");
+                        let mut anti_aliasing = self.daw_state.anti_aliasing;
+                        egui::ComboBox::from_id_salt("anti_aliasing_selector")
+                            .selected_text(match anti_aliasing {
+                                AntiAliasing::PolyBlep => "PolyBLEP",
+                                AntiAliasing::Wavetable => "Wavetable",
+                            })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut anti_aliasing, AntiAliasing::PolyBlep, "PolyBLEP");
+                                ui.selectable_value(&mut anti_aliasing, AntiAliasing::Wavetable, "Wavetable");
+                            })
+                            .response
+                            .labelled_by(label.id)
+                            .on_hover_text("Wavetables remove aliasing on high notes, PolyBLEP keeps more top harmonics");
+
+                        if anti_aliasing != self.daw_state.anti_aliasing {
+                            let cmd = Box::new(SetAntiAliasingCommand::new(anti_aliasing));
+                            if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
+                                eprintln!("Failed to execute anti-aliasing command: {}", e);
+                            }
+                            self.mark_project_modified();
+                        }
+                    });
+
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    // Second oscillator, detuned from the note and mixed with the first
+                    ui.heading("Oscillator 2");
+                    let mut oscillator2 = self.daw_state.oscillator2;
+                    let mut oscillator2_changed = false;
+                    ui.horizontal(|ui| {
+                        let label = ui.label("Waveform:");
+                        egui::ComboBox::from_id_salt("oscillator2_waveform")
+                            .selected_text(format!("{:?}", oscillator2.waveform))
+                            .show_ui(ui, |ui| {
+                                for waveform in [
+                                    WaveformType::Sine,
+                                    WaveformType::Square,
+                                    WaveformType::Saw,
+                                    WaveformType::Triangle,
+                                ] {
+                                    oscillator2_changed |= ui
+                                        .selectable_value(&mut oscillator2.waveform, waveform, format!("{:?}", waveform))
+                                        .changed();
+                                }
+                            })
+                            .response
+                            .labelled_by(label.id);
+                    });
+                    ui.horizontal(|ui| {
+                        oscillator2_changed |= ui
+                            .labelled(
+                                "Coarse:",
+                                egui::Slider::new(&mut oscillator2.coarse, -MAX_OSC2_COARSE..=MAX_OSC2_COARSE)
+                                    .text("st"),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        oscillator2_changed |= ui
+                            .labelled(
+                                "Fine:",
+                                egui::Slider::new(&mut oscillator2.fine, -MAX_OSC2_FINE..=MAX_OSC2_FINE)
+                                    .text("ct"),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        oscillator2_changed |= ui
+                            .labelled("Mix:", egui::Slider::new(&mut oscillator2.mix, 0.0..=1.0))
+                            .on_hover_text("0: oscillator 1 only, 1: oscillator 2 only")
+                            .changed();
+                    });
+                    if oscillator2_changed {
+                        let cmd = Box::new(SetOscillator2Command::new(oscillator2));
+                        if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
+                            eprintln!("Failed to execute Oscillator 2 command: {}", e);
+                        }
+                        self.mark_project_modified();
+                    }
+
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    // Noise source and cross-modulation of the oscillators
+                    ui.heading("Noise & Cross-Mod");
+                    let mut noise = self.daw_state.noise;
+                    let mut noise_changed = false;
+                    ui.horizontal(|ui| {
+                        let label = ui.label("Noise:");
+                        egui::ComboBox::from_id_salt("noise_type")
+                            .selected_text(format!("{:?}", noise.noise_type))
+                            .show_ui(ui, |ui| {
+                                for noise_type in [NoiseType::White, NoiseType::Pink] {
+                                    noise_changed |= ui
+                                        .selectable_value(&mut noise.noise_type, noise_type, format!("{:?}", noise_type))
+                                        .changed();
+                                }
+                            })
+                            .response
+                            .labelled_by(label.id);
+                    });
+                    ui.horizontal(|ui| {
+                        noise_changed |= ui
+                            .labelled("Level:", egui::Slider::new(&mut noise.level, 0.0..=1.0))
+                            .on_hover_text("Noise added to the oscillators, before the filter")
+                            .changed();
+                    });
+                    if noise_changed {
+                        let noise = NoiseParams::new(noise.noise_type, noise.level);
+                        let cmd = Box::new(SetNoiseCommand::new(noise));
+                        if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
+                            eprintln!("Failed to execute noise command: {}", e);
+                        }
+                        self.mark_project_modified();
+                    }
+
+                    let mut cross_mod = self.daw_state.cross_mod;
+                    let mut cross_mod_changed = false;
+                    ui.horizontal(|ui| {
+                        cross_mod_changed |= ui
+                            .labelled("FM:", egui::Slider::new(&mut cross_mod.fm, 0.0..=1.0))
+                            .on_hover_text("Oscillator 2 modulates the frequency of oscillator 1")
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        cross_mod_changed |= ui
+                            .labelled("Ring:", egui::Slider::new(&mut cross_mod.ring, 0.0..=1.0))
+                            .on_hover_text("Blend towards the product of the two oscillators")
+                            .changed();
+                    });
+                    if cross_mod_changed {
+                        let cross_mod = CrossModParams::new(cross_mod.fm, cross_mod.ring);
+                        let cmd = Box::new(SetCrossModCommand::new(cross_mod));
+                        if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
+                            eprintln!("Failed to execute cross-mod command: {}", e);
+                        }
+                        self.mark_project_modified();
+                    }
+
+                    ui.add_space(10.0);
+                    ui.separator();
+
+                    // Unison: detuned voices stacked on each note
+                    ui.heading("Unison");
+                    let mut unison = self.daw_state.unison;
+                    let mut unison_changed = false;
+                    ui.horizontal(|ui| {
+                        unison_changed |= ui
+                            .labelled("Voices:", egui::Slider::new(&mut unison.voices, 1..=MAX_UNISON_VOICES))
+                            .on_hover_text("Voices per note (1: unison off); each one takes a polyphony slot")
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        unison_changed |= ui
+                            .labelled(
+                                "Detune:",
+                                egui::Slider::new(&mut unison.detune, 0.0..=MAX_UNISON_DETUNE).text("ct"),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        unison_changed |= ui
+                            .labelled("Spread:", egui::Slider::new(&mut unison.spread, 0.0..=1.0))
+                            .on_hover_text("Stereo width of the stacked voices")
+                            .changed();
+                    });
+                    if unison_changed {
+                        let unison = UnisonParams::new(unison.voices, unison.detune, unison.spread);
+                        let cmd = Box::new(SetUnisonCommand::new(unison));
+                        if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
+                            eprintln!("Failed to execute unison command: {}", e);
+                        }
+                        self.mark_project_modified();
+                    }
+
                     ui.add_space(10.0);
                     ui.separator();
 
@@ -2649,15 +6834,14 @@ impl eframe::App for DawApp {
                     ui.heading("ADSR Envelope");
 
                     ui.horizontal(|ui| {
-                        ui.label("Attack:");
-                        if ui
-                            .add(
-                                egui::Slider::new(&mut self.adsr_attack, 0.001..=2.0)
-                                    .text("s")
-                                    .logarithmic(true),
-                            )
-                            .changed()
-                        {
+                        let response = ui.labelled(
+                            "Attack:",
+                            egui::Slider::new(&mut self.adsr_attack, 0.001..=2.0)
+                                .text("s")
+                                .logarithmic(true),
+                        );
+                        midi_learn_menu(&response, &mut self.daw_state.cc_map, CcParameter::Attack);
+                        if response.changed() {
                             let params = AdsrParams::new(
                                 self.adsr_attack,
                                 self.adsr_decay,
@@ -2673,15 +6857,14 @@ impl eframe::App for DawApp {
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("Decay:");
-                        if ui
-                            .add(
-                                egui::Slider::new(&mut self.adsr_decay, 0.001..=2.0)
-                                    .text("s")
-                                    .logarithmic(true),
-                            )
-                            .changed()
-                        {
+                        let response = ui.labelled(
+                            "Decay:",
+                            egui::Slider::new(&mut self.adsr_decay, 0.001..=2.0)
+                                .text("s")
+                                .logarithmic(true),
+                        );
+                        midi_learn_menu(&response, &mut self.daw_state.cc_map, CcParameter::Decay);
+                        if response.changed() {
                             let params = AdsrParams::new(
                                 self.adsr_attack,
                                 self.adsr_decay,
@@ -2697,11 +6880,10 @@ impl eframe::App for DawApp {
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("Sustain:");
-                        if ui
-                            .add(egui::Slider::new(&mut self.adsr_sustain, 0.0..=1.0))
-                            .changed()
-                        {
+                        let response =
+                            ui.labelled("Sustain:", egui::Slider::new(&mut self.adsr_sustain, 0.0..=1.0));
+                        midi_learn_menu(&response, &mut self.daw_state.cc_map, CcParameter::Sustain);
+                        if response.changed() {
                             let params = AdsrParams::new(
                                 self.adsr_attack,
                                 self.adsr_decay,
@@ -2717,15 +6899,14 @@ impl eframe::App for DawApp {
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("Release:");
-                        if ui
-                            .add(
-                                egui::Slider::new(&mut self.adsr_release, 0.001..=5.0)
-                                    .text("s")
-                                    .logarithmic(true),
-                            )
-                            .changed()
-                        {
+                        let response = ui.labelled(
+                            "Release:",
+                            egui::Slider::new(&mut self.adsr_release, 0.001..=5.0)
+                                .text("s")
+                                .logarithmic(true),
+                        );
+                        midi_learn_menu(&response, &mut self.daw_state.cc_map, CcParameter::Release);
+                        if response.changed() {
                             let params = AdsrParams::new(
                                 self.adsr_attack,
                                 self.adsr_decay,
@@ -2747,7 +6928,7 @@ impl eframe::App for DawApp {
                     ui.heading("Polyphony Mode");
 
                     ui.horizontal(|ui| {
-                        ui.label("Mode:");
+                        let label = ui.label("Mode:");
                         let previous_mode = self.poly_mode;
                         egui::ComboBox::from_id_salt("poly_mode_selector")
                             .selected_text(match self.poly_mode {
@@ -2771,7 +6952,9 @@ impl eframe::App for DawApp {
                                     PolyMode::Legato,
                                     "Legato (Smooth pitch slide)",
                                 );
-                            });
+                            })
+                            .response
+                            .labelled_by(label.id);
 
                         if previous_mode != self.poly_mode {
                             let cmd = Box::new(SetPolyModeCommand::new(self.poly_mode));
@@ -2782,9 +6965,9 @@ impl eframe::App for DawApp {
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("Glide Time:");
                         if ui
-                            .add(
+                            .labelled(
+                                "Glide Time:",
                                 egui::Slider::new(&mut self.portamento_time, 0.0..=2.0)
                                     .text("s")
                                     .logarithmic(false),
@@ -2801,6 +6984,22 @@ impl eframe::App for DawApp {
                     ui.label("Set to 0 for instant pitch changes, >0 for smooth glides.");
                     ui.label("Works best in Mono/Legato modes.");
 
+                    ui.horizontal(|ui| {
+                        let mut range = self.daw_state.pitch_bend_range;
+                        if ui
+                            .labelled(
+                                "Bend Range:",
+                                egui::Slider::new(&mut range, 0.0..=MAX_PITCH_BEND_RANGE)
+                                    .step_by(1.0)
+                                    .text("st"),
+                            )
+                            .changed()
+                            && let Err(e) = self.daw_state.set_pitch_bend_range(range)
+                        {
+                            eprintln!("Failed to set pitch bend range: {}", e);
+                        }
+                    });
+
                     ui.add_space(10.0);
                     ui.separator();
 
@@ -2846,8 +7045,8 @@ impl eframe::App for DawApp {
 
                     // Filter type
                     ui.horizontal(|ui| {
-                        ui.label("Type:");
-                        let filter_type_changed = egui::ComboBox::from_id_salt("filter_type")
+                        let label = ui.label("Type:");
+                        let filter_type = egui::ComboBox::from_id_salt("filter_type")
                             .selected_text(format!("{:?}", filter_params.filter_type))
                             .show_ui(ui, |ui| {
                                 let mut changed = false;
@@ -2880,11 +7079,10 @@ impl eframe::App for DawApp {
                                     )
                                     .changed();
                                 changed
-                            })
-                            .inner
-                            .unwrap_or(false);
+                            });
+                        filter_type.response.labelled_by(label.id);
 
-                        if filter_type_changed {
+                        if filter_type.inner.unwrap_or(false) {
                             let cmd = Box::new(SetFilterCommand::new(filter_params));
                             let _ = self.command_manager.execute(cmd, &mut self.daw_state);
                         }
@@ -2892,15 +7090,14 @@ impl eframe::App for DawApp {
 
                     // Cutoff frequency
                     ui.horizontal(|ui| {
-                        ui.label("Cutoff:");
-                        if ui
-                            .add(
-                                egui::Slider::new(&mut filter_params.cutoff, 20.0..=10000.0)
-                                    .text("Hz")
-                                    .logarithmic(true),
-                            )
-                            .changed()
-                        {
+                        let response = ui.labelled(
+                            "Cutoff:",
+                            egui::Slider::new(&mut filter_params.cutoff, 20.0..=10000.0)
+                                .text("Hz")
+                                .logarithmic(true),
+                        );
+                        midi_learn_menu(&response, &mut self.daw_state.cc_map, CcParameter::FilterCutoff);
+                        if response.changed() {
                             let cmd = Box::new(SetFilterCommand::new(filter_params));
                             let _ = self.command_manager.execute(cmd, &mut self.daw_state);
                         }
@@ -2908,17 +7105,52 @@ impl eframe::App for DawApp {
 
                     // Resonance (Q factor)
                     ui.horizontal(|ui| {
-                        ui.label("Resonance (Q):");
-                        if ui
-                            .add(egui::Slider::new(&mut filter_params.resonance, 0.5..=20.0).logarithmic(true))
-                            .changed()
-                        {
+                        let response = ui.labelled(
+                            "Resonance (Q):",
+                            egui::Slider::new(&mut filter_params.resonance, 0.5..=20.0).logarithmic(true),
+                        );
+                        midi_learn_menu(&response, &mut self.daw_state.cc_map, CcParameter::FilterResonance);
+                        if response.changed() {
                             let cmd = Box::new(SetFilterCommand::new(filter_params));
                             let _ = self.command_manager.execute(cmd, &mut self.daw_state);
                         }
                     });
 
-                    ui.label("Cutoff can be modulated via the Modulation Matrix (Envelope → FilterCutoff).");
+                    // Filter envelope (the Mod Envelope), key tracking and input drive
+                    let mut filter_mod = self.daw_state.filter_mod;
+                    let mut filter_mod_changed = false;
+                    ui.horizontal(|ui| {
+                        filter_mod_changed |= ui
+                            .labelled(
+                                "Env Amount:",
+                                egui::Slider::new(&mut filter_mod.envelope_amount, -8.0..=8.0).text("oct"),
+                            )
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        filter_mod_changed |= ui
+                            .labelled("Key Tracking:", egui::Slider::new(&mut filter_mod.key_tracking, 0.0..=1.0))
+                            .changed();
+                    });
+                    ui.horizontal(|ui| {
+                        filter_mod_changed |= ui
+                            .labelled("Drive:", egui::Slider::new(&mut filter_mod.drive, 0.0..=1.0))
+                            .changed();
+                    });
+                    if filter_mod_changed {
+                        let filter_mod = FilterModParams::new(
+                            filter_mod.envelope_amount,
+                            filter_mod.key_tracking,
+                            filter_mod.drive,
+                        );
+                        let cmd = Box::new(SetFilterModCommand::new(filter_mod));
+                        if let Err(e) = self.command_manager.execute(cmd, &mut self.daw_state) {
+                            eprintln!("Failed to execute filter mod command: {}", e);
+                        }
+                        self.mark_project_modified();
+                    }
+
+                    ui.label("Env Amount opens the cutoff with the Mod Envelope (Modulation tab); the Modulation Matrix can add more.");
                 }
                 UiTab::Plugins => {
                     // Plugins tab - CLAP plugin management
@@ -3102,6 +7334,14 @@ impl eframe::App for DawApp {
                     ui.add_space(10.0);
                     ui.label("Info : Play with your computer keyboard or an external MIDI Keyboard");
                 }
+                UiTab::Script => {
+                    // Script tab: Rhai console over the active pattern
+                    self.draw_script_console(ui);
+                }
+                UiTab::Tuner => {
+                    // Tuner tab: pitch of the master output
+                    self.draw_tuner(ui);
+                }
                 UiTab::Performance => {
                     // Performance tab: CPU + notifications
                     ui.heading("Performance");
@@ -3122,11 +7362,110 @@ impl eframe::App for DawApp {
                             ui.colored_label(egui::Color32::RED, "⚠ High CPU load!");
                         }
         });
+                    ui.horizontal(|ui| {
+                        let xruns = self.cpu_monitor.xrun_stats();
+                        ui.label("Xruns:");
+                        let xrun_color = if xruns.total() == 0 {
+                            egui::Color32::GREEN
+                        } else {
+                            egui::Color32::RED
+                        };
+                        ui.colored_label(xrun_color, xruns.total().to_string());
+                        ui.label(format!(
+                            "(deadline overruns: {}, underruns: {})",
+                            xruns.deadline_overruns, xruns.underruns
+                        ));
+                        if ui.button("Reset").clicked() {
+                            self.cpu_monitor.reset_xruns();
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        let mut max_voices = self.daw_state.max_voices;
+                        let slider = egui::Slider::new(
+                            &mut max_voices,
+                            1..=crate::synth::voice_manager::MAX_VOICES,
+                        );
+                        if ui.labelled("Max voices:", slider).changed() {
+                            let _ = self.daw_state.set_max_voices(max_voices);
+                        }
+                        // The engine lowers the limit under sustained CPU load
+                        let voices = self.cpu_monitor.voice_limit();
+                        if voices.is_limited() {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 165, 0),
+                                format!("⚠ Limited to {} voices (CPU load)", voices.limit),
+                            );
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        use crate::audio::thread_priority::{self, PriorityStatus, ThreadKind};
+
+                        let mut realtime = thread_priority::is_enabled();
+                        if ui
+                            .checkbox(&mut realtime, "Real-time thread priority")
+                            .on_hover_text("Applies from the next audio engine start")
+                            .changed()
+                        {
+                            thread_priority::set_enabled(realtime);
+                        }
+                        for (name, kind) in [("Audio", ThreadKind::Audio), ("MIDI", ThreadKind::Midi)] {
+                            let status = thread_priority::status(kind);
+                            let text = format!("{}: {}", name, status);
+                            if matches!(status, PriorityStatus::Denied(_)) {
+                                ui.colored_label(egui::Color32::from_rgb(255, 165, 0), text);
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                    });
+                    ui.label(format!(
+                        "Internal processing: {}-bit float",
+                        crate::audio::precision::INTERNAL_PRECISION_BITS
+                    ));
     }
 
 
 }
 
+            // Offer to restore the session rescued after a crash
+            let mut rescue_choice = None;
+
+            if let Some(rescue) = &self.pending_rescue {
+                egui::Window::new("Session recovery")
+                    .collapsible(false)
+                    .resizable(false)
+                    .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                    .show(ctx, |ui| {
+                        ui.label(format!(
+                            "MyMusic DAW closed unexpectedly. \"{}\" was rescued on {}.",
+                            rescue.info.project_name,
+                            rescue.info.saved_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S")
+                        ));
+                        if let Some(report) = &rescue.report_file {
+                            ui.label(format!("Crash report: {}", report.display()));
+                        }
+                        ui.add_space(10.0);
+                        ui.horizontal(|ui| {
+                            if ui.button("Discard").clicked() {
+                                rescue_choice = Some(false);
+                            }
+                            if ui.button("Restore").clicked() {
+                                rescue_choice = Some(true);
+                            }
+                        });
+                    });
+            }
+
+            if let Some(restore) = rescue_choice
+                && let Some(rescue) = self.pending_rescue.take()
+            {
+                if restore {
+                    self.restore_rescue(rescue);
+                } else {
+                    crash::discard_rescue();
+                }
+            }
+
             // Show confirmation dialog if there's one
             let mut close_confirmation = false;
             let mut confirm_action = None;
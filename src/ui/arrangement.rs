@@ -0,0 +1,307 @@
+// Arrangement editor - Song timeline of pattern clips
+//
+// Rows are the tracks (the synth track, then the instrument tracks), columns
+// the bars of the song. Click an empty cell to place the selected pattern,
+// drag a clip to move it (to another bar or track), drag its right edge to
+// resize it and right-click it to remove it. Drags are made on a copy of the
+// arrangement and handed back once the gesture ends, so each one is a single
+// undo step.
+
+use crate::sequencer::{Arrangement, ClipId, Pattern, PatternClip, PatternId};
+use crate::ui::a11y;
+use eframe::egui;
+use egui::{Align2, Color32, FontId, Pos2, Rect, Sense, Stroke, Ui, Vec2};
+use std::sync::Arc;
+
+/// Size of a bar cell in pixels
+const BAR_WIDTH: f32 = 32.0;
+const ROW_HEIGHT: f32 = 26.0;
+
+/// Height of the bar numbers ruler
+const RULER_HEIGHT: f32 = 16.0;
+
+/// Width of the track names column
+const LABEL_WIDTH: f32 = 110.0;
+
+/// Width of the handle resizing a clip (right edge)
+const RESIZE_HANDLE: f32 = 6.0;
+
+/// Empty bars shown after the end of the song
+const EXTRA_BARS: u32 = 8;
+
+/// Bars shown at least
+const MIN_BARS: u32 = 32;
+
+/// Finished edit of the arrangement: description and every clip after it
+pub type ArrangementEdit = (&'static str, Arrangement);
+
+/// Clip being dragged
+#[derive(Debug, Clone, Copy)]
+struct ClipDrag {
+    id: ClipId,
+    /// Resizing (right edge) rather than moving
+    resize: bool,
+    /// Bar of the clip under the pointer when the drag started
+    grab_offset: i64,
+}
+
+/// Arrangement editor state
+#[derive(Default)]
+pub struct ArrangementEditor {
+    /// Pattern placed by a click
+    selected_pattern: Option<PatternId>,
+    /// Clip being dragged
+    drag: Option<ClipDrag>,
+    /// Arrangement being edited by a drag
+    pending: Option<Arrangement>,
+}
+
+impl ArrangementEditor {
+    /// Show the timeline of the arrangement
+    ///
+    /// `tracks` are the rows (id and name), `patterns` the patterns that can be
+    /// placed. Returns the edit once a gesture ends.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        arrangement: &Arrangement,
+        tracks: &[(u32, String)],
+        patterns: &[&Pattern],
+        playhead_bars: f64,
+    ) -> Option<ArrangementEdit> {
+        let mut edit = None;
+        if !patterns
+            .iter()
+            .any(|pattern| Some(pattern.id) == self.selected_pattern)
+        {
+            self.selected_pattern = patterns.first().map(|pattern| pattern.id);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Pattern:");
+            let name = patterns
+                .iter()
+                .find(|pattern| Some(pattern.id) == self.selected_pattern)
+                .map_or("None", |pattern| pattern.name.as_str());
+            egui::ComboBox::from_id_salt("arrangement_pattern")
+                .selected_text(name)
+                .show_ui(ui, |ui| {
+                    for pattern in patterns {
+                        ui.selectable_value(
+                            &mut self.selected_pattern,
+                            Some(pattern.id),
+                            &pattern.name,
+                        );
+                    }
+                });
+            ui.label("Click: place, drag: move, drag the edge: resize, right-click: remove");
+        });
+
+        let mut pending = self.pending.take();
+        let length_bars = pending.as_ref().unwrap_or(arrangement).length_bars();
+        let bar_count = (length_bars + EXTRA_BARS).max(MIN_BARS);
+
+        egui::ScrollArea::horizontal()
+            .id_salt("arrangement_scroll")
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                let (response, painter) = ui.allocate_painter(
+                    Vec2::new(
+                        LABEL_WIDTH + bar_count as f32 * BAR_WIDTH,
+                        RULER_HEIGHT + tracks.len() as f32 * ROW_HEIGHT,
+                    ),
+                    Sense::click_and_drag(),
+                );
+                let rect = response.rect;
+                let shown = pending.as_ref().unwrap_or(arrangement);
+                let grid_left = rect.left() + LABEL_WIDTH;
+                let grid_top = rect.top() + RULER_HEIGHT;
+                painter.rect_filled(rect, 0.0, Color32::from_gray(30));
+
+                // Ruler and bar lines
+                for bar in 0..bar_count {
+                    let x = grid_left + bar as f32 * BAR_WIDTH;
+                    painter.text(
+                        Pos2::new(x + 3.0, rect.top() + RULER_HEIGHT / 2.0),
+                        Align2::LEFT_CENTER,
+                        (bar + 1).to_string(),
+                        FontId::proportional(10.0),
+                        Color32::from_gray(160),
+                    );
+                    painter.line_segment(
+                        [Pos2::new(x, grid_top), Pos2::new(x, rect.bottom())],
+                        Stroke::new(1.0, Color32::from_gray(50)),
+                    );
+                }
+
+                for (row, (track, name)) in tracks.iter().enumerate() {
+                    let top = grid_top + row as f32 * ROW_HEIGHT;
+                    painter.text(
+                        Pos2::new(rect.left() + 4.0, top + ROW_HEIGHT / 2.0),
+                        Align2::LEFT_CENTER,
+                        name,
+                        FontId::proportional(12.0),
+                        Color32::from_gray(200),
+                    );
+                    painter.line_segment(
+                        [
+                            Pos2::new(rect.left(), top + ROW_HEIGHT),
+                            Pos2::new(rect.right(), top + ROW_HEIGHT),
+                        ],
+                        Stroke::new(1.0, Color32::from_gray(50)),
+                    );
+                    for clip in shown.track_clips(*track) {
+                        let clip_rect = Rect::from_min_size(
+                            Pos2::new(grid_left + clip.start_bar as f32 * BAR_WIDTH, top),
+                            Vec2::new(clip.length_bars as f32 * BAR_WIDTH, ROW_HEIGHT),
+                        )
+                        .shrink(2.0);
+                        painter.rect_filled(clip_rect, 3.0, pattern_color(clip.pattern_id()));
+                        if self.drag.is_some_and(|drag| drag.id == clip.id) {
+                            painter.rect_stroke(clip_rect, 3.0, Stroke::new(2.0, Color32::WHITE));
+                        }
+                        painter.with_clip_rect(clip_rect).text(
+                            Pos2::new(clip_rect.left() + 4.0, clip_rect.center().y),
+                            Align2::LEFT_CENTER,
+                            &clip.pattern.name,
+                            FontId::proportional(11.0),
+                            Color32::BLACK,
+                        );
+                    }
+                }
+
+                // Playhead
+                let x = grid_left + playhead_bars as f32 * BAR_WIDTH;
+                if x <= rect.right() {
+                    painter.line_segment(
+                        [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+                        Stroke::new(1.5, Color32::from_rgb(255, 80, 80)),
+                    );
+                }
+
+                // Cell under the pointer: (track, bar, x from the first bar)
+                let hit = response.interact_pointer_pos().and_then(|pos| {
+                    let row = ((pos.y - grid_top) / ROW_HEIGHT).floor();
+                    let bar = ((pos.x - grid_left) / BAR_WIDTH).floor();
+                    (row >= 0.0 && bar >= 0.0)
+                        .then(|| tracks.get(row as usize))
+                        .flatten()
+                        .map(|(track, _)| (*track, bar as u32, pos.x - grid_left))
+                });
+                let clip_at = |arrangement: &Arrangement, track: u32, bar: u32| {
+                    arrangement
+                        .track_clips(track)
+                        .iter()
+                        .find(|clip| clip.start_bar <= bar && bar < clip.end_bar())
+                        .map(|clip| (clip.id, clip.start_bar, clip.end_bar()))
+                };
+
+                if response.drag_started()
+                    && let Some((track, bar, x)) = hit
+                    && let Some((id, start_bar, end_bar)) = clip_at(arrangement, track, bar)
+                {
+                    self.drag = Some(ClipDrag {
+                        id,
+                        resize: x >= end_bar as f32 * BAR_WIDTH - RESIZE_HANDLE,
+                        grab_offset: bar as i64 - start_bar as i64,
+                    });
+                    pending = Some(arrangement.clone());
+                }
+                if let (Some(drag), Some(pending), Some((track, bar, _))) =
+                    (self.drag, pending.as_mut(), hit)
+                {
+                    // Invalid places (overlaps) keep the last valid one
+                    if drag.resize {
+                        if let Some(clip) = pending.get(drag.id) {
+                            let length = (bar + 1).saturating_sub(clip.start_bar);
+                            let _ = pending.resize_clip(drag.id, length);
+                        }
+                    } else {
+                        let start_bar = (bar as i64 - drag.grab_offset).max(0) as u32;
+                        let _ = pending.move_clip(drag.id, track, start_bar);
+                    }
+                }
+                if response.drag_stopped()
+                    && let Some(drag) = self.drag.take()
+                    && let Some(pending) = pending.take()
+                {
+                    let before = arrangement.get(drag.id);
+                    let after = pending.get(drag.id);
+                    let changed = match (before, after) {
+                        (Some(before), Some(after)) => {
+                            (before.track, before.start_bar, before.length_bars)
+                                != (after.track, after.start_bar, after.length_bars)
+                        }
+                        _ => false,
+                    };
+                    if changed {
+                        let description = if drag.resize {
+                            "Resize Clip"
+                        } else {
+                            "Move Clip"
+                        };
+                        edit = Some((description, pending));
+                    }
+                }
+
+                if response.clicked()
+                    && let Some((track, bar, _)) = hit
+                    && clip_at(arrangement, track, bar).is_none()
+                    && let Some(pattern) = patterns
+                        .iter()
+                        .find(|pattern| Some(pattern.id) == self.selected_pattern)
+                {
+                    // Clips of a pattern share it
+                    let shared = arrangement
+                        .clips()
+                        .iter()
+                        .find(|clip| clip.pattern_id() == pattern.id)
+                        .map(|clip| clip.pattern.clone())
+                        .unwrap_or_else(|| Arc::new((*pattern).clone()));
+                    let mut edited = arrangement.clone();
+                    let clip = PatternClip::new(track, bar, pattern.length_bars, shared);
+                    // A clip too long for the gap is shortened to fit
+                    let gap = edited
+                        .track_clips(track)
+                        .iter()
+                        .find(|other| other.start_bar > bar)
+                        .map_or(clip.length_bars, |other| other.start_bar - bar);
+                    let clip = PatternClip {
+                        length_bars: clip.length_bars.min(gap),
+                        ..clip
+                    };
+                    if edited.add_clip(clip).is_ok() {
+                        edit = Some(("Add Clip", edited));
+                    }
+                }
+                if response.secondary_clicked()
+                    && let Some((track, bar, _)) = hit
+                    && let Some((id, _, _)) = clip_at(arrangement, track, bar)
+                {
+                    let mut edited = arrangement.clone();
+                    edited.remove_clip(id);
+                    edit = Some(("Remove Clip", edited));
+                }
+
+                a11y::describe(
+                    &response,
+                    egui::accesskit::Role::Canvas,
+                    "Song arrangement",
+                    &format!(
+                        "{} clips, {} bars",
+                        arrangement.clips().len(),
+                        arrangement.length_bars()
+                    ),
+                );
+            });
+
+        self.pending = pending;
+        edit
+    }
+}
+
+/// Color of the clips of a pattern
+pub(crate) fn pattern_color(id: PatternId) -> Color32 {
+    let hue = (id.wrapping_mul(0x9E37_79B9) % 360) as f32 / 360.0;
+    egui::ecolor::Hsva::new(hue, 0.45, 0.85, 1.0).into()
+}
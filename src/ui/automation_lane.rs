@@ -0,0 +1,332 @@
+// Automation lane editor - Breakpoint editor drawn under the piano roll
+//
+// The lane follows the piano roll zoom and horizontal scroll so beats line up
+// with the notes. Click to add a point, drag a point to move it, right-click a
+// point to remove it. Edits are made on a copy of the points and handed back
+// once the gesture ends, so each one is a single undo step.
+
+use crate::automation::{AutomationLane, AutomationParameter, AutomationPoint, CurveShape};
+use crate::ui::a11y;
+use eframe::egui;
+use egui::{Color32, Pos2, Rect, Sense, Stroke, Ui, Vec2};
+use std::collections::BTreeMap;
+
+/// Height of the lane in pixels
+const LANE_HEIGHT: f32 = 96.0;
+
+/// Radius within which the pointer grabs a point
+const GRAB_RADIUS: f32 = 6.0;
+
+/// Grid of added and moved points (a sixteenth note)
+const SNAP_BEATS: f64 = 0.25;
+
+/// Finished edit of a lane: parameter, description and every point after it
+pub type LaneEdit = (AutomationParameter, &'static str, Vec<AutomationPoint>);
+
+/// Automation lane editor state
+pub struct AutomationLaneEditor {
+    /// Parameter shown in the lane
+    parameter: AutomationParameter,
+    /// Curve of added points (and of the selected point)
+    curve: CurveShape,
+    /// Point last clicked or moved (index in the lane)
+    selected: Option<usize>,
+    /// Points being dragged, with the index of the moved one
+    drag: Option<(Vec<AutomationPoint>, usize)>,
+}
+
+impl Default for AutomationLaneEditor {
+    fn default() -> Self {
+        Self {
+            parameter: AutomationParameter::FilterCutoff,
+            curve: CurveShape::Linear,
+            selected: None,
+            drag: None,
+        }
+    }
+}
+
+impl AutomationLaneEditor {
+    /// Show the lane of the selected parameter
+    ///
+    /// `length_beats` is the width of the piano roll, `pixels_per_beat` and
+    /// `scroll_x` its zoom and horizontal scroll. Returns the edit once a
+    /// gesture ends.
+    #[allow(clippy::too_many_arguments)]
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        lanes: &BTreeMap<AutomationParameter, AutomationLane>,
+        length_beats: f64,
+        beats_per_bar: f64,
+        playhead_beats: f64,
+        pixels_per_beat: f32,
+        scroll_x: f32,
+    ) -> Option<LaneEdit> {
+        let mut edit = None;
+        let stored = lanes
+            .get(&self.parameter)
+            .map(|lane| lane.points().to_vec())
+            .unwrap_or_default();
+
+        ui.horizontal(|ui| {
+            ui.label("Automation:");
+            let previous = self.parameter;
+            egui::ComboBox::from_id_salt("automation_parameter")
+                .selected_text(self.parameter.name())
+                .show_ui(ui, |ui| {
+                    for parameter in AutomationParameter::ALL {
+                        let automated = lanes.contains_key(&parameter);
+                        let name = if automated {
+                            format!("{} ●", parameter.name())
+                        } else {
+                            parameter.name().to_string()
+                        };
+                        ui.selectable_value(&mut self.parameter, parameter, name);
+                    }
+                });
+            if self.parameter != previous {
+                self.selected = None;
+                self.drag = None;
+            }
+
+            ui.label("Curve:");
+            let previous = self.curve;
+            egui::ComboBox::from_id_salt("automation_curve")
+                .selected_text(curve_name(self.curve))
+                .show_ui(ui, |ui| {
+                    for curve in [
+                        CurveShape::Linear,
+                        CurveShape::Step,
+                        CurveShape::Exponential,
+                    ] {
+                        ui.selectable_value(&mut self.curve, curve, curve_name(curve));
+                    }
+                });
+            if self.curve != previous
+                && let Some(index) = self.selected.filter(|index| *index < stored.len())
+            {
+                let mut points = stored.clone();
+                points[index].curve = self.curve;
+                edit = Some((self.parameter, "Change Automation Curve", points));
+            }
+
+            if ui
+                .add_enabled(!stored.is_empty(), egui::Button::new("Clear Lane"))
+                .clicked()
+            {
+                self.selected = None;
+                edit = Some((self.parameter, "Clear Automation", Vec::new()));
+            }
+        });
+
+        // Room for one more bar after the last point
+        let last_beat = stored.last().map_or(0.0, |point| point.time_beats);
+        let width_beats = length_beats.max(last_beat + beats_per_bar);
+
+        egui::ScrollArea::horizontal()
+            .id_salt("automation_lane_scroll")
+            .horizontal_scroll_offset(scroll_x)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                let (response, painter) = ui.allocate_painter(
+                    Vec2::new(width_beats as f32 * pixels_per_beat, LANE_HEIGHT),
+                    Sense::click_and_drag(),
+                );
+                let rect = response.rect;
+                let view = LaneView {
+                    rect,
+                    parameter: self.parameter,
+                    pixels_per_beat,
+                };
+                let points = match &self.drag {
+                    Some((points, _)) => points.clone(),
+                    None => stored.clone(),
+                };
+
+                view.draw_grid(&painter, width_beats, beats_per_bar);
+                view.draw_curve(&painter, &points);
+                for (index, point) in points.iter().enumerate() {
+                    let selected = self.selected == Some(index);
+                    let color = if selected {
+                        Color32::from_rgb(255, 200, 80)
+                    } else {
+                        Color32::from_rgb(120, 200, 255)
+                    };
+                    painter.circle_filled(view.point_pos(point), 4.0, color);
+                }
+                let x = view.beat_to_x(playhead_beats);
+                painter.line_segment(
+                    [Pos2::new(x, rect.top()), Pos2::new(x, rect.bottom())],
+                    Stroke::new(2.0, Color32::from_rgb(255, 100, 100)),
+                );
+
+                let pointer = response.interact_pointer_pos();
+                let hit_at = |pos: Pos2| {
+                    points
+                        .iter()
+                        .position(|point| view.point_pos(point).distance(pos) <= GRAB_RADIUS)
+                };
+                let hit = pointer.and_then(hit_at);
+
+                // A drag grabs the point under the press, not under the moved pointer
+                if response.drag_started()
+                    && let Some(index) = ui.input(|i| i.pointer.press_origin()).and_then(hit_at)
+                {
+                    self.drag = Some((points.clone(), index));
+                    self.selected = Some(index);
+                }
+                if response.dragged()
+                    && let (Some((dragged, index)), Some(pos)) = (&mut self.drag, pointer)
+                {
+                    let (time_beats, value) = view.pos_to_point(pos);
+                    dragged[*index].time_beats = time_beats;
+                    dragged[*index].value = value;
+                }
+                if response.drag_stopped()
+                    && let Some((dragged, _)) = self.drag.take()
+                {
+                    self.selected = None;
+                    edit = Some((self.parameter, "Move Automation Point", dragged));
+                }
+
+                if response.clicked()
+                    && let Some(pos) = pointer
+                {
+                    match hit {
+                        Some(index) => {
+                            self.selected = Some(index);
+                            self.curve = points[index].curve;
+                        }
+                        None => {
+                            let (time_beats, value) = view.pos_to_point(pos);
+                            let mut points = stored.clone();
+                            points.push(AutomationPoint {
+                                time_beats,
+                                value,
+                                curve: self.curve,
+                            });
+                            self.selected = None;
+                            edit = Some((self.parameter, "Add Automation Point", points));
+                        }
+                    }
+                }
+                if response.secondary_clicked()
+                    && let Some(index) = hit
+                {
+                    let mut points = stored.clone();
+                    points.remove(index);
+                    self.selected = None;
+                    edit = Some((self.parameter, "Remove Automation Point", points));
+                }
+
+                a11y::describe(
+                    &response,
+                    egui::accesskit::Role::Canvas,
+                    &format!("{} automation", self.parameter.name()),
+                    &format!("{} points", points.len()),
+                );
+            });
+
+        edit
+    }
+}
+
+/// Display name of a curve shape
+fn curve_name(curve: CurveShape) -> &'static str {
+    match curve {
+        CurveShape::Linear => "Linear",
+        CurveShape::Step => "Step",
+        CurveShape::Exponential => "Exponential",
+    }
+}
+
+/// Screen mapping of a lane (beats left to right, values bottom to top)
+struct LaneView {
+    rect: Rect,
+    parameter: AutomationParameter,
+    pixels_per_beat: f32,
+}
+
+impl LaneView {
+    fn beat_to_x(&self, beats: f64) -> f32 {
+        self.rect.left() + beats as f32 * self.pixels_per_beat
+    }
+
+    /// Position of a value in 0.0 - 1.0 (logarithmic for the filter cutoff)
+    fn normalize(&self, value: f32) -> f32 {
+        let (min, max) = self.parameter.range();
+        let unit = match self.parameter {
+            AutomationParameter::FilterCutoff => (value / min).ln() / (max / min).ln(),
+            _ => (value - min) / (max - min),
+        };
+        unit.clamp(0.0, 1.0)
+    }
+
+    fn denormalize(&self, unit: f32) -> f32 {
+        let (min, max) = self.parameter.range();
+        match self.parameter {
+            AutomationParameter::FilterCutoff => min * (max / min).powf(unit),
+            _ => min + (max - min) * unit,
+        }
+    }
+
+    fn point_pos(&self, point: &AutomationPoint) -> Pos2 {
+        Pos2::new(
+            self.beat_to_x(point.time_beats),
+            self.rect.bottom() - self.normalize(point.value) * self.rect.height(),
+        )
+    }
+
+    /// Snapped time and value under a screen position
+    fn pos_to_point(&self, pos: Pos2) -> (f64, f32) {
+        let beats = ((pos.x - self.rect.left()) / self.pixels_per_beat).max(0.0) as f64;
+        let time_beats = (beats / SNAP_BEATS).round() * SNAP_BEATS;
+        let unit = ((self.rect.bottom() - pos.y) / self.rect.height()).clamp(0.0, 1.0);
+        (time_beats, self.denormalize(unit))
+    }
+
+    fn draw_grid(&self, painter: &egui::Painter, width_beats: f64, beats_per_bar: f64) {
+        painter.rect_filled(self.rect, 0.0, Color32::from_gray(30));
+        let beats = width_beats.ceil() as usize;
+        for beat in 0..=beats {
+            let x = self.beat_to_x(beat as f64);
+            let bar_line = beats_per_bar > 0.0 && (beat as f64 % beats_per_bar) == 0.0;
+            let stroke = if bar_line {
+                (2.0, Color32::from_gray(80))
+            } else {
+                (1.0, Color32::from_gray(50))
+            };
+            painter.line_segment(
+                [
+                    Pos2::new(x, self.rect.top()),
+                    Pos2::new(x, self.rect.bottom()),
+                ],
+                stroke,
+            );
+        }
+    }
+
+    /// Curve sampled every few pixels (held before the first and after the last point)
+    fn draw_curve(&self, painter: &egui::Painter, points: &[AutomationPoint]) {
+        let mut lane = AutomationLane::new(self.parameter);
+        if points.is_empty() || lane.set_points(points.to_vec()).is_err() {
+            return;
+        }
+        let step = 3.0;
+        let mut line = Vec::with_capacity((self.rect.width() / step) as usize + 2);
+        let mut x = self.rect.left();
+        while x <= self.rect.right() {
+            let beats = ((x - self.rect.left()) / self.pixels_per_beat) as f64;
+            if let Some(value) = lane.value_at(beats) {
+                let y = self.rect.bottom() - self.normalize(value) * self.rect.height();
+                line.push(Pos2::new(x, y));
+            }
+            x += step;
+        }
+        painter.add(egui::Shape::line(
+            line,
+            Stroke::new(2.0, Color32::from_rgb(120, 200, 255)),
+        ));
+    }
+}
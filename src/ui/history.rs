@@ -0,0 +1,55 @@
+// History panel - Steps of the undo history
+//
+// Lists the commands of the `CommandManager`, oldest first, with the time they
+// were executed. Undone steps are greyed out and the current step is selected.
+// Clicking a step undoes or redoes every command up to it; "Initial state"
+// undoes the whole history.
+
+use crate::command::HistoryEntry;
+use eframe::egui;
+use egui::{RichText, Ui};
+
+/// Show the history list
+///
+/// Returns the number of commands to leave applied when a step is clicked
+/// (see `CommandManager::jump_to`).
+pub fn show(ui: &mut Ui, history: &[HistoryEntry]) -> Option<usize> {
+    let applied = history.iter().filter(|entry| entry.applied).count();
+    let mut jump = None;
+
+    ui.heading("History");
+    ui.label(format!(
+        "{} steps, {} undone",
+        history.len(),
+        history.len() - applied
+    ));
+    ui.separator();
+
+    egui::ScrollArea::vertical()
+        .id_salt("history_scroll")
+        .auto_shrink([false, false])
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            if ui
+                .selectable_label(applied == 0, "Initial state")
+                .on_hover_text("Undo every step")
+                .clicked()
+            {
+                jump = Some(0);
+            }
+            for (index, entry) in history.iter().enumerate() {
+                let time = entry
+                    .executed_at
+                    .with_timezone(&chrono::Local)
+                    .format("%H:%M:%S");
+                let mut text = RichText::new(format!("{}  {}", time, entry.description));
+                if !entry.applied {
+                    text = text.weak();
+                }
+                if ui.selectable_label(index + 1 == applied, text).clicked() {
+                    jump = Some(index + 1);
+                }
+            }
+        });
+    jump.filter(|index| *index != applied)
+}
@@ -1,4 +1,12 @@
 // Module UI - Interface utilisateur egui
 
+pub mod a11y;
+pub mod arrangement;
 pub mod app;
+pub mod automation_lane;
+pub mod history;
 pub mod piano_roll;
+pub mod preset_browser;
+pub mod session;
+pub mod step_sequencer;
+pub mod zone_editor;
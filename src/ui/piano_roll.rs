@@ -1,11 +1,20 @@
 // Piano Roll UI - MIDI note editor
 // Phase 4: Sequencer - MVP implementation
 
-use crate::sequencer::{Note, NoteId, Pattern, Position, Tempo, TimeSignature, generate_note_id};
+use crate::sequencer::chord::{self, chord_pitches, insert_chord};
+use crate::sequencer::scale::PITCH_CLASS_NAMES;
+use crate::sequencer::{
+    ChordQuality, KeyEstimate, Note, NoteId, Pattern, Position, Scale, ScaleKind, StrumDirection,
+    Tempo, TimeSignature, detect_key, generate_note_id,
+};
+use crate::ui::a11y;
 use eframe::egui;
 use egui::{Color32, Pos2, Rect, Response, Sense, Ui, Vec2};
 use std::collections::HashSet;
 
+/// Width (pixels) of the right edge of a note that resizes it when dragged
+const RESIZE_HANDLE_WIDTH: f32 = 6.0;
+
 /// Tool mode for piano roll interaction
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PianoRollTool {
@@ -17,6 +26,15 @@ pub enum PianoRollTool {
     Erase,
 }
 
+/// What dragging a note does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DragMode {
+    /// Move the note (start and pitch)
+    Move,
+    /// Move the end of the note (its right edge was grabbed)
+    Resize,
+}
+
 /// Piano roll editor state
 pub struct PianoRollEditor {
     /// Current tool mode
@@ -39,10 +57,29 @@ pub struct PianoRollEditor {
     is_dragging: bool,
     drag_start_pos: Option<Pos2>,
     drag_note_id: Option<NoteId>,
+    drag_mode: DragMode,
+    /// The velocity slider is being dragged (one undo step per drag)
+    velocity_dragging: bool,
 
     /// Snap to grid
     snap_enabled: bool,
     snap_subdivision: u16, // 1, 2, 4, 8, 16 (whole, half, quarter, eighth, sixteenth)
+
+    /// Scale highlighted in the grid (None: no highlight)
+    scale: Option<Scale>,
+    /// Keep drawn and moved notes in the scale
+    scale_lock: bool,
+    /// Last key detected from the pattern
+    detected_key: Option<KeyEstimate>,
+
+    /// Chord added by the Draw tool (None: single notes)
+    chord_quality: Option<ChordQuality>,
+    /// Delay between strummed notes in milliseconds
+    strum_ms: f32,
+    /// Name of the last edit for the undo history (None: a note edit)
+    edit_description: Option<&'static str>,
+    /// Horizontal scroll of the note area (followed by the automation lane)
+    scroll_x: f32,
 }
 
 impl Default for PianoRollEditor {
@@ -57,13 +94,53 @@ impl Default for PianoRollEditor {
             is_dragging: false,
             drag_start_pos: None,
             drag_note_id: None,
+            drag_mode: DragMode::Move,
+            velocity_dragging: false,
             snap_enabled: true,
             snap_subdivision: 4, // Quarter notes by default
+            scale: None,
+            scale_lock: false,
+            detected_key: None,
+            chord_quality: None,
+            strum_ms: 30.0,
+            edit_description: None,
+            scroll_x: 0.0,
         }
     }
 }
 
 impl PianoRollEditor {
+    /// IDs of the selected notes
+    pub fn selected_notes(&self) -> Vec<NoteId> {
+        self.selected_notes.iter().copied().collect()
+    }
+
+    /// Replace the selection (e.g. with generated notes)
+    pub fn select_notes(&mut self, ids: &[NoteId]) {
+        self.selected_notes = ids.iter().copied().collect();
+    }
+
+    /// Scale shown on the grid (None: chromatic)
+    pub fn scale(&self) -> Option<Scale> {
+        self.scale
+    }
+
+    /// Whether a note or the velocity slider is being dragged (the pattern is
+    /// edited until release)
+    pub fn is_dragging(&self) -> bool {
+        self.is_dragging || self.velocity_dragging
+    }
+
+    /// Horizontal zoom and scroll of the note area, to align other views
+    pub fn horizontal_view(&self) -> (f32, f32) {
+        (self.pixels_per_beat, self.scroll_x)
+    }
+
+    /// Name of the last edit (chord tools), to describe it in the history
+    pub fn take_edit_description(&mut self) -> Option<&'static str> {
+        self.edit_description.take()
+    }
+
     /// Show the piano roll UI
     ///
     /// Returns true if the pattern was modified (and needs to be sent to audio thread)
@@ -79,11 +156,18 @@ impl PianoRollEditor {
         let mut pattern_changed = false;
         // Toolbar
         self.show_toolbar(ui);
+        self.show_scale_toolbar(ui, pattern);
+        if self.show_chord_toolbar(ui, pattern, tempo, time_signature, sample_rate) {
+            pattern_changed = true;
+        }
+        if self.show_velocity_toolbar(ui, pattern) {
+            pattern_changed = true;
+        }
 
         ui.separator();
 
         // Main piano roll area (scrollable)
-        egui::ScrollArea::both()
+        let scroll = egui::ScrollArea::both()
             .auto_shrink([false, false])
             .show(ui, |ui| {
                 // Calculate dimensions
@@ -141,7 +225,19 @@ impl PianoRollEditor {
                 if changed {
                     pattern_changed = true;
                 }
+
+                a11y::describe(
+                    &response,
+                    egui::accesskit::Role::Canvas,
+                    "Piano roll",
+                    &format!(
+                        "{} notes, {} selected",
+                        pattern.note_count(),
+                        self.selected_notes.len()
+                    ),
+                );
             });
+        self.scroll_x = scroll.state.offset.x;
 
         pattern_changed
     }
@@ -185,6 +281,194 @@ impl PianoRollEditor {
         });
     }
 
+    /// Show the scale highlight and lock controls
+    fn show_scale_toolbar(&mut self, ui: &mut Ui, pattern: &Pattern) {
+        ui.horizontal(|ui| {
+            ui.label("Scale:");
+
+            let root_text = self
+                .scale
+                .map_or("Off", |scale| PITCH_CLASS_NAMES[scale.root as usize]);
+            egui::ComboBox::from_id_salt("scale_root")
+                .selected_text(root_text)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.scale, None, "Off");
+                    let kind = self.scale.map_or(ScaleKind::Major, |scale| scale.kind);
+                    for (root, name) in PITCH_CLASS_NAMES.iter().enumerate() {
+                        ui.selectable_value(
+                            &mut self.scale,
+                            Some(Scale::new(root as u8, kind)),
+                            *name,
+                        );
+                    }
+                });
+
+            if let Some(scale) = &mut self.scale {
+                egui::ComboBox::from_id_salt("scale_kind")
+                    .selected_text(scale.kind.name())
+                    .show_ui(ui, |ui| {
+                        for kind in ScaleKind::ALL {
+                            ui.selectable_value(&mut scale.kind, kind, kind.name());
+                        }
+                    });
+                ui.checkbox(&mut self.scale_lock, "Lock")
+                    .on_hover_text("Keep drawn and moved notes in the scale");
+            }
+
+            if ui
+                .button("Detect key")
+                .on_hover_text("Estimate the key from the pattern notes")
+                .clicked()
+            {
+                self.detected_key = detect_key(pattern.notes());
+                if let Some(key) = self.detected_key {
+                    self.scale = Some(key.scale);
+                }
+            }
+            match self.detected_key {
+                Some(key) => ui.label(format!(
+                    "Detected: {} ({:.0}% match)",
+                    key.scale.name(),
+                    key.confidence.max(0.0) * 100.0
+                )),
+                None => ui.label(""),
+            };
+        });
+    }
+
+    /// Show the chord palette and the tools editing the selected chord
+    ///
+    /// Returns true if the pattern was modified
+    fn show_chord_toolbar(
+        &mut self,
+        ui: &mut Ui,
+        pattern: &mut Pattern,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+        sample_rate: f64,
+    ) -> bool {
+        let ids = self.selected_notes();
+        let mut edit = None;
+        ui.horizontal(|ui| {
+            let label = ui.label("Chord:");
+            egui::ComboBox::from_id_salt("chord_quality")
+                .selected_text(self.chord_quality.map_or("Single note", ChordQuality::name))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.chord_quality, None, "Single note");
+                    for quality in ChordQuality::ALL {
+                        ui.selectable_value(&mut self.chord_quality, Some(quality), quality.name());
+                    }
+                })
+                .response
+                .labelled_by(label.id);
+
+            ui.separator();
+
+            ui.add_enabled_ui(ids.len() >= 2, |ui| {
+                if ui.button("Invert ↑").clicked() && chord::invert_chord(pattern, &ids, true) {
+                    edit = Some("Invert Chord");
+                }
+                if ui.button("Invert ↓").clicked() && chord::invert_chord(pattern, &ids, false) {
+                    edit = Some("Invert Chord");
+                }
+                if ui.button("Drop 2").clicked() && chord::drop2_voicing(pattern, &ids) {
+                    edit = Some("Drop 2 Voicing");
+                }
+
+                ui.separator();
+
+                let step_samples = (self.strum_ms as f64 * sample_rate / 1000.0) as u64;
+                for (label, direction) in [
+                    ("Strum ↑", StrumDirection::Up),
+                    ("Strum ↓", StrumDirection::Down),
+                ] {
+                    if ui
+                        .button(label)
+                        .on_hover_text("Stagger the starts of the selected chords")
+                        .clicked()
+                        && chord::strum(
+                            pattern,
+                            &ids,
+                            step_samples,
+                            direction,
+                            sample_rate,
+                            tempo,
+                            time_signature,
+                        )
+                    {
+                        edit = Some("Strum Chord");
+                    }
+                }
+                ui.add(
+                    egui::DragValue::new(&mut self.strum_ms)
+                        .range(1.0..=250.0)
+                        .suffix(" ms"),
+                );
+            });
+        });
+
+        if edit.is_some() {
+            self.edit_description = edit;
+        }
+        edit.is_some()
+    }
+
+    /// Show the velocity of the selected notes, editable with a slider
+    ///
+    /// Returns true once a velocity change is finished (slider released)
+    fn show_velocity_toolbar(&mut self, ui: &mut Ui, pattern: &mut Pattern) -> bool {
+        let selected: Vec<&Note> = pattern
+            .notes()
+            .iter()
+            .filter(|note| self.selected_notes.contains(&note.id))
+            .collect();
+        // Mixed velocities show their average until changed
+        let mut velocity = if selected.is_empty() {
+            100
+        } else {
+            (selected
+                .iter()
+                .map(|note| note.velocity as u32)
+                .sum::<u32>()
+                / selected.len() as u32) as u8
+        };
+        let enabled = !selected.is_empty();
+
+        let response = ui
+            .horizontal(|ui| {
+                let label = ui.label("Velocity:");
+                ui.add_enabled(enabled, egui::Slider::new(&mut velocity, 1..=127))
+                    .on_hover_text("Velocity of the selected notes")
+                    .labelled_by(label.id)
+            })
+            .inner;
+
+        if response.changed() {
+            for id in &self.selected_notes {
+                if let Some(note) = pattern.get_note_mut(*id) {
+                    note.velocity = velocity;
+                }
+            }
+        }
+
+        // While the slider is dragged the pattern is edited in place: the
+        // change is reported (one undo step) on release
+        self.velocity_dragging = response.dragged();
+        let finished = response.drag_stopped() || (response.changed() && !response.dragged());
+        if finished {
+            self.edit_description = Some("Change Velocity");
+        }
+        finished
+    }
+
+    /// Pitch of a drawn or moved note (snapped to the scale when locked)
+    fn scale_pitch(&self, pitch: u8) -> u8 {
+        match self.scale {
+            Some(scale) if self.scale_lock => scale.snap(pitch),
+            _ => pitch,
+        }
+    }
+
     /// Draw the background grid (bars, beats, subdivisions)
     fn draw_grid(
         &self,
@@ -198,6 +482,26 @@ impl PianoRollEditor {
         // Background
         painter.rect_filled(rect, 0.0, Color32::from_gray(30));
 
+        // Scale rows: out-of-scale notes dimmed, roots tinted
+        if let Some(scale) = self.scale {
+            for note_offset in 0..self.visible_note_count {
+                let note = self.visible_note_start + note_offset;
+                let color = if scale.is_root(note) {
+                    Color32::from_rgb(38, 44, 62)
+                } else if !scale.contains(note) {
+                    Color32::from_gray(20)
+                } else {
+                    continue;
+                };
+                let y_bottom = rect.bottom() - note_offset as f32 * self.pixels_per_note;
+                let row = Rect::from_min_max(
+                    Pos2::new(rect.left(), y_bottom - self.pixels_per_note),
+                    Pos2::new(rect.right(), y_bottom),
+                );
+                painter.rect_filled(row, 0.0, color);
+            }
+        }
+
         let beats_per_bar = time_signature.numerator as f32;
 
         // Draw vertical lines for beats and bars
@@ -254,10 +558,12 @@ impl PianoRollEditor {
             // Determine if this is a black or white key
             let is_black_key = matches!(note % 12, 1 | 3 | 6 | 8 | 10);
 
-            let key_color = if is_black_key {
-                Color32::from_gray(60)
-            } else {
-                Color32::from_gray(200)
+            let in_scale = self.scale.is_none_or(|scale| scale.contains(note));
+            let key_color = match (is_black_key, in_scale) {
+                (true, true) => Color32::from_gray(60),
+                (true, false) => Color32::from_gray(35),
+                (false, true) => Color32::from_gray(200),
+                (false, false) => Color32::from_gray(120),
             };
 
             let key_rect = Rect::from_min_max(
@@ -465,17 +771,57 @@ impl PianoRollEditor {
                     self.is_dragging = true;
                     self.drag_start_pos = Some(pos);
                     self.drag_note_id = Some(note.id);
+                    self.drag_mode = if self.on_note_end(pos, rect, note, tempo, sample_rate) {
+                        DragMode::Resize
+                    } else {
+                        DragMode::Move
+                    };
                     break;
                 }
             }
         }
 
+        // Hovering the end of a note shows it can be resized
+        if self.tool == PianoRollTool::Select
+            && let Some(pos) = response.hover_pos()
+        {
+            let pitch = self.screen_y_to_pitch(pos.y, rect);
+            let resizing = self.is_dragging && self.drag_mode == DragMode::Resize;
+            if resizing
+                || pattern.notes().iter().any(|note| {
+                    note.pitch == pitch && self.on_note_end(pos, rect, note, tempo, sample_rate)
+                })
+            {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::ResizeHorizontal);
+            }
+        }
+
         // Handle dragging
         if response.dragged()
             && self.is_dragging
             && let (Some(note_id), Some(current_pos)) =
                 (self.drag_note_id, response.interact_pointer_pos())
             && let Some(note) = pattern.get_note_mut(note_id)
+            && self.drag_mode == DragMode::Resize
+        {
+            // Move the end of the note, keeping at least one grid step
+            let end_beats = self.screen_x_to_beats(current_pos.x, rect);
+            let (end_beats, min_beats) = if self.snap_enabled {
+                (
+                    self.snap_to_grid(end_beats, time_signature),
+                    1.0 / self.snap_subdivision as f32,
+                )
+            } else {
+                (end_beats, 1.0 / 16.0)
+            };
+            let start_beats = self.samples_to_beats(note.start.samples, sample_rate, tempo);
+            note.duration_samples =
+                self.beats_to_samples((end_beats - start_beats).max(min_beats), sample_rate, tempo);
+        } else if response.dragged()
+            && self.is_dragging
+            && let (Some(note_id), Some(current_pos)) =
+                (self.drag_note_id, response.interact_pointer_pos())
+            && let Some(note) = pattern.get_note_mut(note_id)
         {
             // Calculate new position
             let new_pitch = self.screen_y_to_pitch(current_pos.y, rect);
@@ -494,7 +840,7 @@ impl PianoRollEditor {
             let new_position =
                 Position::from_samples(new_start_samples, sample_rate, tempo, time_signature);
 
-            note.pitch = new_pitch.clamp(0, 127);
+            note.pitch = self.scale_pitch(new_pitch.clamp(0, 127));
             note.start = new_position;
         }
 
@@ -502,6 +848,10 @@ impl PianoRollEditor {
         if response.drag_stopped() {
             if self.is_dragging {
                 pattern_changed = true; // Pattern was modified by dragging
+                self.edit_description = Some(match self.drag_mode {
+                    DragMode::Move => "Move Note",
+                    DragMode::Resize => "Resize Note",
+                });
             }
             self.is_dragging = false;
             self.drag_start_pos = None;
@@ -566,9 +916,50 @@ impl PianoRollEditor {
             }
         });
 
+        // Once the grid has the focus (Tab), arrows transpose the selection
+        // by a semitone, Shift+arrows by an octave
+        if response.has_focus() {
+            a11y::lock_arrows(response);
+            let (up, down, octave) = ui.input(|input| {
+                (
+                    input.key_pressed(egui::Key::ArrowUp),
+                    input.key_pressed(egui::Key::ArrowDown),
+                    input.modifiers.shift,
+                )
+            });
+            let semitones = (up as i32 - down as i32) * if octave { 12 } else { 1 };
+            if semitones != 0 && !self.selected_notes.is_empty() {
+                for id in &self.selected_notes {
+                    if let Some(note) = pattern.get_note_mut(*id) {
+                        note.pitch = (note.pitch as i32 + semitones).clamp(0, 127) as u8;
+                    }
+                }
+                self.edit_description = Some("Transpose Notes");
+                pattern_changed = true;
+            }
+        }
+
         pattern_changed
     }
 
+    /// Whether a position is on the right edge of a note (resize handle)
+    fn on_note_end(
+        &self,
+        pos: Pos2,
+        rect: Rect,
+        note: &Note,
+        tempo: &Tempo,
+        sample_rate: f64,
+    ) -> bool {
+        let end_beats = self.samples_to_beats(note.end_sample(), sample_rate, tempo);
+        let start_beats = self.samples_to_beats(note.start.samples, sample_rate, tempo);
+        let end_x = rect.left() + end_beats * self.pixels_per_beat;
+        let start_x = rect.left() + start_beats * self.pixels_per_beat;
+        // Short notes keep a grip to be moved by
+        let handle = RESIZE_HANDLE_WIDTH.min((end_x - start_x) / 3.0);
+        pos.x <= end_x && pos.x >= end_x - handle
+    }
+
     /// Add a note at the clicked position
     fn add_note_at_position(
         &mut self,
@@ -580,7 +971,7 @@ impl PianoRollEditor {
         sample_rate: f64,
     ) {
         // Convert screen position to MIDI note and time
-        let pitch = self.screen_y_to_pitch(pos.y, rect);
+        let pitch = self.scale_pitch(self.screen_y_to_pitch(pos.y, rect));
         let start_beats = self.screen_x_to_beats(pos.x, rect);
 
         // Snap to grid if enabled
@@ -601,6 +992,19 @@ impl PianoRollEditor {
         let start_position =
             Position::from_samples(start_samples, sample_rate, tempo, time_signature);
 
+        // Chord palette: the clicked note is the root (chord notes kept in the
+        // scale when it is locked), the new chord is selected for the chord tools
+        if let Some(quality) = self.chord_quality {
+            let pitches: Vec<u8> = chord_pitches(pitch, quality)
+                .into_iter()
+                .map(|pitch| self.scale_pitch(pitch))
+                .collect();
+            let ids = insert_chord(pattern, &pitches, start_position, duration_samples, 100);
+            self.selected_notes = ids.into_iter().collect();
+            self.edit_description = Some("Add Chord");
+            return;
+        }
+
         // Create note
         let note = Note::new(
             generate_note_id(),
@@ -719,7 +1123,7 @@ impl PianoRollEditor {
         (beats / subdivision_beats).round() * subdivision_beats
     }
 
-    fn get_note_name(midi_note: u8) -> String {
+    pub(crate) fn get_note_name(midi_note: u8) -> String {
         const NOTE_NAMES: [&str; 12] = [
             "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
         ];
@@ -0,0 +1,177 @@
+// Preset browser - Factory and user synth presets
+//
+// Lists the presets of the `PresetStore` (factory presets marked with ★),
+// filtered by name. Double-click a preset or press Load to apply it; the app
+// loads it as an undoable `LoadSynthPresetCommand`. Save stores the live synth
+// sound under a name (overwriting a user preset with the same name) and Delete
+// removes a user preset.
+
+use crate::command::state::DawState;
+use crate::project::{PresetEntry, PresetKind, PresetStore, SynthPreset};
+use eframe::egui;
+use egui::{Color32, RichText, Ui};
+
+/// Height of the preset list
+const LIST_HEIGHT: f32 = 160.0;
+
+/// Preset browser state
+pub struct PresetBrowser {
+    store: PresetStore,
+    /// Listed presets (refreshed after each save or delete)
+    entries: Option<Vec<PresetEntry>>,
+    filter: String,
+    selected: Option<String>,
+    save_name: String,
+    /// Result of the last action (message, is error)
+    status: Option<(String, bool)>,
+}
+
+impl Default for PresetBrowser {
+    fn default() -> Self {
+        Self::new(PresetStore::default())
+    }
+}
+
+impl PresetBrowser {
+    pub fn new(store: PresetStore) -> Self {
+        Self {
+            store,
+            entries: None,
+            filter: String::new(),
+            selected: None,
+            save_name: String::new(),
+            status: None,
+        }
+    }
+
+    fn entries(&mut self) -> &[PresetEntry] {
+        if self.entries.is_none() {
+            let entries = self.store.browse_synth().unwrap_or_else(|e| {
+                self.status = Some((e, true));
+                Vec::new()
+            });
+            self.entries = Some(entries);
+        }
+        self.entries.as_deref().unwrap_or_default()
+    }
+
+    /// Show the browser
+    ///
+    /// Returns the preset to load.
+    pub fn show(&mut self, ui: &mut Ui, state: &DawState) -> Option<SynthPreset> {
+        let mut load = None;
+
+        ui.horizontal(|ui| {
+            ui.label("Search:");
+            ui.text_edit_singleline(&mut self.filter);
+            if ui
+                .button("⟳")
+                .on_hover_text("Rescan the preset folder")
+                .clicked()
+            {
+                self.entries = None;
+            }
+        });
+
+        let filter = self.filter.to_lowercase();
+        let entries: Vec<PresetEntry> = self
+            .entries()
+            .iter()
+            .filter(|entry| entry.name.to_lowercase().contains(&filter))
+            .cloned()
+            .collect();
+        egui::ScrollArea::vertical()
+            .id_salt("preset_list")
+            .max_height(LIST_HEIGHT)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for entry in &entries {
+                    let selected = self.selected.as_ref() == Some(&entry.name);
+                    let text = if entry.factory {
+                        format!("★ {}", entry.name)
+                    } else {
+                        entry.name.clone()
+                    };
+                    let response = ui.selectable_label(selected, text);
+                    if response.clicked() {
+                        self.selected = Some(entry.name.clone());
+                        self.save_name = entry.name.clone();
+                    }
+                    if response.double_clicked() {
+                        load = self.load(&entry.name);
+                    }
+                }
+                if entries.is_empty() {
+                    ui.weak("No preset");
+                }
+            });
+
+        let selected = self
+            .selected
+            .as_ref()
+            .and_then(|name| entries.iter().find(|entry| &entry.name == name))
+            .cloned();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(selected.is_some(), egui::Button::new("Load"))
+                .clicked()
+                && let Some(entry) = &selected
+            {
+                load = self.load(&entry.name);
+            }
+            let deletable = selected.as_ref().is_some_and(|entry| !entry.factory);
+            if ui
+                .add_enabled(deletable, egui::Button::new("Delete"))
+                .on_disabled_hover_text("Factory presets cannot be deleted")
+                .clicked()
+                && let Some(entry) = &selected
+            {
+                self.status = Some(match self.store.delete(PresetKind::Synth, &entry.name) {
+                    Ok(()) => (format!("Deleted '{}'", entry.name), false),
+                    Err(e) => (e, true),
+                });
+                self.selected = None;
+                self.entries = None;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Name:");
+            ui.text_edit_singleline(&mut self.save_name);
+            if ui.button("Save").clicked() {
+                let preset = SynthPreset::from_state(self.save_name.clone(), state);
+                self.status = Some(match self.store.save_synth(&preset) {
+                    Ok(_) => {
+                        self.selected = Some(preset.name.clone());
+                        (format!("Saved '{}'", preset.name), false)
+                    }
+                    Err(e) => (e, true),
+                });
+                self.entries = None;
+            }
+        });
+
+        if let Some((message, error)) = &self.status {
+            let color = if *error {
+                Color32::from_rgb(255, 100, 100)
+            } else {
+                ui.visuals().weak_text_color()
+            };
+            ui.label(RichText::new(message).color(color));
+        }
+        load
+    }
+
+    fn load(&mut self, name: &str) -> Option<SynthPreset> {
+        match self.store.load_synth(name) {
+            Ok(preset) => {
+                self.status = Some((format!("Loaded '{}'", name), false));
+                Some(preset)
+            }
+            Err(e) => {
+                self.status = Some((e, true));
+                None
+            }
+        }
+    }
+}
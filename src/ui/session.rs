@@ -0,0 +1,245 @@
+// Session view - Clip launcher grid
+//
+// Columns are the tracks (the synth track, then the instrument tracks), rows
+// the scenes. Click a slot to launch it, click `+` in an empty slot to put the
+// selected pattern there and right-click a slot to empty it. Launches wait for
+// the quantization boundary: a queued slot is outlined until it plays. The
+// stop row stops a track, the scene column launches a whole row.
+
+use crate::sequencer::{LaunchQuantization, Pattern, PatternId, Session, TrackLaunch};
+use crate::ui::arrangement::pattern_color;
+use eframe::egui;
+use egui::{Color32, RichText, Stroke, Ui, Vec2};
+use std::sync::Arc;
+
+/// Size of a slot button
+const SLOT_SIZE: Vec2 = Vec2::new(96.0, 22.0);
+
+/// Playing and queued slots
+const PLAYING_COLOR: Color32 = Color32::from_rgb(90, 200, 90);
+const QUEUED_COLOR: Color32 = Color32::from_rgb(255, 200, 60);
+
+/// Gesture of the session view
+#[derive(Debug)]
+pub enum SessionAction {
+    /// Edit of the grid: description and the whole grid after it
+    Edit(&'static str, Session),
+    /// Launch a slot (`scene` None stops the track)
+    Launch { track: u32, scene: Option<u32> },
+    /// Launch every slot of a scene
+    LaunchScene(u32),
+    /// Stop every track playing in the session
+    StopAll,
+    /// Hand the tracks back to their patterns or the arrangement
+    Release,
+}
+
+/// Session view state
+#[derive(Default)]
+pub struct SessionEditor {
+    /// Pattern put in a slot by `+`
+    selected_pattern: Option<PatternId>,
+}
+
+impl SessionEditor {
+    /// Show the clip grid
+    ///
+    /// `launches` is the launch state of the tracks (see
+    /// `DawState::session_launches`), `tracks` the columns (id and name) and
+    /// `patterns` the patterns that can fill a slot.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        session: &Session,
+        launches: &[TrackLaunch],
+        tracks: &[(u32, String)],
+        patterns: &[&Pattern],
+    ) -> Option<SessionAction> {
+        let mut action = None;
+        if !patterns
+            .iter()
+            .any(|pattern| Some(pattern.id) == self.selected_pattern)
+        {
+            self.selected_pattern = patterns.first().map(|pattern| pattern.id);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Pattern:");
+            let name = patterns
+                .iter()
+                .find(|pattern| Some(pattern.id) == self.selected_pattern)
+                .map_or("None", |pattern| pattern.name.as_str());
+            egui::ComboBox::from_id_salt("session_pattern")
+                .selected_text(name)
+                .show_ui(ui, |ui| {
+                    for pattern in patterns {
+                        ui.selectable_value(
+                            &mut self.selected_pattern,
+                            Some(pattern.id),
+                            &pattern.name,
+                        );
+                    }
+                });
+
+            ui.label("Launch:");
+            let mut quantization = session.quantization;
+            egui::ComboBox::from_id_salt("session_quantization")
+                .selected_text(quantization.name())
+                .show_ui(ui, |ui| {
+                    for option in LaunchQuantization::ALL {
+                        ui.selectable_value(&mut quantization, option, option.name());
+                    }
+                });
+            if quantization != session.quantization {
+                let mut edited = session.clone();
+                edited.quantization = quantization;
+                action = Some(SessionAction::Edit("Change Launch Quantization", edited));
+            }
+
+            if ui.button("■ Stop All").clicked() {
+                action = Some(SessionAction::StopAll);
+            }
+            if ui
+                .add_enabled(
+                    !launches.is_empty(),
+                    egui::Button::new("Back to Arrangement"),
+                )
+                .on_hover_text("Play the patterns (or the song clips) again")
+                .clicked()
+            {
+                action = Some(SessionAction::Release);
+            }
+        });
+
+        let launch_of = |track: u32| launches.iter().find(|launch| launch.track == track);
+        egui::ScrollArea::horizontal()
+            .id_salt("session_scroll")
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                egui::Grid::new("session_grid")
+                    .spacing(Vec2::new(4.0, 4.0))
+                    .show(ui, |ui| {
+                        for (_, name) in tracks {
+                            ui.label(RichText::new(name).strong());
+                        }
+                        ui.label("Scene");
+                        ui.end_row();
+
+                        for scene in 0..session.scene_count() {
+                            for (track, _) in tracks {
+                                let launch = launch_of(*track);
+                                if let Some(edit) =
+                                    self.show_slot(ui, session, launch, *track, scene, patterns)
+                                {
+                                    action = Some(edit);
+                                }
+                            }
+                            if ui
+                                .add_sized(SLOT_SIZE, egui::Button::new(format!("▶ {}", scene + 1)))
+                                .on_hover_text("Launch the scene")
+                                .clicked()
+                            {
+                                action = Some(SessionAction::LaunchScene(scene));
+                            }
+                            ui.end_row();
+                        }
+
+                        for (track, name) in tracks {
+                            let launch = launch_of(*track);
+                            let stopping = launch
+                                .is_some_and(|launch| matches!(launch.queued, Some((None, _))));
+                            let mut button = egui::Button::new("■");
+                            if stopping {
+                                button = button.stroke(Stroke::new(2.0, QUEUED_COLOR));
+                            }
+                            if ui
+                                .add_sized(SLOT_SIZE, button)
+                                .on_hover_text(format!("Stop {}", name))
+                                .clicked()
+                            {
+                                action = Some(SessionAction::Launch {
+                                    track: *track,
+                                    scene: None,
+                                });
+                            }
+                        }
+                        ui.end_row();
+                    });
+            });
+        action
+    }
+
+    /// Button of a slot: launch when filled, fill with the selected pattern
+    /// when empty
+    fn show_slot(
+        &self,
+        ui: &mut Ui,
+        session: &Session,
+        launch: Option<&TrackLaunch>,
+        track: u32,
+        scene: u32,
+        patterns: &[&Pattern],
+    ) -> Option<SessionAction> {
+        let Some(slot) = session.get(track, scene) else {
+            let selected = patterns
+                .iter()
+                .find(|pattern| Some(pattern.id) == self.selected_pattern);
+            // Without patterns the cell stays in the grid, disabled
+            let response = ui.add_enabled_ui(selected.is_some(), |ui| {
+                ui.add_sized(SLOT_SIZE, egui::Button::new("+"))
+            });
+            let pattern = selected?;
+            let clicked = response
+                .inner
+                .on_hover_text(format!("Put {} here", pattern.name))
+                .clicked();
+            if !clicked {
+                return None;
+            }
+            // Slots of a pattern share it
+            let shared = session
+                .slots()
+                .iter()
+                .find(|slot| slot.pattern.id == pattern.id)
+                .map(|slot| slot.pattern.clone())
+                .unwrap_or_else(|| Arc::new((*pattern).clone()));
+            let mut edited = session.clone();
+            edited.set_slot(track, scene, shared);
+            return Some(SessionAction::Edit("Fill Slot", edited));
+        };
+
+        let playing = launch
+            .and_then(|launch| launch.playing)
+            .is_some_and(|(playing, _)| playing == scene);
+        let queued = launch
+            .and_then(|launch| launch.queued)
+            .is_some_and(|(next, _)| next == Some(scene));
+        let fill = if playing {
+            PLAYING_COLOR
+        } else {
+            pattern_color(slot.pattern.id)
+        };
+        let mut button = egui::Button::new(
+            RichText::new(format!("▶ {}", slot.pattern.name)).color(Color32::BLACK),
+        )
+        .fill(fill);
+        if queued {
+            button = button.stroke(Stroke::new(2.0, QUEUED_COLOR));
+        }
+        let response = ui
+            .add_sized(SLOT_SIZE, button)
+            .on_hover_text("Click: launch, right-click: empty the slot");
+        if response.clicked() {
+            return Some(SessionAction::Launch {
+                track,
+                scene: Some(scene),
+            });
+        }
+        if response.secondary_clicked() {
+            let mut edited = session.clone();
+            edited.remove_slot(track, scene);
+            return Some(SessionAction::Edit("Empty Slot", edited));
+        }
+        None
+    }
+}
@@ -0,0 +1,347 @@
+// Step sequencer - Drum-machine grid editing the synth pattern
+//
+// Rows are pitches (a General MIDI drum kit, plus every other pitch found in
+// the pattern), columns the steps of the pattern. A note sits on the step its
+// start is closest to. Click a step to toggle it, right-click a lit step to
+// select it: its velocity, probability and micro-timing offset are edited under
+// the grid. Edits are made on a copy of the notes and handed back once the
+// gesture ends, so each one is a single undo step.
+
+use crate::sequencer::note::MAX_OFFSET_TICKS;
+use crate::sequencer::{Note, NoteId, Pattern, StepGrid, Tempo, TimeSignature, generate_note_id};
+use crate::ui::a11y;
+use eframe::egui;
+use egui::{Align2, Color32, FontId, Pos2, Rect, Sense, Stroke, Ui, Vec2};
+use std::collections::BTreeMap;
+
+/// Size of a step cell in pixels
+const CELL_WIDTH: f32 = 22.0;
+const CELL_HEIGHT: f32 = 18.0;
+
+/// Width of the row names column
+const LABEL_WIDTH: f32 = 84.0;
+
+/// Velocity of added steps
+const DEFAULT_VELOCITY: u8 = 100;
+
+/// Drum rows, top to bottom (General MIDI percussion)
+const DRUM_ROWS: [(u8, &str); 8] = [
+    (49, "Crash"),
+    (46, "Open Hat"),
+    (42, "Closed Hat"),
+    (50, "High Tom"),
+    (45, "Low Tom"),
+    (39, "Clap"),
+    (38, "Snare"),
+    (36, "Kick"),
+];
+
+/// Step resolutions (steps per beat and name)
+const RESOLUTIONS: [(u32, &str); 5] = [
+    (2, "1/8"),
+    (3, "1/8T"),
+    (4, "1/16"),
+    (6, "1/16T"),
+    (8, "1/32"),
+];
+
+/// Finished edit of the pattern: description and every note after it
+pub type StepEdit = (&'static str, Vec<Note>);
+
+/// Step sequencer state
+pub struct StepSequencerEditor {
+    /// Steps per beat of the grid
+    steps_per_beat: u32,
+    /// Note whose settings are edited under the grid
+    selected: Option<NoteId>,
+    /// Notes being edited by a slider drag
+    pending: Option<Vec<Note>>,
+}
+
+impl Default for StepSequencerEditor {
+    fn default() -> Self {
+        Self {
+            steps_per_beat: 4,
+            selected: None,
+            pending: None,
+        }
+    }
+}
+
+impl StepSequencerEditor {
+    /// Show the grid of the pattern and the settings of the selected step
+    ///
+    /// Returns the edit once a gesture ends.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        pattern: &Pattern,
+        tempo: &Tempo,
+        time_signature: &TimeSignature,
+        sample_rate: f64,
+        playhead_samples: u64,
+    ) -> Option<StepEdit> {
+        let mut edit = None;
+        let grid = StepGrid::new(
+            pattern,
+            self.steps_per_beat,
+            sample_rate,
+            *tempo,
+            *time_signature,
+        );
+        let step_count = grid.step_count();
+        let notes = match &self.pending {
+            Some(notes) => notes.clone(),
+            None => pattern.notes().to_vec(),
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Resolution:");
+            let name = RESOLUTIONS
+                .iter()
+                .find(|(steps, _)| *steps == self.steps_per_beat)
+                .map_or("?", |(_, name)| name);
+            egui::ComboBox::from_id_salt("step_sequencer_resolution")
+                .selected_text(name)
+                .show_ui(ui, |ui| {
+                    for (steps, name) in RESOLUTIONS {
+                        ui.selectable_value(&mut self.steps_per_beat, steps, name);
+                    }
+                });
+            ui.label("Click: toggle a step, right-click: select it");
+        });
+
+        // Rows: pitches outside the drum kit first (highest on top)
+        let mut rows: Vec<(u8, String)> = Vec::new();
+        for note in &notes {
+            let known = DRUM_ROWS.iter().any(|(pitch, _)| *pitch == note.pitch)
+                || rows.iter().any(|(pitch, _)| *pitch == note.pitch);
+            if !known {
+                rows.push((note.pitch, note.note_name()));
+            }
+        }
+        rows.sort_by_key(|(pitch, _)| std::cmp::Reverse(*pitch));
+        rows.extend(
+            DRUM_ROWS
+                .iter()
+                .map(|(pitch, name)| (*pitch, name.to_string())),
+        );
+
+        // Lit steps: (pitch, step) -> note
+        let step_samples = grid.step_samples();
+        let mut cells: BTreeMap<(u8, usize), Note> = BTreeMap::new();
+        for note in &notes {
+            let step = (note.start.samples as f64 / step_samples).round() as usize;
+            if step < step_count {
+                cells.entry((note.pitch, step)).or_insert(*note);
+            }
+        }
+        let playhead_step = (step_samples > 0.0 && grid.length_samples > 0)
+            .then(|| ((playhead_samples % grid.length_samples) as f64 / step_samples) as usize);
+
+        egui::ScrollArea::horizontal()
+            .id_salt("step_sequencer_scroll")
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                let (response, painter) = ui.allocate_painter(
+                    Vec2::new(
+                        LABEL_WIDTH + step_count as f32 * CELL_WIDTH,
+                        rows.len() as f32 * CELL_HEIGHT,
+                    ),
+                    Sense::click(),
+                );
+                let rect = response.rect;
+                painter.rect_filled(rect, 0.0, Color32::from_gray(30));
+                let cell_rect = |row: usize, step: usize| {
+                    Rect::from_min_size(
+                        Pos2::new(
+                            rect.left() + LABEL_WIDTH + step as f32 * CELL_WIDTH,
+                            rect.top() + row as f32 * CELL_HEIGHT,
+                        ),
+                        Vec2::new(CELL_WIDTH, CELL_HEIGHT),
+                    )
+                    .shrink(1.0)
+                };
+
+                for (row, (pitch, name)) in rows.iter().enumerate() {
+                    painter.text(
+                        Pos2::new(
+                            rect.left() + 4.0,
+                            rect.top() + (row as f32 + 0.5) * CELL_HEIGHT,
+                        ),
+                        Align2::LEFT_CENTER,
+                        name,
+                        FontId::proportional(12.0),
+                        Color32::from_gray(200),
+                    );
+                    for step in 0..step_count {
+                        let cell = cell_rect(row, step);
+                        // Every other beat is shaded, the playing step highlighted
+                        let beat = step / self.steps_per_beat as usize;
+                        let background = if playhead_step == Some(step) {
+                            Color32::from_gray(85)
+                        } else if beat.is_multiple_of(2) {
+                            Color32::from_gray(55)
+                        } else {
+                            Color32::from_gray(45)
+                        };
+                        painter.rect_filled(cell, 2.0, background);
+
+                        let Some(note) = cells.get(&(*pitch, step)) else {
+                            continue;
+                        };
+                        let strength = 0.35 + 0.65 * note.velocity as f32 / 127.0;
+                        painter.rect_filled(
+                            cell,
+                            2.0,
+                            Color32::from_rgb(255, 160, 60).gamma_multiply(strength),
+                        );
+                        // Probability: the unlikely part of the cell is darkened
+                        if note.probability < 1.0 {
+                            let mut shade = cell;
+                            shade.set_bottom(cell.top() + cell.height() * (1.0 - note.probability));
+                            painter.rect_filled(shade, 2.0, Color32::from_black_alpha(150));
+                        }
+                        // Micro-timing: a tick left (early) or right (late) of the center
+                        if note.offset_ticks != 0 {
+                            let shift = note.offset_ticks as f32 / MAX_OFFSET_TICKS as f32;
+                            let x = cell.center().x + shift * cell.width() / 2.0;
+                            painter.line_segment(
+                                [
+                                    Pos2::new(x, cell.bottom() - 5.0),
+                                    Pos2::new(x, cell.bottom()),
+                                ],
+                                Stroke::new(2.0, Color32::WHITE),
+                            );
+                        }
+                        if self.selected == Some(note.id) {
+                            painter.rect_stroke(
+                                cell,
+                                2.0,
+                                Stroke::new(2.0, Color32::from_rgb(255, 230, 80)),
+                            );
+                        }
+                    }
+                }
+
+                let hit = response.interact_pointer_pos().and_then(|pos| {
+                    let row = ((pos.y - rect.top()) / CELL_HEIGHT).floor();
+                    let step = ((pos.x - rect.left() - LABEL_WIDTH) / CELL_WIDTH).floor();
+                    (row >= 0.0 && step >= 0.0 && (step as usize) < step_count)
+                        .then(|| {
+                            rows.get(row as usize)
+                                .map(|(pitch, _)| (*pitch, step as usize))
+                        })
+                        .flatten()
+                });
+                if response.clicked()
+                    && let Some((pitch, step)) = hit
+                {
+                    let mut edited = notes.clone();
+                    match cells.get(&(pitch, step)) {
+                        Some(note) => {
+                            edited.retain(|other| other.id != note.id);
+                            edit = Some(("Remove Step", edited));
+                        }
+                        None => {
+                            let note = Note::new(
+                                generate_note_id(),
+                                pitch,
+                                grid.position(grid.step_start(step)),
+                                (step_samples.round() as u64).max(1),
+                                DEFAULT_VELOCITY,
+                            );
+                            self.selected = Some(note.id);
+                            edited.push(note);
+                            edit = Some(("Add Step", edited));
+                        }
+                    }
+                }
+                if response.secondary_clicked()
+                    && let Some(note) = hit.and_then(|cell| cells.get(&cell))
+                {
+                    self.selected = Some(note.id);
+                }
+
+                a11y::describe(
+                    &response,
+                    egui::accesskit::Role::Canvas,
+                    "Step sequencer",
+                    &format!("{} of {} steps lit", cells.len(), step_count),
+                );
+            });
+
+        if let Some(edited) = self.show_step_settings(ui, &notes) {
+            edit = Some(edited);
+        }
+        edit
+    }
+
+    /// Sliders of the selected step
+    fn show_step_settings(&mut self, ui: &mut Ui, notes: &[Note]) -> Option<StepEdit> {
+        let Some(index) = self
+            .selected
+            .and_then(|id| notes.iter().position(|note| note.id == id))
+        else {
+            self.selected = None;
+            self.pending = None;
+            return None;
+        };
+        let mut note = notes[index];
+
+        ui.horizontal(|ui| {
+            let mut edit = None;
+            ui.label(format!("Step {}:", note.note_name()));
+
+            let mut velocity = note.velocity;
+            let response = ui.add(egui::Slider::new(&mut velocity, 1..=127).text("Velocity"));
+            note.velocity = velocity;
+            edit = edit.or(self.slider_edit(&response, notes, note, "Change Step Velocity"));
+
+            let mut percent = note.probability * 100.0;
+            let response = ui.add(
+                egui::Slider::new(&mut percent, 0.0..=100.0)
+                    .text("Probability")
+                    .suffix(" %")
+                    .fixed_decimals(0),
+            );
+            note.probability = percent / 100.0;
+            edit = edit.or(self.slider_edit(&response, notes, note, "Change Step Probability"));
+
+            let response = ui
+                .add(
+                    egui::Slider::new(&mut note.offset_ticks, -MAX_OFFSET_TICKS..=MAX_OFFSET_TICKS)
+                        .text("Offset")
+                        .suffix(" ticks"),
+                )
+                .on_hover_text("Micro-timing: negative plays early, positive late");
+            edit = edit.or(self.slider_edit(&response, notes, note, "Change Step Timing"));
+            edit
+        })
+        .inner
+    }
+
+    /// Keep a slider change on a copy of the notes, handing it back once the
+    /// drag (or keyboard step) ends
+    fn slider_edit(
+        &mut self,
+        response: &egui::Response,
+        notes: &[Note],
+        note: Note,
+        description: &'static str,
+    ) -> Option<StepEdit> {
+        if response.changed() {
+            let mut edited = notes.to_vec();
+            if let Some(slot) = edited.iter_mut().find(|other| other.id == note.id) {
+                *slot = note;
+            }
+            self.pending = Some(edited);
+        }
+        let finished = response.drag_stopped() || (response.changed() && !response.dragged());
+        if finished {
+            self.pending.take().map(|edited| (description, edited))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// Zone editor - Key ranges of the sampler slots
+//
+// The key map shows the 128 MIDI notes with one row per sample: its zone as a
+// bar and its root note as a marker (notes mapped explicitly win over zones).
+// Below it, each sample gets a Zone checkbox and its low, high and root notes.
+// A drag edits a draft; the change is reported once, when the gesture ends,
+// so the app records it as one undoable `SetSampleZoneCommand`.
+
+use crate::sampler::zone::SampleZone;
+use crate::sequencer::PatternId;
+use crate::ui::arrangement::pattern_color;
+use crate::ui::piano_roll::PianoRollEditor;
+use eframe::egui;
+use egui::{Color32, Rect, Sense, Stroke, Ui, Vec2, pos2};
+use std::collections::BTreeMap;
+
+/// Height of a key map row
+const ROW_HEIGHT: f32 = 12.0;
+
+/// Zone given to a sample when its Zone box is checked (C2-B4, root C3)
+const DEFAULT_ZONE: SampleZone = SampleZone {
+    low_note: 36,
+    high_note: 71,
+    root_note: 48,
+    low_velocity: 0,
+    high_velocity: 127,
+};
+
+/// Zone editor state
+#[derive(Default)]
+pub struct ZoneEditor {
+    /// Zone being dragged (slot and its values)
+    draft: Option<(usize, SampleZone)>,
+}
+
+impl ZoneEditor {
+    /// Show the key map and the zone controls
+    ///
+    /// `samples` are the names of the sample slots, `zones` their current
+    /// zones. Returns a zone change to apply (slot and new zone).
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        samples: &[String],
+        zones: &BTreeMap<usize, SampleZone>,
+    ) -> Option<(usize, Option<SampleZone>)> {
+        let draft = self.draft;
+        let zone_of = |slot: usize| match draft {
+            Some((draft_slot, zone)) if draft_slot == slot => Some(zone),
+            _ => zones.get(&slot).copied(),
+        };
+        Self::show_key_map(ui, samples.len(), zone_of);
+
+        let mut change = None;
+        for (slot, name) in samples.iter().enumerate() {
+            let current = zone_of(slot);
+            ui.horizontal(|ui| {
+                let mut enabled = current.is_some();
+                if ui
+                    .checkbox(&mut enabled, format!("{} zone", name))
+                    .on_hover_text("Play the sample across a range of notes")
+                    .changed()
+                {
+                    change = Some((slot, enabled.then_some(DEFAULT_ZONE)));
+                }
+                let Some(mut zone) = current else {
+                    return;
+                };
+
+                let mut ended = false;
+                for (label, note) in [
+                    ("Low:", &mut zone.low_note),
+                    ("High:", &mut zone.high_note),
+                    ("Root:", &mut zone.root_note),
+                ] {
+                    ui.label(label);
+                    let response = ui.add(
+                        egui::DragValue::new(note)
+                            .range(0..=127)
+                            .speed(0.2)
+                            .custom_formatter(|value, _| {
+                                PianoRollEditor::get_note_name(value as u8)
+                            }),
+                    );
+                    ended |= response.drag_stopped() || (response.changed() && !response.dragged());
+                }
+                // Dragging one end past the other moves both
+                if zone.low_note > zone.high_note {
+                    zone.high_note = zone.low_note;
+                }
+                if Some(zone) != current {
+                    self.draft = Some((slot, zone));
+                }
+                if ended {
+                    self.draft = None;
+                    if zones.get(&slot) != Some(&zone) {
+                        change = Some((slot, Some(zone)));
+                    }
+                }
+            });
+        }
+        change
+    }
+
+    /// Key map: one row per sample, its zone as a bar and its root as a marker
+    fn show_key_map(ui: &mut Ui, rows: usize, zone_of: impl Fn(usize) -> Option<SampleZone>) {
+        let width = ui.available_width();
+        let height = ROW_HEIGHT * rows.max(1) as f32;
+        let (rect, response) = ui.allocate_exact_size(Vec2::new(width, height), Sense::hover());
+        let painter = ui.painter_at(rect);
+        let key_width = rect.width() / 128.0;
+        let key_x = |note: u8| rect.left() + note as f32 * key_width;
+
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        // Octave lines (C notes)
+        for note in (0..128).step_by(12) {
+            let x = key_x(note);
+            painter.line_segment(
+                [pos2(x, rect.top()), pos2(x, rect.bottom())],
+                Stroke::new(1.0, ui.visuals().weak_text_color().gamma_multiply(0.4)),
+            );
+        }
+
+        for row in 0..rows {
+            let Some(zone) = zone_of(row) else {
+                continue;
+            };
+            let top = rect.top() + row as f32 * ROW_HEIGHT;
+            let color = pattern_color(row as PatternId + 1);
+            let bar = Rect::from_min_max(
+                pos2(key_x(zone.low_note), top + 2.0),
+                pos2(key_x(zone.high_note) + key_width, top + ROW_HEIGHT - 2.0),
+            );
+            painter.rect_filled(bar, 2.0, color.gamma_multiply(0.7));
+            let root_x = key_x(zone.root_note) + key_width / 2.0;
+            painter.line_segment(
+                [pos2(root_x, top), pos2(root_x, top + ROW_HEIGHT)],
+                Stroke::new(2.0, Color32::WHITE),
+            );
+        }
+
+        if let Some(pointer) = response.hover_pos() {
+            let note = ((pointer.x - rect.left()) / key_width).clamp(0.0, 127.0) as u8;
+            response.on_hover_text(PianoRollEditor::get_note_name(note));
+        }
+    }
+}
@@ -0,0 +1,269 @@
+// Integration test: Audio performance regression harness
+//
+// Renders reference patches offline, block by block, and checks the time spent
+// per block against a budget expressed as a fraction of the block duration.
+// A patch that gets slower than its budget fails the test, so regressions in
+// `synth` / `sampler` show up in CI and optimizations are measurable from the
+// printed report.
+//
+// Timings are only meaningful with optimizations: budgets are asserted in
+// release builds and only reported in debug builds.
+// Run with: cargo test --release --test perf_regression -- --nocapture
+
+use mymusic_daw::sampler::loader::{LoopMode, Sample, SampleData};
+use mymusic_daw::synth::filter::{FilterParams, FilterType};
+use mymusic_daw::synth::lfo::{LfoDestination, LfoParams};
+use mymusic_daw::synth::master_chain::{MasterChain, MasterChainParams, MasterEffect};
+use mymusic_daw::synth::oscillator::WaveformType;
+use mymusic_daw::synth::voice_manager::{VoiceManager, VoiceMode};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const SAMPLE_RATE: f32 = 48000.0;
+const BLOCK_SIZE: usize = 512;
+/// Blocks rendered before measuring (envelopes settle, caches warm up)
+const WARMUP_BLOCKS: usize = 20;
+/// Measured blocks (~2 s of audio)
+const MEASURED_BLOCKS: usize = 200;
+
+/// Something that renders stereo blocks
+trait Patch {
+    fn render(&mut self, left: &mut [f32], right: &mut [f32]);
+}
+
+/// Per-block timings of a rendered patch
+struct BlockStats {
+    mean: Duration,
+    worst: Duration,
+    peak_level: f32,
+}
+
+impl BlockStats {
+    /// Mean block time as a fraction of the block duration
+    fn load(&self) -> f64 {
+        let block_duration = BLOCK_SIZE as f64 / SAMPLE_RATE as f64;
+        self.mean.as_secs_f64() / block_duration
+    }
+}
+
+fn measure(patch: &mut dyn Patch) -> BlockStats {
+    let mut left = vec![0.0; BLOCK_SIZE];
+    let mut right = vec![0.0; BLOCK_SIZE];
+
+    for _ in 0..WARMUP_BLOCKS {
+        patch.render(&mut left, &mut right);
+    }
+
+    let mut total = Duration::ZERO;
+    let mut worst = Duration::ZERO;
+    let mut peak_level: f32 = 0.0;
+    for _ in 0..MEASURED_BLOCKS {
+        left.fill(0.0);
+        right.fill(0.0);
+
+        let start = Instant::now();
+        patch.render(&mut left, &mut right);
+        let elapsed = start.elapsed();
+
+        total += elapsed;
+        worst = worst.max(elapsed);
+        for sample in left.iter().chain(right.iter()) {
+            assert!(sample.is_finite(), "Patch produced a non-finite sample");
+            peak_level = peak_level.max(sample.abs());
+        }
+    }
+
+    BlockStats {
+        mean: total / MEASURED_BLOCKS as u32,
+        worst,
+        peak_level,
+    }
+}
+
+/// Render a patch, print its report and check its budget
+fn check_budget(name: &str, patch: &mut dyn Patch, budget: f64) {
+    let stats = measure(patch);
+    println!(
+        "{:<16} mean {:>9.1?} worst {:>9.1?} load {:>5.1}% (budget {:.0}%)",
+        name,
+        stats.mean,
+        stats.worst,
+        stats.load() * 100.0,
+        budget * 100.0
+    );
+
+    assert!(stats.peak_level > 0.0, "{} rendered silence", name);
+    if !cfg!(debug_assertions) {
+        assert!(
+            stats.load() < budget,
+            "{} exceeds its budget: {:.1}% of the block duration (max {:.0}%)",
+            name,
+            stats.load() * 100.0,
+            budget * 100.0
+        );
+    }
+}
+
+/// Voice managers summed into the same block
+struct SynthPatch {
+    managers: Vec<VoiceManager>,
+    scratch_left: Vec<f32>,
+    scratch_right: Vec<f32>,
+}
+
+impl SynthPatch {
+    fn new(managers: Vec<VoiceManager>) -> Self {
+        Self {
+            managers,
+            scratch_left: vec![0.0; BLOCK_SIZE],
+            scratch_right: vec![0.0; BLOCK_SIZE],
+        }
+    }
+}
+
+impl Patch for SynthPatch {
+    fn render(&mut self, left: &mut [f32], right: &mut [f32]) {
+        for manager in &mut self.managers {
+            self.scratch_left.fill(0.0);
+            self.scratch_right.fill(0.0);
+            manager.process_block(&mut self.scratch_left, &mut self.scratch_right);
+            for (out, voice) in left.iter_mut().zip(&self.scratch_left) {
+                *out += voice;
+            }
+            for (out, voice) in right.iter_mut().zip(&self.scratch_right) {
+                *out += voice;
+            }
+        }
+    }
+}
+
+/// Synth followed by the master effect chain
+struct ChainPatch {
+    synth: SynthPatch,
+    chain: MasterChain,
+}
+
+impl Patch for ChainPatch {
+    fn render(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.synth.render(left, right);
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            (*l, *r) = self.chain.process(*l, *r);
+        }
+    }
+}
+
+/// Voice manager playing `notes` (one note per voice)
+fn synth_voices(waveform: WaveformType, notes: impl Iterator<Item = u8>) -> VoiceManager {
+    let mut manager = VoiceManager::new(SAMPLE_RATE);
+    manager.set_waveform(waveform);
+    for note in notes {
+        manager.note_on(note, 100);
+    }
+    manager
+}
+
+/// 64 voices: a voice manager holds 16 voices, four of them are summed
+fn poly_64_patch() -> SynthPatch {
+    SynthPatch::new(
+        (0..4)
+            .map(|i| {
+                synth_voices(
+                    WaveformType::Saw,
+                    (36 + i * 16..52 + i * 16).map(|n| n as u8),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// 16 voices through a resonant filter swept by an LFO
+fn filter_lfo_patch() -> SynthPatch {
+    let mut manager = VoiceManager::new(SAMPLE_RATE);
+    manager.set_waveform(WaveformType::Square);
+    manager.set_filter(FilterParams {
+        cutoff: 800.0,
+        resonance: 8.0,
+        filter_type: FilterType::LowPass,
+        enabled: true,
+    });
+    manager.set_lfo(LfoParams {
+        waveform: WaveformType::Triangle,
+        rate: 5.0,
+        depth: 1.0,
+        destination: LfoDestination::FilterCutoff,
+        ..LfoParams::default()
+    });
+    for note in 48..64 {
+        manager.note_on(note, 100);
+    }
+    SynthPatch::new(vec![manager])
+}
+
+/// 16 looping sample voices, transposed around the root note
+fn sampler_patch() -> SynthPatch {
+    let length = SAMPLE_RATE as usize;
+    let data = (0..length)
+        .map(|i| (i as f32 * 220.0 * std::f32::consts::TAU / SAMPLE_RATE).sin())
+        .collect();
+    let sample = Arc::new(Sample {
+        name: "sine".to_string(),
+        data: SampleData::F32(data),
+        sample_rate: SAMPLE_RATE as u32,
+        source_channels: 1,
+        loop_mode: LoopMode::Forward,
+        loop_start: 0,
+        loop_end: length,
+        reverse: false,
+        volume: 1.0,
+        pan: 0.0,
+        pitch_offset: 0,
+        fine_tune: 0.0,
+        envelope: None,
+        stretch: None,
+    });
+
+    let mut manager = VoiceManager::new(SAMPLE_RATE);
+    manager.set_voice_mode(VoiceMode::Sampler);
+    assert!(manager.add_sample(sample).is_ok());
+    for note in 52..68 {
+        manager.set_note_to_sample(note, 0);
+        manager.note_on(note, 100);
+    }
+    SynthPatch::new(vec![manager])
+}
+
+/// 16 voices into reverb, delay and limiter
+///
+/// CLAP plugins need binaries on disk, so the built-in master chain stands in
+/// for a plugin chain.
+fn effect_chain_patch() -> ChainPatch {
+    let mut params = MasterChainParams::default();
+    params.set_bypassed(MasterEffect::Reverb, false);
+    params.set_bypassed(MasterEffect::Delay, false);
+    params.set_bypassed(MasterEffect::Limiter, false);
+
+    ChainPatch {
+        synth: SynthPatch::new(vec![synth_voices(WaveformType::Saw, 48..64)]),
+        chain: MasterChain::new(params, SAMPLE_RATE),
+    }
+}
+
+#[test]
+fn test_poly_64_voices_budget() {
+    check_budget("poly_64", &mut poly_64_patch(), 0.5);
+}
+
+#[test]
+fn test_filter_lfo_budget() {
+    check_budget("filter_lfo", &mut filter_lfo_patch(), 0.25);
+}
+
+#[test]
+fn test_sampler_playback_budget() {
+    check_budget("sampler", &mut sampler_patch(), 0.25);
+}
+
+#[test]
+fn test_effect_chain_budget() {
+    check_budget("effect_chain", &mut effect_chain_patch(), 0.25);
+}
@@ -0,0 +1,82 @@
+// Integration test for project format migrations
+// Projects saved by older versions (tests/fixtures/projects) still open
+
+use mymusic_daw::project::{ProjectLoadOptions, ProjectManager, ProjectVersion};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Pack a fixture directory (manifest.json + project.ron) into a project file
+fn pack_fixture(name: &str, dir: &Path) -> PathBuf {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/projects")
+        .join(name);
+    let path = dir.join(format!("{}.mymusic", name));
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    for file in ["manifest.json", "project.ron"] {
+        let options: zip::write::FileOptions<()> = zip::write::FileOptions::default();
+        zip.start_file(file, options).unwrap();
+        zip.write_all(&std::fs::read(fixture.join(file)).unwrap())
+            .unwrap();
+    }
+    zip.finish().unwrap();
+    path
+}
+
+// One test: project loads share a temporary extraction directory per process
+#[test]
+fn test_old_projects_still_open() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = ProjectManager::new(48000.0);
+    let current = ProjectVersion::current();
+
+    // v1.0: no metronome nor loop settings
+    let path = pack_fixture("v1_0", dir.path());
+    let check = manager.check_project(&path).unwrap();
+    assert!(check.is_ok(), "{:?}", check);
+    assert_eq!(check.version, ProjectVersion::new(1, 0, 0));
+    assert_eq!(
+        check.migrations,
+        [
+            "v1.0 -> v1.1: Add default metronome settings",
+            "v1.1 -> v1.2: Add default loop settings"
+        ]
+    );
+
+    let project = manager
+        .load_project(&path, &ProjectLoadOptions::default())
+        .unwrap();
+    assert_eq!(project.metadata.version, current);
+    assert_eq!(project.metadata.name, "Old Song");
+    assert_eq!(project.metadata.author.as_deref(), Some("Alice"));
+    assert_eq!(project.metadata.tempo, 96.0);
+    assert_eq!(project.metadata.metronome_enabled, Some(true));
+    assert_eq!(project.metadata.loop_end_bars, Some(8));
+    assert_eq!(project.patterns[&1].notes.len(), 2);
+    assert_eq!(project.tracks[&0].name, "Lead");
+    assert!(dir.path().join("v1_0.mymusic.backup").exists());
+
+    // v1.1: its metronome settings are kept
+    let path = pack_fixture("v1_1", dir.path());
+    let check = manager.check_project(&path).unwrap();
+    assert_eq!(
+        check.migrations,
+        ["v1.1 -> v1.2: Add default loop settings"]
+    );
+
+    let project = manager
+        .load_project(&path, &ProjectLoadOptions::default())
+        .unwrap();
+    assert_eq!(project.metadata.version, current);
+    assert_eq!(project.metadata.metronome_enabled, Some(false));
+    assert_eq!(project.metadata.metronome_volume, Some(0.3));
+    assert_eq!(project.metadata.loop_enabled, Some(false));
+    assert_eq!(project.patterns[&3].notes[1].pitch, 38);
+
+    // A project at the current version needs no migration
+    let path = dir.path().join("current.mymusic");
+    manager.save_project(&project, &path).unwrap();
+    let check = manager.check_project(&path).unwrap();
+    assert!(check.is_ok());
+    assert!(check.migrations.is_empty());
+    assert!(!check.compatibility.needs_migration);
+}
@@ -11,7 +11,7 @@ fn test_sample_bank_save_load_integration() {
     // Create test samples
     let sample1 = Sample {
         name: "Kick Drum".to_string(),
-        data: SampleData::F32(vec![0.1, -0.1, 0.2, -0.2]),
+        data: SampleData::F32(vec![0.1, -0.1, 0.2, -0.2].into()),
         sample_rate: 48000,
         source_channels: 1,
         loop_mode: LoopMode::Off,
@@ -21,11 +21,14 @@ fn test_sample_bank_save_load_integration() {
         volume: 1.5,
         pan: 0.0,
         pitch_offset: 0,
+        fine_tune: 0.0,
+        envelope: None,
+        stretch: None,
     };
 
     let sample2 = Sample {
         name: "Snare".to_string(),
-        data: SampleData::F32(vec![0.3, -0.3, 0.1, -0.1]),
+        data: SampleData::F32(vec![0.3, -0.3, 0.1, -0.1].into()),
         sample_rate: 48000,
         source_channels: 1,
         loop_mode: LoopMode::Forward,
@@ -35,6 +38,9 @@ fn test_sample_bank_save_load_integration() {
         volume: 1.2,
         pan: -0.5,
         pitch_offset: 2,
+        fine_tune: 0.0,
+        envelope: None,
+        stretch: None,
     };
 
     let samples = vec![sample1, sample2];
@@ -126,6 +132,10 @@ fn test_sample_bank_duplicate_notes() {
         loop_end: 1000,
         reverse: false,
         pitch_offset: 0,
+        fine_tune: 0.0,
+        envelope: None,
+        stretch: Default::default(),
+        zone: None,
     };
 
     // Add another mapping for same note 60
@@ -140,6 +150,10 @@ fn test_sample_bank_duplicate_notes() {
         loop_end: 900,
         reverse: true,
         pitch_offset: -2,
+        fine_tune: 0.0,
+        envelope: None,
+        stretch: Default::default(),
+        zone: None,
     };
 
     bank.add_mapping(mapping1);
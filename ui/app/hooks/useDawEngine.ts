@@ -7,6 +7,7 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { useErrorReporting } from '../components/ErrorHandling';
+import { formatError, isDawError } from '../types/error';
 
 /**
  * Wrapper function for Tauri invoke calls with centralized error handling
@@ -22,7 +23,7 @@ async function invokeWithErrorHandling<T>(
     const result = await invoke<T>(command, args);
     return result;
   } catch (err) {
-    const errorMessage = err instanceof Error ? err.message : String(err);
+    const errorMessage = formatError(err);
     const context = errorContext || command;
     const fullErrorMessage = `Failed to ${context}: ${errorMessage}`;
     
@@ -30,7 +31,9 @@ async function invokeWithErrorHandling<T>(
     if (reportError) {
       // Determine error source based on command
       let source: 'audio' | 'midi' | 'ui' | 'system' = 'system';
-      if (command.includes('midi') || command.includes('note')) {
+      if (isDawError(err) && err.category === 'engine') {
+        source = 'audio';
+      } else if (command.includes('midi') || command.includes('note')) {
         source = 'midi';
       } else if (command.includes('volume') || command.includes('waveform') || command.includes('filter')) {
         source = 'audio';
@@ -42,6 +45,8 @@ async function invokeWithErrorHandling<T>(
         command,
         args,
         originalError: errorMessage,
+        code: isDawError(err) ? err.code : undefined,
+        recoverable: isDawError(err) ? err.recoverable : undefined,
       });
     }
     
@@ -154,7 +159,7 @@ export function useDawEngine(): UseDawEngine {
       setVolumeState(clampedVolume);
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to set volume:', err);
     }
@@ -179,7 +184,7 @@ export function useDawEngine(): UseDawEngine {
       setError(null);
       reportSuccess(`Note ${note} played with velocity ${velocity}`);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to play note:', err);
     }
@@ -199,7 +204,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('stop_note', { note }, 'stop note', reportMidiError);
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to stop note:', err);
     }
@@ -215,7 +220,7 @@ export function useDawEngine(): UseDawEngine {
       setIsEngineReady(status.status === 'running');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to get engine status:', err);
       setIsEngineReady(false);
@@ -231,7 +236,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('set_waveform', { waveform }, 'set waveform');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to set waveform:', err);
     }
@@ -246,7 +251,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('set_adsr', params, 'set ADSR');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to set ADSR:', err);
     }
@@ -261,7 +266,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('set_lfo', params, 'set LFO');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to set LFO:', err);
     }
@@ -276,7 +281,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('set_filter', params, 'set filter');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to set filter:', err);
     }
@@ -291,7 +296,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('set_poly_mode', { mode }, 'set poly mode');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to set poly mode:', err);
     }
@@ -306,7 +311,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('set_portamento', { time }, 'set portamento');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to set portamento:', err);
     }
@@ -321,7 +326,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('set_voice_mode', { mode }, 'set voice mode');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to set voice mode:', err);
     }
@@ -336,7 +341,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('set_mod_routing', params, 'set mod routing');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to set mod routing:', err);
     }
@@ -351,7 +356,7 @@ export function useDawEngine(): UseDawEngine {
       await invokeWithErrorHandling('clear_mod_routing', { index }, 'clear mod routing');
       setError(null);
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err);
+      const errorMessage = formatError(err);
       setError(errorMessage);
       console.error('Failed to clear mod routing:', err);
     }
@@ -381,7 +386,7 @@ export function useDawEngine(): UseDawEngine {
         // Mark as initialized
         isInitialized.current = true;
       } catch (err) {
-        const errorMessage = err instanceof Error ? err.message : String(err);
+        const errorMessage = formatError(err);
         console.error('Failed to initialize DAW engine:', err);
         setError(errorMessage);
       }
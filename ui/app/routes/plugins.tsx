@@ -5,6 +5,7 @@ import { Button } from "~/components/ui/button";
 import { Skeleton } from "~/components/ui/skeleton";
 import { mockPlugins } from "~/lib/mockData";
 import type { Plugin, PluginCategory, PluginFormat } from "~/types/plugin";
+import { formatError } from "~/types/error";
 import { cn } from "~/lib/utils";
 import { useToast } from "~/lib/toast";
 import { invoke } from "@tauri-apps/api/core";
@@ -147,7 +148,7 @@ export default function PluginsPage() {
       }
     } catch (error) {
       console.error("Plugin scanning failed:", error);
-      showToast(`Plugin scanning failed: ${error}`, "error", 3000);
+      showToast(`Plugin scanning failed: ${formatError(error)}`, "error", 3000);
     } finally {
       setIsScanning(false);
     }
@@ -195,7 +196,7 @@ export default function PluginsPage() {
           )
         );
 
-        showToast(`Plugin "${plugin.name}" loaded (demo mode) - Error: ${error}`, "warning");
+        showToast(`Plugin "${plugin.name}" loaded (demo mode) - Error: ${formatError(error)}`, "warning");
       }
     } else {
       // Unload plugin
@@ -227,7 +228,7 @@ export default function PluginsPage() {
       showToast(`Plugin GUI shown`, "success");
       await updatePluginGuiInfo(instanceId);
     } catch (error) {
-      showToast(`Failed to show GUI: ${error}`, "error");
+      showToast(`Failed to show GUI: ${formatError(error)}`, "error");
     }
   };
 
@@ -237,7 +238,7 @@ export default function PluginsPage() {
       showToast(`Plugin GUI hidden`, "success");
       await updatePluginGuiInfo(instanceId);
     } catch (error) {
-      showToast(`Failed to hide GUI: ${error}`, "error");
+      showToast(`Failed to hide GUI: ${formatError(error)}`, "error");
     }
   };
 
@@ -255,7 +256,7 @@ export default function PluginsPage() {
       showToast(`Plugin GUI attached to window`, "success");
       await updatePluginGuiInfo(instanceId);
     } catch (error) {
-      showToast(`Failed to attach GUI: ${error}`, "error");
+      showToast(`Failed to attach GUI: ${formatError(error)}`, "error");
     }
   };
 
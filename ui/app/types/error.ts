@@ -0,0 +1,51 @@
+/**
+ * Errors returned by Tauri commands (mirrors `src-tauri/src/error.rs`)
+ */
+
+/** Bridge API version this frontend was written against */
+export const BRIDGE_API_VERSION = 1;
+
+export type DawErrorCategory =
+  | "validation"
+  | "not_found"
+  | "engine"
+  | "state"
+  | "io"
+  | "plugin"
+  | "unsupported";
+
+export type DawErrorCode =
+  | "invalid_argument"
+  | "not_found"
+  | "queue_full"
+  | "engine_failure"
+  | "state_poisoned"
+  | "io_error"
+  | "plugin_error"
+  | "window_error"
+  | "not_implemented"
+  | "unavailable";
+
+export interface DawError {
+  category: DawErrorCategory;
+  code: DawErrorCode;
+  message: string;
+  /** False when retrying cannot succeed (poisoned state, missing feature) */
+  recoverable: boolean;
+}
+
+export function isDawError(value: unknown): value is DawError {
+  return (
+    typeof value === "object" &&
+    value !== null &&
+    typeof (value as DawError).code === "string" &&
+    typeof (value as DawError).message === "string"
+  );
+}
+
+/** Human readable message of anything thrown by `invoke` */
+export function formatError(err: unknown): string {
+  if (isDawError(err)) return err.message;
+  if (err instanceof Error) return err.message;
+  return String(err);
+}